@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use neonsearch::networking::manual_client::decode_chunked;
+
+fuzz_target!(|data: &[u8]| {
+    let lenient = decode_chunked(data, false);
+    // Whatever strict decoding accepts, lenient decoding reads the same way
+    if let Ok(strict) = decode_chunked(data, true) {
+        let lenient = lenient.expect("lenient decoding accepts what strict decoding does");
+        assert_eq!(strict.data, lenient.data);
+        assert!(strict.complete);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use neonsearch::networking::cookie_manager::CookieManager;
+
+fuzz_target!(|data: &[u8]| {
+    let header = String::from_utf8_lossy(data);
+    let mut manager = CookieManager::new();
+    manager.parse_set_cookie_header(&header, "example.com");
+    manager.get_cookie_header_for_request("example.com", "/", true);
+    manager.set_script_cookie(&header, "example.com", "/", false);
+    manager.script_cookies("example.com", "/", false);
+});
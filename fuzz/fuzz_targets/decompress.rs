@@ -0,0 +1,32 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use neonsearch::networking::decode_content;
+use neonsearch::networking::streaming_compression::{CompressionType, StreamingDecompressConfig, StreamingDecompressor};
+
+// The first byte picks the coding, the second how the body is split into chunks for streaming decoding
+const ENCODINGS: [(&str, CompressionType); 4] = [
+    ("gzip", CompressionType::Gzip),
+    ("deflate", CompressionType::Deflate),
+    ("br", CompressionType::Brotli),
+    ("zstd", CompressionType::Zstd),
+];
+const LIMIT: usize = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let [selector, chunk, body @ ..] = data else { return };
+    let (encoding, compression) = ENCODINGS[*selector as usize % ENCODINGS.len()];
+
+    // Decoded bodies stay within the limit, and failures are errors rather than the encoded bytes
+    if let Ok(decoded) = decode_content(body.to_vec(), encoding, Some(LIMIT)) {
+        assert!(decoded.len() <= LIMIT);
+    }
+
+    let config = StreamingDecompressConfig::default();
+    let Ok(mut decompressor) = StreamingDecompressor::new(compression, config.clone()) else { return };
+    for piece in body.chunks(*chunk as usize + 1) {
+        if decompressor.add_chunk(piece, &config).is_err() {
+            return;
+        }
+    }
+    let _ = decompressor.finalize();
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use neonsearch::engine::html_parser;
+
+fuzz_target!(|data: &[u8]| {
+    // Pages are decoded to text before they're parsed
+    html_parser::parse(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,534 @@
+// The accessibility tree: what assistive technology is told about a page, built from its DOM and ARIA attributes
+use eframe::egui::accesskit::{self, Role, Toggled};
+use std::collections::HashMap;
+use crate::engine::dom::DOMNode;
+use crate::engine::forms::{self, FormState};
+
+/// States a node reports, from ARIA attributes or the element's own
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct States {
+    pub disabled: bool,
+    pub required: bool,
+    pub read_only: bool,
+    pub checked: Option<Toggled>,
+    pub expanded: Option<bool>,
+    pub selected: Option<bool>,
+}
+
+/// One node of the accessibility tree. Text is exposed as `Label` nodes for its text node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessNode {
+    /// Only compared, never dereferenced
+    pub node: *const DOMNode,
+    pub role: Role,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub value: Option<String>,
+    pub url: Option<String>,
+    pub level: Option<usize>,
+    pub states: States,
+    pub children: Vec<AccessNode>,
+}
+
+impl AccessNode {
+    fn new(node: &DOMNode, role: Role) -> Self {
+        Self {
+            node,
+            role,
+            name: None,
+            description: None,
+            value: None,
+            url: None,
+            level: None,
+            states: States::default(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Fill an AccessKit node with this node's role, name, value and states
+    pub fn write_to(&self, builder: &mut accesskit::NodeBuilder) {
+        builder.set_role(self.role);
+        if let Some(name) = &self.name {
+            builder.set_name(name.clone());
+        }
+        if let Some(description) = &self.description {
+            builder.set_description(description.clone());
+        }
+        if let Some(value) = &self.value {
+            builder.set_value(value.clone());
+        }
+        if let Some(url) = &self.url {
+            builder.set_url(url.clone());
+        }
+        if let Some(level) = self.level {
+            builder.set_level(level);
+        }
+        if self.states.disabled {
+            builder.set_disabled();
+        }
+        if self.states.required {
+            builder.set_required();
+        }
+        if self.states.read_only {
+            builder.set_read_only();
+        }
+        if let Some(checked) = self.states.checked {
+            builder.set_toggled(checked);
+        }
+        if let Some(expanded) = self.states.expanded {
+            builder.set_expanded(expanded);
+        }
+        if let Some(selected) = self.states.selected {
+            builder.set_selected(selected);
+        }
+    }
+
+    /// One line per node, indented by depth, as a screen reader would announce it
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        self.describe_into(&mut out, 0);
+        out
+    }
+
+    fn describe_into(&self, out: &mut String, depth: usize) {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&format!("{:?}", self.role));
+        if let Some(level) = self.level {
+            out.push_str(&format!(" {}", level));
+        }
+        if let Some(name) = &self.name {
+            out.push_str(&format!(" \"{}\"", name));
+        }
+        if let Some(value) = &self.value {
+            out.push_str(&format!(" = {}", value));
+        }
+        let states = &self.states;
+        for (on, label) in [(states.disabled, "disabled"), (states.required, "required"), (states.read_only, "read-only")] {
+            if on {
+                out.push_str(&format!(" [{}]", label));
+            }
+        }
+        if let Some(checked) = states.checked {
+            out.push_str(match checked {
+                Toggled::True => " [checked]",
+                Toggled::False => " [unchecked]",
+                Toggled::Mixed => " [mixed]",
+            });
+        }
+        if let Some(expanded) = states.expanded {
+            out.push_str(if expanded { " [expanded]" } else { " [collapsed]" });
+        }
+        if states.selected == Some(true) {
+            out.push_str(" [selected]");
+        }
+        out.push('\n');
+        for child in &self.children {
+            child.describe_into(out, depth + 1);
+        }
+    }
+}
+
+/// The accessibility tree of `dom`, skipping what `hidden` says isn't rendered. Form controls report the
+/// values in `forms`, and link URLs are resolved against `base_url`.
+pub fn build(dom: &DOMNode, hidden: &dyn Fn(&DOMNode) -> bool, forms: &FormState, base_url: Option<&str>) -> AccessNode {
+    let mut ids = HashMap::new();
+    let mut labels = HashMap::new();
+    index(dom, &mut ids, &mut labels);
+    let builder = Builder { hidden, forms, base_url: base_url.and_then(|url| url::Url::parse(url).ok()), ids, labels };
+    let mut root = AccessNode::new(dom, Role::Document);
+    builder.children_of(dom, &mut root.children);
+    root
+}
+
+// Elements by id, for aria-labelledby, and the text of each `<label for>`
+fn index<'a>(node: &'a DOMNode, ids: &mut HashMap<String, &'a DOMNode>, labels: &mut HashMap<String, String>) {
+    let DOMNode::Element { tag_name, attributes, children } = node else { return };
+    if let Some(id) = attributes.get("id") {
+        ids.entry(id.clone()).or_insert(node);
+    }
+    if tag_name == "label" {
+        if let Some(target) = attributes.get("for") {
+            labels.insert(target.clone(), normalize(&node.collect_text()));
+        }
+    }
+    for child in children {
+        index(child, ids, labels);
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct Builder<'a> {
+    hidden: &'a dyn Fn(&DOMNode) -> bool,
+    forms: &'a FormState,
+    base_url: Option<url::Url>,
+    ids: HashMap<String, &'a DOMNode>,
+    labels: HashMap<String, String>,
+}
+
+// Roles whose name is the text inside them, which then isn't exposed again as children
+fn named_from_content(role: Role) -> bool {
+    matches!(role, Role::Link | Role::Button | Role::Heading | Role::CheckBox | Role::RadioButton | Role::Tab
+        | Role::MenuItem | Role::MenuItemCheckBox | Role::MenuItemRadio | Role::ListBoxOption | Role::Cell
+        | Role::ColumnHeader | Role::RowHeader | Role::Tooltip | Role::TreeItem | Role::Switch
+        | Role::DisclosureTriangle | Role::Label | Role::FigureCaption | Role::Legend)
+}
+
+impl Builder<'_> {
+    // Nodes for the children of `node`; elements without a role of their own are replaced by their children
+    fn children_of(&self, node: &DOMNode, out: &mut Vec<AccessNode>) {
+        let DOMNode::Element { children, .. } = node else { return };
+        for child in children {
+            match child {
+                DOMNode::Text(text) => {
+                    let text = normalize(text);
+                    if !text.is_empty() {
+                        let mut label = AccessNode::new(child, Role::Label);
+                        label.name = Some(text);
+                        out.push(label);
+                    }
+                }
+                DOMNode::Element { .. } => {
+                    if self.is_hidden(child) {
+                        continue;
+                    }
+                    match self.role_of(child) {
+                        Some(role) => out.push(self.node_for(child, role)),
+                        None => self.children_of(child, out),
+                    }
+                }
+                DOMNode::Comment(_) => {}
+            }
+        }
+    }
+
+    fn is_hidden(&self, node: &DOMNode) -> bool {
+        let attribute = |name: &str| node.get_attribute(name).map(|v| v.trim().to_ascii_lowercase());
+        attribute("aria-hidden").as_deref() == Some("true")
+            || attribute("hidden").is_some()
+            || (node.tag_name().is_some_and(|tag| tag == "input") && forms::input_type(node) == "hidden")
+            || (self.hidden)(node)
+    }
+
+    // The explicit ARIA role, or the element's implicit one; None for elements that only group their children
+    fn role_of(&self, node: &DOMNode) -> Option<Role> {
+        if let Some(role) = node.get_attribute("role") {
+            // The first role we know of wins, as with fallback role lists
+            for name in role.split_whitespace() {
+                match aria_role(&name.to_ascii_lowercase()) {
+                    Some(role) => return role,
+                    None => continue,
+                }
+            }
+        }
+        let tag = node.tag_name()?.as_str();
+        Some(match tag {
+            "a" | "area" if node.get_attribute("href").is_some() => Role::Link,
+            "button" => Role::Button,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Role::Heading,
+            "p" => Role::Paragraph,
+            // An empty alt marks a decorative image
+            "img" => match node.get_attribute("alt") {
+                Some(alt) if alt.trim().is_empty() => return None,
+                _ => Role::Image,
+            },
+            "svg" => Role::SvgRoot,
+            "ul" | "ol" | "menu" => Role::List,
+            "li" => Role::ListItem,
+            "dl" => Role::DescriptionList,
+            "dt" => Role::DescriptionListTerm,
+            "dd" => Role::DescriptionListDetail,
+            "nav" => Role::Navigation,
+            "main" => Role::Main,
+            "header" => Role::Header,
+            "footer" => Role::Footer,
+            "aside" => Role::Complementary,
+            "article" => Role::Article,
+            "section" => Role::Section,
+            "form" => Role::Form,
+            "search" => Role::Search,
+            "table" => Role::Table,
+            "tr" => Role::Row,
+            "td" => Role::Cell,
+            "th" => Role::ColumnHeader,
+            "caption" => Role::Caption,
+            "input" => match forms::input_type(node).as_str() {
+                "checkbox" => Role::CheckBox,
+                "radio" => Role::RadioButton,
+                "submit" | "reset" | "button" | "image" => Role::Button,
+                "range" => Role::Slider,
+                "search" => Role::SearchInput,
+                "email" => Role::EmailInput,
+                "password" => Role::PasswordInput,
+                "number" => Role::NumberInput,
+                "url" => Role::UrlInput,
+                "tel" => Role::PhoneNumberInput,
+                "date" => Role::DateInput,
+                "time" => Role::TimeInput,
+                "color" => Role::ColorWell,
+                _ => Role::TextInput,
+            },
+            "textarea" => Role::MultilineTextInput,
+            "select" => Role::ComboBox,
+            "option" => Role::ListBoxOption,
+            "label" => Role::Label,
+            "fieldset" => Role::Group,
+            "legend" => Role::Legend,
+            "progress" => Role::ProgressIndicator,
+            "meter" => Role::Meter,
+            "details" => Role::Details,
+            "summary" => Role::DisclosureTriangle,
+            "dialog" => Role::Dialog,
+            "figure" => Role::Figure,
+            "figcaption" => Role::FigureCaption,
+            "blockquote" => Role::Blockquote,
+            "pre" => Role::Pre,
+            "code" => Role::Code,
+            "strong" => Role::Strong,
+            "em" => Role::Emphasis,
+            "mark" => Role::Mark,
+            "time" => Role::Time,
+            "hr" => Role::Splitter,
+            "iframe" => Role::Iframe,
+            "video" => Role::Video,
+            "audio" => Role::Audio,
+            "canvas" => Role::Canvas,
+            _ => return None,
+        })
+    }
+
+    fn node_for(&self, node: &DOMNode, role: Role) -> AccessNode {
+        let mut access = AccessNode::new(node, role);
+        let attribute = |name: &str| node.get_attribute(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let flag = |name: &str| attribute(name).is_some_and(|v| v.eq_ignore_ascii_case("true"));
+        let tag = node.tag_name().map(String::as_str).unwrap_or("");
+        let input_type = forms::input_type(node);
+        let is_control = matches!(tag, "input" | "textarea" | "select" | "button" | "option" | "fieldset");
+
+        // The accessible name: aria-labelledby, then aria-label, then what the element itself offers
+        let labelled_by = attribute("aria-labelledby").map(|ids| self.text_of_ids(&ids)).filter(|name| !name.is_empty());
+        let native = || -> Option<String> {
+            match tag {
+                "img" | "area" => attribute("alt"),
+                "input" if matches!(input_type.as_str(), "submit" | "reset" | "button") => attribute("value")
+                    .or_else(|| Some(if input_type == "reset" { "Reset" } else { "Submit" }.to_string()).filter(|_| input_type != "button")),
+                "input" if input_type == "image" => attribute("alt"),
+                "input" | "textarea" | "select" => attribute("id").and_then(|id| self.labels.get(&id).cloned())
+                    .filter(|label| !label.is_empty()),
+                "fieldset" => None,
+                "iframe" => attribute("title"),
+                _ => None,
+            }
+        };
+        access.name = labelled_by.or_else(|| attribute("aria-label")).or_else(native);
+        if access.name.is_none() && named_from_content(role) {
+            access.name = Some(normalize(&node.collect_text())).filter(|text| !text.is_empty());
+        }
+        if access.name.is_none() && matches!(tag, "input" | "textarea") {
+            access.name = attribute("placeholder");
+        }
+        // title is the name of last resort, and otherwise describes the element
+        let described_by = attribute("aria-describedby").map(|ids| self.text_of_ids(&ids)).filter(|text| !text.is_empty());
+        match attribute("title") {
+            Some(title) if access.name.is_none() => access.name = Some(title),
+            title => access.description = described_by.or(title),
+        }
+
+        access.level = match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => tag[1..].parse().ok(),
+            _ => None,
+        };
+        if let Some(level) = attribute("aria-level").and_then(|level| level.parse().ok()) {
+            access.level = Some(level);
+        }
+        if role == Role::Link {
+            access.url = attribute("href").map(|href| match &self.base_url {
+                Some(base) => base.join(&href).map(|url| url.to_string()).unwrap_or(href),
+                None => href,
+            });
+        }
+
+        // Values of form controls as the user left them; passwords are never read out
+        access.value = match role {
+            Role::PasswordInput => Some("•".repeat(self.forms.value(node).chars().count())),
+            Role::TextInput | Role::SearchInput | Role::EmailInput | Role::NumberInput | Role::UrlInput
+                | Role::PhoneNumberInput | Role::DateInput | Role::TimeInput | Role::MultilineTextInput
+                | Role::Slider | Role::ColorWell if is_control => Some(self.forms.value(node)),
+            Role::ComboBox if tag == "select" => {
+                let value = self.forms.value(node);
+                forms::select_options(node).into_iter().find(|(v, _)| *v == value).map(|(_, label)| label)
+            }
+            Role::ProgressIndicator | Role::Meter => attribute("value"),
+            _ => attribute("aria-valuetext").or_else(|| attribute("aria-valuenow")),
+        };
+
+        access.states = States {
+            disabled: (is_control && node.get_attribute("disabled").is_some()) || flag("aria-disabled"),
+            required: (is_control && node.get_attribute("required").is_some()) || flag("aria-required"),
+            read_only: (is_control && node.get_attribute("readonly").is_some()) || flag("aria-readonly"),
+            checked: match attribute("aria-checked").map(|v| v.to_ascii_lowercase()).as_deref() {
+                Some("true") => Some(Toggled::True),
+                Some("false") => Some(Toggled::False),
+                Some("mixed") => Some(Toggled::Mixed),
+                _ if tag == "input" && matches!(input_type.as_str(), "checkbox" | "radio") => {
+                    Some(if self.forms.is_checked(node) { Toggled::True } else { Toggled::False })
+                }
+                _ => None,
+            },
+            expanded: match attribute("aria-expanded") {
+                Some(expanded) => Some(expanded.eq_ignore_ascii_case("true")),
+                None if tag == "details" => Some(node.get_attribute("open").is_some()),
+                None => None,
+            },
+            selected: match attribute("aria-selected") {
+                Some(selected) => Some(selected.eq_ignore_ascii_case("true")),
+                None if tag == "option" => Some(node.get_attribute("selected").is_some()),
+                None => None,
+            },
+        };
+
+        // Options of a select and text of a textarea are its value, not content to read out
+        let named_by_content = named_from_content(role) && access.name.is_some();
+        if !named_by_content && !matches!(tag, "select" | "textarea") {
+            self.children_of(node, &mut access.children);
+        }
+        access
+    }
+
+    // Text of the elements with these space-separated ids, for aria-labelledby and aria-describedby
+    fn text_of_ids(&self, ids: &str) -> String {
+        let texts: Vec<String> = ids.split_whitespace()
+            .filter_map(|id| self.ids.get(id))
+            .map(|element| element.get_attribute("aria-label").cloned().unwrap_or_else(|| normalize(&element.collect_text())))
+            .collect();
+        normalize(&texts.join(" "))
+    }
+}
+
+// A known ARIA role: Some(None) for `presentation`/`none`, which drop the element's own semantics
+fn aria_role(name: &str) -> Option<Option<Role>> {
+    Some(Some(match name {
+        "presentation" | "none" | "generic" => return Some(None),
+        "button" => Role::Button,
+        "link" => Role::Link,
+        "heading" => Role::Heading,
+        "paragraph" => Role::Paragraph,
+        "img" | "image" => Role::Image,
+        "checkbox" => Role::CheckBox,
+        "radio" => Role::RadioButton,
+        "radiogroup" => Role::RadioGroup,
+        "switch" => Role::Switch,
+        "textbox" => Role::TextInput,
+        "searchbox" => Role::SearchInput,
+        "combobox" => Role::ComboBox,
+        "listbox" => Role::ListBox,
+        "option" => Role::ListBoxOption,
+        "list" => Role::List,
+        "listitem" => Role::ListItem,
+        "navigation" => Role::Navigation,
+        "main" => Role::Main,
+        "banner" => Role::Banner,
+        "contentinfo" => Role::ContentInfo,
+        "complementary" => Role::Complementary,
+        "region" => Role::Region,
+        "search" => Role::Search,
+        "form" => Role::Form,
+        "article" => Role::Article,
+        "document" => Role::Document,
+        "application" => Role::Application,
+        "dialog" => Role::Dialog,
+        "alertdialog" => Role::AlertDialog,
+        "alert" => Role::Alert,
+        "status" => Role::Status,
+        "log" => Role::Log,
+        "marquee" => Role::Marquee,
+        "timer" => Role::Timer,
+        "tooltip" => Role::Tooltip,
+        "tab" => Role::Tab,
+        "tablist" => Role::TabList,
+        "tabpanel" => Role::TabPanel,
+        "menu" => Role::Menu,
+        "menubar" => Role::MenuBar,
+        "menuitem" => Role::MenuItem,
+        "menuitemcheckbox" => Role::MenuItemCheckBox,
+        "menuitemradio" => Role::MenuItemRadio,
+        "toolbar" => Role::Toolbar,
+        "tree" => Role::Tree,
+        "treeitem" => Role::TreeItem,
+        "treegrid" => Role::TreeGrid,
+        "grid" => Role::Grid,
+        "table" => Role::Table,
+        "row" => Role::Row,
+        "rowgroup" => Role::RowGroup,
+        "cell" | "gridcell" => Role::Cell,
+        "columnheader" => Role::ColumnHeader,
+        "rowheader" => Role::RowHeader,
+        "slider" => Role::Slider,
+        "spinbutton" => Role::SpinButton,
+        "progressbar" => Role::ProgressIndicator,
+        "meter" => Role::Meter,
+        "scrollbar" => Role::ScrollBar,
+        "separator" => Role::Splitter,
+        "group" => Role::Group,
+        "figure" => Role::Figure,
+        "note" => Role::Note,
+        "feed" => Role::Feed,
+        "definition" => Role::Definition,
+        "term" => Role::Term,
+        "math" => Role::Math,
+        _ => return None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_roles_names_and_states() {
+        let dom = html_parser::parse(
+            "<html><body><nav aria-label=\"Primary\"><ul><li><a href=\"/docs\">Docs</a></li></ul></nav>\
+             <div><h2>Sign in</h2><img src=\"deco.png\" alt=\"\"><img src=\"logo.png\" alt=\"Logo\"></div>\
+             <form><label for=\"user\">User name</label><input id=\"user\" required value=\"ada\">\
+             <input type=\"checkbox\" checked title=\"Remember me\"><input type=\"hidden\" name=\"t\">\
+             <div role=\"button\" aria-expanded=\"false\" aria-disabled=\"true\">More</div>\
+             <span aria-hidden=\"true\">decoration</span><input type=\"password\" value=\"pw\" placeholder=\"Password\"></form></body></html>"
+        );
+        let tree = build(&dom, &|_| false, &FormState::default(), Some("https://example.com/login"));
+        assert_eq!(tree.describe(), "\
+Document
+  Navigation \"Primary\"
+    List
+      ListItem
+        Link \"Docs\"
+  Heading 2 \"Sign in\"
+  Image \"Logo\"
+  Form
+    Label \"User name\"
+    TextInput \"User name\" = ada [required]
+    CheckBox \"Remember me\" [checked]
+    Button \"More\" [disabled] [collapsed]
+    PasswordInput \"Password\" = ••
+");
+        let link = &tree.children[0].children[0].children[0].children[0];
+        assert_eq!(link.url.as_deref(), Some("https://example.com/docs"));
+    }
+
+    #[test]
+    fn test_labelled_by_and_presentation() {
+        let dom = html_parser::parse(
+            "<html><body><h1 id=\"t\">Title</h1><span id=\"s\">sub</span>\
+             <section aria-labelledby=\"t s\" aria-describedby=\"s\"><table role=\"presentation\"><tr><td>cell</td></tr></table></section></body></html>"
+        );
+        let tree = build(&dom, &|node| node.tag_name().is_some_and(|tag| tag == "h1"), &FormState::default(), None);
+        let section = tree.children.iter().find(|node| node.role == Role::Section).unwrap();
+        assert_eq!(section.name.as_deref(), Some("Title sub"));
+        assert_eq!(section.description.as_deref(), Some("sub"));
+        // The table's semantics are dropped, but its rows and cells are still there
+        assert_eq!(section.children[0].role, Role::Row);
+        assert_eq!(tree.children[0].role, Role::Label);
+    }
+}
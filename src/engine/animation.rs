@@ -0,0 +1,502 @@
+// CSS transitions and keyframe animations of opacity, transform and color, run on each page's clock
+use eframe::egui::{self, Color32};
+use std::collections::{HashMap, HashSet};
+use crate::engine::css_parser;
+use crate::engine::dom::DOMNode;
+use crate::engine::style::LengthContext;
+
+/// `translate()` and `scale()`, the transforms we can paint. Non-uniform scales use their x factor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translate: egui::Vec2,
+    pub scale: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self { translate: egui::Vec2::ZERO, scale: 1.0 }
+    }
+}
+
+impl Transform {
+    /// A `transform` value such as `translate(10px, 2em) scale(1.5)`; None if it has functions we can't paint
+    pub fn parse(text: &str, context: &LengthContext) -> Option<Self> {
+        let mut transform = Transform::default();
+        let text = text.trim().to_ascii_lowercase();
+        if text == "none" {
+            return Some(transform);
+        }
+        for function in split_top_level(&text, char::is_whitespace) {
+            let (name, args) = function.split_once('(')?;
+            let args: Vec<&str> = args.strip_suffix(')')?.split(',').map(str::trim).collect();
+            let length = |arg: Option<&&str>| match arg {
+                // Percentages are of the element's own size, which isn't known while styling
+                Some(arg) => context.resolve(&css_parser::parse_value(arg), 0.0),
+                None => Some(0.0),
+            };
+            let number = |arg: &str| arg.strip_suffix('%')
+                .map_or_else(|| arg.parse::<f32>().ok(), |p| p.parse::<f32>().ok().map(|p| p / 100.0));
+            match name.trim() {
+                "translate" => transform.translate += egui::vec2(length(args.first())?, length(args.get(1))?),
+                "translatex" => transform.translate.x += length(args.first())?,
+                "translatey" => transform.translate.y += length(args.first())?,
+                "scale" | "scalex" => transform.scale *= number(args.first()?)?,
+                _ => return None,
+            }
+        }
+        Some(transform)
+    }
+
+    /// As a paint transform scaling about `center`
+    pub fn around(&self, center: egui::Pos2) -> egui::emath::TSTransform {
+        egui::emath::TSTransform::from_translation(center.to_vec2() + self.translate)
+            * egui::emath::TSTransform::from_scaling(self.scale)
+            * egui::emath::TSTransform::from_translation(-center.to_vec2())
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::default()
+    }
+}
+
+/// The properties transitions and animations can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Property {
+    Opacity,
+    Transform,
+    Color,
+}
+
+const PROPERTIES: [Property; 3] = [Property::Opacity, Property::Transform, Property::Color];
+
+impl Property {
+    fn name(&self) -> &'static str {
+        match self {
+            Property::Opacity => "opacity",
+            Property::Transform => "transform",
+            Property::Color => "color",
+        }
+    }
+}
+
+/// An element's values of the animatable properties
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimatedValues {
+    pub opacity: f32,
+    pub transform: Transform,
+    pub color: Color32,
+}
+
+impl AnimatedValues {
+    pub fn lerp(&self, to: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        // Color32 is premultiplied, which is the space CSS interpolates colors in
+        let channel = |a: u8, b: u8| mix(a as f32, b as f32).round().clamp(0.0, 255.0) as u8;
+        let (a, b) = (self.color.to_array(), to.color.to_array());
+        Self {
+            opacity: mix(self.opacity, to.opacity),
+            transform: Transform {
+                translate: self.transform.translate + (to.transform.translate - self.transform.translate) * t,
+                scale: mix(self.transform.scale, to.transform.scale),
+            },
+            color: Color32::from_rgba_premultiplied(channel(a[0], b[0]), channel(a[1], b[1]), channel(a[2], b[2]), channel(a[3], b[3])),
+        }
+    }
+
+    fn differs(&self, other: &Self, property: Property) -> bool {
+        match property {
+            Property::Opacity => self.opacity != other.opacity,
+            Property::Transform => self.transform != other.transform,
+            Property::Color => self.color != other.color,
+        }
+    }
+
+    // Take one property's value from `from`
+    fn set(&mut self, from: &Self, property: Property) {
+        match property {
+            Property::Opacity => self.opacity = from.opacity,
+            Property::Transform => self.transform = from.transform,
+            Property::Color => self.color = from.color,
+        }
+    }
+}
+
+/// A timing function
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    CubicBezier(f32, f32, f32, f32),
+    /// `steps(n)`, jumping at the start of each step when set, otherwise at the end
+    Steps(u32, bool),
+}
+
+impl Easing {
+    pub const EASE: Easing = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+
+    fn parse(text: &str) -> Option<Self> {
+        Some(match text {
+            "linear" => Easing::Linear,
+            "ease" => Easing::EASE,
+            "ease-in" => Easing::CubicBezier(0.42, 0.0, 1.0, 1.0),
+            "ease-out" => Easing::CubicBezier(0.0, 0.0, 0.58, 1.0),
+            "ease-in-out" => Easing::CubicBezier(0.42, 0.0, 0.58, 1.0),
+            "step-start" => Easing::Steps(1, true),
+            "step-end" => Easing::Steps(1, false),
+            _ => {
+                let (name, args) = text.split_once('(')?;
+                let args = args.strip_suffix(')')?;
+                let numbers: Vec<f32> = args.split(',').filter_map(|a| a.trim().parse().ok()).collect();
+                match (name, numbers.as_slice()) {
+                    ("cubic-bezier", [x1, y1, x2, y2]) => Easing::CubicBezier(x1.clamp(0.0, 1.0), *y1, x2.clamp(0.0, 1.0), *y2),
+                    ("steps", [n]) if *n >= 1.0 => Easing::Steps(*n as u32, args.contains("start")),
+                    _ => return None,
+                }
+            }
+        })
+    }
+
+    /// Output progress for input progress `t` in 0..=1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::Steps(n, true) => (t * n as f32).ceil() / n as f32,
+            Easing::Steps(n, false) => (t * n as f32).floor() / n as f32,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let bezier = |a: f32, b: f32, s: f32| 3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s * s * (1.0 - s) + s.powi(3);
+                // x(s) is monotonic for control points inside 0..=1, so bisect for the s giving x = t
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..24 {
+                    let mid = (low + high) / 2.0;
+                    if bezier(x1, x2, mid) < t {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                bezier(y1, y2, (low + high) / 2.0)
+            }
+        }
+    }
+}
+
+/// Duration, delay and easing shared by transitions and animations, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub duration: f64,
+    pub delay: f64,
+    pub easing: Easing,
+}
+
+/// One entry of a `transition` list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// A property name, or `all`
+    pub property: String,
+    pub timing: Timing,
+}
+
+impl Transition {
+    fn covers(&self, property: Property) -> bool {
+        self.property == "all" || self.property == property.name()
+    }
+}
+
+/// One entry of an `animation` list, with its keyframes once they are resolved against the element's style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub name: String,
+    pub timing: Timing,
+    /// Infinite for `infinite`
+    pub iterations: f64,
+    pub alternate: bool,
+    pub keyframes: Vec<(f32, AnimatedValues)>,
+}
+
+impl Animation {
+    fn end(&self, start: f64) -> f64 {
+        start + self.timing.delay + self.timing.duration * self.iterations
+    }
+
+    // The animated values at `elapsed` seconds since the animation started, None while it isn't running
+    fn values_at(&self, elapsed: f64, base: &AnimatedValues) -> Option<AnimatedValues> {
+        let active = elapsed - self.timing.delay;
+        if active < 0.0 || self.timing.duration <= 0.0 || active >= self.timing.duration * self.iterations {
+            return None;
+        }
+        let progress = active / self.timing.duration;
+        let iteration = progress.floor();
+        let mut t = (progress - iteration) as f32;
+        if self.alternate && iteration as u64 % 2 == 1 {
+            t = 1.0 - t;
+        }
+
+        // Offsets without a keyframe take the element's own values
+        let first = self.keyframes.first().filter(|(offset, _)| *offset == 0.0).map(|(_, v)| *v).unwrap_or(*base);
+        let last = self.keyframes.last().filter(|(offset, _)| *offset == 1.0).map(|(_, v)| *v).unwrap_or(*base);
+        let mut from = (0.0, first);
+        for &(offset, values) in self.keyframes.iter().chain(std::iter::once(&(1.0, last))) {
+            if offset >= t && offset > from.0 {
+                let local = (t - from.0) / (offset - from.0);
+                return Some(from.1.lerp(&values, self.timing.easing.apply(local)));
+            }
+            from = (offset, values);
+        }
+        Some(from.1)
+    }
+}
+
+/// A `transition` value as a list of transitions
+pub fn parse_transitions(text: &str) -> Vec<Transition> {
+    split_top_level(text, |c| c == ',').into_iter().filter_map(|item| {
+        let mut property = None;
+        let mut timing = parse_timing(&item, |token| {
+            if property.is_some() {
+                return false;
+            }
+            property = Some(token.to_string());
+            true
+        });
+        let property = property.unwrap_or_else(|| "all".to_string());
+        if property == "none" {
+            return None;
+        }
+        timing.delay = timing.delay.max(0.0);
+        Some(Transition { property, timing })
+    }).collect()
+}
+
+/// An `animation` value as a list of animations, without their keyframes
+pub fn parse_animations(text: &str) -> Vec<Animation> {
+    split_top_level(text, |c| c == ',').into_iter().filter_map(|item| {
+        let mut name = None;
+        let mut iterations = 1.0;
+        let mut alternate = false;
+        let timing = parse_timing(&item, |token| {
+            match token {
+                "infinite" => iterations = f64::INFINITY,
+                "alternate" | "alternate-reverse" => alternate = true,
+                "normal" | "reverse" | "forwards" | "backwards" | "both" | "running" | "paused" => {}
+                _ => match token.parse::<f64>() {
+                    Ok(count) => iterations = count.max(0.0),
+                    Err(_) if name.is_none() => name = Some(token.to_string()),
+                    Err(_) => return false,
+                },
+            }
+            true
+        });
+        let name = name.filter(|name| name != "none")?;
+        Some(Animation { name, timing, iterations, alternate, keyframes: Vec::new() })
+    }).collect()
+}
+
+// Times and an easing from one list entry; `other` claims the remaining tokens
+fn parse_timing(item: &str, mut other: impl FnMut(&str) -> bool) -> Timing {
+    let mut times = Vec::new();
+    let mut easing = Easing::EASE;
+    for token in split_top_level(item, char::is_whitespace) {
+        let token = token.to_ascii_lowercase();
+        if let Some(seconds) = parse_time(&token) {
+            times.push(seconds);
+        } else if let Some(parsed) = Easing::parse(&token) {
+            easing = parsed;
+        } else {
+            other(&token);
+        }
+    }
+    Timing {
+        duration: times.first().copied().unwrap_or(0.0).max(0.0),
+        delay: times.get(1).copied().unwrap_or(0.0),
+        easing,
+    }
+}
+
+fn parse_time(token: &str) -> Option<f64> {
+    if let Some(ms) = token.strip_suffix("ms") {
+        return ms.parse::<f64>().ok().map(|ms| ms / 1000.0);
+    }
+    token.strip_suffix('s')?.parse().ok()
+}
+
+// `text` split where `separator` matches outside parentheses, without empty pieces
+fn split_top_level(text: &str, separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if depth == 0 && separator(c) => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    parts.into_iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect()
+}
+
+// A transition in progress of one property of one element
+#[derive(Debug)]
+struct RunningTransition {
+    from: AnimatedValues,
+    to: AnimatedValues,
+    start: f64,
+    timing: Timing,
+}
+
+impl RunningTransition {
+    fn end(&self) -> f64 {
+        self.start + self.timing.delay + self.timing.duration
+    }
+}
+
+/// Transitions and animations running on one page, keyed by element. Times are the page clock's, in seconds.
+#[derive(Debug, Default)]
+pub struct Animations {
+    transitions: HashMap<(*const DOMNode, Property), RunningTransition>,
+    // Start and end time of each named animation an element has
+    started: HashMap<(*const DOMNode, String), (f64, f64)>,
+    // Elements that have anything running, so everything else is styled without a lookup
+    nodes: HashSet<*const DOMNode>,
+}
+
+impl Animations {
+    /// Forget everything, e.g. when the document is replaced
+    pub fn clear(&mut self) {
+        *self = Animations::default();
+    }
+
+    /// The values `node` shows at `now`, given its computed values and animations
+    pub fn values_at(&self, node: *const DOMNode, base: AnimatedValues, animations: &[Animation], now: f64) -> AnimatedValues {
+        let key = node;
+        if !self.nodes.contains(&key) {
+            return base;
+        }
+        let mut values = base;
+        for property in PROPERTIES {
+            if let Some(transition) = self.transitions.get(&(key, property)) {
+                let elapsed = now - transition.start - transition.timing.delay;
+                let t = if transition.timing.duration > 0.0 { (elapsed / transition.timing.duration) as f32 } else { 1.0 };
+                if elapsed >= 0.0 {
+                    let current = transition.from.lerp(&transition.to, transition.timing.easing.apply(t));
+                    values.set(&current, property);
+                } else {
+                    values.set(&transition.from, property);
+                }
+            }
+        }
+        // Animations override transitions, and later ones in the list override earlier ones
+        for animation in animations {
+            let Some((start, _)) = self.started.get(&(key, animation.name.clone())) else { continue };
+            if let Some(animated) = animation.values_at(now - start, &base) {
+                for property in PROPERTIES {
+                    if animation.keyframes.iter().any(|(_, frame)| frame.differs(&base, property)) {
+                        values.set(&animated, property);
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// `node`'s style was recomputed: start transitions for properties whose computed value changed from what
+    /// was `shown`, and start or stop its keyframe animations
+    pub fn style_changed(&mut self, node: *const DOMNode, shown: AnimatedValues, after: AnimatedValues, transitions: &[Transition], animations: &[Animation], now: f64) {
+        let key = node;
+        for property in PROPERTIES {
+            let target = self.transitions.get(&(key, property)).map_or(shown, |running| running.to);
+            if !target.differs(&after, property) {
+                continue;
+            }
+            match transitions.iter().rev().find(|t| t.covers(property)).filter(|t| t.timing.duration > 0.0) {
+                Some(transition) => {
+                    self.transitions.insert((key, property), RunningTransition { from: shown, to: after, start: now, timing: transition.timing });
+                    self.nodes.insert(key);
+                }
+                None => {
+                    self.transitions.remove(&(key, property));
+                }
+            }
+        }
+        self.start_animations(node, animations, now);
+    }
+
+    /// Start the animations `node` has that aren't running yet, and stop the ones it no longer has
+    pub fn start_animations(&mut self, node: *const DOMNode, animations: &[Animation], now: f64) {
+        let key = node;
+        self.started.retain(|(n, name), _| *n != key || animations.iter().any(|a| &a.name == name));
+        for animation in animations.iter().filter(|a| !a.keyframes.is_empty()) {
+            self.started.entry((key, animation.name.clone())).or_insert((now, animation.end(now)));
+            self.nodes.insert(key);
+        }
+    }
+
+    /// Whether `node` has a transition or animation running
+    pub fn is_running(&self, node: *const DOMNode) -> bool {
+        self.nodes.contains(&node)
+    }
+
+    /// Drop what has finished by `now`; whether anything is still moving
+    pub fn prune(&mut self, now: f64) -> bool {
+        self.transitions.retain(|_, transition| transition.end() > now);
+        // Finished animations stay recorded, so they don't start over when the style is recomputed
+        let running: HashSet<*const DOMNode> = self.transitions.keys().map(|(node, _)| *node)
+            .chain(self.started.iter().filter(|(_, (_, end))| *end > now).map(|((node, _), _)| *node))
+            .collect();
+        self.nodes = running;
+        !self.nodes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(opacity: f32) -> AnimatedValues {
+        AnimatedValues { opacity, transform: Transform::default(), color: Color32::BLACK }
+    }
+
+    #[test]
+    fn test_parse_timing_lists() {
+        let transitions = parse_transitions("opacity 300ms ease-in, transform 1s cubic-bezier(0, 0, 1, 1) 0.5s");
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0].property, "opacity");
+        assert_eq!(transitions[0].timing.duration, 0.3);
+        assert_eq!(transitions[1].timing.delay, 0.5);
+        assert!((transitions[1].timing.easing.apply(0.25) - 0.25).abs() < 1e-4);
+
+        let animations = parse_animations("pulse 2s infinite alternate linear, none");
+        assert_eq!(animations.len(), 1);
+        assert_eq!(animations[0].name, "pulse");
+        assert!(animations[0].iterations.is_infinite() && animations[0].alternate);
+
+        let context = LengthContext { font_size: 10.0, root_font_size: 10.0, viewport: egui::vec2(100.0, 100.0) };
+        let transform = Transform::parse("translate(10px, 2em) scale(150%)", &context).unwrap();
+        assert_eq!(transform, Transform { translate: egui::vec2(10.0, 20.0), scale: 1.5 });
+        assert!(Transform::parse("rotate(45deg)", &context).is_none());
+    }
+
+    #[test]
+    fn test_transitions_and_keyframes_interpolate() {
+        let node = DOMNode::new_element("div".to_string());
+        let transitions = parse_transitions("opacity 1s linear");
+        let mut animations = Animations::default();
+        animations.style_changed(&node, values(0.0), values(1.0), &transitions, &[], 10.0);
+        assert_eq!(animations.values_at(&node, values(1.0), &[], 10.5).opacity, 0.5);
+        assert!(animations.prune(10.5));
+        assert!(!animations.prune(11.0));
+        assert_eq!(animations.values_at(&node, values(1.0), &[], 11.0).opacity, 1.0);
+
+        let mut pulse = parse_animations("pulse 1s linear 2 alternate").remove(0);
+        pulse.keyframes = vec![(0.0, values(0.0)), (1.0, values(0.8))];
+        animations.start_animations(&node, std::slice::from_ref(&pulse), 0.0);
+        assert!((animations.values_at(&node, values(1.0), std::slice::from_ref(&pulse), 0.25).opacity - 0.2).abs() < 1e-5);
+        // The second iteration runs backwards, and once both are done the element's own value shows again
+        assert!((animations.values_at(&node, values(1.0), std::slice::from_ref(&pulse), 1.25).opacity - 0.6).abs() < 1e-5);
+        assert!(animations.prune(1.5));
+        assert!(!animations.prune(2.0));
+        assert_eq!(animations.values_at(&node, values(1.0), std::slice::from_ref(&pulse), 2.0).opacity, 1.0);
+    }
+}
@@ -0,0 +1,274 @@
+// Conformance cases for the engine, kept under tests/conformance: html5lib tree-construction tests for the HTML
+// parser, css-parsing-tests style fixtures for the CSS parser, and reference pictures of layout cases. Run with
+// `cargo test --features engine-conformance`; set NEON_UPDATE_REFERENCES=1 to write the pictures anew after a
+// change to how pages look.
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use super::css_parser::{self, Declaration, Stylesheet, Unit, Value};
+use super::dom::DOMNode;
+use super::html_parser;
+use super::{ParsedDocument, WebPage};
+use crate::platform::headless;
+use crate::ui::theme::{NeonTheme, Palette};
+
+// Viewport layout cases are pictured in
+const LAYOUT_SIZE: (u32, u32) = (240, 160);
+// A pixel differs from its reference when a channel is further off than this
+const CHANNEL_TOLERANCE: u8 = 16;
+// Share of pixels that may differ, for antialiasing that varies between builds
+const PIXEL_TOLERANCE: f64 = 0.005;
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("conformance")
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|e| panic!("Could not read {}: {}", path.display(), e))
+}
+
+// One case of an html5lib .dat file: the markup and the tree it should build
+struct TreeCase {
+    data: Vec<String>,
+    document: Vec<String>,
+}
+
+fn read_tree_cases(text: &str) -> Vec<TreeCase> {
+    let mut cases = Vec::new();
+    let mut section = "";
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix('#') {
+            section = name;
+            if section == "data" {
+                cases.push(TreeCase { data: Vec::new(), document: Vec::new() });
+            }
+            continue;
+        }
+        let Some(case) = cases.last_mut() else { continue };
+        match section {
+            "data" => case.data.push(line.to_string()),
+            "document" if line.starts_with('|') => case.document.push(line.to_string()),
+            _ => {}
+        }
+    }
+    cases
+}
+
+// The tree in html5lib's format: one node per line, indented two spaces a level, attributes sorted under
+// their element
+fn dump(node: &DOMNode, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    match node {
+        DOMNode::Element { tag_name, attributes, children } => {
+            lines.push(format!("| {}<{}>", indent, tag_name));
+            let mut names: Vec<&String> = attributes.keys().collect();
+            names.sort();
+            for name in names {
+                lines.push(format!("| {}  {}=\"{}\"", indent, name, attributes[name]));
+            }
+            for child in children {
+                dump(child, depth + 1, lines);
+            }
+        }
+        DOMNode::Text(text) => lines.push(format!("| {}\"{}\"", indent, text)),
+        DOMNode::Comment(text) => lines.push(format!("| {}<!-- {} -->", indent, text)),
+    }
+}
+
+// A dumped node with the lines nested under it
+struct DumpedNode {
+    line: String,
+    children: Vec<DumpedNode>,
+}
+
+fn nest(lines: &[String]) -> Vec<DumpedNode> {
+    let mut roots: Vec<DumpedNode> = Vec::new();
+    for line in lines {
+        let content = line.strip_prefix("| ").unwrap_or(line);
+        let depth = (content.len() - content.trim_start_matches(' ').len()) / 2;
+        let mut siblings = &mut roots;
+        for _ in 0..depth {
+            if siblings.is_empty() {
+                break;
+            }
+            let parent = siblings.len() - 1;
+            siblings = &mut siblings[parent].children;
+        }
+        siblings.push(DumpedNode { line: content.trim_start_matches(' ').to_string(), children: Vec::new() });
+    }
+    roots
+}
+
+// Put a dump in the terms both trees can be compared in. Our parser doesn't create <head> and <body> or keep
+// comments, doctypes and whitespace between tags, and doesn't note namespaces, so those are left out.
+fn normalize(lines: &[String]) -> Vec<String> {
+    fn flatten(nodes: &[DumpedNode], depth: usize, out: &mut Vec<String>) {
+        for node in nodes {
+            let line = node.line.replacen("<svg ", "<", 1).replacen("<math ", "<", 1);
+            if matches!(line.as_str(), "<html>" | "<head>" | "<body>") {
+                flatten(&node.children, depth, out);
+                continue;
+            }
+            let whitespace = line.starts_with('"') && line.trim_matches('"').trim().is_empty();
+            if whitespace || line.starts_with("<!") {
+                continue;
+            }
+            out.push(format!("| {}{}", "  ".repeat(depth), line));
+            flatten(&node.children, depth + 1, out);
+        }
+    }
+    let mut out = Vec::new();
+    flatten(&nest(lines), 0, &mut out);
+    out
+}
+
+// Stylesheets described in one line per rule, in the terms the fixtures use
+fn describe(stylesheet: &Stylesheet) -> Vec<String> {
+    let declarations = |declarations: &[Declaration]| {
+        declarations.iter()
+            .map(|d| format!("{}: {}{}", d.name, describe_value(&d.value), if d.important { " !important" } else { "" }))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+    let rules = stylesheet.rules.iter().map(|rule| {
+        let selectors = rule.selectors.iter()
+            .map(|selector| {
+                selector.simple.iter()
+                    .map(|simple| {
+                        let mut text = simple.tag_name.clone().unwrap_or_default();
+                        if let Some(id) = &simple.id {
+                            text.push_str(&format!("#{}", id));
+                        }
+                        for class in &simple.class {
+                            text.push_str(&format!(".{}", class));
+                        }
+                        for pseudo in &simple.pseudo_classes {
+                            text.push_str(&format!(":{:?}", pseudo).to_lowercase());
+                        }
+                        if text.is_empty() { "*".to_string() } else { text }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} {{ {} }}", selectors, declarations(&rule.declarations))
+    });
+    let font_faces = stylesheet.font_faces.iter().map(|face| format!("@font-face {{ {} }}", declarations(&face.declarations)));
+    let keyframes = stylesheet.keyframes.iter().map(|keyframes| {
+        let frames = keyframes.frames.iter()
+            .map(|(offset, frame)| format!("{}% {{ {} }}", offset * 100.0, declarations(frame)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("@keyframes {} {{ {} }}", keyframes.name, frames)
+    });
+    rules.chain(font_faces).chain(keyframes).collect()
+}
+
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::Keyword(keyword) => keyword.clone(),
+        Value::Length(length, unit) => {
+            let unit = match unit {
+                Unit::Px => "px",
+                Unit::Em => "em",
+                Unit::Rem => "rem",
+                Unit::Percent => "%",
+                Unit::Vw => "vw",
+                Unit::Vh => "vh",
+                Unit::Fr => "fr",
+            };
+            format!("{}{}", length, unit)
+        }
+        Value::Number(number) => number.to_string(),
+        Value::ColorValue(color) if color.a == 255 => format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+        Value::ColorValue(color) => format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a),
+        Value::List(values) => values.iter().map(describe_value).collect::<Vec<_>>().join(" "),
+    }
+}
+
+fn picture(html: &str) -> image::RgbaImage {
+    let mut page = WebPage::from_parsed(ParsedDocument::parse(html));
+    page.load_inline_styles("about:blank");
+    let ctx = egui::Context::default();
+    NeonTheme::set_palette(&ctx, Palette::NEON_DARK);
+    headless::screenshot(&page, &ctx, LAYOUT_SIZE)
+}
+
+// Share of pixels that differ past the tolerance, or None when the sizes differ
+fn difference(actual: &image::RgbaImage, reference: &image::RgbaImage) -> Option<f64> {
+    if actual.dimensions() != reference.dimensions() {
+        return None;
+    }
+    let differing = actual.pixels().zip(reference.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > CHANNEL_TOLERANCE))
+        .count();
+    Some(differing as f64 / (actual.width() * actual.height()) as f64)
+}
+
+#[test]
+fn test_html5lib_tree_construction() {
+    let cases = read_tree_cases(&read(&fixtures().join("html").join("tree-construction.dat")));
+    assert!(!cases.is_empty());
+    let failures: Vec<String> = cases.iter()
+        .filter_map(|case| {
+            let data = case.data.join("\n");
+            let mut actual = Vec::new();
+            dump(&html_parser::parse(&data), 0, &mut actual);
+            let (actual, expected) = (normalize(&actual), normalize(&case.document));
+            (actual != expected).then(|| format!("{:?}\nexpected:\n{}\nparsed:\n{}", data, expected.join("\n"), actual.join("\n")))
+        })
+        .collect();
+    assert!(failures.is_empty(), "{} of {} tree-construction cases failed:\n\n{}", failures.len(), cases.len(), failures.join("\n\n"));
+}
+
+#[test]
+fn test_css_parsing_fixtures() {
+    let fixtures: serde_json::Value = serde_json::from_str(&read(&fixtures().join("css").join("stylesheets.json"))).unwrap();
+    // A flat array of stylesheet and expected rules, alternating
+    let pairs = fixtures.as_array().expect("an array of fixtures");
+    assert!(!pairs.is_empty() && pairs.len().is_multiple_of(2));
+    let failures: Vec<String> = pairs.chunks(2)
+        .filter_map(|pair| {
+            let css = pair[0].as_str().expect("stylesheet text");
+            let expected: Vec<String> = serde_json::from_value(pair[1].clone()).expect("expected rules");
+            let actual = describe(&css_parser::parse(css));
+            (actual != expected).then(|| format!("{:?}\nexpected: {:#?}\nparsed: {:#?}", css, expected, actual))
+        })
+        .collect();
+    assert!(failures.is_empty(), "{} of {} CSS fixtures failed:\n\n{}", failures.len(), pairs.len() / 2, failures.join("\n\n"));
+}
+
+#[test]
+fn test_layout_reference_images() {
+    let directory = fixtures().join("layout");
+    let update = std::env::var_os("NEON_UPDATE_REFERENCES").is_some();
+    let mut cases: Vec<PathBuf> = std::fs::read_dir(&directory).unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "html"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty());
+
+    let mut failures = Vec::new();
+    for case in &cases {
+        let actual = picture(&read(case));
+        let reference_path = case.with_extension("png");
+        let reference = image::open(&reference_path).ok().map(|reference| reference.to_rgba8());
+        if update || reference.is_none() {
+            actual.save(&reference_path).unwrap();
+            if !update {
+                failures.push(format!("{} had no reference picture; wrote one to check in", case.display()));
+            }
+            continue;
+        }
+        match reference.and_then(|reference| difference(&actual, &reference)) {
+            Some(share) if share <= PIXEL_TOLERANCE => {}
+            share => {
+                let actual_path = case.with_extension("actual.png");
+                actual.save(&actual_path).unwrap();
+                failures.push(format!("{} differs from its reference ({:?} of pixels); see {}", case.display(), share, actual_path.display()));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
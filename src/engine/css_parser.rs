@@ -1,10 +1,26 @@
 // CSS Parser - Basic implementation for styling
 
-// use std::collections::HashMap;
+use std::collections::HashSet;
+use crate::engine::dom::DOMNode;
 
 #[derive(Debug, Clone)]
 pub struct Stylesheet {
     pub rules: Vec<Rule>,
+    pub font_faces: Vec<FontFaceRule>,
+    pub keyframes: Vec<KeyframesRule>,
+}
+
+/// An `@font-face` block; its descriptors are interpreted by the font loader
+#[derive(Debug, Clone)]
+pub struct FontFaceRule {
+    pub declarations: Vec<Declaration>,
+}
+
+/// An `@keyframes` block: declarations at offsets between 0 (`from`) and 1 (`to`), in source order
+#[derive(Debug, Clone)]
+pub struct KeyframesRule {
+    pub name: String,
+    pub frames: Vec<(f32, Vec<Declaration>)>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,19 +39,114 @@ pub struct SimpleSelector {
     pub tag_name: Option<String>,
     pub id: Option<String>,
     pub class: Vec<String>,
+    pub pseudo_classes: Vec<PseudoClass>,
+}
+
+/// The pseudo-classes we can match, all of which depend on pointer, keyboard or history state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoClass {
+    Hover,
+    Active,
+    Link,
+    Visited,
+    Focus,
+    FocusVisible,
+}
+
+impl PseudoClass {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hover" => Some(Self::Hover),
+            "active" => Some(Self::Active),
+            "link" => Some(Self::Link),
+            "visited" => Some(Self::Visited),
+            "focus" => Some(Self::Focus),
+            "focus-visible" => Some(Self::FocusVisible),
+            _ => None,
+        }
+    }
+}
+
+/// What dynamic pseudo-classes are matched against: where the pointer and focus are and which links were visited
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementState {
+    /// The element under the pointer and its ancestors. Only compared, never dereferenced.
+    pub hovered: HashSet<*const DOMNode>,
+    /// The element being pressed and its ancestors
+    pub active: HashSet<*const DOMNode>,
+    /// hrefs, as written in the document, that lead to pages in the history
+    pub visited: HashSet<String>,
+    /// The element with keyboard focus
+    pub focused: Option<*const DOMNode>,
+    /// Set when focus was moved with the keyboard, which :focus-visible shows
+    pub focus_visible: bool,
+}
+
+impl Selector {
+    /// Whether this matches `element`, given its ancestors ordered from the root down
+    pub fn matches(&self, element: &DOMNode, ancestors: &[&DOMNode], state: &ElementState) -> bool {
+        let Some((subject, rest)) = self.simple.split_last() else {
+            return false;
+        };
+        // Descendant combinators: each earlier compound must match some ancestor further out
+        let mut outer = ancestors.iter().rev();
+        subject.matches(element, state)
+            && rest.iter().rev().all(|compound| outer.any(|ancestor| compound.matches(ancestor, state)))
+    }
+    
+    /// (ids, classes, tags), compared lexicographically; pseudo-classes count as classes
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.simple.iter().fold((0, 0, 0), |(ids, classes, tags), s| {
+            (ids + s.id.is_some() as usize, classes + s.class.len() + s.pseudo_classes.len(), tags + s.tag_name.is_some() as usize)
+        })
+    }
+}
+
+impl SimpleSelector {
+    pub fn matches(&self, element: &DOMNode, state: &ElementState) -> bool {
+        let DOMNode::Element { tag_name, attributes, .. } = element else {
+            return false;
+        };
+        let classes = attributes.get("class").map(String::as_str).unwrap_or_default();
+        self.tag_name.as_ref().is_none_or(|tag| tag.eq_ignore_ascii_case(tag_name))
+            && self.id.as_ref().is_none_or(|id| attributes.get("id") == Some(id))
+            && self.class.iter().all(|class| classes.split_whitespace().any(|c| c == class))
+            && self.pseudo_classes.iter().all(|pseudo| {
+                let node = element as *const DOMNode;
+                // Only links with a destination are :link or :visited
+                let visited = (tag_name == "a" || tag_name == "area")
+                    .then(|| attributes.get("href"))
+                    .flatten()
+                    .map(|href| state.visited.contains(href.trim()));
+                match pseudo {
+                    PseudoClass::Hover => state.hovered.contains(&node),
+                    PseudoClass::Active => state.active.contains(&node),
+                    PseudoClass::Link => visited == Some(false),
+                    PseudoClass::Visited => visited == Some(true),
+                    PseudoClass::Focus => state.focused == Some(node),
+                    PseudoClass::FocusVisible => state.focus_visible && state.focused == Some(node),
+                }
+            })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Declaration {
     pub name: String,
     pub value: Value,
+    /// Marked `!important`, which outranks every normal declaration
+    pub important: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Keyword(String),
     Length(f32, Unit),
+    /// A unitless number, e.g. `flex-grow: 2`
+    Number(f32),
     ColorValue(Color),
+    /// Space-separated values, e.g. `flex: 1 1 200px`
+    List(Vec<Value>),
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +155,12 @@ pub enum Unit {
     Em,
     Rem,
     Percent,
+    /// Percent of the viewport width
+    Vw,
+    /// Percent of the viewport height
+    Vh,
+    /// Fraction of the free space in a grid container
+    Fr,
 }
 
 #[derive(Debug, Clone)]
@@ -54,11 +171,26 @@ pub struct Color {
     pub a: u8,
 }
 
+// Properties whose comma-separated values are kept as written, to be split by whoever uses them
+const RAW_PROPERTIES: &[&str] = &["font", "font-family", "src", "transform", "transition", "animation"];
+
 pub fn parse(css: &str) -> Stylesheet {
     let mut parser = CSSParser::new(css);
     parser.parse_stylesheet()
 }
 
+/// Parse a single property value, e.g. the arguments of `repeat()`
+pub fn parse_value(css: &str) -> Value {
+    let mut parser = CSSParser::new(css);
+    parser.parse_value()
+}
+
+/// Parse a bare declaration block such as the contents of a `style` attribute
+pub fn parse_declarations(css: &str) -> Vec<Declaration> {
+    let mut parser = CSSParser::new(css);
+    parser.parse_declarations()
+}
+
 struct CSSParser {
     input: String,
     position: usize,
@@ -74,15 +206,81 @@ impl CSSParser {
     
     fn parse_stylesheet(&mut self) -> Stylesheet {
         let mut rules = Vec::new();
+        let mut font_faces = Vec::new();
+        let mut keyframes = Vec::new();
         
         while !self.at_end() {
             self.skip_whitespace();
-            if let Some(rule) = self.parse_rule() {
-                rules.push(rule);
+            if self.at_end() {
+                break;
+            }
+            if self.input[self.position..].starts_with("@font-face") {
+                self.position += "@font-face".len();
+                self.skip_whitespace();
+                if self.peek() == '{' {
+                    self.consume_char();
+                    font_faces.push(FontFaceRule { declarations: self.parse_declarations() });
+                    self.consume_char();
+                    continue;
+                }
+            }
+            let rest = &self.input[self.position..];
+            if let Some(prefix) = ["@keyframes", "@-webkit-keyframes"].into_iter().find(|p| rest.starts_with(p)) {
+                self.position += prefix.len();
+                if let Some(rule) = self.parse_keyframes() {
+                    keyframes.push(rule);
+                    continue;
+                }
+            }
+            match self.parse_rule() {
+                Some(rule) => rules.push(rule),
+                // At-rules and selectors we don't support are skipped whole
+                None => self.skip_rule(),
             }
         }
         
-        Stylesheet { rules }
+        Stylesheet { rules, font_faces, keyframes }
+    }
+    
+    // The name and frames of an `@keyframes` block, after the at-keyword
+    fn parse_keyframes(&mut self) -> Option<KeyframesRule> {
+        self.skip_whitespace();
+        let name = self.parse_identifier();
+        self.skip_whitespace();
+        if name.is_empty() || self.peek() != '{' {
+            return None;
+        }
+        self.consume_char();
+        
+        let mut frames = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.at_end() || self.peek() == '}' {
+                self.consume_char();
+                break;
+            }
+            let start = self.position;
+            self.skip_until(&['{', '}']);
+            let selectors = self.input[start..self.position].to_string();
+            if self.peek() != '{' {
+                continue;
+            }
+            self.consume_char();
+            let declarations = self.parse_declarations();
+            self.consume_char();
+            // `from, 50%` shares one block between several offsets
+            for selector in selectors.split(',').map(|s| s.trim().to_ascii_lowercase()) {
+                let offset = match selector.as_str() {
+                    "from" => Some(0.0),
+                    "to" => Some(1.0),
+                    percent => percent.strip_suffix('%').and_then(|p| p.trim().parse::<f32>().ok()).map(|p| p / 100.0),
+                };
+                if let Some(offset) = offset.filter(|o| (0.0..=1.0).contains(o)) {
+                    frames.push((offset, declarations.clone()));
+                }
+            }
+        }
+        Some(KeyframesRule { name, frames })
     }
     
     fn parse_rule(&mut self) -> Option<Rule> {
@@ -152,10 +350,29 @@ impl CSSParser {
             tag_name: None,
             id: None,
             class: Vec::new(),
+            pseudo_classes: Vec::new(),
         };
+        // `*` matches any element, as a compound with nothing else in it does
+        let mut universal = false;
         
         loop {
             match self.peek() {
+                '*' if !universal && selector.tag_name.is_none() => {
+                    self.consume_char();
+                    universal = true;
+                }
+                ':' => {
+                    // A pseudo-class we can't match leaves the ':' in place, which drops the rule
+                    let start = self.position;
+                    self.consume_char();
+                    match PseudoClass::from_name(&self.parse_identifier()) {
+                        Some(pseudo) => selector.pseudo_classes.push(pseudo),
+                        None => {
+                            self.position = start;
+                            break;
+                        }
+                    }
+                }
                 '#' => {
                     self.consume_char();
                     selector.id = Some(self.parse_identifier());
@@ -171,7 +388,7 @@ impl CSSParser {
             }
         }
         
-        if selector.tag_name.is_some() || selector.id.is_some() || !selector.class.is_empty() {
+        if universal || selector.tag_name.is_some() || selector.id.is_some() || !selector.class.is_empty() || !selector.pseudo_classes.is_empty() {
             Some(selector)
         } else {
             None
@@ -186,6 +403,8 @@ impl CSSParser {
             
             if let Some(declaration) = self.parse_declaration() {
                 declarations.push(declaration);
+            } else {
+                self.skip_until(&[';', '}']);
             }
             
             self.skip_whitespace();
@@ -198,7 +417,9 @@ impl CSSParser {
     }
     
     fn parse_declaration(&mut self) -> Option<Declaration> {
+        // Property names are case-insensitive, except custom properties
         let name = self.parse_identifier();
+        let name = if name.starts_with("--") { name } else { name.to_ascii_lowercase() };
         
         self.skip_whitespace();
         if self.peek() != ':' {
@@ -207,45 +428,142 @@ impl CSSParser {
         self.consume_char();
         
         self.skip_whitespace();
-        let value = self.parse_value();
+        let value = if RAW_PROPERTIES.contains(&name.as_str()) {
+            self.parse_raw_value()
+        } else {
+            self.parse_value()
+        };
+        
+        self.skip_whitespace();
+        let mut important = false;
+        if self.peek() == '!' {
+            self.consume_char();
+            self.skip_whitespace();
+            important = self.parse_identifier().eq_ignore_ascii_case("important");
+        }
+        // Ignore anything else after the value
+        self.skip_until(&[';', '}']);
         
-        Some(Declaration { name, value })
+        if name.is_empty() {
+            return None;
+        }
+        Some(Declaration { name, value, important })
     }
     
     fn parse_value(&mut self) -> Value {
-        self.skip_whitespace();
+        let mut values = Vec::new();
+        
+        loop {
+            self.skip_whitespace();
+            if self.at_end() || matches!(self.peek(), ';' | '}' | '!') {
+                break;
+            }
+            
+            let start = self.position;
+            let value = self.parse_single_value();
+            if self.position == start {
+                // Commas, slashes and other separators carry no value of their own
+                self.consume_char();
+                continue;
+            }
+            values.push(value);
+        }
         
-        if self.peek().is_numeric() {
+        match values.len() {
+            0 => Value::Keyword(String::new()),
+            1 => values.remove(0),
+            _ => Value::List(values),
+        }
+    }
+    
+    // The value as written, up to the end of the declaration outside any quotes or parentheses
+    fn parse_raw_value(&mut self) -> Value {
+        let start = self.position;
+        let mut depth = 0;
+        let mut quote = None;
+        
+        while !self.at_end() {
+            match (self.peek(), quote) {
+                (c, Some(q)) if c == q => quote = None,
+                (_, Some(_)) => {}
+                ('"' | '\'', None) => quote = Some(self.peek()),
+                ('(', None) => depth += 1,
+                (')', None) => depth -= 1,
+                (';' | '}' | '!', None) if depth <= 0 => break,
+                _ => {}
+            }
+            self.consume_char();
+        }
+        
+        Value::Keyword(self.input[start..self.position].trim().to_string())
+    }
+    
+    fn parse_single_value(&mut self) -> Value {
+        if self.starts_number() {
             self.parse_length()
         } else if self.peek() == '#' {
             self.parse_color()
         } else {
-            Value::Keyword(self.parse_identifier())
+            let identifier = self.parse_identifier();
+            if !identifier.is_empty() && self.peek() == '(' {
+                // Functions such as rgb() or calc() are kept whole until they are resolved
+                Value::Keyword(format!("{}{}", identifier, self.parse_parenthesized()))
+            } else {
+                Value::Keyword(identifier)
+            }
+        }
+    }
+    
+    fn starts_number(&self) -> bool {
+        let mut chars = self.input[self.position..].chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('.') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+            Some('-') | Some('+') => match chars.next() {
+                Some(c) if c.is_ascii_digit() => true,
+                Some('.') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+                _ => false,
+            },
+            _ => false,
         }
     }
     
     fn parse_length(&mut self) -> Value {
         let mut num_str = String::new();
         
-        while !self.at_end() && (self.peek().is_numeric() || self.peek() == '.') {
+        if matches!(self.peek(), '-' | '+') {
+            num_str.push(self.consume_char());
+        }
+        while !self.at_end() && (self.peek().is_ascii_digit() || self.peek() == '.') {
             num_str.push(self.consume_char());
         }
         
         let number: f32 = num_str.parse().unwrap_or(0.0);
-        let unit = self.parse_unit();
-        
-        Value::Length(number, unit)
+        match self.parse_unit() {
+            Some(unit) => Value::Length(number, unit),
+            None => Value::Number(number),
+        }
     }
     
-    fn parse_unit(&mut self) -> Unit {
+    fn parse_unit(&mut self) -> Option<Unit> {
+        if self.peek() == '%' {
+            self.consume_char();
+            return Some(Unit::Percent);
+        }
+        
         let unit_str = self.parse_identifier();
+        if unit_str.is_empty() {
+            return None;
+        }
         
         match unit_str.to_lowercase().as_str() {
-            "px" => Unit::Px,
-            "em" => Unit::Em,
-            "rem" => Unit::Rem,
-            "%" => Unit::Percent,
-            _ => Unit::Px,
+            "px" => Some(Unit::Px),
+            "em" => Some(Unit::Em),
+            "rem" => Some(Unit::Rem),
+            "vw" => Some(Unit::Vw),
+            "vh" => Some(Unit::Vh),
+            "fr" => Some(Unit::Fr),
+            _ => Some(Unit::Px),
         }
     }
     
@@ -253,15 +571,73 @@ impl CSSParser {
         self.consume_char(); // Skip '#'
         
         let mut hex = String::new();
-        while !self.at_end() && hex.len() < 6 && self.peek().is_ascii_hexdigit() {
+        while !self.at_end() && hex.len() < 8 && self.peek().is_ascii_hexdigit() {
             hex.push(self.consume_char());
         }
         
-        let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0);
-        let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0);
-        let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0);
+        // Expand the #rgb shorthand to #rrggbb
+        if hex.len() == 3 || hex.len() == 4 {
+            hex = hex.chars().flat_map(|c| [c, c]).collect();
+        }
+        let channel = |index: usize| {
+            hex.get(index * 2..index * 2 + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        };
         
-        Value::ColorValue(Color { r, g, b, a: 255 })
+        Value::ColorValue(Color {
+            r: channel(0).unwrap_or(0),
+            g: channel(1).unwrap_or(0),
+            b: channel(2).unwrap_or(0),
+            a: channel(3).unwrap_or(255),
+        })
+    }
+    
+    // Consume a balanced (...) group and return it including the parentheses
+    fn parse_parenthesized(&mut self) -> String {
+        let start = self.position;
+        let mut depth = 0;
+        
+        while !self.at_end() {
+            match self.consume_char() {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        self.input[start..self.position].to_string()
+    }
+    
+    // Skip an unparseable rule: an at-rule statement up to ';', or anything up to the end of its block
+    fn skip_rule(&mut self) {
+        while !self.at_end() {
+            match self.consume_char() {
+                ';' => return,
+                '{' => {
+                    let mut depth = 1;
+                    while !self.at_end() && depth > 0 {
+                        match self.consume_char() {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    fn skip_until(&mut self, stops: &[char]) {
+        while !self.at_end() && !stops.contains(&self.peek()) {
+            self.consume_char();
+        }
     }
     
     fn parse_identifier(&mut self) -> String {
@@ -280,7 +656,7 @@ impl CSSParser {
     }
     
     fn peek(&self) -> char {
-        self.input.chars().nth(self.position).unwrap_or('\0')
+        self.input[self.position..].chars().next().unwrap_or('\0')
     }
     
     fn consume_char(&mut self) -> char {
@@ -292,12 +668,123 @@ impl CSSParser {
     }
     
     fn skip_whitespace(&mut self) {
-        while !self.at_end() && self.peek().is_whitespace() {
-            self.consume_char();
+        loop {
+            while !self.at_end() && self.peek().is_whitespace() {
+                self.consume_char();
+            }
+            
+            // Comments count as whitespace
+            if !self.input[self.position..].starts_with("/*") {
+                break;
+            }
+            match self.input[self.position + 2..].find("*/") {
+                Some(end) => self.position += end + 4,
+                None => self.position = self.input.len(),
+            }
         }
     }
     
     fn at_end(&self) -> bool {
         self.position >= self.input.len()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values() {
+        let declarations = parse_declarations("flex: 1 1 50%; flex-grow: 2; margin: -4px; color: #fff !important; width: calc(100% - 2px)");
+        let values: Vec<(&str, &Value)> = declarations.iter().map(|d| (d.name.as_str(), &d.value)).collect();
+
+        assert!(matches!(values[0], ("flex", Value::List(list))
+            if matches!(list.as_slice(), [Value::Number(_), Value::Number(_), Value::Length(p, Unit::Percent)] if *p == 50.0)));
+        assert!(matches!(values[1], ("flex-grow", Value::Number(n)) if *n == 2.0));
+        assert!(matches!(values[2], ("margin", Value::Length(n, Unit::Px)) if *n == -4.0));
+        assert!(matches!(values[3], ("color", Value::ColorValue(Color { r: 255, g: 255, b: 255, a: 255 }))));
+        assert!(matches!(values[4], ("width", Value::Keyword(k)) if k == "calc(100% - 2px)"));
+        assert!(declarations[3].important && !declarations[2].important);
+    }
+
+    #[test]
+    fn test_unsupported_rules_are_skipped() {
+        let stylesheet = parse("/* header */ @import url(a.css); @media print { p { color: red } } a:first-child { color: blue } .nav { display: flex }");
+        assert_eq!(stylesheet.rules.len(), 1);
+        assert_eq!(stylesheet.rules[0].selectors[0].simple[0].class, vec!["nav".to_string()]);
+    }
+
+    #[test]
+    fn test_font_values_are_kept_raw() {
+        let stylesheet = parse(r#"@font-face { font-family: "My Font"; src: url("a;b.woff2") format('woff2'), local(Arial) }
+            body { font: bold 12px/1.4 "Helvetica Neue", Arial !important; color: red }"#);
+        assert_eq!(stylesheet.font_faces.len(), 1);
+        let face = &stylesheet.font_faces[0].declarations;
+        assert!(matches!(&face[0].value, Value::Keyword(k) if k == "\"My Font\""));
+        assert!(matches!(&face[1].value, Value::Keyword(k) if k == "url(\"a;b.woff2\") format('woff2'), local(Arial)"));
+
+        let body = &stylesheet.rules[0].declarations;
+        assert!(matches!(&body[0].value, Value::Keyword(k) if k == "bold 12px/1.4 \"Helvetica Neue\", Arial"));
+        assert!(body[0].important);
+        assert_eq!(body[1].name, "color");
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let stylesheet = parse("@keyframes pulse { from { opacity: 0 } 50%, 75% { opacity: 0.5 } to { opacity: 1 } } \
+            p { animation: pulse 1s infinite, spin 2s; transition: opacity 0.3s, color 1s }");
+        assert_eq!(stylesheet.keyframes.len(), 1);
+        let pulse = &stylesheet.keyframes[0];
+        assert_eq!(pulse.name, "pulse");
+        assert_eq!(pulse.frames.iter().map(|(offset, _)| *offset).collect::<Vec<_>>(), vec![0.0, 0.5, 0.75, 1.0]);
+        let p = &stylesheet.rules[0].declarations;
+        assert!(matches!(&p[0].value, Value::Keyword(k) if k == "pulse 1s infinite, spin 2s"));
+        assert!(matches!(&p[1].value, Value::Keyword(k) if k == "opacity 0.3s, color 1s"));
+    }
+
+    #[test]
+    fn test_descendant_selectors() {
+        let mut article = DOMNode::new_element("article".to_string());
+        article.set_attribute("id".to_string(), "main".to_string());
+        let mut p = DOMNode::new_element("p".to_string());
+        p.set_attribute("class".to_string(), "lead note".to_string());
+        let body = DOMNode::new_element("body".to_string());
+
+        let stylesheet = parse("#main p.note, body p { } section p { }");
+        let selectors: Vec<&Selector> = stylesheet.rules.iter().flat_map(|r| &r.selectors).collect();
+        let state = ElementState::default();
+        assert!(selectors[0].matches(&p, &[&body, &article], &state));
+        assert!(!selectors[0].matches(&p, &[&body], &state));
+        assert!(!selectors.last().unwrap().matches(&p, &[&body, &article], &state));
+        assert_eq!(selectors[0].specificity(), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_dynamic_pseudo_classes() {
+        let mut link = DOMNode::new_element("a".to_string());
+        link.set_attribute("href".to_string(), "/docs".to_string());
+        let nav = DOMNode::new_element("nav".to_string());
+
+        let stylesheet = parse("a:link { } a:visited { } nav:hover a { } a:HOVER:active { }");
+        let selectors: Vec<&Selector> = stylesheet.rules.iter().flat_map(|r| &r.selectors).collect();
+        assert_eq!(selectors.len(), 4);
+        assert_eq!(selectors[3].specificity(), (0, 2, 1));
+
+        let mut state = ElementState::default();
+        assert!(selectors[0].matches(&link, &[&nav], &state));
+        assert!(!selectors[1].matches(&link, &[&nav], &state));
+        assert!(!selectors[2].matches(&link, &[&nav], &state));
+
+        state.visited.insert("/docs".to_string());
+        state.hovered.insert(&nav);
+        assert!(!selectors[0].matches(&link, &[&nav], &state));
+        assert!(selectors[1].matches(&link, &[&nav], &state));
+        assert!(selectors[2].matches(&link, &[&nav], &state));
+        assert!(!selectors[3].matches(&link, &[&nav], &state));
+
+        let focus = parse("a:focus { } a:focus-visible { }");
+        state.focused = Some(&link);
+        assert!(focus.rules[0].selectors[0].matches(&link, &[&nav], &state));
+        assert!(!focus.rules[1].selectors[0].matches(&link, &[&nav], &state));
+    }
+}
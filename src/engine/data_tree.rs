@@ -0,0 +1,715 @@
+// JSON and XML documents as a tree of keys and values for the data viewer. Both are parsed here rather than with
+// serde_json so that keys keep their order and numbers are shown exactly as written
+use anyhow::{Result, anyhow};
+
+// Nesting past this is taken for a hostile document
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    Json,
+    Xml,
+}
+
+impl DataFormat {
+    /// The format a response of this type is in; None for types the data viewer doesn't show
+    pub fn of(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/json" | "text/json" => Some(DataFormat::Json),
+            "application/xml" | "text/xml" => Some(DataFormat::Xml),
+            // SVG and XHTML are drawn as documents
+            "image/svg+xml" | "application/xhtml+xml" => None,
+            _ if essence.ends_with("+json") => Some(DataFormat::Json),
+            _ if essence.ends_with("+xml") => Some(DataFormat::Xml),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Xml => "XML",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+    Element,
+    Attribute,
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataNode {
+    /// The object key, array index, tag name, "@attribute" or "#text"
+    pub key: String,
+    pub kind: NodeKind,
+    /// A leaf's value as shown; JSON strings without their quotes. An element holding only text has it here
+    pub value: String,
+    /// Where the node is: JSONPath such as `$.items[0].name` or XPath such as `/feed/entry[2]/@id`
+    pub path: String,
+    pub depth: usize,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// A parsed document; the first node is the root value or element
+pub struct DataTree {
+    pub format: DataFormat,
+    pub nodes: Vec<DataNode>,
+}
+
+impl DataTree {
+    pub fn parse(format: DataFormat, text: &str) -> Result<Self> {
+        let mut tree = Self { format, nodes: Vec::new() };
+        match format {
+            DataFormat::Json => JsonParser { tree: &mut tree, text, pos: 0 }.document()?,
+            DataFormat::Xml => XmlParser { tree: &mut tree, text, pos: 0 }.document()?,
+        }
+        Ok(tree)
+    }
+
+    fn push(&mut self, key: String, kind: NodeKind, path: String, parent: Option<usize>) -> usize {
+        let depth = parent.map_or(0, |parent| self.nodes[parent].depth + 1);
+        let index = self.nodes.len();
+        self.nodes.push(DataNode { key, kind, value: String::new(), path, depth, parent, children: Vec::new() });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(index);
+        }
+        index
+    }
+
+    /// What "Copy value" puts on the clipboard: a leaf's text, or a branch written out in the document's format
+    pub fn value_text(&self, index: usize) -> String {
+        let node = &self.nodes[index];
+        match node.kind {
+            NodeKind::Object | NodeKind::Array => self.to_json(index, true),
+            NodeKind::Element => self.to_xml(index, true),
+            _ => node.value.clone(),
+        }
+    }
+
+    /// The whole document, indented
+    pub fn pretty(&self) -> String {
+        match self.format {
+            DataFormat::Json => self.to_json(0, true),
+            DataFormat::Xml => self.to_xml(0, true),
+        }
+    }
+
+    /// Nodes whose key or value contains `query`, ignoring case, and their ancestors, which stay shown so the
+    /// matches can be reached; None for an empty query
+    pub fn filter(&self, query: &str) -> Option<Vec<bool>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return None;
+        }
+        let mut shown = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.key.to_lowercase().contains(&query) || node.value.to_lowercase().contains(&query) {
+                let mut current = Some(index);
+                while let Some(index) = current.filter(|index| !shown[*index]) {
+                    shown[index] = true;
+                    current = self.nodes[index].parent;
+                }
+            }
+        }
+        Some(shown)
+    }
+
+    fn to_json(&self, index: usize, pretty: bool) -> String {
+        let mut out = String::new();
+        self.write_json(index, pretty, 0, &mut out);
+        out
+    }
+
+    fn write_json(&self, index: usize, pretty: bool, indent: usize, out: &mut String) {
+        let node = &self.nodes[index];
+        let (open, close) = match node.kind {
+            NodeKind::Object => ('{', '}'),
+            NodeKind::Array => ('[', ']'),
+            NodeKind::String => {
+                out.push_str(&serde_json::to_string(&node.value).unwrap_or_default());
+                return;
+            }
+            _ => {
+                out.push_str(&node.value);
+                return;
+            }
+        };
+        out.push(open);
+        for (position, &child) in node.children.iter().enumerate() {
+            if position > 0 {
+                out.push(',');
+            }
+            if pretty {
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent + 1));
+            }
+            if node.kind == NodeKind::Object {
+                out.push_str(&serde_json::to_string(&self.nodes[child].key).unwrap_or_default());
+                out.push_str(if pretty { ": " } else { ":" });
+            }
+            self.write_json(child, pretty, indent + 1, out);
+        }
+        if pretty && !node.children.is_empty() {
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+        }
+        out.push(close);
+    }
+
+    fn to_xml(&self, index: usize, pretty: bool) -> String {
+        let mut out = String::new();
+        self.write_xml(index, pretty, 0, &mut out);
+        out
+    }
+
+    fn write_xml(&self, index: usize, pretty: bool, indent: usize, out: &mut String) {
+        let node = &self.nodes[index];
+        let pad = if pretty { "  ".repeat(indent) } else { String::new() };
+        match node.kind {
+            NodeKind::Element => {
+                out.push_str(&pad);
+                out.push('<');
+                out.push_str(&node.key);
+                let (attributes, content): (Vec<usize>, Vec<usize>) = node.children.iter()
+                    .partition(|&&child| self.nodes[child].kind == NodeKind::Attribute);
+                for attribute in attributes {
+                    let attribute = &self.nodes[attribute];
+                    out.push_str(&format!(" {}=\"{}\"", &attribute.key[1..], escape_xml(&attribute.value, true)));
+                }
+                if content.is_empty() && node.value.is_empty() {
+                    out.push_str("/>");
+                } else if content.is_empty() {
+                    out.push_str(&format!(">{}</{}>", escape_xml(&node.value, false), node.key));
+                } else {
+                    out.push('>');
+                    for child in content {
+                        if pretty {
+                            out.push('\n');
+                        }
+                        self.write_xml(child, pretty, indent + 1, out);
+                    }
+                    if pretty {
+                        out.push('\n');
+                        out.push_str(&pad);
+                    }
+                    out.push_str(&format!("</{}>", node.key));
+                }
+            }
+            _ => {
+                out.push_str(&pad);
+                out.push_str(&escape_xml(&node.value, false));
+            }
+        }
+    }
+}
+
+fn escape_xml(text: &str, attribute: bool) -> String {
+    let text = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    if attribute { text.replace('"', "&quot;") } else { text }
+}
+
+// Line and column of a byte offset, for error messages
+fn location(text: &str, pos: usize) -> String {
+    let before = &text[..pos.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+    format!("line {}, column {}", line, column)
+}
+
+struct JsonParser<'a> {
+    tree: &'a mut DataTree,
+    text: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn document(&mut self) -> Result<()> {
+        self.value(String::new(), "$".to_string(), None)?;
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            return Err(self.error("Unexpected text after the JSON value"));
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow!("{} at {}", message, location(self.text, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, message: &str) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(message));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, key: String, path: String, parent: Option<usize>) -> Result<()> {
+        self.skip_whitespace();
+        if parent.is_some_and(|parent| self.tree.nodes[parent].depth >= MAX_DEPTH) {
+            return Err(self.error("The JSON is nested too deeply"));
+        }
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let node = self.tree.push(key, NodeKind::Object, path.clone(), parent);
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("Expected a key in quotes"));
+                    }
+                    let name = self.string()?;
+                    self.expect(b':', "Expected ':' after the key")?;
+                    let child_path = if is_identifier(&name) {
+                        format!("{}.{}", path, name)
+                    } else {
+                        format!("{}[{}]", path, serde_json::to_string(&name).unwrap_or_default())
+                    };
+                    self.value(name, child_path, Some(node))?;
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        _ => return Err(self.error("Expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let node = self.tree.push(key, NodeKind::Array, path.clone(), parent);
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                for index in 0.. {
+                    self.value(index.to_string(), format!("{}[{}]", path, index), Some(node))?;
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => return Err(self.error("Expected ',' or ']'")),
+                    }
+                }
+                Ok(())
+            }
+            Some(b'"') => {
+                let value = self.string()?;
+                let node = self.tree.push(key, NodeKind::String, path, parent);
+                self.tree.nodes[node].value = value;
+                Ok(())
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+                    self.pos += 1;
+                }
+                let number = &self.text[start..self.pos];
+                if number.parse::<f64>().is_err() {
+                    self.pos = start;
+                    return Err(self.error("Invalid number"));
+                }
+                let node = self.tree.push(key, NodeKind::Number, path, parent);
+                self.tree.nodes[node].value = number.to_string();
+                Ok(())
+            }
+            _ => {
+                let rest = &self.text[self.pos..];
+                let (kind, word) = [(NodeKind::Bool, "true"), (NodeKind::Bool, "false"), (NodeKind::Null, "null")]
+                    .into_iter()
+                    .find(|(_, word)| rest.starts_with(word))
+                    .ok_or_else(|| self.error("Expected a value"))?;
+                self.pos += word.len();
+                let node = self.tree.push(key, kind, path, parent);
+                self.tree.nodes[node].value = word.to_string();
+                Ok(())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(end) = rest.find(['"', '\\']) else {
+                return Err(self.error("Unterminated string"));
+            };
+            value.push_str(&rest[..end]);
+            self.pos += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(value);
+            }
+            let escaped = self.peek().ok_or_else(|| self.error("Unterminated string"))?;
+            self.pos += 1;
+            match escaped {
+                b'"' => value.push('"'),
+                b'\\' => value.push('\\'),
+                b'/' => value.push('/'),
+                b'b' => value.push('\u{8}'),
+                b'f' => value.push('\u{c}'),
+                b'n' => value.push('\n'),
+                b'r' => value.push('\r'),
+                b't' => value.push('\t'),
+                b'u' => {
+                    let mut unit = self.hex_unit()?;
+                    // A high surrogate pairs with the low one escaped after it
+                    if (0xd800..0xdc00).contains(&unit) && self.text[self.pos..].starts_with("\\u") {
+                        self.pos += 2;
+                        let low = self.hex_unit()?;
+                        unit = 0x10000 + ((unit - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                    }
+                    value.push(char::from_u32(unit).unwrap_or('\u{fffd}'));
+                }
+                _ => return Err(self.error("Invalid escape in string")),
+            }
+        }
+    }
+
+    fn hex_unit(&mut self) -> Result<u32> {
+        let digits = self.text.get(self.pos..self.pos + 4).ok_or_else(|| self.error("Invalid \\u escape"))?;
+        let unit = u32::from_str_radix(digits, 16).map_err(|_| self.error("Invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(unit)
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+struct XmlParser<'a> {
+    tree: &'a mut DataTree,
+    text: &'a str,
+    pos: usize,
+}
+
+impl XmlParser<'_> {
+    fn document(&mut self) -> Result<()> {
+        self.skip_misc()?;
+        if !self.rest().starts_with('<') {
+            return Err(self.error("Expected the root element"));
+        }
+        self.element(None, "")?;
+        self.skip_misc()?;
+        if self.pos < self.text.len() {
+            return Err(self.error("Unexpected content after the root element"));
+        }
+        Ok(())
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow!("{} at {}", message, location(self.text, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<()> {
+        let found = self.rest().find(end).ok_or_else(|| self.error(&format!("Expected '{}'", end)))?;
+        self.pos += found + end.len();
+        Ok(())
+    }
+
+    // The declaration, comments, processing instructions and the doctype around the root element
+    fn skip_misc(&mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") || rest.starts_with("<!doctype") {
+                // The internal subset in brackets may hold '>' of its own
+                let mut depth = 0;
+                let end = rest.char_indices().find(|&(_, c)| {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        '>' if depth == 0 => return true,
+                        _ => {}
+                    }
+                    false
+                });
+                let (end, _) = end.ok_or_else(|| self.error("Unterminated doctype"))?;
+                self.pos += end + 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let rest = self.rest();
+        let end = rest.find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '=')).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("Expected a name"));
+        }
+        let name = rest[..end].to_string();
+        self.pos += end;
+        Ok(name)
+    }
+
+    fn element(&mut self, parent: Option<usize>, parent_path: &str) -> Result<()> {
+        if parent.is_some_and(|parent| self.tree.nodes[parent].depth >= MAX_DEPTH) {
+            return Err(self.error("The XML is nested too deeply"));
+        }
+        self.pos += 1;
+        let name = self.name()?;
+        // Siblings of the same name are told apart by position, counting from 1
+        let position = parent.map_or(1, |parent| {
+            self.tree.nodes[parent].children.iter().filter(|&&child| {
+                let child = &self.tree.nodes[child];
+                child.kind == NodeKind::Element && child.key == name
+            }).count() + 1
+        });
+        let path = format!("{}/{}", parent_path, name);
+        let node = self.tree.push(name.clone(), NodeKind::Element, path, parent);
+        if position > 1 {
+            self.tree.nodes[node].path.push_str(&format!("[{}]", position));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(());
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            if rest.is_empty() {
+                return Err(self.error("Unterminated start tag"));
+            }
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("Expected '=' after the attribute name"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| self.error("Expected a quoted attribute value"))?;
+            self.pos += 1;
+            let end = self.rest().find(quote).ok_or_else(|| self.error("Unterminated attribute value"))?;
+            let value = decode_entities(&self.rest()[..end]);
+            self.pos += end + 1;
+            let element_path = self.tree.nodes[node].path.clone();
+            let child = self.tree.push(format!("@{}", attribute), NodeKind::Attribute, format!("{}/@{}", element_path, attribute), Some(node));
+            self.tree.nodes[child].value = value;
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let closing = self.name()?;
+                if closing != name {
+                    return Err(self.error(&format!("Expected </{}> but found </{}>", name, closing)));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("Expected '>'"));
+                }
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or_else(|| self.error("Unterminated CDATA section"))?;
+                let text = cdata[..end].to_string();
+                self.pos += 9 + end + 3;
+                self.text_node(node, text);
+            } else if rest.starts_with('<') {
+                let element_path = self.tree.nodes[node].path.clone();
+                self.element(Some(node), &element_path)?;
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("Missing </{}>", name)));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let text = decode_entities(rest[..end].trim());
+                self.pos += end;
+                if !text.is_empty() {
+                    self.text_node(node, text);
+                }
+            }
+        }
+
+        self.number_same_name_siblings(node);
+        // An element holding nothing but text shows it on its own row
+        let children = &self.tree.nodes[node].children;
+        if let [only] = children[..] {
+            if self.tree.nodes[only].kind == NodeKind::Text {
+                let text = self.tree.nodes.pop().map(|text| text.value).unwrap_or_default();
+                self.tree.nodes[node].children.clear();
+                self.tree.nodes[node].value = text;
+            }
+        }
+        Ok(())
+    }
+
+    fn text_node(&mut self, parent: usize, text: String) {
+        let path = format!("{}/text()", self.tree.nodes[parent].path);
+        let node = self.tree.push("#text".to_string(), NodeKind::Text, path, Some(parent));
+        self.tree.nodes[node].value = text;
+    }
+
+    // The first of several same-named children is [1] too, which is only known once the parent is complete
+    fn number_same_name_siblings(&mut self, parent: usize) {
+        let children = self.tree.nodes[parent].children.clone();
+        for &child in &children {
+            let node = &self.tree.nodes[child];
+            if node.kind != NodeKind::Element || node.path.ends_with(']') {
+                continue;
+            }
+            let repeated = children.iter().filter(|&&other| {
+                let other = &self.tree.nodes[other];
+                other.kind == NodeKind::Element && other.key == node.key
+            }).count() > 1;
+            if repeated {
+                let old = node.path.clone();
+                let new = format!("{}[1]", old);
+                self.rename_paths(child, &old, &new);
+            }
+        }
+    }
+
+    fn rename_paths(&mut self, node: usize, old: &str, new: &str) {
+        if let Some(rest) = self.tree.nodes[node].path.strip_prefix(old) {
+            self.tree.nodes[node].path = format!("{}{}", new, rest);
+        }
+        for child in self.tree.nodes[node].children.clone() {
+            self.rename_paths(child, old, new);
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .unwrap_or_else(|| entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_tree_keeps_order_and_paths() {
+        let text = r#"{"zeta": 1.50, "items": [{"name": "A \"b\" é"}, null], "odd key": true}"#;
+        let tree = DataTree::parse(DataFormat::Json, text).unwrap();
+        let keys: Vec<&str> = tree.nodes[0].children.iter().map(|&child| tree.nodes[child].key.as_str()).collect();
+        assert_eq!(keys, ["zeta", "items", "odd key"]);
+        let name = tree.nodes.iter().find(|node| node.key == "name").unwrap();
+        assert_eq!((name.path.as_str(), name.value.as_str()), ("$.items[0].name", "A \"b\" é"));
+        assert_eq!(tree.nodes.iter().find(|node| node.key == "odd key").unwrap().path, "$[\"odd key\"]");
+        assert_eq!(tree.value_text(1), "1.50");
+        assert_eq!(tree.to_json(0, false), r#"{"zeta":1.50,"items":[{"name":"A \"b\" é"},null],"odd key":true}"#);
+
+        let shown = tree.filter("NAME").unwrap();
+        let shown: Vec<&str> = tree.nodes.iter().zip(&shown).filter(|(_, shown)| **shown).map(|(node, _)| node.path.as_str()).collect();
+        assert_eq!(shown, ["$", "$.items", "$.items[0]", "$.items[0].name"]);
+
+        let error = DataTree::parse(DataFormat::Json, "{\n  \"a\": 1,\n  \"b\" 2\n}").err().unwrap();
+        assert_eq!(error.to_string(), "Expected ':' after the key at line 3, column 7");
+    }
+
+    #[test]
+    fn test_xml_tree_and_paths() {
+        let text = r#"<?xml version="1.0"?><!DOCTYPE feed [<!ENTITY x "y">]><feed lang="en">
+            <entry id="1"><title>One &amp; two</title></entry>
+            <entry id="2"><![CDATA[<raw>]]><!-- note --></entry>
+            <empty/>
+        </feed>"#;
+        let tree = DataTree::parse(DataFormat::Xml, text).unwrap();
+        let paths: Vec<&str> = tree.nodes.iter().map(|node| node.path.as_str()).collect();
+        assert_eq!(paths, [
+            "/feed", "/feed/@lang", "/feed/entry[1]", "/feed/entry[1]/@id", "/feed/entry[1]/title",
+            "/feed/entry[2]", "/feed/entry[2]/@id", "/feed/entry[2]/text()", "/feed/empty",
+        ]);
+        assert_eq!(tree.nodes[4].value, "One & two");
+        assert_eq!(tree.value_text(2), "<entry id=\"1\">\n  <title>One &amp; two</title>\n</entry>");
+        assert!(DataTree::parse(DataFormat::Xml, "<a><b></a>").is_err());
+        assert_eq!(DataFormat::of("application/problem+json; charset=utf-8"), Some(DataFormat::Json));
+        assert_eq!(DataFormat::of("image/svg+xml"), None);
+    }
+}
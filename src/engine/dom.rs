@@ -71,4 +71,183 @@ impl DOMNode {
     pub fn is_text(&self) -> bool {
         matches!(self, DOMNode::Text(_))
     }
-}
\ No newline at end of file
+    
+    /// Child indices leading from this node to `target`, matched by identity rather than content
+    pub fn path_to(&self, target: *const DOMNode) -> Option<Vec<usize>> {
+        if std::ptr::eq(self, target) {
+            return Some(Vec::new());
+        }
+        let DOMNode::Element { children, .. } = self else { return None };
+        children.iter().enumerate().find_map(|(index, child)| {
+            child.path_to(target).map(|mut path| {
+                path.insert(0, index);
+                path
+            })
+        })
+    }
+    
+    /// The node's subtree, itself included, by identity
+    pub fn descendants(&self) -> impl Iterator<Item = *const DOMNode> + '_ {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            if let DOMNode::Element { children, .. } = node {
+                stack.extend(children.iter().rev());
+            }
+            Some(node as *const DOMNode)
+        })
+    }
+    
+    pub fn node_at(&self, path: &[usize]) -> Option<&DOMNode> {
+        path.iter().try_fold(self, |node, &index| match node {
+            DOMNode::Element { children, .. } => children.get(index),
+            _ => None,
+        })
+    }
+    
+    /// All descendant text, space separated
+    pub fn collect_text(&self) -> String {
+        match self {
+            DOMNode::Text(text) => text.clone(),
+            DOMNode::Element { children, .. } => children.iter()
+                .map(|child| child.collect_text())
+                .collect::<Vec<_>>()
+                .join(" "),
+            DOMNode::Comment(_) => String::new(),
+        }
+    }    
+    /// The node and its descendants as HTML, attributes in name order so the output is stable
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html, false);
+        html
+    }
+    
+    fn write_html(&self, html: &mut String, raw_text: bool) {
+        match self {
+            DOMNode::Text(text) if raw_text => html.push_str(text),
+            DOMNode::Text(text) => html.push_str(&escape(text, false)),
+            DOMNode::Comment(text) => {
+                html.push_str("<!--");
+                html.push_str(text);
+                html.push_str("-->");
+            }
+            DOMNode::Element { tag_name, attributes, children } => {
+                html.push('<');
+                html.push_str(tag_name);
+                let mut names: Vec<_> = attributes.keys().collect();
+                names.sort();
+                for name in names {
+                    html.push_str(&format!(" {}=\"{}\"", name, escape(&attributes[name], true)));
+                }
+                html.push('>');
+                if VOID_ELEMENTS.contains(&tag_name.as_str()) {
+                    return;
+                }
+                let raw_text = RAW_TEXT_ELEMENTS.contains(&tag_name.as_str());
+                for child in children {
+                    child.write_html(html, raw_text);
+                }
+                html.push_str("</");
+                html.push_str(tag_name);
+                html.push('>');
+            }
+        }
+    }
+    
+    /// The text a reader sees: scripts, styles and the head left out, one line per block
+    pub fn visible_text(&self) -> String {
+        let mut lines = vec![String::new()];
+        self.collect_visible_text(&mut lines);
+        lines.into_iter()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    
+    fn collect_visible_text(&self, lines: &mut Vec<String>) {
+        match self {
+            DOMNode::Text(text) => {
+                let line = lines.last_mut().expect("always one line");
+                line.push(' ');
+                line.push_str(text);
+            }
+            DOMNode::Element { tag_name, children, .. } => {
+                let tag = tag_name.to_ascii_lowercase();
+                if HIDDEN_ELEMENTS.contains(&tag.as_str()) {
+                    return;
+                }
+                let block = BLOCK_ELEMENTS.contains(&tag.as_str());
+                if block || tag == "br" {
+                    lines.push(String::new());
+                }
+                for child in children {
+                    child.collect_visible_text(lines);
+                }
+                if block {
+                    lines.push(String::new());
+                }
+            }
+            DOMNode::Comment(_) => {}
+        }
+    }
+}
+
+/// Elements that never have contents or an end tag
+pub const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text is written as is, since the parser doesn't decode it either, and where markup is just text
+pub const RAW_TEXT_ELEMENTS: [&str; 6] = ["script", "style", "xmp", "iframe", "noembed", "noframes"];
+
+const HIDDEN_ELEMENTS: [&str; 6] = ["head", "script", "style", "template", "noscript", "title"];
+
+const BLOCK_ELEMENTS: [&str; 27] = [
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p", "pre", "section",
+];
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_and_extract_text() {
+        let mut link = DOMNode::new_element("a".to_string());
+        link.set_attribute("title".to_string(), "Say \"hi\"".to_string());
+        link.set_attribute("href".to_string(), "/a?b=1&c=2".to_string());
+        link.add_child(DOMNode::new_text("Tom & Jerry".to_string()));
+        let mut script = DOMNode::new_element("script".to_string());
+        script.add_child(DOMNode::new_text("if (a < b) {}".to_string()));
+        let mut paragraph = DOMNode::new_element("p".to_string());
+        paragraph.add_child(DOMNode::new_text("Read  about".to_string()));
+        paragraph.add_child(link);
+        let mut body = DOMNode::new_element("body".to_string());
+        body.add_child(DOMNode::new_element("br".to_string()));
+        body.add_child(script);
+        body.add_child(paragraph);
+        body.add_child(DOMNode::new_text("the end".to_string()));
+
+        assert_eq!(
+            body.to_html(),
+            "<body><br><script>if (a < b) {}</script><p>Read  about<a href=\"/a?b=1&amp;c=2\" title=\"Say &quot;hi&quot;\">Tom &amp; Jerry</a></p>the end</body>"
+        );
+        assert_eq!(body.visible_text(), "Read about Tom & Jerry\nthe end");
+    }
+}
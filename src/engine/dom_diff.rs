@@ -0,0 +1,116 @@
+// Bringing the rendered document up to date with one a script or the parser changed by patching only what
+// differs, so nodes that stayed the same keep their identity and only changed subtrees are restyled
+use super::dom::DOMNode;
+
+/// What patching a document changed
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DomPatch {
+    /// Paths, as child indices, to the roots of the subtrees whose styles may have changed: elements whose
+    /// attributes or children changed, and nodes that were replaced. None lies inside another.
+    pub restyle: Vec<Vec<usize>>,
+    /// Paths to the text nodes whose content changed in place, which only needs a new layout
+    pub text: Vec<Vec<usize>>,
+}
+
+impl DomPatch {
+    pub fn is_empty(&self) -> bool {
+        self.restyle.is_empty() && self.text.is_empty()
+    }
+}
+
+/// Make `current` equal to `updated`, changing as little of it as possible
+pub fn patch(current: &mut DOMNode, updated: &DOMNode) -> DomPatch {
+    let mut patch = DomPatch::default();
+    patch_node(current, updated, &mut Vec::new(), &mut patch);
+    // Subtrees inside one that's restyled anyway needn't be listed
+    let mut roots: Vec<Vec<usize>> = Vec::new();
+    patch.restyle.sort();
+    for path in std::mem::take(&mut patch.restyle) {
+        if !roots.last().is_some_and(|root| path.starts_with(root)) {
+            roots.push(path);
+        }
+    }
+    patch.restyle = roots;
+    patch
+}
+
+// Whether `updated` can be patched into `current` rather than replacing it
+fn same_kind(current: &DOMNode, updated: &DOMNode) -> bool {
+    match (current, updated) {
+        (DOMNode::Element { tag_name: a, .. }, DOMNode::Element { tag_name: b, .. }) => a == b,
+        (DOMNode::Text(_), DOMNode::Text(_)) | (DOMNode::Comment(_), DOMNode::Comment(_)) => true,
+        _ => false,
+    }
+}
+
+fn patch_node(current: &mut DOMNode, updated: &DOMNode, path: &mut Vec<usize>, patch: &mut DomPatch) {
+    if !same_kind(current, updated) {
+        *current = updated.clone();
+        patch.restyle.push(path.clone());
+        return;
+    }
+    match (current, updated) {
+        (DOMNode::Text(text), DOMNode::Text(new_text)) if text != new_text => {
+            new_text.clone_into(text);
+            patch.text.push(path.clone());
+        }
+        (DOMNode::Comment(text), DOMNode::Comment(new_text)) if text != new_text => new_text.clone_into(text),
+        (
+            DOMNode::Element { attributes, children, .. },
+            DOMNode::Element { attributes: new_attributes, children: new_children, .. },
+        ) => {
+            if attributes != new_attributes {
+                attributes.clone_from(new_attributes);
+                patch.restyle.push(path.clone());
+            }
+            patch_children(children, new_children, path, patch);
+        }
+        _ => {}
+    }
+}
+
+// Children matching at either end are patched in place; the ones between are replaced
+fn patch_children(children: &mut Vec<DOMNode>, updated: &[DOMNode], path: &mut Vec<usize>, patch: &mut DomPatch) {
+    let prefix = children.iter().zip(updated).take_while(|(a, b)| same_kind(a, b)).count();
+    let suffix = if prefix == children.len().min(updated.len()) {
+        0
+    } else {
+        children[prefix..].iter().rev().zip(updated[prefix..].iter().rev()).take_while(|(a, b)| same_kind(a, b)).count()
+    };
+    if children.len() != updated.len() || prefix + suffix < children.len() {
+        let removed = children.len() - suffix;
+        children.splice(prefix..removed, updated[prefix..updated.len() - suffix].iter().cloned());
+        patch.restyle.push(path.clone());
+    }
+    let kept = (0..prefix).chain(updated.len() - suffix..updated.len());
+    for index in kept {
+        path.push(index);
+        patch_node(&mut children[index], &updated[index], path, patch);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_patch_keeps_unchanged_nodes() {
+        let mut current = html_parser::parse("<div><p class=a>one</p><ul><li>x</li></ul><p>two</p></div>");
+        let updated = html_parser::parse("<div><p class=b>one</p><ul><li>x</li><li>y</li></ul><p>three</p></div>");
+        let list = current.node_at(&[0, 1]).unwrap() as *const DOMNode;
+        let last = current.node_at(&[0, 2]).unwrap() as *const DOMNode;
+
+        let patched = patch(&mut current, &updated);
+        assert_eq!(current.to_html(), updated.to_html());
+        assert_eq!(patched.restyle, vec![vec![0, 0], vec![0, 1]]);
+        assert_eq!(patched.text, vec![vec![0, 2, 0]]);
+        assert!(std::ptr::eq(current.node_at(&[0, 1]).unwrap(), list));
+        assert!(std::ptr::eq(current.node_at(&[0, 2]).unwrap(), last));
+
+        assert!(patch(&mut current, &updated).is_empty());
+        let replaced = html_parser::parse("<section></section>");
+        assert_eq!(patch(&mut current, &replaced).restyle, vec![Vec::<usize>::new()]);
+    }
+}
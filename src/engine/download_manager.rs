@@ -10,6 +10,8 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use crate::engine::executor::{Executor, TaskKind};
+use crate::networking::local_url;
 use crate::storage::{DownloadsDatabase, DownloadRecord, DownloadState};
 use crate::security::download_validator::{DownloadValidator, ValidationResult};
 
@@ -57,6 +59,8 @@ struct ActiveDownload {
     last_progress_time: Instant,
 }
 
+/// Cloning is cheap and yields a handle to the same downloads
+#[derive(Clone)]
 pub struct DownloadManager {
     db: Arc<DownloadsDatabase>,
     active_downloads: Arc<Mutex<HashMap<String, ActiveDownload>>>,
@@ -86,10 +90,17 @@ impl DownloadManager {
         self.throttle_bps = bps;
     }
     
+    /// Current bandwidth throttle in bytes per second, if any
+    pub fn bandwidth_throttle(&self) -> Option<u64> {
+        self.throttle_bps
+    }
+    
     /// Start a new download
     pub async fn start_download(&self, url: String, save_path: PathBuf) -> Result<String> {
-        // Validate URL
-        DownloadValidator::validate_url(&url)?;
+        // Validate URL; data: and blob: content is already in the browser
+        if !local_url::is_local(&url) {
+            DownloadValidator::validate_url(&url)?;
+        }
         
         let id = Uuid::new_v4().to_string();
         
@@ -108,7 +119,7 @@ impl DownloadManager {
             }
             ValidationResult::RequiresConfirmation(msg) => {
                 // Log warning but continue (UI should have already confirmed)
-                println!("Warning: {}", msg);
+                log::warn!("{}", msg);
             }
             ValidationResult::Safe => {}
         }
@@ -169,9 +180,10 @@ impl DownloadManager {
     
     /// Pause an active download
     pub async fn pause_download(&self, id: &str) -> Result<()> {
-        let mut active = self.active_downloads.lock().unwrap();
+        // Release the lock before awaiting so the future stays Send
+        let removed = self.active_downloads.lock().unwrap().remove(id);
         
-        if let Some(download) = active.remove(id) {
+        if let Some(download) = removed {
             let _ = download.cancel_tx.send(()).await;
             
             // Update database
@@ -189,9 +201,9 @@ impl DownloadManager {
     
     /// Cancel a download
     pub async fn cancel_download(&self, id: &str) -> Result<()> {
-        let mut active = self.active_downloads.lock().unwrap();
+        let removed = self.active_downloads.lock().unwrap().remove(id);
         
-        if let Some(download) = active.remove(id) {
+        if let Some(download) = removed {
             let _ = download.cancel_tx.send(()).await;
             
             // Clean up partial file
@@ -292,7 +304,7 @@ impl DownloadManager {
         let semaphore = self.download_semaphore.clone();
         let throttle = self.throttle_bps;
         
-        tokio::spawn(async move {
+        Executor::shared().spawn(TaskKind::Download, async move {
             // Acquire semaphore permit (limits concurrent downloads)
             let _permit = semaphore.acquire().await.unwrap();
             
@@ -348,7 +360,7 @@ impl DownloadManager {
                 Ok(path) => return Ok(path),
                 Err(e) if retry_count < MAX_RETRY_ATTEMPTS => {
                     retry_count += 1;
-                    println!("Download attempt {} failed: {}. Retrying in {:?}...", 
+                    log::warn!("Download attempt {} failed: {}. Retrying in {:?}...", 
                              retry_count, e, retry_delay);
                     
                     tokio::time::sleep(retry_delay).await;
@@ -376,9 +388,14 @@ impl DownloadManager {
         cancel_rx: &mut mpsc::Receiver<()>,
         throttle_bps: Option<u64>,
     ) -> Result<PathBuf> {
+        if local_url::is_local(&task.url) {
+            return Self::save_local_content(task, db, event_tx).await;
+        }
+        
         // Create client
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(300))
+            .default_headers(crate::networking::http_client::privacy_headers())
             .build()?;
         
         // Make HEAD request to get file size
@@ -399,7 +416,7 @@ impl DownloadManager {
         if let Some(ref mime) = mime_type {
             match DownloadValidator::validate_mime_type(mime) {
                 ValidationResult::RequiresConfirmation(msg) => {
-                    println!("Warning: {}", msg);
+                    log::warn!("{}", msg);
                 }
                 _ => {}
             }
@@ -597,8 +614,43 @@ impl DownloadManager {
         self.db.cleanup_old_completed(days)
     }
     
+    /// Write out a data: or blob: URL, which needs no network and completes at once
+    async fn save_local_content(
+        task: &DownloadTask,
+        db: Arc<DownloadsDatabase>,
+        event_tx: mpsc::UnboundedSender<DownloadEvent>,
+    ) -> Result<PathBuf> {
+        let content = local_url::resolve(&task.url)?;
+        if let Some(parent) = task.save_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&task.save_path, &content.data).await?;
+        let _ = event_tx.send(DownloadEvent::Started(task.id.clone()));
+        
+        if let Ok(Some(mut record)) = db.get_by_id(&task.id) {
+            let size = content.data.len() as u64;
+            record.file_size = Some(size);
+            record.downloaded_bytes = size;
+            record.mime_type = Some(content.mime_type);
+            record.status = DownloadState::Completed;
+            record.checksum = Some(format!("{:x}", Sha256::digest(&content.data)));
+            record.updated_at = Utc::now();
+            record.completed_at = Some(Utc::now());
+            let _ = db.update(&record);
+        }
+        Ok(task.save_path.clone())
+    }
+    
     /// Generate a safe download path for a URL
     pub fn generate_safe_download_path(download_dir: &Path, url: &str) -> PathBuf {
+        // data: and blob: URLs have no file name, only a type to name the file after
+        if local_url::is_local(url) {
+            let extension = local_url::resolve(url).ok()
+                .and_then(|content| extension_for_mime(&content.mime_type))
+                .unwrap_or("bin");
+            return DownloadValidator::generate_safe_path(download_dir, &format!("download.{}", extension));
+        }
+        
         // Extract filename from URL
         let filename = if let Ok(parsed_url) = url::Url::parse(url) {
             if let Some(segments) = parsed_url.path_segments() {
@@ -616,6 +668,29 @@ impl DownloadManager {
     }
 }
 
+fn extension_for_mime(mime_type: &str) -> Option<&'static str> {
+    let essence = mime_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    Some(match essence.as_str() {
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "application/json" => "json",
+        "application/javascript" | "text/javascript" => "js",
+        "application/xml" | "text/xml" => "xml",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "audio/mpeg" => "mp3",
+        "video/mp4" => "mp4",
+        _ => return None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,286 @@
+// Process-wide executor: one Tokio runtime with bounded pools for blocking work
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+
+const WORKER_THREADS: usize = 4;
+// Upper bound for the runtime's blocking threads across all task kinds
+const MAX_BLOCKING_THREADS: usize = 16;
+
+/// What a task does; each kind has its own limit on concurrent blocking work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskKind {
+    Network,
+    Parse,
+    Download,
+    ImageDecode,
+    Prefetch,
+    /// Scripts' workers, each holding a thread for as long as it lives
+    Worker,
+    /// Queries against the on-disk stores, kept off the UI thread
+    Storage,
+}
+
+impl TaskKind {
+    pub const ALL: [TaskKind; 7] = [TaskKind::Network, TaskKind::Parse, TaskKind::Download, TaskKind::ImageDecode, TaskKind::Prefetch, TaskKind::Worker, TaskKind::Storage];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskKind::Network => "Network",
+            TaskKind::Parse => "Parsing",
+            TaskKind::Download => "Downloads",
+            TaskKind::ImageDecode => "Image decoding",
+            TaskKind::Prefetch => "Prefetching",
+            TaskKind::Worker => "Workers",
+            TaskKind::Storage => "Storage",
+        }
+    }
+
+    /// Blocking jobs of this kind allowed to run at the same time
+    pub fn blocking_limit(&self) -> usize {
+        match self {
+            TaskKind::Network => 2,
+            TaskKind::Parse => 2,
+            TaskKind::Download => 3,
+            TaskKind::ImageDecode => 4,
+            TaskKind::Prefetch => 1,
+            TaskKind::Worker => 4,
+            TaskKind::Storage => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskError {
+    Cancelled,
+    Panicked,
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::Cancelled => write!(f, "task was cancelled"),
+            TaskError::Panicked => write!(f, "task panicked"),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// Typed handle to a spawned task; dropping it detaches the task rather than cancelling it
+pub struct TaskHandle<T> {
+    kind: TaskKind,
+    receiver: oneshot::Receiver<T>,
+    abort: AbortHandle,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T> TaskHandle<T> {
+    pub fn kind(&self) -> TaskKind {
+        self.kind
+    }
+
+    /// Stop the task at its next await point. Blocking work that has already started runs to
+    /// completion, but its result is discarded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.abort.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.abort.is_finished()
+    }
+
+    /// Take the result without blocking; `None` while the task is still running
+    pub fn try_take(&mut self) -> Option<Result<T, TaskError>> {
+        match self.receiver.try_recv() {
+            Ok(value) => Some(Ok(value)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(self.failure())),
+        }
+    }
+
+    /// Wait for the result from async code
+    pub async fn join(self) -> Result<T, TaskError> {
+        let failure = self.failure();
+        self.receiver.await.map_err(|_| failure)
+    }
+
+    // A task that ended without sending was either cancelled or panicked
+    fn failure(&self) -> TaskError {
+        if self.cancelled.load(Ordering::Relaxed) {
+            TaskError::Cancelled
+        } else {
+            TaskError::Panicked
+        }
+    }
+}
+
+// Keeps the per-kind active count accurate however the task ends
+struct ActiveGuard(Arc<AtomicUsize>);
+
+impl ActiveGuard {
+    fn new(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct Executor {
+    runtime: Runtime,
+    blocking_pools: HashMap<TaskKind, Arc<Semaphore>>,
+    active: HashMap<TaskKind, Arc<AtomicUsize>>,
+}
+
+static SHARED_EXECUTOR: OnceLock<Executor> = OnceLock::new();
+
+impl Executor {
+    pub fn new() -> std::io::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(WORKER_THREADS)
+            .max_blocking_threads(MAX_BLOCKING_THREADS)
+            .thread_name("neon-worker")
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            runtime,
+            blocking_pools: TaskKind::ALL.iter()
+                .map(|kind| (*kind, Arc::new(Semaphore::new(kind.blocking_limit()))))
+                .collect(),
+            active: TaskKind::ALL.iter()
+                .map(|kind| (*kind, Arc::new(AtomicUsize::new(0))))
+                .collect(),
+        })
+    }
+
+    /// Executor shared by every part of the browser that does background work
+    pub fn shared() -> &'static Executor {
+        SHARED_EXECUTOR.get_or_init(|| Executor::new().expect("Failed to build Tokio runtime"))
+    }
+
+    pub fn handle(&self) -> &Handle {
+        self.runtime.handle()
+    }
+
+    /// Run async work on the runtime's worker threads
+    pub fn spawn<F>(&self, kind: TaskKind, future: F) -> TaskHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_task(kind, async move { Some(future.await) })
+    }
+
+    /// Run CPU-bound or blocking work, queued behind the kind's pool limit
+    pub fn spawn_blocking<F, T>(&self, kind: TaskKind, work: F) -> TaskHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.blocking_pools[&kind].clone();
+        self.spawn_task(kind, async move {
+            let _permit = pool.acquire_owned().await.ok()?;
+            tokio::task::spawn_blocking(work).await.ok()
+        })
+    }
+
+    /// Drive a future to completion on the calling thread. Never call this from the UI thread.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Tasks of this kind currently queued or running
+    pub fn active_tasks(&self, kind: TaskKind) -> usize {
+        self.active[&kind].load(Ordering::Relaxed)
+    }
+
+    // Tasks that finish with `None` report as panicked (or cancelled, if cancel was requested)
+    fn spawn_task<F, T>(&self, kind: TaskKind, future: F) -> TaskHandle<T>
+    where
+        F: Future<Output = Option<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let guard = ActiveGuard::new(self.active[&kind].clone());
+
+        let join = self.runtime.spawn(async move {
+            let _guard = guard;
+            if let Some(value) = future.await {
+                let _ = sender.send(value);
+            }
+        });
+
+        TaskHandle {
+            kind,
+            receiver,
+            abort: join.abort_handle(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_spawn_and_join() {
+        let executor = Executor::new().unwrap();
+        let handle = executor.spawn(TaskKind::Network, async { 40 + 2 });
+        assert_eq!(executor.block_on(handle.join()), Ok(42));
+
+        let handle = executor.spawn_blocking(TaskKind::Parse, || "parsed".to_string());
+        assert_eq!(executor.block_on(handle.join()).as_deref(), Ok("parsed"));
+    }
+
+    #[test]
+    fn test_cancel_reports_cancelled() {
+        let executor = Executor::new().unwrap();
+        let mut handle = executor.spawn(TaskKind::Download, async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        assert!(handle.try_take().is_none());
+
+        handle.cancel();
+        executor.block_on(async { while !handle.is_finished() { tokio::task::yield_now().await } });
+        assert_eq!(handle.try_take(), Some(Err(TaskError::Cancelled)));
+        assert_eq!(executor.active_tasks(TaskKind::Download), 0);
+    }
+
+    #[test]
+    fn test_blocking_pool_is_bounded() {
+        let executor = Executor::new().unwrap();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8).map(|_| {
+            let running = running.clone();
+            let peak = peak.clone();
+            executor.spawn_blocking(TaskKind::Parse, move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        }).collect();
+
+        for handle in handles {
+            executor.block_on(handle.join()).unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= TaskKind::Parse.blocking_limit());
+    }
+}
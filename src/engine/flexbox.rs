@@ -0,0 +1,696 @@
+// Flexbox layout: sizes and positions the items of a display: flex container
+use crate::engine::css_parser::Value;
+use crate::engine::layout::{Rect, to_px, resolve_gaps};
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlexDirection {
+    #[default]
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+impl FlexDirection {
+    pub fn is_row(&self) -> bool {
+        matches!(self, FlexDirection::Row | FlexDirection::RowReverse)
+    }
+
+    fn is_reverse(&self) -> bool {
+        matches!(self, FlexDirection::RowReverse | FlexDirection::ColumnReverse)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlexWrap {
+    #[default]
+    NoWrap,
+    Wrap,
+    WrapReverse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JustifyContent {
+    #[default]
+    FlexStart,
+    FlexEnd,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+/// Cross-axis alignment; baseline alignment is treated as flex-start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignItems {
+    #[default]
+    Stretch,
+    FlexStart,
+    FlexEnd,
+    Center,
+}
+
+impl AlignItems {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "stretch" | "normal" => Some(AlignItems::Stretch),
+            "flex-start" | "start" | "self-start" | "baseline" => Some(AlignItems::FlexStart),
+            "flex-end" | "end" | "self-end" => Some(AlignItems::FlexEnd),
+            "center" => Some(AlignItems::Center),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlexContainerStyle {
+    pub direction: FlexDirection,
+    pub wrap: FlexWrap,
+    pub justify_content: JustifyContent,
+    pub align_items: AlignItems,
+    pub row_gap: f32,
+    pub column_gap: f32,
+}
+
+impl FlexContainerStyle {
+    pub fn from_style(style: &HashMap<String, Value>) -> Self {
+        let mut container = FlexContainerStyle::default();
+
+        // flex-flow first so the longhands can override it
+        for keyword in keywords(style.get("flex-flow")) {
+            container.apply_direction(&keyword);
+            container.apply_wrap(&keyword);
+        }
+        if let Some(keyword) = keyword(style.get("flex-direction")) {
+            container.apply_direction(&keyword);
+        }
+        if let Some(keyword) = keyword(style.get("flex-wrap")) {
+            container.apply_wrap(&keyword);
+        }
+
+        if let Some(keyword) = keyword(style.get("justify-content")) {
+            container.justify_content = match keyword.as_str() {
+                "flex-end" | "end" | "right" => JustifyContent::FlexEnd,
+                "center" => JustifyContent::Center,
+                "space-between" => JustifyContent::SpaceBetween,
+                "space-around" => JustifyContent::SpaceAround,
+                "space-evenly" => JustifyContent::SpaceEvenly,
+                _ => JustifyContent::FlexStart,
+            };
+        }
+        if let Some(align) = keyword(style.get("align-items")).and_then(|k| AlignItems::from_keyword(&k)) {
+            container.align_items = align;
+        }
+
+        (container.row_gap, container.column_gap) = resolve_gaps(style);
+
+        container
+    }
+
+    fn apply_direction(&mut self, keyword: &str) {
+        self.direction = match keyword {
+            "row" => FlexDirection::Row,
+            "row-reverse" => FlexDirection::RowReverse,
+            "column" => FlexDirection::Column,
+            "column-reverse" => FlexDirection::ColumnReverse,
+            _ => return,
+        };
+    }
+
+    fn apply_wrap(&mut self, keyword: &str) {
+        self.wrap = match keyword {
+            "nowrap" => FlexWrap::NoWrap,
+            "wrap" => FlexWrap::Wrap,
+            "wrap-reverse" => FlexWrap::WrapReverse,
+            _ => return,
+        };
+    }
+
+    fn main_gap(&self) -> f32 {
+        if self.direction.is_row() { self.column_gap } else { self.row_gap }
+    }
+
+    fn cross_gap(&self) -> f32 {
+        if self.direction.is_row() { self.row_gap } else { self.column_gap }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FlexItemStyle {
+    pub grow: f32,
+    pub shrink: f32,
+    /// Resolved flex-basis in pixels; `None` means auto (use the item's main size)
+    pub basis: Option<f32>,
+    pub align_self: Option<AlignItems>,
+}
+
+impl Default for FlexItemStyle {
+    fn default() -> Self {
+        Self { grow: 0.0, shrink: 1.0, basis: None, align_self: None }
+    }
+}
+
+impl FlexItemStyle {
+    /// `main_reference` is the container's main size, which percentage bases resolve against
+    pub fn from_style(style: &HashMap<String, Value>, main_reference: f32) -> Self {
+        let mut item = FlexItemStyle::default();
+
+        if let Some(flex) = style.get("flex") {
+            item.apply_shorthand(flex, main_reference);
+        }
+        if let Some(grow) = style.get("flex-grow").and_then(number) {
+            item.grow = grow.max(0.0);
+        }
+        if let Some(shrink) = style.get("flex-shrink").and_then(number) {
+            item.shrink = shrink.max(0.0);
+        }
+        if let Some(basis) = style.get("flex-basis") {
+            item.basis = basis_px(basis, main_reference);
+        }
+        if let Some(keyword) = keyword(style.get("align-self")) {
+            item.align_self = AlignItems::from_keyword(&keyword);
+        }
+
+        item
+    }
+
+    fn apply_shorthand(&mut self, flex: &Value, main_reference: f32) {
+        match flex {
+            Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("none") => {
+                *self = FlexItemStyle { grow: 0.0, shrink: 0.0, basis: None, ..*self };
+            }
+            Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("auto") => {
+                *self = FlexItemStyle { grow: 1.0, shrink: 1.0, basis: None, ..*self };
+            }
+            Value::List(values) => {
+                // Up to two numbers (grow, shrink) and a basis, as in `flex: 1 1 200px`
+                let mut numbers = values.iter().filter_map(number);
+                self.grow = numbers.next().unwrap_or(1.0);
+                self.shrink = numbers.next().unwrap_or(1.0);
+                self.basis = values.iter()
+                    .find(|value| !matches!(value, Value::Number(_)))
+                    .map_or(Some(0.0), |basis| basis_px(basis, main_reference));
+            }
+            Value::Number(grow) => {
+                *self = FlexItemStyle { grow: *grow, shrink: 1.0, basis: Some(0.0), ..*self };
+            }
+            basis => {
+                *self = FlexItemStyle { grow: 1.0, shrink: 1.0, basis: basis_px(basis, main_reference), ..*self };
+            }
+        }
+    }
+}
+
+/// One flex item as the algorithm sees it, with sizes along the container's main and cross axes
+#[derive(Debug, Clone)]
+pub struct FlexItem {
+    pub style: FlexItemStyle,
+    /// Definite width (row) or height (column)
+    pub main_size: Option<f32>,
+    /// Definite height (row) or width (column)
+    pub cross_size: Option<f32>,
+    /// Size along the main axis when nothing else sets it
+    pub content_main: f32,
+    pub min_main: f32,
+    pub max_main: f32,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self {
+            style: FlexItemStyle::default(),
+            main_size: None,
+            cross_size: None,
+            content_main: 0.0,
+            min_main: 0.0,
+            max_main: f32::INFINITY,
+        }
+    }
+}
+
+impl FlexItem {
+    fn clamp_main(&self, size: f32) -> f32 {
+        size.min(self.max_main).max(self.min_main).max(0.0)
+    }
+}
+
+/// Item rectangles relative to the container's content box, and the size the container needs
+#[derive(Debug, Clone, Default)]
+pub struct FlexLayout {
+    pub items: Vec<Rect>,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Lay out `items` in a container whose main and cross sizes may be indefinite (`None`).
+/// `measure_cross` returns an item's cross size once its main size is known, for items
+/// without a definite cross size.
+pub fn layout(
+    container: &FlexContainerStyle,
+    main_size: Option<f32>,
+    cross_size: Option<f32>,
+    items: &[FlexItem],
+    mut measure_cross: impl FnMut(usize, f32) -> f32,
+) -> FlexLayout {
+    let main_gap = container.main_gap();
+    let cross_gap = container.cross_gap();
+
+    let base: Vec<f32> = items.iter()
+        .map(|item| item.style.basis.or(item.main_size).unwrap_or(item.content_main))
+        .collect();
+    let hypothetical: Vec<f32> = items.iter().zip(&base).map(|(item, size)| item.clamp_main(*size)).collect();
+
+    let lines = collect_lines(container.wrap, main_size, &hypothetical, main_gap);
+    let container_main = main_size.unwrap_or_else(|| {
+        lines.iter()
+            .map(|line| hypothetical[line.clone()].iter().sum::<f32>() + gaps(line.len(), main_gap))
+            .fold(0.0, f32::max)
+    });
+
+    // Main sizes, then cross sizes now that each item's main size is known
+    let mut main_sizes = vec![0.0; items.len()];
+    for line in &lines {
+        let space = container_main - gaps(line.len(), main_gap);
+        let sizes = resolve_flexible_lengths(&items[line.clone()], &base[line.clone()], &hypothetical[line.clone()], space);
+        main_sizes[line.clone()].copy_from_slice(&sizes);
+    }
+    let mut cross_sizes: Vec<f32> = items.iter().enumerate()
+        .map(|(index, item)| item.cross_size.unwrap_or_else(|| measure_cross(index, main_sizes[index])))
+        .collect();
+
+    let mut line_cross: Vec<f32> = lines.iter()
+        .map(|line| cross_sizes[line.clone()].iter().copied().fold(0.0, f32::max))
+        .collect();
+    if let Some(cross) = cross_size {
+        if container.wrap == FlexWrap::NoWrap {
+            line_cross[0] = cross;
+        } else {
+            // align-content: normal stretches the lines to fill the container
+            let extra = cross - line_cross.iter().sum::<f32>() - gaps(lines.len(), cross_gap);
+            if extra > 0.0 {
+                let share = extra / lines.len() as f32;
+                line_cross.iter_mut().for_each(|size| *size += share);
+            }
+        }
+    }
+    let container_cross = cross_size
+        .unwrap_or_else(|| line_cross.iter().sum::<f32>() + gaps(lines.len(), cross_gap));
+
+    let mut rects = vec![Rect::default(); items.len()];
+    let mut line_start = 0.0;
+    for (line, &line_size) in lines.iter().zip(&line_cross) {
+        let cross_start = if container.wrap == FlexWrap::WrapReverse {
+            container_cross - line_start - line_size
+        } else {
+            line_start
+        };
+        line_start += line_size + cross_gap;
+
+        let used: f32 = main_sizes[line.clone()].iter().sum::<f32>() + gaps(line.len(), main_gap);
+        let (mut position, spacing) = justify(container.justify_content, container_main - used, line.len());
+
+        for index in line.clone() {
+            let item = &items[index];
+            let align = item.style.align_self.unwrap_or(container.align_items);
+            if align == AlignItems::Stretch && item.cross_size.is_none() {
+                cross_sizes[index] = line_size;
+            }
+            let cross_offset = match align {
+                AlignItems::Stretch | AlignItems::FlexStart => 0.0,
+                AlignItems::FlexEnd => line_size - cross_sizes[index],
+                AlignItems::Center => (line_size - cross_sizes[index]) / 2.0,
+            };
+
+            let main = main_sizes[index];
+            let main_position = if container.direction.is_reverse() {
+                container_main - position - main
+            } else {
+                position
+            };
+            position += main + main_gap + spacing;
+
+            let cross = cross_sizes[index];
+            let cross_position = cross_start + cross_offset;
+            rects[index] = if container.direction.is_row() {
+                Rect { x: main_position, y: cross_position, width: main, height: cross }
+            } else {
+                Rect { x: cross_position, y: main_position, width: cross, height: main }
+            };
+        }
+    }
+
+    let (width, height) = if container.direction.is_row() {
+        (container_main, container_cross)
+    } else {
+        (container_cross, container_main)
+    };
+    FlexLayout { items: rects, width, height }
+}
+
+// Break items into lines; without wrapping or a definite main size everything shares one line
+fn collect_lines(wrap: FlexWrap, main_size: Option<f32>, hypothetical: &[f32], gap: f32) -> Vec<Range<usize>> {
+    let available = match (wrap, main_size) {
+        (FlexWrap::NoWrap, _) | (_, None) => return std::iter::once(0..hypothetical.len()).collect(),
+        (_, Some(size)) => size,
+    };
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut used = 0.0;
+    for (index, size) in hypothetical.iter().enumerate() {
+        if index > start && used + gap + size > available {
+            lines.push(start..index);
+            start = index;
+            used = 0.0;
+        }
+        used += if index > start { gap + size } else { *size };
+    }
+    lines.push(start..hypothetical.len());
+    lines
+}
+
+// Grow or shrink the items of one line to fill `space`, freezing items as they hit min/max limits
+fn resolve_flexible_lengths(items: &[FlexItem], base: &[f32], hypothetical: &[f32], space: f32) -> Vec<f32> {
+    let growing = hypothetical.iter().sum::<f32>() < space;
+    let factor = |item: &FlexItem| if growing { item.style.grow } else { item.style.shrink };
+
+    let mut sizes = hypothetical.to_vec();
+    let mut frozen: Vec<bool> = items.iter().enumerate()
+        .map(|(n, item)| {
+            factor(item) == 0.0
+                || (growing && base[n] > hypothetical[n])
+                || (!growing && base[n] < hypothetical[n])
+        })
+        .collect();
+
+    let free_space = |sizes: &[f32], frozen: &[bool]| {
+        space - (0..items.len()).map(|n| if frozen[n] { sizes[n] } else { base[n] }).sum::<f32>()
+    };
+    let initial_free = free_space(&sizes, &frozen);
+
+    while frozen.iter().any(|f| !f) {
+        let mut free = free_space(&sizes, &frozen);
+        let factor_sum: f32 = (0..items.len()).filter(|&n| !frozen[n]).map(|n| factor(&items[n])).sum();
+        // Factors summing below 1 only take that fraction of the free space
+        if factor_sum < 1.0 {
+            let scaled = initial_free * factor_sum;
+            if scaled.abs() < free.abs() {
+                free = scaled;
+            }
+        }
+
+        let scaled_shrink_sum: f32 = (0..items.len())
+            .filter(|&n| !frozen[n])
+            .map(|n| items[n].style.shrink * base[n])
+            .sum();
+
+        let mut total_violation = 0.0;
+        let mut violations = vec![0.0; items.len()];
+        for n in (0..items.len()).filter(|&n| !frozen[n]) {
+            let target = if growing {
+                base[n] + free * items[n].style.grow / factor_sum
+            } else if scaled_shrink_sum > 0.0 {
+                base[n] + free * items[n].style.shrink * base[n] / scaled_shrink_sum
+            } else {
+                base[n]
+            };
+            sizes[n] = items[n].clamp_main(target);
+            violations[n] = sizes[n] - target;
+            total_violation += violations[n];
+        }
+
+        for n in 0..items.len() {
+            if frozen[n] {
+                continue;
+            }
+            frozen[n] = if total_violation > 0.0 {
+                violations[n] > 0.0
+            } else if total_violation < 0.0 {
+                violations[n] < 0.0
+            } else {
+                true
+            };
+        }
+    }
+
+    sizes
+}
+
+// Starting offset and extra spacing between items for the leftover main-axis space
+fn justify(justify: JustifyContent, remaining: f32, count: usize) -> (f32, f32) {
+    let count = count as f32;
+    match justify {
+        JustifyContent::FlexStart => (0.0, 0.0),
+        JustifyContent::FlexEnd => (remaining, 0.0),
+        JustifyContent::Center => (remaining / 2.0, 0.0),
+        JustifyContent::SpaceBetween if remaining > 0.0 && count > 1.0 => (0.0, remaining / (count - 1.0)),
+        JustifyContent::SpaceBetween => (0.0, 0.0),
+        JustifyContent::SpaceAround if remaining > 0.0 => (remaining / count / 2.0, remaining / count),
+        JustifyContent::SpaceEvenly if remaining > 0.0 => (remaining / (count + 1.0), remaining / (count + 1.0)),
+        JustifyContent::SpaceAround | JustifyContent::SpaceEvenly => (remaining / 2.0, 0.0),
+    }
+}
+
+fn gaps(count: usize, gap: f32) -> f32 {
+    count.saturating_sub(1) as f32 * gap
+}
+
+fn keyword(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Keyword(keyword) => Some(keyword.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+fn keywords(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::List(values)) => values.iter().filter_map(|v| keyword(Some(v))).collect(),
+        other => keyword(other).into_iter().collect(),
+    }
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => Some(*number),
+        _ => None,
+    }
+}
+
+// `auto` and `content` defer to the item's own size
+fn basis_px(value: &Value, main_reference: f32) -> Option<f32> {
+    match value {
+        Value::Keyword(_) => None,
+        value => Some(to_px(value, main_reference)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::css_parser;
+    use crate::engine::dom::DOMNode;
+    use crate::engine::layout::{LayoutEngine, StyledNode};
+
+    struct Fixture {
+        name: &'static str,
+        container: &'static str,
+        items: &'static [&'static str],
+        expected: &'static [(f32, f32, f32, f32)],
+    }
+
+    const WIDTH: f32 = 300.0;
+
+    // Expected (x, y, width, height) of each item inside a 300px wide container
+    const FIXTURES: &[Fixture] = &[
+        Fixture {
+            name: "row of fixed widths",
+            container: "display: flex",
+            items: &["width: 100px; height: 50px", "width: 100px; height: 50px"],
+            expected: &[(0.0, 0.0, 100.0, 50.0), (100.0, 0.0, 100.0, 50.0)],
+        },
+        Fixture {
+            name: "equal grow",
+            container: "display: flex",
+            items: &["flex-grow: 1; height: 20px", "flex-grow: 1; height: 20px", "flex-grow: 1; height: 20px"],
+            expected: &[(0.0, 0.0, 100.0, 20.0), (100.0, 0.0, 100.0, 20.0), (200.0, 0.0, 100.0, 20.0)],
+        },
+        Fixture {
+            name: "flex shorthand ratios",
+            container: "display: flex",
+            items: &["flex: 1; height: 10px", "flex: 2; height: 10px"],
+            expected: &[(0.0, 0.0, 100.0, 10.0), (100.0, 0.0, 200.0, 10.0)],
+        },
+        Fixture {
+            name: "grow from basis next to a fixed item",
+            container: "display: flex",
+            items: &["flex-basis: 100px; flex-grow: 1; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(0.0, 0.0, 250.0, 10.0), (250.0, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "percentage basis",
+            container: "display: flex",
+            items: &["flex-basis: 50%; height: 10px", "flex-grow: 1; height: 10px"],
+            expected: &[(0.0, 0.0, 150.0, 10.0), (150.0, 0.0, 150.0, 10.0)],
+        },
+        Fixture {
+            name: "equal shrink",
+            container: "display: flex",
+            items: &["width: 200px; height: 10px", "width: 200px; height: 10px"],
+            expected: &[(0.0, 0.0, 150.0, 10.0), (150.0, 0.0, 150.0, 10.0)],
+        },
+        Fixture {
+            name: "weighted shrink",
+            container: "display: flex",
+            items: &["width: 200px; height: 10px", "width: 200px; flex-shrink: 3; height: 10px"],
+            expected: &[(0.0, 0.0, 175.0, 10.0), (175.0, 0.0, 125.0, 10.0)],
+        },
+        Fixture {
+            name: "shrink stops at min-width",
+            container: "display: flex",
+            items: &["width: 200px; min-width: 180px; height: 10px", "width: 200px; height: 10px"],
+            expected: &[(0.0, 0.0, 180.0, 10.0), (180.0, 0.0, 120.0, 10.0)],
+        },
+        Fixture {
+            name: "grow stops at max-width",
+            container: "display: flex",
+            items: &["flex-grow: 1; max-width: 50px; height: 10px", "flex-grow: 1; height: 10px"],
+            expected: &[(0.0, 0.0, 50.0, 10.0), (50.0, 0.0, 250.0, 10.0)],
+        },
+        Fixture {
+            name: "flex none overflows",
+            container: "display: flex",
+            items: &["flex: none; width: 200px; height: 10px", "flex: none; width: 200px; height: 10px"],
+            expected: &[(0.0, 0.0, 200.0, 10.0), (200.0, 0.0, 200.0, 10.0)],
+        },
+        Fixture {
+            name: "justify center",
+            container: "display: flex; justify-content: center",
+            items: &["width: 50px; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(100.0, 0.0, 50.0, 10.0), (150.0, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "justify flex-end",
+            container: "display: flex; justify-content: flex-end",
+            items: &["width: 50px; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(200.0, 0.0, 50.0, 10.0), (250.0, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "justify space-between",
+            container: "display: flex; justify-content: space-between",
+            items: &["width: 50px; height: 10px", "width: 50px; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(0.0, 0.0, 50.0, 10.0), (125.0, 0.0, 50.0, 10.0), (250.0, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "justify space-around",
+            container: "display: flex; justify-content: space-around",
+            items: &["width: 50px; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(50.0, 0.0, 50.0, 10.0), (200.0, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "justify space-evenly",
+            container: "display: flex; justify-content: space-evenly",
+            items: &["width: 50px; height: 10px", "width: 50px; height: 10px"],
+            expected: &[(66.667, 0.0, 50.0, 10.0), (183.333, 0.0, 50.0, 10.0)],
+        },
+        Fixture {
+            name: "align-items center",
+            container: "display: flex; height: 100px; align-items: center",
+            items: &["width: 50px; height: 20px", "width: 50px; height: 60px"],
+            expected: &[(0.0, 40.0, 50.0, 20.0), (50.0, 20.0, 50.0, 60.0)],
+        },
+        Fixture {
+            name: "stretch fills the line unless height is set",
+            container: "display: flex; height: 100px",
+            items: &["width: 50px", "width: 50px; height: 30px"],
+            expected: &[(0.0, 0.0, 50.0, 100.0), (50.0, 0.0, 50.0, 30.0)],
+        },
+        Fixture {
+            name: "align-self overrides align-items",
+            container: "display: flex; height: 100px; align-items: flex-start",
+            items: &["width: 50px; height: 20px", "width: 50px; height: 20px; align-self: flex-end"],
+            expected: &[(0.0, 0.0, 50.0, 20.0), (50.0, 80.0, 50.0, 20.0)],
+        },
+        Fixture {
+            name: "row-reverse",
+            container: "display: flex; flex-direction: row-reverse",
+            items: &["width: 100px; height: 10px", "width: 100px; height: 10px"],
+            expected: &[(200.0, 0.0, 100.0, 10.0), (100.0, 0.0, 100.0, 10.0)],
+        },
+        Fixture {
+            name: "column stretches across",
+            container: "display: flex; flex-direction: column",
+            items: &["height: 40px", "height: 60px"],
+            expected: &[(0.0, 0.0, 300.0, 40.0), (0.0, 40.0, 300.0, 60.0)],
+        },
+        Fixture {
+            name: "column grows into definite height",
+            container: "display: flex; flex-direction: column; height: 200px",
+            items: &["height: 50px", "flex-grow: 1"],
+            expected: &[(0.0, 0.0, 300.0, 50.0), (0.0, 50.0, 300.0, 150.0)],
+        },
+        Fixture {
+            name: "column-reverse",
+            container: "display: flex; flex-direction: column-reverse; height: 200px",
+            items: &["height: 50px", "height: 50px"],
+            expected: &[(0.0, 150.0, 300.0, 50.0), (0.0, 100.0, 300.0, 50.0)],
+        },
+        Fixture {
+            name: "wrap onto a second line",
+            container: "display: flex; flex-wrap: wrap",
+            items: &["width: 120px; height: 30px", "width: 120px; height: 30px", "width: 120px; height: 30px"],
+            expected: &[(0.0, 0.0, 120.0, 30.0), (120.0, 0.0, 120.0, 30.0), (0.0, 30.0, 120.0, 30.0)],
+        },
+        Fixture {
+            name: "wrap with gap",
+            container: "display: flex; flex-flow: row wrap; gap: 10px",
+            items: &["width: 140px; height: 20px", "width: 140px; height: 20px", "width: 140px; height: 20px"],
+            expected: &[(0.0, 0.0, 140.0, 20.0), (150.0, 0.0, 140.0, 20.0), (0.0, 30.0, 140.0, 20.0)],
+        },
+        Fixture {
+            name: "wrap-reverse",
+            container: "display: flex; flex-wrap: wrap-reverse",
+            items: &["width: 120px; height: 30px", "width: 120px; height: 30px", "width: 120px; height: 30px"],
+            expected: &[(0.0, 30.0, 120.0, 30.0), (120.0, 30.0, 120.0, 30.0), (0.0, 0.0, 120.0, 30.0)],
+        },
+    ];
+
+    fn styled(tag: &str, css: &str, children: Vec<StyledNode>) -> StyledNode {
+        StyledNode {
+            node: DOMNode::new_element(tag.to_string()),
+            specified_values: css_parser::parse_declarations(css)
+                .into_iter()
+                .map(|declaration| (declaration.name, declaration.value))
+                .collect(),
+            children,
+        }
+    }
+
+    #[test]
+    fn test_flex_fixtures() {
+        for fixture in FIXTURES {
+            let items = fixture.items.iter().map(|css| styled("div", css, Vec::new())).collect();
+            let root = styled("div", fixture.container, items);
+            let layout = LayoutEngine::new(WIDTH, 600.0).layout(&root);
+
+            assert_eq!(layout.children.len(), fixture.expected.len(), "{}", fixture.name);
+            for (index, (child, expected)) in layout.children.iter().zip(fixture.expected).enumerate() {
+                let actual = (child.content.x, child.content.y, child.content.width, child.content.height);
+                let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+                assert!(
+                    close(actual.0, expected.0) && close(actual.1, expected.1)
+                        && close(actual.2, expected.2) && close(actual.3, expected.3),
+                    "{}: item {} was {:?}, expected {:?}", fixture.name, index, actual, expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_container_height_follows_lines() {
+        let items = (0..3).map(|_| styled("div", "width: 120px; height: 30px", Vec::new())).collect();
+        let root = styled("div", "display: flex; flex-wrap: wrap; row-gap: 5px", items);
+        let layout = LayoutEngine::new(WIDTH, 600.0).layout(&root);
+        assert_eq!(layout.content.height, 65.0);
+    }
+}
@@ -0,0 +1,119 @@
+// Keyboard focus in rendered pages: the order Tab visits elements in and where the arrow keys lead
+use eframe::egui;
+use crate::engine::dom::DOMNode;
+use crate::engine::forms;
+
+/// How a key press asks focus to move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FocusMove {
+    Next,
+    Previous,
+    /// Spatial navigation towards a unit direction on screen
+    Toward(egui::Vec2),
+}
+
+/// Whether `node` takes keyboard focus: links, and form controls that aren't disabled or hidden
+pub fn is_focusable(node: &DOMNode) -> bool {
+    let DOMNode::Element { tag_name, attributes, .. } = node else { return false };
+    match tag_name.as_str() {
+        "a" => attributes.contains_key("href"),
+        "input" => !attributes.contains_key("disabled") && forms::input_type(node) != "hidden",
+        "button" | "select" | "textarea" => !attributes.contains_key("disabled"),
+        _ => false,
+    }
+}
+
+/// Whether a focused `node` uses the arrow keys itself, as text fields and lists do, so they don't move focus
+pub fn uses_arrow_keys(node: &DOMNode) -> bool {
+    match node.tag_name().map(String::as_str) {
+        Some("textarea" | "select") => true,
+        Some("input") => !matches!(forms::input_type(node).as_str(), "checkbox" | "radio" | "submit" | "image" | "reset" | "button"),
+        _ => false,
+    }
+}
+
+/// Focusable elements in the order Tab visits them: positive tabindex values first, ascending, then the
+/// rest in document order. A negative tabindex takes an element out of the order.
+pub fn tab_order(dom: &DOMNode) -> Vec<*const DOMNode> {
+    fn collect(node: &DOMNode, nodes: &mut Vec<(i32, *const DOMNode)>) {
+        if is_focusable(node) {
+            let index = node.get_attribute("tabindex").and_then(|index| index.trim().parse().ok()).unwrap_or(0);
+            if index >= 0 {
+                nodes.push((index, node));
+            }
+        }
+        if let DOMNode::Element { children, .. } = node {
+            for child in children {
+                collect(child, nodes);
+            }
+        }
+    }
+    let mut nodes = Vec::new();
+    collect(dom, &mut nodes);
+    // Stable, so equal indices keep document order; zero sorts after every positive index
+    nodes.sort_by_key(|(index, _)| if *index == 0 { i32::MAX } else { *index });
+    nodes.into_iter().map(|(_, node)| node).collect()
+}
+
+/// The element Tab or Shift+Tab moves to from `current` in `order`, wrapping around at either end
+pub fn step<T: Copy + PartialEq>(order: &[T], current: Option<T>, backward: bool) -> Option<T> {
+    let position = current.and_then(|current| order.iter().position(|node| *node == current));
+    let index = match (position, backward) {
+        (None, false) => 0,
+        (None, true) => order.len().checked_sub(1)?,
+        (Some(i), false) => (i + 1) % order.len(),
+        (Some(i), true) => (i + order.len() - 1) % order.len(),
+    };
+    order.get(index).copied()
+}
+
+/// The candidate nearest to `from` in `direction`, preferring ones in line with it over ones off to the side
+pub fn nearest_in_direction<T: Copy>(from: egui::Rect, direction: egui::Vec2, candidates: impl IntoIterator<Item = (T, egui::Rect)>) -> Option<T> {
+    candidates.into_iter()
+        .filter_map(|(node, rect)| {
+            let offset = rect.center() - from.center();
+            let along = offset.dot(direction);
+            // Anything overlapping the current box along the direction isn't beyond it
+            let extent = (from.size() + rect.size()).dot(direction.abs()) / 2.0;
+            (along >= extent - 1.0).then(|| (node, along + 2.0 * (offset - direction * along).length()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(node, _)| node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_tab_order() {
+        let dom = html_parser::parse(
+            "<a href=\"/a\">A</a><a>no href</a><input type=\"hidden\" name=\"h\"><input name=\"q\">\
+             <button disabled>off</button><a href=\"/skip\" tabindex=\"-1\">skip</a><select tabindex=\"2\"></select>\
+             <textarea tabindex=\"1\"></textarea>"
+        );
+        let names: Vec<String> = tab_order(&dom).into_iter()
+            .filter_map(|node| dom.path_to(node).and_then(|path| dom.node_at(&path)))
+            .filter_map(|node| node.tag_name().cloned())
+            .collect();
+        assert_eq!(names, ["textarea", "select", "a", "input"]);
+
+        assert_eq!(step(&[1, 2, 3], None, false), Some(1));
+        assert_eq!(step(&[1, 2, 3], None, true), Some(3));
+        assert_eq!(step(&[1, 2, 3], Some(3), false), Some(1));
+        assert_eq!(step(&[1, 2, 3], Some(1), true), Some(3));
+        assert_eq!(step::<i32>(&[], None, false), None);
+    }
+
+    #[test]
+    fn test_spatial_navigation() {
+        let rect = |x: f32, y: f32| egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(80.0, 20.0));
+        let from = rect(100.0, 100.0);
+        let candidates = [("right", rect(200.0, 100.0)), ("below", rect(100.0, 140.0)), ("below-right", rect(300.0, 130.0)), ("above", rect(110.0, 40.0))];
+        assert_eq!(nearest_in_direction(from, egui::Vec2::DOWN, candidates), Some("below"));
+        assert_eq!(nearest_in_direction(from, egui::Vec2::RIGHT, candidates), Some("right"));
+        assert_eq!(nearest_in_direction(from, egui::Vec2::UP, candidates), Some("above"));
+        assert_eq!(nearest_in_direction(from, egui::Vec2::LEFT, candidates), None);
+    }
+}
@@ -0,0 +1,644 @@
+// Page typography: CSS font properties, @font-face web fonts and system fonts, registered with egui
+use ab_glyph::Font as _;
+use anyhow::{anyhow, bail, Result};
+use eframe::egui;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use crate::engine::css_parser::{Declaration, FontFaceRule, Stylesheet, Value};
+use crate::engine::resource_loader::{ResourceKind, ResourceRequest};
+use crate::engine::woff;
+
+// Fonts registered with egui at once; every one is rasterised into the shared glyph atlas
+const MAX_LOADED_FONTS: usize = 48;
+
+// Web font files a single page may download
+const MAX_PAGE_FONTS: usize = 12;
+
+// How deep to look below each system font directory
+const FONT_DIR_DEPTH: usize = 4;
+
+// Installed serif fonts to try for the generic `serif` family, egui only ships sans and mono
+const SERIF_FALLBACKS: &[&str] = &["Georgia", "Times New Roman", "DejaVu Serif", "Liberation Serif", "Noto Serif"];
+
+// `@font-face` formats we can unpack; fonts declaring any other format are skipped
+const SUPPORTED_FORMATS: &[&str] = &["woff2", "woff", "truetype", "opentype", "ttf", "otf"];
+
+/// The font properties that decide which face text is drawn with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontDescriptor {
+    /// font-family in order of preference, unquoted
+    pub families: Vec<String>,
+    pub weight: u16,
+    pub italic: bool,
+}
+
+impl Default for FontDescriptor {
+    fn default() -> Self {
+        Self {
+            families: Vec::new(),
+            weight: 400,
+            italic: false,
+        }
+    }
+}
+
+impl FontDescriptor {
+    /// Apply one declaration on top of the inherited font; unrelated declarations are ignored
+    pub fn apply(&mut self, declaration: &Declaration) {
+        match (declaration.name.as_str(), &declaration.value) {
+            ("font-family", Value::Keyword(raw)) => {
+                let families = parse_family_list(raw);
+                if !families.is_empty() {
+                    self.families = families;
+                }
+            }
+            ("font-weight", value) => {
+                if let Some(weight) = parse_weight(value, self.weight) {
+                    self.weight = weight;
+                }
+            }
+            ("font-style", Value::Keyword(style)) => {
+                self.italic = matches!(style.to_ascii_lowercase().as_str(), "italic" | "oblique");
+            }
+            ("font", Value::Keyword(raw)) => {
+                if let Some(font) = self.with_shorthand(raw) {
+                    *self = font;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // `font: [style] [weight] size[/line-height] family, ...`; anything it leaves out resets to normal
+    fn with_shorthand(&self, raw: &str) -> Option<Self> {
+        let mut font = Self { families: self.families.clone(), ..Self::default() };
+        let mut rest = raw.trim();
+        loop {
+            let (token, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let lower = token.to_ascii_lowercase();
+            match lower.as_str() {
+                "" => return None,
+                "italic" | "oblique" => font.italic = true,
+                "normal" | "small-caps" | "condensed" | "expanded" => {}
+                // The size is the first token that isn't a bare weight number; families follow it
+                _ if is_size_keyword(&lower)
+                    || (lower.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                        && !lower.chars().all(|c| c.is_ascii_digit())) =>
+                {
+                    let families = parse_family_list(after);
+                    if families.is_empty() {
+                        return None;
+                    }
+                    font.families = families;
+                    return Some(font);
+                }
+                _ => font.weight = parse_weight(&crate::engine::css_parser::parse_value(token), self.weight)?,
+            }
+            rest = after.trim_start();
+        }
+    }
+}
+
+fn is_size_keyword(token: &str) -> bool {
+    matches!(token, "xx-small" | "x-small" | "small" | "medium" | "large" | "x-large" | "xx-large" | "larger" | "smaller")
+}
+
+/// Split a font-family value such as `"Open Sans", Arial, sans-serif` into unquoted names
+pub fn parse_family_list(raw: &str) -> Vec<String> {
+    let mut families = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in raw.chars().chain(std::iter::once(',')) {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            ('"' | '\'', None) => quote = Some(c),
+            (',', None) => {
+                let name = current.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !name.is_empty() {
+                    families.push(name);
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    families
+}
+
+/// font-weight as a number; `bolder` and `lighter` are relative to the inherited weight
+pub fn parse_weight(value: &Value, inherited: u16) -> Option<u16> {
+    match value {
+        Value::Number(n) if (1.0..=1000.0).contains(n) => Some(*n as u16),
+        Value::Keyword(keyword) => match keyword.to_ascii_lowercase().as_str() {
+            "normal" => Some(400),
+            "bold" => Some(700),
+            "bolder" => Some(match inherited {
+                0..=349 => 400,
+                350..=549 => 700,
+                _ => 900,
+            }),
+            "lighter" => Some(match inherited {
+                0..=549 => 100,
+                550..=749 => 400,
+                _ => 700,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_generic(family: &str) -> bool {
+    matches!(
+        family.to_ascii_lowercase().as_str(),
+        "serif" | "sans-serif" | "monospace" | "cursive" | "fantasy" | "system-ui" | "ui-sans-serif"
+            | "ui-serif" | "ui-monospace" | "ui-rounded" | "-apple-system" | "blinkmacsystemfont" | "math"
+            | "emoji"
+    )
+}
+
+/// A downloadable face declared by `@font-face`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFace {
+    pub family: String,
+    /// The first source in a format we can unpack, resolved to an absolute URL
+    pub url: String,
+    /// Weights this face covers; variable fonts declare a range
+    pub weight: (u16, u16),
+    pub italic: bool,
+}
+
+impl FontFace {
+    /// Interpret an `@font-face` rule whose URLs are relative to `base_url`
+    pub fn from_rule(rule: &FontFaceRule, base_url: &str) -> Option<Self> {
+        let base = url::Url::parse(base_url).ok();
+        let mut family = None;
+        let mut url = None;
+        let mut weight = (400, 400);
+        let mut italic = false;
+
+        for declaration in &rule.declarations {
+            match (declaration.name.as_str(), &declaration.value) {
+                ("font-family", Value::Keyword(raw)) => family = parse_family_list(raw).into_iter().next(),
+                ("src", Value::Keyword(raw)) => {
+                    url = split_top_level(raw).into_iter()
+                        .filter_map(|source| {
+                            let location = function_argument(source, "url(")?;
+                            let format = function_argument(source, "format(").map(|f| f.to_ascii_lowercase());
+                            let supported = format.as_deref().is_none_or(|f| SUPPORTED_FORMATS.iter().any(|s| f.starts_with(s)));
+                            let resolved = match &base {
+                                Some(base) => base.join(location).ok(),
+                                None => url::Url::parse(location).ok(),
+                            }?;
+                            (supported && matches!(resolved.scheme(), "http" | "https")).then(|| resolved.to_string())
+                        })
+                        .next();
+                }
+                ("font-weight", Value::List(range)) => {
+                    if let [low, high] = range.as_slice() {
+                        if let (Some(low), Some(high)) = (parse_weight(low, 400), parse_weight(high, 400)) {
+                            weight = (low.min(high), low.max(high));
+                        }
+                    }
+                }
+                ("font-weight", value) => {
+                    if let Some(w) = parse_weight(value, 400) {
+                        weight = (w, w);
+                    }
+                }
+                ("font-style", Value::Keyword(style)) => italic = style != "normal",
+                _ => {}
+            }
+        }
+
+        Some(Self { family: family?, url: url?, weight, italic })
+    }
+
+    // Lower is better: a matching style beats any weight, then the closest weight wins
+    fn distance(&self, weight: u16, italic: bool) -> (bool, u16) {
+        let (low, high) = self.weight;
+        let off = if weight < low { low - weight } else { weight.saturating_sub(high) };
+        (self.italic != italic, off)
+    }
+}
+
+// Split on commas that aren't inside parentheses or quotes
+fn split_top_level(raw: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0i32, None, 0);
+    for (i, c) in raw.char_indices() {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                parts.push(raw[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(raw[start..].trim());
+    parts
+}
+
+// The unquoted argument of e.g. `url(...)` within a source descriptor
+fn function_argument<'a>(source: &'a str, function: &str) -> Option<&'a str> {
+    let start = source.to_ascii_lowercase().find(function)? + function.len();
+    let end = start + source[start..].find(')')?;
+    Some(source[start..end].trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Font state for one page: the faces its stylesheets declare
+#[derive(Debug, Default)]
+pub struct PageFonts {
+    faces: Vec<FontFace>,
+    requested: HashSet<String>,
+    pending: Vec<ResourceRequest>,
+}
+
+impl PageFonts {
+    /// Take in a newly loaded stylesheet, queueing downloads for declared faces the page's styles use
+    pub fn add_stylesheet(&mut self, stylesheet: &Stylesheet, base_url: &str, stylesheets: &[Stylesheet]) {
+        self.faces.extend(stylesheet.font_faces.iter().filter_map(|rule| FontFace::from_rule(rule, base_url)));
+
+        let used = used_families(stylesheets);
+        for face in &self.faces {
+            if self.requested.len() >= MAX_PAGE_FONTS {
+                break;
+            }
+            if used.contains(&face.family.to_lowercase()) && self.requested.insert(face.url.clone()) {
+                self.pending.push(ResourceRequest {
+                    kind: ResourceKind::Font,
+                    url: face.url.clone(),
+                    reference: face.url.clone(),
+                    lazy: false,
+                });
+            }
+        }
+    }
+
+    /// Font downloads queued since the last call
+    pub fn take_requests(&mut self) -> Vec<ResourceRequest> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// The egui family to draw `font` with, preferring the page's own faces
+    pub fn family(&self, font: &FontDescriptor) -> egui::FontFamily {
+        FontRegistry::shared().resolve(font, &self.faces)
+    }
+}
+
+// Lowercased families named anywhere in the stylesheets
+fn used_families(stylesheets: &[Stylesheet]) -> HashSet<String> {
+    let mut used = HashSet::new();
+    for declaration in stylesheets.iter().flat_map(|s| &s.rules).flat_map(|r| &r.declarations) {
+        let mut font = FontDescriptor::default();
+        font.apply(declaration);
+        used.extend(font.families.into_iter().map(|f| f.to_lowercase()));
+    }
+    used
+}
+
+static SHARED_FONTS: OnceLock<FontRegistry> = OnceLock::new();
+
+/// The fonts registered with egui, shared by every page
+pub struct FontRegistry {
+    state: Mutex<RegistryState>,
+}
+
+struct RegistryState {
+    definitions: egui::FontDefinitions,
+    // egui family name of each loaded web font URL or system font file
+    loaded: HashMap<String, String>,
+    failed: HashSet<String>,
+    // Names sent to egui this frame, usable from the next one
+    staged: Vec<String>,
+    active: HashSet<String>,
+    dirty: bool,
+    system: Option<SystemFonts>,
+}
+
+#[derive(Debug, Clone)]
+struct SystemFace {
+    path: PathBuf,
+    weight: u16,
+    italic: bool,
+}
+
+// Installed font files indexed by normalised file name, matched to families lazily
+struct SystemFonts {
+    files: Vec<(String, PathBuf)>,
+    families: HashMap<String, Vec<SystemFace>>,
+}
+
+impl FontRegistry {
+    pub fn shared() -> &'static FontRegistry {
+        SHARED_FONTS.get_or_init(|| FontRegistry {
+            state: Mutex::new(RegistryState {
+                definitions: egui::FontDefinitions::default(),
+                loaded: HashMap::new(),
+                failed: HashSet::new(),
+                staged: Vec::new(),
+                active: HashSet::new(),
+                dirty: false,
+                system: None,
+            }),
+        })
+    }
+
+    /// Make a downloaded font available to pages; it can be drawn once `apply` has run twice
+    pub fn register_web_font(&self, url: &str, data: Vec<u8>) -> Result<()> {
+        let mut state = self.state.lock().map_err(|_| anyhow!("font registry poisoned"))?;
+        state.register(url, data).map(|_| ())
+    }
+
+    /// Hand newly registered fonts to egui. Call once per frame, before anything draws page text.
+    /// Returns true when fonts registered earlier just became usable, so the page should repaint.
+    pub fn apply(&self, ctx: &egui::Context) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        // egui switches fonts at the start of a frame, so last frame's names are live now
+        let activated = !state.staged.is_empty();
+        let staged = std::mem::take(&mut state.staged);
+        state.active.extend(staged);
+
+        if state.dirty {
+            ctx.set_fonts(state.definitions.clone());
+            state.staged = state.loaded.values()
+                .filter(|name| !state.active.contains(*name))
+                .cloned()
+                .collect();
+            state.dirty = false;
+            ctx.request_repaint();
+        }
+        activated
+    }
+
+    /// The egui family to draw `font` with, using the page's declared faces where they are loaded
+    pub fn resolve(&self, font: &FontDescriptor, faces: &[FontFace]) -> egui::FontFamily {
+        let Ok(mut state) = self.state.lock() else {
+            return egui::FontFamily::Proportional;
+        };
+
+        for family in &font.families {
+            let declared = faces.iter()
+                .filter(|face| face.family.eq_ignore_ascii_case(family))
+                .min_by_key(|face| face.distance(font.weight, font.italic));
+            if let Some(face) = declared {
+                // Until the download lands, fall through to the next family like font-display: swap
+                if let Some(name) = state.active_name(&face.url) {
+                    return egui::FontFamily::Name(name.into());
+                }
+                continue;
+            }
+
+            match family.to_ascii_lowercase().as_str() {
+                "monospace" | "ui-monospace" => return egui::FontFamily::Monospace,
+                "serif" | "ui-serif" => {
+                    if let Some(name) = SERIF_FALLBACKS.iter().find_map(|f| state.system_font(f, font.weight, font.italic)) {
+                        return egui::FontFamily::Name(name.into());
+                    }
+                    return egui::FontFamily::Proportional;
+                }
+                _ if is_generic(family) => return egui::FontFamily::Proportional,
+                _ => {}
+            }
+            if let Some(name) = state.system_font(family, font.weight, font.italic) {
+                return egui::FontFamily::Name(name.into());
+            }
+        }
+        egui::FontFamily::Proportional
+    }
+}
+
+impl RegistryState {
+    fn register(&mut self, key: &str, data: Vec<u8>) -> Result<String> {
+        if let Some(name) = self.loaded.get(key) {
+            return Ok(name.clone());
+        }
+        if self.loaded.len() >= MAX_LOADED_FONTS {
+            bail!("too many fonts loaded");
+        }
+        validate(&data)?;
+
+        let name = format!("page-font-{}", self.loaded.len());
+        self.definitions.font_data.insert(name.clone(), egui::FontData::from_owned(data));
+        // Glyphs the font lacks, such as emoji and icons, still come from egui's own fonts
+        let mut fallbacks = vec![name.clone()];
+        fallbacks.extend(self.definitions.families.get(&egui::FontFamily::Proportional).cloned().unwrap_or_default());
+        self.definitions.families.insert(egui::FontFamily::Name(name.as_str().into()), fallbacks);
+        self.loaded.insert(key.to_string(), name.clone());
+        self.dirty = true;
+        Ok(name)
+    }
+
+    fn active_name(&self, key: &str) -> Option<String> {
+        self.loaded.get(key).filter(|name| self.active.contains(*name)).cloned()
+    }
+
+    // Load an installed font on first use; it becomes drawable a frame later
+    fn system_font(&mut self, family: &str, weight: u16, italic: bool) -> Option<String> {
+        let face = self.system.get_or_insert_with(SystemFonts::scan).face(family, weight, italic)?;
+        let key = face.path.to_string_lossy().to_string();
+        if let Some(name) = self.active_name(&key) {
+            return Some(name);
+        }
+        if self.loaded.contains_key(&key) || self.failed.contains(&key) {
+            return None;
+        }
+        let loaded = std::fs::read(&face.path).map_err(anyhow::Error::from).and_then(|data| self.register(&key, data));
+        if let Err(e) = loaded {
+            log::warn!("Could not load font {}: {}", key, e);
+            self.failed.insert(key);
+        }
+        None
+    }
+}
+
+// egui panics on fonts it can't parse, so anything registered is checked the same way first
+fn validate(data: &[u8]) -> Result<()> {
+    let font = ab_glyph::FontRef::try_from_slice(data).map_err(|e| anyhow!("invalid font: {}", e))?;
+    match font.units_per_em() {
+        Some(units) if (16.0..=16384.0).contains(&units) => Ok(()),
+        _ => bail!("invalid font: units per em out of range"),
+    }
+}
+
+/// Unpack and check a downloaded font, keeping the result in the disk cache
+pub fn prepare_download(url: &str, body: &[u8]) -> Result<Vec<u8>> {
+    let sfnt = woff::to_sfnt(body)?;
+    validate(&sfnt)?;
+    let path = cache_path(url);
+    if let Err(e) = path.parent().map(std::fs::create_dir_all).transpose().and_then(|_| std::fs::write(&path, &sfnt)) {
+        log::warn!("Could not cache font {}: {}", url, e);
+    }
+    Ok(sfnt)
+}
+
+/// A font downloaded and unpacked by an earlier page
+pub fn cached_font(url: &str) -> Option<Vec<u8>> {
+    std::fs::read(cache_path(url)).ok()
+}
+
+fn cache_path(url: &str) -> PathBuf {
+    crate::storage::data_dir()
+        .join("font_cache")
+        .join(format!("{:x}.font", Sha256::digest(url.as_bytes())))
+}
+
+// Lowercase ASCII letters and digits only, so "DejaVu Serif" and "DejaVuSerif-Bold" can be compared
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+impl SystemFonts {
+    fn scan() -> Self {
+        let mut files = Vec::new();
+        for dir in system_font_dirs() {
+            collect_font_files(&dir, FONT_DIR_DEPTH, &mut files);
+        }
+        Self { files, families: HashMap::new() }
+    }
+
+    fn face(&mut self, family: &str, weight: u16, italic: bool) -> Option<SystemFace> {
+        let key = normalize(family);
+        if key.is_empty() {
+            return None;
+        }
+        let files = &self.files;
+        let faces = self.families.entry(key.clone()).or_insert_with(|| {
+            files.iter()
+                .filter_map(|(stem, path)| {
+                    let (weight, italic) = parse_style_suffix(stem.strip_prefix(&key)?)?;
+                    Some(SystemFace { path: path.clone(), weight, italic })
+                })
+                .collect()
+        });
+        faces.iter()
+            .min_by_key(|face| (face.italic != italic, face.weight.abs_diff(weight)))
+            .cloned()
+    }
+}
+
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "windows") {
+        let windows = std::env::var_os("WINDIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("C:\\Windows"));
+        dirs.push(windows.join("Fonts"));
+    } else if cfg!(target_os = "macos") {
+        dirs.extend(["/System/Library/Fonts", "/Library/Fonts"].map(PathBuf::from));
+        dirs.extend(dirs::home_dir().map(|home| home.join("Library/Fonts")));
+    } else {
+        dirs.extend(["/usr/share/fonts", "/usr/local/share/fonts"].map(PathBuf::from));
+        dirs.extend(dirs::home_dir().into_iter().flat_map(|home| [home.join(".local/share/fonts"), home.join(".fonts")]));
+    }
+    dirs
+}
+
+fn collect_font_files(dir: &Path, depth: usize, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth > 0 {
+                collect_font_files(&path, depth - 1, files);
+            }
+            continue;
+        }
+        let is_font = path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc"));
+        if let (true, Some(stem)) = (is_font, path.file_stem().and_then(|s| s.to_str())) {
+            files.push((normalize(stem), path.clone()));
+        }
+    }
+}
+
+// What follows the family in a font file name, e.g. "bolditalic" or Windows' "bd"; None for
+// anything else, which usually means a different family such as "DejaVu Sans Mono"
+fn parse_style_suffix(suffix: &str) -> Option<(u16, bool)> {
+    match suffix {
+        "bd" | "b" => return Some((700, false)),
+        "i" | "it" => return Some((400, true)),
+        "bi" | "z" => return Some((700, true)),
+        _ => {}
+    }
+
+    const WORDS: &[(&str, Option<u16>)] = &[
+        ("extralight", Some(200)), ("ultralight", Some(200)), ("semibold", Some(600)), ("demibold", Some(600)),
+        ("extrabold", Some(800)), ("ultrabold", Some(800)), ("regular", None), ("oblique", None), ("italic", None),
+        ("medium", Some(500)), ("normal", None), ("light", Some(300)), ("black", Some(900)), ("heavy", Some(900)),
+        ("roman", None), ("thin", Some(100)), ("bold", Some(700)), ("book", None),
+    ];
+    let (mut weight, mut italic) = (400, false);
+    let mut rest = suffix;
+    while !rest.is_empty() {
+        let (word, word_weight) = WORDS.iter().find(|(word, _)| rest.starts_with(word))?;
+        match word_weight {
+            Some(w) => weight = *w,
+            None => italic |= matches!(*word, "italic" | "oblique"),
+        }
+        rest = &rest[word.len()..];
+    }
+    Some((weight, italic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::css_parser;
+
+    #[test]
+    fn test_parse_font_properties() {
+        assert_eq!(parse_family_list(r#""Open Sans", 'Fira  Code',sans-serif"#), vec!["Open Sans", "Fira Code", "sans-serif"]);
+
+        let mut font = FontDescriptor::default();
+        for declaration in css_parser::parse_declarations("font: italic 600 16px/1.5 Inter, system-ui; font-weight: bolder") {
+            font.apply(&declaration);
+        }
+        assert_eq!(font, FontDescriptor { families: vec!["Inter".into(), "system-ui".into()], weight: 900, italic: true });
+
+        // An invalid shorthand is ignored whole
+        font.apply(&css_parser::parse_declarations("font: 16px").remove(0));
+        assert_eq!(font.weight, 900);
+        assert_eq!(parse_style_suffix("bolditalic"), Some((700, true)));
+        assert_eq!(parse_style_suffix("mono"), None);
+    }
+
+    #[test]
+    fn test_font_face_sources() {
+        let stylesheet = css_parser::parse(r#"
+            @font-face {
+                font-family: "Brand Sans";
+                src: url(brand.eot) format("embedded-opentype"), url('/fonts/brand.woff2') format("woff2"), url(brand.ttf);
+                font-weight: 300 700;
+                font-style: italic;
+            }
+            @font-face { font-family: Nothing; src: local("Nothing") }
+            h1 { font-family: 'Brand Sans', serif }
+        "#);
+        let faces: Vec<_> = stylesheet.font_faces.iter()
+            .filter_map(|rule| FontFace::from_rule(rule, "https://example.com/css/site.css"))
+            .collect();
+        assert_eq!(faces, vec![FontFace {
+            family: "Brand Sans".into(),
+            url: "https://example.com/fonts/brand.woff2".into(),
+            weight: (300, 700),
+            italic: true,
+        }]);
+
+        let mut fonts = PageFonts::default();
+        fonts.add_stylesheet(&stylesheet, "https://example.com/css/site.css", std::slice::from_ref(&stylesheet));
+        let requests = fonts.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].kind, ResourceKind::Font);
+        assert!(fonts.take_requests().is_empty());
+    }
+}
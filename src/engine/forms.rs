@@ -0,0 +1,271 @@
+// HTML forms: what the user entered into their controls and the request submitting one makes
+use std::collections::HashMap;
+use crate::engine::dom::DOMNode;
+use crate::networking::manual_client::RequestBody;
+
+/// The navigation a submitted form asks for
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormSubmission {
+    pub url: String,
+    /// Set for POST forms; GET forms carry their data in the URL's query instead
+    pub body: Option<RequestBody>,
+    /// Username and password entered, when the form is a login form
+    pub login: Option<(String, String)>,
+}
+
+/// The fields a login form takes its credentials in
+pub struct LoginFields<'a> {
+    pub username: Option<&'a DOMNode>,
+    pub password: &'a DOMNode,
+}
+
+/// Values of the page's form controls that differ from their markup, keyed by element
+#[derive(Debug, Default)]
+pub struct FormState {
+    text: HashMap<*const DOMNode, String>,
+    checked: HashMap<*const DOMNode, bool>,
+    // Checked radio button of each group, by name
+    radios: HashMap<String, *const DOMNode>,
+}
+
+impl FormState {
+    /// The current value of an input, textarea or select
+    pub fn value(&self, control: &DOMNode) -> String {
+        if let Some(value) = self.text.get(&(control as *const DOMNode)) {
+            return value.clone();
+        }
+        match control {
+            DOMNode::Element { tag_name, .. } if tag_name == "textarea" => control.collect_text(),
+            DOMNode::Element { tag_name, children, .. } if tag_name == "select" => {
+                let mut options = Vec::new();
+                collect_options(children, &mut options);
+                options.iter().find(|option| option.get_attribute("selected").is_some())
+                    .or(options.first())
+                    .map(|option| option_value(option))
+                    .unwrap_or_default()
+            }
+            _ => control.get_attribute("value").cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn set_value(&mut self, control: &DOMNode, value: String) {
+        self.text.insert(control, value);
+    }
+
+    /// Whether a checkbox or radio button is checked
+    pub fn is_checked(&self, control: &DOMNode) -> bool {
+        let key = control as *const DOMNode;
+        if let Some(checked) = self.checked.get(&key) {
+            return *checked;
+        }
+        match control.get_attribute("name").and_then(|name| self.radios.get(name)) {
+            Some(selected) if is_type(control, "radio") => *selected == key,
+            _ => control.get_attribute("checked").is_some(),
+        }
+    }
+
+    pub fn set_checked(&mut self, control: &DOMNode, checked: bool) {
+        match control.get_attribute("name") {
+            // Checking a radio button unchecks the rest of its group
+            Some(name) if is_type(control, "radio") && checked => {
+                self.radios.insert(name.clone(), control);
+            }
+            _ => {
+                self.checked.insert(control, checked);
+            }
+        }
+    }
+}
+
+/// An `<input>`'s type, lowercased, defaulting to text
+pub fn input_type(control: &DOMNode) -> String {
+    control.get_attribute("type").map(|t| t.trim().to_ascii_lowercase()).unwrap_or_else(|| "text".to_string())
+}
+
+fn is_type(control: &DOMNode, kind: &str) -> bool {
+    input_type(control) == kind
+}
+
+/// Whether clicking this element submits its form
+pub fn is_submit_button(control: &DOMNode) -> bool {
+    match control.tag_name().map(String::as_str) {
+        Some("button") => matches!(control.get_attribute("type").map(|t| t.to_ascii_lowercase()).as_deref(), None | Some("submit")),
+        Some("input") => matches!(input_type(control).as_str(), "submit" | "image"),
+        _ => false,
+    }
+}
+
+fn collect_options<'a>(children: &'a [DOMNode], options: &mut Vec<&'a DOMNode>) {
+    for child in children {
+        match child.tag_name().map(String::as_str) {
+            Some("option") => options.push(child),
+            Some("optgroup") => {
+                if let DOMNode::Element { children, .. } = child {
+                    collect_options(children, options);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A `<select>`'s options as (value, label) pairs
+pub fn select_options(select: &DOMNode) -> Vec<(String, String)> {
+    let DOMNode::Element { children, .. } = select else { return Vec::new() };
+    let mut options = Vec::new();
+    collect_options(children, &mut options);
+    options.into_iter().map(|option| (option_value(option), option.collect_text().trim().to_string())).collect()
+}
+
+fn option_value(option: &DOMNode) -> String {
+    option.get_attribute("value").cloned().unwrap_or_else(|| option.collect_text().trim().to_string())
+}
+
+fn collect_inputs<'a>(node: &'a DOMNode, inputs: &mut Vec<&'a DOMNode>) {
+    let DOMNode::Element { tag_name, children, .. } = node else { return };
+    if tag_name == "input" && node.get_attribute("disabled").is_none() {
+        inputs.push(node);
+    }
+    for child in children {
+        collect_inputs(child, inputs);
+    }
+}
+
+/// The login fields of `form`: its first password field, and the text field before it holding the username
+pub fn login_fields(form: &DOMNode) -> Option<LoginFields<'_>> {
+    let mut inputs = Vec::new();
+    collect_inputs(form, &mut inputs);
+    let index = inputs.iter().position(|input| is_type(input, "password"))?;
+    let before = &inputs[..index];
+    let username = before.iter()
+        .find(|input| input.get_attribute("autocomplete").is_some_and(|a| a.split_whitespace().any(|t| t == "username")))
+        .or_else(|| before.iter().rev().find(|input| matches!(input_type(input).as_str(), "text" | "email" | "tel")))
+        .copied();
+    Some(LoginFields { username, password: inputs[index] })
+}
+
+/// The first form on the page that asks for a password
+pub fn find_login_form(node: &DOMNode) -> Option<LoginFields<'_>> {
+    let DOMNode::Element { tag_name, children, .. } = node else { return None };
+    if tag_name == "form" {
+        return login_fields(node);
+    }
+    children.iter().find_map(find_login_form)
+}
+
+// Name/value pairs the form's controls contribute, in document order
+fn collect_entries(node: &DOMNode, state: &FormState, submitter: Option<&DOMNode>, entries: &mut Vec<(String, String)>) {
+    let DOMNode::Element { tag_name, attributes, children } = node else { return };
+    let name = attributes.get("name").filter(|name| !name.is_empty());
+    if let Some(name) = name.filter(|_| !attributes.contains_key("disabled")) {
+        let value = match tag_name.as_str() {
+            "input" => match input_type(node).as_str() {
+                "checkbox" | "radio" => state.is_checked(node)
+                    .then(|| attributes.get("value").cloned().unwrap_or_else(|| "on".to_string())),
+                // Buttons only count when they're the one that submitted the form
+                "submit" | "image" | "button" | "reset" => submitter
+                    .filter(|submitter| std::ptr::eq(*submitter, node))
+                    .map(|_| state.value(node)),
+                "file" => None,
+                _ => Some(state.value(node)),
+            },
+            "textarea" | "select" => Some(state.value(node)),
+            "button" => submitter.filter(|submitter| std::ptr::eq(*submitter, node)).map(|_| state.value(node)),
+            _ => None,
+        };
+        if let Some(value) = value {
+            entries.push((name.clone(), value));
+        }
+    }
+    // Controls never contain other controls, except the options of a select
+    if !matches!(tag_name.as_str(), "select" | "textarea") {
+        for child in children {
+            collect_entries(child, state, submitter, entries);
+        }
+    }
+}
+
+/// The request submitting `form` makes, with `submitter` being the button that was clicked, if any
+pub fn submit(form: &DOMNode, state: &FormState, submitter: Option<&DOMNode>, base_url: &str) -> Option<FormSubmission> {
+    // A submit button's formaction and formmethod win over the form's own
+    let attribute = |name: &str, form_name: &str| submitter
+        .and_then(|button| button.get_attribute(form_name))
+        .or_else(|| form.get_attribute(name))
+        .map(|value| value.trim().to_string());
+    let base = url::Url::parse(base_url).ok()?;
+    let mut action = match attribute("action", "formaction").filter(|action| !action.is_empty()) {
+        Some(action) => base.join(&action).ok()?,
+        None => base,
+    };
+    action.set_fragment(None);
+
+    let mut entries = Vec::new();
+    collect_entries(form, state, submitter, &mut entries);
+    let encoded = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(entries).finish();
+    let login = login_fields(form)
+        .map(|fields| (fields.username.map(|u| state.value(u)).unwrap_or_default(), state.value(fields.password)))
+        .filter(|(_, password)| !password.is_empty());
+
+    if attribute("method", "formmethod").is_some_and(|method| method.eq_ignore_ascii_case("post")) {
+        Some(FormSubmission { url: action.to_string(), body: Some(RequestBody::form_urlencoded(encoded)), login })
+    } else {
+        action.set_query(Some(&encoded));
+        Some(FormSubmission { url: action.to_string(), body: None, login })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    fn find<'a>(node: &'a DOMNode, attribute: &str, value: &str) -> Option<&'a DOMNode> {
+        if node.get_attribute(attribute).is_some_and(|v| v == value) {
+            return Some(node);
+        }
+        match node {
+            DOMNode::Element { children, .. } => children.iter().find_map(|child| find(child, attribute, value)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_form_submission() {
+        let dom = html_parser::parse(
+            "<form name=\"f\" action=\"/search#top\"><input name=\"q\" value=\"neon\"><input type=\"checkbox\" name=\"safe\">\
+             <input type=\"radio\" name=\"size\" value=\"s\" checked><input type=\"radio\" name=\"size\" value=\"l\">\
+             <select name=\"lang\"><option>en</option><option value=\"fr\" selected>French</option></select>\
+             <button name=\"go\" value=\"1\">Go</button></form>"
+        );
+        let form = find(&dom, "name", "f").unwrap();
+        let mut state = FormState::default();
+        let submission = submit(form, &state, None, "https://example.com/index.html").unwrap();
+        assert_eq!(submission.url, "https://example.com/search?q=neon&size=s&lang=fr");
+        assert_eq!(submission.body, None);
+
+        state.set_value(find(&dom, "name", "q").unwrap(), "a b&c".to_string());
+        state.set_checked(find(&dom, "name", "safe").unwrap(), true);
+        state.set_checked(find(&dom, "value", "l").unwrap(), true);
+        let button = find(&dom, "name", "go").unwrap();
+        assert!(is_submit_button(button));
+        let submission = submit(form, &state, Some(button), "https://example.com/").unwrap();
+        assert_eq!(submission.url, "https://example.com/search?q=a+b%26c&safe=on&size=l&lang=fr&go=1");
+    }
+
+    #[test]
+    fn test_post_form_body() {
+        let dom = html_parser::parse(
+            "<form name=\"login\" method=\"POST\" action=\"https://example.com/login\">\
+             <input name=\"user\" value=\"ada\"><input type=\"password\" name=\"pass\" value=\"x y\">\
+             <input name=\"skip\" value=\"1\" disabled><textarea name=\"note\">hi</textarea></form>"
+        );
+        let form = find(&dom, "name", "login").unwrap();
+        let submission = submit(form, &FormState::default(), None, "https://example.com/").unwrap();
+        assert_eq!(submission.url, "https://example.com/login");
+        let body = submission.body.unwrap();
+        assert_eq!(body.method, "POST");
+        assert_eq!(body.content_type, "application/x-www-form-urlencoded");
+        assert_eq!(String::from_utf8(body.data).unwrap(), "user=ada&pass=x+y&note=hi");
+        assert_eq!(submission.login, Some(("ada".to_string(), "x y".to_string())));
+    }
+}
@@ -0,0 +1,362 @@
+// <iframe>: nested documents loaded and rendered inside their parent page, subject to sandbox and embedding policy
+use std::sync::{Arc, Mutex};
+use eframe::egui;
+use crate::engine::WebPage;
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::engine::resource_loader::{self, ResourceKind, ResourceLoadHandle, ResourceLoader};
+use crate::networking::HttpResponse;
+use crate::networking::cookie_manager::CookieManager;
+use crate::networking::image_loader::ImageCache;
+use crate::networking::manual_client::{FetchContext, ManualFetchResult, ManualHttpClient, RequestBody};
+use crate::extensions::web_request::ResourceType;
+use crate::networking::redirect::ReferrerPolicy;
+use crate::networking::tracking::{self, TrackingProtection};
+use crate::storage::Preferences;
+
+/// Frames nested deeper than this are left empty, so a page framing itself can't recurse forever
+pub const MAX_FRAME_DEPTH: usize = 4;
+
+/// What a `sandbox` attribute still lets the framed document do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sandbox {
+    pub allow_scripts: bool,
+    /// Without this the frame gets an opaque origin: no cookies, and never same-origin with anything
+    pub allow_same_origin: bool,
+    pub allow_forms: bool,
+}
+
+impl Sandbox {
+    /// Restrictions of an iframe; without the attribute nothing is restricted
+    pub fn from_attribute(value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self { allow_scripts: true, allow_same_origin: true, allow_forms: true };
+        };
+        let allows = |token: &str| value.split_ascii_whitespace().any(|t| t.eq_ignore_ascii_case(token));
+        Self {
+            allow_scripts: allows("allow-scripts"),
+            allow_same_origin: allows("allow-same-origin"),
+            allow_forms: allows("allow-forms"),
+        }
+    }
+}
+
+fn header<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    response.headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn same_origin(a: &url::Url, b: &url::Url) -> bool {
+    a.origin() == b.origin()
+}
+
+// Whether one frame-ancestors source expression matches an ancestor
+fn source_matches(source: &str, ancestor: &url::Url, frame: &url::Url) -> bool {
+    match source.to_ascii_lowercase().as_str() {
+        "*" => true,
+        "'self'" => same_origin(ancestor, frame),
+        source if source.ends_with(':') && !source.contains('/') => ancestor.scheme() == source.trim_end_matches(':'),
+        source => {
+            let (scheme, rest) = match source.split_once("://") {
+                Some((scheme, rest)) => (Some(scheme), rest),
+                None => (None, source),
+            };
+            let authority = rest.split('/').next().unwrap_or_default();
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            };
+            let Some(ancestor_host) = ancestor.host_str() else { return false };
+            let host_matches = match host.strip_prefix("*.") {
+                Some(parent) => ancestor_host.ends_with(&format!(".{}", parent)),
+                None => ancestor_host.eq_ignore_ascii_case(host),
+            };
+            // A host without a scheme matches the framed document's scheme, or its secure upgrade
+            let scheme_matches = match scheme {
+                Some(scheme) => ancestor.scheme() == scheme,
+                None => ancestor.scheme() == frame.scheme() || ancestor.scheme() == "https",
+            };
+            let port_matches = match port {
+                None => ancestor.port().is_none(),
+                Some("*") => true,
+                Some(port) => ancestor.port_or_known_default().is_some_and(|p| p.to_string() == port),
+            };
+            host_matches && scheme_matches && port_matches
+        }
+    }
+}
+
+/// Whether a document served with `response` from `frame_url` agrees to be framed by `ancestors`, top first.
+/// CSP frame-ancestors wins over X-Frame-Options when both are sent.
+pub fn embedding_allowed(response: &HttpResponse, frame_url: &str, ancestors: &[String]) -> bool {
+    let Ok(frame) = url::Url::parse(frame_url) else { return false };
+    let ancestors: Vec<url::Url> = ancestors.iter().filter_map(|url| url::Url::parse(url).ok()).collect();
+
+    let policies: Vec<Vec<&str>> = header(response, "Content-Security-Policy")
+        .into_iter()
+        .flat_map(|csp| csp.split([',', '\n']))
+        .flat_map(|policy| policy.split(';'))
+        .filter_map(|directive| {
+            let mut tokens = directive.split_ascii_whitespace();
+            tokens.next()
+                .filter(|name| name.eq_ignore_ascii_case("frame-ancestors"))
+                .map(|_| tokens.collect())
+        })
+        .collect();
+    if !policies.is_empty() {
+        return policies.iter().all(|sources| {
+            ancestors.iter().all(|ancestor| sources.iter().any(|source| source_matches(source, ancestor, &frame)))
+        });
+    }
+
+    match header(response, "X-Frame-Options").map(|value| value.trim().to_ascii_lowercase()) {
+        Some(value) if value == "deny" => false,
+        Some(value) if value == "sameorigin" => ancestors.iter().all(|ancestor| same_origin(ancestor, &frame)),
+        _ => true,
+    }
+}
+
+/// A loaded or loading `<iframe>`
+pub struct Frame {
+    url: String,
+    sandbox: Sandbox,
+    /// URLs of the documents this frame is nested in, top first
+    ancestors: Vec<String>,
+    /// The parent's jar, which cookies of sites other than the top-level one are kept in partitions of
+    cookies: Option<Arc<Mutex<CookieManager>>>,
+    /// Top-level site of the tab the frame is in
+    cookie_partition: Option<String>,
+    /// Policy of the embedding document, or the iframe's referrerpolicy, for the Referer the frame is requested with
+    referrer_policy: ReferrerPolicy,
+    /// The top-level page's tracking protection, which the frame's own requests go through too
+    tracking: Option<TrackingProtection>,
+    fetch: Option<TaskHandle<anyhow::Result<ManualFetchResult>>>,
+    page: Option<Box<WebPage>>,
+    resources: Option<ResourceLoadHandle>,
+    error: Option<String>,
+}
+
+impl Frame {
+    /// Start loading `url` into a frame of the document at the end of `ancestors`
+    pub fn open(url: &str, sandbox: Sandbox, ancestors: Vec<String>, parent_cookies: Option<Arc<Mutex<CookieManager>>>, referrer_policy: ReferrerPolicy, tracking: Option<TrackingProtection>) -> Self {
+        // Cross-site frames keep their cookies across loads in a partition of the shared jar for the top-level site
+        let cookies = parent_cookies.filter(|_| sandbox.allow_same_origin);
+        let cookie_partition = ancestors.first().and_then(|top| tracking::site_of(top));
+        let mut frame = Self { url: url.to_string(), sandbox, ancestors, cookies, cookie_partition, referrer_policy, tracking, fetch: None, page: None, resources: None, error: None };
+        frame.navigate(url, None);
+        frame
+    }
+
+    /// A frame showing its `srcdoc` markup, which belongs to the parent's origin
+    pub fn from_srcdoc(html: &str, sandbox: Sandbox, ancestors: Vec<String>, parent_cookies: Option<Arc<Mutex<CookieManager>>>, referrer_policy: ReferrerPolicy, tracking: Option<TrackingProtection>) -> Self {
+        let cookies = parent_cookies.filter(|_| sandbox.allow_same_origin);
+        let cookie_partition = ancestors.first().and_then(|top| tracking::site_of(top));
+        let mut frame = Self { url: "about:srcdoc".to_string(), sandbox, ancestors, cookies, cookie_partition, referrer_policy, tracking, fetch: None, page: None, resources: None, error: None };
+        let base = frame.ancestors.last().cloned().unwrap_or_default();
+        frame.show_document(&base, html, Some(referrer_policy));
+        frame
+    }
+
+    fn navigate(&mut self, url: &str, body: Option<RequestBody>) {
+        self.url = url.to_string();
+        self.resources = None;
+        self.error = None;
+        let client = match ManualHttpClient::new() {
+            Ok(client) => client,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        let context = FetchContext {
+            cookies: self.cookies.clone(),
+            referrer: self.ancestors.last().cloned(),
+            referrer_policy: self.referrer_policy,
+            body,
+            tracking: self.tracking.clone(),
+            cookie_partition: self.cookie_partition.clone(),
+            resource_type: ResourceType::SubFrame,
+            ..FetchContext::default()
+        };
+        let url = url.to_string();
+        self.fetch = Some(Executor::shared().spawn(TaskKind::Network, async move {
+            client.fetch_with_context(&url, &context).await
+        }));
+    }
+
+    fn show_document(&mut self, url: &str, html: &str, referrer_policy: Option<ReferrerPolicy>) {
+        let allow_scripts = self.sandbox.allow_scripts && Preferences::current().javascript.allows(url);
+        let mut page = WebPage::from_html(html, None);
+        page.set_scripts_enabled(allow_scripts);
+        page.frame_ancestors = self.ancestors.clone();
+        page.cookie_jar = self.cookies.clone();
+        page.tracking = self.tracking.clone();
+        page.cookie_partition = self.cookie_partition.clone();
+        page.set_document_url(url);
+        page.set_referrer_policy(referrer_policy);
+        page.load_inline_styles(url);
+        page.refresh_user_styles();
+        page.run_inline_scripts();
+
+        let mut requests = resource_loader::discover_resources(&page.dom, url, Preferences::current().image_loading);
+        let images_allowed = Preferences::current().images.allowed(url);
+        requests.retain(|request| match request.kind {
+            ResourceKind::Script => allow_scripts,
+            ResourceKind::Image => images_allowed,
+            _ => true,
+        });
+        requests.extend(page.take_font_requests());
+        if !requests.is_empty() {
+            if let Ok(client) = ManualHttpClient::new() {
+                let context = FetchContext {
+                    cookies: self.cookies.clone(),
+                    referrer: Some(url.to_string()),
+                    referrer_policy: page.referrer_policy(),
+                    tracking: self.tracking.clone(),
+                    cookie_partition: self.cookie_partition.clone(),
+                    ..FetchContext::default()
+                };
+                self.resources = Some(ResourceLoader::new(client, ImageCache::new()).start(requests, context));
+            }
+        }
+        self.page = Some(Box::new(page));
+    }
+
+    /// Take in whatever finished loading since the last frame, and follow the framed page's form submissions
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        if let Some(fetch) = self.fetch.as_mut() {
+            match fetch.try_take() {
+                None => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+                Some(result) => {
+                    self.fetch = None;
+                    match result.map_err(|e| e.to_string()).and_then(|fetched| fetched.map_err(|e| e.to_string())) {
+                        Ok(fetched) if !embedding_allowed(&fetched.response, &fetched.final_url, &self.ancestors) => {
+                            let host = url::Url::parse(&fetched.final_url).ok()
+                                .and_then(|url| url.host_str().map(str::to_string))
+                                .unwrap_or(fetched.final_url);
+                            self.page = None;
+                            self.error = Some(format!("{} refused to be shown in a frame", host));
+                        }
+                        Ok(fetched) => match fetched.response.body_as_string() {
+                            Ok(html) => {
+                                self.url = fetched.final_url.clone();
+                                let referrer_policy = header(&fetched.response, "Referrer-Policy").and_then(ReferrerPolicy::from_header);
+                                self.show_document(&fetched.final_url, &html, referrer_policy);
+                            }
+                            Err(e) => self.error = Some(e.to_string()),
+                        },
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+            }
+        }
+
+        let Some(page) = self.page.as_mut() else { return };
+        if let Some(resources) = self.resources.as_mut() {
+            resources.load_deferred(page.take_visible_images());
+            let finished = resources.progress().is_finished();
+            for resource in resources.take_ready() {
+                if let Err(e) = page.apply_resource(resource) {
+                    log::warn!("Frame resource {}", e);
+                }
+            }
+            let fonts = page.take_font_requests();
+            if !fonts.is_empty() {
+                resources.request(fonts);
+            } else if finished && !resources.has_deferred() {
+                self.resources = None;
+            } else if !finished {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            }
+        }
+
+        if page.tick_animations(ctx.input(|i| i.time)) {
+            ctx.request_repaint();
+        }
+        if let Some(submission) = page.take_form_submission() {
+            if self.sandbox.allow_forms {
+                self.navigate(&submission.url, submission.body);
+            } else {
+                log::info!("Blocked form submission in sandboxed frame {}", self.url);
+            }
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn page(&self) -> Option<&WebPage> {
+        self.page.as_deref()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.fetch.is_some()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn response(headers: &[(&str, &str)]) -> HttpResponse {
+        let headers: HashMap<String, String> = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        HttpResponse::new(200, "OK".to_string(), headers, Vec::new())
+    }
+
+    #[test]
+    fn test_sandbox_flags() {
+        assert!(Sandbox::from_attribute(None).allow_scripts);
+        let sandbox = Sandbox::from_attribute(Some(" allow-forms  ALLOW-SCRIPTS"));
+        assert_eq!(sandbox, Sandbox { allow_scripts: true, allow_same_origin: false, allow_forms: true });
+        assert_eq!(Sandbox::from_attribute(Some("")), Sandbox { allow_scripts: false, allow_same_origin: false, allow_forms: false });
+    }
+
+    #[test]
+    fn test_frames_partition_the_shared_jar() {
+        let jar = Arc::new(Mutex::new(CookieManager::new()));
+        let ancestors = vec!["https://news.example.com/story".to_string()];
+        let frame = Frame::from_srcdoc("<p>ad</p>", Sandbox::from_attribute(None), ancestors.clone(), Some(jar.clone()), ReferrerPolicy::default(), None);
+        assert!(frame.cookies.as_ref().is_some_and(|cookies| Arc::ptr_eq(cookies, &jar)));
+        assert_eq!(frame.cookie_partition.as_deref(), Some("example.com"));
+
+        // A cross-site host's cookies go to its partition for the top-level site, which later loads find again
+        let partition = frame.cookie_partition.as_deref();
+        let key = tracking::cookie_domain(None, partition, "https://widget.other.org/", "widget.other.org", 1);
+        assert_eq!(key, crate::networking::cookie_manager::partition_key("widget.other.org", "example.com"));
+        assert_eq!(tracking::cookie_domain(None, partition, "https://cdn.example.com/", "cdn.example.com", 1), "cdn.example.com");
+
+        let sandboxed = Frame::from_srcdoc("<p>ad</p>", Sandbox::from_attribute(Some("")), ancestors, Some(jar), ReferrerPolicy::default(), None);
+        assert!(sandboxed.cookies.is_none());
+    }
+
+    #[test]
+    fn test_embedding_policy() {
+        let top = vec!["https://example.com/page".to_string()];
+        let other = vec!["https://evil.test/".to_string()];
+        let frame = "https://example.com/widget";
+
+        assert!(embedding_allowed(&response(&[]), frame, &other));
+        assert!(!embedding_allowed(&response(&[("x-frame-options", "DENY")]), frame, &top));
+        let same_origin = response(&[("x-frame-options", "sameorigin")]);
+        assert!(embedding_allowed(&same_origin, frame, &top));
+        assert!(!embedding_allowed(&same_origin, frame, &other));
+
+        // frame-ancestors overrides X-Frame-Options
+        let csp = response(&[("content-security-policy", "default-src 'self'; frame-ancestors 'self' *.partner.org"), ("x-frame-options", "DENY")]);
+        assert!(embedding_allowed(&csp, frame, &top));
+        assert!(embedding_allowed(&csp, frame, &["https://app.partner.org/".to_string()]));
+        assert!(!embedding_allowed(&csp, frame, &other));
+        assert!(!embedding_allowed(&response(&[("content-security-policy", "frame-ancestors 'none'")]), frame, &top));
+        assert!(embedding_allowed(&response(&[("content-security-policy", "frame-ancestors https:")]), frame, &other));
+
+        // Every ancestor has to be allowed, not just the parent
+        let nested = vec!["https://evil.test/".to_string(), "https://example.com/".to_string()];
+        assert!(!embedding_allowed(&same_origin, frame, &nested));
+    }
+}
@@ -0,0 +1,261 @@
+// Maps screen positions back to the DOM nodes the widget renderer drew there
+use eframe::egui;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use crate::engine::dom::DOMNode;
+
+// Characters of text content kept on a hit target
+const TEXT_SNIPPET_LEN: usize = 120;
+// Added to the depth of boxes painted above the flow, so they win over whatever they cover
+const OVERLAY_DEPTH: usize = 10_000;
+
+/// Screen area covered by one rendered element during the last frame
+#[derive(Debug, Clone, Copy)]
+struct HitBox {
+    rect: egui::Rect,
+    // Only ever compared against nodes of the same document, never dereferenced
+    node: *const DOMNode,
+    depth: usize,
+}
+
+/// Boxes recorded while a subtree rendered, with depths relative to it, to replay with its retained painting
+#[derive(Debug, Clone, Default)]
+pub struct RecordedBoxes(Vec<HitBox>);
+
+/// Collects element boxes while the page renders, so a position can be traced back to its node
+#[derive(Debug, Default)]
+pub struct HitTestRecorder {
+    boxes: RefCell<Vec<HitBox>>,
+    depth: Cell<usize>,
+}
+
+impl HitTestRecorder {
+    /// Forget the boxes of the previous frame
+    pub fn clear(&self) {
+        self.boxes.borrow_mut().clear();
+        self.depth.set(0);
+    }
+
+    /// Render `node` through `render`, recording the area it ends up covering
+    pub fn record(&self, node: &DOMNode, render: impl FnOnce() -> egui::Rect) {
+        let depth = self.depth.get();
+        self.depth.set(depth + 1);
+        let rect = render();
+        self.depth.set(depth);
+
+        if rect.is_positive() {
+            self.boxes.borrow_mut().push(HitBox { rect, node, depth });
+        }
+    }
+
+    /// Render boxes painted on top of the rest of the page, such as fixed and sticky elements
+    pub fn overlay<R>(&self, render: impl FnOnce() -> R) -> R {
+        let depth = self.depth.get();
+        self.depth.set(depth + OVERLAY_DEPTH);
+        let result = render();
+        self.depth.set(depth);
+        result
+    }
+
+    /// Where the boxes recorded from now on start, for `since`
+    pub fn mark(&self) -> usize {
+        self.boxes.borrow().len()
+    }
+
+    /// The boxes recorded since `mark`, relative to the current depth
+    pub fn since(&self, mark: usize) -> RecordedBoxes {
+        let depth = self.depth.get();
+        RecordedBoxes(self.boxes.borrow().get(mark..).unwrap_or_default().iter()
+            .map(|b| HitBox { depth: b.depth.saturating_sub(depth), ..*b })
+            .collect())
+    }
+
+    /// Record `boxes` again at the current depth, moved by `offset` to where their subtree is drawn now
+    pub fn replay(&self, boxes: &RecordedBoxes, offset: egui::Vec2) {
+        let depth = self.depth.get();
+        self.boxes.borrow_mut().extend(boxes.0.iter().map(|b| HitBox { rect: b.rect.translate(offset), node: b.node, depth: b.depth + depth }));
+    }
+
+    /// Where `node` was drawn, if it has been recorded
+    pub fn rect_of(&self, node: *const DOMNode) -> Option<egui::Rect> {
+        self.boxes.borrow().iter().rev().find(|b| b.node == node).map(|b| b.rect)
+    }
+
+    /// Deepest recorded node under `pos`, resolved against the document it was rendered from
+    pub fn hit_test(&self, dom: &DOMNode, pos: egui::Pos2) -> Option<HitTarget> {
+        let hit = self.boxes.borrow().iter()
+            .filter(|b| b.rect.contains(pos))
+            .max_by_key(|b| b.depth)
+            .copied()?;
+        HitTarget::resolve(dom, hit.node, hit.rect)
+    }
+
+    /// Every recorded node under `pos`: the deepest one and the ancestors that were drawn around it
+    pub fn nodes_at(&self, pos: egui::Pos2) -> HashSet<*const DOMNode> {
+        self.boxes.borrow().iter()
+            .filter(|b| b.rect.contains(pos))
+            .map(|b| b.node)
+            .collect()
+    }
+}
+
+/// The DOM element found at a screen position
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitTarget {
+    /// Child indices from the document root down to the element
+    pub path: Vec<usize>,
+    pub tag_name: String,
+    pub attributes: HashMap<String, String>,
+    pub text: String,
+    pub rect: egui::Rect,
+    /// href of the element or its nearest enclosing link, as written in the document
+    pub link: Option<String>,
+}
+
+impl HitTarget {
+    fn resolve(dom: &DOMNode, node: *const DOMNode, rect: egui::Rect) -> Option<Self> {
+        let path = dom.path_to(node)?;
+
+        // Walk up from the element itself so the nearest enclosing <a href> wins
+        let link = (0..=path.len()).rev()
+            .filter_map(|len| dom.node_at(&path[..len]))
+            .filter(|node| node.tag_name().map(String::as_str) == Some("a"))
+            .find_map(|node| node.get_attribute("href"))
+            .map(|href| href.trim().to_string());
+
+        let current = dom.node_at(&path)?;
+        let DOMNode::Element { tag_name, attributes, .. } = current else { return None };
+        let mut text = current.collect_text().split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some((cut, _)) = text.char_indices().nth(TEXT_SNIPPET_LEN) {
+            text.truncate(cut);
+            text.push('…');
+        }
+
+        Some(Self {
+            path,
+            tag_name: tag_name.clone(),
+            attributes: attributes.clone(),
+            text,
+            rect,
+            link,
+        })
+    }
+
+    pub fn image_src(&self) -> Option<&str> {
+        (self.tag_name == "img")
+            .then(|| self.attributes.get("src").map(|s| s.trim()))
+            .flatten()
+    }
+
+    /// Short CSS-like description, e.g. `div#main.card`
+    pub fn selector(&self) -> String {
+        let mut selector = self.tag_name.clone();
+        if let Some(id) = self.attributes.get("id").filter(|id| !id.is_empty()) {
+            selector.push('#');
+            selector.push_str(id);
+        }
+        if let Some(class) = self.attributes.get("class") {
+            for name in class.split_whitespace() {
+                selector.push('.');
+                selector.push_str(name);
+            }
+        }
+        selector
+    }
+
+    /// Multi-line summary for the developer console
+    pub fn describe(&self) -> String {
+        let mut attributes: Vec<_> = self.attributes.iter().collect();
+        attributes.sort();
+
+        let mut lines = vec![
+            format!("<{}> {}", self.tag_name, self.selector()),
+            format!("path: {:?}", self.path),
+            format!(
+                "box: {:.0}×{:.0} at ({:.0}, {:.0})",
+                self.rect.width(), self.rect.height(), self.rect.left(), self.rect.top()
+            ),
+        ];
+        lines.extend(attributes.into_iter().map(|(name, value)| format!("  {}=\"{}\"", name, value)));
+        if !self.text.is_empty() {
+            lines.push(format!("text: {}", self.text));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tag: &str, attributes: &[(&str, &str)], children: Vec<DOMNode>) -> DOMNode {
+        let mut node = DOMNode::new_element(tag.to_string());
+        for (name, value) in attributes {
+            node.set_attribute(name.to_string(), value.to_string());
+        }
+        for child in children {
+            node.add_child(child);
+        }
+        node
+    }
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> egui::Rect {
+        egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(w, h))
+    }
+
+    #[test]
+    fn test_deepest_box_wins() {
+        let dom = element("body", &[], vec![
+            element("p", &[], vec![DOMNode::new_text("intro".to_string())]),
+            element("a", &[("href", "/docs")], vec![
+                element("span", &[("class", "label big")], vec![DOMNode::new_text("Read   the docs".to_string())]),
+            ]),
+        ]);
+        let DOMNode::Element { children, .. } = &dom else { unreachable!() };
+        let DOMNode::Element { children: link_children, .. } = &children[1] else { unreachable!() };
+
+        // Same nesting the renderer produces: children finish before their parent
+        let recorder = HitTestRecorder::default();
+        recorder.record(&dom, || {
+            recorder.record(&children[0], || rect(0.0, 0.0, 200.0, 20.0));
+            recorder.record(&children[1], || {
+                recorder.record(&link_children[0], || rect(0.0, 20.0, 80.0, 20.0));
+                rect(0.0, 20.0, 200.0, 20.0)
+            });
+            rect(0.0, 0.0, 200.0, 40.0)
+        });
+
+        let target = recorder.hit_test(&dom, egui::pos2(10.0, 30.0)).unwrap();
+        assert_eq!(target.path, vec![1, 0]);
+        assert_eq!(target.selector(), "span.label.big");
+        assert_eq!(target.text, "Read the docs");
+        assert_eq!(target.link.as_deref(), Some("/docs"));
+
+        let target = recorder.hit_test(&dom, egui::pos2(150.0, 30.0)).unwrap();
+        assert_eq!(target.tag_name, "a");
+
+        let target = recorder.hit_test(&dom, egui::pos2(10.0, 5.0)).unwrap();
+        assert_eq!(target.path, vec![0]);
+        assert_eq!(target.link, None);
+
+        // A box painted over the flow, like a fixed header, wins even over deeper ones
+        recorder.overlay(|| recorder.record(&children[0], || rect(0.0, 25.0, 200.0, 10.0)));
+        assert_eq!(recorder.hit_test(&dom, egui::pos2(10.0, 30.0)).unwrap().path, vec![0]);
+        assert_eq!(recorder.rect_of(&children[0]), Some(rect(0.0, 25.0, 200.0, 10.0)));
+
+        assert!(recorder.hit_test(&dom, egui::pos2(500.0, 5.0)).is_none());
+        recorder.clear();
+        assert!(recorder.hit_test(&dom, egui::pos2(10.0, 5.0)).is_none());
+    }
+
+    #[test]
+    fn test_boxes_from_another_document_are_ignored() {
+        let rendered = element("img", &[("src", "cat.png")], vec![]);
+        let recorder = HitTestRecorder::default();
+        recorder.record(&rendered, || rect(0.0, 0.0, 10.0, 10.0));
+
+        let other = element("img", &[("src", "cat.png")], vec![]);
+        assert!(recorder.hit_test(&other, egui::pos2(5.0, 5.0)).is_none());
+        assert_eq!(recorder.hit_test(&rendered, egui::pos2(5.0, 5.0)).unwrap().image_src(), Some("cat.png"));
+    }
+}
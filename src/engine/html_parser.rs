@@ -1,4 +1,4 @@
-use crate::engine::dom::DOMNode;
+use crate::engine::dom::{DOMNode, RAW_TEXT_ELEMENTS, VOID_ELEMENTS};
 use crate::js::JSEngine;
 use std::collections::HashMap;
 
@@ -12,330 +12,330 @@ pub fn parse_with_js(html: &str, js_engine: &mut Option<JSEngine>) -> DOMNode {
     parser.parse_with_javascript(js_engine)
 }
 
+// Elements whose text can't contain markup but does have its entities decoded
+const RCDATA_ELEMENTS: [&str; 2] = ["textarea", "title"];
+
+// Start tags that end an open paragraph, since block content can't sit in one
+const CLOSES_PARAGRAPH: [&str; 36] = [
+    "address", "article", "aside", "blockquote", "center", "details", "dialog", "dir", "div", "dl", "dd", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup",
+    "hr", "li", "main", "menu", "nav", "ol", "p", "pre", "section", "table", "ul",
+];
+
+// Elements an implied or stray end tag doesn't reach past
+const DEFAULT_SCOPE: [&str; 9] = ["html", "table", "td", "th", "caption", "marquee", "object", "applet", "template"];
+const BUTTON_SCOPE: [&str; 10] = ["html", "table", "td", "th", "caption", "marquee", "object", "applet", "template", "button"];
+const LIST_SCOPE: [&str; 12] = ["html", "table", "td", "th", "caption", "marquee", "object", "applet", "template", "ul", "ol", "menu"];
+
+// Deepest an element may be nested, as in Chromium; deeper elements become siblings of the innermost one, so
+// scope checks stay cheap and the passes that walk the tree can't run out of stack
+const MAX_DEPTH: usize = 512;
+
+// Elements where whitespace is content, and whose first newline is dropped
+const PRESERVES_WHITESPACE: [&str; 3] = ["pre", "listing", "textarea"];
+
 pub struct HTMLParser {
-    input: String,
     chars: Vec<char>,
     position: usize,
+    // Elements started and not yet ended, innermost last. The first is the root, which is never ended.
+    open: Vec<DOMNode>,
 }
 
 impl HTMLParser {
     fn new(input: String) -> Self {
-        let chars: Vec<char> = input.chars().collect();
         HTMLParser {
-            input,
-            chars,
+            chars: input.chars().collect(),
             position: 0,
+            open: Vec::new(),
         }
     }
     
     fn parse(&mut self) -> DOMNode {
-        let mut root = DOMNode::new_element("html".to_string());
-        
-        while !self.at_end() {
-            self.skip_whitespace();
-            if self.at_end() {
-                break;
-            }
-            
-            if let Some(node) = self.parse_node() {
-                root.add_child(node);
-            }
-        }
-        
-        root
+        self.build(&mut None, false)
     }
     
     fn parse_with_javascript(&mut self, js_engine: &mut Option<JSEngine>) -> DOMNode {
-        let mut root = DOMNode::new_element("html".to_string());
+        self.build(js_engine, true)
+    }
+    
+    // Tokenize and build the tree in one pass, keeping open elements on a stack rather than recursing, so deep
+    // or unbalanced markup can't run out of stack
+    fn build(&mut self, js_engine: &mut Option<JSEngine>, run_scripts: bool) -> DOMNode {
+        self.open = vec![DOMNode::new_element("html".to_string())];
         
         while !self.at_end() {
-            self.skip_whitespace();
-            if self.at_end() {
-                break;
-            }
-            
-            if let Some(node) = self.parse_node_with_js(js_engine) {
-                root.add_child(node);
+            if self.peek() == '<' {
+                self.parse_markup(js_engine, run_scripts);
+            } else {
+                self.parse_text();
             }
         }
         
-        root
-    }
-    
-    fn parse_node(&mut self) -> Option<DOMNode> {
-        if self.peek() == '<' {
-            self.parse_element()
-        } else {
-            self.parse_text()
+        // Whatever is still open ends with the document
+        while self.open.len() > 1 {
+            self.close_current();
         }
+        self.open.pop().expect("the root is never closed")
     }
     
-    fn parse_node_with_js(&mut self, js_engine: &mut Option<JSEngine>) -> Option<DOMNode> {
-        if self.peek() == '<' {
-            self.parse_element_with_js(js_engine)
-        } else {
-            self.parse_text()
+    fn parse_markup(&mut self, js_engine: &mut Option<JSEngine>, run_scripts: bool) {
+        match self.peek_ahead(1) {
+            '!' if self.looking_at(0, "<!--") => self.skip_comment(),
+            // DOCTYPE, CDATA sections and processing instructions are dropped
+            '!' | '?' => self.skip_past('>'),
+            '/' if self.peek_ahead(2).is_ascii_alphabetic() => self.parse_end_tag(),
+            // "</>" and end tags without a name
+            '/' => self.skip_past('>'),
+            c if c.is_ascii_alphabetic() => self.parse_start_tag(js_engine, run_scripts),
+            // A '<' that starts no tag is just text, as in "a < b"
+            _ => {
+                self.position += 1;
+                self.insert_text("<");
+            }
         }
     }
     
-    fn parse_element(&mut self) -> Option<DOMNode> {
-        // Skip '<'
-        self.consume_char();
-        
-        // Handle comments and DOCTYPE
-        if self.input[self.position..].starts_with("!--") {
-            return self.parse_comment();
-        }
-        
-        if self.input[self.position..].to_lowercase().starts_with("!doctype") {
-            return self.parse_doctype();
+    fn parse_start_tag(&mut self, js_engine: &mut Option<JSEngine>, run_scripts: bool) {
+        self.position += 1; // '<'
+        let name = self.parse_tag_name();
+        // SVG and MathML keep their camelCase names and may close themselves with "/>"
+        let foreign = self.in_foreign_content() || name.eq_ignore_ascii_case("svg") || name.eq_ignore_ascii_case("math");
+        let tag_name = match name.to_ascii_lowercase() {
+            _ if foreign => name,
+            tag if tag == "image" => "img".to_string(),
+            tag => tag,
+        };
+        // A tag cut off by the end of the document is dropped
+        let Some((attributes, self_closing)) = self.parse_attributes(!foreign) else {
+            return;
+        };
+        
+        if !foreign {
+            self.close_implied_by(&tag_name);
         }
         
-        // Check for closing tag
-        let is_closing = self.peek() == '/';
-        if is_closing {
-            self.consume_char();
-        }
-        
-        let tag_name = self.parse_tag_name();
-        
-        if is_closing {
-            // This is a closing tag, skip it for now
-            self.skip_until('>');
-            self.consume_char(); // Skip '>'
-            return None;
-        }
-        
-        let attributes = self.parse_attributes();
-        
-        self.skip_whitespace();
-        
-        // Check for self-closing tag
-        let is_self_closing = self.peek() == '/' || 
-                             matches!(tag_name.to_lowercase().as_str(), 
-                                     "img" | "br" | "hr" | "input" | "meta" | "link");
-        
-        if is_self_closing && self.peek() == '/' {
-            self.consume_char(); // Skip '/'
-        }
-        
-        if self.peek() != '>' {
-            return None;
-        }
-        self.consume_char(); // Skip '>'
-        
         let mut element = DOMNode::new_element(tag_name.clone());
-        
         for (name, value) in attributes {
             element.set_attribute(name, value);
         }
         
-        if !is_self_closing {
-            // Parse children until we find the closing tag
-            while !self.at_end() {
-                self.skip_whitespace();
-                
-                if self.peek() == '<' && self.peek_ahead(1) == '/' {
-                    // Found closing tag
-                    self.consume_char(); // '<'
-                    self.consume_char(); // '/'
-                    let closing_tag = self.parse_tag_name();
-                    
-                    if closing_tag.to_lowercase() == tag_name.to_lowercase() {
-                        self.skip_until('>');
-                        self.consume_char(); // '>'
-                        break;
-                    }
+        if (foreign && self_closing) || (!foreign && VOID_ELEMENTS.contains(&tag_name.as_str())) {
+            self.insert(element);
+        } else if !foreign && RAW_TEXT_ELEMENTS.contains(&tag_name.as_str()) {
+            // Script text is kept as is for the page to run once it's built, even where it contains '<'
+            let text = self.raw_text(&tag_name);
+            if tag_name == "script" && run_scripts {
+                self.execute_script(&mut element, &text, js_engine);
+            }
+            if !text.is_empty() {
+                element.add_child(DOMNode::new_text(text));
+            }
+            self.insert(element);
+        } else if !foreign && RCDATA_ELEMENTS.contains(&tag_name.as_str()) {
+            let text = self.raw_text(&tag_name);
+            let text = decode_entities(text.strip_prefix('\n').unwrap_or(&text), false);
+            if !text.is_empty() {
+                element.add_child(DOMNode::new_text(text));
+            }
+            self.insert(element);
+        } else {
+            if self.open.len() >= MAX_DEPTH {
+                self.close_current();
+            }
+            self.open.push(element);
+            if PRESERVES_WHITESPACE.contains(&tag_name.as_str()) && self.peek() == '\n' {
+                self.consume_char();
+            }
+        }
+    }
+    
+    fn execute_script(&self, element: &mut DOMNode, script_content: &str, js_engine: &mut Option<JSEngine>) {
+        if script_content.trim().is_empty() {
+            return;
+        }
+        // Execute JavaScript if engine is available
+        if let Some(ref mut engine) = js_engine {
+            match engine.execute(script_content) {
+                Ok(result) => {
+                    log::debug!("Script executed: {}", result);
+                    // Store script execution result as a comment for debugging
+                    element.add_child(DOMNode::new_comment(format!("Script result: {}", result)));
                 }
-                
-                if let Some(child) = self.parse_node() {
-                    element.add_child(child);
+                Err(e) => {
+                    log::warn!("Script execution error: {}", e);
+                    // Store error as a comment for debugging
+                    element.add_child(DOMNode::new_comment(format!("Script error: {}", e)));
                 }
             }
+        } else {
+            log::debug!("Script tag found but no JavaScript engine available");
         }
-        
-        Some(element)
     }
     
-    fn parse_element_with_js(&mut self, js_engine: &mut Option<JSEngine>) -> Option<DOMNode> {
-        // Skip '<'
-        self.consume_char();
-        
-        // Handle comments and DOCTYPE
-        if self.input[self.position..].starts_with("!--") {
-            return self.parse_comment();
-        }
-        
-        if self.input[self.position..].to_lowercase().starts_with("!doctype") {
-            return self.parse_doctype();
-        }
-        
-        // Check for closing tag
-        let is_closing = self.peek() == '/';
-        if is_closing {
-            self.consume_char();
-        }
-        
-        let tag_name = self.parse_tag_name();
-        
-        if is_closing {
-            // This is a closing tag, skip it for now
-            self.skip_until('>');
-            self.consume_char(); // Skip '>'
-            return None;
-        }
-        
-        let attributes = self.parse_attributes();
-        
-        self.skip_whitespace();
-        
-        // Check for self-closing tag
-        let is_self_closing = self.peek() == '/' || 
-                             matches!(tag_name.to_lowercase().as_str(), 
-                                     "img" | "br" | "hr" | "input" | "meta" | "link");
-        
-        if is_self_closing && self.peek() == '/' {
-            self.consume_char(); // Skip '/'
-        }
-        
-        if self.peek() != '>' {
-            return None;
+    // End the elements a start tag implies the end of, like an open <li> when another starts
+    fn close_implied_by(&mut self, tag_name: &str) {
+        match tag_name {
+            "li" => {
+                self.close_in_scope(&["li"], &LIST_SCOPE);
+            }
+            "dd" | "dt" => {
+                self.close_in_scope(&["dd", "dt"], &["html", "dl", "table", "td", "th"]);
+            }
+            "option" => self.close_if_current(&["option"]),
+            "optgroup" => {
+                self.close_if_current(&["option"]);
+                self.close_if_current(&["optgroup"]);
+            }
+            "tr" => {
+                self.close_in_scope(&["tr"], &["html", "table", "thead", "tbody", "tfoot"]);
+            }
+            "td" | "th" => {
+                self.close_in_scope(&["td", "th"], &["html", "table", "tr"]);
+            }
+            "thead" | "tbody" | "tfoot" => {
+                self.close_in_scope(&["thead", "tbody", "tfoot"], &["html", "table"]);
+            }
+            // Links and buttons don't nest
+            "a" | "button" => {
+                self.close_in_scope(&[tag_name], &DEFAULT_SCOPE);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.close_if_current(&["h1", "h2", "h3", "h4", "h5", "h6"]),
+            _ => {}
         }
-        self.consume_char(); // Skip '>'
-        
-        let mut element = DOMNode::new_element(tag_name.clone());
-        
-        for (name, value) in attributes {
-            element.set_attribute(name, value);
+        if CLOSES_PARAGRAPH.contains(&tag_name) {
+            self.close_in_scope(&["p"], &BUTTON_SCOPE);
         }
-        
-        // Special handling for script tags
-        if tag_name.to_lowercase() == "script" && !is_self_closing {
-            if let Some(script_content) = self.extract_script_content() {
-                // Execute JavaScript if engine is available
-                if let Some(ref mut engine) = js_engine {
-                    match engine.execute(&script_content) {
-                        Ok(result) => {
-                            println!("🚀 Script executed: {}", result);
-                            // Store script execution result as a comment for debugging
-                            let comment = DOMNode::new_comment(format!("Script result: {}", result));
-                            element.add_child(comment);
-                        }
-                        Err(e) => {
-                            println!("❌ Script execution error: {}", e);
-                            // Store error as a comment for debugging
-                            let error_comment = DOMNode::new_comment(format!("Script error: {}", e));
-                            element.add_child(error_comment);
-                        }
-                    }
-                } else {
-                    println!("⚠️ Script tag found but no JavaScript engine available");
+    }
+    
+    fn parse_end_tag(&mut self) {
+        self.position += 2; // "</"
+        let tag_name = self.parse_tag_name().to_ascii_lowercase();
+        // End tags can't have attributes; whatever is there is dropped
+        self.skip_past('>');
+        
+        match tag_name.as_str() {
+            // Read as <br>, as every browser does
+            "br" => self.insert(DOMNode::new_element("br".to_string())),
+            // A stray </p> makes an empty paragraph
+            "p" => {
+                if !self.close_in_scope(&["p"], &BUTTON_SCOPE) {
+                    self.insert(DOMNode::new_element("p".to_string()));
                 }
-                
-                // Also store the script content as text node for debugging
-                let script_text = DOMNode::new_text(script_content);
-                element.add_child(script_text);
             }
-        } else if !is_self_closing {
-            // Parse children until we find the closing tag
-            while !self.at_end() {
-                self.skip_whitespace();
-                
-                if self.peek() == '<' && self.peek_ahead(1) == '/' {
-                    // Found closing tag
-                    self.consume_char(); // '<'
-                    self.consume_char(); // '/'
-                    let closing_tag = self.parse_tag_name();
-                    
-                    if closing_tag.to_lowercase() == tag_name.to_lowercase() {
-                        self.skip_until('>');
-                        self.consume_char(); // '>'
-                        break;
-                    }
-                }
-                
-                if let Some(child) = self.parse_node_with_js(js_engine) {
-                    element.add_child(child);
-                }
+            "li" => {
+                self.close_in_scope(&["li"], &LIST_SCOPE);
+            }
+            // Table parts end the cells open in them
+            "table" => {
+                self.close_in_scope(&["table"], &["html"]);
+            }
+            "thead" | "tbody" | "tfoot" | "tr" | "td" | "th" => {
+                self.close_in_scope(&[tag_name.as_str()], &["html", "table"]);
+            }
+            // End tags for elements that aren't open are ignored
+            _ => {
+                self.close_in_scope(&[tag_name.as_str()], &DEFAULT_SCOPE);
             }
         }
-        
-        Some(element)
     }
     
-    fn extract_script_content(&mut self) -> Option<String> {
-        let mut script_content = String::new();
-        
-        // Read content until we find </script>
-        while !self.at_end() {
-            if self.peek() == '<' && self.peek_ahead(1) == '/' {
-                // Check if this is </script>
-                let saved_position = self.position;
-                self.consume_char(); // '<'
-                self.consume_char(); // '/'
-                let tag = self.parse_tag_name();
-                
-                if tag.to_lowercase() == "script" {
-                    // Found closing script tag, consume it
-                    self.skip_until('>');
-                    self.consume_char(); // '>'
-                    break;
-                } else {
-                    // Not a script closing tag, restore position and continue
-                    self.position = saved_position;
-                    script_content.push(self.consume_char());
-                }
-            } else {
-                script_content.push(self.consume_char());
-            }
+    // End open elements up to and including the innermost one named in `targets`, unless an element named in
+    // `boundaries` comes first. Returns whether one was found.
+    fn close_in_scope(&mut self, targets: &[&str], boundaries: &[&str]) -> bool {
+        let found = (1..self.open.len()).rev()
+            .map(|index| (index, self.tag_at(index)))
+            .find(|(_, tag)| targets.iter().chain(boundaries).any(|name| tag.eq_ignore_ascii_case(name)))
+            .filter(|(_, tag)| targets.iter().any(|name| tag.eq_ignore_ascii_case(name)))
+            .map(|(index, _)| index);
+        let Some(index) = found else {
+            return false;
+        };
+        while self.open.len() > index {
+            self.close_current();
+        }
+        true
+    }
+    
+    fn close_if_current(&mut self, tags: &[&str]) {
+        if self.open.len() > 1 && tags.contains(&self.tag_at(self.open.len() - 1)) {
+            self.close_current();
         }
-        
-        if script_content.trim().is_empty() {
-            None
-        } else {
-            Some(script_content.trim().to_string())
+    }
+    
+    fn close_current(&mut self) {
+        if let Some(node) = self.open.pop() {
+            self.insert(node);
         }
     }
     
-    fn parse_text(&mut self) -> Option<DOMNode> {
+    fn insert(&mut self, node: DOMNode) {
+        self.open.last_mut().expect("the root is never closed").add_child(node);
+    }
+    
+    // Text joins the text just before it, so "a < b" stays one node
+    fn insert_text(&mut self, text: &str) {
+        if let Some(DOMNode::Element { children, .. }) = self.open.last_mut() {
+            match children.last_mut() {
+                Some(DOMNode::Text(last)) => last.push_str(text),
+                _ => children.push(DOMNode::new_text(text.to_string())),
+            }
+        }
+    }
+    
+    fn tag_at(&self, index: usize) -> &str {
+        self.open[index].tag_name().map_or("", |tag| tag.as_str())
+    }
+    
+    fn in_foreign_content(&self) -> bool {
+        self.open.iter().skip(1).any(|node| node.tag_name().is_some_and(|tag| tag.eq_ignore_ascii_case("svg") || tag.eq_ignore_ascii_case("math")))
+    }
+    
+    fn parse_text(&mut self) {
         let mut text = String::new();
-        
         while !self.at_end() && self.peek() != '<' {
             text.push(self.consume_char());
         }
         
-        if text.trim().is_empty() {
-            None
-        } else {
-            Some(DOMNode::new_text(text))
+        // Whitespace between tags isn't content, except where it's kept or it separates words of a text
+        let follows_text = matches!(self.open.last(), Some(DOMNode::Element { children, .. }) if matches!(children.last(), Some(DOMNode::Text(_))));
+        let preserved = self.open.iter().any(|node| node.tag_name().is_some_and(|tag| PRESERVES_WHITESPACE.contains(&tag.as_str())));
+        if text.trim().is_empty() && !follows_text && !preserved {
+            return;
         }
+        self.insert_text(&decode_entities(&text, false));
     }
     
-    fn parse_comment(&mut self) -> Option<DOMNode> {
-        // Skip "!--"
-        self.position += 3;
-        
-        // Find "-->"
+    // Text up to the end tag of the raw text element `tag_name`, which is consumed too
+    fn raw_text(&mut self, tag_name: &str) -> String {
+        let mut text = String::new();
         while !self.at_end() {
-            if self.position + 2 < self.chars.len() 
-                && self.chars[self.position] == '-' 
-                && self.chars[self.position + 1] == '-' 
-                && self.chars[self.position + 2] == '>' {
-                self.position += 3;
+            let end_tag = self.looking_at(0, "</") && self.looking_at(2, tag_name) && {
+                let next = self.peek_ahead(2 + tag_name.len());
+                next == '>' || next == '/' || next == '\0' || next.is_whitespace()
+            };
+            if end_tag {
+                self.skip_past('>');
                 break;
             }
-            self.consume_char();
+            text.push(self.consume_char());
         }
-        
-        None // Comments are ignored
+        text
     }
     
-    fn parse_doctype(&mut self) -> Option<DOMNode> {
-        self.skip_until('>');
-        if !self.at_end() {
-            self.consume_char(); // Skip '>'
+    fn skip_comment(&mut self) {
+        self.position += 4; // "<!--"
+        // "<!-->" and "<!--->" end as soon as they start
+        if self.looking_at(0, ">") || self.looking_at(0, "->") {
+            self.skip_past('>');
+            return;
+        }
+        while !self.at_end() {
+            if self.looking_at(0, "-->") {
+                self.position += 3;
+                return;
+            }
+            self.consume_char();
         }
-        None // DOCTYPE is ignored
     }
     
     fn parse_tag_name(&mut self) -> String {
@@ -343,24 +343,38 @@ impl HTMLParser {
         
         while !self.at_end() {
             let ch = self.peek();
-            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
-                name.push(self.consume_char());
-            } else {
+            if ch.is_whitespace() || ch == '/' || ch == '>' {
                 break;
             }
+            name.push(self.consume_char());
         }
         
         name
     }
     
-    fn parse_attributes(&mut self) -> HashMap<String, String> {
+    // The attributes of a start tag and whether it ended with "/>", or None if the document ended first.
+    // Names are lowercased in HTML; the first of repeated attributes wins.
+    fn parse_attributes(&mut self, html: bool) -> Option<(HashMap<String, String>, bool)> {
         let mut attributes = HashMap::new();
         
         loop {
             self.skip_whitespace();
             
-            if self.at_end() || self.peek() == '>' || self.peek() == '/' {
-                break;
+            match self.peek() {
+                '\0' if self.at_end() => return None,
+                '>' => {
+                    self.consume_char();
+                    return Some((attributes, false));
+                }
+                '/' => {
+                    self.consume_char();
+                    if self.peek() == '>' {
+                        self.consume_char();
+                        return Some((attributes, true));
+                    }
+                    continue;
+                }
+                _ => {}
             }
             
             let name = self.parse_attribute_name();
@@ -371,25 +385,24 @@ impl HTMLParser {
             if self.peek() == '=' {
                 self.consume_char(); // Skip '='
                 self.skip_whitespace();
-                value = self.parse_attribute_value();
+                value = decode_entities(&self.parse_attribute_value(), true);
             }
             
-            attributes.insert(name, value);
+            let name = if html { name.to_ascii_lowercase() } else { name };
+            attributes.entry(name).or_insert(value);
         }
-        
-        attributes
     }
     
     fn parse_attribute_name(&mut self) -> String {
-        let mut name = String::new();
+        // A name may start with '=', which is then part of it
+        let mut name = String::from(self.consume_char());
         
         while !self.at_end() {
             let ch = self.peek();
-            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
-                name.push(self.consume_char());
-            } else {
+            if ch.is_whitespace() || ch == '/' || ch == '>' || ch == '=' {
                 break;
             }
+            name.push(self.consume_char());
         }
         
         name
@@ -412,12 +425,12 @@ impl HTMLParser {
             
             value
         } else {
-            // Unquoted value
+            // Unquoted values run to whitespace or the end of the tag, so they may contain '/' as URLs do
             let mut value = String::new();
             
             while !self.at_end() {
                 let ch = self.peek();
-                if ch.is_whitespace() || ch == '>' || ch == '/' {
+                if ch.is_whitespace() || ch == '>' {
                     break;
                 }
                 value.push(self.consume_char());
@@ -427,6 +440,14 @@ impl HTMLParser {
         }
     }
     
+    // Whether `text` comes `offset` characters ahead, ignoring ASCII case
+    fn looking_at(&self, offset: usize, text: &str) -> bool {
+        let start = self.position + offset;
+        let count = text.chars().count();
+        start + count <= self.chars.len()
+            && self.chars[start..start + count].iter().zip(text.chars()).all(|(a, b)| a.eq_ignore_ascii_case(&b))
+    }
+    
     fn peek(&self) -> char {
         self.chars.get(self.position).copied().unwrap_or('\0')
     }
@@ -437,7 +458,7 @@ impl HTMLParser {
     
     fn consume_char(&mut self) -> char {
         let ch = self.peek();
-        if ch != '\0' {
+        if !self.at_end() {
             self.position += 1;
         }
         ch
@@ -449,13 +470,161 @@ impl HTMLParser {
         }
     }
     
-    fn skip_until(&mut self, target: char) {
+    // Skip to just after the next `target`, or to the end
+    fn skip_past(&mut self, target: char) {
         while !self.at_end() && self.peek() != target {
             self.consume_char();
         }
+        self.consume_char();
     }
     
     fn at_end(&self) -> bool {
         self.position >= self.chars.len()
     }
-}
\ No newline at end of file
+}
+
+// Old entities that are recognised without their semicolon, as in "&copy 2024"
+const LEGACY_ENTITIES: [&str; 106] = [
+    "AElig", "AMP", "Aacute", "Acirc", "Agrave", "Aring", "Atilde", "Auml", "COPY", "Ccedil", "ETH", "Eacute",
+    "Ecirc", "Egrave", "Euml", "GT", "Iacute", "Icirc", "Igrave", "Iuml", "LT", "Ntilde", "Oacute", "Ocirc",
+    "Ograve", "Oslash", "Otilde", "Ouml", "QUOT", "REG", "THORN", "Uacute", "Ucirc", "Ugrave", "Uuml", "Yacute",
+    "aacute", "acirc", "acute", "aelig", "agrave", "amp", "aring", "atilde", "auml", "brvbar", "ccedil", "cedil",
+    "cent", "copy", "curren", "deg", "divide", "eacute", "ecirc", "egrave", "eth", "euml", "frac12", "frac14",
+    "frac34", "gt", "iacute", "icirc", "iexcl", "igrave", "iquest", "iuml", "laquo", "lt", "macr", "micro",
+    "middot", "nbsp", "not", "ntilde", "oacute", "ocirc", "ograve", "ordf", "ordm", "oslash", "otilde", "ouml",
+    "para", "plusmn", "pound", "quot", "raquo", "reg", "sect", "shy", "sup1", "sup2", "sup3", "szlig", "thorn",
+    "times", "uacute", "ucirc", "ugrave", "uml", "uuml", "yacute", "yen", "yuml",
+];
+
+// What numeric references to the C1 controls mean, since pages that use them meant Windows-1252
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Replace the character references in text or an attribute value with the characters they stand for
+pub fn decode_entities(text: &str, attribute: bool) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp + 1..];
+        match decode_reference(rest, attribute) {
+            Some((replacement, length)) => {
+                decoded.push_str(&replacement);
+                rest = &rest[length..];
+            }
+            None => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// The reference starting `text`, just past its '&': what it stands for and how many bytes it takes
+fn decode_reference(text: &str, attribute: bool) -> Option<(String, usize)> {
+    if let Some(number) = text.strip_prefix('#') {
+        let (digits, radix, prefix) = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16, 2),
+            None => (number, 10, 1),
+        };
+        let length = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+        if length == 0 {
+            return None;
+        }
+        let code = if length > 8 { u32::MAX } else { u32::from_str_radix(&digits[..length], radix).unwrap_or(u32::MAX) };
+        let semicolon = usize::from(digits[length..].starts_with(';'));
+        return Some((numeric_character(code).to_string(), prefix + length + semicolon));
+    }
+
+    let length = text.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(text.len());
+    let name = &text[..length];
+    if text[length..].starts_with(';') {
+        if let Some(value) = named_entity(name) {
+            return Some((value, length + 1));
+        }
+    }
+    let legacy = LEGACY_ENTITIES.iter().filter(|entity| name.starts_with(**entity)).max_by_key(|entity| entity.len())?;
+    // In attributes "&copy=1" is more likely part of a URL than a copyright sign
+    let next = text[legacy.len()..].chars().next();
+    if attribute && next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '=') {
+        return None;
+    }
+    Some((named_entity(legacy)?, legacy.len()))
+}
+
+fn named_entity(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    html_entities::decode_html_entities(&format!("&{};", name)).ok()
+}
+
+fn numeric_character(code: u32) -> char {
+    match code {
+        0x80..=0x9f => WINDOWS_1252[(code - 0x80) as usize],
+        0 => char::REPLACEMENT_CHARACTER,
+        code => char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nesting_depth_is_capped() {
+        let dom = parse(&"<div>".repeat(MAX_DEPTH * 4));
+        let mut depth = 0;
+        let mut node = &dom;
+        while let DOMNode::Element { children, .. } = node {
+            let Some(child) = children.last() else { break };
+            depth += 1;
+            node = child;
+        }
+        assert_eq!(depth, MAX_DEPTH - 1);
+        assert_eq!(dom.descendants().count(), MAX_DEPTH * 4 + 1);
+    }
+
+    #[test]
+    fn test_implied_end_tags() {
+        let dom = parse("<ul><li>one<li>two</ul><p>a<p>b<div>c</div><dl><dt>t<dd>d</dl><table><tr><td>1<td>2<tr><td>3</table>");
+        assert_eq!(
+            dom.to_html(),
+            "<html><ul><li>one</li><li>two</li></ul><p>a</p><p>b</p><div>c</div><dl><dt>t</dt><dd>d</dd></dl>\
+             <table><tr><td>1</td><td>2</td></tr><tr><td>3</td></tr></table></html>"
+        );
+        // Stray and misnested end tags are recovered from
+        let dom = parse("<DIV><b>bold<i>both</b>plain</span></div></p><br/><svg viewBox=\"0 0 1 1\"><linearGradient/></svg>");
+        assert_eq!(
+            dom.to_html(),
+            "<html><div><b>bold<i>both</i></b>plain</div><p></p><br><svg viewBox=\"0 0 1 1\"><linearGradient></linearGradient></svg></html>"
+        );
+    }
+
+    #[test]
+    fn test_raw_text_elements() {
+        let dom = parse("<script>if (a<b) { s = \"</p>\" }</script><style>p > a {}</style><textarea>\n&lt;b&gt; <i></textarea><title>A &amp; B</title><pre>\n  x</pre>");
+        let text = |path: &[usize]| dom.node_at(path).and_then(|node| node.text_content()).cloned();
+        assert_eq!(text(&[0, 0]).as_deref(), Some("if (a<b) { s = \"</p>\" }"));
+        assert_eq!(text(&[1, 0]).as_deref(), Some("p > a {}"));
+        assert_eq!(text(&[2, 0]).as_deref(), Some("<b> <i>"));
+        assert_eq!(text(&[3, 0]).as_deref(), Some("A & B"));
+        assert_eq!(text(&[4, 0]).as_deref(), Some("  x"));
+    }
+
+    #[test]
+    fn test_entities_and_stray_markup() {
+        let dom = parse("<p title='&quot;Hi&quot;' data-x=\"&copy=1\">Tom &amp; Jerry &#169; &#x1F600; &#150; &bogus; a < b &copy 2024 <!-- gone --></p><a href=/x/y?a=1&amp;b=2>link</a>");
+        let paragraph = dom.node_at(&[0]).unwrap();
+        assert_eq!(paragraph.get_attribute("title").map(String::as_str), Some("\"Hi\""));
+        assert_eq!(paragraph.get_attribute("data-x").map(String::as_str), Some("&copy=1"));
+        assert_eq!(dom.node_at(&[0, 0]).and_then(|node| node.text_content()).map(String::as_str), Some("Tom & Jerry © 😀 – &bogus; a < b © 2024 "));
+        assert_eq!(dom.node_at(&[1]).unwrap().get_attribute("href").map(String::as_str), Some("/x/y?a=1&b=2"));
+        // A document cut off in a tag still parses
+        assert_eq!(parse("<p>text<a href=\"x").to_html(), "<html><p>text</p></html>");
+    }
+}
@@ -0,0 +1,351 @@
+// Image memory manager: decoded page images are uploaded to the GPU only while they're on screen, small ones packed
+// into shared atlas textures, and the least recently visible are evicted once uploads go over a memory budget
+use eframe::egui;
+use egui::{ColorImage, TextureOptions};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+/// Side of an atlas texture in pixels
+pub const ATLAS_SIZE: usize = 2048;
+/// Images no larger than this on either side share atlases; bigger ones get a texture of their own
+pub const ATLAS_MAX_SIDE: usize = 256;
+/// Images are downsampled to at most this many pixels on their longer side when they're decoded
+pub const MAX_IMAGE_SIDE: usize = 4096;
+/// Bytes of textures images may take before the least recently visible are evicted
+pub const DEFAULT_BUDGET: usize = 256 << 20;
+
+const ATLAS_BYTES: usize = ATLAS_SIZE * ATLAS_SIZE * 4;
+// Transparent pixels left between images in an atlas, so filtering doesn't bleed one into the next
+const PADDING: usize = 1;
+
+static SHARED_IMAGES: OnceLock<ImageMemory> = OnceLock::new();
+
+/// Where an image is on the GPU for the frame being painted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageTexture {
+    pub id: egui::TextureId,
+    /// The part of the texture holding the image, in normalised coordinates
+    pub uv: egui::Rect,
+}
+
+// Where an image's pixels are uploaded
+enum Placement {
+    Atlas { atlas: u64, uv: egui::Rect },
+    Own(egui::TextureHandle),
+}
+
+struct Entry {
+    pixels: Arc<ColorImage>,
+    placement: Option<Placement>,
+    last_visible: u64,
+}
+
+// A shared texture filled shelf by shelf, top to bottom; space is reclaimed when all its images are gone
+struct Atlas {
+    texture: egui::TextureHandle,
+    // (top, height, filled width) of each row of images
+    shelves: Vec<(usize, usize, usize)>,
+    images: usize,
+    last_visible: u64,
+}
+
+impl Atlas {
+    // Top-left corner of room for a `width` by `height` image, taken from the first shelf it fits on
+    fn allocate(&mut self, width: usize, height: usize) -> Option<[usize; 2]> {
+        let (width, height) = (width + PADDING, height + PADDING);
+        if let Some(shelf) = self.shelves.iter_mut().find(|(_, shelf_height, filled)| height <= *shelf_height && filled + width <= ATLAS_SIZE) {
+            let corner = [shelf.2, shelf.0];
+            shelf.2 += width;
+            return Some(corner);
+        }
+        let top = self.shelves.last().map_or(0, |(top, height, _)| top + height);
+        if top + height > ATLAS_SIZE {
+            return None;
+        }
+        self.shelves.push((top, height, width));
+        Some([0, top])
+    }
+}
+
+struct Images {
+    budget: usize,
+    next_id: u64,
+    entries: HashMap<u64, Entry>,
+    atlases: HashMap<u64, Atlas>,
+    // Bumped whenever an uploaded image moves or goes, since what was painted from it is then out of date
+    generation: u64,
+}
+
+impl Images {
+    fn resident_bytes(&self) -> usize {
+        let own: usize = self.entries.values()
+            .filter_map(|entry| match &entry.placement {
+                Some(Placement::Own(texture)) => Some(texture.byte_size()),
+                _ => None,
+            })
+            .sum();
+        own + self.atlases.len() * ATLAS_BYTES
+    }
+
+    // Drop the least recently visible uploads until `needed` more bytes fit the budget. What was visible this
+    // frame or the one before may still be painted from, so it stays even if that means going over.
+    fn evict(&mut self, needed: usize, frame: u64) {
+        while self.resident_bytes() + needed > self.budget {
+            let stale = |last_visible: u64| last_visible + 1 < frame;
+            let own = self.entries.iter()
+                .filter(|(_, entry)| matches!(entry.placement, Some(Placement::Own(_))) && stale(entry.last_visible))
+                .map(|(id, entry)| (entry.last_visible, Some(*id), None));
+            let atlases = self.atlases.iter()
+                .filter(|(_, atlas)| stale(atlas.last_visible))
+                .map(|(id, atlas)| (atlas.last_visible, None, Some(*id)));
+            let Some((_, image, atlas)) = own.chain(atlases).min_by_key(|(last_visible, ..)| *last_visible) else {
+                break;
+            };
+            if let Some(entry) = image.and_then(|id| self.entries.get_mut(&id)) {
+                entry.placement = None;
+            }
+            if let Some(atlas) = atlas {
+                self.atlases.remove(&atlas);
+                for entry in self.entries.values_mut() {
+                    if matches!(entry.placement, Some(Placement::Atlas { atlas: placed, .. }) if placed == atlas) {
+                        entry.placement = None;
+                    }
+                }
+            }
+            self.generation += 1;
+        }
+    }
+
+    fn upload(&mut self, ctx: &egui::Context, id: u64, frame: u64) -> Option<Placement> {
+        let mut pixels = self.entries.get(&id)?.pixels.clone();
+        // The GPU may not take textures as large as MAX_IMAGE_SIDE
+        let max_side = ctx.input(|i| i.max_texture_side);
+        let longest = pixels.size[0].max(pixels.size[1]);
+        if longest > max_side {
+            pixels = Arc::new(crate::ui::tab_switcher::downscale(&pixels, (pixels.size[0] * max_side / longest).max(1)));
+        }
+        let [width, height] = pixels.size;
+        if width > ATLAS_MAX_SIDE || height > ATLAS_MAX_SIDE {
+            self.evict(width * height * 4, frame);
+            let texture = ctx.load_texture(format!("page_img_{}", id), pixels.as_ref().clone(), TextureOptions::LINEAR);
+            return Some(Placement::Own(texture));
+        }
+        let mut found = self.atlases.iter_mut().find_map(|(atlas_id, atlas)| atlas.allocate(width, height).map(|corner| (*atlas_id, corner)));
+        if found.is_none() {
+            self.evict(ATLAS_BYTES, frame);
+            let atlas_id = self.next_id;
+            self.next_id += 1;
+            let texture = ctx.load_texture(
+                format!("page_img_atlas_{}", atlas_id),
+                ColorImage::new([ATLAS_SIZE, ATLAS_SIZE], egui::Color32::TRANSPARENT),
+                TextureOptions::LINEAR,
+            );
+            let mut atlas = Atlas { texture, shelves: Vec::new(), images: 0, last_visible: frame };
+            found = atlas.allocate(width, height).map(|corner| (atlas_id, corner));
+            self.atlases.insert(atlas_id, atlas);
+        }
+        let (atlas_id, [x, y]) = found?;
+        let atlas = self.atlases.get_mut(&atlas_id)?;
+        atlas.texture.set_partial([x, y], pixels.as_ref().clone(), TextureOptions::LINEAR);
+        atlas.images += 1;
+        let scale = 1.0 / ATLAS_SIZE as f32;
+        let uv = egui::Rect::from_min_size(
+            egui::pos2(x as f32 * scale, y as f32 * scale),
+            egui::vec2(width as f32 * scale, height as f32 * scale),
+        );
+        Some(Placement::Atlas { atlas: atlas_id, uv })
+    }
+
+    fn release(&mut self, id: u64) {
+        let Some(Entry { placement: Some(Placement::Atlas { atlas, .. }), .. }) = self.entries.remove(&id) else {
+            return;
+        };
+        if let Some(shared) = self.atlases.get_mut(&atlas) {
+            shared.images -= 1;
+            if shared.images == 0 {
+                self.atlases.remove(&atlas);
+            }
+        }
+    }
+}
+
+/// The decoded images of every page, and the textures the visible ones are uploaded to
+#[derive(Clone)]
+pub struct ImageMemory {
+    images: Arc<Mutex<Images>>,
+}
+
+/// A page's hold on one decoded image; dropping it frees the image and its texture
+pub struct ManagedImage {
+    id: u64,
+    size: [usize; 2],
+    memory: Weak<Mutex<Images>>,
+}
+
+impl ManagedImage {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The image's size as decoded, which is what it's laid out at even when it was downsampled
+    pub fn size_vec2(&self) -> egui::Vec2 {
+        egui::vec2(self.size[0] as f32, self.size[1] as f32)
+    }
+}
+
+impl Drop for ManagedImage {
+    fn drop(&mut self) {
+        if let Some(memory) = self.memory.upgrade() {
+            memory.lock().unwrap().release(self.id);
+        }
+    }
+}
+
+impl ImageMemory {
+    pub fn shared() -> &'static ImageMemory {
+        SHARED_IMAGES.get_or_init(|| ImageMemory::new(DEFAULT_BUDGET))
+    }
+
+    pub fn new(budget: usize) -> Self {
+        Self {
+            images: Arc::new(Mutex::new(Images {
+                budget,
+                next_id: 0,
+                entries: HashMap::new(),
+                atlases: HashMap::new(),
+                generation: 0,
+            })),
+        }
+    }
+
+    pub fn set_budget(&self, budget: usize) {
+        self.images.lock().unwrap().budget = budget;
+    }
+
+    /// Take charge of a decoded image. Very large ones are downsampled; nothing is uploaded until it's painted.
+    pub fn add(&self, image: Arc<ColorImage>) -> ManagedImage {
+        let size = image.size;
+        let longest = size[0].max(size[1]);
+        let pixels = if longest > MAX_IMAGE_SIDE {
+            Arc::new(crate::ui::tab_switcher::downscale(&image, (size[0] * MAX_IMAGE_SIDE / longest).max(1)))
+        } else {
+            image
+        };
+        let mut images = self.images.lock().unwrap();
+        let id = images.next_id;
+        images.next_id += 1;
+        images.entries.insert(id, Entry { pixels, placement: None, last_visible: 0 });
+        ManagedImage { id, size, memory: Arc::downgrade(&self.images) }
+    }
+
+    /// The texture to paint `image` from this frame, uploading it if it isn't. Call only for images that are on
+    /// screen: being asked for is what keeps an image from being evicted.
+    pub fn texture(&self, ctx: &egui::Context, image: &ManagedImage) -> Option<ImageTexture> {
+        self.texture_at(ctx, image.id, ctx.cumulative_pass_nr())
+    }
+
+    fn texture_at(&self, ctx: &egui::Context, id: u64, frame: u64) -> Option<ImageTexture> {
+        let mut images = self.images.lock().unwrap();
+        if images.entries.get(&id)?.placement.is_none() {
+            let placement = images.upload(ctx, id, frame);
+            images.entries.get_mut(&id)?.placement = placement;
+        }
+        let entry = images.entries.get_mut(&id)?;
+        entry.last_visible = frame;
+        let texture = match entry.placement.as_ref()? {
+            Placement::Own(texture) => ImageTexture { id: texture.id(), uv: egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)) },
+            Placement::Atlas { atlas, uv } => {
+                let (atlas, uv) = (*atlas, *uv);
+                let atlas = images.atlases.get_mut(&atlas)?;
+                atlas.last_visible = frame;
+                ImageTexture { id: atlas.texture.id(), uv }
+            }
+        };
+        Some(texture)
+    }
+
+    /// Keep the image with `id` from eviction this frame without asking for its texture, because a recording
+    /// of it is replayed
+    pub fn touch(&self, ctx: &egui::Context, id: u64) {
+        let frame = ctx.cumulative_pass_nr();
+        let mut images = self.images.lock().unwrap();
+        let Some(entry) = images.entries.get_mut(&id) else {
+            return;
+        };
+        entry.last_visible = frame;
+        if let Some(Placement::Atlas { atlas, .. }) = entry.placement {
+            if let Some(atlas) = images.atlases.get_mut(&atlas) {
+                atlas.last_visible = frame;
+            }
+        }
+    }
+
+    /// Changes whenever an uploaded image is evicted, after which anything painted from it must be painted again
+    pub fn generation(&self) -> u64 {
+        self.images.lock().unwrap().generation
+    }
+
+    /// Bytes of textures images are uploaded to
+    pub fn resident_bytes(&self) -> usize {
+        self.images.lock().unwrap().resident_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize) -> Arc<ColorImage> {
+        Arc::new(ColorImage::new([width, height], egui::Color32::RED))
+    }
+
+    #[test]
+    fn test_small_images_share_an_atlas() {
+        let ctx = egui::Context::default();
+        let memory = ImageMemory::new(DEFAULT_BUDGET);
+        let icon = memory.add(image(16, 16));
+        let avatar = memory.add(image(64, 32));
+        assert_eq!(memory.resident_bytes(), 0);
+
+        let first = memory.texture_at(&ctx, icon.id(), 1).unwrap();
+        let second = memory.texture_at(&ctx, avatar.id(), 1).unwrap();
+        assert_eq!(first.id, second.id);
+        assert!(!first.uv.intersects(second.uv));
+        assert_eq!(first.uv.width(), 16.0 / ATLAS_SIZE as f32);
+        assert_eq!(memory.resident_bytes(), ATLAS_BYTES);
+
+        drop(icon);
+        assert_eq!(memory.resident_bytes(), ATLAS_BYTES);
+        drop(avatar);
+        assert_eq!(memory.resident_bytes(), 0);
+
+        let huge = memory.add(image(MAX_IMAGE_SIDE * 2, 10));
+        assert_eq!(huge.size_vec2(), egui::vec2(MAX_IMAGE_SIDE as f32 * 2.0, 10.0));
+        memory.texture_at(&ctx, huge.id(), 2).unwrap();
+        // Downsampled again to the largest texture the renderer takes
+        let max_side = ctx.input(|i| i.max_texture_side);
+        assert_eq!(memory.resident_bytes(), max_side * (10 * max_side / (MAX_IMAGE_SIDE * 2)).max(1) * 4);
+    }
+
+    #[test]
+    fn test_least_recently_visible_images_are_evicted() {
+        let ctx = egui::Context::default();
+        let photo_bytes = 512 * 512 * 4;
+        let memory = ImageMemory::new(photo_bytes * 2);
+        let photos: Vec<ManagedImage> = (0..3).map(|_| memory.add(image(512, 512))).collect();
+
+        memory.texture_at(&ctx, photos[0].id(), 1).unwrap();
+        memory.texture_at(&ctx, photos[1].id(), 2).unwrap();
+        assert_eq!(memory.generation(), 0);
+        // The first has been off screen longest, so it makes room
+        memory.texture_at(&ctx, photos[2].id(), 5).unwrap();
+        assert_eq!(memory.resident_bytes(), photo_bytes * 2);
+        assert_eq!(memory.generation(), 1);
+        assert!(memory.images.lock().unwrap().entries[&photos[0].id()].placement.is_none());
+
+        // Images visible a frame ago may still be painted, so the budget gives instead
+        memory.texture_at(&ctx, photos[0].id(), 6).unwrap();
+        memory.texture_at(&ctx, photos[1].id(), 6).unwrap();
+        assert_eq!(memory.resident_bytes(), photo_bytes * 3);
+    }
+}
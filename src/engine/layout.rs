@@ -1,7 +1,8 @@
 // Layout engine for positioning elements
 
 use crate::engine::dom::DOMNode;
-use crate::engine::css_parser::{Value, Unit};
+use crate::engine::css_parser::{self, Value, Unit};
+use crate::engine::flexbox::{self, FlexContainerStyle, FlexItem, FlexItemStyle};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -12,15 +13,48 @@ pub struct LayoutBox {
     pub padding: EdgeSizes,
     pub border: EdgeSizes,
     pub margin: EdgeSizes,
+    /// Specified values of the node this box was generated for
+    pub style: HashMap<String, Value>,
 }
 
 #[derive(Debug, Clone)]
 pub enum BoxType {
     BlockNode(DOMNode),
     InlineNode(DOMNode),
+    FlexNode(DOMNode),
+    GridNode(DOMNode),
     AnonymousBlock,
 }
 
+/// Size of one grid row or column as written in a track list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GridTrack {
+    Fixed(f32),
+    Fraction(f32),
+    Auto,
+}
+
+/// One end of an item's placement along a grid axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GridLine {
+    Auto,
+    /// 1-based line number; negative numbers count back from the last explicit line
+    Line(i32),
+    Span(usize),
+}
+
+// Start and end lines of an item along one axis
+type GridLines = (GridLine, GridLine);
+
+/// Cells covered by a grid item, as 0-based track indices
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GridArea {
+    row: usize,
+    row_span: usize,
+    column: usize,
+    column_span: usize,
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Rect {
     pub x: f32,
@@ -53,12 +87,13 @@ impl LayoutBox {
             padding: EdgeSizes::default(),
             border: EdgeSizes::default(),
             margin: EdgeSizes::default(),
+            style: HashMap::new(),
         }
     }
     
     pub fn get_style_node(&self) -> &DOMNode {
         match &self.box_type {
-            BoxType::BlockNode(node) | BoxType::InlineNode(node) => node,
+            BoxType::BlockNode(node) | BoxType::InlineNode(node) | BoxType::FlexNode(node) | BoxType::GridNode(node) => node,
             BoxType::AnonymousBlock => panic!("Anonymous block has no style node"),
         }
     }
@@ -67,6 +102,8 @@ impl LayoutBox {
         match &self.box_type {
             BoxType::BlockNode(_) => self.layout_block(containing_block),
             BoxType::InlineNode(_) => self.layout_inline(containing_block),
+            BoxType::FlexNode(_) => self.layout_flex(containing_block),
+            BoxType::GridNode(_) => self.layout_grid(containing_block),
             BoxType::AnonymousBlock => self.layout_block(containing_block),
         }
     }
@@ -134,8 +171,10 @@ impl LayoutBox {
     
     fn layout_block_children(&mut self) {
         let d = &mut self.content;
+        d.height = 0.0;
         for child in &mut self.children {
-            child.layout(*d);
+            // Each child starts below the ones already laid out
+            child.layout(Rect { y: d.y + d.height, height: 0.0, ..*d });
             d.height += child.margin_box().height;
         }
     }
@@ -153,6 +192,152 @@ impl LayoutBox {
         self.layout_block(containing_block);
     }
     
+    fn layout_flex(&mut self, containing_block: Rect) {
+        // The container itself sits in the flow like a block
+        self.calculate_block_width(containing_block);
+        self.calculate_block_position(containing_block);
+        
+        let container = FlexContainerStyle::from_style(&self.style);
+        let is_row = container.direction.is_row();
+        let width = self.content.width;
+        let height = self.length("height", containing_block.height);
+        let (main_size, cross_size) = if is_row { (Some(width), height) } else { (height, Some(width)) };
+        
+        let items: Vec<FlexItem> = self.children.iter_mut()
+            .map(|child| child.flex_item(is_row, main_size.unwrap_or(0.0), width))
+            .collect();
+        
+        let origin = self.content;
+        let children = &mut self.children;
+        let result = flexbox::layout(&container, main_size, cross_size, &items, |index, main| {
+            if is_row {
+                // Height follows from laying the item out at its flexed width
+                children[index].layout(Rect { width: main, height: 0.0, ..origin });
+                children[index].margin_box().height
+            } else {
+                // Without text measurement an auto-width item spans the container
+                width
+            }
+        });
+        
+        for (child, rect) in self.children.iter_mut().zip(&result.items) {
+            child.layout(Rect { x: origin.x + rect.x, y: origin.y + rect.y, width: rect.width, height: 0.0 });
+            child.content.height = rect.height;
+        }
+        self.content.height = result.height;
+    }
+    
+    // Flex item view of this box. Items without a width get no intrinsic width in a row
+    // until text measurement is available, so they rely on flex-grow or flex-basis.
+    fn flex_item(&mut self, is_row: bool, main_reference: f32, container_width: f32) -> FlexItem {
+        let (main, cross, min, max) = if is_row {
+            ("width", "height", "min-width", "max-width")
+        } else {
+            ("height", "width", "min-height", "max-height")
+        };
+        
+        let content_main = if is_row {
+            0.0
+        } else {
+            let width = self.length("width", container_width).unwrap_or(container_width);
+            self.layout(Rect { width, ..Rect::default() });
+            self.margin_box().height
+        };
+        
+        FlexItem {
+            style: FlexItemStyle::from_style(&self.style, main_reference),
+            main_size: self.length(main, main_reference),
+            cross_size: self.length(cross, if is_row { 0.0 } else { container_width }),
+            content_main,
+            min_main: self.length(min, main_reference).unwrap_or(0.0),
+            max_main: self.length(max, main_reference).unwrap_or(f32::INFINITY),
+        }
+    }
+    
+    fn layout_grid(&mut self, containing_block: Rect) {
+        // The container itself sits in the flow like a block
+        self.calculate_block_width(containing_block);
+        self.calculate_block_position(containing_block);
+        
+        let width = self.content.width;
+        let height = self.length("height", containing_block.height);
+        let (row_gap, column_gap) = resolve_gaps(&self.style);
+        
+        let mut columns = parse_tracks(self.style.get("grid-template-columns"), width);
+        let mut rows = parse_tracks(self.style.get("grid-template-rows"), height.unwrap_or(0.0));
+        if columns.is_empty() {
+            columns.push(GridTrack::Auto);
+        }
+        
+        let placements: Vec<_> = self.children.iter()
+            .map(|child| (
+                grid_placement(&child.style, "grid-row", "grid-row-start", "grid-row-end"),
+                grid_placement(&child.style, "grid-column", "grid-column-start", "grid-column-end"),
+            ))
+            .collect();
+        let areas = place_grid_items(&placements, rows.len(), columns.len());
+        
+        // Items placed outside the template create implicit auto tracks
+        let column_count = areas.iter().map(|a| a.column + a.column_span).max().unwrap_or(0);
+        let row_count = areas.iter().map(|a| a.row + a.row_span).max().unwrap_or(0);
+        columns.resize(columns.len().max(column_count), GridTrack::Auto);
+        rows.resize(rows.len().max(row_count), GridTrack::Auto);
+        
+        let column_sizes = size_tracks(&columns, Some(width), column_gap, &vec![0.0; columns.len()]);
+        
+        // Rows without a fixed size fit the tallest item that sits only in that row
+        let origin = self.content;
+        let mut item_heights = Vec::with_capacity(self.children.len());
+        let mut row_content = vec![0.0_f32; rows.len()];
+        for (child, area) in self.children.iter_mut().zip(&areas) {
+            let area_width = span_size(&column_sizes, area.column, area.column_span, column_gap);
+            let item_height = match child.length("height", 0.0) {
+                Some(height) => height,
+                None => {
+                    child.layout(Rect { width: area_width, height: 0.0, ..origin });
+                    child.margin_box().height
+                }
+            };
+            if area.row_span == 1 {
+                row_content[area.row] = row_content[area.row].max(item_height);
+            }
+            item_heights.push(item_height);
+        }
+        let mut row_sizes = size_tracks(&rows, height, row_gap, &row_content);
+        
+        // Grow the last flexible row of a spanning item that doesn't fit yet
+        for (area, item_height) in areas.iter().zip(&item_heights) {
+            let spanned = span_size(&row_sizes, area.row, area.row_span, row_gap);
+            let flexible = (area.row..area.row + area.row_span).rev().find(|&r| !matches!(rows[r], GridTrack::Fixed(_)));
+            if let (true, Some(row)) = (*item_height > spanned, flexible) {
+                row_sizes[row] += item_height - spanned;
+            }
+        }
+        
+        for ((child, area), item_height) in self.children.iter_mut().zip(&areas).zip(&item_heights) {
+            let x = origin.x + track_offset(&column_sizes, area.column, column_gap);
+            let y = origin.y + track_offset(&row_sizes, area.row, row_gap);
+            let area_width = span_size(&column_sizes, area.column, area.column_span, column_gap);
+            let area_height = span_size(&row_sizes, area.row, area.row_span, row_gap);
+            
+            child.layout(Rect { x, y, width: area_width, height: 0.0 });
+            // Items stretch to fill their area unless they have a height of their own
+            child.content.height = if child.style.contains_key("height") { *item_height } else { area_height };
+        }
+        
+        self.content.height = height.unwrap_or_else(|| {
+            row_sizes.iter().sum::<f32>() + row_sizes.len().saturating_sub(1) as f32 * row_gap
+        });
+    }
+    
+    // A length property in pixels, or `None` when it is auto or unset
+    fn length(&self, name: &str, reference: f32) -> Option<f32> {
+        match self.style.get(name)? {
+            value @ (Value::Length(..) | Value::Number(_)) => Some(to_px(value, reference)),
+            _ => None,
+        }
+    }
+    
     fn margin_box(&self) -> Rect {
         self.border_box().expanded_by(self.margin)
     }
@@ -177,9 +362,10 @@ impl Rect {
     }
 }
 
-fn to_px(value: &Value, containing_block_size: f32) -> f32 {
+pub(crate) fn to_px(value: &Value, containing_block_size: f32) -> f32 {
     match value {
         Value::Length(length, Unit::Px) => *length,
+        Value::Number(number) => *number,
         Value::Length(length, Unit::Percent) => containing_block_size * length / 100.0,
         Value::Length(length, Unit::Em) => length * 16.0, // Assume 16px base font size
         Value::Length(length, Unit::Rem) => length * 16.0, // Assume 16px base font size
@@ -202,6 +388,8 @@ pub fn build_layout_tree(root: &StyledNode) -> LayoutBox {
             match display {
                 "block" => LayoutBox::new(BoxType::BlockNode(root.node.clone())),
                 "inline" => LayoutBox::new(BoxType::InlineNode(root.node.clone())),
+                "flex" | "inline-flex" => LayoutBox::new(BoxType::FlexNode(root.node.clone())),
+                "grid" | "inline-grid" => LayoutBox::new(BoxType::GridNode(root.node.clone())),
                 _ => LayoutBox::new(BoxType::BlockNode(root.node.clone())),
             }
         }
@@ -209,13 +397,275 @@ pub fn build_layout_tree(root: &StyledNode) -> LayoutBox {
         DOMNode::Comment(_) => LayoutBox::new(BoxType::InlineNode(root.node.clone())), // Comments don't affect layout
     };
     
+    root_box.style = root.specified_values.clone();
+    
+    let is_item_container = matches!(root_box.box_type, BoxType::FlexNode(_) | BoxType::GridNode(_));
     for child in &root.children {
+        // Whitespace between flex or grid items does not become an item of its own
+        if is_item_container && is_collapsible(&child.node) {
+            continue;
+        }
         root_box.children.push(build_layout_tree(child));
     }
     
     root_box
 }
 
+/// `gap` is "<row-gap> <column-gap>", with a single value used for both; the longhands override it
+pub(crate) fn resolve_gaps(style: &HashMap<String, Value>) -> (f32, f32) {
+    let (mut row_gap, mut column_gap) = match style.get("gap") {
+        Some(Value::List(values)) if values.len() >= 2 => (to_px(&values[0], 0.0), to_px(&values[1], 0.0)),
+        Some(value) => (to_px(value, 0.0), to_px(value, 0.0)),
+        None => (0.0, 0.0),
+    };
+    if let Some(value) = style.get("row-gap") {
+        row_gap = to_px(value, 0.0);
+    }
+    if let Some(value) = style.get("column-gap") {
+        column_gap = to_px(value, 0.0);
+    }
+    (row_gap, column_gap)
+}
+
+// Track list such as `200px 1fr`, `repeat(3, 1fr)` or `25% auto`; percentages resolve against `reference`
+fn parse_tracks(value: Option<&Value>, reference: f32) -> Vec<GridTrack> {
+    let mut tracks = Vec::new();
+    match value {
+        Some(Value::List(values)) => values.iter().for_each(|v| push_tracks(v, reference, &mut tracks)),
+        Some(value) => push_tracks(value, reference, &mut tracks),
+        None => {}
+    }
+    tracks
+}
+
+fn push_tracks(value: &Value, reference: f32, tracks: &mut Vec<GridTrack>) {
+    match value {
+        Value::Length(fraction, Unit::Fr) => tracks.push(GridTrack::Fraction(*fraction)),
+        Value::Length(..) | Value::Number(_) => tracks.push(GridTrack::Fixed(to_px(value, reference))),
+        Value::Keyword(keyword) => {
+            let keyword = keyword.to_ascii_lowercase();
+            if let Some(arguments) = keyword.strip_prefix("repeat(").and_then(|k| k.strip_suffix(')')) {
+                // auto-fill/auto-fit would need the item count, so they repeat once
+                let (count, track_list) = arguments.split_once(',').unwrap_or(("1", ""));
+                let count = count.trim().parse::<usize>().unwrap_or(1).min(1000);
+                let repeated = parse_tracks(Some(&css_parser::parse_value(track_list)), reference);
+                for _ in 0..count {
+                    tracks.extend_from_slice(&repeated);
+                }
+            } else if let Some(arguments) = keyword.strip_prefix("minmax(").and_then(|k| k.strip_suffix(')')) {
+                // Only the maximum is honoured
+                let maximum = arguments.split_once(',').map_or(arguments, |(_, max)| max);
+                push_tracks(&css_parser::parse_value(maximum), reference, tracks);
+            } else if !keyword.is_empty() {
+                tracks.push(GridTrack::Auto);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Start and end lines from the `grid-row`/`grid-column` shorthand and its longhands
+fn grid_placement(style: &HashMap<String, Value>, shorthand: &str, start: &str, end: &str) -> GridLines {
+    let mut lines = match style.get(shorthand) {
+        Some(value) => {
+            // The parser drops the `/`, so "1 / span 2" arrives as [1, span, 2]
+            let tokens = match value {
+                Value::List(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            let (start_line, used) = parse_grid_line(tokens);
+            let (end_line, _) = parse_grid_line(&tokens[used..]);
+            (start_line, end_line)
+        }
+        None => (GridLine::Auto, GridLine::Auto),
+    };
+    if let Some(value) = style.get(start) {
+        lines.0 = parse_grid_line(std::slice::from_ref(value)).0;
+    }
+    if let Some(value) = style.get(end) {
+        lines.1 = parse_grid_line(std::slice::from_ref(value)).0;
+    }
+    lines
+}
+
+// One grid line from the front of `tokens`, and how many tokens it used
+fn parse_grid_line(tokens: &[Value]) -> (GridLine, usize) {
+    match tokens {
+        [Value::Keyword(span), Value::Number(count), ..] if span.eq_ignore_ascii_case("span") => {
+            (GridLine::Span((*count as usize).max(1)), 2)
+        }
+        [Value::Number(line), ..] if *line != 0.0 => (GridLine::Line(*line as i32), 1),
+        [_, ..] => (GridLine::Auto, 1),
+        [] => (GridLine::Auto, 0),
+    }
+}
+
+// Resolve a start/end pair against `track_count` explicit tracks into a start index, if definite, and a span
+fn resolve_grid_lines(lines: GridLines, track_count: usize) -> (Option<usize>, usize) {
+    let index = |line: i32| -> usize {
+        if line > 0 {
+            line as usize - 1
+        } else {
+            (track_count as i32 + 1 + line).max(0) as usize
+        }
+    };
+
+    match lines {
+        (GridLine::Line(start), GridLine::Line(end)) => {
+            let (start, end) = (index(start), index(end));
+            (Some(start.min(end)), start.abs_diff(end).max(1))
+        }
+        (GridLine::Line(start), GridLine::Span(span)) => (Some(index(start)), span),
+        (GridLine::Line(start), GridLine::Auto) => (Some(index(start)), 1),
+        (GridLine::Span(span), GridLine::Line(end)) => (Some(index(end).saturating_sub(span)), span),
+        (GridLine::Auto, GridLine::Line(end)) => (Some(index(end).saturating_sub(1)), 1),
+        (GridLine::Span(span), _) | (GridLine::Auto, GridLine::Span(span)) => (None, span),
+        (GridLine::Auto, GridLine::Auto) => (None, 1),
+    }
+}
+
+// Place items on the grid: fully explicit items first, then row-locked items, then the rest in
+// document order with a row-major cursor (grid-auto-flow: row, sparse)
+fn place_grid_items(placements: &[(GridLines, GridLines)], row_count: usize, column_count: usize) -> Vec<GridArea> {
+    let resolved: Vec<_> = placements.iter()
+        .map(|(rows, columns)| (resolve_grid_lines(*rows, row_count), resolve_grid_lines(*columns, column_count)))
+        .collect();
+
+    // Explicit column placements can widen the grid; auto items are clamped to it
+    let column_count = resolved.iter()
+        .filter_map(|(_, (column, span))| column.map(|c| c + span))
+        .fold(column_count.max(1), usize::max);
+
+    let mut occupied: Vec<Vec<bool>> = Vec::new();
+    let fits = |occupied: &Vec<Vec<bool>>, area: &GridArea| {
+        (area.row..area.row + area.row_span).all(|r| {
+            (area.column..area.column + area.column_span)
+                .all(|c| !occupied.get(r).is_some_and(|row| row[c]))
+        })
+    };
+    let occupy = |occupied: &mut Vec<Vec<bool>>, area: &GridArea| {
+        while occupied.len() < area.row + area.row_span {
+            occupied.push(vec![false; column_count]);
+        }
+        for row in &mut occupied[area.row..area.row + area.row_span] {
+            row[area.column..area.column + area.column_span].iter_mut().for_each(|cell| *cell = true);
+        }
+    };
+
+    let mut areas: Vec<Option<GridArea>> = vec![None; resolved.len()];
+    for (index, ((row, row_span), (column, column_span))) in resolved.iter().enumerate() {
+        if let (Some(row), Some(column)) = (row, column) {
+            let area = GridArea { row: *row, row_span: *row_span, column: *column, column_span: *column_span };
+            occupy(&mut occupied, &area);
+            areas[index] = Some(area);
+        }
+    }
+
+    for (index, ((row, row_span), (column, column_span))) in resolved.iter().enumerate() {
+        if let (Some(row), None) = (row, column) {
+            let column_span = (*column_span).min(column_count);
+            let column = (0..=column_count - column_span)
+                .find(|&c| fits(&occupied, &GridArea { row: *row, row_span: *row_span, column: c, column_span }))
+                .unwrap_or(0);
+            let area = GridArea { row: *row, row_span: *row_span, column, column_span };
+            occupy(&mut occupied, &area);
+            areas[index] = Some(area);
+        }
+    }
+
+    let (mut cursor_row, mut cursor_column) = (0, 0);
+    for (index, ((row, row_span), (column, column_span))) in resolved.iter().enumerate() {
+        if row.is_some() {
+            continue;
+        }
+        let area = match column {
+            Some(column) => {
+                if *column < cursor_column {
+                    cursor_row += 1;
+                }
+                let mut area = GridArea { row: cursor_row, row_span: *row_span, column: *column, column_span: *column_span };
+                while !fits(&occupied, &area) {
+                    area.row += 1;
+                }
+                area
+            }
+            None => {
+                let column_span = (*column_span).min(column_count);
+                let mut area = GridArea { row: cursor_row, row_span: *row_span, column: cursor_column, column_span };
+                loop {
+                    if area.column + column_span > column_count {
+                        area.column = 0;
+                        area.row += 1;
+                    } else if fits(&occupied, &area) {
+                        break;
+                    } else {
+                        area.column += 1;
+                    }
+                }
+                area
+            }
+        };
+        occupy(&mut occupied, &area);
+        cursor_row = area.row;
+        cursor_column = area.column + area.column_span;
+        areas[index] = Some(area);
+    }
+
+    areas.into_iter().flatten().collect()
+}
+
+// Track sizes: fixed tracks keep their size, auto tracks fit `content`, and fr tracks share what is
+// left of `available`. Without fr tracks, leftover space stretches the auto tracks.
+fn size_tracks(tracks: &[GridTrack], available: Option<f32>, gap: f32, content: &[f32]) -> Vec<f32> {
+    let mut sizes: Vec<f32> = tracks.iter().zip(content)
+        .map(|(track, content)| match track {
+            GridTrack::Fixed(size) => *size,
+            GridTrack::Auto => *content,
+            // An indefinite container has no free space to share, so fr tracks fit their content
+            GridTrack::Fraction(_) if available.is_none() => *content,
+            GridTrack::Fraction(_) => 0.0,
+        })
+        .collect();
+
+    let Some(available) = available else {
+        return sizes;
+    };
+    let free = available - sizes.iter().sum::<f32>() - tracks.len().saturating_sub(1) as f32 * gap;
+    let fractions: f32 = tracks.iter().map(|t| if let GridTrack::Fraction(f) = t { *f } else { 0.0 }).sum();
+
+    if fractions > 0.0 {
+        for (size, track) in sizes.iter_mut().zip(tracks) {
+            if let GridTrack::Fraction(fraction) = track {
+                *size = free.max(0.0) * fraction / fractions.max(1.0);
+            }
+        }
+    } else if free > 0.0 {
+        let auto_count = tracks.iter().filter(|t| **t == GridTrack::Auto).count();
+        for (size, track) in sizes.iter_mut().zip(tracks) {
+            if *track == GridTrack::Auto {
+                *size += free / auto_count as f32;
+            }
+        }
+    }
+    sizes
+}
+
+fn track_offset(sizes: &[f32], index: usize, gap: f32) -> f32 {
+    sizes[..index].iter().sum::<f32>() + index as f32 * gap
+}
+
+fn span_size(sizes: &[f32], start: usize, span: usize, gap: f32) -> f32 {
+    sizes[start..start + span].iter().sum::<f32>() + span.saturating_sub(1) as f32 * gap
+}
+
+fn is_collapsible(node: &DOMNode) -> bool {
+    match node {
+        DOMNode::Text(text) => text.trim().is_empty(),
+        DOMNode::Comment(_) => true,
+        DOMNode::Element { .. } => false,
+    }
+}
+
 /// Layout engine for processing DOM trees and computing layout
 #[derive(Debug)]
 pub struct LayoutEngine {
@@ -254,4 +704,86 @@ impl LayoutEngine {
     pub fn get_viewport(&self) -> (f32, f32) {
         (self.viewport_width, self.viewport_height)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(css: &str, children: Vec<StyledNode>) -> StyledNode {
+        StyledNode {
+            node: DOMNode::new_element("div".to_string()),
+            specified_values: css_parser::parse_declarations(css)
+                .into_iter()
+                .map(|declaration| (declaration.name, declaration.value))
+                .collect(),
+            children,
+        }
+    }
+
+    fn layout(width: f32, container: &str, items: &[&str]) -> LayoutBox {
+        let items = items.iter().map(|css| styled(css, Vec::new())).collect();
+        LayoutEngine::new(width, 600.0).layout(&styled(container, items))
+    }
+
+    fn geometry(layout: &LayoutBox) -> Vec<(f32, f32, f32, f32)> {
+        layout.children.iter()
+            .map(|child| (child.content.x, child.content.y, child.content.width, child.content.height))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tracks() {
+        let tracks = parse_tracks(Some(&css_parser::parse_value("200px repeat(2, 1fr) minmax(100px, 25%) auto")), 400.0);
+        assert_eq!(tracks, vec![
+            GridTrack::Fixed(200.0),
+            GridTrack::Fraction(1.0),
+            GridTrack::Fraction(1.0),
+            GridTrack::Fixed(100.0),
+            GridTrack::Auto,
+        ]);
+    }
+
+    #[test]
+    fn test_grid_page_shell() {
+        let page = layout(800.0,
+            "display: grid; grid-template-columns: 200px 1fr; grid-template-rows: 60px 1fr 40px; height: 500px; gap: 10px",
+            &["grid-column: 1 / 3", "", "", "grid-column: 1 / -1"],
+        );
+        assert_eq!(geometry(&page), vec![
+            (0.0, 0.0, 800.0, 60.0),
+            (0.0, 70.0, 200.0, 380.0),
+            (210.0, 70.0, 590.0, 380.0),
+            (0.0, 460.0, 800.0, 40.0),
+        ]);
+        assert_eq!(page.content.height, 500.0);
+    }
+
+    #[test]
+    fn test_grid_auto_placement_adds_rows() {
+        let grid = layout(320.0,
+            "display: grid; grid-template-columns: repeat(3, 1fr); gap: 10px",
+            &["height: 50px", "height: 50px", "height: 50px", "height: 50px"],
+        );
+        assert_eq!(geometry(&grid), vec![
+            (0.0, 0.0, 100.0, 50.0),
+            (110.0, 0.0, 100.0, 50.0),
+            (220.0, 0.0, 100.0, 50.0),
+            (0.0, 60.0, 100.0, 50.0),
+        ]);
+        assert_eq!(grid.content.height, 110.0);
+    }
+
+    #[test]
+    fn test_grid_line_placement() {
+        let grid = layout(400.0,
+            "display: grid; grid-template-columns: 25% 1fr 2fr",
+            &["grid-column: span 2; height: 20px", "height: 20px", "grid-row: 2; grid-column: 2; height: 30px"],
+        );
+        assert_eq!(geometry(&grid), vec![
+            (0.0, 0.0, 200.0, 20.0),
+            (200.0, 0.0, 200.0, 20.0),
+            (100.0, 20.0, 100.0, 30.0),
+        ]);
+    }
+}
@@ -0,0 +1,793 @@
+// <audio> and <video> playback: ranged streaming, decoding on background threads and a shared clock
+use anyhow::{anyhow, Result};
+use eframe::egui::{self, ColorImage};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::engine::executor::Executor;
+use crate::networking::manual_client::{FetchContext, ManualHttpClient};
+use crate::extensions::web_request::ResourceType;
+
+// Bytes asked for by each ranged request
+const CHUNK_SIZE: u64 = 512 * 1024;
+// Chunks kept in memory per resource; the ones farthest from the read position are dropped first
+const MAX_CACHED_CHUNKS: usize = 64;
+// Largest whole file kept from a server that ignores Range; that's as much as the chunk cache holds
+const MAX_UNRANGED_BODY: usize = CHUNK_SIZE as usize * MAX_CACHED_CHUNKS;
+// How far decoded audio may run ahead of the clock
+const AUDIO_LEAD: f64 = 0.5;
+// How long the decoder threads sleep while there's nothing for them to do
+const IDLE_WAIT: Duration = Duration::from_millis(20);
+
+// Containers and codecs the decoders understand
+const PLAYABLE_TYPES: &[&str] = &[
+    "audio/mpeg", "audio/mp3", "audio/mp4", "audio/aac", "audio/x-m4a", "audio/ogg", "audio/vorbis",
+    "audio/wav", "audio/x-wav", "audio/wave", "audio/flac", "audio/x-flac", "video/mp4",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+/// Whether a `<source type>` can be played; codec parameters are ignored
+pub fn can_play(mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    PLAYABLE_TYPES.contains(&essence.as_str())
+}
+
+/// `m:ss`, or `h:mm:ss` for an hour or more
+pub fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+// Chunks of one resource, shared by its audio and video readers
+#[derive(Default)]
+struct ChunkStore {
+    size: Option<u64>,
+    chunks: BTreeMap<u64, Arc<Vec<u8>>>,
+}
+
+impl ChunkStore {
+    fn insert(&mut self, index: u64, chunk: Vec<u8>) -> Arc<Vec<u8>> {
+        while self.chunks.len() >= MAX_CACHED_CHUNKS {
+            let farthest = self.chunks.keys().copied().max_by_key(|key| key.abs_diff(index));
+            farthest.and_then(|key| self.chunks.remove(&key));
+        }
+        let chunk = Arc::new(chunk);
+        self.chunks.insert(index, chunk.clone());
+        chunk
+    }
+}
+
+/// `Content-Range: bytes 0-99/1234` as (first, last, total); the total is None when given as `*`
+fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    Some((first.trim().parse().ok()?, last.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// Read + Seek over a media URL, fetched in ranged chunks as the decoders reach them
+#[derive(Clone)]
+pub struct RangedSource {
+    url: String,
+    client: ManualHttpClient,
+    context: FetchContext,
+    store: Arc<Mutex<ChunkStore>>,
+    position: u64,
+}
+
+impl RangedSource {
+    pub fn new(url: &str, mut client: ManualHttpClient, context: FetchContext) -> Self {
+        // Ranged responses are a chunk each; only a server that ignores Range sends more
+        client.set_max_body_size(Some(MAX_UNRANGED_BODY));
+        Self {
+            url: url.to_string(),
+            client,
+            context,
+            store: Arc::new(Mutex::new(ChunkStore::default())),
+            position: 0,
+        }
+    }
+
+    /// Total size of the resource, fetching the first chunk to learn it
+    pub fn size(&self) -> Result<u64> {
+        if let Some(size) = self.store.lock().unwrap().size {
+            return Ok(size);
+        }
+        self.chunk(0)?;
+        self.store.lock().unwrap().size.ok_or_else(|| anyhow!("{} did not report its size", self.url))
+    }
+
+    fn chunk(&self, index: u64) -> Result<Arc<Vec<u8>>> {
+        if let Some(chunk) = self.store.lock().unwrap().chunks.get(&index) {
+            return Ok(chunk.clone());
+        }
+        let start = index * CHUNK_SIZE;
+        let context = FetchContext { range: Some((start, start + CHUNK_SIZE - 1)), ..self.context.clone() };
+        let response = Executor::shared().block_on(self.client.fetch_with_context(&self.url, &context))?.response;
+        if let Some(oversized) = &response.oversized {
+            return Err(anyhow!(
+                "{} ignores range requests and is larger than the {} MB that can be held without them",
+                self.url, oversized.limit / 1024 / 1024
+            ));
+        }
+        let body = response.get_raw_body()?;
+
+        let mut store = self.store.lock().unwrap();
+        match response.status_code {
+            206 => {
+                let total = response.get_header("content-range")
+                    .and_then(|value| parse_content_range(value))
+                    .and_then(|(_, _, total)| total);
+                store.size = total.or(store.size);
+                Ok(store.insert(index, body))
+            }
+            200 => {
+                // The server ignored the range and sent everything, which fits the cache as the body was capped
+                store.size = Some(body.len() as u64);
+                for (i, part) in body.chunks(CHUNK_SIZE as usize).enumerate() {
+                    store.insert(i as u64, part.to_vec());
+                }
+                Ok(store.chunks.get(&index).cloned().unwrap_or_default())
+            }
+            status => Err(anyhow!("{} answered HTTP {}", self.url, status)),
+        }
+    }
+}
+
+impl Read for RangedSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.size().map_err(io::Error::other)?;
+        if self.position >= size || buf.is_empty() {
+            return Ok(0);
+        }
+        let index = self.position / CHUNK_SIZE;
+        let chunk = self.chunk(index).map_err(io::Error::other)?;
+        let available = chunk.get((self.position - index * CHUNK_SIZE) as usize..).unwrap_or_default();
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for RangedSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.size().map_err(io::Error::other)?.checked_add_signed(delta),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"))?;
+        Ok(self.position)
+    }
+}
+
+impl symphonia::core::io::MediaSource for RangedSource {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.store.lock().ok()?.size
+    }
+}
+
+/// Media time that advances with the wall clock while playing
+#[derive(Debug, Clone, Copy, Default)]
+struct PlaybackClock {
+    base: f64,
+    started: Option<Instant>,
+}
+
+impl PlaybackClock {
+    fn position(&self) -> f64 {
+        self.base + self.started.map_or(0.0, |started| started.elapsed().as_secs_f64())
+    }
+
+    fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    fn play(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    fn pause(&mut self) {
+        self.base = self.position();
+        self.started = None;
+    }
+
+    fn seek(&mut self, to: f64) {
+        self.base = to;
+        if self.started.is_some() {
+            self.started = Some(Instant::now());
+        }
+    }
+}
+
+// Shared between the element's controls and its decoder threads
+#[derive(Default)]
+struct Shared {
+    clock: PlaybackClock,
+    // Bumped on every seek so the decoders drop what they had buffered
+    seek_generation: u64,
+    duration: Option<f64>,
+    video_size: Option<[usize; 2]>,
+    // Set once an audio track is decoding to a sound device
+    has_audio: bool,
+    frame: Option<ColorImage>,
+    frame_generation: u64,
+    gain: f32,
+    error: Option<String>,
+    stopped: bool,
+}
+
+impl Shared {
+    fn extend_duration(&mut self, duration: f64) {
+        self.duration = Some(self.duration.unwrap_or(0.0).max(duration));
+    }
+}
+
+/// One `<audio>` or `<video>` element's playback; dropping it stops the decoder threads
+pub struct MediaPlayer {
+    shared: Arc<Mutex<Shared>>,
+    volume: f32,
+    muted: bool,
+    // Muted from the tab strip, on top of the element's own mute
+    tab_muted: bool,
+    pub looping: bool,
+    texture: Option<egui::TextureHandle>,
+    texture_generation: u64,
+}
+
+impl MediaPlayer {
+    /// Start fetching `url` and decoding its first frame and duration; playback waits for `play`
+    pub fn open(url: &str, kind: MediaKind, referrer: Option<&str>, ctx: &egui::Context) -> Result<Self> {
+        let context = FetchContext {
+            referrer: referrer.map(str::to_string),
+            resource_type: ResourceType::Media,
+            ..FetchContext::default()
+        };
+        let source = RangedSource::new(url, ManualHttpClient::new()?, context);
+        let shared = Arc::new(Mutex::new(Shared { gain: 1.0, ..Shared::default() }));
+
+        if kind == MediaKind::Video {
+            let (source, shared, ctx) = (source.clone(), shared.clone(), ctx.clone());
+            thread::Builder::new().name("media-video".to_string()).spawn(move || {
+                if let Err(e) = run_video(source, &shared, &ctx) {
+                    shared.lock().unwrap().error = Some(format!("Video can't be played: {}", e));
+                    ctx.request_repaint();
+                }
+            })?;
+        }
+
+        let extension = url::Url::parse(url).ok()
+            .and_then(|url| url.path().rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()));
+        let audio_shared = shared.clone();
+        thread::Builder::new().name("media-audio".to_string()).spawn(move || {
+            // A video without a sound track is fine, so only audio elements report this
+            if let Err(e) = run_audio(source, extension, &audio_shared) {
+                if kind == MediaKind::Audio {
+                    audio_shared.lock().unwrap().error = Some(format!("Audio can't be played: {}", e));
+                }
+            }
+        })?;
+
+        Ok(Self { shared, volume: 1.0, muted: false, tab_muted: false, looping: false, texture: None, texture_generation: 0 })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.shared.lock().unwrap().clock.is_running()
+    }
+
+    pub fn play(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        // Playing again from the end starts over
+        if shared.duration.is_some_and(|duration| shared.clock.position() >= duration) {
+            shared.clock.seek(0.0);
+            shared.seek_generation += 1;
+        }
+        shared.clock.play();
+    }
+
+    pub fn pause(&mut self) {
+        self.shared.lock().unwrap().clock.pause();
+    }
+
+    pub fn toggle(&mut self) {
+        if self.is_playing() {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+
+    pub fn seek(&mut self, to: f64) {
+        let mut shared = self.shared.lock().unwrap();
+        let end = shared.duration.unwrap_or(f64::MAX);
+        shared.clock.seek(to.clamp(0.0, end));
+        shared.seek_generation += 1;
+    }
+
+    /// Current time, held at the end (or wrapped around when looping) once the media runs out
+    pub fn position(&mut self) -> f64 {
+        let mut shared = self.shared.lock().unwrap();
+        let position = shared.clock.position();
+        match shared.duration {
+            Some(duration) if position >= duration && shared.clock.is_running() => {
+                if self.looping {
+                    shared.clock.seek(0.0);
+                    shared.seek_generation += 1;
+                    0.0
+                } else {
+                    shared.clock.pause();
+                    shared.clock.seek(duration);
+                    duration
+                }
+            }
+            _ => position,
+        }
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        self.shared.lock().unwrap().duration
+    }
+
+    pub fn video_size(&self) -> Option<[usize; 2]> {
+        self.shared.lock().unwrap().video_size
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.shared.lock().unwrap().error.clone()
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.update_gain();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.update_gain();
+    }
+
+    pub fn set_tab_muted(&mut self, muted: bool) {
+        if self.tab_muted != muted {
+            self.tab_muted = muted;
+            self.update_gain();
+        }
+    }
+
+    /// Playing with a sound track, even if muted, for the tab's audio indicator
+    pub fn is_playing_audio(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.has_audio && shared.clock.is_running()
+    }
+
+    fn update_gain(&self) {
+        self.shared.lock().unwrap().gain = if self.muted || self.tab_muted { 0.0 } else { self.volume };
+    }
+
+    /// The latest decoded video frame, uploaded to the GPU when it changed
+    pub fn frame_texture(&mut self, ctx: &egui::Context) -> Option<&egui::TextureHandle> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.frame_generation != self.texture_generation {
+            self.texture_generation = shared.frame_generation;
+            if let Some(frame) = shared.frame.take() {
+                match &mut self.texture {
+                    Some(texture) => texture.set(frame, egui::TextureOptions::LINEAR),
+                    None => self.texture = Some(ctx.load_texture("media_frame", frame, egui::TextureOptions::LINEAR)),
+                }
+            }
+        }
+        drop(shared);
+        self.texture.as_ref()
+    }
+}
+
+impl Drop for MediaPlayer {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.stopped = true;
+        }
+    }
+}
+
+// Presentation time of every sample of a video track, and which samples are keyframes
+struct VideoTimeline {
+    times: Vec<f64>,
+    keyframes: Vec<usize>,
+    end: f64,
+}
+
+impl VideoTimeline {
+    fn new(track: &mp4::Mp4Track) -> Self {
+        let stbl = &track.trak.mdia.minf.stbl;
+        let deltas: Vec<(u32, u32)> = stbl.stts.entries.iter().map(|e| (e.sample_count, e.sample_delta)).collect();
+        let sync = stbl.stss.as_ref().map(|stss| stss.entries.as_slice());
+        Self::from_tables(&deltas, sync, track.timescale())
+    }
+
+    // `deltas` are (sample count, duration) runs; `sync` lists 1-based keyframe numbers, all samples when absent
+    fn from_tables(deltas: &[(u32, u32)], sync: Option<&[u32]>, timescale: u32) -> Self {
+        let timescale = f64::from(timescale.max(1));
+        let mut times = Vec::new();
+        let mut ticks = 0u64;
+        for &(count, delta) in deltas {
+            for _ in 0..count {
+                times.push(ticks as f64 / timescale);
+                ticks += u64::from(delta);
+            }
+        }
+        let keyframes = match sync {
+            Some(sync) => sync.iter().map(|&n| n.saturating_sub(1) as usize).filter(|&i| i < times.len()).collect(),
+            None => (0..times.len()).collect(),
+        };
+        Self { times, keyframes, end: ticks as f64 / timescale }
+    }
+
+    // The keyframe decoding has to start from to show `time`
+    fn keyframe_before(&self, time: f64) -> usize {
+        self.keyframes.iter().rev().copied().find(|&i| self.times[i] <= time).unwrap_or(0)
+    }
+}
+
+// MP4 stores NAL units behind 4-byte lengths; the decoder wants Annex B start codes
+fn annex_b(sample: &[u8], out: &mut Vec<u8>) {
+    let mut rest = sample;
+    while let Some((length, tail)) = rest.split_first_chunk::<4>() {
+        let length = u32::from_be_bytes(*length) as usize;
+        let Some(nal) = tail.get(..length) else { break };
+        out.extend_from_slice(&[0, 0, 0, 1]);
+        out.extend_from_slice(nal);
+        rest = &tail[length..];
+    }
+}
+
+// Sleep until the clock reaches `time`; false when a seek or stop comes first
+fn wait_until(shared: &Mutex<Shared>, time: f64, generation: u64) -> bool {
+    loop {
+        let remaining = {
+            let shared = shared.lock().unwrap();
+            if shared.stopped || shared.seek_generation != generation {
+                return false;
+            }
+            shared.clock.is_running().then(|| time - shared.clock.position())
+        };
+        match remaining {
+            Some(remaining) if remaining <= 0.0 => return true,
+            Some(remaining) => thread::sleep(Duration::from_secs_f64(remaining).min(IDLE_WAIT)),
+            None => thread::sleep(IDLE_WAIT),
+        }
+    }
+}
+
+// Decode the H.264 track of an MP4 file, publishing each frame when the clock reaches it
+fn run_video(source: RangedSource, shared: &Mutex<Shared>, ctx: &egui::Context) -> Result<()> {
+    let size = source.size()?;
+    let mut reader = mp4::Mp4Reader::read_header(source, size)?;
+    let track = reader.tracks().values()
+        .find(|track| matches!(track.media_type(), Ok(mp4::MediaType::H264)))
+        .ok_or_else(|| anyhow!("only H.264 video in MP4 is supported"))?;
+    let track_id = track.track_id();
+    let timeline = VideoTimeline::new(track);
+    // Parameter sets go in front of every keyframe so decoding can start at any of them
+    let mut parameter_sets = Vec::new();
+    for nal in [track.sequence_parameter_set()?, track.picture_parameter_set()?] {
+        parameter_sets.extend_from_slice(&[0, 0, 0, 1]);
+        parameter_sets.extend_from_slice(nal);
+    }
+    {
+        let mut shared = shared.lock().unwrap();
+        shared.video_size = Some([usize::from(track.width()), usize::from(track.height())]);
+        shared.extend_duration(timeline.end);
+    }
+    ctx.request_repaint();
+
+    let mut decoder = openh264::decoder::Decoder::new()?;
+    let mut next = timeline.times.len();
+    let mut seen_generation = None;
+    // After a seek, frames before this time are decoded but not shown
+    let mut seek_target: Option<f64> = None;
+    loop {
+        let (generation, position, running) = {
+            let shared = shared.lock().unwrap();
+            if shared.stopped {
+                return Ok(());
+            }
+            (shared.seek_generation, shared.clock.position(), shared.clock.is_running())
+        };
+        if seen_generation != Some(generation) {
+            seen_generation = Some(generation);
+            next = timeline.keyframe_before(position);
+            seek_target = Some(position);
+        }
+        if next >= timeline.times.len() || (!running && seek_target.is_none()) {
+            thread::sleep(IDLE_WAIT);
+            continue;
+        }
+
+        let Some(sample) = reader.read_sample(track_id, next as u32 + 1)? else {
+            next = timeline.times.len();
+            continue;
+        };
+        let time = timeline.times[next];
+        let frame_end = timeline.times.get(next + 1).copied().unwrap_or(timeline.end);
+        next += 1;
+
+        let mut packet = if sample.is_sync { parameter_sets.clone() } else { Vec::new() };
+        annex_b(&sample.bytes, &mut packet);
+        // A damaged frame is skipped rather than ending playback
+        let Ok(Some(yuv)) = decoder.decode(&packet) else { continue };
+        if seek_target.is_some_and(|target| frame_end <= target) {
+            continue;
+        }
+        let (width, height) = openh264::formats::YUVSource::dimensions(&yuv);
+        let mut rgba = vec![0; width * height * 4];
+        yuv.write_rgba8(&mut rgba);
+        let frame = ColorImage::from_rgba_unmultiplied([width, height], &rgba);
+
+        // The first frame after a seek shows straight away, even while paused
+        if seek_target.take().is_none() && !wait_until(shared, time, generation) {
+            continue;
+        }
+        let mut shared = shared.lock().unwrap();
+        shared.frame = Some(frame);
+        shared.frame_generation += 1;
+        ctx.request_repaint();
+    }
+}
+
+// Decode the first audio track into the sound device, restarting from the clock after seeks and pauses
+fn run_audio(source: RangedSource, extension: Option<String>, shared: &Mutex<Shared>) -> Result<()> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+    use symphonia::core::errors::Error as DecodeError;
+    use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+    use symphonia::core::units::Time;
+
+    let mut hint = Hint::new();
+    if let Some(extension) = &extension {
+        hint.with_extension(extension);
+    }
+    let stream = MediaSourceStream::new(Box::new(source), Default::default());
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())?
+        .format;
+    let track = format.tracks().iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("no audio track"))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+    if let (Some(time_base), Some(frames)) = (track.codec_params.time_base, track.codec_params.n_frames) {
+        let time = time_base.calc_time(frames);
+        shared.lock().unwrap().extend_duration(time.seconds as f64 + time.frac);
+    }
+
+    let Some(output) = AudioOutput::open() else {
+        // Nothing to play it through, so the track only contributes its duration
+        return Ok(());
+    };
+    shared.lock().unwrap().has_audio = true;
+
+    let mut seen_generation = None;
+    let mut was_running = false;
+    loop {
+        let (generation, position, running) = {
+            let shared = shared.lock().unwrap();
+            if shared.stopped {
+                return Ok(());
+            }
+            output.set_gain(shared.gain);
+            (shared.seek_generation, shared.clock.position(), shared.clock.is_running())
+        };
+        if !running {
+            if was_running {
+                output.clear();
+                was_running = false;
+            }
+            thread::sleep(IDLE_WAIT);
+            continue;
+        }
+        if !was_running || seen_generation != Some(generation) {
+            // Start over from the clock so sound and picture stay in step
+            seen_generation = Some(generation);
+            was_running = true;
+            output.clear();
+            let _ = format.seek(SeekMode::Coarse, SeekTo::Time { time: Time::from(position), track_id: Some(track_id) });
+            decoder.reset();
+        }
+        if output.buffered_seconds() > AUDIO_LEAD {
+            thread::sleep(IDLE_WAIT / 2);
+            continue;
+        }
+
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                thread::sleep(IDLE_WAIT);
+                continue;
+            }
+            Err(DecodeError::ResetRequired) => {
+                decoder.reset();
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                samples.copy_interleaved_ref(decoded);
+                output.push(samples.samples(), spec.channels.count(), spec.rate);
+            }
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// Interleaved samples from `from` channels to `to`: mono is copied to every channel, extra channels dropped
+fn remix(samples: &[f32], from: usize, to: usize) -> Vec<f32> {
+    if from == to || from == 0 || to == 0 {
+        return samples.to_vec();
+    }
+    let mut out = Vec::with_capacity(samples.len() / from * to);
+    for frame in samples.chunks_exact(from) {
+        if to == 1 {
+            out.push(frame.iter().sum::<f32>() / from as f32);
+        } else {
+            out.extend((0..to).map(|channel| frame[channel.min(from - 1)]));
+        }
+    }
+    out
+}
+
+// Linear interpolation between sample rates, per channel
+fn resample(samples: &[f32], channels: usize, from: u32, to: u32) -> Vec<f32> {
+    if from == to || from == 0 || channels == 0 {
+        return samples.to_vec();
+    }
+    let frames = samples.len() / channels;
+    let out_frames = (frames as u64 * u64::from(to) / u64::from(from)) as usize;
+    let step = f64::from(from) / f64::from(to);
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for i in 0..out_frames {
+        let source = i as f64 * step;
+        let index = source as usize;
+        let fraction = (source - index as f64) as f32;
+        for channel in 0..channels {
+            let current = samples[index * channels + channel];
+            let next = samples.get((index + 1) * channels + channel).copied().unwrap_or(current);
+            out.push(current + (next - current) * fraction);
+        }
+    }
+    out
+}
+
+/// Samples queued for the sound device, which pulls them at its own rate
+struct AudioOutput {
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    // Volume as f32 bits, applied as the device pulls samples so changes are heard at once
+    gain: Arc<AtomicU32>,
+    rate: u32,
+    channels: usize,
+    #[cfg(feature = "audio-output")]
+    _stream: cpal::Stream,
+}
+
+impl AudioOutput {
+    #[cfg(feature = "audio-output")]
+    fn open() -> Option<Self> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host().default_output_device()?;
+        let config = device.default_output_config().ok()?.config();
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let gain = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let (source, volume) = (queue.clone(), gain.clone());
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let gain = f32::from_bits(volume.load(Ordering::Relaxed));
+                let mut queue = source.lock().unwrap();
+                for sample in data.iter_mut() {
+                    *sample = queue.pop_front().unwrap_or(0.0) * gain;
+                }
+            },
+            |e| log::warn!("Audio output error: {}", e),
+            None,
+        ).ok()?;
+        stream.play().ok()?;
+        Some(Self { queue, gain, rate: config.sample_rate.0, channels: usize::from(config.channels), _stream: stream })
+    }
+
+    // Built without a sound backend
+    #[cfg(not(feature = "audio-output"))]
+    fn open() -> Option<Self> {
+        None
+    }
+
+    fn set_gain(&self, gain: f32) {
+        self.gain.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.queue.lock().unwrap().clear();
+    }
+
+    fn buffered_seconds(&self) -> f64 {
+        self.queue.lock().unwrap().len() as f64 / (self.rate as f64 * self.channels as f64).max(1.0)
+    }
+
+    fn push(&self, samples: &[f32], channels: usize, rate: u32) {
+        let samples = resample(&remix(samples, channels, self.channels), self.channels, rate, self.rate);
+        self.queue.lock().unwrap().extend(samples);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range_and_types() {
+        assert_eq!(parse_content_range("bytes 0-524287/1048576"), Some((0, 524287, Some(1048576))));
+        assert_eq!(parse_content_range("bytes 10-20/*"), Some((10, 20, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+
+        assert!(can_play("video/mp4; codecs=\"avc1.42E01E, mp4a.40.2\""));
+        assert!(can_play("audio/MPEG"));
+        assert!(!can_play("video/webm"));
+        assert_eq!(format_time(75.9), "1:15");
+        assert_eq!(format_time(3725.0), "1:02:05");
+    }
+
+    #[test]
+    fn test_video_timeline_and_annex_b() {
+        // Three frames of 1000 ticks, then two of 500, with keyframes at samples 1 and 4
+        let timeline = VideoTimeline::from_tables(&[(3, 1000), (2, 500)], Some(&[1, 4]), 1000);
+        assert_eq!(timeline.times, vec![0.0, 1.0, 2.0, 3.0, 3.5]);
+        assert_eq!(timeline.end, 4.0);
+        assert_eq!(timeline.keyframe_before(2.5), 0);
+        assert_eq!(timeline.keyframe_before(3.2), 3);
+
+        let mut out = Vec::new();
+        annex_b(&[0, 0, 0, 2, 0x67, 0x42, 0, 0, 0, 1, 0x68, 0, 0], &mut out);
+        assert_eq!(out, vec![0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x68]);
+    }
+
+    #[test]
+    fn test_remix_and_resample() {
+        assert_eq!(remix(&[0.5, -0.5], 1, 2), vec![0.5, 0.5, -0.5, -0.5]);
+        assert_eq!(remix(&[0.2, 0.4, 1.0, 0.0], 2, 1), vec![0.3, 0.5]);
+        assert_eq!(resample(&[0.0, 1.0], 1, 1, 2), vec![0.0, 0.5, 1.0, 1.0]);
+        assert_eq!(resample(&[0.0, 0.0, 1.0, 1.0], 2, 2, 1), vec![0.0, 0.0]);
+    }
+}
@@ -1,18 +1,69 @@
 pub mod html_parser;
 pub mod css_parser;
 pub mod layout;
+pub mod flexbox;
 pub mod renderer;
 pub mod dom;
+pub mod dom_diff;
 pub mod streaming_parser;
 pub mod virtual_scroll;
 pub mod adaptive_renderer;
 pub mod content_analyzer;
 pub mod background_processor;
 pub mod download_manager;
+pub mod executor;
+pub mod paged_layout;
+pub mod resource_loader;
+pub mod hit_test;
+pub mod woff;
+pub mod fonts;
+pub mod style;
+pub mod reader;
+pub mod svg;
+pub mod media;
+pub mod forms;
+pub mod frames;
+pub mod animation;
+pub mod accessibility;
+pub mod focus;
+pub mod raster;
+pub mod prefetch;
+pub mod paint_cache;
+pub mod image_memory;
+pub mod usage;
+pub mod pdf;
+pub mod data_tree;
+#[cfg(all(test, feature = "engine-conformance"))]
+mod conformance;
 
 use eframe::egui;
 use self::dom::DOMNode;
+use self::virtual_scroll::VirtualScrollConfig;
+use self::hit_test::{HitTarget, HitTestRecorder};
+use self::fonts::{FontRegistry, PageFonts};
+use self::style::{ComputedStyle, Insets, PageStyles, Position, TextAlign};
+use self::css_parser::ElementState;
+use self::focus::FocusMove;
+use self::media::{MediaKind, MediaPlayer};
+use self::frames::{Frame, Sandbox};
+use self::paint_cache::PaintKey;
+use self::image_memory::{ImageMemory, ManagedImage};
+use crate::networking::cookie_manager::CookieManager;
+use crate::networking::redirect::ReferrerPolicy;
+use crate::storage::UserStyles;
+use crate::networking::tracking::{self, TrackingProtection};
 use crate::js::JSEngine;
+use crate::js::cookies::DocumentCookies;
+use crate::js::location::NavigationRequest;
+use crate::extensions::{ContentScript, RunAt};
+use crate::ui::NeonIcons;
+use crate::js::locale::LocaleOverrides;
+use crate::js::navigator::ScreenMetrics;
+use self::resource_loader::{LoadedResource, ResourceContent, ResourceRequest};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 pub struct WebPage {
     pub dom: DOMNode,
@@ -25,6 +76,170 @@ pub struct WebPage {
     pub content_size: usize,
     pub is_large_content: bool,
     pub js_engine: Option<JSEngine>,
+    // Off when the site's JavaScript permission blocks scripts; content scripts still run
+    scripts_enabled: bool,
+    // Set when the user stopped the page's slow scripts; none run again until the page is loaded afresh
+    scripts_stopped: bool,
+    // How long the page's scripts may run in one turn before they're interrupted and the user asked
+    script_time_budget: std::time::Duration,
+    // The document as the page's scripts see it; their changes are copied back into `dom`
+    script_document: Option<Rc<RefCell<DOMNode>>>,
+    // Length of the tab's back/forward list and the current entry's state, for `history`
+    session_history: (usize, Option<String>),
+    // Bumped each time scripts or the parser change the document, for what's cached against its layout
+    dom_generation: u64,
+    // Loaded <img> textures keyed by the element's src attribute
+    pub images: HashMap<String, ManagedImage>,
+    // SVG images, keyed like `images`
+    svg_images: HashMap<String, std::sync::Arc<svg::SvgDocument>>,
+    // Inline <svg> elements parsed on first render
+    inline_svgs: RefCell<HashMap<*const DOMNode, Rc<svg::SvgDocument>>>,
+    // <audio> and <video> playback keyed by resolved source URL, opened on first render
+    media: RefCell<HashMap<String, MediaPlayer>>,
+    // Muted from the tab strip
+    audio_muted: Cell<bool>,
+    // Source of the video showing in the picture-in-picture window, which its element then stands in for
+    pip_video: RefCell<Option<String>>,
+    // <iframe> documents, loaded on first render. Keyed by the iframe's child-index path and source, which stay
+    // the same when the DOM is rebuilt, unlike node addresses
+    frames: RefCell<HashMap<(Vec<usize>, String), Frame>>,
+    // URLs of the documents this page is framed in, top first; empty for a tab's own page
+    frame_ancestors: Vec<String>,
+    // The jar the page's requests use, which its frames share; cross-site ones keep their cookies in partitions of it
+    cookie_jar: Option<Arc<Mutex<CookieManager>>>,
+    // Tracking protection of the tab's top-level page, which its frames' requests go through too
+    tracking: Option<TrackingProtection>,
+    // For a framed page, the top-level site its cookies from other sites are partitioned under
+    cookie_partition: Option<String>,
+    // What the user entered into the page's form controls
+    form_state: RefCell<forms::FormState>,
+    // The <form> being rendered, which its controls submit
+    current_form: Cell<Option<*const DOMNode>>,
+    // Form and submit button of a submission made during the last render
+    form_submit: Cell<Option<(*const DOMNode, Option<*const DOMNode>)>>,
+    // Unloaded images that were near the viewport during the last render
+    visible_images: RefCell<HashSet<String>>,
+    // Element boxes from the last render, for mapping pointer positions back to the DOM
+    hit_boxes: HitTestRecorder,
+    // Time spent parsing, laying out and running scripts for the page, for neon://tasks
+    usage: usage::CpuMeter,
+    // Web fonts declared by the page's stylesheets
+    fonts: PageFonts,
+    // Computed style of every element, from the user agent, page, inline and user styles
+    styles: PageStyles,
+    // Revision of the user's stylesheets the page last picked up
+    user_styles_revision: Option<u64>,
+    // Style and font of the element being rendered, used by the text inside it
+    current_style: RefCell<ComputedStyle>,
+    current_font: RefCell<egui::FontFamily>,
+    // The element being rendered, which is the containing block of its sticky children
+    current_element: Cell<Option<*const DOMNode>>,
+    // Fixed and sticky elements met during the render, painted over the page once the flow is done
+    positioned: RefCell<Vec<PositionedBox>>,
+    // Size each positioned element took when last painted, so it can be placed before it's painted again
+    positioned_sizes: RefCell<HashMap<*const DOMNode, egui::Vec2>>,
+    // Set while positioned elements are painted, so they render in place instead of being queued again
+    painting_positioned: Cell<bool>,
+    // What finished subtrees painted, replayed while nothing they were painted from changed
+    paint_cache: paint_cache::PaintCache,
+    // The font atlas and palette of the frame being rendered, or None when painting isn't retained this frame
+    paint_frame: Cell<Option<(usize, crate::ui::theme::Palette, u64)>>,
+    // Images painted during the render, by id, with where they were painted
+    painted_images: RefCell<Vec<(u64, egui::Rect)>>,
+    // Set while rendering a subtree once something in it is interactive or changes by itself, so it can't be
+    // replayed
+    dynamic_paint: Cell<bool>,
+    // Size of the visible area during the last render, which vw and vh are relative to
+    viewport: Cell<egui::Vec2>,
+    // Where the document was loaded from, once known
+    document_url: Option<String>,
+    // Policy from the document's Referrer-Policy header, or inherited from the parent of a srcdoc frame
+    referrer_policy: Option<ReferrerPolicy>,
+    // hrefs in the document that lead to pages in the history, for :visited
+    visited_links: HashSet<String>,
+    // Locale and time zone handed to the page's scripts
+    locale_overrides: LocaleOverrides,
+    // Display the tab is shown on, for `screen` in the page's scripts
+    screen: ScreenMetrics,
+    // Frame time the page's animation clock started at, in seconds
+    clock_origin: Option<f64>,
+    // Widgets drawn for focusable elements during the last render, which keyboard focus moves between
+    focus_widgets: RefCell<Vec<(*const DOMNode, egui::Id)>>,
+    // The element whose widget had keyboard focus at the end of the last render
+    focused: Cell<Option<*const DOMNode>>,
+    // Widget with keyboard focus at the end of the last render, which may be part of the browser instead
+    last_focus_id: Cell<Option<egui::Id>>,
+    // Set when focus was last moved with the keyboard, which shows the focus ring
+    focus_visible: Cell<bool>,
+    // href of a link activated with Enter or Space during the last render
+    activated_link: RefCell<Option<String>>,
+}
+
+// How far outside the visible area a lazy image starts loading, in points
+const LAZY_IMAGE_MARGIN: f32 = 600.0;
+
+// A fixed or sticky element waiting to be painted over the flow
+struct PositionedBox {
+    node: *const DOMNode,
+    inset: Insets,
+    // Space a sticky element holds in the flow; fixed elements have none
+    flow_rect: Option<egui::Rect>,
+    parent: Option<*const DOMNode>,
+}
+
+/// Parsed HTML without any UI or script state, so it can be built on a worker thread
+pub struct ParsedDocument {
+    pub dom: DOMNode,
+    pub html: String,
+    pub title: Option<String>,
+    pub plain_text: String,
+    pub content_size: usize,
+    /// How long parsing took, which the page counts toward its CPU time
+    pub parse_time: std::time::Duration,
+}
+
+impl ParsedDocument {
+    pub fn parse(html: &str) -> Self {
+        let started = std::time::Instant::now();
+        let limited_html = limit_html(html);
+        let dom = html_parser::parse(&limited_html);
+        Self::with_dom(dom, limited_html, html.len(), started)
+    }
+    
+    fn with_dom(dom: DOMNode, html: String, content_size: usize, started: std::time::Instant) -> Self {
+        Self {
+            dom,
+            title: extract_title(&html),
+            plain_text: strip_html(&html),
+            html,
+            content_size,
+            parse_time: started.elapsed(),
+        }
+    }
+    
+    /// Whether the tab will render this response as a full HTML page rather than a preview
+    pub fn should_preparse(response: &crate::networking::HttpResponse) -> bool {
+        // Images, PDFs, JSON and XML have viewers of their own, so their bytes aren't parsed as markup
+        let viewed = response.content_type().is_some_and(|content_type| {
+            crate::ui::image_viewer::handles(content_type)
+                || crate::ui::pdf_viewer::handles(content_type)
+                || crate::ui::data_viewer::handles(content_type)
+        });
+        response.is_success() && response.body.len() <= 50_000 && !response.is_large_content() && !viewed
+    }
+}
+
+// Limit HTML size to prevent crashes (5MB limit for large content support)
+fn limit_html(html: &str) -> String {
+    if html.len() > 5 * 1024 * 1024 {
+        let mut end = 5 * 1024 * 1024;
+        while !html.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}<br><br><i>[Content truncated at 5MB to prevent crashes]</i>", &html[..end])
+    } else {
+        html.to_string()
+    }
 }
 
 /// Progress tracking for large website loading
@@ -421,6 +636,43 @@ impl WebPage {
         Self::from_html(&simple_html, None)
     }
     
+    /// A text document (a data: or blob: URL, say) shown as is rather than parsed as markup
+    pub fn create_plain_text_page(text: &str, url: &str) -> Self {
+        let html = format!(r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <title>{}</title>
+                <style>
+                    body {{ background: #1a1a1a; color: #ffffff; margin: 12px; }}
+                    pre {{ white-space: pre-wrap; word-wrap: break-word; font-family: monospace; }}
+                </style>
+            </head>
+            <body><pre>{}</pre></body>
+            </html>
+        "#, Self::escape_html(url), Self::escape_html(text));
+        
+        Self::from_html(&html, None)
+    }
+    
+    /// A page showing just the image at `url`, which the resource loader then fetches
+    pub fn create_image_page(url: &str) -> Self {
+        let html = format!(r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <title>Image</title>
+                <style>
+                    body {{ background: #0e0e0e; margin: 0; text-align: center; }}
+                </style>
+            </head>
+            <body><img src="{}" alt="Image"></body>
+            </html>
+        "#, Self::escape_html(url));
+        
+        Self::from_html(&html, None)
+    }
+    
     // Helper method to escape HTML content for safe display
     fn escape_html(content: &str) -> String {
         content
@@ -431,29 +683,29 @@ impl WebPage {
             .replace('\'', "&#39;")
     }
     
-    pub fn from_html(html: &str, mut js_engine: Option<JSEngine>) -> Self {
-        let content_size = html.len();
+    pub fn from_html(html: &str, js_engine: Option<JSEngine>) -> Self {
+        match js_engine {
+            Some(engine) => {
+                // Scripts run while the document is parsed, so this path stays on the calling thread
+                let started = std::time::Instant::now();
+                let mut js_engine = Some(engine);
+                let limited_html = limit_html(html);
+                let dom = html_parser::parse_with_js(&limited_html, &mut js_engine);
+                let mut page = Self::from_parsed(ParsedDocument::with_dom(dom, limited_html, html.len(), started));
+                page.js_engine = js_engine;
+                page
+            }
+            None => Self::from_parsed(ParsedDocument::parse(html)),
+        }
+    }
+    
+    /// Build a page from a document parsed elsewhere, typically on the executor's parse pool
+    pub fn from_parsed(document: ParsedDocument) -> Self {
+        let ParsedDocument { dom, html, title, plain_text, content_size, parse_time } = document;
+        let usage = usage::CpuMeter::default();
+        usage.add_parse(parse_time);
         let is_large_content = content_size > 25 * 1024; // 25KB threshold
         
-        // Limit HTML size to prevent crashes (5MB limit for large content support)
-        let limited_html = if html.len() > 5 * 1024 * 1024 {
-            let truncated = &html[..5 * 1024 * 1024];
-            format!("{}<br><br><i>[Content truncated at 5MB to prevent crashes]</i>", truncated)
-        } else {
-            html.to_string()
-        };
-        
-        // Parse HTML with JavaScript execution support
-        let dom = if js_engine.is_some() {
-            html_parser::parse_with_js(&limited_html, &mut js_engine)
-        } else {
-            html_parser::parse(&limited_html)
-        };
-        
-        let stylesheets = Vec::new(); // CSS parsing implemented via css_parser module
-        let title = extract_title(&limited_html);
-        let plain = strip_html(&limited_html);
-        
         // Create progress indicator for large content
         let loading_progress = if is_large_content {
             Some(LoadingProgress {
@@ -470,19 +722,590 @@ impl WebPage {
         
         Self {
             dom,
-            stylesheets,
+            stylesheets: Vec::new(), // CSS parsing implemented via css_parser module
             layout_tree: None,
-            raw_html: Some(limited_html.clone()),
-            plain_text: Some(plain),
+            raw_html: Some(html),
+            plain_text: Some(plain_text),
             extracted_title: title,
             loading_progress,
             content_size,
             is_large_content,
-            js_engine,
+            js_engine: None,
+            scripts_enabled: true,
+            scripts_stopped: false,
+            script_time_budget: std::time::Duration::from_secs(crate::js::watchdog::DEFAULT_SLOW_SCRIPT_SECS),
+            script_document: None,
+            session_history: (1, None),
+            dom_generation: 0,
+            images: HashMap::new(),
+            svg_images: HashMap::new(),
+            inline_svgs: RefCell::new(HashMap::new()),
+            media: RefCell::new(HashMap::new()),
+            audio_muted: Cell::new(false),
+            pip_video: RefCell::new(None),
+            frames: RefCell::new(HashMap::new()),
+            frame_ancestors: Vec::new(),
+            cookie_jar: None,
+            cookie_partition: None,
+            tracking: None,
+            form_state: RefCell::new(forms::FormState::default()),
+            current_form: Cell::new(None),
+            form_submit: Cell::new(None),
+            visible_images: RefCell::new(HashSet::new()),
+            hit_boxes: HitTestRecorder::default(),
+            fonts: PageFonts::default(),
+            styles: PageStyles::default(),
+            user_styles_revision: None,
+            current_style: RefCell::new(ComputedStyle::default()),
+            current_font: RefCell::new(egui::FontFamily::Proportional),
+            current_element: Cell::new(None),
+            positioned: RefCell::new(Vec::new()),
+            positioned_sizes: RefCell::new(HashMap::new()),
+            painting_positioned: Cell::new(false),
+            paint_cache: paint_cache::PaintCache::default(),
+            paint_frame: Cell::new(None),
+            painted_images: RefCell::new(Vec::new()),
+            dynamic_paint: Cell::new(false),
+            viewport: Cell::new(egui::Vec2::ZERO),
+            document_url: None,
+            locale_overrides: LocaleOverrides::default(),
+            screen: ScreenMetrics::default(),
+            visited_links: HashSet::new(),
+            clock_origin: None,
+            referrer_policy: None,
+            focus_widgets: RefCell::new(Vec::new()),
+            focused: Cell::new(None),
+            last_focus_id: Cell::new(None),
+            focus_visible: Cell::new(false),
+            activated_link: RefCell::new(None),
+            usage,
+        }
+    }
+    
+    /// Feed a fetched subresource to the CSS engine, JS engine or image renderer
+    pub fn apply_resource(&mut self, resource: LoadedResource) -> Result<(), String> {
+        let LoadedResource { request, result, .. } = resource;
+        match result.map_err(|e| format!("{}: {}", request.url, e))? {
+            ResourceContent::Stylesheet(css) => {
+                self.add_stylesheet(&css, &request.url);
+            }
+            ResourceContent::Script(_) if !self.scripts_enabled => {}
+            ResourceContent::Script(source) => {
+                // Errors in the script are the page's, reported in its console rather than as a failed load
+                self.run_script(&source, &request.url);
+            }
+            ResourceContent::Image(image) => {
+                // Uploaded once it's on screen
+                self.images.insert(request.reference, ImageMemory::shared().add(image));
+            }
+            ResourceContent::Svg(document) => {
+                self.svg_images.insert(request.reference, document);
+            }
+            ResourceContent::Font(data) => {
+                FontRegistry::shared().register_web_font(&request.url, data)
+                    .map_err(|e| format!("{}: {}", request.url, e))?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Run the page's inline `<script>` elements in document order, as the parser reaches them. External scripts
+    /// follow through `apply_resource` as they arrive.
+    pub fn run_inline_scripts(&mut self) {
+        if !self.scripts_enabled {
+            return;
+        }
+        let mut sources = Vec::new();
+        collect_inline_scripts(&self.dom, &mut sources);
+        for (index, source) in sources.iter().enumerate() {
+            self.run_script(source, &format!("inline script {}", index + 1));
+        }
+    }
+    
+    // Run the script named `name` in the page's engine and keep what it changed in the document
+    fn run_script(&mut self, source: &str, name: &str) {
+        if self.scripts_stopped {
+            return;
+        }
+        let document = self.script_document.get_or_insert_with(|| Rc::new(RefCell::new(self.dom.clone()))).clone();
+        if self.js_engine.is_none() {
+            let engine = JSEngine::new().and_then(|mut engine| {
+                engine.set_locale_overrides(&self.locale_overrides);
+                engine.set_screen(self.screen);
+                engine.set_turn_budget(self.script_time_budget);
+                if let Some(url) = &self.document_url {
+                    engine.set_document_url(url);
+                }
+                engine.set_cookies(self.document_cookies());
+                engine.set_session_history(self.session_history.0, self.session_history.1.clone());
+                engine.set_dom_root(document)?;
+                Ok(engine)
+            });
+            match engine {
+                Ok(engine) => self.js_engine = Some(engine),
+                Err(e) => {
+                    log::warn!("No script engine for {}: {}", name, e);
+                    return;
+                }
+            }
+        }
+        let Some(engine) = self.js_engine.as_mut() else { return };
+        let started = std::time::Instant::now();
+        engine.start_turn();
+        if let Err(e) = engine.run_script(source, name) {
+            log::debug!("{} stopped at {}: {}", name, e.location(), e.message);
+        }
+        self.pick_up_script_changes();
+        self.usage.add_script(started.elapsed());
+    }
+    
+    /// The tab's back/forward list length and the state its current entry was pushed with, for `history`
+    pub fn set_session_history(&mut self, length: usize, state: Option<String>) {
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_session_history(length, state.clone());
+        }
+        self.session_history = (length, state);
+    }
+    
+    /// Navigations, reloads and history entries the page's scripts asked for since the last call
+    pub fn take_navigation_requests(&mut self) -> Vec<NavigationRequest> {
+        self.js_engine.as_mut().map(JSEngine::take_navigation_requests).unwrap_or_default()
+    }
+    
+    /// Go back or forward to another history entry of this document, at `url` with `state`, firing popstate
+    pub fn pop_state(&mut self, url: &str, state: Option<String>) {
+        self.set_document_url(url);
+        self.session_history.1 = state.clone();
+        let Some(engine) = self.js_engine.as_mut() else { return };
+        let started = std::time::Instant::now();
+        engine.start_turn();
+        if let Err(e) = engine.pop_state(url, state) {
+            log::warn!("popstate handler failed: {}", e);
+        }
+        self.pick_up_script_changes();
+        self.usage.add_script(started.elapsed());
+    }
+    
+    /// Errors and warnings from the page's scripts since the last call
+    pub fn take_script_errors(&mut self) -> Vec<crate::js::ScriptError> {
+        self.js_engine.as_mut().map(JSEngine::take_errors).unwrap_or_default()
+    }
+    
+    // Copy the scripts' document into the rendered one after they changed it
+    fn pick_up_script_changes(&mut self) {
+        let Some(engine) = self.js_engine.as_mut() else { return };
+        if !engine.take_dom_mutated() {
+            return;
+        }
+        if let Some(document) = self.script_document.clone() {
+            self.update_dom(&document.borrow());
+        }
+    }
+    
+    /// Bring the rendered document up to date with `updated`, a changed copy of it. Only the subtrees that
+    /// differ are replaced and restyled; everything else keeps its identity, and with it its computed style,
+    /// running transitions and focus. Returns whether anything changed.
+    pub fn update_dom(&mut self, updated: &DOMNode) -> bool {
+        let patch = dom_diff::patch(&mut self.dom, updated);
+        if patch.is_empty() {
+            return false;
+        }
+        self.dom_generation += 1;
+        self.styles.invalidate_subtrees(&patch.restyle);
+        // Caches keyed by node identity can't tell a node from one patched in at its address
+        let changed: HashSet<*const DOMNode> = patch.restyle.iter()
+            .filter_map(|path| self.dom.node_at(path))
+            .flat_map(DOMNode::descendants)
+            .collect();
+        self.inline_svgs.get_mut().retain(|node, _| !changed.contains(node));
+        self.positioned_sizes.get_mut().retain(|node, _| !changed.contains(node));
+        // What was painted from a changed node or any element around it is out of date
+        let mut repaint = changed;
+        for path in patch.restyle.iter().chain(&patch.text) {
+            repaint.extend((0..=path.len()).filter_map(|len| self.dom.node_at(&path[..len])).map(|node| node as *const DOMNode));
+        }
+        self.paint_cache.forget(&repaint);
+        true
+    }
+    
+    /// Counts changes to the document, so what's derived from it can tell when it's out of date
+    pub fn dom_generation(&self) -> u64 {
+        self.dom_generation
+    }
+    
+    // `base_url` is where the stylesheet came from, which its font URLs are relative to
+    fn add_stylesheet(&mut self, css: &str, base_url: &str) {
+        let started = std::time::Instant::now();
+        self.stylesheets.push(css_parser::parse(css));
+        self.usage.add_parse(started.elapsed());
+        self.styles.invalidate();
+        if let Some(stylesheet) = self.stylesheets.last() {
+            self.fonts.add_stylesheet(stylesheet, base_url, &self.stylesheets);
+        }
+    }
+    
+    /// Run extension content scripts, each extension in a script world of its own. document_start scripts see an
+    /// empty document; document_end ones see the parsed page, and what they change in it is kept
+    pub fn run_content_scripts(&mut self, scripts: &[ContentScript]) {
+        if scripts.is_empty() {
+            return;
+        }
+        let empty = Rc::new(RefCell::new(DOMNode::new_element("html".to_string())));
+        let document = Rc::new(RefCell::new(self.dom.clone()));
+        let mut worlds: HashMap<&str, JSEngine> = HashMap::new();
+        for run_at in [RunAt::DocumentStart, RunAt::DocumentEnd] {
+            for script in scripts.iter().filter(|script| script.run_at == run_at) {
+                let world = match worlds.entry(script.extension_id.as_str()) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => match JSEngine::new_isolated() {
+                        Ok(mut engine) => {
+                            engine.set_locale_overrides(&self.locale_overrides);
+                            engine.set_screen(self.screen);
+                            if let Some(url) = &self.document_url {
+                                engine.set_document_url(url);
+                            }
+                            entry.insert(engine)
+                        }
+                        Err(e) => {
+                            log::warn!("No script world for '{}': {}", script.extension_name, e);
+                            continue;
+                        }
+                    },
+                };
+                let root = if run_at == RunAt::DocumentStart { &empty } else { &document };
+                if world.set_dom_root(root.clone()).is_err() {
+                    continue;
+                }
+                for statement in crate::js::animation_frame::split_statements(&script.source) {
+                    if let Err(e) = world.execute(statement) {
+                        log::warn!("Content script of '{}' failed: {}", script.extension_name, e);
+                        break;
+                    }
+                }
+            }
+        }
+        self.update_dom(&document.borrow());
+        // The page's own scripts see what content scripts changed
+        if let Some(script_document) = &self.script_document {
+            *script_document.borrow_mut() = self.dom.clone();
+        }
+    }
+    
+    /// Locale and time zone scripts on this page see
+    pub fn set_locale_overrides(&mut self, overrides: &LocaleOverrides) {
+        self.locale_overrides = overrides.clone();
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_locale_overrides(overrides);
+        }
+    }
+    
+    /// The display the tab is shown on, as its scripts see it
+    pub fn set_screen(&mut self, screen: ScreenMetrics) {
+        self.screen = screen;
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_screen(screen);
+        }
+    }
+    
+    /// Whether the page's own scripts may run, from the site's JavaScript permission
+    pub fn set_scripts_enabled(&mut self, enabled: bool) {
+        self.scripts_enabled = enabled;
+        if !enabled {
+            self.js_engine = None;
         }
     }
     
+    pub fn scripts_enabled(&self) -> bool {
+        self.scripts_enabled
+    }
+    
+    /// How long the page's scripts may run in one turn of its event loop before they're interrupted
+    pub fn set_script_time_budget(&mut self, budget: std::time::Duration) {
+        self.script_time_budget = budget;
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_turn_budget(budget);
+        }
+    }
+    
+    /// Whether the page's scripts ran out of time in a turn since the last call, so the user should be asked
+    /// whether to stop them
+    pub fn take_slow_script(&mut self) -> bool {
+        self.js_engine.as_mut().is_some_and(JSEngine::take_slow_script)
+    }
+    
+    /// Stop the page's scripts for good: its timers, animation frames and workers go with its engine
+    pub fn stop_scripts(&mut self) {
+        self.scripts_stopped = true;
+        self.js_engine = None;
+    }
+    
+    /// Let the page's slow scripts go on, with more time from the next turn on
+    pub fn wait_for_scripts(&mut self) {
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.extend_turn_budget();
+            self.script_time_budget = engine.turn_budget();
+        }
+    }
+    
+    /// Record the URL the document was finally loaded from, after redirects
+    pub fn set_document_url(&mut self, url: &str) {
+        self.document_url = Some(url.to_string());
+        self.visited_links = visited_links(&self.dom, url);
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_document_url(url);
+        }
+        self.refresh_script_cookies();
+    }
+    
+    /// Referrer policy the document was served with, e.g. from its Referrer-Policy header
+    pub fn set_referrer_policy(&mut self, policy: Option<ReferrerPolicy>) {
+        self.referrer_policy = policy;
+    }
+    
+    /// How much of the page's address its links, forms and subresources send as Referer: a `<meta name="referrer">`
+    /// wins over the header, and the user's default applies when the page sets neither
+    pub fn referrer_policy(&self) -> ReferrerPolicy {
+        meta_referrer_policy(&self.dom)
+            .or(self.referrer_policy)
+            .unwrap_or_else(|| crate::storage::Preferences::current().referrer_policy)
+    }
+    
+    /// Advance the page's animation clock to the frame time `time`, in seconds, and run the callbacks scripts
+    /// queued with requestAnimationFrame. Returns whether the page wants another frame.
+    pub fn tick_animations(&mut self, time: f64) -> bool {
+        let now = time - *self.clock_origin.get_or_insert(time);
+        let mut animating = self.styles.set_time(now);
+        let started = std::time::Instant::now();
+        if let Some(engine) = self.js_engine.as_mut() {
+            if engine.has_animation_frame_callbacks() {
+                engine.start_turn();
+                if let Err(e) = engine.run_animation_frames(now * 1000.0) {
+                    log::warn!("animation frame callback failed: {}", e);
+                }
+            }
+            animating |= engine.has_animation_frame_callbacks();
+        }
+        self.pick_up_script_changes();
+        self.usage.add_script(started.elapsed());
+        animating
+    }
+    
+    /// Hand what the page's workers sent since the last call to its scripts; true while any worker still runs
+    pub fn poll_workers(&mut self) -> bool {
+        let Some(engine) = self.js_engine.as_mut() else { return false };
+        let started = std::time::Instant::now();
+        engine.start_turn();
+        let running = engine.poll_workers();
+        self.pick_up_script_changes();
+        self.usage.add_script(started.elapsed());
+        running
+    }
+    
+    /// Notifications the page's scripts created since the last call
+    pub fn take_notifications(&mut self) -> Vec<crate::js::ScriptNotification> {
+        self.js_engine.as_mut().map(JSEngine::take_notifications).unwrap_or_default()
+    }
+    
+    /// Whether a script on the page asked for notification permission since the last call
+    pub fn take_notification_permission_request(&mut self) -> bool {
+        self.js_engine.as_mut().is_some_and(JSEngine::take_notification_permission_request)
+    }
+    
+    /// Cookies for the page's frames, which same-site frames share and cross-site ones get a partition of
+    pub fn set_cookie_jar(&mut self, cookies: Arc<Mutex<CookieManager>>) {
+        self.cookie_jar = Some(cookies);
+        self.refresh_script_cookies();
+    }
+    
+    /// Tracking protection for the page's subresources and frames; None loads everything as asked
+    pub fn set_tracking_protection(&mut self, tracking: Option<TrackingProtection>) {
+        self.tracking = tracking;
+        self.refresh_script_cookies();
+    }
+    
+    // The page's cookies as `document.cookie` sees them, under the same jar key its requests use
+    fn document_cookies(&self) -> Option<DocumentCookies> {
+        let url = self.document_url.as_deref()?;
+        if crate::storage::Preferences::current().cookies.blocks(url) {
+            return None;
+        }
+        let host = url::Url::parse(url).ok()?.host_str()?.to_string();
+        let domain = tracking::cookie_domain(self.tracking.as_ref(), self.cookie_partition.as_deref(), url, &host, 0);
+        DocumentCookies::new(self.cookie_jar.clone()?, url, domain)
+    }
+    
+    fn refresh_script_cookies(&mut self) {
+        let cookies = self.document_cookies();
+        if let Some(engine) = self.js_engine.as_mut() {
+            engine.set_cookies(cookies);
+        }
+    }
+    
+    pub fn tracking_protection(&self) -> Option<&TrackingProtection> {
+        self.tracking.as_ref()
+    }
+    
+    /// Parse the document's `<style>` elements; call once the page's final URL is known
+    pub fn load_inline_styles(&mut self, base_url: &str) {
+        let mut sources = Vec::new();
+        collect_style_elements(&self.dom, &mut sources);
+        for css in sources {
+            self.add_stylesheet(&css, base_url);
+        }
+    }
+    
+    /// Pick up the user's stylesheets for the document's site; does nothing when they haven't changed
+    pub fn refresh_user_styles(&mut self) {
+        let Some(url) = &self.document_url else {
+            return;
+        };
+        let styles = UserStyles::shared();
+        let Ok(styles) = styles.lock() else {
+            return;
+        };
+        if self.user_styles_revision == Some(styles.revision()) {
+            return;
+        }
+        self.user_styles_revision = Some(styles.revision());
+        let css = styles.css_for_url(url);
+        self.styles.set_user_stylesheet((!css.trim().is_empty()).then(|| css_parser::parse(&css)));
+    }
+    
+    /// Web fonts the page's stylesheets asked for since the last call
+    pub fn take_font_requests(&mut self) -> Vec<ResourceRequest> {
+        self.fonts.take_requests()
+    }
+    
+    /// Image sources that came near the viewport without a texture since the last call
+    pub fn take_visible_images(&self) -> Vec<String> {
+        self.visible_images.borrow_mut().drain().collect()
+    }
+    
+    /// Whether any <audio> or <video> on the page or its frames is playing sound, muted or not
+    pub fn is_playing_audio(&self) -> bool {
+        self.media.borrow().values().any(MediaPlayer::is_playing_audio)
+            || self.frames.borrow().values().any(|frame| frame.page().is_some_and(WebPage::is_playing_audio))
+    }
+    
+    /// Mute or unmute every media element on the page, including ones that haven't started yet
+    pub fn set_audio_muted(&self, muted: bool) {
+        self.audio_muted.set(muted);
+        for player in self.media.borrow_mut().values_mut() {
+            player.set_tab_muted(muted);
+        }
+        for frame in self.frames.borrow().values() {
+            if let Some(page) = frame.page() {
+                page.set_audio_muted(muted);
+            }
+        }
+    }
+    
+    /// Source URL of the <video> at `path` in the document, as its player is keyed
+    pub fn video_source(&self, path: &[usize]) -> Option<String> {
+        match self.dom.node_at(path)? {
+            DOMNode::Element { tag_name, attributes, children } if tag_name == "video" => {
+                media_source(attributes, children).and_then(|src| self.resolve_url(&src))
+            }
+            _ => None,
+        }
+    }
+    
+    /// Size of the picture of the video playing from `url`, once its first frame is decoded
+    pub fn video_size(&self, url: &str) -> Option<[usize; 2]> {
+        self.media.borrow().get(url).and_then(MediaPlayer::video_size)
+    }
+    
+    /// Mark the video playing from `url` as moved to the picture-in-picture window, or None once it's back
+    pub fn set_picture_in_picture(&self, url: Option<String>) {
+        self.pip_video.replace(url);
+    }
+    
+    /// Draw the video playing from `url` to fill `ui`, with its controls. False once the page no longer has it
+    pub fn render_picture_in_picture(&self, ui: &mut egui::Ui, url: &str) -> bool {
+        let mut players = self.media.borrow_mut();
+        let Some(player) = players.get_mut(url) else {
+            return false;
+        };
+        let controls_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+        let size = (ui.available_size() - egui::vec2(0.0, controls_height)).max(egui::Vec2::ZERO);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        self.paint_video(ui, rect, player, None);
+        if response.clicked() {
+            player.toggle();
+        }
+        self.render_media_controls(ui, player);
+        true
+    }
+    
+    /// The form submitted during the last render, as the navigation it asks for
+    pub fn take_form_submission(&self) -> Option<forms::FormSubmission> {
+        let (form, submitter) = self.form_submit.take()?;
+        let find = |node: *const DOMNode| self.dom.path_to(node).and_then(|path| self.dom.node_at(&path));
+        let base_url = self.document_url.as_deref()?;
+        forms::submit(find(form)?, &self.form_state.borrow(), submitter.and_then(find), base_url)
+    }
+    
+    /// Whether the page has a form asking for a password
+    pub fn has_login_form(&self) -> bool {
+        forms::find_login_form(&self.dom).is_some()
+    }
+    
+    /// Fill the page's login form with saved credentials, leaving fields the user already typed in alone
+    pub fn autofill_login(&self, username: &str, password: &str) -> bool {
+        let Some(fields) = forms::find_login_form(&self.dom) else {
+            return false;
+        };
+        let mut state = self.form_state.borrow_mut();
+        if let Some(field) = fields.username.filter(|field| state.value(field).is_empty()) {
+            state.set_value(field, username.to_string());
+        }
+        if state.value(fields.password).is_empty() {
+            state.set_value(fields.password, password.to_string());
+        }
+        true
+    }
+    
+    /// The element drawn at `pos` during the last render, if any
+    pub fn hit_test(&self, pos: egui::Pos2) -> Option<HitTarget> {
+        self.hit_boxes.hit_test(&self.dom, pos)
+    }
+    
     pub fn render(&self, ui: &mut egui::Ui) {
+        let started = std::time::Instant::now();
+        self.render_page(ui);
+        self.usage.add_layout(started.elapsed());
+    }
+    
+    fn render_page(&self, ui: &mut egui::Ui) {
+        // Pointer state comes from the boxes of the previous frame, before they're cleared
+        let (pointer, pressed) = ui.input(|i| (i.pointer.hover_pos(), i.pointer.primary_down()));
+        let hovered = pointer
+            .filter(|pos| ui.clip_rect().contains(*pos))
+            .map(|pos| self.hit_boxes.nodes_at(pos))
+            .unwrap_or_default();
+        // Keyboard focus moves between the boxes of the previous frame too
+        let focus_target = self.move_focus(ui);
+        self.styles.set_element_state(ElementState {
+            active: if pressed { hovered.clone() } else { HashSet::new() },
+            hovered,
+            visited: self.visited_links.clone(),
+            focused: self.focused.get(),
+            focus_visible: self.focus_visible.get(),
+        });
+        
+        self.hit_boxes.clear();
+        // Painting is only retained while nothing on the page may be pressed, dragged or selected, since
+        // replayed text doesn't respond to the pointer
+        let pointer_busy = ui.input(|i| i.pointer.any_down() || i.pointer.any_pressed() || i.pointer.any_released());
+        let selecting = egui::text_selection::LabelSelectionState::load(ui.ctx()).has_selection();
+        let font_atlas = Arc::as_ptr(&ui.ctx().fonts(|fonts| fonts.texture_atlas())) as usize;
+        let images = ImageMemory::shared().generation();
+        self.paint_frame.set((!pointer_busy && !selecting).then(|| (font_atlas, crate::ui::theme::NeonTheme::palette(), images)));
+        self.painted_images.borrow_mut().clear();
+        self.current_style.replace(ComputedStyle::default());
+        self.current_font.replace(egui::FontFamily::Proportional);
+        self.viewport.set(ui.clip_rect().size());
+        
         // Show progress indicator for large content if loading
         if let Some(progress) = &self.loading_progress {
             if progress.phase != LoadingPhase::Complete {
@@ -497,7 +1320,507 @@ impl WebPage {
         }
         
         // For now, render a simplified version of the DOM
+        self.positioned.borrow_mut().clear();
         self.render_dom_node(ui, &self.dom);
+        self.paint_positioned(ui);
+        self.finish_focus(ui, focus_target);
+        self.expose_accessibility(ui);
+    }
+    
+    /// Time spent on the page since it was loaded
+    pub fn cpu_time(&self) -> usage::CpuTime {
+        self.usage.time()
+    }
+    
+    /// Memory the page holds: its document, the source and text kept with it, and its decoded images
+    pub fn memory_estimate(&self) -> usage::MemoryEstimate {
+        let strings = self.raw_html.as_ref().map_or(0, String::capacity)
+            + self.plain_text.as_ref().map_or(0, String::capacity)
+            + self.extracted_title.as_ref().map_or(0, String::capacity)
+            + self.stylesheets.len() * std::mem::size_of::<css_parser::Stylesheet>();
+        let textures = self.images.values()
+            .map(|image| {
+                let size = image.size_vec2();
+                size.x as usize * size.y as usize * 4
+            })
+            .sum();
+        usage::MemoryEstimate { dom: usage::dom_bytes(&self.dom), strings, textures }
+    }
+    
+    /// href of the link activated from the keyboard during the last render, as written in the document
+    pub fn take_activated_link(&self) -> Option<String> {
+        self.activated_link.take()
+    }
+    
+    // Move focus on Tab, Shift+Tab and, from links and buttons, the arrow keys. Only does so while focus is
+    // on the page, or nowhere; the browser's own controls keep their keys. Returns the element focus moved to.
+    fn move_focus(&self, ui: &mut egui::Ui) -> Option<*const DOMNode> {
+        let widgets = self.focus_widgets.take();
+        if ui.input(|i| i.pointer.any_pressed()) {
+            self.focus_visible.set(false);
+        }
+        if self.last_focus_id.get().is_some() && self.focused.get().is_none() {
+            return None;
+        }
+        let current = self.focused.get();
+        let find = |node: *const DOMNode| self.dom.path_to(node).and_then(|path| self.dom.node_at(&path));
+        let focus_move = ui.input_mut(|i| {
+            if i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab) {
+                return Some(FocusMove::Previous);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Tab) {
+                return Some(FocusMove::Next);
+            }
+            // Text fields and lists use the arrow keys themselves
+            if current.and_then(find).is_none_or(focus::uses_arrow_keys) {
+                return None;
+            }
+            [
+                (egui::Key::ArrowUp, egui::Vec2::UP),
+                (egui::Key::ArrowDown, egui::Vec2::DOWN),
+                (egui::Key::ArrowLeft, egui::Vec2::LEFT),
+                (egui::Key::ArrowRight, egui::Vec2::RIGHT),
+            ].into_iter().find(|(key, _)| i.consume_key(egui::Modifiers::NONE, *key)).map(|(_, direction)| FocusMove::Toward(direction))
+        })?;
+        
+        // Only elements that were drawn can take focus
+        let target = match focus_move {
+            FocusMove::Toward(direction) => {
+                let from = self.hit_boxes.rect_of(current?)?;
+                let candidates = widgets.iter()
+                    .filter(|(node, _)| Some(*node) != current)
+                    .filter_map(|(node, _)| Some((*node, self.hit_boxes.rect_of(*node)?)));
+                focus::nearest_in_direction(from, direction, candidates)
+            }
+            FocusMove::Next | FocusMove::Previous => {
+                let order: Vec<*const DOMNode> = focus::tab_order(&self.dom).into_iter()
+                    .filter(|node| widgets.iter().any(|(drawn, _)| drawn == node))
+                    .collect();
+                focus::step(&order, current, focus_move == FocusMove::Previous)
+            }
+        }?;
+        self.focused.set(Some(target));
+        self.focus_visible.set(true);
+        Some(target)
+    }
+    
+    // Hand focus to the widget of the element it moved to, and ring the focused element
+    fn finish_focus(&self, ui: &mut egui::Ui, target: Option<*const DOMNode>) {
+        let widgets = self.focus_widgets.borrow();
+        let widget_of = |node: *const DOMNode| widgets.iter().find(|(drawn, _)| *drawn == node).map(|(_, id)| *id);
+        if let Some(node) = target {
+            if let Some(id) = widget_of(node) {
+                ui.memory_mut(|memory| memory.request_focus(id));
+            }
+            if let Some(rect) = self.hit_boxes.rect_of(node) {
+                ui.scroll_to_rect(rect, None);
+            }
+        }
+        
+        // Focus follows egui's, so clicking a control or pressing Escape moves it too
+        let focused_id = ui.memory(|memory| memory.focused());
+        let focused = focused_id.and_then(|id| widgets.iter().find(|(_, widget)| *widget == id));
+        self.last_focus_id.set(focused_id);
+        self.focused.set(focused.map(|(node, _)| *node));
+        let Some((node, id)) = focused else { return };
+        // Tab and the arrow keys come to the page instead of moving egui's focus
+        ui.memory_mut(|memory| memory.set_focus_lock_filter(*id, egui::EventFilter {
+            tab: true,
+            horizontal_arrows: true,
+            vertical_arrows: true,
+            escape: false,
+        }));
+        if self.focus_visible.get() {
+            if let Some(rect) = self.hit_boxes.rect_of(*node) {
+                ui.painter().rect_stroke(rect.expand(2.0), 3.0, egui::Stroke::new(2.0, crate::ui::theme::NeonTheme::neon_cyan()));
+            }
+        }
+    }
+    
+    // Remember the widget drawn for a focusable element, so keyboard focus can reach it
+    fn register_focusable(&self, node: &DOMNode, response: &egui::Response) {
+        self.focus_widgets.borrow_mut().push((node, response.id));
+    }
+    
+    /// The page as assistive technology sees it
+    pub fn accessibility_tree(&self) -> accessibility::AccessNode {
+        let hidden = |node: &DOMNode| self.styles.style_for(&self.dom, &self.stylesheets, self.viewport.get(), node).hidden;
+        let mut tree = accessibility::build(&self.dom, &hidden, &self.form_state.borrow(), self.document_url.as_deref());
+        tree.name = self.extracted_title.clone();
+        tree
+    }
+    
+    // Hand the accessibility tree to AccessKit, only while a screen reader is listening
+    fn expose_accessibility(&self, ui: &egui::Ui) {
+        let root_id = ui.id().with("accessibility");
+        if ui.ctx().accesskit_node_builder(root_id, |_| ()).is_none() {
+            return;
+        }
+        self.expose_access_node(ui.ctx(), root_id, &self.accessibility_tree());
+    }
+    
+    fn expose_access_node(&self, ctx: &egui::Context, id: egui::Id, node: &accessibility::AccessNode) {
+        ctx.accesskit_node_builder(id, |builder| {
+            node.write_to(builder);
+            // Boxes are in points, like egui's own widgets
+            if let Some(rect) = self.hit_boxes.rect_of(node.node) {
+                builder.set_bounds(egui::accesskit::Rect {
+                    x0: rect.min.x.into(),
+                    y0: rect.min.y.into(),
+                    x1: rect.max.x.into(),
+                    y1: rect.max.y.into(),
+                });
+            }
+        });
+        ctx.with_accessibility_parent(id, || {
+            for child in &node.children {
+                self.expose_access_node(ctx, id.with(child.node as usize), child);
+            }
+        });
+    }
+    
+    // Fixed elements relative to the visible area, and sticky ones held inside it, on top of the flow
+    fn paint_positioned(&self, ui: &mut egui::Ui) {
+        let queued = self.positioned.take();
+        let viewport = ui.clip_rect();
+        self.painting_positioned.set(true);
+        for item in queued {
+            let Some(node) = self.dom.path_to(item.node).and_then(|path| self.dom.node_at(&path)) else { continue };
+            let last_size = self.positioned_sizes.borrow().get(&item.node).copied();
+            let rect = match item.flow_rect {
+                Some(flow) => item.inset.sticky_rect(flow, viewport, item.parent.and_then(|parent| self.hit_boxes.rect_of(parent))),
+                None => item.inset.fixed_rect(viewport, last_size),
+            };
+            let mut child = ui.new_child(egui::UiBuilder::new().max_rect(rect).layout(egui::Layout::top_down(egui::Align::Min)));
+            self.hit_boxes.overlay(|| self.render_dom_node(&mut child, node));
+            // Boxes anchored to the right or bottom are placed by their size, which is only known once painted
+            if self.positioned_sizes.borrow().get(&item.node).copied() != last_size {
+                ui.ctx().request_repaint();
+            }
+        }
+        self.painting_positioned.set(false);
+    }
+    
+    // Reserve the image's declared size so the page doesn't jump when it loads
+    fn render_image_placeholder(&self, ui: &mut egui::Ui, attributes: &HashMap<String, String>, alt: &str, src: &str) -> egui::Rect {
+        use crate::ui::theme::NeonTheme;
+        
+        let dimension = |name: &str| length_attribute(attributes, name);
+        
+        match (dimension("width"), dimension("height")) {
+            (Some(width), Some(height)) => {
+                let scale = (ui.available_width() / width).min(1.0);
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height) * scale, egui::Sense::hover());
+                ui.painter().rect_filled(rect, 4.0, NeonTheme::elevated_bg());
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    format!("🖼️ {}", alt),
+                    egui::FontId::proportional(12.0),
+                    NeonTheme::muted_text(),
+                );
+                response.on_hover_text(src);
+                rect
+            }
+            _ => ui.label(
+                egui::RichText::new(format!("🖼️ {} [{}]", alt, src))
+                    .color(NeonTheme::muted_text())
+            ).rect,
+        }
+    }
+    
+    // Sized by its attributes or the image's own size, scaled down to fit
+    fn render_svg(&self, ui: &mut egui::Ui, document: &svg::SvgDocument, attributes: &HashMap<String, String>) {
+        // Only the visible part is painted
+        self.dynamic_paint.set(true);
+        let size = replaced_size(attributes, document.intrinsic_size());
+        let scale = (ui.available_width() / size.x).min(1.0);
+        let (rect, _) = ui.allocate_exact_size(size * scale, egui::Sense::hover());
+        if ui.is_rect_visible(rect) {
+            document.paint(&ui.painter().with_clip_rect(rect.intersect(ui.clip_rect())), rect);
+        }
+    }
+    
+    // A video has its picture above the controls; audio is only drawn with the `controls` attribute
+    fn render_media(&self, ui: &mut egui::Ui, kind: MediaKind, attributes: &HashMap<String, String>, children: &[DOMNode]) {
+        use crate::ui::theme::NeonTheme;
+        
+        let has = |name: &str| attributes.contains_key(name);
+        // Pages can't script their own controls yet, so a video always gets ours
+        if kind == MediaKind::Audio && !has("controls") {
+            return;
+        }
+        let Some(url) = media_source(attributes, children).and_then(|src| self.resolve_url(&src)) else {
+            return;
+        };
+        
+        let in_pip = self.pip_video.borrow().as_deref() == Some(url.as_str());
+        let mut players = self.media.borrow_mut();
+        let player = match players.entry(url) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                match MediaPlayer::open(entry.key(), kind, self.document_url.as_deref(), ui.ctx()) {
+                    Ok(mut player) => {
+                        player.looping = has("loop");
+                        player.set_muted(has("muted"));
+                        player.set_tab_muted(self.audio_muted.get());
+                        // Like other browsers, only muted media may start playing on its own
+                        if has("autoplay") && has("muted") {
+                            player.play();
+                        }
+                        entry.insert(player)
+                    }
+                    Err(e) => {
+                        ui.label(egui::RichText::new(format!("Media can't be played: {}", e)).color(NeonTheme::muted_text()));
+                        return;
+                    }
+                }
+            }
+        };
+        
+        if kind == MediaKind::Video {
+            let natural = player.video_size()
+                .map(|[width, height]| egui::vec2(width as f32, height as f32))
+                .unwrap_or(egui::vec2(300.0, 150.0));
+            let size = replaced_size(attributes, natural);
+            let scale = (ui.available_width() / size.x).min(1.0);
+            let (rect, response) = ui.allocate_exact_size(size * scale, egui::Sense::click());
+            if in_pip {
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+                painter.text(rect.center(), egui::Align2::CENTER_CENTER, "Playing in picture-in-picture", egui::FontId::proportional(14.0), NeonTheme::muted_text());
+            } else if ui.is_rect_visible(rect) {
+                let poster = attributes.get("poster").and_then(|poster| self.images.get(poster.trim()));
+                self.paint_video(ui, rect, player, poster);
+            }
+            if response.clicked() {
+                player.toggle();
+            }
+        }
+        
+        self.render_media_controls(ui, player);
+        if let Some(error) = player.error() {
+            ui.label(egui::RichText::new(error).color(NeonTheme::muted_text()).small());
+        }
+    }
+    
+    // The current frame, or the poster until there is one, letterboxed into `rect`
+    fn paint_video(&self, ui: &egui::Ui, rect: egui::Rect, player: &mut MediaPlayer, poster: Option<&ManagedImage>) {
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+        let full = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        let frame = player.frame_texture(ui.ctx()).map(|texture| (texture.id(), texture.size_vec2(), full));
+        let poster = || poster.and_then(|image| {
+            ImageMemory::shared().texture(ui.ctx(), image).map(|texture| (texture.id, image.size_vec2(), texture.uv))
+        });
+        if let Some((texture, size, uv)) = frame.or_else(poster) {
+            let fit = size * (rect.width() / size.x).min(rect.height() / size.y);
+            painter.image(texture, egui::Rect::from_center_size(rect.center(), fit), uv, egui::Color32::WHITE);
+        }
+        if !player.is_playing() {
+            painter.circle_filled(rect.center(), 24.0, egui::Color32::from_black_alpha(160));
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, NeonIcons::PLAY, egui::FontId::proportional(22.0), egui::Color32::WHITE);
+        }
+    }
+    
+    fn render_media_controls(&self, ui: &mut egui::Ui, player: &mut MediaPlayer) {
+        ui.horizontal(|ui| {
+            let icon = if player.is_playing() { NeonIcons::PAUSE } else { NeonIcons::PLAY };
+            if ui.small_button(icon).clicked() {
+                player.toggle();
+            }
+            let duration = player.duration().unwrap_or(0.0);
+            let mut position = player.position();
+            ui.label(egui::RichText::new(format!("{} / {}", media::format_time(position), media::format_time(duration))).monospace());
+            ui.spacing_mut().slider_width = (ui.available_width() - 120.0).clamp(60.0, 400.0);
+            let seek = ui.add_enabled(duration > 0.0, egui::Slider::new(&mut position, 0.0..=duration).show_value(false));
+            if seek.changed() {
+                player.seek(position);
+            }
+            let muted = player.is_muted();
+            let mute_icon = if muted { NeonIcons::SPEAKER_MUTED } else { NeonIcons::SPEAKER };
+            if ui.small_button(mute_icon).on_hover_text(if muted { "Unmute" } else { "Mute" }).clicked() {
+                player.set_muted(!muted);
+            }
+            let mut volume = player.volume();
+            ui.spacing_mut().slider_width = 60.0;
+            if ui.add(egui::Slider::new(&mut volume, 0.0..=1.0).show_value(false)).on_hover_text("Volume").changed() {
+                player.set_volume(volume);
+            }
+        });
+        if player.is_playing() {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+        }
+    }
+    
+    // Controls keep their values in `form_state`; submit buttons and Enter in a text field submit the enclosing form
+    fn render_input(&self, ui: &mut egui::Ui, node: &DOMNode) {
+        let enabled = node.get_attribute("disabled").is_none();
+        let kind = forms::input_type(node);
+        match kind.as_str() {
+            "hidden" => {}
+            "checkbox" => {
+                let mut checked = self.form_state.borrow().is_checked(node);
+                let response = ui.add_enabled(enabled, egui::Checkbox::without_text(&mut checked));
+                self.register_focusable(node, &response);
+                if response.changed() {
+                    self.form_state.borrow_mut().set_checked(node, checked);
+                }
+            }
+            "radio" => {
+                let checked = self.form_state.borrow().is_checked(node);
+                let response = ui.add_enabled(enabled, egui::RadioButton::new(checked, ""));
+                self.register_focusable(node, &response);
+                if response.clicked() {
+                    self.form_state.borrow_mut().set_checked(node, true);
+                }
+            }
+            "submit" | "image" | "reset" | "button" => {
+                let label = node.get_attribute("value").cloned().unwrap_or_else(|| match kind.as_str() {
+                    "reset" => "Reset".to_string(),
+                    "button" => String::new(),
+                    _ => "Submit".to_string(),
+                });
+                let response = ui.add_enabled(enabled, egui::Button::new(label));
+                self.register_focusable(node, &response);
+                if response.clicked() && forms::is_submit_button(node) {
+                    self.request_submit(Some(node));
+                }
+            }
+            _ => {
+                let mut value = self.form_state.borrow().value(node);
+                let width = node.get_attribute("size")
+                    .and_then(|size| size.trim().parse::<f32>().ok())
+                    .map_or(200.0, |size| size * 8.0);
+                let mut edit = egui::TextEdit::singleline(&mut value)
+                    .desired_width(width.min(ui.available_width()))
+                    .password(kind == "password");
+                if let Some(placeholder) = node.get_attribute("placeholder") {
+                    edit = edit.hint_text(placeholder.as_str());
+                }
+                let response = ui.add_enabled(enabled, edit);
+                self.register_focusable(node, &response);
+                if response.changed() {
+                    self.form_state.borrow_mut().set_value(node, value);
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.request_submit(None);
+                }
+            }
+        }
+    }
+    
+    fn render_textarea(&self, ui: &mut egui::Ui, node: &DOMNode) {
+        let mut value = self.form_state.borrow().value(node);
+        let rows = node.get_attribute("rows").and_then(|rows| rows.trim().parse().ok()).unwrap_or(2);
+        let mut edit = egui::TextEdit::multiline(&mut value).desired_rows(rows);
+        if let Some(placeholder) = node.get_attribute("placeholder") {
+            edit = edit.hint_text(placeholder.as_str());
+        }
+        let response = ui.add_enabled(node.get_attribute("disabled").is_none(), edit);
+        self.register_focusable(node, &response);
+        if response.changed() {
+            self.form_state.borrow_mut().set_value(node, value);
+        }
+    }
+    
+    fn render_select(&self, ui: &mut egui::Ui, node: &DOMNode) {
+        let options = forms::select_options(node);
+        let mut value = self.form_state.borrow().value(node);
+        let selected = options.iter()
+            .find(|(option, _)| *option == value)
+            .map(|(_, label)| label.clone())
+            .unwrap_or_default();
+        let before = value.clone();
+        ui.add_enabled_ui(node.get_attribute("disabled").is_none(), |ui| {
+            let response = egui::ComboBox::from_id_salt(node as *const DOMNode)
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (option, label) in &options {
+                        ui.selectable_value(&mut value, option.clone(), label);
+                    }
+                })
+                .response;
+            self.register_focusable(node, &response);
+        });
+        if value != before {
+            self.form_state.borrow_mut().set_value(node, value);
+        }
+    }
+    
+    fn request_submit(&self, submitter: Option<&DOMNode>) {
+        if let Some(form) = self.current_form.get() {
+            self.form_submit.set(Some((form, submitter.map(|button| button as *const DOMNode))));
+        }
+    }
+    
+    // The framed document in a clipped box of the iframe's size, or why it couldn't be shown
+    fn render_iframe(&self, ui: &mut egui::Ui, node: &DOMNode, attributes: &HashMap<String, String>) {
+        use crate::ui::theme::NeonTheme;
+        
+        let size = replaced_size(attributes, egui::vec2(300.0, 150.0));
+        let scale = (ui.available_width() / size.x).min(1.0);
+        let (rect, _) = ui.allocate_exact_size(size * scale, egui::Sense::hover());
+        if attributes.get("frameborder").is_none_or(|border| border.trim() != "0") {
+            ui.painter().rect_stroke(rect, 0.0, egui::Stroke::new(1.0, NeonTheme::border_color()));
+        }
+        
+        let Some(path) = self.dom.path_to(node) else { return };
+        let source = attributes.get("srcdoc").or_else(|| attributes.get("src")).cloned().unwrap_or_default();
+        let key = (path, source);
+        let scroll_id = egui::Id::new(("iframe", &key));
+        let mut frames = self.frames.borrow_mut();
+        let frame = match frames.entry(key) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let Some(parent_url) = self.document_url.clone() else { return };
+                if self.frame_ancestors.len() >= frames::MAX_FRAME_DEPTH {
+                    return;
+                }
+                let sandbox = Sandbox::from_attribute(attributes.get("sandbox").map(String::as_str));
+                let mut ancestors = self.frame_ancestors.clone();
+                ancestors.push(parent_url);
+                let referrer_policy = attributes.get("referrerpolicy")
+                    .and_then(|policy| ReferrerPolicy::from_token(policy))
+                    .unwrap_or_else(|| self.referrer_policy());
+                let frame = match (attributes.get("srcdoc"), attributes.get("src").and_then(|src| self.resolve_url(src))) {
+                    (Some(html), _) => Frame::from_srcdoc(html, sandbox, ancestors, self.cookie_jar.clone(), referrer_policy, self.tracking.clone()),
+                    (None, Some(url)) => Frame::open(&url, sandbox, ancestors, self.cookie_jar.clone(), referrer_policy, self.tracking.clone()),
+                    (None, None) => return,
+                };
+                entry.insert(frame)
+            }
+        };
+        frame.poll(ui.ctx());
+        if !ui.is_rect_visible(rect) {
+            return;
+        }
+        
+        let message = |text: &str| {
+            ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(12.0), NeonTheme::muted_text());
+        };
+        match (frame.page(), frame.error()) {
+            (_, Some(error)) => message(error),
+            (Some(page), None) => {
+                let mut child = ui.new_child(egui::UiBuilder::new().max_rect(rect.shrink(1.0)));
+                child.set_clip_rect(rect.shrink(1.0).intersect(ui.clip_rect()));
+                egui::ScrollArea::both()
+                    .id_salt(scroll_id)
+                    .auto_shrink([false; 2])
+                    .show(&mut child, |ui| page.render(ui));
+            }
+            (None, None) if frame.is_loading() => message(&format!("Loading {}…", frame.url())),
+            (None, None) => {}
+        }
+    }
+    
+    // `src` resolved against the document's URL
+    fn resolve_url(&self, src: &str) -> Option<String> {
+        let resolved = match &self.document_url {
+            Some(base) => url::Url::parse(base).ok()?.join(src.trim()).ok()?,
+            None => url::Url::parse(src.trim()).ok()?,
+        };
+        matches!(resolved.scheme(), "http" | "https").then(|| resolved.to_string())
     }
     
     fn render_progress_indicator(&self, ui: &mut egui::Ui, progress: &LoadingProgress) {
@@ -560,10 +1883,160 @@ impl WebPage {
     }
     
     fn render_dom_node(&self, ui: &mut egui::Ui, node: &DOMNode) {
+        if !node.is_element() {
+            self.render_dom_node_contents(ui, node);
+            return;
+        }
+        
+        let style = self.styles.style_for(&self.dom, &self.stylesheets, self.viewport.get(), node);
+        if style.hidden {
+            return;
+        }
+        if style.position != Position::Static && !self.painting_positioned.get() {
+            let key = node as *const DOMNode;
+            let last_size = self.positioned_sizes.borrow().get(&key).copied();
+            let flow_rect = match (style.position, last_size) {
+                (Position::Sticky, Some(size)) => Some(ui.allocate_exact_size(egui::vec2(ui.available_width(), size.y), egui::Sense::hover()).0),
+                _ => None,
+            };
+            // Sticky elements are measured in the flow the first time, fixed ones take no space in it
+            if flow_rect.is_some() || style.position == Position::Fixed {
+                self.dynamic_paint.set(true);
+                self.positioned.borrow_mut().push(PositionedBox { node: key, inset: style.inset, flow_rect, parent: self.current_element.get() });
+                return;
+            }
+        }
+        let parent = self.current_style.replace(style.clone());
+        // Resolving a family goes through the shared registry, so only do it when the font changes
+        let parent_font = (style.font != parent.font)
+            .then(|| self.current_font.replace(self.fonts.family(&style.font)));
+        
+        let parent_element = self.current_element.replace(Some(node));
+        let mut drawn = egui::Rect::NOTHING;
+        
+        // Transformed elements are painted in place, then their shapes are moved
+        let first_shape = (!style.transform.is_identity()).then(|| ui.painter().add(egui::Shape::Noop));
+        
+        let key = self.paint_frame.get().filter(|_| !self.styles.is_animating(node)).map(|(font_atlas, palette, images)| PaintKey {
+            style_revision: self.styles.revision(),
+            font_atlas,
+            palette,
+            images,
+            width: ui.available_width(),
+        });
+        let outer_dynamic = self.dynamic_paint.replace(false);
+        
+        // A scope per element gives us the rect it covered, which widgets alone don't report
+        self.hit_boxes.record(node, || {
+            if let Some(rect) = key.and_then(|key| self.paint_cache.replay(node, key, ui, &self.hit_boxes, &self.painted_images)) {
+                drawn = rect;
+                return drawn;
+            }
+            let first_shape = paint_cache::next_shape(ui);
+            let first_box = self.hit_boxes.mark();
+            let first_image = self.painted_images.borrow().len();
+            drawn = ui.scope(|ui| {
+                if style.opacity < 1.0 {
+                    ui.multiply_opacity(style.opacity);
+                }
+                let align = match style.text_align {
+                    TextAlign::Left => egui::Align::Min,
+                    TextAlign::Center => egui::Align::Center,
+                    TextAlign::Right => egui::Align::Max,
+                };
+                let contents = |ui: &mut egui::Ui| {
+                    if style.text_align != parent.text_align {
+                        ui.with_layout(egui::Layout::top_down(align), |ui| self.render_dom_node_contents(ui, node));
+                    } else {
+                        self.render_dom_node_contents(ui, node);
+                    }
+                };
+                match style.background_color {
+                    Some(fill) => {
+                        egui::Frame::none().fill(fill).show(ui, contents);
+                    }
+                    None => contents(ui),
+                }
+            }).response.rect;
+            // Widgets paint only what's visible, so only a subtree shown whole can be replayed elsewhere
+            if let Some(key) = key.filter(|_| !self.dynamic_paint.get() && ui.clip_rect().contains_rect(drawn)) {
+                let images = self.painted_images.borrow()[first_image..].to_vec();
+                let shapes = paint_cache::shapes_since(ui, first_shape);
+                self.paint_cache.record(node, key, drawn, shapes, self.hit_boxes.since(first_box), images);
+            }
+            drawn
+        });
+        self.dynamic_paint.set(outer_dynamic || key.is_none() || self.dynamic_paint.get());
+        
+        if let Some(start) = first_shape {
+            let end = ui.painter().add(egui::Shape::Noop);
+            let transform = style.transform.around(drawn.center());
+            ui.ctx().graphics_mut(|graphics| graphics.entry(ui.layer_id()).transform_range(start, end, transform));
+        }
+        if style.position != Position::Static {
+            self.positioned_sizes.borrow_mut().insert(node, drawn.size());
+        }
+        self.current_element.set(parent_element);
+        self.current_style.replace(parent);
+        if let Some(parent_font) = parent_font {
+            self.current_font.replace(parent_font);
+        }
+    }
+    
+    // `text` in the computed style of the element being rendered
+    fn styled_text(&self, text: impl Into<String>) -> egui::RichText {
+        self.current_style.borrow().rich_text(text, self.current_font.borrow().clone())
+    }
+    
+    // Text inside `node` as one job, each inline descendant in its own computed style
+    fn inline_text(&self, node: &DOMNode) -> egui::text::LayoutJob {
+        let mut job = egui::text::LayoutJob::default();
+        // The element's own background is already filled behind the whole block
+        let style = ComputedStyle { background_color: None, ..self.current_style.borrow().clone() };
+        let family = self.current_font.borrow().clone();
+        if let DOMNode::Element { children, .. } = node {
+            for child in children {
+                self.append_inline_runs(&mut job, child, &style, &family);
+            }
+        }
+        job
+    }
+    
+    fn append_inline_runs(&self, job: &mut egui::text::LayoutJob, node: &DOMNode, style: &ComputedStyle, family: &egui::FontFamily) {
+        match node {
+            DOMNode::Text(text) => {
+                let words: Vec<&str> = text.split_whitespace().collect();
+                if words.is_empty() {
+                    return;
+                }
+                if !job.text.is_empty() {
+                    job.append(" ", 0.0, style.text_format(family.clone()));
+                }
+                job.append(&words.join(" "), 0.0, style.text_format(family.clone()));
+            }
+            DOMNode::Element { children, .. } => {
+                let own = self.styles.style_for(&self.dom, &self.stylesheets, self.viewport.get(), node);
+                if own.hidden {
+                    return;
+                }
+                let own_family = if own.font != style.font { self.fonts.family(&own.font) } else { family.clone() };
+                for child in children {
+                    self.append_inline_runs(job, child, &own, &own_family);
+                }
+            }
+            DOMNode::Comment(_) => {}
+        }
+    }
+    
+    fn render_dom_node_contents(&self, ui: &mut egui::Ui, node: &DOMNode) {
         use crate::ui::theme::NeonTheme;
         
         match node {
             DOMNode::Element { tag_name, attributes, children } => {
+                // Widgets that respond to the pointer or keyboard are drawn afresh every frame
+                if matches!(tag_name.as_str(), "a" | "input" | "textarea" | "select" | "button" | "iframe" | "video" | "audio") {
+                    self.dynamic_paint.set(true);
+                }
                 match tag_name.as_str() {
                     "html" | "body" => {
                         // Render children directly
@@ -572,130 +2045,55 @@ impl WebPage {
                         }
                     }
                     "div" => {
-                        // Enhanced div rendering with better styling
-                        let style_attr = attributes.get("style").cloned();
-                        let class_attr = attributes.get("class").cloned();
-                        
-                        if let Some(style) = style_attr {
-                            if style.contains("text-align: center") {
-                                ui.centered_and_justified(|ui| {
-                                    for child in children {
-                                        self.render_dom_node(ui, child);
-                                    }
-                                });
-                            } else {
-                                egui::Frame::none()
-                                    .inner_margin(egui::Margin::same(8.0))
-                                    .show(ui, |ui| {
-                                        for child in children {
-                                            self.render_dom_node(ui, child);
-                                        }
-                                    });
+                        ui.vertical(|ui| {
+                            for child in children {
+                                self.render_dom_node(ui, child);
                             }
-                        } else {
-                            ui.vertical(|ui| {
-                                for child in children {
-                                    self.render_dom_node(ui, child);
-                                }
-                            });
-                        }
+                        });
                     }
                     "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
-                        let (size, color) = match tag_name.as_str() {
-                            "h1" => (28.0, NeonTheme::NEON_CYAN),
-                            "h2" => (24.0, NeonTheme::NEON_BLUE),
-                            "h3" => (20.0, NeonTheme::PRIMARY_TEXT),
-                            "h4" => (18.0, NeonTheme::PRIMARY_TEXT),
-                            "h5" => (16.0, NeonTheme::SECONDARY_TEXT),
-                            "h6" => (14.0, NeonTheme::SECONDARY_TEXT),
-                            _ => (16.0, NeonTheme::PRIMARY_TEXT),
-                        };
-                        
-                        let text = self.extract_text(node);
                         ui.add_space(8.0);
-                        ui.label(egui::RichText::new(text).size(size).strong().color(color));
+                        ui.label(self.inline_text(node));
                         ui.add_space(4.0);
                     }
                     "p" => {
-                        let text = self.extract_text(node);
-                        if !text.trim().is_empty() {
-                            ui.label(
-                                egui::RichText::new(text)
-                                    .size(14.0)
-                                    .color(NeonTheme::PRIMARY_TEXT)
-                            );
+                        let text = self.inline_text(node);
+                        if !text.is_empty() {
+                            ui.label(text);
                             ui.add_space(8.0);
                         }
                     }
                     "a" => {
-                        let text = self.extract_text(node);
-                        let href = attributes.get("href").cloned().unwrap_or_default();
+                        let text = self.inline_text(node);
                         
-                        if !text.trim().is_empty() {
-                            let link = ui.add(
-                                egui::Label::new(
-                                    egui::RichText::new(&text)
-                                        .color(NeonTheme::NEON_BLUE)
-                                        .underline()
-                                )
-                                .sense(egui::Sense::click())
-                            );
-                            
-                            if link.clicked() {
-                                log::info!("Link clicked: {} -> {}", text, href);
-                                // Navigation handled by browser tab system
-                            }
-                            
-                            if link.hovered() {
-                                link.on_hover_text(format!("Navigate to: {}", href));
+                        // Clicks and the hovered URL are resolved through hit testing by the browser tab
+                        if !text.is_empty() {
+                            let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .on_hover_cursor(egui::CursorIcon::PointingHand);
+                            if let Some(href) = attributes.get("href") {
+                                self.register_focusable(node, &response);
+                                if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter) || i.key_pressed(egui::Key::Space)) {
+                                    self.activated_link.replace(Some(href.trim().to_string()));
+                                }
                             }
                         }
                     }
-                    "strong" | "b" => {
-                        let text = self.extract_text(node);
-                        if !text.trim().is_empty() {
-                            ui.label(
-                                egui::RichText::new(text)
-                                    .strong()
-                                    .color(NeonTheme::PRIMARY_TEXT)
-                            );
-                        }
-                    }
-                    "em" | "i" => {
-                        let text = self.extract_text(node);
-                        if !text.trim().is_empty() {
-                            ui.label(
-                                egui::RichText::new(text)
-                                    .italics()
-                                    .color(NeonTheme::SECONDARY_TEXT)
-                            );
-                        }
-                    }
-                    "code" => {
-                        let text = self.extract_text(node);
-                        if !text.trim().is_empty() {
-                            ui.label(
-                                egui::RichText::new(text)
-                                    .monospace()
-                                    .background_color(NeonTheme::ELEVATED_BG)
-                                    .color(NeonTheme::NEON_GREEN)
-                            );
+                    "strong" | "b" | "em" | "i" | "code" => {
+                        let text = self.inline_text(node);
+                        if !text.is_empty() {
+                            ui.label(text);
                         }
                     }
                     "pre" => {
+                        // The fill comes from the computed background
                         let text = self.extract_text(node);
                         if !text.trim().is_empty() {
                             egui::Frame::none()
-                                .fill(NeonTheme::DARKER_BG)
-                                .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
+                                .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
                                 .rounding(egui::Rounding::same(4.0))
                                 .inner_margin(egui::Margin::same(8.0))
                                 .show(ui, |ui| {
-                                    ui.label(
-                                        egui::RichText::new(text)
-                                            .monospace()
-                                            .color(NeonTheme::PRIMARY_TEXT)
-                                    );
+                                    ui.label(self.styled_text(text));
                                 });
                         }
                     }
@@ -706,7 +2104,7 @@ impl WebPage {
                                     if child_tag == "li" {
                                         ui.horizontal(|ui| {
                                             let bullet = if tag_name == "ul" { "•" } else { &format!("{}.", i + 1) };
-                                            ui.label(egui::RichText::new(bullet).color(NeonTheme::NEON_CYAN));
+                                            ui.label(egui::RichText::new(bullet).color(NeonTheme::neon_cyan()));
                                             self.render_dom_node(ui, child);
                                         });
                                     }
@@ -734,15 +2132,71 @@ impl WebPage {
                         let src = attributes.get("src").cloned().unwrap_or_default();
                         let alt = attributes.get("alt").cloned().unwrap_or_else(|| "Image".to_string());
                         
-                        // For now, show a placeholder
-                        ui.label(
-                            egui::RichText::new(format!("🖼️ {} [{}]", alt, src))
-                                .color(NeonTheme::MUTED_TEXT)
-                        );
+                        if let Some(document) = self.svg_images.get(src.trim()) {
+                            self.render_svg(ui, document, attributes);
+                        } else if let Some(image) = self.images.get(src.trim()) {
+                            // Scale down to the available width, never up
+                            let size = image.size_vec2();
+                            let scale = (ui.available_width() / size.x).min(1.0);
+                            let (rect, response) = ui.allocate_exact_size(size * scale, egui::Sense::hover());
+                            // Only images on screen take texture memory
+                            if ui.is_rect_visible(rect) {
+                                if let Some(texture) = ImageMemory::shared().texture(ui.ctx(), image) {
+                                    egui::Image::new((texture.id, rect.size())).uv(texture.uv).paint_at(ui, rect);
+                                    self.painted_images.borrow_mut().push((image.id(), rect));
+                                }
+                            }
+                            response.on_hover_text(alt);
+                        } else {
+                            self.dynamic_paint.set(true);
+                            let placeholder = self.render_image_placeholder(ui, attributes, &alt, &src);
+                            
+                            // Lazy images start loading once their placeholder is close to the visible area
+                            let clip = ui.clip_rect();
+                            let viewport = VirtualScrollConfig::for_span(clip.top(), clip.height());
+                            if viewport.is_near_viewport(placeholder.top(), placeholder.bottom(), LAZY_IMAGE_MARGIN) {
+                                self.visible_images.borrow_mut().insert(src.trim().to_string());
+                            }
+                        }
+                    }
+                    "form" => {
+                        let parent_form = self.current_form.replace(Some(node as *const DOMNode));
+                        ui.vertical(|ui| {
+                            for child in children {
+                                self.render_dom_node(ui, child);
+                            }
+                        });
+                        self.current_form.set(parent_form);
+                    }
+                    "input" => self.render_input(ui, node),
+                    "textarea" => self.render_textarea(ui, node),
+                    "select" => self.render_select(ui, node),
+                    "button" => {
+                        let label = node.collect_text().trim().to_string();
+                        let enabled = attributes.get("disabled").is_none();
+                        let response = ui.add_enabled(enabled, egui::Button::new(label));
+                        self.register_focusable(node, &response);
+                        if response.clicked() && forms::is_submit_button(node) {
+                            self.request_submit(Some(node));
+                        }
+                    }
+                    "iframe" => {
+                        self.render_iframe(ui, node, attributes);
+                    }
+                    "video" | "audio" => {
+                        let kind = if tag_name == "video" { MediaKind::Video } else { MediaKind::Audio };
+                        self.render_media(ui, kind, attributes, children);
+                    }
+                    "svg" => {
+                        let document = self.inline_svgs.borrow_mut()
+                            .entry(node as *const DOMNode)
+                            .or_insert_with(|| Rc::new(svg::SvgDocument::from_element(node)))
+                            .clone();
+                        self.render_svg(ui, &document, attributes);
                     }
                     "table" => {
                         egui::Frame::none()
-                            .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
+                            .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
                             .show(ui, |ui| {
                                 for child in children {
                                     self.render_dom_node(ui, child);
@@ -757,19 +2211,13 @@ impl WebPage {
                         });
                     }
                     "td" | "th" => {
-                        let text = self.extract_text(node);
-                        let rich_text = if tag_name == "th" {
-                            egui::RichText::new(text).strong().color(NeonTheme::NEON_CYAN)
-                        } else {
-                            egui::RichText::new(text).color(NeonTheme::PRIMARY_TEXT)
-                        };
-                        ui.label(rich_text);
+                        ui.label(self.inline_text(node));
                         ui.separator();
                     }
                     "blockquote" => {
                         ui.indent("blockquote", |ui| {
                             egui::Frame::none()
-                                .stroke(egui::Stroke::new(3.0, NeonTheme::NEON_PURPLE))
+                                .stroke(egui::Stroke::new(3.0, NeonTheme::neon_purple()))
                                 .inner_margin(egui::Margin::symmetric(12.0, 8.0))
                                 .show(ui, |ui| {
                                     for child in children {
@@ -778,9 +2226,6 @@ impl WebPage {
                                 });
                         });
                     }
-                    "style" | "script" | "head" | "title" | "meta" | "link" => {
-                        // Skip these elements - they don't produce visible content
-                    }
                     _ => {
                         // Default rendering for unknown elements
                         for child in children {
@@ -792,11 +2237,7 @@ impl WebPage {
             DOMNode::Text(text) => {
                 let trimmed = text.trim();
                 if !trimmed.is_empty() {
-                    ui.label(
-                        egui::RichText::new(trimmed)
-                            .color(NeonTheme::PRIMARY_TEXT)
-                            .size(14.0)
-                    );
+                    ui.label(self.styled_text(trimmed));
                 }
             },
             DOMNode::Comment(comment) => {
@@ -804,7 +2245,7 @@ impl WebPage {
                 if cfg!(debug_assertions) {
                     ui.label(
                         egui::RichText::new(format!("<!-- {} -->", comment))
-                            .color(NeonTheme::MUTED_TEXT)
+                            .color(NeonTheme::muted_text())
                             .size(10.0)
                             .italics()
                     );
@@ -827,6 +2268,99 @@ impl WebPage {
     }
 }
 
+// hrefs of the document's links whose absolute URL is in the history
+// A width or height attribute in pixels
+fn length_attribute(attributes: &HashMap<String, String>, name: &str) -> Option<f32> {
+    attributes.get(name)
+        .and_then(|v| v.trim().trim_end_matches("px").parse::<f32>().ok())
+        .filter(|v| *v > 0.0)
+}
+
+// The element's width and height attributes win over its content's own size, keeping the aspect ratio when only one is set
+fn replaced_size(attributes: &HashMap<String, String>, intrinsic: egui::Vec2) -> egui::Vec2 {
+    match (length_attribute(attributes, "width"), length_attribute(attributes, "height")) {
+        (Some(width), Some(height)) => egui::vec2(width, height),
+        (Some(width), None) => egui::vec2(width, width * intrinsic.y / intrinsic.x),
+        (None, Some(height)) => egui::vec2(height * intrinsic.x / intrinsic.y, height),
+        (None, None) => intrinsic,
+    }
+}
+
+// The element's src, or else its first <source> in a format we can play
+fn media_source(attributes: &HashMap<String, String>, children: &[DOMNode]) -> Option<String> {
+    if let Some(src) = attributes.get("src").filter(|src| !src.trim().is_empty()) {
+        return Some(src.clone());
+    }
+    children.iter().find_map(|child| match child {
+        DOMNode::Element { tag_name, attributes, .. } if tag_name == "source" => {
+            let playable = attributes.get("type").is_none_or(|mime| media::can_play(mime));
+            attributes.get("src").filter(|_| playable).cloned()
+        }
+        _ => None,
+    })
+}
+
+fn visited_links(dom: &DOMNode, base_url: &str) -> HashSet<String> {
+    fn collect<'a>(node: &'a DOMNode, hrefs: &mut HashSet<&'a str>) {
+        if let DOMNode::Element { tag_name, attributes, children } = node {
+            if let Some(href) = attributes.get("href").filter(|_| tag_name == "a" || tag_name == "area") {
+                hrefs.insert(href.trim());
+            }
+            children.iter().for_each(|child| collect(child, hrefs));
+        }
+    }
+    
+    let Some(history) = crate::storage::HistoryDatabase::shared() else {
+        return HashSet::new();
+    };
+    let Ok(base) = url::Url::parse(base_url) else {
+        return HashSet::new();
+    };
+    let mut hrefs = HashSet::new();
+    collect(dom, &mut hrefs);
+    hrefs.into_iter()
+        .filter(|href| base.join(href).is_ok_and(|url| history.is_visited(url.as_str()).unwrap_or(false)))
+        .map(str::to_string)
+        .collect()
+}
+
+// The policy of the document's last `<meta name="referrer">` with a recognised value
+fn meta_referrer_policy(node: &DOMNode) -> Option<ReferrerPolicy> {
+    let DOMNode::Element { tag_name, attributes, children } = node else { return None };
+    let own = (tag_name == "meta")
+        .then(|| attributes.get("name").filter(|name| name.trim().eq_ignore_ascii_case("referrer")))
+        .flatten()
+        .and_then(|_| attributes.get("content"))
+        .and_then(|content| ReferrerPolicy::from_meta(content));
+    children.iter().filter_map(meta_referrer_policy).next_back().or(own)
+}
+
+// Text of every inline classic <script> element, in document order
+fn collect_inline_scripts(node: &DOMNode, sources: &mut Vec<String>) {
+    if let DOMNode::Element { tag_name, attributes, children } = node {
+        if tag_name.eq_ignore_ascii_case("script") {
+            let classic = attributes.get("type").map(|kind| kind.trim().to_ascii_lowercase())
+                .is_none_or(|kind| matches!(kind.as_str(), "" | "text/javascript" | "application/javascript" | "text/ecmascript"));
+            if classic && !attributes.contains_key("src") {
+                sources.push(node.collect_text());
+            }
+        } else {
+            children.iter().for_each(|child| collect_inline_scripts(child, sources));
+        }
+    }
+}
+
+// Text of every <style> element, in document order
+fn collect_style_elements(node: &DOMNode, sources: &mut Vec<String>) {
+    if let DOMNode::Element { tag_name, children, .. } = node {
+        if tag_name == "style" {
+            sources.push(node.collect_text());
+        } else {
+            children.iter().for_each(|child| collect_style_elements(child, sources));
+        }
+    }
+}
+
 fn extract_title(html: &str) -> Option<String> {
     use regex::Regex;
     let re = Regex::new("(?is)<title>(.*?)</title>").ok()?; // (?i) case-insensitive, (?s) dot matches newline
@@ -863,4 +2397,165 @@ fn strip_html(html: &str) -> String {
     } else {
         result
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_scripts_change_the_page() {
+        let html = r#"<h1 id="title">Old</h1><script>if (1 < 2) {}
+            document.getElementById('title').textContent = 'New'; console.log('ran')</script>
+            <script type="application/ld+json">{"@type": "Article"}</script>"#;
+        let mut page = WebPage::from_html(html, None);
+        page.run_inline_scripts();
+        assert!(page.dom.collect_text().contains("New"));
+        // The engine skips the `if` with a warning and carries on
+        let output = page.js_engine.as_ref().unwrap().take_console_output();
+        assert!(output[0].starts_with("[WARN] Statement not supported"));
+        assert_eq!(output[1], "[LOG] ran");
+
+        let mut blocked = WebPage::from_html(html, None);
+        blocked.set_scripts_enabled(false);
+        blocked.run_inline_scripts();
+        assert!(blocked.dom.collect_text().contains("Old"));
+        assert!(blocked.js_engine.is_none());
+    }
+
+    #[test]
+    fn test_script_errors_have_locations() {
+        let mut page = WebPage::from_html("<script>var a = 1\nmissing\nconsole.log('after')</script>", None);
+        page.run_inline_scripts();
+        let errors = page.take_script_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity, crate::js::ErrorSeverity::Error);
+        assert_eq!(errors[0].location(), "inline script 1:2");
+        // The error stops the script, as an uncaught exception would
+        let output = page.js_engine.as_ref().unwrap().take_console_output();
+        assert_eq!(output, vec!["[ERROR] Uncaught ReferenceError: missing is not defined (inline script 1:2)".to_string()]);
+    }
+
+    #[test]
+    fn test_scripts_share_cookies_with_requests() {
+        let jar = Arc::new(Mutex::new(CookieManager::new()));
+        jar.lock().unwrap().parse_set_cookie_header("session=abc; Path=/; HttpOnly", "example.com");
+        let mut page = WebPage::from_html("<script>document.cookie = 'theme=dark'\nvar seen = document.cookie\nconsole.log(seen)</script>", None);
+        page.set_document_url("https://example.com/");
+        page.set_cookie_jar(jar.clone());
+        page.run_inline_scripts();
+        let output = page.js_engine.as_ref().unwrap().take_console_output();
+        assert_eq!(output, vec!["[LOG] theme=dark".to_string()]);
+        assert_eq!(jar.lock().unwrap().get_cookie_header_for_request("example.com", "/", true).as_deref(), Some("session=abc; theme=dark"));
+    }
+
+    #[test]
+    fn test_scripts_use_the_builtins() {
+        let mut page = WebPage::from_html("<script>const cart = JSON.parse('{\"items\":[{\"name\":\"pen\",\"price\":2}]}')\n\
+            cart.items.push({ name: 'ink', price: 5.5 })\n\
+            cart.owner = 'ada'\n\
+            cart.items.forEach(item => console.log(`${item.name}: ${item.price.toFixed(2)}`))\n\
+            console.log(JSON.stringify(cart))</script>", None);
+        page.run_inline_scripts();
+        let output = page.js_engine.as_ref().unwrap().take_console_output();
+        assert_eq!(output, vec![
+            "[LOG] pen: 2.00".to_string(),
+            "[LOG] ink: 5.50".to_string(),
+            r#"[LOG] {"items":[{"name":"pen","price":2},{"name":"ink","price":5.5}],"owner":"ada"}"#.to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_script_changes_restyle_only_what_changed() {
+        fn by_id<'a>(node: &'a DOMNode, id: &str) -> Option<&'a DOMNode> {
+            if node.get_attribute("id").is_some_and(|own| own == id) {
+                return Some(node);
+            }
+            let DOMNode::Element { children, .. } = node else { return None };
+            children.iter().find_map(|child| by_id(child, id))
+        }
+        
+        let mut page = WebPage::from_html("<style>p { color: blue } .on { color: red }</style><p id=\"a\">one</p><p id=\"b\">two</p>\
+            <script>document.getElementById('b').setAttribute('class', 'on')</script>", None);
+        page.load_inline_styles("https://app.example/");
+        let viewport = egui::vec2(800.0, 600.0);
+        let color = |page: &WebPage, id: &str| page.styles.style_for(&page.dom, &page.stylesheets, viewport, by_id(&page.dom, id).unwrap()).color;
+        let blue = color(&page, "b");
+        let untouched = by_id(&page.dom, "a").unwrap() as *const DOMNode;
+        
+        page.run_inline_scripts();
+        assert_eq!(page.dom_generation(), 1);
+        assert!(std::ptr::eq(by_id(&page.dom, "a").unwrap(), untouched));
+        assert_eq!(color(&page, "a"), blue);
+        assert_ne!(color(&page, "b"), blue);
+        
+        let unchanged = page.dom.clone();
+        assert!(!page.update_dom(&unchanged));
+        assert_eq!(page.dom_generation(), 1);
+    }
+    
+    #[test]
+    fn test_static_content_is_painted_from_the_cache() {
+        let page = WebPage::from_html("<h1>Title</h1><p>Some <b>static</b> text</p><div><a href=\"/next\">link</a></div>", None);
+        let ctx = egui::Context::default();
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 300.0))),
+            ..Default::default()
+        };
+        let frame = || ctx.run(input.clone(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| page.render(ui));
+        }).shapes;
+        
+        frame();
+        // The heading and the paragraph; the link responds to the pointer, so it and the elements around it are
+        // painted every frame
+        assert_eq!(page.paint_cache.len(), 2);
+        let replayed = frame();
+        assert!(page.hit_test(egui::pos2(20.0, 20.0)).is_some_and(|target| target.tag_name == "h1"));
+        page.paint_cache.clear();
+        assert_eq!(frame(), replayed);
+    }
+    
+    #[test]
+    fn test_slow_scripts_are_interrupted() {
+        let mut page = WebPage::from_html("<script>var grid = Array.from({length: 3000}, () => Array.from({length: 3000}, () => 1))</script>\
+            <script>console.log('next script')</script>", None);
+        page.set_script_time_budget(std::time::Duration::from_millis(50));
+        let started = std::time::Instant::now();
+        page.run_inline_scripts();
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        assert!(page.take_slow_script());
+        assert!(!page.take_slow_script());
+        let errors = page.take_script_errors();
+        assert!(errors[0].message.starts_with("RangeError: Script ran longer than 50 ms"));
+        // The next script is a turn of its own
+        assert_eq!(page.js_engine.as_ref().unwrap().take_console_output().last().map(String::as_str), Some("[LOG] next script"));
+        
+        page.wait_for_scripts();
+        assert_eq!(page.script_time_budget, std::time::Duration::from_millis(100));
+        page.stop_scripts();
+        page.run_inline_scripts();
+        assert!(page.js_engine.is_none());
+    }
+    
+    #[test]
+    fn test_workers_answer_the_page() {
+        let mut page = WebPage::from_html("<script>const code = URL.createObjectURL(new Blob([\"onmessage = (e) => { postMessage(e.data * 2) }\"]))\n\
+            const worker = new Worker(code)\n\
+            worker.onmessage = (e) => { console.log('doubled ' + e.data) }\n\
+            worker.postMessage(21)\n\
+            var nothing = new Worker('https://elsewhere.example/w.js')</script>", None);
+        page.set_document_url("https://app.example/");
+        page.run_inline_scripts();
+        let started = std::time::Instant::now();
+        let mut output = Vec::new();
+        while output.len() < 2 && started.elapsed() < std::time::Duration::from_secs(10) {
+            assert!(page.poll_workers());
+            output.extend(page.js_engine.as_ref().unwrap().take_console_output());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(output.last().map(String::as_str), Some("[LOG] doubled 42"));
+        let errors = page.take_script_errors();
+        assert!(errors.iter().any(|error| error.message.starts_with("SecurityError: Failed to construct 'Worker'")));
+    }
+}
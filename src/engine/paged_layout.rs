@@ -0,0 +1,404 @@
+// Paged layout for print preview and PDF export
+
+use crate::engine::dom::DOMNode;
+
+// Print geometry is expressed in CSS pixels (96 per inch)
+pub const MM_TO_PX: f32 = 96.0 / 25.4;
+const PX_TO_PT: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaperSize {
+    A4,
+    A3,
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 4] = [PaperSize::A4, PaperSize::A3, PaperSize::Letter, PaperSize::Legal];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+            PaperSize::Letter => "Letter",
+            PaperSize::Legal => "Legal",
+        }
+    }
+
+    /// Portrait width and height in millimetres
+    pub fn size_mm(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintSettings {
+    pub paper_size: PaperSize,
+    pub orientation: Orientation,
+    pub margin_mm: f32,
+    pub scale: f32,
+    pub headers_footers: bool,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        Self {
+            paper_size: PaperSize::A4,
+            orientation: Orientation::Portrait,
+            margin_mm: 12.0,
+            scale: 1.0,
+            headers_footers: true,
+        }
+    }
+}
+
+impl PrintSettings {
+    /// Page width and height in CSS pixels, honouring orientation
+    pub fn page_size_px(&self) -> (f32, f32) {
+        let (w, h) = self.paper_size.size_mm();
+        let (w, h) = match self.orientation {
+            Orientation::Portrait => (w, h),
+            Orientation::Landscape => (h, w),
+        };
+        (w * MM_TO_PX, h * MM_TO_PX)
+    }
+
+    /// Printable area inside the margins as (x, y, width, height)
+    pub fn content_rect_px(&self) -> (f32, f32, f32, f32) {
+        let (w, h) = self.page_size_px();
+        let margin = self.margin_mm * MM_TO_PX;
+        (margin, margin, (w - 2.0 * margin).max(1.0), (h - 2.0 * margin).max(1.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Heading(u8),
+    Paragraph,
+    ListItem,
+    Preformatted,
+    Rule,
+}
+
+impl BlockKind {
+    /// Font size in CSS pixels at 100% scale
+    pub fn font_size(&self) -> f32 {
+        match self {
+            BlockKind::Heading(1) => 24.0,
+            BlockKind::Heading(2) => 20.0,
+            BlockKind::Heading(3) => 17.0,
+            BlockKind::Heading(_) => 15.0,
+            BlockKind::Preformatted => 12.0,
+            _ => 13.0,
+        }
+    }
+
+    pub fn space_before(&self) -> f32 {
+        match self {
+            BlockKind::Heading(_) => 14.0,
+            BlockKind::ListItem => 3.0,
+            _ => 8.0,
+        }
+    }
+
+    pub fn is_monospace(&self) -> bool {
+        matches!(self, BlockKind::Preformatted)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintBlock {
+    pub kind: BlockKind,
+    pub text: String,
+}
+
+/// A single wrapped line, measured by the caller's text shaper
+#[derive(Debug, Clone)]
+pub struct PrintLine {
+    pub kind: BlockKind,
+    pub text: String,
+    pub height: f32,
+    pub gap_before: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedLine {
+    pub line: usize,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrintPage {
+    pub lines: Vec<PlacedLine>,
+}
+
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "em", "i", "kbd", "label", "mark",
+    "q", "s", "small", "span", "strong", "sub", "sup", "time", "u",
+];
+const SKIPPED_TAGS: &[&str] = &["head", "title", "meta", "link", "script", "style", "noscript", "template"];
+
+/// Flatten a DOM into printable blocks
+pub fn collect_blocks(node: &DOMNode) -> Vec<PrintBlock> {
+    let mut blocks = Vec::new();
+    collect_into(node, &mut blocks);
+    blocks
+}
+
+fn collect_into(node: &DOMNode, blocks: &mut Vec<PrintBlock>) {
+    let DOMNode::Element { tag_name, children, .. } = node else {
+        if let DOMNode::Text(text) = node {
+            push_text(blocks, BlockKind::Paragraph, collapse_whitespace(text));
+        }
+        return;
+    };
+
+    match tag_name.as_str() {
+        tag if SKIPPED_TAGS.contains(&tag) => {}
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = tag_name[1..].parse().unwrap_or(6);
+            push_text(blocks, BlockKind::Heading(level), collapse_whitespace(&inline_text(node)));
+        }
+        "p" | "td" | "th" | "dt" | "dd" | "figcaption" | "caption" => {
+            push_text(blocks, BlockKind::Paragraph, collapse_whitespace(&inline_text(node)));
+        }
+        "li" => {
+            let text = collapse_whitespace(&inline_text(node));
+            if !text.is_empty() {
+                push_text(blocks, BlockKind::ListItem, format!("• {}", text));
+            }
+        }
+        "pre" => push_text(blocks, BlockKind::Preformatted, inline_text(node).trim_matches('\n').to_string()),
+        "hr" => blocks.push(PrintBlock { kind: BlockKind::Rule, text: String::new() }),
+        _ => {
+            // Runs of inline content between block children print as one paragraph
+            let mut run = String::new();
+            for child in children {
+                if is_inline(child) {
+                    run.push_str(&inline_text(child));
+                    run.push(' ');
+                } else {
+                    push_text(blocks, BlockKind::Paragraph, collapse_whitespace(&run));
+                    run.clear();
+                    collect_into(child, blocks);
+                }
+            }
+            push_text(blocks, BlockKind::Paragraph, collapse_whitespace(&run));
+        }
+    }
+}
+
+fn is_inline(node: &DOMNode) -> bool {
+    match node {
+        DOMNode::Text(_) => true,
+        DOMNode::Element { tag_name, .. } => INLINE_TAGS.contains(&tag_name.as_str()),
+        DOMNode::Comment(_) => false,
+    }
+}
+
+fn inline_text(node: &DOMNode) -> String {
+    match node {
+        DOMNode::Text(text) => text.clone(),
+        DOMNode::Element { tag_name, children, .. } if !SKIPPED_TAGS.contains(&tag_name.as_str()) => {
+            children.iter().map(inline_text).collect::<Vec<_>>().join(" ")
+        }
+        _ => String::new(),
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn push_text(blocks: &mut Vec<PrintBlock>, kind: BlockKind, text: String) {
+    if !text.is_empty() {
+        blocks.push(PrintBlock { kind, text });
+    }
+}
+
+/// Distribute measured lines over pages; the gap before a line is dropped at the top of a page
+pub fn paginate(lines: &[PrintLine], content_height: f32) -> Vec<PrintPage> {
+    let mut pages = vec![PrintPage::default()];
+    let mut y = 0.0;
+
+    for (index, line) in lines.iter().enumerate() {
+        let gap = if y > 0.0 { line.gap_before } else { 0.0 };
+        if y > 0.0 && y + gap + line.height > content_height {
+            pages.push(PrintPage::default());
+            y = 0.0;
+        } else {
+            y += gap;
+        }
+
+        if let Some(page) = pages.last_mut() {
+            page.lines.push(PlacedLine { line: index, y });
+        }
+        y += line.height;
+    }
+
+    pages
+}
+
+/// Text printed in the top and bottom margins of each page
+pub fn header_footer(title: &str, url: &str, page: usize, page_count: usize) -> [(String, String); 2] {
+    [
+        (title.to_string(), chrono::Local::now().format("%Y-%m-%d %H:%M").to_string()),
+        (url.to_string(), format!("{} / {}", page + 1, page_count)),
+    ]
+}
+
+/// Serialize paginated lines as a PDF using the standard Helvetica and Courier fonts
+pub fn write_pdf(
+    settings: &PrintSettings,
+    lines: &[PrintLine],
+    pages: &[PrintPage],
+    title: &str,
+    url: &str,
+) -> Vec<u8> {
+    let (page_w, page_h) = settings.page_size_px();
+    let (content_x, content_y, content_w, _) = settings.content_rect_px();
+    let margin = settings.margin_mm * MM_TO_PX;
+
+    // Objects 1-4 are fixed; each page adds a page object and a content stream
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 5 + i * 2)).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec());
+
+    for (page_index, page) in pages.iter().enumerate() {
+        let mut content: Vec<u8> = Vec::new();
+        for placed in &page.lines {
+            let line = &lines[placed.line];
+            let top = content_y + placed.y;
+            if line.kind == BlockKind::Rule {
+                let y = (page_h - top - line.height / 2.0) * PX_TO_PT;
+                content.extend(format!(
+                    "0.6 G 0.75 w {:.2} {:.2} m {:.2} {:.2} l S\n",
+                    content_x * PX_TO_PT, y, (content_x + content_w) * PX_TO_PT, y
+                ).into_bytes());
+                continue;
+            }
+            let font = if line.kind.is_monospace() { "F2" } else { "F1" };
+            let size = line.kind.font_size() * settings.scale;
+            let baseline = page_h - top - line.height * 0.8;
+            pdf_text(&mut content, font, size, content_x, baseline, &line.text);
+        }
+
+        if settings.headers_footers {
+            let [(header_left, header_right), (footer_left, footer_right)] =
+                header_footer(title, url, page_index, pages.len());
+            let header_y = page_h - margin / 2.0;
+            let footer_y = margin / 2.0 - 8.0;
+            let right_x = page_w - margin - 90.0;
+            pdf_text(&mut content, "F1", 12.0, content_x, header_y, &header_left);
+            pdf_text(&mut content, "F1", 12.0, right_x, header_y, &header_right);
+            pdf_text(&mut content, "F1", 12.0, content_x, footer_y, &footer_left);
+            pdf_text(&mut content, "F1", 12.0, right_x, footer_y, &footer_right);
+        }
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            page_w * PX_TO_PT, page_h * PX_TO_PT, 6 + page_index * 2
+        ).into_bytes());
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+
+    let xref_offset = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    out.extend(format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1, xref_offset
+    ).into_bytes());
+    out
+}
+
+// Positions are in CSS pixels from the bottom-left corner
+fn pdf_text(content: &mut Vec<u8>, font: &str, size_px: f32, x: f32, y: f32, text: &str) {
+    content.extend(format!(
+        "0 g BT /{} {:.2} Tf {:.2} {:.2} Td (",
+        font, size_px * PX_TO_PT, x * PX_TO_PT, y * PX_TO_PT
+    ).into_bytes());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => content.extend([b'\\', c as u8]),
+            '•' => content.push(0x95), // WinAnsi bullet
+            c if (c as u32) >= 0x20 && (c as u32) <= 0xFF => content.push(c as u32 as u8),
+            _ => content.push(b'?'),
+        }
+    }
+    content.extend(b") Tj ET\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    fn line(height: f32, gap_before: f32) -> PrintLine {
+        PrintLine { kind: BlockKind::Paragraph, text: "x".to_string(), height, gap_before }
+    }
+
+    #[test]
+    fn test_landscape_swaps_dimensions() {
+        let mut settings = PrintSettings::default();
+        let (w, h) = settings.page_size_px();
+        settings.orientation = Orientation::Landscape;
+        assert_eq!(settings.page_size_px(), (h, w));
+    }
+
+    #[test]
+    fn test_paginate_drops_gap_at_page_top() {
+        let lines = vec![line(40.0, 0.0), line(40.0, 10.0), line(40.0, 10.0)];
+        let pages = paginate(&lines, 100.0);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].lines[1].y, 50.0);
+        assert_eq!(pages[1].lines[0].y, 0.0);
+    }
+
+    #[test]
+    fn test_collect_blocks() {
+        let dom = html_parser::parse("<html><head><title>T</title></head><body><h1>Title</h1><p>Hello <b>world</b></p><ul><li>One</li></ul></body></html>");
+        let blocks = collect_blocks(&dom);
+        assert_eq!(blocks[0], PrintBlock { kind: BlockKind::Heading(1), text: "Title".to_string() });
+        assert_eq!(blocks[1].text, "Hello world");
+        assert_eq!(blocks[2].kind, BlockKind::ListItem);
+    }
+
+    #[test]
+    fn test_write_pdf_structure() {
+        let lines = vec![line(16.0, 0.0)];
+        let pages = paginate(&lines, 100.0);
+        let pdf = write_pdf(&PrintSettings::default(), &lines, &pages, "Title", "https://example.com");
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+    }
+}
@@ -0,0 +1,121 @@
+// Retained painting: the shapes a subtree of the page painted, kept and replayed on later frames while nothing it
+// was painted from changed, so static content isn't restyled, laid out and shaped again every frame
+use eframe::egui;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use super::dom::DOMNode;
+use super::hit_test::{HitTestRecorder, RecordedBoxes};
+use super::image_memory::ImageMemory;
+use crate::ui::theme::Palette;
+
+/// What a recording was painted with besides its subtree of the document; it's only replayed while all of
+/// this is unchanged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaintKey {
+    /// `PageStyles::revision`
+    pub style_revision: u64,
+    /// The font atlas text was shaped into, which egui replaces when fonts, the scale or the atlas size change
+    pub font_atlas: usize,
+    pub palette: Palette,
+    /// `ImageMemory::generation`, since images may have moved to other textures
+    pub images: u64,
+    /// Width available to the subtree
+    pub width: f32,
+}
+
+// One subtree's painting and hit boxes, where they were when recorded
+struct Recording {
+    key: PaintKey,
+    rect: egui::Rect,
+    shapes: Vec<egui::Shape>,
+    boxes: RecordedBoxes,
+    // Images painted, by id, with where they were
+    images: Vec<(u64, egui::Rect)>,
+}
+
+/// Recordings of the page's subtrees, keyed by the root element of each
+#[derive(Default)]
+pub struct PaintCache {
+    recordings: RefCell<HashMap<*const DOMNode, Recording>>,
+}
+
+impl PaintCache {
+    /// Paint the recording of `node` made with `key` at the cursor of `ui`, recording its hit boxes and the images
+    /// it painted again. Returns the area it covers, or None when there's no such recording.
+    pub fn replay(
+        &self,
+        node: &DOMNode,
+        key: PaintKey,
+        ui: &mut egui::Ui,
+        hit_boxes: &HitTestRecorder,
+        images: &RefCell<Vec<(u64, egui::Rect)>>,
+    ) -> Option<egui::Rect> {
+        let recordings = self.recordings.borrow();
+        let recording = recordings.get(&(node as *const DOMNode)).filter(|recording| recording.key == key)?;
+        let (rect, _) = ui.allocate_exact_size(recording.rect.size(), egui::Sense::hover());
+        let offset = rect.min - recording.rect.min;
+        ui.painter().extend(recording.shapes.iter().cloned().map(|mut shape| {
+            shape.translate(offset);
+            shape
+        }));
+        hit_boxes.replay(&recording.boxes, offset);
+        // Images on screen are still in use even though they aren't asked for
+        for (image, rect) in &recording.images {
+            let rect = rect.translate(offset);
+            if ui.is_rect_visible(rect) {
+                ImageMemory::shared().touch(ui.ctx(), *image);
+            }
+            images.borrow_mut().push((*image, rect));
+        }
+        Some(rect)
+    }
+
+    /// Keep what `node` painted: `shapes` covering `rect`, and the images among them. Recordings of the elements
+    /// inside it are dropped, since this one includes them.
+    pub fn record(
+        &self,
+        node: &DOMNode,
+        key: PaintKey,
+        rect: egui::Rect,
+        shapes: Vec<egui::Shape>,
+        boxes: RecordedBoxes,
+        images: Vec<(u64, egui::Rect)>,
+    ) {
+        let mut recordings = self.recordings.borrow_mut();
+        for inner in node.descendants().skip(1) {
+            recordings.remove(&inner);
+        }
+        recordings.insert(node, Recording { key, rect, shapes, boxes, images });
+    }
+
+    /// Drop the recordings of `nodes`, because they or something inside them changed
+    pub fn forget(&self, nodes: &HashSet<*const DOMNode>) {
+        self.recordings.borrow_mut().retain(|node, _| !nodes.contains(node));
+    }
+
+    pub fn len(&self) -> usize {
+        self.recordings.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recordings.borrow().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.recordings.borrow_mut().clear();
+    }
+}
+
+/// The number of shapes in the layer of `ui` so far, where a recording of what's painted next starts
+pub fn next_shape(ui: &egui::Ui) -> usize {
+    ui.ctx().graphics(|graphics| graphics.get(ui.layer_id()).map_or(0, |list| list.next_idx().0))
+}
+
+/// The shapes in the layer of `ui` from index `first_shape` on
+pub fn shapes_since(ui: &egui::Ui, first_shape: usize) -> Vec<egui::Shape> {
+    ui.ctx().graphics(|graphics| {
+        graphics.get(ui.layer_id())
+            .map(|list| list.all_entries().skip(first_shape).map(|clipped| clipped.shape.clone()).collect())
+            .unwrap_or_default()
+    })
+}
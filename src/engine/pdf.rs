@@ -0,0 +1,1663 @@
+// A reader for PDF files, enough to show them in a tab. Objects are found by scanning the file rather than through
+// its cross-reference table, which is often stale after edits, and each page's content stream is run to collect
+// positioned text, filled and stroked paths and images. Encrypted files aren't supported
+use anyhow::{Result, anyhow};
+use eframe::egui::{Color32, ColorImage, Pos2, Rect, Vec2, pos2, vec2};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::Read;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Form XObjects drawn inside one another
+const MAX_FORM_DEPTH: usize = 8;
+// Images larger than this are left out rather than decoded
+const MAX_IMAGE_PIXELS: usize = 4096 * 4096;
+// Bytes one stream may decode to, and all of a document's streams together
+const MAX_STREAM_BYTES: usize = 64 * 1024 * 1024;
+const MAX_DOCUMENT_BYTES: usize = 512 * 1024 * 1024;
+// Colour components of the largest DeviceN space
+const MAX_COMPONENTS: usize = 32;
+// Line segments a Bézier curve is flattened into
+const CURVE_STEPS: usize = 8;
+// A TJ adjustment wider than this, in thousandths of the font size, stands for a space between words
+const WORD_GAP: f32 = 200.0;
+
+// What the WinAnsi encoding puts at 0x80-0x9F; the rest of it matches Latin-1
+const WIN_ANSI_HIGH: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž', '\u{fffd}',
+    '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}', 'ž', 'Ÿ',
+];
+
+// Glyph names used in font encodings that aren't a single character or uniXXXX
+const GLYPH_NAMES: &[(&str, char)] = &[
+    ("space", ' '), ("exclam", '!'), ("quotedbl", '"'), ("numbersign", '#'), ("dollar", '$'), ("percent", '%'),
+    ("ampersand", '&'), ("quotesingle", '\''), ("parenleft", '('), ("parenright", ')'), ("asterisk", '*'),
+    ("plus", '+'), ("comma", ','), ("hyphen", '-'), ("period", '.'), ("slash", '/'), ("zero", '0'), ("one", '1'),
+    ("two", '2'), ("three", '3'), ("four", '4'), ("five", '5'), ("six", '6'), ("seven", '7'), ("eight", '8'),
+    ("nine", '9'), ("colon", ':'), ("semicolon", ';'), ("less", '<'), ("equal", '='), ("greater", '>'),
+    ("question", '?'), ("at", '@'), ("bracketleft", '['), ("backslash", '\\'), ("bracketright", ']'),
+    ("asciicircum", '^'), ("underscore", '_'), ("grave", '`'), ("braceleft", '{'), ("bar", '|'),
+    ("braceright", '}'), ("asciitilde", '~'), ("quoteleft", '‘'), ("quoteright", '’'), ("quotedblleft", '“'),
+    ("quotedblright", '”'), ("endash", '–'), ("emdash", '—'), ("bullet", '•'), ("ellipsis", '…'),
+    ("fi", 'ﬁ'), ("fl", 'ﬂ'), ("copyright", '©'), ("registered", '®'), ("degree", '°'), ("section", '§'),
+];
+
+/// A parsed PDF, ready to draw: every page's text, paths and images in points from the page's top-left corner
+pub struct PdfDocument {
+    /// The title set in the document's information dictionary
+    pub title: Option<String>,
+    pub pages: Vec<PdfPage>,
+}
+
+pub struct PdfPage {
+    /// Width and height in points, after the page's rotation
+    pub size: Vec2,
+    pub items: Vec<PdfItem>,
+}
+
+pub enum PdfItem {
+    Text {
+        /// Start of the baseline
+        origin: Pos2,
+        size: f32,
+        /// How far the PDF's own glyph widths take the text
+        width: f32,
+        /// Clockwise turn of the baseline, in radians
+        angle: f32,
+        text: String,
+        color: Color32,
+        monospace: bool,
+    },
+    Path {
+        subpaths: Vec<Vec<Pos2>>,
+        fill: Option<Color32>,
+        stroke: Option<(f32, Color32)>,
+    },
+    Image {
+        rect: Rect,
+        /// Shared by every placement of the same image in the document
+        id: usize,
+        image: Arc<ColorImage>,
+    },
+}
+
+/// Where a search term was found: characters `start..end` of a text item
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfMatch {
+    pub page: usize,
+    pub item: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Whether the bytes look like a PDF file
+pub fn is_pdf(data: &[u8]) -> bool {
+    data.len() >= 5 && data[..data.len().min(1024)].windows(5).any(|window| window == b"%PDF-")
+}
+
+impl PdfDocument {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if !is_pdf(data) {
+            return Err(anyhow!("This isn't a PDF file"));
+        }
+        let file = File::scan(data);
+        if file.trailers.iter().any(|trailer| trailer.contains_key("Encrypt")) {
+            return Err(anyhow!("Encrypted PDFs can't be shown yet"));
+        }
+        let root = file.trailers.iter().rev().find_map(|trailer| trailer.get("Root"))
+            .and_then(|root| file.resolve(root).as_dict())
+            .or_else(|| file.objects.values().filter_map(Object::as_dict).find(|dict| name_of(dict, "Type") == Some("Catalog")))
+            .ok_or_else(|| anyhow!("The PDF has no document catalog"))?;
+        let title = file.trailers.iter().rev().find_map(|trailer| trailer.get("Info"))
+            .and_then(|info| file.resolve(info).as_dict())
+            .and_then(|info| info.get("Title"))
+            .and_then(|title| file.resolve(title).as_string())
+            .map(text_string)
+            .filter(|title| !title.trim().is_empty());
+
+        let mut page_dicts = Vec::new();
+        if let Some(pages) = root.get("Pages") {
+            collect_pages(&file, file.resolve(pages), &Inherited::default(), &mut page_dicts, 0);
+        }
+        if page_dicts.is_empty() {
+            return Err(anyhow!("The PDF has no pages"));
+        }
+        let mut renderer = Renderer { file: &file, fonts: HashMap::new(), images: HashMap::new(), next_image: 0 };
+        let pages = page_dicts.iter().map(|(page, inherited)| renderer.page(page, inherited)).collect();
+        Ok(Self { title, pages })
+    }
+
+    /// Every place `query` appears in the text, ignoring case, in page order
+    pub fn find(&self, query: &str) -> Vec<PdfMatch> {
+        let query: Vec<char> = query.chars().map(fold_case).collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = Vec::new();
+        for (page_index, page) in self.pages.iter().enumerate() {
+            for (item_index, item) in page.items.iter().enumerate() {
+                let PdfItem::Text { text, .. } = item else { continue };
+                let chars: Vec<char> = text.chars().map(fold_case).collect();
+                let mut start = 0;
+                while start + query.len() <= chars.len() {
+                    if chars[start..start + query.len()] == query[..] {
+                        matches.push(PdfMatch { page: page_index, item: item_index, start, end: start + query.len() });
+                        start += query.len();
+                    } else {
+                        start += 1;
+                    }
+                }
+            }
+        }
+        matches
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// Objects
+
+type Dict = HashMap<String, Object>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Null,
+    Bool(bool),
+    Number(f32),
+    String(Vec<u8>),
+    Name(String),
+    Array(Vec<Object>),
+    Dict(Dict),
+    Stream(Dict, Vec<u8>),
+    /// An indirect reference; generations are ignored, the object defined last in the file wins
+    Ref(u32),
+    /// A content stream operator, or a keyword out of place
+    Operator(String),
+}
+
+impl Object {
+    fn as_f32(&self) -> Option<f32> {
+        match self {
+            Object::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn as_string(&self) -> Option<&[u8]> {
+        match self {
+            Object::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Object]> {
+        match self {
+            Object::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Object::Dict(dict) | Object::Stream(dict, _) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+fn name_of<'a>(dict: &'a Dict, key: &str) -> Option<&'a str> {
+    dict.get(key).and_then(Object::as_name)
+}
+
+// A text string in the document's metadata: UTF-16 with a byte order mark, or close enough to Latin-1
+fn text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xfe, 0xff, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => bytes.iter().map(|&byte| win_ansi(byte)).collect(),
+    }
+}
+
+fn win_ansi(byte: u8) -> char {
+    match byte {
+        0x80..=0x9f => WIN_ANSI_HIGH[byte as usize - 0x80],
+        _ => byte as char,
+    }
+}
+
+// Lexer
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, 0 | b'\t' | b'\n' | 0x0c | b'\r' | b' ')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn is_regular(byte: u8) -> bool {
+    !is_whitespace(byte) && !is_delimiter(byte)
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            if is_whitespace(byte) {
+                self.pos += 1;
+            } else if byte == b'%' {
+                while self.peek().is_some_and(|byte| byte != b'\n' && byte != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn regular_run(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    /// The next object, or None at the end of the data
+    fn object(&mut self) -> Option<Object> {
+        self.object_at_depth(0)
+    }
+
+    fn object_at_depth(&mut self, depth: usize) -> Option<Object> {
+        self.skip_whitespace();
+        let byte = self.peek()?;
+        if depth > 64 {
+            self.pos = self.data.len();
+            return None;
+        }
+        Some(match byte {
+            b'/' => {
+                self.pos += 1;
+                Object::Name(self.name())
+            }
+            b'(' => {
+                self.pos += 1;
+                Object::String(self.literal_string())
+            }
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut dict = Dict::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        None => break,
+                        Some(b'>') => {
+                            self.pos += 2;
+                            break;
+                        }
+                        Some(b'/') => {
+                            self.pos += 1;
+                            let key = self.name();
+                            let value = self.object_at_depth(depth + 1).unwrap_or(Object::Null);
+                            dict.insert(key, value);
+                        }
+                        // Garbage where a key should be
+                        Some(_) => {
+                            self.object_at_depth(depth + 1);
+                        }
+                    }
+                }
+                Object::Dict(dict)
+            }
+            b'<' => {
+                self.pos += 1;
+                Object::String(self.hex_string())
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        None => break,
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(_) => items.extend(self.object_at_depth(depth + 1)),
+                    }
+                }
+                Object::Array(items)
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => self.number_or_ref(),
+            _ if is_delimiter(byte) => {
+                self.pos += 1;
+                Object::Operator((byte as char).to_string())
+            }
+            _ => match self.regular_run() {
+                b"true" => Object::Bool(true),
+                b"false" => Object::Bool(false),
+                b"null" => Object::Null,
+                keyword => Object::Operator(String::from_utf8_lossy(keyword).into_owned()),
+            },
+        })
+    }
+
+    fn name(&mut self) -> String {
+        let raw = self.regular_run();
+        let mut name = Vec::with_capacity(raw.len());
+        let mut index = 0;
+        while index < raw.len() {
+            let hex = raw.get(index + 1..index + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+            match (raw[index], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+                (b'#', Some(byte)) => {
+                    name.push(byte);
+                    index += 3;
+                }
+                (byte, _) => {
+                    name.push(byte);
+                    index += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&name).into_owned()
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut nesting = 0;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    nesting += 1;
+                    bytes.push(byte);
+                }
+                b')' if nesting == 0 => break,
+                b')' => {
+                    nesting -= 1;
+                    bytes.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else { break };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        value = value * 8 + (digit - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(value as u8);
+                        }
+                        // A backslash at the end of a line continues the string on the next
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => bytes.push(other),
+                    }
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        bytes
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            if byte == b'>' {
+                break;
+            }
+            if let Some(digit) = (byte as char).to_digit(16) {
+                digits.push(digit as u8);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits.chunks_exact(2).map(|pair| pair[0] << 4 | pair[1]).collect()
+    }
+
+    // A number, or the start of an "N G R" reference
+    fn number_or_ref(&mut self) -> Object {
+        let raw = self.regular_run();
+        let number = parse_number(raw);
+        let is_integer = raw.iter().all(u8::is_ascii_digit);
+        if is_integer {
+            let save = self.pos;
+            self.skip_whitespace();
+            let generation = self.regular_run();
+            self.skip_whitespace();
+            if !generation.is_empty() && generation.iter().all(u8::is_ascii_digit) && self.regular_run() == b"R" {
+                return Object::Ref(number as u32);
+            }
+            self.pos = save;
+        }
+        Object::Number(number)
+    }
+}
+
+// Lenient about the doubled signs and stray characters some writers produce
+fn parse_number(raw: &[u8]) -> f32 {
+    let text = String::from_utf8_lossy(raw);
+    let trimmed = text.trim_start_matches(['+', '-']);
+    let negative = text.starts_with('-');
+    let end = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let value = trimmed[..end].parse::<f32>().unwrap_or(0.0);
+    if negative { -value } else { value }
+}
+
+// The file
+
+struct File {
+    objects: HashMap<u32, Object>,
+    trailers: Vec<Dict>,
+    // Bytes decoded so far, counted against MAX_DOCUMENT_BYTES
+    decoded: Cell<usize>,
+}
+
+impl File {
+    fn scan(data: &[u8]) -> Self {
+        let mut objects = HashMap::new();
+        let mut trailers = Vec::new();
+        let mut pos = 0;
+        while let Some(found) = find(data, b"obj", pos) {
+            pos = found + 3;
+            let Some(number) = object_number(data, found) else { continue };
+            if data.get(pos).is_some_and(|&byte| is_regular(byte)) {
+                continue;
+            }
+            let mut lexer = Lexer::new(data, pos);
+            let Some(mut object) = lexer.object() else { break };
+            lexer.skip_whitespace();
+            if let Object::Dict(dict) = &object {
+                if data[lexer.pos..].starts_with(b"stream") {
+                    let (content, end) = stream_data(data, lexer.pos + 6, dict);
+                    object = Object::Stream(dict.clone(), content.to_vec());
+                    lexer.pos = end;
+                }
+            }
+            if let Object::Stream(dict, _) = &object {
+                if name_of(dict, "Type") == Some("XRef") {
+                    trailers.push(dict.clone());
+                }
+            }
+            objects.insert(number, object);
+            pos = lexer.pos;
+        }
+        let mut pos = 0;
+        while let Some(found) = find(data, b"trailer", pos) {
+            pos = found + 7;
+            if let Some(Object::Dict(dict)) = Lexer::new(data, pos).object() {
+                trailers.push(dict);
+            }
+        }
+        let mut file = Self { objects, trailers, decoded: Cell::new(0) };
+        file.unpack_object_streams();
+        file
+    }
+
+    // Objects packed into object streams, where they weren't also written on their own
+    fn unpack_object_streams(&mut self) {
+        let containers: Vec<u32> = self.objects.iter()
+            .filter(|(_, object)| matches!(object, Object::Stream(dict, _) if name_of(dict, "Type") == Some("ObjStm")))
+            .map(|(number, _)| *number)
+            .collect();
+        for container in containers {
+            let Some(Object::Stream(dict, _)) = self.objects.get(&container) else { continue };
+            let count = dict.get("N").and_then(Object::as_f32).unwrap_or(0.0) as usize;
+            let first = dict.get("First").and_then(Object::as_f32).unwrap_or(0.0) as usize;
+            let Ok((data, None)) = self.decode(&self.objects[&container]) else { continue };
+            let mut header = Lexer::new(&data, 0);
+            // Every entry takes at least a few bytes of the header, so the count can't outgrow the data
+            let mut entries = Vec::with_capacity(count.min(data.len()));
+            for _ in 0..count {
+                match (header.object(), header.object()) {
+                    (Some(Object::Number(number)), Some(Object::Number(offset))) => entries.push((number as u32, offset as usize)),
+                    _ => break,
+                }
+            }
+            for (number, offset) in entries {
+                if self.objects.contains_key(&number) {
+                    continue;
+                }
+                let Some(start) = first.checked_add(offset) else { continue };
+                if let Some(object) = Lexer::new(&data, start).object() {
+                    self.objects.insert(number, object);
+                }
+            }
+        }
+    }
+
+    fn resolve<'a>(&'a self, object: &'a Object) -> &'a Object {
+        let mut object = object;
+        for _ in 0..8 {
+            match object {
+                Object::Ref(number) => object = self.objects.get(number).unwrap_or(&Object::Null),
+                _ => return object,
+            }
+        }
+        &Object::Null
+    }
+
+    fn get<'a>(&'a self, dict: &'a Dict, key: &str) -> &'a Object {
+        dict.get(key).map_or(&Object::Null, |object| self.resolve(object))
+    }
+
+    fn number(&self, dict: &Dict, key: &str) -> Option<f32> {
+        self.get(dict, key).as_f32()
+    }
+
+    /// A stream's data with its filters undone, and the image filter left for an image decoder, if there is one
+    fn decode(&self, stream: &Object) -> Result<(Vec<u8>, Option<String>)> {
+        let Object::Stream(dict, raw) = stream else {
+            return Err(anyhow!("Not a stream"));
+        };
+        let filters: Vec<&str> = match self.get(dict, "Filter") {
+            Object::Name(name) => vec![name],
+            Object::Array(names) => names.iter().filter_map(|name| self.resolve(name).as_name()).collect(),
+            _ => Vec::new(),
+        };
+        let params: Vec<Option<&Dict>> = match self.get(dict, "DecodeParms") {
+            Object::Array(params) => params.iter().map(|param| self.resolve(param).as_dict()).collect(),
+            param => vec![param.as_dict()],
+        };
+        let budget = MAX_DOCUMENT_BYTES.saturating_sub(self.decoded.get());
+        if budget == 0 {
+            return Err(anyhow!("PDF streams decode to more than {} MB", MAX_DOCUMENT_BYTES / (1024 * 1024)));
+        }
+        let limit = MAX_STREAM_BYTES.min(budget);
+        let mut data = raw.clone();
+        for (index, filter) in filters.iter().enumerate() {
+            let param = params.get(index).copied().flatten();
+            data = match *filter {
+                "FlateDecode" | "Fl" => unpredict(inflate(&data, limit), param.map(|param| (self, param))),
+                "ASCIIHexDecode" | "AHx" => Lexer::new(&data, 0).hex_string(),
+                "ASCII85Decode" | "A85" => ascii85(&data, limit),
+                "DCTDecode" | "DCT" | "JPXDecode" | "JBIG2Decode" | "CCITTFaxDecode" | "CCF" => {
+                    return Ok((data, Some(filter.to_string())));
+                }
+                other => return Err(anyhow!("Unsupported PDF filter {}", other)),
+            };
+            self.decoded.set(self.decoded.get() + data.len());
+        }
+        Ok((data, None))
+    }
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|index| from + index)
+}
+
+// The N of "N G obj" ending just before `keyword`
+fn object_number(data: &[u8], keyword: usize) -> Option<u32> {
+    let mut pos = keyword;
+    let skip_back_whitespace = |pos: &mut usize| {
+        let start = *pos;
+        while *pos > 0 && is_whitespace(data[*pos - 1]) {
+            *pos -= 1;
+        }
+        *pos < start
+    };
+    let digits_back = |pos: &mut usize| {
+        let end = *pos;
+        while *pos > 0 && data[*pos - 1].is_ascii_digit() {
+            *pos -= 1;
+        }
+        (*pos < end).then(|| std::str::from_utf8(&data[*pos..end]).ok()?.parse::<u32>().ok()).flatten()
+    };
+    if !skip_back_whitespace(&mut pos) {
+        return None;
+    }
+    digits_back(&mut pos)?;
+    if !skip_back_whitespace(&mut pos) {
+        return None;
+    }
+    let number = digits_back(&mut pos)?;
+    (pos == 0 || !is_regular(data[pos - 1])).then_some(number)
+}
+
+// A stream's bytes, from just after the "stream" keyword, and where its "endstream" ends
+fn stream_data<'a>(data: &'a [u8], mut start: usize, dict: &Dict) -> (&'a [u8], usize) {
+    if data.get(start) == Some(&b'\r') {
+        start += 1;
+    }
+    if data.get(start) == Some(&b'\n') {
+        start += 1;
+    }
+    // A direct length is trusted when "endstream" follows it; an indirect one isn't known yet
+    if let Some(length) = dict.get("Length").and_then(Object::as_f32).map(|length| length as usize) {
+        let end = start.saturating_add(length).min(data.len());
+        let mut lexer = Lexer::new(data, end);
+        lexer.skip_whitespace();
+        if data[lexer.pos..].starts_with(b"endstream") {
+            return (&data[start..end], lexer.pos + 9);
+        }
+    }
+    match find(data, b"endstream", start) {
+        Some(end) => {
+            let mut content_end = end;
+            if content_end > start && data[content_end - 1] == b'\n' {
+                content_end -= 1;
+            }
+            if content_end > start && data[content_end - 1] == b'\r' {
+                content_end -= 1;
+            }
+            (&data[start..content_end], end + 9)
+        }
+        None => (&data[start..], data.len()),
+    }
+}
+
+// As much as can be inflated, up to `limit` bytes; a damaged stream still gives what came before the damage
+fn inflate(data: &[u8], limit: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut decoder = flate2::read::ZlibDecoder::new(data).take(limit as u64);
+    let mut buffer = [0u8; 16 * 1024];
+    loop {
+        match decoder.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => out.extend_from_slice(&buffer[..read]),
+        }
+    }
+    if out.is_empty() && !data.is_empty() {
+        let _ = flate2::read::DeflateDecoder::new(data).take(limit as u64).read_to_end(&mut out);
+    }
+    out
+}
+
+// Undo the PNG row filters a Flate stream may have been written with
+fn unpredict(data: Vec<u8>, params: Option<(&File, &Dict)>) -> Vec<u8> {
+    let Some((file, params)) = params else { return data };
+    let predictor = file.number(params, "Predictor").unwrap_or(1.0) as usize;
+    if predictor < 10 {
+        return data;
+    }
+    let colors = file.number(params, "Colors").unwrap_or(1.0).max(1.0) as usize;
+    let bits = file.number(params, "BitsPerComponent").unwrap_or(8.0) as usize;
+    let columns = file.number(params, "Columns").unwrap_or(1.0).max(1.0) as usize;
+    if colors > MAX_COMPONENTS || !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+        return data;
+    }
+    let pixel_bytes = (colors * bits).div_ceil(8);
+    // A row can't be longer than the data it's read from
+    let Some(row_bits) = (colors * bits).checked_mul(columns) else { return data };
+    let row_bytes = row_bits.div_ceil(8).min(data.len());
+    let mut out = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; row_bytes];
+    for row in data.chunks(row_bytes + 1) {
+        let (filter, row) = (row[0], &row[1..]);
+        let mut current = row.to_vec();
+        current.resize(row_bytes, 0);
+        for index in 0..row_bytes {
+            let left = if index >= pixel_bytes { current[index - pixel_bytes] } else { 0 };
+            let up = previous[index];
+            let up_left = if index >= pixel_bytes { previous[index - pixel_bytes] } else { 0 };
+            current[index] = current[index].wrapping_add(match filter {
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => 0,
+            });
+        }
+        out.extend_from_slice(&current[..row.len().min(row_bytes)]);
+        previous = current;
+    }
+    out
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let (to_left, to_up, to_up_left) = ((estimate - left as i16).abs(), (estimate - up as i16).abs(), (estimate - up_left as i16).abs());
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+fn ascii85(data: &[u8], limit: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut group = Vec::with_capacity(5);
+    let flush = |group: &mut Vec<u32>, out: &mut Vec<u8>| {
+        if group.is_empty() {
+            return;
+        }
+        let kept = group.len() - 1;
+        group.resize(5, 84);
+        let value = group.iter().fold(0u32, |value, &digit| value.wrapping_mul(85).wrapping_add(digit));
+        out.extend_from_slice(&value.to_be_bytes()[..kept]);
+        group.clear();
+    };
+    for &byte in data {
+        if out.len() >= limit {
+            break;
+        }
+        match byte {
+            b'~' => break,
+            b'z' if group.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push((byte - b'!') as u32);
+                if group.len() == 5 {
+                    flush(&mut group, &mut out);
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut group, &mut out);
+    out
+}
+
+// Pages
+
+// Page attributes a page takes from its ancestors in the page tree when it doesn't set them
+#[derive(Clone, Default)]
+struct Inherited {
+    resources: Option<Dict>,
+    media_box: Option<[f32; 4]>,
+    rotate: i32,
+}
+
+fn collect_pages(file: &File, node: &Object, inherited: &Inherited, pages: &mut Vec<(Dict, Inherited)>, depth: usize) {
+    let Some(dict) = node.as_dict() else { return };
+    if depth > 32 {
+        return;
+    }
+    let mut inherited = inherited.clone();
+    if let Some(resources) = file.get(dict, "Resources").as_dict() {
+        inherited.resources = Some(resources.clone());
+    }
+    if let Some(media_box) = rect_of(file, file.get(dict, "MediaBox")) {
+        inherited.media_box = Some(media_box);
+    }
+    if let Some(rotate) = file.number(dict, "Rotate") {
+        inherited.rotate = rotate as i32;
+    }
+    match file.get(dict, "Kids") {
+        Object::Array(kids) => {
+            for kid in kids {
+                collect_pages(file, file.resolve(kid), &inherited, pages, depth + 1);
+            }
+        }
+        _ => pages.push((dict.clone(), inherited)),
+    }
+}
+
+fn rect_of(file: &File, object: &Object) -> Option<[f32; 4]> {
+    let values: Vec<f32> = object.as_array()?.iter().filter_map(|value| file.resolve(value).as_f32()).collect();
+    let [x0, y0, x1, y1] = <[f32; 4]>::try_from(values).ok()?;
+    Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+}
+
+// Matrices, as PDF writes them: [a b c d e f] applied to row vectors
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn transform(m: &Matrix, x: f32, y: f32) -> Pos2 {
+    pos2(x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5])
+}
+
+// Fonts
+
+struct Font {
+    /// Unicode for each character code, from the font's ToUnicode map or its encoding
+    to_unicode: HashMap<u32, String>,
+    /// Bytes per character code
+    code_bytes: usize,
+    /// Glyph advances in thousandths of the font size
+    widths: HashMap<u32, f32>,
+    default_width: f32,
+    monospace: bool,
+}
+
+impl Font {
+    fn load(file: &File, dict: &Dict) -> Self {
+        let base_font = name_of(dict, "BaseFont").unwrap_or("");
+        let monospace = base_font.contains("Courier") || base_font.contains("Mono");
+        let composite = name_of(dict, "Subtype") == Some("Type0");
+        let mut font = Self {
+            to_unicode: HashMap::new(),
+            code_bytes: if composite { 2 } else { 1 },
+            widths: HashMap::new(),
+            default_width: if monospace { 600.0 } else { 500.0 },
+            monospace,
+        };
+        if composite {
+            let descendant = file.get(dict, "DescendantFonts").as_array()
+                .and_then(|fonts| fonts.first())
+                .and_then(|descendant| file.resolve(descendant).as_dict());
+            if let Some(descendant) = descendant {
+                font.default_width = file.number(descendant, "DW").unwrap_or(1000.0);
+                font.load_cid_widths(file, file.get(descendant, "W"));
+            }
+        } else {
+            let first = file.number(dict, "FirstChar").unwrap_or(0.0) as u32;
+            if let Some(widths) = file.get(dict, "Widths").as_array() {
+                for (index, width) in widths.iter().enumerate() {
+                    if let Some(width) = file.resolve(width).as_f32() {
+                        font.widths.insert(first + index as u32, width);
+                    }
+                }
+            }
+            font.load_encoding(file, file.get(dict, "Encoding"));
+        }
+        if let Ok((cmap, None)) = file.decode(file.get(dict, "ToUnicode")) {
+            font.load_to_unicode(&cmap);
+        }
+        font
+    }
+
+    // "c [w1 w2 ...]" gives consecutive codes from c their widths, "c1 c2 w" a range one width
+    fn load_cid_widths(&mut self, file: &File, widths: &Object) {
+        let Some(items) = widths.as_array() else { return };
+        let items: Vec<&Object> = items.iter().map(|item| file.resolve(item)).collect();
+        let mut index = 0;
+        while index + 1 < items.len() {
+            let Some(first) = items[index].as_f32() else { break };
+            match items[index + 1] {
+                Object::Array(list) => {
+                    for (offset, width) in list.iter().enumerate() {
+                        if let Some(width) = file.resolve(width).as_f32() {
+                            self.widths.insert(first as u32 + offset as u32, width);
+                        }
+                    }
+                    index += 2;
+                }
+                Object::Number(last) if index + 2 < items.len() => {
+                    let width = items[index + 2].as_f32().unwrap_or(self.default_width);
+                    for code in first as u32..=(*last as u32).min(first as u32 + 0xffff) {
+                        self.widths.insert(code, width);
+                    }
+                    index += 3;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    // WinAnsi stands in for the standard and Mac encodings, which differ from it in few characters that matter
+    fn load_encoding(&mut self, file: &File, encoding: &Object) {
+        for code in 32..=255u32 {
+            self.to_unicode.insert(code, win_ansi(code as u8).to_string());
+        }
+        let Some(differences) = encoding.as_dict().map(|encoding| file.get(encoding, "Differences")).and_then(Object::as_array) else {
+            return;
+        };
+        let mut code = 0;
+        for difference in differences {
+            match file.resolve(difference) {
+                Object::Number(number) => code = *number as u32,
+                Object::Name(name) => {
+                    if let Some(c) = glyph_char(name) {
+                        self.to_unicode.insert(code, c.to_string());
+                    }
+                    code += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn load_to_unicode(&mut self, cmap: &[u8]) {
+        let mut lexer = Lexer::new(cmap, 0);
+        let mut operands: Vec<Object> = Vec::new();
+        let code = |bytes: &[u8]| bytes.iter().fold(0u32, |code, &byte| code << 8 | byte as u32);
+        let utf16 = |bytes: &[u8]| {
+            let units: Vec<u16> = bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])).collect();
+            String::from_utf16_lossy(&units)
+        };
+        while let Some(object) = lexer.object() {
+            let Object::Operator(operator) = object else {
+                operands.push(object);
+                continue;
+            };
+            match operator.as_str() {
+                "endcodespacerange" => {
+                    if let Some(Object::String(low)) = operands.first() {
+                        self.code_bytes = low.len().clamp(1, 4);
+                    }
+                }
+                "endbfchar" => {
+                    for pair in operands.chunks_exact(2) {
+                        if let (Object::String(source), Object::String(target)) = (&pair[0], &pair[1]) {
+                            self.to_unicode.insert(code(source), utf16(target));
+                        }
+                    }
+                }
+                "endbfrange" => {
+                    for range in operands.chunks_exact(3) {
+                        let (Object::String(low), Object::String(high)) = (&range[0], &range[1]) else { continue };
+                        let (low, high) = (code(low), code(high));
+                        for (offset, source) in (low..=high.min(low + 0xffff)).enumerate() {
+                            let target = match &range[2] {
+                                Object::String(start) if start.len() >= 2 => {
+                                    let mut target = start.clone();
+                                    let last = target.len() - 2;
+                                    let unit = u16::from_be_bytes([target[last], target[last + 1]]).wrapping_add(offset as u16);
+                                    target[last..].copy_from_slice(&unit.to_be_bytes());
+                                    utf16(&target)
+                                }
+                                Object::Array(targets) => match targets.get(offset) {
+                                    Some(Object::String(target)) => utf16(target),
+                                    _ => continue,
+                                },
+                                _ => continue,
+                            };
+                            self.to_unicode.insert(source, target);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            operands.clear();
+        }
+    }
+
+    /// Each character code in a shown string with its text
+    fn codes<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = (u32, &'a str)> + 'a {
+        bytes.chunks(self.code_bytes).map(move |chunk| {
+            let code = chunk.iter().fold(0u32, |code, &byte| code << 8 | byte as u32);
+            (code, self.to_unicode.get(&code).map_or("", String::as_str))
+        })
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        self.widths.get(&code).copied().unwrap_or(self.default_width)
+    }
+}
+
+fn glyph_char(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    let hex = name.strip_prefix("uni").or_else(|| name.strip_prefix('u'));
+    if let Some(c) = hex.filter(|hex| hex.len() >= 4).and_then(|hex| u32::from_str_radix(&hex[..4], 16).ok()).and_then(char::from_u32) {
+        return Some(c);
+    }
+    GLYPH_NAMES.iter().find(|(glyph, _)| *glyph == name).map(|(_, c)| *c)
+}
+
+// Running content streams
+
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Matrix,
+    fill: Color32,
+    stroke: Color32,
+    /// Separation and DeviceN colours give a tint, where 1 is full ink
+    fill_is_tint: bool,
+    stroke_is_tint: bool,
+    line_width: f32,
+    font: Option<Rc<Font>>,
+    font_size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+}
+
+impl GraphicsState {
+    fn new(ctm: Matrix) -> Self {
+        Self {
+            ctm,
+            fill: Color32::BLACK,
+            stroke: Color32::BLACK,
+            fill_is_tint: false,
+            stroke_is_tint: false,
+            line_width: 1.0,
+            font: None,
+            font_size: 12.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+struct Renderer<'a> {
+    file: &'a File,
+    /// Fonts by the object they're defined in
+    fonts: HashMap<u32, Rc<Font>>,
+    /// Decoded images by the object they're defined in, with their ids; None when they couldn't be decoded
+    images: HashMap<u32, Option<(usize, Arc<ColorImage>)>>,
+    next_image: usize,
+}
+
+// A page's content as it's run
+struct Canvas {
+    items: Vec<PdfItem>,
+    states: Vec<GraphicsState>,
+    state: GraphicsState,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    path: Vec<Vec<Pos2>>,
+    current: Pos2,
+}
+
+impl Renderer<'_> {
+    fn page(&mut self, page: &Dict, inherited: &Inherited) -> PdfPage {
+        let [x0, y0, x1, y1] = inherited.media_box.unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let (width, height) = (x1 - x0, y1 - y0);
+        // Flip to a top-left origin, then turn the page as it asks to be shown
+        let flip = [1.0, 0.0, 0.0, -1.0, -x0, y1];
+        let (rotation, size) = match inherited.rotate.rem_euclid(360) {
+            90 => ([0.0, 1.0, -1.0, 0.0, height, 0.0], vec2(height, width)),
+            180 => ([-1.0, 0.0, 0.0, -1.0, width, height], vec2(width, height)),
+            270 => ([0.0, -1.0, 1.0, 0.0, 0.0, width], vec2(height, width)),
+            _ => (IDENTITY, vec2(width, height)),
+        };
+        let mut canvas = Canvas {
+            items: Vec::new(),
+            states: Vec::new(),
+            state: GraphicsState::new(multiply(&flip, &rotation)),
+            text_matrix: IDENTITY,
+            line_matrix: IDENTITY,
+            path: Vec::new(),
+            current: Pos2::ZERO,
+        };
+        let content: Vec<u8> = match self.file.get(page, "Contents") {
+            Object::Array(parts) => parts.iter()
+                .filter_map(|part| self.file.decode(self.file.resolve(part)).ok())
+                .flat_map(|(mut data, _)| {
+                    data.push(b'\n');
+                    data
+                })
+                .collect(),
+            contents => self.file.decode(contents).map(|(data, _)| data).unwrap_or_default(),
+        };
+        let resources = inherited.resources.clone().unwrap_or_default();
+        self.run(&content, &resources, &mut canvas, 0);
+        PdfPage { size, items: canvas.items }
+    }
+
+    fn run(&mut self, content: &[u8], resources: &Dict, canvas: &mut Canvas, depth: usize) {
+        let mut lexer = Lexer::new(content, 0);
+        let mut operands: Vec<Object> = Vec::new();
+        while let Some(object) = lexer.object() {
+            let Object::Operator(operator) = object else {
+                operands.push(object);
+                continue;
+            };
+            if operator == "BI" {
+                skip_inline_image(&mut lexer);
+            } else {
+                self.operator(&operator, &operands, resources, canvas, depth);
+            }
+            operands.clear();
+        }
+    }
+
+    fn operator(&mut self, operator: &str, operands: &[Object], resources: &Dict, canvas: &mut Canvas, depth: usize) {
+        let number = |index: usize| operands.get(index).and_then(Object::as_f32).unwrap_or(0.0);
+        let numbers = || operands.iter().filter_map(Object::as_f32).collect::<Vec<f32>>();
+        let state = &mut canvas.state;
+        match operator {
+            "q" => canvas.states.push(state.clone()),
+            "Q" => {
+                if let Some(saved) = canvas.states.pop() {
+                    canvas.state = saved;
+                }
+            }
+            "cm" => {
+                let matrix = [number(0), number(1), number(2), number(3), number(4), number(5)];
+                state.ctm = multiply(&matrix, &state.ctm);
+            }
+            "w" => state.line_width = number(0),
+            "g" => (state.fill, state.fill_is_tint) = (gray(number(0)), false),
+            "G" => (state.stroke, state.stroke_is_tint) = (gray(number(0)), false),
+            "rg" => (state.fill, state.fill_is_tint) = (rgb(number(0), number(1), number(2)), false),
+            "RG" => (state.stroke, state.stroke_is_tint) = (rgb(number(0), number(1), number(2)), false),
+            "k" => (state.fill, state.fill_is_tint) = (cmyk(number(0), number(1), number(2), number(3)), false),
+            "K" => (state.stroke, state.stroke_is_tint) = (cmyk(number(0), number(1), number(2), number(3)), false),
+            "cs" | "CS" => {
+                let tint = operands.first().and_then(Object::as_name).is_some_and(|name| self.is_tint_space(resources, name));
+                let black = if tint { Color32::WHITE } else { Color32::BLACK };
+                if operator == "cs" {
+                    (state.fill, state.fill_is_tint) = (black, tint);
+                } else {
+                    (state.stroke, state.stroke_is_tint) = (black, tint);
+                }
+            }
+            "sc" | "scn" => state.fill = color_of(&numbers(), state.fill_is_tint).unwrap_or(state.fill),
+            "SC" | "SCN" => state.stroke = color_of(&numbers(), state.stroke_is_tint).unwrap_or(state.stroke),
+
+            "BT" => {
+                canvas.text_matrix = IDENTITY;
+                canvas.line_matrix = IDENTITY;
+            }
+            "Tf" => {
+                state.font = operands.first().and_then(Object::as_name).and_then(|name| self.font(resources, name));
+                state.font_size = number(1);
+            }
+            "Tc" => state.char_spacing = number(0),
+            "Tw" => state.word_spacing = number(0),
+            "Tz" => state.horizontal_scale = number(0) / 100.0,
+            "TL" => state.leading = number(0),
+            "Ts" => state.rise = number(0),
+            "Td" | "TD" => {
+                if operator == "TD" {
+                    state.leading = -number(1);
+                }
+                canvas.line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &canvas.line_matrix);
+                canvas.text_matrix = canvas.line_matrix;
+            }
+            "Tm" => {
+                canvas.line_matrix = [number(0), number(1), number(2), number(3), number(4), number(5)];
+                canvas.text_matrix = canvas.line_matrix;
+            }
+            "T*" => canvas.next_line(),
+            "Tj" => canvas.show_text(operands.first().map(std::slice::from_ref).unwrap_or_default()),
+            "TJ" => canvas.show_text(operands.first().and_then(Object::as_array).unwrap_or_default()),
+            "'" => {
+                canvas.next_line();
+                canvas.show_text(operands.first().map(std::slice::from_ref).unwrap_or_default());
+            }
+            "\"" => {
+                state.word_spacing = number(0);
+                state.char_spacing = number(1);
+                canvas.next_line();
+                canvas.show_text(operands.get(2).map(std::slice::from_ref).unwrap_or_default());
+            }
+
+            "m" => {
+                let point = transform(&state.ctm, number(0), number(1));
+                canvas.path.push(vec![point]);
+                canvas.current = point;
+            }
+            "l" => {
+                let point = transform(&state.ctm, number(0), number(1));
+                canvas.line_to(point);
+            }
+            "c" | "v" | "y" => {
+                let values = numbers();
+                let ctm = state.ctm;
+                let at = |index: usize| transform(&ctm, values.get(index).copied().unwrap_or(0.0), values.get(index + 1).copied().unwrap_or(0.0));
+                let start = canvas.current;
+                let (first, second, end) = match operator {
+                    "c" => (at(0), at(2), at(4)),
+                    "v" => (start, at(0), at(2)),
+                    _ => (at(0), at(2), at(2)),
+                };
+                for step in 1..=CURVE_STEPS {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let u = 1.0 - t;
+                    let point = start.to_vec2() * (u * u * u) + first.to_vec2() * (3.0 * u * u * t)
+                        + second.to_vec2() * (3.0 * u * t * t) + end.to_vec2() * (t * t * t);
+                    canvas.line_to(point.to_pos2());
+                }
+            }
+            "re" => {
+                let (x, y, width, height) = (number(0), number(1), number(2), number(3));
+                let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+                let ctm = state.ctm;
+                canvas.path.push(corners.iter().map(|&(x, y)| transform(&ctm, x, y)).collect());
+                canvas.current = transform(&ctm, x, y);
+            }
+            "h" => {
+                if let Some(first) = canvas.path.last().and_then(|subpath| subpath.first().copied()) {
+                    canvas.line_to(first);
+                }
+            }
+            "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n" => {
+                let fill = matches!(operator, "f" | "F" | "f*" | "B" | "B*" | "b" | "b*").then_some(state.fill);
+                let scale = (state.ctm[0] * state.ctm[3] - state.ctm[1] * state.ctm[2]).abs().sqrt();
+                let stroke = matches!(operator, "S" | "s" | "B" | "B*" | "b" | "b*")
+                    .then(|| ((state.line_width * scale).max(0.5), state.stroke));
+                let subpaths: Vec<Vec<Pos2>> = std::mem::take(&mut canvas.path).into_iter().filter(|subpath| subpath.len() > 1).collect();
+                if !subpaths.is_empty() && (fill.is_some() || stroke.is_some()) {
+                    canvas.items.push(PdfItem::Path { subpaths, fill, stroke });
+                }
+            }
+            "Do" => {
+                if let Some(name) = operands.first().and_then(Object::as_name) {
+                    self.draw_xobject(resources, name, canvas, depth);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resource<'b>(&'b self, resources: &'b Dict, category: &str, name: &str) -> (Option<u32>, &'b Object) {
+        let entry = self.file.get(resources, category).as_dict().and_then(|entries| entries.get(name));
+        let number = match entry {
+            Some(Object::Ref(number)) => Some(*number),
+            _ => None,
+        };
+        (number, entry.map_or(&Object::Null, |entry| self.file.resolve(entry)))
+    }
+
+    fn font(&mut self, resources: &Dict, name: &str) -> Option<Rc<Font>> {
+        let (number, font) = self.resource(resources, "Font", name);
+        if let Some(cached) = number.and_then(|number| self.fonts.get(&number)) {
+            return Some(cached.clone());
+        }
+        let font = Rc::new(Font::load(self.file, font.as_dict()?));
+        if let Some(number) = number {
+            self.fonts.insert(number, font.clone());
+        }
+        Some(font)
+    }
+
+    fn is_tint_space(&self, resources: &Dict, name: &str) -> bool {
+        let (_, space) = self.resource(resources, "ColorSpace", name);
+        let family = space.as_array().and_then(|space| space.first()).and_then(Object::as_name);
+        matches!(family, Some("Separation" | "DeviceN"))
+    }
+
+    fn draw_xobject(&mut self, resources: &Dict, name: &str, canvas: &mut Canvas, depth: usize) {
+        let (number, xobject) = self.resource(resources, "XObject", name);
+        let Some(dict) = xobject.as_dict() else { return };
+        match name_of(dict, "Subtype") {
+            Some("Image") => {
+                let image = match number.and_then(|number| self.images.get(&number)) {
+                    Some(cached) => cached.clone(),
+                    None => {
+                        let decoded = decode_image(self.file, xobject, canvas.state.fill).map(|image| {
+                            self.next_image += 1;
+                            (self.next_image, Arc::new(image))
+                        });
+                        if let Some(number) = number {
+                            self.images.insert(number, decoded.clone());
+                        }
+                        decoded
+                    }
+                };
+                let Some((id, image)) = image else { return };
+                let ctm = canvas.state.ctm;
+                let corners = [transform(&ctm, 0.0, 0.0), transform(&ctm, 1.0, 0.0), transform(&ctm, 0.0, 1.0), transform(&ctm, 1.0, 1.0)];
+                canvas.items.push(PdfItem::Image { rect: Rect::from_points(&corners), id, image });
+            }
+            Some("Form") if depth < MAX_FORM_DEPTH => {
+                let Ok((content, None)) = self.file.decode(xobject) else { return };
+                let matrix = self.file.get(dict, "Matrix").as_array()
+                    .map(|values| values.iter().filter_map(|value| self.file.resolve(value).as_f32()).collect::<Vec<f32>>())
+                    .and_then(|values| <[f32; 6]>::try_from(values).ok())
+                    .unwrap_or(IDENTITY);
+                let form_resources = self.file.get(dict, "Resources").as_dict().cloned().unwrap_or_else(|| resources.clone());
+                canvas.states.push(canvas.state.clone());
+                canvas.state.ctm = multiply(&matrix, &canvas.state.ctm);
+                self.run(&content, &form_resources, canvas, depth + 1);
+                if let Some(saved) = canvas.states.pop() {
+                    canvas.state = saved;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Canvas {
+    fn line_to(&mut self, point: Pos2) {
+        match self.path.last_mut() {
+            Some(subpath) => subpath.push(point),
+            None => self.path.push(vec![self.current, point]),
+        }
+        self.current = point;
+    }
+
+    fn next_line(&mut self) {
+        self.line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -self.state.leading], &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    // Tj and TJ: strings are shown, numbers move the next glyph back by thousandths of the font size
+    fn show_text(&mut self, parts: &[Object]) {
+        let Some(font) = self.state.font.clone() else { return };
+        let state = &self.state;
+        let rendering = |text_matrix: &Matrix| {
+            let scaled = [state.font_size * state.horizontal_scale, 0.0, 0.0, state.font_size, 0.0, state.rise];
+            multiply(&multiply(&scaled, text_matrix), &state.ctm)
+        };
+        let start = rendering(&self.text_matrix);
+        let origin = transform(&start, 0.0, 0.0);
+        let size = (start[2] * start[2] + start[3] * start[3]).sqrt();
+        let angle = start[1].atan2(start[0]);
+        let mut text = String::new();
+        for part in parts {
+            match part {
+                Object::String(bytes) => {
+                    for (code, unicode) in font.codes(bytes) {
+                        text.push_str(unicode);
+                        let word_space = if font.code_bytes == 1 && code == 32 { state.word_spacing } else { 0.0 };
+                        let advance = (font.width(code) / 1000.0 * state.font_size + state.char_spacing + word_space) * state.horizontal_scale;
+                        self.text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, advance, 0.0], &self.text_matrix);
+                    }
+                }
+                Object::Number(adjustment) => {
+                    if *adjustment < -WORD_GAP && !text.is_empty() && !text.ends_with(' ') {
+                        text.push(' ');
+                    }
+                    let advance = -adjustment / 1000.0 * state.font_size * state.horizontal_scale;
+                    self.text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, advance, 0.0], &self.text_matrix);
+                }
+                _ => {}
+            }
+        }
+        let end = transform(&rendering(&self.text_matrix), 0.0, 0.0);
+        let width = end.distance(origin);
+        if text.trim().is_empty() || size < 0.1 {
+            return;
+        }
+        let color = state.fill;
+        // Runs continuing one another on a line are joined, so words drawn a glyph at a time can be found
+        if let Some(PdfItem::Text { origin: previous, size: previous_size, width: previous_width, angle: previous_angle, text: previous_text, color: previous_color, .. }) = self.items.last_mut() {
+            let gap = origin.x - (previous.x + *previous_width);
+            let same_line = angle.abs() < 0.01 && previous_angle.abs() < 0.01 && (origin.y - previous.y).abs() < size * 0.2;
+            if same_line && (*previous_size - size).abs() < 0.5 && *previous_color == color && gap > -size * 0.3 && gap < size * 0.6 {
+                if gap > size * 0.15 && !previous_text.ends_with(' ') && !text.starts_with(' ') {
+                    previous_text.push(' ');
+                }
+                previous_text.push_str(&text);
+                *previous_width = end.x - previous.x;
+                return;
+            }
+        }
+        self.items.push(PdfItem::Text { origin, size, width, angle, text, color, monospace: font.monospace });
+    }
+}
+
+// Past the image data between ID and EI; inline images are small and not drawn
+fn skip_inline_image(lexer: &mut Lexer) {
+    while let Some(object) = lexer.object() {
+        if object == Object::Operator("ID".to_string()) {
+            break;
+        }
+    }
+    let data = lexer.data;
+    let mut pos = lexer.pos + 1;
+    while let Some(found) = find(data, b"EI", pos) {
+        let before = found == 0 || is_whitespace(data[found - 1]);
+        let after = data.get(found + 2).is_none_or(|&byte| is_whitespace(byte));
+        if before && after {
+            lexer.pos = found + 2;
+            return;
+        }
+        pos = found + 2;
+    }
+    lexer.pos = data.len();
+}
+
+fn gray(value: f32) -> Color32 {
+    let value = (value.clamp(0.0, 1.0) * 255.0) as u8;
+    Color32::from_rgb(value, value, value)
+}
+
+fn rgb(r: f32, g: f32, b: f32) -> Color32 {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u8;
+    Color32::from_rgb(channel(r), channel(g), channel(b))
+}
+
+fn cmyk(c: f32, m: f32, y: f32, k: f32) -> Color32 {
+    let k = k.clamp(0.0, 1.0);
+    rgb((1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k))
+}
+
+fn color_of(components: &[f32], tint: bool) -> Option<Color32> {
+    match components {
+        [tint_value] if tint => Some(gray(1.0 - tint_value)),
+        [value] => Some(gray(*value)),
+        [r, g, b] => Some(rgb(*r, *g, *b)),
+        [c, m, y, k] => Some(cmyk(*c, *m, *y, *k)),
+        _ => None,
+    }
+}
+
+// Images
+
+fn decode_image(file: &File, stream: &Object, fill: Color32) -> Option<ColorImage> {
+    let dict = stream.as_dict()?;
+    let (data, filter) = file.decode(stream).ok()?;
+    let mut image = match filter.as_deref() {
+        Some("DCTDecode" | "DCT") => {
+            // The header's size is checked before the JPEG is decoded into memory
+            let reader = image::ImageReader::with_format(std::io::Cursor::new(&data), image::ImageFormat::Jpeg);
+            let (width, height) = reader.into_dimensions().ok()?;
+            if (width as usize).checked_mul(height as usize)? > MAX_IMAGE_PIXELS {
+                return None;
+            }
+            let decoded = image::load_from_memory_with_format(&data, image::ImageFormat::Jpeg).ok()?.to_rgba8();
+            let size = [decoded.width() as usize, decoded.height() as usize];
+            ColorImage::from_rgba_unmultiplied(size, decoded.as_raw())
+        }
+        Some(_) => return None,
+        None => raw_image(file, dict, &data, fill)?,
+    };
+    // A soft mask of the same size gives the image its transparency
+    if let Some(mask) = file.get(dict, "SMask").as_dict().map(|_| file.get(dict, "SMask")) {
+        if let Some(mask) = decode_image(file, mask, Color32::WHITE).filter(|mask| mask.size == image.size) {
+            for (pixel, alpha) in image.pixels.iter_mut().zip(&mask.pixels) {
+                let [r, g, b, _] = pixel.to_srgba_unmultiplied();
+                *pixel = Color32::from_rgba_unmultiplied(r, g, b, alpha.r());
+            }
+        }
+    }
+    Some(image)
+}
+
+// Samples packed 1, 2, 4, 8 or 16 bits each, rows starting on byte boundaries
+fn raw_image(file: &File, dict: &Dict, data: &[u8], fill: Color32) -> Option<ColorImage> {
+    let width = file.number(dict, "Width")? as usize;
+    let height = file.number(dict, "Height")? as usize;
+    let pixel_count = width.checked_mul(height)?;
+    if pixel_count == 0 || pixel_count > MAX_IMAGE_PIXELS {
+        return None;
+    }
+    let stencil = matches!(file.get(dict, "ImageMask"), Object::Bool(true));
+    let bits = if stencil { 1 } else { file.number(dict, "BitsPerComponent").unwrap_or(8.0) as usize };
+    if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+        return None;
+    }
+    // Indexed images look their colours up in a palette of a base colour space
+    let space = file.get(dict, "ColorSpace");
+    let (components, palette) = match space {
+        Object::Array(parts) if parts.first().and_then(Object::as_name) == Some("Indexed") => {
+            let base = parts.get(1).map_or(1, |base| space_components(file, file.resolve(base)));
+            let palette = match parts.get(3).map(|lookup| file.resolve(lookup)) {
+                Some(Object::String(bytes)) => bytes.clone(),
+                Some(stream @ Object::Stream(..)) => file.decode(stream).ok()?.0,
+                _ => return None,
+            };
+            (1, Some((base, palette)))
+        }
+        _ if stencil => (1, None),
+        space => (space_components(file, space), None),
+    };
+    if components > MAX_COMPONENTS {
+        return None;
+    }
+    let row_bits = width.checked_mul(components)?.checked_mul(bits)?;
+    let row_bytes = row_bits.div_ceil(8);
+    if data.len() < row_bytes.checked_mul(height)? {
+        return None;
+    }
+    let max = ((1u32 << bits) - 1) as f32;
+    let sample = |row: &[u8], index: usize| -> u32 {
+        let bit = index * bits;
+        match bits {
+            8 => row[index] as u32,
+            16 => row[index * 2] as u32,
+            _ => (row[bit / 8] >> (8 - bits - bit % 8)) as u32 & ((1 << bits) - 1),
+        }
+    };
+    let scale = |value: u32| if bits == 16 { value as f32 / 255.0 } else { value as f32 / max };
+    let mut pixels = Vec::with_capacity(pixel_count);
+    for row in data.chunks(row_bytes).take(height) {
+        for x in 0..width {
+            let values: Vec<u32> = (0..components).map(|component| sample(row, x * components + component)).collect();
+            let color = if stencil {
+                // Painted where the sample is 0, unless /Decode swaps it
+                let inverted = file.get(dict, "Decode").as_array().and_then(|decode| decode.first()).and_then(Object::as_f32) == Some(1.0);
+                if (values[0] == 0) != inverted { fill } else { Color32::TRANSPARENT }
+            } else if let Some((base, palette)) = &palette {
+                let start = values[0] as usize * base;
+                let entry: Vec<f32> = (0..*base).map(|offset| *palette.get(start + offset).unwrap_or(&0) as f32 / 255.0).collect();
+                color_of(&entry, false).unwrap_or(Color32::BLACK)
+            } else {
+                let values: Vec<f32> = values.into_iter().map(scale).collect();
+                color_of(&values, false).unwrap_or(Color32::BLACK)
+            };
+            pixels.push(color);
+        }
+    }
+    Some(ColorImage { size: [width, height], pixels })
+}
+
+fn space_components(file: &File, space: &Object) -> usize {
+    match space {
+        Object::Name(name) => match name.as_str() {
+            "DeviceRGB" | "CalRGB" | "RGB" => 3,
+            "DeviceCMYK" | "CMYK" => 4,
+            _ => 1,
+        },
+        Object::Array(parts) => match parts.first().and_then(Object::as_name) {
+            Some("ICCBased") => parts.get(1)
+                .and_then(|profile| file.resolve(profile).as_dict())
+                .and_then(|profile| file.number(profile, "N"))
+                .map_or(3, |n| n as usize),
+            Some("CalRGB" | "Lab") => 3,
+            Some("DeviceN") => parts.get(1).and_then(|names| file.resolve(names).as_array()).map_or(1, <[Object]>::len),
+            _ => 1,
+        },
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::paged_layout::{self, BlockKind, PrintLine, PrintSettings};
+
+    #[test]
+    fn test_reads_back_printed_pages() {
+        let settings = PrintSettings::default();
+        let lines = vec![
+            PrintLine { kind: BlockKind::Heading(1), text: "Quarterly (report)".to_string(), height: 30.0, gap_before: 0.0 },
+            PrintLine { kind: BlockKind::Paragraph, text: "Revenue grew in every region".to_string(), height: 20.0, gap_before: 8.0 },
+        ];
+        let (_, _, _, content_height) = settings.content_rect_px();
+        let pages = paged_layout::paginate(&lines, content_height);
+        let pdf = paged_layout::write_pdf(&settings, &lines, &pages, "Report", "https://example.com/");
+
+        let document = PdfDocument::parse(&pdf).unwrap();
+        assert_eq!(document.pages.len(), pages.len());
+        let texts: Vec<&str> = document.pages[0].items.iter()
+            .filter_map(|item| match item {
+                PdfItem::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(texts.contains(&"Quarterly (report)"), "{:?}", texts);
+        let found = document.find("REGION");
+        assert_eq!(found.len(), 1);
+        let PdfItem::Text { text, origin, .. } = &document.pages[0].items[found[0].item] else { panic!() };
+        assert_eq!(text.chars().skip(found[0].start).take(6).collect::<String>(), "region");
+        assert!(origin.y > 0.0 && origin.y < document.pages[0].size.y);
+        assert!(PdfDocument::parse(b"<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_compressed_content_and_unicode_map() {
+        use std::io::Write;
+        let content = b"BT /F1 10 Tf 72 700 Td <00010002> Tj ET 0 0 1 rg 10 10 50 20 re f";
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let cmap = b"1 begincodespacerange <0000> <ffff> endcodespacerange 1 beginbfrange <0001> <0002> <00e9> endbfrange";
+        let mut pdf = b"%PDF-1.7\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+            2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 /MediaBox [0 0 200 800] >> endobj\n\
+            3 0 obj << /Type /Page /Parent 2 0 R /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >> endobj\n".to_vec();
+        pdf.extend(format!("4 0 obj << /Length {} /Filter /FlateDecode >>\nstream\n", compressed.len()).into_bytes());
+        pdf.extend(&compressed);
+        pdf.extend(b"\nendstream endobj\n5 0 obj << /Type /Font /Subtype /Type0 /BaseFont /Sans /ToUnicode 6 0 R >> endobj\n");
+        pdf.extend(format!("6 0 obj << /Length {} >>\nstream\n", cmap.len()).into_bytes());
+        pdf.extend(cmap);
+        pdf.extend(b"\nendstream endobj\ntrailer << /Root 1 0 R >>\n%%EOF");
+
+        let document = PdfDocument::parse(&pdf).unwrap();
+        let page = &document.pages[0];
+        assert_eq!(page.size, vec2(200.0, 800.0));
+        let PdfItem::Text { text, origin, size, .. } = &page.items[0] else { panic!("no text") };
+        assert_eq!(text, "éê");
+        assert_eq!((*origin, *size), (pos2(72.0, 100.0), 10.0));
+        let PdfItem::Path { subpaths, fill, .. } = &page.items[1] else { panic!("no path") };
+        assert_eq!(*fill, Some(Color32::BLUE));
+        assert_eq!(Rect::from_points(&subpaths[0]), Rect::from_min_max(pos2(10.0, 770.0), pos2(60.0, 790.0)));
+    }
+
+    #[test]
+    fn test_sizes_from_the_file_are_bounded() {
+        use std::io::Write;
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&vec![0u8; 1024 * 1024]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(inflate(&compressed, 4096).len(), 4096);
+
+        let mut pdf = b"%PDF-1.7\n1 0 obj << /DecodeParms << /Predictor 12 /Columns 4000000000 >> >> endobj\n\
+            2 0 obj << /Width 4000000000 /Height 4000000000 >> endobj\n\
+            3 0 obj << /Type /ObjStm /N 4000000000 /First 4000000000 /Length 7 >>\nstream\n1 1 1 1\nendstream endobj\n".to_vec();
+        pdf.extend(b"trailer << >>\n%%EOF");
+        let file = File::scan(&pdf);
+        let params = file.objects[&1].as_dict().unwrap().get("DecodeParms").unwrap().as_dict().unwrap();
+        assert_eq!(unpredict(vec![2, 1, 2, 3], Some((&file, params))), vec![1, 2, 3]);
+        assert!(raw_image(&file, file.objects[&2].as_dict().unwrap(), &[0; 16], Color32::BLACK).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+// Speculative loading: pages the user is likely to open next, the top address bar suggestion and pages'
+// `<link rel="prefetch">` targets, are fetched into the HTTP cache while nothing else uses the network
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::engine::ParsedDocument;
+use crate::engine::executor::{Executor, TaskKind};
+use crate::networking::http_cache::HttpCache;
+use crate::networking::manual_client::{FetchContext, ManualHttpClient};
+use crate::networking::throttling::NetworkConditions;
+use crate::storage::Preferences;
+
+// How long a prefetched page is used without asking the server again
+const PREFETCH_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+// Bigger pages are left for when they're actually opened
+const MAX_PREFETCH_SIZE: usize = 2 * 1024 * 1024;
+
+// Parsed documents kept for pages opened from a prefetch
+const MAX_PREPARSED: usize = 4;
+
+// Addresses remembered as already prefetched before the list starts over
+const MAX_REMEMBERED: usize = 256;
+
+const IDLE_POLL: Duration = Duration::from_millis(500);
+
+static SHARED: OnceLock<Prefetcher> = OnceLock::new();
+
+#[derive(Default)]
+struct PrefetchState {
+    queue: VecDeque<(String, FetchContext)>,
+    requested: HashSet<String>,
+    running: bool,
+    preparsed: HashMap<String, (ParsedDocument, Instant)>,
+}
+
+#[derive(Default)]
+pub struct Prefetcher {
+    state: Mutex<PrefetchState>,
+}
+
+// Nothing the user asked for is loading, and the network isn't being throttled for testing
+fn network_idle() -> bool {
+    let executor = Executor::shared();
+    executor.active_tasks(TaskKind::Network) == 0
+        && executor.active_tasks(TaskKind::Download) == 0
+        && NetworkConditions::current() == NetworkConditions::NoThrottling
+}
+
+impl Prefetcher {
+    pub fn shared() -> &'static Prefetcher {
+        SHARED.get_or_init(Prefetcher::default)
+    }
+
+    /// Queue http(s) `urls` for prefetching with `context`'s cookies and referrer, up to the configured number
+    pub fn prefetch(&self, client: &ManualHttpClient, context: FetchContext, urls: impl IntoIterator<Item = String>) {
+        let settings = Preferences::current().prefetch.clone();
+        if !settings.enabled {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else { return };
+        if state.requested.len() > MAX_REMEMBERED {
+            state.requested.clear();
+        }
+        let urls = urls.into_iter()
+            .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+            .take(settings.max_pages)
+            .collect::<Vec<_>>();
+        for url in urls {
+            if state.requested.insert(url.clone()) {
+                state.queue.push_back((url, context.clone()));
+            }
+        }
+        if state.running || state.queue.is_empty() {
+            return;
+        }
+        state.running = true;
+        let mut client = client.clone();
+        client.set_max_body_size(Some(MAX_PREFETCH_SIZE));
+        Executor::shared().spawn(TaskKind::Prefetch, async move {
+            Prefetcher::shared().run(client, settings.preparse).await;
+        });
+    }
+
+    // Work through the queue one page at a time, waiting whenever the network is busy
+    async fn run(&self, client: ManualHttpClient, preparse: bool) {
+        loop {
+            while !network_idle() {
+                tokio::time::sleep(IDLE_POLL).await;
+            }
+            let Some((url, context)) = self.next() else { return };
+            let fetched = match client.fetch_with_context(&url, &context).await {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    log::debug!("Prefetch of {} failed: {}", url, e);
+                    continue;
+                }
+            };
+            let mut response = fetched.response;
+            // The prefetch's own size cap isn't the limit the page is opened with
+            response.size_limit = None;
+            if !response.is_success() || response.oversized.is_some() {
+                continue;
+            }
+            HttpCache::shared().store_for(&fetched.final_url, &fetched.request_headers, &response, PREFETCH_LIFETIME);
+            log::debug!("Prefetched {}", fetched.final_url);
+
+            if preparse && ParsedDocument::should_preparse(&response) {
+                let document = Executor::shared()
+                    .spawn_blocking(TaskKind::Parse, move || response.body_as_string().ok().map(|html| ParsedDocument::parse(&html)))
+                    .join()
+                    .await
+                    .ok()
+                    .flatten();
+                if let (Some(document), Ok(mut state)) = (document, self.state.lock()) {
+                    state.preparsed.retain(|_, (_, parsed)| parsed.elapsed() < PREFETCH_LIFETIME);
+                    if state.preparsed.len() >= MAX_PREPARSED {
+                        if let Some(oldest) = state.preparsed.iter().min_by_key(|(_, (_, parsed))| *parsed).map(|(url, _)| url.clone()) {
+                            state.preparsed.remove(&oldest);
+                        }
+                    }
+                    state.preparsed.insert(fetched.final_url, (document, Instant::now()));
+                }
+            }
+        }
+    }
+
+    // The next queued page, or None after marking the run finished so a later `prefetch` starts another
+    fn next(&self) -> Option<(String, FetchContext)> {
+        let mut state = self.state.lock().ok()?;
+        let next = state.queue.pop_front();
+        state.running = next.is_some();
+        next
+    }
+
+    /// The document parsed for `url` when it was prefetched, for a navigation the cache answered
+    pub fn take_preparsed(&self, url: &str) -> Option<ParsedDocument> {
+        let mut state = self.state.lock().ok()?;
+        let (document, parsed) = state.preparsed.remove(url)?;
+        (parsed.elapsed() < PREFETCH_LIFETIME).then_some(document)
+    }
+}
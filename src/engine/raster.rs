@@ -0,0 +1,163 @@
+// Software rasterizer for egui's tessellated output, so a page can be painted to an image without a GPU
+use eframe::egui::{self, Color32, ImageData, Pos2, TextureId};
+use eframe::egui::epaint::{ClippedPrimitive, Mesh, Primitive};
+use std::collections::HashMap;
+
+// A texture as premultiplied RGBA texels
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+}
+
+/// Keeps the textures egui has uploaded and paints meshes that use them into an RGBA image
+#[derive(Default)]
+pub struct SoftwareRasterizer {
+    textures: HashMap<TextureId, Texture>,
+}
+
+impl SoftwareRasterizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take in the texture changes of one frame, as a GPU painter would
+    pub fn apply_textures(&mut self, delta: &egui::TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            let (size, pixels) = match &image_delta.image {
+                ImageData::Color(image) => (image.size, image.pixels.clone()),
+                ImageData::Font(image) => (image.size, image.srgba_pixels(None).collect()),
+            };
+            match image_delta.pos {
+                None => {
+                    self.textures.insert(*id, Texture { size, pixels });
+                }
+                // A patch of a texture already uploaded, like new glyphs in the font atlas
+                Some([x, y]) => {
+                    let Some(texture) = self.textures.get_mut(id) else { continue };
+                    for row in 0..size[1] {
+                        for column in 0..size[0] {
+                            let (tx, ty) = (x + column, y + row);
+                            if tx < texture.size[0] && ty < texture.size[1] {
+                                texture.pixels[ty * texture.size[0] + tx] = pixels[row * size[0] + column];
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    /// Paint `primitives` over `background` into a `width` x `height` image, one pixel per point
+    pub fn paint(&self, width: u32, height: u32, primitives: &[ClippedPrimitive], background: Color32) -> image::RgbaImage {
+        let mut pixels = vec![background; (width * height) as usize];
+        for primitive in primitives {
+            // Paint callbacks draw with the GPU directly; there's nothing to rasterize for them
+            if let Primitive::Mesh(mesh) = &primitive.primitive {
+                let clip = primitive.clip_rect.intersect(egui::Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32)));
+                self.paint_mesh(&mut pixels, width as usize, mesh, clip);
+            }
+        }
+        let mut image = image::RgbaImage::new(width, height);
+        for (pixel, color) in image.pixels_mut().zip(pixels) {
+            *pixel = image::Rgba(color.to_srgba_unmultiplied());
+        }
+        image
+    }
+
+    fn paint_mesh(&self, pixels: &mut [Color32], width: usize, mesh: &Mesh, clip: egui::Rect) {
+        let Some(texture) = self.textures.get(&mesh.texture_id) else { return };
+        if clip.is_negative() {
+            return;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let area = edge(a.pos, b.pos, c.pos);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            let bounds = egui::Rect::from_points(&[a.pos, b.pos, c.pos]).intersect(clip);
+            if bounds.is_negative() {
+                continue;
+            }
+            for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+                for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+                    // Sample at the pixel's centre; dividing by the area makes the weights work for either winding
+                    let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let weights = [edge(b.pos, c.pos, p) / area, edge(c.pos, a.pos, p) / area, edge(a.pos, b.pos, p) / area];
+                    if weights.iter().any(|w| *w < 0.0) {
+                        continue;
+                    }
+                    let uv = a.uv.to_vec2() * weights[0] + b.uv.to_vec2() * weights[1] + c.uv.to_vec2() * weights[2];
+                    let tint = blend_weights([a.color, b.color, c.color], weights);
+                    let source = multiply(texture.sample(uv.x, uv.y), tint);
+                    let destination = &mut pixels[y * width + x];
+                    *destination = over(source, *destination);
+                }
+            }
+        }
+    }
+}
+
+impl Texture {
+    fn sample(&self, u: f32, v: f32) -> Color32 {
+        let x = ((u * self.size[0] as f32) as usize).min(self.size[0].saturating_sub(1));
+        let y = ((v * self.size[1] as f32) as usize).min(self.size[1].saturating_sub(1));
+        self.pixels.get(y * self.size[0] + x).copied().unwrap_or(Color32::TRANSPARENT)
+    }
+}
+
+// Twice the signed area of the triangle a, b, p
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+fn blend_weights(colors: [Color32; 3], weights: [f32; 3]) -> Color32 {
+    let channel = |i: usize| {
+        let value: f32 = colors.iter().zip(weights).map(|(color, weight)| color.to_array()[i] as f32 * weight).sum();
+        value.round().clamp(0.0, 255.0) as u8
+    };
+    Color32::from_rgba_premultiplied(channel(0), channel(1), channel(2), channel(3))
+}
+
+fn multiply(a: Color32, b: Color32) -> Color32 {
+    let [a, b] = [a.to_array(), b.to_array()];
+    let channel = |i: usize| ((a[i] as u16 * b[i] as u16 + 127) / 255) as u8;
+    Color32::from_rgba_premultiplied(channel(0), channel(1), channel(2), channel(3))
+}
+
+// Premultiplied source-over blending
+fn over(source: Color32, destination: Color32) -> Color32 {
+    let [s, d] = [source.to_array(), destination.to_array()];
+    let remaining = 255 - s[3] as u16;
+    let channel = |i: usize| (s[i] as u16 + (d[i] as u16 * remaining + 127) / 255).min(255) as u8;
+    Color32::from_rgba_premultiplied(channel(0), channel(1), channel(2), channel(3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paints_a_rectangle() {
+        let ctx = egui::Context::default();
+        let mut rasterizer = SoftwareRasterizer::new();
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(Pos2::ZERO, egui::vec2(40.0, 30.0))),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            ctx.layer_painter(egui::LayerId::background())
+                .rect_filled(egui::Rect::from_min_max(Pos2::new(10.0, 10.0), Pos2::new(20.0, 20.0)), 0.0, Color32::RED);
+        });
+        rasterizer.apply_textures(&output.textures_delta);
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let image = rasterizer.paint(40, 30, &primitives, Color32::WHITE);
+
+        assert_eq!(image.get_pixel(15, 15).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(25, 15).0, [255, 255, 255, 255]);
+    }
+}
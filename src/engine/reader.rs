@@ -0,0 +1,126 @@
+// Reader mode: the main text of a page without navigation, sidebars or page styling
+use crate::engine::dom::DOMNode;
+use crate::engine::paged_layout::{self, BlockKind, PrintBlock};
+
+// Average adult silent reading speed
+const WORDS_PER_MINUTE: usize = 230;
+// Pages with less text than this aren't worth a reader view
+const MIN_ARTICLE_WORDS: usize = 80;
+
+// Page furniture that never belongs to the article itself
+const BOILERPLATE_TAGS: &[&str] = &["nav", "header", "footer", "aside", "form", "button", "menu", "iframe", "svg"];
+const BOILERPLATE_HINTS: &[&str] = &["nav", "menu", "sidebar", "footer", "comment", "share", "social", "banner", "cookie", "promo", "related"];
+
+#[derive(Debug, Clone)]
+pub struct ReaderArticle {
+    pub title: String,
+    pub blocks: Vec<PrintBlock>,
+    pub word_count: usize,
+}
+
+impl ReaderArticle {
+    /// The page's main content, or None when it doesn't look like an article
+    pub fn extract(dom: &DOMNode, title: &str) -> Option<Self> {
+        let root = content_root(dom).unwrap_or(dom);
+        let mut blocks = paged_layout::collect_blocks(&without_boilerplate(root));
+        // The title is shown above the article, so don't repeat it as the first heading
+        if blocks.first().is_some_and(|b| matches!(b.kind, BlockKind::Heading(_)) && b.text.trim() == title.trim()) {
+            blocks.remove(0);
+        }
+
+        let word_count = blocks.iter()
+            .filter(|b| !matches!(b.kind, BlockKind::Rule))
+            .map(|b| b.text.split_whitespace().count())
+            .sum();
+        (word_count >= MIN_ARTICLE_WORDS).then(|| Self { title: title.to_string(), blocks, word_count })
+    }
+
+    /// Estimated minutes to read, at least one
+    pub fn reading_minutes(&self) -> usize {
+        self.word_count.div_ceil(WORDS_PER_MINUTE).max(1)
+    }
+}
+
+// <article> or <main> when the page marks its content up, otherwise the element holding the most paragraph text
+fn content_root(dom: &DOMNode) -> Option<&DOMNode> {
+    fn find<'a>(node: &'a DOMNode, tag: &str) -> Option<&'a DOMNode> {
+        let DOMNode::Element { tag_name, children, .. } = node else { return None };
+        if tag_name == tag {
+            return Some(node);
+        }
+        children.iter().find_map(|child| find(child, tag))
+    }
+
+    fn densest<'a>(node: &'a DOMNode, best: &mut Option<(usize, &'a DOMNode)>) {
+        let DOMNode::Element { children, .. } = node else { return };
+        if is_boilerplate(node) {
+            return;
+        }
+        let score: usize = children.iter()
+            .filter(|child| child.tag_name().is_some_and(|tag| tag == "p"))
+            .map(|p| p.collect_text().split_whitespace().count())
+            .sum();
+        if score > best.map_or(0, |(top, _)| top) {
+            *best = Some((score, node));
+        }
+        children.iter().for_each(|child| densest(child, best));
+    }
+
+    if let Some(article) = find(dom, "article").or_else(|| find(dom, "main")) {
+        return Some(article);
+    }
+    let mut best = None;
+    densest(dom, &mut best);
+    best.map(|(_, node)| node)
+}
+
+fn is_boilerplate(node: &DOMNode) -> bool {
+    let DOMNode::Element { tag_name, attributes, .. } = node else { return false };
+    if BOILERPLATE_TAGS.contains(&tag_name.as_str()) || attributes.get("role").is_some_and(|role| role == "navigation") {
+        return true;
+    }
+    let names = format!(
+        "{} {}",
+        attributes.get("id").map(String::as_str).unwrap_or_default(),
+        attributes.get("class").map(String::as_str).unwrap_or_default(),
+    ).to_ascii_lowercase();
+    names.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|name| BOILERPLATE_HINTS.contains(&name))
+}
+
+fn without_boilerplate(node: &DOMNode) -> DOMNode {
+    match node {
+        DOMNode::Element { tag_name, attributes, children } => DOMNode::Element {
+            tag_name: tag_name.clone(),
+            attributes: attributes.clone(),
+            children: children.iter()
+                .filter(|child| !is_boilerplate(child))
+                .map(without_boilerplate)
+                .collect(),
+        },
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_extract_article() {
+        let body = "word ".repeat(300);
+        let html = format!(
+            "<html><body><nav class=\"top-nav\"><a href=\"/\">Home</a></nav>\
+             <div class=\"content\"><h1>Story</h1><p>{body}</p><div class=\"share-buttons\">Share this</div><p>{body}</p></div>\
+             <footer>Copyright</footer></body></html>"
+        );
+        let article = ReaderArticle::extract(&html_parser::parse(&html), "Story").unwrap();
+        assert_eq!(article.word_count, 600);
+        assert_eq!(article.reading_minutes(), 3);
+        assert!(article.blocks.iter().all(|b| b.kind == BlockKind::Paragraph));
+        assert!(!article.blocks.iter().any(|b| b.text.contains("Share") || b.text.contains("Home")));
+
+        assert!(ReaderArticle::extract(&html_parser::parse("<p>Too short</p>"), "").is_none());
+    }
+}
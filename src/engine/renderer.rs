@@ -97,7 +97,7 @@ fn render_layout_box(list: &mut DisplayList, layout_box: &LayoutBox) {
                 render_text(list, layout_box, text);
             }
         }
-        BoxType::AnonymousBlock => {}
+        BoxType::FlexNode(_) | BoxType::GridNode(_) | BoxType::AnonymousBlock => {}
     }
     
     for child in &layout_box.children {
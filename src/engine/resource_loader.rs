@@ -0,0 +1,518 @@
+// Subresource loading: stylesheets, scripts, images and web fonts referenced by a parsed page
+
+use crate::engine::dom::DOMNode;
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::engine::fonts;
+use crate::engine::svg::SvgDocument;
+use crate::networking::image_loader::ImageCache;
+use crate::networking::manual_client::{FetchContext, ManualHttpClient};
+use crate::networking::timing::RequestTiming;
+use crate::extensions::web_request::ResourceType;
+use crate::storage::ImageLoadingMode;
+use egui::ColorImage;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+
+/// Default number of subresource fetches in flight per page
+pub const DEFAULT_MAX_CONCURRENT: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Stylesheet,
+    Script,
+    Image,
+    /// Requested by `@font-face` once a stylesheet that uses the family has loaded
+    Font,
+}
+
+impl ResourceKind {
+    /// How extension request rules see a fetch of this kind
+    pub fn resource_type(self) -> ResourceType {
+        match self {
+            ResourceKind::Stylesheet => ResourceType::Stylesheet,
+            ResourceKind::Script => ResourceType::Script,
+            ResourceKind::Image => ResourceType::Image,
+            ResourceKind::Font => ResourceType::Font,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceRequest {
+    pub kind: ResourceKind,
+    /// Absolute URL to fetch
+    pub url: String,
+    /// Attribute value as written in the document, used to match the resource back to its element
+    pub reference: String,
+    /// Deferred until the element scrolls near the viewport
+    pub lazy: bool,
+}
+
+pub enum ResourceContent {
+    Stylesheet(String),
+    Script(String),
+    Image(Arc<ColorImage>),
+    /// Kept as shapes and painted at whatever size the element ends up
+    Svg(Arc<SvgDocument>),
+    /// Unpacked to plain TrueType/OpenType and checked to be loadable
+    Font(Vec<u8>),
+}
+
+pub struct LoadedResource {
+    pub request: ResourceRequest,
+    pub result: Result<ResourceContent, String>,
+    /// Missing when the fetch failed before a response arrived or the resource came from a cache
+    pub timing: Option<RequestTiming>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceProgress {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+impl ResourceProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.completed >= self.total
+    }
+}
+
+/// Find every stylesheet, external script and image in document order, resolved against `base_url`
+pub fn discover_resources(dom: &DOMNode, base_url: &str, image_mode: ImageLoadingMode) -> Vec<ResourceRequest> {
+    let base = url::Url::parse(base_url).ok();
+    let mut requests = Vec::new();
+    let mut seen = HashSet::new();
+    collect_resources(dom, base.as_ref(), image_mode, &mut requests, &mut seen);
+    requests
+}
+
+fn collect_resources(
+    node: &DOMNode,
+    base: Option<&url::Url>,
+    image_mode: ImageLoadingMode,
+    requests: &mut Vec<ResourceRequest>,
+    seen: &mut HashSet<(ResourceKind, String)>,
+) {
+    let DOMNode::Element { tag_name, attributes, children } = node else {
+        return;
+    };
+
+    let found = match tag_name.as_str() {
+        "link" => {
+            let is_stylesheet = attributes.get("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("stylesheet")));
+            if is_stylesheet { attributes.get("href").map(|href| (ResourceKind::Stylesheet, href)) } else { None }
+        }
+        "script" => attributes.get("src").map(|src| (ResourceKind::Script, src)),
+        "img" => attributes.get("src").map(|src| (ResourceKind::Image, src)),
+        "video" => attributes.get("poster").map(|poster| (ResourceKind::Image, poster)),
+        _ => None,
+    };
+
+    if let Some((kind, reference)) = found {
+        let reference = reference.trim();
+        let resolved = match base {
+            Some(base) => base.join(reference).ok(),
+            None => url::Url::parse(reference).ok(),
+        };
+        // Only a local page may load local files, so a web page can't read them off disk
+        let local_page = base.is_some_and(|base| base.scheme() == "file");
+        let allowed = |u: &url::Url| matches!(u.scheme(), "http" | "https" | "data" | "blob") || (local_page && u.scheme() == "file");
+        if let Some(url) = resolved.filter(allowed) {
+            if seen.insert((kind, url.to_string())) {
+                let lazy = kind == ResourceKind::Image
+                    && image_mode.defers(attributes.get("loading").map(String::as_str));
+                requests.push(ResourceRequest { kind, url: url.to_string(), reference: reference.to_string(), lazy });
+            }
+        }
+    }
+
+    for child in children {
+        collect_resources(child, base, image_mode, requests, seen);
+    }
+}
+
+/// A `<link>` asking for a connection or DNS lookup to an origin the page is about to use, or for a page
+/// the user is likely to open next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceHint {
+    Preconnect(String),
+    DnsPrefetch(String),
+    Prefetch(String),
+}
+
+/// Every preconnect, dns-prefetch and prefetch link in the page, resolved against `base_url`
+pub fn resource_hints(dom: &DOMNode, base_url: &str) -> Vec<ResourceHint> {
+    let base = url::Url::parse(base_url).ok();
+    let mut hints = Vec::new();
+    collect_hints(dom, base.as_ref(), &mut hints);
+    hints
+}
+
+fn collect_hints(node: &DOMNode, base: Option<&url::Url>, hints: &mut Vec<ResourceHint>) {
+    let DOMNode::Element { tag_name, attributes, children } = node else {
+        return;
+    };
+    if let ("link", Some(rel), Some(href)) = (tag_name.as_str(), attributes.get("rel"), attributes.get("href")) {
+        let resolved = match base {
+            Some(base) => base.join(href.trim()).ok(),
+            None => url::Url::parse(href.trim()).ok(),
+        };
+        if let Some(url) = resolved.filter(|url| matches!(url.scheme(), "http" | "https")) {
+            for rel in rel.split_whitespace() {
+                if rel.eq_ignore_ascii_case("preconnect") {
+                    hints.push(ResourceHint::Preconnect(url.to_string()));
+                } else if rel.eq_ignore_ascii_case("dns-prefetch") {
+                    hints.push(ResourceHint::DnsPrefetch(url.to_string()));
+                } else if rel.eq_ignore_ascii_case("prefetch") {
+                    hints.push(ResourceHint::Prefetch(url.to_string()));
+                }
+            }
+        }
+    }
+    for child in children {
+        collect_hints(child, base, hints);
+    }
+}
+
+/// Fetches a page's subresources with bounded concurrency
+pub struct ResourceLoader {
+    client: ManualHttpClient,
+    image_cache: ImageCache,
+    max_concurrent: usize,
+}
+
+impl ResourceLoader {
+    pub fn new(client: ManualHttpClient, image_cache: ImageCache) -> Self {
+        Self {
+            client,
+            image_cache,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+        }
+    }
+
+    /// Start loading on the shared executor; results are collected through the returned handle.
+    /// Lazy images are held back until the page reports them near the viewport.
+    pub fn start(&self, requests: Vec<ResourceRequest>, context: FetchContext) -> ResourceLoadHandle {
+        let (sender, receiver) = mpsc::channel();
+        let (deferred, eager): (Vec<_>, Vec<_>) = requests.into_iter().partition(|r| r.lazy);
+
+        let spawner = ResourceSpawner {
+            client: self.client.clone(),
+            image_cache: self.image_cache.clone(),
+            context,
+            semaphore: Arc::new(Semaphore::new(self.max_concurrent)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(Mutex::new(ResourceProgress { total: eager.len(), ..Default::default() })),
+            sender,
+        };
+
+        // Scripts must run in document order even though they download concurrently
+        let mut script_position = 0;
+        let mut tasks = Vec::with_capacity(eager.len());
+        for request in eager {
+            let position = (request.kind == ResourceKind::Script).then(|| {
+                script_position += 1;
+                script_position - 1
+            });
+            tasks.push(spawner.spawn(request, position));
+        }
+
+        ResourceLoadHandle {
+            receiver,
+            spawner,
+            tasks,
+            deferred: deferred.into_iter().map(|r| (r.reference.clone(), r)).collect(),
+            pending_scripts: BTreeMap::new(),
+            next_script: 0,
+        }
+    }
+}
+
+// Everything needed to start another fetch for the same page
+struct ResourceSpawner {
+    client: ManualHttpClient,
+    image_cache: ImageCache,
+    context: FetchContext,
+    semaphore: Arc<Semaphore>,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<ResourceProgress>>,
+    sender: Sender<(Option<usize>, LoadedResource)>,
+}
+
+impl ResourceSpawner {
+    fn spawn(&self, request: ResourceRequest, script_position: Option<usize>) -> TaskHandle<()> {
+        let task = ResourceTask {
+            client: self.client.clone(),
+            image_cache: self.image_cache.clone(),
+            context: self.context.clone(),
+            semaphore: self.semaphore.clone(),
+            cancelled: self.cancelled.clone(),
+            progress: self.progress.clone(),
+            sender: self.sender.clone(),
+            script_position,
+        };
+        Executor::shared().spawn(TaskKind::Network, task.run(request))
+    }
+}
+
+struct ResourceTask {
+    client: ManualHttpClient,
+    image_cache: ImageCache,
+    context: FetchContext,
+    semaphore: Arc<Semaphore>,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<ResourceProgress>>,
+    sender: Sender<(Option<usize>, LoadedResource)>,
+    script_position: Option<usize>,
+}
+
+impl ResourceTask {
+    async fn run(self, request: ResourceRequest) {
+        let Ok(_permit) = self.semaphore.acquire().await else {
+            return;
+        };
+
+        let mut timing = None;
+        let result = if self.cancelled.load(Ordering::Relaxed) {
+            Err("Cancelled".to_string())
+        } else {
+            self.fetch(&request, &mut timing).await
+        };
+
+        let failed = result.is_err();
+        if let Err(e) = &result {
+            log::warn!("Subresource {} failed: {}", request.url, e);
+        }
+
+        // Send before counting, so a finished progress means every result is already queued
+        let _ = self.sender.send((self.script_position, LoadedResource { request, result, timing }));
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.completed += 1;
+            if failed {
+                progress.failed += 1;
+            }
+        }
+    }
+
+    async fn fetch(&self, request: &ResourceRequest, timing: &mut Option<RequestTiming>) -> Result<ResourceContent, String> {
+        if request.kind == ResourceKind::Font {
+            if let Some(data) = fonts::cached_font(&request.url) {
+                return Ok(ResourceContent::Font(data));
+            }
+        }
+
+        let context = FetchContext { resource_type: request.kind.resource_type(), ..self.context.clone() };
+        let fetched = self.client.fetch_with_context(&request.url, &context).await
+            .map_err(|e| e.to_string())?;
+        *timing = Some(fetched.timing);
+        let response = fetched.response;
+        if !response.is_success() {
+            return Err(format!("HTTP {}", response.status_code));
+        }
+        if let Some(oversized) = response.oversized {
+            return Err(format!("Larger than the {}MB size limit", oversized.limit / 1024 / 1024));
+        }
+
+        match request.kind {
+            ResourceKind::Stylesheet => response.body_as_string()
+                .map(ResourceContent::Stylesheet)
+                .map_err(|e| e.to_string()),
+            ResourceKind::Script => response.body_as_string()
+                .map(ResourceContent::Script)
+                .map_err(|e| e.to_string()),
+            ResourceKind::Image if is_svg(&request.url, response.content_type().map(String::as_str)) => {
+                let source = response.body_as_string().map_err(|e| e.to_string())?;
+                SvgDocument::parse(&source)
+                    .map(|document| ResourceContent::Svg(Arc::new(document)))
+                    .ok_or_else(|| "Not an SVG image".to_string())
+            }
+            ResourceKind::Image => self.image_cache.store_response(&request.url, &response).await
+                .map(ResourceContent::Image)
+                .map_err(|e| e.to_string()),
+            ResourceKind::Font => {
+                let (url, body) = (request.url.clone(), response.body);
+                Executor::shared()
+                    .spawn_blocking(TaskKind::Parse, move || fonts::prepare_download(&url, &body))
+                    .join().await
+                    .map_err(|e| e.to_string())?
+                    .map(ResourceContent::Font)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+// SVG images are recognised by their type, or by extension when the server doesn't say
+fn is_svg(url: &str, content_type: Option<&str>) -> bool {
+    match content_type.filter(|ct| ct.starts_with("image/")) {
+        Some(content_type) => content_type.contains("svg"),
+        None => url::Url::parse(url).is_ok_and(|url| url.path().to_ascii_lowercase().ends_with(".svg")),
+    }
+}
+
+/// Per-tab view of an in-flight subresource load
+pub struct ResourceLoadHandle {
+    receiver: Receiver<(Option<usize>, LoadedResource)>,
+    spawner: ResourceSpawner,
+    tasks: Vec<TaskHandle<()>>,
+    // Lazy images not requested yet, keyed by their src as written
+    deferred: HashMap<String, ResourceRequest>,
+    pending_scripts: BTreeMap<usize, LoadedResource>,
+    next_script: usize,
+}
+
+impl ResourceLoadHandle {
+    /// Stop fetching; anything still queued or in flight is aborted and its result dropped
+    pub fn cancel(&self) {
+        self.spawner.cancelled.store(true, Ordering::Relaxed);
+        for task in &self.tasks {
+            task.cancel();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.spawner.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn progress(&self) -> ResourceProgress {
+        self.spawner.progress.lock().map(|p| *p).unwrap_or_default()
+    }
+
+    /// Lazy images still waiting to scroll into view
+    pub fn has_deferred(&self) -> bool {
+        !self.deferred.is_empty()
+    }
+
+    /// Start fetching deferred images whose elements are now near the viewport
+    pub fn load_deferred(&mut self, references: impl IntoIterator<Item = String>) {
+        if self.is_cancelled() {
+            return;
+        }
+        for reference in references {
+            if let Some(request) = self.deferred.remove(&reference) {
+                if let Ok(mut progress) = self.spawner.progress.lock() {
+                    progress.total += 1;
+                }
+                let task = self.spawner.spawn(request, None);
+                self.tasks.push(task);
+            }
+        }
+    }
+
+    /// Fetch resources the page discovered after loading started, such as web fonts
+    pub fn request(&mut self, requests: Vec<ResourceRequest>) {
+        if self.is_cancelled() {
+            return;
+        }
+        for request in requests {
+            if let Ok(mut progress) = self.spawner.progress.lock() {
+                progress.total += 1;
+            }
+            let task = self.spawner.spawn(request, None);
+            self.tasks.push(task);
+        }
+    }
+
+    /// Resources ready to apply, with scripts released in document order
+    pub fn take_ready(&mut self) -> Vec<LoadedResource> {
+        let mut ready = Vec::new();
+        if self.is_cancelled() {
+            return ready;
+        }
+
+        while let Ok((script_position, resource)) = self.receiver.try_recv() {
+            match script_position {
+                Some(position) => {
+                    self.pending_scripts.insert(position, resource);
+                }
+                None => ready.push(resource),
+            }
+        }
+
+        while let Some(script) = self.pending_scripts.remove(&self.next_script) {
+            ready.push(script);
+            self.next_script += 1;
+        }
+
+        ready
+    }
+}
+
+impl Drop for ResourceLoadHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_resource_hints() {
+        let dom = html_parser::parse(r#"<html><head>
+            <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
+            <link rel="dns-prefetch" href="//cdn.example.net">
+            <link rel="stylesheet" href="/site.css">
+            <link rel="prefetch" href="/next-page.html">
+        </head><body></body></html>"#);
+
+        assert_eq!(resource_hints(&dom, "https://example.com/"), vec![
+            ResourceHint::Preconnect("https://fonts.gstatic.com/".to_string()),
+            ResourceHint::DnsPrefetch("https://cdn.example.net/".to_string()),
+            ResourceHint::Prefetch("https://example.com/next-page.html".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_discover_resources() {
+        let dom = html_parser::parse(r#"<html><head>
+            <link rel="stylesheet" href="/css/site.css">
+            <link rel="icon" href="/favicon.ico">
+            <script src="app.js"></script>
+        </head><body>
+            <img src="https://cdn.example.com/logo.png">
+            <img src="data:image/png;base64,AAAA">
+            <img src="/css/../logo.png">
+            <img src="/logo.png">
+            <img src="/photos/beach.jpg" loading="lazy">
+        </body></html>"#);
+
+        let requests = discover_resources(&dom, "https://example.com/blog/post.html", ImageLoadingMode::Auto);
+        let urls: Vec<(ResourceKind, &str)> = requests.iter().map(|r| (r.kind, r.url.as_str())).collect();
+        assert_eq!(urls, vec![
+            (ResourceKind::Stylesheet, "https://example.com/css/site.css"),
+            (ResourceKind::Script, "https://example.com/blog/app.js"),
+            (ResourceKind::Image, "https://cdn.example.com/logo.png"),
+            (ResourceKind::Image, "data:image/png;base64,AAAA"),
+            (ResourceKind::Image, "https://example.com/logo.png"),
+            (ResourceKind::Image, "https://example.com/photos/beach.jpg"),
+        ]);
+        let lazy: Vec<&str> = requests.iter().filter(|r| r.lazy).map(|r| r.reference.as_str()).collect();
+        assert_eq!(lazy, vec!["/photos/beach.jpg"]);
+
+        let forced = discover_resources(&dom, "https://example.com/", ImageLoadingMode::Lazy);
+        assert!(forced.iter().all(|r| r.lazy == (r.kind == ResourceKind::Image)));
+
+        // Local files load only into local pages
+        let local = html_parser::parse(r#"<img src="file:///etc/shadow.png"><img src="photo.png">"#);
+        assert!(discover_resources(&local, "https://example.com/", ImageLoadingMode::Auto).iter().all(|r| !r.url.starts_with("file:")));
+        let from_disk = discover_resources(&local, "file:///home/ada/index.html", ImageLoadingMode::Auto);
+        assert_eq!(from_disk.len(), 2);
+        assert_eq!(from_disk[1].url, "file:///home/ada/photo.png");
+    }
+}
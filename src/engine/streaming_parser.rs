@@ -141,7 +141,7 @@ impl StreamingHtmlParser {
                 if let Some(expected) = self.tag_stack.pop() {
                     if expected != tag_name {
                         // Mismatched tag - be lenient and continue
-                        println!("Warning: Mismatched closing tag. Expected {}, got {}", expected, tag_name);
+                        log::debug!("Mismatched closing tag. Expected {}, got {}", expected, tag_name);
                     }
                 }
                 Ok(None) // Closing tags don't create new nodes
@@ -0,0 +1,1034 @@
+// Computed styles: CSS colors and lengths resolved to values, cascaded and inherited down the DOM
+use eframe::egui::{self, Color32};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use crate::engine::animation::{self, AnimatedValues, Animation, Animations, Transform, Transition};
+use crate::engine::css_parser::{self, Declaration, ElementState, KeyframesRule, Selector, Stylesheet, Unit, Value};
+use crate::engine::dom::DOMNode;
+use crate::engine::fonts::FontDescriptor;
+
+// Font size of the root element when nothing sets one, in points
+const ROOT_FONT_SIZE: f32 = 14.0;
+
+// Defaults every page starts from, in the browser's own colors
+const USER_AGENT_CSS: &str = "
+    html { color: #f8fafc; font-size: 14px }
+    head, script, style, title, meta, link, template { display: none }
+    h1 { font-size: 28px; font-weight: bold; color: #00ffff }
+    h2 { font-size: 24px; font-weight: bold; color: #1e90ff }
+    h3 { font-size: 20px; font-weight: bold }
+    h4 { font-size: 18px; font-weight: bold }
+    h5 { font-size: 16px; font-weight: bold; color: #c9d1d9 }
+    h6 { font-size: 14px; font-weight: bold; color: #c9d1d9 }
+    a { color: #1e90ff; text-decoration: underline }
+    a:visited { color: #b388ff }
+    a:hover { color: #00ffff }
+    a:active { color: #ff00ff }
+    button { color: #00ffff; background-color: #212936 }
+    button:hover { background-color: #2d3748 }
+    button:active { color: #080c10; background-color: #00ffff }
+    b, strong { font-weight: bold }
+    em, i, cite, var, dfn, address { font-style: italic }
+    em, i { color: #c9d1d9 }
+    code, kbd, samp { font-family: monospace; color: #39ff14; background-color: #212936 }
+    pre { font-family: monospace; background-color: #080c10 }
+    th { font-weight: bold; color: #00ffff }
+    small { font-size: smaller }
+    s, del, strike { text-decoration: line-through }
+    u, ins { text-decoration: underline }
+    center { text-align: center }
+";
+
+fn user_agent_stylesheet() -> &'static Stylesheet {
+    static SHEET: OnceLock<Stylesheet> = OnceLock::new();
+    SHEET.get_or_init(|| css_parser::parse(USER_AGENT_CSS))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How an element is placed. Relative and absolute positioning are laid out in flow like static.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Position {
+    #[default]
+    Static,
+    /// Painted relative to the viewport, out of the flow
+    Fixed,
+    /// In flow, but held inside the viewport by its insets while its parent is in view
+    Sticky,
+}
+
+/// `top`, `right`, `bottom` and `left` in points; None is `auto`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Insets {
+    pub top: Option<f32>,
+    pub right: Option<f32>,
+    pub bottom: Option<f32>,
+    pub left: Option<f32>,
+}
+
+impl Insets {
+    /// Area a fixed box is laid out in: its insets from the viewport, with `size` from when it was last painted
+    /// placing boxes anchored to the right or bottom
+    pub fn fixed_rect(&self, viewport: egui::Rect, size: Option<egui::Vec2>) -> egui::Rect {
+        let width = viewport.width() - self.left.unwrap_or(0.0) - self.right.unwrap_or(0.0);
+        let size = size.unwrap_or(egui::vec2(width, 0.0));
+        let (x, width) = match (self.left, self.right) {
+            (None, Some(right)) => (viewport.right() - right - size.x, size.x),
+            (left, _) => (viewport.left() + left.unwrap_or(0.0), width),
+        };
+        let y = match (self.top, self.bottom) {
+            (None, Some(bottom)) => viewport.bottom() - bottom - size.y,
+            (top, _) => viewport.top() + top.unwrap_or(0.0),
+        };
+        egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(width.max(0.0), size.y.max(viewport.bottom() - y)))
+    }
+
+    /// Where a sticky box whose place in the flow is `flow` is painted: pushed inside the viewport by its insets,
+    /// but never out of its parent
+    pub fn sticky_rect(&self, flow: egui::Rect, viewport: egui::Rect, parent: Option<egui::Rect>) -> egui::Rect {
+        let mut top = flow.top();
+        if let Some(inset) = self.top {
+            top = top.max(viewport.top() + inset);
+        }
+        if let Some(inset) = self.bottom {
+            top = top.min(viewport.bottom() - inset - flow.height());
+        }
+        if let Some(parent) = parent {
+            top = top.min(parent.bottom() - flow.height()).max(parent.top());
+        }
+        egui::Rect::from_min_size(egui::pos2(flow.left(), top), flow.size())
+    }
+}
+
+/// The resolved style of one element. Text inside the element is drawn with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    pub color: Color32,
+    /// Not inherited; None is transparent
+    pub background_color: Option<Color32>,
+    /// In points
+    pub font_size: f32,
+    pub font: FontDescriptor,
+    pub underline: bool,
+    pub line_through: bool,
+    pub text_align: TextAlign,
+    /// `display: none`, which also hides every descendant
+    pub hidden: bool,
+    /// Not inherited, like the insets
+    pub position: Position,
+    pub inset: Insets,
+    /// Not inherited, like the transitions and animations
+    pub opacity: f32,
+    pub transform: Transform,
+    pub transitions: Vec<Transition>,
+    pub animations: Vec<Animation>,
+}
+
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        Self {
+            color: Color32::BLACK,
+            background_color: None,
+            font_size: ROOT_FONT_SIZE,
+            font: FontDescriptor::default(),
+            underline: false,
+            line_through: false,
+            text_align: TextAlign::Left,
+            hidden: false,
+            position: Position::Static,
+            inset: Insets::default(),
+            opacity: 1.0,
+            transform: Transform::default(),
+            transitions: Vec::new(),
+            animations: Vec::new(),
+        }
+    }
+}
+
+/// What relative lengths are measured against
+#[derive(Debug, Clone, Copy)]
+pub struct LengthContext {
+    /// Font size that `em` refers to
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport: egui::Vec2,
+}
+
+impl LengthContext {
+    /// `value` in points, with percentages taken of `percent_of`; None for anything that isn't a length
+    pub fn resolve(&self, value: &Value, percent_of: f32) -> Option<f32> {
+        match value {
+            Value::Length(length, unit) => Some(match unit {
+                Unit::Px => *length,
+                Unit::Em => length * self.font_size,
+                Unit::Rem => length * self.root_font_size,
+                Unit::Percent => length * percent_of / 100.0,
+                Unit::Vw => length * self.viewport.x / 100.0,
+                Unit::Vh => length * self.viewport.y / 100.0,
+                Unit::Fr => return None,
+            }),
+            // Unitless zero is a valid length
+            Value::Number(number) if *number == 0.0 => Some(0.0),
+            _ => None,
+        }
+    }
+}
+
+impl ComputedStyle {
+    fn inherit_from(parent: &ComputedStyle) -> Self {
+        Self {
+            background_color: None,
+            hidden: false,
+            position: Position::Static,
+            inset: Insets::default(),
+            opacity: 1.0,
+            transform: Transform::default(),
+            transitions: Vec::new(),
+            animations: Vec::new(),
+            ..parent.clone()
+        }
+    }
+
+    /// The values transitions and animations change
+    pub fn animated_values(&self) -> AnimatedValues {
+        AnimatedValues { opacity: self.opacity, transform: self.transform, color: self.color }
+    }
+
+    fn set_animated_values(&mut self, values: AnimatedValues) {
+        self.opacity = values.opacity;
+        self.transform = values.transform;
+        self.color = values.color;
+    }
+
+    // Apply one declaration. `parent` is where inherited and relative values come from.
+    fn apply(&mut self, declaration: &Declaration, parent: &ComputedStyle, context: &LengthContext) {
+        let value = &declaration.value;
+        let keyword = match value {
+            Value::Keyword(keyword) => keyword.to_ascii_lowercase(),
+            _ => String::new(),
+        };
+        let initial = ComputedStyle::default();
+        // `unset` behaves as `inherit` for inherited properties and as `initial` for the rest
+        let inherited = !matches!(declaration.name.as_str(), "background" | "background-color" | "position" | "top" | "right" | "bottom" | "left"
+            | "opacity" | "transform" | "transition" | "animation");
+        let inherit = keyword == "inherit" || (keyword == "unset" && inherited);
+        let reset = keyword == "initial" || keyword == "unset";
+
+        match declaration.name.as_str() {
+            "color" => {
+                if inherit {
+                    self.color = parent.color;
+                } else if reset {
+                    self.color = initial.color;
+                } else if let Some(color) = parse_color(value, parent.color) {
+                    self.color = color;
+                }
+            }
+            "background-color" | "background" => {
+                if inherit {
+                    self.background_color = parent.background_color;
+                } else if reset || keyword == "none" {
+                    self.background_color = None;
+                } else if let Some(color) = background_color(value, self.color) {
+                    self.background_color = Some(color).filter(|c| c.a() > 0);
+                }
+            }
+            "font-size" => {
+                if inherit {
+                    self.font_size = parent.font_size;
+                } else if reset {
+                    self.font_size = initial.font_size;
+                } else if let Some(size) = font_size(value, parent.font_size, context) {
+                    self.font_size = size;
+                }
+            }
+            "font" | "font-family" | "font-weight" | "font-style" => {
+                if inherit {
+                    self.font = parent.font.clone();
+                } else if reset && declaration.name == "font" {
+                    self.font = initial.font.clone();
+                } else {
+                    self.font.apply(declaration);
+                }
+            }
+            // Decorations are drawn through descendants, so they are never turned off by a child
+            "text-decoration" | "text-decoration-line" if !inherit && !reset => {
+                let words = keywords(value);
+                if words.iter().any(|w| w == "none") {
+                    self.underline = parent.underline;
+                    self.line_through = parent.line_through;
+                }
+                self.underline |= words.iter().any(|w| w == "underline");
+                self.line_through |= words.iter().any(|w| w == "line-through");
+            }
+            "text-align" => {
+                self.text_align = match keyword.as_str() {
+                    "inherit" | "unset" => parent.text_align,
+                    "center" => TextAlign::Center,
+                    "right" | "end" => TextAlign::Right,
+                    "left" | "start" | "initial" | "justify" => TextAlign::Left,
+                    _ => self.text_align,
+                };
+            }
+            "display" => self.hidden = keyword == "none",
+            "position" => {
+                self.position = match keyword.as_str() {
+                    "inherit" => parent.position,
+                    "fixed" => Position::Fixed,
+                    "sticky" | "-webkit-sticky" => Position::Sticky,
+                    _ => Position::Static,
+                };
+            }
+            // Both are relative to the viewport, which is the containing block of fixed and sticky boxes
+            "top" | "bottom" | "left" | "right" => {
+                let percent_of = if matches!(declaration.name.as_str(), "top" | "bottom") { context.viewport.y } else { context.viewport.x };
+                let length = if inherit {
+                    match declaration.name.as_str() {
+                        "top" => parent.inset.top,
+                        "bottom" => parent.inset.bottom,
+                        "left" => parent.inset.left,
+                        _ => parent.inset.right,
+                    }
+                } else {
+                    context.resolve(value, percent_of)
+                };
+                match declaration.name.as_str() {
+                    "top" => self.inset.top = length,
+                    "bottom" => self.inset.bottom = length,
+                    "left" => self.inset.left = length,
+                    _ => self.inset.right = length,
+                }
+            }
+            "opacity" => {
+                let opacity = match value {
+                    _ if inherit => Some(parent.opacity),
+                    _ if reset => Some(1.0),
+                    Value::Number(number) => Some(*number),
+                    Value::Length(percent, Unit::Percent) => Some(percent / 100.0),
+                    _ => None,
+                };
+                if let Some(opacity) = opacity {
+                    self.opacity = opacity.clamp(0.0, 1.0);
+                }
+            }
+            "transform" => {
+                if inherit {
+                    self.transform = parent.transform;
+                } else if reset {
+                    self.transform = Transform::default();
+                } else if let Some(transform) = Transform::parse(&keyword, context) {
+                    self.transform = transform;
+                }
+            }
+            "transition" => {
+                self.transitions = if inherit { parent.transitions.clone() } else { animation::parse_transitions(&keyword) };
+            }
+            // Keyframes are filled in once the whole element is styled
+            "animation" => {
+                self.animations = if inherit { parent.animations.clone() } else { animation::parse_animations(&keyword) };
+            }
+            _ => {}
+        }
+    }
+
+    /// Text format for a run of inline text in this style
+    pub fn text_format(&self, family: egui::FontFamily) -> egui::TextFormat {
+        let line = |on: bool| if on { egui::Stroke::new(1.0, self.color) } else { egui::Stroke::NONE };
+        egui::TextFormat {
+            font_id: egui::FontId::new(self.font_size, family),
+            color: self.color,
+            background: self.background_color.unwrap_or(Color32::TRANSPARENT),
+            italics: self.font.italic,
+            underline: line(self.underline),
+            strikethrough: line(self.line_through),
+            ..Default::default()
+        }
+    }
+
+    /// `text` drawn in this style
+    pub fn rich_text(&self, text: impl Into<String>, family: egui::FontFamily) -> egui::RichText {
+        let mut rich = egui::RichText::new(text)
+            .size(self.font_size)
+            .family(family)
+            .color(self.color);
+        if self.font.weight >= 600 {
+            rich = rich.strong();
+        }
+        if self.font.italic {
+            rich = rich.italics();
+        }
+        if self.underline {
+            rich = rich.underline();
+        }
+        if self.line_through {
+            rich = rich.strikethrough();
+        }
+        rich
+    }
+}
+
+fn keywords(value: &Value) -> Vec<String> {
+    match value {
+        Value::Keyword(keyword) => vec![keyword.to_ascii_lowercase()],
+        Value::List(values) => values.iter().flat_map(keywords).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// The color in a `background` shorthand, or a `background-color` value
+fn background_color(value: &Value, current: Color32) -> Option<Color32> {
+    match value {
+        Value::List(values) => values.iter().find_map(|v| parse_color(v, current)),
+        value => parse_color(value, current),
+    }
+}
+
+fn font_size(value: &Value, parent: f32, context: &LengthContext) -> Option<f32> {
+    // em and % are relative to the parent's font size here
+    let context = LengthContext { font_size: parent, ..*context };
+    if let Some(size) = context.resolve(value, parent) {
+        return Some(size.max(0.0));
+    }
+    let Value::Keyword(keyword) = value else {
+        return None;
+    };
+    // Absolute keywords scale from `medium`, which is the root default
+    let scale = match keyword.to_ascii_lowercase().as_str() {
+        "xx-small" => 0.6,
+        "x-small" => 0.75,
+        "small" => 0.89,
+        "medium" => 1.0,
+        "large" => 1.2,
+        "x-large" => 1.5,
+        "xx-large" => 2.0,
+        "smaller" => return Some(parent / 1.2),
+        "larger" => return Some(parent * 1.2),
+        _ => return None,
+    };
+    Some(ROOT_FONT_SIZE * scale)
+}
+
+/// A CSS color value: a name, `#hex`, `rgb()`/`rgba()` or `hsl()`/`hsla()`.
+/// `currentcolor` resolves to `current`.
+pub fn parse_color(value: &Value, current: Color32) -> Option<Color32> {
+    match value {
+        Value::ColorValue(c) => Some(Color32::from_rgba_unmultiplied(c.r, c.g, c.b, c.a)),
+        Value::Keyword(keyword) => parse_color_str(keyword, current),
+        _ => None,
+    }
+}
+
+fn parse_color_str(text: &str, current: Color32) -> Option<Color32> {
+    let text = text.trim().to_ascii_lowercase();
+    if let Some(hex) = text.strip_prefix('#') {
+        return match css_parser::parse_value(&format!("#{}", hex)) {
+            Value::ColorValue(c) if matches!(hex.len(), 3 | 4 | 6 | 8) => Some(Color32::from_rgba_unmultiplied(c.r, c.g, c.b, c.a)),
+            _ => None,
+        };
+    }
+    if let Some((function, args)) = text.split_once('(') {
+        let args = args.strip_suffix(')')?;
+        // Both `rgb(1, 2, 3, 0.5)` and `rgb(1 2 3 / 50%)`
+        let parts: Vec<&str> = args.split([',', ' ', '/']).filter(|p| !p.is_empty()).collect();
+        if parts.len() < 3 || parts.len() > 4 {
+            return None;
+        }
+        let alpha = match parts.get(3) {
+            Some(alpha) => parse_fraction(alpha, 1.0)?,
+            None => 1.0,
+        };
+        let (r, g, b) = match function.trim() {
+            "rgb" | "rgba" => (
+                parse_fraction(parts[0], 255.0)?,
+                parse_fraction(parts[1], 255.0)?,
+                parse_fraction(parts[2], 255.0)?,
+            ),
+            "hsl" | "hsla" => {
+                let hue = parts[0].trim_end_matches("deg").parse::<f32>().ok()?;
+                hsl_to_rgb(hue, parse_fraction(parts[1], 1.0)?, parse_fraction(parts[2], 1.0)?)
+            }
+            _ => return None,
+        };
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        return Some(Color32::from_rgba_unmultiplied(channel(r), channel(g), channel(b), channel(alpha)));
+    }
+    match text.as_str() {
+        "transparent" => Some(Color32::TRANSPARENT),
+        "currentcolor" => Some(current),
+        name => NAMED_COLORS.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, rgb)| Color32::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8)),
+    }
+}
+
+// A number out of `scale`, or a percentage, as a fraction in 0..=1
+fn parse_fraction(text: &str, scale: f32) -> Option<f32> {
+    let text = text.trim();
+    let fraction = match text.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+        None => text.parse::<f32>().ok()? / scale,
+    };
+    Some(fraction.clamp(0.0, 1.0))
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    (r + m, g + m, b + m)
+}
+
+/// Computed styles of every element in a document, recomputed when the stylesheets, viewport or
+/// element state change, with the page's transitions and animations applied on top
+#[derive(Debug, Default)]
+pub struct PageStyles {
+    computed: std::cell::RefCell<Option<StyleTree>>,
+    // The computed tree is kept when stale, so recomputing can tell which values changed
+    stale: std::cell::Cell<bool>,
+    // Subtrees, as child index paths, whose styles need recomputing while the rest of the tree stays
+    dirty: std::cell::RefCell<Vec<Vec<usize>>>,
+    // Bumped when recomputing changed any element's style
+    revision: std::cell::Cell<u64>,
+    state: std::cell::RefCell<ElementState>,
+    animations: std::cell::RefCell<Animations>,
+    // The page's animation clock, in seconds
+    now: std::cell::Cell<f64>,
+    // The user's own styles for the page's site, which outrank the page's
+    user_stylesheet: Option<Stylesheet>,
+}
+
+// Styles keyed by node identity. Only valid while `root` is where the document lives and the
+// viewport is the one it was computed for.
+#[derive(Debug)]
+struct StyleTree {
+    root: *const DOMNode,
+    viewport: egui::Vec2,
+    styles: HashMap<*const DOMNode, ComputedStyle>,
+    // `@keyframes` by name while computing; the last one of a name wins
+    keyframes: HashMap<String, KeyframesRule>,
+}
+
+impl PageStyles {
+    /// Forget computed styles, e.g. after a stylesheet was added
+    pub fn invalidate(&mut self) {
+        self.stale.set(true);
+    }
+
+    /// Recompute only the styles of the subtrees at `paths` in the document, after they changed in place
+    pub fn invalidate_subtrees(&mut self, paths: &[Vec<usize>]) {
+        self.dirty.get_mut().extend_from_slice(paths);
+    }
+
+    /// Replace the user's stylesheet for the page, e.g. after it was edited
+    pub fn set_user_stylesheet(&mut self, stylesheet: Option<Stylesheet>) {
+        self.user_stylesheet = stylesheet;
+        self.stale.set(true);
+    }
+
+    /// Pointer and visited-link state for the pseudo-classes; styles are recomputed when it changed
+    pub fn set_element_state(&self, state: ElementState) {
+        if *self.state.borrow() != state {
+            self.state.replace(state);
+            self.stale.set(true);
+        }
+    }
+
+    /// Advance the animation clock to `now` seconds. Returns whether any transition or animation is still
+    /// running, so the page needs another frame.
+    pub fn set_time(&self, now: f64) -> bool {
+        self.now.set(now);
+        self.animations.borrow_mut().prune(now)
+    }
+
+    /// The style of `element`, an element of `dom`, as it shows right now
+    pub fn style_for(&self, dom: &DOMNode, stylesheets: &[Stylesheet], viewport: egui::Vec2, element: &DOMNode) -> ComputedStyle {
+        let mut computed = self.computed.borrow_mut();
+        if self.stale.get() || computed.as_ref().is_none_or(|c| !std::ptr::eq(c.root, dom) || c.viewport != viewport) {
+            let tree = StyleTree::compute(dom, stylesheets, self.user_stylesheet.as_ref(), viewport, &self.state.borrow());
+            let previous = computed.take().filter(|c| std::ptr::eq(c.root, dom));
+            if previous.as_ref().is_none_or(|previous| previous.styles != tree.styles) {
+                self.revision.set(self.revision.get() + 1);
+            }
+            self.restyled(previous.as_ref().map(|c| &c.styles), &tree.styles);
+            *computed = Some(tree);
+            self.stale.set(false);
+            self.dirty.borrow_mut().clear();
+        } else if let Some(tree) = computed.as_mut().filter(|_| !self.dirty.borrow().is_empty()) {
+            let rules = cascade_rules(stylesheets, self.user_stylesheet.as_ref());
+            for path in self.dirty.take() {
+                let Some(node) = dom.node_at(&path) else { continue };
+                let previous: HashMap<_, _> = node.descendants().filter_map(|node| Some((node, tree.styles.get(&node)?.clone()))).collect();
+                tree.restyle(dom, &path, &rules, &self.state.borrow());
+                let styles: HashMap<_, _> = node.descendants().filter_map(|node| Some((node, tree.styles.get(&node)?.clone()))).collect();
+                self.restyled(Some(&previous), &styles);
+            }
+        }
+        let Some(mut style) = computed.as_ref().and_then(|c| c.styles.get(&(element as *const DOMNode))).cloned() else {
+            return ComputedStyle::default();
+        };
+        // Only elements with something running are touched; the rest of the tree stays as computed
+        let values = self.animations.borrow().values_at(element, style.animated_values(), &style.animations, self.now.get());
+        style.set_animated_values(values);
+        style
+    }
+
+    /// Counts recomputations that changed a style. Restyling subtrees after the document changed doesn't
+    /// count, since what was painted from them is forgotten with the change.
+    pub fn revision(&self) -> u64 {
+        self.revision.get()
+    }
+
+    /// Whether `element` has a transition or animation running, so it looks different every frame
+    pub fn is_animating(&self, element: &DOMNode) -> bool {
+        self.animations.borrow().is_running(element)
+    }
+
+    // Start transitions and animations for what changed between the `previous` styles and `styles`
+    fn restyled(&self, previous: Option<&HashMap<*const DOMNode, ComputedStyle>>, styles: &HashMap<*const DOMNode, ComputedStyle>) {
+        let mut animations = self.animations.borrow_mut();
+        let now = self.now.get();
+        let Some(previous) = previous else {
+            // A new document: nothing transitions, but its animations start
+            animations.clear();
+            for (&node, style) in styles {
+                animations.start_animations(node, &style.animations, now);
+            }
+            return;
+        };
+        for (&node, style) in styles {
+            match previous.get(&node) {
+                Some(before) => {
+                    let shown = animations.values_at(node, before.animated_values(), &before.animations, now);
+                    animations.style_changed(node, shown, style.animated_values(), &style.transitions, &style.animations, now);
+                }
+                None => animations.start_animations(node, &style.animations, now),
+            }
+        }
+    }
+}
+
+// A selector with its rule's declarations, and whether it's from the user's stylesheet
+type CascadeRule<'a> = (&'a Selector, &'a [Declaration], bool);
+
+fn by_specificity<'a>(stylesheets: impl Iterator<Item = &'a Stylesheet>, from_user: bool) -> Vec<CascadeRule<'a>> {
+    let mut rules: Vec<CascadeRule> = Vec::new();
+    for rule in stylesheets.flat_map(|s| &s.rules) {
+        rules.extend(rule.selectors.iter().map(|selector| (selector, rule.declarations.as_slice(), from_user)));
+    }
+    rules.sort_by_key(|(selector, ..)| selector.specificity());
+    rules
+}
+
+// User agent rules first, then the page's and the user's, each in specificity order; the sort is stable, so
+// source order still breaks ties and the page wins over the user agent at equal specificity
+fn cascade_rules<'a>(stylesheets: &'a [Stylesheet], user: Option<&'a Stylesheet>) -> Vec<CascadeRule<'a>> {
+    let mut rules: Vec<CascadeRule> = Vec::new();
+    for rule in &user_agent_stylesheet().rules {
+        rules.extend(rule.selectors.iter().map(|selector| (selector, rule.declarations.as_slice(), false)));
+    }
+    rules.extend(by_specificity(stylesheets.iter(), false));
+    rules.extend(by_specificity(user.into_iter(), true));
+    rules
+}
+
+impl StyleTree {
+    fn compute(dom: &DOMNode, stylesheets: &[Stylesheet], user: Option<&Stylesheet>, viewport: egui::Vec2, state: &ElementState) -> Self {
+        let rules = cascade_rules(stylesheets, user);
+        let keyframes = stylesheets.iter()
+            .chain(user)
+            .flat_map(|s| &s.keyframes)
+            .map(|rule| (rule.name.to_ascii_lowercase(), rule.clone()))
+            .collect();
+        let mut tree = Self { root: dom, viewport, styles: HashMap::new(), keyframes };
+        let initial = ComputedStyle::default();
+        let context = LengthContext { font_size: ROOT_FONT_SIZE, root_font_size: ROOT_FONT_SIZE, viewport };
+        tree.visit(dom, &mut Vec::new(), &initial, context, &rules, state);
+        tree
+    }
+
+    // Recompute the subtree at `path` in `dom`, inheriting from its parent's style as already computed. Only
+    // descendant combinators exist, so nothing outside the subtree can be affected by what changed in it.
+    fn restyle(&mut self, dom: &DOMNode, path: &[usize], rules: &[CascadeRule], state: &ElementState) {
+        let mut ancestors = Vec::new();
+        let mut node = dom;
+        for &index in path {
+            let DOMNode::Element { children, .. } = node else { return };
+            let Some(child) = children.get(index) else { return };
+            ancestors.push(node);
+            node = child;
+        }
+        let initial = ComputedStyle::default();
+        let parent = ancestors.last().and_then(|parent| self.styles.get(&(*parent as *const DOMNode))).unwrap_or(&initial).clone();
+        let root_font_size = self.styles.get(&(dom as *const DOMNode)).map_or(ROOT_FONT_SIZE, |root| root.font_size);
+        let context = LengthContext {
+            font_size: if ancestors.is_empty() { ROOT_FONT_SIZE } else { parent.font_size },
+            root_font_size,
+            viewport: self.viewport,
+        };
+        self.visit(node, &mut ancestors, &parent, context, rules, state);
+    }
+
+    fn visit<'a>(
+        &mut self,
+        node: &'a DOMNode,
+        ancestors: &mut Vec<&'a DOMNode>,
+        parent: &ComputedStyle,
+        mut context: LengthContext,
+        rules: &[CascadeRule],
+        state: &ElementState,
+    ) {
+        let DOMNode::Element { attributes, children, .. } = node else {
+            return;
+        };
+
+        let mut style = ComputedStyle::inherit_from(parent);
+        let matched: Vec<&CascadeRule> = rules.iter()
+            .filter(|(selector, ..)| selector.matches(node, ancestors, state))
+            .collect();
+        let from = |user: bool| matched.iter()
+            .filter(move |(.., from_user)| *from_user == user)
+            .flat_map(|(_, declarations, _)| declarations.iter());
+        let mut declarations: Vec<&Declaration> = from(false).collect();
+        // The style attribute outranks any selector; `!important` outranks both
+        let inline = attributes.get("style").map(|style| css_parser::parse_declarations(style)).unwrap_or_default();
+        declarations.extend(inline.iter());
+        declarations.sort_by_key(|declaration| declaration.important);
+        // The user's stylesheet outranks everything the page says, `!important` included
+        let mut user: Vec<&Declaration> = from(true).collect();
+        user.sort_by_key(|declaration| declaration.important);
+        declarations.extend(user);
+
+        // The font size comes first, since other lengths are measured in it
+        for declaration in declarations.iter().filter(|d| d.name == "font-size") {
+            style.apply(declaration, parent, &context);
+        }
+        context.font_size = style.font_size;
+        if ancestors.is_empty() {
+            context.root_font_size = style.font_size;
+        }
+        for declaration in declarations.iter().filter(|d| d.name != "font-size") {
+            style.apply(declaration, parent, &context);
+        }
+        // Each keyframe is the element's style with the keyframe's declarations applied
+        let base = style.clone();
+        for animation in &mut style.animations {
+            let Some(rule) = self.keyframes.get(&animation.name) else { continue };
+            let mut frames: Vec<(f32, AnimatedValues)> = rule.frames.iter().map(|(offset, declarations)| {
+                let mut frame = base.clone();
+                for declaration in declarations {
+                    frame.apply(declaration, parent, &context);
+                }
+                (*offset, frame.animated_values())
+            }).collect();
+            frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+            animation.keyframes = frames;
+        }
+
+        self.styles.insert(node, style.clone());
+        ancestors.push(node);
+        for child in children {
+            self.visit(child, ancestors, &style, context, rules, state);
+        }
+        ancestors.pop();
+    }
+}
+
+// CSS Color Module Level 4 named colors
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc), ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    fn color(css: &str) -> Option<Color32> {
+        parse_color(&css_parser::parse_value(css), Color32::RED)
+    }
+
+    // Computed style of each element, keyed by its trimmed text
+    fn styles_by_text(html: &str, css: &str) -> HashMap<String, ComputedStyle> {
+        styles_with_user_css(html, css, None)
+    }
+
+    fn styles_with_user_css(html: &str, css: &str, user_css: Option<&str>) -> HashMap<String, ComputedStyle> {
+        let dom = html_parser::parse(html);
+        let stylesheet = css_parser::parse(css);
+        let user = user_css.map(css_parser::parse);
+        let tree = StyleTree::compute(&dom, std::slice::from_ref(&stylesheet), user.as_ref(), egui::vec2(1000.0, 800.0), &ElementState::default());
+        let mut by_text = HashMap::new();
+        fn collect(node: &DOMNode, tree: &StyleTree, out: &mut HashMap<String, ComputedStyle>) {
+            if let Some(style) = tree.styles.get(&(node as *const DOMNode)) {
+                out.insert(node.collect_text().split_whitespace().collect::<Vec<_>>().join(" "), style.clone());
+            }
+            if let DOMNode::Element { children, .. } = node {
+                children.iter().for_each(|child| collect(child, tree, out));
+            }
+        }
+        collect(&dom, &tree, &mut by_text);
+        by_text
+    }
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(color("rebeccapurple"), Some(Color32::from_rgb(0x66, 0x33, 0x99)));
+        assert_eq!(color("#0f08"), Some(Color32::from_rgba_unmultiplied(0, 255, 0, 0x88)));
+        assert_eq!(color("rgb(255, 128, 0)"), Some(Color32::from_rgb(255, 128, 0)));
+        assert_eq!(color("rgb(100% 0% 0% / 50%)"), Some(Color32::from_rgba_unmultiplied(255, 0, 0, 128)));
+        assert_eq!(color("rgba(0,0,255,0.25)"), Some(Color32::from_rgba_unmultiplied(0, 0, 255, 64)));
+        assert_eq!(color("hsl(120, 100%, 25%)"), Some(Color32::from_rgb(0, 128, 0)));
+        assert_eq!(color("hsla(240deg 100% 50% / 1)"), Some(Color32::from_rgb(0, 0, 255)));
+        assert_eq!(color("currentColor"), Some(Color32::RED));
+        assert_eq!(color("transparent"), Some(Color32::TRANSPARENT));
+        assert_eq!(color("notacolor"), None);
+        assert_eq!(color("rgb(1, 2)"), None);
+    }
+
+    #[test]
+    fn test_resolve_lengths() {
+        let context = LengthContext { font_size: 20.0, root_font_size: 16.0, viewport: egui::vec2(1200.0, 600.0) };
+        let resolve = |css: &str| context.resolve(&css_parser::parse_value(css), 300.0);
+        assert_eq!(resolve("12px"), Some(12.0));
+        assert_eq!(resolve("1.5em"), Some(30.0));
+        assert_eq!(resolve("2rem"), Some(32.0));
+        assert_eq!(resolve("50%"), Some(150.0));
+        assert_eq!(resolve("10vw"), Some(120.0));
+        assert_eq!(resolve("50vh"), Some(300.0));
+        assert_eq!(resolve("0"), Some(0.0));
+        assert_eq!(resolve("auto"), None);
+    }
+
+    #[test]
+    fn test_cascade_and_inheritance() {
+        let styles = styles_by_text(
+            r#"<html><body style="font-size: 20px"><div class="card"><p>Text <b>bold</b> <a href="/">link</a></p>
+            <p class="big" style="color: inherit">Big</p></div><p>Plain</p><h1>Title</h1></body></html>"#,
+            ".card { color: rgb(10, 20, 30); background: #fff url(bg.png); font-size: 1.5em }
+             .card p { text-decoration: underline } p.big { font-size: 200%; color: red } p { color: blue }
+             a { color: initial } h1 { font-size: 2rem }",
+        );
+
+        let text = &styles["Text bold link"];
+        assert_eq!(text.color, Color32::BLUE);
+        assert_eq!(text.font_size, 30.0);
+        assert!(text.underline);
+        // Backgrounds are not inherited
+        assert_eq!(styles["Text bold link Big"].background_color, Some(Color32::WHITE));
+        assert_eq!(text.background_color, None);
+
+        assert_eq!(styles["bold"].font.weight, 700);
+        assert_eq!(styles["bold"].color, Color32::BLUE);
+        assert_eq!(styles["link"].color, Color32::BLACK);
+
+        // An inline `inherit` beats the more specific rule
+        assert_eq!(styles["Big"].color, Color32::from_rgb(10, 20, 30));
+        assert_eq!(styles["Big"].font_size, 60.0);
+
+        assert_eq!(styles["Plain"].font_size, 20.0);
+        // rem is relative to the root element, which the user agent sizes
+        assert_eq!(styles["Title"].font_size, 28.0);
+    }
+
+    #[test]
+    fn test_position_and_insets() {
+        let styles = styles_by_text(
+            r#"<html><body><nav>Nav <span>inner</span></nav><h2 style="position: sticky; top: 0">Head</h2>
+            <p style="position: relative; top: 1em">Flow</p></body></html>"#,
+            "nav { position: fixed; bottom: 10%; left: 0; right: auto }",
+        );
+
+        assert_eq!(styles["Nav inner"].position, Position::Fixed);
+        assert_eq!(styles["Nav inner"].inset, Insets { top: None, right: None, bottom: Some(80.0), left: Some(0.0) });
+        // Neither is inherited
+        assert_eq!(styles["inner"].position, Position::Static);
+        assert_eq!(styles["inner"].inset, Insets::default());
+        assert_eq!(styles["Head"].position, Position::Sticky);
+        assert_eq!(styles["Head"].inset.top, Some(0.0));
+        assert_eq!(styles["Flow"].position, Position::Static);
+    }
+
+    #[test]
+    fn test_positioned_rects() {
+        let viewport = egui::Rect::from_min_size(egui::pos2(0.0, 100.0), egui::vec2(800.0, 600.0));
+        let insets = |top, right, bottom, left| Insets { top, right, bottom, left };
+
+        let header = insets(Some(0.0), Some(0.0), None, Some(0.0)).fixed_rect(viewport, None);
+        assert_eq!((header.min, header.width()), (egui::pos2(0.0, 100.0), 800.0));
+        let badge = insets(None, Some(10.0), Some(20.0), None).fixed_rect(viewport, Some(egui::vec2(50.0, 30.0)));
+        assert_eq!(badge.min, egui::pos2(740.0, 650.0));
+
+        // A sticky header stays in place until scrolled past, sticks to the top, then leaves with its parent
+        let sticky = insets(Some(10.0), None, None, None);
+        let parent = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(800.0, 400.0));
+        let at = |flow_top: f32| sticky.sticky_rect(
+            egui::Rect::from_min_size(egui::pos2(0.0, flow_top), egui::vec2(800.0, 40.0)), viewport, Some(parent.translate(egui::vec2(0.0, flow_top)))
+        ).top();
+        assert_eq!(at(300.0), 300.0);
+        assert_eq!(at(50.0), 110.0);
+        assert_eq!(at(-350.0), 10.0);
+    }
+
+    #[test]
+    fn test_inline_styles_outrank_rules() {
+        let styles = styles_by_text(
+            r#"<html><body><p id="a" style="color: red; font-size: 10px; background-color: lime">A</p>
+            <p class="x" style="color: red">B</p></body></html>"#,
+            "#a { color: blue; font-size: 30px !important } p.x { color: green !important }",
+        );
+
+        assert_eq!(styles["A"].color, Color32::RED);
+        assert_eq!(styles["A"].font_size, 30.0);
+        assert_eq!(styles["A"].background_color, Some(Color32::from_rgb(0, 255, 0)));
+        assert_eq!(styles["B"].color, Color32::from_rgb(0, 128, 0));
+    }
+
+    #[test]
+    fn test_user_stylesheet_outranks_the_page() {
+        let styles = styles_with_user_css(
+            r#"<html><body><p id="a" style="color: red">A</p><p>B</p></body></html>"#,
+            "#a { font-size: 30px !important } p { background-color: lime }",
+            Some("p { color: blue; font-size: 12px } body p { background-color: navy }"),
+        );
+
+        assert_eq!(styles["A"].color, Color32::from_rgb(0, 0, 255));
+        assert_eq!(styles["A"].font_size, 12.0);
+        assert_eq!(styles["B"].background_color, Some(Color32::from_rgb(0, 0, 128)));
+    }
+
+    #[test]
+    fn test_pseudo_classes_follow_element_state() {
+        let dom = html_parser::parse("<html><body><a href=\"/seen\">Seen</a><a href=\"/new\">New</a></body></html>");
+        fn find_links<'a>(node: &'a DOMNode, out: &mut Vec<&'a DOMNode>) {
+            if let DOMNode::Element { tag_name, children, .. } = node {
+                if tag_name == "a" {
+                    out.push(node);
+                }
+                children.iter().for_each(|child| find_links(child, out));
+            }
+        }
+        let mut links = Vec::new();
+        find_links(&dom, &mut links);
+        assert_eq!(links.len(), 2);
+        let viewport = egui::vec2(1000.0, 800.0);
+        let styles = PageStyles::default();
+        let color = |link: &DOMNode| styles.style_for(&dom, &[], viewport, link).color;
+        assert_eq!(color(links[0]), Color32::from_rgb(0x1e, 0x90, 0xff));
+
+        let mut state = ElementState::default();
+        state.visited.insert("/seen".to_string());
+        styles.set_element_state(state.clone());
+        assert_eq!(color(links[0]), Color32::from_rgb(0xb3, 0x88, 0xff));
+        assert_eq!(color(links[1]), Color32::from_rgb(0x1e, 0x90, 0xff));
+
+        // Hover wins over visited, and pressing wins over both
+        state.hovered.insert(links[0]);
+        styles.set_element_state(state.clone());
+        assert_eq!(color(links[0]), Color32::from_rgb(0x00, 0xff, 0xff));
+        state.active.insert(links[0]);
+        styles.set_element_state(state);
+        assert_eq!(color(links[0]), Color32::from_rgb(0xff, 0x00, 0xff));
+    }
+
+    #[test]
+    fn test_transitions_and_animations_apply() {
+        let dom = html_parser::parse("<html><body><a href=\"/\">Go</a><p>Pulse</p></body></html>");
+        let stylesheets = [css_parser::parse(
+            "a { opacity: 0.5; transition: opacity 1s linear } a:hover { opacity: 1 } \
+             @keyframes slide { from { transform: translateX(0) } to { transform: translateX(100px) } } \
+             p { animation: slide 2s linear }"
+        )];
+        let find = |tag: &str| {
+            fn walk<'a>(node: &'a DOMNode, tag: &str) -> Option<&'a DOMNode> {
+                match node {
+                    DOMNode::Element { tag_name, .. } if tag_name == tag => Some(node),
+                    DOMNode::Element { children, .. } => children.iter().find_map(|child| walk(child, tag)),
+                    _ => None,
+                }
+            }
+            walk(&dom, tag).unwrap()
+        };
+        let (link, p) = (find("a"), find("p"));
+        let viewport = egui::vec2(1000.0, 800.0);
+        let styles = PageStyles::default();
+        let style = |node: &DOMNode| styles.style_for(&dom, &stylesheets, viewport, node);
+        assert_eq!(style(link).opacity, 0.5);
+        assert_eq!(style(p).transform.translate.x, 0.0);
+
+        let mut state = ElementState::default();
+        state.hovered.insert(link);
+        styles.set_element_state(state);
+        assert_eq!(style(link).opacity, 0.5);
+        assert!(styles.set_time(0.5));
+        assert_eq!(style(link).opacity, 0.75);
+        assert_eq!(style(p).transform.translate.x, 25.0);
+        assert!(!styles.set_time(2.0));
+        assert_eq!(style(link).opacity, 1.0);
+        assert_eq!(style(p).transform.translate.x, 0.0);
+    }
+
+    #[test]
+    fn test_fonts_cascade_and_inherit() {
+        let styles = styles_by_text(
+            r#"<html><body><div class="article"><p>Body <b>bold</b></p>
+            <p class="note" style="font-style: italic">Note</p></div><p>Outside</p></body></html>"#,
+            ".article p { font-family: Georgia } p.note { font-weight: 300 } p { font-weight: 500 }",
+        );
+
+        let georgia = vec!["Georgia".to_string()];
+        assert_eq!(styles["bold"].font, FontDescriptor { families: georgia.clone(), weight: 700, italic: false });
+        assert_eq!(styles["Note"].font, FontDescriptor { families: georgia, weight: 300, italic: true });
+        assert_eq!(styles["Outside"].font, FontDescriptor { families: vec![], weight: 500, italic: false });
+    }
+}
@@ -0,0 +1,752 @@
+// SVG rendering: basic shapes and paths from inline <svg> elements and .svg images, painted with egui
+use eframe::egui::{self, Color32, Pos2, Rect, Stroke, Vec2};
+use std::collections::HashMap;
+use crate::engine::css_parser;
+use crate::engine::dom::DOMNode;
+use crate::engine::html_parser;
+use crate::engine::style;
+
+// Size of an <svg> that declares neither width, height nor viewBox, as in browsers
+const DEFAULT_SIZE: Vec2 = Vec2::new(300.0, 150.0);
+// Straight segments each curve is flattened into
+const CURVE_SEGMENTS: usize = 16;
+
+// Containers whose contents are never drawn directly
+const NON_RENDERED: &[&str] = &["defs", "clippath", "mask", "lineargradient", "radialgradient", "pattern", "symbol", "marker", "title", "desc", "metadata", "style", "script"];
+
+/// A parsed SVG image, flattened to polygons in viewBox coordinates
+#[derive(Debug, Clone, Default)]
+pub struct SvgDocument {
+    view_box: Option<Rect>,
+    width: Option<f32>,
+    height: Option<f32>,
+    shapes: Vec<SvgShape>,
+}
+
+#[derive(Debug, Clone)]
+struct SvgShape {
+    subpaths: Vec<Subpath>,
+    fill: Option<Color32>,
+    even_odd: bool,
+    stroke: Option<Color32>,
+    stroke_width: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Subpath {
+    points: Vec<Pos2>,
+    closed: bool,
+}
+
+// Presentation attributes as inherited down the tree
+#[derive(Debug, Clone)]
+struct Paint {
+    color: Color32,
+    fill: Option<Color32>,
+    stroke: Option<Color32>,
+    stroke_width: f32,
+    even_odd: bool,
+    opacity: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Self {
+            color: Color32::BLACK,
+            fill: Some(Color32::BLACK),
+            stroke: None,
+            stroke_width: 1.0,
+            even_odd: false,
+            opacity: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+        }
+    }
+}
+
+/// 2D affine transform: x' = a·x + c·y + e, y' = b·x + d·y + f
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform([f32; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn then(self, inner: Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = inner.0;
+        Transform([
+            a * a2 + c * b2,
+            b * a2 + d * b2,
+            a * c2 + c * d2,
+            b * c2 + d * d2,
+            a * e2 + c * f2 + e,
+            b * e2 + d * f2 + f,
+        ])
+    }
+
+    fn apply(&self, p: Pos2) -> Pos2 {
+        let [a, b, c, d, e, f] = self.0;
+        Pos2::new(a * p.x + c * p.y + e, b * p.x + d * p.y + f)
+    }
+
+    // How much lengths grow, for stroke widths
+    fn scale(&self) -> f32 {
+        let [a, b, c, d, _, _] = self.0;
+        ((a * d - b * c).abs()).sqrt()
+    }
+
+    /// Parse a `transform` attribute such as `translate(10 5) scale(2)`
+    fn parse(value: &str) -> Transform {
+        let mut transform = Transform::IDENTITY;
+        for part in value.split(')') {
+            let Some((name, args)) = part.split_once('(') else { continue };
+            let n = numbers(args);
+            let arg = |i: usize| n.get(i).copied().unwrap_or(0.0);
+            let step = match (name.trim().trim_start_matches(',').trim(), n.len()) {
+                ("matrix", 6) => Transform([n[0], n[1], n[2], n[3], n[4], n[5]]),
+                ("translate", _) => Transform([1.0, 0.0, 0.0, 1.0, arg(0), arg(1)]),
+                ("scale", 1) => Transform([n[0], 0.0, 0.0, n[0], 0.0, 0.0]),
+                ("scale", _) => Transform([arg(0), 0.0, 0.0, arg(1), 0.0, 0.0]),
+                ("rotate", _) => {
+                    let (sin, cos) = arg(0).to_radians().sin_cos();
+                    let (cx, cy) = (arg(1), arg(2));
+                    Transform([1.0, 0.0, 0.0, 1.0, cx, cy])
+                        .then(Transform([cos, sin, -sin, cos, 0.0, 0.0]))
+                        .then(Transform([1.0, 0.0, 0.0, 1.0, -cx, -cy]))
+                }
+                ("skewX", _) => Transform([1.0, 0.0, arg(0).to_radians().tan(), 1.0, 0.0, 0.0]),
+                ("skewY", _) => Transform([1.0, arg(0).to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+                _ => continue,
+            };
+            transform = transform.then(step);
+        }
+        transform
+    }
+}
+
+impl SvgDocument {
+    /// Parse the text of an .svg file
+    pub fn parse(source: &str) -> Option<Self> {
+        // Skip the XML prolog and doctype, which the HTML parser doesn't know
+        let start = source.find("<svg").or_else(|| source.find("<SVG"))?;
+        let dom = html_parser::parse(&source[start..]);
+        find_svg(&dom).map(Self::from_element)
+    }
+
+    /// Build from an inline `<svg>` element
+    pub fn from_element(svg: &DOMNode) -> Self {
+        let view_box = attr(svg, "viewBox")
+            .map(numbers)
+            .filter(|n| n.len() == 4 && n[2] > 0.0 && n[3] > 0.0)
+            .map(|n| Rect::from_min_size(Pos2::new(n[0], n[1]), Vec2::new(n[2], n[3])));
+        let mut gradients = HashMap::new();
+        collect_gradients(svg, &mut gradients);
+
+        let mut document = Self {
+            view_box,
+            width: attr(svg, "width").and_then(length),
+            height: attr(svg, "height").and_then(length),
+            shapes: Vec::new(),
+        };
+        document.collect(svg, &Paint::default(), Transform::IDENTITY, &gradients);
+        document
+    }
+
+    /// Size the image asks for, from width/height or else the viewBox
+    pub fn intrinsic_size(&self) -> Vec2 {
+        let ratio = self.view_box.map(|vb| vb.width() / vb.height());
+        match (self.width, self.height, ratio) {
+            (Some(w), Some(h), _) => Vec2::new(w, h),
+            (Some(w), None, Some(ratio)) => Vec2::new(w, w / ratio),
+            (None, Some(h), Some(ratio)) => Vec2::new(h * ratio, h),
+            (None, None, Some(_)) => self.view_box.map(|vb| vb.size()).unwrap_or(DEFAULT_SIZE),
+            _ => DEFAULT_SIZE,
+        }
+    }
+
+    /// Paint into `rect`, scaling the viewBox to fit and centering it like `xMidYMid meet`
+    pub fn paint(&self, painter: &egui::Painter, rect: Rect) {
+        let view_box = self.view_box.unwrap_or_else(|| Rect::from_min_size(Pos2::ZERO, self.intrinsic_size()));
+        let scale = (rect.width() / view_box.width()).min(rect.height() / view_box.height());
+        let offset = rect.center() - view_box.center().to_vec2() * scale;
+        let to_screen = Transform([scale, 0.0, 0.0, scale, offset.x, offset.y]);
+
+        for shape in &self.shapes {
+            let subpaths: Vec<Subpath> = shape.subpaths.iter()
+                .map(|s| Subpath { points: s.points.iter().map(|p| to_screen.apply(*p)).collect(), closed: s.closed })
+                .collect();
+            if let Some(fill) = shape.fill {
+                let mut mesh = egui::Mesh::default();
+                fill_spans(&subpaths, shape.even_odd, |y, x0, x1| {
+                    mesh.add_colored_rect(Rect::from_min_max(Pos2::new(x0, y), Pos2::new(x1, y + 1.0)), fill);
+                });
+                painter.add(egui::Shape::mesh(mesh));
+            }
+            if let Some(color) = shape.stroke {
+                let stroke = Stroke::new(shape.stroke_width * scale, color);
+                for subpath in subpaths.into_iter().filter(|s| s.points.len() > 1) {
+                    let path = egui::epaint::PathShape {
+                        points: subpath.points,
+                        closed: subpath.closed,
+                        fill: Color32::TRANSPARENT,
+                        stroke: stroke.into(),
+                    };
+                    painter.add(path);
+                }
+            }
+        }
+    }
+
+    fn collect(&mut self, node: &DOMNode, inherited: &Paint, transform: Transform, gradients: &HashMap<String, Color32>) {
+        let DOMNode::Element { tag_name, children, .. } = node else { return };
+        let tag = tag_name.to_ascii_lowercase();
+        if NON_RENDERED.contains(&tag.as_str()) || attr(node, "display").is_some_and(|d| d.trim() == "none") {
+            return;
+        }
+        let paint = paint_for(node, inherited, gradients);
+        let transform = match attr(node, "transform") {
+            Some(value) => transform.then(Transform::parse(value)),
+            None => transform,
+        };
+
+        let subpaths = match tag.as_str() {
+            "svg" | "g" | "a" => {
+                for child in children {
+                    self.collect(child, &paint, transform, gradients);
+                }
+                return;
+            }
+            "rect" => rect_path(node),
+            "circle" => {
+                let r = num_attr(node, "r");
+                ellipse_path(num_attr(node, "cx"), num_attr(node, "cy"), r, r)
+            }
+            "ellipse" => ellipse_path(num_attr(node, "cx"), num_attr(node, "cy"), num_attr(node, "rx"), num_attr(node, "ry")),
+            "line" => vec![Subpath {
+                points: vec![
+                    Pos2::new(num_attr(node, "x1"), num_attr(node, "y1")),
+                    Pos2::new(num_attr(node, "x2"), num_attr(node, "y2")),
+                ],
+                closed: false,
+            }],
+            "polyline" | "polygon" => {
+                let n = attr(node, "points").map(numbers).unwrap_or_default();
+                vec![Subpath {
+                    points: n.chunks_exact(2).map(|p| Pos2::new(p[0], p[1])).collect(),
+                    closed: tag == "polygon",
+                }]
+            }
+            "path" => attr(node, "d").map(parse_path).unwrap_or_default(),
+            _ => return,
+        };
+        if subpaths.is_empty() {
+            return;
+        }
+
+        let alpha = |color: Option<Color32>, opacity: f32| {
+            color.map(|c| c.gamma_multiply(opacity * paint.opacity)).filter(|c| c.a() > 0)
+        };
+        // Lines have no inside to fill
+        let fillable = !matches!(tag.as_str(), "line");
+        self.shapes.push(SvgShape {
+            subpaths: subpaths.into_iter()
+                .map(|s| Subpath { points: s.points.into_iter().map(|p| transform.apply(p)).collect(), closed: s.closed })
+                .collect(),
+            fill: alpha(paint.fill, paint.fill_opacity).filter(|_| fillable),
+            even_odd: paint.even_odd,
+            stroke: alpha(paint.stroke, paint.stroke_opacity).filter(|_| paint.stroke_width > 0.0),
+            stroke_width: paint.stroke_width * transform.scale(),
+        });
+    }
+}
+
+fn find_svg(node: &DOMNode) -> Option<&DOMNode> {
+    let DOMNode::Element { tag_name, children, .. } = node else { return None };
+    if tag_name.eq_ignore_ascii_case("svg") {
+        return Some(node);
+    }
+    children.iter().find_map(find_svg)
+}
+
+// Attribute by name, ignoring case since the HTML parser may have lowercased it
+fn attr<'a>(node: &'a DOMNode, name: &str) -> Option<&'a str> {
+    let DOMNode::Element { attributes, .. } = node else { return None };
+    attributes.get(name)
+        .or_else(|| attributes.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value))
+        .map(String::as_str)
+}
+
+fn num_attr(node: &DOMNode, name: &str) -> f32 {
+    attr(node, name).and_then(length).unwrap_or(0.0)
+}
+
+// A user-space length; percentages and font-relative units aren't resolved
+fn length(value: &str) -> Option<f32> {
+    value.trim().trim_end_matches("px").parse().ok()
+}
+
+/// Every number in `text`, however it is separated, e.g. `"10-5.5.5"` gives 10, -5.5, 0.5
+fn numbers(text: &str) -> Vec<f32> {
+    let mut scanner = NumberScanner { chars: text.as_bytes(), pos: 0 };
+    std::iter::from_fn(|| scanner.next_number()).collect()
+}
+
+struct NumberScanner<'a> {
+    chars: &'a [u8],
+    pos: usize,
+}
+
+impl NumberScanner<'_> {
+    fn skip_separators(&mut self) {
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_ascii_whitespace() || self.chars[self.pos] == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next_number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let mut seen_dot = false;
+        let mut seen_digit = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => seen_digit = true,
+                b'.' if !seen_dot => seen_dot = true,
+                b'e' | b'E' if seen_digit && matches!(self.chars.get(self.pos + 1), Some(b'0'..=b'9' | b'-' | b'+')) => {
+                    self.pos += 2;
+                    while matches!(self.peek(), Some(b'0'..=b'9')) {
+                        self.pos += 1;
+                    }
+                    break;
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        if !seen_digit {
+            self.pos = start;
+            return None;
+        }
+        std::str::from_utf8(&self.chars[start..self.pos]).ok()?.parse().ok()
+    }
+
+    // Arc flags may be written without separators, e.g. `a1 1 0 011 1`
+    fn next_flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(flag)
+    }
+}
+
+fn paint_for(node: &DOMNode, inherited: &Paint, gradients: &HashMap<String, Color32>) -> Paint {
+    let mut paint = Paint { opacity: inherited.opacity, ..inherited.clone() };
+    let mut properties: Vec<(String, String)> = ["color", "fill", "stroke", "stroke-width", "fill-rule", "opacity", "fill-opacity", "stroke-opacity"]
+        .iter()
+        .filter_map(|name| attr(node, name).map(|value| (name.to_string(), value.to_string())))
+        .collect();
+    // The style attribute outranks presentation attributes
+    if let Some(css) = attr(node, "style") {
+        for declaration in css_parser::parse_declarations(css) {
+            let value = match &declaration.value {
+                css_parser::Value::Keyword(k) => k.clone(),
+                css_parser::Value::Number(n) | css_parser::Value::Length(n, _) => n.to_string(),
+                css_parser::Value::ColorValue(c) => format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b),
+                css_parser::Value::List(_) => continue,
+            };
+            properties.push((declaration.name, value));
+        }
+    }
+
+    for (name, value) in properties {
+        let value = value.trim();
+        let color = || -> Option<Option<Color32>> {
+            if value == "none" {
+                return Some(None);
+            }
+            if let Some(id) = value.strip_prefix("url(").and_then(|v| v.split(')').next()) {
+                // Gradients are drawn in their first stop's color
+                return Some(gradients.get(id.trim().trim_matches(['\'', '"']).trim_start_matches('#')).copied());
+            }
+            style::parse_color(&css_parser::parse_value(value), paint.color).map(Some)
+        };
+        match name.as_str() {
+            "color" => {
+                if let Some(Some(c)) = color() {
+                    paint.color = c;
+                }
+            }
+            "fill" => paint.fill = color().unwrap_or(paint.fill),
+            "stroke" => paint.stroke = color().unwrap_or(paint.stroke),
+            "stroke-width" => paint.stroke_width = length(value).unwrap_or(paint.stroke_width),
+            "fill-rule" => paint.even_odd = value == "evenodd",
+            "opacity" => paint.opacity = inherited.opacity * value.parse::<f32>().unwrap_or(1.0).clamp(0.0, 1.0),
+            "fill-opacity" => paint.fill_opacity = value.parse::<f32>().unwrap_or(1.0).clamp(0.0, 1.0),
+            "stroke-opacity" => paint.stroke_opacity = value.parse::<f32>().unwrap_or(1.0).clamp(0.0, 1.0),
+            _ => {}
+        }
+    }
+    paint
+}
+
+fn collect_gradients(node: &DOMNode, gradients: &mut HashMap<String, Color32>) {
+    let DOMNode::Element { tag_name, children, .. } = node else { return };
+    if tag_name.eq_ignore_ascii_case("linearGradient") || tag_name.eq_ignore_ascii_case("radialGradient") {
+        let first_stop = children.iter()
+            .filter(|child| child.tag_name().is_some_and(|t| t.eq_ignore_ascii_case("stop")))
+            .find_map(|stop| {
+                let inline = attr(stop, "style").and_then(|css| {
+                    css_parser::parse_declarations(css).into_iter()
+                        .find(|d| d.name == "stop-color")
+                        .and_then(|d| style::parse_color(&d.value, Color32::BLACK))
+                });
+                inline.or_else(|| attr(stop, "stop-color").and_then(|v| style::parse_color(&css_parser::parse_value(v), Color32::BLACK)))
+            });
+        if let (Some(id), Some(color)) = (attr(node, "id"), first_stop) {
+            gradients.insert(id.to_string(), color);
+        }
+    }
+    children.iter().for_each(|child| collect_gradients(child, gradients));
+}
+
+fn rect_path(node: &DOMNode) -> Vec<Subpath> {
+    let (x, y, w, h) = (num_attr(node, "x"), num_attr(node, "y"), num_attr(node, "width"), num_attr(node, "height"));
+    if w <= 0.0 || h <= 0.0 {
+        return Vec::new();
+    }
+    // A missing rx or ry takes the other's value
+    let (rx, ry) = match (attr(node, "rx").and_then(length), attr(node, "ry").and_then(length)) {
+        (Some(rx), Some(ry)) => (rx, ry),
+        (Some(r), None) | (None, Some(r)) => (r, r),
+        (None, None) => (0.0, 0.0),
+    };
+    let (rx, ry) = (rx.clamp(0.0, w / 2.0), ry.clamp(0.0, h / 2.0));
+    if rx == 0.0 || ry == 0.0 {
+        let points = vec![Pos2::new(x, y), Pos2::new(x + w, y), Pos2::new(x + w, y + h), Pos2::new(x, y + h)];
+        return vec![Subpath { points, closed: true }];
+    }
+
+    let mut points = Vec::new();
+    let corners = [
+        (x + w - rx, y + ry, -90.0f32),
+        (x + w - rx, y + h - ry, 0.0),
+        (x + rx, y + h - ry, 90.0),
+        (x + rx, y + ry, 180.0),
+    ];
+    for (cx, cy, start) in corners {
+        for i in 0..=CURVE_SEGMENTS / 4 {
+            let angle = (start + 90.0 * i as f32 / (CURVE_SEGMENTS / 4) as f32).to_radians();
+            points.push(Pos2::new(cx + rx * angle.cos(), cy + ry * angle.sin()));
+        }
+    }
+    vec![Subpath { points, closed: true }]
+}
+
+fn ellipse_path(cx: f32, cy: f32, rx: f32, ry: f32) -> Vec<Subpath> {
+    if rx <= 0.0 || ry <= 0.0 {
+        return Vec::new();
+    }
+    let segments = CURVE_SEGMENTS * 2;
+    let points = (0..segments)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+            Pos2::new(cx + rx * angle.cos(), cy + ry * angle.sin())
+        })
+        .collect();
+    vec![Subpath { points, closed: true }]
+}
+
+/// Flatten path data (`d`) into polylines; malformed data ends the path where it breaks, as browsers do
+fn parse_path(d: &str) -> Vec<Subpath> {
+    let mut subpaths: Vec<Subpath> = Vec::new();
+    let mut current = Subpath::default();
+    let mut pos = Pos2::ZERO;
+    let mut start = Pos2::ZERO;
+    // Control point of the previous curve, reflected by the S and T shorthands
+    let mut last_control: Option<(u8, Pos2)> = None;
+    let mut scanner = NumberScanner { chars: d.as_bytes(), pos: 0 };
+    let mut command = 0u8;
+
+    loop {
+        scanner.skip_separators();
+        let Some(next) = scanner.peek() else { break };
+        if next.is_ascii_alphabetic() {
+            command = next;
+            scanner.pos += 1;
+        } else if command == 0 {
+            break;
+        }
+        let relative = command.is_ascii_lowercase();
+        let origin = if relative { pos.to_vec2() } else { Vec2::ZERO };
+        let mut point = || -> Option<Pos2> {
+            let x = scanner.next_number()?;
+            let y = scanner.next_number()?;
+            Some(Pos2::new(x, y) + origin)
+        };
+
+        let upper = command.to_ascii_uppercase();
+        match upper {
+            b'M' => {
+                let Some(p) = point() else { break };
+                if current.points.len() > 1 {
+                    subpaths.push(std::mem::take(&mut current));
+                }
+                current = Subpath { points: vec![p], closed: false };
+                pos = p;
+                start = p;
+                // Further pairs after a moveto are implicit linetos
+                command = if relative { b'l' } else { b'L' };
+            }
+            b'L' => {
+                let Some(p) = point() else { break };
+                current.points.push(p);
+                pos = p;
+            }
+            b'H' | b'V' => {
+                let Some(value) = scanner.next_number() else { break };
+                pos = match (upper, relative) {
+                    (b'H', true) => Pos2::new(pos.x + value, pos.y),
+                    (b'H', false) => Pos2::new(value, pos.y),
+                    (_, true) => Pos2::new(pos.x, pos.y + value),
+                    (_, false) => Pos2::new(pos.x, value),
+                };
+                current.points.push(pos);
+            }
+            b'C' | b'S' => {
+                let c1 = if upper == b'C' {
+                    let Some(c1) = point() else { break };
+                    c1
+                } else {
+                    reflect(last_control, b'C', pos)
+                };
+                let (Some(c2), Some(end)) = (point(), point()) else { break };
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f32 / CURVE_SEGMENTS as f32;
+                    let mt = 1.0 - t;
+                    let p = pos.to_vec2() * mt * mt * mt + c1.to_vec2() * 3.0 * mt * mt * t + c2.to_vec2() * 3.0 * mt * t * t + end.to_vec2() * t * t * t;
+                    current.points.push(p.to_pos2());
+                }
+                last_control = Some((b'C', c2));
+                pos = end;
+                continue;
+            }
+            b'Q' | b'T' => {
+                let control = if upper == b'Q' {
+                    let Some(c) = point() else { break };
+                    c
+                } else {
+                    reflect(last_control, b'Q', pos)
+                };
+                let Some(end) = point() else { break };
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f32 / CURVE_SEGMENTS as f32;
+                    let mt = 1.0 - t;
+                    let p = pos.to_vec2() * mt * mt + control.to_vec2() * 2.0 * mt * t + end.to_vec2() * t * t;
+                    current.points.push(p.to_pos2());
+                }
+                last_control = Some((b'Q', control));
+                pos = end;
+                continue;
+            }
+            b'A' => {
+                let (Some(rx), Some(ry), Some(rotation)) = (scanner.next_number(), scanner.next_number(), scanner.next_number()) else { break };
+                let (Some(large_arc), Some(sweep)) = (scanner.next_flag(), scanner.next_flag()) else { break };
+                let (Some(x), Some(y)) = (scanner.next_number(), scanner.next_number()) else { break };
+                let end = Pos2::new(x, y) + origin;
+                current.points.extend(arc_points(pos, end, rx, ry, rotation, large_arc, sweep));
+                pos = end;
+            }
+            b'Z' => {
+                current.closed = true;
+                if current.points.len() > 1 {
+                    subpaths.push(std::mem::take(&mut current));
+                }
+                // Drawing continues from the start of the closed subpath
+                current = Subpath { points: vec![start], closed: false };
+                pos = start;
+                command = 0;
+            }
+            _ => break,
+        }
+        last_control = None;
+    }
+    if current.points.len() > 1 {
+        subpaths.push(current);
+    }
+    subpaths
+}
+
+// The previous control point mirrored through `pos`, when the previous segment was the same kind of curve
+fn reflect(last: Option<(u8, Pos2)>, kind: u8, pos: Pos2) -> Pos2 {
+    match last {
+        Some((last_kind, control)) if last_kind == kind => pos + (pos - control),
+        _ => pos,
+    }
+}
+
+// Points along an elliptical arc from `from` to `to`, excluding `from` (SVG spec, appendix B.2.4)
+fn arc_points(from: Pos2, to: Pos2, rx: f32, ry: f32, rotation: f32, large_arc: bool, sweep: bool) -> Vec<Pos2> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 || from == to {
+        return vec![to];
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let half = (from - to) / 2.0;
+    let x1 = cos * half.x + sin * half.y;
+    let y1 = -sin * half.x + cos * half.y;
+
+    // Radii too small to reach are scaled up just enough
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = (rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1).max(0.0);
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut factor = (numerator / denominator).sqrt();
+    if large_arc == sweep {
+        factor = -factor;
+    }
+    let cx1 = factor * rx * y1 / ry;
+    let cy1 = -factor * ry * x1 / rx;
+    let mid = (from.to_vec2() + to.to_vec2()) / 2.0;
+    let center = Pos2::new(cos * cx1 - sin * cy1 + mid.x, sin * cx1 + cos * cy1 + mid.y);
+
+    let angle = |ux: f32, uy: f32| uy.atan2(ux);
+    let start = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start;
+    if sweep && delta < 0.0 {
+        delta += std::f32::consts::TAU;
+    } else if !sweep && delta > 0.0 {
+        delta -= std::f32::consts::TAU;
+    }
+
+    let segments = ((delta.abs() / std::f32::consts::TAU) * CURVE_SEGMENTS as f32 * 2.0).ceil().max(1.0) as usize;
+    (1..=segments)
+        .map(|i| {
+            if i == segments {
+                return to;
+            }
+            let theta = start + delta * i as f32 / segments as f32;
+            let (x, y) = (rx * theta.cos(), ry * theta.sin());
+            Pos2::new(cos * x - sin * y + center.x, sin * x + cos * y + center.y)
+        })
+        .collect()
+}
+
+/// Horizontal spans inside the outline, one row per point, calling `span(top, x0, x1)` for each.
+/// Every subpath is treated as closed, like SVG fills.
+fn fill_spans(subpaths: &[Subpath], even_odd: bool, mut span: impl FnMut(f32, f32, f32)) {
+    let edges: Vec<(Pos2, Pos2)> = subpaths.iter()
+        .filter(|s| s.points.len() > 2)
+        .flat_map(|s| s.points.iter().zip(s.points.iter().cycle().skip(1)).map(|(a, b)| (*a, *b)))
+        .filter(|(a, b)| a.y != b.y)
+        .collect();
+    let Some((top, bottom)) = edges.iter()
+        .flat_map(|(a, b)| [a.y, b.y])
+        .fold(None, |range: Option<(f32, f32)>, y| Some(range.map_or((y, y), |(lo, hi)| (lo.min(y), hi.max(y)))))
+    else {
+        return;
+    };
+
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    let mut row = top.floor();
+    while row < bottom {
+        // Sample each row through its middle
+        let y = row + 0.5;
+        crossings.clear();
+        for (a, b) in &edges {
+            let (lo, hi, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+            if y >= lo.y && y < hi.y {
+                crossings.push((lo.x + (y - lo.y) / (hi.y - lo.y) * (hi.x - lo.x), winding));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            let inside = if even_odd { winding % 2 != 0 } else { winding != 0 };
+            if inside && pair[1].0 > pair[0].0 {
+                span(row, pair[0].0, pair[1].0);
+            }
+        }
+        row += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path_commands() {
+        assert_eq!(numbers("10-5.5.5e1,3"), vec![10.0, -5.5, 5.0, 3.0]);
+
+        let subpaths = parse_path("M10 10 h20 v20 H10 z m5 5 l5 0 5 5");
+        assert_eq!(subpaths.len(), 2);
+        assert_eq!(subpaths[0].points, vec![Pos2::new(10.0, 10.0), Pos2::new(30.0, 10.0), Pos2::new(30.0, 30.0), Pos2::new(10.0, 30.0)]);
+        assert!(subpaths[0].closed);
+        assert_eq!(subpaths[1].points, vec![Pos2::new(15.0, 15.0), Pos2::new(20.0, 15.0), Pos2::new(25.0, 20.0)]);
+
+        // A half circle arc ends exactly at its end point and bulges the right way
+        let arc = parse_path("M0 0 A10 10 0 0 1 20 0");
+        let points = &arc[0].points;
+        assert_eq!(*points.last().unwrap(), Pos2::new(20.0, 0.0));
+        assert!(points.iter().all(|p| p.y <= 0.001) && points.iter().any(|p| p.y < -9.0));
+    }
+
+    #[test]
+    fn test_document_shapes_and_paint() {
+        let doc = SvgDocument::parse(r##"<?xml version="1.0"?>
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 50" width="200">
+              <defs><linearGradient id="g"><stop offset="0" stop-color="#ff0000"/></linearGradient></defs>
+              <g fill="blue" transform="translate(10 0)">
+                <rect x="0" y="0" width="10" height="10"/>
+                <circle cx="20" cy="20" r="5" fill="url(#g)" stroke="black" stroke-width="2"/>
+              </g>
+              <line x1="0" y1="0" x2="10" y2="10" stroke="green" style="stroke-width: 3"/>
+              <path d="M0 0 L5 5" fill="none"/>
+            </svg>"##).unwrap();
+        assert_eq!(doc.intrinsic_size(), Vec2::new(200.0, 100.0));
+        assert_eq!(doc.shapes.len(), 4);
+        assert_eq!(doc.shapes[0].fill, Some(Color32::BLUE));
+        assert_eq!(doc.shapes[0].subpaths[0].points[0], Pos2::new(10.0, 0.0));
+        assert_eq!(doc.shapes[1].fill, Some(Color32::RED));
+        assert_eq!(doc.shapes[1].stroke, Some(Color32::BLACK));
+        assert_eq!(doc.shapes[2].fill, None);
+        assert_eq!(doc.shapes[2].stroke_width, 3.0);
+        assert_eq!(doc.shapes[3].fill, None);
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        // A square with a square hole wound the same way: nonzero fills the hole, evenodd doesn't
+        let square = |x: f32, size: f32| Subpath {
+            points: vec![Pos2::new(x, x), Pos2::new(x + size, x), Pos2::new(x + size, x + size), Pos2::new(x, x + size)],
+            closed: true,
+        };
+        let shape = [square(0.0, 10.0), square(3.0, 4.0)];
+        let mut rows = Vec::new();
+        fill_spans(&shape, false, |y, x0, x1| rows.push((y, x0, x1)));
+        assert_eq!(rows.iter().filter(|(y, _, _)| *y == 5.0).count(), 3);
+        assert_eq!(rows.len(), 10 + 4 * 2);
+
+        let mut spans_in_hole_row = Vec::new();
+        fill_spans(&shape, true, |y, x0, x1| if y == 5.0 { spans_in_hole_row.push((x0, x1)) });
+        assert_eq!(spans_in_hole_row, vec![(0.0, 3.0), (7.0, 10.0)]);
+    }
+}
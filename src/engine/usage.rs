@@ -0,0 +1,141 @@
+// What a page costs the browser, for neon://tasks: the time spent parsing it, laying it out and painting it, and
+// running its scripts, and an estimate of the memory it holds
+use std::cell::Cell;
+use std::mem::size_of;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+use super::dom::DOMNode;
+
+// What each open tab used when the window last recorded it
+static OPEN_TABS: Mutex<Vec<TabUsage>> = Mutex::new(Vec::new());
+
+/// Time spent on a page since it was loaded
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTime {
+    /// Parsing its document and stylesheets
+    pub parse: Duration,
+    /// Styling, laying out and painting it, over every frame it was shown in
+    pub layout: Duration,
+    pub script: Duration,
+}
+
+impl CpuTime {
+    pub fn total(&self) -> Duration {
+        self.parse + self.layout + self.script
+    }
+}
+
+/// Adds up a page's `CpuTime` as it's spent
+#[derive(Debug, Default)]
+pub struct CpuMeter {
+    time: Cell<CpuTime>,
+}
+
+impl CpuMeter {
+    pub fn add_parse(&self, spent: Duration) {
+        self.add(|time| time.parse += spent);
+    }
+
+    pub fn add_layout(&self, spent: Duration) {
+        self.add(|time| time.layout += spent);
+    }
+
+    pub fn add_script(&self, spent: Duration) {
+        self.add(|time| time.script += spent);
+    }
+
+    pub fn time(&self) -> CpuTime {
+        self.time.get()
+    }
+
+    fn add(&self, apply: impl FnOnce(&mut CpuTime)) {
+        let mut time = self.time.get();
+        apply(&mut time);
+        self.time.set(time);
+    }
+}
+
+/// Bytes a page holds, estimated from what it keeps rather than measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// The document's nodes with their names, attributes and text
+    pub dom: usize,
+    /// Source, text and stylesheets kept alongside the document
+    pub strings: usize,
+    /// Decoded images, at four bytes a pixel
+    pub textures: usize,
+}
+
+impl MemoryEstimate {
+    pub fn total(&self) -> usize {
+        self.dom + self.strings + self.textures
+    }
+}
+
+/// What one tab uses, as neon://tasks lists it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabUsage {
+    pub id: Uuid,
+    pub title: String,
+    pub url: String,
+    pub memory: MemoryEstimate,
+    pub cpu: CpuTime,
+    /// The document and subresources still being fetched
+    pub requests: usize,
+    /// Whether the tab holds a web page, which ending it drops
+    pub has_page: bool,
+}
+
+/// Record what the open tabs use, in tab strip order
+pub fn record_tabs(tabs: Vec<TabUsage>) {
+    if let Ok(mut open) = OPEN_TABS.lock() {
+        *open = tabs;
+    }
+}
+
+/// The open tabs as last recorded
+pub fn tabs() -> Vec<TabUsage> {
+    OPEN_TABS.lock().map(|open| open.clone()).unwrap_or_default()
+}
+
+/// Estimated bytes held by `node` and everything in it
+pub fn dom_bytes(node: &DOMNode) -> usize {
+    let mut bytes = 0;
+    let mut pending = vec![node];
+    while let Some(node) = pending.pop() {
+        bytes += size_of::<DOMNode>();
+        match node {
+            DOMNode::Element { tag_name, attributes, children } => {
+                bytes += tag_name.capacity();
+                bytes += attributes.iter()
+                    .map(|(name, value)| size_of::<(String, String)>() + name.capacity() + value.capacity())
+                    .sum::<usize>();
+                pending.extend(children);
+            }
+            DOMNode::Text(text) | DOMNode::Comment(text) => bytes += text.capacity(),
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_dom_bytes_grow_with_the_document() {
+        let small = dom_bytes(&html_parser::parse("<p>hello</p>"));
+        let large = dom_bytes(&html_parser::parse(&"<p class=\"item\">hello world</p>".repeat(100)));
+        assert!(small >= 3 * size_of::<DOMNode>());
+        assert!(large > 100 * (2 * size_of::<DOMNode>() + "hello world".len()));
+
+        let meter = CpuMeter::default();
+        meter.add_parse(Duration::from_millis(3));
+        meter.add_script(Duration::from_millis(5));
+        meter.add_script(Duration::from_millis(5));
+        assert_eq!(meter.time().script, Duration::from_millis(10));
+        assert_eq!(meter.time().total(), Duration::from_millis(13));
+    }
+}
@@ -22,6 +22,23 @@ impl Default for VirtualScrollConfig {
     }
 }
 
+impl VirtualScrollConfig {
+    /// Viewport covering a visible span, with `scroll_offset` as its top edge
+    pub fn for_span(top: f32, height: f32) -> Self {
+        Self {
+            viewport_height: height,
+            scroll_offset: top,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a span measured in the same coordinates as `scroll_offset` is within `margin` of the viewport
+    pub fn is_near_viewport(&self, top: f32, bottom: f32, margin: f32) -> bool {
+        bottom >= self.scroll_offset - margin
+            && top <= self.scroll_offset + self.viewport_height + margin
+    }
+}
+
 /// Virtual viewport for managing large DOM trees efficiently
 #[derive(Debug)]
 pub struct VirtualViewport {
@@ -85,3 +102,18 @@ impl VirtualViewport {
         self.total_items as f32 * self.config.item_height
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_near_viewport() {
+        let viewport = VirtualScrollConfig::for_span(1000.0, 600.0);
+        assert!(viewport.is_near_viewport(1200.0, 1300.0, 0.0));
+        assert!(viewport.is_near_viewport(1700.0, 1800.0, 200.0));
+        assert!(!viewport.is_near_viewport(1700.0, 1800.0, 50.0));
+        assert!(viewport.is_near_viewport(700.0, 900.0, 150.0));
+        assert!(!viewport.is_near_viewport(0.0, 100.0, 200.0));
+    }
+}
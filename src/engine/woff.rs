@@ -0,0 +1,663 @@
+// WOFF and WOFF2 web font containers, unpacked into the plain sfnt (TrueType/OpenType) data egui loads
+use anyhow::{anyhow, bail, Result};
+use std::io::Read;
+
+/// Font file formats recognised by their leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontFormat {
+    TrueType,
+    OpenType,
+    Collection,
+    Woff,
+    Woff2,
+}
+
+impl FontFormat {
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        match data.get(..4)? {
+            [0, 1, 0, 0] | b"true" => Some(FontFormat::TrueType),
+            b"OTTO" => Some(FontFormat::OpenType),
+            b"ttcf" => Some(FontFormat::Collection),
+            b"wOFF" => Some(FontFormat::Woff),
+            b"wOF2" => Some(FontFormat::Woff2),
+            _ => None,
+        }
+    }
+}
+
+/// Unpack a downloaded font into sfnt data; TrueType and OpenType files are returned as they are
+pub fn to_sfnt(data: &[u8]) -> Result<Vec<u8>> {
+    match FontFormat::sniff(data) {
+        Some(FontFormat::TrueType | FontFormat::OpenType | FontFormat::Collection) => Ok(data.to_vec()),
+        Some(FontFormat::Woff) => decode_woff(data),
+        Some(FontFormat::Woff2) => decode_woff2(data),
+        None => bail!("not a font file"),
+    }
+}
+
+// Tags for the table directory's one-byte shorthand, in the order the WOFF2 spec numbers them
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+// Simple glyph flags in the glyf table
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+// Composite glyph component flags
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+struct Table {
+    tag: [u8; 4],
+    data: Vec<u8>,
+}
+
+fn decode_woff(data: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(30)?;
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let tag = header.tag()?;
+        let offset = header.u32()? as usize;
+        let compressed_length = header.u32()? as usize;
+        let length = header.u32()? as usize;
+        header.skip(4)?;
+
+        let stored = data.get(offset..offset + compressed_length)
+            .ok_or_else(|| anyhow!("table {} lies outside the file", tag_name(&tag)))?;
+        let table = if compressed_length < length {
+            let mut table = Vec::with_capacity(length);
+            flate2::read::ZlibDecoder::new(stored).read_to_end(&mut table)?;
+            table
+        } else {
+            stored.to_vec()
+        };
+        if table.len() != length {
+            bail!("table {} has the wrong length", tag_name(&tag));
+        }
+        tables.push(Table { tag, data: table });
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+struct Woff2Entry {
+    tag: [u8; 4],
+    length: usize,
+    transformed: bool,
+    stored_length: usize,
+}
+
+fn decode_woff2(data: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    if &flavor.to_be_bytes() == b"ttcf" {
+        bail!("WOFF2 font collections are not supported");
+    }
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(6)?;
+    let compressed_length = header.u32()? as usize;
+    header.skip(24)?;
+
+    let mut entries = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match flags & 0x3f {
+            63 => header.tag()?,
+            index => *KNOWN_TAGS[index as usize],
+        };
+        let version = flags >> 6;
+        let length = header.base128()? as usize;
+        // glyf and loca use version 3 for "not transformed", every other table uses 0
+        let transformed = if &tag == b"glyf" || &tag == b"loca" { version != 3 } else { version != 0 };
+        let stored_length = if transformed { header.base128()? as usize } else { length };
+        entries.push(Woff2Entry { tag, length, transformed, stored_length });
+    }
+
+    let mut stream = Vec::new();
+    brotli::Decompressor::new(header.bytes(compressed_length)?, 4096).read_to_end(&mut stream)?;
+
+    // Tables follow each other in directory order, without padding
+    let mut offset = 0;
+    let mut stored = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let bytes = stream.get(offset..offset + entry.stored_length)
+            .ok_or_else(|| anyhow!("table {} lies outside the font data", tag_name(&entry.tag)))?;
+        stored.push(bytes);
+        offset += entry.stored_length;
+    }
+    let find = |tag: &[u8; 4]| entries.iter().position(|e| &e.tag == tag);
+
+    let mut glyf = None;
+    if let Some(index) = find(b"glyf").filter(|&i| entries[i].transformed) {
+        glyf = Some(reconstruct_glyf(stored[index])?);
+    }
+
+    let mut tables = Vec::with_capacity(entries.len());
+    for (entry, bytes) in entries.iter().zip(&stored) {
+        let data = match (&entry.tag, entry.transformed, &glyf) {
+            (_, false, _) => bytes.to_vec(),
+            (b"glyf", true, Some(glyf)) => glyf.glyf.clone(),
+            (b"loca", true, Some(glyf)) => glyf.loca.clone(),
+            (b"hmtx", true, Some(glyf)) => {
+                let table = |tag| find(tag).map(|i| stored[i]).ok_or_else(|| anyhow!("missing {} table", tag_name(tag)));
+                let h_metrics = Reader::new(table(b"hhea")?).at(34)?.u16()? as usize;
+                reconstruct_hmtx(bytes, h_metrics, &glyf.x_mins)?
+            }
+            _ => bail!("unsupported transform of table {}", tag_name(&entry.tag)),
+        };
+        // Rebuilt glyf data is encoded our own way, so only the other tables must match exactly
+        let rebuilt = entry.transformed && (&entry.tag == b"glyf" || &entry.tag == b"loca");
+        if !rebuilt && data.len() != entry.length {
+            bail!("table {} has the wrong length", tag_name(&entry.tag));
+        }
+        tables.push(Table { tag: entry.tag, data });
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+struct Glyf {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    // Per glyph, used to rebuild left side bearings the hmtx transform dropped
+    x_mins: Vec<i16>,
+}
+
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyf> {
+    let mut header = Reader::new(data);
+    header.skip(2)?;
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let long_loca = header.u16()? != 0;
+    let mut sizes = [0usize; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+
+    let mut n_contours = Reader::new(header.bytes(sizes[0])?);
+    let mut n_points = Reader::new(header.bytes(sizes[1])?);
+    let mut flags = Reader::new(header.bytes(sizes[2])?);
+    let mut glyphs = Reader::new(header.bytes(sizes[3])?);
+    let mut composites = Reader::new(header.bytes(sizes[4])?);
+    let bbox_stream = header.bytes(sizes[5])?;
+    let mut instructions = Reader::new(header.bytes(sizes[6])?);
+    let overlap_bitmap = if option_flags & 1 != 0 { Some(header.bytes(num_glyphs.div_ceil(8))?) } else { None };
+
+    let bitmap_length = 4 * num_glyphs.div_ceil(32);
+    let bbox_bitmap = bbox_stream.get(..bitmap_length).ok_or_else(|| anyhow!("truncated bounding boxes"))?;
+    let mut bboxes = Reader::new(&bbox_stream[bitmap_length..]);
+    let bit = |bitmap: &[u8], i: usize| bitmap[i >> 3] & (0x80 >> (i & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let contours = n_contours.i16()?;
+        let explicit_bbox = bit(bbox_bitmap, index);
+
+        if contours == 0 {
+            if explicit_bbox {
+                bail!("empty glyph {} has a bounding box", index);
+            }
+            x_mins.push(0);
+            continue;
+        }
+
+        let start = glyf.len();
+        if contours < 0 {
+            if !explicit_bbox {
+                bail!("composite glyph {} has no bounding box", index);
+            }
+            let bbox = [bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?];
+            push_i16(&mut glyf, -1);
+            bbox.iter().for_each(|&v| push_i16(&mut glyf, v));
+
+            let mut has_instructions = false;
+            loop {
+                let component_flags = composites.u16()?;
+                let mut length = 2 + if component_flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+                length += if component_flags & WE_HAVE_A_SCALE != 0 {
+                    2
+                } else if component_flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    4
+                } else if component_flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    8
+                } else {
+                    0
+                };
+                glyf.extend_from_slice(&component_flags.to_be_bytes());
+                glyf.extend_from_slice(composites.bytes(length)?);
+                has_instructions |= component_flags & WE_HAVE_INSTRUCTIONS != 0;
+                if component_flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            if has_instructions {
+                let length = glyphs.u255_16()?;
+                glyf.extend_from_slice(&length.to_be_bytes());
+                glyf.extend_from_slice(instructions.bytes(length as usize)?);
+            }
+            x_mins.push(bbox[0]);
+        } else {
+            let mut end_points = Vec::with_capacity(contours as usize);
+            let mut total = 0usize;
+            for _ in 0..contours {
+                total += n_points.u255_16()? as usize;
+                end_points.push(total.checked_sub(1).ok_or_else(|| anyhow!("empty contour in glyph {}", index))?);
+            }
+
+            let mut points = Vec::with_capacity(total);
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..total {
+                let flag = flags.u8()?;
+                let (dx, dy) = decode_triplet(flag & 0x7f, &mut glyphs)?;
+                x += dx;
+                y += dy;
+                points.push((x, y, flag & 0x80 == 0));
+            }
+            let instruction_length = glyphs.u255_16()?;
+
+            let bbox = if explicit_bbox {
+                [bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?]
+            } else {
+                let (xs, ys) = (points.iter().map(|p| p.0), points.iter().map(|p| p.1));
+                [
+                    xs.clone().min().unwrap_or(0) as i16,
+                    ys.clone().min().unwrap_or(0) as i16,
+                    xs.max().unwrap_or(0) as i16,
+                    ys.max().unwrap_or(0) as i16,
+                ]
+            };
+
+            push_i16(&mut glyf, contours);
+            bbox.iter().for_each(|&v| push_i16(&mut glyf, v));
+            for end in end_points {
+                let end = u16::try_from(end).map_err(|_| anyhow!("too many points in glyph {}", index))?;
+                glyf.extend_from_slice(&end.to_be_bytes());
+            }
+            glyf.extend_from_slice(&instruction_length.to_be_bytes());
+            glyf.extend_from_slice(instructions.bytes(instruction_length as usize)?);
+            let overlap = overlap_bitmap.is_some_and(|bitmap| bit(bitmap, index));
+            encode_points(&mut glyf, &points, overlap);
+            x_mins.push(bbox[0]);
+        }
+
+        // Keep every glyph 4-byte aligned, which satisfies both loca formats
+        while (glyf.len() - start) % 4 != 0 {
+            glyf.push(0);
+        }
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::with_capacity(offsets.len() * if long_loca { 4 } else { 2 });
+    for offset in offsets {
+        if long_loca {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        } else {
+            let half = u16::try_from(offset / 2).map_err(|_| anyhow!("glyphs too large for a short loca table"))?;
+            loca.extend_from_slice(&half.to_be_bytes());
+        }
+    }
+
+    Ok(Glyf { glyf, loca, x_mins })
+}
+
+// Point deltas are stored as a flag plus 1-4 bytes; the flag selects how those bytes split into x and y
+fn decode_triplet(flag: u8, data: &mut Reader) -> Result<(i32, i32)> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let flag_value = flag as i32;
+
+    Ok(match flag {
+        0..=9 => (0, with_sign(flag, ((flag_value & 14) << 7) + data.u8()? as i32)),
+        10..=19 => (with_sign(flag, (((flag_value - 10) & 14) << 7) + data.u8()? as i32), 0),
+        20..=83 => {
+            let b0 = flag_value - 20;
+            let b1 = data.u8()? as i32;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0c) << 2) + (b1 & 0x0f)),
+            )
+        }
+        84..=119 => {
+            let b0 = flag_value - 84;
+            let (b1, b2) = (data.u8()? as i32, data.u8()? as i32);
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        }
+        120..=123 => {
+            let (b1, b2, b3) = (data.u8()? as i32, data.u8()? as i32, data.u8()? as i32);
+            (with_sign(flag, (b1 << 4) + (b2 >> 4)), with_sign(flag >> 1, ((b2 & 0x0f) << 8) + b3))
+        }
+        _ => {
+            let (x, y) = (data.u16()? as i32, data.u16()? as i32);
+            (with_sign(flag, x), with_sign(flag >> 1, y))
+        }
+    })
+}
+
+// Write the flags and coordinate arrays of a simple glyph, using the short forms where they fit
+fn encode_points(out: &mut Vec<u8>, points: &[(i32, i32, bool)], overlap: bool) {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let (mut previous_x, mut previous_y) = (0, 0);
+
+    for (i, &(x, y, on_curve)) in points.iter().enumerate() {
+        let mut flag = if on_curve { ON_CURVE } else { 0 };
+        if i == 0 && overlap {
+            flag |= OVERLAP_SIMPLE;
+        }
+        flag |= encode_delta(x - previous_x, X_SHORT, X_SAME_OR_POSITIVE, &mut xs);
+        flag |= encode_delta(y - previous_y, Y_SHORT, Y_SAME_OR_POSITIVE, &mut ys);
+        flags.push(flag);
+        (previous_x, previous_y) = (x, y);
+    }
+
+    out.extend(flags);
+    out.extend(xs);
+    out.extend(ys);
+}
+
+fn encode_delta(delta: i32, short: u8, same_or_positive: u8, out: &mut Vec<u8>) -> u8 {
+    if delta == 0 {
+        same_or_positive
+    } else if (-255..=255).contains(&delta) {
+        out.push(delta.unsigned_abs() as u8);
+        if delta > 0 { short | same_or_positive } else { short }
+    } else {
+        out.extend_from_slice(&(delta as i16).to_be_bytes());
+        0
+    }
+}
+
+fn reconstruct_hmtx(data: &[u8], h_metrics: usize, x_mins: &[i16]) -> Result<Vec<u8>> {
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let num_glyphs = x_mins.len();
+    if h_metrics == 0 || h_metrics > num_glyphs {
+        bail!("invalid number of horizontal metrics");
+    }
+
+    let mut advances = Vec::with_capacity(h_metrics);
+    for _ in 0..h_metrics {
+        advances.push(reader.u16()?);
+    }
+    // Bearings left out of the stream equal each glyph's xMin
+    let mut bearings = Vec::with_capacity(num_glyphs);
+    for (i, &x_min) in x_mins.iter().enumerate() {
+        let omitted = if i < h_metrics { flags & 1 != 0 } else { flags & 2 != 0 };
+        bearings.push(if omitted { x_min } else { reader.i16()? });
+    }
+
+    let mut hmtx = Vec::with_capacity(h_metrics * 2 + num_glyphs * 2);
+    for (i, bearing) in bearings.into_iter().enumerate() {
+        if let Some(advance) = advances.get(i) {
+            hmtx.extend_from_slice(&advance.to_be_bytes());
+        }
+        push_i16(&mut hmtx, bearing);
+    }
+    Ok(hmtx)
+}
+
+fn build_sfnt(flavor: u32, mut tables: Vec<Table>) -> Vec<u8> {
+    tables.sort_by_key(|t| t.tag);
+    let count = tables.len() as u16;
+    let entry_selector = if count == 0 { 0 } else { 15 - count.leading_zeros() as u16 };
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&flavor.to_be_bytes());
+    for value in [count, search_range, entry_selector, count * 16 - search_range] {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + 16 * tables.len();
+    for table in &tables {
+        out.extend_from_slice(&table.tag);
+        out.extend_from_slice(&checksum(&table.data).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(table.data.len() as u32).to_be_bytes());
+        offset += table.data.len().next_multiple_of(4);
+    }
+    for table in tables {
+        out.extend(table.data);
+        out.resize(out.len().next_multiple_of(4), 0);
+    }
+    out
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn push_i16(out: &mut Vec<u8>, value: i16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn tag_name(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).trim_end().to_string()
+}
+
+// Bounds-checked big-endian reader over one of the font's data streams
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn at(mut self, position: usize) -> Result<Self> {
+        if position > self.data.len() {
+            bail!("unexpected end of font data");
+        }
+        self.position = position;
+        Ok(self)
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + length)
+            .ok_or_else(|| anyhow!("unexpected end of font data"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<()> {
+        self.bytes(length).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn tag(&mut self) -> Result<[u8; 4]> {
+        Ok(self.bytes(4)?.try_into()?)
+    }
+
+    // UIntBase128: 7 bits per byte, high bit set on all but the last, at most 5 bytes
+    fn base128(&mut self) -> Result<u32> {
+        let mut value: u32 = 0;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            if i == 0 && byte == 0x80 {
+                bail!("invalid UIntBase128 with a leading zero");
+            }
+            if value & 0xfe00_0000 != 0 {
+                bail!("UIntBase128 overflows 32 bits");
+            }
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("UIntBase128 longer than 5 bytes")
+    }
+
+    // 255UInt16: one byte for small values, with 253-255 as escapes for larger ones
+    fn u255_16(&mut self) -> Result<u16> {
+        Ok(match self.u8()? {
+            253 => self.u16()?,
+            254 => self.u8()? as u16 + 506,
+            255 => self.u8()? as u16 + 253,
+            code => code as u16,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn sfnt_tables(sfnt: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut reader = Reader::new(sfnt);
+        reader.skip(4).unwrap();
+        let count = reader.u16().unwrap();
+        reader.skip(6).unwrap();
+        (0..count).map(|_| {
+            let tag = reader.tag().unwrap();
+            reader.skip(4).unwrap();
+            let offset = reader.u32().unwrap() as usize;
+            let length = reader.u32().unwrap() as usize;
+            (tag_name(&tag), sfnt[offset..offset + length].to_vec())
+        }).collect()
+    }
+
+    #[test]
+    fn test_number_encodings() {
+        assert_eq!(Reader::new(&[0x3f]).base128().unwrap(), 63);
+        assert_eq!(Reader::new(&[0x81, 0x00]).base128().unwrap(), 128);
+        assert!(Reader::new(&[0x80, 0x01]).base128().is_err());
+        assert_eq!(Reader::new(&[252]).u255_16().unwrap(), 252);
+        assert_eq!(Reader::new(&[255, 0]).u255_16().unwrap(), 253);
+        assert_eq!(Reader::new(&[254, 10]).u255_16().unwrap(), 516);
+        assert_eq!(Reader::new(&[253, 0x12, 0x34]).u255_16().unwrap(), 0x1234);
+    }
+
+    #[test]
+    fn test_woff_tables_are_inflated() {
+        let head = vec![7u8; 54];
+        let name = b"NeonSans".to_vec();
+        let mut compressed = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        compressed.write_all(&head).unwrap();
+        let compressed = compressed.finish().unwrap();
+
+        let directory_end = 44 + 2 * 20;
+        let mut woff = b"wOFF".to_vec();
+        woff.extend_from_slice(&[0, 1, 0, 0]);
+        woff.extend_from_slice(&[0; 4]);
+        woff.extend_from_slice(&2u16.to_be_bytes());
+        woff.extend_from_slice(&[0; 30]);
+        let mut offset = directory_end;
+        for (tag, stored, length) in [(b"head", &compressed, head.len()), (b"name", &name, name.len())] {
+            woff.extend_from_slice(tag);
+            woff.extend_from_slice(&(offset as u32).to_be_bytes());
+            woff.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+            woff.extend_from_slice(&(length as u32).to_be_bytes());
+            woff.extend_from_slice(&[0; 4]);
+            offset += stored.len();
+        }
+        woff.extend_from_slice(&compressed);
+        woff.extend_from_slice(&name);
+
+        let sfnt = to_sfnt(&woff).unwrap();
+        assert_eq!(FontFormat::sniff(&sfnt), Some(FontFormat::TrueType));
+        assert_eq!(sfnt_tables(&sfnt), vec![("head".to_string(), head), ("name".to_string(), name)]);
+    }
+
+    #[test]
+    fn test_woff2_glyf_transform() {
+        // One triangle: (0,0) (100,0) (50,200), all on-curve
+        let mut glyf = Vec::new();
+        glyf.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 0]);
+        let streams: [&[u8]; 7] = [
+            &[0, 1],               // one contour
+            &[3],                  // of three points
+            &[1, 11, 86],          // triplet flags
+            &[0, 100, 49, 199, 0], // triplet data, then no instructions
+            &[],
+            &[0, 0, 0, 0],         // no explicit bounding boxes
+            &[],
+        ];
+        for stream in streams {
+            glyf.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+        }
+        streams.iter().for_each(|s| glyf.extend_from_slice(s));
+        let head = vec![1u8; 54];
+
+        let mut stream = glyf.clone();
+        stream.extend_from_slice(&head);
+        let mut compressed = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        compressed.write_all(&stream).unwrap();
+        let compressed = compressed.into_inner();
+
+        let mut woff2 = b"wOF2".to_vec();
+        woff2.extend_from_slice(&[0, 1, 0, 0]);
+        woff2.extend_from_slice(&[0; 4]);
+        woff2.extend_from_slice(&3u16.to_be_bytes());
+        woff2.extend_from_slice(&[0; 6]);
+        woff2.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+        woff2.extend_from_slice(&[0; 24]);
+        woff2.extend_from_slice(&[10, 20, glyf.len() as u8]); // glyf, transformed
+        woff2.extend_from_slice(&[11, 4, 0]);                  // loca, transformed
+        woff2.extend_from_slice(&[1, 54]);                     // head
+        woff2.extend_from_slice(&compressed);
+
+        let tables = sfnt_tables(&to_sfnt(&woff2).unwrap());
+        let expected_glyf = vec![
+            0, 1, 0, 0, 0, 0, 0, 100, 0, 200, // one contour, bbox (0,0)-(100,200)
+            0, 2,                             // last point
+            0, 0,                             // no instructions
+            0x31, 0x33, 0x27,                 // flags
+            100, 50,                          // x deltas
+            200,                              // y delta
+        ];
+        assert_eq!(tables[0], ("glyf".to_string(), expected_glyf));
+        assert_eq!(tables[1], ("head".to_string(), head));
+        assert_eq!(tables[2], ("loca".to_string(), vec![0, 0, 0, 10]));
+    }
+}
@@ -0,0 +1,131 @@
+// Content scripts: JavaScript from enabled extensions and userscripts, run in pages whose URL matches their patterns
+use std::sync::{Mutex, OnceLock};
+use crate::extensions::manifest::RunAt;
+use crate::extensions::match_pattern::{self, MatchPattern, ALL_URLS};
+use crate::extensions::registry::{ExtensionRegistry, InstalledExtension};
+use crate::extensions::userscripts::{UserScript, UserScriptStore};
+
+#[derive(Debug, Clone)]
+pub struct ContentScript {
+    pub extension_id: String,
+    pub extension_name: String,
+    pub run_at: RunAt,
+    pub source: String,
+    matches: Vec<MatchPattern>,
+    exclude_matches: Vec<MatchPattern>,
+    // Globs over the whole URL that further narrow `matches`
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+}
+
+impl ContentScript {
+    pub fn applies_to(&self, url: &str) -> bool {
+        self.matches.iter().any(|pattern| pattern.matches(url))
+            && !self.exclude_matches.iter().any(|pattern| pattern.matches(url))
+            && (self.include_globs.is_empty() || self.include_globs.iter().any(|glob| match_pattern::glob_matches(glob, url)))
+            && !self.exclude_globs.iter().any(|glob| match_pattern::glob_matches(glob, url))
+    }
+
+    /// A userscript runs in a world of its own; with only @include globs it's offered every page and the globs decide
+    pub fn from_userscript(script: &UserScript) -> Self {
+        let meta = &script.meta;
+        let matches = match meta.matches.is_empty() {
+            true => vec![ALL_URLS.to_string()],
+            false => meta.matches.clone(),
+        };
+        ContentScript {
+            extension_id: format!("userscript:{}", script.file),
+            extension_name: meta.name.clone(),
+            run_at: meta.run_at,
+            source: script.source.clone(),
+            matches: matches.iter().filter_map(|p| MatchPattern::parse(p).ok()).collect(),
+            exclude_matches: meta.exclude_matches.iter().filter_map(|p| MatchPattern::parse(p).ok()).collect(),
+            include_globs: if meta.matches.is_empty() { meta.includes.clone() } else { Vec::new() },
+            exclude_globs: meta.excludes.clone(),
+        }
+    }
+
+    /// Every script an extension declares, read from its directory; unreadable files are skipped
+    pub fn load_all(extension: &InstalledExtension) -> Vec<ContentScript> {
+        let parse = |patterns: &[String]| patterns.iter().filter_map(|p| MatchPattern::parse(p).ok()).collect::<Vec<_>>();
+        let mut scripts = Vec::new();
+        for entry in &extension.manifest.content_scripts {
+            for file in &entry.js {
+                match std::fs::read_to_string(extension.path.join(file)) {
+                    Ok(source) => scripts.push(ContentScript {
+                        extension_id: extension.id.clone(),
+                        extension_name: extension.manifest.name.clone(),
+                        run_at: entry.run_at,
+                        source,
+                        matches: parse(&entry.matches),
+                        exclude_matches: parse(&entry.exclude_matches),
+                        include_globs: Vec::new(),
+                        exclude_globs: Vec::new(),
+                    }),
+                    Err(e) => log::warn!("Cannot read content script {} of '{}': {}", file, extension.manifest.name, e),
+                }
+            }
+        }
+        scripts
+    }
+}
+
+// Scripts of the enabled extensions and userscripts, and the registry and store revisions they were read at
+type LoadedScripts = (Option<(u64, u64)>, Vec<ContentScript>);
+static LOADED_SCRIPTS: OnceLock<Mutex<LoadedScripts>> = OnceLock::new();
+
+/// Content scripts of enabled extensions and userscripts that apply to `url`, in the order they run
+pub fn for_url(url: &str) -> Vec<ContentScript> {
+    let (registry, store) = (ExtensionRegistry::shared(), UserScriptStore::shared());
+    let (Ok(registry), Ok(store)) = (registry.lock(), store.lock()) else {
+        return Vec::new();
+    };
+    let Ok(mut loaded) = LOADED_SCRIPTS.get_or_init(|| Mutex::new((None, Vec::new()))).lock() else {
+        return Vec::new();
+    };
+    let revisions = (registry.revision(), store.revision());
+    if loaded.0 != Some(revisions) {
+        let mut scripts: Vec<ContentScript> = registry.enabled().flat_map(ContentScript::load_all).collect();
+        scripts.extend(store.enabled().map(ContentScript::from_userscript));
+        *loaded = (Some(revisions), scripts);
+    }
+    let mut scripts: Vec<ContentScript> = loaded.1.iter().filter(|script| script.applies_to(url)).cloned().collect();
+    scripts.sort_by_key(|script| script.run_at != RunAt::DocumentStart);
+    scripts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::WebPage;
+
+    fn script(run_at: RunAt, source: &str) -> ContentScript {
+        ContentScript {
+            extension_id: "cleaner".to_string(),
+            extension_name: "Cleaner".to_string(),
+            run_at,
+            source: source.to_string(),
+            matches: vec![MatchPattern::parse("https://*.example.com/*").unwrap()],
+            exclude_matches: vec![MatchPattern::parse("https://example.com/keep/*").unwrap()],
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_content_scripts_change_the_page() {
+        let scripts = [
+            script(RunAt::DocumentStart, "var seen = document.querySelector('.ad')"),
+            script(RunAt::DocumentEnd, "document.querySelectorAll('.ad').forEach(ad => ad.remove());\n\
+                document.getElementById('title').textContent = 'Cleaned'"),
+        ];
+        assert!(scripts[1].applies_to("https://www.example.com/news"));
+        assert!(!scripts[1].applies_to("https://example.com/keep/this"));
+
+        let mut page = WebPage::from_html(r#"<div class="ad">Buy</div><h1 id="title">News</h1><p class="ad">Now</p>"#, None);
+        page.run_content_scripts(&scripts);
+        let text = page.dom.collect_text();
+        assert!(text.contains("Cleaned"));
+        assert!(!text.contains("Buy") && !text.contains("Now"));
+    }
+}
@@ -0,0 +1,145 @@
+// Extension manifest (manifest.json) parsing and validation
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::extensions::match_pattern::MatchPattern;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// Permissions an extension may request; anything else is rejected at load time
+pub const KNOWN_PERMISSIONS: &[&str] = &[
+    "tabs",
+    "storage",
+    "cookies",
+    "history",
+    "downloads",
+    "webRequest",
+    "webRequestBlocking",
+    "<all_urls>",
+];
+
+/// When a content script runs: before the page's content exists, or once the document is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunAt {
+    DocumentStart,
+    #[default]
+    #[serde(alias = "document_idle")]
+    DocumentEnd,
+}
+
+/// Scripts an extension runs in pages matching its patterns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentScriptEntry {
+    pub matches: Vec<String>,
+    #[serde(default)]
+    pub exclude_matches: Vec<String>,
+    /// Files inside the extension directory
+    pub js: Vec<String>,
+    #[serde(default)]
+    pub run_at: RunAt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// Match patterns for the sites the extension may read and change
+    #[serde(default)]
+    pub host_permissions: Vec<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub options_page: Option<String>,
+    #[serde(default)]
+    pub content_scripts: Vec<ContentScriptEntry>,
+    /// JSON file of request rules, which needs the webRequestBlocking permission
+    #[serde(default)]
+    pub declarative_rules: Option<String>,
+}
+
+impl ExtensionManifest {
+    /// Read and validate `manifest.json` from an extension directory
+    pub fn load(dir: &Path) -> Result<Self> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let content = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| anyhow!("Cannot read {}: {}", manifest_path.display(), e))?;
+        let manifest = Self::parse(&content)?;
+
+        // Referenced files must live inside the extension directory
+        let scripts = manifest.content_scripts.iter().flat_map(|entry| entry.js.iter());
+        let files = manifest.icon.iter().chain(manifest.options_page.iter()).chain(manifest.declarative_rules.iter());
+        for file in files.chain(scripts) {
+            if file.contains("..") || Path::new(file).is_absolute() {
+                return Err(anyhow!("Manifest path '{}' escapes the extension directory", file));
+            }
+            if !dir.join(file).exists() {
+                return Err(anyhow!("Manifest references missing file '{}'", file));
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: ExtensionManifest = serde_json::from_str(content)
+            .map_err(|e| anyhow!("Invalid manifest: {}", e))?;
+
+        if manifest.name.trim().is_empty() {
+            return Err(anyhow!("Manifest is missing a name"));
+        }
+        if manifest.version.trim().is_empty() {
+            return Err(anyhow!("Manifest is missing a version"));
+        }
+        if let Some(unknown) = manifest.permissions.iter().find(|p| !KNOWN_PERMISSIONS.contains(&p.as_str())) {
+            return Err(anyhow!("Unknown permission '{}'", unknown));
+        }
+        if manifest.declarative_rules.is_some() && !manifest.permissions.iter().any(|p| p == "webRequestBlocking") {
+            return Err(anyhow!("Request rules need the webRequestBlocking permission"));
+        }
+        let content_patterns = manifest.content_scripts.iter()
+            .flat_map(|entry| entry.matches.iter().chain(entry.exclude_matches.iter()));
+        for pattern in manifest.host_permissions.iter().chain(content_patterns) {
+            MatchPattern::parse(pattern)?;
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_minimal_manifest() {
+        let manifest = ExtensionManifest::parse(r#"{"name": "Dark Reader", "version": "1.0.0"}"#).unwrap();
+        assert_eq!(manifest.name, "Dark Reader");
+        assert!(manifest.permissions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_content_scripts() {
+        let manifest = ExtensionManifest::parse(r#"{"name": "x", "version": "1", "content_scripts": [
+            {"matches": ["https://*.example.com/*"], "js": ["early.js"], "run_at": "document_start"},
+            {"matches": ["<all_urls>"], "js": ["late.js"], "run_at": "document_idle"}
+        ]}"#).unwrap();
+        assert_eq!(manifest.content_scripts[0].run_at, RunAt::DocumentStart);
+        assert_eq!(manifest.content_scripts[1].run_at, RunAt::DocumentEnd);
+    }
+
+    #[test]
+    fn test_reject_unknown_permission() {
+        let result = ExtensionManifest::parse(r#"{"name": "x", "version": "1", "permissions": ["nativeMessaging"]}"#);
+        assert!(result.is_err());
+
+        let result = ExtensionManifest::parse(r#"{"name": "x", "version": "1", "host_permissions": ["example.com"]}"#);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// URL match patterns (`*://*.example.com/*`, `<all_urls>`) used for host permissions and, by extensions, to pick pages
+use anyhow::{Result, anyhow};
+
+pub const ALL_URLS: &str = "<all_urls>";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchPattern {
+    // None matches http and https, like `*`
+    scheme: Option<String>,
+    // None matches any host; a leading `*.` also matches the domain itself
+    host: Option<String>,
+    subdomains: bool,
+    // Glob over the path and query, where `*` matches any run of characters
+    path: String,
+    all_urls: bool,
+}
+
+impl MatchPattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if pattern == ALL_URLS {
+            return Ok(Self { scheme: None, host: None, subdomains: false, path: "*".to_string(), all_urls: true });
+        }
+        let invalid = |reason: &str| anyhow!("Invalid match pattern '{}': {}", pattern, reason);
+        let (scheme, rest) = pattern.split_once("://").ok_or_else(|| invalid("missing scheme"))?;
+        let scheme = match scheme {
+            "*" => None,
+            "http" | "https" | "file" => Some(scheme.to_string()),
+            _ => return Err(invalid("unsupported scheme")),
+        };
+        let (host, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => return Err(invalid("missing path")),
+        };
+        let (host, subdomains) = match host {
+            "*" => (None, false),
+            _ if scheme.as_deref() == Some("file") => (None, false),
+            _ => {
+                let (name, subdomains) = match host.strip_prefix("*.") {
+                    Some(name) => (name, true),
+                    None => (host, false),
+                };
+                if name.is_empty() || name.contains('*') {
+                    return Err(invalid("'*' is only allowed as the whole host or before its first dot"));
+                }
+                (Some(name.to_ascii_lowercase()), subdomains)
+            }
+        };
+        Ok(Self { scheme, host, subdomains, path: path.to_string(), all_urls: false })
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        let Ok(url) = url::Url::parse(url) else {
+            return false;
+        };
+        let scheme_matches = match &self.scheme {
+            Some(scheme) => url.scheme() == scheme,
+            None if self.all_urls => matches!(url.scheme(), "http" | "https" | "file"),
+            None => matches!(url.scheme(), "http" | "https"),
+        };
+        if !scheme_matches {
+            return false;
+        }
+        let host_matches = match (&self.host, url.host_str()) {
+            (None, _) => true,
+            (Some(pattern), Some(host)) => {
+                host == pattern || (self.subdomains && host.strip_suffix(pattern.as_str()).is_some_and(|prefix| prefix.ends_with('.')))
+            }
+            (Some(_), None) => false,
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        host_matches && glob_matches(&self.path, &path)
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of characters
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all, so the whole text had to be the prefix
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_patterns() {
+        let pattern = MatchPattern::parse("*://*.example.com/docs/*").unwrap();
+        assert!(pattern.matches("https://example.com/docs/intro"));
+        assert!(pattern.matches("http://www.example.com/docs/a?b=c"));
+        assert!(!pattern.matches("https://notexample.com/docs/intro"));
+        assert!(!pattern.matches("https://example.com/blog"));
+        assert!(!pattern.matches("ftp://example.com/docs/x"));
+
+        let all = MatchPattern::parse(ALL_URLS).unwrap();
+        assert!(all.matches("file:///home/user/page.html"));
+        assert!(!all.matches("neon://settings"));
+
+        assert!(MatchPattern::parse("https://exa*mple.com/*").is_err());
+        assert!(MatchPattern::parse("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob_matches("/*", "/anything/at/all"));
+        assert!(glob_matches("/a*c*e", "/abcde"));
+        assert!(!glob_matches("/a*c*e", "/abcdef"));
+        assert!(glob_matches("/exact", "/exact"));
+        assert!(!glob_matches("/exact", "/exactly"));
+    }
+}
@@ -0,0 +1,12 @@
+// Browser extension subsystem
+pub mod content_scripts;
+pub mod manifest;
+pub mod match_pattern;
+pub mod registry;
+pub mod userscripts;
+pub mod web_request;
+
+pub use content_scripts::ContentScript;
+pub use manifest::{ExtensionManifest, RunAt};
+pub use match_pattern::MatchPattern;
+pub use registry::{ExtensionRegistry, InstalledExtension};
@@ -0,0 +1,250 @@
+// Installed extension state: discovery, enable/disable and unpacked loading
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::extensions::manifest::{ExtensionManifest, MANIFEST_FILE};
+use crate::extensions::match_pattern::{MatchPattern, ALL_URLS};
+
+#[derive(Debug, Clone)]
+pub struct InstalledExtension {
+    pub id: String,
+    pub manifest: ExtensionManifest,
+    pub path: PathBuf,
+    pub enabled: bool,
+    pub unpacked: bool,
+}
+
+impl InstalledExtension {
+    pub fn icon_path(&self) -> Option<PathBuf> {
+        self.manifest.icon.as_ref().map(|icon| self.path.join(icon))
+    }
+
+    pub fn options_path(&self) -> Option<PathBuf> {
+        self.manifest.options_page.as_ref().map(|page| self.path.join(page))
+    }
+
+    /// The extension's request rules file, if it may block requests
+    pub fn rules_path(&self) -> Option<PathBuf> {
+        let rules = self.manifest.declarative_rules.as_ref().filter(|_| self.has_permission("webRequestBlocking"))?;
+        Some(self.path.join(rules))
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.manifest.permissions.iter().any(|p| p == permission)
+    }
+
+    /// Whether the extension's host permissions cover `url`, which it needs before touching a page or its requests
+    pub fn can_access(&self, url: &str) -> bool {
+        self.has_permission(ALL_URLS) || self.manifest.host_permissions.iter()
+            .filter_map(|pattern| MatchPattern::parse(pattern).ok())
+            .any(|pattern| pattern.matches(url))
+    }
+}
+
+// Persisted between runs in extensions.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryState {
+    disabled: Vec<String>,
+    unpacked: Vec<PathBuf>,
+}
+
+pub struct ExtensionRegistry {
+    extensions_dir: PathBuf,
+    state_path: PathBuf,
+    extensions: Vec<InstalledExtension>,
+    load_errors: Vec<String>,
+    // Bumped on every change to the set of enabled extensions, so what's built from them can be rebuilt
+    revision: u64,
+}
+
+static SHARED_REGISTRY: OnceLock<Arc<Mutex<ExtensionRegistry>>> = OnceLock::new();
+
+impl ExtensionRegistry {
+    pub fn new(data_dir: &Path) -> Self {
+        let extensions_dir = data_dir.join("extensions");
+        let _ = std::fs::create_dir_all(&extensions_dir);
+
+        let mut registry = Self {
+            extensions_dir,
+            state_path: data_dir.join("extensions.json"),
+            extensions: Vec::new(),
+            load_errors: Vec::new(),
+            revision: 0,
+        };
+        registry.reload();
+        registry
+    }
+
+    /// Process-wide registry shared by the extensions page and the browser
+    pub fn shared() -> Arc<Mutex<ExtensionRegistry>> {
+        SHARED_REGISTRY
+            .get_or_init(|| Arc::new(Mutex::new(ExtensionRegistry::new(&crate::storage::data_dir()))))
+            .clone()
+    }
+
+    /// Stable id derived from the extension's directory
+    pub fn extension_id(path: &Path) -> String {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let digest = Sha256::digest(canonical.to_string_lossy().as_bytes());
+        digest.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Rescan the extensions directory and all registered unpacked paths
+    pub fn reload(&mut self) {
+        let state = self.read_state();
+        self.extensions.clear();
+        self.load_errors.clear();
+
+        let mut dirs: Vec<(PathBuf, bool)> = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(&self.extensions_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.join(MANIFEST_FILE).exists() {
+                    dirs.push((path, false));
+                }
+            }
+        }
+        dirs.extend(state.unpacked.iter().cloned().map(|p| (p, true)));
+
+        for (path, unpacked) in dirs {
+            match ExtensionManifest::load(&path) {
+                Ok(manifest) => {
+                    let id = Self::extension_id(&path);
+                    let enabled = !state.disabled.contains(&id);
+                    self.extensions.push(InstalledExtension { id, manifest, path, enabled, unpacked });
+                }
+                Err(e) => {
+                    log::warn!("Failed to load extension at {}: {}", path.display(), e);
+                    self.load_errors.push(format!("{}: {}", path.display(), e));
+                }
+            }
+        }
+
+        self.extensions.sort_by_key(|extension| extension.manifest.name.to_lowercase());
+        self.revision += 1;
+    }
+
+    /// Register a local directory as an unpacked (developer) extension
+    pub fn load_unpacked(&mut self, dir: &Path) -> Result<String> {
+        if !dir.is_dir() {
+            return Err(anyhow!("{} is not a directory", dir.display()));
+        }
+        let manifest = ExtensionManifest::load(dir)?;
+        let id = Self::extension_id(dir);
+        if self.extensions.iter().any(|e| e.id == id) {
+            return Err(anyhow!("Extension '{}' is already loaded", manifest.name));
+        }
+
+        let mut state = self.read_state();
+        state.unpacked.push(dir.to_path_buf());
+        self.write_state(&state)?;
+
+        log::info!("Loaded unpacked extension '{}' from {}", manifest.name, dir.display());
+        self.reload();
+        Ok(id)
+    }
+
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> Result<()> {
+        let extension = self.extensions.iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| anyhow!("Unknown extension: {}", id))?;
+        extension.enabled = enabled;
+        self.revision += 1;
+
+        let mut state = self.read_state();
+        state.disabled.retain(|d| d != id);
+        if !enabled {
+            state.disabled.push(id.to_string());
+        }
+        self.write_state(&state)
+    }
+
+    /// Remove an extension. An unpacked one is only forgotten; an installed one is deleted from the extensions directory
+    pub fn uninstall(&mut self, id: &str) -> Result<()> {
+        let extension = self.get(id).cloned().ok_or_else(|| anyhow!("Unknown extension: {}", id))?;
+        if !extension.unpacked {
+            std::fs::remove_dir_all(&extension.path)
+                .map_err(|e| anyhow!("Failed to remove {}: {}", extension.path.display(), e))?;
+        }
+
+        let mut state = self.read_state();
+        state.disabled.retain(|d| d != id);
+        state.unpacked.retain(|path| Self::extension_id(path) != id);
+        self.write_state(&state)?;
+
+        log::info!("Uninstalled extension '{}'", extension.manifest.name);
+        self.reload();
+        Ok(())
+    }
+
+    /// Changes with every install, uninstall, enable, disable and reload
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn list(&self) -> &[InstalledExtension] {
+        &self.extensions
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &InstalledExtension> {
+        self.extensions.iter().filter(|e| e.enabled)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&InstalledExtension> {
+        self.extensions.iter().find(|e| e.id == id)
+    }
+
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+
+    pub fn extensions_dir(&self) -> &Path {
+        &self.extensions_dir
+    }
+
+    fn read_state(&self) -> RegistryState {
+        std::fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_state(&self, state: &RegistryState) -> Result<()> {
+        let content = serde_json::to_string_pretty(state)?;
+        std::fs::write(&self.state_path, content)
+            .map_err(|e| anyhow!("Failed to save extension state: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_lifecycle() {
+        let data_dir = std::env::temp_dir().join(format!("neon-extensions-{}", std::process::id()));
+        let extension_dir = data_dir.join("extensions").join("reader");
+        std::fs::create_dir_all(&extension_dir).unwrap();
+        std::fs::write(
+            extension_dir.join(MANIFEST_FILE),
+            r#"{"name": "Reader", "version": "1.0", "host_permissions": ["https://*.example.com/*"]}"#,
+        ).unwrap();
+
+        let mut registry = ExtensionRegistry::new(&data_dir);
+        let id = registry.list()[0].id.clone();
+        assert!(registry.list()[0].can_access("https://news.example.com/today"));
+        assert!(!registry.list()[0].can_access("https://example.org/"));
+
+        let revision = registry.revision();
+        registry.set_enabled(&id, false).unwrap();
+        assert!(registry.revision() > revision);
+        assert_eq!(ExtensionRegistry::new(&data_dir).enabled().count(), 0);
+
+        registry.uninstall(&id).unwrap();
+        assert!(registry.list().is_empty());
+        assert!(!extension_dir.exists());
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+}
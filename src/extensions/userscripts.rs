@@ -0,0 +1,303 @@
+// Userscripts: single files with a Greasemonkey `// ==UserScript==` metadata block, kept in the profile and run as content scripts
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::extensions::manifest::RunAt;
+use crate::extensions::match_pattern::MatchPattern;
+
+const SCRIPT_EXTENSION: &str = ".user.js";
+
+/// Starting point for a script written in the editor
+pub const NEW_SCRIPT_TEMPLATE: &str = "// ==UserScript==
+// @name        New script
+// @match       https://example.com/*
+// @run-at      document-end
+// ==/UserScript==
+
+";
+
+/// What a script's metadata block says about it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserScriptMeta {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub version: Option<String>,
+    pub description: String,
+    pub matches: Vec<String>,
+    pub exclude_matches: Vec<String>,
+    /// Greasemonkey globs over the whole URL, where `*` matches anything
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+    pub run_at: RunAt,
+}
+
+impl UserScriptMeta {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut lines = source.lines().map(str::trim);
+        if !lines.any(|line| line.starts_with("//") && line.trim_start_matches('/').trim() == "==UserScript==") {
+            return Err(anyhow!("Missing the // ==UserScript== metadata block"));
+        }
+        let mut meta = UserScriptMeta::default();
+        let mut closed = false;
+        for line in lines {
+            let Some(comment) = line.strip_prefix("//") else {
+                // Code before the closing line ends the block too
+                if line.is_empty() {
+                    continue;
+                }
+                break;
+            };
+            let comment = comment.trim();
+            if comment == "==/UserScript==" {
+                closed = true;
+                break;
+            }
+            let Some(entry) = comment.strip_prefix('@') else { continue };
+            let (key, value) = entry.split_once(char::is_whitespace).unwrap_or((entry, ""));
+            let value = value.trim().to_string();
+            match key {
+                "name" => meta.name = value,
+                "namespace" => meta.namespace = Some(value),
+                "version" => meta.version = Some(value),
+                "description" => meta.description = value,
+                "match" => meta.matches.push(value),
+                "exclude-match" => meta.exclude_matches.push(value),
+                "include" => meta.includes.push(value),
+                "exclude" => meta.excludes.push(value),
+                "run-at" => {
+                    meta.run_at = match value.as_str() {
+                        "document-start" => RunAt::DocumentStart,
+                        "document-end" | "document-idle" => RunAt::DocumentEnd,
+                        _ => return Err(anyhow!("Unknown @run-at '{}'", value)),
+                    }
+                }
+                // Other keys (@grant, @icon, ...) don't change how the script runs here
+                _ => {}
+            }
+        }
+
+        if !closed {
+            return Err(anyhow!("The metadata block isn't closed with // ==/UserScript=="));
+        }
+        if meta.name.is_empty() {
+            return Err(anyhow!("The script needs an @name"));
+        }
+        if meta.matches.is_empty() && meta.includes.is_empty() {
+            return Err(anyhow!("The script needs an @match or @include saying where it runs"));
+        }
+        for pattern in meta.matches.iter().chain(meta.exclude_matches.iter()) {
+            MatchPattern::parse(pattern)?;
+        }
+        Ok(meta)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UserScript {
+    /// File name in the userscripts directory, which identifies the script
+    pub file: String,
+    pub meta: UserScriptMeta,
+    pub source: String,
+    pub enabled: bool,
+}
+
+// Persisted between runs in userscripts.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreState {
+    disabled: Vec<String>,
+}
+
+pub struct UserScriptStore {
+    dir: PathBuf,
+    state_path: PathBuf,
+    scripts: Vec<UserScript>,
+    load_errors: Vec<String>,
+    // Bumped on every change, like the extension registry's
+    revision: u64,
+}
+
+static SHARED_STORE: OnceLock<Arc<Mutex<UserScriptStore>>> = OnceLock::new();
+
+impl UserScriptStore {
+    pub fn new(data_dir: &Path) -> Self {
+        let dir = data_dir.join("userscripts");
+        let _ = std::fs::create_dir_all(&dir);
+        let mut store = Self {
+            dir,
+            state_path: data_dir.join("userscripts.json"),
+            scripts: Vec::new(),
+            load_errors: Vec::new(),
+            revision: 0,
+        };
+        store.reload();
+        store
+    }
+
+    /// Process-wide store shared by neon://userscripts and page loads
+    pub fn shared() -> Arc<Mutex<UserScriptStore>> {
+        SHARED_STORE
+            .get_or_init(|| Arc::new(Mutex::new(UserScriptStore::new(&crate::storage::data_dir()))))
+            .clone()
+    }
+
+    /// Read every script in the userscripts directory again
+    pub fn reload(&mut self) {
+        let state = self.read_state();
+        self.scripts.clear();
+        self.load_errors.clear();
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| path.to_string_lossy().ends_with(SCRIPT_EXTENSION));
+        files.sort();
+
+        for path in files {
+            let file = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|source| UserScriptMeta::parse(&source).map(|meta| (meta, source)));
+            match loaded {
+                Ok((meta, source)) => {
+                    let enabled = !state.disabled.contains(&file);
+                    self.scripts.push(UserScript { file, meta, source, enabled });
+                }
+                Err(e) => self.load_errors.push(format!("{}: {}", file, e)),
+            }
+        }
+        self.revision += 1;
+    }
+
+    /// Save `source`, replacing the script in `file` or adding a new one named after the script. Returns its file name
+    pub fn save(&mut self, file: Option<&str>, source: &str) -> Result<String> {
+        let meta = UserScriptMeta::parse(source)?;
+        let file = match file {
+            Some(file) => file.to_string(),
+            None => self.unused_file_name(&meta.name),
+        };
+        std::fs::write(self.dir.join(&file), source)
+            .map_err(|e| anyhow!("Failed to save {}: {}", file, e))?;
+        self.reload();
+        Ok(file)
+    }
+
+    pub fn delete(&mut self, file: &str) -> Result<()> {
+        std::fs::remove_file(self.dir.join(file))
+            .map_err(|e| anyhow!("Failed to delete {}: {}", file, e))?;
+        let mut state = self.read_state();
+        state.disabled.retain(|d| d != file);
+        self.write_state(&state)?;
+        self.reload();
+        Ok(())
+    }
+
+    pub fn set_enabled(&mut self, file: &str, enabled: bool) -> Result<()> {
+        let script = self.scripts.iter_mut()
+            .find(|script| script.file == file)
+            .ok_or_else(|| anyhow!("Unknown userscript: {}", file))?;
+        script.enabled = enabled;
+        self.revision += 1;
+
+        let mut state = self.read_state();
+        state.disabled.retain(|d| d != file);
+        if !enabled {
+            state.disabled.push(file.to_string());
+        }
+        self.write_state(&state)
+    }
+
+    pub fn list(&self) -> &[UserScript] {
+        &self.scripts
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &UserScript> {
+        self.scripts.iter().filter(|script| script.enabled)
+    }
+
+    pub fn get(&self, file: &str) -> Option<&UserScript> {
+        self.scripts.iter().find(|script| script.file == file)
+    }
+
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // `my-script.user.js` from "My Script", numbered if that's taken
+    fn unused_file_name(&self, name: &str) -> String {
+        let slug: String = name.to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = if slug.is_empty() { "script".to_string() } else { slug };
+        (1..)
+            .map(|n| if n == 1 { format!("{}{}", slug, SCRIPT_EXTENSION) } else { format!("{}-{}{}", slug, n, SCRIPT_EXTENSION) })
+            .find(|file| !self.dir.join(file).exists())
+            .unwrap_or_default()
+    }
+
+    fn read_state(&self) -> StoreState {
+        std::fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_state(&self, state: &StoreState) -> Result<()> {
+        let content = serde_json::to_string_pretty(state)?;
+        std::fs::write(&self.state_path, content)
+            .map_err(|e| anyhow!("Failed to save userscript state: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metadata() {
+        let meta = UserScriptMeta::parse("// ==UserScript==
+// @name         Wide Docs
+// @namespace    neon
+// @match        https://docs.example.com/*
+// @include      *://*.example.org/*
+// @grant        none
+// @run-at       document-start
+// ==/UserScript==
+document.getElementById('sidebar').remove()").unwrap();
+        assert_eq!(meta.name, "Wide Docs");
+        assert_eq!(meta.matches, ["https://docs.example.com/*"]);
+        assert_eq!(meta.includes, ["*://*.example.org/*"]);
+        assert_eq!(meta.run_at, RunAt::DocumentStart);
+
+        assert!(UserScriptMeta::parse("// @name No block").is_err());
+        assert!(UserScriptMeta::parse("// ==UserScript==\n// @name Nowhere\n// ==/UserScript==").is_err());
+    }
+
+    #[test]
+    fn test_store_saves_and_names_scripts() {
+        let data_dir = std::env::temp_dir().join(format!("neon-userscripts-{}", std::process::id()));
+        let mut store = UserScriptStore::new(&data_dir);
+        let source = NEW_SCRIPT_TEMPLATE.replace("New script", "Hide Ads!");
+        let first = store.save(None, &source).unwrap();
+        let second = store.save(None, &source).unwrap();
+        assert_eq!((first.as_str(), second.as_str()), ("hide-ads.user.js", "hide-ads-2.user.js"));
+
+        store.set_enabled(&first, false).unwrap();
+        assert_eq!(UserScriptStore::new(&data_dir).enabled().count(), 1);
+        store.delete(&second).unwrap();
+        assert_eq!(store.list().len(), 1);
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+}
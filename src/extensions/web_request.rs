@@ -0,0 +1,370 @@
+// Declarative request rules from extensions: block, allow, redirect or change the headers of requests
+// picked by URL filter and resource type, checked by the manual client before each request is sent
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::extensions::registry::{ExtensionRegistry, InstalledExtension};
+
+/// What a request is for, which rules can be limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    MainFrame,
+    SubFrame,
+    Stylesheet,
+    Script,
+    Image,
+    Font,
+    Media,
+    #[default]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderOperation {
+    Set,
+    Append,
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeaderChange {
+    pub header: String,
+    pub operation: HeaderOperation,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RuleAction {
+    Block,
+    /// Exempt matching requests from lower-priority rules
+    Allow,
+    Redirect { url: String },
+    #[serde(rename_all = "camelCase")]
+    ModifyHeaders { request_headers: Vec<HeaderChange> },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RuleCondition {
+    /// `||` anchors to a domain, `|` to the start or end of the URL, `*` is a wildcard and `^` a separator
+    pub url_filter: Option<String>,
+    /// Empty for every type
+    pub resource_types: Vec<ResourceType>,
+    pub excluded_resource_types: Vec<ResourceType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestRule {
+    pub id: u32,
+    #[serde(default = "default_priority")]
+    pub priority: u32,
+    pub action: RuleAction,
+    #[serde(default)]
+    pub condition: RuleCondition,
+}
+
+fn default_priority() -> u32 {
+    1
+}
+
+impl RequestRule {
+    /// Read a rules file, a JSON array of rules
+    pub fn parse_list(content: &str) -> Result<Vec<RequestRule>> {
+        let rules: Vec<RequestRule> = serde_json::from_str(content).map_err(|e| anyhow!("Invalid rules: {}", e))?;
+        for rule in &rules {
+            if let RuleAction::Redirect { url } = &rule.action {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    return Err(anyhow!("Rule {} redirects to '{}', which isn't an http(s) URL", rule.id, url));
+                }
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// What the rules decided for a request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleVerdict {
+    /// Name of the extension whose rule blocked the request
+    pub blocked_by: Option<String>,
+    pub redirect: Option<String>,
+    pub headers: Vec<HeaderChange>,
+}
+
+// A `urlFilter` split into its parts; matching is case-insensitive
+#[derive(Debug, Clone, PartialEq)]
+struct UrlFilter {
+    domain_anchor: bool,
+    start_anchor: bool,
+    end_anchor: bool,
+    pattern: Vec<char>,
+}
+
+impl UrlFilter {
+    fn parse(filter: &str) -> Self {
+        let filter = filter.to_lowercase();
+        let (domain_anchor, rest) = match filter.strip_prefix("||") {
+            Some(rest) => (true, rest),
+            None => (false, filter.as_str()),
+        };
+        let (start_anchor, rest) = match rest.strip_prefix('|') {
+            Some(rest) if !domain_anchor => (true, rest),
+            _ => (false, rest),
+        };
+        let (end_anchor, rest) = match rest.strip_suffix('|') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        Self { domain_anchor, start_anchor, end_anchor, pattern: rest.chars().collect() }
+    }
+
+    // Domain a `||` filter is anchored to, which rules are indexed by
+    fn domain(&self) -> Option<String> {
+        self.domain_anchor.then(|| self.pattern.iter().take_while(|c| !matches!(c, '^' | '/' | '*' | ':')).collect())
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        let text: Vec<char> = url.to_lowercase().chars().collect();
+        let starts: Vec<usize> = if self.domain_anchor {
+            // The start of the host, or just after any dot in it
+            let Some(host_start) = url.find("://").map(|i| i + 3) else { return false };
+            let host_end = url[host_start..].find(['/', '?', '#', ':']).map_or(url.len(), |i| host_start + i);
+            std::iter::once(host_start)
+                .chain(url[host_start..host_end].match_indices('.').map(|(i, _)| host_start + i + 1))
+                .map(|byte| url[..byte].chars().count())
+                .collect()
+        } else if self.start_anchor {
+            vec![0]
+        } else {
+            (0..=text.len()).collect()
+        };
+        starts.into_iter().any(|start| self.matches_at(&self.pattern, &text[start..]))
+    }
+
+    fn matches_at(&self, pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => !self.end_anchor || text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| self.matches_at(rest, &text[skip..])),
+            Some(('^', rest)) => match text.split_first() {
+                None => rest.is_empty(),
+                Some((c, after)) => !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '%')) && self.matches_at(rest, after),
+            },
+            Some((p, rest)) => text.first() == Some(p) && self.matches_at(rest, &text[1..]),
+        }
+    }
+}
+
+struct CompiledRule {
+    rule: RequestRule,
+    filter: Option<UrlFilter>,
+    // Index into the rule set's extensions
+    extension: usize,
+}
+
+impl CompiledRule {
+    fn applies(&self, url: &str, resource_type: ResourceType) -> bool {
+        let condition = &self.rule.condition;
+        (condition.resource_types.is_empty() || condition.resource_types.contains(&resource_type))
+            && !condition.excluded_resource_types.contains(&resource_type)
+            && self.filter.as_ref().is_none_or(|filter| filter.matches(url))
+    }
+}
+
+/// The rules of every enabled extension, indexed by the domain they're anchored to
+#[derive(Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+    extensions: Vec<InstalledExtension>,
+    by_domain: HashMap<String, Vec<usize>>,
+    // Rules without a domain anchor, checked for every request
+    unanchored: Vec<usize>,
+}
+
+impl RuleSet {
+    pub fn add(&mut self, extension: InstalledExtension, rules: Vec<RequestRule>) {
+        self.extensions.push(extension);
+        let extension = self.extensions.len() - 1;
+        for rule in rules {
+            let filter = rule.condition.url_filter.as_deref().map(UrlFilter::parse);
+            let index = self.rules.len();
+            match filter.as_ref().and_then(UrlFilter::domain).filter(|domain| !domain.is_empty()) {
+                Some(domain) => self.by_domain.entry(domain).or_default().push(index),
+                None => self.unanchored.push(index),
+            }
+            self.rules.push(CompiledRule { rule, filter, extension });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Highest priority wins; at equal priority allow beats block, which beats redirect. Redirects and header
+    /// changes only apply where the extension has host access
+    pub fn evaluate(&self, url: &str, resource_type: ResourceType) -> RuleVerdict {
+        let host = url::Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_lowercase)).unwrap_or_default();
+        let mut candidates: Vec<usize> = self.unanchored.clone();
+        let mut suffix = host.as_str();
+        loop {
+            candidates.extend(self.by_domain.get(suffix).into_iter().flatten());
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => break,
+            }
+        }
+
+        let has_access = |rule: &CompiledRule| self.extensions[rule.extension].can_access(url);
+        let matched: Vec<&CompiledRule> = candidates.iter()
+            .map(|index| &self.rules[*index])
+            .filter(|rule| rule.applies(url, resource_type))
+            .collect();
+        let rank = |action: &RuleAction| match action {
+            RuleAction::Allow => 3,
+            RuleAction::Block => 2,
+            RuleAction::Redirect { .. } => 1,
+            RuleAction::ModifyHeaders { .. } => 0,
+        };
+        let decisive = matched.iter()
+            .filter(|rule| match &rule.rule.action {
+                RuleAction::ModifyHeaders { .. } => false,
+                RuleAction::Redirect { url: target } => target != url && has_access(rule),
+                _ => true,
+            })
+            .max_by_key(|rule| (rule.rule.priority, rank(&rule.rule.action)));
+
+        let mut verdict = RuleVerdict::default();
+        let floor = match decisive.map(|rule| (&rule.rule.action, rule)) {
+            Some((RuleAction::Block, rule)) => {
+                verdict.blocked_by = Some(self.extensions[rule.extension].manifest.name.clone());
+                return verdict;
+            }
+            Some((RuleAction::Redirect { url }, _)) => {
+                verdict.redirect = Some(url.clone());
+                return verdict;
+            }
+            // Header changes of a higher priority than the allow rule still apply
+            Some((_, rule)) => Some(rule.rule.priority),
+            None => None,
+        };
+        let mut modifying: Vec<&&CompiledRule> = matched.iter()
+            .filter(|rule| matches!(rule.rule.action, RuleAction::ModifyHeaders { .. }))
+            .filter(|rule| floor.is_none_or(|floor| rule.rule.priority > floor) && has_access(rule))
+            .collect();
+        modifying.sort_by_key(|rule| std::cmp::Reverse(rule.rule.priority));
+        for rule in modifying {
+            if let RuleAction::ModifyHeaders { request_headers } = &rule.rule.action {
+                verdict.headers.extend(request_headers.iter().cloned());
+            }
+        }
+        verdict
+    }
+}
+
+/// Make `changes` to a request's headers, matching names case-insensitively
+pub fn apply_header_changes(headers: &mut Vec<(String, String)>, changes: &[HeaderChange]) {
+    for change in changes {
+        let value = change.value.clone().unwrap_or_default();
+        match change.operation {
+            HeaderOperation::Remove => headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&change.header)),
+            HeaderOperation::Set => {
+                headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&change.header));
+                headers.push((change.header.clone(), value));
+            }
+            HeaderOperation::Append => match headers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(&change.header)) {
+                Some((_, existing)) => {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+                None => headers.push((change.header.clone(), value)),
+            },
+        }
+    }
+}
+
+// Rules of the enabled extensions and the registry revision they were read at
+static RULES: OnceLock<Mutex<(Option<u64>, Arc<RuleSet>)>> = OnceLock::new();
+
+/// The rules of every enabled extension allowed to block requests, read again whenever the registry changes
+pub fn rules() -> Arc<RuleSet> {
+    let registry = ExtensionRegistry::shared();
+    let Ok(registry) = registry.lock() else {
+        return Arc::default();
+    };
+    let Ok(mut rules) = RULES.get_or_init(|| Mutex::new((None, Arc::default()))).lock() else {
+        return Arc::default();
+    };
+    if rules.0 != Some(registry.revision()) {
+        let mut set = RuleSet::default();
+        for extension in registry.enabled() {
+            let Some(path) = extension.rules_path() else { continue };
+            match std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|content| RequestRule::parse_list(&content)) {
+                Ok(list) => set.add(extension.clone(), list),
+                Err(e) => log::warn!("Cannot load request rules of '{}': {}", extension.manifest.name, e),
+            }
+        }
+        *rules = (Some(registry.revision()), Arc::new(set));
+    }
+    rules.1.clone()
+}
+
+/// What the enabled extensions' rules decide for a request to `url`
+pub fn evaluate(url: &str, resource_type: ResourceType) -> RuleVerdict {
+    let rules = rules();
+    if rules.is_empty() {
+        return RuleVerdict::default();
+    }
+    rules.evaluate(url, resource_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::ExtensionManifest;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_url_filters() {
+        let filter = UrlFilter::parse("||ads.example.com^");
+        assert!(filter.matches("https://ads.example.com/banner.js"));
+        assert!(filter.matches("https://cdn.ads.example.com:8080/x"));
+        assert!(!filter.matches("https://badads.example.com/"));
+        assert!(!filter.matches("https://ads.example.com.evil.org/"));
+        assert_eq!(filter.domain().as_deref(), Some("ads.example.com"));
+
+        assert!(UrlFilter::parse("|https://*/tracker.js|").matches("https://site.org/tracker.js"));
+        assert!(!UrlFilter::parse("|https://*/tracker.js|").matches("https://site.org/tracker.json"));
+        assert!(UrlFilter::parse("/pixel?").matches("http://a.org/img/PIXEL?id=1"));
+    }
+
+    #[test]
+    fn test_rule_priority_and_access() {
+        let manifest = ExtensionManifest::parse(r#"{"name": "Blocker", "version": "1", "permissions": ["webRequestBlocking"],
+            "host_permissions": ["*://*.example.com/*"]}"#).unwrap();
+        let extension = InstalledExtension { id: "b".to_string(), manifest, path: PathBuf::new(), enabled: true, unpacked: true };
+        let rules = RequestRule::parse_list(r#"[
+            {"id": 1, "action": {"type": "block"}, "condition": {"urlFilter": "||example.com/ads/", "resourceTypes": ["script"]}},
+            {"id": 2, "priority": 2, "action": {"type": "allow"}, "condition": {"urlFilter": "||example.com/ads/ok.js"}},
+            {"id": 3, "action": {"type": "redirect", "url": "https://example.com/blank.js"}, "condition": {"urlFilter": "/old.js|"}},
+            {"id": 4, "priority": 3, "action": {"type": "modifyHeaders", "requestHeaders": [{"header": "DNT", "operation": "set", "value": "1"}]}}
+        ]"#).unwrap();
+        let mut set = RuleSet::default();
+        set.add(extension, rules);
+
+        assert_eq!(set.evaluate("https://www.example.com/ads/a.js", ResourceType::Script).blocked_by.as_deref(), Some("Blocker"));
+        assert_eq!(set.evaluate("https://www.example.com/ads/a.js", ResourceType::Image).blocked_by, None);
+        let allowed = set.evaluate("https://example.com/ads/ok.js", ResourceType::Script);
+        assert_eq!((allowed.blocked_by, allowed.headers.len()), (None, 1));
+        assert_eq!(set.evaluate("https://example.com/old.js", ResourceType::Script).redirect.as_deref(), Some("https://example.com/blank.js"));
+        // Without host access only blocking applies
+        assert_eq!(set.evaluate("https://other.org/old.js", ResourceType::Script), RuleVerdict::default());
+
+        let mut headers = vec![("dnt".to_string(), "0".to_string())];
+        apply_header_changes(&mut headers, &allowed.headers);
+        assert_eq!(headers, [("DNT".to_string(), "1".to_string())]);
+    }
+}
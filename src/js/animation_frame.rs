@@ -0,0 +1,166 @@
+// requestAnimationFrame: callbacks a script queued for the next frame of the page's animation clock
+
+/// A callback passed to requestAnimationFrame: the name of its timestamp parameter and its body
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameCallback {
+    /// Set for named function expressions, which can request themselves again by name
+    pub name: Option<String>,
+    pub param: Option<String>,
+    pub body: String,
+}
+
+impl FrameCallback {
+    /// A function or arrow function literal, e.g. `function (t) { ... }` or `t => step(t)`
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let mut name = None;
+        let (params, body) = if let Some(rest) = source.strip_prefix("function") {
+            let rest = rest.trim_start();
+            let open = rest.find('(')?;
+            name = Some(rest[..open].trim().to_string()).filter(|name| !name.is_empty());
+            let (params, body) = rest[open + 1..].split_once(')')?;
+            (params, body.trim())
+        } else {
+            let (params, body) = source.split_once("=>")?;
+            (params.trim().trim_start_matches('(').trim_end_matches(')'), body.trim())
+        };
+        let body = match body.strip_prefix('{') {
+            Some(block) => block.strip_suffix('}')?.trim(),
+            None => body,
+        };
+        let param = params.split(',').next().map(str::trim).filter(|p| !p.is_empty()).map(str::to_string);
+        Some(Self { name, param, body: body.to_string() })
+    }
+}
+
+/// Callbacks waiting for the next frame, with the ids requestAnimationFrame handed out for them
+#[derive(Debug, Default)]
+pub struct AnimationFrames {
+    next_id: u32,
+    pending: Vec<(u32, FrameCallback)>,
+}
+
+impl AnimationFrames {
+    pub fn request(&mut self, callback: FrameCallback) -> u32 {
+        self.next_id += 1;
+        self.pending.push((self.next_id, callback));
+        self.next_id
+    }
+
+    pub fn cancel(&mut self, id: u32) {
+        self.pending.retain(|(pending, _)| *pending != id);
+    }
+
+    /// The callbacks for this frame; ones they request in turn wait for the next
+    pub fn take(&mut self) -> Vec<FrameCallback> {
+        std::mem::take(&mut self.pending).into_iter().map(|(_, callback)| callback).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// The argument list of the first call to `function` in `code`, and where the call ends
+pub fn call_arguments<'a>(code: &'a str, function: &str) -> Option<(&'a str, usize)> {
+    let start = code.find(function)?;
+    let rest = &code[start + function.len()..];
+    let open = start + function.len() + rest.find(|c: char| !c.is_whitespace()).filter(|i| rest[*i..].starts_with('('))?;
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in code[open..].char_indices() {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'' | '`', None) => quote = Some(c),
+            ('(' | '{' | '[', None) => depth += 1,
+            (')' | '}' | ']', None) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&code[open + 1..open + i], open + i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `body` split into statements at semicolons and line breaks outside brackets, strings and regex literals
+pub fn split_statements(body: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        if quote.is_some() && std::mem::take(&mut escaped) {
+            continue;
+        }
+        match (c, quote) {
+            ('\\', Some(_)) => escaped = true,
+            ('\n', Some('/')) => quote = None,
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'' | '`', None) => quote = Some(c),
+            ('/', None) if starts_regex(&body[..i]) => quote = Some('/'),
+            ('(' | '{' | '[', None) => depth += 1,
+            (')' | '}' | ']', None) => depth -= 1,
+            (';' | '\n', None) if depth == 0 => {
+                statements.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(body[start..].trim());
+    statements.retain(|s| !s.is_empty());
+    statements
+}
+
+// Whether a `/` after `before` starts a regex literal rather than dividing, from what precedes it
+fn starts_regex(before: &str) -> bool {
+    let before = before.trim_end();
+    match before.chars().last() {
+        None => true,
+        Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => before.ends_with("return") || before.ends_with("typeof"),
+        Some(c) => !matches!(c, ')' | ']' | '}' | '"' | '\'' | '`' | '/'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_callbacks() {
+        let callback = FrameCallback::parse("function step(ts) { x = ts; console.log('tick') }").unwrap();
+        assert_eq!(callback, FrameCallback {
+            name: Some("step".to_string()),
+            param: Some("ts".to_string()),
+            body: "x = ts; console.log('tick')".to_string(),
+        });
+        let arrow = FrameCallback::parse("() => console.log('a')").unwrap();
+        assert_eq!(arrow.param, None);
+        assert_eq!(arrow.body, "console.log('a')");
+        assert!(FrameCallback::parse("step").is_none());
+
+        let code = "var id = requestAnimationFrame(t => { f(t) }); other()";
+        let (args, end) = call_arguments(code, "requestAnimationFrame").unwrap();
+        assert_eq!(args, "t => { f(t) }");
+        assert_eq!(&code[end..], "; other()");
+        assert_eq!(split_statements("a = 1; b('x;y')\nc({ d: 1; })"), vec!["a = 1", "b('x;y')", "c({ d: 1; })"]);
+        assert_eq!(split_statements("ok = /[;'\\/]/.test(s); n = a / 2; t = 'it\\'s'"), vec!["ok = /[;'\\/]/.test(s)", "n = a / 2", "t = 'it\\'s'"]);
+    }
+
+    #[test]
+    fn test_request_and_cancel() {
+        let mut frames = AnimationFrames::default();
+        let callback = FrameCallback::parse("t => t").unwrap();
+        let first = frames.request(callback.clone());
+        frames.request(callback);
+        frames.cancel(first);
+        assert_eq!(frames.take().len(), 1);
+        assert!(frames.is_empty());
+    }
+}
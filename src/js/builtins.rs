@@ -0,0 +1,1917 @@
+// Expressions over the standard built-ins nearly every script relies on: JSON, Math, Date, and the String,
+// Number, Array and Object methods, evaluated against the engine's variables and host objects
+use std::collections::HashMap;
+use std::rc::Rc;
+use anyhow::{anyhow, bail, Result};
+use crate::js::JSValue;
+use crate::js::regexp;
+
+// Deepest nesting of expressions and callbacks before evaluation gives up, so hostile input can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+const PUNCTUATORS: &[&str] = &[
+    "===", "!==", "...", "=>", "==", "!=", "<=", ">=", "&&", "||", "??", "?.",
+    "(", ")", "[", "]", "{", "}", ",", ".", ":", "?", ";", "+", "-", "*", "/", "%", "!", "<", ">", "=",
+];
+
+// Globals that are objects of static methods rather than values
+const NAMESPACES: &[&str] = &["JSON", "Math", "Object", "Array", "Number", "String", "Date", "RegExp", "console"];
+
+const GLOBAL_FUNCTIONS: &[&str] = &[
+    "parseInt", "parseFloat", "isNaN", "isFinite", "Number", "String", "Boolean",
+    "encodeURIComponent", "decodeURIComponent", "encodeURI", "decodeURI",
+];
+
+/// What the evaluator asks the engine for
+pub trait Host {
+    /// A variable, or a dotted path the engine provides such as `location.pathname`; None when it doesn't exist
+    fn lookup(&self, path: &str) -> Option<JSValue>;
+    /// Call a host function such as `console.log` by its dotted path; None when there's no such function
+    fn call(&self, path: &str, args: &[JSValue]) -> Option<JSValue>;
+    /// `Date.prototype.<method>()` for the instant `time`, in milliseconds since the epoch, which is NaN for
+    /// invalid dates
+    fn date_method(&self, time: f64, method: &str) -> Result<JSValue>;
+    /// The error that interrupts evaluation once the script has run too long
+    fn check_running_time(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Evaluate the expression `source`. Errors are what the script would throw, or syntax this evaluator doesn't
+/// handle, such as statements and assignments.
+pub fn evaluate(source: &str, host: &dyn Host) -> Result<JSValue> {
+    let expression = parse(source.trim().trim_end_matches(';'))?;
+    Evaluator::new(host).eval(&expression)
+}
+
+/// Call one of the array methods that change the array in place, with the source of its argument list, as in
+/// `items.push(4)`; returns what the call returns
+pub fn call_mutating_method(array: &mut Vec<JSValue>, method: &str, args: &str, host: &dyn Host) -> Result<JSValue> {
+    let args = if args.trim().is_empty() { Vec::new() } else { parse_arguments(args)? };
+    let mut evaluator = Evaluator::new(host);
+    let args = evaluator.arguments(&args)?;
+    evaluator.mutate_array(array, method, &args)
+}
+
+/// Whether `source` is an expression this evaluator reads, whether or not evaluating it would throw
+pub fn is_expression(source: &str) -> bool {
+    parse(source.trim().trim_end_matches(';')).is_ok()
+}
+
+/// `JSON.stringify(value)`; None for undefined, which has no JSON form
+pub fn to_json(value: &JSValue) -> Option<String> {
+    json_value(value).map(|json| json.to_string())
+}
+
+/// `JSON.parse(text)`
+pub fn from_json(text: &str) -> Option<JSValue> {
+    serde_json::from_str(text).ok().map(|json| from_json_value(&json))
+}
+
+/// A number as JavaScript prints it
+pub fn number_to_string(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if n == 0.0 {
+        "0".to_string()
+    } else {
+        n.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Template(String),
+    Ident(String),
+    Punct(&'static str),
+    /// A regex literal's source and flags
+    Regex(String, String),
+}
+
+// Whether a `/` after `previous` starts a regex literal rather than dividing
+fn starts_regex(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(Token::Punct(punct)) => !matches!(*punct, ")" | "]" | "}"),
+        Some(Token::Ident(keyword)) => matches!(keyword.as_str(), "return" | "typeof" | "in" | "case"),
+        _ => false,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            if c == '0' && matches!(chars.get(i + 1), Some('x' | 'X')) {
+                i += 2;
+                while chars.get(i).is_some_and(char::is_ascii_hexdigit) {
+                    i += 1;
+                }
+                let digits: String = chars[start + 2..i].iter().collect();
+                tokens.push(Token::Number(i64::from_str_radix(&digits, 16)? as f64));
+                continue;
+            }
+            while chars.get(i).is_some_and(|c| c.is_ascii_digit() || *c == '.' || *c == '_') {
+                i += 1;
+            }
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                i += 1;
+                if matches!(chars.get(i), Some('+' | '-')) {
+                    i += 1;
+                }
+                while chars.get(i).is_some_and(char::is_ascii_digit) {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            tokens.push(Token::Number(text.parse()?));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while chars.get(i).is_some_and(|c| c.is_alphanumeric() || *c == '_' || *c == '$') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("SyntaxError: Invalid or unexpected token"),
+                    Some(q) if *q == c => break,
+                    Some('\\') => {
+                        i += 1;
+                        let escaped = chars.get(i).copied().ok_or_else(|| anyhow!("SyntaxError: Invalid or unexpected token"))?;
+                        match escaped {
+                            'n' => text.push('\n'),
+                            't' => text.push('\t'),
+                            'r' => text.push('\r'),
+                            '0' => text.push('\0'),
+                            'u' => {
+                                let hex: String = chars.get(i + 1..i + 5).map(|hex| hex.iter().collect()).unwrap_or_default();
+                                let code = u32::from_str_radix(&hex, 16).map_err(|_| anyhow!("SyntaxError: Invalid Unicode escape sequence"))?;
+                                text.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                                i += 4;
+                            }
+                            other => text.push(other),
+                        }
+                    }
+                    Some(other) => text.push(*other),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else if c == '/' && starts_regex(tokens.last()) {
+            let start = i + 1;
+            let mut in_class = false;
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => bail!("SyntaxError: Invalid regular expression: missing /"),
+                    Some('\\') => i += 1,
+                    Some('[') => in_class = true,
+                    Some(']') => in_class = false,
+                    Some('/') if !in_class => break,
+                    _ => {}
+                }
+                i += 1;
+            }
+            let source: String = chars[start..i].iter().collect();
+            i += 1;
+            let flags_start = i;
+            while chars.get(i).is_some_and(|c| c.is_ascii_alphabetic()) {
+                i += 1;
+            }
+            let flags: String = chars[flags_start..i].iter().collect();
+            if source.is_empty() {
+                bail!("SyntaxError: Unexpected token '/'");
+            }
+            tokens.push(Token::Regex(source, flags));
+        } else if c == '`' {
+            // Kept raw; its ${...} parts are parsed when the template is
+            let start = i + 1;
+            let mut depth = 0;
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("SyntaxError: Unterminated template literal"),
+                    Some('\\') => i += 1,
+                    Some('`') if depth == 0 => break,
+                    Some('$') if chars.get(i + 1) == Some(&'{') => {
+                        depth += 1;
+                        i += 1;
+                    }
+                    Some('}') if depth > 0 => depth -= 1,
+                    _ => {}
+                }
+                i += 1;
+            }
+            tokens.push(Token::Template(chars[start..i].iter().collect()));
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let punct = PUNCTUATORS.iter()
+                .find(|punct| rest.starts_with(**punct))
+                .ok_or_else(|| anyhow!("SyntaxError: Unexpected token '{}'", c))?;
+            // `a ?.5 : 1` is a conditional, not optional chaining
+            if *punct == "?." && chars.get(i + 2).is_some_and(char::is_ascii_digit) {
+                tokens.push(Token::Punct("?"));
+                i += 1;
+                continue;
+            }
+            tokens.push(Token::Punct(punct));
+            i += punct.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(JSValue),
+    Ident(String),
+    Template(Vec<TemplatePart>),
+    /// Elements, each marked when it's spread with `...`
+    Array(Vec<(bool, Expr)>),
+    Object(Vec<Property>),
+    Member { object: Box<Expr>, property: String, optional: bool },
+    Index { object: Box<Expr>, index: Box<Expr> },
+    Call { callee: Box<Expr>, args: Vec<(bool, Expr)> },
+    New { callee: String, args: Vec<(bool, Expr)> },
+    Function(Rc<Function>),
+    Unary(&'static str, Box<Expr>),
+    Typeof(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum TemplatePart {
+    Text(String),
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+enum Property {
+    Field(String, Expr),
+    Spread(Expr),
+}
+
+/// An arrow function or function expression passed to a built-in, such as a `map` callback
+#[derive(Debug)]
+struct Function {
+    params: Vec<String>,
+    body: Vec<Statement>,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Let(String, Expr),
+    Expr(Expr),
+    Return(Expr),
+}
+
+fn parse(source: &str) -> Result<Expr> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+    let expression = parser.expression()?;
+    match parser.peek() {
+        None => Ok(expression),
+        Some(token) => bail!("SyntaxError: Unexpected token {:?}", token),
+    }
+}
+
+fn parse_arguments(source: &str) -> Result<Vec<(bool, Expr)>> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+    let mut args = Vec::new();
+    while parser.peek().is_some() {
+        let spread = parser.eat("...");
+        args.push((spread, parser.expression()?));
+        if !parser.eat(",") && parser.peek().is_some() {
+            bail!("SyntaxError: missing ) after argument list");
+        }
+    }
+    Ok(args)
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "??" => 1,
+        "||" => 2,
+        "&&" => 3,
+        "==" | "!=" | "===" | "!==" => 4,
+        "<" | ">" | "<=" | ">=" | "in" => 5,
+        "+" | "-" => 6,
+        "*" | "/" | "%" => 7,
+        _ => return None,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn is(&self, offset: usize, punct: &str) -> bool {
+        matches!(self.tokens.get(self.pos + offset), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.is(0, punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        bail!("SyntaxError: expected '{}'", punct)
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            }
+            _ => bail!("SyntaxError: expected a name"),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            bail!("RangeError: Maximum nesting depth exceeded");
+        }
+        let expression = self.conditional();
+        self.depth -= 1;
+        expression
+    }
+
+    fn conditional(&mut self) -> Result<Expr> {
+        if let Some(function) = self.arrow_function()? {
+            return Ok(Expr::Function(Rc::new(function)));
+        }
+        let test = self.binary(1)?;
+        if !self.eat("?") {
+            return Ok(test);
+        }
+        let consequent = self.expression()?;
+        self.expect(":")?;
+        let alternate = self.expression()?;
+        Ok(Expr::Conditional(Box::new(test), Box::new(consequent), Box::new(alternate)))
+    }
+
+    fn binary(&mut self, min: u8) -> Result<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct(op)) => *op,
+                Some(Token::Ident(name)) if name == "in" => "in",
+                _ => break,
+            };
+            let Some(level) = precedence(op).filter(|level| *level >= min) else { break };
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        for op in ["!", "-", "+"] {
+            if self.eat(op) {
+                return Ok(Expr::Unary(op, Box::new(self.unary()?)));
+            }
+        }
+        match self.peek() {
+            Some(Token::Ident(name)) if name == "typeof" => {
+                self.pos += 1;
+                Ok(Expr::Typeof(Box::new(self.unary()?)))
+            }
+            Some(Token::Ident(name)) if name == "new" => {
+                self.pos += 1;
+                let callee = self.ident()?;
+                let args = if self.eat("(") { self.arguments()? } else { Vec::new() };
+                self.postfix(Expr::New { callee, args })
+            }
+            _ => {
+                let primary = self.primary()?;
+                self.postfix(primary)
+            }
+        }
+    }
+
+    fn postfix(&mut self, mut expression: Expr) -> Result<Expr> {
+        loop {
+            if self.is(0, ".") || self.is(0, "?.") {
+                let optional = self.is(0, "?.");
+                self.pos += 1;
+                let property = self.ident()?;
+                expression = Expr::Member { object: Box::new(expression), property, optional };
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expression = Expr::Index { object: Box::new(expression), index: Box::new(index) };
+            } else if self.eat("(") {
+                let args = self.arguments()?;
+                expression = Expr::Call { callee: Box::new(expression), args };
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    // After the opening parenthesis, up to and including the closing one
+    fn arguments(&mut self) -> Result<Vec<(bool, Expr)>> {
+        let mut args = Vec::new();
+        while !self.eat(")") {
+            let spread = self.eat("...");
+            args.push((spread, self.expression()?));
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| anyhow!("SyntaxError: Unexpected end of input"))?;
+        self.pos += 1;
+        Ok(match token {
+            Token::Number(n) => Expr::Literal(JSValue::Number(n)),
+            Token::Str(text) => Expr::Literal(JSValue::String(text)),
+            Token::Template(raw) => Expr::Template(template_parts(&raw)?),
+            Token::Regex(source, flags) => Expr::Literal(new_regexp(source, &flags)?),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Expr::Literal(JSValue::Boolean(true)),
+                "false" => Expr::Literal(JSValue::Boolean(false)),
+                "null" => Expr::Literal(JSValue::Null),
+                "undefined" => Expr::Literal(JSValue::Undefined),
+                "NaN" => Expr::Literal(JSValue::Number(f64::NAN)),
+                "Infinity" => Expr::Literal(JSValue::Number(f64::INFINITY)),
+                "function" => {
+                    if matches!(self.peek(), Some(Token::Ident(_))) {
+                        self.pos += 1;
+                    }
+                    self.expect("(")?;
+                    let params = self.parameters()?;
+                    Expr::Function(Rc::new(Function { params, body: self.block()? }))
+                }
+                _ => Expr::Ident(name),
+            },
+            Token::Punct("(") => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                expression
+            }
+            Token::Punct("[") => {
+                let mut elements = Vec::new();
+                while !self.eat("]") {
+                    let spread = self.eat("...");
+                    elements.push((spread, self.expression()?));
+                    if !self.eat(",") {
+                        self.expect("]")?;
+                        break;
+                    }
+                }
+                Expr::Array(elements)
+            }
+            Token::Punct("{") => {
+                let mut properties = Vec::new();
+                while !self.eat("}") {
+                    if self.eat("...") {
+                        properties.push(Property::Spread(self.expression()?));
+                    } else {
+                        let key = match self.tokens.get(self.pos).cloned() {
+                            Some(Token::Ident(key) | Token::Str(key)) => key,
+                            Some(Token::Number(n)) => number_to_string(n),
+                            _ => bail!("SyntaxError: Unexpected token in object literal"),
+                        };
+                        self.pos += 1;
+                        let value = if self.eat(":") { self.expression()? } else { Expr::Ident(key.clone()) };
+                        properties.push(Property::Field(key, value));
+                    }
+                    if !self.eat(",") {
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                Expr::Object(properties)
+            }
+            token => bail!("SyntaxError: Unexpected token {:?}", token),
+        })
+    }
+
+    // `x => ...` or `(a, b) => ...`, when that's what comes next
+    fn arrow_function(&mut self) -> Result<Option<Function>> {
+        let params = match self.peek() {
+            Some(Token::Ident(name)) if self.is(1, "=>") => {
+                let params = vec![name.clone()];
+                self.pos += 2;
+                params
+            }
+            Some(Token::Punct("(")) => {
+                let mut close = self.pos + 1;
+                while close < self.tokens.len() && matches!(self.tokens[close], Token::Ident(_) | Token::Punct(",")) {
+                    close += 1;
+                }
+                if !matches!(self.tokens.get(close), Some(Token::Punct(")"))) || !matches!(self.tokens.get(close + 1), Some(Token::Punct("=>"))) {
+                    return Ok(None);
+                }
+                self.pos += 1;
+                let params = self.parameters()?;
+                self.expect("=>")?;
+                params
+            }
+            _ => return Ok(None),
+        };
+        let body = if self.is(0, "{") { self.block()? } else { vec![Statement::Return(self.expression()?)] };
+        Ok(Some(Function { params, body }))
+    }
+
+    // After the opening parenthesis, up to and including the closing one
+    fn parameters(&mut self) -> Result<Vec<String>> {
+        let mut params = Vec::new();
+        while !self.eat(")") {
+            params.push(self.ident()?);
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok(params)
+    }
+
+    // `{ const x = ...; ...; return ... }`
+    fn block(&mut self) -> Result<Vec<Statement>> {
+        self.expect("{")?;
+        let mut statements = Vec::new();
+        while !self.eat("}") {
+            if self.eat(";") {
+                continue;
+            }
+            let statement = match self.peek() {
+                Some(Token::Ident(keyword)) if keyword == "return" => {
+                    self.pos += 1;
+                    if self.is(0, ";") || self.is(0, "}") {
+                        Statement::Return(Expr::Literal(JSValue::Undefined))
+                    } else {
+                        Statement::Return(self.expression()?)
+                    }
+                }
+                Some(Token::Ident(keyword)) if matches!(keyword.as_str(), "const" | "let" | "var") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    self.expect("=")?;
+                    Statement::Let(name, self.expression()?)
+                }
+                _ => Statement::Expr(self.expression()?),
+            };
+            statements.push(statement);
+        }
+        Ok(statements)
+    }
+}
+
+fn template_parts(raw: &str) -> Result<Vec<TemplatePart>> {
+    let mut parts = Vec::new();
+    let mut rest = raw;
+    while let Some(start) = rest.find("${") {
+        parts.push(TemplatePart::Text(unescape_template(&rest[..start])));
+        let mut depth = 0;
+        let end = rest[start + 2..].char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' if depth == 0 => return true,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                false
+            })
+            .map(|(i, _)| start + 2 + i)
+            .ok_or_else(|| anyhow!("SyntaxError: Unterminated template literal"))?;
+        parts.push(TemplatePart::Expr(parse(&rest[start + 2..end])?));
+        rest = &rest[end + 1..];
+    }
+    parts.push(TemplatePart::Text(unescape_template(rest)));
+    Ok(parts)
+}
+
+fn unescape_template(text: &str) -> String {
+    text.replace("\\n", "\n").replace("\\t", "\t").replace("\\`", "`").replace("\\$", "$").replace("\\\\", "\\")
+}
+
+/// A call's evaluated argument: a value, or a function literal for a built-in to call back
+#[derive(Clone)]
+enum Arg {
+    Value(JSValue),
+    Function(Rc<Function>),
+}
+
+impl Arg {
+    fn value(&self) -> JSValue {
+        match self {
+            Arg::Value(value) => value.clone(),
+            Arg::Function(_) => JSValue::Undefined,
+        }
+    }
+}
+
+fn arg(args: &[Arg], index: usize) -> JSValue {
+    args.get(index).map_or(JSValue::Undefined, Arg::value)
+}
+
+struct Evaluator<'a> {
+    host: &'a dyn Host,
+    // Parameters and constants of the callbacks being run, innermost last
+    scopes: Vec<HashMap<String, JSValue>>,
+    // Expressions evaluated, so the host's clock is only asked now and then
+    steps: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(host: &'a dyn Host) -> Self {
+        Self { host, scopes: Vec::new(), steps: 0 }
+    }
+
+    fn local(&self, name: &str) -> Option<&JSValue> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    // `a.b.c` as a path the host may know, unless it starts at a callback's parameter
+    fn host_path(&self, expression: &Expr) -> Option<String> {
+        match expression {
+            Expr::Ident(name) if self.local(name).is_none() => Some(name.clone()),
+            Expr::Member { object, property, optional: false } => Some(format!("{}.{}", self.host_path(object)?, property)),
+            _ => None,
+        }
+    }
+
+    fn eval(&mut self, expression: &Expr) -> Result<JSValue> {
+        if self.scopes.len() > MAX_DEPTH {
+            bail!("RangeError: Maximum call stack size exceeded");
+        }
+        self.steps += 1;
+        if self.steps.is_multiple_of(256) {
+            self.host.check_running_time()?;
+        }
+        Ok(match expression {
+            Expr::Literal(value) => value.clone(),
+            Expr::Ident(name) => match self.local(name).cloned().or_else(|| self.host.lookup(name)) {
+                Some(value) => value,
+                None => bail!("ReferenceError: {} is not defined", name),
+            },
+            Expr::Template(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(part) => text.push_str(part),
+                        TemplatePart::Expr(part) => text.push_str(&js_string(&self.eval(part)?)),
+                    }
+                }
+                JSValue::String(text)
+            }
+            Expr::Array(elements) => JSValue::Array(self.elements(elements)?),
+            Expr::Object(properties) => {
+                let mut fields = HashMap::new();
+                for property in properties {
+                    match property {
+                        Property::Field(key, value) => {
+                            fields.insert(key.clone(), self.eval(value)?);
+                        }
+                        Property::Spread(value) => match self.eval(value)? {
+                            JSValue::Object(spread) => fields.extend(spread),
+                            JSValue::Array(items) => {
+                                fields.extend(items.into_iter().enumerate().map(|(i, item)| (i.to_string(), item)));
+                            }
+                            _ => {}
+                        },
+                    }
+                }
+                JSValue::Object(fields)
+            }
+            Expr::Member { object, property, optional } => {
+                if let Some(value) = self.host_path(expression).and_then(|path| self.host.lookup(&path)) {
+                    return Ok(value);
+                }
+                if let Expr::Ident(namespace) = object.as_ref() {
+                    if self.local(namespace).is_none() {
+                        if let Some(constant) = static_constant(namespace, property) {
+                            return Ok(JSValue::Number(constant));
+                        }
+                    }
+                }
+                let object = self.eval(object)?;
+                if *optional && matches!(object, JSValue::Null | JSValue::Undefined) {
+                    return Ok(JSValue::Undefined);
+                }
+                get_property(&object, property)?
+            }
+            Expr::Index { object, index } => {
+                let object = self.eval(object)?;
+                let index = self.eval(index)?;
+                get_property(&object, &js_string(&index))?
+            }
+            Expr::Call { callee, args } => self.call(callee, args)?,
+            Expr::New { callee, args } => {
+                let args = self.arguments(args)?;
+                match callee.as_str() {
+                    "Date" => self.date(new_date(&args), "toString")?,
+                    "Array" => match args.as_slice() {
+                        [Arg::Value(JSValue::Number(length))] => JSValue::Array(vec![JSValue::Undefined; *length as usize]),
+                        _ => JSValue::Array(args.iter().map(Arg::value).collect()),
+                    },
+                    "Object" => JSValue::Object(HashMap::new()),
+                    "String" | "Number" | "Boolean" => global_function(callee, &args)?,
+                    "RegExp" => regexp_constructor(&args)?,
+                    _ => bail!("TypeError: {} is not a constructor", callee),
+                }
+            }
+            Expr::Function(_) => bail!("TypeError: functions can only be passed to built-in methods"),
+            Expr::Unary(op, operand) => {
+                let value = self.eval(operand)?;
+                match *op {
+                    "!" => JSValue::Boolean(!value.is_truthy()),
+                    "-" => JSValue::Number(-to_number(&value)),
+                    _ => JSValue::Number(to_number(&value)),
+                }
+            }
+            Expr::Typeof(operand) => JSValue::String(match operand.as_ref() {
+                Expr::Ident(name) if self.local(name).is_none() && self.host.lookup(name).is_none() => {
+                    if NAMESPACES.contains(&name.as_str()) {
+                        "object"
+                    } else if GLOBAL_FUNCTIONS.contains(&name.as_str()) {
+                        "function"
+                    } else {
+                        "undefined"
+                    }
+                }
+                Expr::Function(_) => "function",
+                operand => type_of(&self.eval(operand)?),
+            }.to_string()),
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                match *op {
+                    "&&" if !left.is_truthy() => return Ok(left),
+                    "||" if left.is_truthy() => return Ok(left),
+                    "??" if !matches!(left, JSValue::Null | JSValue::Undefined) => return Ok(left),
+                    "&&" | "||" | "??" => return self.eval(right),
+                    _ => {}
+                }
+                binary(op, &left, &self.eval(right)?)?
+            }
+            Expr::Conditional(test, consequent, alternate) => {
+                if self.eval(test)?.is_truthy() {
+                    self.eval(consequent)?
+                } else {
+                    self.eval(alternate)?
+                }
+            }
+        })
+    }
+
+    fn elements(&mut self, elements: &[(bool, Expr)]) -> Result<Vec<JSValue>> {
+        let mut values = Vec::new();
+        for (spread, element) in elements {
+            let value = self.eval(element)?;
+            if *spread {
+                values.extend(spread_items(value)?);
+            } else {
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+
+    // Like `elements`, but function literals are kept for the built-in to call back
+    fn arguments(&mut self, args: &[(bool, Expr)]) -> Result<Vec<Arg>> {
+        let mut values = Vec::new();
+        for (spread, arg) in args {
+            match arg {
+                Expr::Function(function) if !spread => values.push(Arg::Function(function.clone())),
+                arg if *spread => values.extend(spread_items(self.eval(arg)?)?.into_iter().map(Arg::Value)),
+                arg => values.push(Arg::Value(self.eval(arg)?)),
+            }
+        }
+        Ok(values)
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[(bool, Expr)]) -> Result<JSValue> {
+        match callee {
+            Expr::Ident(name) if self.local(name).is_none() => {
+                let args = self.arguments(args)?;
+                if GLOBAL_FUNCTIONS.contains(&name.as_str()) {
+                    return global_function(name, &args);
+                }
+                // Date() without new is the current time as a string, whatever the arguments
+                if name == "Date" {
+                    return self.date(chrono::Utc::now().timestamp_millis() as f64, "toString");
+                }
+                if name == "RegExp" {
+                    return regexp_constructor(&args);
+                }
+                let values = args.iter().map(Arg::value).collect::<Vec<_>>();
+                self.host.call(name, &values).ok_or_else(|| anyhow!("TypeError: {} is not a function", name))
+            }
+            Expr::Member { object, property, optional } => {
+                if let Expr::New { callee: constructor, args: date_args } = object.as_ref() {
+                    if constructor == "Date" {
+                        let date_args = self.arguments(date_args)?;
+                        return self.date(new_date(&date_args), property);
+                    }
+                }
+                let args = self.arguments(args)?;
+                if let Expr::Ident(namespace) = object.as_ref() {
+                    if self.local(namespace).is_none() && NAMESPACES.contains(&namespace.as_str()) && namespace != "console" {
+                        return self.static_call(namespace, property, &args);
+                    }
+                }
+                if let Some(path) = self.host_path(callee) {
+                    let values = args.iter().map(Arg::value).collect::<Vec<_>>();
+                    if let Some(result) = self.host.call(&path, &values) {
+                        return Ok(result);
+                    }
+                }
+                let receiver = self.eval(object)?;
+                if *optional && matches!(receiver, JSValue::Null | JSValue::Undefined) {
+                    return Ok(JSValue::Undefined);
+                }
+                self.method(receiver, property, &args)
+            }
+            _ => bail!("TypeError: expression is not a function"),
+        }
+    }
+
+    fn invoke(&mut self, function: &Function, args: Vec<JSValue>) -> Result<JSValue> {
+        let mut scope: HashMap<String, JSValue> = function.params.iter().cloned().zip(args.into_iter().chain(std::iter::repeat(JSValue::Undefined))).collect();
+        scope.retain(|name, _| function.params.contains(name));
+        self.scopes.push(scope);
+        let mut result = Ok(JSValue::Undefined);
+        for statement in &function.body {
+            match statement {
+                Statement::Let(name, value) => match self.eval(value) {
+                    Ok(value) => {
+                        if let Some(scope) = self.scopes.last_mut() {
+                            scope.insert(name.clone(), value);
+                        }
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                },
+                Statement::Expr(expression) => {
+                    if let Err(e) = self.eval(expression) {
+                        result = Err(e);
+                        break;
+                    }
+                }
+                Statement::Return(expression) => {
+                    result = self.eval(expression);
+                    break;
+                }
+            }
+        }
+        self.scopes.pop();
+        result
+    }
+
+    fn callback(&self, args: &[Arg], index: usize) -> Result<Rc<Function>> {
+        match args.get(index) {
+            Some(Arg::Function(function)) => Ok(function.clone()),
+            _ => bail!("TypeError: {} is not a function", js_string(&arg(args, index))),
+        }
+    }
+
+    fn date(&self, time: f64, method: &str) -> Result<JSValue> {
+        self.host.date_method(time, method)
+    }
+
+    fn static_call(&mut self, namespace: &str, method: &str, args: &[Arg]) -> Result<JSValue> {
+        let number = |index: usize| to_number(&arg(args, index));
+        let numbers = || args.iter().map(|arg| to_number(&arg.value())).collect::<Vec<_>>();
+        Ok(match (namespace, method) {
+            ("JSON", "parse") => {
+                let text = js_string(&arg(args, 0));
+                from_json(&text).ok_or_else(|| anyhow!("SyntaxError: Unexpected token in JSON"))?
+            }
+            ("JSON", "stringify") => {
+                let Some(json) = json_value(&arg(args, 0)) else { return Ok(JSValue::Undefined) };
+                let indent = match arg(args, 2) {
+                    JSValue::Number(n) => " ".repeat(n.clamp(0.0, 10.0) as usize),
+                    JSValue::String(text) => text.chars().take(10).collect(),
+                    _ => String::new(),
+                };
+                if indent.is_empty() {
+                    JSValue::String(json.to_string())
+                } else {
+                    let mut out = Vec::new();
+                    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+                    serde::Serialize::serialize(&json, &mut serializer)?;
+                    JSValue::String(String::from_utf8_lossy(&out).into_owned())
+                }
+            }
+            ("Math", "max") => JSValue::Number(numbers().into_iter().fold(f64::NEG_INFINITY, |a, b| if a.is_nan() || b.is_nan() { f64::NAN } else { a.max(b) })),
+            ("Math", "min") => JSValue::Number(numbers().into_iter().fold(f64::INFINITY, |a, b| if a.is_nan() || b.is_nan() { f64::NAN } else { a.min(b) })),
+            ("Math", "random") => JSValue::Number(random()),
+            ("Math", "pow") => JSValue::Number(number(0).powf(number(1))),
+            ("Math", "atan2") => JSValue::Number(number(0).atan2(number(1))),
+            ("Math", "hypot") => JSValue::Number(numbers().iter().map(|n| n * n).sum::<f64>().sqrt()),
+            ("Math", method) => {
+                let n = number(0);
+                JSValue::Number(match method {
+                    "abs" => n.abs(),
+                    "floor" => n.floor(),
+                    "ceil" => n.ceil(),
+                    // Halves round up, towards +Infinity, unlike Rust's round
+                    "round" => (n + 0.5).floor(),
+                    "trunc" => n.trunc(),
+                    "sign" => if n == 0.0 || n.is_nan() { n } else { n.signum() },
+                    "sqrt" => n.sqrt(),
+                    "cbrt" => n.cbrt(),
+                    "exp" => n.exp(),
+                    "log" => n.ln(),
+                    "log2" => n.log2(),
+                    "log10" => n.log10(),
+                    "sin" => n.sin(),
+                    "cos" => n.cos(),
+                    "tan" => n.tan(),
+                    "asin" => n.asin(),
+                    "acos" => n.acos(),
+                    "atan" => n.atan(),
+                    _ => bail!("TypeError: Math.{} is not a function", method),
+                })
+            }
+            ("Object", "keys" | "values" | "entries") => {
+                let entries = match arg(args, 0) {
+                    JSValue::Object(fields) => sorted_fields(fields),
+                    JSValue::Array(items) => items.into_iter().enumerate().map(|(i, item)| (i.to_string(), item)).collect(),
+                    JSValue::String(text) => text.chars().enumerate().map(|(i, c)| (i.to_string(), JSValue::String(c.to_string()))).collect(),
+                    JSValue::Null | JSValue::Undefined => bail!("TypeError: Cannot convert undefined or null to object"),
+                    _ => Vec::new(),
+                };
+                JSValue::Array(entries.into_iter().map(|(key, value)| match method {
+                    "keys" => JSValue::String(key),
+                    "values" => value,
+                    _ => JSValue::Array(vec![JSValue::String(key), value]),
+                }).collect())
+            }
+            ("Object", "assign") => {
+                let mut target = match arg(args, 0) {
+                    JSValue::Object(fields) => fields,
+                    _ => HashMap::new(),
+                };
+                for source in args.iter().skip(1) {
+                    if let JSValue::Object(fields) = source.value() {
+                        target.extend(fields);
+                    }
+                }
+                JSValue::Object(target)
+            }
+            ("Object", "fromEntries") => {
+                let JSValue::Array(entries) = arg(args, 0) else { bail!("TypeError: object is not iterable") };
+                JSValue::Object(entries.into_iter().filter_map(|entry| match entry {
+                    JSValue::Array(pair) => Some((js_string(pair.first()?), pair.get(1).cloned().unwrap_or(JSValue::Undefined))),
+                    _ => None,
+                }).collect())
+            }
+            ("Object", "freeze" | "seal") => arg(args, 0),
+            ("Array", "isArray") => JSValue::Boolean(matches!(arg(args, 0), JSValue::Array(_))),
+            ("Array", "of") => JSValue::Array(args.iter().map(Arg::value).collect()),
+            ("Array", "from") => {
+                let items = match arg(args, 0) {
+                    JSValue::Array(items) => items,
+                    JSValue::String(text) => text.chars().map(|c| JSValue::String(c.to_string())).collect(),
+                    JSValue::Object(fields) => match fields.get("length") {
+                        Some(length) => vec![JSValue::Undefined; to_number(length).max(0.0) as usize],
+                        None => Vec::new(),
+                    },
+                    _ => Vec::new(),
+                };
+                match args.get(1) {
+                    Some(Arg::Function(map)) => {
+                        let mut mapped = Vec::with_capacity(items.len());
+                        for (i, item) in items.into_iter().enumerate() {
+                            mapped.push(self.invoke(map, vec![item, JSValue::Number(i as f64)])?);
+                        }
+                        JSValue::Array(mapped)
+                    }
+                    _ => JSValue::Array(items),
+                }
+            }
+            ("Number", "isInteger") => JSValue::Boolean(matches!(arg(args, 0), JSValue::Number(n) if n.is_finite() && n.fract() == 0.0)),
+            ("Number", "isFinite") => JSValue::Boolean(matches!(arg(args, 0), JSValue::Number(n) if n.is_finite())),
+            ("Number", "isNaN") => JSValue::Boolean(matches!(arg(args, 0), JSValue::Number(n) if n.is_nan())),
+            ("Number", "parseInt" | "parseFloat") => global_function(method, args)?,
+            ("String", "fromCharCode") => JSValue::String(numbers().into_iter().filter_map(|code| char::from_u32(code as u32)).collect()),
+            ("Date", "now") => self.date(chrono::Utc::now().timestamp_millis() as f64, "getTime")?,
+            ("Date", "parse") => JSValue::Number(parse_date(&js_string(&arg(args, 0)))),
+            ("Date", "UTC") => JSValue::Number(utc_date(&numbers())),
+            (namespace, method) => bail!("TypeError: {}.{} is not a function", namespace, method),
+        })
+    }
+
+    fn method(&mut self, receiver: JSValue, method: &str, args: &[Arg]) -> Result<JSValue> {
+        match receiver {
+            JSValue::String(text) if matches!(method, "match" | "matchAll" | "search")
+                || (matches!(method, "replace" | "replaceAll" | "split") && matches!(arg(args, 0), JSValue::RegExp { .. }))
+                || (matches!(method, "replace" | "replaceAll") && matches!(args.get(1), Some(Arg::Function(_)))) => {
+                self.regexp_string_method(&text, method, args)
+            }
+            JSValue::String(text) => string_method(&text, method, args),
+            JSValue::RegExp { source, flags } => {
+                let regex = regexp::compile(&source, &flags)?;
+                let input = js_string(&arg(args, 0));
+                Ok(match method {
+                    "test" => JSValue::Boolean(regex.is_match(&input)),
+                    "exec" => regex.captures(&input).map_or(JSValue::Null, |captures| match_result(&regex, &captures, &input)),
+                    "toString" => JSValue::String(format!("/{}/{}", source, flags)),
+                    _ => bail!("TypeError: regexp.{} is not a function", method),
+                })
+            }
+            JSValue::Number(n) => Ok(match method {
+                "toFixed" => JSValue::String(format!("{:.*}", to_number(&arg(args, 0)).clamp(0.0, 100.0) as usize, n)),
+                "toString" => match arg(args, 0) {
+                    JSValue::Number(radix) if radix != 10.0 => JSValue::String(to_radix(n, radix as u32)?),
+                    _ => JSValue::String(number_to_string(n)),
+                },
+                "valueOf" => JSValue::Number(n),
+                _ => bail!("TypeError: {}.{} is not a function", number_to_string(n), method),
+            }),
+            JSValue::Array(mut items) => self.array_method(&mut items, method, args),
+            JSValue::Object(fields) => Ok(match method {
+                "hasOwnProperty" => JSValue::Boolean(fields.contains_key(&js_string(&arg(args, 0)))),
+                "toString" => JSValue::String("[object Object]".to_string()),
+                _ => bail!("TypeError: object.{} is not a function", method),
+            }),
+            JSValue::Boolean(b) if method == "toString" => Ok(JSValue::String(b.to_string())),
+            value => bail!("TypeError: Cannot read properties of {} (reading '{}')", js_string(&value), method),
+        }
+    }
+
+    // The string methods that take a regex, and replace with a function; a string pattern matches literally
+    fn regexp_string_method(&mut self, text: &str, method: &str, args: &[Arg]) -> Result<JSValue> {
+        let (regex, global) = match arg(args, 0) {
+            JSValue::RegExp { source, flags } => (regexp::compile(&source, &flags)?, flags.contains('g')),
+            JSValue::Undefined if method != "replace" && method != "replaceAll" => (regexp::compile("(?:)", "")?, false),
+            pattern => {
+                let pattern = js_string(&pattern);
+                // match and search read a string as a pattern; replace takes it literally
+                let source = if matches!(method, "replace" | "replaceAll") { regex::escape(&pattern) } else { pattern };
+                (regexp::compile(&source, "")?, method == "replaceAll")
+            }
+        };
+        if matches!(method, "matchAll" | "replaceAll") && !global {
+            bail!("TypeError: {} must be called with a global RegExp", method);
+        }
+        Ok(match method {
+            "search" => JSValue::Number(regex.find(text).map_or(-1.0, |m| regexp::char_index(text, m.start()) as f64)),
+            "match" if global => {
+                let matches: Vec<JSValue> = regex.find_iter(text).map(|m| JSValue::String(m.as_str().to_string())).collect();
+                if matches.is_empty() { JSValue::Null } else { JSValue::Array(matches) }
+            }
+            "match" => regex.captures(text).map_or(JSValue::Null, |captures| match_result(&regex, &captures, text)),
+            "matchAll" => JSValue::Array(regex.captures_iter(text).map(|captures| match_result(&regex, &captures, text)).collect()),
+            "split" => {
+                let limit = match arg(args, 1) {
+                    JSValue::Undefined => usize::MAX,
+                    limit => to_number(&limit).max(0.0) as usize,
+                };
+                let mut parts = Vec::new();
+                let mut last = 0;
+                for captures in regex.captures_iter(text) {
+                    let Some(whole) = captures.get(0) else { continue };
+                    // Empty matches at either end don't split anything off
+                    if whole.is_empty() && (whole.start() == 0 || whole.start() == text.len()) {
+                        continue;
+                    }
+                    parts.push(JSValue::String(text[last..whole.start()].to_string()));
+                    // Captured groups are spliced into the result
+                    parts.extend(captures.iter().skip(1).map(|group| group.map_or(JSValue::Undefined, |m| JSValue::String(m.as_str().to_string()))));
+                    last = whole.end();
+                }
+                parts.push(JSValue::String(text[last..].to_string()));
+                parts.truncate(limit);
+                JSValue::Array(parts)
+            }
+            _ => {
+                let replacement = args.get(1).cloned().unwrap_or(Arg::Value(JSValue::Undefined));
+                let mut replaced = String::new();
+                let mut last = 0;
+                for captures in regex.captures_iter(text).take(if global { usize::MAX } else { 1 }) {
+                    let Some(whole) = captures.get(0) else { continue };
+                    replaced.push_str(&text[last..whole.start()]);
+                    match &replacement {
+                        Arg::Function(function) => {
+                            // (match, p1, ..., offset, string, groups)
+                            let mut call_args: Vec<JSValue> = captures.iter()
+                                .map(|group| group.map_or(JSValue::Undefined, |m| JSValue::String(m.as_str().to_string())))
+                                .collect();
+                            call_args.push(JSValue::Number(regexp::char_index(text, whole.start()) as f64));
+                            call_args.push(JSValue::String(text.to_string()));
+                            if let JSValue::Object(groups) = named_groups(&regex, &captures) {
+                                call_args.push(JSValue::Object(groups));
+                            }
+                            replaced.push_str(&js_string(&self.invoke(function, call_args)?));
+                        }
+                        Arg::Value(value) => replaced.push_str(&regexp::expand(&js_string(value), &captures, text)),
+                    }
+                    last = whole.end();
+                }
+                replaced.push_str(&text[last..]);
+                JSValue::String(replaced)
+            }
+        })
+    }
+
+    fn array_method(&mut self, items: &mut Vec<JSValue>, method: &str, args: &[Arg]) -> Result<JSValue> {
+        let length = items.len() as f64;
+        Ok(match method {
+            "map" | "filter" | "forEach" | "find" | "findIndex" | "findLast" | "some" | "every" | "flatMap" => {
+                let callback = self.callback(args, 0)?;
+                let mut results = Vec::new();
+                for (i, item) in items.iter().enumerate() {
+                    let result = self.invoke(&callback, vec![item.clone(), JSValue::Number(i as f64), JSValue::Array(items.clone())])?;
+                    match method {
+                        "find" if result.is_truthy() => return Ok(item.clone()),
+                        "findIndex" if result.is_truthy() => return Ok(JSValue::Number(i as f64)),
+                        "some" if result.is_truthy() => return Ok(JSValue::Boolean(true)),
+                        "every" if !result.is_truthy() => return Ok(JSValue::Boolean(false)),
+                        "filter" if result.is_truthy() => results.push(item.clone()),
+                        "findLast" if result.is_truthy() => results = vec![item.clone()],
+                        "map" => results.push(result),
+                        "flatMap" => match result {
+                            JSValue::Array(inner) => results.extend(inner),
+                            value => results.push(value),
+                        },
+                        _ => {}
+                    }
+                }
+                match method {
+                    "find" | "forEach" => JSValue::Undefined,
+                    "findLast" => results.pop().unwrap_or(JSValue::Undefined),
+                    "findIndex" => JSValue::Number(-1.0),
+                    "some" => JSValue::Boolean(false),
+                    "every" => JSValue::Boolean(true),
+                    _ => JSValue::Array(results),
+                }
+            }
+            "reduce" | "reduceRight" => {
+                let callback = self.callback(args, 0)?;
+                let mut order: Vec<usize> = (0..items.len()).collect();
+                if method == "reduceRight" {
+                    order.reverse();
+                }
+                let mut order = order.into_iter();
+                let mut accumulator = match args.get(1) {
+                    Some(initial) => initial.value(),
+                    None => match order.next() {
+                        Some(first) => items[first].clone(),
+                        None => bail!("TypeError: Reduce of empty array with no initial value"),
+                    },
+                };
+                for i in order {
+                    accumulator = self.invoke(&callback, vec![accumulator, items[i].clone(), JSValue::Number(i as f64)])?;
+                }
+                accumulator
+            }
+            "join" => {
+                let separator = match arg(args, 0) {
+                    JSValue::Undefined => ",".to_string(),
+                    separator => js_string(&separator),
+                };
+                JSValue::String(items.iter().map(element_string).collect::<Vec<_>>().join(&separator))
+            }
+            "toString" => JSValue::String(js_string(&JSValue::Array(items.clone()))),
+            "slice" => {
+                let (start, end) = slice_range(length, &arg(args, 0), &arg(args, 1));
+                JSValue::Array(items[start..end.max(start)].to_vec())
+            }
+            "at" => {
+                let index = to_number(&arg(args, 0)).trunc();
+                let index = if index < 0.0 { length + index } else { index };
+                items.get(index as usize).filter(|_| index >= 0.0).cloned().unwrap_or(JSValue::Undefined)
+            }
+            "concat" => {
+                let mut joined = items.clone();
+                for value in args.iter().map(Arg::value) {
+                    match value {
+                        JSValue::Array(more) => joined.extend(more),
+                        value => joined.push(value),
+                    }
+                }
+                JSValue::Array(joined)
+            }
+            "includes" => {
+                let needle = arg(args, 0);
+                JSValue::Boolean(items.iter().any(|item| same_value_zero(item, &needle)))
+            }
+            "indexOf" | "lastIndexOf" => {
+                let needle = arg(args, 0);
+                let position = if method == "indexOf" {
+                    items.iter().position(|item| strict_equals(item, &needle))
+                } else {
+                    items.iter().rposition(|item| strict_equals(item, &needle))
+                };
+                JSValue::Number(position.map_or(-1.0, |i| i as f64))
+            }
+            "flat" => {
+                let depth = match arg(args, 0) {
+                    JSValue::Undefined => 1,
+                    depth => to_number(&depth).max(0.0) as usize,
+                };
+                JSValue::Array(flatten(items.clone(), depth))
+            }
+            "keys" => JSValue::Array((0..items.len()).map(|i| JSValue::Number(i as f64)).collect()),
+            "entries" => JSValue::Array(items.iter().enumerate().map(|(i, item)| JSValue::Array(vec![JSValue::Number(i as f64), item.clone()])).collect()),
+            // The in-place methods on a value that isn't a variable: their changes go nowhere, only the result counts
+            _ => return self.mutate_array(items, method, args),
+        })
+    }
+
+    fn mutate_array(&mut self, items: &mut Vec<JSValue>, method: &str, args: &[Arg]) -> Result<JSValue> {
+        Ok(match method {
+            "push" => {
+                items.extend(args.iter().map(Arg::value));
+                JSValue::Number(items.len() as f64)
+            }
+            "unshift" => {
+                items.splice(0..0, args.iter().map(Arg::value));
+                JSValue::Number(items.len() as f64)
+            }
+            "pop" => items.pop().unwrap_or(JSValue::Undefined),
+            "shift" => if items.is_empty() { JSValue::Undefined } else { items.remove(0) },
+            "reverse" => {
+                items.reverse();
+                JSValue::Array(items.clone())
+            }
+            "splice" => {
+                let length = items.len() as f64;
+                let (start, _) = slice_range(length, &arg(args, 0), &JSValue::Undefined);
+                let count = match args.get(1) {
+                    None => items.len() - start,
+                    Some(count) => (to_number(&count.value()).max(0.0) as usize).min(items.len() - start),
+                };
+                let removed = items.splice(start..start + count, args.iter().skip(2).map(Arg::value)).collect();
+                JSValue::Array(removed)
+            }
+            "fill" => {
+                let (start, end) = slice_range(items.len() as f64, &arg(args, 1), &arg(args, 2));
+                let value = arg(args, 0);
+                for item in &mut items[start..end.max(start)] {
+                    *item = value.clone();
+                }
+                JSValue::Array(items.clone())
+            }
+            "sort" => {
+                let comparator = match args.first() {
+                    Some(Arg::Function(function)) => Some(function.clone()),
+                    _ => None,
+                };
+                // Undefined sorts last and isn't passed to the comparator
+                let undefined = items.iter().filter(|item| matches!(item, JSValue::Undefined)).count();
+                let mut sorted: Vec<JSValue> = items.drain(..).filter(|item| !matches!(item, JSValue::Undefined)).collect();
+                let mut error = None;
+                sorted.sort_by(|a, b| {
+                    let order = match &comparator {
+                        Some(function) => match self.invoke(function, vec![a.clone(), b.clone()]) {
+                            Ok(result) => to_number(&result),
+                            Err(e) => {
+                                error.get_or_insert(e);
+                                0.0
+                            }
+                        },
+                        None => return js_string(a).encode_utf16().cmp(js_string(b).encode_utf16()),
+                    };
+                    order.partial_cmp(&0.0).unwrap_or(std::cmp::Ordering::Equal)
+                });
+                if let Some(e) = error {
+                    return Err(e);
+                }
+                sorted.extend(std::iter::repeat_n(JSValue::Undefined, undefined));
+                *items = sorted;
+                JSValue::Array(items.clone())
+            }
+            _ => bail!("TypeError: array.{} is not a function", method),
+        })
+    }
+}
+
+// The values `...value` stands for
+fn spread_items(value: JSValue) -> Result<Vec<JSValue>> {
+    Ok(match value {
+        JSValue::Array(items) => items,
+        JSValue::String(text) => text.chars().map(|c| JSValue::String(c.to_string())).collect(),
+        value => bail!("TypeError: {} is not iterable", js_string(&value)),
+    })
+}
+
+fn new_regexp(source: String, flags: &str) -> Result<JSValue> {
+    let flags = regexp::normalize_flags(flags)?;
+    regexp::compile(&source, &flags)?;
+    Ok(JSValue::RegExp { source, flags })
+}
+
+// `new RegExp(pattern, flags)`, where the pattern may be another regex
+fn regexp_constructor(args: &[Arg]) -> Result<JSValue> {
+    let (source, flags) = match (arg(args, 0), arg(args, 1)) {
+        (JSValue::RegExp { source, flags }, JSValue::Undefined) => (source, flags),
+        (JSValue::RegExp { source, .. }, flags) => (source, js_string(&flags)),
+        (JSValue::Undefined, flags) => (String::new(), flags_string(flags)),
+        (pattern, flags) => (js_string(&pattern), flags_string(flags)),
+    };
+    new_regexp(if source.is_empty() { "(?:)".to_string() } else { source }, &flags)
+}
+
+fn flags_string(flags: JSValue) -> String {
+    match flags {
+        JSValue::Undefined => String::new(),
+        flags => js_string(&flags),
+    }
+}
+
+// What exec and match give for a match: the match and its groups by index, with `index`, `input` and `groups`.
+// It's an object rather than an array, since arrays here can't carry those properties.
+fn match_result(regex: &regex::Regex, captures: &regex::Captures, input: &str) -> JSValue {
+    let mut fields: HashMap<String, JSValue> = captures.iter()
+        .enumerate()
+        .map(|(i, group)| (i.to_string(), group.map_or(JSValue::Undefined, |m| JSValue::String(m.as_str().to_string()))))
+        .collect();
+    fields.insert("length".to_string(), JSValue::Number(captures.len() as f64));
+    let start = captures.get(0).map_or(0, |m| m.start());
+    fields.insert("index".to_string(), JSValue::Number(regexp::char_index(input, start) as f64));
+    fields.insert("input".to_string(), JSValue::String(input.to_string()));
+    fields.insert("groups".to_string(), named_groups(regex, captures));
+    JSValue::Object(fields)
+}
+
+// Named groups as an object, or undefined for patterns without any
+fn named_groups(regex: &regex::Regex, captures: &regex::Captures) -> JSValue {
+    let names: Vec<&str> = regex.capture_names().flatten().collect();
+    if names.is_empty() {
+        return JSValue::Undefined;
+    }
+    JSValue::Object(names.into_iter()
+        .map(|name| (name.to_string(), captures.name(name).map_or(JSValue::Undefined, |m| JSValue::String(m.as_str().to_string()))))
+        .collect())
+}
+
+// Math.random(): uniform in [0, 1), from the standard library's randomly keyed hasher
+fn random() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn string_method(text: &str, method: &str, args: &[Arg]) -> Result<JSValue> {
+    let chars: Vec<char> = text.chars().collect();
+    let length = chars.len() as f64;
+    let string = |index: usize| js_string(&arg(args, index));
+    let substring = |start: usize, end: usize| JSValue::String(chars[start.min(chars.len())..end.clamp(start.min(chars.len()), chars.len())].iter().collect());
+    Ok(match method {
+        "toUpperCase" | "toLocaleUpperCase" => JSValue::String(text.to_uppercase()),
+        "toLowerCase" | "toLocaleLowerCase" => JSValue::String(text.to_lowercase()),
+        "trim" => JSValue::String(text.trim().to_string()),
+        "trimStart" => JSValue::String(text.trim_start().to_string()),
+        "trimEnd" => JSValue::String(text.trim_end().to_string()),
+        "toString" | "valueOf" => JSValue::String(text.to_string()),
+        "split" => {
+            let limit = match arg(args, 1) {
+                JSValue::Undefined => usize::MAX,
+                limit => to_number(&limit).max(0.0) as usize,
+            };
+            let parts: Vec<JSValue> = match arg(args, 0) {
+                JSValue::Undefined => vec![JSValue::String(text.to_string())],
+                separator => match js_string(&separator) {
+                    separator if separator.is_empty() => chars.iter().map(|c| JSValue::String(c.to_string())).collect(),
+                    separator => text.split(separator.as_str()).map(|part| JSValue::String(part.to_string())).collect(),
+                },
+            };
+            JSValue::Array(parts.into_iter().take(limit).collect())
+        }
+        "slice" => {
+            let (start, end) = slice_range(length, &arg(args, 0), &arg(args, 1));
+            substring(start, end)
+        }
+        "substring" => {
+            let clamp = |value: JSValue, default: f64| match value {
+                JSValue::Undefined => default,
+                value => to_number(&value).clamp(0.0, length),
+            };
+            let (a, b) = (clamp(arg(args, 0), 0.0), clamp(arg(args, 1), length));
+            substring(a.min(b) as usize, a.max(b) as usize)
+        }
+        "substr" => {
+            let (start, _) = slice_range(length, &arg(args, 0), &JSValue::Undefined);
+            let count = match arg(args, 1) {
+                JSValue::Undefined => chars.len(),
+                count => to_number(&count).max(0.0) as usize,
+            };
+            substring(start, start.saturating_add(count))
+        }
+        "indexOf" | "lastIndexOf" | "includes" | "startsWith" | "endsWith" => {
+            let needle = string(0);
+            let found = |index: Option<usize>| index.map(|byte| text[..byte].chars().count() as f64);
+            match method {
+                "indexOf" => JSValue::Number(found(text.find(&needle)).unwrap_or(-1.0)),
+                "lastIndexOf" => JSValue::Number(found(text.rfind(&needle)).unwrap_or(-1.0)),
+                "includes" => JSValue::Boolean(text.contains(&needle)),
+                "startsWith" => JSValue::Boolean(text.starts_with(&needle)),
+                _ => JSValue::Boolean(text.ends_with(&needle)),
+            }
+        }
+        "replace" => JSValue::String(text.replacen(&string(0), &string(1), 1)),
+        "replaceAll" => JSValue::String(text.replace(&string(0), &string(1))),
+        "repeat" => {
+            let count = to_number(&arg(args, 0));
+            if !(0.0..=1e6).contains(&count) {
+                bail!("RangeError: Invalid count value: {}", number_to_string(count));
+            }
+            JSValue::String(text.repeat(count as usize))
+        }
+        "padStart" | "padEnd" => {
+            let target = to_number(&arg(args, 0)).clamp(0.0, 1e6) as usize;
+            let fill = match arg(args, 1) {
+                JSValue::Undefined => " ".to_string(),
+                fill => js_string(&fill),
+            };
+            if target <= chars.len() || fill.is_empty() {
+                return Ok(JSValue::String(text.to_string()));
+            }
+            let padding: String = fill.chars().cycle().take(target - chars.len()).collect();
+            JSValue::String(if method == "padStart" { padding + text } else { text.to_string() + &padding })
+        }
+        "charAt" | "at" | "charCodeAt" => {
+            let index = to_number(&arg(args, 0)).trunc();
+            let index = if method == "at" && index < 0.0 { length + index } else { index };
+            let c = chars.get(index as usize).filter(|_| index >= 0.0);
+            match method {
+                "charCodeAt" => JSValue::Number(c.map_or(f64::NAN, |c| *c as u32 as f64)),
+                "at" => c.map_or(JSValue::Undefined, |c| JSValue::String(c.to_string())),
+                _ => JSValue::String(c.map(char::to_string).unwrap_or_default()),
+            }
+        }
+        "concat" => JSValue::String(text.to_string() + &args.iter().map(|arg| js_string(&arg.value())).collect::<String>()),
+        "localeCompare" => JSValue::Number(match text.cmp(&string(0)) {
+            std::cmp::Ordering::Less => -1.0,
+            std::cmp::Ordering::Equal => 0.0,
+            std::cmp::Ordering::Greater => 1.0,
+        }),
+        _ => bail!("TypeError: string.{} is not a function", method),
+    })
+}
+
+fn global_function(name: &str, args: &[Arg]) -> Result<JSValue> {
+    let value = arg(args, 0);
+    Ok(match name {
+        "Number" => JSValue::Number(if args.is_empty() { 0.0 } else { to_number(&value) }),
+        "String" => JSValue::String(if args.is_empty() { String::new() } else { js_string(&value) }),
+        "Boolean" => JSValue::Boolean(value.is_truthy()),
+        "isNaN" => JSValue::Boolean(to_number(&value).is_nan()),
+        "isFinite" => JSValue::Boolean(to_number(&value).is_finite()),
+        "parseFloat" => {
+            let text = js_string(&value);
+            let text = text.trim_start();
+            let end = text.char_indices()
+                .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || ((*c == '-' || *c == '+') && *i == 0) || *c == 'e' || *c == 'E')
+                .map(|(i, c)| i + c.len_utf8())
+                .last()
+                .unwrap_or(0);
+            // The longest prefix that is a number, e.g. "3.5px" is 3.5
+            let number = (1..=end).rev().find_map(|end| text[..end].parse::<f64>().ok());
+            JSValue::Number(number.or_else(|| text.starts_with("Infinity").then_some(f64::INFINITY)).unwrap_or(f64::NAN))
+        }
+        "parseInt" => {
+            let text = js_string(&value);
+            let text = text.trim();
+            let (negative, text) = match text.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, text.strip_prefix('+').unwrap_or(text)),
+            };
+            let mut radix = match arg(args, 1) {
+                JSValue::Undefined => 10,
+                radix => to_number(&radix) as u32,
+            };
+            let text = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(hex) if radix == 10 || radix == 16 || radix == 0 => {
+                    radix = 16;
+                    hex
+                }
+                _ => text,
+            };
+            if radix == 0 {
+                radix = 10;
+            }
+            if !(2..=36).contains(&radix) {
+                return Ok(JSValue::Number(f64::NAN));
+            }
+            let digits: String = text.chars().take_while(|c| c.is_digit(radix)).collect();
+            let n = digits.chars().fold(None, |n: Option<f64>, c| Some(n.unwrap_or(0.0) * radix as f64 + c.to_digit(radix).unwrap_or(0) as f64));
+            JSValue::Number(n.map_or(f64::NAN, |n| if negative { -n } else { n }))
+        }
+        "encodeURIComponent" => JSValue::String(encode_uri(&js_string(&value), "-_.!~*'()")),
+        "encodeURI" => JSValue::String(encode_uri(&js_string(&value), "-_.!~*'();/?:@&=+$,#")),
+        "decodeURIComponent" | "decodeURI" => match urlencoding::decode(&js_string(&value)) {
+            Ok(decoded) => JSValue::String(decoded.into_owned()),
+            Err(_) => bail!("URIError: URI malformed"),
+        },
+        _ => bail!("TypeError: {} is not a function", name),
+    })
+}
+
+fn encode_uri(text: &str, unreserved: &str) -> String {
+    let mut encoded = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || unreserved.contains(c) {
+            encoded.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    encoded
+}
+
+fn static_constant(namespace: &str, name: &str) -> Option<f64> {
+    Some(match (namespace, name) {
+        ("Math", "PI") => std::f64::consts::PI,
+        ("Math", "E") => std::f64::consts::E,
+        ("Math", "LN2") => std::f64::consts::LN_2,
+        ("Math", "LN10") => std::f64::consts::LN_10,
+        ("Math", "SQRT2") => std::f64::consts::SQRT_2,
+        ("Number", "MAX_SAFE_INTEGER") => 9007199254740991.0,
+        ("Number", "MIN_SAFE_INTEGER") => -9007199254740991.0,
+        ("Number", "EPSILON") => f64::EPSILON,
+        ("Number", "MAX_VALUE") => f64::MAX,
+        ("Number", "POSITIVE_INFINITY") => f64::INFINITY,
+        ("Number", "NEGATIVE_INFINITY") => f64::NEG_INFINITY,
+        ("Number", "NaN") => f64::NAN,
+        _ => return None,
+    })
+}
+
+// The instant `new Date(args)` stands for, in milliseconds; component dates are taken as UTC
+fn new_date(args: &[Arg]) -> f64 {
+    match args.iter().map(Arg::value).collect::<Vec<_>>().as_slice() {
+        [] => chrono::Utc::now().timestamp_millis() as f64,
+        [JSValue::Number(time)] => *time,
+        [JSValue::String(text)] => parse_date(text),
+        components => utc_date(&components.iter().map(to_number).collect::<Vec<_>>()),
+    }
+}
+
+fn parse_date(text: &str) -> f64 {
+    let text = text.trim();
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(text) {
+        return date.timestamp_millis() as f64;
+    }
+    if let Ok(date) = chrono::DateTime::parse_from_rfc2822(text) {
+        return date.timestamp_millis() as f64;
+    }
+    if let Ok(date) = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        return date.and_utc().timestamp_millis() as f64;
+    }
+    match chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(date) => date.and_hms_opt(0, 0, 0).map_or(f64::NAN, |date| date.and_utc().timestamp_millis() as f64),
+        Err(_) => f64::NAN,
+    }
+}
+
+// `Date.UTC(year, monthIndex, day, hours, minutes, seconds, ms)`, with months counted from zero
+fn utc_date(components: &[f64]) -> f64 {
+    if components.iter().any(|n| !n.is_finite()) {
+        return f64::NAN;
+    }
+    let part = |index: usize, default: f64| components.get(index).copied().unwrap_or(default);
+    let months = part(0, 1970.0) * 12.0 + part(1, 0.0);
+    let (year, month) = ((months / 12.0).floor() as i32, (months.rem_euclid(12.0)) as u32 + 1);
+    let Some(first) = chrono::NaiveDate::from_ymd_opt(year, month, 1) else { return f64::NAN };
+    let Some(midnight) = first.and_hms_opt(0, 0, 0) else { return f64::NAN };
+    let base = midnight.and_utc().timestamp_millis() as f64;
+    base + (part(2, 1.0) - 1.0) * 86_400_000.0 + part(3, 0.0) * 3_600_000.0 + part(4, 0.0) * 60_000.0 + part(5, 0.0) * 1000.0 + part(6, 0.0)
+}
+
+fn to_radix(n: f64, radix: u32) -> Result<String> {
+    if !(2..=36).contains(&radix) {
+        bail!("RangeError: toString() radix must be between 2 and 36");
+    }
+    if !n.is_finite() || n.fract() != 0.0 {
+        return Ok(number_to_string(n));
+    }
+    let mut value = n.abs() as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit((value % radix as u64) as u32, radix).unwrap_or('0'));
+        value /= radix as u64;
+        if value == 0 {
+            break;
+        }
+    }
+    if n < 0.0 {
+        digits.push('-');
+    }
+    Ok(digits.into_iter().rev().collect())
+}
+
+// Start and end indices for slice(begin, end), where negative positions count from the end
+fn slice_range(length: f64, start: &JSValue, end: &JSValue) -> (usize, usize) {
+    let position = |value: &JSValue, default: f64| {
+        let n = match value {
+            JSValue::Undefined => default,
+            value => to_number(value).trunc(),
+        };
+        let n = if n.is_nan() { 0.0 } else { n };
+        (if n < 0.0 { length + n } else { n }).clamp(0.0, length) as usize
+    };
+    (position(start, 0.0), position(end, length))
+}
+
+fn flatten(items: Vec<JSValue>, depth: usize) -> Vec<JSValue> {
+    items.into_iter().flat_map(|item| match item {
+        JSValue::Array(inner) if depth > 0 => flatten(inner, depth - 1),
+        item => vec![item],
+    }).collect()
+}
+
+fn get_property(object: &JSValue, property: &str) -> Result<JSValue> {
+    let index = property.parse::<usize>().ok();
+    Ok(match object {
+        JSValue::String(text) => match (property, index) {
+            ("length", _) => JSValue::Number(text.chars().count() as f64),
+            (_, Some(index)) => text.chars().nth(index).map_or(JSValue::Undefined, |c| JSValue::String(c.to_string())),
+            _ => JSValue::Undefined,
+        },
+        JSValue::Array(items) => match (property, index) {
+            ("length", _) => JSValue::Number(items.len() as f64),
+            (_, Some(index)) => items.get(index).cloned().unwrap_or(JSValue::Undefined),
+            _ => JSValue::Undefined,
+        },
+        JSValue::Object(fields) => fields.get(property).cloned().unwrap_or(JSValue::Undefined),
+        JSValue::RegExp { source, flags } => match property {
+            "source" => JSValue::String(source.clone()),
+            "flags" => JSValue::String(flags.clone()),
+            "global" => JSValue::Boolean(flags.contains('g')),
+            "ignoreCase" => JSValue::Boolean(flags.contains('i')),
+            "multiline" => JSValue::Boolean(flags.contains('m')),
+            "sticky" => JSValue::Boolean(flags.contains('y')),
+            "unicode" => JSValue::Boolean(flags.contains('u')),
+            "lastIndex" => JSValue::Number(0.0),
+            _ => JSValue::Undefined,
+        },
+        JSValue::Null | JSValue::Undefined => {
+            bail!("TypeError: Cannot read properties of {} (reading '{}')", js_string(object), property)
+        }
+        _ => JSValue::Undefined,
+    })
+}
+
+fn binary(op: &str, left: &JSValue, right: &JSValue) -> Result<JSValue> {
+    let numbers = || (to_number(left), to_number(right));
+    Ok(match op {
+        "+" => match (left, right) {
+            (JSValue::Number(a), JSValue::Number(b)) => JSValue::Number(a + b),
+            (JSValue::Number(_) | JSValue::Boolean(_) | JSValue::Null | JSValue::Undefined, JSValue::Number(_) | JSValue::Boolean(_) | JSValue::Null | JSValue::Undefined) => {
+                JSValue::Number(to_number(left) + to_number(right))
+            }
+            _ => JSValue::String(js_string(left) + &js_string(right)),
+        },
+        "-" => JSValue::Number(numbers().0 - numbers().1),
+        "*" => JSValue::Number(numbers().0 * numbers().1),
+        "/" => JSValue::Number(numbers().0 / numbers().1),
+        "%" => JSValue::Number(numbers().0 % numbers().1),
+        "===" => JSValue::Boolean(strict_equals(left, right)),
+        "!==" => JSValue::Boolean(!strict_equals(left, right)),
+        "==" => JSValue::Boolean(loose_equals(left, right)),
+        "!=" => JSValue::Boolean(!loose_equals(left, right)),
+        "<" | ">" | "<=" | ">=" => {
+            let order = match (left, right) {
+                (JSValue::String(a), JSValue::String(b)) => Some(a.cmp(b)),
+                _ => numbers().0.partial_cmp(&numbers().1),
+            };
+            JSValue::Boolean(order.is_some_and(|order| match op {
+                "<" => order.is_lt(),
+                ">" => order.is_gt(),
+                "<=" => order.is_le(),
+                _ => order.is_ge(),
+            }))
+        }
+        "in" => JSValue::Boolean(match right {
+            JSValue::Object(fields) => fields.contains_key(&js_string(left)),
+            JSValue::Array(items) => js_string(left) == "length" || js_string(left).parse::<usize>().is_ok_and(|i| i < items.len()),
+            _ => bail!("TypeError: Cannot use 'in' operator to search for '{}' in {}", js_string(left), js_string(right)),
+        }),
+        _ => bail!("SyntaxError: Unexpected operator {}", op),
+    })
+}
+
+fn to_number(value: &JSValue) -> f64 {
+    match value {
+        JSValue::Number(n) => *n,
+        JSValue::Boolean(b) => if *b { 1.0 } else { 0.0 },
+        JSValue::Null => 0.0,
+        JSValue::Undefined | JSValue::Object(_) | JSValue::RegExp { .. } => f64::NAN,
+        JSValue::String(text) => {
+            let text = text.trim();
+            if text.is_empty() {
+                0.0
+            } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                i64::from_str_radix(hex, 16).map_or(f64::NAN, |n| n as f64)
+            } else {
+                match text {
+                    "Infinity" | "+Infinity" => f64::INFINITY,
+                    "-Infinity" => f64::NEG_INFINITY,
+                    // Rust also reads "inf" and "nan", which JavaScript doesn't
+                    text if text.chars().any(|c| c.is_ascii_alphabetic() && c != 'e' && c != 'E') => f64::NAN,
+                    text => text.parse().unwrap_or(f64::NAN),
+                }
+            }
+        }
+        JSValue::Array(items) => match items.as_slice() {
+            [] => 0.0,
+            [item] => to_number(&JSValue::String(js_string(item))),
+            _ => f64::NAN,
+        },
+    }
+}
+
+/// A value converted to a string as JavaScript's String() does, e.g. in concatenation and templates
+pub fn js_string(value: &JSValue) -> String {
+    match value {
+        JSValue::Number(n) => number_to_string(*n),
+        JSValue::Array(items) => items.iter().map(element_string).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+// Arrays print their null and undefined elements as empty strings
+fn element_string(value: &JSValue) -> String {
+    match value {
+        JSValue::Null | JSValue::Undefined => String::new(),
+        value => js_string(value),
+    }
+}
+
+fn type_of(value: &JSValue) -> &'static str {
+    match value {
+        JSValue::String(_) => "string",
+        JSValue::Number(_) => "number",
+        JSValue::Boolean(_) => "boolean",
+        JSValue::Undefined => "undefined",
+        JSValue::Null | JSValue::Object(_) | JSValue::Array(_) | JSValue::RegExp { .. } => "object",
+    }
+}
+
+// Objects and arrays compare by contents, since values here are copies without an identity
+fn strict_equals(a: &JSValue, b: &JSValue) -> bool {
+    match (a, b) {
+        (JSValue::Number(a), JSValue::Number(b)) => a == b,
+        (JSValue::String(a), JSValue::String(b)) => a == b,
+        (JSValue::Boolean(a), JSValue::Boolean(b)) => a == b,
+        (JSValue::Null, JSValue::Null) | (JSValue::Undefined, JSValue::Undefined) => true,
+        (JSValue::Array(a), JSValue::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| strict_equals(a, b)),
+        (JSValue::RegExp { source: a, flags: a_flags }, JSValue::RegExp { source: b, flags: b_flags }) => a == b && a_flags == b_flags,
+        (JSValue::Object(a), JSValue::Object(b)) => a.len() == b.len() && a.iter().all(|(key, value)| b.get(key).is_some_and(|other| strict_equals(value, other))),
+        _ => false,
+    }
+}
+
+fn same_value_zero(a: &JSValue, b: &JSValue) -> bool {
+    matches!((a, b), (JSValue::Number(a), JSValue::Number(b)) if a.is_nan() && b.is_nan()) || strict_equals(a, b)
+}
+
+fn loose_equals(a: &JSValue, b: &JSValue) -> bool {
+    match (a, b) {
+        (JSValue::Null | JSValue::Undefined, JSValue::Null | JSValue::Undefined) => true,
+        (JSValue::Null | JSValue::Undefined, _) | (_, JSValue::Null | JSValue::Undefined) => false,
+        (JSValue::Number(_) | JSValue::Boolean(_), _) | (_, JSValue::Number(_) | JSValue::Boolean(_)) => to_number(a) == to_number(b),
+        _ => strict_equals(a, b),
+    }
+}
+
+// Object fields in a stable order, as JSON output and Object.keys give them
+fn sorted_fields(fields: HashMap<String, JSValue>) -> Vec<(String, JSValue)> {
+    let mut fields: Vec<_> = fields.into_iter().collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    fields
+}
+
+fn json_value(value: &JSValue) -> Option<serde_json::Value> {
+    Some(match value {
+        JSValue::Undefined => return None,
+        JSValue::Null => serde_json::Value::Null,
+        JSValue::Boolean(b) => serde_json::Value::Bool(*b),
+        JSValue::String(text) => serde_json::Value::String(text.clone()),
+        JSValue::Number(n) if !n.is_finite() => serde_json::Value::Null,
+        // Whole numbers print without a fraction, as JavaScript prints them
+        JSValue::Number(n) if n.fract() == 0.0 && n.abs() < 9007199254740992.0 => serde_json::Value::from(*n as i64),
+        JSValue::Number(n) => serde_json::Value::from(*n),
+        JSValue::Array(items) => serde_json::Value::Array(items.iter().map(|item| json_value(item).unwrap_or(serde_json::Value::Null)).collect()),
+        JSValue::Object(fields) => serde_json::Value::Object(fields.iter().filter_map(|(key, value)| Some((key.clone(), json_value(value)?))).collect()),
+        JSValue::RegExp { .. } => serde_json::Value::Object(serde_json::Map::new()),
+    })
+}
+
+fn from_json_value(json: &serde_json::Value) -> JSValue {
+    match json {
+        serde_json::Value::Null => JSValue::Null,
+        serde_json::Value::Bool(b) => JSValue::Boolean(*b),
+        serde_json::Value::Number(n) => JSValue::Number(n.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(text) => JSValue::String(text.clone()),
+        serde_json::Value::Array(items) => JSValue::Array(items.iter().map(from_json_value).collect()),
+        serde_json::Value::Object(fields) => JSValue::Object(fields.iter().map(|(key, value)| (key.clone(), from_json_value(value))).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Variables(HashMap<String, JSValue>);
+
+    impl Host for Variables {
+        fn lookup(&self, path: &str) -> Option<JSValue> {
+            self.0.get(path).cloned()
+        }
+
+        fn call(&self, _path: &str, _args: &[JSValue]) -> Option<JSValue> {
+            None
+        }
+
+        fn date_method(&self, time: f64, _method: &str) -> Result<JSValue> {
+            Ok(JSValue::Number(time))
+        }
+    }
+
+    fn run(source: &str) -> String {
+        let items = JSValue::Array(vec![JSValue::Number(3.0), JSValue::Number(1.0), JSValue::Number(2.0)]);
+        let host = Variables(HashMap::from([("items".to_string(), items)]));
+        match evaluate(source, &host) {
+            Ok(value) => js_string(&value),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_and_array_methods() {
+        assert_eq!(run(r#"JSON.stringify(JSON.parse('{"a":[1,2.5,"x"],"b":null}'))"#), r#"{"a":[1,2.5,"x"],"b":null}"#);
+        assert_eq!(run("JSON.stringify({ n: 1, skip: undefined, list: [undefined] })"), r#"{"list":[null],"n":1}"#);
+        assert_eq!(run("JSON.stringify([1], null, 2)"), "[\n  1\n]");
+        assert!(run("JSON.parse('{bad')").starts_with("SyntaxError"));
+        assert_eq!(run("items.map(n => n * 2).filter(n => n > 2).join('-')"), "6-4");
+        assert_eq!(run("items.reduce((sum, n) => sum + n, 0)"), "6");
+        assert_eq!(run("[...items].sort((a, b) => a - b)"), "1,2,3");
+        assert_eq!(run("Object.keys({ b: 1, a: 2 }).length + Math.max(...items)"), "5");
+        assert!(run("items.reduce(function (a, b) { return missing })").starts_with("ReferenceError"));
+    }
+
+    #[test]
+    fn test_strings_and_numbers() {
+        assert_eq!(run("`${items.length} items, first ${items[0]}`"), "3 items, first 3");
+        assert_eq!(run("'a,b,,c'.split(',').slice(1, -1)"), "b,");
+        assert_eq!(run("' Neon '.trim().toUpperCase().padStart(6, '*')"), "**NEON");
+        assert_eq!(run("'1' + 2 + 3 === '123' && 1 + 2 + '3' === '33'"), "true");
+        assert_eq!(run("(0.1 + 0.2).toFixed(2) + ' ' + parseInt('42px') + ' ' + 10 / 0"), "0.30 42 Infinity");
+        assert_eq!(run("typeof JSON + typeof items + typeof nothing"), "objectobjectundefined");
+        assert_eq!(run("encodeURIComponent('a b&c')"), "a%20b%26c");
+    }
+
+    #[test]
+    fn test_regular_expressions() {
+        assert_eq!(run(r"/^[\w.]+@\w+\.\w+$/.test('ada@example.com') && !/^\d+$/.test('12a')"), "true");
+        assert_eq!(run(r"'2024-05-01'.replace(/(\d+)-(\d+)-(\d+)/, '$3/$2/$1')"), "01/05/2024");
+        assert_eq!(run(r"'a1b22c333'.match(/\d+/g).join('|')"), "1|22|333");
+        assert_eq!(run(r"'/users/42/posts'.match(/^\/users\/(?<id>\d+)/).groups.id"), "42");
+        assert_eq!(run(r"/(\w)(\d)/.exec('--x9--').index + ':' + /(\w)(\d)/.exec('--x9--')[2]"), "2:9");
+        assert_eq!(run(r"'one  two,three'.split(/[\s,]+/)"), "one,two,three");
+        assert_eq!(run(r"'hello world'.replace(/o/g, c => c.toUpperCase())"), "hellO wOrld");
+        assert_eq!(run(r"new RegExp('a+', 'gi').flags + String(RegExp('x').test('X'))"), "gifalse");
+        assert_eq!(run("10 / 2 / 5"), "1");
+        assert!(run("/(?=a)/.test('a')").starts_with("SyntaxError: Invalid regular expression"));
+        assert!(run("'abc'.matchAll(/b/)").starts_with("TypeError"));
+    }
+}
@@ -27,10 +27,20 @@ impl ConsoleAPI {
         self.output.borrow_mut().push(format!("[INFO] {}", message));
     }
     
+    /// Lines already formatted by another console, such as a worker's
+    pub fn append(&self, lines: Vec<String>) {
+        self.output.borrow_mut().extend(lines);
+    }
+    
     pub fn get_output(&self) -> Vec<String> {
         self.output.borrow().clone()
     }
     
+    /// Messages logged since the last call
+    pub fn take_output(&self) -> Vec<String> {
+        std::mem::take(&mut *self.output.borrow_mut())
+    }
+    
     pub fn clear_output(&self) {
         self.output.borrow_mut().clear();
     }
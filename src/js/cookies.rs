@@ -0,0 +1,63 @@
+// `document.cookie`, read from and written to the same jar the page's requests use, so scripts and
+// Set-Cookie headers see one set of cookies
+use std::sync::{Arc, Mutex};
+use crate::networking::cookie_manager::CookieManager;
+
+/// The cookies one document's scripts can reach
+#[derive(Clone)]
+pub struct DocumentCookies {
+    jar: Arc<Mutex<CookieManager>>,
+    // Where the jar keeps the document's cookies: its host, or a partition of it inside a cross-site page
+    domain: String,
+    path: String,
+    secure: bool,
+}
+
+impl DocumentCookies {
+    /// Cookies for the document at `url`, kept under `domain` in `jar`; None for documents without an http(s) origin
+    pub fn new(jar: Arc<Mutex<CookieManager>>, url: &str, domain: String) -> Option<Self> {
+        let parsed = url::Url::parse(url).ok()?;
+        let secure = match parsed.scheme() {
+            "https" => true,
+            "http" => false,
+            _ => return None,
+        };
+        Some(Self { jar, domain, path: parsed.path().to_string(), secure })
+    }
+
+    /// The `name=value; ...` string reading `document.cookie` gives, without HttpOnly cookies
+    pub fn get(&self) -> String {
+        self.jar.lock().map(|jar| jar.script_cookies(&self.domain, &self.path, self.secure)).unwrap_or_default()
+    }
+
+    /// Assign `document.cookie`; false when the cookie was refused
+    pub fn set(&self, cookie: &str) -> bool {
+        self.jar.lock().is_ok_and(|mut jar| jar.set_script_cookie(cookie, &self.domain, self.default_path(), self.secure))
+    }
+
+    // The directory of the document, which cookies without a Path belong to
+    fn default_path(&self) -> &str {
+        match self.path.rfind('/') {
+            Some(0) | None => "/",
+            Some(end) => &self.path[..end],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookies_without_path_belong_to_the_directory() {
+        let jar = Arc::new(Mutex::new(CookieManager::new()));
+        let shop = DocumentCookies::new(jar.clone(), "http://example.com/shop/cart", "example.com".to_string()).unwrap();
+        assert!(shop.set("items=3"));
+        assert!(!shop.set("id=1; Secure"));
+        assert_eq!(shop.get(), "items=3");
+
+        let home = DocumentCookies::new(jar, "https://example.com/", "example.com".to_string()).unwrap();
+        assert_eq!(home.get(), "");
+        assert!(DocumentCookies::new(Arc::new(Mutex::new(CookieManager::new())), "about:blank", String::new()).is_none());
+    }
+}
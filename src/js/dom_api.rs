@@ -4,6 +4,9 @@ use std::cell::RefCell;
 use crate::engine::dom::DOMNode;
 use crate::js::JSValue;
 
+// A change made to an element's attributes and children in place
+type ElementUpdate<'a> = dyn FnMut(&mut HashMap<String, String>, &mut Vec<DOMNode>) + 'a;
+
 /// JavaScript DOM API implementation
 /// Provides JavaScript access to DOM manipulation functions
 pub struct DOMApi {
@@ -123,6 +126,67 @@ impl DOMApi {
         JSValue::Object(text_obj)
     }
     
+    /// Remove the first element matching `selector`, or every one with `all`. Returns how many were removed
+    pub fn remove_elements(&self, selector: &str, all: bool) -> usize {
+        let Some(root) = &self.document_root else { return 0 };
+        let mut remaining = if all { usize::MAX } else { 1 };
+        self.remove_matching(&mut root.borrow_mut(), selector, &mut remaining)
+    }
+    
+    /// Replace the children of matching elements with `text`, as assigning textContent does
+    pub fn set_text_content(&self, selector: &str, text: &str, all: bool) -> usize {
+        self.update_elements(selector, all, &mut |_, children| {
+            *children = vec![DOMNode::new_text(text.to_string())];
+        })
+    }
+    
+    pub fn set_attribute(&self, selector: &str, name: &str, value: &str, all: bool) -> usize {
+        self.update_elements(selector, all, &mut |attributes, _| {
+            attributes.insert(name.to_lowercase(), value.to_string());
+        })
+    }
+    
+    // Apply `update` to the first matching element, or all of them, in document order
+    fn update_elements(&self, selector: &str, all: bool, update: &mut ElementUpdate) -> usize {
+        fn visit(api: &DOMApi, node: &mut DOMNode, selector: &str, remaining: &mut usize, update: &mut ElementUpdate) -> usize {
+            if *remaining == 0 {
+                return 0;
+            }
+            let matched = api.matches_selector(node, selector);
+            let DOMNode::Element { attributes, children, .. } = node else { return 0 };
+            let mut count = 0;
+            if matched {
+                update(attributes, children);
+                *remaining -= 1;
+                count += 1;
+            }
+            for child in children.iter_mut() {
+                count += visit(api, child, selector, remaining, update);
+            }
+            count
+        }
+        let Some(root) = &self.document_root else { return 0 };
+        let mut remaining = if all { usize::MAX } else { 1 };
+        visit(self, &mut root.borrow_mut(), selector, &mut remaining, update)
+    }
+    
+    fn remove_matching(&self, node: &mut DOMNode, selector: &str, remaining: &mut usize) -> usize {
+        let DOMNode::Element { children, .. } = node else { return 0 };
+        let mut removed = 0;
+        let mut index = 0;
+        while index < children.len() && *remaining > 0 {
+            if self.matches_selector(&children[index], selector) {
+                children.remove(index);
+                *remaining -= 1;
+                removed += 1;
+            } else {
+                removed += self.remove_matching(&mut children[index], selector, remaining);
+                index += 1;
+            }
+        }
+        removed
+    }
+    
     // Private helper methods for DOM traversal
     
     fn find_first_element(&self, node: &Rc<RefCell<DOMNode>>, selector: &str) -> Option<Rc<RefCell<DOMNode>>> {
@@ -40,12 +40,12 @@ impl EventSystem {
     pub fn trigger_event(&self, event: EventData) -> Result<()> {
         // This would be called from the UI layer when events occur
         // For now, just log the event
-        println!("Event triggered: {:?}", event);
+        log::debug!("Event triggered: {:?}", event);
         
         // In a full implementation, we'd execute the JavaScript callbacks here
         if let Some(callbacks) = self.listeners.borrow().get(&event.event_type) {
             for callback in callbacks {
-                println!("Would execute callback: {}", callback);
+                log::debug!("Would execute callback: {}", callback);
             }
         }
         
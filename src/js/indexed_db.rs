@@ -0,0 +1,196 @@
+// `indexedDB` for page scripts: a synchronous, JSON-in JSON-out surface over the storage layer
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use crate::storage::indexed_db::{CursorDirection, IdbKey, IndexedDatabase, KeyRange, StoreOptions, StoreScope};
+
+/// Storage calls made by one page, confined to the origin it was loaded from
+pub struct IndexedDbApi {
+    db: Option<Arc<IndexedDatabase>>,
+    origin: Option<String>,
+}
+
+impl IndexedDbApi {
+    pub fn new() -> Self {
+        Self::with_database(None)
+    }
+
+    /// Use `db` instead of the browser-wide store, which is otherwise opened on first use
+    pub fn with_database(db: Option<Arc<IndexedDatabase>>) -> Self {
+        Self { db, origin: None }
+    }
+
+    /// Scope storage to the origin of `url`. Pages without a tuple origin (about:, data:, file:) get none.
+    pub fn set_document_url(&mut self, url: &str) {
+        self.origin = url::Url::parse(url).ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(|url| url.origin().ascii_serialization());
+    }
+
+    /// Run `indexedDB.<method>(...args)`, where every argument is a JSON value
+    pub fn call(&mut self, method: &str, args: &[Value]) -> Result<Value> {
+        let origin = self.origin.clone()
+            .ok_or_else(|| anyhow!("SecurityError: storage is not available on this page"))?;
+        if self.db.is_none() {
+            self.db = IndexedDatabase::shared();
+        }
+        let db = self.db.as_ref().ok_or_else(|| anyhow!("UnknownError: storage could not be opened"))?;
+
+        let scope = || -> Result<StoreScope> {
+            Ok(StoreScope { origin: origin.clone(), database: string_arg(args, 0)?, store: string_arg(args, 1)? })
+        };
+
+        match method {
+            "databases" => Ok(json!(db.database_names(&origin)?)),
+            "objectStoreNames" => Ok(json!(db.object_store_names(&origin, &string_arg(args, 0)?)?)),
+            "deleteDatabase" => db.delete_database(&origin, &string_arg(args, 0)?).map(|_| Value::Null),
+            "estimate" => Ok(json!({ "usage": db.usage(&origin)?, "quota": db.quota() })),
+            "createObjectStore" => {
+                let options = args.get(2);
+                let key_path = options.and_then(|o| o.get("keyPath")).and_then(Value::as_str).map(str::to_string);
+                let auto_increment = options.and_then(|o| o.get("autoIncrement")).and_then(Value::as_bool).unwrap_or(false);
+                db.create_object_store(&scope()?, &StoreOptions { key_path, auto_increment }).map(|_| Value::Null)
+            }
+            "deleteObjectStore" => db.delete_object_store(&scope()?).map(|_| Value::Null),
+            "createIndex" => {
+                let unique = args.get(4).and_then(|o| o.get("unique")).and_then(Value::as_bool).unwrap_or(false);
+                db.create_index(&scope()?, &string_arg(args, 2)?, &string_arg(args, 3)?, unique).map(|_| Value::Null)
+            }
+            "put" | "add" => {
+                let scope = scope()?;
+                let value = args.get(2).cloned().ok_or_else(|| anyhow!("TypeError: {} needs a value", method))?;
+                let key = args.get(3).map(key_arg).transpose()?;
+                if method == "add" {
+                    if let Some(key) = &key {
+                        if db.get(&scope, key)?.is_some() {
+                            bail!("ConstraintError: a record with that key already exists");
+                        }
+                    }
+                }
+                Ok(db.put(&scope, key, value)?.to_json())
+            }
+            "get" => {
+                let key = key_arg(args.get(2).unwrap_or(&Value::Null))?;
+                Ok(db.get(&scope()?, &key)?.unwrap_or(Value::Null))
+            }
+            "getAll" | "getAllKeys" | "count" => {
+                let query = Query::parse(args.get(2))?;
+                let mut cursor = db.open_cursor(&scope()?, query.index.as_deref(), query.range, query.direction)?;
+                let mut results = Vec::new();
+                while results.len() < query.limit {
+                    let Some(record) = cursor.advance()? else { break };
+                    results.push(match method {
+                        "getAll" => record.value,
+                        _ => record.primary_key.to_json(),
+                    });
+                }
+                Ok(if method == "count" { json!(results.len()) } else { Value::Array(results) })
+            }
+            "delete" => {
+                let range = match args.get(2) {
+                    Some(Value::Object(_)) => Query::parse(args.get(2))?.range,
+                    Some(key) => KeyRange::only(key_arg(key)?),
+                    None => bail!("TypeError: delete needs a key or range"),
+                };
+                Ok(json!(db.delete(&scope()?, &range)?))
+            }
+            "clear" => db.clear(&scope()?).map(|_| Value::Null),
+            _ => bail!("TypeError: indexedDB.{} is not a function", method),
+        }
+    }
+}
+
+impl Default for IndexedDbApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn string_arg(args: &[Value], position: usize) -> Result<String> {
+    args.get(position)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("TypeError: argument {} must be a string", position + 1))
+}
+
+fn key_arg(value: &Value) -> Result<IdbKey> {
+    IdbKey::from_json(value).ok_or_else(|| anyhow!("DataError: {} is not a valid key", value))
+}
+
+// Options object for getAll/count/delete: {index, only, lower, upper, lowerOpen, upperOpen, direction, limit}
+struct Query {
+    index: Option<String>,
+    range: KeyRange,
+    direction: CursorDirection,
+    limit: usize,
+}
+
+impl Query {
+    fn parse(options: Option<&Value>) -> Result<Self> {
+        let option = |name: &str| options.and_then(|o| o.get(name)).filter(|v| !v.is_null());
+        let key = |name: &str| option(name).map(key_arg).transpose();
+
+        let range = match key("only")? {
+            Some(only) => KeyRange::only(only),
+            None => KeyRange {
+                lower: key("lower")?,
+                upper: key("upper")?,
+                lower_open: option("lowerOpen").and_then(Value::as_bool).unwrap_or(false),
+                upper_open: option("upperOpen").and_then(Value::as_bool).unwrap_or(false),
+            },
+        };
+        let direction = match option("direction").and_then(Value::as_str) {
+            Some("prev") => CursorDirection::Prev,
+            Some("next") | None => CursorDirection::Next,
+            Some(other) => bail!("TypeError: unknown cursor direction '{}'", other),
+        };
+
+        Ok(Self {
+            index: option("index").and_then(Value::as_str).map(str::to_string),
+            range,
+            direction,
+            limit: option("limit").and_then(Value::as_u64).map_or(usize::MAX, |n| n as usize),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::indexed_db::DEFAULT_ORIGIN_QUOTA;
+
+    fn api(url: &str, db: &Arc<IndexedDatabase>) -> IndexedDbApi {
+        let mut api = IndexedDbApi::with_database(Some(db.clone()));
+        api.set_document_url(url);
+        api
+    }
+
+    #[test]
+    fn test_page_api_is_origin_scoped() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("test_js_indexeddb_{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(IndexedDatabase::new(&path, DEFAULT_ORIGIN_QUOTA)?);
+        let mut page = api("https://app.example/inbox?x=1", &db);
+
+        page.call("createObjectStore", &[json!("mail"), json!("messages"), json!({"keyPath": "id", "autoIncrement": true})])?;
+        page.call("createIndex", &[json!("mail"), json!("messages"), json!("by_from"), json!("from")])?;
+        for from in ["ann", "bob", "ann"] {
+            page.call("put", &[json!("mail"), json!("messages"), json!({ "from": from })])?;
+        }
+
+        let query = json!({"index": "by_from", "only": "ann", "direction": "prev"});
+        assert_eq!(page.call("getAllKeys", &[json!("mail"), json!("messages"), query])?, json!([3, 1]));
+        assert_eq!(page.call("get", &[json!("mail"), json!("messages"), json!(2)])?, json!({"id": 2, "from": "bob"}));
+        assert_eq!(page.call("count", &[json!("mail"), json!("messages"), json!({"lower": 2, "limit": 1})])?, json!(1));
+        assert!(page.call("add", &[json!("mail"), json!("messages"), json!({"from": "x"}), json!(1)]).is_err());
+        assert_eq!(page.call("delete", &[json!("mail"), json!("messages"), json!(1)])?, json!(1));
+        assert_eq!(page.call("databases", &[])?, json!(["mail"]));
+
+        // Same site on another port is another origin
+        let mut other = api("https://app.example:8443/", &db);
+        assert_eq!(other.call("databases", &[])?, json!([]));
+
+        let mut blank = api("about:blank", &db);
+        assert!(blank.call("databases", &[]).unwrap_err().to_string().starts_with("SecurityError"));
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+// Locale and time zone seen by page scripts, which dev tools can override per tab
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc, Weekday};
+
+/// What a tab pretends its user's language and location are. Unset fields use the system's.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocaleOverrides {
+    /// Sent as the Accept-Language request header
+    pub accept_language: Option<String>,
+    /// BCP 47 tag for `navigator.language` and `Intl`
+    pub locale: Option<String>,
+    /// IANA zone name, `Etc/GMT±N` or a fixed `+HH:MM` offset, for `Date` and `Intl`
+    pub time_zone: Option<String>,
+}
+
+impl LocaleOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.accept_language.is_none() && self.locale.is_none() && self.time_zone.is_none()
+    }
+
+    /// Accept-Language to send instead of the default, if anything is overridden
+    pub fn accept_language_header(&self) -> Option<String> {
+        self.accept_language.clone().or_else(|| {
+            // A locale override alone still changes the language sites see
+            let locale = self.locale.as_deref()?;
+            match locale.split_once('-') {
+                Some((language, _)) => Some(format!("{},{};q=0.9", locale, language)),
+                None => Some(locale.to_string()),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DstRule {
+    None,
+    // Second Sunday in March to the first Sunday in November, at 02:00 local time
+    NorthAmerica,
+    // Last Sunday in March to the last Sunday in October, at 01:00 UTC
+    Europe,
+}
+
+// Zones that can be emulated: name, standard offset in minutes east of UTC, daylight saving rule
+const TIME_ZONES: &[(&str, i32, DstRule)] = &[
+    ("UTC", 0, DstRule::None),
+    ("Europe/London", 0, DstRule::Europe),
+    ("Europe/Dublin", 0, DstRule::Europe),
+    ("Europe/Lisbon", 0, DstRule::Europe),
+    ("Europe/Paris", 60, DstRule::Europe),
+    ("Europe/Berlin", 60, DstRule::Europe),
+    ("Europe/Madrid", 60, DstRule::Europe),
+    ("Europe/Rome", 60, DstRule::Europe),
+    ("Europe/Amsterdam", 60, DstRule::Europe),
+    ("Europe/Stockholm", 60, DstRule::Europe),
+    ("Europe/Warsaw", 60, DstRule::Europe),
+    ("Europe/Athens", 120, DstRule::Europe),
+    ("Europe/Helsinki", 120, DstRule::Europe),
+    ("Europe/Kyiv", 120, DstRule::Europe),
+    ("Europe/Istanbul", 180, DstRule::None),
+    ("Europe/Moscow", 180, DstRule::None),
+    ("Africa/Lagos", 60, DstRule::None),
+    ("Africa/Johannesburg", 120, DstRule::None),
+    ("Africa/Nairobi", 180, DstRule::None),
+    ("Asia/Dubai", 240, DstRule::None),
+    ("Asia/Karachi", 300, DstRule::None),
+    ("Asia/Kolkata", 330, DstRule::None),
+    ("Asia/Dhaka", 360, DstRule::None),
+    ("Asia/Bangkok", 420, DstRule::None),
+    ("Asia/Jakarta", 420, DstRule::None),
+    ("Asia/Shanghai", 480, DstRule::None),
+    ("Asia/Singapore", 480, DstRule::None),
+    ("Asia/Hong_Kong", 480, DstRule::None),
+    ("Asia/Tokyo", 540, DstRule::None),
+    ("Asia/Seoul", 540, DstRule::None),
+    ("Australia/Brisbane", 600, DstRule::None),
+    ("Pacific/Honolulu", -600, DstRule::None),
+    ("America/Anchorage", -540, DstRule::NorthAmerica),
+    ("America/Los_Angeles", -480, DstRule::NorthAmerica),
+    ("America/Denver", -420, DstRule::NorthAmerica),
+    ("America/Phoenix", -420, DstRule::None),
+    ("America/Chicago", -360, DstRule::NorthAmerica),
+    ("America/Mexico_City", -360, DstRule::None),
+    ("America/New_York", -300, DstRule::NorthAmerica),
+    ("America/Toronto", -300, DstRule::NorthAmerica),
+    ("America/Sao_Paulo", -180, DstRule::None),
+    ("America/Argentina/Buenos_Aires", -180, DstRule::None),
+];
+
+/// Names offered by the dev tools time zone picker
+pub fn time_zone_names() -> impl Iterator<Item = &'static str> {
+    TIME_ZONES.iter().map(|(name, _, _)| *name)
+}
+
+/// A time zone scripts see, with enough rules to get the common zones' offsets right
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatedTimeZone {
+    name: String,
+    standard_offset: i32,
+    dst: DstRule,
+}
+
+impl EmulatedTimeZone {
+    /// A known zone name, `Etc/GMT±N` or a fixed offset such as `+05:30`
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if let Some((known, offset, dst)) = TIME_ZONES.iter().find(|(n, _, _)| n.eq_ignore_ascii_case(name)) {
+            return Some(Self { name: known.to_string(), standard_offset: *offset, dst: *dst });
+        }
+        if matches!(name.to_ascii_uppercase().as_str(), "GMT" | "ETC/UTC" | "ETC/GMT" | "Z") {
+            return Self::parse("UTC");
+        }
+        // POSIX-style names have the sign inverted: Etc/GMT+5 is five hours behind UTC
+        if let Some(hours) = name.strip_prefix("Etc/GMT") {
+            let hours: i32 = hours.parse().ok().filter(|h: &i32| (-14..=12).contains(h))?;
+            return Some(Self { name: name.to_string(), standard_offset: -hours * 60, dst: DstRule::None });
+        }
+        let offset = name.strip_prefix("UTC").unwrap_or(name);
+        let (sign, rest) = match offset.chars().next()? {
+            '+' => (1, &offset[1..]),
+            '-' => (-1, &offset[1..]),
+            _ => return None,
+        };
+        let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+        let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        if hours > 14 || minutes >= 60 {
+            return None;
+        }
+        Some(Self { name: format!("{}{:02}:{:02}", if sign > 0 { '+' } else { '-' }, hours, minutes), standard_offset: sign * (hours * 60 + minutes), dst: DstRule::None })
+    }
+
+    /// The zone the machine runs in: `TZ`, then /etc/timezone, then the current offset as a fixed zone
+    pub fn system() -> Self {
+        std::env::var("TZ").ok()
+            .or_else(|| std::fs::read_to_string("/etc/timezone").ok())
+            .and_then(|name| Self::parse(name.trim_start_matches(':')))
+            .unwrap_or_else(|| {
+                let offset = chrono::Local::now().offset().local_minus_utc() / 60;
+                Self::parse(&format!("{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 60, offset.abs() % 60))
+                    .unwrap_or_else(|| Self { name: "UTC".to_string(), standard_offset: 0, dst: DstRule::None })
+            })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Minutes east of UTC at the instant `at`
+    pub fn offset_minutes(&self, at: DateTime<Utc>) -> i32 {
+        let year = at.year();
+        let daylight = match self.dst {
+            DstRule::None => false,
+            DstRule::NorthAmerica => {
+                let start = nth_weekday(year, 3, 2) + Duration::hours(2) - Duration::minutes(self.standard_offset as i64);
+                let end = nth_weekday(year, 11, 1) + Duration::hours(2) - Duration::minutes(self.standard_offset as i64 + 60);
+                at >= start && at < end
+            }
+            DstRule::Europe => {
+                let start = last_sunday(year, 3) + Duration::hours(1);
+                let end = last_sunday(year, 10) + Duration::hours(1);
+                at >= start && at < end
+            }
+        };
+        self.standard_offset + if daylight { 60 } else { 0 }
+    }
+
+    /// Wall-clock time in this zone at `at`
+    pub fn local_time(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.naive_utc() + Duration::minutes(self.offset_minutes(at) as i64)
+    }
+}
+
+// Midnight UTC on the `n`th Sunday of `month`
+fn nth_weekday(year: i32, month: u32, n: u32) -> DateTime<Utc> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default();
+    let to_sunday = (7 - first.weekday().num_days_from_sunday()) % 7;
+    let day = first + Duration::days((to_sunday + (n - 1) * 7) as i64);
+    Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+fn last_sunday(year: i32, month: u32) -> DateTime<Utc> {
+    let next_month = NaiveDate::from_ymd_opt(year, month + 1, 1).unwrap_or_default();
+    let mut day = next_month - Duration::days(1);
+    while day.weekday() != Weekday::Sun {
+        day -= Duration::days(1);
+    }
+    Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// The locale and zone in effect for one page's scripts
+#[derive(Debug, Clone)]
+pub struct LocaleEnvironment {
+    pub locale: String,
+    pub languages: Vec<String>,
+    pub time_zone: EmulatedTimeZone,
+}
+
+impl LocaleEnvironment {
+    /// The system's settings with `overrides` applied; invalid overrides are ignored
+    pub fn new(overrides: &LocaleOverrides) -> Self {
+        let locale = overrides.locale.clone().unwrap_or_else(system_locale);
+        let languages = match overrides.accept_language_header() {
+            Some(header) => header.split(',')
+                .map(|language| language.split(';').next().unwrap_or("").trim().to_string())
+                .filter(|language| !language.is_empty() && language != "*")
+                .collect(),
+            None => vec![locale.clone()],
+        };
+        let time_zone = overrides.time_zone.as_deref()
+            .and_then(EmulatedTimeZone::parse)
+            .unwrap_or_else(EmulatedTimeZone::system);
+        Self { locale, languages, time_zone }
+    }
+
+    /// `Date.prototype.toString()`
+    pub fn date_string(&self, at: DateTime<Utc>) -> String {
+        let offset = self.time_zone.offset_minutes(at);
+        format!(
+            "{} GMT{}{:02}{:02} ({})",
+            self.time_zone.local_time(at).format("%a %b %d %Y %H:%M:%S"),
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 60,
+            offset.abs() % 60,
+            self.time_zone.name(),
+        )
+    }
+
+    /// `toLocaleString()`, `toLocaleDateString()` or `toLocaleTimeString()` in this locale's usual order
+    pub fn locale_string(&self, at: DateTime<Utc>, date: bool, time: bool) -> String {
+        let local = self.time_zone.local_time(at);
+        let (year, month, day) = (local.year(), local.month(), local.day());
+        let language = self.locale.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        let twelve_hour = self.locale.eq_ignore_ascii_case("en-US") || self.locale.eq_ignore_ascii_case("en");
+
+        let date_part = match language.as_str() {
+            _ if twelve_hour => format!("{}/{}/{}", month, day, year),
+            "de" | "ru" | "pl" | "cs" | "fi" | "nb" | "da" | "tr" | "uk" => format!("{:02}.{:02}.{}", day, month, year),
+            "ja" | "zh" => format!("{}/{}/{}", year, month, day),
+            "ko" => format!("{}. {}. {}.", year, month, day),
+            "sv" | "lt" => format!("{}-{:02}-{:02}", year, month, day),
+            "nl" => format!("{}-{}-{}", day, month, year),
+            _ => format!("{:02}/{:02}/{}", day, month, year),
+        };
+        let time_part = if twelve_hour {
+            let hour = match local.hour() % 12 { 0 => 12, hour => hour };
+            format!("{}:{:02}:{:02} {}", hour, local.minute(), local.second(), if local.hour() < 12 { "AM" } else { "PM" })
+        } else {
+            format!("{:02}:{:02}:{:02}", local.hour(), local.minute(), local.second())
+        };
+
+        match (date, time) {
+            (true, false) => date_part,
+            (false, true) => time_part,
+            _ if matches!(language.as_str(), "ja" | "zh" | "ko") => format!("{} {}", date_part, time_part),
+            _ => format!("{}, {}", date_part, time_part),
+        }
+    }
+}
+
+// LANG/LC_ALL such as de_DE.UTF-8, as a BCP 47 tag
+pub fn system_locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| value.split(['.', '@']).next().unwrap_or("").replace('_', "-"))
+        .find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+        .unwrap_or_else(|| "en-US".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_time_zone_offsets() {
+        let berlin = EmulatedTimeZone::parse("europe/berlin").unwrap();
+        assert_eq!(berlin.offset_minutes(at("2026-01-15T12:00:00Z")), 60);
+        assert_eq!(berlin.offset_minutes(at("2026-07-15T12:00:00Z")), 120);
+        // Summer time ends at 01:00 UTC on the last Sunday of October
+        assert_eq!(berlin.offset_minutes(at("2026-10-25T00:59:00Z")), 120);
+        assert_eq!(berlin.offset_minutes(at("2026-10-25T01:00:00Z")), 60);
+
+        let new_york = EmulatedTimeZone::parse("America/New_York").unwrap();
+        assert_eq!(new_york.offset_minutes(at("2026-03-08T06:59:00Z")), -300);
+        assert_eq!(new_york.offset_minutes(at("2026-03-08T07:00:00Z")), -240);
+        assert_eq!(new_york.offset_minutes(at("2026-11-01T06:00:00Z")), -300);
+
+        assert_eq!(EmulatedTimeZone::parse("Etc/GMT+5").unwrap().offset_minutes(Utc::now()), -300);
+        assert_eq!(EmulatedTimeZone::parse("+05:30").unwrap().offset_minutes(Utc::now()), 330);
+        assert!(EmulatedTimeZone::parse("Mars/Olympus").is_none());
+    }
+
+    #[test]
+    fn test_locale_environment() {
+        let overrides = LocaleOverrides {
+            accept_language: None,
+            locale: Some("de-DE".to_string()),
+            time_zone: Some("Asia/Tokyo".to_string()),
+        };
+        assert_eq!(overrides.accept_language_header().as_deref(), Some("de-DE,de;q=0.9"));
+
+        let environment = LocaleEnvironment::new(&overrides);
+        assert_eq!(environment.languages, vec!["de-DE", "de"]);
+        let instant = at("2026-10-15T13:05:09Z");
+        assert_eq!(environment.date_string(instant), "Thu Oct 15 2026 22:05:09 GMT+0900 (Asia/Tokyo)");
+        assert_eq!(environment.locale_string(instant, true, true), "15.10.2026, 22:05:09");
+
+        let us = LocaleEnvironment::new(&LocaleOverrides { locale: Some("en-US".to_string()), time_zone: Some("UTC".to_string()), ..Default::default() });
+        assert_eq!(us.locale_string(instant, true, true), "10/15/2026, 1:05:09 PM");
+    }
+}
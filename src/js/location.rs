@@ -0,0 +1,124 @@
+// `window.location` and `window.history`: the page's address as scripts see it, and the navigations and session
+// history changes they ask the tab for
+
+/// Something a script asked the tab to do with its navigation or back/forward list
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavigationRequest {
+    /// `location.assign(url)` and `location.href = url`; `replace` for `location.replace(url)`, which takes the
+    /// current entry's place instead of adding one
+    Navigate { url: String, replace: bool },
+    Reload,
+    /// `history.pushState`: a new entry for the same document, with the script's state
+    PushState { url: String, state: Option<String> },
+    /// `history.replaceState`: the current entry gets a new address and state
+    ReplaceState { url: String, state: Option<String> },
+    /// `history.go(delta)`, with back and forward as -1 and 1
+    Traverse(i32),
+}
+
+/// `location.<name>` for a document at `url`, or None for properties `location` doesn't have
+pub fn location_property(url: &str, name: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok();
+    let part = |f: fn(&url::Url) -> String| parsed.as_ref().map(f).unwrap_or_default();
+    Some(match name {
+        "href" => url.to_string(),
+        "origin" => parsed.as_ref().map_or_else(|| "null".to_string(), |url| url.origin().ascii_serialization()),
+        "protocol" => part(|url| format!("{}:", url.scheme())),
+        "host" => part(|url| match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        }),
+        "hostname" => part(|url| url.host_str().unwrap_or_default().to_string()),
+        "port" => part(|url| url.port().map(|port| port.to_string()).unwrap_or_default()),
+        "pathname" => part(|url| url.path().to_string()),
+        "search" => part(|url| url.query().filter(|query| !query.is_empty()).map(|query| format!("?{}", query)).unwrap_or_default()),
+        "hash" => part(|url| url.fragment().filter(|fragment| !fragment.is_empty()).map(|fragment| format!("#{}", fragment)).unwrap_or_default()),
+        _ => return None,
+    })
+}
+
+/// `target` resolved against the document at `document_url`
+pub fn resolve(document_url: &str, target: &str) -> Option<String> {
+    let base = url::Url::parse(document_url).ok()?;
+    base.join(target).ok().map(String::from)
+}
+
+/// Whether the page at `document_url` may send its tab to `target`, by script or a link. Local files are only
+/// reachable from other local files, `data:` never loads at the top level, and the browser's own pages only
+/// link to each other.
+pub fn navigation_allowed(document_url: &str, target: &str) -> bool {
+    let scheme = |url: &str| url.split_once(':').map(|(scheme, _)| scheme.to_ascii_lowercase()).unwrap_or_default();
+    let from = scheme(document_url);
+    match scheme(target).as_str() {
+        "javascript" | "data" => false,
+        "file" => from == "file",
+        "about" if target.eq_ignore_ascii_case("about:blank") => true,
+        "neon" | "about" => matches!(from.as_str(), "neon" | "about"),
+        _ => true,
+    }
+}
+
+/// The address pushState and replaceState move to: `target` resolved against the document, which must stay on
+/// the document's origin. An empty or missing target keeps the current address.
+pub fn same_origin_url(document_url: &str, target: Option<&str>) -> Result<String, String> {
+    let Some(target) = target.filter(|target| !target.is_empty()) else {
+        return Ok(document_url.to_string());
+    };
+    let resolved = resolve(document_url, target).ok_or_else(|| format!("SyntaxError: Invalid URL '{}'", target))?;
+    let origin = |url: &str| url::Url::parse(url).ok().map(|url| url.origin());
+    if origin(&resolved) != origin(document_url) || origin(document_url).is_some_and(|origin| !origin.is_tuple()) {
+        return Err(format!("SecurityError: A history state object with URL '{}' cannot be created in a document with origin '{}'",
+            resolved, location_property(document_url, "origin").unwrap_or_default()));
+    }
+    Ok(resolved)
+}
+
+/// A call's argument list split at its top-level commas
+pub fn split_arguments(args: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'' | '`', None) => quote = Some(c),
+            ('(' | '{' | '[', None) => depth += 1,
+            (')' | '}' | ']', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                arguments.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(args[start..].trim());
+    arguments.retain(|argument| !argument.is_empty());
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_and_history_urls() {
+        let url = "https://example.com:8443/app/list?page=2#top";
+        assert_eq!(location_property(url, "origin").as_deref(), Some("https://example.com:8443"));
+        assert_eq!(location_property(url, "host").as_deref(), Some("example.com:8443"));
+        assert_eq!(location_property(url, "pathname").as_deref(), Some("/app/list"));
+        assert_eq!(location_property(url, "search").as_deref(), Some("?page=2"));
+        assert_eq!(location_property(url, "hash").as_deref(), Some("#top"));
+        assert_eq!(location_property(url, "assign"), None);
+
+        assert_eq!(same_origin_url(url, Some("item/7")).as_deref(), Ok("https://example.com:8443/app/item/7"));
+        assert_eq!(same_origin_url(url, None).as_deref(), Ok(url));
+        assert!(same_origin_url(url, Some("https://evil.example/")).unwrap_err().starts_with("SecurityError"));
+        assert!(navigation_allowed(url, "https://other.example/") && navigation_allowed(url, "about:blank"));
+        assert!(!navigation_allowed(url, "file:///etc/passwd") && navigation_allowed("file:///home/a.html", "file:///home/b.html"));
+        assert!(!navigation_allowed(url, "data:text/html,<p>hi</p>") && !navigation_allowed(url, "neon://settings"));
+        assert!(navigation_allowed("neon://history", "neon://settings") && !navigation_allowed(url, "about:home"));
+        assert_eq!(split_arguments("{ page: 1, tab: 'a,b' }, '', '/p/1'"), vec!["{ page: 1, tab: 'a,b' }", "''", "'/p/1'"]);
+    }
+}
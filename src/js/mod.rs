@@ -7,15 +7,32 @@ use regex::Regex;
 
 use crate::engine::dom::DOMNode;
 
+pub mod animation_frame;
+pub mod builtins;
 pub mod console;
+pub mod cookies;
 pub mod dom_api;
 
 use dom_api::DOMApi;
 pub mod event_system;
+pub mod indexed_db;
+pub mod locale;
+pub mod location;
+pub mod navigator;
+pub mod regexp;
 pub mod test;
+pub mod watchdog;
+pub mod worker;
 
+use animation_frame::{AnimationFrames, FrameCallback};
 use console::ConsoleAPI;
+use cookies::DocumentCookies;
 use event_system::EventSystem;
+use indexed_db::IndexedDbApi;
+use locale::{LocaleEnvironment, LocaleOverrides};
+use location::NavigationRequest;
+use navigator::{NavigatorInfo, ScreenMetrics};
+use worker::{Worker, WorkerEvent, WorkerLimits, WorkerSource};
 
 #[derive(Debug, Clone)]
 pub enum JSValue {
@@ -26,13 +43,15 @@ pub enum JSValue {
     Undefined,
     Object(HashMap<String, JSValue>),
     Array(Vec<JSValue>),
+    /// A regex literal or `new RegExp(...)`, kept as its source and flags
+    RegExp { source: String, flags: String },
 }
 
 impl JSValue {
     pub fn to_string(&self) -> String {
         match self {
             JSValue::String(s) => s.clone(),
-            JSValue::Number(n) => n.to_string(),
+            JSValue::Number(n) => builtins::number_to_string(*n),
             JSValue::Boolean(b) => b.to_string(),
             JSValue::Null => "null".to_string(),
             JSValue::Undefined => "undefined".to_string(),
@@ -40,6 +59,7 @@ impl JSValue {
             JSValue::Array(arr) => {
                 format!("[{}]", arr.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
             }
+            JSValue::RegExp { source, flags } => format!("/{}/{}", source, flags),
         }
     }
     
@@ -49,17 +69,97 @@ impl JSValue {
             JSValue::Number(n) => *n != 0.0 && !n.is_nan(),
             JSValue::String(s) => !s.is_empty(),
             JSValue::Null | JSValue::Undefined => false,
-            JSValue::Object(_) | JSValue::Array(_) => true,
+            JSValue::Object(_) | JSValue::Array(_) | JSValue::RegExp { .. } => true,
         }
     }
 }
 
+/// A notification a script created with `new Notification(...)`, for the browser to show
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptNotification {
+    pub title: String,
+    pub body: String,
+}
+
+/// How bad a script problem is: errors stop the script, warnings are statements the engine skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorSeverity {
+    Error,
+    Warning,
+}
+
+/// A problem in one of the page's scripts, with where it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptError {
+    pub severity: ErrorSeverity,
+    pub message: String,
+    /// The script's URL, or a name like "inline script 2" for ones written into the page
+    pub source: String,
+    /// 1-based line in the script
+    pub line: usize,
+}
+
+impl ScriptError {
+    /// `source:line`, as shown next to the message
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.source, self.line)
+    }
+}
+
+// Results the interpreter returns for exceptions it would throw
+const THROWN_ERRORS: [&str; 6] = ["ReferenceError", "TypeError", "SyntaxError", "RangeError", "URIError", "SecurityError"];
+
+// Errors kept until the page picks them up, so a looping script can't grow them without bound
+const MAX_PENDING_ERRORS: usize = 100;
+
+// A worker a script created, with the handlers the page set on it
+struct WorkerBinding {
+    worker: Worker,
+    onmessage: Option<FrameCallback>,
+    onerror: Option<FrameCallback>,
+    listeners: Vec<FrameCallback>,
+}
+
 pub struct JSEngine {
     variables: HashMap<String, JSValue>,
     console_api: ConsoleAPI,
     event_system: EventSystem,
     dom_root: Option<Rc<RefCell<DOMNode>>>,
     dom_api: DOMApi,
+    indexed_db: IndexedDbApi,
+    locale: LocaleEnvironment,
+    screen: ScreenMetrics,
+    document_url: Option<String>,
+    cookies: Option<DocumentCookies>,
+    // The tab's back/forward list as `history.length` and `history.state` report it
+    history_length: usize,
+    history_state: Option<String>,
+    navigation_requests: Vec<NavigationRequest>,
+    // `window.onpopstate` and the listeners added for popstate, in that order
+    popstate_handler: Option<FrameCallback>,
+    popstate_listeners: Vec<FrameCallback>,
+    animation_frames: AnimationFrames,
+    // Named function expressions passed to requestAnimationFrame, which may request themselves again
+    frame_functions: HashMap<String, FrameCallback>,
+    notifications: Vec<ScriptNotification>,
+    notification_permission_requested: bool,
+    // Set when a script changed the document, so the page can pick the change up
+    dom_mutated: bool,
+    errors: Vec<ScriptError>,
+    // A content script's world: the page's DOM and console only, without the page's storage or notifications
+    isolated: bool,
+    // Workers the page's scripts started, by the variable holding them
+    workers: HashMap<String, WorkerBinding>,
+    // A worker's own global scope: no DOM, and postMessage and onmessage talk to the page that started it
+    worker_scope: bool,
+    posted_messages: Vec<JSValue>,
+    message_handler: Option<FrameCallback>,
+    message_listeners: Vec<FrameCallback>,
+    closing: bool,
+    // Statements left and when time runs out for the task a worker is running
+    budget: Option<(usize, std::time::Instant)>,
+    // How long the page's scripts may run in one turn of its event loop
+    watchdog: watchdog::Watchdog,
 }
 
 impl JSEngine {
@@ -75,6 +175,31 @@ impl JSEngine {
             event_system,
             dom_root: None,
             dom_api,
+            indexed_db: IndexedDbApi::new(),
+            locale: script_locale(&LocaleOverrides::default()),
+            screen: ScreenMetrics::default(),
+            document_url: None,
+            cookies: None,
+            history_length: 1,
+            history_state: None,
+            navigation_requests: Vec::new(),
+            popstate_handler: None,
+            popstate_listeners: Vec::new(),
+            animation_frames: AnimationFrames::default(),
+            frame_functions: HashMap::new(),
+            notifications: Vec::new(),
+            notification_permission_requested: false,
+            dom_mutated: false,
+            errors: Vec::new(),
+            isolated: false,
+            workers: HashMap::new(),
+            worker_scope: false,
+            posted_messages: Vec::new(),
+            message_handler: None,
+            message_listeners: Vec::new(),
+            closing: false,
+            budget: None,
+            watchdog: watchdog::Watchdog::default(),
         };
         
         // Set up global objects
@@ -83,6 +208,21 @@ impl JSEngine {
         Ok(engine)
     }
     
+    /// An engine for an extension's content scripts, with variables of its own and a limited API
+    pub fn new_isolated() -> Result<Self> {
+        let mut engine = Self::new()?;
+        engine.isolated = true;
+        Ok(engine)
+    }
+    
+    /// The global scope of a worker's script, which has no document or window
+    pub fn new_worker() -> Result<Self> {
+        let mut engine = Self::new()?;
+        engine.worker_scope = true;
+        engine.variables.remove("window");
+        Ok(engine)
+    }
+    
     pub fn execute(&mut self, code: &str) -> Result<String> {
         // Simple JavaScript interpreter
         // This handles basic statements like:
@@ -92,16 +232,97 @@ impl JSEngine {
         
         let code = code.trim();
         
+        if let Some((statements, deadline)) = &mut self.budget {
+            if *statements == 0 {
+                return Ok("RangeError: Worker ran more statements than it may for one task".to_string());
+            }
+            if std::time::Instant::now() > *deadline {
+                return Ok("RangeError: Worker ran longer than it may for one task".to_string());
+            }
+            *statements -= 1;
+        }
+        if let Err(e) = self.watchdog.check() {
+            return Ok(e.to_string());
+        }
+        
+        // Frame callbacks first, since their bodies may hold any other statement
+        if let Some(result) = self.handle_animation_frame_call(code)? {
+            return Ok(result);
+        }
+        
+        if self.isolated && (code.contains("Notification") || code.contains("indexedDB") || code.contains("navigator.storage")) {
+            return Ok("ReferenceError: not available to content scripts".to_string());
+        }
+        
+        if self.worker_scope {
+            let global_regex = Regex::new(r#"\b(document|window|localStorage|sessionStorage|alert)\b"#)?;
+            if let Some(name) = global_regex.captures(code).map(|captures| captures[1].to_string()) {
+                return Ok(format!("ReferenceError: {} is not defined", name));
+            }
+        }
+        
+        if let Some(result) = self.handle_notification_call(code)? {
+            return Ok(result);
+        }
+        
         // Handle console.log statements
         if let Some(result) = self.handle_console_log(code)? {
             return Ok(result);
         }
         
+        // Handle storage calls before assignments, since their JSON arguments may contain '='
+        if let Some(result) = self.handle_storage_call(code)? {
+            return Ok(result);
+        }
+        
+        if let Some(result) = self.handle_locale_call(code)? {
+            return Ok(result);
+        }
+        
+        // Blob options contain ':' and '=' could follow a declaration, so blobs come before assignments too
+        if let Some(result) = self.handle_blob_call(code)? {
+            return Ok(result);
+        }
+        
+        if let Some(result) = self.handle_navigator_call(code)? {
+            return Ok(result);
+        }
+        
+        // `document.cookie = ...` would otherwise be taken for an assignment to a variable named cookie
+        if let Some(result) = self.handle_document_cookie(code)? {
+            return Ok(result);
+        }
+        
+        // Likewise `location.href = ...` and `window.onpopstate = ...`
+        if let Some(result) = self.handle_location_call(code)? {
+            return Ok(result);
+        }
+        
+        // Before assignments too, for `worker.onmessage = ...`
+        if let Some(result) = self.handle_worker_call(code)? {
+            return Ok(result);
+        }
+        
+        // `items.push(...)` and `settings.theme = ...` change the variable they're called on
+        if let Some(result) = self.handle_object_update(code)? {
+            return Ok(result);
+        }
+        
+        // DOM changes before assignments, since setting textContent looks like one
+        if let Some(result) = self.handle_dom_mutation(code)? {
+            return Ok(result);
+        }
+        
         // Handle variable declarations
         if let Some(result) = self.handle_variable_declaration(code)? {
             return Ok(result);
         }
         
+        // Expressions over the built-ins, e.g. `items.forEach(item => console.log(item))`
+        if let Some(result) = self.handle_expression(code)? {
+            return Ok(result);
+        }
+        
         // Handle variable assignments
         if let Some(result) = self.handle_variable_assignment(code)? {
             return Ok(result);
@@ -127,7 +348,7 @@ impl JSEngine {
     }
     
     fn handle_console_log(&mut self, code: &str) -> Result<Option<String>> {
-        let console_regex = Regex::new(r#"console\.log\s*\(\s*["']([^"']*)["']\s*\)"#)?;
+        let console_regex = Regex::new(r#"^console\.log\s*\(\s*["']([^"']*)["']\s*\)\s*;?$"#)?;
         
         if let Some(captures) = console_regex.captures(code) {
             let message = captures.get(1).map_or("", |m| m.as_str());
@@ -136,7 +357,7 @@ impl JSEngine {
         }
         
         // Handle console.log with variables
-        let var_regex = Regex::new(r#"console\.log\s*\(\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\)"#)?;
+        let var_regex = Regex::new(r#"^console\.log\s*\(\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\)\s*;?$"#)?;
         if let Some(captures) = var_regex.captures(code) {
             let var_name = captures.get(1).map_or("", |m| m.as_str());
             if let Some(value) = self.variables.get(var_name) {
@@ -200,6 +421,25 @@ impl JSEngine {
         Ok(None)
     }
     
+    fn handle_expression(&mut self, code: &str) -> Result<Option<String>> {
+        Ok(self.evaluate(code).map(|result| match result {
+            Ok(value) => value.to_string(),
+            Err(e) => e.to_string(),
+        }))
+    }
+    
+    // `expression` evaluated over the variables and built-ins. None when it isn't an expression the evaluator
+    // reads, or names something it doesn't know, such as DOM methods, which other handlers deal with.
+    fn evaluate(&self, expression: &str) -> Option<Result<JSValue>> {
+        if !builtins::is_expression(expression) {
+            return None;
+        }
+        match builtins::evaluate(expression, self) {
+            Err(e) if e.to_string().starts_with("ReferenceError") || e.to_string().starts_with("TypeError") => None,
+            result => Some(result),
+        }
+    }
+    
     fn handle_function_call(&mut self, code: &str) -> Result<Option<String>> {
         // Handle basic function calls like alert("message")
         let func_regex = Regex::new(r#"([a-zA-Z_][a-zA-Z0-9_]*)\s*\(\s*["']([^"']*)["']\s*\)"#)?;
@@ -226,6 +466,10 @@ impl JSEngine {
     fn parse_value(&self, value_str: &str) -> Result<JSValue> {
         let value_str = value_str.trim();
         
+        if let Some(value) = self.evaluate(value_str) {
+            return value;
+        }
+        
         // String literals
         if (value_str.starts_with('"') && value_str.ends_with('"')) ||
            (value_str.starts_with('\'') && value_str.ends_with('\'')) {
@@ -247,11 +491,25 @@ impl JSEngine {
             _ => {}
         }
         
+        if value_str == "document.cookie" {
+            return Ok(JSValue::String(self.cookies.as_ref().map(DocumentCookies::get).unwrap_or_default()));
+        }
+        if let Some(value) = self.location_value(value_str).or_else(|| self.navigator_value(value_str)) {
+            return Ok(value);
+        }
+        
         // Variable reference
         if let Some(value) = self.variables.get(value_str) {
             return Ok(value.clone());
         }
         
+        // Property of an object variable, like a popstate handler's `event.state`
+        if let Some((object, property)) = value_str.split_once('.') {
+            if let Some(JSValue::Object(fields)) = self.variables.get(object) {
+                return Ok(fields.get(property).cloned().unwrap_or(JSValue::Undefined));
+            }
+        }
+        
         // Default to string if nothing else matches
         Ok(JSValue::String(value_str.to_string()))
     }
@@ -284,10 +542,227 @@ impl JSEngine {
         Ok(())
     }
     
+    /// Tell the engine which page it runs in, which decides the origin its storage belongs to
+    pub fn set_document_url(&mut self, url: &str) {
+        if let Some(JSValue::Object(window)) = self.variables.get_mut("window") {
+            window.insert("location".to_string(), JSValue::String(url.to_string()));
+        }
+        self.indexed_db.set_document_url(url);
+        self.document_url = Some(url.to_string());
+    }
+    
+    /// The cookie jar behind `document.cookie`; None leaves it empty, as on pages without an http(s) origin
+    pub fn set_cookies(&mut self, cookies: Option<DocumentCookies>) {
+        self.cookies = cookies;
+    }
+    
+    /// The tab's back/forward list as the page's scripts see it: how many entries it has, and the state the
+    /// current entry was pushed with
+    pub fn set_session_history(&mut self, length: usize, state: Option<String>) {
+        self.history_length = length.max(1);
+        self.history_state = state;
+    }
+    
+    /// Navigations and history changes scripts asked for since the last call, in order
+    pub fn take_navigation_requests(&mut self) -> Vec<NavigationRequest> {
+        std::mem::take(&mut self.navigation_requests)
+    }
+    
+    /// The user went back or forward to another entry of this document, now at `url`: update `location` and
+    /// `history.state` and fire popstate
+    pub fn pop_state(&mut self, url: &str, state: Option<String>) -> Result<()> {
+        self.set_document_url(url);
+        self.history_state = state.clone();
+        let mut event = HashMap::new();
+        event.insert("type".to_string(), JSValue::String("popstate".to_string()));
+        event.insert("state".to_string(), state.as_deref().and_then(builtins::from_json).unwrap_or(JSValue::Null));
+        let handlers = self.popstate_handler.iter().chain(&self.popstate_listeners).cloned().collect::<Vec<_>>();
+        for handler in handlers {
+            if let Some(param) = &handler.param {
+                self.variables.insert(param.clone(), JSValue::Object(event.clone()));
+            }
+            for statement in animation_frame::split_statements(&handler.body) {
+                self.execute(statement)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Language, locale and time zone scripts see, e.g. from dev tools emulation
+    pub fn set_locale_overrides(&mut self, overrides: &LocaleOverrides) {
+        self.locale = script_locale(overrides);
+    }
+    
+    /// The display the page is shown on, for `screen` and `devicePixelRatio`
+    pub fn set_screen(&mut self, screen: ScreenMetrics) {
+        self.screen = screen;
+    }
+    
     pub fn get_console_output(&self) -> Vec<String> {
         self.console_api.get_output()
     }
     
+    /// Console messages logged since the last call, each prefixed with its level like `[LOG]`
+    pub fn take_console_output(&self) -> Vec<String> {
+        self.console_api.take_output()
+    }
+    
+    /// Run a whole script named `source` one statement at a time, stopping at the first thrown error the way an
+    /// uncaught exception does. Problems are logged to the console and kept for `take_errors`.
+    pub fn run_script(&mut self, code: &str, source: &str) -> std::result::Result<(), ScriptError> {
+        for statement in animation_frame::split_statements(code) {
+            // Statements are slices of `code`, so their offset gives the line
+            let offset = statement.as_ptr() as usize - code.as_ptr() as usize;
+            let line = code[..offset].matches('\n').count() + 1;
+            let problem = match self.execute(statement) {
+                Err(e) => Some((ErrorSeverity::Error, e.to_string())),
+                Ok(result) if THROWN_ERRORS.iter().any(|name| result.starts_with(name)) => Some((ErrorSeverity::Error, result)),
+                Ok(result) if result.starts_with("Unrecognized JavaScript") => {
+                    Some((ErrorSeverity::Warning, format!("Statement not supported, skipped: {}", statement)))
+                }
+                Ok(_) => None,
+            };
+            let Some((severity, message)) = problem else { continue };
+            let error = ScriptError { severity, message, source: source.to_string(), line };
+            self.record_error(error.clone());
+            if severity == ErrorSeverity::Error {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+    
+    // Log a script problem to the console and keep it for `take_errors`
+    fn record_error(&mut self, error: ScriptError) {
+        match error.severity {
+            ErrorSeverity::Error => self.console_api.error(&format!("Uncaught {} ({})", error.message, error.location())),
+            ErrorSeverity::Warning => self.console_api.warn(&format!("{} ({})", error.message, error.location())),
+        }
+        if self.errors.len() < MAX_PENDING_ERRORS {
+            self.errors.push(error);
+        }
+    }
+    
+    // Run event handlers with `event` as their parameter, each until its first thrown error, which is recorded
+    // against `source`
+    fn run_event_handlers(&mut self, handlers: Vec<FrameCallback>, event: JSValue, source: &str) {
+        for handler in handlers {
+            if let Some(param) = &handler.param {
+                self.variables.insert(param.clone(), event.clone());
+            }
+            for statement in animation_frame::split_statements(&handler.body) {
+                let message = match self.execute(statement) {
+                    Err(e) => e.to_string(),
+                    Ok(result) if THROWN_ERRORS.iter().any(|name| result.starts_with(name)) => result,
+                    Ok(_) => continue,
+                };
+                self.record_error(ScriptError { severity: ErrorSeverity::Error, message, source: source.to_string(), line: 1 });
+                break;
+            }
+        }
+    }
+    
+    /// Start a worker task's budget of statements and time
+    pub fn start_budget(&mut self, statements: usize, time: std::time::Duration) {
+        self.budget = Some((statements, std::time::Instant::now() + time));
+    }
+    
+    /// How long the page's scripts may run in one turn before they're interrupted
+    pub fn set_turn_budget(&mut self, budget: std::time::Duration) {
+        self.watchdog.set_budget(budget);
+    }
+    
+    pub fn turn_budget(&self) -> std::time::Duration {
+        self.watchdog.budget()
+    }
+    
+    /// Start metering a turn of the page's event loop against its budget
+    pub fn start_turn(&mut self) {
+        self.watchdog.start_turn();
+    }
+    
+    /// Whether a turn ran out of time since the last call, so the user should be asked about the page
+    pub fn take_slow_script(&mut self) -> bool {
+        self.watchdog.take_tripped()
+    }
+    
+    /// The user chose to wait for a slow page: give its scripts longer from the next turn on
+    pub fn extend_turn_budget(&mut self) {
+        self.watchdog.extend();
+    }
+    
+    /// In a worker: what its script posted to the page since the last call
+    pub fn take_posted_messages(&mut self) -> Vec<JSValue> {
+        std::mem::take(&mut self.posted_messages)
+    }
+    
+    /// In a worker: whether its script called close()
+    pub fn is_closing(&self) -> bool {
+        self.closing
+    }
+    
+    /// In a worker: fire a message event with `data` from the page
+    pub fn dispatch_worker_message(&mut self, data: JSValue) {
+        let event = JSValue::Object(HashMap::from([
+            ("type".to_string(), JSValue::String("message".to_string())),
+            ("data".to_string(), data),
+        ]));
+        let handlers = self.message_handler.iter().chain(&self.message_listeners).cloned().collect();
+        let source = self.document_url.clone().unwrap_or_else(|| "worker".to_string());
+        self.run_event_handlers(handlers, event, &source);
+    }
+    
+    /// Deliver what the page's workers sent since the last call to their handlers; true while any still runs
+    pub fn poll_workers(&mut self) -> bool {
+        let names: Vec<String> = self.workers.keys().cloned().collect();
+        for name in names {
+            let Some(binding) = self.workers.get(&name) else { continue };
+            let events = binding.worker.take_events();
+            for event in events {
+                let Some(binding) = self.workers.get(&name) else { break };
+                match event {
+                    WorkerEvent::Message(data) => {
+                        let handlers = binding.onmessage.iter().chain(&binding.listeners).cloned().collect();
+                        let event = JSValue::Object(HashMap::from([
+                            ("type".to_string(), JSValue::String("message".to_string())),
+                            ("data".to_string(), data),
+                        ]));
+                        let source = binding.worker.url.clone();
+                        self.run_event_handlers(handlers, event, &source);
+                    }
+                    WorkerEvent::Console(lines) => self.console_api.append(lines),
+                    // The worker's console already has the error, so without a handler it's only kept for the page
+                    WorkerEvent::Error(error) => match binding.onerror.clone().filter(|_| error.severity == ErrorSeverity::Error) {
+                        Some(handler) => {
+                            let event = JSValue::Object(HashMap::from([
+                                ("type".to_string(), JSValue::String("error".to_string())),
+                                ("message".to_string(), JSValue::String(error.message.clone())),
+                                ("filename".to_string(), JSValue::String(error.source.clone())),
+                                ("lineno".to_string(), JSValue::Number(error.line as f64)),
+                            ]));
+                            self.run_event_handlers(vec![handler], event, &error.source);
+                        }
+                        None if self.errors.len() < MAX_PENDING_ERRORS => self.errors.push(error),
+                        None => {}
+                    },
+                    WorkerEvent::Closed => {}
+                }
+            }
+        }
+        self.workers.retain(|_, binding| binding.worker.is_running());
+        !self.workers.is_empty()
+    }
+    
+    /// Errors and warnings from `run_script` since the last call
+    pub fn take_errors(&mut self) -> Vec<ScriptError> {
+        std::mem::take(&mut self.errors)
+    }
+    
+    /// Whether a script changed the document since the last call
+    pub fn take_dom_mutated(&mut self) -> bool {
+        std::mem::take(&mut self.dom_mutated)
+    }
+    
     pub fn clear_console(&self) {
         self.console_api.clear_output();
     }
@@ -300,6 +775,647 @@ impl JSEngine {
         self.event_system.has_listeners(event_type)
     }
     
+    /// Run the callbacks queued with requestAnimationFrame, passing `timestamp` in milliseconds on the page's clock
+    pub fn run_animation_frames(&mut self, timestamp: f64) -> Result<()> {
+        for callback in self.animation_frames.take() {
+            if let Some(param) = &callback.param {
+                self.variables.insert(param.clone(), JSValue::Number(timestamp));
+            }
+            for statement in animation_frame::split_statements(&callback.body) {
+                self.execute(statement)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Whether a script is waiting for the next frame
+    pub fn has_animation_frame_callbacks(&self) -> bool {
+        !self.animation_frames.is_empty()
+    }
+    
+    fn handle_animation_frame_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle cancelAnimationFrame(id) and [var id =] requestAnimationFrame(callback)
+        if let Some((args, _)) = animation_frame::call_arguments(code, "cancelAnimationFrame") {
+            if let JSValue::Number(id) = self.parse_value(args)? {
+                self.animation_frames.cancel(id as u32);
+            }
+            return Ok(Some("undefined".to_string()));
+        }
+        let Some((args, _)) = animation_frame::call_arguments(code, "requestAnimationFrame") else {
+            return Ok(None);
+        };
+        let callback = match FrameCallback::parse(args) {
+            Some(callback) => callback,
+            None => match self.frame_functions.get(args.trim()) {
+                Some(callback) => callback.clone(),
+                None => return Ok(Some(format!("TypeError: {} is not a function", args.trim()))),
+            },
+        };
+        if let Some(name) = &callback.name {
+            self.frame_functions.insert(name.clone(), callback.clone());
+        }
+        let id = self.animation_frames.request(callback);
+        
+        let assign_regex = Regex::new(r#"^(?:(?:var|let|const)\s+)?([a-zA-Z_][a-zA-Z0-9_]*)\s*=\s*(?:window\.)?requestAnimationFrame"#)?;
+        if let Some(captures) = assign_regex.captures(code) {
+            self.variables.insert(captures[1].to_string(), JSValue::Number(id as f64));
+        }
+        Ok(Some(id.to_string()))
+    }
+    
+    /// Notifications scripts created since the last call
+    pub fn take_notifications(&mut self) -> Vec<ScriptNotification> {
+        std::mem::take(&mut self.notifications)
+    }
+    
+    /// Whether a script asked for notification permission since the last call
+    pub fn take_notification_permission_request(&mut self) -> bool {
+        std::mem::take(&mut self.notification_permission_requested)
+    }
+    
+    fn handle_notification_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle Notification.permission, Notification.requestPermission() and new Notification(title, options)
+        let code = code.trim_end_matches(';').trim();
+        let location = match self.variables.get("window") {
+            Some(JSValue::Object(window)) => window.get("location").map(JSValue::to_string).unwrap_or_default(),
+            _ => String::new(),
+        };
+        let permission = crate::storage::Preferences::current().notifications.permission(&location);
+        let permission = match permission {
+            Some(true) => "granted",
+            Some(false) => "denied",
+            None => "default",
+        };
+        
+        if code == "Notification.permission" {
+            return Ok(Some(permission.to_string()));
+        }
+        if animation_frame::call_arguments(code, "Notification.requestPermission").is_some() {
+            // Only pages with an origin can be asked about; the answer comes later from the prompt bar
+            if permission == "default" && crate::networking::site_metadata::origin_of(&location).is_some() {
+                self.notification_permission_requested = true;
+            }
+            return Ok(Some(permission.to_string()));
+        }
+        let Some((args, _)) = animation_frame::call_arguments(code, "new Notification") else {
+            return Ok(None);
+        };
+        let args: Vec<serde_json::Value> = match serde_json::from_str(&format!("[{}]", args)) {
+            Ok(args) => args,
+            Err(_) => return Ok(Some("TypeError: Notification arguments must be JSON values".to_string())),
+        };
+        let Some(title) = args.first().and_then(|title| title.as_str()) else {
+            return Ok(Some("TypeError: Notification requires a title".to_string()));
+        };
+        if permission == "granted" {
+            let body = args.get(1).and_then(|options| options["body"].as_str()).unwrap_or_default();
+            self.notifications.push(ScriptNotification { title: title.to_string(), body: body.to_string() });
+        }
+        Ok(Some("[object Notification]".to_string()))
+    }
+    
+    fn handle_storage_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle indexedDB.method(args) and navigator.storage.estimate(), with JSON literal arguments
+        let storage_regex = Regex::new(r#"(?s)^(indexedDB\.([A-Za-z]+)|navigator\.storage\.(estimate))\s*\((.*)\)\s*;?$"#)?;
+        let Some(captures) = storage_regex.captures(code) else {
+            return Ok(None);
+        };
+        
+        // Experimental: without the flag the API doesn't exist, as in browsers that lack it
+        if !crate::storage::Preferences::current().experimental_indexed_db {
+            let name = if captures.get(2).is_some() { "indexedDB" } else { "navigator.storage" };
+            return Ok(Some(format!("ReferenceError: {} is not defined", name)));
+        }
+        
+        let method = captures.get(2).or_else(|| captures.get(3)).map_or("", |m| m.as_str());
+        let args = captures.get(4).map_or("", |m| m.as_str());
+        let args: Vec<serde_json::Value> = match serde_json::from_str(&format!("[{}]", args)) {
+            Ok(args) => args,
+            Err(_) => return Ok(Some("TypeError: storage arguments must be JSON values".to_string())),
+        };
+        
+        Ok(Some(match self.indexed_db.call(method, &args) {
+            Ok(value) => value.to_string(),
+            Err(e) => e.to_string(),
+        }))
+    }
+    
+    fn handle_blob_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle [var x =] URL.createObjectURL(new Blob([parts], {type: "..."})) and URL.revokeObjectURL(url)
+        let code = code.trim_end_matches(';').trim();
+        let create_regex = Regex::new(r#"(?s)^(?:(?:var|let|const)\s+([a-zA-Z_][a-zA-Z0-9_]*)\s*=\s*)?URL\.createObjectURL\s*\(\s*new\s+Blob\s*\(\s*\[(.*)\]\s*(?:,\s*\{\s*type\s*:\s*["']([^"']*)["']\s*\})?\s*\)\s*\)$"#)?;
+        if let Some(captures) = create_regex.captures(code) {
+            let part_regex = Regex::new(r#""[^"]*"|'[^']*'|[^,\s]+"#)?;
+            let mut data = Vec::new();
+            for part in part_regex.find_iter(captures.get(2).map_or("", |m| m.as_str())) {
+                data.extend_from_slice(self.parse_value(part.as_str())?.to_string().as_bytes());
+            }
+            let mime_type = captures.get(3).map_or("", |m| m.as_str()).to_ascii_lowercase();
+            let origin = self.document_url.as_deref().and_then(crate::networking::site_metadata::origin_of);
+            let content = crate::networking::local_url::LocalContent { mime_type, data };
+            let url = crate::networking::local_url::BlobRegistry::shared().lock()
+                .map_err(|_| anyhow::anyhow!("Blob registry unavailable"))?
+                .create(origin.as_deref(), content);
+            return Ok(Some(match captures.get(1) {
+                Some(name) => {
+                    self.variables.insert(name.as_str().to_string(), JSValue::String(url));
+                    "undefined".to_string()
+                }
+                None => url,
+            }));
+        }
+        
+        let revoke_regex = Regex::new(r#"^URL\.revokeObjectURL\s*\((.*)\)$"#)?;
+        if let Some(captures) = revoke_regex.captures(code) {
+            let url = self.parse_value(captures.get(1).map_or("", |m| m.as_str()))?.to_string();
+            if let Ok(mut registry) = crate::networking::local_url::BlobRegistry::shared().lock() {
+                registry.revoke(&url);
+            }
+            return Ok(Some("undefined".to_string()));
+        }
+        
+        Ok(None)
+    }
+    
+    fn handle_locale_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle Intl.DateTimeFormat().resolvedOptions(); Date goes through the built-ins, with `date_value`
+        let code = code.trim_end_matches(';').trim();
+        let intl_regex = Regex::new(r#"^(?:new\s+)?Intl\.DateTimeFormat\s*\([^)]*\)\s*\.resolvedOptions\s*\(\s*\)\s*\.(timeZone|locale)$"#)?;
+        let Some(captures) = intl_regex.captures(code) else {
+            return Ok(None);
+        };
+        Ok(Some(match &captures[1] {
+            "timeZone" => self.locale.time_zone.name().to_string(),
+            _ => self.locale.locale.clone(),
+        }))
+    }
+    
+    // `Date.prototype.<method>()` for `time` in milliseconds since the epoch, in the page's locale and time zone
+    fn date_value(&self, time: f64, method: &str) -> Result<JSValue> {
+        use chrono::{Datelike, Timelike};
+        let at = if time.is_finite() { chrono::DateTime::from_timestamp_millis(time as i64) } else { None };
+        let Some(at) = at else {
+            return Ok(match method {
+                "toISOString" => anyhow::bail!("RangeError: Invalid time value"),
+                "toJSON" => JSValue::Null,
+                method if method.starts_with("to") => JSValue::String("Invalid Date".to_string()),
+                _ => JSValue::Number(f64::NAN),
+            });
+        };
+        let (fields, part) = match method.strip_prefix("getUTC") {
+            Some(part) => (at.naive_utc(), part),
+            None => (self.locale.time_zone.local_time(at), method.strip_prefix("get").unwrap_or_default()),
+        };
+        Ok(match method {
+            "toString" => JSValue::String(self.locale.date_string(at)),
+            "toLocaleString" => JSValue::String(self.locale.locale_string(at, true, true)),
+            "toLocaleDateString" => JSValue::String(self.locale.locale_string(at, true, false)),
+            "toLocaleTimeString" => JSValue::String(self.locale.locale_string(at, false, true)),
+            "toISOString" | "toJSON" => JSValue::String(at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+            "toUTCString" => JSValue::String(at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            // JavaScript counts the offset the other way round, in minutes behind UTC
+            "getTimezoneOffset" => JSValue::Number(-self.locale.time_zone.offset_minutes(at) as f64),
+            "getTime" | "valueOf" => JSValue::Number(at.timestamp_millis() as f64),
+            _ => JSValue::Number(match part {
+                "FullYear" => fields.year() as f64,
+                // Months count from zero and weekdays from Sunday
+                "Month" => fields.month0() as f64,
+                "Date" => fields.day() as f64,
+                "Day" => fields.weekday().num_days_from_sunday() as f64,
+                "Hours" => fields.hour() as f64,
+                "Minutes" => fields.minute() as f64,
+                "Seconds" => fields.second() as f64,
+                "Milliseconds" => (fields.nanosecond() / 1_000_000) as f64,
+                _ => anyhow::bail!("TypeError: Date.prototype.{} is not a function", method),
+            }),
+        })
+    }
+    
+    fn handle_navigator_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle navigator.userAgent and friends, screen.width and friends, innerWidth/innerHeight and devicePixelRatio
+        let code = code.trim_end_matches(';').trim();
+        Ok(self.navigator_value(code).map(|value| value.to_string()))
+    }
+    
+    // `navigator`, `screen` and window size properties read as an expression; properties these objects don't
+    // have are undefined, so feature detection finds them missing
+    fn navigator_value(&self, expression: &str) -> Option<JSValue> {
+        let navigator_regex = Regex::new(r#"^(?:window\.)?(?:(navigator|screen)(?:\.([A-Za-z]+))?|(devicePixelRatio|innerWidth|innerHeight))$"#).ok()?;
+        let captures = navigator_regex.captures(expression)?;
+        let (object, name) = match (captures.get(1), captures.get(2), captures.get(3)) {
+            (_, _, Some(name)) => ("window", name.as_str()),
+            (Some(object), Some(name), _) => (object.as_str(), name.as_str()),
+            (Some(object), None, _) => {
+                let mut name = object.as_str().to_string();
+                name[..1].make_ascii_uppercase();
+                return Some(JSValue::String(format!("[object {}]", name)));
+            }
+            _ => return None,
+        };
+        match (object, name) {
+            ("navigator", "language") => return Some(JSValue::String(self.locale.locale.clone())),
+            ("navigator", "languages") => {
+                return Some(JSValue::Array(self.locale.languages.iter().cloned().map(JSValue::String).collect()));
+            }
+            _ => {}
+        }
+        let preferences = crate::storage::Preferences::current();
+        let preset = preferences.user_agent.preset_for(self.document_url.as_deref().unwrap_or_default());
+        let info = NavigatorInfo::new(preset, &preferences.privacy, self.screen);
+        let Some(value) = info.property(object, name) else {
+            return Some(JSValue::Undefined);
+        };
+        Some(match value.as_str() {
+            "true" => JSValue::Boolean(true),
+            "false" => JSValue::Boolean(false),
+            "null" => JSValue::Null,
+            number if object != "navigator" || name == "hardwareConcurrency" || name == "maxTouchPoints" => {
+                number.parse().map_or(JSValue::String(value.clone()), JSValue::Number)
+            }
+            _ => JSValue::String(value),
+        })
+    }
+    
+    fn handle_document_cookie(&mut self, code: &str) -> Result<Option<String>> {
+        // Reading gives the page's cookies other than HttpOnly ones; assigning sets one cookie, and the jar
+        // quietly ignores cookies a script isn't allowed to set, as browsers do
+        let code = code.trim_end_matches(';').trim();
+        let cookie_regex = Regex::new(r#"^document\.cookie(?:\s*=\s*(.+))?$"#)?;
+        let Some(captures) = cookie_regex.captures(code) else {
+            return Ok(None);
+        };
+        let Some(assigned) = captures.get(1) else {
+            return Ok(Some(self.cookies.as_ref().map(DocumentCookies::get).unwrap_or_default()));
+        };
+        let cookie = self.parse_value(assigned.as_str())?.to_string();
+        if let Some(cookies) = &self.cookies {
+            if !cookies.set(&cookie) {
+                log::debug!("Refused cookie set by script: {}", cookie);
+            }
+        }
+        Ok(Some(cookie))
+    }
+    
+    // `location`, `location.<property>`, `history.length` and `history.state` read as an expression
+    fn location_value(&self, expression: &str) -> Option<JSValue> {
+        let expression = expression.strip_prefix("window.").unwrap_or(expression);
+        match expression {
+            "history.length" => return Some(JSValue::Number(self.history_length as f64)),
+            "history.state" => return Some(self.history_state.as_deref().and_then(builtins::from_json).unwrap_or(JSValue::Null)),
+            _ => {}
+        }
+        let expression = expression.strip_prefix("document.").unwrap_or(expression);
+        let property = match expression.strip_prefix("location") {
+            Some("") => "href",
+            Some(rest) => rest.strip_prefix('.')?,
+            None => return None,
+        };
+        let url = self.document_url.as_deref().unwrap_or("about:blank");
+        location::location_property(url, property).map(JSValue::String)
+    }
+    
+    fn handle_location_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle location reads, assignments and methods, the history methods, and popstate handlers
+        let code = code.trim_end_matches(';').trim();
+        if let Some(value) = self.location_value(code) {
+            return Ok(Some(value.to_string()));
+        }
+        let document_url = self.document_url.clone().unwrap_or_else(|| "about:blank".to_string());
+        
+        let handler_regex = Regex::new(r#"(?s)^(?:window\.)?onpopstate\s*=\s*(.+)$"#)?;
+        if let Some(captures) = handler_regex.captures(code) {
+            self.popstate_handler = FrameCallback::parse(&captures[1]);
+            return Ok(Some("undefined".to_string()));
+        }
+        if code.starts_with("window.addEventListener") || code.starts_with("addEventListener") {
+            let Some((args, _)) = animation_frame::call_arguments(code, "addEventListener") else {
+                return Ok(None);
+            };
+            let arguments = location::split_arguments(args);
+            if arguments.first().map(|event| self.parse_value(event)).transpose()?.map(|event| event.to_string()).as_deref() != Some("popstate") {
+                return Ok(None);
+            }
+            return Ok(Some(match arguments.get(1).and_then(|callback| FrameCallback::parse(callback)) {
+                Some(callback) => {
+                    self.popstate_listeners.push(callback);
+                    "undefined".to_string()
+                }
+                None => "TypeError: popstate listener must be a function".to_string(),
+            }));
+        }
+        
+        let history_regex = Regex::new(r#"(?s)^(?:window\.)?history\.(pushState|replaceState|back|forward|go)\s*\((.*)\)$"#)?;
+        if let Some(captures) = history_regex.captures(code) {
+            let arguments = location::split_arguments(captures.get(2).map_or("", |m| m.as_str()))
+                .into_iter()
+                .map(|argument| self.parse_value(argument))
+                .collect::<Result<Vec<_>>>()?;
+            let request = match &captures[1] {
+                "back" => NavigationRequest::Traverse(-1),
+                "forward" => NavigationRequest::Traverse(1),
+                "go" => match arguments.first() {
+                    Some(JSValue::Number(delta)) => NavigationRequest::Traverse(*delta as i32),
+                    _ => NavigationRequest::Traverse(0),
+                },
+                method => {
+                    let present = |value: Option<&JSValue>| value.filter(|value| !matches!(value, JSValue::Null | JSValue::Undefined)).map(JSValue::to_string);
+                    let url = match location::same_origin_url(&document_url, present(arguments.get(2)).as_deref()) {
+                        Ok(url) => url,
+                        Err(error) => return Ok(Some(error)),
+                    };
+                    // Kept as JSON, the serialized form browsers keep state in too
+                    let state = arguments.first().filter(|state| !matches!(state, JSValue::Null)).and_then(builtins::to_json);
+                    self.set_document_url(&url);
+                    self.history_state = state.clone();
+                    if method == "pushState" {
+                        self.history_length += 1;
+                        NavigationRequest::PushState { url, state }
+                    } else {
+                        NavigationRequest::ReplaceState { url, state }
+                    }
+                }
+            };
+            // go(0) reloads, as history.go() does
+            self.navigation_requests.push(match request {
+                NavigationRequest::Traverse(0) => NavigationRequest::Reload,
+                request => request,
+            });
+            return Ok(Some("undefined".to_string()));
+        }
+        
+        let method_regex = Regex::new(r#"(?s)^(?:window\.|document\.)?location\.(assign|replace|reload)\s*\((.*)\)$"#)?;
+        let assign_regex = Regex::new(r#"(?s)^(?:window\.|document\.)?location(?:\.(href|hash))?\s*=\s*(.+)$"#)?;
+        let (method, target) = if let Some(captures) = method_regex.captures(code) {
+            (captures[1].to_string(), captures.get(2).map_or("", |m| m.as_str()).to_string())
+        } else if let Some(captures) = assign_regex.captures(code) {
+            (captures.get(1).map_or("href", |m| m.as_str()).to_string(), captures[2].to_string())
+        } else {
+            return Ok(None);
+        };
+        if method == "reload" {
+            self.navigation_requests.push(NavigationRequest::Reload);
+            return Ok(Some("undefined".to_string()));
+        }
+        let target = self.parse_value(&target)?.to_string();
+        if method == "hash" {
+            // A new fragment is a new entry for the same document rather than a load
+            let fragment = target.trim_start_matches('#');
+            let Ok(mut url) = url::Url::parse(&document_url) else {
+                return Ok(Some(target));
+            };
+            url.set_fragment(Some(fragment));
+            let url = String::from(url);
+            self.set_document_url(&url);
+            self.history_state = None;
+            self.history_length += 1;
+            self.navigation_requests.push(NavigationRequest::PushState { url, state: None });
+            return Ok(Some(target));
+        }
+        let Some(url) = location::resolve(&document_url, &target) else {
+            return Ok(Some(format!("SyntaxError: Invalid URL '{}'", target)));
+        };
+        if url.starts_with("javascript:") {
+            return Ok(Some(target));
+        }
+        // Scripts can't reach local files, data: documents or the browser's own pages from a web page
+        if !location::navigation_allowed(&document_url, &url) {
+            return Ok(Some(format!("SecurityError: Not allowed to navigate to '{}'", url)));
+        }
+        self.navigation_requests.push(NavigationRequest::Navigate { url, replace: method == "replace" });
+        Ok(Some(if method == "href" { target } else { "undefined".to_string() }))
+    }
+    
+    fn handle_worker_call(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle `new Worker(url)` and the worker's postMessage, terminate and handlers on the page's side, and
+        // postMessage, onmessage and close() inside a worker
+        let code = code.trim_end_matches(';').trim();
+        if self.worker_scope {
+            let post_regex = Regex::new(r#"(?s)^(?:self\.)?postMessage\s*\((.*)\)$"#)?;
+            if let Some(captures) = post_regex.captures(code) {
+                let data = location::split_arguments(&captures[1]).first().map(|data| self.parse_value(data)).transpose()?;
+                self.posted_messages.push(data.unwrap_or(JSValue::Undefined));
+                return Ok(Some("undefined".to_string()));
+            }
+            let handler_regex = Regex::new(r#"(?s)^(?:self\.)?onmessage\s*=\s*(.+)$"#)?;
+            if let Some(captures) = handler_regex.captures(code) {
+                self.message_handler = FrameCallback::parse(&captures[1]);
+                return Ok(Some("undefined".to_string()));
+            }
+            if let Some((args, _)) = animation_frame::call_arguments(code, "addEventListener").filter(|_| !code.contains('.') || code.starts_with("self.")) {
+                let arguments = location::split_arguments(args);
+                if arguments.first().map(|event| self.parse_value(event)).transpose()?.map(|event| event.to_string()).as_deref() == Some("message") {
+                    return Ok(Some(match arguments.get(1).and_then(|callback| FrameCallback::parse(callback)) {
+                        Some(callback) => {
+                            self.message_listeners.push(callback);
+                            "undefined".to_string()
+                        }
+                        None => "TypeError: message listener must be a function".to_string(),
+                    }));
+                }
+            }
+            if Regex::new(r#"^(?:self\.)?close\s*\(\s*\)$"#)?.is_match(code) {
+                self.closing = true;
+                return Ok(Some("undefined".to_string()));
+            }
+        }
+        
+        let create_regex = Regex::new(r#"(?s)^(?:var|let|const)\s+([A-Za-z_$][\w$]*)\s*=\s*new\s+Worker\s*\((.*)\)$"#)?;
+        if let Some(captures) = create_regex.captures(code) {
+            let Some(target) = location::split_arguments(&captures[2]).first().map(|target| self.parse_value(target)).transpose()? else {
+                return Ok(Some("TypeError: Failed to construct 'Worker': 1 argument required".to_string()));
+            };
+            let document_url = self.document_url.clone().unwrap_or_else(|| "about:blank".to_string());
+            let url = match worker::script_url(&document_url, &target.to_string()) {
+                Ok(url) => url,
+                Err(error) => return Ok(Some(error)),
+            };
+            if self.workers.values().filter(|binding| binding.worker.is_running()).count() >= worker::MAX_WORKERS_PER_PAGE {
+                return Ok(Some(format!("RangeError: A page may run at most {} workers", worker::MAX_WORKERS_PER_PAGE)));
+            }
+            // blob: and data: scripts are read now, since the page may revoke the URL as soon as the worker exists
+            let source = match crate::networking::local_url::is_local(&url).then(|| crate::networking::local_url::resolve(&url)) {
+                Some(Ok(content)) => WorkerSource::Script(String::from_utf8_lossy(&content.data).into_owned()),
+                _ => WorkerSource::Url(url.clone()),
+            };
+            let worker = Worker::spawn(url.clone(), source, WorkerLimits::default());
+            let binding = WorkerBinding { worker, onmessage: None, onerror: None, listeners: Vec::new() };
+            self.workers.insert(captures[1].to_string(), binding);
+            self.variables.insert(captures[1].to_string(), JSValue::String("[object Worker]".to_string()));
+            return Ok(Some("undefined".to_string()));
+        }
+        
+        let member_regex = Regex::new(r#"(?s)^([A-Za-z_$][\w$]*)\.(postMessage|terminate|addEventListener|onmessage|onerror)\s*(?:\((.*)\)|=\s*(.+))$"#)?;
+        let Some(captures) = member_regex.captures(code) else {
+            return Ok(None);
+        };
+        let name = captures[1].to_string();
+        if !self.workers.contains_key(&name) {
+            return Ok(None);
+        }
+        let arguments = captures.get(3).map(|args| location::split_arguments(args.as_str())).unwrap_or_default();
+        let data = match (&captures[2], arguments.first()) {
+            ("postMessage", Some(data)) => Some(self.parse_value(data)?),
+            ("addEventListener", Some(event)) => Some(self.parse_value(event)?),
+            _ => None,
+        };
+        let Some(binding) = self.workers.get_mut(&name) else { return Ok(None) };
+        Ok(Some(match (&captures[2], captures.get(4)) {
+            ("postMessage", None) => match binding.worker.post_message(data.unwrap_or(JSValue::Undefined)) {
+                Ok(()) => "undefined".to_string(),
+                Err(error) => error,
+            },
+            ("terminate", None) => {
+                binding.worker.terminate();
+                self.workers.remove(&name);
+                "undefined".to_string()
+            }
+            ("addEventListener", None) => {
+                let callback = arguments.get(1).and_then(|callback| FrameCallback::parse(callback));
+                match (data.map(|event| event.to_string()).as_deref(), callback) {
+                    (Some("message"), Some(callback)) => binding.listeners.push(callback),
+                    (Some("error"), Some(callback)) => binding.onerror = Some(callback),
+                    (_, None) => return Ok(Some("TypeError: listener must be a function".to_string())),
+                    _ => {}
+                }
+                "undefined".to_string()
+            }
+            ("onmessage", Some(handler)) => {
+                binding.onmessage = FrameCallback::parse(handler.as_str());
+                "undefined".to_string()
+            }
+            ("onerror", Some(handler)) => {
+                binding.onerror = FrameCallback::parse(handler.as_str());
+                "undefined".to_string()
+            }
+            _ => return Ok(None),
+        }))
+    }
+    
+    fn handle_object_update(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle the array methods that change the array, and assigning a property or element, on arrays and
+        // objects held in variables, e.g. `cart.items.push(item)`; `window.x = ...` stays a global, as the
+        // assignment handler makes it
+        let code = code.trim_end_matches(';').trim();
+        let method_regex = Regex::new(r#"(?s)^([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)\.(push|pop|shift|unshift|splice|sort|reverse|fill)\s*\((.*)\)$"#)?;
+        if let Some(captures) = method_regex.captures(code) {
+            let Some(JSValue::Array(items)) = self.value_at(&captures[1]) else {
+                return Ok(None);
+            };
+            let mut items = items.clone();
+            let result = builtins::call_mutating_method(&mut items, &captures[2], &captures[3], self);
+            if let Some(target) = self.value_at_mut(&captures[1]) {
+                *target = JSValue::Array(items);
+            }
+            return Ok(Some(match result {
+                Ok(value) => value.to_string(),
+                Err(e) => e.to_string(),
+            }));
+        }
+        
+        let property_regex = Regex::new(r#"(?s)^([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*?)(?:\.([A-Za-z_$][\w$]*)|\[(.+?)\])\s*=\s*([^=].*)$"#)?;
+        let Some(captures) = property_regex.captures(code) else {
+            return Ok(None);
+        };
+        let path = &captures[1];
+        if path == "window" || !matches!(self.value_at(path), Some(JSValue::Object(_) | JSValue::Array(_))) {
+            return Ok(None);
+        }
+        let key = match (captures.get(2), captures.get(3)) {
+            (Some(property), _) => property.as_str().to_string(),
+            (_, Some(index)) => builtins::js_string(&self.parse_value(index.as_str())?),
+            _ => return Ok(None),
+        };
+        let value = self.parse_value(&captures[4])?;
+        let result = value.to_string();
+        match self.value_at_mut(path) {
+            Some(JSValue::Object(fields)) => {
+                fields.insert(key, value);
+            }
+            Some(JSValue::Array(items)) => {
+                // Writing past the end grows the array, leaving holes undefined
+                if let Ok(index) = key.parse::<usize>() {
+                    if index >= items.len() {
+                        items.resize(index + 1, JSValue::Undefined);
+                    }
+                    items[index] = value;
+                }
+            }
+            _ => {}
+        }
+        Ok(Some(result))
+    }
+    
+    // The value at a dotted path into the variables, like `cart.items.0`
+    fn value_at(&self, path: &str) -> Option<&JSValue> {
+        let mut parts = path.split('.');
+        let mut value = self.variables.get(parts.next()?)?;
+        for part in parts {
+            value = match value {
+                JSValue::Object(fields) => fields.get(part)?,
+                JSValue::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+    
+    fn value_at_mut(&mut self, path: &str) -> Option<&mut JSValue> {
+        let mut parts = path.split('.');
+        let mut value = self.variables.get_mut(parts.next()?)?;
+        for part in parts {
+            value = match value {
+                JSValue::Object(fields) => fields.get_mut(part)?,
+                JSValue::Array(items) => items.get_mut(part.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+    
+    fn handle_dom_mutation(&mut self, code: &str) -> Result<Option<String>> {
+        // Handle .remove(), .textContent/.innerText = value and .setAttribute(name, value) on
+        // document.querySelector(s) and document.getElementById(id), and on each of document.querySelectorAll(s) via forEach
+        let code = code.trim_end_matches(';').trim();
+        let target_regex = Regex::new(r#"^document\.(querySelector|querySelectorAll|getElementById)\s*\(\s*["']([^"']*)["']\s*\)(.*)$"#)?;
+        let Some(captures) = target_regex.captures(code) else {
+            return Ok(None);
+        };
+        let method = captures.get(1).map_or("", |m| m.as_str());
+        let argument = captures.get(2).map_or("", |m| m.as_str());
+        let mut rest = captures.get(3).map_or("", |m| m.as_str()).trim();
+        let selector = if method == "getElementById" { format!("#{}", argument) } else { argument.to_string() };
+        let all = method == "querySelectorAll";
+        
+        let for_each_regex = Regex::new(r#"^\.forEach\s*\(\s*\(?\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\)?\s*=>\s*(.*)\)$"#)?;
+        if all {
+            let Some(captures) = for_each_regex.captures(rest) else {
+                return Ok(None);
+            };
+            let name = captures.get(1).map_or("", |m| m.as_str());
+            let Some(body) = captures.get(2).map_or("", |m| m.as_str()).trim().strip_prefix(name) else {
+                return Ok(None);
+            };
+            rest = body;
+        }
+        
+        let assign_regex = Regex::new(r#"^\.(textContent|innerText)\s*=\s*(.+)$"#)?;
+        let attribute_regex = Regex::new(r#"^\.setAttribute\s*\(\s*["']([^"']*)["']\s*,\s*(.+)\)$"#)?;
+        let changed = if rest == ".remove()" {
+            self.dom_api.remove_elements(&selector, all)
+        } else if let Some(captures) = assign_regex.captures(rest) {
+            let value = self.parse_value(captures.get(2).map_or("", |m| m.as_str()))?.to_string();
+            self.dom_api.set_text_content(&selector, &value, all)
+        } else if let Some(captures) = attribute_regex.captures(rest) {
+            let name = captures.get(1).map_or("", |m| m.as_str());
+            let value = self.parse_value(captures.get(2).map_or("", |m| m.as_str()))?.to_string();
+            self.dom_api.set_attribute(&selector, name, &value, all)
+        } else {
+            return Ok(None);
+        };
+        self.dom_mutated |= changed > 0;
+        Ok(Some("undefined".to_string()))
+    }
+    
     fn handle_dom_api_call(&mut self, code: &str) -> Result<Option<String>> {
         // Handle document.querySelector() calls
         let query_selector_regex = Regex::new(r#"document\.querySelector\s*\(\s*["']([^"']*)["']\s*\)"#)?;
@@ -364,4 +1480,55 @@ impl JSEngine {
         // Basic DOM API will be implemented later
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl builtins::Host for JSEngine {
+    fn lookup(&self, path: &str) -> Option<JSValue> {
+        if path == "document.cookie" {
+            return Some(JSValue::String(self.cookies.as_ref().map(DocumentCookies::get).unwrap_or_default()));
+        }
+        self.variables.get(path).cloned()
+            .or_else(|| self.location_value(path))
+            .or_else(|| self.navigator_value(path))
+    }
+    
+    fn call(&self, path: &str, args: &[JSValue]) -> Option<JSValue> {
+        let message = args.iter().map(console_text).collect::<Vec<_>>().join(" ");
+        match path {
+            "console.log" | "console.debug" => self.console_api.log(&message),
+            "console.info" => self.console_api.info(&message),
+            "console.warn" => self.console_api.warn(&message),
+            "console.error" => self.console_api.error(&message),
+            _ => return None,
+        }
+        Some(JSValue::Undefined)
+    }
+    
+    fn date_method(&self, time: f64, method: &str) -> Result<JSValue> {
+        self.date_value(time, method)
+    }
+    
+    fn check_running_time(&self) -> Result<()> {
+        self.watchdog.check()
+    }
+}
+
+// A console argument as the console shows it: strings as they are, objects and arrays as JSON
+fn console_text(value: &JSValue) -> String {
+    match value {
+        JSValue::Object(_) | JSValue::Array(_) => builtins::to_json(value).unwrap_or_else(|| value.to_string()),
+        value => builtins::js_string(value),
+    }
+}
+
+// Locale and zone for scripts: dev tools overrides first, then the uniform ones if fingerprinting is reduced
+fn script_locale(overrides: &LocaleOverrides) -> LocaleEnvironment {
+    if !crate::storage::Preferences::current().privacy.reduce_fingerprinting {
+        return LocaleEnvironment::new(overrides);
+    }
+    LocaleEnvironment::new(&LocaleOverrides {
+        locale: overrides.locale.clone().or_else(|| Some(navigator::REDUCED_LOCALE.to_string())),
+        time_zone: overrides.time_zone.clone().or_else(|| Some(navigator::REDUCED_TIME_ZONE.to_string())),
+        ..overrides.clone()
+    })
+}
@@ -0,0 +1,137 @@
+// What scripts learn about the browser and machine through `navigator`, `screen` and the window's size, and the
+// uniform values reported instead while fingerprinting reduction is on
+use crate::networking::connectivity;
+use crate::networking::throttling::NetworkConditions;
+use crate::networking::user_agent::UserAgentPreset;
+use crate::storage::PrivacySettings;
+
+/// The display the browser window is on, and the part of the window the page is shown in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenMetrics {
+    /// In CSS pixels
+    pub width: f32,
+    pub height: f32,
+    pub device_pixel_ratio: f32,
+    /// The page's viewport in CSS pixels, for `window.innerWidth` and `innerHeight`
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+}
+
+impl Default for ScreenMetrics {
+    fn default() -> Self {
+        REDUCED_SCREEN
+    }
+}
+
+// Reported by every browser reducing its fingerprint, so they all look alike
+const REDUCED_SCREEN: ScreenMetrics = ScreenMetrics {
+    width: 1920.0,
+    height: 1080.0,
+    device_pixel_ratio: 1.0,
+    viewport_width: 1400.0,
+    viewport_height: 900.0,
+};
+// The reported viewport is rounded down to these steps, so the exact window size doesn't tell browsers apart
+const VIEWPORT_STEP: [f32; 2] = [200.0, 100.0];
+const REDUCED_HARDWARE_CONCURRENCY: usize = 2;
+/// Zone and locale scripts see instead of the machine's, unless dev tools emulate others
+pub const REDUCED_TIME_ZONE: &str = "UTC";
+pub const REDUCED_LOCALE: &str = "en-US";
+
+/// The `navigator` and `screen` values one page's scripts see
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigatorInfo {
+    pub user_agent: &'static str,
+    pub platform: &'static str,
+    pub hardware_concurrency: usize,
+    pub do_not_track: bool,
+    pub global_privacy_control: bool,
+    pub online: bool,
+    pub vendor: &'static str,
+    pub max_touch_points: u32,
+    pub screen: ScreenMetrics,
+}
+
+impl NavigatorInfo {
+    /// Values for a page requested with `preset`, on a machine with `screen`
+    pub fn new(preset: UserAgentPreset, privacy: &PrivacySettings, screen: ScreenMetrics) -> Self {
+        let reduce = privacy.reduce_fingerprinting;
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self {
+            user_agent: preset.user_agent(),
+            platform: preset.platform(),
+            hardware_concurrency: if reduce { REDUCED_HARDWARE_CONCURRENCY } else { cores },
+            do_not_track: privacy.do_not_track,
+            global_privacy_control: privacy.global_privacy_control,
+            online: NetworkConditions::current() != NetworkConditions::Offline && connectivity::route_available(),
+            vendor: preset.vendor(),
+            max_touch_points: preset.max_touch_points(),
+            screen: if reduce { reduced_screen(screen) } else { screen },
+        }
+    }
+
+    /// `navigator.<name>`, `screen.<name>` or `window.<name>` as a script would print it; None for other properties
+    pub fn property(&self, object: &str, name: &str) -> Option<String> {
+        let screen = &self.screen;
+        Some(match (object, name) {
+            ("navigator", "userAgent") => self.user_agent.to_string(),
+            // Everything after "Mozilla/", as browsers report it
+            ("navigator", "appVersion") => self.user_agent.trim_start_matches("Mozilla/").to_string(),
+            ("navigator", "appName") => "Netscape".to_string(),
+            ("navigator", "product") => "Gecko".to_string(),
+            ("navigator", "vendor") => self.vendor.to_string(),
+            ("navigator", "platform") => self.platform.to_string(),
+            ("navigator", "hardwareConcurrency") => self.hardware_concurrency.to_string(),
+            ("navigator", "maxTouchPoints") => self.max_touch_points.to_string(),
+            ("navigator", "onLine") => self.online.to_string(),
+            ("navigator", "cookieEnabled") => "true".to_string(),
+            ("navigator", "webdriver") => "false".to_string(),
+            ("navigator", "doNotTrack") => if self.do_not_track { "1" } else { "null" }.to_string(),
+            ("navigator", "globalPrivacyControl") => self.global_privacy_control.to_string(),
+            ("screen", "width" | "availWidth") => screen.width.round().to_string(),
+            ("screen", "height" | "availHeight") => screen.height.round().to_string(),
+            ("screen", "colorDepth" | "pixelDepth") => "24".to_string(),
+            ("window", "devicePixelRatio") => screen.device_pixel_ratio.to_string(),
+            ("window", "innerWidth") => screen.viewport_width.round().to_string(),
+            ("window", "innerHeight") => screen.viewport_height.round().to_string(),
+            _ => return None,
+        })
+    }
+}
+
+// The common screen, with the viewport rounded down but still close enough for layout scripts to use
+fn reduced_screen(screen: ScreenMetrics) -> ScreenMetrics {
+    let step = |size: f32, step: f32| ((size / step).floor() * step).max(step);
+    ScreenMetrics {
+        viewport_width: step(screen.viewport_width, VIEWPORT_STEP[0]).min(REDUCED_SCREEN.width),
+        viewport_height: step(screen.viewport_height, VIEWPORT_STEP[1]).min(REDUCED_SCREEN.height),
+        ..REDUCED_SCREEN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprinting_reduction() {
+        let screen = ScreenMetrics { width: 2560.0, height: 1440.0, device_pixel_ratio: 2.0, viewport_width: 1283.0, viewport_height: 755.0 };
+        let privacy = PrivacySettings { do_not_track: true, ..Default::default() };
+        let info = NavigatorInfo::new(UserAgentPreset::ChromeWindows, &privacy, screen);
+        assert_eq!(info.property("screen", "width").as_deref(), Some("2560"));
+        assert_eq!(info.property("window", "devicePixelRatio").as_deref(), Some("2"));
+        assert_eq!(info.property("navigator", "platform").as_deref(), Some("Win32"));
+        assert_eq!(info.property("navigator", "doNotTrack").as_deref(), Some("1"));
+        assert_eq!(info.property("navigator", "globalPrivacyControl").as_deref(), Some("false"));
+        assert_eq!(info.property("navigator", "vendor").as_deref(), Some("Google Inc."));
+        assert_eq!(info.property("window", "innerWidth").as_deref(), Some("1283"));
+
+        let reduced = NavigatorInfo::new(UserAgentPreset::ChromeWindows, &PrivacySettings { reduce_fingerprinting: true, ..privacy }, screen);
+        assert_eq!(reduced.property("screen", "availHeight").as_deref(), Some("1080"));
+        assert_eq!(reduced.property("window", "devicePixelRatio").as_deref(), Some("1"));
+        assert_eq!(reduced.property("navigator", "hardwareConcurrency").as_deref(), Some("2"));
+        assert_eq!(reduced.property("window", "innerWidth").as_deref(), Some("1200"));
+        assert_eq!(reduced.property("window", "innerHeight").as_deref(), Some("700"));
+        assert_eq!(reduced.property("navigator", "battery"), None);
+    }
+}
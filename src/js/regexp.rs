@@ -0,0 +1,141 @@
+// Regular expressions for scripts: regex literals and RegExp, run on the regex crate. Patterns are translated to
+// its syntax where JavaScript's differs; what it can't do, like lookaround and backreferences, is a SyntaxError.
+use anyhow::{bail, Result};
+use regex::{Captures, Regex};
+
+// Flags in the order RegExp.prototype.flags lists them
+const FLAGS: &str = "dgimsuy";
+
+/// `flags` checked and put in their usual order, as `RegExp.prototype.flags` gives them
+pub fn normalize_flags(flags: &str) -> Result<String> {
+    let mut sorted: Vec<char> = flags.chars().collect();
+    sorted.sort_by_key(|flag| FLAGS.find(*flag));
+    let valid = sorted.iter().all(|flag| FLAGS.contains(*flag)) && sorted.windows(2).all(|pair| pair[0] != pair[1]);
+    if !valid {
+        bail!("SyntaxError: Invalid flags supplied to RegExp constructor '{}'", flags);
+    }
+    Ok(sorted.into_iter().collect())
+}
+
+/// The pattern `/source/flags` compiled for matching
+pub fn compile(source: &str, flags: &str) -> Result<Regex> {
+    let mut pattern = String::new();
+    for (flag, inline) in [('i', "(?i)"), ('m', "(?m)"), ('s', "(?s)")] {
+        if flags.contains(flag) {
+            pattern.push_str(inline);
+        }
+    }
+    pattern.push_str(&translate(source));
+    Regex::new(&pattern).map_err(|e| {
+        let reason = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+        anyhow::anyhow!("SyntaxError: Invalid regular expression: /{}/{}: {}", source, flags, reason)
+    })
+}
+
+// JavaScript's character classes are ASCII where the regex crate's are Unicode, and `[^]` matches anything
+fn translate(source: &str) -> String {
+    let mut translated = String::new();
+    let mut chars = source.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('d') => translated.push_str(if in_class { "0-9" } else { "[0-9]" }),
+                Some('w') => translated.push_str(if in_class { "A-Za-z0-9_" } else { "[A-Za-z0-9_]" }),
+                Some('D') if !in_class => translated.push_str("[^0-9]"),
+                Some('W') if !in_class => translated.push_str("[^A-Za-z0-9_]"),
+                Some('/') => translated.push('/'),
+                Some(escaped) => {
+                    translated.push('\\');
+                    translated.push(escaped);
+                }
+                None => translated.push_str("\\\\"),
+            },
+            '[' if !in_class => {
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                    if chars.peek() == Some(&']') {
+                        chars.next();
+                        translated.push_str("(?s:.)");
+                        continue;
+                    }
+                    translated.push_str("[^");
+                } else {
+                    translated.push('[');
+                }
+                in_class = true;
+            }
+            // A literal `[` inside a class needs escaping in the regex crate, where it would start a nested class
+            '[' => translated.push_str("\\["),
+            ']' if in_class => {
+                in_class = false;
+                translated.push(']');
+            }
+            c => translated.push(c),
+        }
+    }
+    translated
+}
+
+/// A replacement string with its `$&`, `$1`, `$<name>`, `` $` ``, `$'` and `$$` patterns filled in from a match
+pub fn expand(replacement: &str, captures: &Captures, input: &str) -> String {
+    let whole = captures.get(0).map_or(0..0, |m| m.range());
+    let group = |index: usize| captures.get(index).map_or("", |m| m.as_str());
+    let mut expanded = String::new();
+    let mut rest = replacement;
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (text, used) = match after.chars().next() {
+            Some('$') => ("$", 1),
+            Some('&') => (group(0), 1),
+            Some('`') => (&input[..whole.start], 1),
+            Some('\'') => (&input[whole.end..], 1),
+            Some('<') => match after.find('>') {
+                Some(end) => (captures.name(&after[1..end]).map_or("", |m| m.as_str()), end + 1),
+                None => ("$<", 1),
+            },
+            Some(digit) if digit.is_ascii_digit() => {
+                // Two digits when there are that many groups, as in `$10`
+                let two = after.get(..2).and_then(|digits| digits.parse::<usize>().ok()).filter(|n| *n > 0 && *n < captures.len());
+                match two {
+                    Some(n) => (group(n), 2),
+                    None => match digit.to_digit(10).map(|n| n as usize).filter(|n| *n > 0 && *n < captures.len()) {
+                        Some(n) => (group(n), 1),
+                        None => (&rest[dollar..dollar + 2], 1),
+                    },
+                }
+            }
+            _ => ("$", 0),
+        };
+        expanded.push_str(text);
+        rest = &after[used..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The index JavaScript gives the position `byte` of `text`, counted in characters
+pub fn char_index(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_and_replacements() {
+        let date = compile(r"(?<year>\d{4})-(\d\d)", "").unwrap();
+        let captures = date.captures("due 2024-05").unwrap();
+        assert_eq!(expand("$2/$<year> ($&) $$1", &captures, "due 2024-05"), "05/2024 (2024-05) $1");
+        assert_eq!(expand("[$`]", &captures, "due 2024-05"), "[due ]");
+
+        assert!(compile(r"\d", "").unwrap().find("٣").is_none());
+        assert!(compile("[^]+", "").unwrap().is_match("a\nb"));
+        assert!(compile("HELLO", "i").unwrap().is_match("hello"));
+        assert!(compile(r"(a)\1", "").unwrap_err().to_string().starts_with("SyntaxError: Invalid regular expression"));
+        assert_eq!(normalize_flags("yig").unwrap(), "giy");
+        assert!(normalize_flags("gg").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+// Slow-script watchdog: meters how long a page's scripts run in one turn of its event loop, and interrupts them
+// when they go over so a runaway script can't freeze the tab
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result};
+
+/// Seconds one turn may take before the page's scripts are interrupted, unless settings say otherwise
+pub const DEFAULT_SLOW_SCRIPT_SECS: u64 = 10;
+
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    budget: Duration,
+    // When the turn being run has to end; None between turns, when nothing is metered
+    deadline: Option<Instant>,
+    // Whether a turn ran out of time since the last `take_tripped`
+    tripped: Cell<bool>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_SLOW_SCRIPT_SECS))
+    }
+}
+
+impl Watchdog {
+    pub fn new(budget: Duration) -> Self {
+        Self { budget, deadline: None, tripped: Cell::new(false) }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Start metering a turn: running inline scripts, animation frames, or an event's handlers
+    pub fn start_turn(&mut self) {
+        self.deadline = Some(Instant::now() + self.budget);
+    }
+
+    /// The error that interrupts the script once the turn is out of time
+    pub fn check(&self) -> Result<()> {
+        if self.deadline.is_some_and(|deadline| Instant::now() > deadline) {
+            self.tripped.set(true);
+            bail!("RangeError: Script ran longer than {} ms and was interrupted", self.budget.as_millis());
+        }
+        Ok(())
+    }
+
+    /// Whether a turn ran out of time since the last call
+    pub fn take_tripped(&self) -> bool {
+        self.tripped.replace(false)
+    }
+
+    /// The user chose to wait for the page: give its scripts twice as long from the next turn on
+    pub fn extend(&mut self) {
+        self.budget = self.budget.saturating_mul(2).max(Duration::from_millis(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turns_run_out_of_time() {
+        let mut watchdog = Watchdog::new(Duration::from_millis(20));
+        assert!(watchdog.check().is_ok());
+        watchdog.start_turn();
+        assert!(watchdog.check().is_ok());
+        assert!(!watchdog.take_tripped());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(watchdog.check().unwrap_err().to_string().starts_with("RangeError: Script ran longer than 20 ms"));
+        assert!(watchdog.take_tripped());
+        assert!(!watchdog.take_tripped());
+
+        watchdog.extend();
+        watchdog.start_turn();
+        assert!(watchdog.check().is_ok());
+        assert_eq!(watchdog.budget(), Duration::from_millis(40));
+    }
+}
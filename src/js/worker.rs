@@ -0,0 +1,226 @@
+// Dedicated workers: scripts that run in an engine of their own on the executor, without a DOM, and talk to the
+// page that started them only through postMessage
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::js::{builtins, JSEngine, JSValue, ScriptError};
+use crate::networking::local_url;
+use crate::networking::manual_client::ManualHttpClient;
+
+/// Workers one page may have running at once
+pub const MAX_WORKERS_PER_PAGE: usize = 4;
+
+/// What a worker may use
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerLimits {
+    /// Statements one task may run: the script's first run, or handling one message
+    pub max_statements: usize,
+    /// Time one task may take, checked between statements
+    pub max_run_time: Duration,
+    /// Largest message, as JSON, either way
+    pub max_message_bytes: usize,
+    /// Messages waiting for the worker before postMessage refuses more
+    pub max_queued_messages: usize,
+}
+
+impl Default for WorkerLimits {
+    fn default() -> Self {
+        Self {
+            max_statements: 10_000,
+            max_run_time: Duration::from_secs(5),
+            max_message_bytes: 1024 * 1024,
+            max_queued_messages: 64,
+        }
+    }
+}
+
+/// What a worker sent its page
+#[derive(Debug, Clone)]
+pub enum WorkerEvent {
+    Message(JSValue),
+    /// Console lines the worker logged, already formatted
+    Console(Vec<String>),
+    /// An uncaught error, which also ends the task it happened in
+    Error(ScriptError),
+    /// The worker stopped, by calling close() or because its script couldn't be loaded
+    Closed,
+}
+
+/// Where a worker's script comes from
+pub enum WorkerSource {
+    /// Already resolved, as blob: and data: URLs are when the worker is created, since they may be revoked right after
+    Script(String),
+    /// An http(s) URL, fetched by the worker
+    Url(String),
+}
+
+/// The page's side of a running worker
+pub struct Worker {
+    pub url: String,
+    to_worker: Option<SyncSender<JSValue>>,
+    from_worker: Receiver<WorkerEvent>,
+    closed: Arc<AtomicBool>,
+    limits: WorkerLimits,
+    _task: TaskHandle<()>,
+}
+
+impl Worker {
+    pub fn spawn(url: String, source: WorkerSource, limits: WorkerLimits) -> Self {
+        let (to_worker, inbox) = mpsc::sync_channel(limits.max_queued_messages);
+        let (outbox, from_worker) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+        let worker_closed = closed.clone();
+        let script_url = url.clone();
+        let task = Executor::shared().spawn_blocking(TaskKind::Worker, move || {
+            run(&script_url, source, limits, inbox, &outbox);
+            worker_closed.store(true, Ordering::Relaxed);
+            let _ = outbox.send(WorkerEvent::Closed);
+        });
+        Self { url, to_worker: Some(to_worker), from_worker, closed, limits, _task: task }
+    }
+
+    /// Queue `data` for the worker's message handlers; the error is what postMessage throws
+    pub fn post_message(&self, data: JSValue) -> std::result::Result<(), String> {
+        check_size(&data, self.limits.max_message_bytes)?;
+        let Some(to_worker) = &self.to_worker else { return Ok(()) };
+        match to_worker.try_send(data) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(format!("RangeError: Worker {} has too many messages waiting", self.url)),
+        }
+    }
+
+    /// Everything the worker sent since the last call
+    pub fn take_events(&self) -> Vec<WorkerEvent> {
+        self.from_worker.try_iter().collect()
+    }
+
+    /// Stop the worker once it finishes what it's doing; messages it hasn't handled are dropped
+    pub fn terminate(&mut self) {
+        self.to_worker = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.to_worker.is_some() && !self.closed.load(Ordering::Relaxed)
+    }
+}
+
+/// The address of a worker script created by a document at `document_url`: it must be on the document's
+/// origin, or a blob: or data: URL. The error is what `new Worker` throws.
+pub fn script_url(document_url: &str, target: &str) -> std::result::Result<String, String> {
+    if local_url::is_local(target) {
+        return Ok(target.to_string());
+    }
+    let resolved = crate::js::location::resolve(document_url, target).ok_or_else(|| format!("SyntaxError: Invalid URL '{}'", target))?;
+    let origin = |url: &str| url::Url::parse(url).ok().map(|url| url.origin());
+    if origin(&resolved).is_none_or(|origin| !origin.is_tuple()) || origin(&resolved) != origin(document_url) {
+        return Err(format!("SecurityError: Failed to construct 'Worker': Script at '{}' cannot be accessed from origin '{}'",
+            resolved, crate::js::location::location_property(document_url, "origin").unwrap_or_default()));
+    }
+    Ok(resolved)
+}
+
+fn check_size(data: &JSValue, max_bytes: usize) -> std::result::Result<(), String> {
+    if builtins::to_json(data).is_some_and(|json| json.len() > max_bytes) {
+        return Err(format!("RangeError: Message is larger than the {} KB a worker message may be", max_bytes / 1024));
+    }
+    Ok(())
+}
+
+// The worker's life, on a blocking thread: run its script, then handle messages until the page drops it or it
+// closes itself
+fn run(url: &str, source: WorkerSource, limits: WorkerLimits, inbox: Receiver<JSValue>, outbox: &mpsc::Sender<WorkerEvent>) {
+    let script = match source {
+        WorkerSource::Script(script) => Ok(script),
+        WorkerSource::Url(url) => fetch_script(&url),
+    };
+    let mut engine = match script.and_then(|script| Ok((script, JSEngine::new_worker()?))) {
+        Ok((script, mut engine)) => {
+            engine.set_document_url(url);
+            engine.start_budget(limits.max_statements, limits.max_run_time);
+            let _ = engine.run_script(&script, url);
+            engine
+        }
+        Err(e) => {
+            let error = ScriptError {
+                severity: crate::js::ErrorSeverity::Error,
+                message: format!("NetworkError: Failed to load worker script: {}", e),
+                source: url.to_string(),
+                line: 1,
+            };
+            let _ = outbox.send(WorkerEvent::Console(vec![format!("[ERROR] Uncaught {} ({})", error.message, error.location())]));
+            let _ = outbox.send(WorkerEvent::Error(error));
+            return;
+        }
+    };
+    loop {
+        let output = engine.take_console_output();
+        if !output.is_empty() {
+            let _ = outbox.send(WorkerEvent::Console(output));
+        }
+        for error in engine.take_errors() {
+            let _ = outbox.send(WorkerEvent::Error(error));
+        }
+        for message in engine.take_posted_messages() {
+            let event = match check_size(&message, limits.max_message_bytes) {
+                Ok(()) => WorkerEvent::Message(message),
+                Err(error) => WorkerEvent::Error(ScriptError { severity: crate::js::ErrorSeverity::Error, message: error, source: url.to_string(), line: 1 }),
+            };
+            let _ = outbox.send(event);
+        }
+        if engine.is_closing() {
+            return;
+        }
+        let Ok(data) = inbox.recv() else { return };
+        engine.start_budget(limits.max_statements, limits.max_run_time);
+        engine.dispatch_worker_message(data);
+    }
+}
+
+fn fetch_script(url: &str) -> Result<String> {
+    if local_url::is_local(url) {
+        return Ok(String::from_utf8_lossy(&local_url::resolve(url)?.data).into_owned());
+    }
+    let client = ManualHttpClient::new()?;
+    let fetched = Executor::shared().handle().block_on(client.fetch(url))?;
+    if !fetched.response.is_success() {
+        return Err(anyhow!("HTTP {}", fetched.response.status_code));
+    }
+    fetched.response.body_as_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn wait_for(worker: &Worker, count: usize) -> Vec<WorkerEvent> {
+        let started = Instant::now();
+        let mut events = Vec::new();
+        while events.len() < count && started.elapsed() < Duration::from_secs(10) {
+            events.extend(worker.take_events());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        events
+    }
+
+    #[test]
+    fn test_workers_exchange_messages() {
+        let script = "onmessage = (e) => { postMessage(e.data.map(n => n * n)) }\nconsole.log('ready')".to_string();
+        let limits = WorkerLimits { max_queued_messages: 4, ..WorkerLimits::default() };
+        let mut worker = Worker::spawn("blob:null/squares".to_string(), WorkerSource::Script(script), limits);
+        worker.post_message(JSValue::Array(vec![JSValue::Number(2.0), JSValue::Number(3.0)])).unwrap();
+        let events = wait_for(&worker, 2);
+        assert!(matches!(&events[0], WorkerEvent::Console(lines) if lines == &["[LOG] ready".to_string()]));
+        assert!(matches!(&events[1], WorkerEvent::Message(JSValue::Array(squares)) if builtins::js_string(&JSValue::Array(squares.clone())) == "4,9"));
+        assert!(worker.post_message(JSValue::String("x".repeat(2 * 1024 * 1024))).unwrap_err().starts_with("RangeError"));
+
+        worker.terminate();
+        assert!(!worker.is_running());
+        assert!(matches!(wait_for(&worker, 1).last(), Some(WorkerEvent::Closed)));
+        assert!(script_url("https://app.example/index.html", "https://cdn.example/w.js").unwrap_err().starts_with("SecurityError"));
+        assert_eq!(script_url("https://app.example/index.html", "w.js").as_deref(), Ok("https://app.example/w.js"));
+    }
+}
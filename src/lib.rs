@@ -0,0 +1,14 @@
+// The browser as a library, for main.rs and for the fuzz targets under fuzz/ to reach the parsers and decoders
+#![allow(dead_code)] // Allow dead code for future engine components
+pub mod networking;
+pub mod ui;
+pub mod engine;
+pub mod js;
+pub mod security;
+pub mod pages;
+pub mod storage;
+pub mod extensions;
+pub mod sync;
+pub mod translate;
+pub mod platform;
+
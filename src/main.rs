@@ -1,20 +1,28 @@
-#![allow(dead_code)] // Allow dead code for future engine components
-pub mod networking;
-pub mod ui;
-pub mod engine;
-pub mod js;
-pub mod security;
-pub mod pages;
-pub mod storage;
-
 use eframe::egui;
 use log::info;
+use neonsearch::{js, platform, storage, ui};
+use platform::CommandLine;
 
 fn main() -> Result<(), eframe::Error> {
     // Initialize logger
-    env_logger::init();
+    platform::logging::init();
+    storage::crash_reports::install_panic_hook();
     info!("Starting NeonSearch Browser by NeonDev™");
 
+    let command_line = CommandLine::from_env();
+    // Headless runs write their output and exit before anything else prints or a window opens
+    if command_line.headless {
+        let Some(url) = command_line.urls.first() else {
+            eprintln!("--headless needs an address to load");
+            std::process::exit(2);
+        };
+        if let Err(e) = platform::headless::run(url, &command_line.headless_options) {
+            eprintln!("Headless load of {} failed: {}", url, e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Test JavaScript engine on startup
     println!("\n🚀 NeonSearch v0.2.0 - JavaScript Engine Test");
     js::test::test_js_engine();
@@ -29,10 +37,52 @@ fn main() -> Result<(), eframe::Error> {
         std::env::set_var("RUST_BACKTRACE", "1");
     }
 
+    if command_line.set_default_browser {
+        if let Err(e) = platform::default_browser::register() {
+            eprintln!("Could not make NeonSearch the default browser: {}", e);
+        }
+        return Ok(());
+    }
+
+    // A browser already running carries out this launch instead; windows it starts run on their own
+    let instance = if command_line.standalone {
+        None
+    } else {
+        match platform::single_instance::acquire(&storage::data_dir(), &command_line.remote_command()) {
+            Ok(None) => {
+                info!("Handed {} link(s) to the running NeonSearch", command_line.urls.len());
+                return Ok(());
+            }
+            // A private window never becomes the instance others hand their links to
+            Ok(Some(_)) if command_line.private => None,
+            Ok(Some(listener)) => Some(listener),
+            Err(e) => {
+                log::warn!("Could not set up the single-instance listener: {}", e);
+                None
+            }
+        }
+    };
+    // Tools can drive this window over a local WebSocket when asked for on the command line
+    let debugger = command_line.remote_debugging_port.and_then(|port| match platform::remote_debugging::DebugListener::bind(port) {
+        Ok(debugger) => {
+            eprintln!("Remote debugging listening on {}", debugger.url());
+            Some(debugger)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    });
+    let title = if command_line.private {
+        "NeonSearch - Private Window"
+    } else {
+        "NeonSearch - Web Browser by NeonDev™"
+    };
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.0, 800.0])
-            .with_title("NeonSearch - Web Browser by NeonDev™")
+            .with_title(title)
             .with_min_inner_size([800.0, 600.0])
             .with_resizable(true)
             .with_decorations(true),
@@ -44,10 +94,10 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "NeonSearch",
         options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             // Configure egui style for NeonSearch theme
             setup_custom_style(&cc.egui_ctx);
-            Ok(Box::new(ui::NeonSearchApp::new(cc)))
+            Ok(Box::new(ui::NeonSearchApp::new(cc, command_line.urls, command_line.private, instance, debugger)))
         }),
     )
 }
@@ -0,0 +1,112 @@
+// Character encoding detection and decoding for response bodies
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+// How far into the document to look for a <meta charset> declaration
+const META_SNIFF_LIMIT: usize = 1024;
+
+/// Extract the charset parameter from a Content-Type header value
+pub fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim().trim_matches(|c: char| c == '"' || c == '\'').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Look for `<meta charset>` or an http-equiv Content-Type in the first 1KB of the body
+pub fn sniff_meta_charset(body: &[u8]) -> Option<String> {
+    let head = &body[..body.len().min(META_SNIFF_LIMIT)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    let mut search_from = 0;
+    while let Some(pos) = head[search_from..].find("<meta") {
+        let tag_start = search_from + pos;
+        let tag_end = head[tag_start..].find('>').map(|e| tag_start + e).unwrap_or(head.len());
+        let tag = &head[tag_start..tag_end];
+
+        if let Some(idx) = tag.find("charset=") {
+            let value: String = tag[idx + "charset=".len()..]
+                .trim_start_matches(['"', '\''])
+                .chars()
+                .take_while(|c| !matches!(c, '"' | '\'' | ';' | ' ' | '/' | '>'))
+                .collect();
+            if !value.is_empty() {
+                return Some(value);
+            }
+        }
+        search_from = tag_end;
+    }
+    None
+}
+
+/// Pick the encoding for a body: BOM, then Content-Type charset, then <meta>, then UTF-8 or windows-1252
+pub fn detect_encoding(body: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+
+    let declared = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| sniff_meta_charset(body));
+    if let Some(label) = declared {
+        if let Some(encoding) = Encoding::for_label(label.as_bytes()) {
+            return encoding;
+        }
+        log::warn!("Unknown charset label '{}', falling back to sniffing", label);
+    }
+
+    if std::str::from_utf8(body).is_ok() {
+        UTF_8
+    } else {
+        // Browsers treat undeclared legacy content as windows-1252 (a superset of Latin-1)
+        WINDOWS_1252
+    }
+}
+
+/// Decode a response body into text using the detected encoding
+pub fn decode_body(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = detect_encoding(body, content_type);
+    let (text, used, had_errors) = encoding.decode(body);
+    if used != UTF_8 {
+        log::debug!("Decoded content as {}", used.name());
+    }
+    if had_errors {
+        log::debug!("Content contained byte sequences invalid for {}", used.name());
+    }
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charset_from_content_type() {
+        assert_eq!(
+            charset_from_content_type("text/html; charset=\"Shift_JIS\""),
+            Some("Shift_JIS".to_string())
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+    }
+
+    #[test]
+    fn test_meta_charset_sniffing() {
+        let html = br#"<html><head><meta http-equiv="Content-Type" content="text/html; charset=gbk"></head>"#;
+        assert_eq!(sniff_meta_charset(html), Some("gbk".to_string()));
+        assert_eq!(sniff_meta_charset(b"<meta charset='euc-kr'>"), Some("euc-kr".to_string()));
+    }
+
+    #[test]
+    fn test_decode_windows_1251() {
+        // "Привет" in windows-1251
+        let body = [0xCF, 0xF0, 0xE8, 0xE2, 0xE5, 0xF2];
+        assert_eq!(decode_body(&body, Some("text/html; charset=windows-1251")), "Привет");
+    }
+
+    #[test]
+    fn test_undeclared_legacy_bytes_use_windows_1252() {
+        assert_eq!(decode_body(&[0x93, 0x68, 0x69, 0x94], None), "\u{201C}hi\u{201D}");
+    }
+}
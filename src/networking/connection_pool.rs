@@ -0,0 +1,162 @@
+// Connections and DNS answers warmed ahead of requests: preconnect and dns-prefetch hints, and links the pointer
+// rests on. Requests still send Connection: close, so a warmed connection serves exactly one request.
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+
+// Servers drop idle connections after a few seconds; older ones are not worth trying
+const IDLE_LIMIT: Duration = Duration::from_secs(10);
+
+const DNS_TTL: Duration = Duration::from_secs(60);
+
+static SHARED: OnceLock<ConnectionPool> = OnceLock::new();
+
+/// A connection ready for a request: TLS has already been negotiated on `Tls` ones
+pub enum Connection {
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Connection {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Connection::Tcp(stream) => stream,
+            Connection::Tls(stream) => stream.get_ref().0,
+        }
+    }
+
+    // The server may have closed it while it sat in the pool
+    async fn is_open(&self) -> bool {
+        let mut byte = [0u8; 1];
+        // A timeout of zero still polls once, which is all a non-blocking check needs
+        match tokio::time::timeout(Duration::ZERO, self.tcp().peek(&mut byte)).await {
+            Err(_) => true,
+            // TLS servers may send session tickets after the handshake; on plain HTTP nothing is expected
+            Ok(Ok(n)) => n > 0 && matches!(self, Connection::Tls(_)),
+            Ok(Err(_)) => false,
+        }
+    }
+}
+
+/// Scheme, host and port a connection can serve
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Origin {
+    pub https: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Origin {
+    pub fn of(url: &str) -> Option<Self> {
+        let parsed = url::Url::parse(url).ok()?;
+        let https = match parsed.scheme() {
+            "https" => true,
+            "http" => false,
+            _ => return None,
+        };
+        Some(Self { https, host: parsed.host_str()?.to_string(), port: parsed.port_or_known_default()? })
+    }
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: HashMap<Origin, Vec<(Connection, Instant)>>,
+    // Origins being connected to, so repeated hints don't open more than one
+    connecting: HashSet<Origin>,
+    dns: HashMap<(String, u16), (Vec<SocketAddr>, Instant)>,
+}
+
+#[derive(Default)]
+pub struct ConnectionPool {
+    state: Mutex<PoolState>,
+}
+
+impl ConnectionPool {
+    pub fn shared() -> &'static ConnectionPool {
+        SHARED.get_or_init(ConnectionPool::default)
+    }
+
+    /// An open warmed connection to `origin`, if one is waiting
+    pub async fn take(&self, origin: &Origin) -> Option<Connection> {
+        loop {
+            let (connection, opened) = {
+                let mut state = self.state.lock().ok()?;
+                let idle = state.idle.get_mut(origin)?;
+                let taken = idle.pop();
+                if idle.is_empty() {
+                    state.idle.remove(origin);
+                }
+                taken?
+            };
+            if opened.elapsed() < IDLE_LIMIT && connection.is_open().await {
+                return Some(connection);
+            }
+        }
+    }
+
+    /// Whether a connection to `origin` is waiting or being opened
+    pub fn has_connection(&self, origin: &Origin) -> bool {
+        self.state.lock().is_ok_and(|state| {
+            state.connecting.contains(origin)
+                || state.idle.get(origin).is_some_and(|idle| idle.iter().any(|(_, opened)| opened.elapsed() < IDLE_LIMIT))
+        })
+    }
+
+    /// Mark `origin` as being connected to; false when it already is
+    pub fn begin_connecting(&self, origin: &Origin) -> bool {
+        self.state.lock().is_ok_and(|mut state| state.connecting.insert(origin.clone()))
+    }
+
+    /// Finish what `begin_connecting` started, keeping the connection when it succeeded
+    pub fn finish_connecting(&self, origin: &Origin, connection: Option<Connection>) {
+        let Ok(mut state) = self.state.lock() else { return };
+        state.connecting.remove(origin);
+        if let Some(connection) = connection {
+            let idle = state.idle.entry(origin.clone()).or_default();
+            idle.retain(|(_, opened)| opened.elapsed() < IDLE_LIMIT);
+            idle.push((connection, Instant::now()));
+        }
+    }
+
+    pub fn cached_addresses(&self, host: &str, port: u16) -> Option<Vec<SocketAddr>> {
+        let state = self.state.lock().ok()?;
+        let (addresses, resolved) = state.dns.get(&(host.to_string(), port))?;
+        (resolved.elapsed() < DNS_TTL).then(|| addresses.clone())
+    }
+
+    pub fn cache_addresses(&self, host: &str, port: u16, addresses: Vec<SocketAddr>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.dns.retain(|_, (_, resolved)| resolved.elapsed() < DNS_TTL);
+            state.dns.insert((host.to_string(), port), (addresses, Instant::now()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pooled_connection_serves_one_request() {
+        crate::engine::executor::Executor::shared().block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let origin = Origin::of(&format!("http://{}/", address)).unwrap();
+            assert_eq!(origin, Origin { https: false, host: "127.0.0.1".to_string(), port: address.port() });
+
+            let pool = ConnectionPool::default();
+            assert!(pool.begin_connecting(&origin));
+            assert!(!pool.begin_connecting(&origin));
+            let stream = TcpStream::connect(address).await.unwrap();
+            let (_server_side, _) = listener.accept().await.unwrap();
+            pool.finish_connecting(&origin, Some(Connection::Tcp(stream)));
+
+            assert!(pool.has_connection(&origin));
+            assert!(pool.take(&origin).await.is_some());
+            assert!(pool.take(&origin).await.is_none());
+        });
+    }
+}
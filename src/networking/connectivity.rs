@@ -0,0 +1,50 @@
+// Whether the machine can reach the network at all, so a failed load says "offline" rather than showing a
+// generic error, and retries once the connection is back
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::networking::throttling::NetworkConditions;
+
+// How often the OS routes are looked at while something waits for the connection to come back
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// A name every working resolver answers, looked up to tell "offline" from a single site that doesn't exist
+const PROBE_HOST: &str = "example.com:80";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+static ROUTE_AVAILABLE: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Whether the OS has a route to the internet. Connecting a UDP socket only consults the routing table,
+/// so nothing is sent.
+pub fn has_route() -> bool {
+    let targets = [
+        (SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)), SocketAddr::from((Ipv4Addr::new(1, 1, 1, 1), 53))),
+        (SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)), SocketAddr::from((Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111), 53))),
+    ];
+    targets.iter().any(|(local, remote)| UdpSocket::bind(local).and_then(|socket| socket.connect(remote)).is_ok())
+}
+
+/// Whether a failed load failed because the machine is offline: no route, no resolver answering, or the
+/// dev console emulating no network
+pub async fn is_offline() -> bool {
+    if NetworkConditions::current() == NetworkConditions::Offline || !has_route() {
+        return true;
+    }
+    !matches!(tokio::time::timeout(PROBE_TIMEOUT, tokio::net::lookup_host(PROBE_HOST)).await, Ok(Ok(_)))
+}
+
+/// The OS's view of connectivity, kept current by a background poll that starts on first use
+pub fn route_available() -> bool {
+    ROUTE_AVAILABLE
+        .get_or_init(|| {
+            let available = Arc::new(AtomicBool::new(has_route()));
+            let poll = available.clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(POLL_INTERVAL);
+                poll.store(has_route(), Ordering::Relaxed);
+            });
+            available
+        })
+        .load(Ordering::Relaxed)
+}
@@ -1,7 +1,9 @@
 // Cookie management for web requests
 
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +19,47 @@ pub struct Cookie {
     pub same_site: Option<String>,
 }
 
+impl Cookie {
+    /// Session cookies have neither Expires nor Max-Age and only live until the browser exits
+    pub fn is_session(&self) -> bool {
+        self.expires.is_none() && self.max_age.is_none()
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+// Cookie dates are RFC 1123 dates, though older servers still send the dashed Netscape form
+fn parse_cookie_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value.trim_end_matches(" GMT"), "%a, %d-%b-%Y %H:%M:%S")
+        .ok()
+        .map(|date| date.and_utc())
+}
+
+// Separates a third-party host from the top-level site its partitioned cookies belong to
+const PARTITION_SEPARATOR: char = '^';
+
+// Whether the jar key `key` is `host`, one of its subdomains, or partitioned under either
+fn belongs_to_site(key: &str, host: &str) -> bool {
+    let host = host.to_lowercase();
+    let suffix = format!(".{}", host);
+    let matches = |domain: &str| domain == host || domain.ends_with(&suffix);
+    match key.split_once(PARTITION_SEPARATOR) {
+        Some((domain, top_site)) => matches(domain) || matches(top_site),
+        None => matches(key),
+    }
+}
+
+/// Jar key for the cookies `domain` sets while embedded in pages of `top_site`, kept apart from its own
+pub fn partition_key(domain: &str, top_site: &str) -> String {
+    format!("{}{}{}", domain, PARTITION_SEPARATOR, top_site)
+}
+
+#[derive(Default)]
 pub struct CookieManager {
     cookies: HashMap<String, Vec<Cookie>>,
 }
@@ -28,12 +71,49 @@ impl CookieManager {
         }
     }
     
+    /// Where persistent cookies are kept between runs
+    pub fn path() -> PathBuf {
+        crate::storage::data_dir().join("cookies.json")
+    }
+
+    /// A jar holding the persistent cookies saved at `path` that haven't expired yet
+    pub fn load(path: &Path) -> Self {
+        let saved: HashMap<String, Vec<Cookie>> = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let mut manager = Self::new();
+        for (domain, cookies) in saved {
+            for cookie in cookies {
+                manager.add_cookie(cookie, &domain);
+            }
+        }
+        manager
+    }
+
+    /// Write the persistent cookies to `path`; session cookies are never saved
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let now = Utc::now();
+        let persistent: HashMap<&String, Vec<&Cookie>> = self.cookies.iter()
+            .map(|(domain, cookies)| (domain, cookies.iter().filter(|c| !c.is_session() && !c.is_expired(now)).collect::<Vec<_>>()))
+            .filter(|(_, cookies)| !cookies.is_empty())
+            .collect();
+        let content = serde_json::to_string_pretty(&persistent)?;
+        std::fs::write(path, content)
+            .map_err(|e| anyhow!("Failed to save cookies: {}", e))
+    }
+
+    /// Store a cookie, replacing any with the same name and path; an already expired cookie deletes it instead
     pub fn add_cookie(&mut self, cookie: Cookie, domain: &str) {
         let domain = domain.to_lowercase();
-        self.cookies
-            .entry(domain)
-            .or_insert_with(Vec::new)
-            .push(cookie);
+        let cookies = self.cookies.entry(domain.clone()).or_default();
+        cookies.retain(|existing| existing.name != cookie.name || existing.path != cookie.path);
+        if !cookie.is_expired(Utc::now()) {
+            cookies.push(cookie);
+        }
+        if cookies.is_empty() {
+            self.cookies.remove(&domain);
+        }
     }
     
     pub fn get_cookies_for_domain(&self, domain: &str) -> Vec<&Cookie> {
@@ -50,7 +130,8 @@ impl CookieManager {
         }
     }
     
-    fn parse_cookie(cookie_str: &str) -> Option<Cookie> {
+    /// Parse a Set-Cookie header value
+    pub fn parse_cookie(cookie_str: &str) -> Option<Cookie> {
         let parts: Vec<&str> = cookie_str.split(';').collect();
         if parts.is_empty() {
             return None;
@@ -91,12 +172,12 @@ impl CookieManager {
                 match attr_name.as_str() {
                     "domain" => cookie.domain = Some(attr_value.to_string()),
                     "path" => cookie.path = Some(attr_value.to_string()),
-                    "expires" => {
-                        // HTTP date parsing can be implemented with chrono when needed
-                    }
+                    // Max-Age wins over Expires whichever comes first
+                    "expires" if cookie.max_age.is_none() => cookie.expires = parse_cookie_date(attr_value),
                     "max-age" => {
                         if let Ok(max_age) = attr_value.parse::<i64>() {
                             cookie.max_age = Some(max_age);
+                            cookie.expires = Some(Utc::now() + Duration::seconds(max_age.clamp(0, i32::MAX as i64)));
                         }
                     }
                     "samesite" => cookie.same_site = Some(attr_value.to_string()),
@@ -124,7 +205,9 @@ impl CookieManager {
                 }
             }
             
-            // Cookie expiration checking can be implemented when needed
+            if cookie.is_expired(Utc::now()) {
+                continue;
+            }
             
             valid_cookies.push(format!("{}={}", cookie.name, cookie.value));
         }
@@ -136,12 +219,140 @@ impl CookieManager {
         }
     }
     
+    /// `document.cookie` for a page at `path` on `domain`: what a request would send, minus HttpOnly cookies
+    pub fn script_cookies(&self, domain: &str, path: &str, is_secure: bool) -> String {
+        let now = Utc::now();
+        self.get_cookies_for_domain(domain).into_iter()
+            .filter(|cookie| !cookie.http_only && !cookie.is_expired(now))
+            .filter(|cookie| is_secure || !cookie.secure)
+            .filter(|cookie| cookie.path.as_deref().is_none_or(|cookie_path| path.starts_with(cookie_path)))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Store a cookie a script set with `document.cookie = cookie_str`. Scripts can't create or overwrite HttpOnly
+    /// cookies, Secure ones need a secure page and SameSite=None needs Secure; false when the cookie was refused.
+    /// Without a Path the cookie belongs to `default_path`, the directory of the page.
+    pub fn set_script_cookie(&mut self, cookie_str: &str, domain: &str, default_path: &str, is_secure: bool) -> bool {
+        let Some(mut cookie) = Self::parse_cookie(cookie_str) else { return false };
+        if cookie.name.is_empty() || cookie.http_only || (cookie.secure && !is_secure) {
+            return false;
+        }
+        match cookie.same_site.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            Some("none") if !cookie.secure => return false,
+            Some("strict" | "lax" | "none") | None => {}
+            // Unknown values are treated as if the attribute were missing
+            Some(_) => cookie.same_site = None,
+        }
+        if cookie.path.is_none() {
+            cookie.path = Some(default_path.to_string());
+        }
+        let protected = self.get_cookies_for_domain(domain).into_iter()
+            .any(|existing| existing.http_only && existing.name == cookie.name && existing.path == cookie.path);
+        if protected {
+            return false;
+        }
+        self.add_cookie(cookie, domain);
+        true
+    }
+    
     pub fn clear_cookies_for_domain(&mut self, domain: &str) {
         let domain = domain.to_lowercase();
         self.cookies.remove(&domain);
     }
     
+    /// Remove the cookies of `host` and all of its subdomains, including those partitioned under it
+    pub fn clear_cookies_for_site(&mut self, host: &str) {
+        self.cookies.retain(|key, _| !belongs_to_site(key, host));
+    }
+    
+    /// How many cookies `host`, its subdomains and the frames partitioned under it hold
+    pub fn cookie_count_for_site(&self, host: &str) -> usize {
+        self.cookies.iter()
+            .filter(|(key, _)| belongs_to_site(key, host))
+            .map(|(_, cookies)| cookies.len())
+            .sum()
+    }
+    
+    /// Drop every session cookie, as happens when the browser exits
+    pub fn clear_session_cookies(&mut self) {
+        for cookies in self.cookies.values_mut() {
+            cookies.retain(|cookie| !cookie.is_session());
+        }
+        self.cookies.retain(|_, cookies| !cookies.is_empty());
+    }
+    
     pub fn clear_all_cookies(&mut self) {
         self.cookies.clear();
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookie_lifetimes() {
+        let mut jar = CookieManager::new();
+        jar.parse_set_cookie_header("sid=1; Path=/", "example.com");
+        jar.parse_set_cookie_header("theme=dark; Expires=Wed, 21 Oct 2099 07:28:00 GMT", "example.com");
+        jar.parse_set_cookie_header("old=1; Expires=Thu, 01-Jan-1970 00:00:00 GMT", "example.com");
+        jar.parse_set_cookie_header("lang=en; Max-Age=3600", "example.com");
+        let cookies = jar.get_cookies_for_domain("example.com");
+        assert_eq!(cookies.iter().map(|c| (c.name.as_str(), c.is_session())).collect::<Vec<_>>(),
+            vec![("sid", true), ("theme", false), ("lang", false)]);
+
+        // Max-Age=0 deletes, and setting a cookie again replaces it
+        jar.parse_set_cookie_header("lang=en; Max-Age=0", "example.com");
+        jar.parse_set_cookie_header("theme=light; Expires=Wed, 21 Oct 2099 07:28:00 GMT", "example.com");
+        assert_eq!(jar.get_cookie_header_for_request("example.com", "/", true).as_deref(), Some("sid=1; theme=light"));
+
+        jar.clear_session_cookies();
+        assert_eq!(jar.get_cookie_header_for_request("example.com", "/", true).as_deref(), Some("theme=light"));
+    }
+
+    #[test]
+    fn test_persists_only_persistent_cookies() {
+        let path = std::env::temp_dir().join(format!("neon-cookies-{}.json", std::process::id()));
+        let mut jar = CookieManager::new();
+        jar.parse_set_cookie_header("sid=1", "example.com");
+        jar.parse_set_cookie_header("keep=1; Max-Age=600", "example.com");
+        jar.parse_set_cookie_header("other=1; Max-Age=600", "news.example.com");
+        jar.save(&path).unwrap();
+
+        let mut loaded = CookieManager::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.get_cookie_header_for_request("example.com", "/", false).as_deref(), Some("keep=1"));
+        assert_eq!(loaded.cookie_count_for_site("example.com"), 2);
+        loaded.clear_cookies_for_site("example.com");
+        assert_eq!(loaded.cookie_count_for_site("example.com"), 0);
+        assert!(loaded.get_cookies_for_domain("news.example.com").is_empty());
+
+        // Partitioned cookies go with either the embedded site or the one embedding it
+        loaded.parse_set_cookie_header("id=1", &partition_key("ads.tracker.net", "example.org"));
+        assert_eq!(loaded.get_cookie_header_for_request("ads.tracker.net", "/", true), None);
+        loaded.clear_cookies_for_site("example.org");
+        assert!(loaded.get_cookies_for_domain(&partition_key("ads.tracker.net", "example.org")).is_empty());
+    }
+
+    #[test]
+    fn test_script_cookies() {
+        let mut jar = CookieManager::new();
+        jar.parse_set_cookie_header("session=abc; Path=/; HttpOnly", "example.com");
+        jar.parse_set_cookie_header("token=1; Path=/; Secure", "example.com");
+        assert!(jar.set_script_cookie("theme=dark", "example.com", "/", false));
+        assert!(!jar.set_script_cookie("session=stolen; Path=/", "example.com", "/", true));
+        assert!(!jar.set_script_cookie("flag=1; HttpOnly", "example.com", "/", true));
+        assert!(!jar.set_script_cookie("id=1; Secure", "example.com", "/", false));
+        assert!(!jar.set_script_cookie("id=1; SameSite=None", "example.com", "/", true));
+        assert!(jar.set_script_cookie("id=1; SameSite=None; Secure", "example.com", "/", true));
+        assert!(jar.set_script_cookie("cart=2", "example.com", "/shop", false));
+
+        assert_eq!(jar.script_cookies("example.com", "/", false), "theme=dark");
+        assert_eq!(jar.script_cookies("example.com", "/shop/item", true), "token=1; theme=dark; id=1; cart=2");
+        // Scripts see what requests send, apart from HttpOnly cookies
+        assert_eq!(jar.get_cookie_header_for_request("example.com", "/", true).as_deref(),
+            Some("session=abc; token=1; theme=dark; id=1"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,434 @@
+// Connectivity checks and a speed test run over the manual networking stack, for neon://diagnostics
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::networking::manual_client::ManualHttpClient;
+
+/// Speed test server used unless the user picks another; any server answering
+/// `GET /__down?bytes=N` and `POST /__up` works
+pub const DEFAULT_ENDPOINT: &str = "https://speed.cloudflare.com";
+
+// Limit for each connectivity step, and for each transfer as a whole
+const STEP_TIMEOUT: Duration = Duration::from_secs(20);
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(60);
+// Response heads larger than this are treated as a broken server
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct DiagnosticsConfig {
+    /// Base URL of the speed test server
+    pub endpoint: String,
+    pub download_bytes: usize,
+    pub upload_bytes: usize,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            download_bytes: 10 * 1024 * 1024,
+            upload_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl DiagnosticsConfig {
+    fn base(&self) -> &str {
+        self.endpoint.trim().trim_end_matches('/')
+    }
+
+    pub fn download_url(&self) -> String {
+        format!("{}/__down?bytes={}", self.base(), self.download_bytes)
+    }
+
+    pub fn upload_url(&self) -> String {
+        format!("{}/__up", self.base())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStep {
+    Dns,
+    Connect,
+    TlsHandshake,
+    Download,
+    Upload,
+}
+
+impl DiagnosticStep {
+    pub const ALL: [DiagnosticStep; 5] = [
+        DiagnosticStep::Dns,
+        DiagnosticStep::Connect,
+        DiagnosticStep::TlsHandshake,
+        DiagnosticStep::Download,
+        DiagnosticStep::Upload,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiagnosticStep::Dns => "DNS lookup",
+            DiagnosticStep::Connect => "TCP connect",
+            DiagnosticStep::TlsHandshake => "TLS handshake",
+            DiagnosticStep::Download => "Download",
+            DiagnosticStep::Upload => "Upload",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub step: DiagnosticStep,
+    pub passed: bool,
+    pub duration: Duration,
+    /// What was measured, or why the step failed
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiagnosticsReport {
+    pub endpoint: String,
+    pub started_at: DateTime<Local>,
+    pub results: Vec<StepResult>,
+}
+
+impl DiagnosticsReport {
+    pub fn passed(&self) -> bool {
+        self.results.len() == DiagnosticStep::ALL.len() && self.results.iter().all(|r| r.passed)
+    }
+
+    /// Plain-text version for pasting into a bug report or chat
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "NeonSearch {} network diagnostics\nEndpoint: {}\nRun at: {}\n\n",
+            env!("CARGO_PKG_VERSION"),
+            self.endpoint,
+            self.started_at.format("%Y-%m-%d %H:%M:%S %:z"),
+        );
+        for result in &self.results {
+            let _ = writeln!(
+                text,
+                "{:<14} {:<4} {:>9}  {}",
+                result.step.label(),
+                if result.passed { "ok" } else { "FAIL" },
+                format!("{} ms", result.duration.as_millis()),
+                result.detail,
+            );
+        }
+        for step in DiagnosticStep::ALL.iter().skip(self.results.len()) {
+            let _ = writeln!(text, "{:<14} skipped", step.label());
+        }
+        text
+    }
+}
+
+/// Transfer rate in megabits per second, e.g. `48.2 Mbit/s`
+pub fn format_rate(bytes: usize, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64().max(0.001);
+    format!("{:.1} Mbit/s", bytes as f64 * 8.0 / seconds / 1_000_000.0)
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+// Where the endpoint lives, taken apart once
+struct Target {
+    host: String,
+    port: u16,
+    is_https: bool,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid endpoint '{}': {}", url, e))?;
+        let is_https = match parsed.scheme() {
+            "https" => true,
+            "http" => false,
+            scheme => return Err(anyhow!("Unsupported endpoint scheme '{}'", scheme)),
+        };
+        Ok(Self {
+            host: parsed.host_str().ok_or_else(|| anyhow!("Endpoint has no host"))?.to_string(),
+            port: parsed.port_or_known_default().unwrap_or(if is_https { 443 } else { 80 }),
+            is_https,
+        })
+    }
+}
+
+/// Run every check in order, handing each result to `progress` as it completes. Transfers are
+/// skipped once a connectivity step fails, since they would fail the same way.
+pub async fn run(config: DiagnosticsConfig, progress: Arc<Mutex<Vec<StepResult>>>) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport {
+        endpoint: config.base().to_string(),
+        started_at: Local::now(),
+        results: Vec::new(),
+    };
+    let mut record = |result: StepResult| {
+        if let Ok(mut progress) = progress.lock() {
+            progress.push(result.clone());
+        }
+        report.results.push(result);
+    };
+
+    let client = match ManualHttpClient::new() {
+        Ok(client) => client,
+        Err(e) => {
+            record(failed(DiagnosticStep::Dns, Duration::ZERO, e));
+            return report;
+        }
+    };
+    let target = match Target::parse(&config.download_url()) {
+        Ok(target) => target,
+        Err(e) => {
+            record(failed(DiagnosticStep::Dns, Duration::ZERO, e));
+            return report;
+        }
+    };
+
+    // DNS, TCP and TLS are timed separately on the connection the download then reuses
+    let started = Instant::now();
+    let addrs = match resolve(&target).await {
+        Ok(addrs) => {
+            let detail = format!("{} address(es), first {}", addrs.len(), addrs[0].ip());
+            record(passed(DiagnosticStep::Dns, started.elapsed(), detail));
+            addrs
+        }
+        Err(e) => {
+            record(failed(DiagnosticStep::Dns, started.elapsed(), e));
+            return report;
+        }
+    };
+
+    let started = Instant::now();
+    let stream = match connect(&addrs).await {
+        Ok(stream) => {
+            let detail = stream.peer_addr().map(|addr| format!("connected to {}", addr)).unwrap_or_default();
+            record(passed(DiagnosticStep::Connect, started.elapsed(), detail));
+            stream
+        }
+        Err(e) => {
+            record(failed(DiagnosticStep::Connect, started.elapsed(), e));
+            return report;
+        }
+    };
+
+    let started = Instant::now();
+    let connection: Box<dyn Connection> = if target.is_https {
+        match handshake(&client, &target, stream).await {
+            Ok((connection, detail)) => {
+                record(passed(DiagnosticStep::TlsHandshake, started.elapsed(), detail));
+                connection
+            }
+            Err(e) => {
+                record(failed(DiagnosticStep::TlsHandshake, started.elapsed(), e));
+                return report;
+            }
+        }
+    } else {
+        record(passed(DiagnosticStep::TlsHandshake, Duration::ZERO, "not used for http:// endpoints".to_string()));
+        Box::new(stream)
+    };
+
+    let started = Instant::now();
+    let result = match tokio::time::timeout(TRANSFER_TIMEOUT, download(connection, &target, &config)).await {
+        Ok(Ok(received)) => passed(DiagnosticStep::Download, started.elapsed(), format!(
+            "{} in {:.2}s, {}", format_bytes(received), started.elapsed().as_secs_f64(), format_rate(received, started.elapsed())
+        )),
+        Ok(Err(e)) => failed(DiagnosticStep::Download, started.elapsed(), e),
+        Err(_) => failed(DiagnosticStep::Download, started.elapsed(), anyhow!("timed out after {}s", TRANSFER_TIMEOUT.as_secs())),
+    };
+    record(result);
+
+    // The upload gets a fresh connection, whose setup isn't part of the measurement
+    let result = match open(&client, &target).await {
+        Ok(connection) => {
+            let started = Instant::now();
+            match tokio::time::timeout(TRANSFER_TIMEOUT, upload(connection, &target, &config)).await {
+                Ok(Ok(())) => passed(DiagnosticStep::Upload, started.elapsed(), format!(
+                    "{} in {:.2}s, {}", format_bytes(config.upload_bytes), started.elapsed().as_secs_f64(), format_rate(config.upload_bytes, started.elapsed())
+                )),
+                Ok(Err(e)) => failed(DiagnosticStep::Upload, started.elapsed(), e),
+                Err(_) => failed(DiagnosticStep::Upload, started.elapsed(), anyhow!("timed out after {}s", TRANSFER_TIMEOUT.as_secs())),
+            }
+        }
+        Err(e) => failed(DiagnosticStep::Upload, Duration::ZERO, e),
+    };
+    record(result);
+
+    report
+}
+
+fn passed(step: DiagnosticStep, duration: Duration, detail: String) -> StepResult {
+    StepResult { step, passed: true, duration, detail }
+}
+
+fn failed(step: DiagnosticStep, duration: Duration, error: anyhow::Error) -> StepResult {
+    StepResult { step, passed: false, duration, detail: error.to_string() }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+}
+
+async fn resolve(target: &Target) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((target.host.as_str(), target.port)))
+        .await
+        .map_err(|_| anyhow!("timed out resolving {}", target.host))??
+        .collect();
+    if addrs.is_empty() {
+        return Err(anyhow!("{} has no addresses", target.host));
+    }
+    Ok(addrs)
+}
+
+async fn connect(addrs: &[SocketAddr]) -> Result<TcpStream> {
+    let mut last_error = anyhow!("no addresses to connect to");
+    for addr in addrs {
+        match tokio::time::timeout(STEP_TIMEOUT, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => last_error = anyhow!("{}: {}", addr, e),
+            Err(_) => last_error = anyhow!("{}: timed out", addr),
+        }
+    }
+    Err(last_error)
+}
+
+// Returns the encrypted connection with the negotiated version and cipher suite
+async fn handshake(client: &ManualHttpClient, target: &Target, stream: TcpStream) -> Result<(Box<dyn Connection>, String)> {
+    let domain = rustls::pki_types::ServerName::try_from(target.host.clone())
+        .map_err(|_| anyhow!("Invalid hostname for TLS: {}", target.host))?;
+    let tls = tokio::time::timeout(STEP_TIMEOUT, client.tls_connector().connect(domain, stream))
+        .await
+        .map_err(|_| anyhow!("timed out"))??;
+    let (_, session) = tls.get_ref();
+    let detail = format!(
+        "{}, {}",
+        session.protocol_version().map(|v| format!("{:?}", v)).unwrap_or_default(),
+        session.negotiated_cipher_suite().map(|s| format!("{:?}", s.suite())).unwrap_or_default(),
+    );
+    Ok((Box::new(tls), detail))
+}
+
+async fn open(client: &ManualHttpClient, target: &Target) -> Result<Box<dyn Connection>> {
+    let stream = connect(&resolve(target).await?).await?;
+    if target.is_https {
+        Ok(handshake(client, target, stream).await?.0)
+    } else {
+        Ok(Box::new(stream))
+    }
+}
+
+fn request_head(method: &str, url: &str, target: &Target, extra: &str) -> String {
+    let path = url::Url::parse(url)
+        .map(|u| format!("{}{}", u.path(), u.query().map(|q| format!("?{}", q)).unwrap_or_default()))
+        .unwrap_or_else(|_| "/".to_string());
+    format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: NeonSearch/{}\r\nAccept-Encoding: identity\r\n{}Connection: close\r\n\r\n",
+        method, path, target.host, env!("CARGO_PKG_VERSION"), extra,
+    )
+}
+
+// Reads the response head; returns the status and any body bytes that arrived with it
+async fn read_head(connection: &mut Box<dyn Connection>) -> Result<(u16, usize)> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = connection.read(&mut buffer).await?;
+        if read == 0 {
+            return Err(anyhow!("connection closed before the response headers"));
+        }
+        head.extend_from_slice(&buffer[..read]);
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            let status = parse_status(&head).ok_or_else(|| anyhow!("malformed status line"))?;
+            return Ok((status, head.len() - end - 4));
+        }
+        if head.len() > MAX_HEAD_SIZE {
+            return Err(anyhow!("response headers too large"));
+        }
+    }
+}
+
+fn parse_status(head: &[u8]) -> Option<u16> {
+    let line = head.split(|b| *b == b'\n').next()?;
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.split_whitespace();
+    parts.next().filter(|version| version.starts_with("HTTP/"))?;
+    parts.next()?.parse().ok()
+}
+
+// Body bytes received, counted as they arrive without keeping them
+async fn download(mut connection: Box<dyn Connection>, target: &Target, config: &DiagnosticsConfig) -> Result<usize> {
+    connection.write_all(request_head("GET", &config.download_url(), target, "").as_bytes()).await?;
+    let (status, mut received) = read_head(&mut connection).await?;
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("server answered {}", status));
+    }
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = connection.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(received);
+        }
+        received += read;
+    }
+}
+
+async fn upload(mut connection: Box<dyn Connection>, target: &Target, config: &DiagnosticsConfig) -> Result<()> {
+    let extra = format!("Content-Type: application/octet-stream\r\nContent-Length: {}\r\n", config.upload_bytes);
+    connection.write_all(request_head("POST", &config.upload_url(), target, &extra).as_bytes()).await?;
+    let chunk = vec![0u8; UPLOAD_CHUNK_SIZE];
+    let mut remaining = config.upload_bytes;
+    while remaining > 0 {
+        let size = remaining.min(chunk.len());
+        connection.write_all(&chunk[..size]).await?;
+        remaining -= size;
+    }
+    connection.flush().await?;
+    let (status, _) = read_head(&mut connection).await?;
+    if !(200..300).contains(&status) {
+        return Err(anyhow!("server answered {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_urls_and_status() {
+        let config = DiagnosticsConfig { endpoint: " https://speed.example.com/ ".to_string(), download_bytes: 1000, upload_bytes: 10 };
+        assert_eq!(config.download_url(), "https://speed.example.com/__down?bytes=1000");
+        assert_eq!(config.upload_url(), "https://speed.example.com/__up");
+
+        let target = Target::parse(&config.download_url()).unwrap();
+        assert!(target.is_https && target.port == 443);
+        assert!(request_head("GET", &config.download_url(), &target, "").starts_with("GET /__down?bytes=1000 HTTP/1.1\r\nHost: speed.example.com\r\n"));
+        assert!(Target::parse("ftp://example.com").is_err());
+
+        assert_eq!(parse_status(b"HTTP/1.1 204 No Content\r\n\r\n"), Some(204));
+        assert_eq!(parse_status(b"garbage 200\r\n"), None);
+    }
+
+    #[test]
+    fn test_report_text() {
+        let report = DiagnosticsReport {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            started_at: Local::now(),
+            results: vec![
+                passed(DiagnosticStep::Dns, Duration::from_millis(12), "1 address(es)".to_string()),
+                failed(DiagnosticStep::Connect, Duration::from_millis(20), anyhow!("refused")),
+            ],
+        };
+        assert!(!report.passed());
+        let text = report.to_text();
+        assert!(text.contains("DNS lookup     ok       12 ms  1 address(es)"));
+        assert!(text.contains("TCP connect    FAIL     20 ms  refused"));
+        assert!(text.contains("Upload         skipped"));
+        assert_eq!(format_rate(1_250_000, Duration::from_secs(1)), "10.0 Mbit/s");
+    }
+}
@@ -0,0 +1,210 @@
+// file:// URLs: local files read from disk, and folders shown as a listing page
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::path::Path;
+use crate::networking::HttpResponse;
+use crate::storage::Preferences;
+use crate::ui::i18n;
+
+pub fn is_file(url: &str) -> bool {
+    url.get(..7).is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://"))
+}
+
+/// Column a folder listing is ordered by, from the `sort` query parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortKey {
+    fn parse(value: &str) -> Self {
+        match value {
+            "size" => SortKey::Size,
+            "modified" => SortKey::Modified,
+            _ => SortKey::Name,
+        }
+    }
+
+    fn param(&self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+/// A file:// URL as the response a server would have sent for it; folders become a listing page
+pub fn response_for(url: &str) -> Result<HttpResponse> {
+    if !Preferences::current().allow_file_urls {
+        return Err(anyhow!("Opening local files is turned off. Turn it on in Settings under Privacy & Security"));
+    }
+    let parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid file URL '{}': {}", url, e))?;
+    let path = parsed.to_file_path().map_err(|_| anyhow!("Not a local file path: {}", url))?;
+    let metadata = std::fs::metadata(&path).map_err(|e| anyhow!("Can't open {}: {}", path.display(), e))?;
+
+    let (mime_type, body) = if metadata.is_dir() {
+        let query: HashMap<_, _> = parsed.query_pairs().into_owned().collect();
+        let sort = SortKey::parse(query.get("sort").map_or("", String::as_str));
+        let descending = query.get("order").is_some_and(|order| order == "desc");
+        let entries = read_dir(&path)?;
+        ("text/html; charset=utf-8".to_string(), directory_listing(&path, entries, sort, descending).into_bytes())
+    } else {
+        let body = std::fs::read(&path).map_err(|e| anyhow!("Can't read {}: {}", path.display(), e))?;
+        (mime_for_path(&path).to_string(), body)
+    };
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), mime_type);
+    headers.insert("Content-Length".to_string(), body.len().to_string());
+    Ok(HttpResponse::new(200, "OK".to_string(), headers, body))
+}
+
+fn read_dir(path: &Path) -> Result<Vec<DirEntry>> {
+    let entries = std::fs::read_dir(path).map_err(|e| anyhow!("Can't list {}: {}", path.display(), e))?;
+    Ok(entries.flatten().map(|entry| {
+        let metadata = entry.metadata().ok();
+        DirEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+            size: metadata.as_ref().map_or(0, |m| m.len()),
+            modified: metadata.and_then(|m| m.modified().ok()).map(DateTime::<Local>::from),
+        }
+    }).collect())
+}
+
+/// Content type of a local file, from its extension
+pub fn mime_for_path(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "xhtml" => "application/xhtml+xml",
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "txt" | "md" | "log" | "csv" | "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" | "rs" | "py" | "c" | "h"
+        | "cpp" | "ts" | "sh" | "" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The listing page for the folder at `path`, folders first, then ordered by `sort`
+pub fn directory_listing(path: &Path, mut entries: Vec<DirEntry>, sort: SortKey, descending: bool) -> String {
+    entries.sort_by(|a, b| {
+        let order = match sort {
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        };
+        b.is_dir.cmp(&a.is_dir).then(if descending { order.reverse() } else { order })
+    });
+
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+    let folder_url = url::Url::from_directory_path(path).map(|u| u.to_string()).unwrap_or_default();
+
+    // Each ancestor of the folder, as a link back up to it
+    let mut crumbs = Vec::new();
+    for ancestor in path.ancestors() {
+        let name = ancestor.file_name().map_or_else(|| ancestor.display().to_string(), |n| n.to_string_lossy().into_owned());
+        let href = url::Url::from_directory_path(ancestor).map(|u| u.to_string()).unwrap_or_default();
+        crumbs.push(format!("<a href=\"{}\">{}</a>", escape(&href), escape(&name)));
+    }
+    crumbs.reverse();
+
+    // Clicking the column already sorted by flips its order
+    let header = |key: SortKey, label: &str| {
+        let order = if key == sort && !descending { "desc" } else { "asc" };
+        let arrow = if key != sort { "" } else if descending { " ▼" } else { " ▲" };
+        format!("<th><a href=\"{}?sort={}&amp;order={}\">{}{}</a></th>", escape(&folder_url), key.param(), order, label, arrow)
+    };
+
+    let mut rows = String::new();
+    if let Some(parent) = path.parent().and_then(|p| url::Url::from_directory_path(p).ok()) {
+        rows.push_str(&format!("<tr><td><a href=\"{}\">📁 ..</a></td><td></td><td></td></tr>\n", escape(parent.as_str())));
+    }
+    for entry in &entries {
+        let mut href = url::Url::from_file_path(path.join(&entry.name)).map(|u| u.to_string()).unwrap_or_default();
+        if entry.is_dir {
+            href.push('/');
+        }
+        let (icon, size) = if entry.is_dir { ("📁", String::new()) } else { ("📄", i18n::format_bytes(entry.size)) };
+        let modified = entry.modified.map(i18n::format_date_time).unwrap_or_default();
+        rows.push_str(&format!(
+            "<tr><td><a href=\"{}\">{} {}</a></td><td>{}</td><td>{}</td></tr>\n",
+            escape(&href), icon, escape(&entry.name), size, modified
+        ));
+    }
+
+    format!(r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Index of {title}</title>
+    <style>
+        body {{ background: #1a1a1a; color: #ffffff; font-family: Arial, sans-serif; margin: 20px; }}
+        a {{ color: #00d4ff; text-decoration: none; }}
+        .crumbs {{ font-size: 18px; margin-bottom: 16px; }}
+        table {{ width: 100%; }}
+        th {{ text-align: left; color: #aaaaaa; }}
+        td {{ padding: 4px 12px 4px 0; }}
+    </style>
+</head>
+<body>
+    <div class="crumbs">{crumbs}</div>
+    <table>
+        <tr>{name}{size}{modified}</tr>
+{rows}    </table>
+</body>
+</html>
+"#,
+        title = escape(&path.display().to_string()),
+        crumbs = crumbs.join(" / "),
+        name = header(SortKey::Name, "Name"),
+        size = header(SortKey::Size, "Size"),
+        modified = header(SortKey::Modified, "Modified"),
+        rows = rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_listing_sorts_folders_first() {
+        let entry = |name: &str, is_dir: bool, size: u64| DirEntry { name: name.to_string(), is_dir, size, modified: None };
+        let entries = vec![entry("b.txt", false, 10), entry("src", true, 0), entry("a.txt", false, 500)];
+        let dir = std::env::temp_dir().join(format!("neon-listing-{}", std::process::id()));
+
+        let html = directory_listing(&dir, entries.clone(), SortKey::Size, true);
+        let src = html.find(">📁 src<").unwrap();
+        let a = html.find(">📄 a.txt<").unwrap();
+        let b = html.find(">📄 b.txt<").unwrap();
+        assert!(src < a && a < b, "{}", html);
+        // The sorted column links to the opposite order
+        assert!(html.contains("?sort=size&amp;order=asc"));
+
+        let html = directory_listing(&dir, entries, SortKey::Name, false);
+        assert!(html.find(">📄 a.txt<").unwrap() < html.find(">📄 b.txt<").unwrap());
+        assert_eq!(mime_for_path(Path::new("notes.MD")), "text/plain; charset=utf-8");
+    }
+}
@@ -0,0 +1,249 @@
+// HTTP authentication: the Basic and Digest challenges of a 401 response, and the credentials answering them
+use base64::Engine;
+use sha2::{Digest as _, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::networking::site_metadata::origin_of;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthScheme {
+    Basic,
+    Digest {
+        nonce: String,
+        opaque: Option<String>,
+        /// Quality of protection the server offers, "auth" being the only one answered
+        qop: Option<String>,
+        /// MD5, SHA-256 or either with "-sess"
+        algorithm: String,
+    },
+}
+
+/// A `WWW-Authenticate` challenge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub scheme: AuthScheme,
+    pub realm: String,
+}
+
+/// The challenges of `header` this browser can answer, strongest first
+pub fn parse_challenges(header: &str) -> Vec<Challenge> {
+    let mut challenges = Vec::new();
+    let mut rest = header.trim();
+    while !rest.is_empty() {
+        let (scheme, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let scheme = scheme.trim_end_matches(',');
+        let (params, remaining) = parse_params(after);
+        rest = remaining.trim_start_matches([',', ' ']);
+        let realm = params.get("realm").cloned().unwrap_or_default();
+        let scheme = if scheme.eq_ignore_ascii_case("basic") {
+            AuthScheme::Basic
+        } else if scheme.eq_ignore_ascii_case("digest") {
+            let Some(nonce) = params.get("nonce") else { continue };
+            let algorithm = params.get("algorithm").map_or("MD5".to_string(), |a| a.to_ascii_uppercase());
+            if !matches!(algorithm.as_str(), "MD5" | "MD5-SESS" | "SHA-256" | "SHA-256-SESS") {
+                continue;
+            }
+            let qop = params.get("qop").and_then(|qop| qop.split(',').map(str::trim).find(|q| *q == "auth")).map(str::to_string);
+            AuthScheme::Digest { nonce: nonce.clone(), opaque: params.get("opaque").cloned(), qop, algorithm }
+        } else {
+            continue;
+        };
+        challenges.push(Challenge { scheme, realm });
+    }
+    // Digest never sends the password itself
+    challenges.sort_by_key(|challenge| matches!(challenge.scheme, AuthScheme::Basic));
+    challenges
+}
+
+// The comma-separated `name=value` parameters of one challenge, up to where the next challenge starts
+fn parse_params(input: &str) -> (HashMap<String, String>, &str) {
+    let mut params = HashMap::new();
+    let mut rest = input.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim();
+        // A bare token before `=` with a space in it is the next challenge's scheme
+        if name.is_empty() || name.contains(char::is_whitespace) || name.contains(',') {
+            break;
+        }
+        let after = rest[eq + 1..].trim_start();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    _ => value.push(c),
+                }
+            }
+            (value, &quoted[end..])
+        } else {
+            let end = after.find(',').unwrap_or(after.len());
+            (after[..end].trim().to_string(), &after[end..])
+        };
+        params.insert(name.to_ascii_lowercase(), value);
+        rest = remaining.trim_start();
+        match rest.strip_prefix(',') {
+            Some(next) => rest = next.trim_start(),
+            None => break,
+        }
+    }
+    (params, rest)
+}
+
+/// The `Authorization` header answering `challenge` for a request of `method` to `uri` (its path and query)
+pub fn authorization(challenge: &Challenge, username: &str, password: &str, method: &str, uri: &str, nonce_count: u32, cnonce: &str) -> String {
+    match &challenge.scheme {
+        AuthScheme::Basic => {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+            format!("Basic {}", encoded)
+        }
+        AuthScheme::Digest { nonce, opaque, qop, algorithm } => {
+            let hash = |data: String| if algorithm.starts_with("SHA-256") {
+                Sha256::digest(data.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect::<String>()
+            } else {
+                md5_hex(data.as_bytes())
+            };
+            let mut ha1 = hash(format!("{}:{}:{}", username, challenge.realm, password));
+            if algorithm.ends_with("-SESS") {
+                ha1 = hash(format!("{}:{}:{}", ha1, nonce, cnonce));
+            }
+            let ha2 = hash(format!("{}:{}", method, uri));
+            let nc = format!("{:08x}", nonce_count);
+            let response = match qop {
+                Some(qop) => hash(format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2)),
+                None => hash(format!("{}:{}:{}", ha1, nonce, ha2)),
+            };
+            let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+            let mut header = format!(
+                "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+                quote(username), quote(&challenge.realm), quote(nonce), quote(uri), algorithm, response
+            );
+            if let Some(qop) = qop {
+                header.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+            }
+            if let Some(opaque) = opaque {
+                header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+            }
+            header
+        }
+    }
+}
+
+// Digest's default hash. Broken for signatures, but it's what most servers still ask for
+fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (0..64).map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32).collect();
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in message.chunks(64) {
+        let words: Vec<u32> = block.chunks(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(constants[i]).wrapping_add(words[g]).rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+    state.iter().flat_map(|word| word.to_le_bytes()).map(|b| format!("{:02x}", b)).collect()
+}
+
+// Credentials the user entered for an origin this session, with the challenge they answer
+struct SessionCredentials {
+    challenge: Challenge,
+    username: String,
+    password: String,
+    nonce_count: u32,
+}
+
+/// Credentials entered this session, sent up front with every request to their origin like other browsers do
+#[derive(Default)]
+pub struct AuthCache {
+    by_origin: HashMap<String, SessionCredentials>,
+}
+
+static SHARED_AUTH: OnceLock<Arc<Mutex<AuthCache>>> = OnceLock::new();
+
+impl AuthCache {
+    pub fn shared() -> Arc<Mutex<AuthCache>> {
+        SHARED_AUTH.get_or_init(|| Arc::new(Mutex::new(AuthCache::default()))).clone()
+    }
+
+    pub fn insert(&mut self, origin: &str, challenge: Challenge, username: String, password: String) {
+        self.by_origin.insert(origin.to_string(), SessionCredentials { challenge, username, password, nonce_count: 0 });
+    }
+
+    /// Forget the credentials for `origin`, returning whether there were any; a 401 despite them means they're wrong
+    pub fn remove(&mut self, origin: &str) -> bool {
+        self.by_origin.remove(origin).is_some()
+    }
+
+    /// The `Authorization` header for a `method` request to `url`, if credentials were entered for its origin
+    pub fn authorization_for(&mut self, url: &str, method: &str) -> Option<String> {
+        let parsed = url::Url::parse(url).ok()?;
+        let credentials = self.by_origin.get_mut(&origin_of(url)?)?;
+        credentials.nonce_count += 1;
+        let uri = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+        let cnonce = uuid::Uuid::new_v4().simple().to_string();
+        Some(authorization(&credentials.challenge, &credentials.username, &credentials.password, method, &uri, credentials.nonce_count, &cnonce))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenges() {
+        let challenges = parse_challenges(
+            "Basic realm=\"Admin, \\\"staff\\\"\", Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\", Bearer realm=\"api\""
+        );
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].realm, "testrealm@host.com");
+        assert!(matches!(&challenges[0].scheme, AuthScheme::Digest { qop: Some(qop), algorithm, .. } if qop == "auth" && algorithm == "MD5"));
+        assert_eq!(challenges[1], Challenge { scheme: AuthScheme::Basic, realm: "Admin, \"staff\"".to_string() });
+    }
+
+    #[test]
+    fn test_authorization_headers() {
+        let basic = Challenge { scheme: AuthScheme::Basic, realm: String::new() };
+        assert_eq!(authorization(&basic, "Aladdin", "open sesame", "GET", "/", 1, ""), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+
+        // The worked example of RFC 2617
+        let digest = parse_challenges(
+            "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""
+        ).remove(0);
+        let header = authorization(&digest, "Mufasa", "Circle Of Life", "GET", "/dir/index.html", 1, "0a4f113b");
+        assert!(header.contains("response=\"6629fae49393a05397450978507c4ef1\""), "{}", header);
+        assert!(header.contains("nc=00000001") && header.contains("opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+    }
+}
@@ -0,0 +1,283 @@
+// In-memory HTTP cache shared by page and subresource fetches. Fresh responses are answered without a request,
+// stale ones and everything on a normal reload are checked with If-None-Match / If-Modified-Since, and a hard
+// reload skips the cache entirely. Responses marked private or sent for requests with credentials aren't kept, and
+// responses that vary on request headers are only reused for requests that send the same values
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::networking::HttpResponse;
+
+// Entries kept before the least recently stored are dropped
+const MAX_ENTRIES: usize = 256;
+
+// Larger bodies live in temporary files and are fetched again instead
+const MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+static SHARED: OnceLock<HttpCache> = OnceLock::new();
+
+/// How a fetch uses the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    /// Use fresh entries as they are and check stale ones with the server
+    #[default]
+    Default,
+    /// Check every entry with the server, as a normal reload does
+    Revalidate,
+    /// Neither read the cache nor let intermediaries answer, as a hard reload does
+    Bypass,
+}
+
+/// Where a response came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheStatus {
+    #[default]
+    Network,
+    /// Answered from the cache without a request
+    Fresh,
+    /// The server answered 304 Not Modified and the cached body was used
+    Revalidated,
+}
+
+impl CacheStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CacheStatus::Network => "network",
+            CacheStatus::Fresh => "cache",
+            CacheStatus::Revalidated => "304",
+        }
+    }
+}
+
+/// What the cache has for a URL
+#[derive(Debug)]
+pub enum Lookup {
+    Fresh(Box<HttpResponse>),
+    /// Conditional headers to send; a 304 answer means the stored response is still good
+    Validate(Vec<(String, String)>),
+    Miss,
+}
+
+struct CacheEntry {
+    response: HttpResponse,
+    stored_at: Instant,
+    lifetime: Duration,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    // Headers named by Vary and the values the request that got the response sent for them
+    vary: Vec<(String, Option<String>)>,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self) -> bool {
+        self.stored_at.elapsed() < self.lifetime
+    }
+
+    fn matches(&self, request_headers: &[(String, String)]) -> bool {
+        self.vary.iter().all(|(name, value)| request_header(request_headers, name) == value.as_deref())
+    }
+
+    fn validators(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since".to_string(), last_modified.clone()));
+        }
+        headers
+    }
+}
+
+#[derive(Default)]
+pub struct HttpCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl HttpCache {
+    pub fn shared() -> &'static HttpCache {
+        SHARED.get_or_init(HttpCache::default)
+    }
+
+    /// What is stored for `url` that a request with `request_headers` may use
+    pub fn lookup(&self, url: &str, request_headers: &[(String, String)], mode: CacheMode) -> Lookup {
+        if mode == CacheMode::Bypass {
+            return Lookup::Miss;
+        }
+        let Ok(entries) = self.entries.lock() else { return Lookup::Miss };
+        let Some(entry) = entries.get(url).filter(|entry| entry.matches(request_headers)) else { return Lookup::Miss };
+        if mode == CacheMode::Default && entry.is_fresh() {
+            return Lookup::Fresh(Box::new(entry.response.clone()));
+        }
+        match entry.validators() {
+            validators if validators.is_empty() => Lookup::Miss,
+            validators => Lookup::Validate(validators),
+        }
+    }
+
+    /// Keep a complete 200 response to a request sent with `request_headers` that the server allows a shared
+    /// cache to keep and that can be reused or validated
+    pub fn store(&self, url: &str, request_headers: &[(String, String)], response: &HttpResponse) {
+        self.store_for(url, request_headers, response, Duration::ZERO);
+    }
+
+    /// Like `store`, but usable without validation for at least `fresh_for`, as prefetched pages are
+    pub fn store_for(&self, url: &str, request_headers: &[(String, String)], response: &HttpResponse, fresh_for: Duration) {
+        if response.status_code != 200
+            || response.temp_file.is_some()
+            || response.truncation.is_some()
+            || response.oversized.is_some()
+            || response.body.len() > MAX_BODY_SIZE
+        {
+            return;
+        }
+        let cache_control = header(response, "cache-control").unwrap_or_default().to_ascii_lowercase();
+        if cache_control.contains("no-store") || cache_control.contains("private") {
+            return;
+        }
+        if ["authorization", "cookie"].iter().any(|name| request_header(request_headers, name).is_some()) {
+            return;
+        }
+        let vary: Vec<String> = header(response, "vary").unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if vary.iter().any(|name| name == "*") {
+            return;
+        }
+        let entry = CacheEntry {
+            response: response.clone(),
+            stored_at: Instant::now(),
+            lifetime: freshness_lifetime(response).max(fresh_for),
+            etag: header(response, "etag").map(str::to_string),
+            last_modified: header(response, "last-modified").map(str::to_string),
+            vary: vary.into_iter()
+                .map(|name| {
+                    let value = request_header(request_headers, &name).map(str::to_string);
+                    (name, value)
+                })
+                .collect(),
+        };
+        if entry.lifetime.is_zero() && entry.etag.is_none() && entry.last_modified.is_none() {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else { return };
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(url) {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, entry)| entry.stored_at).map(|(key, _)| key.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(url.to_string(), entry);
+    }
+
+    /// The stored response for a 304 answer, refreshed with the headers that came with it
+    pub fn revalidated(&self, url: &str, not_modified: &HttpResponse) -> Option<HttpResponse> {
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries.get_mut(url)?;
+        for (name, value) in &not_modified.headers {
+            if let Some(key) = entry.response.headers.keys().find(|key| key.eq_ignore_ascii_case(name)).cloned() {
+                entry.response.headers.remove(&key);
+            }
+            entry.response.headers.insert(name.clone(), value.clone());
+        }
+        entry.stored_at = Instant::now();
+        entry.lifetime = freshness_lifetime(&entry.response);
+        if let Some(etag) = header(not_modified, "etag") {
+            entry.etag = Some(etag.to_string());
+        }
+        Some(entry.response.clone())
+    }
+
+    /// Entries kept and the bytes of their bodies
+    pub fn usage(&self) -> (usize, usize) {
+        self.entries.lock()
+            .map(|entries| (entries.len(), entries.values().map(|entry| entry.response.body.len()).sum()))
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+fn header<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    response.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+}
+
+fn request_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+}
+
+// How long the response may be used without asking, from max-age or Expires; no-cache means never
+fn freshness_lifetime(response: &HttpResponse) -> Duration {
+    let cache_control = header(response, "cache-control").unwrap_or_default().to_ascii_lowercase();
+    if cache_control.contains("no-cache") {
+        return Duration::ZERO;
+    }
+    let max_age = cache_control.split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.trim_matches('"').parse().ok());
+    if let Some(seconds) = max_age {
+        return Duration::from_secs(seconds);
+    }
+    let parse_date = |name| header(response, name).and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok());
+    match (parse_date("expires"), parse_date("date")) {
+        (Some(expires), Some(date)) => (expires - date).to_std().unwrap_or_default(),
+        (Some(expires), None) => (expires.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default(),
+        _ => Duration::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[(&str, &str)]) -> HttpResponse {
+        let headers = headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        HttpResponse::new(200, "OK".to_string(), headers, b"body".to_vec())
+    }
+
+    #[test]
+    fn test_reload_modes() {
+        let cache = HttpCache::default();
+        cache.store("https://example.com/app.js", &[], &response(&[("cache-control", "max-age=600"), ("etag", "\"v1\"")]));
+        cache.store("https://example.com/", &[], &response(&[("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")]));
+        cache.store("https://example.com/live", &[], &response(&[("cache-control", "no-store"), ("etag", "\"x\"")]));
+
+        assert!(matches!(cache.lookup("https://example.com/app.js", &[], CacheMode::Default), Lookup::Fresh(_)));
+        let Lookup::Validate(headers) = cache.lookup("https://example.com/app.js", &[], CacheMode::Revalidate) else { panic!() };
+        assert_eq!(headers, vec![("If-None-Match".to_string(), "\"v1\"".to_string())]);
+        assert!(matches!(cache.lookup("https://example.com/app.js", &[], CacheMode::Bypass), Lookup::Miss));
+        assert!(matches!(cache.lookup("https://example.com/", &[], CacheMode::Default), Lookup::Validate(_)));
+        assert!(matches!(cache.lookup("https://example.com/live", &[], CacheMode::Default), Lookup::Miss));
+
+        let refreshed = cache.revalidated("https://example.com/app.js", &response(&[("cache-control", "max-age=60")])).unwrap();
+        assert_eq!(refreshed.body, b"body");
+        assert_eq!(header(&refreshed, "cache-control"), Some("max-age=60"));
+    }
+
+    #[test]
+    fn test_private_credentialed_and_varying_responses() {
+        let cache = HttpCache::default();
+        let request = |headers: &[(&str, &str)]| -> Vec<(String, String)> {
+            headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+        };
+        let english = request(&[("Accept-Language", "en-US")]);
+        let german = request(&[("Accept-Language", "de-DE")]);
+
+        cache.store("https://example.com/account", &[], &response(&[("cache-control", "private, max-age=600")]));
+        cache.store("https://example.com/inbox", &request(&[("Cookie", "session=1")]), &response(&[("cache-control", "max-age=600")]));
+        cache.store("https://example.com/api", &request(&[("Authorization", "Bearer t")]), &response(&[("etag", "\"a\"")]));
+        cache.store("https://example.com/any", &[], &response(&[("cache-control", "max-age=600"), ("vary", "*")]));
+        cache.store("https://example.com/news", &english, &response(&[("cache-control", "max-age=600"), ("vary", "Accept-Language")]));
+
+        for url in ["https://example.com/account", "https://example.com/inbox", "https://example.com/api", "https://example.com/any"] {
+            assert!(matches!(cache.lookup(url, &[], CacheMode::Default), Lookup::Miss), "{}", url);
+        }
+        assert!(matches!(cache.lookup("https://example.com/news", &english, CacheMode::Default), Lookup::Fresh(_)));
+        assert!(matches!(cache.lookup("https://example.com/news", &german, CacheMode::Default), Lookup::Miss));
+        assert!(matches!(cache.lookup("https://example.com/news", &[], CacheMode::Default), Lookup::Miss));
+    }
+}
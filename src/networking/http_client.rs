@@ -1,4 +1,6 @@
 use crate::networking::{HttpRequest, HttpResponse};
+use crate::networking::redirect::MAX_REDIRECTS;
+use crate::storage::Preferences;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -7,6 +9,8 @@ pub async fn send_request(request: HttpRequest) -> Result<HttpResponse> {
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(30))
         .user_agent("NeonSearch/0.1.0 (Custom Browser by NeonDev)")
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .default_headers(privacy_headers())
         .build()?;
     
     let mut req_builder = match request.method.to_uppercase().as_str() {
@@ -48,6 +52,24 @@ pub async fn send_request(request: HttpRequest) -> Result<HttpResponse> {
     Ok(HttpResponse::new(status_code, status_text, headers, body))
 }
 
+/// A client that hands redirects back instead of following them, for callers that apply cookies, Referer and
+/// credentials to every hop themselves
+pub fn single_hop_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?)
+}
+
+/// The Do Not Track and Global Privacy Control headers the user has turned on, for clients sending them by default
+pub fn privacy_headers() -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in Preferences::current().privacy.headers() {
+        headers.insert(name, reqwest::header::HeaderValue::from_static(value));
+    }
+    headers
+}
+
 pub async fn download_file(url: &str) -> Result<Vec<u8>> {
     let request = HttpRequest::new_get(url.to_string());
     let response = send_request(request).await?;
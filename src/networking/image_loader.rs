@@ -4,6 +4,8 @@ use anyhow::{Result, anyhow};
 use tokio::sync::Mutex;
 use image::{DynamicImage, ImageFormat};
 use egui::{ColorImage, TextureHandle, Context};
+use crate::engine::executor::{Executor, TaskKind};
+use crate::networking::HttpResponse;
 use crate::networking::manual_client::ManualHttpClient;
 
 #[derive(Clone)]
@@ -58,7 +60,7 @@ impl ImageCache {
             }
         }
 
-        println!("Loading image: {}", url);
+        log::debug!("Loading image: {}", url);
         
         // Fetch the image
         let fetch_result = client.fetch(url).await
@@ -69,18 +71,24 @@ impl ImageCache {
                              fetch_result.response.status_code, url));
         }
 
+        self.store_response(url, &fetch_result.response).await
+    }
+
+    /// Decode an already-fetched image response and cache it under `url`
+    pub async fn store_response(&self, url: &str, response: &HttpResponse) -> Result<Arc<ColorImage>> {
         // Detect image format from content-type or URL extension
-        let content_type = fetch_result.response.content_type()
+        let content_type = response.content_type()
             .map(|s| s.to_lowercase())
             .unwrap_or_default();
         
-        let format = detect_image_format(&content_type, url, &fetch_result.response.body)?;
-        
-        // Load and convert the image
-        let dynamic_image = image::load_from_memory_with_format(&fetch_result.response.body, format)
-            .map_err(|e| anyhow!("Failed to decode image {}: {}", url, e))?;
-        
-        let color_image = convert_to_color_image(dynamic_image)?;
+        // Decoding is CPU-bound, so it runs on the executor's image pool rather than a network worker
+        let body = response.body.clone();
+        let image_url = url.to_string();
+        let color_image = Executor::shared()
+            .spawn_blocking(TaskKind::ImageDecode, move || decode_image(&content_type, &image_url, &body))
+            .join()
+            .await
+            .map_err(|e| anyhow!("Decoding {} failed: {}", url, e))??;
         let arc_image = Arc::new(color_image);
 
         // Cache the result
@@ -89,23 +97,12 @@ impl ImageCache {
             cache.insert(url.to_string(), arc_image.clone());
         }
 
-        println!("Successfully loaded and cached image: {} ({}x{})", 
+        log::debug!("Successfully loaded and cached image: {} ({}x{})", 
                 url, arc_image.width(), arc_image.height());
         
         Ok(arc_image)
     }
 
-    pub async fn preload_favicon(&self, base_url: &str, html_content: &str, client: &ManualHttpClient) {
-        let favicon_urls = extract_favicon_urls(base_url, html_content);
-        
-        for favicon_url in favicon_urls {
-            if let Ok(_) = self.load_image(&favicon_url, client).await {
-                println!("Preloaded favicon: {}", favicon_url);
-                break; // Stop at first successful favicon
-            }
-        }
-    }
-
     pub async fn clear_cache(&self) {
         let mut cache = self.cache.lock().await;
         let mut textures = self.egui_textures.lock().await;
@@ -114,7 +111,17 @@ impl ImageCache {
     }
 }
 
-fn detect_image_format(content_type: &str, url: &str, data: &[u8]) -> Result<ImageFormat> {
+fn decode_image(content_type: &str, url: &str, data: &[u8]) -> Result<ColorImage> {
+    let format = detect_image_format(content_type, url, data)?;
+    
+    // Load and convert the image
+    let dynamic_image = image::load_from_memory_with_format(data, format)
+        .map_err(|e| anyhow!("Failed to decode image {}: {}", url, e))?;
+    
+    convert_to_color_image(dynamic_image)
+}
+
+pub fn detect_image_format(content_type: &str, url: &str, data: &[u8]) -> Result<ImageFormat> {
     // Try content-type first
     if content_type.contains("png") {
         return Ok(ImageFormat::Png);
@@ -184,54 +191,6 @@ fn convert_to_color_image(dynamic_image: DynamicImage) -> Result<ColorImage> {
     Ok(color_image)
 }
 
-fn extract_favicon_urls(base_url: &str, html_content: &str) -> Vec<String> {
-    let mut urls = Vec::new();
-    
-    // Parse base URL
-    let base = match reqwest::Url::parse(base_url) {
-        Ok(url) => url,
-        Err(_) => return urls,
-    };
-
-    // Look for favicon links in HTML
-    let html_lower = html_content.to_lowercase();
-    let lines: Vec<&str> = html_lower.split('\n').collect();
-    
-    for line in lines {
-        if line.contains("rel") && (line.contains("icon") || line.contains("shortcut")) {
-            if let Some(href_start) = line.find("href") {
-                let after_href = &line[href_start + 4..];
-                if let Some(eq_pos) = after_href.find('=') {
-                    let after_eq = &after_href[eq_pos + 1..].trim_start();
-                    let quote_char = if after_eq.starts_with('"') { '"' } else if after_eq.starts_with('\'') { '\'' } else { ' ' };
-                    
-                    if quote_char != ' ' {
-                        let after_quote = &after_eq[1..];
-                        if let Some(end_quote) = after_quote.find(quote_char) {
-                            let href_value = &after_quote[..end_quote];
-                            
-                            // Resolve relative URL
-                            if let Ok(full_url) = base.join(href_value) {
-                                urls.push(full_url.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Add default favicon locations
-    if let Ok(favicon_url) = base.join("/favicon.ico") {
-        urls.push(favicon_url.to_string());
-    }
-    if let Ok(favicon_url) = base.join("/favicon.png") {
-        urls.push(favicon_url.to_string());
-    }
-    
-    urls
-}
-
 // Default fallback favicon as a simple colored square
 pub fn create_fallback_favicon(ctx: &Context, url: &str) -> TextureHandle {
     // Create a simple 16x16 colored square based on URL hash
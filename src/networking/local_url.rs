@@ -0,0 +1,171 @@
+// URLs whose content is already in the browser: data: URLs carry it inline, blob: URLs name it in the blob registry
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::networking::HttpResponse;
+
+// data: URLs may leave out base64 padding
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalContent {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// How a tab shows a data: or blob: URL it navigates to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Presentation {
+    Html,
+    Text,
+    Image,
+    /// Anything else is saved instead of shown
+    Download,
+}
+
+impl Presentation {
+    pub fn of(mime_type: &str) -> Self {
+        let essence = mime_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "text/html" | "application/xhtml+xml" => Presentation::Html,
+            "application/json" | "application/xml" | "application/javascript" => Presentation::Text,
+            _ if essence.starts_with("text/") => Presentation::Text,
+            _ if essence.starts_with("image/") => Presentation::Image,
+            _ => Presentation::Download,
+        }
+    }
+}
+
+pub fn is_local(url: &str) -> bool {
+    let scheme = url.split(':').next().unwrap_or("");
+    scheme.eq_ignore_ascii_case("data") || scheme.eq_ignore_ascii_case("blob")
+}
+
+/// Decode `data:[<media type>][;base64],<data>`, percent-decoding the data first
+pub fn parse_data_url(url: &str) -> Result<LocalContent> {
+    let rest = url.get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .map(|_| &url[5..])
+        .ok_or_else(|| anyhow!("Not a data: URL"))?;
+    // The fragment belongs to the URL, not the content
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (header, body) = rest.split_once(',').ok_or_else(|| anyhow!("Malformed data: URL, missing ','"))?;
+
+    let header = header.trim();
+    let (media_type, base64) = match header.rsplit_once(';') {
+        Some((media_type, flag)) if flag.trim().eq_ignore_ascii_case("base64") => (media_type.trim(), true),
+        _ => (header, false),
+    };
+    let mime_type = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_string()
+    } else if media_type.starts_with(';') {
+        format!("text/plain{}", media_type)
+    } else {
+        media_type.to_string()
+    };
+
+    let decoded = urlencoding::decode_binary(body.as_bytes()).into_owned();
+    let data = if base64 {
+        let compact: Vec<u8> = decoded.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        LENIENT_BASE64.decode(compact).map_err(|e| anyhow!("Malformed base64 in data: URL: {}", e))?
+    } else {
+        decoded
+    };
+    Ok(LocalContent { mime_type, data })
+}
+
+/// Content registered by scripts or the browser under blob: URLs, until revoked
+#[derive(Default)]
+pub struct BlobRegistry {
+    blobs: HashMap<String, LocalContent>,
+}
+
+static SHARED_BLOBS: OnceLock<Arc<Mutex<BlobRegistry>>> = OnceLock::new();
+
+impl BlobRegistry {
+    /// Process-wide registry, so any tab, script or the download manager can resolve a blob: URL
+    pub fn shared() -> Arc<Mutex<BlobRegistry>> {
+        SHARED_BLOBS.get_or_init(|| Arc::new(Mutex::new(BlobRegistry::default()))).clone()
+    }
+
+    /// Register `content` for `origin` (None for an opaque one), returning the new blob: URL
+    pub fn create(&mut self, origin: Option<&str>, content: LocalContent) -> String {
+        let url = format!("blob:{}/{}", origin.unwrap_or("null"), uuid::Uuid::new_v4());
+        self.blobs.insert(url.clone(), content);
+        url
+    }
+
+    pub fn resolve(&self, url: &str) -> Option<LocalContent> {
+        // Like data: URLs, the fragment isn't part of what's looked up
+        self.blobs.get(url.split('#').next().unwrap_or(url)).cloned()
+    }
+
+    pub fn revoke(&mut self, url: &str) -> bool {
+        self.blobs.remove(url).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+}
+
+/// The content of a data: or blob: URL
+pub fn resolve(url: &str) -> Result<LocalContent> {
+    if url.get(..5).is_some_and(|scheme| scheme.eq_ignore_ascii_case("blob:")) {
+        return BlobRegistry::shared().lock()
+            .ok()
+            .and_then(|registry| registry.resolve(url))
+            .ok_or_else(|| anyhow!("No blob registered for {} (it may have been revoked)", url));
+    }
+    parse_data_url(url)
+}
+
+/// A data: or blob: URL as the response a server would have sent for it
+pub fn response_for(url: &str) -> Result<HttpResponse> {
+    let content = resolve(url)?;
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), content.mime_type);
+    headers.insert("Content-Length".to_string(), content.data.len().to_string());
+    Ok(HttpResponse::new(200, "OK".to_string(), headers, content.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data_urls() {
+        let text = parse_data_url("data:,Hello%2C%20World%21").unwrap();
+        assert_eq!((text.mime_type.as_str(), text.data.as_slice()), ("text/plain;charset=US-ASCII", b"Hello, World!".as_slice()));
+
+        let html = parse_data_url("DATA:text/html;charset=utf-8;base64,PGgxPkhp PC9oMT4#top").unwrap();
+        assert_eq!(html.mime_type, "text/html;charset=utf-8");
+        assert_eq!(html.data, b"<h1>Hi</h1>");
+        assert_eq!(Presentation::of(&html.mime_type), Presentation::Html);
+
+        let png = parse_data_url("data:image/png;base64,iVBORw0KGgo").unwrap();
+        assert_eq!(png.data[..4], [0x89, b'P', b'N', b'G']);
+        assert_eq!(Presentation::of("application/zip"), Presentation::Download);
+        assert!(parse_data_url("data:text/plain;base64").is_err());
+    }
+
+    #[test]
+    fn test_blob_registry() {
+        let mut registry = BlobRegistry::default();
+        let content = LocalContent { mime_type: "text/plain".to_string(), data: b"hi".to_vec() };
+        let url = registry.create(Some("https://example.com"), content.clone());
+        assert!(url.starts_with("blob:https://example.com/"));
+        assert_eq!(registry.resolve(&format!("{}#part", url)), Some(content));
+        assert!(registry.revoke(&url));
+        assert_eq!(registry.resolve(&url), None);
+    }
+}
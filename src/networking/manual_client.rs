@@ -1,4 +1,5 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::net::SocketAddr;
 use std::time::Duration;
 use std::collections::HashMap;
 use anyhow::{Result, anyhow};
@@ -7,9 +8,20 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_rustls::TlsConnector;
 use rustls::ClientConfig;
 use webpki_roots;
-use crate::networking::{HttpResponse, temp_storage::TempStorageManager};
+use crate::networking::{DEFAULT_PAGE_SIZE_LIMIT, HttpResponse, OversizedBody, RequestBlocked, temp_storage::TempFileWriter};
+use crate::networking::cookie_manager::CookieManager;
+use crate::networking::{file_url, http_client, local_url, throttling};
+use crate::networking::http_cache::{CacheMode, CacheStatus, HttpCache, Lookup};
+use crate::networking::connection_pool::{Connection, ConnectionPool, Origin};
+use crate::networking::timing::RequestTiming;
+use crate::security::certificate::TlsInfo;
+use crate::networking::tracking::{self, TrackingProtection};
+use crate::storage::Preferences;
+use crate::engine::executor::{Executor, TaskKind};
+use crate::extensions::web_request::{self, ResourceType};
+use crate::networking::redirect::{RedirectHop, ReferrerPolicy, MAX_REDIRECTS, resolve_location, is_same_origin, preserves_method};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FetchPhase {
     Resolving,
     Connecting,
@@ -21,11 +33,89 @@ pub enum FetchPhase {
     Completed,
 }
 
+impl FetchPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FetchPhase::Resolving => "DNS lookup",
+            FetchPhase::Connecting => "Connecting",
+            FetchPhase::TlsHandshake => "TLS handshake",
+            FetchPhase::SendingRequest => "Sending request",
+            FetchPhase::ReadingHeaders => "Waiting for headers",
+            FetchPhase::ReadingBody => "Downloading body",
+            FetchPhase::Redirecting => "Redirecting",
+            FetchPhase::Completed => "Completed",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ManualFetchResult {
     pub response: HttpResponse,
-    pub phases: Vec<FetchPhase>,
-    pub redirects: Vec<String>,
+    pub timing: RequestTiming,
+    pub redirect_chain: Vec<RedirectHop>,
+    pub final_url: String,
+    // Headers sent for the final URL, which cached responses that vary on them are stored under
+    pub request_headers: Vec<(String, String)>,
+}
+
+// Per-navigation request state that is re-evaluated on every redirect hop
+#[derive(Clone, Default)]
+pub struct FetchContext {
+    pub cookies: Option<Arc<Mutex<CookieManager>>>,
+    pub referrer: Option<String>,
+    pub referrer_policy: ReferrerPolicy,
+    pub authorization: Option<String>,
+    // Replaces the default Accept-Language, e.g. while dev tools emulate another locale
+    pub accept_language: Option<String>,
+    // Inclusive byte range to ask for, e.g. while streaming media
+    pub range: Option<(u64, u64)>,
+    // Sent instead of a plain GET, e.g. for a form submitted with POST
+    pub body: Option<RequestBody>,
+    // Set for a page's subresources and frames: blocks trackers and partitions third-party cookies
+    pub tracking: Option<TrackingProtection>,
+    // Top-level site of the frame the request is for, which cookies of other sites are partitioned under
+    pub cookie_partition: Option<String>,
+    // What the request is for, which extension request rules can be limited to
+    pub resource_type: ResourceType,
+    // Whether the HTTP cache may answer, must be checked with the server, or is skipped, e.g. on reload
+    pub cache_mode: CacheMode,
+}
+
+/// Method and payload of a request that isn't a plain GET
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestBody {
+    pub method: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl RequestBody {
+    pub fn form_urlencoded(data: String) -> Self {
+        Self {
+            method: "POST".to_string(),
+            content_type: "application/x-www-form-urlencoded".to_string(),
+            data: data.into_bytes(),
+        }
+    }
+}
+
+const DEFAULT_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.9";
+
+// What a single exchange sends besides the standard headers, recomputed for every redirect hop
+struct RoundRequest<'a> {
+    path_and_query: String,
+    headers: Vec<(String, String)>,
+    body: Option<&'a RequestBody>,
+}
+
+// Outcome of a single request/response exchange
+enum RoundOutcome {
+    Complete(Box<HttpResponse>),
+    Redirect {
+        status_code: u16,
+        location: String,
+        referrer_policy: Option<String>,
+    },
 }
 
 #[derive(Clone)]
@@ -33,7 +123,12 @@ pub struct ManualHttpClient {
     tls_config: Arc<ClientConfig>,
     timeout: Duration,
     max_redirects: usize,
-    max_body_size: usize,
+    // Bodies past this are not read; None reads everything
+    max_body_size: Option<usize>,
+    // Accept short or malformed bodies instead of failing the request
+    lenient_body: bool,
+    // Decides which failed exchanges are tried again over reqwest; None never retries
+    fallback: Option<fn(&anyhow::Error) -> bool>,
 }
 
 // Threshold for when to use temporary file storage instead of memory (5MB)
@@ -54,15 +149,60 @@ impl ManualHttpClient {
         Ok(Self {
             tls_config: Arc::new(config),
             timeout: Duration::from_secs(30),
-            max_redirects: 10,
-            max_body_size: 50 * 1024 * 1024, // 50MB for better big site compatibility
+            max_redirects: MAX_REDIRECTS,
+            max_body_size: Some(DEFAULT_PAGE_SIZE_LIMIT),
+            lenient_body: false,
+            fallback: None,
         })
     }
 
+    /// Keep partial bodies (marked as truncated) instead of failing on a premature close
+    pub fn set_lenient_body(&mut self, lenient: bool) {
+        self.lenient_body = lenient;
+    }
+
+    /// Stop reading bodies larger than `limit` bytes and mark the response as oversized instead
+    pub fn set_max_body_size(&mut self, limit: Option<usize>) {
+        self.max_body_size = limit;
+    }
+
+    /// Send a hop again over reqwest when its exchange fails with an error `permits` accepts. The retried hop
+    /// goes through the same redirect, cookie and credential handling as any other.
+    pub fn set_fallback(&mut self, permits: Option<fn(&anyhow::Error) -> bool>) {
+        self.fallback = permits;
+    }
+
+    /// Connector with the same root certificates the client's requests are verified against
+    pub fn tls_connector(&self) -> TlsConnector {
+        TlsConnector::from(self.tls_config.clone())
+    }
+
     pub async fn fetch(&self, url: &str) -> Result<ManualFetchResult> {
+        self.fetch_with_context(url, &FetchContext::default()).await
+    }
+
+    /// Fetch a URL, following redirects with cookies, Referer and credentials recomputed per hop
+    pub async fn fetch_with_context(&self, url: &str, context: &FetchContext) -> Result<ManualFetchResult> {
         let mut current_url = url.to_string();
-        let mut redirects = Vec::new();
-        let mut phases = Vec::new();
+        let mut redirect_chain: Vec<RedirectHop> = Vec::new();
+        let mut timing = RequestTiming::new(url);
+        let mut referrer_policy = context.referrer_policy;
+        let mut authorization = context.authorization.clone();
+        // Dropped once a redirect turns the request into a GET
+        let mut request_body = context.body.clone();
+        
+        // data:, blob: and file:// URLs are answered without going to the network
+        if local_url::is_local(url) || file_url::is_file(url) {
+            let response = if file_url::is_file(url) { file_url::response_for(url)? } else { local_url::response_for(url)? };
+            timing.push(FetchPhase::Completed);
+            return Ok(ManualFetchResult {
+                response,
+                timing,
+                redirect_chain,
+                final_url: current_url,
+                request_headers: Vec::new(),
+            });
+        }
 
         // Handle common URL corrections
         if !current_url.starts_with("http://") && 
@@ -72,8 +212,8 @@ impl ManualHttpClient {
             current_url = format!("https://{}", current_url);
         }
 
-        for redirect_count in 0..=self.max_redirects {
-            phases.push(FetchPhase::Resolving);
+        for _ in 0..=self.max_redirects {
+            timing.push(FetchPhase::Resolving);
             
             let parsed = reqwest::Url::parse(&current_url)
                 .map_err(|e| anyhow!("Invalid URL '{}': {}", current_url, e))?;
@@ -81,6 +221,31 @@ impl ManualHttpClient {
             let host = parsed.host_str()
                 .ok_or_else(|| anyhow!("Missing host in URL: {}", current_url))?
                 .to_string();
+            // Checked on every hop, so a redirect can't lead to a tracker either
+            if context.tracking.as_ref().is_some_and(|tracking| tracking.intercept(&current_url)) {
+                return Err(RequestBlocked { reason: format!("Blocked by tracking protection: {}", host) }.into());
+            }
+            // Extension rules see every hop as well, and may block it or send it elsewhere
+            let verdict = web_request::evaluate(&current_url, context.resource_type);
+            if let Some(extension) = verdict.blocked_by {
+                return Err(RequestBlocked { reason: format!("Blocked by extension '{}': {}", extension, current_url) }.into());
+            }
+            if let Some(target) = verdict.redirect {
+                log::info!("Extension redirect {} -> {}", current_url, target);
+                timing.push(FetchPhase::Redirecting);
+                redirect_chain.push(RedirectHop {
+                    from: current_url.clone(),
+                    to: target.clone(),
+                    status_code: 307,
+                    cross_origin: !is_same_origin(&current_url, &target),
+                    method: request_body.as_ref().map_or("GET", |body| body.method.as_str()).to_string(),
+                });
+                if !is_same_origin(&current_url, &target) {
+                    authorization = None;
+                }
+                current_url = target;
+                continue;
+            }
             
             let port = parsed.port_or_known_default()
                 .ok_or_else(|| anyhow!("Cannot determine port for URL: {}", current_url))?;
@@ -99,63 +264,151 @@ impl ManualHttpClient {
                 pq
             };
 
-            phases.push(FetchPhase::Connecting);
-            
-            // Enhanced DNS resolution with multiple attempts
-            let addr_iter = match tokio::time::timeout(
-                Duration::from_secs(10), 
-                tokio::net::lookup_host((host.as_str(), port))
-            ).await {
-                Ok(Ok(iter)) => iter,
-                Ok(Err(e)) => return Err(anyhow!("DNS resolution failed for {}: {}", host, e)),
-                Err(_) => return Err(anyhow!("DNS resolution timeout for {}", host)),
+            // User agent, cookies, Referer and Authorization are decided for this hop's URL, not the original one
+            let preferences = Preferences::current();
+            let mut extra_headers = preferences.user_agent.preset_for(&current_url).headers();
+            extra_headers.extend(preferences.privacy.headers().into_iter().map(|(name, value)| (name.to_string(), value.to_string())));
+            let accept_language = context.accept_language.as_deref().unwrap_or(DEFAULT_ACCEPT_LANGUAGE);
+            extra_headers.push(("Accept-Language".to_string(), accept_language.to_string()));
+            let cookies_blocked = preferences.cookies.blocks(&current_url);
+            if let Some(jar) = context.cookies.as_ref().filter(|_| !cookies_blocked) {
+                if let Ok(jar) = jar.lock() {
+                    let domain = tracking::cookie_domain(context.tracking.as_ref(), context.cookie_partition.as_deref(), &current_url, &host, 0);
+                    if let Some(cookie) = jar.get_cookie_header_for_request(&domain, parsed.path(), is_https) {
+                        extra_headers.push(("Cookie".to_string(), cookie));
+                    }
+                }
+            }
+            if let Some(referrer) = &context.referrer {
+                if let Some(value) = referrer_policy.referrer_for(referrer, &current_url) {
+                    extra_headers.push(("Referer".to_string(), value));
+                }
+            }
+            if let Some(auth) = &authorization {
+                extra_headers.push(("Authorization".to_string(), auth.clone()));
+            }
+            if let Some((start, end)) = context.range {
+                extra_headers.push(("Range".to_string(), format!("bytes={}-{}", start, end)));
+            }
+            // A hard reload asks proxies and CDNs for a fresh copy too
+            if context.cache_mode == CacheMode::Bypass {
+                extra_headers.push(("Cache-Control".to_string(), "no-cache".to_string()));
+                extra_headers.push(("Pragma".to_string(), "no-cache".to_string()));
+            } else {
+                extra_headers.push(("Cache-Control".to_string(), "max-age=0".to_string()));
+            }
+            web_request::apply_header_changes(&mut extra_headers, &verdict.headers);
+
+            // Only plain GETs of whole resources go through the cache, and stored responses that vary on
+            // request headers only answer requests sending the same values
+            let cacheable = request_body.is_none() && context.range.is_none();
+            let mut validators = Vec::new();
+            if cacheable {
+                match HttpCache::shared().lookup(&current_url, &extra_headers, context.cache_mode) {
+                    Lookup::Fresh(response) => {
+                        timing.cache = CacheStatus::Fresh;
+                        timing.push(FetchPhase::Completed);
+                        timing.url = current_url.clone();
+                        return Ok(ManualFetchResult {
+                            response: *response,
+                            timing,
+                            redirect_chain,
+                            final_url: current_url,
+                            request_headers: extra_headers,
+                        });
+                    }
+                    Lookup::Validate(headers) => validators = headers,
+                    Lookup::Miss => {}
+                }
+            }
+            let request_headers = extra_headers.clone();
+            extra_headers.extend(validators.iter().cloned());
+
+            throttling::before_request(&current_url).await?;
+
+            let request = RoundRequest { path_and_query, headers: extra_headers, body: request_body.as_ref() };
+            let exchange = async {
+                // A connection warmed by a preconnect hint skips DNS, TCP and TLS setup
+                let origin = Origin { https: is_https, host: host.clone(), port };
+                let connection = match ConnectionPool::shared().take(&origin).await {
+                    Some(connection) => {
+                        timing.connection_reused = true;
+                        connection
+                    }
+                    None => {
+                        let addresses = self.resolve(&host, port).await?;
+                        timing.push(FetchPhase::Connecting);
+                        Connection::Tcp(self.connect_tcp(&host, &addresses).await?)
+                    }
+                };
+                self.fetch_single_round(is_https, host.clone(), connection, &request, &mut timing).await
+            }.await;
+            let (outcome, set_cookies) = match exchange {
+                Ok(exchange) => exchange,
+                Err(e) if self.fallback.is_some_and(|permits| permits(&e)) => {
+                    log::info!("Retrying {} over reqwest ({})", current_url, e);
+                    self.fetch_round_with_reqwest(&current_url, &request, &mut timing).await
+                        .map_err(|fallback| anyhow!("{}. Fallback error: {}", e, fallback))?
+                }
+                Err(e) => return Err(e),
             };
 
-            let mut last_err = None;
-            let mut stream_opt = None;
-            
-            // Try connecting to multiple resolved addresses
-            for addr in addr_iter {
-                match tokio::time::timeout(self.timeout, TcpStream::connect(addr)).await {
-                    Ok(Ok(s)) => { 
-                        stream_opt = Some(s); 
-                        break; 
-                    },
-                    Ok(Err(e)) => { 
-                        last_err = Some(e); 
-                        continue; 
-                    },
-                    Err(_) => continue, // Try next address on timeout
+            // Store cookies from every hop, including intermediate redirects
+            if let Some(jar) = context.cookies.as_ref().filter(|_| !cookies_blocked) {
+                if let Ok(mut jar) = jar.lock() {
+                    let domain = tracking::cookie_domain(context.tracking.as_ref(), context.cookie_partition.as_deref(), &current_url, &host, set_cookies.len());
+                    for value in &set_cookies {
+                        jar.parse_set_cookie_header(value, &domain);
+                    }
                 }
             }
-            
-            let stream = stream_opt.ok_or_else(|| {
-                anyhow!("Failed to connect to {}: {:?}", host, last_err)
-            })?;
-
-            // Attempt the actual HTTP request
-            match self.fetch_single_round(
-                is_https, 
-                host, 
-                port, 
-                path_and_query, 
-                stream, 
-                redirects.clone(), 
-                phases.clone(), 
-                current_url.clone()
-            ).await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    let err_str = e.to_string();
-                    if err_str.starts_with("REDIRECT:") {
-                        let new_url = err_str.strip_prefix("REDIRECT:").unwrap_or(&current_url);
-                        redirects.push(current_url.clone());
-                        current_url = new_url.to_string();
-                        phases.push(FetchPhase::Redirecting);
-                        continue;
-                    } else {
-                        return Err(e);
+
+            match outcome {
+                RoundOutcome::Complete(mut response) => {
+                    if response.status_code == 304 && !validators.is_empty() {
+                        if let Some(cached) = HttpCache::shared().revalidated(&current_url, &response) {
+                            *response = cached;
+                            timing.cache = CacheStatus::Revalidated;
+                        }
+                    } else if cacheable {
+                        HttpCache::shared().store(&current_url, &request_headers, &response);
+                    }
+                    timing.push(FetchPhase::Completed);
+                    timing.url = current_url.clone();
+                    return Ok(ManualFetchResult {
+                        response: *response,
+                        timing,
+                        redirect_chain,
+                        final_url: current_url,
+                        request_headers,
+                    });
+                }
+                RoundOutcome::Redirect { status_code, location, referrer_policy: policy_header } => {
+                    let next_url = resolve_location(&current_url, &location)?;
+                    let cross_origin = !is_same_origin(&current_url, &next_url);
+                    
+                    // Never forward credentials to a different origin
+                    if cross_origin && authorization.take().is_some() {
+                        log::info!("Dropping Authorization header on cross-origin redirect to {}", next_url);
                     }
+                    if let Some(policy) = policy_header.as_deref().and_then(ReferrerPolicy::from_header) {
+                        referrer_policy = policy;
+                    }
+                    let method = request_body.as_ref().map_or("GET", |body| body.method.as_str());
+                    if !preserves_method(status_code, method) {
+                        request_body = None;
+                    }
+                    
+                    log::debug!("Redirect {} -> {}", status_code, next_url);
+                    timing.push(FetchPhase::Redirecting);
+                    redirect_chain.push(RedirectHop {
+                        from: current_url.clone(),
+                        to: next_url.clone(),
+                        status_code,
+                        cross_origin,
+                        method: request_body.as_ref().map_or("GET", |body| body.method.as_str()).to_string(),
+                    });
+                    current_url = next_url;
                 }
             }
         }
@@ -163,61 +416,135 @@ impl ManualHttpClient {
         Err(anyhow!("Too many redirects ({}), stopped at: {}", self.max_redirects, current_url))
     }
 
+    /// Open a connection to `url`'s origin ahead of a request for it, so that request skips DNS, TCP and TLS setup
+    pub fn preconnect(&self, url: &str) {
+        let Some(origin) = Origin::of(url) else { return };
+        let pool = ConnectionPool::shared();
+        if pool.has_connection(&origin) || !pool.begin_connecting(&origin) {
+            return;
+        }
+        let client = self.clone();
+        Executor::shared().spawn(TaskKind::Network, async move {
+            let connection = client.open_connection(&origin).await;
+            if let Err(e) = &connection {
+                log::debug!("Preconnect to {} failed: {}", origin.host, e);
+            }
+            ConnectionPool::shared().finish_connecting(&origin, connection.ok());
+        });
+    }
+
+    /// Look up `url`'s host ahead of a request for it
+    pub fn dns_prefetch(&self, url: &str) {
+        let Some(origin) = Origin::of(url) else { return };
+        if ConnectionPool::shared().cached_addresses(&origin.host, origin.port).is_some() {
+            return;
+        }
+        let client = self.clone();
+        Executor::shared().spawn(TaskKind::Network, async move {
+            if let Err(e) = client.resolve(&origin.host, origin.port).await {
+                log::debug!("DNS prefetch failed: {}", e);
+            }
+        });
+    }
+
+    async fn open_connection(&self, origin: &Origin) -> Result<Connection> {
+        let addresses = self.resolve(&origin.host, origin.port).await?;
+        let stream = self.connect_tcp(&origin.host, &addresses).await?;
+        if origin.https {
+            return Ok(Connection::Tls(Box::new(self.tls_handshake(&origin.host, stream).await?)));
+        }
+        Ok(Connection::Tcp(stream))
+    }
+
+    // Recent answers, including ones from dns-prefetch hints, are reused
+    async fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+        let pool = ConnectionPool::shared();
+        if let Some(addresses) = pool.cached_addresses(host, port) {
+            return Ok(addresses);
+        }
+        let addresses: Vec<SocketAddr> = match tokio::time::timeout(
+            Duration::from_secs(10), 
+            tokio::net::lookup_host((host, port))
+        ).await {
+            Ok(Ok(iter)) => iter.collect(),
+            Ok(Err(e)) => return Err(anyhow!("DNS resolution failed for {}: {}", host, e)),
+            Err(_) => return Err(anyhow!("DNS resolution timeout for {}", host)),
+        };
+        pool.cache_addresses(host, port, addresses.clone());
+        Ok(addresses)
+    }
+
+    // Try each resolved address in turn
+    async fn connect_tcp(&self, host: &str, addresses: &[SocketAddr]) -> Result<TcpStream> {
+        let mut last_err = None;
+        for addr in addresses {
+            match tokio::time::timeout(self.timeout, TcpStream::connect(addr)).await {
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(e)) => last_err = Some(e),
+                Err(_) => continue, // Try next address on timeout
+            }
+        }
+        Err(anyhow!("Failed to connect to {}: {:?}", host, last_err))
+    }
+
+    async fn tls_handshake(&self, host: &str, stream: TcpStream) -> Result<tokio_rustls::client::TlsStream<TcpStream>> {
+        let connector = TlsConnector::from(self.tls_config.clone());
+        let domain = rustls::pki_types::ServerName::try_from(host.to_string())
+            .map_err(|_| anyhow!("Invalid hostname for TLS: {}", host))?;
+        
+        tokio::time::timeout(
+            Duration::from_secs(20), 
+            connector.connect(domain, stream)
+        )
+        .await
+        .map_err(|_| anyhow!("TLS handshake timeout after 20s"))?
+        .map_err(|e| {
+            let err_str = e.to_string();
+            if err_str.contains("close_notify") || err_str.contains("CloseNotify") {
+                anyhow!("TLS session closed by server (close_notify)")
+            } else if err_str.contains("certificate") {
+                anyhow!("TLS certificate validation failed: {}", e)
+            } else if err_str.contains("protocol") {
+                anyhow!("TLS protocol version mismatch: {}", e)
+            } else {
+                anyhow!("TLS handshake failed: {}", e)
+            }
+        })
+    }
+
+    // Returns the round outcome plus every Set-Cookie value seen on the response
     async fn fetch_single_round(
         &self,
         is_https: bool,
         host: String,
-        _port: u16,
-        path_and_query: String,
-        stream_plain: TcpStream,
-        redirects: Vec<String>,
-        mut phases: Vec<FetchPhase>,
-        original_url: String,
-    ) -> Result<ManualFetchResult> {
+        connection: Connection,
+        request: &RoundRequest<'_>,
+        timing: &mut RequestTiming,
+    ) -> Result<(RoundOutcome, Vec<String>)> {
         enum Conn { 
             Plain(TcpStream), 
             Tls(tokio_rustls::client::TlsStream<TcpStream>) 
         }
         
-        let mut conn = if is_https {
-            phases.push(FetchPhase::TlsHandshake);
-            let connector = TlsConnector::from(self.tls_config.clone());
-            
-            let domain = rustls::pki_types::ServerName::try_from(host.clone())
-                .map_err(|_| anyhow!("Invalid hostname for TLS: {}", host))?;
-            
-            let tls_stream = tokio::time::timeout(
-                Duration::from_secs(20), 
-                connector.connect(domain, stream_plain)
-            )
-            .await
-            .map_err(|_| anyhow!("TLS handshake timeout after 20s"))?
-            .map_err(|e| {
-                let err_str = e.to_string();
-                if err_str.contains("close_notify") || err_str.contains("CloseNotify") {
-                    anyhow!("TLS session closed by server (close_notify)")
-                } else if err_str.contains("certificate") {
-                    anyhow!("TLS certificate validation failed: {}", e)
-                } else if err_str.contains("protocol") {
-                    anyhow!("TLS protocol version mismatch: {}", e)
-                } else {
-                    anyhow!("TLS handshake failed: {}", e)
-                }
-            })?;
-            Conn::Tls(tls_stream)
-        } else {
-            Conn::Plain(stream_plain)
+        let mut conn = match connection {
+            Connection::Tls(stream) => Conn::Tls(*stream),
+            Connection::Tcp(stream) if is_https => {
+                timing.push(FetchPhase::TlsHandshake);
+                Conn::Tls(self.tls_handshake(&host, stream).await?)
+            }
+            Connection::Tcp(stream) => Conn::Plain(stream),
+        };
+        timing.tls = match &conn {
+            Conn::Tls(s) => Some(TlsInfo::of(s.get_ref().1)),
+            Conn::Plain(_) => None,
         };
 
-        phases.push(FetchPhase::SendingRequest);
+        timing.push(FetchPhase::SendingRequest);
         
         // Enhanced HTTP request with comprehensive headers
-        let request_headers = format!(
-            "GET {} HTTP/1.1\r\n\
+        let mut request_headers = format!(
+            "{} {} HTTP/1.1\r\n\
             Host: {}\r\n\
-            User-Agent: Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) NeonSearch/1.0 Chrome/120.0.0.0 Safari/537.36\r\n\
-            Accept: text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7\r\n\
-            Accept-Language: en-US,en;q=0.9\r\n\
             Accept-Encoding: gzip, deflate, br, zstd\r\n\
             DNT: 1\r\n\
             Connection: close\r\n\
@@ -225,24 +552,34 @@ impl ManualHttpClient {
             Sec-Fetch-Dest: document\r\n\
             Sec-Fetch-Mode: navigate\r\n\
             Sec-Fetch-Site: none\r\n\
-            Sec-Fetch-User: ?1\r\n\
-            Cache-Control: max-age=0\r\n\
-            \r\n", 
-            path_and_query, host
+            Sec-Fetch-User: ?1\r\n", 
+            request.body.map_or("GET", |body| body.method.as_str()), request.path_and_query, host
         );
+        for (name, value) in &request.headers {
+            request_headers.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(body) = request.body {
+            request_headers.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n", body.content_type, body.data.len()));
+        }
+        request_headers.push_str("\r\n");
+        let mut request_bytes = request_headers.into_bytes();
+        if let Some(body) = request.body {
+            request_bytes.extend_from_slice(&body.data);
+        }
 
+        timing.bytes_sent += request_bytes.len();
         match &mut conn {
             Conn::Plain(s) => {
-                s.write_all(request_headers.as_bytes()).await
+                s.write_all(&request_bytes).await
                     .map_err(|e| anyhow!("Failed to send HTTP request: {}", e))?;
             },
             Conn::Tls(s) => {
-                s.write_all(request_headers.as_bytes()).await
+                s.write_all(&request_bytes).await
                     .map_err(|e| anyhow!("Failed to send HTTPS request: {}", e))?;
             },
         }
 
-        phases.push(FetchPhase::ReadingHeaders);
+        timing.push(FetchPhase::ReadingHeaders);
         let mut raw = Vec::new();
         let mut buf = [0u8; 8192]; // Larger buffer for better performance
         let mut header_end = None;
@@ -255,7 +592,11 @@ impl ManualHttpClient {
                     Conn::Tls(s) => s.read(&mut buf).await,
                 }
             }).await {
-                Ok(Ok(n)) => n,
+                Ok(Ok(n)) => {
+                    timing.bytes_received += n;
+                    throttling::after_read(n).await;
+                    n
+                },
                 Ok(Err(e)) => {
                     return Err(anyhow!("Network read error while reading headers: {}", e));
                 },
@@ -315,22 +656,29 @@ impl ManualHttpClient {
         
         // Parse response headers
         let mut headers = HashMap::new();
+        let mut set_cookies = Vec::new();
         for line in lines {
             let line = line.trim();
             if let Some((key, value)) = line.split_once(':') {
                 let key = key.trim().to_lowercase(); // Normalize header names
                 let value = value.trim().to_string();
+                // Set-Cookie may repeat, so keep every value before the map collapses them
+                if key == "set-cookie" {
+                    set_cookies.push(value.clone());
+                    headers.insert(key, set_cookies.join("\n"));
+                    continue;
+                }
                 headers.insert(key, value);
             }
         }
         
         // Log response info for debugging
-        println!("HTTP Response: {} {} {} (Content-Length: {:?}, Content-Encoding: {:?})", 
+        log::debug!("HTTP Response: {} {} {} (Content-Length: {:?}, Content-Encoding: {:?})", 
                  http_version, status_code, status_text,
                  headers.get("content-length"),
                  headers.get("content-encoding"));
 
-        phases.push(FetchPhase::ReadingBody);
+        timing.push(FetchPhase::ReadingBody);
         
         // Determine body reading strategy
         let transfer_encoding = headers.get("transfer-encoding")
@@ -340,12 +688,17 @@ impl ManualHttpClient {
         let content_length = headers.get("content-length")
             .and_then(|v| v.parse::<usize>().ok());
 
-        let mut body = body_bytes.to_vec();
+        let content_type = headers.get("content-type").cloned();
+        let mut body = BodySink::new(content_type);
+        let mut truncation: Option<String> = None;
+        let mut oversized: Option<OversizedBody> = None;
         let read_timeout = Duration::from_secs(15);
         
-        if transfer_encoding.contains("chunked") {
+        if matches!(status_code, 204 | 304) {
+            // Never followed by a body, whatever the headers say
+        } else if transfer_encoding.contains("chunked") {
             // Handle chunked transfer encoding
-            let mut remainder = Vec::new();
+            let mut remainder = body_bytes.to_vec();
             let mut total_read = 0;
             
             loop {
@@ -355,13 +708,17 @@ impl ManualHttpClient {
                         Conn::Tls(s) => s.read(&mut buf).await,
                     }
                 }).await {
-                    Ok(Ok(n)) => n,
+                    Ok(Ok(n)) => {
+                        timing.bytes_received += n;
+                        throttling::after_read(n).await;
+                        n
+                    },
                     Ok(Err(e)) => {
-                        println!("Read error during chunked transfer: {}", e);
+                        log::warn!("Read error during chunked transfer: {}", e);
                         break;
                     },
                     Err(_) => {
-                        println!("Timeout during chunked transfer after {}KB", total_read / 1024);
+                        log::warn!("Timeout during chunked transfer after {}KB", total_read / 1024);
                         break;
                     }
                 };
@@ -371,59 +728,93 @@ impl ManualHttpClient {
                 remainder.extend_from_slice(&buf[..n]);
                 total_read += n;
                 
-                // Safety limits
-                if remainder.len() > self.max_body_size {
-                    println!("Chunked response truncated at {}MB", remainder.len() / 1024 / 1024);
+                // Stop at the page size limit and let the user decide whether to continue
+                if let Some(limit) = self.max_body_size.filter(|limit| remainder.len() > *limit) {
+                    log::warn!("Chunked response passed the {}MB limit", limit / 1024 / 1024);
+                    oversized = Some(OversizedBody { limit, received: remainder.len(), expected: None });
                     break;
                 }
             }
             
-            body.extend_from_slice(&remainder);
-            body = match decode_chunked(&body) { 
-                Ok(decoded) => decoded,
-                Err(e) => {
-                    println!("Chunked decoding failed: {}, using raw data", e);
-                    body
+            if oversized.is_none() {
+                let strict = !self.lenient_body;
+                match decode_chunked(&remainder, strict) {
+                    Ok(decoded) => {
+                        if !decoded.complete {
+                            truncation = Some("Connection closed before the final chunk".to_string());
+                        }
+                        merge_trailers(&mut headers, decoded.trailers);
+                        body.push(&decoded.data)?;
+                    }
+                    Err(e) if strict => {
+                        return Err(anyhow!("Malformed chunked response: {}", e));
+                    }
+                    Err(e) => {
+                        log::warn!("Chunked decoding failed: {}, using raw data", e);
+                        truncation = Some(format!("Malformed chunked body: {}", e));
+                        body.push(&remainder)?;
+                    }
                 }
-            };
+            }
             
         } else if let Some(expected_len) = content_length {
             // Handle Content-Length specified response
-            let mut total_read = body.len();
-            
-            while total_read < expected_len && total_read < self.max_body_size {
-                let n = match tokio::time::timeout(read_timeout, async {
-                    match &mut conn {
-                        Conn::Plain(s) => s.read(&mut buf).await,
-                        Conn::Tls(s) => s.read(&mut buf).await,
-                    }
-                }).await {
-                    Ok(Ok(n)) => n,
-                    Ok(Err(e)) => {
-                        println!("Read error during content-length transfer: {}", e);
-                        break;
-                    },
-                    Err(_) => {
-                        println!("Timeout during content-length transfer at {}/{}KB", 
-                                total_read / 1024, expected_len / 1024);
-                        break;
-                    }
-                };
+            if let Some(limit) = self.max_body_size.filter(|limit| expected_len > *limit) {
+                // Known up front, so don't spend the bandwidth
+                log::warn!("Content-length response of {}MB passes the {}MB limit", expected_len / 1024 / 1024, limit / 1024 / 1024);
+                oversized = Some(OversizedBody { limit, received: body_bytes.len(), expected: Some(expected_len) });
+            } else {
+                // Anything past the declared length is not part of this response
+                body.push(&body_bytes[..body_bytes.len().min(expected_len)])?;
+                if body_bytes.len() > expected_len {
+                    log::debug!("Discarding {} bytes beyond Content-Length", body_bytes.len() - expected_len);
+                }
                 
-                if n == 0 { break; }
+                while body.len() < expected_len {
+                    let n = match tokio::time::timeout(read_timeout, async {
+                        match &mut conn {
+                            Conn::Plain(s) => s.read(&mut buf).await,
+                            Conn::Tls(s) => s.read(&mut buf).await,
+                        }
+                    }).await {
+                        Ok(Ok(n)) => {
+                            timing.bytes_received += n;
+                            throttling::after_read(n).await;
+                            n
+                        },
+                        Ok(Err(e)) => {
+                            log::warn!("Read error during content-length transfer: {}", e);
+                            break;
+                        },
+                        Err(_) => {
+                            log::warn!("Timeout during content-length transfer at {}/{}KB", 
+                                    body.len() / 1024, expected_len / 1024);
+                            break;
+                        }
+                    };
+                    
+                    if n == 0 { break; }
+                    
+                    let wanted = n.min(expected_len - body.len());
+                    if wanted < n {
+                        log::debug!("Discarding {} bytes beyond Content-Length", n - wanted);
+                    }
+                    body.push(&buf[..wanted])?;
+                }
                 
-                body.extend_from_slice(&buf[..n]);
-                total_read += n;
-            }
-            
-            if total_read >= self.max_body_size {
-                println!("Content-length response truncated at {}MB (expected {}MB)", 
-                        total_read / 1024 / 1024, expected_len / 1024 / 1024);
+                if body.len() < expected_len {
+                    let reason = format!("Connection closed after {} of {} bytes", body.len(), expected_len);
+                    if !self.lenient_body {
+                        body.discard();
+                        return Err(anyhow!("Incomplete response body: {}", reason));
+                    }
+                    truncation = Some(reason);
+                }
             }
             
         } else {
             // Read until connection closes (HTTP/1.0 style or Connection: close)
-            let mut total_read = body.len();
+            body.push(body_bytes)?;
             
             loop {
                 let n = match tokio::time::timeout(read_timeout, async {
@@ -432,136 +823,287 @@ impl ManualHttpClient {
                         Conn::Tls(s) => s.read(&mut buf).await,
                     }
                 }).await {
-                    Ok(Ok(n)) => n,
+                    Ok(Ok(n)) => {
+                        timing.bytes_received += n;
+                        throttling::after_read(n).await;
+                        n
+                    },
                     Ok(Err(_)) => break, // Connection closed or error
                     Err(_) => break, // timeout - assume end of response
                 };
                 
                 if n == 0 { break; }
                 
-                body.extend_from_slice(&buf[..n]);
-                total_read += n;
+                body.push(&buf[..n])?;
                 
-                if total_read > self.max_body_size {
-                    println!("Connection-close response truncated at {}MB", total_read / 1024 / 1024);
+                if let Some(limit) = self.max_body_size.filter(|limit| body.len() > *limit) {
+                    log::warn!("Connection-close response passed the {}MB limit", limit / 1024 / 1024);
+                    oversized = Some(OversizedBody { limit, received: body.len(), expected: None });
                     break;
                 }
             }
         }
         
-        println!("Final response body size: {}KB", body.len() / 1024);
+        // An oversized body is not kept: the user either loads it in full or downloads it
+        if oversized.is_some() {
+            body.discard();
+            body = BodySink::new(None);
+        }
+        log::debug!("Final response body size: {}KB", body.len() / 1024);
 
-        // Handle redirects
-        if (300..400).contains(&status_code) {
+        // Hand redirects back to the caller, which owns the hop policy
+        if matches!(status_code, 301 | 302 | 303 | 307 | 308) {
+            body.discard();
             if let Some(location) = headers.get("location") {
-                let new_url = if location.starts_with("http://") || location.starts_with("https://") {
-                    location.clone()
-                } else {
-                    // Handle relative redirects
-                    let base = reqwest::Url::parse(&original_url)
-                        .map_err(|e| anyhow!("Cannot parse base URL for redirect: {}", e))?;
-                    base.join(location)
-                        .map_err(|e| anyhow!("Cannot resolve redirect URL '{}': {}", location, e))?
-                        .to_string()
-                };
-                
-                println!("Redirect {} -> {}", status_code, new_url);
-                phases.push(FetchPhase::Redirecting);
-                return Err(anyhow!("REDIRECT:{}", new_url));
+                return Ok((
+                    RoundOutcome::Redirect {
+                        status_code,
+                        location: location.clone(),
+                        referrer_policy: headers.get("referrer-policy").cloned(),
+                    },
+                    set_cookies,
+                ));
             } else {
                 return Err(anyhow!("Redirect response {} without Location header", status_code));
             }
         }
 
-        phases.push(FetchPhase::Completed);
+        let mut response = body.into_response(status_code, status_text, standard_headers(headers))?;
+        response.oversized = oversized;
+        response.size_limit = self.max_body_size;
+        response.truncation = truncation;
         
-        // Convert lowercase headers back to standard case for compatibility
-        let mut final_headers = HashMap::new();
-        for (k, v) in headers {
-            let standard_key = match k.as_str() {
-                "content-type" => "Content-Type".to_string(),
-                "content-length" => "Content-Length".to_string(),
-                "content-encoding" => "Content-Encoding".to_string(),
-                "location" => "Location".to_string(),
-                "set-cookie" => "Set-Cookie".to_string(),
-                _ => k,
-            };
-            final_headers.insert(standard_key, v);
+        Ok((RoundOutcome::Complete(Box::new(response)), set_cookies))
+    }
+
+    // The exchange of one hop over reqwest, for when the manual one failed. Redirects aren't followed but handed
+    // back like the manual exchange's, so the caller applies its per-hop policy to them as well.
+    async fn fetch_round_with_reqwest(
+        &self,
+        url: &str,
+        request: &RoundRequest<'_>,
+        timing: &mut RequestTiming,
+    ) -> Result<(RoundOutcome, Vec<String>)> {
+        let client = http_client::single_hop_client()?;
+        let method = reqwest::Method::from_bytes(request.body.map_or("GET", |body| body.method.as_str()).as_bytes())?;
+        let mut builder = client.request(method, url).header("Accept-Encoding", "gzip, deflate, br, zstd");
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
         }
-        
-        // Decide whether to use memory or temporary file storage
-        let response = if body.len() > TEMP_FILE_THRESHOLD {
-            println!("Large content ({}KB) - using temporary file storage", body.len() / 1024);
-            
-            // Store content in temporary file
-            let temp_manager = TempStorageManager::new()
-                .map_err(|e| anyhow!("Failed to create temp storage manager: {}", e))?;
-            
-            let content_type = final_headers.get("Content-Type").cloned();
-            let temp_file = temp_manager.store_content(&body, content_type)
-                .map_err(|e| anyhow!("Failed to store content in temp file: {}", e))?;
-                
-            println!("Content stored in temporary file: {:?}", temp_file.path);
-            HttpResponse::new_with_temp_file(status_code, status_text, final_headers, temp_file)
-        } else {
-            // Use traditional in-memory storage for smaller content
-            HttpResponse::new(status_code, status_text, final_headers, body)
+        if let Some(body) = request.body {
+            builder = builder.header("Content-Type", &body.content_type).body(body.data.clone());
+        }
+        timing.push(FetchPhase::SendingRequest);
+        let mut response = builder.send().await?;
+        timing.push(FetchPhase::ReadingBody);
+
+        let status_code = response.status().as_u16();
+        let status_text = response.status().canonical_reason().unwrap_or_default().to_string();
+        let set_cookies: Vec<String> = response.headers().get_all(reqwest::header::SET_COOKIE).iter()
+            .filter_map(|value| value.to_str().ok().map(str::to_string))
+            .collect();
+        let mut headers = HashMap::new();
+        for (name, value) in response.headers() {
+            if let Ok(value) = value.to_str() {
+                headers.insert(name.as_str().to_string(), value.to_string());
+            }
+        }
+        if !set_cookies.is_empty() {
+            headers.insert("set-cookie".to_string(), set_cookies.join("\n"));
+        }
+        if matches!(status_code, 301 | 302 | 303 | 307 | 308) {
+            let location = headers.get("location").cloned()
+                .ok_or_else(|| anyhow!("Redirect response {} without Location header", status_code))?;
+            let referrer_policy = headers.get("referrer-policy").cloned();
+            return Ok((RoundOutcome::Redirect { status_code, location, referrer_policy }, set_cookies));
+        }
+
+        let expected = response.content_length().map(|length| length as usize);
+        let mut body = BodySink::new(headers.get("content-type").cloned());
+        let mut oversized = None;
+        while let Some(chunk) = response.chunk().await? {
+            timing.bytes_received += chunk.len();
+            body.push(&chunk)?;
+            if let Some(limit) = self.max_body_size.filter(|limit| body.len() > *limit) {
+                oversized = Some(OversizedBody { limit, received: body.len(), expected });
+                break;
+            }
+        }
+        if oversized.is_some() {
+            body.discard();
+            body = BodySink::new(None);
+        }
+        let mut response = body.into_response(status_code, status_text, standard_headers(headers))?;
+        response.oversized = oversized;
+        response.size_limit = self.max_body_size;
+        Ok((RoundOutcome::Complete(Box::new(response)), set_cookies))
+    }
+}
+
+// Lowercase header names back in the case the rest of the browser looks them up in
+fn standard_headers(headers: HashMap<String, String>) -> HashMap<String, String> {
+    headers.into_iter().map(|(name, value)| {
+        let name = match name.as_str() {
+            "content-type" => "Content-Type".to_string(),
+            "content-length" => "Content-Length".to_string(),
+            "content-encoding" => "Content-Encoding".to_string(),
+            "location" => "Location".to_string(),
+            "set-cookie" => "Set-Cookie".to_string(),
+            _ => name,
         };
-        
-        Ok(ManualFetchResult { 
-            response, 
-            phases, 
-            redirects 
+        (name, value)
+    }).collect()
+}
+
+// Response body as it is read: in memory up to TEMP_FILE_THRESHOLD, then streamed to a temporary file
+struct BodySink {
+    memory: Vec<u8>,
+    file: Option<TempFileWriter>,
+    content_type: Option<String>,
+}
+
+impl BodySink {
+    fn new(content_type: Option<String>) -> Self {
+        Self { memory: Vec::new(), file: None, content_type }
+    }
+
+    fn len(&self) -> usize {
+        self.file.as_ref().map_or(self.memory.len(), TempFileWriter::len)
+    }
+
+    fn push(&mut self, data: &[u8]) -> Result<()> {
+        if let Some(file) = &mut self.file {
+            return file.write(data);
+        }
+        self.memory.extend_from_slice(data);
+        if self.memory.len() > TEMP_FILE_THRESHOLD {
+            log::debug!("Large content ({}KB) - streaming to temporary file", self.memory.len() / 1024);
+            let mut file = TempFileWriter::create(self.content_type.clone())?;
+            file.write(&std::mem::take(&mut self.memory))?;
+            self.file = Some(file);
+        }
+        Ok(())
+    }
+
+    // Drop whatever was received, including any temporary file
+    fn discard(&mut self) {
+        self.memory.clear();
+        if let Some(file) = self.file.take() {
+            if let Ok(temp_file) = file.finish() {
+                let _ = temp_file.remove();
+            }
+        }
+    }
+
+    fn into_response(self, status_code: u16, status_text: String, headers: HashMap<String, String>) -> Result<HttpResponse> {
+        Ok(match self.file {
+            Some(file) => {
+                let temp_file = file.finish()?;
+                log::debug!("Content stored in temporary file: {:?}", temp_file.path);
+                HttpResponse::new_with_temp_file(status_code, status_text, headers, temp_file)
+            }
+            None => HttpResponse::new(status_code, status_text, headers, self.memory),
         })
     }
 }
 
-fn decode_chunked(input: &[u8]) -> Result<Vec<u8>> {
+// Trailer fields kept once the body has been read; the rest may only come in the header section, since framing,
+// routing, security policies and cookies can't be changed after the body (RFC 9110 §6.5.1)
+const MERGED_TRAILERS: [&str; 4] = ["server-timing", "content-digest", "repr-digest", "digest"];
+
+// Add the allowed trailer fields to the header section, after any values the headers gave them
+fn merge_trailers(headers: &mut HashMap<String, String>, trailers: Vec<(String, String)>) {
+    for (key, value) in trailers {
+        if !MERGED_TRAILERS.contains(&key.as_str()) {
+            log::debug!("Ignoring chunked trailer {}", key);
+            continue;
+        }
+        headers.entry(key)
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+}
+
+/// Decoded chunked body with any trailer fields sent after the last chunk
+pub struct ChunkedBody {
+    pub data: Vec<u8>,
+    pub trailers: Vec<(String, String)>,
+    /// False when the stream ended before the zero-size chunk
+    pub complete: bool,
+}
+
+// Locate the next line ending at or after `from`: (end of line content, start of next line)
+fn find_line_end(input: &[u8], from: usize) -> Option<(usize, usize)> {
+    let lf = from + memchr::memchr(b'\n', &input[from..])?;
+    let end = if lf > from && input[lf - 1] == b'\r' { lf - 1 } else { lf };
+    Some((end, lf + 1))
+}
+
+/// Decode a chunked transfer-coded body. `strict` fails on anything malformed or cut off; otherwise as much as
+/// could be read is kept.
+pub fn decode_chunked(input: &[u8], strict: bool) -> Result<ChunkedBody> {
     let mut i = 0;
     let mut out = Vec::new();
+    let mut trailers = Vec::new();
+    let mut complete = false;
     let mut chunk_count = 0;
     
-    while i < input.len() && chunk_count < 10000 { // Prevent infinite loops
+    while i < input.len() {
+        if chunk_count >= 10000 { // Prevent infinite loops
+            return Err(anyhow!("Too many chunks (possible infinite loop)"));
+        }
+        
         // Find the end of the chunk size line
-        let line_end = if let Some(crlf_pos) = twoway::find_bytes(&input[i..], b"\r\n") {
-            i + crlf_pos
-        } else if let Some(lf_pos) = memchr::memchr(b'\n', &input[i..]) {
-            i + lf_pos
-        } else {
+        let Some((line_end, next)) = find_line_end(input, i) else {
             break; // No more complete lines
         };
+        let line_str = std::str::from_utf8(&input[i..line_end]).unwrap_or("").trim();
+        i = next;
         
-        // Parse chunk size
-        let line = &input[i..line_end];
-        let line_str = std::str::from_utf8(line).unwrap_or("").trim();
-        
-        // Move past the line ending
-        i = line_end;
-        if i < input.len() && input[i] == b'\r' { i += 1; }
-        if i < input.len() && input[i] == b'\n' { i += 1; }
-        
-        if line_str.is_empty() { 
-            continue; 
+        if line_str.is_empty() {
+            if strict {
+                return Err(anyhow!("Empty chunk size line"));
+            }
+            continue;
         }
         
         // Parse hexadecimal chunk size, ignoring chunk extensions (after semicolon)
         let size_str = line_str.split(';').next().unwrap_or(line_str).trim();
         let chunk_size = match usize::from_str_radix(size_str, 16) {
             Ok(size) => size,
+            Err(_) if strict => return Err(anyhow!("Invalid chunk size: '{}'", size_str)),
             Err(_) => {
-                println!("Invalid chunk size: '{}'", size_str);
+                log::debug!("Invalid chunk size: '{}'", size_str);
                 break;
             }
         };
         
         if chunk_size == 0 {
-            // End of chunks
+            // Trailer section: header fields up to an empty line
+            while let Some((line_end, next)) = find_line_end(input, i) {
+                let line = String::from_utf8_lossy(&input[i..line_end]).trim().to_string();
+                i = next;
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    trailers.push((key.trim().to_lowercase(), value.trim().to_string()));
+                }
+            }
+            complete = true;
             break;
         }
         
         // Check if we have enough data for this chunk
-        if i + chunk_size > input.len() {
-            println!("Incomplete chunk: need {} bytes but only {} available", chunk_size, input.len() - i);
+        if chunk_size > input.len() - i {
+            log::debug!("Incomplete chunk: need {} bytes but only {} available", chunk_size, input.len() - i);
+            if strict {
+                return Err(anyhow!("Connection closed in the middle of a chunk"));
+            }
+            out.extend_from_slice(&input[i..]);
             break;
         }
         
@@ -569,17 +1111,129 @@ fn decode_chunked(input: &[u8]) -> Result<Vec<u8>> {
         out.extend_from_slice(&input[i..i + chunk_size]);
         i += chunk_size;
         
-        // Skip trailing CRLF after chunk data
-        if i < input.len() && input[i] == b'\r' { i += 1; }
-        if i < input.len() && input[i] == b'\n' { i += 1; }
+        // Chunk data must be followed by a line ending
+        if input[i..].starts_with(b"\r\n") {
+            i += 2;
+        } else if input[i..].starts_with(b"\n") {
+            i += 1;
+        } else if strict && i < input.len() {
+            return Err(anyhow!("Missing CRLF after chunk data"));
+        }
         
         chunk_count += 1;
     }
     
-    if chunk_count >= 10000 {
-        return Err(anyhow!("Too many chunks (possible infinite loop)"));
+    if strict && !complete {
+        return Err(anyhow!("Connection closed before the final chunk"));
     }
     
-    println!("Decoded {} chunks, total size: {}KB", chunk_count, out.len() / 1024);
-    Ok(out)
+    log::debug!("Decoded {} chunks, total size: {}KB", chunk_count, out.len() / 1024);
+    Ok(ChunkedBody { data: out, trailers, complete })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_chunked_with_trailers() {
+        let input = b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\nServer-Timing: db;dur=53\r\n\r\n";
+        let decoded = decode_chunked(input, true).unwrap();
+        assert_eq!(decoded.data, b"hello world");
+        assert!(decoded.complete);
+        assert_eq!(decoded.trailers, vec![("server-timing".to_string(), "db;dur=53".to_string())]);
+    }
+
+    #[test]
+    fn test_trailers_cannot_replace_headers() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}/", listener.local_addr().unwrap().port());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0u8; 4096]).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Security-Policy: script-src 'none'\r\n\
+                Server-Timing: app;dur=1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\ntext\r\n0\r\nContent-Type: text/html\r\n\
+                Content-Security-Policy: script-src *\r\nSet-Cookie: id=1\r\nServer-Timing: db;dur=53\r\n\r\n").unwrap();
+        });
+        let client = ManualHttpClient::new().unwrap();
+        let response = Executor::shared().block_on(client.fetch(&url)).unwrap().response;
+        server.join().unwrap();
+        assert_eq!(response.headers.get("Content-Type").map(String::as_str), Some("text/plain"));
+        assert_eq!(response.headers.get("content-security-policy").map(String::as_str), Some("script-src 'none'"));
+        assert!(!response.headers.contains_key("Set-Cookie"));
+        assert_eq!(response.headers.get("server-timing").map(String::as_str), Some("app;dur=1, db;dur=53"));
+    }
+
+    #[test]
+    fn test_decode_chunked_premature_close() {
+        let input = b"5\r\nhello\r\n6\r\n wo";
+        assert!(decode_chunked(input, true).is_err());
+
+        let decoded = decode_chunked(input, false).unwrap();
+        assert_eq!(decoded.data, b"hello wo");
+        assert!(!decoded.complete);
+
+        // A size too large to add to the position is cut off like any other
+        let input = b"ffffffffffffffff\r\nab";
+        assert!(decode_chunked(input, true).is_err());
+        assert_eq!(decode_chunked(input, false).unwrap().data, b"ab");
+    }
+
+    #[test]
+    fn test_fallback_hops_follow_the_redirect_loop() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        // The manual exchange gets garbage, reqwest's retry a redirect, and the next hop its page
+        let server = std::thread::spawn(move || {
+            let replies: [&[u8]; 3] = [
+                b"garbage\r\n\r\n",
+                b"HTTP/1.1 302 Found\r\nLocation: /final\r\nSet-Cookie: hop=1\r\nContent-Length: 0\r\n\r\n",
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 4\r\n\r\ndone",
+            ];
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0u8; 4096];
+                let read = stream.read(&mut buffer).unwrap();
+                requests.push(String::from_utf8_lossy(&buffer[..read]).to_string());
+                stream.write_all(reply).unwrap();
+            }
+            requests
+        });
+
+        let mut client = ManualHttpClient::new().unwrap();
+        client.set_fallback(Some(|_| true));
+        let cookies = Arc::new(Mutex::new(CookieManager::new()));
+        let context = FetchContext { cookies: Some(cookies.clone()), ..FetchContext::default() };
+        let result = Executor::shared().block_on(client.fetch_with_context(&format!("{}/start", base), &context)).unwrap();
+        assert_eq!(result.final_url, format!("{}/final", base));
+        assert_eq!(result.redirect_chain.len(), 1);
+        assert_eq!(result.response.body, b"done");
+        // The cookie the fallback hop set went to its host and was sent on the next hop
+        let requests = server.join().unwrap();
+        assert!(requests[2].starts_with("GET /final ") && requests[2].contains("Cookie: hop=1"));
+        assert!(cookies.lock().unwrap().get_cookie_header_for_request("127.0.0.1", "/", false).is_some());
+    }
+
+    #[test]
+    fn test_large_body_streams_to_temp_file() -> Result<()> {
+        let mut body = BodySink::new(Some("text/plain".to_string()));
+        body.push(b"small")?;
+        assert!(body.file.is_none());
+
+        let block = vec![b'x'; TEMP_FILE_THRESHOLD];
+        body.push(&block)?;
+        body.push(b"tail")?;
+        assert_eq!(body.len(), TEMP_FILE_THRESHOLD + 9);
+
+        let response = body.into_response(200, "OK".to_string(), HashMap::new())?;
+        let temp_file = response.get_temp_file().expect("spilled to disk").clone();
+        let raw = response.get_raw_body()?;
+        assert!(raw.starts_with(b"smallxx") && raw.ends_with(b"xtail"));
+        response.cleanup_temp_file()?;
+        assert!(!temp_file.path.exists());
+        Ok(())
+    }
 }
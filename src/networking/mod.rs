@@ -6,15 +6,71 @@ pub mod image_loader;
 pub mod performance;
 pub mod temp_storage;
 pub mod streaming_compression;
+pub mod redirect;
+pub mod charset;
+pub mod site_metadata;
+pub mod diagnostics;
+pub mod user_agent;
+pub mod tracking;
+pub mod http_auth;
+pub mod local_url;
+pub mod file_url;
+pub mod protocol_handlers;
+pub mod connectivity;
+pub mod throttling;
+pub mod http_cache;
+pub mod connection_pool;
+pub mod timing;
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use temp_storage::{TempStorageManager, TempFile};
+use temp_storage::TempFile;
 
-// Maximum content size to prevent memory exhaustion (50MB)
-const MAX_CONTENT_SIZE: usize = 50 * 1024 * 1024;
+/// Default for how much of a page body is read before the user is asked to continue (50MB)
+pub const DEFAULT_PAGE_SIZE_LIMIT: usize = 50 * 1024 * 1024;
+
+// Bodies above this size are previewed rather than fully rendered (25MB)
+const LARGE_CONTENT_SIZE: usize = 25 * 1024 * 1024;
+
+/// A body that was not read in full because it passed the page size limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OversizedBody {
+    pub limit: usize,
+    /// Bytes received before reading stopped
+    pub received: usize,
+    /// Declared Content-Length, when the server sent one
+    pub expected: Option<usize>,
+}
+
+/// The decoded body would exceed the page size limit the response was fetched under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentTooLarge {
+    pub limit: usize,
+}
+
+impl std::fmt::Display for ContentTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Decoded content exceeds the {}MB page size limit", self.limit / 1024 / 1024)
+    }
+}
+
+impl std::error::Error for ContentTooLarge {}
+
+/// An extension rule or tracking protection turned the request down; it's never fetched another way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestBlocked {
+    pub reason: String,
+}
+
+impl std::fmt::Display for RequestBlocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl std::error::Error for RequestBlocked {}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpRequest {
@@ -32,6 +88,12 @@ pub struct HttpResponse {
     // Content storage - either in memory or temporary file
     pub body: Vec<u8>,  // Keep for small content/backward compatibility
     pub temp_file: Option<TempFile>,  // Use for large content
+    // Set when the body is known to be incomplete (lenient mode or a premature close)
+    pub truncation: Option<String>,
+    // Set instead of reading on when the body passed `size_limit`
+    pub oversized: Option<OversizedBody>,
+    // Largest body, before and after decoding, this response may hold; None means unlimited
+    pub size_limit: Option<usize>,
     // Cache for decompressed content to prevent re-processing
     cached_string: Arc<Mutex<Option<String>>>,
 }
@@ -73,6 +135,9 @@ impl HttpResponse {
             headers,
             body,
             temp_file: None,
+            truncation: None,
+            oversized: None,
+            size_limit: Some(DEFAULT_PAGE_SIZE_LIMIT),
             cached_string: Arc::new(Mutex::new(None)),
         }
     }
@@ -84,6 +149,9 @@ impl HttpResponse {
             headers,
             body: Vec::new(),  // Empty body when using temp file
             temp_file: Some(temp_file),
+            truncation: None,
+            oversized: None,
+            size_limit: Some(DEFAULT_PAGE_SIZE_LIMIT),
             cached_string: Arc::new(Mutex::new(None)),
         }
     }
@@ -110,117 +178,22 @@ impl HttpResponse {
     
     fn decompress_body_internal(&self) -> Result<String> {
         // Get content from either memory or temporary file
+        let limit = self.size_limit;
         let mut data = if let Some(ref temp_file) = self.temp_file {
-            // Read content from temporary file
-            let temp_manager = TempStorageManager::new()?;
-            temp_manager.read_content(temp_file)?
+            temp_file.read()?
         } else {
-            // Use in-memory content
-            if self.body.len() > MAX_CONTENT_SIZE {
-                return Err(anyhow::anyhow!(
-                    "Content too large: {} bytes (max: {} bytes). Use streaming for large sites.",
-                    self.body.len(),
-                    MAX_CONTENT_SIZE
-                ));
-            }
             self.body.clone()
         };
+        if let Some(limit) = limit.filter(|limit| data.len() > *limit) {
+            return Err(ContentTooLarge { limit }.into());
+        }
         
         if let Some(encoding) = self.get_header("Content-Encoding").or_else(|| self.get_header("content-encoding")) {
-            let encodings: Vec<String> = encoding.split(',').map(|e| e.trim().to_lowercase()).collect();
-            
-            // Handle multiple encodings in reverse order (last applied first)
-            for enc in encodings.iter().rev() {
-                data = match enc.as_str() {
-                    "gzip" | "x-gzip" => {
-                        println!("Decompressing gzip content ({} bytes)", data.len());
-                        match decode_gzip(&data) {
-                            Ok(decompressed) => {
-                                if decompressed.len() > MAX_CONTENT_SIZE {
-                                    println!("Decompressed content too large ({} bytes), truncating", decompressed.len());
-                                    decompressed[..MAX_CONTENT_SIZE].to_vec()
-                                } else {
-                                    decompressed
-                                }
-                            },
-                            Err(_) => {
-                                println!("Gzip decompression failed, using original data");
-                                data
-                            }
-                        }
-                    },
-                    "deflate" => {
-                        println!("Decompressing deflate content ({} bytes)", data.len());
-                        match decode_deflate(&data) {
-                            Ok(decompressed) => {
-                                if decompressed.len() > MAX_CONTENT_SIZE {
-                                    println!("Decompressed content too large ({} bytes), truncating", decompressed.len());
-                                    decompressed[..MAX_CONTENT_SIZE].to_vec()
-                                } else {
-                                    decompressed
-                                }
-                            },
-                            Err(_) => {
-                                println!("Deflate decompression failed, using original data");
-                                data
-                            }
-                        }
-                    },
-                    "br" | "brotli" => {
-                        println!("Decompressing brotli content ({} bytes)", data.len());
-                        match decode_brotli(&data) {
-                            Ok(decompressed) => {
-                                if decompressed.len() > MAX_CONTENT_SIZE {
-                                    println!("Decompressed content too large ({} bytes), truncating", decompressed.len());
-                                    decompressed[..MAX_CONTENT_SIZE].to_vec()
-                                } else {
-                                    decompressed
-                                }
-                            },
-                            Err(_) => {
-                                println!("Brotli decompression failed, using original data");
-                                data
-                            }
-                        }
-                    },
-                    "zstd" => {
-                        println!("Attempting to decompress zstd content ({} bytes)", data.len());
-                        match decode_zstd(&data) {
-                            Ok(decompressed) => {
-                                if decompressed.len() > MAX_CONTENT_SIZE {
-                                    println!("Decompressed content too large ({} bytes), truncating", decompressed.len());
-                                    decompressed[..MAX_CONTENT_SIZE].to_vec()
-                                } else {
-                                    decompressed
-                                }
-                            },
-                            Err(_) => {
-                                println!("ZSTD decompression failed, using original data");
-                                data
-                            }
-                        }
-                    },
-                    "identity" | "" => {
-                        data // No compression
-                    },
-                    _ => {
-                        println!("Unknown content encoding: {}", enc);
-                        data // Unknown encoding, use as-is
-                    }
-                };
-            }
+            data = decode_content(data, encoding, limit)?;
         }
         
-        // Convert to string with better error handling
-        match String::from_utf8(data.clone()) {
-            Ok(text) => Ok(text),
-            Err(_) => {
-                // Try as latin-1 if UTF-8 fails
-                let text = data.iter().map(|&b| b as char).collect();
-                println!("Content converted from bytes to latin-1 (non-UTF8)");
-                Ok(text)
-            }
-        }
+        // Convert to string honoring the declared or sniffed charset
+        Ok(charset::decode_body(&data, self.content_type().map(|s| s.as_str())))
     }
     
     pub fn is_success(&self) -> bool {
@@ -245,14 +218,13 @@ impl HttpResponse {
     
     /// Check if this response represents very large content
     pub fn is_large_content(&self) -> bool {
-        self.body.len() > MAX_CONTENT_SIZE / 2 // Half of max size threshold
+        self.body.len() > LARGE_CONTENT_SIZE
     }
     
     /// Get raw body content as bytes (from memory or temp file)
     pub fn get_raw_body(&self) -> Result<Vec<u8>> {
         if let Some(ref temp_file) = self.temp_file {
-            let temp_manager = TempStorageManager::new()?;
-            temp_manager.read_content(temp_file)
+            temp_file.read()
         } else {
             Ok(self.body.clone())
         }
@@ -271,8 +243,7 @@ impl HttpResponse {
     /// Cleanup temporary file if present
     pub fn cleanup_temp_file(&self) -> Result<()> {
         if let Some(ref temp_file) = self.temp_file {
-            let temp_manager = TempStorageManager::new()?;
-            temp_manager.remove_file(temp_file)?;
+            temp_file.remove()?;
         }
         Ok(())
     }
@@ -287,7 +258,40 @@ impl HttpResponse {
     }
 }
 
-fn decode_gzip(input: &[u8]) -> Result<Vec<u8>> {
+/// Undo the Content-Encoding of a body, last applied coding first. Fails when a coding can't be decoded rather
+/// than passing the still-encoded bytes on, and when the result would pass `limit`.
+pub fn decode_content(mut data: Vec<u8>, content_encoding: &str, limit: Option<usize>) -> Result<Vec<u8>> {
+    let encodings: Vec<String> = content_encoding.split(',').map(|e| e.trim().to_lowercase()).collect();
+    for enc in encodings.iter().rev() {
+        let decoded = match enc.as_str() {
+            "gzip" | "x-gzip" => decode_gzip(&data, limit),
+            "deflate" => decode_deflate(&data, limit),
+            "br" | "brotli" => decode_brotli(&data, limit),
+            "zstd" => decode_zstd(&data, limit),
+            "identity" | "" => continue,
+            _ => {
+                log::warn!("Unknown content encoding: {}", enc);
+                continue; // Unknown encoding, use as-is
+            }
+        };
+        data = within_limit(decoded?, limit)?;
+    }
+    Ok(data)
+}
+
+// Read one byte past the limit so oversized content is detected by `within_limit`
+fn read_limit(limit: Option<usize>) -> u64 {
+    limit.map_or(u64::MAX, |limit| limit as u64 + 1)
+}
+
+fn within_limit(decoded: Vec<u8>, limit: Option<usize>) -> Result<Vec<u8>> {
+    match limit {
+        Some(limit) if decoded.len() > limit => Err(ContentTooLarge { limit }.into()),
+        _ => Ok(decoded),
+    }
+}
+
+fn decode_gzip(input: &[u8], limit: Option<usize>) -> Result<Vec<u8>> {
     use flate2::read::GzDecoder;
     use std::io::Read;
     
@@ -295,77 +299,79 @@ fn decode_gzip(input: &[u8]) -> Result<Vec<u8>> {
         return Ok(Vec::new());
     }
     
-    let mut decoder = GzDecoder::new(input);
+    let mut decoder = GzDecoder::new(input).take(read_limit(limit));
     let mut output = Vec::new();
     
-    match decoder.read_to_end(&mut output) {
-        Ok(_) => Ok(output),
-        Err(e) => {
-            println!("Gzip decompression failed: {}", e);
-            // Return original data if decompression fails
-            Ok(input.to_vec())
-        }
-    }
+    decoder.read_to_end(&mut output)
+        .map_err(|e| anyhow::anyhow!("Gzip decompression failed: {}", e))?;
+    Ok(output)
 }
 
-fn decode_deflate(input: &[u8]) -> Result<Vec<u8>> {
-    use flate2::read::DeflateDecoder;
+fn decode_deflate(input: &[u8], limit: Option<usize>) -> Result<Vec<u8>> {
+    use flate2::read::{DeflateDecoder, ZlibDecoder};
     use std::io::Read;
     
     if input.is_empty() {
         return Ok(Vec::new());
     }
     
-    let mut decoder = DeflateDecoder::new(input);
+    let mut decoder = DeflateDecoder::new(input).take(read_limit(limit));
     let mut output = Vec::new();
     
     match decoder.read_to_end(&mut output) {
         Ok(_) => Ok(output),
         Err(e) => {
-            println!("Deflate decompression failed: {}", e);
-            // Try raw deflate instead of zlib deflate
-            use flate2::read::DeflateDecoder;
-            let mut raw_decoder = DeflateDecoder::new(input);
-            let mut raw_output = Vec::new();
-            match raw_decoder.read_to_end(&mut raw_output) {
-                Ok(_) => Ok(raw_output),
-                Err(_) => Ok(input.to_vec()) // Return original on failure
-            }
+            log::debug!("Raw deflate decompression failed: {}", e);
+            // Servers disagree on whether deflate means zlib-wrapped or raw; try the zlib wrapping too
+            let mut zlib_decoder = ZlibDecoder::new(input).take(read_limit(limit));
+            let mut zlib_output = Vec::new();
+            zlib_decoder.read_to_end(&mut zlib_output)
+                .map_err(|_| anyhow::anyhow!("Deflate decompression failed: {}", e))?;
+            Ok(zlib_output)
         }
     }
 }
 
-fn decode_brotli(input: &[u8]) -> Result<Vec<u8>> {
+fn decode_brotli(input: &[u8], limit: Option<usize>) -> Result<Vec<u8>> {
     use std::io::Read;
     
     if input.is_empty() {
         return Ok(Vec::new());
     }
     
-    let mut decoder = brotli::Decompressor::new(input, 8192);
+    let mut decoder = brotli::Decompressor::new(input, 8192).take(read_limit(limit));
     let mut output = Vec::new();
     
     match decoder.read_to_end(&mut output) {
         Ok(_) => {
-            println!("Brotli decompressed: {} -> {} bytes", input.len(), output.len());
+            log::debug!("Brotli decompressed: {} -> {} bytes", input.len(), output.len());
             Ok(output)
         },
-        Err(e) => {
-            println!("Brotli decompression failed: {}", e);
-            Ok(input.to_vec()) // Return original data on failure
-        }
+        Err(e) => Err(anyhow::anyhow!("Brotli decompression failed: {}", e)),
     }
 }
 
-fn decode_zstd(input: &[u8]) -> Result<Vec<u8>> {
+fn decode_zstd(input: &[u8], limit: Option<usize>) -> Result<Vec<u8>> {
+    use std::io::Read;
+    
     if input.is_empty() {
         return Ok(Vec::new());
     }
     
-    // For now, return original data since zstd is less common
-    // In a full implementation, we'd add zstd dependency and decode here
-    println!("ZSTD compression detected but not supported yet");
-    Ok(input.to_vec())
+    let decoder = zstd::stream::read::Decoder::new(input)
+        .map_err(|e| anyhow::anyhow!("Failed to initialize zstd decoder: {}", e))?;
+    let mut output = Vec::new();
+    
+    match decoder.take(read_limit(limit)).read_to_end(&mut output) {
+        Ok(_) => {
+            log::debug!("Zstd decompressed: {} -> {} bytes", input.len(), output.len());
+            Ok(output)
+        },
+        Err(e) => {
+            log::debug!("Zstd decompression failed: {}", e);
+            Err(anyhow::anyhow!("Zstd decompression failed: {}", e))
+        }
+    }
 }
 
 fn decode_identity(input: &[u8]) -> Result<Vec<u8>> {
@@ -376,4 +382,23 @@ pub async fn fetch(url: &str) -> Result<HttpResponse> {
     // Simple wrapper retained for backward compatibility; cookie/header logic now lives in UI fetch_url
     let request = HttpRequest::new_get(url.to_string());
     http_client::send_request(request).await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_undecodable_content_is_an_error() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"<p>hello</p>").unwrap();
+        let gzip = encoder.finish().unwrap();
+        assert_eq!(decode_content(gzip.clone(), "gzip", None).unwrap(), b"<p>hello</p>");
+        assert_eq!(decode_content(gzip[..gzip.len() - 4].to_vec(), "identity, gzip", None).map_err(|e| e.to_string()).unwrap_err(),
+                   "Gzip decompression failed: unexpected end of file");
+        assert!(decode_content(b"<p>hello</p>".to_vec(), "br", None).is_err());
+        assert!(decode_content(b"<p>hello</p>".to_vec(), "deflate", None).is_err());
+        assert_eq!(decode_content(b"<p>hello</p>".to_vec(), "x-unknown", None).unwrap(), b"<p>hello</p>");
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use anyhow::Result;
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
 use crate::networking::{HttpResponse, manual_client::ManualHttpClient};
 
 // Response cache entry
@@ -71,7 +72,7 @@ impl CachingHttpClient {
         if self.enable_caching {
             if let Some(cached) = self.get_cached_response(&cache_key).await {
                 if !cached.is_expired() {
-                    println!("🚀 Cache hit for {}", url);
+                    log::debug!("Cache hit for {}", url);
                     return Ok(cached.response);
                 } else if !cached.is_stale() {
                     // Entry is expired but not stale, try conditional request
@@ -83,7 +84,7 @@ impl CachingHttpClient {
         }
 
         // Cache miss or error - fetch normally
-        println!("🌐 Cache miss for {} - fetching", url);
+        log::debug!("Cache miss for {} - fetching", url);
         let fetch_result = self.manual_client.fetch(url).await?;
         let response = fetch_result.response;
         
@@ -98,7 +99,7 @@ impl CachingHttpClient {
     async fn conditional_fetch(&self, url: &str, cached_entry: &CacheEntry) -> Result<HttpResponse> {
         // Try to use ETag or Last-Modified for conditional requests
         // For now, just refetch - in a full implementation we'd send If-None-Match/If-Modified-Since headers
-        println!("🔄 Conditional fetch for {}", url);
+        log::debug!("Conditional fetch for {}", url);
         let fetch_result = self.manual_client.fetch(url).await?;
         Ok(fetch_result.response)
     }
@@ -198,7 +199,7 @@ impl CachingHttpClient {
         let mut dns_cache = self.dns_cache.write().await;
         response_cache.clear();
         dns_cache.clear();
-        println!("🧹 Cleared all caches");
+        log::info!("Cleared all caches");
     }
 
     pub async fn get_cache_stats(&self) -> CacheStats {
@@ -230,19 +231,19 @@ impl CachingHttpClient {
 
     pub fn set_caching_enabled(&mut self, enabled: bool) {
         self.enable_caching = enabled;
-        println!("🔧 HTTP caching {}", if enabled { "enabled" } else { "disabled" });
+        log::info!("HTTP caching {}", if enabled { "enabled" } else { "disabled" });
     }
 
     pub async fn preload_resources(&self, urls: Vec<String>) {
-        println!("📦 Preloading {} resources in background", urls.len());
+        log::info!("Preloading {} resources in background", urls.len());
         
         let tasks: Vec<_> = urls.into_iter().map(|url| {
             let client = self.clone();
-            tokio::spawn(async move {
+            Executor::shared().spawn(TaskKind::Network, async move {
                 if let Err(e) = client.fetch(&url).await {
-                    eprintln!("Preload failed for {}: {}", url, e);
+                    log::warn!("Preload failed for {}: {}", url, e);
                 } else {
-                    println!("✅ Preloaded {}", url);
+                    log::debug!("Preloaded {}", url);
                 }
             })
         }).collect();
@@ -250,7 +251,7 @@ impl CachingHttpClient {
         // Wait for all preload tasks to complete (with timeout)
         let _ = tokio::time::timeout(Duration::from_secs(30), async {
             for task in tasks {
-                let _ = task.await;
+                let _ = task.join().await;
             }
         }).await;
     }
@@ -304,7 +305,7 @@ impl MemoryManager {
 
     pub async fn cleanup_if_needed(&self, client: &CachingHttpClient) {
         if self.should_trigger_cleanup() {
-            println!("🧹 Memory usage high, triggering cleanup");
+            log::info!("Memory usage high, triggering cleanup");
             client.clear_cache().await;
             
             // Additional cleanup could include:
@@ -324,7 +325,7 @@ pub struct MemoryStats {
 
 // Background task manager for performance optimizations
 pub struct BackgroundTaskManager {
-    active_tasks: Arc<RwLock<Vec<tokio::task::JoinHandle<()>>>>,
+    active_tasks: Arc<RwLock<Vec<TaskHandle<()>>>>,
 }
 
 impl BackgroundTaskManager {
@@ -335,7 +336,7 @@ impl BackgroundTaskManager {
     }
 
     pub async fn spawn_cache_cleanup(&self, client: CachingHttpClient) {
-        let task = tokio::spawn(async move {
+        let task = Executor::shared().spawn(TaskKind::Network, async move {
             let mut interval = tokio::time::interval(Duration::from_secs(300)); // Every 5 minutes
             loop {
                 interval.tick().await;
@@ -343,10 +344,10 @@ impl BackgroundTaskManager {
                 // Clean up expired cache entries
                 let stats = client.get_cache_stats().await;
                 if stats.expired_responses > 10 || stats.expired_dns > 10 {
-                    println!("🧹 Background cleanup: removing expired cache entries");
+                    log::debug!("Background cleanup: removing expired cache entries");
                     // In a real implementation, we'd remove only expired entries
                     // For now, just report the stats
-                    println!("Cache stats: {:?}", stats);
+                    log::debug!("Cache stats: {:?}", stats);
                 }
             }
         });
@@ -356,7 +357,7 @@ impl BackgroundTaskManager {
     }
 
     pub async fn spawn_memory_monitor(&self, memory_manager: MemoryManager, client: CachingHttpClient) {
-        let task = tokio::spawn(async move {
+        let task = Executor::shared().spawn(TaskKind::Network, async move {
             let mut interval = tokio::time::interval(Duration::from_secs(60)); // Every minute
             loop {
                 interval.tick().await;
@@ -371,8 +372,8 @@ impl BackgroundTaskManager {
     pub async fn shutdown(&self) {
         let mut tasks = self.active_tasks.write().await;
         for task in tasks.drain(..) {
-            task.abort();
+            task.cancel();
         }
-        println!("🛑 Background tasks shut down");
+        log::info!("Background tasks shut down");
     }
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+// Links to schemes the browser doesn't load itself (mailto:, irc:, magnet:...), sent to a web app or the system
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+
+/// Schemes a web app may handle, as in `navigator.registerProtocolHandler`; others need a `web+` prefix
+pub const SAFELISTED_SCHEMES: [&str; 24] = [
+    "bitcoin", "ftp", "ftps", "geo", "im", "irc", "ircs", "magnet", "mailto", "matrix", "mms", "news",
+    "nntp", "openpgp4fpr", "sftp", "sip", "sms", "smsto", "ssh", "tel", "urn", "webcal", "wtai", "xmpp",
+];
+
+/// Schemes the system's default application may be handed, once the user confirms; other schemes can only go to
+/// a mapped web page
+pub const SYSTEM_SCHEMES: [&str; 13] = [
+    "geo", "irc", "ircs", "magnet", "mailto", "news", "nntp", "sip", "sms", "smsto", "tel", "webcal", "xmpp",
+];
+
+// Schemes the browser loads in the tab
+const BROWSER_SCHEMES: [&str; 8] = ["http", "https", "about", "neon", "data", "blob", "file", "javascript"];
+
+/// Where a link to another scheme goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Handoff {
+    /// The web page mapped to the scheme, with the link filled in
+    Navigate(String),
+    /// No page is mapped, so the system's default application gets the link
+    System(String),
+    /// No page is mapped and the scheme isn't one the system may be handed
+    Refused(String),
+}
+
+pub fn is_valid_scheme(scheme: &str) -> bool {
+    let scheme = scheme.to_ascii_lowercase();
+    SAFELISTED_SCHEMES.contains(&scheme.as_str())
+        || scheme.strip_prefix("web+").is_some_and(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase()))
+}
+
+/// A handler template is an http(s) address with `%s` where the link goes
+pub fn validate_template(template: &str) -> Result<()> {
+    if !template.contains("%s") {
+        return Err(anyhow!("The address needs %s where the link goes"));
+    }
+    let parsed = url::Url::parse(&template.replace("%s", "x")).map_err(|e| anyhow!("Not a valid address: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(anyhow!("Links can only be handed to http or https pages"));
+    }
+    Ok(())
+}
+
+/// The scheme of `url` when it's one the browser doesn't load itself
+pub fn external_scheme(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once(':')?;
+    let scheme = scheme.to_ascii_lowercase();
+    let well_formed = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // "localhost:8080" and the like read as a scheme but are addresses
+    if !well_formed || BROWSER_SCHEMES.contains(&scheme.as_str()) || rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(scheme)
+}
+
+/// Where `url` goes, or None when the browser loads it itself. `handlers` maps schemes to templates.
+pub fn handoff(url: &str, handlers: &BTreeMap<String, String>) -> Option<Handoff> {
+    let scheme = external_scheme(url)?;
+    Some(match handlers.get(&scheme) {
+        Some(template) => Handoff::Navigate(template.replace("%s", &urlencoding::encode(url))),
+        None if SYSTEM_SCHEMES.contains(&scheme.as_str()) => Handoff::System(url.to_string()),
+        None => Handoff::Refused(scheme),
+    })
+}
+
+/// Hand `url` to the system's default application for its scheme. The link is passed as one argument and never
+/// through a shell, and only for the schemes in `SYSTEM_SCHEMES`.
+pub fn open_with_system(url: &str) -> Result<()> {
+    if !external_scheme(url).is_some_and(|scheme| SYSTEM_SCHEMES.contains(&scheme.as_str())) {
+        return Err(anyhow!("Links of this kind aren't handed to other applications"));
+    }
+    // Kept to a single token, so nothing on the way can split it into more arguments
+    if url.chars().any(|c| c.is_whitespace() || c.is_control() || c == '"') {
+        return Err(anyhow!("The link contains characters that can't be handed to another application"));
+    }
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("open");
+        command.arg(url);
+        command
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+    command.spawn().map(|_| ()).map_err(|e| anyhow!("No application could open the link: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handoff() {
+        let handlers = BTreeMap::from([("mailto".to_string(), "https://mail.example.com/compose?to=%s".to_string())]);
+        assert_eq!(
+            handoff("mailto:ada@example.com", &handlers),
+            Some(Handoff::Navigate("https://mail.example.com/compose?to=mailto%3Aada%40example.com".to_string()))
+        );
+        assert_eq!(handoff("magnet:?xt=urn:btih:abc", &handlers), Some(Handoff::System("magnet:?xt=urn:btih:abc".to_string())));
+        assert_eq!(handoff("https://example.com", &handlers), None);
+        assert_eq!(handoff("localhost:8080/app", &handlers), None);
+        assert_eq!(handoff("neon://settings", &handlers), None);
+        assert_eq!(handoff("ms-msdt:/id PCWDiagnostic", &handlers), Some(Handoff::Refused("ms-msdt".to_string())));
+    }
+
+    #[test]
+    fn test_open_with_system_refuses_unsafe_links() {
+        assert!(open_with_system("ms-msdt:/id PCWDiagnostic").is_err());
+        assert!(open_with_system("mailto:a@example.com\" & calc").is_err());
+        assert!(open_with_system("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_scheme_and_template_validation() {
+        assert!(is_valid_scheme("IRC") && is_valid_scheme("web+notes"));
+        assert!(!is_valid_scheme("web+") && !is_valid_scheme("chrome"));
+        assert!(validate_template("https://irc.example.com/join?url=%s").is_ok());
+        assert!(validate_template("https://irc.example.com/join").is_err());
+        assert!(validate_template("javascript:alert(%s)").is_err());
+    }
+}
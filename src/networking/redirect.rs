@@ -0,0 +1,208 @@
+// Redirect following policy shared by every fetch path
+use anyhow::{Result, anyhow};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+// Single redirect cap for the whole browser (manual client, reqwest fallback and UI)
+pub const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct RedirectHop {
+    pub from: String,
+    pub to: String,
+    pub status_code: u16,
+    pub cross_origin: bool,
+    // Method `to` was requested with, which is GET unless a 307/308 kept the original
+    pub method: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferrerPolicy {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    SameOrigin,
+    Origin,
+    StrictOrigin,
+    OriginWhenCrossOrigin,
+    #[default]
+    StrictOriginWhenCrossOrigin,
+    UnsafeUrl,
+}
+
+impl ReferrerPolicy {
+    pub const ALL: [ReferrerPolicy; 8] = [
+        ReferrerPolicy::StrictOriginWhenCrossOrigin,
+        ReferrerPolicy::NoReferrerWhenDowngrade,
+        ReferrerPolicy::OriginWhenCrossOrigin,
+        ReferrerPolicy::SameOrigin,
+        ReferrerPolicy::StrictOrigin,
+        ReferrerPolicy::Origin,
+        ReferrerPolicy::NoReferrer,
+        ReferrerPolicy::UnsafeUrl,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReferrerPolicy::NoReferrer => "Never send",
+            ReferrerPolicy::NoReferrerWhenDowngrade => "Full address, except from HTTPS to HTTP",
+            ReferrerPolicy::SameOrigin => "Full address within a site, nothing to others",
+            ReferrerPolicy::Origin => "Only the site, everywhere",
+            ReferrerPolicy::StrictOrigin => "Only the site, except from HTTPS to HTTP",
+            ReferrerPolicy::OriginWhenCrossOrigin => "Full address within a site, only the site to others",
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => "Full address within a site, only the site to others over HTTPS",
+            ReferrerPolicy::UnsafeUrl => "Full address, everywhere",
+        }
+    }
+
+    /// Parse a single policy token, returning None for unknown values
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "no-referrer" => Some(ReferrerPolicy::NoReferrer),
+            "no-referrer-when-downgrade" => Some(ReferrerPolicy::NoReferrerWhenDowngrade),
+            "same-origin" => Some(ReferrerPolicy::SameOrigin),
+            "origin" => Some(ReferrerPolicy::Origin),
+            "strict-origin" => Some(ReferrerPolicy::StrictOrigin),
+            "origin-when-cross-origin" => Some(ReferrerPolicy::OriginWhenCrossOrigin),
+            "strict-origin-when-cross-origin" => Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+            "unsafe-url" => Some(ReferrerPolicy::UnsafeUrl),
+            _ => None,
+        }
+    }
+
+    /// Parse a Referrer-Policy header; the last recognised token wins
+    pub fn from_header(value: &str) -> Option<Self> {
+        value.split(',').filter_map(Self::from_token).next_back()
+    }
+
+    /// Parse the content of a `<meta name="referrer">`, which also accepts the legacy keywords
+    pub fn from_meta(content: &str) -> Option<Self> {
+        match content.trim().to_ascii_lowercase().as_str() {
+            "never" => Some(ReferrerPolicy::NoReferrer),
+            "default" => Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+            "always" => Some(ReferrerPolicy::UnsafeUrl),
+            "origin-when-crossorigin" => Some(ReferrerPolicy::OriginWhenCrossOrigin),
+            content => Self::from_token(content),
+        }
+    }
+
+    /// Compute the Referer header value for a request from `referrer` to `target`
+    pub fn referrer_for(&self, referrer: &str, target: &str) -> Option<String> {
+        let referrer = Url::parse(referrer).ok()?;
+        let target = Url::parse(target).ok()?;
+
+        // Only http(s) documents ever leak a referrer
+        if referrer.scheme() != "http" && referrer.scheme() != "https" {
+            return None;
+        }
+
+        let mut full = referrer.clone();
+        full.set_fragment(None);
+        let _ = full.set_username("");
+        let _ = full.set_password(None);
+        let origin_only = format!("{}/", referrer.origin().ascii_serialization());
+
+        let same_origin = referrer.origin() == target.origin();
+        let downgrade = referrer.scheme() == "https" && target.scheme() != "https";
+
+        match self {
+            ReferrerPolicy::NoReferrer => None,
+            ReferrerPolicy::UnsafeUrl => Some(full.to_string()),
+            ReferrerPolicy::Origin => Some(origin_only),
+            ReferrerPolicy::SameOrigin => same_origin.then(|| full.to_string()),
+            ReferrerPolicy::NoReferrerWhenDowngrade => (!downgrade).then(|| full.to_string()),
+            ReferrerPolicy::StrictOrigin => (!downgrade).then_some(origin_only),
+            ReferrerPolicy::OriginWhenCrossOrigin => {
+                if same_origin { Some(full.to_string()) } else { Some(origin_only) }
+            }
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => {
+                if same_origin {
+                    Some(full.to_string())
+                } else if downgrade {
+                    None
+                } else {
+                    Some(origin_only)
+                }
+            }
+        }
+    }
+}
+
+/// Resolve a Location header against the URL that returned it
+pub fn resolve_location(base: &str, location: &str) -> Result<String> {
+    let base = Url::parse(base)
+        .map_err(|e| anyhow!("Cannot parse base URL for redirect: {}", e))?;
+    base.join(location.trim())
+        .map(|u| u.to_string())
+        .map_err(|e| anyhow!("Cannot resolve redirect URL '{}': {}", location, e))
+}
+
+/// Whether following a redirect re-sends the request's method and body. 307 and 308 always do;
+/// 303 turns everything but HEAD into GET, and 301/302 turn POST into GET as every browser does.
+pub fn preserves_method(status_code: u16, method: &str) -> bool {
+    match status_code {
+        307 | 308 => true,
+        303 => method.eq_ignore_ascii_case("GET") || method.eq_ignore_ascii_case("HEAD"),
+        301 | 302 => !method.eq_ignore_ascii_case("POST"),
+        _ => false,
+    }
+}
+
+/// True when both URLs share scheme, host and port
+pub fn is_same_origin(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_strips_cross_origin_to_origin() {
+        let policy = ReferrerPolicy::default();
+        assert_eq!(
+            policy.referrer_for("https://a.com/page?q=1#frag", "https://a.com/next"),
+            Some("https://a.com/page?q=1".to_string())
+        );
+        assert_eq!(
+            policy.referrer_for("https://a.com/page", "https://b.com/"),
+            Some("https://a.com/".to_string())
+        );
+        assert_eq!(policy.referrer_for("https://a.com/page", "http://b.com/"), None);
+    }
+
+    #[test]
+    fn test_header_last_valid_token_wins() {
+        assert_eq!(
+            ReferrerPolicy::from_header("no-referrer, bogus, origin"),
+            Some(ReferrerPolicy::Origin)
+        );
+        assert_eq!(ReferrerPolicy::from_header("bogus"), None);
+        assert_eq!(ReferrerPolicy::from_meta(" Never "), Some(ReferrerPolicy::NoReferrer));
+        assert_eq!(ReferrerPolicy::from_meta("same-origin"), Some(ReferrerPolicy::SameOrigin));
+    }
+
+    #[test]
+    fn test_resolve_relative_location() {
+        assert_eq!(
+            resolve_location("https://a.com/dir/page", "../other").unwrap(),
+            "https://a.com/other"
+        );
+        assert!(is_same_origin("https://a.com/x", "https://a.com/y"));
+        assert!(!is_same_origin("https://a.com/x", "http://a.com/x"));
+    }
+
+    #[test]
+    fn test_redirect_method_semantics() {
+        assert!(preserves_method(307, "POST"));
+        assert!(preserves_method(308, "PUT"));
+        assert!(!preserves_method(303, "POST"));
+        assert!(!preserves_method(303, "PUT"));
+        assert!(preserves_method(303, "HEAD"));
+        assert!(!preserves_method(302, "POST"));
+        assert!(!preserves_method(301, "POST"));
+        assert!(preserves_method(301, "DELETE"));
+    }
+}
@@ -0,0 +1,315 @@
+// Per-origin favicon and site name, shared by every part of the UI that labels a site
+use crate::engine::dom::DOMNode;
+use crate::engine::executor::{Executor, TaskKind};
+use crate::engine::html_parser;
+use crate::networking::image_loader::ImageCache;
+use crate::networking::manual_client::ManualHttpClient;
+use crate::storage::HistoryDatabase;
+use egui::{ColorImage, Context, TextureHandle};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// What is known about a site, keyed by origin
+#[derive(Clone, Default)]
+pub struct SiteInfo {
+    pub origin: String,
+    pub name: String,
+    pub favicon_url: Option<String>,
+    pub favicon: Option<Arc<ColorImage>>,
+}
+
+#[derive(Default)]
+struct SiteRecord {
+    info: SiteInfo,
+    // Set once a fetch has been started, so each origin is only fetched once per session
+    fetch_started: bool,
+}
+
+/// Resolves favicons and site names once per origin instead of each view fetching its own
+pub struct SiteMetadataService {
+    client: ManualHttpClient,
+    image_cache: ImageCache,
+    sites: Arc<Mutex<HashMap<String, SiteRecord>>>,
+    textures: Mutex<HashMap<String, TextureHandle>>,
+}
+
+static SHARED_METADATA: OnceLock<Arc<SiteMetadataService>> = OnceLock::new();
+
+impl SiteMetadataService {
+    pub fn new(client: ManualHttpClient, image_cache: ImageCache) -> Self {
+        Self {
+            client,
+            image_cache,
+            sites: Arc::new(Mutex::new(HashMap::new())),
+            textures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Make this the process-wide service; the first installed instance wins
+    pub fn install(self) -> Arc<SiteMetadataService> {
+        SHARED_METADATA.get_or_init(|| Arc::new(self)).clone()
+    }
+
+    /// Service installed by the browser, if it has started
+    pub fn shared() -> Option<Arc<SiteMetadataService>> {
+        SHARED_METADATA.get().cloned()
+    }
+
+    /// Best-known metadata for the site hosting `url`; unknown sites are fetched in the background
+    pub fn lookup(&self, url: &str) -> Option<SiteInfo> {
+        let origin = origin_of(url)?;
+        let mut sites = self.sites.lock().ok()?;
+        let record = sites.entry(origin.clone()).or_insert_with(|| SiteRecord {
+            info: SiteInfo {
+                name: history_name(&origin).unwrap_or_else(|| display_host(&origin)),
+                origin: origin.clone(),
+                ..SiteInfo::default()
+            },
+            fetch_started: false,
+        });
+
+        if !record.fetch_started {
+            record.fetch_started = true;
+            let task = self.task();
+            let origin = origin.clone();
+            Executor::shared().spawn(TaskKind::Network, async move { task.fetch_origin(&origin).await });
+        }
+
+        Some(record.info.clone())
+    }
+
+    /// Learn from a page the browser has just loaded, so its site needs no extra fetch
+    pub fn record_page(&self, url: &str, dom: &DOMNode) {
+        let Some(origin) = origin_of(url) else {
+            return;
+        };
+        let page = PageMetadata::extract(dom, url);
+
+        if let Ok(mut sites) = self.sites.lock() {
+            let record = sites.entry(origin.clone()).or_default();
+            record.info.origin = origin.clone();
+            if let Some(name) = &page.site_name {
+                record.info.name = name.clone();
+            } else if record.info.name.is_empty() {
+                record.info.name = display_host(&origin);
+            }
+            // The page already told us where its icon is; a background fetch would only repeat that work
+            if record.info.favicon.is_some() {
+                return;
+            }
+            record.fetch_started = true;
+        }
+
+        let task = self.task();
+        Executor::shared().spawn(TaskKind::Network, async move { task.load_favicon(&origin, page.icon_candidates).await });
+    }
+
+    /// Favicon texture for the site hosting `url`, once it has been loaded
+    pub fn favicon_texture(&self, ctx: &Context, url: &str) -> Option<TextureHandle> {
+        let info = self.lookup(url)?;
+        let image = info.favicon?;
+
+        let mut textures = self.textures.lock().ok()?;
+        let texture = textures.entry(info.origin.clone()).or_insert_with(|| {
+            ctx.load_texture(format!("favicon_{}", info.origin), image.as_ref().clone(), Default::default())
+        });
+        Some(texture.clone())
+    }
+
+    fn task(&self) -> MetadataTask {
+        MetadataTask {
+            client: self.client.clone(),
+            image_cache: self.image_cache.clone(),
+            sites: self.sites.clone(),
+        }
+    }
+}
+
+// The parts of the service a background fetch needs
+struct MetadataTask {
+    client: ManualHttpClient,
+    image_cache: ImageCache,
+    sites: Arc<Mutex<HashMap<String, SiteRecord>>>,
+}
+
+impl MetadataTask {
+    async fn fetch_origin(&self, origin: &str) {
+        let root = format!("{}/", origin);
+        let mut candidates = Vec::new();
+
+        match self.client.fetch(&root).await {
+            Ok(fetched) if fetched.response.is_success() => {
+                if let Ok(html) = fetched.response.body_as_string() {
+                    let page_url = root.clone();
+                    let parsed = Executor::shared()
+                        .spawn_blocking(TaskKind::Parse, move || PageMetadata::extract(&html_parser::parse(&html), &page_url))
+                        .join()
+                        .await;
+                    let Ok(page) = parsed else {
+                        return;
+                    };
+                    if let Some(name) = page.site_name {
+                        self.update(origin, |info| info.name = name);
+                    }
+                    candidates = page.icon_candidates;
+                }
+            }
+            Ok(fetched) => log::debug!("Site metadata for {}: HTTP {}", origin, fetched.response.status_code),
+            Err(e) => log::debug!("Site metadata for {} failed: {}", origin, e),
+        }
+
+        if candidates.is_empty() {
+            candidates.push(format!("{}/favicon.ico", origin));
+        }
+        self.load_favicon(origin, candidates).await;
+    }
+
+    async fn load_favicon(&self, origin: &str, candidates: Vec<String>) {
+        for candidate in candidates {
+            if let Ok(image) = self.image_cache.load_image(&candidate, &self.client).await {
+                self.update(origin, |info| {
+                    info.favicon_url = Some(candidate.clone());
+                    info.favicon = Some(image);
+                });
+                return;
+            }
+        }
+    }
+
+    fn update(&self, origin: &str, apply: impl FnOnce(&mut SiteInfo)) {
+        if let Ok(mut sites) = self.sites.lock() {
+            apply(&mut sites.entry(origin.to_string()).or_default().info);
+        }
+    }
+}
+
+/// Site name and icon locations declared by a document
+#[derive(Debug, Default)]
+pub struct PageMetadata {
+    pub site_name: Option<String>,
+    /// Icon URLs in order of preference, ending with the conventional /favicon.ico
+    pub icon_candidates: Vec<String>,
+}
+
+impl PageMetadata {
+    pub fn extract(dom: &DOMNode, page_url: &str) -> Self {
+        let base = url::Url::parse(page_url).ok();
+        let mut metadata = PageMetadata::default();
+        let mut application_name = None;
+        collect_metadata(dom, base.as_ref(), &mut metadata, &mut application_name);
+
+        // og:site_name describes the site better than application-name, which is often an app title
+        if metadata.site_name.is_none() {
+            metadata.site_name = application_name;
+        }
+
+        if let Some(fallback) = base.and_then(|b| b.join("/favicon.ico").ok()) {
+            let fallback = fallback.to_string();
+            if !metadata.icon_candidates.contains(&fallback) {
+                metadata.icon_candidates.push(fallback);
+            }
+        }
+        metadata
+    }
+}
+
+fn collect_metadata(
+    node: &DOMNode,
+    base: Option<&url::Url>,
+    metadata: &mut PageMetadata,
+    application_name: &mut Option<String>,
+) {
+    let DOMNode::Element { tag_name, attributes, children } = node else {
+        return;
+    };
+
+    match tag_name.as_str() {
+        "link" => {
+            let is_icon = attributes.get("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("icon")));
+            if let (true, Some(href)) = (is_icon, attributes.get("href")) {
+                let resolved = match base {
+                    Some(base) => base.join(href.trim()).ok(),
+                    None => url::Url::parse(href.trim()).ok(),
+                };
+                if let Some(url) = resolved.filter(|u| matches!(u.scheme(), "http" | "https")) {
+                    metadata.icon_candidates.push(url.to_string());
+                }
+            }
+        }
+        "meta" => {
+            let content = attributes.get("content").map(|c| c.trim()).filter(|c| !c.is_empty());
+            let key = attributes.get("property").or_else(|| attributes.get("name"));
+            match (key.map(|k| k.to_lowercase()), content) {
+                (Some(key), Some(content)) if key == "og:site_name" => {
+                    metadata.site_name.get_or_insert_with(|| content.to_string());
+                }
+                (Some(key), Some(content)) if key == "application-name" => {
+                    application_name.get_or_insert_with(|| content.to_string());
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+
+    for child in children {
+        collect_metadata(child, base, metadata, application_name);
+    }
+}
+
+/// scheme://host[:port] for http(s) URLs
+pub fn origin_of(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(parsed.origin().ascii_serialization())
+}
+
+// Host without scheme or a leading www., used until a real site name is known
+fn display_host(origin: &str) -> String {
+    let host = url::Url::parse(origin).ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| origin.to_string());
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+// Title of the site's front page, if it has been visited
+fn history_name(origin: &str) -> Option<String> {
+    let history = HistoryDatabase::shared()?;
+    [format!("{}/", origin), origin.to_string()]
+        .iter()
+        .find_map(|url| history.latest_title(url).ok().flatten())
+        .filter(|title| !title.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_of() {
+        assert_eq!(origin_of("https://www.example.com/a/b?q=1").as_deref(), Some("https://www.example.com"));
+        assert_eq!(origin_of("http://localhost:8080/").as_deref(), Some("http://localhost:8080"));
+        assert_eq!(origin_of("neon://history"), None);
+        assert_eq!(display_host("https://www.example.com"), "example.com");
+    }
+
+    #[test]
+    fn test_extract_page_metadata() {
+        let dom = html_parser::parse(r#"<html><head>
+            <meta name="application-name" content="Example App">
+            <meta property="og:site_name" content="Example">
+            <link rel="shortcut icon" href="/static/icon.png">
+            <link rel="stylesheet" href="/site.css">
+        </head><body></body></html>"#);
+
+        let metadata = PageMetadata::extract(&dom, "https://example.com/blog/post");
+        assert_eq!(metadata.site_name.as_deref(), Some("Example"));
+        assert_eq!(metadata.icon_candidates, vec![
+            "https://example.com/static/icon.png".to_string(),
+            "https://example.com/favicon.ico".to_string(),
+        ]);
+    }
+}
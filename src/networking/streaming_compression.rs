@@ -1,8 +1,8 @@
-use std::io::{Read, BufReader};
+use std::io::{Read, Write};
 use std::collections::VecDeque;
 use anyhow::{Result, anyhow};
-use flate2::read::{GzDecoder, DeflateDecoder};
-use brotli::Decompressor;
+use flate2::read::DeflateDecoder;
+use super::{ContentTooLarge, DEFAULT_PAGE_SIZE_LIMIT};
 
 /// Streaming decompression for handling large compressed content efficiently
 pub struct StreamingDecompressor {
@@ -38,6 +38,7 @@ pub struct StreamingDecompressConfig {
     pub chunk_size: usize,
     pub max_output_buffer: usize,
     pub max_compression_ratio: f64, // Safety limit to prevent decompression bombs
+    pub max_output_size: Option<usize>, // Decoded bytes allowed in total, as for buffered bodies
 }
 
 impl Default for StreamingDecompressConfig {
@@ -46,6 +47,7 @@ impl Default for StreamingDecompressConfig {
             chunk_size: 8192,          // 8KB input chunks
             max_output_buffer: 1024 * 1024, // 1MB output buffer
             max_compression_ratio: 1000.0,   // Max 1000:1 compression ratio
+            max_output_size: Some(DEFAULT_PAGE_SIZE_LIMIT),
         }
     }
 }
@@ -77,7 +79,7 @@ impl StreamingDecompressor {
             CompressionType::Gzip => Box::new(GzipDecoder::new()?),
             CompressionType::Deflate => Box::new(DeflateStreamDecoder::new()?),
             CompressionType::Brotli => Box::new(BrotliStreamDecoder::new()?),
-            CompressionType::Zstd => Box::new(ZstdStreamDecoder::new()?),
+            CompressionType::Zstd => Box::new(ZstdStreamDecoder::new(config.max_output_size)?),
         };
 
         Ok(Self {
@@ -149,6 +151,9 @@ impl StreamingDecompressor {
         let is_finished = self.decoder.decompress_chunk(chunk, &mut output)?;
         
         self.total_output += output.len();
+        if let Some(limit) = config.max_output_size.filter(|limit| self.total_output > *limit) {
+            return Err(ContentTooLarge { limit }.into());
+        }
         
         // Add to output buffer
         self.output_buffer.extend(output.iter());
@@ -255,18 +260,16 @@ impl CompressionDecoder for NoCompression {
     }
 }
 
-// Implementation for Gzip
+// Gzip decoded as it's written to, so each chunk only has to be decoded once
 struct GzipDecoder {
-    decoder: Option<GzDecoder<BufReader<std::io::Cursor<Vec<u8>>>>>,
-    buffer: Vec<u8>,
+    decoder: flate2::write::GzDecoder<Vec<u8>>,
     finished: bool,
 }
 
 impl GzipDecoder {
     fn new() -> Result<Self> {
         Ok(Self {
-            decoder: None,
-            buffer: Vec::new(),
+            decoder: flate2::write::GzDecoder::new(Vec::new()),
             finished: false,
         })
     }
@@ -274,35 +277,17 @@ impl GzipDecoder {
 
 impl CompressionDecoder for GzipDecoder {
     fn decompress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<bool> {
-        self.buffer.extend_from_slice(input);
-        
-        if self.decoder.is_none() {
-            let cursor = std::io::Cursor::new(self.buffer.clone());
-            let buf_reader = BufReader::new(cursor);
-            self.decoder = Some(GzDecoder::new(buf_reader));
-        }
-        
-        if let Some(ref mut decoder) = self.decoder {
-            let mut temp_output = Vec::new();
-            match decoder.read_to_end(&mut temp_output) {
-                Ok(_) => {
-                    output.extend_from_slice(&temp_output);
-                    self.finished = true;
-                    Ok(true)
-                }
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    // Need more data
-                    Ok(false)
-                }
-                Err(e) => Err(anyhow!("Gzip decompression error: {}", e))
-            }
-        } else {
-            Ok(false)
-        }
+        self.decoder.write_all(input)
+            .map_err(|e| anyhow!("Gzip decompression error: {}", e))?;
+        output.append(self.decoder.get_mut());
+        Ok(false)
     }
     
     fn finish(&mut self, output: &mut Vec<u8>) -> Result<()> {
-        // Gzip decoder should have finished in decompress_chunk
+        self.decoder.try_finish()
+            .map_err(|e| anyhow!("Gzip decompression error: {}", e))?;
+        output.append(self.decoder.get_mut());
+        self.finished = true;
         Ok(())
     }
     
@@ -357,15 +342,14 @@ impl CompressionDecoder for DeflateStreamDecoder {
 }
 
 struct BrotliStreamDecoder {
-    decompressor: Decompressor<std::io::Cursor<Vec<u8>>>,
+    decoder: brotli::DecompressorWriter<Vec<u8>>,
     finished: bool,
 }
 
 impl BrotliStreamDecoder {
     fn new() -> Result<Self> {
-        let cursor = std::io::Cursor::new(Vec::new());
         Ok(Self {
-            decompressor: Decompressor::new(cursor, 8192),
+            decoder: brotli::DecompressorWriter::new(Vec::new(), 8192),
             finished: false,
         })
     }
@@ -373,13 +357,16 @@ impl BrotliStreamDecoder {
 
 impl CompressionDecoder for BrotliStreamDecoder {
     fn decompress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<bool> {
-        // Simplified brotli decompression
-        // In a real implementation, you'd use the brotli crate properly
-        output.extend_from_slice(input); // Fallback: no decompression
+        self.decoder.write_all(input)
+            .map_err(|e| anyhow!("Brotli decompression error: {}", e))?;
+        output.append(self.decoder.get_mut());
         Ok(false)
     }
     
-    fn finish(&mut self, _output: &mut Vec<u8>) -> Result<()> {
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<()> {
+        self.decoder.close()
+            .map_err(|e| anyhow!("Brotli decompression error: {}", e))?;
+        output.append(self.decoder.get_mut());
         self.finished = true;
         Ok(())
     }
@@ -389,31 +376,59 @@ impl CompressionDecoder for BrotliStreamDecoder {
     }
 }
 
+// A body may be several zstd frames back to back, so the stream is only complete at the end of a frame
 struct ZstdStreamDecoder {
-    finished: bool,
+    decoder: zstd::stream::raw::Decoder<'static>,
+    frame_complete: bool,
+    // Decoding stops here, before a bomb in a single chunk can fill memory
+    limit: Option<usize>,
+    decoded: usize,
 }
 
 impl ZstdStreamDecoder {
-    fn new() -> Result<Self> {
-        Ok(Self { finished: false })
+    fn new(limit: Option<usize>) -> Result<Self> {
+        let decoder = zstd::stream::raw::Decoder::new()
+            .map_err(|e| anyhow!("Failed to initialize zstd decoder: {}", e))?;
+        Ok(Self { decoder, frame_complete: false, limit, decoded: 0 })
     }
 }
 
 impl CompressionDecoder for ZstdStreamDecoder {
     fn decompress_chunk(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<bool> {
-        // Simplified zstd decompression
-        // In a real implementation, you'd use the zstd crate
-        output.extend_from_slice(input); // Fallback: no decompression
-        Ok(false)
+        use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+        
+        let mut in_buffer = InBuffer::around(input);
+        let mut scratch = vec![0u8; 64 * 1024];
+        
+        // Keep draining until all input is consumed and the decoder has no more output buffered
+        loop {
+            let mut out_buffer = OutBuffer::around(&mut scratch[..]);
+            let hint = self.decoder.run(&mut in_buffer, &mut out_buffer)
+                .map_err(|e| anyhow!("Zstd decompression error: {}", e))?;
+            let written = out_buffer.pos();
+            self.decoded += written;
+            if let Some(limit) = self.limit.filter(|limit| self.decoded > *limit) {
+                return Err(ContentTooLarge { limit }.into());
+            }
+            output.extend_from_slice(&scratch[..written]);
+            
+            // The decoder starts on the next frame by itself when there's more input after this one
+            self.frame_complete = hint == 0;
+            if in_buffer.pos() >= input.len() && (self.frame_complete || written < scratch.len()) {
+                return Ok(false);
+            }
+        }
     }
     
     fn finish(&mut self, _output: &mut Vec<u8>) -> Result<()> {
-        self.finished = true;
+        if !self.frame_complete {
+            return Err(anyhow!("Zstd stream ended before the frame was complete"));
+        }
         Ok(())
     }
     
     fn is_finished(&self) -> bool {
-        self.finished
+        self.frame_complete
     }
 }
 
@@ -431,6 +446,70 @@ mod tests {
         assert_eq!(StreamingDecompressor::detect_compression(Some("gzip"), &[]), CompressionType::Gzip);
         assert_eq!(StreamingDecompressor::detect_compression(Some("deflate"), &[]), CompressionType::Deflate);
         assert_eq!(StreamingDecompressor::detect_compression(Some("br"), &[]), CompressionType::Brotli);
+        assert_eq!(StreamingDecompressor::detect_compression(Some("zstd"), &[]), CompressionType::Zstd);
+    }
+
+    #[test]
+    fn test_zstd_stream_roundtrip() {
+        let original = b"NeonSearch zstd streaming test ".repeat(200);
+        let compressed = zstd::stream::encode_all(&original[..], 3).unwrap();
+        
+        let mut decoder = ZstdStreamDecoder::new(None).unwrap();
+        let mut output = Vec::new();
+        for chunk in compressed.chunks(100) {
+            decoder.decompress_chunk(chunk, &mut output).unwrap();
+        }
+        decoder.finish(&mut output).unwrap();
+        
+        assert_eq!(output, original);
+    }
+
+    #[test]
+    fn test_zstd_frames_and_size_limit() {
+        let first = zstd::stream::encode_all(&b"first frame "[..], 3).unwrap();
+        let second = zstd::stream::encode_all(&b"second frame"[..], 3).unwrap();
+        let config = StreamingDecompressConfig::default();
+        let mut decompressor = StreamingDecompressor::new(CompressionType::Zstd, config.clone()).unwrap();
+        let mut output = decompressor.add_chunk(&[first, second].concat(), &config).unwrap().data;
+        output.extend(decompressor.finalize().unwrap());
+        assert_eq!(output, b"first frame second frame");
+        
+        // A bomb stops at the limit instead of decoding in full
+        let bomb = zstd::stream::encode_all(&vec![0u8; 1024 * 1024][..], 19).unwrap();
+        let mut decoder = ZstdStreamDecoder::new(Some(64 * 1024)).unwrap();
+        let mut output = Vec::new();
+        let error = decoder.decompress_chunk(&bomb, &mut output).unwrap_err();
+        assert!(error.downcast_ref::<ContentTooLarge>().is_some());
+        assert!(output.len() <= 64 * 1024);
+    }
+
+    #[test]
+    fn test_gzip_and_brotli_streams() {
+        let original = b"NeonSearch streaming test ".repeat(200);
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&original).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(&original).unwrap();
+        let brotli = brotli.into_inner();
+        
+        let config = StreamingDecompressConfig::default();
+        for (compression, compressed) in [(CompressionType::Gzip, &gzip), (CompressionType::Brotli, &brotli)] {
+            let mut decompressor = StreamingDecompressor::new(compression, config.clone()).unwrap();
+            let mut output = Vec::new();
+            for chunk in compressed.chunks(100) {
+                output.extend(decompressor.add_chunk(chunk, &config).unwrap().data);
+            }
+            output.extend(decompressor.finalize().unwrap());
+            assert_eq!(output, original, "{:?}", compression);
+            
+            // Cut off or corrupted streams fail rather than passing the compressed bytes on
+            let mut truncated = StreamingDecompressor::new(compression, config.clone()).unwrap();
+            truncated.add_chunk(&compressed[..compressed.len() / 2], &config).unwrap();
+            assert!(truncated.finalize().is_err(), "{:?}", compression);
+            let mut corrupt = StreamingDecompressor::new(compression, config.clone()).unwrap();
+            assert!(corrupt.add_chunk(&[0xff; 64], &config).and_then(|_| corrupt.finalize()).is_err(), "{:?}", compression);
+        }
     }
 
     #[test]
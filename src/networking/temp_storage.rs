@@ -4,7 +4,7 @@ use std::io::{Write, Read};
 use anyhow::{Result, anyhow};
 use uuid::Uuid;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 
 /// Maximum content size for temporary files (100MB)
 const MAX_TEMP_FILE_SIZE: usize = 100 * 1024 * 1024;
@@ -25,19 +25,88 @@ pub struct TempFile {
     pub content_type: Option<String>,
 }
 
+/// Writes a response body to a temporary file as it arrives
+pub struct TempFileWriter {
+    file: fs::File,
+    temp_file: TempFile,
+}
+
+fn cache_dir() -> Result<PathBuf> {
+    let temp_dir = std::env::temp_dir().join("neonsearch_cache");
+    if !temp_dir.exists() {
+        fs::create_dir_all(&temp_dir)
+            .map_err(|e| anyhow!("Failed to create temp directory: {}", e))?;
+    }
+    Ok(temp_dir)
+}
+
+impl TempFile {
+    /// Read the whole file back into memory
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(&self.path)
+            .map_err(|e| anyhow!("Failed to open temp file: {}", e))?;
+        let mut content = Vec::with_capacity(self.size);
+        file.read_to_end(&mut content)
+            .map_err(|e| anyhow!("Failed to read temp file: {}", e))?;
+        Ok(content)
+    }
+
+    /// Delete the file from disk
+    pub fn remove(&self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)
+                .map_err(|e| anyhow!("Failed to remove temp file: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl TempFileWriter {
+    /// Start a new file in the cache directory. It is not tracked by any manager, so the caller owns cleanup.
+    pub fn create(content_type: Option<String>) -> Result<Self> {
+        let id = Uuid::new_v4().to_string();
+        let path = cache_dir()?.join(format!("{}.cache", id));
+        let file = fs::File::create(&path)
+            .map_err(|e| anyhow!("Failed to create temp file: {}", e))?;
+        Ok(Self {
+            file,
+            temp_file: TempFile { id, path, size: 0, content_type },
+        })
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.file.write_all(data)
+            .map_err(|e| anyhow!("Failed to write to temp file: {}", e))?;
+        self.temp_file.size += data.len();
+        Ok(())
+    }
+
+    /// Bytes written so far
+    pub fn len(&self) -> usize {
+        self.temp_file.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.temp_file.size == 0
+    }
+
+    pub fn finish(mut self) -> Result<TempFile> {
+        self.file.flush()
+            .map_err(|e| anyhow!("Failed to flush temp file: {}", e))?;
+        Ok(self.temp_file)
+    }
+}
+
 impl TempStorageManager {
     /// Create a new temporary storage manager
     pub fn new() -> Result<Self> {
-        let temp_dir = std::env::temp_dir().join("neonsearch_cache");
-        
-        // Create temporary directory if it doesn't exist
-        if !temp_dir.exists() {
-            fs::create_dir_all(&temp_dir)
-                .map_err(|e| anyhow!("Failed to create temp directory: {}", e))?;
-        }
+        let temp_dir = cache_dir()?;
         
-        // Clean up any existing files from previous runs
-        Self::cleanup_directory(&temp_dir)?;
+        // Clean up any existing files from previous runs, once, so live files of this run survive
+        static CLEANED: Once = Once::new();
+        let mut cleaned = Ok(());
+        CLEANED.call_once(|| cleaned = Self::cleanup_directory(&temp_dir));
+        cleaned?;
         
         Ok(Self {
             temp_dir,
@@ -206,6 +275,19 @@ mod tests {
         manager.remove_file(&temp_file).unwrap();
     }
 
+    #[test]
+    fn test_streamed_file_outlives_writer() {
+        let mut writer = TempFileWriter::create(Some("text/html".to_string())).unwrap();
+        writer.write(b"<html>").unwrap();
+        writer.write(b"</html>").unwrap();
+        assert_eq!(writer.len(), 13);
+
+        let temp_file = writer.finish().unwrap();
+        assert_eq!(temp_file.read().unwrap(), b"<html></html>");
+        temp_file.remove().unwrap();
+        assert!(!temp_file.path.exists());
+    }
+
     #[test]
     fn test_file_size_limit() {
         let manager = TempStorageManager::new().unwrap();
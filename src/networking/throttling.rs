@@ -0,0 +1,82 @@
+// Network conditions emulated from the developer console: latency added to every request and a cap on how
+// fast response bodies arrive, or no network at all, for watching the streaming parser and loading UI at work
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetworkConditions {
+    #[default]
+    NoThrottling,
+    Fast3G,
+    Slow3G,
+    Offline,
+}
+
+impl NetworkConditions {
+    pub const ALL: [NetworkConditions; 4] = [
+        NetworkConditions::NoThrottling,
+        NetworkConditions::Fast3G,
+        NetworkConditions::Slow3G,
+        NetworkConditions::Offline,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NetworkConditions::NoThrottling => "No throttling",
+            NetworkConditions::Fast3G => "Fast 3G",
+            NetworkConditions::Slow3G => "Slow 3G",
+            NetworkConditions::Offline => "Offline",
+        }
+    }
+
+    /// Added before each request is sent, standing in for round trips
+    pub fn latency(&self) -> Duration {
+        match self {
+            NetworkConditions::Fast3G => Duration::from_millis(563),
+            NetworkConditions::Slow3G => Duration::from_millis(2000),
+            NetworkConditions::NoThrottling | NetworkConditions::Offline => Duration::ZERO,
+        }
+    }
+
+    /// Download speed in bytes per second, when capped (the same 1.44 Mbit/s and 400 kbit/s presets as other browsers)
+    pub fn download_rate(&self) -> Option<u64> {
+        match self {
+            NetworkConditions::Fast3G => Some(1_440_000 / 8),
+            NetworkConditions::Slow3G => Some(400_000 / 8),
+            NetworkConditions::NoThrottling | NetworkConditions::Offline => None,
+        }
+    }
+
+    /// The conditions every fetch currently runs under
+    pub fn current() -> Self {
+        Self::ALL.get(CURRENT.load(Ordering::Relaxed) as usize).copied().unwrap_or_default()
+    }
+
+    pub fn set(conditions: NetworkConditions) {
+        let index = Self::ALL.iter().position(|c| *c == conditions).unwrap_or(0);
+        CURRENT.store(index as u8, Ordering::Relaxed);
+    }
+}
+
+/// Wait out the emulated latency before a request goes out, or fail it as if there were no network
+pub async fn before_request(url: &str) -> Result<()> {
+    let conditions = NetworkConditions::current();
+    if conditions == NetworkConditions::Offline {
+        return Err(anyhow!("Failed to connect to {}: network offline (emulated by dev tools)", url));
+    }
+    let latency = conditions.latency();
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    Ok(())
+}
+
+/// Hold a read of `bytes` back long enough to keep the body under the emulated download speed
+pub async fn after_read(bytes: usize) {
+    if let Some(rate) = NetworkConditions::current().download_rate() {
+        tokio::time::sleep(Duration::from_secs_f64(bytes as f64 / rate as f64)).await;
+    }
+}
@@ -0,0 +1,122 @@
+// When each phase of a request started and ended, how many bytes went each way, and the last few navigations'
+// requests for the performance page's waterfall
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::networking::http_cache::CacheStatus;
+use crate::networking::manual_client::FetchPhase;
+use crate::security::certificate::TlsInfo;
+
+// Navigations the performance page keeps, newest first
+const RECENT_NAVIGATIONS: usize = 10;
+
+static RECENT: OnceLock<Mutex<VecDeque<NavigationTiming>>> = OnceLock::new();
+
+/// One phase of a request, as offsets from when the request started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseTiming {
+    pub phase: FetchPhase,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// Timeline of a fetch across all of its redirect hops
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTiming {
+    /// The address the fetch ended up at
+    pub url: String,
+    pub started: Instant,
+    pub phases: Vec<PhaseTiming>,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    /// Set when a connection opened ahead of the request, by a preconnect hint or a hovered link, served it
+    pub connection_reused: bool,
+    pub cache: CacheStatus,
+    /// The protocol, cipher and certificate of the last hop, when it was HTTPS
+    pub tls: Option<TlsInfo>,
+}
+
+impl RequestTiming {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            started: Instant::now(),
+            phases: Vec::new(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            connection_reused: false,
+            cache: CacheStatus::Network,
+            tls: None,
+        }
+    }
+
+    /// End the phase in progress and start `phase`
+    pub fn push(&mut self, phase: FetchPhase) {
+        let now = self.started.elapsed();
+        if let Some(last) = self.phases.last_mut() {
+            last.end = now;
+        }
+        self.phases.push(PhaseTiming { phase, start: now, end: now });
+    }
+
+    /// From the start of the request to the end of its last phase
+    pub fn duration(&self) -> Duration {
+        self.phases.last().map_or(Duration::ZERO, |phase| phase.end)
+    }
+
+    /// How long after `origin` the request started, for lining requests up on one time axis
+    pub fn offset_from(&self, origin: Instant) -> Duration {
+        self.started.saturating_duration_since(origin)
+    }
+}
+
+/// A page load: the document's request first, then its subresources in the order they finished
+#[derive(Debug, Clone)]
+pub struct NavigationTiming {
+    pub url: String,
+    pub requests: Vec<RequestTiming>,
+}
+
+fn recent() -> &'static Mutex<VecDeque<NavigationTiming>> {
+    RECENT.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Start a navigation's entry with its document request
+pub fn record_navigation(document: RequestTiming) {
+    let Ok(mut recent) = recent().lock() else { return };
+    recent.push_front(NavigationTiming { url: document.url.clone(), requests: vec![document] });
+    recent.truncate(RECENT_NAVIGATIONS);
+}
+
+/// Add a subresource to the latest navigation to `document_url`
+pub fn record_subresource(document_url: &str, timing: RequestTiming) {
+    let Ok(mut recent) = recent().lock() else { return };
+    if let Some(navigation) = recent.iter_mut().find(|navigation| navigation.url == document_url) {
+        navigation.requests.push(timing);
+    }
+}
+
+/// The last few navigations, newest first
+pub fn recent_navigations() -> Vec<NavigationTiming> {
+    recent().lock().map(|recent| recent.iter().cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_end_when_the_next_starts() {
+        let mut timing = RequestTiming::new("https://example.com/");
+        timing.push(FetchPhase::Resolving);
+        std::thread::sleep(Duration::from_millis(5));
+        timing.push(FetchPhase::Connecting);
+        timing.push(FetchPhase::Completed);
+
+        let [resolving, connecting, completed] = [timing.phases[0], timing.phases[1], timing.phases[2]];
+        assert_eq!(resolving.end, connecting.start);
+        assert!(resolving.end - resolving.start >= Duration::from_millis(5));
+        assert_eq!(connecting.end, completed.start);
+        assert_eq!(timing.duration(), completed.end);
+    }
+}
@@ -0,0 +1,178 @@
+// Tracking protection: telling third-party requests apart, blocking known trackers among them and
+// keeping the cookies of the rest out of the jar shared with their own sites
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Bundled list of tracking domains; subdomains of an entry are trackers too
+const TRACKERS: &[&str] = &[
+    "2mdn.net",
+    "adnxs.com",
+    "adsrvr.org",
+    "advertising.com",
+    "amazon-adsystem.com",
+    "bat.bing.com",
+    "bluekai.com",
+    "chartbeat.com",
+    "clarity.ms",
+    "criteo.com",
+    "criteo.net",
+    "demdex.net",
+    "doubleclick.net",
+    "facebook.net",
+    "google-analytics.com",
+    "googleadservices.com",
+    "googlesyndication.com",
+    "googletagmanager.com",
+    "googletagservices.com",
+    "hotjar.com",
+    "krxd.net",
+    "mathtag.com",
+    "mixpanel.com",
+    "moatads.com",
+    "newrelic.com",
+    "nr-data.net",
+    "omtrdc.net",
+    "outbrain.com",
+    "pubmatic.com",
+    "quantserve.com",
+    "rubiconproject.com",
+    "scorecardresearch.com",
+    "segment.io",
+    "taboola.com",
+    "yieldmo.com",
+];
+
+// Second-level labels under which sites register a third level, as in example.co.uk
+const SHARED_SECOND_LEVELS: &[&str] = &["ac", "co", "com", "edu", "gov", "net", "org"];
+
+/// The registrable site of `host`: its last two labels, or three under a country's shared second level
+pub fn site_of_host(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let labels: Vec<&str> = host.rsplit('.').collect();
+    let shared = labels.len() > 2 && labels[0].len() == 2 && SHARED_SECOND_LEVELS.contains(&labels[1]);
+    let take = if shared { 3 } else { 2 };
+    labels.into_iter().take(take).rev().collect::<Vec<_>>().join(".")
+}
+
+/// The registrable site of `url`'s host
+pub fn site_of(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(site_of_host)
+}
+
+/// Whether a request to `url` made by the page at `top_url` goes to another site
+pub fn is_third_party(top_url: &str, url: &str) -> bool {
+    match (site_of(top_url), site_of(url)) {
+        (Some(top), Some(site)) => top != site,
+        _ => false,
+    }
+}
+
+/// The jar key requests to `url` keep cookies under. Tracking protection decides when it's on; otherwise a frame
+/// partitions hosts off `partition`, its top-level site, and everything else uses the host itself.
+pub fn cookie_domain(tracking: Option<&TrackingProtection>, partition: Option<&str>, url: &str, host: &str, set_cookies: usize) -> String {
+    match (tracking, partition) {
+        (Some(tracking), _) => tracking.cookie_domain(url, host, set_cookies),
+        (None, Some(top_site)) if site_of_host(host) != top_site => crate::networking::cookie_manager::partition_key(host, top_site),
+        _ => host.to_string(),
+    }
+}
+
+/// Whether `host` or one of its parent domains is on the tracker list
+pub fn is_tracker(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    TRACKERS.iter().any(|tracker| host == *tracker || host.strip_suffix(tracker).is_some_and(|rest| rest.ends_with('.')))
+}
+
+/// What tracking protection did on one page, for the toolbar shield
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShieldReport {
+    /// Blocked requests per tracker host
+    pub blocked: BTreeMap<String, usize>,
+    /// Cookies set by third-party hosts that went into the page's partition
+    pub partitioned: BTreeMap<String, usize>,
+}
+
+impl ShieldReport {
+    pub fn trackers_blocked(&self) -> usize {
+        self.blocked.values().sum()
+    }
+
+    pub fn cookies_partitioned(&self) -> usize {
+        self.partitioned.values().sum()
+    }
+}
+
+/// Tracking protection for the requests of one top-level page, shared with its subresources and frames
+#[derive(Debug, Clone)]
+pub struct TrackingProtection {
+    top_site: String,
+    report: Arc<Mutex<ShieldReport>>,
+}
+
+impl TrackingProtection {
+    /// Protection for the page at `top_url`; None for pages without a host to compare requests to
+    pub fn new(top_url: &str) -> Option<Self> {
+        site_of(top_url).map(|top_site| Self { top_site, report: Arc::default() })
+    }
+
+    fn third_party_host(&self, url: &str) -> Option<String> {
+        let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+        (site_of_host(&host) != self.top_site).then_some(host)
+    }
+
+    /// Whether a request to `url` is stopped before it leaves, as a third-party tracker
+    pub fn intercept(&self, url: &str) -> bool {
+        let Some(host) = self.third_party_host(url).filter(|host| is_tracker(host)) else {
+            return false;
+        };
+        if let Ok(mut report) = self.report.lock() {
+            *report.blocked.entry(host).or_default() += 1;
+        }
+        true
+    }
+
+    /// The cookie jar key requests to `url` use: the host itself first-party, a partition of it under the
+    /// page's site third-party. `set_cookies` is how many cookies the response is setting
+    pub fn cookie_domain(&self, url: &str, host: &str, set_cookies: usize) -> String {
+        match self.third_party_host(url) {
+            Some(third_party) => {
+                if set_cookies > 0 {
+                    if let Ok(mut report) = self.report.lock() {
+                        *report.partitioned.entry(third_party).or_default() += set_cookies;
+                    }
+                }
+                crate::networking::cookie_manager::partition_key(host, &self.top_site)
+            }
+            None => host.to_string(),
+        }
+    }
+
+    pub fn report(&self) -> ShieldReport {
+        self.report.lock().map(|report| report.clone()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_third_party_classification() {
+        assert_eq!(site_of_host("news.bbc.co.uk"), "bbc.co.uk");
+        assert_eq!(site_of_host("static.example.com"), "example.com");
+        assert!(!is_third_party("https://www.example.com/", "https://cdn.example.com/app.js"));
+        assert!(is_third_party("https://example.com/", "https://cdn.example.net/app.js"));
+
+        assert!(is_tracker("stats.g.doubleclick.net"));
+        assert!(is_tracker("www.google-analytics.com"));
+        assert!(!is_tracker("notdoubleclick.net"));
+
+        let protection = TrackingProtection::new("https://example.com/article").unwrap();
+        assert!(protection.intercept("https://www.googletagmanager.com/gtm.js"));
+        assert!(!protection.intercept("https://example.com/analytics.js"));
+        assert_eq!(protection.cookie_domain("https://img.example.com/a.png", "img.example.com", 1), "img.example.com");
+        assert_ne!(protection.cookie_domain("https://cdn.other.org/a.png", "cdn.other.org", 2), "cdn.other.org");
+        let report = protection.report();
+        assert_eq!((report.trackers_blocked(), report.cookies_partitioned()), (1, 2));
+    }
+}
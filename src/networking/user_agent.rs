@@ -0,0 +1,130 @@
+// User-agent profiles: the User-Agent, Accept and client hint headers a request presents itself with
+use serde::{Deserialize, Serialize};
+
+/// A browser the request pipeline can present itself as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum UserAgentPreset {
+    #[default]
+    NeonSearch,
+    ChromeWindows,
+    FirefoxLinux,
+    Mobile,
+}
+
+/// Client hints Chromium-based browsers send alongside their User-Agent
+struct ClientHints {
+    brands: &'static str,
+    mobile: bool,
+    platform: &'static str,
+}
+
+const CHROMIUM_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+const FIREFOX_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8";
+
+impl UserAgentPreset {
+    pub const ALL: [UserAgentPreset; 4] = [
+        UserAgentPreset::NeonSearch,
+        UserAgentPreset::ChromeWindows,
+        UserAgentPreset::FirefoxLinux,
+        UserAgentPreset::Mobile,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UserAgentPreset::NeonSearch => "NeonSearch",
+            UserAgentPreset::ChromeWindows => "Chrome on Windows",
+            UserAgentPreset::FirefoxLinux => "Firefox on Linux",
+            UserAgentPreset::Mobile => "Chrome on Android",
+        }
+    }
+
+    pub fn user_agent(&self) -> &'static str {
+        match self {
+            UserAgentPreset::NeonSearch => "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) NeonSearch/1.0 Chrome/120.0.0.0 Safari/537.36",
+            UserAgentPreset::ChromeWindows => "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            UserAgentPreset::FirefoxLinux => "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0",
+            UserAgentPreset::Mobile => "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        }
+    }
+
+    /// `navigator.platform` of the browser the profile claims to be
+    pub fn platform(&self) -> &'static str {
+        match self {
+            UserAgentPreset::NeonSearch => "MacIntel",
+            UserAgentPreset::ChromeWindows => "Win32",
+            UserAgentPreset::FirefoxLinux => "Linux x86_64",
+            UserAgentPreset::Mobile => "Linux armv8l",
+        }
+    }
+
+    /// `navigator.vendor`, which only Chromium-based browsers fill in
+    pub fn vendor(&self) -> &'static str {
+        match self {
+            UserAgentPreset::FirefoxLinux => "",
+            _ => "Google Inc.",
+        }
+    }
+
+    /// `navigator.maxTouchPoints`: only the phone profile has a touch screen
+    pub fn max_touch_points(&self) -> u32 {
+        match self {
+            UserAgentPreset::Mobile => 5,
+            _ => 0,
+        }
+    }
+
+    // Firefox doesn't send client hints, so neither does a request claiming to be it
+    fn client_hints(&self) -> Option<ClientHints> {
+        match self {
+            UserAgentPreset::NeonSearch => Some(ClientHints {
+                brands: "\"Not_A Brand\";v=\"8\", \"Chromium\";v=\"120\", \"NeonSearch\";v=\"1\"",
+                mobile: false,
+                platform: "macOS",
+            }),
+            UserAgentPreset::ChromeWindows => Some(ClientHints {
+                brands: "\"Not_A Brand\";v=\"8\", \"Chromium\";v=\"120\", \"Google Chrome\";v=\"120\"",
+                mobile: false,
+                platform: "Windows",
+            }),
+            UserAgentPreset::FirefoxLinux => None,
+            UserAgentPreset::Mobile => Some(ClientHints {
+                brands: "\"Not_A Brand\";v=\"8\", \"Chromium\";v=\"120\", \"Google Chrome\";v=\"120\"",
+                mobile: true,
+                platform: "Android",
+            }),
+        }
+    }
+
+    /// Headers identifying the browser, in the order the browser itself sends them
+    pub fn headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(hints) = self.client_hints() {
+            headers.push(("Sec-CH-UA".to_string(), hints.brands.to_string()));
+            headers.push(("Sec-CH-UA-Mobile".to_string(), if hints.mobile { "?1" } else { "?0" }.to_string()));
+            headers.push(("Sec-CH-UA-Platform".to_string(), format!("\"{}\"", hints.platform)));
+        }
+        headers.push(("User-Agent".to_string(), self.user_agent().to_string()));
+        let accept = if self.client_hints().is_some() { CHROMIUM_ACCEPT } else { FIREFOX_ACCEPT };
+        headers.push(("Accept".to_string(), accept.to_string()));
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_headers_are_consistent() {
+        let header = |preset: UserAgentPreset, name: &str| preset.headers().into_iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value);
+        assert_eq!(header(UserAgentPreset::FirefoxLinux, "Sec-CH-UA"), None);
+        assert!(header(UserAgentPreset::FirefoxLinux, "User-Agent").unwrap().contains("Firefox/"));
+        assert_eq!(header(UserAgentPreset::Mobile, "Sec-CH-UA-Mobile").as_deref(), Some("?1"));
+        assert_eq!(header(UserAgentPreset::Mobile, "Sec-CH-UA-Platform").as_deref(), Some("\"Android\""));
+        assert!(header(UserAgentPreset::Mobile, "User-Agent").unwrap().contains("Mobile"));
+        assert_eq!(header(UserAgentPreset::ChromeWindows, "Sec-CH-UA-Platform").as_deref(), Some("\"Windows\""));
+        assert!(header(UserAgentPreset::NeonSearch, "Sec-CH-UA").unwrap().contains("NeonSearch"));
+    }
+}
@@ -22,6 +22,11 @@ pub trait CustomPage {
     fn can_handle(&self, url: &str) -> bool {
         url == self.get_url() || url.starts_with(&format!("{}?", self.get_url()))
     }
+    
+    /// Actions the page asks the browser to carry out, such as opening tabs
+    fn take_page_actions(&mut self) -> Vec<crate::ui::PageAction> {
+        Vec::new()
+    }
 }
 
 /// Router for custom pages
@@ -46,6 +51,15 @@ impl PageRouter {
         router.register_page(Box::new(pages::DownloadsPage::new()));
         router.register_page(Box::new(pages::ExtensionsPage::new()));
         router.register_page(Box::new(pages::ExperimentsPage::new()));
+        router.register_page(Box::new(pages::DiagnosticsPage::new()));
+        router.register_page(Box::new(pages::NotificationsPage::new()));
+        router.register_page(Box::new(pages::CrashPage::new()));
+        router.register_page(Box::new(pages::UserScriptsPage::new()));
+        router.register_page(Box::new(pages::ImportPage::new()));
+        router.register_page(Box::new(pages::SyncPage::new()));
+        router.register_page(Box::new(pages::PasswordsPage::new()));
+        router.register_page(Box::new(pages::LogsPage::new()));
+        router.register_page(Box::new(pages::TasksPage::new()));
         
         router
     }
@@ -78,6 +92,13 @@ impl PageRouter {
         }
     }
     
+    pub fn take_page_actions(&mut self, url: &str) -> Vec<crate::ui::PageAction> {
+        self.pages.values_mut()
+            .find(|page| page.can_handle(url))
+            .map(|page| page.take_page_actions())
+            .unwrap_or_default()
+    }
+    
     pub fn on_page_load(&mut self, url: &str) {
         if let Some(page) = self.pages.values_mut().find(|page| page.can_handle(url)) {
             page.on_load();
@@ -93,24 +114,40 @@ impl PageRouter {
 
 /// Base components for pages
 pub mod components {
-    use eframe::egui::{RichText, Ui};
+    use eframe::egui::{Color32, Image, Pos2, RichText, Sense, Shape, Stroke, Ui, Vec2};
+    use crate::networking::site_metadata::SiteMetadataService;
     use crate::ui::theme::NeonTheme;
+    use crate::ui::i18n::tr;
     use crate::ui::icons::NeonIcons;
+    use crate::security::{CheckStatus, SecurityLevel, SecurityReport};
+
+    /// Favicon of the site hosting `url`, or a globe until one has loaded
+    pub fn site_icon(ui: &mut Ui, url: &str, size: f32) {
+        let texture = SiteMetadataService::shared().and_then(|service| service.favicon_texture(ui.ctx(), url));
+        match texture {
+            Some(texture) => {
+                ui.add(Image::new(&texture).fit_to_exact_size(Vec2::splat(size)));
+            }
+            None => {
+                ui.label(RichText::new(NeonIcons::GLOBE).size(size).color(NeonTheme::secondary_text()));
+            }
+        }
+    }
 
     /// Standard page header with title and optional description
     pub fn page_header(ui: &mut Ui, title: &str, description: Option<&str>) {
         ui.add_space(20.0);
         
         // Main title
-        ui.heading(RichText::new(title)
+        ui.heading(RichText::new(tr(title))
             .size(32.0)
-            .color(NeonTheme::PRIMARY_TEXT));
+            .color(NeonTheme::primary_text()));
         
         if let Some(desc) = description {
             ui.add_space(8.0);
-            ui.label(RichText::new(desc)
+            ui.label(RichText::new(tr(desc))
                 .size(16.0)
-                .color(NeonTheme::SECONDARY_TEXT));
+                .color(NeonTheme::secondary_text()));
         }
         
         ui.add_space(20.0);
@@ -122,18 +159,18 @@ pub mod components {
     pub fn section_header(ui: &mut Ui, icon: &str, title: &str) {
         ui.add_space(16.0);
         ui.horizontal(|ui| {
-            ui.label(RichText::new(icon).size(20.0).color(NeonTheme::NEON_CYAN));
-            ui.label(RichText::new(title)
+            ui.label(RichText::new(icon).size(20.0).color(NeonTheme::neon_cyan()));
+            ui.label(RichText::new(tr(title))
                 .size(20.0)
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
         });
         ui.add_space(8.0);
     }
     
     /// Card container for content sections
     pub fn card_container<R>(ui: &mut Ui, content: impl FnOnce(&mut Ui) -> R) -> R {
-        let card_color = NeonTheme::CARD_BG;
+        let card_color = NeonTheme::card_bg();
         
         eframe::egui::Frame::none()
             .fill(card_color)
@@ -149,14 +186,14 @@ pub mod components {
             for (i, item) in items.iter().enumerate() {
                 if i > 0 {
                     ui.label(RichText::new(NeonIcons::ARROW_RIGHT)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     ui.add_space(8.0);
                 }
                 
                 let color = if i == items.len() - 1 {
-                    NeonTheme::PRIMARY_TEXT
+                    NeonTheme::primary_text()
                 } else {
-                    NeonTheme::SECONDARY_TEXT
+                    NeonTheme::secondary_text()
                 };
                 
                 ui.label(RichText::new(*item).color(color));
@@ -176,9 +213,94 @@ pub mod components {
             };
             
             ui.label(RichText::new(icon).color(color));
-            ui.label(RichText::new(text).color(NeonTheme::PRIMARY_TEXT));
+            ui.label(RichText::new(text).color(NeonTheme::primary_text()));
         });
     }
+    
+    /// Color and icon for a security check outcome
+    pub fn check_status_style(status: CheckStatus) -> (&'static str, Color32) {
+        match status {
+            CheckStatus::Pass => (NeonIcons::CHECK_CIRCLE, NeonTheme::success_color()),
+            CheckStatus::Warning => (NeonIcons::WARNING, NeonTheme::warning_color()),
+            CheckStatus::Fail => (NeonIcons::X_CIRCLE, NeonTheme::error_color()),
+        }
+    }
+    
+    /// Color a score out of 100 is shown in
+    pub fn security_level_color(level: SecurityLevel) -> Color32 {
+        match level {
+            SecurityLevel::Excellent => NeonTheme::success_color(),
+            SecurityLevel::Good => NeonTheme::neon_cyan(),
+            SecurityLevel::Fair => NeonTheme::warning_color(),
+            SecurityLevel::Poor => NeonTheme::error_color(),
+        }
+    }
+    
+    /// Per-category score of a site with what was found and how to improve it
+    pub fn security_breakdown(ui: &mut Ui, report: &SecurityReport) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{}", report.security_score))
+                .size(28.0)
+                .strong()
+                .color(security_level_color(report.level())));
+            ui.vertical(|ui| {
+                ui.label(RichText::new(format!("{:?}", report.level())).strong().color(NeonTheme::primary_text()));
+                ui.label(RichText::new("out of 100").size(11.0).color(NeonTheme::muted_text()));
+            });
+        });
+        ui.add_space(6.0);
+        
+        for check in &report.checks {
+            let (icon, color) = check_status_style(check.status);
+            let header = format!("{} {}  {}/{}  ·  {}",
+                icon, check.category.label(), check.points, check.category.max_points(), check.summary);
+            eframe::egui::CollapsingHeader::new(RichText::new(header).color(color))
+                .id_salt((report.url.as_str(), check.category))
+                .default_open(check.status == CheckStatus::Fail)
+                .show(ui, |ui| {
+                    for finding in &check.findings {
+                        ui.label(RichText::new(format!("• {}", finding)).color(NeonTheme::secondary_text()));
+                    }
+                    for recommendation in &check.recommendations {
+                        ui.label(RichText::new(format!("→ {}", recommendation)).color(NeonTheme::accent_text()));
+                    }
+                });
+        }
+    }
+    
+    /// Small line graph of recent samples, scaled to the largest value
+    pub fn sparkline<'a>(ui: &mut Ui, samples: impl ExactSizeIterator<Item = &'a f32> + Clone, size: Vec2, color: Color32) {
+        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, NeonTheme::darker_bg());
+        
+        if samples.len() < 2 {
+            return;
+        }
+        
+        let max = samples.clone().cloned().fold(0.0_f32, f32::max).max(1.0);
+        let step = rect.width() / (samples.len() - 1) as f32;
+        let points: Vec<Pos2> = samples
+            .enumerate()
+            .map(|(i, value)| Pos2::new(
+                rect.left() + i as f32 * step,
+                rect.bottom() - 1.0 - (value / max) * (rect.height() - 2.0),
+            ))
+            .collect();
+        
+        painter.add(Shape::line(points, Stroke::new(1.5, color)));
+    }
+    
+    /// Hour and minute fields for `minutes` after midnight. Returns whether they changed
+    pub fn time_of_day(ui: &mut Ui, minutes: &mut u16) -> bool {
+        let (mut hour, mut minute) = (*minutes / 60, *minutes % 60);
+        let two_digits = |n: f64, _| format!("{:02}", n);
+        let mut changed = ui.add(eframe::egui::DragValue::new(&mut hour).range(0..=23).custom_formatter(two_digits)).changed();
+        ui.label(":");
+        changed |= ui.add(eframe::egui::DragValue::new(&mut minute).range(0..=59).custom_formatter(two_digits)).changed();
+        *minutes = hour * 60 + minute;
+        changed
+    }
 }
 
 // Page modules
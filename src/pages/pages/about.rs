@@ -38,7 +38,7 @@ impl CustomPage for AboutPage {
             ui.add_space(20.0);
             ui.label(RichText::new("⚡")
                 .size(80.0)
-                .color(NeonTheme::NEON_CYAN));
+                .color(NeonTheme::neon_cyan()));
             
             ui.add_space(20.0);
             
@@ -48,17 +48,17 @@ impl CustomPage for AboutPage {
                     ui.label(RichText::new("NeonSearch Browser")
                         .size(28.0)
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     
                     ui.add_space(8.0);
                     ui.label(RichText::new("Version 0.2.0")
                         .size(18.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     
                     ui.add_space(16.0);
                     ui.label(RichText::new("Built with ❤️ by NeonDev™")
                         .size(16.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                 });
             });
             
@@ -88,10 +88,10 @@ impl CustomPage for AboutPage {
                             ui.label(RichText::new(*title)
                                 .size(16.0)
                                 .strong()
-                                .color(NeonTheme::PRIMARY_TEXT));
+                                .color(NeonTheme::primary_text()));
                             ui.label(RichText::new(*description)
                                 .size(14.0)
-                                .color(NeonTheme::SECONDARY_TEXT));
+                                .color(NeonTheme::secondary_text()));
                         });
                     });
                     columns[col].add_space(16.0);
@@ -110,19 +110,19 @@ impl CustomPage for AboutPage {
                 columns[0].vertical(|ui| {
                     ui.label(RichText::new("Browser Engine")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     ui.label("NeonEngine v0.2.0");
                     
                     ui.add_space(12.0);
                     ui.label(RichText::new("JavaScript Engine")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     ui.label("NeonJS (Custom Implementation)");
                     
                     ui.add_space(12.0);
                     ui.label(RichText::new("User Agent")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     ui.label("NeonSearch/0.2.0 (Rust/Native)");
                 });
                 
@@ -130,7 +130,7 @@ impl CustomPage for AboutPage {
                 columns[1].vertical(|ui| {
                     ui.label(RichText::new("Operating System")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     let os = std::env::consts::OS;
                     let arch = std::env::consts::ARCH;
                     ui.label(format!("{} ({})", os, arch));
@@ -138,13 +138,13 @@ impl CustomPage for AboutPage {
                     ui.add_space(12.0);
                     ui.label(RichText::new("Rust Version")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     ui.label("rustc 1.75+ (Rust 2021 Edition)");
                     
                     ui.add_space(12.0);
                     ui.label(RichText::new("Build Type")
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     if cfg!(debug_assertions) {
                         ui.label("Debug");
                     } else {
@@ -164,7 +164,7 @@ impl CustomPage for AboutPage {
                 ui.label(RichText::new("Open Source Libraries")
                     .size(16.0)
                     .strong()
-                    .color(NeonTheme::PRIMARY_TEXT));
+                    .color(NeonTheme::primary_text()));
                 
                 ui.add_space(12.0);
                 
@@ -179,14 +179,14 @@ impl CustomPage for AboutPage {
                 for lib in libraries.iter() {
                     ui.label(RichText::new(*lib)
                         .size(14.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                 }
                 
                 ui.add_space(20.0);
                 
                 ui.label(RichText::new("© 2025 NeonDev™. All rights reserved.")
                     .size(14.0)
-                    .color(NeonTheme::SECONDARY_TEXT));
+                    .color(NeonTheme::secondary_text()));
                 
                 ui.add_space(8.0);
                 
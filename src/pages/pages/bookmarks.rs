@@ -3,6 +3,7 @@ use crate::pages::{CustomPage, components};
 use crate::ui::theme::NeonTheme;
 use crate::ui::icons::NeonIcons;
 use std::time::SystemTime;
+use crate::storage::BookmarkStore;
 
 #[derive(Debug, Clone)]
 pub struct Bookmark {
@@ -54,6 +55,8 @@ pub struct BookmarksPage {
     new_bookmark_title: String,
     new_bookmark_url: String,
     new_bookmark_folder: Option<String>,
+    // Revision of the profile's bookmark store last shown
+    store_revision: Option<u64>,
 }
 
 impl BookmarksPage {
@@ -155,6 +158,45 @@ impl BookmarksPage {
             new_bookmark_title: String::new(),
             new_bookmark_url: String::new(),
             new_bookmark_folder: None,
+            store_revision: None,
+        }
+    }
+    
+    // Show the bookmarks saved in the profile, such as imported ones, in folders named after their paths
+    fn sync_stored_bookmarks(&mut self) {
+        let store = BookmarkStore::shared();
+        let Ok(store) = store.lock() else { return };
+        if self.store_revision == Some(store.revision()) {
+            return;
+        }
+        self.store_revision = Some(store.revision());
+        self.bookmarks.retain(|bookmark| !bookmark.id.starts_with("stored_"));
+        self.folders.retain(|folder| !folder.id.starts_with("stored_"));
+        
+        for (i, stored) in store.list().iter().enumerate() {
+            let mut parent_id: Option<String> = None;
+            for depth in 1..=stored.folder.len() {
+                let id = format!("stored_folder_{}", stored.folder[..depth].join("/"));
+                if !self.folders.iter().any(|folder| folder.id == id) {
+                    self.folders.push(BookmarkFolder {
+                        id: id.clone(),
+                        name: stored.folder[depth - 1].clone(),
+                        parent_id: parent_id.clone(),
+                        created_at: stored.added_at.into(),
+                    });
+                }
+                parent_id = Some(id);
+            }
+            self.bookmarks.push(Bookmark {
+                id: format!("stored_{}", i),
+                title: stored.title.clone(),
+                url: stored.url.clone(),
+                folder_id: parent_id,
+                created_at: stored.added_at.into(),
+                favicon_url: None,
+                tags: Vec::new(),
+                description: None,
+            });
         }
     }
     
@@ -213,6 +255,8 @@ impl CustomPage for BookmarksPage {
             Some("Organize and manage your saved bookmarks")
         );
         
+        self.sync_stored_bookmarks();
+        
         // Top toolbar
         components::card_container(ui, |ui| {
             ui.vertical(|ui| {
@@ -221,7 +265,7 @@ impl CustomPage for BookmarksPage {
                     // Back button (if in a folder)
                     if self.current_folder.is_some() {
                         if ui.button(RichText::new(format!("{} Back", NeonIcons::ARROW_LEFT))
-                            .color(NeonTheme::NEON_CYAN)).clicked() {
+                            .color(NeonTheme::neon_cyan())).clicked() {
                             self.current_folder = None;
                         }
                         ui.add_space(8.0);
@@ -231,12 +275,12 @@ impl CustomPage for BookmarksPage {
                     ui.label(RichText::new(format!("{} {}", NeonIcons::FOLDER, self.get_current_folder_name()))
                         .size(16.0)
                         .strong()
-                        .color(NeonTheme::PRIMARY_TEXT));
+                        .color(NeonTheme::primary_text()));
                     
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                         // Add bookmark button
                         if ui.button(RichText::new(format!("{} Add Bookmark", NeonIcons::PLUS))
-                            .color(NeonTheme::NEON_CYAN)).clicked() {
+                            .color(NeonTheme::neon_cyan())).clicked() {
                             self.show_add_dialog = true;
                             self.new_bookmark_title.clear();
                             self.new_bookmark_url.clear();
@@ -247,12 +291,12 @@ impl CustomPage for BookmarksPage {
                         
                         // Import/Export buttons
                         if ui.button(RichText::new(format!("{} Import", NeonIcons::UPLOAD))
-                            .color(NeonTheme::SECONDARY_TEXT)).clicked() {
+                            .color(NeonTheme::secondary_text())).clicked() {
                             // TODO: Import bookmarks
                         }
                         
                         if ui.button(RichText::new(format!("{} Export", NeonIcons::DOWNLOAD))
-                            .color(NeonTheme::SECONDARY_TEXT)).clicked() {
+                            .color(NeonTheme::secondary_text())).clicked() {
                             // TODO: Export bookmarks
                         }
                     });
@@ -263,7 +307,7 @@ impl CustomPage for BookmarksPage {
                 // Second row: Search and view options
                 ui.horizontal(|ui| {
                     // Search box
-                    ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::SECONDARY_TEXT));
+                    ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::secondary_text()));
                     ui.add(egui::TextEdit::singleline(&mut self.search_query)
                         .hint_text("Search bookmarks, tags, or URLs...")
                         .desired_width(300.0));
@@ -318,16 +362,16 @@ impl CustomPage for BookmarksPage {
                         ui.label(RichText::new("Folders")
                             .size(16.0)
                             .strong()
-                            .color(NeonTheme::PRIMARY_TEXT));
+                            .color(NeonTheme::primary_text()));
                         
                         ui.add_space(8.0);
                         
                         // Root folder
                         let is_root_selected = self.current_folder.is_none();
                         let root_color = if is_root_selected {
-                            NeonTheme::NEON_CYAN
+                            NeonTheme::neon_cyan()
                         } else {
-                            NeonTheme::SECONDARY_TEXT
+                            NeonTheme::secondary_text()
                         };
                         
                         let root_response = ui.horizontal(|ui| {
@@ -351,7 +395,7 @@ impl CustomPage for BookmarksPage {
                         
                         // Add folder button
                         if ui.button(RichText::new(format!("{} New Folder", NeonIcons::PLUS))
-                            .color(NeonTheme::SECONDARY_TEXT)).clicked() {
+                            .color(NeonTheme::secondary_text())).clicked() {
                             // TODO: Show add folder dialog
                         }
                     });
@@ -370,7 +414,7 @@ impl CustomPage for BookmarksPage {
                             ui.add_space(40.0);
                             ui.label(RichText::new(NeonIcons::BOOKMARK)
                                 .size(48.0)
-                                .color(NeonTheme::SECONDARY_TEXT));
+                                .color(NeonTheme::secondary_text()));
                             ui.add_space(16.0);
                             
                             let message = if self.bookmarks.is_empty() {
@@ -383,7 +427,7 @@ impl CustomPage for BookmarksPage {
                             
                             ui.label(RichText::new(message)
                                 .size(18.0)
-                                .color(NeonTheme::SECONDARY_TEXT));
+                                .color(NeonTheme::secondary_text()));
                             ui.add_space(40.0);
                         });
                     });
@@ -420,9 +464,9 @@ impl BookmarksPage {
     fn render_folder_item(&mut self, ui: &mut Ui, folder: &BookmarkFolder) {
         let is_selected = self.current_folder.as_ref() == Some(&folder.id);
         let color = if is_selected {
-            NeonTheme::NEON_CYAN
+            NeonTheme::neon_cyan()
         } else {
-            NeonTheme::SECONDARY_TEXT
+            NeonTheme::secondary_text()
         };
         
         let response = ui.horizontal(|ui| {
@@ -441,8 +485,8 @@ impl BookmarksPage {
             for subfolder in subfolders {
                 ui.horizontal(|ui| {
                     ui.add_space(32.0); // Double indent
-                    ui.label(RichText::new(NeonIcons::FOLDER).color(NeonTheme::SECONDARY_TEXT));
-                    ui.label(RichText::new(&subfolder.name).color(NeonTheme::SECONDARY_TEXT));
+                    ui.label(RichText::new(NeonIcons::FOLDER).color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new(&subfolder.name).color(NeonTheme::secondary_text()));
                 });
             }
         }
@@ -452,26 +496,24 @@ impl BookmarksPage {
         let is_selected = self.selected_items.contains(&bookmark.id);
         
         let frame_color = if is_selected {
-            NeonTheme::NEON_CYAN.linear_multiply(0.2)
+            NeonTheme::neon_cyan().linear_multiply(0.2)
         } else {
-            NeonTheme::CARD_BG
+            NeonTheme::card_bg()
         };
         
         let response = egui::Frame::none()
             .fill(frame_color)
             .rounding(8.0)
             .stroke(egui::Stroke::new(1.0, if is_selected { 
-                NeonTheme::NEON_CYAN 
+                NeonTheme::neon_cyan() 
             } else { 
-                NeonTheme::BORDER_COLOR 
+                NeonTheme::border_color() 
             }))
             .inner_margin(12.0)
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
                     // Favicon
-                    ui.label(RichText::new(NeonIcons::GLOBE)
-                        .size(16.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                    components::site_icon(ui, &bookmark.url, 16.0);
                     
                     ui.add_space(8.0);
                     
@@ -483,12 +525,12 @@ impl BookmarksPage {
                                 RichText::new(&bookmark.title)
                                     .size(14.0)
                                     .strong()
-                                    .color(NeonTheme::PRIMARY_TEXT)
+                                    .color(NeonTheme::primary_text())
                             ).sense(egui::Sense::click()));
                             
                             if title_response.clicked() {
                                 // TODO: Navigate to bookmark URL
-                                println!("Opening: {}", bookmark.url);
+                                log::debug!("Opening: {}", bookmark.url);
                             }
                             
                             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
@@ -506,13 +548,13 @@ impl BookmarksPage {
                         // URL
                         ui.label(RichText::new(&bookmark.url)
                             .size(12.0)
-                            .color(NeonTheme::NEON_CYAN));
+                            .color(NeonTheme::neon_cyan()));
                         
                         // Description (if available)
                         if let Some(description) = &bookmark.description {
                             ui.label(RichText::new(description)
                                 .size(11.0)
-                                .color(NeonTheme::SECONDARY_TEXT));
+                                .color(NeonTheme::secondary_text()));
                         }
                         
                         // Tags
@@ -520,7 +562,7 @@ impl BookmarksPage {
                             ui.horizontal(|ui| {
                                 for tag in &bookmark.tags {
                                     ui.small(RichText::new(format!("#{}", tag))
-                                        .color(NeonTheme::NEON_PURPLE));
+                                        .color(NeonTheme::neon_purple()));
                                 }
                             });
                         }
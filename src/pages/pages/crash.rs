@@ -0,0 +1,155 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use std::path::PathBuf;
+use crate::pages::{CustomPage, components};
+use crate::storage::crash_reports::CrashReport;
+use crate::ui::PageAction;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+// Where "Submit report" files the crash, as a prefilled issue
+const ISSUE_TRACKER: &str = "https://github.com/DEV-AdriBOT/NeonSearch/issues/new";
+// Issue URLs this long still open in every browser
+const MAX_ISSUE_BODY: usize = 6000;
+
+pub struct CrashPage {
+    url: String,
+    title: String,
+    // The newest report and the file it was read from, loaded when the page is opened
+    report: Option<(PathBuf, CrashReport)>,
+    show_details: bool,
+    restored: bool,
+    actions: Vec<PageAction>,
+}
+
+impl CrashPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://crash".to_string(),
+            title: "Crash Recovery".to_string(),
+            report: None,
+            show_details: false,
+            restored: false,
+            actions: Vec::new(),
+        }
+    }
+
+    fn issue_url(report: &CrashReport) -> String {
+        let title = format!("Crash: {}", report.message.lines().next().unwrap_or_default());
+        let body: String = report.to_text().chars().take(MAX_ISSUE_BODY).collect();
+        format!(
+            "{}?title={}&body={}",
+            ISSUE_TRACKER,
+            urlencoding::encode(&title),
+            urlencoding::encode(&format!("```\n{}\n```", body))
+        )
+    }
+}
+
+impl Default for CrashPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for CrashPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn on_load(&mut self) {
+        self.report = CrashReport::load_all(&CrashReport::dir()).into_iter().next();
+        self.show_details = false;
+        self.restored = false;
+    }
+
+    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+        components::page_header(
+            ui,
+            "Crash Recovery",
+            Some("NeonSearch closed unexpectedly. Pick up where you left off, and help fix it by sending the report")
+        );
+
+        if self.report.is_none() {
+            self.on_load();
+        }
+        let Some((path, report)) = self.report.as_mut() else {
+            components::card_container(ui, |ui| {
+                ui.label(RichText::new("No crash reports. NeonSearch hasn't crashed.").color(NeonTheme::secondary_text()));
+            });
+            return;
+        };
+        // Seen now, so the next launch doesn't open this page again. The file is named after the
+        // crash time, so saving rewrites it in place
+        if !report.reviewed {
+            report.reviewed = true;
+            if let Some(Err(e)) = path.parent().map(|dir| report.save(dir)) {
+                log::warn!("{}", e);
+            }
+        }
+
+        components::section_header(ui, NeonIcons::ARROWS_CLOCKWISE, "Restore session");
+        components::card_container(ui, |ui| {
+            if report.tabs.is_empty() {
+                ui.label(RichText::new("No tabs were open.").color(NeonTheme::secondary_text()));
+                return;
+            }
+            for url in &report.tabs {
+                ui.label(RichText::new(url).color(NeonTheme::primary_text()));
+            }
+            ui.add_space(8.0);
+            let label = format!("{} Restore {} tab{}", NeonIcons::ARROWS_CLOCKWISE, report.tabs.len(), if report.tabs.len() == 1 { "" } else { "s" });
+            if ui.add_enabled(!self.restored, egui::Button::new(label)).clicked() {
+                self.actions.extend(report.tabs.iter().cloned().map(PageAction::OpenInNewTab));
+                self.restored = true;
+            }
+        });
+
+        components::section_header(ui, NeonIcons::WARNING, "Crash report");
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new(&report.message).strong().color(NeonTheme::error_color()));
+            ui.label(RichText::new(format!(
+                "NeonSearch {} on {} · {}",
+                report.version,
+                report.os,
+                report.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+            )).color(NeonTheme::secondary_text()));
+            if let Some(location) = &report.location {
+                ui.label(RichText::new(location).monospace().color(NeonTheme::muted_text()));
+            }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                let details = if self.show_details { "Hide report" } else { "View report" };
+                if ui.button(format!("{} {}", NeonIcons::INFO, details)).clicked() {
+                    self.show_details = !self.show_details;
+                }
+                if ui.button("Copy report").clicked() {
+                    ui.ctx().copy_text(report.to_text());
+                }
+                if ui.button(format!("{} Submit report", NeonIcons::UPLOAD))
+                    .on_hover_text("Open a prefilled issue; nothing is sent until you submit it there")
+                    .clicked() {
+                    self.actions.push(PageAction::OpenInNewTab(Self::issue_url(report)));
+                }
+            });
+            if self.show_details {
+                ui.add_space(8.0);
+                let mut text = report.to_text();
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut text)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY)
+                        .interactive(false));
+                });
+            }
+            ui.label(RichText::new(format!("Saved at {}", path.display())).size(11.0).color(NeonTheme::muted_text()));
+        });
+    }
+
+    fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.actions)
+    }
+}
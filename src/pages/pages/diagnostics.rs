@@ -0,0 +1,165 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use std::sync::{Arc, Mutex};
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::networking::diagnostics::{self, DiagnosticStep, DiagnosticsConfig, DiagnosticsReport, StepResult};
+use crate::pages::{CustomPage, components};
+use crate::storage::Preferences;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+pub struct DiagnosticsPage {
+    url: String,
+    title: String,
+    config: DiagnosticsConfig,
+    // Results of the running check, filled in step by step
+    progress: Arc<Mutex<Vec<StepResult>>>,
+    task: Option<TaskHandle<DiagnosticsReport>>,
+    report: Option<DiagnosticsReport>,
+}
+
+impl DiagnosticsPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://diagnostics".to_string(),
+            title: "Diagnostics".to_string(),
+            config: DiagnosticsConfig {
+                endpoint: Preferences::current().diagnostics_endpoint.clone(),
+                ..DiagnosticsConfig::default()
+            },
+            progress: Arc::new(Mutex::new(Vec::new())),
+            task: None,
+            report: None,
+        }
+    }
+
+    fn start(&mut self) {
+        let endpoint = self.config.endpoint.trim().to_string();
+        Preferences::update(|prefs| prefs.diagnostics_endpoint = endpoint);
+
+        self.report = None;
+        self.progress = Arc::new(Mutex::new(Vec::new()));
+        let config = self.config.clone();
+        let progress = self.progress.clone();
+        self.task = Some(Executor::shared().spawn(TaskKind::Network, diagnostics::run(config, progress)));
+    }
+
+    fn render_results(ui: &mut Ui, results: &[StepResult], running: bool) {
+        egui::Grid::new("diagnostic_steps")
+            .num_columns(4)
+            .spacing([24.0, 8.0])
+            .show(ui, |ui| {
+                for (index, step) in DiagnosticStep::ALL.iter().enumerate() {
+                    let result = results.iter().find(|r| r.step == *step);
+                    let (icon, color) = match result {
+                        Some(r) if r.passed => (NeonIcons::CHECK_CIRCLE, NeonTheme::success_color()),
+                        Some(_) => (NeonIcons::X_CIRCLE, NeonTheme::error_color()),
+                        None => ("·", NeonTheme::muted_text()),
+                    };
+                    ui.label(RichText::new(icon).color(color));
+                    ui.label(RichText::new(step.label()).color(NeonTheme::primary_text()));
+                    match result {
+                        Some(r) => {
+                            ui.label(RichText::new(format!("{} ms", r.duration.as_millis())).color(NeonTheme::neon_cyan()));
+                            ui.label(RichText::new(&r.detail).color(NeonTheme::secondary_text()));
+                        }
+                        None if running && index == results.len() => {
+                            ui.spinner();
+                            ui.label(RichText::new("Running…").color(NeonTheme::secondary_text()));
+                        }
+                        None => {
+                            ui.label("");
+                            ui.label(RichText::new(if running { "Waiting" } else { "Skipped" }).color(NeonTheme::muted_text()));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+impl Default for DiagnosticsPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for DiagnosticsPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
+        components::page_header(
+            ui,
+            "Network Diagnostics",
+            Some("Check DNS, connections, TLS and throughput against a speed test server")
+        );
+
+        if let Some(Ok(report)) = self.task.as_mut().and_then(|task| task.try_take()) {
+            self.report = Some(report);
+        }
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            self.task = None;
+        }
+        let running = self.task.is_some();
+
+        components::section_header(ui, NeonIcons::WRENCH, "Endpoint");
+        components::card_container(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add_enabled(!running, egui::TextEdit::singleline(&mut self.config.endpoint).desired_width(320.0));
+                if ui.small_button("Default").clicked() {
+                    self.config.endpoint = diagnostics::DEFAULT_ENDPOINT.to_string();
+                }
+            });
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                let mut download_mb = self.config.download_bytes / 1024 / 1024;
+                let mut upload_mb = self.config.upload_bytes / 1024 / 1024;
+                ui.label(RichText::new(format!("{} Download", NeonIcons::DOWNLOAD)).color(NeonTheme::secondary_text()));
+                ui.add_enabled(!running, egui::DragValue::new(&mut download_mb).range(1..=100).suffix(" MB"));
+                ui.label(RichText::new(format!("{} Upload", NeonIcons::UPLOAD)).color(NeonTheme::secondary_text()));
+                ui.add_enabled(!running, egui::DragValue::new(&mut upload_mb).range(1..=50).suffix(" MB"));
+                self.config.download_bytes = download_mb * 1024 * 1024;
+                self.config.upload_bytes = upload_mb * 1024 * 1024;
+            });
+            ui.add_space(8.0);
+            let label = if running { "Running…" } else { "Run diagnostics" };
+            if ui.add_enabled(!running, egui::Button::new(RichText::new(format!("{} {}", NeonIcons::PLAY, label)))).clicked() {
+                self.start();
+            }
+        });
+
+        components::section_header(ui, NeonIcons::GLOBE, "Results");
+        components::card_container(ui, |ui| {
+            let results = self.progress.lock().map(|progress| progress.clone()).unwrap_or_default();
+            if results.is_empty() && !running {
+                ui.label(RichText::new("Run the diagnostics to measure this connection.").color(NeonTheme::secondary_text()));
+                return;
+            }
+            Self::render_results(ui, &results, running);
+
+            if let Some(report) = &self.report {
+                ui.add_space(12.0);
+                ui.horizontal(|ui| {
+                    components::status_indicator(ui, report.passed(), if report.passed() {
+                        "All checks passed"
+                    } else {
+                        "Some checks failed"
+                    });
+                    if ui.button(RichText::new(format!("{} Copy report", NeonIcons::BOOKMARKS))).clicked() {
+                        ui.ctx().copy_text(report.to_text());
+                    }
+                });
+            }
+        });
+
+        // Steps finish on a worker thread, so poll while they run
+        if running {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+    }
+}
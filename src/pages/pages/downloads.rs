@@ -2,12 +2,18 @@ use eframe::egui::{Context, Ui, RichText, Layout, Align};
 use crate::pages::{CustomPage, components};
 use crate::ui::theme::NeonTheme;
 use crate::ui::icons::NeonIcons;
+use crate::ui::i18n::{self, tr};
 use std::time::SystemTime;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::path::{Path, PathBuf};
 use crate::engine::download_manager::{DownloadManager, DownloadEvent};
+use crate::engine::executor::{Executor, TaskKind};
 use crate::storage::DownloadState;
 
+// Number of speed samples (one per sync tick) kept for the bandwidth graphs
+const SPEED_HISTORY_LEN: usize = 60;
+
 #[derive(Debug, Clone)]
 pub struct DownloadItem {
     pub id: String,
@@ -21,6 +27,13 @@ pub struct DownloadItem {
     pub mime_type: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DownloadAction {
+    Pause,
+    Resume,
+    Cancel,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DownloadStatus {
     InProgress,
@@ -51,6 +64,9 @@ pub struct DownloadsPage {
     selected_download: Option<String>,
     download_manager: Option<Arc<Mutex<DownloadManager>>>,
     last_update: SystemTime,
+    // Recent speed samples in bytes/second, per download and summed across all active downloads
+    speed_history: HashMap<String, VecDeque<f32>>,
+    aggregate_history: VecDeque<f32>,
 }
 
 impl DownloadsPage {
@@ -109,6 +125,8 @@ impl DownloadsPage {
             selected_download: None,
             download_manager,
             last_update: SystemTime::now(),
+            speed_history: HashMap::new(),
+            aggregate_history: VecDeque::with_capacity(SPEED_HISTORY_LEN),
         }
     }
     
@@ -124,11 +142,11 @@ impl DownloadsPage {
             
             match DownloadManager::new(&db_path) {
                 Ok(manager) => {
-                    println!("Download manager initialized with database: {:?}", db_path);
+                    log::info!("Download manager initialized with database: {:?}", db_path);
                     Some(Arc::new(Mutex::new(manager)))
                 }
                 Err(e) => {
-                    eprintln!("Failed to initialize download manager: {}", e);
+                    log::error!("Failed to initialize download manager: {}", e);
                     None
                 }
             }
@@ -141,6 +159,24 @@ impl DownloadsPage {
         self.download_manager = Some(manager);
     }
     
+    // Run a pause/resume/cancel on the shared executor instead of blocking the UI thread
+    fn spawn_download_action(&self, id: &str, action: DownloadAction) {
+        let Some(manager) = self.download_manager.as_ref().and_then(|m| m.lock().ok().map(|m| m.clone())) else {
+            return;
+        };
+        let id = id.to_string();
+        Executor::shared().spawn(TaskKind::Download, async move {
+            let result = match action {
+                DownloadAction::Pause => manager.pause_download(&id).await,
+                DownloadAction::Resume => manager.resume_download(&id).await,
+                DownloadAction::Cancel => manager.cancel_download(&id).await,
+            };
+            if let Err(e) = result {
+                log::warn!("Download {:?} failed for {}: {}", action, id, e);
+            }
+        });
+    }
+    
     fn sync_with_manager(&mut self) {
         // Only update every second to avoid excessive DB queries
         if let Ok(elapsed) = self.last_update.elapsed() {
@@ -188,6 +224,24 @@ impl DownloadsPage {
                     }
                 }
                 
+                // Sample current speeds for the sparklines and the aggregate graph
+                let mut total_bps = 0.0;
+                for item in self.downloads.iter().filter(|d| d.status == DownloadStatus::InProgress) {
+                    let speed = mgr.get_progress(&item.id)
+                        .map(|p| p.speed_bps as f32)
+                        .unwrap_or(0.0);
+                    total_bps += speed;
+                    let history = self.speed_history.entry(item.id.clone())
+                        .or_insert_with(|| VecDeque::with_capacity(SPEED_HISTORY_LEN));
+                    Self::push_sample(history, speed);
+                }
+                // Finished, paused and removed downloads don't draw a sparkline
+                let downloads = &self.downloads;
+                self.speed_history.retain(|id, _| {
+                    downloads.iter().any(|d| &d.id == id && d.status == DownloadStatus::InProgress)
+                });
+                Self::push_sample(&mut self.aggregate_history, total_bps);
+                
                 // Sync downloads from database
                 if let Ok(records) = mgr.get_download_history() {
                     // Update or add download items
@@ -215,25 +269,71 @@ impl DownloadsPage {
         }
     }
     
-    fn format_file_size(bytes: u64) -> String {
-        if bytes < 1024 {
-            format!("{} B", bytes)
-        } else if bytes < 1024 * 1024 {
-            format!("{:.1} KB", bytes as f64 / 1024.0)
-        } else if bytes < 1024 * 1024 * 1024 {
-            format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
-        } else {
-            format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    // The histories are rings as wide as the sparklines: the oldest sample drops off as a new one arrives
+    fn push_sample(history: &mut VecDeque<f32>, sample: f32) {
+        if history.len() == SPEED_HISTORY_LEN {
+            history.pop_front();
         }
+        history.push_back(sample);
+    }
+    
+    fn format_speed(bps: f32) -> String {
+        format!("{}/s", Self::format_file_size(bps as u64))
+    }
+    
+    fn render_bandwidth_graph(&self, ui: &mut Ui) {
+        let current = self.aggregate_history.back().copied().unwrap_or(0.0);
+        let peak = self.aggregate_history.iter().cloned().fold(0.0_f32, f32::max);
+        let average = self.aggregate_history.iter().sum::<f32>() / self.aggregate_history.len().max(1) as f32;
+        let throttle = self.download_manager.as_ref()
+            .and_then(|mgr| mgr.lock().ok().and_then(|m| m.bandwidth_throttle()));
+        
+        components::card_container(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(tr("Now: {speed}").replace("{speed}", &Self::format_speed(current)))
+                    .strong()
+                    .color(NeonTheme::neon_cyan()));
+                ui.add_space(16.0);
+                ui.label(RichText::new(tr("Peak: {speed}").replace("{speed}", &Self::format_speed(peak)))
+                    .color(NeonTheme::secondary_text()));
+                ui.add_space(16.0);
+                ui.label(RichText::new(tr("Average: {speed}").replace("{speed}", &Self::format_speed(average)))
+                    .color(NeonTheme::secondary_text()));
+                
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    // A throttle that is being hit explains slow downloads better than the server does
+                    let (text, color) = match throttle {
+                        Some(limit) if current >= limit as f32 * 0.9 => (
+                            tr("Limited by local throttle ({speed})").replace("{speed}", &Self::format_speed(limit as f32)),
+                            NeonTheme::warning_color(),
+                        ),
+                        Some(limit) => (
+                            tr("Throttle {speed} not reached - limited by server/network").replace("{speed}", &Self::format_speed(limit as f32)),
+                            NeonTheme::secondary_text(),
+                        ),
+                        None => (tr("No local throttle"), NeonTheme::secondary_text()),
+                    };
+                    ui.label(RichText::new(text).size(12.0).color(color));
+                });
+            });
+            
+            ui.add_space(8.0);
+            let width = ui.available_width();
+            components::sparkline(ui, self.aggregate_history.iter(), egui::vec2(width, 60.0), NeonTheme::neon_cyan());
+        });
+    }
+    
+    fn format_file_size(bytes: u64) -> String {
+        i18n::format_bytes(bytes)
     }
     
     fn get_status_icon_and_color(status: &DownloadStatus) -> (&'static str, egui::Color32) {
         match status {
-            DownloadStatus::InProgress => (NeonIcons::DOWNLOAD, NeonTheme::NEON_CYAN),
+            DownloadStatus::InProgress => (NeonIcons::DOWNLOAD, NeonTheme::neon_cyan()),
             DownloadStatus::Completed => (NeonIcons::CHECK_CIRCLE, NeonTheme::success_color()),
             DownloadStatus::Paused => (NeonIcons::PAUSE, NeonTheme::warning_color()),
             DownloadStatus::Failed(_) => (NeonIcons::WARNING, NeonTheme::error_color()),
-            DownloadStatus::Cancelled => (NeonIcons::CROSS, NeonTheme::SECONDARY_TEXT),
+            DownloadStatus::Cancelled => (NeonIcons::CROSS, NeonTheme::secondary_text()),
         }
     }
 }
@@ -261,7 +361,7 @@ impl CustomPage for DownloadsPage {
         components::card_container(ui, |ui| {
             ui.horizontal(|ui| {
                 // Search box
-                ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::SECONDARY_TEXT));
+                ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::secondary_text()));
                 ui.add(egui::TextEdit::singleline(&mut self.search_query)
                     .hint_text("Search downloads...")
                     .desired_width(250.0));
@@ -274,7 +374,7 @@ impl CustomPage for DownloadsPage {
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     // Clear all button
                     if ui.button(RichText::new("Clear All Completed")
-                        .color(NeonTheme::SECONDARY_TEXT)).clicked() {
+                        .color(NeonTheme::secondary_text())).clicked() {
                         self.downloads.retain(|d| d.status != DownloadStatus::Completed);
                     }
                     
@@ -282,7 +382,7 @@ impl CustomPage for DownloadsPage {
                     
                     // Refresh button
                     if ui.button(RichText::new(format!("{} Refresh", NeonIcons::REFRESH))
-                        .color(NeonTheme::NEON_CYAN)).clicked() {
+                        .color(NeonTheme::neon_cyan())).clicked() {
                         // TODO: Refresh download list
                     }
                 });
@@ -291,6 +391,13 @@ impl CustomPage for DownloadsPage {
         
         ui.add_space(16.0);
         
+        // Aggregate bandwidth across all active downloads
+        if self.downloads.iter().any(|d| d.status == DownloadStatus::InProgress) || !self.aggregate_history.is_empty() {
+            components::section_header(ui, NeonIcons::CLOCK, "Bandwidth");
+            self.render_bandwidth_graph(ui);
+            ui.add_space(16.0);
+        }
+        
         // Downloads list
         components::section_header(ui, NeonIcons::DOWNLOAD, "Download History");
         
@@ -319,7 +426,7 @@ impl CustomPage for DownloadsPage {
                     ui.add_space(40.0);
                     ui.label(RichText::new(NeonIcons::DOWNLOAD)
                         .size(48.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     ui.add_space(16.0);
                     
                     let message = if self.downloads.is_empty() {
@@ -332,7 +439,7 @@ impl CustomPage for DownloadsPage {
                     
                     ui.label(RichText::new(message)
                         .size(18.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     ui.add_space(40.0);
                 });
             });
@@ -352,18 +459,18 @@ impl DownloadsPage {
         let is_selected = self.selected_download.as_ref() == Some(&download.id);
         
         let frame_color = if is_selected {
-            NeonTheme::NEON_CYAN.linear_multiply(0.3)
+            NeonTheme::neon_cyan().linear_multiply(0.3)
         } else {
-            NeonTheme::CARD_BG
+            NeonTheme::card_bg()
         };
         
         let response = egui::Frame::none()
             .fill(frame_color)
             .rounding(12.0)
             .stroke(egui::Stroke::new(1.0, if is_selected { 
-                NeonTheme::NEON_CYAN 
+                NeonTheme::neon_cyan() 
             } else { 
-                NeonTheme::BORDER_COLOR 
+                NeonTheme::border_color() 
             }))
             .inner_margin(16.0)
             .show(ui, |ui| {
@@ -380,20 +487,21 @@ impl DownloadsPage {
                             ui.label(RichText::new(&download.filename)
                                 .size(16.0)
                                 .strong()
-                                .color(NeonTheme::PRIMARY_TEXT));
+                                .color(NeonTheme::primary_text()));
                                 
                             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                                 ui.label(RichText::new(Self::format_file_size(download.file_size))
-                                    .color(NeonTheme::SECONDARY_TEXT));
+                                    .color(NeonTheme::secondary_text()));
                             });
                         });
                         
                         ui.add_space(4.0);
                         
-                        // URL
-                        ui.label(RichText::new(&download.url)
+                        // URL and when the download started
+                        let started = chrono::DateTime::<chrono::Local>::from(download.start_time);
+                        ui.label(RichText::new(format!("{} • {}", download.url, i18n::format_date_time(started)))
                             .size(12.0)
-                            .color(NeonTheme::SECONDARY_TEXT));
+                            .color(NeonTheme::secondary_text()));
                         
                         ui.add_space(8.0);
                         
@@ -418,28 +526,23 @@ impl DownloadsPage {
                                 };
                                 
                                 let progress_text = if let Some(info) = progress_info {
-                                    let speed_str = if info.speed_bps > 1024 * 1024 {
-                                        format!("{:.2} MB/s", info.speed_bps as f64 / (1024.0 * 1024.0))
-                                    } else if info.speed_bps > 1024 {
-                                        format!("{:.1} KB/s", info.speed_bps as f64 / 1024.0)
-                                    } else {
-                                        format!("{} B/s", info.speed_bps)
-                                    };
+                                    let speed_str = Self::format_speed(info.speed_bps as f32);
                                     
                                     let eta_str = if let Some(eta) = info.eta_seconds {
-                                        if eta > 3600 {
-                                            format!(" - {}h {}m remaining", eta / 3600, (eta % 3600) / 60)
+                                        let remaining = if eta > 3600 {
+                                            format!("{}h {}m", eta / 3600, (eta % 3600) / 60)
                                         } else if eta > 60 {
-                                            format!(" - {}m {}s remaining", eta / 60, eta % 60)
+                                            format!("{}m {}s", eta / 60, eta % 60)
                                         } else {
-                                            format!(" - {}s remaining", eta)
-                                        }
+                                            format!("{}s", eta)
+                                        };
+                                        format!(" - {}", tr("{time} remaining").replace("{time}", &remaining))
                                     } else {
                                         String::new()
                                     };
                                     
-                                    format!("{:.1}% - {} / {} - {}{}", 
-                                        info.progress_percent,
+                                    format!("{}% - {} / {} - {}{}", 
+                                        i18n::format_number(info.progress_percent as f64, 1),
                                         Self::format_file_size(info.downloaded_bytes),
                                         Self::format_file_size(info.total_bytes.unwrap_or(download.file_size)),
                                         speed_str,
@@ -453,42 +556,28 @@ impl DownloadsPage {
                                 
                                 let progress_bar = egui::ProgressBar::new(progress)
                                     .text(progress_text)
-                                    .fill(NeonTheme::NEON_CYAN);
+                                    .fill(NeonTheme::neon_cyan());
                                 ui.add(progress_bar);
                                 
+                                // Recent speed for this download
+                                if let Some(samples) = self.speed_history.get(&download.id) {
+                                    if samples.len() > 1 {
+                                        ui.add_space(4.0);
+                                        components::sparkline(ui, samples.iter(), egui::vec2(180.0, 24.0), NeonTheme::neon_purple());
+                                    }
+                                }
+                                
                                 // Add pause and cancel buttons
                                 ui.add_space(4.0);
                                 ui.horizontal(|ui| {
                                     if ui.button(RichText::new(format!("{} Pause", NeonIcons::PAUSE))
                                         .color(NeonTheme::warning_color())).clicked() {
-                                        if let Some(ref mgr) = self.download_manager {
-                                            let mgr = mgr.clone();
-                                            let id = download.id.clone();
-                                            std::thread::spawn(move || {
-                                                let rt = tokio::runtime::Runtime::new().unwrap();
-                                                rt.block_on(async {
-                                                    if let Ok(m) = mgr.lock() {
-                                                        let _ = m.pause_download(&id).await;
-                                                    }
-                                                });
-                                            });
-                                        }
+                                        self.spawn_download_action(&download.id, DownloadAction::Pause);
                                     }
                                     
                                     if ui.button(RichText::new(format!("{} Cancel", NeonIcons::CROSS))
                                         .color(NeonTheme::error_color())).clicked() {
-                                        if let Some(ref mgr) = self.download_manager {
-                                            let mgr = mgr.clone();
-                                            let id = download.id.clone();
-                                            std::thread::spawn(move || {
-                                                let rt = tokio::runtime::Runtime::new().unwrap();
-                                                rt.block_on(async {
-                                                    if let Ok(m) = mgr.lock() {
-                                                        let _ = m.cancel_download(&id).await;
-                                                    }
-                                                });
-                                            });
-                                        }
+                                        self.spawn_download_action(&download.id, DownloadAction::Cancel);
                                     }
                                 });
                             },
@@ -499,12 +588,12 @@ impl DownloadsPage {
                                     
                                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                                         if ui.button(RichText::new(format!("{} Open Folder", NeonIcons::FOLDER_OPEN))
-                                            .color(NeonTheme::NEON_CYAN)).clicked() {
+                                            .color(NeonTheme::neon_cyan())).clicked() {
                                             Self::open_file_location(&download.download_path);
                                         }
                                         
                                         if ui.button(RichText::new(format!("{} Open File", NeonIcons::PLAY))
-                                            .color(NeonTheme::NEON_CYAN)).clicked() {
+                                            .color(NeonTheme::neon_cyan())).clicked() {
                                             Self::open_file(&download.download_path);
                                         }
                                     });
@@ -517,35 +606,13 @@ impl DownloadsPage {
                                     
                                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                                         if ui.button(RichText::new(format!("{} Resume", NeonIcons::PLAY))
-                                            .color(NeonTheme::NEON_CYAN)).clicked() {
-                                            if let Some(ref mgr) = self.download_manager {
-                                                let mgr = mgr.clone();
-                                                let id = download.id.clone();
-                                                std::thread::spawn(move || {
-                                                    let rt = tokio::runtime::Runtime::new().unwrap();
-                                                    rt.block_on(async {
-                                                        if let Ok(m) = mgr.lock() {
-                                                            let _ = m.resume_download(&id).await;
-                                                        }
-                                                    });
-                                                });
-                                            }
+                                            .color(NeonTheme::neon_cyan())).clicked() {
+                                            self.spawn_download_action(&download.id, DownloadAction::Resume);
                                         }
                                         
                                         if ui.button(RichText::new(format!("{} Cancel", NeonIcons::CROSS))
                                             .color(NeonTheme::error_color())).clicked() {
-                                            if let Some(ref mgr) = self.download_manager {
-                                                let mgr = mgr.clone();
-                                                let id = download.id.clone();
-                                                std::thread::spawn(move || {
-                                                    let rt = tokio::runtime::Runtime::new().unwrap();
-                                                    rt.block_on(async {
-                                                        if let Ok(m) = mgr.lock() {
-                                                            let _ = m.cancel_download(&id).await;
-                                                        }
-                                                    });
-                                                });
-                                            }
+                                            self.spawn_download_action(&download.id, DownloadAction::Cancel);
                                         }
                                     });
                                 });
@@ -557,19 +624,8 @@ impl DownloadsPage {
                                     
                                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                                         if ui.button(RichText::new(format!("{} Retry", NeonIcons::REFRESH))
-                                            .color(NeonTheme::NEON_CYAN)).clicked() {
-                                            if let Some(ref mgr) = self.download_manager {
-                                                let mgr = mgr.clone();
-                                                let id = download.id.clone();
-                                                std::thread::spawn(move || {
-                                                    let rt = tokio::runtime::Runtime::new().unwrap();
-                                                    rt.block_on(async {
-                                                        if let Ok(m) = mgr.lock() {
-                                                            let _ = m.resume_download(&id).await;
-                                                        }
-                                                    });
-                                                });
-                                            }
+                                            .color(NeonTheme::neon_cyan())).clicked() {
+                                            self.spawn_download_action(&download.id, DownloadAction::Resume);
                                         }
                                         
                                         if ui.button(RichText::new(format!("{} Remove", NeonIcons::DELETE))
@@ -586,7 +642,7 @@ impl DownloadsPage {
                             },
                             DownloadStatus::Cancelled => {
                                 ui.label(RichText::new("✕ Download cancelled")
-                                    .color(NeonTheme::SECONDARY_TEXT));
+                                    .color(NeonTheme::secondary_text()));
                             },
                         }
                     });
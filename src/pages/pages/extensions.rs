@@ -1,9 +1,24 @@
-use eframe::egui::{Context, Ui};
+use eframe::egui::{Context, Ui, RichText, Layout, Align};
 use crate::pages::{CustomPage, components};
+use crate::ui::theme::NeonTheme;
+use crate::ui::icons::NeonIcons;
+use crate::engine::WebPage;
+use crate::extensions::{ExtensionRegistry, InstalledExtension};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 pub struct ExtensionsPage {
     url: String,
     title: String,
+    registry: Arc<Mutex<ExtensionRegistry>>,
+    developer_mode: bool,
+    unpacked_path: String,
+    status_message: Option<(String, bool)>, // (message, is_error)
+    icon_textures: HashMap<String, Option<egui::TextureHandle>>,
+    open_options: HashMap<String, WebPage>,
+    // Extension waiting for the user to confirm its removal
+    confirm_uninstall: Option<String>,
 }
 
 impl ExtensionsPage {
@@ -11,28 +26,290 @@ impl ExtensionsPage {
         Self {
             url: "neon://extensions".to_string(),
             title: "Extensions".to_string(),
+            registry: ExtensionRegistry::shared(),
+            developer_mode: false,
+            unpacked_path: String::new(),
+            status_message: None,
+            icon_textures: HashMap::new(),
+            open_options: HashMap::new(),
+            confirm_uninstall: None,
         }
     }
+
+    fn load_icon(ctx: &Context, extension: &InstalledExtension) -> Option<egui::TextureHandle> {
+        let bytes = std::fs::read(extension.icon_path()?).ok()?;
+        let image = image::load_from_memory(&bytes).ok()?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+        Some(ctx.load_texture(
+            format!("extension-icon-{}", extension.id),
+            color_image,
+            egui::TextureOptions::LINEAR,
+        ))
+    }
+
+    fn render_icon(&mut self, ui: &mut Ui, ctx: &Context, extension: &InstalledExtension) {
+        let texture = self.icon_textures
+            .entry(extension.id.clone())
+            .or_insert_with(|| Self::load_icon(ctx, extension));
+
+        if let Some(texture) = texture {
+            ui.add(egui::Image::new((texture.id(), egui::vec2(40.0, 40.0))));
+        } else {
+            // Fall back to a lettered badge when there is no usable icon
+            let letter = extension.manifest.name.chars().next().unwrap_or('?').to_uppercase().to_string();
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(40.0, 40.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 8.0, NeonTheme::neon_purple());
+            ui.painter().text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                letter,
+                egui::FontId::proportional(20.0),
+                NeonTheme::primary_text(),
+            );
+        }
+    }
+
+    fn render_extension(&mut self, ui: &mut Ui, ctx: &Context, extension: &InstalledExtension) {
+        let mut enabled = extension.enabled;
+
+        components::card_container(ui, |ui| {
+            ui.horizontal(|ui| {
+                self.render_icon(ui, ctx, extension);
+                ui.add_space(12.0);
+
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(&extension.manifest.name)
+                            .size(16.0)
+                            .strong()
+                            .color(NeonTheme::primary_text()));
+                        ui.label(RichText::new(format!("v{}", extension.manifest.version))
+                            .color(NeonTheme::secondary_text()));
+                        if extension.unpacked {
+                            ui.label(RichText::new("Unpacked").size(11.0).color(NeonTheme::warning_color()));
+                        }
+                    });
+
+                    if !extension.manifest.description.is_empty() {
+                        ui.label(RichText::new(&extension.manifest.description).color(NeonTheme::secondary_text()));
+                    }
+
+                    let permissions = if extension.manifest.permissions.is_empty() {
+                        "No special permissions".to_string()
+                    } else {
+                        extension.manifest.permissions.join(", ")
+                    };
+                    ui.label(RichText::new(format!("{} {}", NeonIcons::SHIELD_CHECK, permissions))
+                        .size(12.0)
+                        .color(NeonTheme::muted_text()));
+                    let sites = if extension.has_permission(crate::extensions::match_pattern::ALL_URLS) {
+                        Some("all sites".to_string())
+                    } else if !extension.manifest.host_permissions.is_empty() {
+                        Some(extension.manifest.host_permissions.join(", "))
+                    } else {
+                        None
+                    };
+                    if let Some(sites) = sites {
+                        ui.label(RichText::new(format!("{} Can read and change {}", NeonIcons::GLOBE, sites))
+                            .size(12.0)
+                            .color(NeonTheme::muted_text()));
+                    }
+                    if extension.rules_path().is_some() {
+                        ui.label(RichText::new(format!("{} Can block and redirect requests", NeonIcons::PROHIBIT))
+                            .size(12.0)
+                            .color(NeonTheme::muted_text()));
+                    }
+
+                    if self.developer_mode {
+                        ui.label(RichText::new(format!("ID: {}  •  {}", extension.id, extension.path.display()))
+                            .size(11.0)
+                            .monospace()
+                            .color(NeonTheme::muted_text()));
+                    }
+                });
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if self.confirm_uninstall.as_deref() == Some(extension.id.as_str()) {
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_uninstall = None;
+                        }
+                        let label = if extension.unpacked { "Remove" } else { "Uninstall" };
+                        if ui.button(RichText::new(format!("{} {}", NeonIcons::TRASH, label)).color(NeonTheme::error_color())).clicked() {
+                            self.uninstall(extension);
+                        }
+                        return;
+                    }
+                    if ui.button(RichText::new(NeonIcons::TRASH).color(NeonTheme::secondary_text()))
+                        .on_hover_text(if extension.unpacked { "Remove" } else { "Uninstall" })
+                        .clicked() {
+                        self.confirm_uninstall = Some(extension.id.clone());
+                    }
+                    ui.checkbox(&mut enabled, "Enabled");
+
+                    if let Some(options_path) = extension.options_path() {
+                        let is_open = self.open_options.contains_key(&extension.id);
+                        let label = if is_open { "Hide options" } else { "Options" };
+                        if ui.button(RichText::new(format!("{} {}", NeonIcons::GEAR, label))
+                            .color(NeonTheme::neon_cyan())).clicked() {
+                            if is_open {
+                                self.open_options.remove(&extension.id);
+                            } else {
+                                match std::fs::read_to_string(&options_path) {
+                                    Ok(html) => {
+                                        self.open_options.insert(extension.id.clone(), WebPage::from_html(&html, None));
+                                    }
+                                    Err(e) => {
+                                        self.status_message = Some((format!("Cannot open options page: {}", e), true));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+
+            // Options pages are rendered with the browser engine, inline under the extension
+            if let Some(options) = self.open_options.get(&extension.id) {
+                ui.separator();
+                options.render(ui);
+            }
+        });
+
+        if enabled != extension.enabled {
+            if let Ok(mut registry) = self.registry.lock() {
+                if let Err(e) = registry.set_enabled(&extension.id, enabled) {
+                    self.status_message = Some((e.to_string(), true));
+                }
+            }
+        }
+    }
+
+    // Installed extensions are deleted from disk; unpacked ones only stop being loaded
+    fn uninstall(&mut self, extension: &InstalledExtension) {
+        self.confirm_uninstall = None;
+        let result = self.registry.lock()
+            .map_err(|_| anyhow::anyhow!("Extension registry is unavailable"))
+            .and_then(|mut registry| registry.uninstall(&extension.id));
+        self.status_message = Some(match result {
+            Ok(()) => {
+                self.icon_textures.remove(&extension.id);
+                self.open_options.remove(&extension.id);
+                (format!("Removed {}", extension.manifest.name), false)
+            }
+            Err(e) => (e.to_string(), true),
+        });
+    }
+
+    fn render_developer_tools(&mut self, ui: &mut Ui) {
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new("Load an extension from a local directory containing a manifest.json")
+                .color(NeonTheme::secondary_text()));
+            ui.add_space(8.0);
+
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(NeonIcons::FOLDER).color(NeonTheme::secondary_text()));
+                ui.add(egui::TextEdit::singleline(&mut self.unpacked_path)
+                    .hint_text("/path/to/extension")
+                    .desired_width(360.0));
+
+                if ui.button(RichText::new(format!("{} Load unpacked", NeonIcons::UPLOAD))
+                    .color(NeonTheme::neon_cyan())).clicked() {
+                    let path = PathBuf::from(self.unpacked_path.trim());
+                    let result = self.registry.lock()
+                        .map_err(|_| anyhow::anyhow!("Extension registry is unavailable"))
+                        .and_then(|mut registry| registry.load_unpacked(&path));
+                    self.status_message = Some(match result {
+                        Ok(id) => {
+                            self.unpacked_path.clear();
+                            (format!("Loaded extension {}", id), false)
+                        }
+                        Err(e) => (e.to_string(), true),
+                    });
+                }
+
+                if ui.button(RichText::new(format!("{} Reload all", NeonIcons::REFRESH))
+                    .color(NeonTheme::secondary_text())).clicked() {
+                    if let Ok(mut registry) = self.registry.lock() {
+                        registry.reload();
+                    }
+                    self.icon_textures.clear();
+                    self.open_options.clear();
+                }
+            });
+        });
+    }
 }
 
 impl CustomPage for ExtensionsPage {
     fn get_url(&self) -> &str {
         &self.url
     }
-    
+
     fn get_title(&self) -> &str {
         &self.title
     }
-    
-    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
         components::page_header(
-            ui, 
-            "Extensions", 
+            ui,
+            "Extensions",
             Some("Manage browser extensions and add-ons")
         );
-        
-        components::card_container(ui, |ui| {
-            ui.label("Extensions page implementation coming soon...");
+
+        // Snapshot the registry so the lock is not held while rendering
+        let (extensions, load_errors, extensions_dir) = match self.registry.lock() {
+            Ok(registry) => (
+                registry.list().to_vec(),
+                registry.load_errors().to_vec(),
+                registry.extensions_dir().display().to_string(),
+            ),
+            Err(_) => (Vec::new(), Vec::new(), String::new()),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{} installed", extensions.len()))
+                .color(NeonTheme::secondary_text()));
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                ui.checkbox(&mut self.developer_mode, "Developer mode");
+            });
         });
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+
+        if self.developer_mode {
+            components::section_header(ui, NeonIcons::WRENCH, "Developer");
+            self.render_developer_tools(ui);
+
+            for error in &load_errors {
+                ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error))
+                    .size(12.0)
+                    .color(NeonTheme::warning_color()));
+            }
+        }
+
+        components::section_header(ui, NeonIcons::PACKAGE, "Installed Extensions");
+
+        if extensions.is_empty() {
+            components::card_container(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(24.0);
+                    ui.label(RichText::new("No extensions installed")
+                        .size(18.0)
+                        .color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new(format!("Place extensions in {} or enable developer mode to load one", extensions_dir))
+                        .color(NeonTheme::muted_text()));
+                    ui.add_space(24.0);
+                });
+            });
+        } else {
+            for extension in &extensions {
+                self.render_extension(ui, ctx, extension);
+                ui.add_space(8.0);
+            }
+        }
     }
-}
\ No newline at end of file
+}
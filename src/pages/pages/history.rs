@@ -2,7 +2,16 @@ use eframe::egui::{Context, Ui, RichText, Layout, Align};
 use crate::pages::{CustomPage, components};
 use crate::ui::theme::NeonTheme;
 use crate::ui::icons::NeonIcons;
-use std::time::{SystemTime, Duration};
+use crate::ui::i18n::{self, tr, trn};
+use crate::storage::{HistoryDatabase, HistoryOrder, HistoryQuery, HistoryStats};
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use chrono::{Datelike, Local, NaiveDate, Utc};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+// Most entries the list shows at once; searching narrows it down
+const LIST_LIMIT: usize = 500;
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
 
 #[derive(Debug, Clone)]
 pub struct HistoryItem {
@@ -23,6 +32,12 @@ pub enum TimeFilter {
     AllTime,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryView {
+    List,
+    Statistics,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SortBy {
     VisitTime,
@@ -31,6 +46,22 @@ pub enum SortBy {
     VisitCount,
 }
 
+// What the shown list was read for; when any of it changes the list is read again
+#[derive(Clone, PartialEq)]
+struct ListingKey {
+    revision: u64,
+    search: String,
+    time_filter: TimeFilter,
+    sort_by: SortBy,
+    ascending: bool,
+}
+
+// The list as read on the executor, and whether there's any history at all
+struct Listing {
+    items: Vec<HistoryItem>,
+    has_history: bool,
+}
+
 pub struct HistoryPage {
     url: String,
     title: String,
@@ -41,79 +72,142 @@ pub struct HistoryPage {
     ascending: bool,
     selected_items: Vec<String>,
     show_details: bool,
+    view: HistoryView,
+    history: Option<Arc<HistoryDatabase>>,
+    has_history: bool,
+    listed: Option<ListingKey>,
+    listing_task: Option<(ListingKey, TaskHandle<anyhow::Result<Listing>>)>,
+    stats: HistoryStats,
+    // Store revision the statistics were computed at
+    stats_revision: Option<u64>,
+    stats_task: Option<(u64, TaskHandle<anyhow::Result<HistoryStats>>)>,
 }
 
 impl HistoryPage {
     pub fn new() -> Self {
-        // Create sample history items for demonstration
-        let mut history_items = Vec::new();
-        let now = SystemTime::now();
-        
-        // Recent items
-        history_items.push(HistoryItem {
-            id: "hist_1".to_string(),
-            title: "GitHub - The complete developer platform".to_string(),
-            url: "https://github.com".to_string(),
-            visit_time: now,
-            visit_count: 15,
-            favicon_url: Some("https://github.com/favicon.ico".to_string()),
-        });
-        
-        history_items.push(HistoryItem {
-            id: "hist_2".to_string(),
-            title: "Rust Programming Language".to_string(),
-            url: "https://www.rust-lang.org/".to_string(),
-            visit_time: now - Duration::from_secs(3600), // 1 hour ago
-            visit_count: 8,
-            favicon_url: Some("https://www.rust-lang.org/favicon.ico".to_string()),
-        });
-        
-        history_items.push(HistoryItem {
-            id: "hist_3".to_string(),
-            title: "MDN Web Docs".to_string(),
-            url: "https://developer.mozilla.org/".to_string(),
-            visit_time: now - Duration::from_secs(7200), // 2 hours ago
-            visit_count: 23,
-            favicon_url: Some("https://developer.mozilla.org/favicon.ico".to_string()),
-        });
-        
-        history_items.push(HistoryItem {
-            id: "hist_4".to_string(),
-            title: "Stack Overflow - Where Developers Learn".to_string(),
-            url: "https://stackoverflow.com".to_string(),
-            visit_time: now - Duration::from_secs(86400), // Yesterday
-            visit_count: 42,
-            favicon_url: Some("https://stackoverflow.com/favicon.ico".to_string()),
-        });
-        
-        history_items.push(HistoryItem {
-            id: "hist_5".to_string(),
-            title: "Crates.io: Rust Package Registry".to_string(),
-            url: "https://crates.io".to_string(),
-            visit_time: now - Duration::from_secs(172800), // 2 days ago
-            visit_count: 12,
-            favicon_url: Some("https://crates.io/favicon.ico".to_string()),
-        });
-        
-        history_items.push(HistoryItem {
-            id: "hist_6".to_string(),
-            title: "The egui Book".to_string(),
-            url: "https://docs.rs/egui/latest/egui/".to_string(),
-            visit_time: now - Duration::from_secs(259200), // 3 days ago
-            visit_count: 6,
-            favicon_url: None,
-        });
-        
         Self {
             url: "neon://history".to_string(),
             title: "History".to_string(),
-            history_items,
+            history_items: Vec::new(),
             search_query: String::new(),
             time_filter: TimeFilter::AllTime,
             sort_by: SortBy::VisitTime,
             ascending: false,
             selected_items: Vec::new(),
             show_details: false,
+            view: HistoryView::List,
+            history: HistoryDatabase::shared(),
+            has_history: false,
+            listed: None,
+            listing_task: None,
+            stats: HistoryStats::default(),
+            stats_revision: None,
+            stats_task: None,
+        }
+    }
+    
+    /// Read the list again on the executor when the store changed or the search, filter or order did, and take
+    /// finished reads. Statistics are only recomputed while they're shown.
+    fn refresh(&mut self, ctx: &Context) {
+        let Some(history) = self.history.clone() else {
+            return;
+        };
+        
+        if let Some(result) = self.listing_task.as_mut().and_then(|(_, task)| task.try_take()) {
+            let (key, _) = self.listing_task.take().unwrap();
+            match result {
+                Ok(Ok(listing)) => {
+                    self.history_items = listing.items;
+                    self.has_history = listing.has_history;
+                    self.listed = Some(key);
+                }
+                Ok(Err(e)) => log::warn!("Failed to load history: {}", e),
+                Err(e) => log::warn!("Loading history stopped: {}", e),
+            }
+        }
+        let key = ListingKey {
+            revision: history.revision(),
+            search: self.search_query.trim().to_string(),
+            time_filter: self.time_filter.clone(),
+            sort_by: self.sort_by.clone(),
+            ascending: self.ascending,
+        };
+        let requested = self.listing_task.as_ref().map(|(key, _)| key).or(self.listed.as_ref());
+        if requested != Some(&key) {
+            let query = Self::query_for(&key);
+            let store = history.clone();
+            let task = Executor::shared().spawn_blocking(TaskKind::Storage, move || Self::read_listing(&store, &query));
+            self.listing_task = Some((key, task));
+        }
+        
+        if let Some(result) = self.stats_task.as_mut().and_then(|(_, task)| task.try_take()) {
+            let (revision, _) = self.stats_task.take().unwrap();
+            match result {
+                Ok(Ok(stats)) => {
+                    self.stats = stats;
+                    self.stats_revision = Some(revision);
+                }
+                Ok(Err(e)) => log::warn!("Failed to load visits: {}", e),
+                Err(e) => log::warn!("Computing history statistics stopped: {}", e),
+            }
+        }
+        let revision = history.revision();
+        let requested = self.stats_task.as_ref().map(|(revision, _)| *revision).or(self.stats_revision);
+        if self.view == HistoryView::Statistics && requested != Some(revision) {
+            let task = Executor::shared().spawn_blocking(TaskKind::Storage, move || {
+                history.get_visits().map(|visits| HistoryStats::compute(&visits, &Local))
+            });
+            self.stats_task = Some((revision, task));
+        }
+        
+        if self.listing_task.is_some() || self.stats_task.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+    }
+    
+    fn query_for(key: &ListingKey) -> HistoryQuery {
+        let now = Utc::now();
+        let hours_ago = |hours| Some(now - chrono::Duration::hours(hours));
+        let (visited_after, visited_before) = match key.time_filter {
+            TimeFilter::Today => (hours_ago(24), None),
+            TimeFilter::Yesterday => (hours_ago(48), hours_ago(24)),
+            TimeFilter::LastWeek => (hours_ago(7 * 24), None),
+            TimeFilter::LastMonth => (hours_ago(30 * 24), None),
+            TimeFilter::AllTime => (None, None),
+        };
+        HistoryQuery {
+            search: key.search.clone(),
+            visited_after,
+            visited_before,
+            order: match key.sort_by {
+                SortBy::VisitTime => HistoryOrder::LastVisit,
+                SortBy::Title => HistoryOrder::Title,
+                SortBy::Url => HistoryOrder::Url,
+                SortBy::VisitCount => HistoryOrder::VisitCount,
+            },
+            ascending: key.ascending,
+            limit: LIST_LIMIT,
+        }
+    }
+    
+    fn read_listing(history: &HistoryDatabase, query: &HistoryQuery) -> anyhow::Result<Listing> {
+        let items = history.query_entries(query)?.into_iter().map(|entry| HistoryItem {
+            id: entry.url.clone(),
+            title: if entry.title.is_empty() { entry.url.clone() } else { entry.title },
+            url: entry.url,
+            visit_time: SystemTime::from(entry.last_visit),
+            visit_count: entry.visit_count,
+            favicon_url: None,
+        }).collect();
+        Ok(Listing { items, has_history: history.has_visits()? })
+    }
+    
+    fn format_duration(duration: Duration) -> String {
+        let minutes = duration.as_secs() / 60;
+        if minutes < 60 {
+            format!("{}m", minutes)
+        } else {
+            format!("{}h {}m", minutes / 60, minutes % 60)
         }
     }
     
@@ -122,94 +216,27 @@ impl HistoryPage {
             let seconds = duration.as_secs();
             
             if seconds < 60 {
-                "Just now".to_string()
+                tr("Just now")
             } else if seconds < 3600 {
                 let minutes = seconds / 60;
-                format!("{} minute{} ago", minutes, if minutes == 1 { "" } else { "s" })
+                trn("{n} minute ago", "{n} minutes ago", minutes)
             } else if seconds < 86400 {
                 let hours = seconds / 3600;
-                format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" })
+                trn("{n} hour ago", "{n} hours ago", hours)
             } else if seconds < 604800 {
                 let days = seconds / 86400;
-                format!("{} day{} ago", days, if days == 1 { "" } else { "s" })
+                trn("{n} day ago", "{n} days ago", days)
             } else if seconds < 2592000 {
                 let weeks = seconds / 604800;
-                format!("{} week{} ago", weeks, if weeks == 1 { "" } else { "s" })
+                trn("{n} week ago", "{n} weeks ago", weeks)
             } else {
                 let months = seconds / 2592000;
-                format!("{} month{} ago", months, if months == 1 { "" } else { "s" })
+                trn("{n} month ago", "{n} months ago", months)
             }
         } else {
-            "Unknown".to_string()
+            tr("Unknown")
         }
     }
-    
-    fn get_filtered_and_sorted_items(&self) -> Vec<&HistoryItem> {
-        let now = SystemTime::now();
-        
-        let mut filtered: Vec<&HistoryItem> = self.history_items.iter()
-            .filter(|item| {
-                // Search filter
-                let matches_search = self.search_query.is_empty() || 
-                    item.title.to_lowercase().contains(&self.search_query.to_lowercase()) ||
-                    item.url.to_lowercase().contains(&self.search_query.to_lowercase());
-                
-                // Time filter
-                let matches_time = match self.time_filter {
-                    TimeFilter::AllTime => true,
-                    TimeFilter::Today => {
-                        if let Ok(duration) = now.duration_since(item.visit_time) {
-                            duration.as_secs() < 86400 // Last 24 hours
-                        } else {
-                            false
-                        }
-                    },
-                    TimeFilter::Yesterday => {
-                        if let Ok(duration) = now.duration_since(item.visit_time) {
-                            let seconds = duration.as_secs();
-                            seconds >= 86400 && seconds < 172800 // 24-48 hours ago
-                        } else {
-                            false
-                        }
-                    },
-                    TimeFilter::LastWeek => {
-                        if let Ok(duration) = now.duration_since(item.visit_time) {
-                            duration.as_secs() < 604800 // Last 7 days
-                        } else {
-                            false
-                        }
-                    },
-                    TimeFilter::LastMonth => {
-                        if let Ok(duration) = now.duration_since(item.visit_time) {
-                            duration.as_secs() < 2592000 // Last 30 days
-                        } else {
-                            false
-                        }
-                    },
-                };
-                
-                matches_search && matches_time
-            })
-            .collect();
-        
-        // Sort items
-        filtered.sort_by(|a, b| {
-            let ordering = match self.sort_by {
-                SortBy::VisitTime => a.visit_time.cmp(&b.visit_time),
-                SortBy::Title => a.title.cmp(&b.title),
-                SortBy::Url => a.url.cmp(&b.url),
-                SortBy::VisitCount => a.visit_count.cmp(&b.visit_count),
-            };
-            
-            if self.ascending {
-                ordering
-            } else {
-                ordering.reverse()
-            }
-        });
-        
-        filtered
-    }
 }
 
 impl CustomPage for HistoryPage {
@@ -221,20 +248,33 @@ impl CustomPage for HistoryPage {
         &self.title
     }
     
-    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
         components::page_header(
             ui, 
             "Browsing History", 
             Some("View and manage your browsing history")
         );
         
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.view, HistoryView::List, format!("{} Activity", NeonIcons::CLOCK));
+            ui.selectable_value(&mut self.view, HistoryView::Statistics, format!("{} Statistics", NeonIcons::STAR));
+        });
+        ui.add_space(12.0);
+        
+        self.refresh(ctx);
+        
+        if self.view == HistoryView::Statistics {
+            self.render_statistics(ui);
+            return;
+        }
+        
         // Top toolbar with search, filters, and actions
         components::card_container(ui, |ui| {
             ui.vertical(|ui| {
                 // First row: Search and time filter
                 ui.horizontal(|ui| {
                     // Search box
-                    ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::SECONDARY_TEXT));
+                    ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::secondary_text()));
                     ui.add(egui::TextEdit::singleline(&mut self.search_query)
                         .hint_text("Search history...")
                         .desired_width(300.0));
@@ -289,16 +329,24 @@ impl CustomPage for HistoryPage {
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                         if !self.selected_items.is_empty() {
                             if ui.button(format!("Delete Selected ({})", self.selected_items.len())).clicked() {
-                                // TODO: Delete selected items
+                                if let Some(history) = &self.history {
+                                    for url in &self.selected_items {
+                                        if let Err(e) = history.delete_url(url) {
+                                            log::warn!("Failed to delete {} from history: {}", url, e);
+                                        }
+                                    }
+                                }
+                                // Deleting changes the store's revision, so the list is read again
                                 self.selected_items.clear();
                             }
                             
                             ui.add_space(10.0);
                         }
                         
-                        let filtered_count = self.get_filtered_and_sorted_items().len();
-                        ui.label(RichText::new(format!("{} items", filtered_count))
-                            .color(NeonTheme::SECONDARY_TEXT));
+                        let count = self.history_items.len();
+                        let label = if count >= LIST_LIMIT { format!("First {} items", count) } else { format!("{} items", count) };
+                        ui.label(RichText::new(label)
+                            .color(NeonTheme::secondary_text()));
                     });
                 });
             });
@@ -309,18 +357,18 @@ impl CustomPage for HistoryPage {
         // History list
         components::section_header(ui, NeonIcons::CLOCK, "Recent Activity");
         
-        let filtered_items = self.get_filtered_and_sorted_items();
-        
-        if filtered_items.is_empty() {
+        if self.history_items.is_empty() {
             components::card_container(ui, |ui| {
                 ui.vertical_centered(|ui| {
                     ui.add_space(40.0);
                     ui.label(RichText::new(NeonIcons::CLOCK)
                         .size(48.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     ui.add_space(16.0);
                     
-                    let message = if self.history_items.is_empty() {
+                    let message = if self.listed.is_none() {
+                        "Loading history..."
+                    } else if !self.has_history {
                         "No browsing history yet"
                     } else if !self.search_query.is_empty() {
                         "No history items match your search"
@@ -330,13 +378,13 @@ impl CustomPage for HistoryPage {
                     
                     ui.label(RichText::new(message)
                         .size(18.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                        .color(NeonTheme::secondary_text()));
                     ui.add_space(40.0);
                 });
             });
         } else {
             // Clone items to avoid borrowing issues
-            let items_to_render: Vec<HistoryItem> = filtered_items.iter().map(|item| (*item).clone()).collect();
+            let items_to_render = self.history_items.clone();
             for item in items_to_render {
                 self.render_history_item(ui, &item);
                 ui.add_space(4.0);
@@ -350,18 +398,18 @@ impl HistoryPage {
         let is_selected = self.selected_items.contains(&item.id);
         
         let frame_color = if is_selected {
-            NeonTheme::NEON_CYAN.linear_multiply(0.2)
+            NeonTheme::neon_cyan().linear_multiply(0.2)
         } else {
-            NeonTheme::CARD_BG
+            NeonTheme::card_bg()
         };
         
         let response = egui::Frame::none()
             .fill(frame_color)
             .rounding(8.0)
             .stroke(egui::Stroke::new(1.0, if is_selected { 
-                NeonTheme::NEON_CYAN 
+                NeonTheme::neon_cyan() 
             } else { 
-                NeonTheme::BORDER_COLOR 
+                NeonTheme::border_color() 
             }))
             .inner_margin(12.0)
             .show(ui, |ui| {
@@ -378,10 +426,8 @@ impl HistoryPage {
                     
                     ui.add_space(8.0);
                     
-                    // Favicon placeholder
-                    ui.label(RichText::new(NeonIcons::GLOBE)
-                        .size(16.0)
-                        .color(NeonTheme::SECONDARY_TEXT));
+                    // Favicon
+                    components::site_icon(ui, &item.url, 16.0);
                     
                     ui.add_space(8.0);
                     
@@ -392,7 +438,7 @@ impl HistoryPage {
                             let title_response = ui.add(egui::Label::new(
                                 RichText::new(&item.title)
                                     .size(14.0)
-                                    .color(NeonTheme::PRIMARY_TEXT)
+                                    .color(NeonTheme::primary_text())
                             ).sense(egui::Sense::click()));
                             
                             if title_response.clicked() {
@@ -403,7 +449,7 @@ impl HistoryPage {
                                 // Time ago
                                 ui.label(RichText::new(Self::format_time_ago(item.visit_time))
                                     .size(12.0)
-                                    .color(NeonTheme::SECONDARY_TEXT));
+                                    .color(NeonTheme::secondary_text()));
                                 
                                 if self.show_details {
                                     ui.add_space(10.0);
@@ -411,7 +457,7 @@ impl HistoryPage {
                                     // Visit count
                                     ui.label(RichText::new(format!("{} visits", item.visit_count))
                                         .size(12.0)
-                                        .color(NeonTheme::SECONDARY_TEXT));
+                                        .color(NeonTheme::secondary_text()));
                                 }
                             });
                         });
@@ -421,7 +467,7 @@ impl HistoryPage {
                             let url_response = ui.add(egui::Label::new(
                                 RichText::new(&item.url)
                                     .size(12.0)
-                                    .color(NeonTheme::NEON_CYAN)
+                                    .color(NeonTheme::neon_cyan())
                             ).sense(egui::Sense::click()));
                             
                             if url_response.clicked() {
@@ -468,4 +514,171 @@ impl HistoryPage {
             }
         });
     }
-}
\ No newline at end of file
+    
+    fn render_statistics(&self, ui: &mut Ui) {
+        let stats = &self.stats;
+        
+        if stats.total_visits == 0 {
+            components::card_container(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(40.0);
+                    ui.label(RichText::new("No browsing history to analyse yet")
+                        .size(18.0)
+                        .color(NeonTheme::secondary_text()));
+                    ui.add_space(40.0);
+                });
+            });
+            return;
+        }
+        
+        // Summary
+        ui.horizontal(|ui| {
+            Self::stat_card(ui, "Visits", stats.total_visits.to_string(), NeonTheme::neon_cyan());
+            Self::stat_card(ui, "Sites", stats.unique_domains.to_string(), NeonTheme::neon_purple());
+            Self::stat_card(ui, "Sessions", stats.sessions.to_string(), NeonTheme::neon_pink());
+            Self::stat_card(ui, "Browsing time", format!("~{}", Self::format_duration(stats.estimated_time)), NeonTheme::neon_green());
+        });
+        
+        components::section_header(ui, NeonIcons::CALENDAR_X, "Visits per Day");
+        components::card_container(ui, |ui| self.render_heatmap(ui));
+        
+        components::section_header(ui, NeonIcons::GLOBE, "Top Sites");
+        components::card_container(ui, |ui| {
+            let max = stats.top_domains.first().map_or(1, |(_, count)| *count).max(1);
+            for (domain, count) in &stats.top_domains {
+                ui.horizontal(|ui| {
+                    ui.add_sized([200.0, 18.0], egui::Label::new(
+                        RichText::new(domain).color(NeonTheme::primary_text())
+                    ).truncate());
+                    let bar_width = 240.0 * *count as f32 / max as f32;
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 12.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 3.0, NeonTheme::darker_bg());
+                    ui.painter().rect_filled(
+                        egui::Rect::from_min_size(rect.min, egui::vec2(bar_width, rect.height())),
+                        3.0,
+                        NeonTheme::neon_purple(),
+                    );
+                    ui.label(RichText::new(format!("{}", count)).size(12.0).color(NeonTheme::secondary_text()));
+                });
+            }
+        });
+        
+        components::section_header(ui, NeonIcons::CLOCK, "Busiest Hours");
+        components::card_container(ui, |ui| self.render_hours(ui));
+        
+        ui.add_space(8.0);
+        ui.label(RichText::new("Statistics are computed on this device from your local history. Browsing time is estimated from the gaps between visits.")
+            .size(11.0)
+            .color(NeonTheme::muted_text()));
+    }
+    
+    fn stat_card(ui: &mut Ui, label: &str, value: String, color: egui::Color32) {
+        egui::Frame::none()
+            .fill(NeonTheme::elevated_bg())
+            .rounding(8.0)
+            .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+            .inner_margin(12.0)
+            .show(ui, |ui| {
+                ui.set_min_width(120.0);
+                ui.vertical(|ui| {
+                    ui.label(RichText::new(value).size(22.0).strong().color(color));
+                    ui.label(RichText::new(label).size(12.0).color(NeonTheme::secondary_text()));
+                });
+            });
+    }
+    
+    // Calendar heatmap of the last 26 weeks: one column per week, Monday at the top
+    fn render_heatmap(&self, ui: &mut Ui) {
+        const WEEKS: i64 = 26;
+        const CELL: f32 = 12.0;
+        const GAP: f32 = 3.0;
+        
+        let today = Local::now().date_naive();
+        let start = today
+            - chrono::Duration::days(today.weekday().num_days_from_monday() as i64)
+            - chrono::Duration::weeks(WEEKS - 1);
+        let max = self.stats.max_daily_visits().max(1);
+        
+        let label_width = 32.0;
+        let size = egui::vec2(label_width + WEEKS as f32 * (CELL + GAP), 7.0 * (CELL + GAP));
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        
+        for (row, label) in WEEKDAY_LABELS.iter().enumerate() {
+            if row % 2 == 0 {
+                ui.painter().text(
+                    egui::pos2(rect.left(), rect.top() + row as f32 * (CELL + GAP) + CELL / 2.0),
+                    egui::Align2::LEFT_CENTER,
+                    *label,
+                    egui::FontId::proportional(10.0),
+                    NeonTheme::muted_text(),
+                );
+            }
+        }
+        
+        for week in 0..WEEKS {
+            for day in 0..7 {
+                let date: NaiveDate = start + chrono::Duration::days(week * 7 + day);
+                if date > today {
+                    continue;
+                }
+                let count = self.stats.visits_per_day.get(&date).copied().unwrap_or(0);
+                let cell = egui::Rect::from_min_size(
+                    egui::pos2(
+                        rect.left() + label_width + week as f32 * (CELL + GAP),
+                        rect.top() + day as f32 * (CELL + GAP),
+                    ),
+                    egui::vec2(CELL, CELL),
+                );
+                let color = if count == 0 {
+                    NeonTheme::elevated_bg()
+                } else {
+                    let intensity = 0.25 + 0.75 * count as f32 / max as f32;
+                    NeonTheme::neon_cyan().gamma_multiply(intensity)
+                };
+                ui.painter().rect_filled(cell, 2.0, color);
+                
+                ui.interact(cell, ui.id().with(("history_heatmap", date)), egui::Sense::hover())
+                    .on_hover_text(format!("{}: {}", i18n::format_date(date), trn("{n} visit", "{n} visits", count as u64)));
+            }
+        }
+    }
+    
+    fn render_hours(&self, ui: &mut Ui) {
+        let hours = &self.stats.visits_per_hour;
+        let max = hours.iter().copied().max().unwrap_or(0).max(1);
+        let bar_width = 14.0;
+        let height = 80.0;
+        
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(24.0 * (bar_width + 4.0), height + 16.0), egui::Sense::hover());
+        let busiest = self.stats.busiest_hour();
+        
+        for (hour, count) in hours.iter().enumerate() {
+            let x = rect.left() + hour as f32 * (bar_width + 4.0);
+            let bar_height = (height * *count as f32 / max as f32).max(2.0);
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(x, rect.top() + height - bar_height),
+                egui::pos2(x + bar_width, rect.top() + height),
+            );
+            let color = if busiest == Some(hour) { NeonTheme::neon_pink() } else { NeonTheme::neon_blue() };
+            ui.painter().rect_filled(bar, 2.0, color);
+            
+            if hour % 3 == 0 {
+                ui.painter().text(
+                    egui::pos2(x + bar_width / 2.0, rect.top() + height + 8.0),
+                    egui::Align2::CENTER_CENTER,
+                    format!("{:02}", hour),
+                    egui::FontId::proportional(10.0),
+                    NeonTheme::muted_text(),
+                );
+            }
+            ui.interact(bar, ui.id().with(("history_hour", hour)), egui::Sense::hover())
+                .on_hover_text(format!("{:02}:00–{:02}:59: {} visits", hour, hour, count));
+        }
+        
+        if let Some(hour) = busiest {
+            ui.label(RichText::new(format!("Busiest hour: {:02}:00", hour))
+                .size(12.0)
+                .color(NeonTheme::secondary_text()));
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use eframe::egui::{Context, RichText, Ui};
+use std::sync::mpsc::{self, Receiver};
+use crate::networking::cookie_manager::Cookie;
+use crate::pages::{CustomPage, components};
+use crate::storage::import::{self, BrowserProfile};
+use crate::storage::{BookmarkStore, HistoryDatabase};
+use crate::ui::PageAction;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+// The wizard's steps: pick a profile, pick what to bring over, then see what was imported
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Profile,
+    Data,
+    Importing,
+    Done,
+}
+
+// What an import brought over, for the last step
+#[derive(Debug, Default)]
+struct ImportSummary {
+    visits: usize,
+    bookmarks: usize,
+    cookies: usize,
+    encrypted_cookies: usize,
+}
+
+type ImportResult = anyhow::Result<(ImportSummary, Vec<(String, Cookie)>)>;
+
+pub struct ImportPage {
+    url: String,
+    title: String,
+    step: Step,
+    profiles: Vec<BrowserProfile>,
+    // Whether the usual places were searched for profiles yet
+    scanned: bool,
+    selected: Option<usize>,
+    import_history: bool,
+    import_bookmarks: bool,
+    import_cookies: bool,
+    pending: Option<Receiver<ImportResult>>,
+    summary: Option<ImportSummary>,
+    status_message: Option<(String, bool)>, // (message, is_error)
+    actions: Vec<PageAction>,
+}
+
+impl ImportPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://import".to_string(),
+            title: "Import Browser Data".to_string(),
+            step: Step::Profile,
+            profiles: Vec::new(),
+            scanned: false,
+            selected: None,
+            import_history: true,
+            import_bookmarks: true,
+            // Cookies sign this browser into the other's accounts, so that is left for the user to ask for
+            import_cookies: false,
+            pending: None,
+            summary: None,
+            status_message: None,
+            actions: Vec::new(),
+        }
+    }
+
+    fn start_import(&mut self) {
+        let Some(profile) = self.selected.and_then(|i| self.profiles.get(i)).cloned() else { return };
+        let (history, bookmarks, cookies) = (self.import_history, self.import_bookmarks, self.import_cookies);
+        let (sender, receiver) = mpsc::channel();
+        // Big profiles take a while to read, so it happens off the UI thread
+        std::thread::spawn(move || {
+            let result = import::read_profile(&profile, cookies).and_then(|data| {
+                let mut summary = ImportSummary { encrypted_cookies: data.encrypted_cookies, ..Default::default() };
+                if history {
+                    let db = HistoryDatabase::shared().ok_or_else(|| anyhow::anyhow!("History is unavailable"))?;
+                    summary.visits = db.import_visits(&data.history)?;
+                }
+                if bookmarks {
+                    let store = BookmarkStore::shared();
+                    let mut store = store.lock().map_err(|_| anyhow::anyhow!("Bookmarks are unavailable"))?;
+                    summary.bookmarks = store.add_all(data.bookmarks)?;
+                }
+                summary.cookies = data.cookies.len();
+                Ok((summary, data.cookies))
+            });
+            let _ = sender.send(result);
+        });
+        self.pending = Some(receiver);
+        self.status_message = None;
+        self.step = Step::Importing;
+    }
+
+    fn poll_import(&mut self, ctx: &Context) {
+        let Some(receiver) = &self.pending else { return };
+        match receiver.try_recv() {
+            Ok(Ok((summary, cookies))) => {
+                if !cookies.is_empty() {
+                    self.actions.push(PageAction::ImportCookies(cookies));
+                }
+                self.summary = Some(summary);
+                self.pending = None;
+                self.step = Step::Done;
+            }
+            Ok(Err(e)) => {
+                self.status_message = Some((format!("Import failed: {}", e), true));
+                self.pending = None;
+                self.step = Step::Data;
+            }
+            Err(mpsc::TryRecvError::Empty) => ctx.request_repaint(),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.status_message = Some(("Import stopped unexpectedly".to_string(), true));
+                self.pending = None;
+                self.step = Step::Data;
+            }
+        }
+    }
+
+    fn render_profiles(&mut self, ui: &mut Ui) {
+        components::section_header(ui, NeonIcons::FOLDER, "Choose a profile");
+        components::card_container(ui, |ui| {
+            if self.profiles.is_empty() {
+                ui.label(RichText::new("No Chrome, Chromium or Firefox profiles were found on this computer")
+                    .color(NeonTheme::secondary_text()));
+            }
+            for (i, profile) in self.profiles.iter().enumerate() {
+                ui.radio_value(&mut self.selected, Some(i), profile.label())
+                    .on_hover_text(profile.path.display().to_string());
+            }
+            ui.add_space(12.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.selected.is_some(), eframe::egui::Button::new(RichText::new("Next").color(NeonTheme::neon_cyan()))).clicked() {
+                    self.step = Step::Data;
+                }
+                if ui.button(format!("{} Look again", NeonIcons::REFRESH)).clicked() {
+                    self.on_load();
+                }
+            });
+        });
+    }
+
+    fn render_data_choice(&mut self, ui: &mut Ui) {
+        let label = self.selected.and_then(|i| self.profiles.get(i)).map(BrowserProfile::label).unwrap_or_default();
+        components::section_header(ui, NeonIcons::DOWNLOAD, "Choose what to import");
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new(format!("From {}", label)).strong().color(NeonTheme::primary_text()));
+            ui.add_space(8.0);
+            ui.checkbox(&mut self.import_history, "Browsing history");
+            ui.checkbox(&mut self.import_bookmarks, "Bookmarks");
+            ui.checkbox(&mut self.import_cookies, "Cookies")
+                .on_hover_text("Keeps you signed in to the sites you use in the other browser");
+            if self.import_cookies {
+                ui.label(RichText::new(format!("{} Anyone using NeonSearch on this computer will be signed in to those sites", NeonIcons::WARNING))
+                    .size(12.0)
+                    .color(NeonTheme::warning_color()));
+            }
+            ui.label(RichText::new("Close the other browser first to bring over its latest data")
+                .size(12.0)
+                .color(NeonTheme::muted_text()));
+            ui.add_space(12.0);
+            ui.horizontal(|ui| {
+                if ui.button("Back").clicked() {
+                    self.step = Step::Profile;
+                }
+                let any = self.import_history || self.import_bookmarks || self.import_cookies;
+                if ui.add_enabled(any, eframe::egui::Button::new(RichText::new("Import").color(NeonTheme::neon_cyan()))).clicked() {
+                    self.start_import();
+                }
+            });
+        });
+    }
+
+    fn render_summary(&mut self, ui: &mut Ui) {
+        let Some(summary) = &self.summary else { return };
+        components::section_header(ui, NeonIcons::CHECK, "Import complete");
+        let mut open = None;
+        let mut again = false;
+        components::card_container(ui, |ui| {
+            if self.import_history {
+                ui.label(format!("{} visits added to your history", summary.visits));
+            }
+            if self.import_bookmarks {
+                ui.label(format!("{} bookmarks added", summary.bookmarks));
+            }
+            if self.import_cookies {
+                ui.label(format!("{} cookies added", summary.cookies));
+                if summary.encrypted_cookies > 0 {
+                    ui.label(RichText::new(format!(
+                        "{} cookies are encrypted by the other browser and couldn't be read", summary.encrypted_cookies
+                    )).size(12.0).color(NeonTheme::warning_color()));
+                }
+            }
+            ui.add_space(12.0);
+            ui.horizontal(|ui| {
+                if ui.button(format!("{} Open history", NeonIcons::CLOCK)).clicked() {
+                    open = Some("neon://history");
+                }
+                if ui.button(format!("{} Open bookmarks", NeonIcons::BOOKMARKS)).clicked() {
+                    open = Some("neon://bookmarks");
+                }
+                again = ui.button("Import from another profile").clicked();
+            });
+        });
+        if again {
+            self.step = Step::Profile;
+        }
+        if let Some(url) = open {
+            self.actions.push(PageAction::OpenInNewTab(url.to_string()));
+        }
+    }
+}
+
+impl Default for ImportPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for ImportPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn on_load(&mut self) {
+        self.profiles = import::find_profiles();
+        self.scanned = true;
+        self.selected = (!self.profiles.is_empty()).then_some(0);
+    }
+
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
+        components::page_header(
+            ui,
+            "Import Browser Data",
+            Some("Bring your history, bookmarks and cookies over from Chrome or Firefox")
+        );
+
+        if !self.scanned {
+            self.on_load();
+        }
+        self.poll_import(ctx);
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+
+        match self.step {
+            Step::Profile => self.render_profiles(ui),
+            Step::Data => self.render_data_choice(ui),
+            Step::Importing => {
+                components::card_container(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(RichText::new("Importing…").color(NeonTheme::secondary_text()));
+                    });
+                });
+            }
+            Step::Done => self.render_summary(ui),
+        }
+    }
+
+    fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.actions)
+    }
+}
@@ -0,0 +1,271 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use log::{Level, LevelFilter};
+use std::path::PathBuf;
+use crate::pages::{CustomPage, components};
+use crate::platform::logging::{self, LogEntry, LogLevels, Logger};
+use crate::security::download_validator::DownloadValidator;
+use crate::storage::Preferences;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+// The browser's top-level modules, which get a level each
+const MODULES: [&str; 10] = ["networking", "engine", "ui", "js", "pages", "storage", "platform", "security", "extensions", "sync"];
+const LEVELS: [LevelFilter; 6] = [LevelFilter::Off, LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug, LevelFilter::Trace];
+const CRATE: &str = "neonsearch";
+
+pub struct LogsPage {
+    url: String,
+    title: String,
+    // Entries read from the logger so far, oldest first
+    entries: Vec<LogEntry>,
+    // Least severe level shown
+    shown_level: Level,
+    module_filter: String,
+    search: String,
+    // Keep the newest entries in view as they come in
+    follow: bool,
+    levels: LogLevels,
+    // Outcome of the last export
+    export_status: Option<Result<PathBuf, String>>,
+}
+
+impl LogsPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://logs".to_string(),
+            title: "Logs".to_string(),
+            entries: Vec::new(),
+            shown_level: Level::Trace,
+            module_filter: String::new(),
+            search: String::new(),
+            follow: true,
+            levels: Logger::shared().levels(),
+            export_status: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        let last = self.entries.last().map_or(0, |entry| entry.id);
+        self.entries.extend(Logger::shared().entries_after(last));
+        if self.entries.len() > logging::CAPACITY {
+            self.entries.drain(..self.entries.len() - logging::CAPACITY);
+        }
+    }
+
+    fn visible(&self) -> Vec<&LogEntry> {
+        let module = self.module_filter.trim().to_lowercase();
+        let search = self.search.trim().to_lowercase();
+        self.entries.iter()
+            .filter(|entry| entry.level <= self.shown_level)
+            .filter(|entry| module.is_empty() || entry.target.to_lowercase().contains(&module))
+            .filter(|entry| search.is_empty() || entry.message.to_lowercase().contains(&search))
+            .collect()
+    }
+
+    fn apply_levels(&self) {
+        Logger::shared().set_levels(self.levels.clone());
+        let spec = self.levels.to_string();
+        Preferences::update(|prefs| prefs.log_levels = Some(spec));
+    }
+
+    // The shown entries go to a file in the downloads folder
+    fn export(text: &str) -> Result<PathBuf, String> {
+        let dir = dirs::download_dir().unwrap_or_else(std::env::temp_dir);
+        let name = format!("neonsearch-{}.log", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let path = DownloadValidator::generate_safe_path(&dir, &name);
+        std::fs::write(&path, text)
+            .map(|_| path.clone())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // Pick a level for `module`; None means it takes the level of the path it's in
+    fn level_choice(ui: &mut Ui, id: &str, level: Option<LevelFilter>, inherit: Option<&str>) -> Option<Option<LevelFilter>> {
+        let label = level.map_or_else(|| inherit.unwrap_or("Default").to_string(), |level| level.to_string());
+        let mut chosen = None;
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(label)
+            .width(110.0)
+            .show_ui(ui, |ui| {
+                if let Some(inherit) = inherit {
+                    if ui.selectable_label(level.is_none(), inherit).clicked() {
+                        chosen = Some(None);
+                    }
+                }
+                for option in LEVELS {
+                    if ui.selectable_label(level == Some(option), option.to_string()).clicked() {
+                        chosen = Some(Some(option));
+                    }
+                }
+            });
+        chosen
+    }
+
+    fn render_levels(&mut self, ui: &mut Ui) {
+        let mut changed = false;
+        egui::Grid::new("log_levels")
+            .num_columns(2)
+            .spacing([24.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Libraries").color(NeonTheme::primary_text()));
+                if let Some(Some(level)) = Self::level_choice(ui, "log_level_default", Some(self.levels.default), None) {
+                    self.levels.default = level;
+                    changed = true;
+                }
+                ui.end_row();
+
+                ui.label(RichText::new("NeonSearch").color(NeonTheme::primary_text()));
+                if let Some(choice) = Self::level_choice(ui, "log_level_crate", self.levels.get(CRATE), Some("As libraries")) {
+                    match choice {
+                        Some(level) => self.levels.set(CRATE, level),
+                        None => self.levels.remove(CRATE),
+                    }
+                    changed = true;
+                }
+                ui.end_row();
+
+                for module in MODULES {
+                    let path = format!("{}::{}", CRATE, module);
+                    ui.label(RichText::new(format!("    {}", module)).color(NeonTheme::secondary_text()));
+                    if let Some(choice) = Self::level_choice(ui, &path, self.levels.get(&path), Some("As NeonSearch")) {
+                        match choice {
+                            Some(level) => self.levels.set(&path, level),
+                            None => self.levels.remove(&path),
+                        }
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(self.levels.to_string()).monospace().color(NeonTheme::muted_text()));
+            if ui.small_button("Reset").clicked() {
+                self.levels = LogLevels::parse(logging::DEFAULT_LEVELS);
+                Logger::shared().set_levels(self.levels.clone());
+                Preferences::update(|prefs| prefs.log_levels = None);
+            }
+        });
+        if changed {
+            self.apply_levels();
+        }
+    }
+
+    fn render_entries(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("log_shown_level")
+                .selected_text(format!("{} and above", self.shown_level))
+                .show_ui(ui, |ui| {
+                    for level in [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace] {
+                        ui.selectable_value(&mut self.shown_level, level, level.to_string());
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut self.module_filter).hint_text("Module").desired_width(140.0));
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text(format!("{} Search messages", NeonIcons::SEARCH)).desired_width(200.0));
+            ui.checkbox(&mut self.follow, "Follow");
+        });
+        ui.add_space(6.0);
+
+        let visible = self.visible();
+        let text = || visible.iter().map(|entry| entry.to_line()).collect::<Vec<_>>().join("\n");
+        let (mut clear, mut export) = (false, false);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{} of {} entries", visible.len(), self.entries.len())).color(NeonTheme::secondary_text()));
+            if ui.small_button(format!("{} Copy", NeonIcons::BOOKMARKS)).clicked() {
+                ui.ctx().copy_text(text());
+            }
+            if ui.small_button(format!("{} Export", NeonIcons::DOWNLOAD)).clicked() {
+                export = true;
+            }
+            if ui.small_button("Clear").clicked() {
+                clear = true;
+            }
+        });
+        let export_status = export.then(|| Self::export(&text()));
+        match export_status.as_ref().or(self.export_status.as_ref()) {
+            Some(Ok(path)) => {
+                ui.label(RichText::new(format!("Saved to {}", path.display())).color(NeonTheme::success_color()));
+            }
+            Some(Err(e)) => {
+                ui.label(RichText::new(e).color(NeonTheme::error_color()));
+            }
+            None => {}
+        }
+        ui.add_space(6.0);
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + ui.spacing().item_spacing.y;
+        egui::ScrollArea::both()
+            .max_height(480.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(self.follow)
+            .show_rows(ui, row_height, visible.len(), |ui, rows| {
+                for entry in &visible[rows] {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(entry.time.format("%H:%M:%S%.3f").to_string()).monospace().color(NeonTheme::muted_text()));
+                        ui.label(RichText::new(format!("{:<5}", entry.level)).monospace().color(level_color(entry.level)));
+                        let module = entry.target.strip_prefix("neonsearch::").unwrap_or(&entry.target);
+                        ui.label(RichText::new(module).monospace().color(NeonTheme::secondary_text()));
+                        ui.label(RichText::new(&entry.message).monospace().color(NeonTheme::primary_text()));
+                    });
+                }
+            });
+
+        if export_status.is_some() {
+            self.export_status = export_status;
+        }
+        if clear {
+            Logger::shared().clear();
+            self.entries.clear();
+        }
+    }
+}
+
+fn level_color(level: Level) -> egui::Color32 {
+    match level {
+        Level::Error => NeonTheme::error_color(),
+        Level::Warn => NeonTheme::warning_color(),
+        Level::Info => NeonTheme::neon_cyan(),
+        Level::Debug => NeonTheme::secondary_text(),
+        Level::Trace => NeonTheme::muted_text(),
+    }
+}
+
+impl Default for LogsPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for LogsPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn on_load(&mut self) {
+        self.levels = Logger::shared().levels();
+    }
+
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
+        components::page_header(
+            ui,
+            "Logs",
+            Some("Recent messages from the browser, for tracking down pages that fail to load")
+        );
+        self.refresh();
+
+        components::section_header(ui, NeonIcons::WRENCH, "Levels");
+        components::card_container(ui, |ui| self.render_levels(ui));
+
+        components::section_header(ui, NeonIcons::BOOKMARKS, "Entries");
+        components::card_container(ui, |ui| self.render_entries(ui));
+
+        // New entries are logged from any thread, so poll for them
+        if self.follow {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+    }
+}
@@ -8,6 +8,15 @@ pub mod security;
 pub mod downloads;
 pub mod extensions;
 pub mod experiments;
+pub mod diagnostics;
+pub mod notifications;
+pub mod crash;
+pub mod userscripts;
+pub mod import;
+pub mod sync;
+pub mod passwords;
+pub mod logs;
+pub mod tasks;
 
 pub use about::AboutPage;
 pub use settings::SettingsPage;
@@ -18,4 +27,13 @@ pub use performance::PerformancePage;
 pub use security::SecurityPage;
 pub use downloads::DownloadsPage;
 pub use extensions::ExtensionsPage;
-pub use experiments::ExperimentsPage;
\ No newline at end of file
+pub use experiments::ExperimentsPage;
+pub use diagnostics::DiagnosticsPage;
+pub use notifications::NotificationsPage;
+pub use crash::CrashPage;
+pub use userscripts::UserScriptsPage;
+pub use import::ImportPage;
+pub use sync::SyncPage;
+pub use passwords::PasswordsPage;
+pub use logs::LogsPage;
+pub use tasks::TasksPage;
\ No newline at end of file
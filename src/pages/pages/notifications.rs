@@ -0,0 +1,129 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use crate::pages::{CustomPage, components};
+use crate::storage::Preferences;
+use crate::ui::icons::NeonIcons;
+use crate::ui::notifications::NotificationCenter;
+use crate::ui::theme::NeonTheme;
+
+// Quiet hours offered when the user first turns them on: 22:00 to 07:00
+const DEFAULT_QUIET_HOURS: (u16, u16) = (22 * 60, 7 * 60);
+
+pub struct NotificationsPage {
+    url: String,
+    title: String,
+}
+
+impl NotificationsPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://notifications".to_string(),
+            title: "Notifications".to_string(),
+        }
+    }
+}
+
+impl Default for NotificationsPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for NotificationsPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+        components::page_header(
+            ui,
+            "Notifications",
+            Some("Which sites may show notifications, when none are shown at all, and what sites sent recently")
+        );
+
+        let mut settings = Preferences::current().notifications.clone();
+        let mut changed = false;
+
+        components::section_header(ui, NeonIcons::SPEAKER_MUTED, "Do not disturb");
+        components::card_container(ui, |ui| {
+            changed |= ui.checkbox(&mut settings.do_not_disturb, "Mute all notifications").changed();
+            ui.add_space(4.0);
+
+            let mut scheduled = settings.quiet_hours.is_some();
+            if ui.checkbox(&mut scheduled, "Mute notifications every day between").changed() {
+                settings.quiet_hours = scheduled.then_some(DEFAULT_QUIET_HOURS);
+                changed = true;
+            }
+            if let Some((start, end)) = settings.quiet_hours.as_mut() {
+                ui.horizontal(|ui| {
+                    ui.add_space(24.0);
+                    changed |= components::time_of_day(ui, start);
+                    ui.label("and");
+                    changed |= components::time_of_day(ui, end);
+                });
+            }
+        });
+
+        components::section_header(ui, NeonIcons::GLOBE, "Sites");
+        components::card_container(ui, |ui| {
+            if settings.permissions.is_empty() {
+                ui.label(RichText::new("No site has asked to show notifications.").color(NeonTheme::secondary_text()));
+                return;
+            }
+            let mut revoked = None;
+            egui::Grid::new("notification_permissions")
+                .num_columns(3)
+                .spacing([24.0, 6.0])
+                .show(ui, |ui| {
+                    for (origin, allowed) in &settings.permissions {
+                        ui.label(RichText::new(origin).color(NeonTheme::primary_text()));
+                        match allowed {
+                            true => ui.label(RichText::new("Allowed").color(NeonTheme::success_color())),
+                            false => ui.label(RichText::new("Blocked").color(NeonTheme::error_color())),
+                        };
+                        if ui.button("Revoke").on_hover_text("Forget this answer; the site may ask again").clicked() {
+                            revoked = Some(origin.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(origin) = revoked {
+                settings.permissions.remove(&origin);
+                changed = true;
+            }
+        });
+
+        if changed {
+            Preferences::update(|p| p.notifications = settings);
+        }
+
+        components::section_header(ui, NeonIcons::CLOCK, "Recent notifications");
+        components::card_container(ui, |ui| {
+            let recent = NotificationCenter::shared().recent();
+            if recent.is_empty() {
+                ui.label(RichText::new("No notifications this session.").color(NeonTheme::secondary_text()));
+                return;
+            }
+            for record in &recent {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(RichText::new(record.time.format("%H:%M:%S").to_string()).color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new(&record.title).strong().color(NeonTheme::primary_text()));
+                    ui.label(RichText::new(&record.origin).color(NeonTheme::secondary_text()));
+                    if !record.shown {
+                        ui.label(RichText::new("muted").color(NeonTheme::warning_color()));
+                    }
+                });
+                if !record.body.is_empty() {
+                    ui.label(RichText::new(&record.body).color(NeonTheme::primary_text()));
+                }
+                ui.add_space(4.0);
+            }
+            if ui.button(format!("{} Clear", NeonIcons::TRASH)).clicked() {
+                NotificationCenter::shared().clear();
+            }
+        });
+    }
+}
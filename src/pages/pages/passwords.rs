@@ -0,0 +1,269 @@
+use eframe::egui::{self, Align, Context, Layout, RichText, Ui};
+use crate::pages::{CustomPage, components};
+use crate::security::password_manager::{self, Credential, PasswordManager, Protection};
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+// A saved login open for editing, by its index in the vault
+struct Edit {
+    index: usize,
+    username: String,
+    password: String,
+}
+
+pub struct PasswordsPage {
+    url: String,
+    title: String,
+    search: String,
+    // Master password typed to set up or unlock the vault
+    master_password: String,
+    // Logins whose password is shown in the clear, by index
+    revealed: Vec<usize>,
+    editing: Option<Edit>,
+    // Login waiting for the user to confirm its deletion
+    confirm_delete: Option<usize>,
+    status_message: Option<(String, bool)>, // (message, is_error)
+}
+
+impl PasswordsPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://passwords".to_string(),
+            title: "Passwords".to_string(),
+            search: String::new(),
+            master_password: String::new(),
+            revealed: Vec::new(),
+            editing: None,
+            confirm_delete: None,
+            status_message: None,
+        }
+    }
+
+    fn render_setup(&mut self, ui: &mut Ui, passwords: &mut PasswordManager) {
+        components::section_header(ui, NeonIcons::LOCK_SIMPLE, "Set up saved passwords");
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new("Saved passwords are encrypted on disk. Keep the key in your system keychain, or choose a master password to enter once per session")
+                .color(NeonTheme::secondary_text()));
+            ui.add_space(8.0);
+            if password_manager::keychain_available() && ui.button(format!("{} Use the system keychain", NeonIcons::LOCK)).clicked() {
+                self.status_message = Some(match passwords.set_up(None) {
+                    Ok(()) => ("Saved passwords are protected by the system keychain".to_string(), false),
+                    Err(e) => (e.to_string(), true),
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.master_password).password(true).hint_text("Master password"));
+                if ui.button("Use a master password").clicked() {
+                    self.status_message = Some(if self.master_password.chars().count() < password_manager::MIN_PASSWORD_LEN {
+                        (format!("Use a master password of at least {} characters", password_manager::MIN_PASSWORD_LEN), true)
+                    } else {
+                        match passwords.set_up(Some(&self.master_password)) {
+                            Ok(()) => ("Saved passwords are protected by your master password".to_string(), false),
+                            Err(e) => (e.to_string(), true),
+                        }
+                    });
+                    self.master_password.clear();
+                }
+            });
+            ui.label(RichText::new("A forgotten master password can't be recovered, and neither can the passwords it protects")
+                .size(12.0)
+                .color(NeonTheme::warning_color()));
+        });
+    }
+
+    fn render_unlock(&mut self, ui: &mut Ui, passwords: &mut PasswordManager) {
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new(format!("{} Saved passwords are locked", NeonIcons::LOCK)).strong().color(NeonTheme::primary_text()));
+            ui.horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.master_password).password(true).hint_text("Master password"));
+                let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("Unlock").clicked() || submitted {
+                    self.status_message = passwords.unlock(&self.master_password).err().map(|e| (e.to_string(), true));
+                    self.master_password.clear();
+                }
+            });
+        });
+    }
+
+    fn render_credential(&mut self, ui: &mut Ui, passwords: &mut PasswordManager, index: usize, credential: &Credential, all: &[Credential]) {
+        let warnings = password_manager::password_warnings(credential, all);
+        components::card_container(ui, |ui| {
+            if let Some(edit) = self.editing.as_mut().filter(|edit| edit.index == index) {
+                ui.label(RichText::new(&credential.origin).strong().color(NeonTheme::primary_text()));
+                egui::Grid::new(("password_edit", index)).num_columns(2).show(ui, |ui| {
+                    ui.label("Username");
+                    ui.text_edit_singleline(&mut edit.username);
+                    ui.end_row();
+                    ui.label("Password");
+                    ui.text_edit_singleline(&mut edit.password);
+                    ui.end_row();
+                });
+                let (save, cancel) = ui.horizontal(|ui| {
+                    let save = ui.button(RichText::new(format!("{} Save", NeonIcons::CHECK)).color(NeonTheme::neon_cyan())).clicked();
+                    (save, ui.button("Cancel").clicked())
+                }).inner;
+                if save {
+                    let updated = Credential { username: edit.username.clone(), password: edit.password.clone(), ..credential.clone() };
+                    self.status_message = passwords.update(index, updated).err().map(|e| (e.to_string(), true));
+                }
+                if save || cancel {
+                    self.editing = None;
+                }
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                components::site_icon(ui, &credential.origin, 16.0);
+                ui.vertical(|ui| {
+                    ui.label(RichText::new(&credential.origin).strong().color(NeonTheme::primary_text()));
+                    let username = if credential.username.is_empty() { "(no username)" } else { credential.username.as_str() };
+                    ui.label(RichText::new(username).color(NeonTheme::secondary_text()));
+                    let revealed = self.revealed.contains(&index);
+                    let shown = if revealed { credential.password.clone() } else { "•".repeat(credential.password.chars().count().min(12)) };
+                    ui.label(RichText::new(shown).monospace().color(NeonTheme::secondary_text()));
+                    for warning in &warnings {
+                        ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, warning.description()))
+                            .size(12.0)
+                            .color(NeonTheme::warning_color()));
+                    }
+                });
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if self.confirm_delete == Some(index) {
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_delete = None;
+                        }
+                        if ui.button(RichText::new(format!("{} Delete", NeonIcons::TRASH)).color(NeonTheme::error_color())).clicked() {
+                            self.confirm_delete = None;
+                            self.revealed.clear();
+                            self.status_message = Some(match passwords.remove(index) {
+                                Ok(()) => (format!("Deleted the password for {}", credential.origin), false),
+                                Err(e) => (e.to_string(), true),
+                            });
+                        }
+                        return;
+                    }
+                    if ui.button(RichText::new(NeonIcons::TRASH).color(NeonTheme::secondary_text())).on_hover_text("Delete").clicked() {
+                        self.confirm_delete = Some(index);
+                    }
+                    if ui.button("Edit").clicked() {
+                        self.editing = Some(Edit { index, username: credential.username.clone(), password: credential.password.clone() });
+                    }
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(credential.password.clone());
+                    }
+                    let revealed = self.revealed.contains(&index);
+                    if ui.button(if revealed { "Hide" } else { "Reveal" }).clicked() {
+                        if revealed {
+                            self.revealed.retain(|i| *i != index);
+                        } else {
+                            self.revealed.push(index);
+                        }
+                    }
+                });
+            });
+        });
+        ui.add_space(6.0);
+    }
+
+    fn render_vault(&mut self, ui: &mut Ui, passwords: &mut PasswordManager) {
+        let all = passwords.credentials().to_vec();
+        let weak = all.iter().filter(|c| !password_manager::password_warnings(c, &all).is_empty()).count();
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(NeonIcons::SEARCH).color(NeonTheme::secondary_text()));
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search passwords").desired_width(280.0));
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if passwords.protection().is_some_and(|p| matches!(p, Protection::MasterPassword { .. }))
+                    && ui.button(format!("{} Lock", NeonIcons::LOCK)).clicked() {
+                    passwords.lock();
+                    self.revealed.clear();
+                    self.editing = None;
+                }
+            });
+        });
+        if weak > 0 {
+            ui.label(RichText::new(format!("{} {} of {} passwords are weak or reused", NeonIcons::WARNING, weak, all.len()))
+                .color(NeonTheme::warning_color()));
+        }
+        ui.add_space(8.0);
+
+        components::section_header(ui, NeonIcons::LOCK_SIMPLE, "Saved passwords");
+        if all.is_empty() {
+            components::card_container(ui, |ui| {
+                ui.label(RichText::new("No passwords saved yet. Sign in on a site and choose Save when asked")
+                    .color(NeonTheme::secondary_text()));
+            });
+        }
+        let query = self.search.to_lowercase();
+        for (index, credential) in all.iter().enumerate() {
+            if !query.is_empty() && !credential.origin.to_lowercase().contains(&query) && !credential.username.to_lowercase().contains(&query) {
+                continue;
+            }
+            self.render_credential(ui, passwords, index, credential, &all);
+        }
+
+        let never = passwords.never_saved_origins().to_vec();
+        if !never.is_empty() {
+            ui.add_space(8.0);
+            components::section_header(ui, NeonIcons::PROHIBIT, "Never saved");
+            components::card_container(ui, |ui| {
+                for origin in never {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(&origin).color(NeonTheme::primary_text()));
+                        if ui.small_button(NeonIcons::X).on_hover_text("Offer to save passwords on this site again").clicked() {
+                            self.status_message = passwords.allow_saving(&origin).err().map(|e| (e.to_string(), true));
+                        }
+                    });
+                }
+            });
+        }
+    }
+}
+
+impl Default for PasswordsPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for PasswordsPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+        components::page_header(
+            ui,
+            "Passwords",
+            Some("Logins NeonSearch saved and fills in for you")
+        );
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+
+        let passwords = PasswordManager::shared();
+        let Ok(mut passwords) = passwords.lock() else {
+            return;
+        };
+        if !passwords.is_set_up() {
+            self.render_setup(ui, &mut passwords);
+        } else if !passwords.is_unlocked() {
+            self.render_unlock(ui, &mut passwords);
+        } else {
+            self.render_vault(ui, &mut passwords);
+        }
+    }
+
+    fn on_unload(&mut self) {
+        // Passwords are only shown while the page is open
+        self.revealed.clear();
+        self.editing = None;
+    }
+}
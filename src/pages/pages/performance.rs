@@ -1,5 +1,10 @@
-use eframe::egui::{Context, Ui};
+use eframe::egui::{Context, RichText, Ui};
+use crate::engine::executor::{Executor, TaskKind};
+use crate::networking::timing;
 use crate::pages::{CustomPage, components};
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+use crate::ui::waterfall;
 
 pub struct PerformancePage {
     url: String,
@@ -24,15 +29,51 @@ impl CustomPage for PerformancePage {
         &self.title
     }
     
-    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
         components::page_header(
             ui, 
             "Performance Metrics", 
             Some("Monitor and optimize browser performance")
         );
         
+        components::section_header(ui, NeonIcons::GEAR, "Background Tasks");
         components::card_container(ui, |ui| {
-            ui.label("Performance page implementation coming soon...");
+            let executor = Executor::shared();
+            egui::Grid::new("executor_tasks")
+                .num_columns(3)
+                .spacing([24.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Kind").color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new("Active").color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new("Blocking limit").color(NeonTheme::secondary_text()));
+                    ui.end_row();
+                    
+                    for kind in TaskKind::ALL {
+                        ui.label(RichText::new(kind.label()).color(NeonTheme::primary_text()));
+                        ui.label(RichText::new(executor.active_tasks(kind).to_string()).color(NeonTheme::neon_cyan()));
+                        ui.label(RichText::new(kind.blocking_limit().to_string()).color(NeonTheme::muted_text()));
+                        ui.end_row();
+                    }
+                });
         });
+        
+        components::section_header(ui, NeonIcons::GLOBE, "Page Loads");
+        components::card_container(ui, |ui| {
+            let navigations = timing::recent_navigations();
+            if navigations.is_empty() {
+                ui.label(RichText::new("Pages loaded in this window show their request timings here").color(NeonTheme::muted_text()));
+                return;
+            }
+            waterfall::legend(ui);
+            for (index, navigation) in navigations.iter().enumerate() {
+                egui::CollapsingHeader::new(RichText::new(&navigation.url).color(NeonTheme::primary_text()))
+                    .id_salt(("page_load", index))
+                    .default_open(index == 0)
+                    .show(ui, |ui| waterfall::show(ui, &navigation.requests.iter().collect::<Vec<_>>()));
+            }
+        });
+        
+        // Task counts change without any input, so keep the table fresh
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
     }
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
-use eframe::egui::{Context, Ui};
+use eframe::egui::{self, Context, RichText, Ui};
 use crate::pages::{CustomPage, components};
+use crate::security::{CheckStatus, SecurityCategory, SecurityLevel, SecurityLog};
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
 
 pub struct SecurityPage {
     url: String,
@@ -19,20 +22,71 @@ impl CustomPage for SecurityPage {
     fn get_url(&self) -> &str {
         &self.url
     }
-    
+
     fn get_title(&self) -> &str {
         &self.title
     }
-    
+
     fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
         components::page_header(
-            ui, 
-            "Security Status", 
-            Some("Privacy controls and security information")
+            ui,
+            "Security Status",
+            Some("How the sites you visited this session score on transport, headers, cookies and content policy")
         );
-        
+
+        let reports = SecurityLog::shared().reports();
+        if reports.is_empty() {
+            components::card_container(ui, |ui| {
+                ui.label(RichText::new("No sites visited yet. Scores appear here as pages load.").color(NeonTheme::secondary_text()));
+            });
+            return;
+        }
+
+        components::section_header(ui, NeonIcons::SHIELD_CHECK, "Overview");
         components::card_container(ui, |ui| {
-            ui.label("Security page implementation coming soon...");
+            let average = reports.iter().map(|r| r.security_score).sum::<u32>() / reports.len() as u32;
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(average.to_string())
+                    .size(32.0)
+                    .strong()
+                    .color(components::security_level_color(SecurityLevel::from_score(average))));
+                ui.label(RichText::new(format!("average across {} site(s)", reports.len())).color(NeonTheme::secondary_text()));
+            });
+            ui.add_space(8.0);
+
+            // Which areas let the most sites down
+            egui::Grid::new("security_categories")
+                .num_columns(4)
+                .spacing([24.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Area").color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new("Passing").color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new("Warnings").color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new("Failing").color(NeonTheme::secondary_text()));
+                    ui.end_row();
+
+                    for category in SecurityCategory::ALL {
+                        let count = |status: CheckStatus| reports.iter()
+                            .filter(|r| r.check(category).is_some_and(|c| c.status == status))
+                            .count();
+                        ui.label(RichText::new(category.label()).color(NeonTheme::primary_text()));
+                        ui.label(RichText::new(count(CheckStatus::Pass).to_string()).color(NeonTheme::success_color()));
+                        ui.label(RichText::new(count(CheckStatus::Warning).to_string()).color(NeonTheme::warning_color()));
+                        ui.label(RichText::new(count(CheckStatus::Fail).to_string()).color(NeonTheme::error_color()));
+                        ui.end_row();
+                    }
+                });
+        });
+
+        components::section_header(ui, NeonIcons::LOCK, "Sites");
+        components::card_container(ui, |ui| {
+            for report in &reports {
+                let header = RichText::new(format!("{}  {}  ({:?})", report.security_score, report.site(), report.level()))
+                    .color(components::security_level_color(report.level()));
+                egui::CollapsingHeader::new(header)
+                    .id_salt(("security_site", report.site()))
+                    .show(ui, |ui| components::security_breakdown(ui, report));
+            }
         });
     }
-}
\ No newline at end of file
+}
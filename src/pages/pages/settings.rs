@@ -1,6 +1,21 @@
 use eframe::egui::{Context, RichText, Ui, Slider};
 use crate::pages::{CustomPage, components};
+use crate::networking::protocol_handlers;
+use crate::networking::http_cache::HttpCache;
+use crate::platform::default_browser;
+use crate::networking::redirect::ReferrerPolicy;
+use crate::networking::user_agent::UserAgentPreset;
+use crate::storage::{ClearBrowsingData, ImageLoadingMode, IndexedDatabase, Preferences, StartupPage, UserStyles};
+use crate::storage::preferences::{ThemeSwitch, TranslationSettings};
+use crate::translate::TranslationProvider;
 use crate::ui::theme::NeonTheme;
+use crate::ui::themes::ThemeManager;
+use crate::ui::i18n::{self, tr};
+use crate::ui::PageAction;
+use crate::ui::shortcuts::{self, ShortcutAction, Shortcuts};
+
+// Schedule offered when switching by time of day is first turned on: light from 7:00 until 19:00
+const DEFAULT_THEME_SCHEDULE: ThemeSwitch = ThemeSwitch::Schedule { light_from: 7 * 60, light_until: 19 * 60 };
 use crate::ui::icons::NeonIcons;
 
 pub struct SettingsPage {
@@ -9,7 +24,7 @@ pub struct SettingsPage {
     // Settings state
     current_tab: SettingsTab,
     // General settings
-    startup_page: StartupOption,
+    startup_page: StartupPage,
     search_engine: SearchEngine,
     downloads_path: String,
     // Privacy settings
@@ -17,14 +32,46 @@ pub struct SettingsPage {
     javascript_enabled: bool,
     images_enabled: bool,
     tracking_protection: bool,
-    // Appearance settings
-    theme: ThemeOption,
+    // Appearance settings; the name for a theme about to be created and the outcome of the last theme change
+    new_theme_name: String,
+    theme_status: Option<(String, bool)>, // (message, is_error)
     font_size: f32,
     show_bookmarks_bar: bool,
+    // User stylesheet open in the editor: its site, or None for the global one, and the CSS being edited
+    user_style_draft: Option<(Option<String>, String)>,
+    user_style_site: String,
+    user_style_status: Option<(String, bool)>, // (message, is_error)
     // Performance settings
     cache_size: f32,
     max_connections: i32,
     enable_hardware_acceleration: bool,
+    image_loading: ImageLoadingMode,
+    // Page size limit; the megabyte value is kept while the limit is switched off
+    limit_page_size: bool,
+    page_size_limit_mb: u32,
+    // Advanced settings
+    experimental_indexed_db: bool,
+    keep_session_cookies: bool,
+    strip_tracking_on_copy: bool,
+    // Site override being added to the user-agent profiles
+    user_agent_host: String,
+    user_agent_override: UserAgentPreset,
+    // Protocol handler being added: scheme, page template and why it was refused
+    handler_scheme: String,
+    handler_template: String,
+    handler_error: Option<String>,
+    // Whether NeonSearch is the default browser, asked of the system once the section is first shown
+    default_browser: Option<Option<bool>>,
+    default_browser_error: Option<String>,
+    // Open Clear Browsing Data dialog, and the outcome of the last clear
+    clear_data: Option<ClearBrowsingData>,
+    clear_data_status: Option<String>,
+    // Shortcut waiting for the user to press its new chord
+    recording_shortcut: Option<ShortcutAction>,
+    // A new chord that's already another action's, until the user replaces it or cancels
+    shortcut_conflict: Option<(ShortcutAction, egui::KeyboardShortcut, ShortcutAction)>,
+    shortcut_error: Option<String>,
+    actions: Vec<PageAction>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,16 +80,10 @@ enum SettingsTab {
     Privacy,
     Appearance,
     Performance,
+    Shortcuts,
     Advanced,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum StartupOption {
-    HomePage,
-    NewTab,
-    LastSession,
-}
-
 #[derive(Debug, Clone, PartialEq)]
 enum SearchEngine {
     DuckDuckGo,
@@ -51,13 +92,6 @@ enum SearchEngine {
     Custom(String),
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum ThemeOption {
-    Dark,
-    Light,
-    Auto,
-}
-
 impl SettingsPage {
     pub fn new() -> Self {
         Self {
@@ -65,19 +99,43 @@ impl SettingsPage {
             title: "Settings".to_string(),
             current_tab: SettingsTab::General,
             // Default settings
-            startup_page: StartupOption::HomePage,
+            startup_page: Preferences::current().startup,
             search_engine: SearchEngine::DuckDuckGo,
             downloads_path: "~/Downloads".to_string(),
             cookies_enabled: true,
-            javascript_enabled: true,
+            javascript_enabled: Preferences::current().javascript.enabled,
             images_enabled: true,
-            tracking_protection: true,
-            theme: ThemeOption::Dark,
+            tracking_protection: Preferences::current().tracking.enabled,
+            new_theme_name: String::new(),
+            theme_status: None,
             font_size: 14.0,
             show_bookmarks_bar: true,
+            user_style_draft: None,
+            user_style_site: String::new(),
+            user_style_status: None,
             cache_size: 100.0,
             max_connections: 10,
             enable_hardware_acceleration: true,
+            image_loading: Preferences::current().image_loading,
+            limit_page_size: Preferences::current().page_size_limit_mb.is_some(),
+            page_size_limit_mb: Preferences::current().page_size_limit_mb
+                .unwrap_or((crate::networking::DEFAULT_PAGE_SIZE_LIMIT / 1024 / 1024) as u32),
+            experimental_indexed_db: Preferences::current().experimental_indexed_db,
+            keep_session_cookies: Preferences::current().cookies.keep_session_cookies,
+            strip_tracking_on_copy: Preferences::current().strip_tracking_on_copy,
+            user_agent_host: String::new(),
+            user_agent_override: UserAgentPreset::Mobile,
+            handler_scheme: "mailto".to_string(),
+            handler_template: String::new(),
+            handler_error: None,
+            default_browser: None,
+            default_browser_error: None,
+            clear_data: None,
+            clear_data_status: None,
+            recording_shortcut: None,
+            shortcut_conflict: None,
+            shortcut_error: None,
+            actions: Vec::new(),
         }
     }
 }
@@ -91,7 +149,18 @@ impl CustomPage for SettingsPage {
         &self.title
     }
     
-    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+    fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.actions)
+    }
+    
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
+        // "Edit styles for this site" from a page's context menu opens straight into the editor
+        let edit_request = UserStyles::shared().lock().ok().and_then(|mut styles| styles.take_edit_request());
+        if let Some(site) = edit_request {
+            self.open_user_style(Some(site));
+            self.current_tab = SettingsTab::Appearance;
+        }
+        
         components::page_header(
             ui, 
             "Settings", 
@@ -109,6 +178,7 @@ impl CustomPage for SettingsPage {
                         self.settings_nav_button(ui, SettingsTab::Privacy, NeonIcons::SHIELD_CHECK, "Privacy & Security");
                         self.settings_nav_button(ui, SettingsTab::Appearance, "🎨", "Appearance");
                         self.settings_nav_button(ui, SettingsTab::Performance, "⚡", "Performance");
+                        self.settings_nav_button(ui, SettingsTab::Shortcuts, NeonIcons::TERMINAL, "Shortcuts");
                         self.settings_nav_button(ui, SettingsTab::Advanced, NeonIcons::WRENCH, "Advanced");
                     });
                 });
@@ -125,10 +195,13 @@ impl CustomPage for SettingsPage {
                     SettingsTab::Privacy => self.render_privacy_settings(ui),
                     SettingsTab::Appearance => self.render_appearance_settings(ui),
                     SettingsTab::Performance => self.render_performance_settings(ui),
+                    SettingsTab::Shortcuts => self.render_shortcut_settings(ui),
                     SettingsTab::Advanced => self.render_advanced_settings(ui),
                 }
             });
         });
+        
+        self.render_clear_data_dialog(ctx);
     }
 }
 
@@ -137,9 +210,9 @@ impl SettingsPage {
         let is_selected = self.current_tab == tab;
         
         let (bg_color, text_color) = if is_selected {
-            (NeonTheme::NEON_CYAN.linear_multiply(0.2), NeonTheme::NEON_CYAN)
+            (NeonTheme::neon_cyan().linear_multiply(0.2), NeonTheme::neon_cyan())
         } else {
-            (egui::Color32::TRANSPARENT, NeonTheme::SECONDARY_TEXT)
+            (egui::Color32::TRANSPARENT, NeonTheme::secondary_text())
         };
         
         let response = egui::Frame::none()
@@ -150,7 +223,7 @@ impl SettingsPage {
                 ui.horizontal(|ui| {
                     ui.label(RichText::new(icon).size(16.0).color(text_color));
                     ui.add_space(8.0);
-                    ui.label(RichText::new(label).color(text_color));
+                    ui.label(RichText::new(tr(label)).color(text_color));
                 })
             })
             .response;
@@ -164,7 +237,7 @@ impl SettingsPage {
             ui.painter().rect_filled(
                 response.rect,
                 8.0,
-                NeonTheme::SECONDARY_TEXT.linear_multiply(0.1)
+                NeonTheme::secondary_text().linear_multiply(0.1)
             );
         }
         
@@ -176,26 +249,37 @@ impl SettingsPage {
         
         components::card_container(ui, |ui| {
             // Startup options
-            ui.label(RichText::new("When NeonSearch starts")
+            ui.label(RichText::new(tr("When NeonSearch starts"))
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
+            let startup_page = self.startup_page;
             ui.horizontal(|ui| {
-                ui.radio_value(&mut self.startup_page, StartupOption::HomePage, "Open the home page");
+                ui.radio_value(&mut self.startup_page, StartupPage::HomePage, tr("Open the home page"));
             });
             ui.horizontal(|ui| {
-                ui.radio_value(&mut self.startup_page, StartupOption::NewTab, "Open a new tab");
+                ui.radio_value(&mut self.startup_page, StartupPage::NewTab, tr("Open a new tab"));
             });
             ui.horizontal(|ui| {
-                ui.radio_value(&mut self.startup_page, StartupOption::LastSession, "Continue where you left off");
+                ui.radio_value(&mut self.startup_page, StartupPage::LastSession, tr("Continue where you left off"));
             });
+            if self.startup_page != startup_page {
+                let startup = self.startup_page;
+                Preferences::update(|prefs| prefs.startup = startup);
+            }
+            
+            ui.add_space(20.0);
+            
+            self.render_language_settings(ui);
+            ui.add_space(12.0);
+            self.render_translation_settings(ui);
             
             ui.add_space(20.0);
             
             // Search engine
-            ui.label(RichText::new("Default search engine")
+            ui.label(RichText::new(tr("Default search engine"))
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 ui.radio_value(&mut self.search_engine, SearchEngine::DuckDuckGo, "DuckDuckGo (Privacy-focused)");
@@ -212,15 +296,15 @@ impl SettingsPage {
             // Downloads
             ui.label(RichText::new("Downloads")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
-                ui.label("Save files to:");
+                ui.label(tr("Save files to:"));
                 ui.add(egui::TextEdit::singleline(&mut self.downloads_path)
                     .desired_width(300.0));
                     
                 if ui.button(RichText::new(format!("{} Browse...", NeonIcons::FOLDER))
-                    .color(NeonTheme::NEON_CYAN)).clicked() {
+                    .color(NeonTheme::neon_cyan())).clicked() {
                     // TODO: Open file dialog
                     // For now, show a placeholder path
                     self.downloads_path = "~/Downloads".to_string();
@@ -231,7 +315,7 @@ impl SettingsPage {
             
             // Quick folder shortcuts
             ui.horizontal(|ui| {
-                ui.label("Quick access:");
+                ui.label(tr("Quick access:"));
                 if ui.small_button("Desktop").clicked() {
                     self.downloads_path = "~/Desktop".to_string();
                 }
@@ -242,9 +326,102 @@ impl SettingsPage {
                     self.downloads_path = "~/Documents".to_string();
                 }
             });
+            
+            ui.add_space(20.0);
+            
+            // Import
+            ui.label(RichText::new(tr("Import from another browser"))
+                .strong()
+                .color(NeonTheme::primary_text()));
+            ui.horizontal(|ui| {
+                ui.label(tr("History, bookmarks and cookies from Chrome or Firefox"));
+                if ui.button(RichText::new(format!("{} {}", NeonIcons::DOWNLOAD, tr("Import..."))).color(NeonTheme::neon_cyan())).clicked() {
+                    self.actions.push(PageAction::OpenInNewTab("neon://import".to_string()));
+                }
+            });
+            
+            ui.add_space(20.0);
+            
+            self.render_default_browser(ui);
+            
+            ui.add_space(20.0);
+            
+            self.render_protocol_handlers(ui);
         });
     }
     
+    fn render_default_browser(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(tr("Default browser"))
+            .strong()
+            .color(NeonTheme::primary_text()));
+        let is_default = *self.default_browser.get_or_insert_with(default_browser::is_default);
+        ui.horizontal(|ui| {
+            if is_default == Some(true) {
+                ui.label(RichText::new(format!("{} {}", NeonIcons::CHECK, tr("NeonSearch is your default browser")))
+                    .color(NeonTheme::success_color()));
+            } else if ui.button(RichText::new(tr("Make NeonSearch the default browser")).color(NeonTheme::neon_cyan())).clicked() {
+                self.default_browser_error = default_browser::register().err().map(|e| e.to_string());
+                // Asked again next time, since Windows and macOS leave the final choice to their own settings
+                self.default_browser = None;
+            }
+        });
+        if let Some(error) = &self.default_browser_error {
+            ui.label(RichText::new(error).size(11.0).color(NeonTheme::error_color()));
+        }
+    }
+    
+    // Web apps that open mailto:, irc:, magnet: and other links instead of the system's applications
+    fn render_protocol_handlers(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(tr("Links to other apps"))
+            .strong()
+            .color(NeonTheme::primary_text()));
+        ui.label(RichText::new(tr("Links with a scheme below open the page mapped to it. Others open in your system's default application."))
+            .size(11.0)
+            .color(NeonTheme::muted_text()));
+        
+        for (scheme, template) in &Preferences::current().protocol_handlers {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{}:", scheme)).strong().color(NeonTheme::primary_text()));
+                ui.label(RichText::new(template.as_str()).color(NeonTheme::secondary_text()));
+                if ui.small_button(NeonIcons::X).on_hover_text("Open these links in the system's application").clicked() {
+                    Preferences::update(|prefs| {
+                        prefs.protocol_handlers.remove(scheme);
+                    });
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.handler_scheme)
+                .hint_text("mailto")
+                .desired_width(80.0));
+            ui.add(egui::TextEdit::singleline(&mut self.handler_template)
+                .hint_text("https://mail.example.com/compose?to=%s")
+                .desired_width(300.0));
+            if ui.add_enabled(!self.handler_template.trim().is_empty(), egui::Button::new(tr("Map"))).clicked() {
+                let template = self.handler_template.trim().to_string();
+                let scheme = self.handler_scheme.trim().trim_end_matches(':').to_ascii_lowercase();
+                let valid = if protocol_handlers::is_valid_scheme(&scheme) {
+                    protocol_handlers::validate_template(&template).map_err(|e| e.to_string())
+                } else {
+                    Err(format!("{}: can't be mapped. Use one of {} or a web+ scheme", scheme, protocol_handlers::SAFELISTED_SCHEMES.join(", ")))
+                };
+                self.handler_error = match valid {
+                    Ok(()) => {
+                        Preferences::update(|prefs| {
+                            prefs.protocol_handlers.insert(scheme, template);
+                        });
+                        self.handler_template.clear();
+                        None
+                    }
+                    Err(e) => Some(e),
+                };
+            }
+        });
+        if let Some(error) = &self.handler_error {
+            ui.label(RichText::new(error).size(11.0).color(NeonTheme::error_color()));
+        }
+    }
+    
     fn render_privacy_settings(&mut self, ui: &mut Ui) {
         components::section_header(ui, NeonIcons::SHIELD_CHECK, "Privacy & Security");
         
@@ -252,24 +429,120 @@ impl SettingsPage {
             ui.label(RichText::new("Web Content")
                 .size(18.0)
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.add_space(12.0);
             
-            ui.checkbox(&mut self.javascript_enabled, "Enable JavaScript");
+            if ui.checkbox(&mut self.javascript_enabled, "Enable JavaScript").changed() {
+                let enabled = self.javascript_enabled;
+                // Overrides that now match the default have nothing left to override
+                Preferences::update(|prefs| {
+                    prefs.javascript.enabled = enabled;
+                    prefs.javascript.site_overrides.retain(|_, allowed| *allowed != enabled);
+                });
+                self.actions.push(PageAction::JavaScriptSettingsChanged);
+            }
+            let site_overrides = Preferences::current().javascript.site_overrides.clone();
+            if !site_overrides.is_empty() {
+                let mut removed = None;
+                ui.indent("javascript_site_overrides", |ui| {
+                    for (origin, allowed) in &site_overrides {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(origin).size(12.0).color(NeonTheme::secondary_text()));
+                            match allowed {
+                                true => ui.label(RichText::new("Allowed").size(12.0).color(NeonTheme::success_color())),
+                                false => ui.label(RichText::new("Blocked").size(12.0).color(NeonTheme::error_color())),
+                            };
+                            if ui.small_button("Remove").on_hover_text("Follow the setting above on this site").clicked() {
+                                removed = Some(origin.clone());
+                            }
+                        });
+                    }
+                });
+                if let Some(origin) = removed {
+                    Preferences::update(|prefs| {
+                        prefs.javascript.site_overrides.remove(&origin);
+                    });
+                    self.actions.push(PageAction::JavaScriptSettingsChanged);
+                }
+            }
+            ui.horizontal(|ui| {
+                let mut slow_script_secs = Preferences::current().javascript.slow_script_secs;
+                ui.label("Offer to stop scripts that run longer than:");
+                if ui.add(Slider::new(&mut slow_script_secs, 1..=60).text("s")).changed() {
+                    Preferences::update(|prefs| prefs.javascript.slow_script_secs = slow_script_secs);
+                }
+            });
             ui.checkbox(&mut self.images_enabled, "Load images");
             ui.checkbox(&mut self.cookies_enabled, "Accept cookies");
+            if ui.checkbox(&mut self.keep_session_cookies, "Keep session cookies after closing NeonSearch").changed() {
+                let keep = self.keep_session_cookies;
+                Preferences::update(|prefs| prefs.cookies.keep_session_cookies = keep);
+            }
+            let clear_on_close = Preferences::current().cookies.clear_on_close_sites.clone();
+            if !clear_on_close.is_empty() {
+                ui.label(RichText::new(format!("Cookies cleared when their last tab closes: {}", clear_on_close.into_iter().collect::<Vec<_>>().join(", ")))
+                    .size(11.0)
+                    .color(NeonTheme::muted_text()));
+            }
             
             ui.add_space(20.0);
             
             ui.label(RichText::new("Privacy Protection")
                 .size(18.0)
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.add_space(12.0);
             
-            ui.checkbox(&mut self.tracking_protection, "Block tracking requests");
+            if ui.checkbox(&mut self.tracking_protection, "Block trackers and partition third-party cookies").changed() {
+                let enabled = self.tracking_protection;
+                Preferences::update(|prefs| prefs.tracking.enabled = enabled);
+            }
+            let exceptions = Preferences::current().tracking.exceptions.clone();
+            if !exceptions.is_empty() {
+                ui.label(RichText::new(format!("Turned off on: {}", exceptions.into_iter().collect::<Vec<_>>().join(", ")))
+                    .size(11.0)
+                    .color(NeonTheme::muted_text()));
+            }
+            if ui.checkbox(&mut self.strip_tracking_on_copy, "Remove tracking parameters from copied addresses").changed() {
+                let strip = self.strip_tracking_on_copy;
+                Preferences::update(|prefs| prefs.strip_tracking_on_copy = strip);
+            }
+            
+            let current_privacy = Preferences::current().privacy.clone();
+            let mut privacy = current_privacy.clone();
+            ui.checkbox(&mut privacy.do_not_track, "Send a Do Not Track request");
+            ui.checkbox(&mut privacy.global_privacy_control, "Tell sites not to sell or share my data (Global Privacy Control)");
+            ui.checkbox(&mut privacy.reduce_fingerprinting, "Reduce fingerprinting")
+                .on_hover_text("Scripts see a common screen size, UTC time zone, English locale and hardware instead of this machine's");
+            if privacy != current_privacy {
+                Preferences::update(|prefs| prefs.privacy = privacy);
+            }
+            
+            // Sites can tighten or loosen this with a Referrer-Policy header or meta tag
+            let current = Preferences::current().referrer_policy;
+            let mut referrer_policy = current;
+            ui.horizontal(|ui| {
+                ui.label("Send referrer");
+                egui::ComboBox::from_id_salt("referrer_policy")
+                    .selected_text(referrer_policy.label())
+                    .show_ui(ui, |ui| {
+                        for option in ReferrerPolicy::ALL {
+                            ui.selectable_value(&mut referrer_policy, option, option.label());
+                        }
+                    });
+            });
+            if referrer_policy != current {
+                Preferences::update(|prefs| prefs.referrer_policy = referrer_policy);
+            }
+            
+            let mut allow_file_urls = Preferences::current().allow_file_urls;
+            if ui.checkbox(&mut allow_file_urls, "Open local files and folders from file:// addresses")
+                .on_hover_text("Pages opened from disk can load other files next to them; web pages never can")
+                .changed() {
+                Preferences::update(|prefs| prefs.allow_file_urls = allow_file_urls);
+            }
             
             ui.add_space(20.0);
             
@@ -277,20 +550,23 @@ impl SettingsPage {
             ui.horizontal(|ui| {
                 if ui.button(RichText::new(format!("{} Clear Browsing Data...", NeonIcons::TRASH))
                     .color(NeonTheme::error_color())).clicked() {
-                    // TODO: Open clear data dialog
-                    // For now, show confirmation
-                    println!("Clear browsing data clicked");
+                    self.clear_data = Some(ClearBrowsingData::default());
+                    self.clear_data_status = None;
                 }
                 
                 ui.add_space(8.0);
                 
                 if ui.button(RichText::new(format!("{} Manage Cookies...", NeonIcons::GEAR))
-                    .color(NeonTheme::NEON_CYAN)).clicked() {
+                    .color(NeonTheme::neon_cyan())).clicked() {
                     // TODO: Open cookie manager
-                    println!("Manage cookies clicked");
+                    log::debug!("Manage cookies clicked");
                 }
             });
             
+            if let Some(status) = &self.clear_data_status {
+                ui.label(RichText::new(status).size(11.0).color(NeonTheme::muted_text()));
+            }
+            
             ui.add_space(12.0);
             
             // Privacy status indicators
@@ -302,27 +578,107 @@ impl SettingsPage {
         });
     }
     
+    // Language of the UI, which also sets how numbers and dates are written
+    fn render_language_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(tr("Language"))
+            .strong()
+            .color(NeonTheme::primary_text()));
+        
+        let (packs, load_errors) = i18n::available();
+        let mut language = Preferences::current().language.clone();
+        let selected = match &language {
+            Some(code) => packs.iter().find(|pack| &pack.language == code).map_or(code.clone(), |pack| pack.name.clone()),
+            None => format!("{} ({})", tr("System language"), i18n::active().name),
+        };
+        egui::ComboBox::from_id_salt("ui_language")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut language, None, tr("System language"));
+                for pack in &packs {
+                    ui.selectable_value(&mut language, Some(pack.language.clone()), &pack.name);
+                }
+            });
+        if language != Preferences::current().language {
+            i18n::init(language.as_deref());
+            Preferences::update(|prefs| prefs.language = language);
+        }
+        ui.label(RichText::new(format!("{} {}", tr("Add language packs as .toml files in"), crate::storage::data_dir().join("locales").display()))
+            .size(12.0)
+            .color(NeonTheme::muted_text()));
+        for error in &load_errors {
+            ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error)).size(12.0).color(NeonTheme::warning_color()));
+        }
+    }
+    
+    // The service "Translate page" sends text to, and the language it asks for
+    fn render_translation_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new("Page Translation")
+            .strong()
+            .color(NeonTheme::primary_text()));
+        
+        let current = Preferences::current().translation.clone();
+        let mut settings = current.clone();
+        ui.horizontal(|ui| {
+            ui.label("Translate with");
+            egui::ComboBox::from_id_salt("translation_provider")
+                .selected_text(settings.provider.label())
+                .show_ui(ui, |ui| {
+                    let libre = TranslationProvider::default();
+                    let deepl = TranslationProvider::DeepL { api_key: String::new() };
+                    for option in [libre, deepl] {
+                        let selected = std::mem::discriminant(&option) == std::mem::discriminant(&settings.provider);
+                        if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                            settings.provider = option;
+                        }
+                    }
+                });
+        });
+        match &mut settings.provider {
+            TranslationProvider::LibreTranslate { url, api_key } => {
+                ui.horizontal(|ui| {
+                    ui.label("Server");
+                    ui.add(egui::TextEdit::singleline(url).desired_width(240.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("API key");
+                    ui.add(egui::TextEdit::singleline(api_key).password(true).hint_text("Not needed for your own server").desired_width(240.0));
+                });
+            }
+            TranslationProvider::DeepL { api_key } => {
+                ui.horizontal(|ui| {
+                    ui.label("API key");
+                    ui.add(egui::TextEdit::singleline(api_key).password(true).desired_width(240.0));
+                });
+            }
+        }
+        let mut target = settings.target_language.clone().unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.label("Into");
+            ui.add(egui::TextEdit::singleline(&mut target)
+                .hint_text(format!("{} (browser language)", TranslationSettings::default().target_language()))
+                .desired_width(120.0));
+        });
+        settings.target_language = Some(target.trim().to_string()).filter(|target| !target.is_empty());
+        if settings != current {
+            Preferences::update(|prefs| prefs.translation = settings);
+        }
+        ui.label(RichText::new("Page text is sent to this service when you choose Translate page from the menu")
+            .size(12.0)
+            .color(NeonTheme::muted_text()));
+    }
+    
     fn render_appearance_settings(&mut self, ui: &mut Ui) {
         components::section_header(ui, "🎨", "Appearance");
         
         components::card_container(ui, |ui| {
-            // Theme selection
-            ui.label(RichText::new("Theme")
-                .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
-            
-            ui.horizontal(|ui| {
-                ui.radio_value(&mut self.theme, ThemeOption::Dark, "Dark");
-                ui.radio_value(&mut self.theme, ThemeOption::Light, "Light");
-                ui.radio_value(&mut self.theme, ThemeOption::Auto, "Auto (System)");
-            });
+            self.render_theme_settings(ui);
             
             ui.add_space(20.0);
             
             // Font size
             ui.label(RichText::new("Font Size")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 ui.add(Slider::new(&mut self.font_size, 8.0..=24.0)
@@ -338,10 +694,216 @@ impl SettingsPage {
             // UI options
             ui.label(RichText::new("Interface")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.checkbox(&mut self.show_bookmarks_bar, "Show bookmarks bar");
+            let mut vertical_tabs = Preferences::current().vertical_tabs;
+            if ui.checkbox(&mut vertical_tabs, "Vertical tabs")
+                .on_hover_text("List tabs in a sidebar, with groups and search")
+                .changed() {
+                Preferences::update(|prefs| prefs.vertical_tabs = vertical_tabs);
+            }
+        });
+        
+        ui.add_space(16.0);
+        components::card_container(ui, |ui| self.render_user_styles(ui));
+    }
+    
+    // Theme picker, and a way to start a theme file from the current one
+    fn render_theme_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new("Theme")
+            .strong()
+            .color(NeonTheme::primary_text()));
+        
+        let themes = ThemeManager::shared();
+        let Ok(mut themes) = themes.lock() else {
+            return;
+        };
+        let mut selected = Preferences::current().theme.clone();
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("theme")
+                .selected_text(&selected)
+                .show_ui(ui, |ui| {
+                    for theme in themes.list() {
+                        let label = if theme.palette.dark { theme.name.clone() } else { format!("{} (light)", theme.name) };
+                        ui.selectable_value(&mut selected, theme.name.clone(), label);
+                    }
+                });
+            if let Some(path) = themes.get(&selected).and_then(|theme| theme.path.as_ref()) {
+                ui.label(RichText::new(path.display().to_string()).size(11.0).monospace().color(NeonTheme::muted_text()))
+                    .on_hover_text("Edit this file to change the theme; it updates as soon as the file is saved");
+            }
+        });
+        if selected != Preferences::current().theme {
+            Preferences::update(|prefs| prefs.theme = selected.clone());
+        }
+        
+        // Switching between a light and a dark theme with the system or the clock
+        let mut auto = Preferences::current().auto_theme.clone();
+        let before = auto.clone();
+        let names: Vec<String> = themes.list().iter().map(|theme| theme.name.clone()).collect();
+        ui.horizontal(|ui| {
+            ui.label("Switch automatically");
+            let label = match auto.switch {
+                ThemeSwitch::Off => "Off",
+                ThemeSwitch::System => "With the system",
+                ThemeSwitch::Schedule { .. } => "On a schedule",
+            };
+            egui::ComboBox::from_id_salt("theme_switch")
+                .selected_text(label)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut auto.switch, ThemeSwitch::Off, "Off");
+                    ui.selectable_value(&mut auto.switch, ThemeSwitch::System, "With the system");
+                    let scheduled = matches!(auto.switch, ThemeSwitch::Schedule { .. });
+                    if ui.selectable_label(scheduled, "On a schedule").clicked() && !scheduled {
+                        auto.switch = DEFAULT_THEME_SCHEDULE;
+                    }
+                });
+        });
+        if auto.switch != ThemeSwitch::Off {
+            ui.horizontal(|ui| {
+                for (id, label, theme) in [("light_theme", "Light", &mut auto.light_theme), ("dark_theme", "Dark", &mut auto.dark_theme)] {
+                    ui.label(label);
+                    egui::ComboBox::from_id_salt(id)
+                        .selected_text(theme.as_str())
+                        .show_ui(ui, |ui| {
+                            for name in &names {
+                                ui.selectable_value(theme, name.clone(), name);
+                            }
+                        });
+                }
+            });
+        }
+        if let ThemeSwitch::Schedule { light_from, light_until } = &mut auto.switch {
+            ui.horizontal(|ui| {
+                ui.label("Light from");
+                components::time_of_day(ui, light_from);
+                ui.label("until");
+                components::time_of_day(ui, light_until);
+            });
+        }
+        if auto.switch == ThemeSwitch::System && ui.ctx().system_theme().is_none() {
+            ui.label(RichText::new("The system doesn't say whether it's in dark mode, so the theme above is shown")
+                .size(12.0)
+                .color(NeonTheme::muted_text()));
+        }
+        if auto != before {
+            Preferences::update(|prefs| prefs.auto_theme = auto);
+        }
+        
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_theme_name)
+                .hint_text("My theme")
+                .desired_width(160.0));
+            let button = ui.add_enabled(!self.new_theme_name.trim().is_empty(), egui::Button::new("Create from current"))
+                .on_hover_text(format!("Writes a theme file to {} with the current theme's colors", themes.dir().display()));
+            if button.clicked() {
+                let name = std::mem::take(&mut self.new_theme_name);
+                self.theme_status = Some(match themes.create(&name, NeonTheme::palette()) {
+                    Ok(path) => {
+                        Preferences::update(|prefs| prefs.theme = name.trim().to_string());
+                        (format!("Created {}", path.display()), false)
+                    }
+                    Err(e) => (e.to_string(), true),
+                });
+            }
+        });
+        if let Some((message, is_error)) = &self.theme_status {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+        for error in themes.load_errors() {
+            ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error)).size(12.0).color(NeonTheme::warning_color()));
+        }
+    }
+    
+    // Open the stylesheet for `site`, or the global one, in the editor
+    fn open_user_style(&mut self, site: Option<String>) {
+        let css = UserStyles::shared().lock()
+            .map(|styles| styles.get(site.as_deref()).to_string())
+            .unwrap_or_default();
+        self.user_style_draft = Some((site, css));
+        self.user_style_status = None;
+    }
+    
+    // Global and per-site CSS that outranks the pages' own styles
+    fn render_user_styles(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new("User Stylesheets")
+            .strong()
+            .color(NeonTheme::primary_text()));
+        ui.label(RichText::new("CSS that overrides pages' own styles, for every site or for one site and its subdomains")
+            .size(12.0)
+            .color(NeonTheme::muted_text()));
+        ui.add_space(8.0);
+        
+        let sites: Vec<String> = UserStyles::shared().lock()
+            .map(|styles| styles.sites().map(str::to_string).collect())
+            .unwrap_or_default();
+        let mut open = None;
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("All sites").color(NeonTheme::primary_text()));
+            if ui.small_button("Edit").clicked() {
+                open = Some(None);
+            }
+        });
+        for site in sites {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(&site).color(NeonTheme::primary_text()));
+                if ui.small_button("Edit").clicked() {
+                    open = Some(Some(site.clone()));
+                }
+                if ui.small_button(NeonIcons::X).on_hover_text("Remove this site's styles").clicked() {
+                    if let Ok(mut styles) = UserStyles::shared().lock() {
+                        if let Err(e) = styles.set(Some(&site), "") {
+                            self.user_style_status = Some((e.to_string(), true));
+                        }
+                    }
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.user_style_site)
+                .hint_text("example.com")
+                .desired_width(160.0));
+            if ui.add_enabled(!self.user_style_site.trim().is_empty(), egui::Button::new("Add site")).clicked() {
+                let site = std::mem::take(&mut self.user_style_site).trim().to_ascii_lowercase();
+                open = Some(Some(site));
+            }
         });
+        if let Some(site) = open {
+            self.open_user_style(site);
+        }
+        
+        if let Some((site, css)) = self.user_style_draft.as_mut() {
+            ui.add_space(12.0);
+            ui.label(RichText::new(format!("Styles for {}", site.as_deref().unwrap_or("all sites")))
+                .color(NeonTheme::secondary_text()));
+            ui.add(egui::TextEdit::multiline(css)
+                .code_editor()
+                .desired_rows(10)
+                .desired_width(f32::INFINITY));
+            let (mut save, mut close) = (false, false);
+            ui.horizontal(|ui| {
+                save = ui.button("Save").clicked();
+                close = ui.button("Close").clicked();
+            });
+            if save {
+                let result = UserStyles::shared().lock()
+                    .map_err(|_| anyhow::anyhow!("User stylesheets are unavailable"))
+                    .and_then(|mut styles| styles.set(site.as_deref(), css));
+                self.user_style_status = Some(match result {
+                    Ok(()) => ("Saved; open pages pick the styles up right away".to_string(), false),
+                    Err(e) => (e.to_string(), true),
+                });
+            }
+            if close {
+                self.user_style_draft = None;
+            }
+        }
+        if let Some((message, is_error)) = &self.user_style_status {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
     }
     
     fn render_performance_settings(&mut self, ui: &mut Ui) {
@@ -351,7 +913,7 @@ impl SettingsPage {
             // Cache settings
             ui.label(RichText::new("Cache")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 ui.label("Cache size:");
@@ -363,14 +925,14 @@ impl SettingsPage {
             ui.horizontal(|ui| {
                 if ui.button(RichText::new(format!("{} Clear Cache", NeonIcons::TRASH))
                     .color(NeonTheme::warning_color())).clicked() {
-                    // TODO: Clear cache
-                    println!("Cache cleared");
+                    HttpCache::shared().clear();
+                    log::info!("Cache cleared");
                 }
                 
                 ui.add_space(8.0);
                 
                 ui.label(RichText::new(format!("Current cache size: {:.1} MB", self.cache_size * 0.7))
-                    .color(NeonTheme::SECONDARY_TEXT));
+                    .color(NeonTheme::secondary_text()));
             });
             
             ui.add_space(20.0);
@@ -378,7 +940,7 @@ impl SettingsPage {
             // Network settings
             ui.label(RichText::new("Network")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 ui.label("Max connections:");
@@ -386,18 +948,197 @@ impl SettingsPage {
                     .show_value(true));
             });
             
+            ui.horizontal(|ui| {
+                let toggled = ui.checkbox(&mut self.limit_page_size, "Ask before loading pages larger than").changed();
+                let resized = ui.add_enabled(self.limit_page_size, Slider::new(&mut self.page_size_limit_mb, 5..=1024)
+                    .logarithmic(true)
+                    .text("MB"))
+                    .changed();
+                if toggled || resized {
+                    let limit = self.limit_page_size.then_some(self.page_size_limit_mb);
+                    Preferences::update(|prefs| prefs.page_size_limit_mb = limit);
+                }
+            });
+            ui.label(RichText::new("Larger pages stop loading and offer to continue or download instead.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            
+            let current_prefetch = Preferences::current().prefetch.clone();
+            let mut prefetch = current_prefetch.clone();
+            ui.checkbox(&mut prefetch.enabled, "Preload pages you're likely to open next");
+            ui.add_enabled_ui(prefetch.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Pages at a time:");
+                    ui.add(Slider::new(&mut prefetch.max_pages, 1..=10).show_value(true));
+                });
+                ui.checkbox(&mut prefetch.preparse, "Also parse preloaded pages");
+            });
+            if prefetch != current_prefetch {
+                Preferences::update(|prefs| prefs.prefetch = prefetch);
+            }
+            ui.label(RichText::new("The top address bar suggestion and pages' prefetch links load while the network is otherwise idle.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            
+            ui.add_space(20.0);
+            
+            // Image loading
+            ui.label(RichText::new("Images")
+                .strong()
+                .color(NeonTheme::primary_text()));
+            
+            ui.horizontal(|ui| {
+                ui.label("Image loading:");
+                let before = self.image_loading;
+                egui::ComboBox::from_id_salt("image_loading_mode")
+                    .selected_text(self.image_loading.label())
+                    .show_ui(ui, |ui| {
+                        for mode in ImageLoadingMode::ALL {
+                            ui.selectable_value(&mut self.image_loading, mode, mode.label());
+                        }
+                    });
+                if self.image_loading != before {
+                    let mode = self.image_loading;
+                    Preferences::update(|prefs| prefs.image_loading = mode);
+                }
+            });
+            ui.label(RichText::new("Lazy images load as you scroll near them. Applies to pages loaded after the change.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            
             ui.add_space(20.0);
             
             // Hardware acceleration
             ui.label(RichText::new("Graphics")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.checkbox(&mut self.enable_hardware_acceleration, 
                 "Use hardware acceleration when available");
         });
     }
     
+    fn render_shortcut_settings(&mut self, ui: &mut Ui) {
+        components::section_header(ui, NeonIcons::TERMINAL, "Keyboard Shortcuts");
+        
+        let shortcuts = Shortcuts::current();
+        if let Some(action) = self.recording_shortcut {
+            shortcuts::hold_for_recording(ui.ctx());
+            let press = ui.input(|i| i.events.iter().find_map(|event| match event {
+                egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                _ => None,
+            }));
+            if let Some((key, modifiers)) = press {
+                self.recording_shortcut = None;
+                self.bind_shortcut(&shortcuts, action, shortcuts::chord_from_press(key, modifiers, shortcuts.platform()));
+            }
+        }
+        
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new("Click Change, then press the keys to use. Ctrl+1 to Ctrl+9 always switch to a tab by its place in the tab strip.")
+                .color(NeonTheme::secondary_text()));
+            ui.add_space(12.0);
+            
+            let overrides = Preferences::current().shortcuts.clone();
+            egui::Grid::new("shortcut_settings")
+                .num_columns(3)
+                .spacing([24.0, 8.0])
+                .show(ui, |ui| {
+                    for action in ShortcutAction::ALL {
+                        ui.label(RichText::new(action.label()).color(NeonTheme::primary_text()));
+                        let recording = self.recording_shortcut == Some(action);
+                        let chord = if recording { "Press a key…".to_string() } else { shortcuts.describe(action) };
+                        ui.label(RichText::new(chord).monospace().color(if recording { NeonTheme::neon_cyan() } else { NeonTheme::secondary_text() }));
+                        ui.horizontal(|ui| {
+                            if recording {
+                                if ui.small_button("Cancel").clicked() {
+                                    self.recording_shortcut = None;
+                                }
+                            } else if ui.small_button("Change").clicked() {
+                                shortcuts::hold_for_recording(ui.ctx());
+                                self.recording_shortcut = Some(action);
+                                self.shortcut_conflict = None;
+                                self.shortcut_error = None;
+                            }
+                            if ui.add_enabled(shortcuts.chord(action).is_some(), egui::Button::new("Remove").small()).clicked() {
+                                set_shortcut(action, None);
+                            }
+                            if ui.add_enabled(overrides.contains_key(action.id()), egui::Button::new("Reset").small()).clicked() {
+                                set_shortcut(action, action.default_chord(shortcuts.platform()));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            
+            if let Some((action, chord, other)) = self.shortcut_conflict {
+                ui.add_space(12.0);
+                let chord_text = shortcuts::format_chord(&chord, shortcuts.platform());
+                ui.label(RichText::new(format!("{} is already used for {}.", chord_text, other.label()))
+                    .color(NeonTheme::warning_color()));
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Use it for {} instead", action.label())).clicked() {
+                        set_shortcut(other, None);
+                        set_shortcut(action, Some(chord));
+                        self.shortcut_conflict = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.shortcut_conflict = None;
+                    }
+                });
+            }
+            if let Some(error) = &self.shortcut_error {
+                ui.add_space(12.0);
+                ui.label(RichText::new(error).color(NeonTheme::error_color()));
+            }
+            // Only possible when the preferences file was edited by hand
+            for (first, second) in shortcuts.conflicts() {
+                ui.label(RichText::new(format!("{} and {} share {}; only {} runs.", first.label(), second.label(), shortcuts.describe(first), first.label()))
+                    .color(NeonTheme::warning_color()));
+            }
+            
+            ui.add_space(12.0);
+            if ui.add_enabled(!overrides.is_empty(), egui::Button::new("Restore all defaults")).clicked() {
+                Preferences::update(|prefs| prefs.shortcuts.clear());
+                self.shortcut_conflict = None;
+                self.shortcut_error = None;
+            }
+        });
+        
+        components::section_header(ui, "🖱", "Mouse");
+        components::card_container(ui, |ui| {
+            let current_mouse = Preferences::current().mouse.clone();
+            let mut mouse = current_mouse.clone();
+            ui.checkbox(&mut mouse.rocker_gestures, "Rocker gestures");
+            ui.label(RichText::new("Hold the right button and click the left to go back; hold the left and click the right to go forward.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            ui.add_space(6.0);
+            ui.checkbox(&mut mouse.drag_gestures, "Drag gestures");
+            ui.label(RichText::new("Drag left or right with the right button held to go back or forward.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            if mouse != current_mouse {
+                Preferences::update(|prefs| prefs.mouse = mouse);
+            }
+            ui.add_space(12.0);
+            ui.label(RichText::new("The mouse's back and forward buttons always navigate, and middle-clicking opens links in a new tab or closes the tab clicked.")
+                .color(NeonTheme::secondary_text()));
+        });
+    }
+    
+    // Give `action` the chord just pressed, unless the tab numbers or another action have it
+    fn bind_shortcut(&mut self, shortcuts: &Shortcuts, action: ShortcutAction, chord: egui::KeyboardShortcut) {
+        if shortcuts::is_reserved(&chord) {
+            self.shortcut_error = Some(format!("{} switches to a tab by its place in the tab strip.", shortcuts::format_chord(&chord, shortcuts.platform())));
+            return;
+        }
+        match shortcuts.action_for(&chord) {
+            Some(other) if other != action => self.shortcut_conflict = Some((action, chord, other)),
+            _ => set_shortcut(action, Some(chord)),
+        }
+    }
+
     fn render_advanced_settings(&mut self, ui: &mut Ui) {
         components::section_header(ui, NeonIcons::WRENCH, "Advanced Settings");
         
@@ -412,13 +1153,13 @@ impl SettingsPage {
             // Developer options
             ui.label(RichText::new("Developer")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 if ui.button(RichText::new(format!("{} Open Developer Console", NeonIcons::CODE))
-                    .color(NeonTheme::NEON_CYAN)).clicked() {
+                    .color(NeonTheme::neon_cyan())).clicked() {
                     // TODO: Open dev console
-                    println!("Developer console opened");
+                    log::debug!("Developer console opened");
                 }
                 
                 ui.add_space(8.0);
@@ -427,30 +1168,49 @@ impl SettingsPage {
                     .color(NeonTheme::error_color())).clicked() {
                     // Reset to defaults
                     *self = Self::new();
-                    println!("Settings reset to defaults");
+                    log::info!("Settings reset to defaults");
                 }
             });
             
             ui.add_space(20.0);
             
+            self.render_user_agent_settings(ui);
+            
+            ui.add_space(20.0);
+            
+            // Experimental web platform features
+            ui.label(RichText::new("Experimental")
+                .strong()
+                .color(NeonTheme::primary_text()));
+            
+            if ui.checkbox(&mut self.experimental_indexed_db, "IndexedDB-lite storage for page scripts").changed() {
+                let enabled = self.experimental_indexed_db;
+                Preferences::update(|prefs| prefs.experimental_indexed_db = enabled);
+            }
+            ui.label(RichText::new("Lets sites keep structured data on this device, within a per-site quota. Cleared with Clear Browsing Data.")
+                .size(11.0)
+                .color(NeonTheme::muted_text()));
+            
+            ui.add_space(20.0);
+            
             // Data management
             ui.label(RichText::new("Data")
                 .strong()
-                .color(NeonTheme::PRIMARY_TEXT));
+                .color(NeonTheme::primary_text()));
             
             ui.horizontal(|ui| {
                 if ui.button(RichText::new(format!("{} Export Settings...", NeonIcons::DOWNLOAD))
-                    .color(NeonTheme::NEON_CYAN)).clicked() {
+                    .color(NeonTheme::neon_cyan())).clicked() {
                     // TODO: Export settings
-                    println!("Settings exported");
+                    log::debug!("Settings exported");
                 }
                 
                 ui.add_space(8.0);
                 
                 if ui.button(RichText::new(format!("{} Import Settings...", NeonIcons::UPLOAD))
-                    .color(NeonTheme::NEON_CYAN)).clicked() {
+                    .color(NeonTheme::neon_cyan())).clicked() {
                     // TODO: Import settings
-                    println!("Settings import dialog opened");
+                    log::debug!("Settings import dialog opened");
                 }
             });
             
@@ -461,14 +1221,134 @@ impl SettingsPage {
             ui.add_space(12.0);
             
             ui.horizontal(|ui| {
-                ui.label(RichText::new("Settings Version:").color(NeonTheme::SECONDARY_TEXT));
-                ui.label(RichText::new("1.0.0").color(NeonTheme::PRIMARY_TEXT));
+                ui.label(RichText::new("Settings Version:").color(NeonTheme::secondary_text()));
+                ui.label(RichText::new("1.0.0").color(NeonTheme::primary_text()));
                 
                 ui.add_space(20.0);
                 
-                ui.label(RichText::new("Last Modified:").color(NeonTheme::SECONDARY_TEXT));
-                ui.label(RichText::new("Today").color(NeonTheme::PRIMARY_TEXT));
+                ui.label(RichText::new("Last Modified:").color(NeonTheme::secondary_text()));
+                ui.label(RichText::new("Today").color(NeonTheme::primary_text()));
+            });
+        });
+    }
+    
+    // Default user-agent profile and the sites that get another one
+    fn render_user_agent_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new("User Agent")
+            .strong()
+            .color(NeonTheme::primary_text()));
+        
+        let settings = Preferences::current().user_agent.clone();
+        let mut preset = settings.preset;
+        ui.horizontal(|ui| {
+            ui.label("Identify as");
+            egui::ComboBox::from_id_salt("user_agent_preset")
+                .selected_text(preset.label())
+                .show_ui(ui, |ui| {
+                    for option in UserAgentPreset::ALL {
+                        ui.selectable_value(&mut preset, option, option.label());
+                    }
+                });
+        });
+        if preset != settings.preset {
+            Preferences::update(|prefs| prefs.user_agent.preset = preset);
+        }
+        ui.label(RichText::new(preset.user_agent())
+            .size(11.0)
+            .color(NeonTheme::muted_text()));
+        
+        ui.add_space(8.0);
+        for (host, site_preset) in &settings.site_overrides {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(host).color(NeonTheme::primary_text()));
+                ui.label(RichText::new(site_preset.label()).color(NeonTheme::secondary_text()));
+                if ui.small_button(NeonIcons::X).on_hover_text("Use the default profile on this site").clicked() {
+                    Preferences::update(|prefs| prefs.user_agent.set_override(host, None));
+                }
             });
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.user_agent_host)
+                .hint_text("example.com")
+                .desired_width(160.0));
+            egui::ComboBox::from_id_salt("user_agent_override")
+                .selected_text(self.user_agent_override.label())
+                .show_ui(ui, |ui| {
+                    for option in UserAgentPreset::ALL {
+                        ui.selectable_value(&mut self.user_agent_override, option, option.label());
+                    }
+                });
+            if ui.add_enabled(!self.user_agent_host.trim().is_empty(), egui::Button::new("Add site")).clicked() {
+                let (host, preset) = (std::mem::take(&mut self.user_agent_host), self.user_agent_override);
+                Preferences::update(|prefs| prefs.user_agent.set_override(&host, Some(preset)));
+            }
         });
     }
-}
\ No newline at end of file
+    
+    fn render_clear_data_dialog(&mut self, ctx: &Context) {
+        let Some(mut selection) = self.clear_data else {
+            return;
+        };
+        
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Clear Browsing Data")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut selection.history, "Browsing history");
+                ui.checkbox(&mut selection.site_data, "Site data");
+                
+                // Show what site data amounts to, largest sites first
+                if let Some(usage) = IndexedDatabase::shared().and_then(|db| db.usage_by_origin().ok()) {
+                    let total: u64 = usage.iter().map(|u| u.bytes).sum();
+                    ui.label(RichText::new(format!("{} sites storing {:.1} KB", usage.len(), total as f64 / 1024.0))
+                        .size(11.0)
+                        .color(NeonTheme::muted_text()));
+                    for site in usage.iter().take(5) {
+                        ui.label(RichText::new(format!("  {} — {:.1} KB", site.origin, site.bytes as f64 / 1024.0))
+                            .size(11.0)
+                            .color(NeonTheme::secondary_text()));
+                    }
+                }
+                
+                ui.add_space(12.0);
+                ui.horizontal(|ui| {
+                    let any = selection.history || selection.site_data;
+                    if ui.add_enabled(any, egui::Button::new(RichText::new("Clear data").color(NeonTheme::error_color()))).clicked() {
+                        action = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Some(false);
+                    }
+                });
+            });
+        
+        match action {
+            Some(true) => {
+                self.clear_data_status = Some(match selection.run() {
+                    Ok(()) => "Browsing data cleared".to_string(),
+                    Err(e) => format!("Could not clear all data: {}", e),
+                });
+                self.clear_data = None;
+            }
+            Some(false) => self.clear_data = None,
+            None => self.clear_data = open.then_some(selection),
+        }
+    }
+}
+
+// Save the action's chord; its default is saved as no change at all, so it follows the platform
+fn set_shortcut(action: ShortcutAction, chord: Option<egui::KeyboardShortcut>) {
+    let platform = shortcuts::Platform::current();
+    Preferences::update(|prefs| {
+        if chord == action.default_chord(platform) {
+            prefs.shortcuts.remove(action.id());
+        } else {
+            let text = chord.map(|chord| shortcuts::format_chord(&chord, platform)).unwrap_or_default();
+            prefs.shortcuts.insert(action.id().to_string(), text);
+        }
+    });
+}
@@ -0,0 +1,296 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use crate::pages::{CustomPage, components};
+use crate::storage::Preferences;
+use crate::sync::{SyncEngine, SyncTarget};
+use crate::ui::PageAction;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+// Passphrases shorter than this are too easy to guess for data that sits on someone else's server
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+pub struct SyncPage {
+    url: String,
+    title: String,
+    // Target and device name being set up, before sync is turned on
+    draft_target: SyncTarget,
+    device_name: String,
+    passphrase: String,
+    status_message: Option<(String, bool)>, // (message, is_error)
+    actions: Vec<PageAction>,
+}
+
+impl SyncPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://sync".to_string(),
+            title: "Sync".to_string(),
+            draft_target: SyncTarget::Folder { path: String::new() },
+            device_name: default_device_name(),
+            passphrase: String::new(),
+            status_message: None,
+            actions: Vec::new(),
+        }
+    }
+
+    fn render_setup(&mut self, ui: &mut Ui) {
+        components::section_header(ui, NeonIcons::SHIELD_CHECK, "Set up sync");
+        components::card_container(ui, |ui| {
+            ui.label(RichText::new("Bookmarks, settings, open tabs and history are encrypted on this device with your passphrase before they are uploaded")
+                .color(NeonTheme::secondary_text()));
+            ui.add_space(12.0);
+
+            egui::ComboBox::from_label("Sync to")
+                .selected_text(self.draft_target.label())
+                .show_ui(ui, |ui| {
+                    let options = [
+                        SyncTarget::Folder { path: String::new() },
+                        SyncTarget::WebDav { url: String::new(), username: String::new(), password: String::new() },
+                        SyncTarget::S3 { endpoint: String::new(), bucket: String::new(), region: String::new(), access_key: String::new(), secret_key: String::new() },
+                    ];
+                    for option in options {
+                        let selected = std::mem::discriminant(&option) == std::mem::discriminant(&self.draft_target);
+                        let label = option.label();
+                        if ui.selectable_label(selected, label).clicked() && !selected {
+                            self.draft_target = option;
+                        }
+                    }
+                });
+
+            egui::Grid::new("sync_target").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                match &mut self.draft_target {
+                    SyncTarget::None => {}
+                    SyncTarget::Folder { path } => {
+                        ui.label("Folder");
+                        ui.add(egui::TextEdit::singleline(path).hint_text("A folder shared by a file-sync tool").desired_width(360.0));
+                        ui.end_row();
+                    }
+                    SyncTarget::WebDav { url, username, password } => {
+                        ui.label("Server URL");
+                        ui.add(egui::TextEdit::singleline(url).hint_text("https://dav.example.com/neonsearch").desired_width(360.0));
+                        ui.end_row();
+                        ui.label("Username");
+                        ui.text_edit_singleline(username);
+                        ui.end_row();
+                        ui.label("Password");
+                        ui.add(egui::TextEdit::singleline(password).password(true));
+                        ui.end_row();
+                    }
+                    SyncTarget::S3 { endpoint, bucket, region, access_key, secret_key } => {
+                        ui.label("Endpoint");
+                        ui.add(egui::TextEdit::singleline(endpoint).hint_text("https://s3.us-east-1.amazonaws.com").desired_width(360.0));
+                        ui.end_row();
+                        ui.label("Bucket");
+                        ui.text_edit_singleline(bucket);
+                        ui.end_row();
+                        ui.label("Region");
+                        ui.add(egui::TextEdit::singleline(region).hint_text("us-east-1"));
+                        ui.end_row();
+                        ui.label("Access key");
+                        ui.text_edit_singleline(access_key);
+                        ui.end_row();
+                        ui.label("Secret key");
+                        ui.add(egui::TextEdit::singleline(secret_key).password(true));
+                        ui.end_row();
+                    }
+                }
+                ui.label("This device");
+                ui.text_edit_singleline(&mut self.device_name);
+                ui.end_row();
+                ui.label("Passphrase");
+                ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true)
+                    .hint_text("The same on every device"));
+                ui.end_row();
+            });
+            ui.label(RichText::new("The passphrase can't be recovered: without it, synced data can't be read, not even by NeonSearch")
+                .size(12.0)
+                .color(NeonTheme::warning_color()));
+
+            ui.add_space(12.0);
+            if ui.button(RichText::new(format!("{} Turn on sync", NeonIcons::REFRESH)).color(NeonTheme::neon_cyan())).clicked() {
+                match self.validate() {
+                    Ok(()) => self.turn_on(),
+                    Err(message) => self.status_message = Some((message, true)),
+                }
+            }
+        });
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let filled = |fields: &[&String]| fields.iter().all(|field| !field.trim().is_empty());
+        let complete = match &self.draft_target {
+            SyncTarget::None => false,
+            SyncTarget::Folder { path } => filled(&[path]),
+            SyncTarget::WebDav { url, .. } => filled(&[url]),
+            SyncTarget::S3 { endpoint, bucket, access_key, secret_key, .. } => filled(&[endpoint, bucket, access_key, secret_key]),
+        };
+        if !complete {
+            return Err("Fill in where to sync to".to_string());
+        }
+        if self.passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(format!("Use a passphrase of at least {} characters", MIN_PASSPHRASE_LEN));
+        }
+        Ok(())
+    }
+
+    fn turn_on(&mut self) {
+        let target = self.draft_target.clone();
+        let device_name = if self.device_name.trim().is_empty() { default_device_name() } else { self.device_name.trim().to_string() };
+        Preferences::update(|prefs| {
+            prefs.sync.target = target;
+            prefs.sync.device_name = device_name;
+            if prefs.sync.device_id.is_empty() {
+                prefs.sync.device_id = uuid::Uuid::new_v4().to_string();
+            }
+        });
+        if let Ok(mut engine) = SyncEngine::shared().lock() {
+            engine.connect(std::mem::take(&mut self.passphrase));
+        }
+        self.status_message = None;
+    }
+
+    fn render_status(&mut self, ui: &mut Ui) {
+        let engine = SyncEngine::shared();
+        let Ok(mut engine) = engine.lock() else { return };
+        let settings = Preferences::current().sync.clone();
+
+        components::section_header(ui, NeonIcons::REFRESH, "Status");
+        components::card_container(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{} — {}", settings.target.label(), settings.device_name)).strong().color(NeonTheme::primary_text()));
+                if engine.is_running() {
+                    ui.spinner();
+                    ui.label(RichText::new("Syncing…").color(NeonTheme::secondary_text()));
+                }
+            });
+            match (engine.last_error(), engine.last_success()) {
+                (Some(error), _) => {
+                    ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error)).color(NeonTheme::error_color()));
+                }
+                (None, Some(time)) => {
+                    ui.label(RichText::new(format!("{} Last synced at {}", NeonIcons::CHECK, time.format("%H:%M:%S"))).color(NeonTheme::success_color()));
+                }
+                (None, None) if !engine.is_running() => {
+                    ui.label(RichText::new("Not synced yet this session").color(NeonTheme::secondary_text()));
+                }
+                _ => {}
+            }
+            if let Some(report) = engine.report() {
+                let mut changes = Vec::new();
+                if report.bookmarks_added > 0 {
+                    changes.push(format!("{} bookmarks", report.bookmarks_added));
+                }
+                if report.visits_added > 0 {
+                    changes.push(format!("{} history visits", report.visits_added));
+                }
+                if let Some(device) = &report.settings_from {
+                    changes.push(format!("newer settings from {}", device));
+                }
+                if !changes.is_empty() {
+                    ui.label(RichText::new(format!("Last sync brought in {}", changes.join(", "))).color(NeonTheme::secondary_text()));
+                }
+                for error in &report.errors {
+                    ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error)).size(12.0).color(NeonTheme::warning_color()));
+                }
+            }
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Sync");
+                let mut data = settings.clone();
+                let mut edited = ui.checkbox(&mut data.bookmarks, "Bookmarks").changed();
+                edited |= ui.checkbox(&mut data.settings, "Settings").changed();
+                edited |= ui.checkbox(&mut data.tabs, "Open tabs").changed();
+                edited |= ui.checkbox(&mut data.history, "History").changed();
+                if edited {
+                    Preferences::update(|prefs| prefs.sync = data);
+                }
+            });
+            let mut interval = settings.interval_minutes;
+            if ui.add(egui::Slider::new(&mut interval, 1..=120).text("minutes between syncs")).changed() {
+                Preferences::update(|prefs| prefs.sync.interval_minutes = interval);
+            }
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!engine.is_running(), egui::Button::new(RichText::new(format!("{} Sync now", NeonIcons::REFRESH)).color(NeonTheme::neon_cyan()))).clicked() {
+                    engine.request_sync();
+                }
+                if ui.button(RichText::new("Turn off sync").color(NeonTheme::error_color())).clicked() {
+                    engine.disconnect();
+                }
+            });
+        });
+
+        let Some(report) = engine.report() else { return };
+        components::section_header(ui, NeonIcons::GLOBE, "Other devices");
+        if report.devices.is_empty() {
+            components::card_container(ui, |ui| {
+                ui.label(RichText::new("No other device syncs here yet. Turn on sync on another device with the same target and passphrase")
+                    .color(NeonTheme::secondary_text()));
+            });
+        }
+        for device in &report.devices {
+            components::card_container(ui, |ui| {
+                ui.label(RichText::new(&device.name).strong().color(NeonTheme::primary_text()));
+                ui.label(RichText::new(format!("Synced {}", device.uploaded_at.with_timezone(&chrono::Local).format("%b %-d, %H:%M")))
+                    .size(12.0)
+                    .color(NeonTheme::muted_text()));
+                for tab in &device.tabs {
+                    let title = if tab.title.is_empty() { &tab.url } else { &tab.title };
+                    if ui.link(title).on_hover_text(&tab.url).clicked() {
+                        self.actions.push(PageAction::OpenInNewTab(tab.url.clone()));
+                    }
+                }
+            });
+            ui.add_space(8.0);
+        }
+    }
+}
+
+impl Default for SyncPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_device_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| format!("NeonSearch on {}", std::env::consts::OS))
+}
+
+impl CustomPage for SyncPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+        components::page_header(
+            ui,
+            "Sync",
+            Some("Keep bookmarks, settings, tabs and history in step across your devices, end-to-end encrypted")
+        );
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+
+        // Until the first sync with a new passphrase finishes, the key isn't saved yet but sync is underway
+        let connecting = SyncEngine::shared().lock().is_ok_and(|engine| engine.is_running() || engine.last_error().is_some());
+        if Preferences::current().sync.target == SyncTarget::None || !(SyncEngine::is_set_up() || connecting) {
+            self.render_setup(ui);
+        } else {
+            self.render_status(ui);
+        }
+    }
+
+    fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.actions)
+    }
+}
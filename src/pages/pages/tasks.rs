@@ -0,0 +1,139 @@
+use eframe::egui::{self, Context, RichText, Ui};
+use std::time::Duration;
+use crate::engine::executor::{Executor, TaskKind};
+use crate::engine::image_memory::ImageMemory;
+use crate::engine::usage::{self, TabUsage};
+use crate::networking::http_cache::HttpCache;
+use crate::pages::{CustomPage, components};
+use crate::ui::PageAction;
+use crate::ui::i18n::format_bytes;
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+
+pub struct TasksPage {
+    url: String,
+    title: String,
+    actions: Vec<PageAction>,
+}
+
+impl TasksPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://tasks".to_string(),
+            title: "Task Manager".to_string(),
+            actions: Vec::new(),
+        }
+    }
+
+    fn render_tabs(&mut self, ui: &mut Ui, tabs: &[TabUsage]) {
+        egui::Grid::new("task_manager_tabs")
+            .num_columns(8)
+            .striped(true)
+            .spacing([18.0, 6.0])
+            .show(ui, |ui| {
+                for heading in ["Tab", "Memory", "Images", "Parse", "Layout", "JavaScript", "Requests", ""] {
+                    ui.label(RichText::new(heading).strong().color(NeonTheme::secondary_text()));
+                }
+                ui.end_row();
+
+                for tab in tabs {
+                    let title = if tab.title.is_empty() { &tab.url } else { &tab.title };
+                    ui.label(RichText::new(title).color(NeonTheme::primary_text()))
+                        .on_hover_text(&tab.url);
+                    ui.label(format_bytes(tab.memory.total() as u64))
+                        .on_hover_text(format!(
+                            "Document {}, text {}, images {}",
+                            format_bytes(tab.memory.dom as u64),
+                            format_bytes(tab.memory.strings as u64),
+                            format_bytes(tab.memory.textures as u64)
+                        ));
+                    ui.label(format_bytes(tab.memory.textures as u64));
+                    ui.label(format_time(tab.cpu.parse));
+                    ui.label(format_time(tab.cpu.layout));
+                    ui.label(format_time(tab.cpu.script));
+                    ui.label(tab.requests.to_string());
+                    if ui.add_enabled(tab.has_page, egui::Button::new(RichText::new("End").color(NeonTheme::error_color())))
+                        .on_hover_text("Unload the page, stopping its scripts and requests")
+                        .clicked()
+                    {
+                        self.actions.push(PageAction::KillTab(tab.id));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        let memory: usize = tabs.iter().map(|tab| tab.memory.total()).sum();
+        let cpu: Duration = tabs.iter().map(|tab| tab.cpu.total()).sum();
+        ui.add_space(8.0);
+        ui.label(RichText::new(format!("{} tabs using {} and {} of CPU time", tabs.len(), format_bytes(memory as u64), format_time(cpu)))
+            .color(NeonTheme::muted_text()));
+    }
+
+    fn render_subsystems(ui: &mut Ui) {
+        let (cached, cache_bytes) = HttpCache::shared().usage();
+        egui::Grid::new("task_manager_subsystems")
+            .num_columns(2)
+            .spacing([24.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(RichText::new("Image textures").color(NeonTheme::primary_text()));
+                ui.label(format_bytes(ImageMemory::shared().resident_bytes() as u64));
+                ui.end_row();
+
+                ui.label(RichText::new("HTTP cache").color(NeonTheme::primary_text()));
+                ui.label(format!("{} in {} responses", format_bytes(cache_bytes as u64), cached));
+                ui.end_row();
+
+                for kind in TaskKind::ALL {
+                    ui.label(RichText::new(format!("{} tasks", kind.label())).color(NeonTheme::primary_text()));
+                    ui.label(Executor::shared().active_tasks(kind).to_string());
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+fn format_time(time: Duration) -> String {
+    if time < Duration::from_secs(1) {
+        format!("{:.1} ms", time.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2} s", time.as_secs_f64())
+    }
+}
+
+impl Default for TasksPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomPage for TasksPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, ctx: &Context) {
+        components::page_header(
+            ui,
+            "Task Manager",
+            Some("What each tab and part of the browser is using, for finding the page that slows it down")
+        );
+
+        components::section_header(ui, NeonIcons::GLOBE, "Tabs");
+        let tabs = usage::tabs();
+        components::card_container(ui, |ui| self.render_tabs(ui, &tabs));
+
+        components::section_header(ui, NeonIcons::GEAR, "Browser");
+        components::card_container(ui, Self::render_subsystems);
+
+        // The window records what tabs use once a second while this page is open
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.actions)
+    }
+}
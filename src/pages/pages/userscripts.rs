@@ -0,0 +1,221 @@
+use eframe::egui::{self, Align, Context, Layout, RichText, Ui};
+use crate::extensions::userscripts::{UserScript, UserScriptMeta, UserScriptStore, NEW_SCRIPT_TEMPLATE};
+use crate::extensions::RunAt;
+use crate::pages::{CustomPage, components};
+use crate::ui::icons::NeonIcons;
+use crate::ui::theme::NeonTheme;
+use std::sync::{Arc, Mutex};
+
+// A script open in the editor; `file` is None until a new script is first saved
+struct Draft {
+    file: Option<String>,
+    source: String,
+}
+
+pub struct UserScriptsPage {
+    url: String,
+    title: String,
+    store: Arc<Mutex<UserScriptStore>>,
+    draft: Option<Draft>,
+    status_message: Option<(String, bool)>, // (message, is_error)
+    // Script waiting for the user to confirm its deletion
+    confirm_delete: Option<String>,
+}
+
+impl UserScriptsPage {
+    pub fn new() -> Self {
+        Self {
+            url: "neon://userscripts".to_string(),
+            title: "Userscripts".to_string(),
+            store: UserScriptStore::shared(),
+            draft: None,
+            status_message: None,
+            confirm_delete: None,
+        }
+    }
+
+    fn render_script(&mut self, ui: &mut Ui, script: &UserScript) {
+        let mut enabled = script.enabled;
+        components::card_container(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(&script.meta.name).size(16.0).strong().color(NeonTheme::primary_text()));
+                        if let Some(version) = &script.meta.version {
+                            ui.label(RichText::new(format!("v{}", version)).color(NeonTheme::secondary_text()));
+                        }
+                    });
+                    if !script.meta.description.is_empty() {
+                        ui.label(RichText::new(&script.meta.description).color(NeonTheme::secondary_text()));
+                    }
+                    ui.label(RichText::new(format!("{} {}", NeonIcons::GLOBE, runs_on(&script.meta)))
+                        .size(12.0)
+                        .color(NeonTheme::muted_text()));
+                    ui.label(RichText::new(&script.file).size(11.0).monospace().color(NeonTheme::muted_text()));
+                });
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if self.confirm_delete.as_deref() == Some(script.file.as_str()) {
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_delete = None;
+                        }
+                        if ui.button(RichText::new(format!("{} Delete", NeonIcons::TRASH)).color(NeonTheme::error_color())).clicked() {
+                            self.confirm_delete = None;
+                            let result = self.store.lock()
+                                .map_err(|_| anyhow::anyhow!("Userscript store is unavailable"))
+                                .and_then(|mut store| store.delete(&script.file));
+                            self.status_message = Some(match result {
+                                Ok(()) => (format!("Deleted {}", script.meta.name), false),
+                                Err(e) => (e.to_string(), true),
+                            });
+                        }
+                        return;
+                    }
+                    if ui.button(RichText::new(NeonIcons::TRASH).color(NeonTheme::secondary_text())).on_hover_text("Delete").clicked() {
+                        self.confirm_delete = Some(script.file.clone());
+                    }
+                    if ui.button(RichText::new(format!("{} Edit", NeonIcons::CODE)).color(NeonTheme::neon_cyan())).clicked() {
+                        self.draft = Some(Draft { file: Some(script.file.clone()), source: script.source.clone() });
+                    }
+                    ui.checkbox(&mut enabled, "Enabled");
+                });
+            });
+        });
+
+        if enabled != script.enabled {
+            if let Ok(mut store) = self.store.lock() {
+                if let Err(e) = store.set_enabled(&script.file, enabled) {
+                    self.status_message = Some((e.to_string(), true));
+                }
+            }
+        }
+    }
+
+    fn render_editor(&mut self, ui: &mut Ui) {
+        let Some(draft) = self.draft.as_mut() else { return };
+        let heading = match &draft.file {
+            Some(file) => format!("Editing {}", file),
+            None => "New script".to_string(),
+        };
+        let mut save = false;
+        let mut close = false;
+        components::section_header(ui, NeonIcons::CODE, &heading);
+        components::card_container(ui, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut draft.source)
+                .code_editor()
+                .desired_rows(18)
+                .desired_width(f32::INFINITY));
+            ui.add_space(8.0);
+
+            // The metadata block is checked as it's typed, so problems show before saving
+            let meta = UserScriptMeta::parse(&draft.source);
+            match &meta {
+                Ok(meta) => ui.label(RichText::new(format!("{} {} • {}", NeonIcons::CHECK, meta.name, runs_on(meta)))
+                    .color(NeonTheme::success_color())),
+                Err(e) => ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, e)).color(NeonTheme::warning_color())),
+            };
+            ui.horizontal(|ui| {
+                save = ui.add_enabled(meta.is_ok(), egui::Button::new(RichText::new("Save").color(NeonTheme::neon_cyan()))).clicked();
+                close = ui.button("Close").clicked();
+            });
+        });
+
+        if save {
+            let result = self.store.lock()
+                .map_err(|_| anyhow::anyhow!("Userscript store is unavailable"))
+                .and_then(|mut store| store.save(draft.file.as_deref(), &draft.source));
+            self.status_message = Some(match result {
+                Ok(file) => {
+                    draft.file = Some(file.clone());
+                    (format!("Saved {}; it applies from the next page load", file), false)
+                }
+                Err(e) => (e.to_string(), true),
+            });
+        }
+        if close {
+            self.draft = None;
+        }
+    }
+}
+
+impl Default for UserScriptsPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Where a script runs and when, in a line
+fn runs_on(meta: &UserScriptMeta) -> String {
+    let sites: Vec<&str> = meta.matches.iter().chain(meta.includes.iter()).map(String::as_str).collect();
+    let when = match meta.run_at {
+        RunAt::DocumentStart => "at document start",
+        RunAt::DocumentEnd => "at document end",
+    };
+    format!("{} {}", sites.join(", "), when)
+}
+
+impl CustomPage for UserScriptsPage {
+    fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    fn render(&mut self, ui: &mut Ui, _ctx: &Context) {
+        components::page_header(
+            ui,
+            "Userscripts",
+            Some("Scripts with // ==UserScript== metadata that change the pages they @match")
+        );
+
+        // Snapshot the store so the lock is not held while rendering
+        let (scripts, load_errors, dir) = match self.store.lock() {
+            Ok(store) => (store.list().to_vec(), store.load_errors().to_vec(), store.dir().display().to_string()),
+            Err(_) => (Vec::new(), Vec::new(), String::new()),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("{} scripts", scripts.len())).color(NeonTheme::secondary_text()));
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button(RichText::new(format!("{} Reload", NeonIcons::REFRESH)).color(NeonTheme::secondary_text())).clicked() {
+                    if let Ok(mut store) = self.store.lock() {
+                        store.reload();
+                    }
+                }
+                if ui.button(RichText::new(format!("{} New script", NeonIcons::PLUS)).color(NeonTheme::neon_cyan())).clicked() {
+                    self.draft = Some(Draft { file: None, source: NEW_SCRIPT_TEMPLATE.to_string() });
+                }
+            });
+        });
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.label(RichText::new(message).color(color));
+        }
+        for error in &load_errors {
+            ui.label(RichText::new(format!("{} {}", NeonIcons::WARNING, error)).size(12.0).color(NeonTheme::warning_color()));
+        }
+
+        self.render_editor(ui);
+
+        components::section_header(ui, NeonIcons::CODE, "Installed scripts");
+        if scripts.is_empty() {
+            components::card_container(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(24.0);
+                    ui.label(RichText::new("No userscripts yet").size(18.0).color(NeonTheme::secondary_text()));
+                    ui.label(RichText::new(format!("Write one with New script, or place .user.js files in {}", dir))
+                        .color(NeonTheme::muted_text()));
+                    ui.add_space(24.0);
+                });
+            });
+        } else {
+            for script in &scripts {
+                self.render_script(ui, script);
+                ui.add_space(8.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Arguments NeonSearch is started with, by the user or by the OS opening a link
+use std::path::Path;
+use crate::platform::RemoteCommand;
+use crate::platform::headless::{self, HeadlessOptions};
+use crate::ui;
+
+/// Passed to the windows the running instance starts, so they don't hand their links straight back
+pub const STANDALONE_FLAG: &str = "--standalone";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandLine {
+    /// Addresses to open, one tab each
+    pub urls: Vec<String>,
+    /// Open a window of its own rather than tabs in the running one
+    pub new_window: bool,
+    /// Open a private window, which keeps no history, cookies or session
+    pub private: bool,
+    /// Run as a window the running instance started, rather than handing over to it
+    pub standalone: bool,
+    /// Register as the default browser and exit
+    pub set_default_browser: bool,
+    /// Load the first address without a window and write out what `headless_options` asks for
+    pub headless: bool,
+    pub headless_options: HeadlessOptions,
+    /// Let external tools drive this window over a WebSocket on this loopback port
+    pub remote_debugging_port: Option<u16>,
+}
+
+impl CommandLine {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+    
+    /// What the running instance should do in place of this launch
+    pub fn remote_command(&self) -> RemoteCommand {
+        if self.new_window || self.private {
+            RemoteCommand::OpenWindow { urls: self.urls.clone(), private: self.private }
+        } else {
+            RemoteCommand::OpenUrls(self.urls.clone())
+        }
+    }
+
+    /// Parse the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut command_line = CommandLine::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => command_line.headless = true,
+                "--dump-dom" | "--dump-text" | "--screenshot" | "--window-size" | "--remote-debugging-port" => {
+                    let Some(value) = args.next() else {
+                        eprintln!("Option {} needs a value", arg);
+                        continue;
+                    };
+                    let options = &mut command_line.headless_options;
+                    match arg.as_str() {
+                        "--dump-dom" => options.dump_dom = Some(value.into()),
+                        "--dump-text" => options.dump_text = Some(value.into()),
+                        "--screenshot" => options.screenshot = Some(value.into()),
+                        "--window-size" => match headless::parse_window_size(&value) {
+                            Some(size) => options.window_size = size,
+                            None => eprintln!("Ignoring window size {}; expected WIDTHxHEIGHT", value),
+                        },
+                        _ => match value.parse() {
+                            Ok(port) => command_line.remote_debugging_port = Some(port),
+                            Err(_) => eprintln!("Ignoring remote debugging port {}", value),
+                        },
+                    }
+                }
+                "--new-window" => command_line.new_window = true,
+                "--private" => command_line.private = true,
+                STANDALONE_FLAG => command_line.standalone = true,
+                "--set-default-browser" => command_line.set_default_browser = true,
+                flag if flag.starts_with("--") => eprintln!("Ignoring unknown option {}", flag),
+                _ => command_line.urls.push(Self::resolve(&arg)),
+            }
+        }
+        command_line
+    }
+
+    /// Start a window of its own for `urls` as its own process, as the running instance does for --new-window
+    pub fn spawn_window(urls: &[String], private: bool) -> std::io::Result<()> {
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        command.arg(STANDALONE_FLAG);
+        if private {
+            command.arg("--private");
+        }
+        command.args(urls).spawn().map(|_| ())
+    }
+
+    // A path to an existing file opens as a file:// URL; anything else is what the address bar would make of it
+    fn resolve(arg: &str) -> String {
+        let path = Path::new(arg);
+        if !arg.contains("://") && path.exists() {
+            if let Some(url) = std::fs::canonicalize(path).ok().and_then(|path| url::Url::from_file_path(path).ok()) {
+                return url.to_string();
+            }
+        }
+        ui::resolve_input(arg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command_line() {
+        let args = ["https://example.com", "--private", "rust lang", "--verbose", "--remote-debugging-port", "9222"].map(String::from);
+        let command_line = CommandLine::parse(args);
+        let urls = vec![
+            "https://example.com".to_string(),
+            "https://duckduckgo.com/?q=rust%20lang".to_string(),
+        ];
+        assert_eq!(command_line.urls, urls);
+        assert!(command_line.private && !command_line.new_window && !command_line.set_default_browser);
+        assert_eq!(command_line.remote_debugging_port, Some(9222));
+        assert_eq!(command_line.remote_command(), RemoteCommand::OpenWindow { urls, private: true });
+    }
+}
@@ -0,0 +1,122 @@
+// Registering NeonSearch as the browser the system opens web links with
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const DESKTOP_FILE: &str = "neonsearch.desktop";
+
+/// Whether NeonSearch is the default browser, when the system can tell
+pub fn is_default() -> Option<bool> {
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let output = Command::new("xdg-settings").args(["get", "default-web-browser"]).output().ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim() == DESKTOP_FILE)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let output = Command::new("reg")
+            .args(["query", r"HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice", "/v", "ProgId"])
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).contains("NeonSearchURL"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        None
+    }
+}
+
+/// Register NeonSearch for http(s) links and HTML files, and make it the default where the system allows.
+/// Windows and macOS only let the user make the final choice, so their settings are opened for it.
+pub fn register() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    register_for(&exe)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn register_for(exe: &Path) -> Result<()> {
+    let applications = dirs::data_dir().ok_or_else(|| anyhow!("No applications folder"))?.join("applications");
+    std::fs::create_dir_all(&applications)?;
+    std::fs::write(applications.join(DESKTOP_FILE), desktop_entry(exe))?;
+    let _ = Command::new("update-desktop-database").arg(&applications).status();
+    run(Command::new("xdg-settings").args(["set", "default-web-browser", DESKTOP_FILE]))
+}
+
+#[cfg(target_os = "windows")]
+fn register_for(exe: &Path) -> Result<()> {
+    let open = format!("\"{}\" \"%1\"", exe.display());
+    let client = r"HKCU\Software\Clients\StartMenuInternet\NeonSearch";
+    let entries = [
+        (r"HKCU\Software\Classes\NeonSearchURL".to_string(), None, "NeonSearch URL".to_string()),
+        (r"HKCU\Software\Classes\NeonSearchURL\shell\open\command".to_string(), None, open.clone()),
+        (r"HKCU\Software\Classes\NeonSearchHTML\shell\open\command".to_string(), None, open.clone()),
+        (client.to_string(), None, "NeonSearch".to_string()),
+        (format!(r"{}\shell\open\command", client), None, format!("\"{}\"", exe.display())),
+        (format!(r"{}\Capabilities", client), Some("ApplicationName"), "NeonSearch".to_string()),
+        (format!(r"{}\Capabilities\URLAssociations", client), Some("http"), "NeonSearchURL".to_string()),
+        (format!(r"{}\Capabilities\URLAssociations", client), Some("https"), "NeonSearchURL".to_string()),
+        (format!(r"{}\Capabilities\FileAssociations", client), Some(".html"), "NeonSearchHTML".to_string()),
+        (r"HKCU\Software\RegisteredApplications".to_string(), Some("NeonSearch"), r"Software\Clients\StartMenuInternet\NeonSearch\Capabilities".to_string()),
+    ];
+    for (key, name, value) in entries {
+        let mut command = Command::new("reg");
+        command.args(["add", &key, "/f", "/d", &value]);
+        match name {
+            Some(name) => command.args(["/v", name]),
+            None => command.arg("/ve"),
+        };
+        run(&mut command)?;
+    }
+    run(Command::new("cmd").args(["/C", "start", "", "ms-settings:defaultapps"]))
+}
+
+#[cfg(target_os = "macos")]
+fn register_for(_exe: &Path) -> Result<()> {
+    // The app bundle's Info.plist declares the http(s) schemes; the choice itself is the user's
+    run(Command::new("open").arg("x-apple.systempreferences:com.apple.Desktop-Settings.extension"))
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow!("{:?} failed with {}", command.get_program(), status));
+    }
+    Ok(())
+}
+
+/// The freedesktop entry that makes NeonSearch an application for web links and HTML files
+pub fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=NeonSearch\n\
+         GenericName=Web Browser\n\
+         Exec=\"{exe}\" %U\n\
+         Terminal=false\n\
+         Categories=Network;WebBrowser;\n\
+         MimeType=text/html;application/xhtml+xml;x-scheme-handler/http;x-scheme-handler/https;\n\
+         Actions=new-window;new-private-window;\n\
+         \n\
+         [Desktop Action new-window]\n\
+         Name=New Window\n\
+         Exec=\"{exe}\" --new-window\n\
+         \n\
+         [Desktop Action new-private-window]\n\
+         Name=New Private Window\n\
+         Exec=\"{exe}\" --private\n",
+        exe = exe.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_entry() {
+        let entry = desktop_entry(Path::new("/opt/neon search/neonsearch"));
+        assert!(entry.contains("Exec=\"/opt/neon search/neonsearch\" %U\n"));
+        assert!(entry.contains("x-scheme-handler/https;"));
+    }
+}
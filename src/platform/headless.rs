@@ -0,0 +1,173 @@
+// Loading a page without a window: the same fetch, parse, style and layout the tab runs, written out as the
+// page's DOM, its text or a picture of it, for testing the engine in CI and for scripted scraping
+use anyhow::{Result, anyhow};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::engine::executor::Executor;
+use crate::engine::raster::SoftwareRasterizer;
+use crate::engine::resource_loader::{self, ResourceLoader};
+use crate::engine::fonts::FontRegistry;
+use crate::engine::{ParsedDocument, WebPage};
+use crate::networking::cookie_manager::CookieManager;
+use crate::networking::image_loader::ImageCache;
+use crate::networking::manual_client::{FetchContext, ManualHttpClient};
+use crate::storage::preferences::ImageLoadingMode;
+use crate::ui::theme::NeonTheme;
+
+// Subresources still loading after this are left out rather than holding up the output
+const RESOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Fonts, images and layout settle over a few frames, as they would in the window
+const SETTLE_FRAMES: usize = 3;
+
+/// What a headless run writes; a path of "-" means standard output
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessOptions {
+    pub dump_dom: Option<PathBuf>,
+    pub dump_text: Option<PathBuf>,
+    pub screenshot: Option<PathBuf>,
+    /// Size of the viewport the page is laid out and pictured in
+    pub window_size: (u32, u32),
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self { dump_dom: None, dump_text: None, screenshot: None, window_size: (1280, 800) }
+    }
+}
+
+/// Parse a `--window-size` value such as "1280x800"
+pub fn parse_window_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once(['x', 'X', ','])?;
+    let size = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// Load `url` and write the outputs `options` ask for; with none asked for, the page's text goes to standard output
+pub fn run(url: &str, options: &HeadlessOptions) -> Result<()> {
+    let client = ManualHttpClient::new()?;
+    let cookies = Arc::new(Mutex::new(CookieManager::new()));
+    let context = FetchContext { cookies: Some(cookies.clone()), ..FetchContext::default() };
+    let fetched = Executor::shared().block_on(client.fetch_with_context(url, &context))?;
+    let url = fetched.final_url;
+    let response = fetched.response;
+    if !response.is_success() {
+        return Err(anyhow!("{} answered with status {}", url, response.status_code));
+    }
+
+    let mut page = WebPage::from_parsed(ParsedDocument::parse(&response.body_as_string()?));
+    page.set_document_url(&url);
+    page.set_cookie_jar(cookies.clone());
+    page.load_inline_styles(&url);
+
+    // Images load with the page, since nothing scrolls to bring lazy ones into view
+    let ctx = egui::Context::default();
+    NeonTheme::apply_to_context(&ctx);
+    let mut requests = resource_loader::discover_resources(&page.dom, &url, ImageLoadingMode::Eager);
+    requests.extend(page.take_font_requests());
+    if !requests.is_empty() {
+        let context = FetchContext {
+            cookies: Some(cookies),
+            referrer: Some(url.clone()),
+            referrer_policy: page.referrer_policy(),
+            ..FetchContext::default()
+        };
+        let mut resources = ResourceLoader::new(client, ImageCache::new()).start(requests, context);
+        let deadline = Instant::now() + RESOURCE_TIMEOUT;
+        loop {
+            let mut finished = resources.progress().is_finished();
+            for resource in resources.take_ready() {
+                if let Err(e) = page.apply_resource(resource) {
+                    log::warn!("Failed to load resource {}", e);
+                }
+            }
+            let fonts = page.take_font_requests();
+            if !fonts.is_empty() {
+                resources.request(fonts);
+                finished = false;
+            }
+            if finished {
+                break;
+            }
+            if Instant::now() > deadline {
+                log::warn!("Gave up waiting for the rest of {}'s subresources", url);
+                resources.cancel();
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    let nothing_asked = options.dump_dom.is_none() && options.dump_text.is_none() && options.screenshot.is_none();
+    if let Some(path) = &options.dump_dom {
+        write_output(path, &page.dom.to_html())?;
+    }
+    if let Some(path) = &options.dump_text {
+        write_output(path, &page.dom.visible_text())?;
+    }
+    if nothing_asked {
+        write_output(Path::new("-"), &page.dom.visible_text())?;
+    }
+    if let Some(path) = &options.screenshot {
+        screenshot(&page, &ctx, options.window_size)
+            .save(path)
+            .map_err(|e| anyhow!("Could not save the screenshot to {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Paint the page's viewport as the window's content area would show it
+pub fn screenshot(page: &WebPage, ctx: &egui::Context, (width, height): (u32, u32)) -> image::RgbaImage {
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32))),
+        ..Default::default()
+    };
+    let mut rasterizer = SoftwareRasterizer::new();
+    let mut output = egui::FullOutput::default();
+    for _ in 0..SETTLE_FRAMES {
+        FontRegistry::shared().apply(ctx);
+        output = ctx.run(input.clone(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| page.render(ui));
+            });
+        });
+        rasterizer.apply_textures(&output.textures_delta);
+    }
+    let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+    rasterizer.paint(width, height, &primitives, ctx.style().visuals.panel_fill)
+}
+
+fn write_output(path: &Path, contents: &str) -> Result<()> {
+    if path == Path::new("-") {
+        println!("{}", contents);
+        return Ok(());
+    }
+    std::fs::write(path, contents).map_err(|e| anyhow!("Could not write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headless_run_writes_outputs() {
+        let dir = std::env::temp_dir().join(format!("neon-headless-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let options = HeadlessOptions {
+            dump_dom: Some(dir.join("page.html")),
+            dump_text: Some(dir.join("page.txt")),
+            screenshot: Some(dir.join("page.png")),
+            window_size: (320, 240),
+        };
+        run("data:text/html,<title>T</title><h1>Hello</h1><p>headless <b>world</b></p>", &options).unwrap();
+
+        assert_eq!(std::fs::read_to_string(dir.join("page.txt")).unwrap(), "Hello\nheadless world");
+        assert!(std::fs::read_to_string(dir.join("page.html")).unwrap().contains("<h1>Hello</h1>"));
+        let picture = image::open(dir.join("page.png")).unwrap();
+        assert_eq!((picture.width(), picture.height()), (320, 240));
+        assert_eq!(parse_window_size("1024x768"), Some((1024, 768)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,254 @@
+// Logging through the `log` facade for the whole browser: records go to stderr and into a ring of recent entries
+// that neon://logs shows, each module filtered at a level that can be changed while the browser runs
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Mutex, OnceLock, RwLock};
+
+/// How many entries are kept for neon://logs to look back through
+pub const CAPACITY: usize = 5000;
+
+/// Levels used when neither RUST_LOG nor the preferences set any: the browser's own modules at info and the
+/// libraries it uses at warn
+pub const DEFAULT_LEVELS: &str = "warn,neonsearch=info";
+
+/// One logged record
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Counts up from 1 over the run, so a viewer can ask for what came after the last entry it has
+    pub id: u64,
+    pub time: DateTime<Local>,
+    pub level: Level,
+    /// Module that logged it, like `neonsearch::networking::manual_client`
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// The entry as one line of text, as it's written to stderr and exported
+    pub fn to_line(&self) -> String {
+        format!("{} {:<5} {}: {}", self.time.format("%Y-%m-%d %H:%M:%S%.3f"), self.level, self.target, self.message)
+    }
+}
+
+/// The most detailed level logged for each module, written as RUST_LOG is: "warn,neonsearch::networking=debug"
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLevels {
+    /// Level of modules no entry in `modules` covers
+    pub default: LevelFilter,
+    /// Module paths and the level of everything in them; the longest path a module is in applies
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogLevels {
+    pub fn parse(spec: &str) -> Self {
+        let mut levels = Self { default: LevelFilter::Error, modules: Vec::new() };
+        for directive in spec.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        levels.set(module.trim(), level);
+                    }
+                }
+                // A level alone is the default, and a module alone is logged in full
+                None => match directive.parse() {
+                    Ok(level) => levels.default = level,
+                    Err(_) => levels.set(directive, LevelFilter::Trace),
+                },
+            }
+        }
+        levels
+    }
+
+    /// The level set for exactly `module`, if any
+    pub fn get(&self, module: &str) -> Option<LevelFilter> {
+        self.modules.iter().find(|(path, _)| path == module).map(|(_, level)| *level)
+    }
+
+    pub fn set(&mut self, module: &str, level: LevelFilter) {
+        match self.modules.iter_mut().find(|(path, _)| path == module) {
+            Some((_, existing)) => *existing = level,
+            None => self.modules.push((module.to_string(), level)),
+        }
+    }
+
+    /// Let `module` take the level of the path it's in again
+    pub fn remove(&mut self, module: &str) {
+        self.modules.retain(|(path, _)| path != module);
+    }
+
+    /// The level records logged from `target` are kept at
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules.iter()
+            .filter(|(path, _)| target == path || target.strip_prefix(path.as_str()).is_some_and(|rest| rest.starts_with("::")))
+            .max_by_key(|(path, _)| path.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The most detailed level any module is logged at
+    pub fn max(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, std::cmp::max)
+    }
+}
+
+impl fmt::Display for LogLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+// Recent entries, oldest first
+struct Ring {
+    entries: VecDeque<LogEntry>,
+    last_id: u64,
+}
+
+/// The browser's logger; `init` installs the shared one
+pub struct Logger {
+    levels: RwLock<LogLevels>,
+    ring: Mutex<Ring>,
+    // Whether records are written to stderr too
+    echo: bool,
+}
+
+static SHARED_LOGGER: OnceLock<Logger> = OnceLock::new();
+
+impl Logger {
+    pub fn new(levels: LogLevels, echo: bool) -> Self {
+        Self {
+            levels: RwLock::new(levels),
+            ring: Mutex::new(Ring { entries: VecDeque::new(), last_id: 0 }),
+            echo,
+        }
+    }
+
+    /// The logger records go to, at the levels RUST_LOG sets, or failing that the preferences
+    pub fn shared() -> &'static Logger {
+        SHARED_LOGGER.get_or_init(|| {
+            let spec = std::env::var("RUST_LOG").ok()
+                .or_else(|| crate::storage::Preferences::current().log_levels.clone())
+                .unwrap_or_else(|| DEFAULT_LEVELS.to_string());
+            Logger::new(LogLevels::parse(&spec), true)
+        })
+    }
+
+    pub fn levels(&self) -> LogLevels {
+        self.levels.read().map(|levels| levels.clone()).unwrap_or_else(|_| LogLevels::parse(DEFAULT_LEVELS))
+    }
+
+    /// Log at `levels` from now on
+    pub fn set_levels(&self, levels: LogLevels) {
+        log::set_max_level(levels.max());
+        if let Ok(mut current) = self.levels.write() {
+            *current = levels;
+        }
+    }
+
+    /// Entries logged after the one numbered `id`, oldest first; 0 gives all that are kept
+    pub fn entries_after(&self, id: u64) -> Vec<LogEntry> {
+        let Ok(ring) = self.ring.lock() else {
+            return Vec::new();
+        };
+        let newer = ring.entries.iter().rev().take_while(|entry| entry.id > id).count();
+        ring.entries.iter().skip(ring.entries.len() - newer).cloned().collect()
+    }
+
+    /// Forget the entries kept so far
+    pub fn clear(&self) {
+        if let Ok(mut ring) = self.ring.lock() {
+            ring.entries.clear();
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.levels.read().is_ok_and(|levels| metadata.level() <= levels.level_for(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Ok(mut ring) = self.ring.lock() else {
+            return;
+        };
+        ring.last_id += 1;
+        let entry = LogEntry {
+            id: ring.last_id,
+            time: Local::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        if self.echo {
+            eprintln!("{}", entry.to_line());
+        }
+        if ring.entries.len() == CAPACITY {
+            ring.entries.pop_front();
+        }
+        ring.entries.push_back(entry);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Send the browser's logging to the shared logger
+pub fn init() {
+    let logger = Logger::shared();
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.levels().max());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(logger: &Logger, level: Level, target: &str, message: &str) {
+        logger.log(&Record::builder().level(level).target(target).args(format_args!("{}", message)).build());
+    }
+
+    #[test]
+    fn test_levels_apply_to_the_longest_matching_module() {
+        let mut levels = LogLevels::parse("warn, neonsearch=info,neonsearch::networking=debug,reqwest");
+        assert_eq!(levels.level_for("neonsearch::networking::manual_client"), LevelFilter::Debug);
+        assert_eq!(levels.level_for("neonsearch::networking"), LevelFilter::Debug);
+        // A module whose name only starts the same isn't in it
+        assert_eq!(levels.level_for("neonsearch::networkingx"), LevelFilter::Info);
+        assert_eq!(levels.level_for("neonsearch::engine"), LevelFilter::Info);
+        assert_eq!(levels.level_for("reqwest::connect"), LevelFilter::Trace);
+        assert_eq!(levels.level_for("wgpu_core"), LevelFilter::Warn);
+        assert_eq!(levels.max(), LevelFilter::Trace);
+
+        levels.remove("reqwest");
+        levels.set("neonsearch::networking", LevelFilter::Off);
+        assert_eq!(levels.to_string(), "warn,neonsearch=info,neonsearch::networking=off");
+        assert_eq!(LogLevels::parse(&levels.to_string()), levels);
+    }
+
+    #[test]
+    fn test_recent_entries_are_kept_at_their_levels() {
+        let logger = Logger::new(LogLevels::parse("warn,neonsearch::networking=debug"), false);
+        record(&logger, Level::Debug, "neonsearch::networking::manual_client", "HTTP Response: 200 OK");
+        record(&logger, Level::Debug, "neonsearch::engine", "not kept");
+        record(&logger, Level::Warn, "neonsearch::engine", "Subresource failed");
+        let entries = logger.entries_after(0);
+        assert_eq!(entries.iter().map(|entry| entry.message.as_str()).collect::<Vec<_>>(), ["HTTP Response: 200 OK", "Subresource failed"]);
+        assert_eq!(logger.entries_after(entries[0].id), entries[1..]);
+
+        logger.set_levels(LogLevels::parse("debug"));
+        for index in 0..CAPACITY {
+            record(&logger, Level::Info, "neonsearch::engine", &index.to_string());
+        }
+        let entries = logger.entries_after(0);
+        assert_eq!(entries.len(), CAPACITY);
+        assert_eq!(entries[0].message, "0");
+        assert!(entries[CAPACITY - 1].to_line().ends_with(&format!("INFO  neonsearch::engine: {}", CAPACITY - 1)));
+    }
+}
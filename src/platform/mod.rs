@@ -0,0 +1,11 @@
+// How NeonSearch fits into the operating system: its command line, one running instance, the default browser,
+// running without a window, being driven by external tools, and where its logging goes
+pub mod cli;
+pub mod single_instance;
+pub mod default_browser;
+pub mod headless;
+pub mod remote_debugging;
+pub mod logging;
+
+pub use cli::CommandLine;
+pub use single_instance::{InstanceListener, RemoteCommand};
@@ -0,0 +1,300 @@
+// A local WebSocket endpoint external tools drive the browser through: a small subset of the Chrome DevTools
+// protocol's shape ({"id", "method", "params"} in, {"id", "result"} or {"id", "error"} out), answered by the UI
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+use crate::engine::dom::DOMNode;
+
+// Appended to the client's key before hashing, as RFC 6455 fixes it
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Messages larger than this close the connection
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+// A screenshot waits for the next frame, so answers can take a moment
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One protocol call from a connected tool, answered on the UI thread
+pub struct DebugRequest {
+    pub method: String,
+    pub params: Value,
+    reply: Sender<Result<Value, String>>,
+}
+
+impl DebugRequest {
+    /// The tab a call names with `targetId`, if any
+    pub fn target(&self) -> Option<uuid::Uuid> {
+        self.params.get("targetId").and_then(Value::as_str).and_then(|id| uuid::Uuid::parse_str(id).ok())
+    }
+
+    /// A string parameter the call can't do without
+    pub fn string_param(&self, name: &str) -> Result<&str, String> {
+        self.params.get(name).and_then(Value::as_str).ok_or_else(|| format!("Missing string parameter '{}'", name))
+    }
+
+    pub fn respond(self, result: Result<Value, String>) {
+        let _ = self.reply.send(result);
+    }
+}
+
+/// The bound debugging port, accepting tools once the window exists
+pub struct DebugListener {
+    listener: TcpListener,
+}
+
+impl DebugListener {
+    /// Listen on `port` of the loopback interface only; 0 picks a free port
+    pub fn bind(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| anyhow!("Could not listen for remote debugging on port {}: {}", port, e))?;
+        Ok(Self { listener })
+    }
+
+    /// The address tools connect to
+    pub fn url(&self) -> String {
+        let port = self.listener.local_addr().map(|address| address.port()).unwrap_or_default();
+        format!("ws://127.0.0.1:{}/", port)
+    }
+
+    /// Accept tools on background threads; `notify` wakes the UI for each call
+    pub fn listen(self, notify: impl Fn() + Send + Sync + 'static) -> Receiver<DebugRequest> {
+        let (sender, receiver) = mpsc::channel();
+        let notify = std::sync::Arc::new(notify);
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming().flatten() {
+                let (sender, notify) = (sender.clone(), notify.clone());
+                std::thread::spawn(move || {
+                    if let Err(e) = serve(stream, &sender, &*notify) {
+                        log::debug!("Remote debugging connection closed: {}", e);
+                    }
+                });
+            }
+        });
+        receiver
+    }
+}
+
+fn serve(stream: TcpStream, sender: &Sender<DebugRequest>, notify: &dyn Fn()) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    handshake(&mut reader, &mut writer)?;
+    while let Some(message) = read_message(&mut reader, &mut writer)? {
+        let reply = answer(&message, sender, notify);
+        write_frame(&mut writer, 0x1, reply.to_string().as_bytes())?;
+    }
+    Ok(())
+}
+
+// Turn one text message into its reply, going through the UI for anything that isn't malformed
+fn answer(message: &str, sender: &Sender<DebugRequest>, notify: &dyn Fn()) -> Value {
+    let call: Value = match serde_json::from_str(message) {
+        Ok(call) => call,
+        Err(e) => return json!({ "id": null, "error": { "message": format!("Not JSON: {}", e) } }),
+    };
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = call.get("method").and_then(Value::as_str) else {
+        return json!({ "id": id, "error": { "message": "Missing method" } });
+    };
+    let (reply, response) = mpsc::channel();
+    let request = DebugRequest {
+        method: method.to_string(),
+        params: call.get("params").cloned().unwrap_or_else(|| json!({})),
+        reply,
+    };
+    if sender.send(request).is_err() {
+        return json!({ "id": id, "error": { "message": "The browser is closing" } });
+    }
+    notify();
+    match response.recv_timeout(RESPONSE_TIMEOUT) {
+        Ok(Ok(result)) => json!({ "id": id, "result": result }),
+        Ok(Err(message)) => json!({ "id": id, "error": { "message": message } }),
+        Err(_) => json!({ "id": id, "error": { "message": "The browser didn't answer in time" } }),
+    }
+}
+
+fn handshake(reader: &mut impl BufRead, writer: &mut impl Write) -> Result<()> {
+    let mut key = None;
+    let mut origin = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(anyhow!("Connection closed during the handshake"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "sec-websocket-key" => key = Some(value.trim().to_string()),
+                "origin" => origin = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    // Web pages can open sockets to localhost too; only tools that aren't a page get to drive the browser
+    if let Some(origin) = origin {
+        write!(writer, "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")?;
+        return Err(anyhow!("Refused a connection from {}", origin));
+    }
+    let Some(key) = key else {
+        let body = "NeonSearch remote debugging speaks WebSocket";
+        write!(writer, "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)?;
+        return Err(anyhow!("Not a WebSocket handshake"));
+    };
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    )?;
+    Ok(())
+}
+
+/// The Sec-WebSocket-Accept answer to a client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, format!("{}{}", key, HANDSHAKE_GUID).as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(digest.as_ref())
+}
+
+// The next text message, with pings answered and fragments joined along the way; None once the tool closes
+fn read_message(reader: &mut impl Read, writer: &mut impl Write) -> Result<Option<String>> {
+    let mut message = Vec::new();
+    loop {
+        let mut header = [0u8; 2];
+        if reader.read_exact(&mut header).is_err() {
+            return Ok(None);
+        }
+        let (fin, opcode) = (header[0] & 0x80 != 0, header[0] & 0x0f);
+        let length = match header[1] & 0x7f {
+            126 => {
+                let mut bytes = [0u8; 2];
+                reader.read_exact(&mut bytes)?;
+                u16::from_be_bytes(bytes) as usize
+            }
+            127 => {
+                let mut bytes = [0u8; 8];
+                reader.read_exact(&mut bytes)?;
+                u64::from_be_bytes(bytes) as usize
+            }
+            length => length as usize,
+        };
+        if message.len().saturating_add(length) > MAX_MESSAGE_SIZE {
+            return Err(anyhow!("Message larger than {} bytes", MAX_MESSAGE_SIZE));
+        }
+        // Clients always mask what they send
+        if header[1] & 0x80 == 0 {
+            return Err(anyhow!("Unmasked frame from the client"));
+        }
+        let mut mask = [0u8; 4];
+        reader.read_exact(&mut mask)?;
+        let mut payload = vec![0u8; length];
+        reader.read_exact(&mut payload)?;
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        match opcode {
+            0x0 | 0x1 => message.extend_from_slice(&payload),
+            0x8 => {
+                write_frame(writer, 0x8, &payload)?;
+                return Ok(None);
+            }
+            0x9 => write_frame(writer, 0xA, &payload)?,
+            0xA => {}
+            _ => return Err(anyhow!("Unsupported frame type {:#x}", opcode)),
+        }
+        if fin && matches!(opcode, 0x0 | 0x1) {
+            return Ok(Some(String::from_utf8(message)?));
+        }
+    }
+}
+
+// One unfragmented, unmasked frame, as servers send them
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A DOM node as the protocol describes it: type, name, attributes and children
+pub fn dom_tree(node: &DOMNode) -> Value {
+    match node {
+        DOMNode::Element { tag_name, attributes, children } => json!({
+            "nodeType": 1,
+            "nodeName": tag_name.to_ascii_uppercase(),
+            "attributes": attributes,
+            "children": children.iter().map(dom_tree).collect::<Vec<_>>(),
+        }),
+        DOMNode::Text(text) => json!({ "nodeType": 3, "nodeName": "#text", "nodeValue": text }),
+        DOMNode::Comment(text) => json!({ "nodeType": 8, "nodeName": "#comment", "nodeValue": text }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A client frame: masked, as a tool would send it
+    fn client_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn test_handshake_and_call() {
+        // The example from RFC 6455
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let listener = DebugListener::bind(0).unwrap();
+        let address = listener.listener.local_addr().unwrap();
+        let requests = listener.listen(|| {});
+        std::thread::spawn(move || {
+            for request in requests {
+                let result = match request.method.as_str() {
+                    "Runtime.evaluate" => Ok(json!({ "value": request.string_param("expression").unwrap().len() })),
+                    _ => Err(format!("Unknown method {}", request.method)),
+                };
+                request.respond(result);
+            }
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        assert!(status.starts_with("HTTP/1.1 101"));
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+
+        stream.write_all(&client_frame(0x1, br#"{"id":7,"method":"Runtime.evaluate","params":{"expression":"1+1"}}"#)).unwrap();
+        let mut header = [0u8; 2];
+        reader.read_exact(&mut header).unwrap();
+        let mut payload = vec![0u8; header[1] as usize];
+        reader.read_exact(&mut payload).unwrap();
+        let reply: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(reply, json!({ "id": 7, "result": { "value": 3 } }));
+    }
+}
@@ -0,0 +1,179 @@
+// One NeonSearch per profile: a later launch hands its arguments to the running instance instead of starting
+// a second browser over the same caches. Unix systems talk over a socket only this user can open; elsewhere a
+// loopback port guarded by a token in the profile does the same job.
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+// How long a launch waits for the running instance before starting on its own
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a later launch asks the running instance to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RemoteCommand {
+    /// Open each address in a new tab, or just bring the window forward when there are none
+    OpenUrls(Vec<String>),
+    /// Open the addresses in a window of their own, private when asked
+    OpenWindow { urls: Vec<String>, private: bool },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    // Proves the sender can read this user's profile, where the socket itself doesn't
+    token: Option<String>,
+    command: RemoteCommand,
+}
+
+/// The running instance's end of the connection, listening once the window exists
+pub struct InstanceListener {
+    listener: transport::Listener,
+    token: Option<String>,
+}
+
+/// Send `command` to the instance running on the profile in `dir`, or become that instance when there is none.
+/// Returns None when the command was handed over and this launch should exit.
+pub fn acquire(dir: &Path, command: &RemoteCommand) -> Result<Option<InstanceListener>> {
+    // A second try covers two launches racing to become the running instance
+    for _ in 0..2 {
+        match forward(dir, command) {
+            Ok(()) => return Ok(None),
+            Err(e) => log::debug!("No running instance to hand over to: {}", e),
+        }
+        match transport::bind(dir) {
+            Ok((listener, token)) => return Ok(Some(InstanceListener { listener, token })),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(anyhow!("Another NeonSearch is starting on this profile"))
+}
+
+fn forward(dir: &Path, command: &RemoteCommand) -> Result<()> {
+    let (stream, token) = transport::connect(dir)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let message = Message { token, command: command.clone() };
+    writeln!(&stream, "{}", serde_json::to_string(&message)?)?;
+
+    // Only a NeonSearch acknowledges, so a stale socket or another program on the port isn't mistaken for one
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    if reply.trim() != "ok" {
+        return Err(anyhow!("Unexpected reply from the running instance: {:?}", reply.trim()));
+    }
+    Ok(())
+}
+
+impl InstanceListener {
+    /// Accept commands on a background thread; `notify` wakes the UI for each one
+    pub fn listen(self, notify: impl Fn() + Send + 'static) -> Receiver<RemoteCommand> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming().flatten() {
+                match handle(stream, self.token.as_deref(), &sender) {
+                    Ok(()) => notify(),
+                    Err(e) => log::warn!("Rejected a remote command: {}", e),
+                }
+            }
+        });
+        receiver
+    }
+}
+
+fn handle(stream: transport::Stream, token: Option<&str>, sender: &Sender<RemoteCommand>) -> Result<()> {
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let message: Message = serde_json::from_str(&line)?;
+    if message.token.as_deref() != token {
+        return Err(anyhow!("wrong token"));
+    }
+    sender.send(message.command)?;
+    writeln!(&stream, "ok")?;
+    Ok(())
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+
+    pub type Stream = UnixStream;
+    pub type Listener = UnixListener;
+
+    fn socket_path(dir: &Path) -> PathBuf {
+        dir.join("instance.sock")
+    }
+
+    pub fn connect(dir: &Path) -> std::io::Result<(Stream, Option<String>)> {
+        Ok((UnixStream::connect(socket_path(dir))?, None))
+    }
+
+    pub fn bind(dir: &Path) -> std::io::Result<(Listener, Option<String>)> {
+        let path = socket_path(dir);
+        // Nothing answered on it, so the socket is left over from an instance that crashed
+        if UnixStream::connect(&path).is_err() {
+            let _ = std::fs::remove_file(&path);
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        Ok((listener, None))
+    }
+}
+
+#[cfg(not(unix))]
+mod transport {
+    use serde::{Deserialize, Serialize};
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+    use std::path::{Path, PathBuf};
+
+    pub type Stream = TcpStream;
+    pub type Listener = TcpListener;
+
+    // Where the running instance listens, and the secret that proves a message comes from this user
+    #[derive(Serialize, Deserialize)]
+    struct InstanceFile {
+        port: u16,
+        token: String,
+    }
+
+    fn instance_file(dir: &Path) -> PathBuf {
+        dir.join("instance.json")
+    }
+
+    pub fn connect(dir: &Path) -> std::io::Result<(Stream, Option<String>)> {
+        let file: InstanceFile = serde_json::from_str(&std::fs::read_to_string(instance_file(dir))?)?;
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, file.port));
+        Ok((TcpStream::connect_timeout(&address, super::CONNECT_TIMEOUT)?, Some(file.token)))
+    }
+
+    pub fn bind(dir: &Path) -> std::io::Result<(Listener, Option<String>)> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let file = InstanceFile { port: listener.local_addr()?.port(), token: token.clone() };
+        std::fs::write(instance_file(dir), serde_json::to_string(&file)?)?;
+        Ok((listener, Some(token)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_launch_hands_over() {
+        let dir = std::env::temp_dir().join(format!("neon-instance-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let window = RemoteCommand::OpenWindow { urls: vec!["https://example.com".to_string()], private: true };
+
+        let listener = acquire(&dir, &RemoteCommand::OpenUrls(Vec::new())).unwrap().expect("first launch runs");
+        let received = listener.listen(|| {});
+        assert!(acquire(&dir, &window).unwrap().is_none());
+        assert_eq!(received.recv_timeout(Duration::from_secs(5)).unwrap(), window);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,237 @@
+// What the site info popover says about an HTTPS connection: the TLS version and cipher suite it settled on, and
+// who the server's certificate was issued to and by. Only the few certificate fields shown are read from its DER;
+// rustls has already verified the certificate by then
+use chrono::{DateTime, NaiveDate, Utc};
+use rustls::pki_types::CertificateDer;
+
+// Object identifiers of the name attributes shown
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+const TAG_DNS_NAME: u8 = 0x82;
+
+/// The negotiated security of one HTTPS connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsInfo {
+    /// Like "TLS 1.3"
+    pub protocol: String,
+    /// Like "TLS13_AES_256_GCM_SHA384"
+    pub cipher: String,
+    /// The server's own certificate, without the chain above it
+    pub certificate: Option<CertificateSummary>,
+}
+
+impl TlsInfo {
+    pub fn of(connection: &rustls::ClientConnection) -> Self {
+        let protocol = match connection.protocol_version() {
+            Some(rustls::ProtocolVersion::TLSv1_3) => "TLS 1.3".to_string(),
+            Some(rustls::ProtocolVersion::TLSv1_2) => "TLS 1.2".to_string(),
+            Some(other) => format!("{:?}", other),
+            None => "Unknown".to_string(),
+        };
+        let cipher = connection.negotiated_cipher_suite()
+            .map_or_else(|| "Unknown".to_string(), |suite| format!("{:?}", suite.suite()));
+        let certificate = connection.peer_certificates()
+            .and_then(|chain| chain.first())
+            .and_then(|certificate| CertificateSummary::parse(certificate));
+        Self { protocol, cipher, certificate }
+    }
+}
+
+/// Who a certificate names and how long it's valid
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateSummary {
+    /// Common name of the subject, usually the site's host
+    pub subject: String,
+    /// Organization of the issuer, or its common name when it has none
+    pub issuer: String,
+    /// Hosts the certificate is valid for, from its subject alternative names
+    pub hosts: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl CertificateSummary {
+    /// Read the summary out of a DER certificate; None if it isn't one
+    pub fn parse(certificate: &CertificateDer<'_>) -> Option<Self> {
+        let (certificate, _) = read(certificate.as_ref(), TAG_SEQUENCE)?;
+        let (mut tbs, _) = read(certificate, TAG_SEQUENCE)?;
+        if tbs.first() == Some(&TAG_VERSION) {
+            tbs = read_any(tbs)?.2;
+        }
+        let (_serial, rest) = read_any(tbs).map(|(_, content, rest)| (content, rest))?;
+        let (_signature, rest) = read(rest, TAG_SEQUENCE)?;
+        let (issuer, rest) = read(rest, TAG_SEQUENCE)?;
+        let (validity, rest) = read(rest, TAG_SEQUENCE)?;
+        let (subject, rest) = read(rest, TAG_SEQUENCE)?;
+        let (_public_key, mut rest) = read(rest, TAG_SEQUENCE)?;
+
+        // Unique IDs may come before the extensions
+        let mut hosts = Vec::new();
+        while let Some((tag, content, after)) = read_any(rest) {
+            if tag == TAG_EXTENSIONS {
+                hosts = alt_names(content).unwrap_or_default();
+            }
+            rest = after;
+        }
+
+        let (not_before, rest) = read_time(validity)?;
+        let (not_after, _) = read_time(rest)?;
+        Some(Self {
+            subject: name_attribute(subject, OID_COMMON_NAME).or_else(|| hosts.first().cloned()).unwrap_or_default(),
+            issuer: name_attribute(issuer, OID_ORGANIZATION).or_else(|| name_attribute(issuer, OID_COMMON_NAME)).unwrap_or_default(),
+            hosts,
+            not_before,
+            not_after,
+        })
+    }
+
+    /// Whether `now` is within the validity period
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before <= now && now <= self.not_after
+    }
+}
+
+// Tag, content and what follows of the DER element at the start of `data`
+fn read_any(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let bytes = (first & 0x7f) as usize;
+        if bytes == 0 || bytes > 4 || rest.len() < bytes {
+            return None;
+        }
+        let length = rest[..bytes].iter().fold(0usize, |length, byte| length << 8 | *byte as usize);
+        (length, &rest[bytes..])
+    };
+    (rest.len() >= length).then(|| (tag, &rest[..length], &rest[length..]))
+}
+
+fn read(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    read_any(data).filter(|(found, _, _)| *found == tag).map(|(_, content, rest)| (content, rest))
+}
+
+// The first value of the attribute `oid` in a Name, which is a sequence of sets of (type, value) pairs
+fn name_attribute(mut name: &[u8], oid: &[u8]) -> Option<String> {
+    while let Some((set, rest)) = read(name, TAG_SET) {
+        let mut set = set;
+        while let Some((pair, after)) = read(set, TAG_SEQUENCE) {
+            if let Some((found, value)) = read(pair, TAG_OID) {
+                if found == oid {
+                    // Printable, UTF-8 and IA5 strings all hold ASCII-compatible text here
+                    let (_, text, _) = read_any(value)?;
+                    return Some(String::from_utf8_lossy(text).into_owned());
+                }
+            }
+            set = after;
+        }
+        name = rest;
+    }
+    None
+}
+
+// DNS names from the subject alternative name extension
+fn alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let (mut list, _) = read(extensions, TAG_SEQUENCE)?;
+    while let Some((extension, rest)) = read(list, TAG_SEQUENCE) {
+        let (oid, mut value) = read(extension, TAG_OID)?;
+        if oid == OID_SUBJECT_ALT_NAME {
+            // Skip the critical flag when it's there
+            if value.first() != Some(&TAG_OCTET_STRING) {
+                value = read_any(value)?.2;
+            }
+            let (octets, _) = read(value, TAG_OCTET_STRING)?;
+            let (mut names, _) = read(octets, TAG_SEQUENCE)?;
+            let mut hosts = Vec::new();
+            while let Some((tag, name, rest)) = read_any(names) {
+                if tag == TAG_DNS_NAME {
+                    hosts.push(String::from_utf8_lossy(name).into_owned());
+                }
+                names = rest;
+            }
+            return Some(hosts);
+        }
+        list = rest;
+    }
+    None
+}
+
+// UTCTime has a two-digit year, from 1950 to 2049; GeneralizedTime has four
+fn read_time(data: &[u8]) -> Option<(DateTime<Utc>, &[u8])> {
+    let (tag, text, rest) = read_any(data)?;
+    let text = std::str::from_utf8(text).ok()?;
+    let digits = |range: std::ops::Range<usize>| text.get(range)?.parse::<u32>().ok();
+    let (year, offset) = match tag {
+        TAG_UTC_TIME => {
+            let year = digits(0..2)?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, 2)
+        }
+        TAG_GENERALIZED_TIME => (digits(0..4)?, 4),
+        _ => return None,
+    };
+    let time = NaiveDate::from_ymd_opt(year as i32, digits(offset..offset + 2)?, digits(offset + 2..offset + 4)?)?
+        .and_hms_opt(digits(offset + 4..offset + 6)?, digits(offset + 6..offset + 8)?, digits(offset + 8..offset + 10)?)?;
+    Some((time.and_utc(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.extend([0x82, (content.len() >> 8) as u8, content.len() as u8]);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn name(attributes: &[(&[u8], &str)]) -> Vec<u8> {
+        let sets: Vec<u8> = attributes.iter()
+            .flat_map(|(oid, value)| der(TAG_SET, &der(TAG_SEQUENCE, &[der(TAG_OID, oid), der(0x0c, value.as_bytes())].concat())))
+            .collect();
+        der(TAG_SEQUENCE, &sets)
+    }
+
+    #[test]
+    fn test_certificate_summary() {
+        let san = der(TAG_SEQUENCE, &[der(TAG_DNS_NAME, b"example.com"), der(TAG_DNS_NAME, b"www.example.com")].concat());
+        let extension = der(TAG_SEQUENCE, &[der(TAG_OID, OID_SUBJECT_ALT_NAME), der(TAG_OCTET_STRING, &san)].concat());
+        let tbs = der(TAG_SEQUENCE, &[
+            der(TAG_VERSION, &der(0x02, &[2])),
+            der(0x02, &[0x01, 0x23]),
+            der(TAG_SEQUENCE, &der(TAG_OID, &[0x2a, 0x86, 0x48])),
+            name(&[(OID_COMMON_NAME, "Example Root"), (OID_ORGANIZATION, "Example CA")]),
+            der(TAG_SEQUENCE, &[der(TAG_UTC_TIME, b"250101000000Z"), der(TAG_GENERALIZED_TIME, b"20260401123000Z")].concat()),
+            name(&[(OID_COMMON_NAME, "example.com")]),
+            der(TAG_SEQUENCE, &[0u8; 140]),
+            der(TAG_EXTENSIONS, &der(TAG_SEQUENCE, &extension)),
+        ].concat());
+        let certificate = der(TAG_SEQUENCE, &[tbs, der(TAG_SEQUENCE, &[]), der(0x03, &[0])].concat());
+
+        let summary = CertificateSummary::parse(&CertificateDer::from(certificate)).unwrap();
+        assert_eq!(summary.subject, "example.com");
+        assert_eq!(summary.issuer, "Example CA");
+        assert_eq!(summary.hosts, ["example.com", "www.example.com"]);
+        assert_eq!(summary.not_before.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(summary.not_after.to_rfc3339(), "2026-04-01T12:30:00+00:00");
+        assert!(summary.is_valid_at(summary.not_before));
+        assert!(!summary.is_valid_at(summary.not_after + chrono::Duration::seconds(1)));
+
+        assert!(CertificateSummary::parse(&CertificateDer::from(vec![0x30, 0x05, 0x01])).is_none());
+    }
+}
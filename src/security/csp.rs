@@ -11,4 +11,91 @@ impl CSPManager {
         // TODO: Implement CSP validation
         true
     }
-}
\ No newline at end of file
+}
+// Best score a policy can get in the security report
+pub const MAX_POLICY_POINTS: u32 = 15;
+
+/// How much a Content-Security-Policy actually restricts, with what to tighten
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyAssessment {
+    pub points: u32,
+    pub findings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Grade one Content-Security-Policy header value
+pub fn assess_policy(policy: &str) -> PolicyAssessment {
+    let directives: Vec<(String, Vec<String>)> = policy.split(';')
+        .filter_map(|directive| {
+            let mut parts = directive.split_whitespace();
+            let name = parts.next()?.to_ascii_lowercase();
+            Some((name, parts.map(|source| source.to_ascii_lowercase()).collect()))
+        })
+        .collect();
+    let sources = |name: &str| directives.iter().find(|(n, _)| n == name).map(|(_, s)| s.as_slice());
+
+    let mut penalty = 0;
+    let mut findings = Vec::new();
+    let mut recommendations = Vec::new();
+
+    match sources("script-src").or_else(|| sources("default-src")) {
+        None => {
+            penalty += 6;
+            findings.push("No script-src or default-src, so scripts may load from anywhere".to_string());
+            recommendations.push("Add default-src 'self' and list the script origins the site needs".to_string());
+        }
+        Some(scripts) => {
+            // Browsers ignore 'unsafe-inline' once a nonce or hash is present
+            let has_nonce = scripts.iter().any(|s| s.starts_with("'nonce-") || s.starts_with("'sha"));
+            if scripts.iter().any(|s| s == "'unsafe-inline'") && !has_nonce {
+                penalty += 5;
+                findings.push("Inline scripts are allowed ('unsafe-inline'), which defeats most XSS protection".to_string());
+                recommendations.push("Replace 'unsafe-inline' with nonces or hashes for the inline scripts".to_string());
+            }
+            if scripts.iter().any(|s| s == "'unsafe-eval'") {
+                penalty += 3;
+                findings.push("eval() and new Function() are allowed ('unsafe-eval')".to_string());
+                recommendations.push("Remove 'unsafe-eval' and avoid building code from strings".to_string());
+            }
+            if scripts.iter().any(|s| matches!(s.as_str(), "*" | "http:" | "https:" | "data:")) {
+                penalty += 3;
+                findings.push("Scripts may come from any host or from data: URLs".to_string());
+                recommendations.push("List specific script hosts instead of *, http:, https: or data:".to_string());
+            }
+        }
+    }
+    let no_objects = |name: &str| sources(name).is_some_and(|s| s == ["'none'"]);
+    let blocks_objects = no_objects("object-src") || (sources("object-src").is_none() && no_objects("default-src"));
+    if !blocks_objects {
+        penalty += 1;
+        recommendations.push("Add object-src 'none' to block plugin content".to_string());
+    }
+    if sources("base-uri").is_none() {
+        penalty += 1;
+        recommendations.push("Add base-uri 'self' so injected <base> tags can't redirect relative URLs".to_string());
+    }
+
+    PolicyAssessment {
+        points: MAX_POLICY_POINTS.saturating_sub(penalty),
+        findings,
+        recommendations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_policy() {
+        let strict = assess_policy("default-src 'none'; script-src 'self' 'nonce-abc' 'unsafe-inline'; base-uri 'none'");
+        assert_eq!(strict.points, MAX_POLICY_POINTS);
+        assert!(strict.recommendations.is_empty());
+
+        let loose = assess_policy("script-src * 'unsafe-inline' 'unsafe-eval'");
+        assert_eq!(loose.points, 2);
+        assert_eq!(loose.findings.len(), 3);
+
+        assert_eq!(assess_policy("img-src 'self'").points, 7);
+    }
+}
@@ -4,12 +4,19 @@ pub mod csp;
 pub mod https_validator;
 pub mod sandbox;
 pub mod download_validator;
+pub mod password_manager;
+pub mod certificate;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use anyhow::{Result, anyhow};
+use crate::engine::resource_loader::{ResourceKind, ResourceRequest};
 use crate::networking::HttpResponse;
 
+// HSTS max-age below which the policy lapses too quickly to protect returning visitors (180 days)
+const MIN_HSTS_MAX_AGE: u64 = 15_552_000;
+
 // Enhanced security manager
 pub struct SecurityManager {
     hsts_cache: HashMap<String, HstsEntry>,
@@ -55,30 +62,14 @@ impl SecurityManager {
     }
 
     pub fn process_security_headers(&mut self, url: &str, response: &HttpResponse) -> SecurityReport {
-        let mut report = SecurityReport::new(url);
+        let report = SecurityReport::evaluate(url, response);
         let domain = Self::extract_domain(url);
         
-        // Process HSTS header
-        if let Some(hsts_value) = response.get_header("Strict-Transport-Security") {
-            match self.parse_hsts_header(hsts_value) {
-                Ok(entry) => {
-                    self.hsts_cache.insert(domain.clone(), entry);
-                    report.hsts_enabled = true;
-                    report.security_score += 20;
-                },
-                Err(e) => {
-                    report.warnings.push(format!("Invalid HSTS header: {}", e));
-                }
+        if report.secure_connection {
+            if let Some(entry) = header(response, "Strict-Transport-Security").and_then(|v| self.parse_hsts_header(v).ok()) {
+                self.hsts_cache.insert(domain.clone(), entry);
             }
-        }
-
-        // Check connection security
-        if url.starts_with("https://") {
-            report.secure_connection = true;
-            report.security_score += 30;
             self.secure_contexts.insert(domain);
-        } else if url.starts_with("http://") {
-            report.warnings.push("Insecure HTTP connection".to_string());
         }
 
         report
@@ -125,7 +116,94 @@ impl SecurityManager {
     }
 }
 
-#[derive(Debug)]
+// Response header by name, whatever case the server or client normalised it to
+fn header<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    response.headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The areas a site is scored on, in the order they are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SecurityCategory {
+    Transport,
+    Headers,
+    Cookies,
+    MixedContent,
+    ContentSecurityPolicy,
+}
+
+impl SecurityCategory {
+    pub const ALL: [SecurityCategory; 5] = [
+        SecurityCategory::Transport,
+        SecurityCategory::Headers,
+        SecurityCategory::Cookies,
+        SecurityCategory::MixedContent,
+        SecurityCategory::ContentSecurityPolicy,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecurityCategory::Transport => "Connection",
+            SecurityCategory::Headers => "Security headers",
+            SecurityCategory::Cookies => "Cookie flags",
+            SecurityCategory::MixedContent => "Mixed content",
+            SecurityCategory::ContentSecurityPolicy => "Content Security Policy",
+        }
+    }
+
+    /// Share of the 100-point score this category is worth
+    pub fn max_points(&self) -> u32 {
+        match self {
+            SecurityCategory::Transport => 30,
+            SecurityCategory::Headers => 25,
+            SecurityCategory::Cookies => 15,
+            SecurityCategory::MixedContent => 15,
+            SecurityCategory::ContentSecurityPolicy => csp::MAX_POLICY_POINTS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warning,
+    Fail,
+}
+
+/// Score for one category, with what was found and how the site could do better
+#[derive(Debug, Clone)]
+pub struct SecurityCheck {
+    pub category: SecurityCategory,
+    pub points: u32,
+    pub status: CheckStatus,
+    pub summary: String,
+    pub findings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl SecurityCheck {
+    fn new(category: SecurityCategory, points: u32, summary: impl Into<String>) -> Self {
+        let max = category.max_points();
+        let status = if points >= max {
+            CheckStatus::Pass
+        } else if points * 3 >= max {
+            CheckStatus::Warning
+        } else {
+            CheckStatus::Fail
+        };
+        Self {
+            category,
+            points: points.min(max),
+            status,
+            summary: summary.into(),
+            findings: Vec::new(),
+            recommendations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SecurityReport {
     pub url: String,
     pub secure_connection: bool,
@@ -135,6 +213,8 @@ pub struct SecurityReport {
     pub content_type_protection: bool,
     pub security_score: u32,
     pub warnings: Vec<String>,
+    /// One check per category, in `SecurityCategory::ALL` order
+    pub checks: Vec<SecurityCheck>,
 }
 
 impl SecurityReport {
@@ -148,11 +228,262 @@ impl SecurityReport {
             content_type_protection: false,
             security_score: 0,
             warnings: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Score the page loaded from `url` by its response. Mixed content passes until
+    /// `check_mixed_content` sees the page's subresources.
+    pub fn evaluate(url: &str, response: &HttpResponse) -> Self {
+        let mut report = Self::new(url);
+        report.secure_connection = url.starts_with("https://");
+        if url.starts_with("http://") {
+            report.warnings.push("Insecure HTTP connection".to_string());
+        }
+
+        let checks = vec![
+            report.check_transport(),
+            report.check_headers(response),
+            report.check_cookies(response),
+            report.mixed_content_check(&[]),
+            report.check_csp(response),
+        ];
+        report.checks = checks;
+        report.update_score();
+        report
+    }
+
+    /// Re-score mixed content against the subresources the page asked for
+    pub fn check_mixed_content(&mut self, resources: &[ResourceRequest]) {
+        let check = self.mixed_content_check(resources);
+        if let Some(existing) = self.checks.iter_mut().find(|c| c.category == SecurityCategory::MixedContent) {
+            *existing = check;
+        }
+        self.update_score();
+    }
+
+    pub fn check(&self, category: SecurityCategory) -> Option<&SecurityCheck> {
+        self.checks.iter().find(|check| check.category == category)
+    }
+
+    pub fn level(&self) -> SecurityLevel {
+        SecurityLevel::from_score(self.security_score)
+    }
+
+    /// Host the report is about, which is how sites are told apart
+    pub fn site(&self) -> String {
+        url::Url::parse(&self.url).ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| self.url.clone())
+    }
+
+    fn update_score(&mut self) {
+        self.security_score = self.checks.iter().map(|check| check.points).sum();
+    }
+
+    fn check_transport(&self) -> SecurityCheck {
+        let category = SecurityCategory::Transport;
+        if self.secure_connection {
+            let mut check = SecurityCheck::new(category, category.max_points(), "Encrypted HTTPS connection");
+            check.findings.push("Traffic to this site can't be read or changed in transit".to_string());
+            check
+        } else {
+            let mut check = SecurityCheck::new(category, 0, "Not encrypted");
+            check.findings.push("Anything sent to or from this site can be read or changed on the network".to_string());
+            check.recommendations.push("Serve the site over HTTPS and redirect HTTP requests to it".to_string());
+            check
+        }
+    }
+
+    fn check_headers(&mut self, response: &HttpResponse) -> SecurityCheck {
+        let mut points = 0;
+        let mut findings = Vec::new();
+        let mut recommendations = Vec::new();
+
+        match header(response, "Strict-Transport-Security") {
+            _ if !self.secure_connection => {
+                recommendations.push("Strict-Transport-Security only takes effect once the site is on HTTPS".to_string());
+            }
+            Some(value) => {
+                let max_age = value.split(';')
+                    .find_map(|d| d.trim().strip_prefix("max-age="))
+                    .and_then(|v| v.trim_matches('"').parse::<u64>().ok());
+                match max_age {
+                    Some(max_age) if max_age >= MIN_HSTS_MAX_AGE => {
+                        points += 8;
+                        self.hsts_enabled = true;
+                        findings.push("HSTS keeps browsers on HTTPS for this site".to_string());
+                    }
+                    Some(_) => {
+                        points += 4;
+                        self.hsts_enabled = true;
+                        findings.push("HSTS max-age is short".to_string());
+                        recommendations.push("Raise the HSTS max-age to at least 6 months (15552000)".to_string());
+                    }
+                    None => {
+                        self.warnings.push("Invalid HSTS header: Missing max-age directive".to_string());
+                        recommendations.push("Fix Strict-Transport-Security: it has no valid max-age".to_string());
+                    }
+                }
+            }
+            None => recommendations.push("Send Strict-Transport-Security so browsers never fall back to HTTP".to_string()),
+        }
+
+        if header(response, "X-Content-Type-Options").is_some_and(|v| v.trim().eq_ignore_ascii_case("nosniff")) {
+            points += 5;
+            self.content_type_protection = true;
+            findings.push("Browsers won't guess content types (nosniff)".to_string());
+        } else {
+            recommendations.push("Send X-Content-Type-Options: nosniff so files can't be run as a different type".to_string());
+        }
+
+        let frame_options = header(response, "X-Frame-Options")
+            .is_some_and(|v| matches!(v.trim().to_ascii_uppercase().as_str(), "DENY" | "SAMEORIGIN"));
+        let frame_ancestors = header(response, "Content-Security-Policy").is_some_and(|v| v.to_ascii_lowercase().contains("frame-ancestors"));
+        if frame_options || frame_ancestors {
+            points += 6;
+            self.frame_protection = true;
+            findings.push("Other sites can't embed this page to trick clicks".to_string());
+        } else {
+            recommendations.push("Send X-Frame-Options: DENY or CSP frame-ancestors to prevent clickjacking".to_string());
+        }
+
+        match header(response, "Referrer-Policy").map(|v| v.trim().to_ascii_lowercase()) {
+            Some(policy) if !matches!(policy.as_str(), "unsafe-url" | "no-referrer-when-downgrade") => {
+                points += 3;
+                findings.push(format!("Referrer-Policy: {}", policy));
+            }
+            Some(policy) => recommendations.push(format!("Referrer-Policy '{}' leaks full URLs; use strict-origin-when-cross-origin", policy)),
+            None => recommendations.push("Send a Referrer-Policy such as strict-origin-when-cross-origin".to_string()),
+        }
+
+        if header(response, "Permissions-Policy").is_some() {
+            points += 3;
+            findings.push("Permissions-Policy limits which browser features the page can use".to_string());
+        } else {
+            recommendations.push("Send a Permissions-Policy that turns off features the site doesn't use".to_string());
+        }
+
+        let category = SecurityCategory::Headers;
+        let summary = format!("{} of 5 protective headers", findings.len());
+        let mut check = SecurityCheck::new(category, points, summary);
+        check.findings = findings;
+        check.recommendations = recommendations;
+        check
+    }
+
+    fn check_cookies(&self, response: &HttpResponse) -> SecurityCheck {
+        let category = SecurityCategory::Cookies;
+        // Repeated Set-Cookie headers arrive newline-separated
+        let cookies: Vec<&str> = header(response, "Set-Cookie")
+            .map(|v| v.lines().filter(|line| !line.trim().is_empty()).collect())
+            .unwrap_or_default();
+        if cookies.is_empty() {
+            return SecurityCheck::new(category, category.max_points(), "No cookies set");
+        }
+
+        let (mut missing_secure, mut missing_http_only, mut missing_same_site) = (0, 0, 0);
+        let mut findings = Vec::new();
+        for cookie in &cookies {
+            let mut parts = cookie.split(';');
+            let name = parts.next().and_then(|p| p.split('=').next()).unwrap_or("").trim();
+            let attributes: Vec<String> = parts.map(|a| a.trim().to_ascii_lowercase()).collect();
+            let secure = attributes.iter().any(|a| a == "secure");
+            let http_only = attributes.iter().any(|a| a == "httponly");
+            // SameSite=None is only honoured on Secure cookies
+            let same_site = attributes.iter()
+                .filter_map(|a| a.strip_prefix("samesite="))
+                .any(|v| v != "none" || secure);
+
+            let mut missing = Vec::new();
+            if !secure {
+                missing_secure += 1;
+                missing.push("Secure");
+            }
+            if !http_only {
+                missing_http_only += 1;
+                missing.push("HttpOnly");
+            }
+            if !same_site {
+                missing_same_site += 1;
+                missing.push("SameSite");
+            }
+            if !missing.is_empty() {
+                findings.push(format!("'{}' is missing {}", name, missing.join(", ")));
+            }
+        }
+
+        let flags = cookies.len() as u32 * 3;
+        let set = flags - (missing_secure + missing_http_only + missing_same_site);
+        let points = (category.max_points() * set + flags / 2) / flags;
+        let mut check = SecurityCheck::new(category, points, format!("{} cookie(s), {} of {} flags set", cookies.len(), set, flags));
+        check.findings = findings;
+        if missing_secure > 0 {
+            check.recommendations.push("Mark cookies Secure so they are never sent over plain HTTP".to_string());
+        }
+        if missing_http_only > 0 {
+            check.recommendations.push("Mark cookies scripts don't read HttpOnly so injected scripts can't steal them".to_string());
+        }
+        if missing_same_site > 0 {
+            check.recommendations.push("Set SameSite=Lax or Strict so cross-site requests don't carry the cookie".to_string());
+        }
+        check
+    }
+
+    fn mixed_content_check(&self, resources: &[ResourceRequest]) -> SecurityCheck {
+        let category = SecurityCategory::MixedContent;
+        if !self.secure_connection {
+            let mut check = SecurityCheck::new(category, 0, "Whole page is insecure");
+            check.findings.push("The page itself is loaded over HTTP, so none of its content is protected".to_string());
+            return check;
+        }
+
+        let insecure: Vec<&ResourceRequest> = resources.iter().filter(|r| r.url.starts_with("http://")).collect();
+        // Images can only change what the page shows; scripts, styles and fonts can change what it does
+        let (passive, active): (Vec<&ResourceRequest>, Vec<&ResourceRequest>) =
+            insecure.iter().partition(|r| r.kind == ResourceKind::Image);
+        let mut check = if !active.is_empty() {
+            SecurityCheck::new(category, 0, format!("{} insecure script or style resource(s)", active.len()))
+        } else if !passive.is_empty() {
+            SecurityCheck::new(category, 8, format!("{} insecure image(s)", passive.len()))
+        } else {
+            SecurityCheck::new(category, category.max_points(), "All subresources use HTTPS")
+        };
+        check.findings = insecure.iter().take(10).map(|r| format!("{:?} over HTTP: {}", r.kind, r.url)).collect();
+        if !insecure.is_empty() {
+            check.recommendations.push("Load every subresource over HTTPS, or send CSP upgrade-insecure-requests".to_string());
+        }
+        check
+    }
+
+    fn check_csp(&mut self, response: &HttpResponse) -> SecurityCheck {
+        let category = SecurityCategory::ContentSecurityPolicy;
+        if let Some(policy) = header(response, "Content-Security-Policy") {
+            self.csp_enabled = true;
+            let assessment = csp::assess_policy(policy);
+            let summary = if assessment.findings.is_empty() { "Enforced and strict" } else { "Enforced with gaps" };
+            let mut check = SecurityCheck::new(category, assessment.points, summary);
+            check.findings = assessment.findings;
+            check.recommendations = assessment.recommendations;
+            check
+        } else if let Some(policy) = header(response, "Content-Security-Policy-Report-Only") {
+            let assessment = csp::assess_policy(policy);
+            let mut check = SecurityCheck::new(category, assessment.points / 2, "Report-only");
+            check.findings.push("The policy only reports violations and doesn't block anything".to_string());
+            check.findings.extend(assessment.findings);
+            check.recommendations.push("Switch to an enforced Content-Security-Policy once reports are clean".to_string());
+            check.recommendations.extend(assessment.recommendations);
+            check
+        } else {
+            let mut check = SecurityCheck::new(category, 0, "No policy");
+            check.findings.push("Nothing limits where the page's scripts can come from".to_string());
+            check.recommendations.push("Add a Content-Security-Policy, starting with default-src 'self'".to_string());
+            check
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SecurityLevel {
     Poor,
     Fair,
@@ -160,6 +491,47 @@ pub enum SecurityLevel {
     Excellent,
 }
 
+impl SecurityLevel {
+    pub fn from_score(score: u32) -> Self {
+        match score {
+            90.. => SecurityLevel::Excellent,
+            70..=89 => SecurityLevel::Good,
+            50..=69 => SecurityLevel::Fair,
+            _ => SecurityLevel::Poor,
+        }
+    }
+}
+
+static SHARED_LOG: OnceLock<SecurityLog> = OnceLock::new();
+
+/// The latest report for every site visited this session, for `neon://security`
+#[derive(Default)]
+pub struct SecurityLog {
+    sites: Mutex<BTreeMap<String, SecurityReport>>,
+}
+
+impl SecurityLog {
+    pub fn shared() -> &'static SecurityLog {
+        SHARED_LOG.get_or_init(SecurityLog::default)
+    }
+
+    /// Keep `report` as the site's current one
+    pub fn record(&self, report: &SecurityReport) {
+        if let Ok(mut sites) = self.sites.lock() {
+            sites.insert(report.site(), report.clone());
+        }
+    }
+
+    /// One report per site, worst score first
+    pub fn reports(&self) -> Vec<SecurityReport> {
+        let mut reports: Vec<SecurityReport> = self.sites.lock()
+            .map(|sites| sites.values().cloned().collect())
+            .unwrap_or_default();
+        reports.sort_by_key(|report| report.security_score);
+        reports
+    }
+}
+
 impl SecurityManager {
     pub fn is_url_safe(&self, url: &str) -> bool {
         // Basic URL safety checks
@@ -196,4 +568,47 @@ impl SecurityManager {
         // Certificate validation via rustls and webpki-roots
         true
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[(&str, &str)]) -> HttpResponse {
+        let headers = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        HttpResponse::new(200, "OK".to_string(), headers, Vec::new())
+    }
+
+    #[test]
+    fn test_security_report_breakdown() {
+        let hardened = response(&[
+            ("strict-transport-security", "max-age=63072000; includeSubDomains"),
+            ("x-content-type-options", "nosniff"),
+            ("x-frame-options", "DENY"),
+            ("referrer-policy", "strict-origin-when-cross-origin"),
+            ("permissions-policy", "camera=()"),
+            ("content-security-policy", "default-src 'self'; object-src 'none'; base-uri 'self'"),
+            ("Set-Cookie", "id=1; Secure; HttpOnly; SameSite=Lax"),
+        ]);
+        let mut report = SecurityReport::evaluate("https://bank.example/", &hardened);
+        assert_eq!(report.security_score, 100);
+        assert_eq!(report.level(), SecurityLevel::Excellent);
+        assert!(report.checks.iter().all(|check| check.recommendations.is_empty()));
+
+        report.check_mixed_content(&[ResourceRequest {
+            kind: ResourceKind::Script,
+            url: "http://cdn.example/app.js".to_string(),
+            reference: "http://cdn.example/app.js".to_string(),
+            lazy: false,
+        }]);
+        assert_eq!(report.check(SecurityCategory::MixedContent).map(|c| c.status), Some(CheckStatus::Fail));
+        assert_eq!(report.security_score, 85);
+
+        let bare = response(&[("Set-Cookie", "a=1\nb=2; Secure; HttpOnly; SameSite=None")]);
+        let report = SecurityReport::evaluate("http://blog.example/", &bare);
+        let cookies = report.check(SecurityCategory::Cookies).unwrap();
+        assert_eq!(cookies.points, 8);
+        assert_eq!(cookies.findings.len(), 1);
+        assert_eq!(report.security_score, 8);
+        assert_eq!(report.site(), "blog.example");
+    }
+}
@@ -0,0 +1,388 @@
+// Saved logins, encrypted at rest with a key kept in the OS keychain or derived from a master password
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::sync::crypto::{self, SyncKey};
+
+// Passwords shorter than this fall quickly to guessing once a site's password hashes leak
+pub const MIN_PASSWORD_LEN: usize = 10;
+
+// Among the passwords that turn up most in breach dumps; the first ones any attacker tries
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456", "123456789", "12345678", "password", "qwerty", "qwerty123", "1234567", "111111", "1234567890",
+    "123123", "abc123", "1234", "password1", "iloveyou", "1q2w3e4r", "000000", "qwertyuiop", "123321",
+    "monkey", "dragon", "654321", "666666", "123", "letmein", "football", "baseball", "welcome", "admin",
+    "sunshine", "princess", "master", "shadow", "superman", "trustno1", "passw0rd", "password123", "starwars",
+];
+
+// Name the vault key goes by in the keychain
+const KEYCHAIN_SERVICE: &str = "NeonSearch";
+const KEYCHAIN_ACCOUNT: &str = "password-vault";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Credential {
+    /// Scheme, host and port the login belongs to, like https://example.com
+    pub origin: String,
+    pub username: String,
+    pub password: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// What's wrong with a saved password
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasswordWarning {
+    TooShort,
+    /// Found in lists of breached passwords
+    Common,
+    /// The same password is saved for this many other sites
+    Reused(usize),
+}
+
+impl PasswordWarning {
+    pub fn description(&self) -> String {
+        match self {
+            PasswordWarning::TooShort => format!("Shorter than {} characters", MIN_PASSWORD_LEN),
+            PasswordWarning::Common => "Appears in breached password lists".to_string(),
+            PasswordWarning::Reused(1) => "Also used on another site".to_string(),
+            PasswordWarning::Reused(sites) => format!("Also used on {} other sites", sites),
+        }
+    }
+}
+
+/// Warnings for `credential`'s password, with `all` being every saved login
+pub fn password_warnings(credential: &Credential, all: &[Credential]) -> Vec<PasswordWarning> {
+    let mut warnings = Vec::new();
+    if credential.password.chars().count() < MIN_PASSWORD_LEN {
+        warnings.push(PasswordWarning::TooShort);
+    }
+    if COMMON_PASSWORDS.contains(&credential.password.to_lowercase().as_str()) {
+        warnings.push(PasswordWarning::Common);
+    }
+    let reused = all.iter()
+        .filter(|other| other.origin != credential.origin && other.password == credential.password)
+        .count();
+    if reused > 0 {
+        warnings.push(PasswordWarning::Reused(reused));
+    }
+    warnings
+}
+
+/// Where the key unlocking the vault comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Protection {
+    Keychain,
+    /// Derived from the master password with this salt, in hex
+    MasterPassword { salt: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    protection: Protection,
+    /// The encrypted VaultData, in hex
+    data: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct VaultData {
+    credentials: Vec<Credential>,
+    /// Origins the user never wants to be asked about saving
+    never_save: Vec<String>,
+}
+
+pub struct PasswordManager {
+    path: PathBuf,
+    // None until the vault is set up
+    protection: Option<Protection>,
+    // Key and contents, while unlocked
+    unlocked: Option<(SyncKey, VaultData)>,
+    // Bumped on every change, so neon://passwords knows to list them again
+    revision: u64,
+}
+
+static SHARED_PASSWORDS: OnceLock<Arc<Mutex<PasswordManager>>> = OnceLock::new();
+
+impl PasswordManager {
+    pub fn load(path: &Path) -> Self {
+        let protection = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<VaultFile>(&content).ok())
+            .map(|file| file.protection);
+        Self { path: path.to_path_buf(), protection, unlocked: None, revision: 0 }
+    }
+
+    /// Process-wide vault shared by the tabs and neon://passwords. A keychain-protected vault unlocks right away
+    pub fn shared() -> Arc<Mutex<PasswordManager>> {
+        SHARED_PASSWORDS
+            .get_or_init(|| {
+                let mut manager = PasswordManager::load(&crate::storage::data_dir().join("passwords.json"));
+                if manager.protection == Some(Protection::Keychain) {
+                    if let Err(e) = manager.unlock_with_keychain() {
+                        log::warn!("Couldn't unlock saved passwords: {}", e);
+                    }
+                }
+                Arc::new(Mutex::new(manager))
+            })
+            .clone()
+    }
+
+    pub fn is_set_up(&self) -> bool {
+        self.protection.is_some()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    pub fn protection(&self) -> Option<&Protection> {
+        self.protection.as_ref()
+    }
+
+    /// Create an empty vault, protected by `master_password` or, without one, by a random key in the OS keychain
+    pub fn set_up(&mut self, master_password: Option<&str>) -> Result<()> {
+        let (protection, key) = match master_password {
+            Some(password) => {
+                let salt = crypto::random_salt()?;
+                (Protection::MasterPassword { salt: crypto::to_hex(&salt) }, SyncKey::derive(password, &salt))
+            }
+            None => {
+                let secret = crypto::to_hex(&crypto::random_salt()?);
+                keychain::store(&secret)?;
+                (Protection::Keychain, SyncKey::derive(&secret, KEYCHAIN_ACCOUNT.as_bytes()))
+            }
+        };
+        self.protection = Some(protection);
+        self.unlocked = Some((key, VaultData::default()));
+        self.changed()
+    }
+
+    pub fn unlock(&mut self, master_password: &str) -> Result<()> {
+        match &self.protection {
+            Some(Protection::MasterPassword { salt }) => {
+                let salt = crypto::from_hex(salt).ok_or_else(|| anyhow!("The password vault is damaged"))?;
+                self.open(SyncKey::derive(master_password, &salt))
+            }
+            Some(Protection::Keychain) => self.unlock_with_keychain(),
+            None => Err(anyhow!("No passwords are saved yet")),
+        }
+    }
+
+    fn unlock_with_keychain(&mut self) -> Result<()> {
+        let secret = keychain::load()?;
+        self.open(SyncKey::derive(&secret, KEYCHAIN_ACCOUNT.as_bytes()))
+    }
+
+    fn open(&mut self, key: SyncKey) -> Result<()> {
+        let content = std::fs::read_to_string(&self.path).map_err(|e| anyhow!("Failed to read saved passwords: {}", e))?;
+        let file: VaultFile = serde_json::from_str(&content)?;
+        let sealed = crypto::from_hex(&file.data).ok_or_else(|| anyhow!("The password vault is damaged"))?;
+        let data = key.decrypt(&sealed).map_err(|_| anyhow!("Wrong master password"))?;
+        self.unlocked = Some((key, serde_json::from_slice(&data)?));
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Forget the key until the master password is entered again
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+        self.revision += 1;
+    }
+
+    pub fn credentials(&self) -> &[Credential] {
+        self.unlocked.as_ref().map(|(_, data)| data.credentials.as_slice()).unwrap_or_default()
+    }
+
+    /// Logins saved for `origin`, the most recently used first
+    pub fn credentials_for(&self, origin: &str) -> Vec<&Credential> {
+        let mut found: Vec<&Credential> = self.credentials().iter().filter(|c| c.origin == origin).collect();
+        found.sort_by_key(|c| std::cmp::Reverse(c.last_used.unwrap_or(c.created_at)));
+        found
+    }
+
+    /// Whether to ask about saving a login just submitted on `origin`. Always, while the vault is locked or not set up
+    pub fn should_offer_save(&self, origin: &str, username: &str, password: &str) -> bool {
+        let Some((_, data)) = &self.unlocked else {
+            return true;
+        };
+        !data.never_save.iter().any(|o| o == origin)
+            && !data.credentials.iter().any(|c| c.origin == origin && c.username == username && c.password == password)
+    }
+
+    /// Save a login, replacing the password of one already saved under the same username
+    pub fn save(&mut self, origin: &str, username: &str, password: &str) -> Result<()> {
+        let data = self.data_mut()?;
+        match data.credentials.iter_mut().find(|c| c.origin == origin && c.username == username) {
+            Some(credential) => credential.password = password.to_string(),
+            None => data.credentials.push(Credential {
+                origin: origin.to_string(),
+                username: username.to_string(),
+                password: password.to_string(),
+                created_at: Utc::now(),
+                last_used: None,
+            }),
+        }
+        self.changed()
+    }
+
+    /// Replace the login at `index` of `credentials()`
+    pub fn update(&mut self, index: usize, credential: Credential) -> Result<()> {
+        let data = self.data_mut()?;
+        let slot = data.credentials.get_mut(index).ok_or_else(|| anyhow!("No such saved password"))?;
+        *slot = credential;
+        self.changed()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<()> {
+        let data = self.data_mut()?;
+        if index < data.credentials.len() {
+            data.credentials.remove(index);
+        }
+        self.changed()
+    }
+
+    pub fn never_save(&mut self, origin: &str) -> Result<()> {
+        self.data_mut()?.never_save.push(origin.to_string());
+        self.changed()
+    }
+
+    pub fn never_saved_origins(&self) -> &[String] {
+        self.unlocked.as_ref().map(|(_, data)| data.never_save.as_slice()).unwrap_or_default()
+    }
+
+    pub fn allow_saving(&mut self, origin: &str) -> Result<()> {
+        self.data_mut()?.never_save.retain(|o| o != origin);
+        self.changed()
+    }
+
+    /// Note that the login was filled in, so it's offered first next time
+    pub fn mark_used(&mut self, origin: &str, username: &str) {
+        if let Ok(data) = self.data_mut() {
+            if let Some(credential) = data.credentials.iter_mut().find(|c| c.origin == origin && c.username == username) {
+                credential.last_used = Some(Utc::now());
+                let _ = self.changed();
+            }
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn data_mut(&mut self) -> Result<&mut VaultData> {
+        self.unlocked.as_mut().map(|(_, data)| data).ok_or_else(|| anyhow!("Saved passwords are locked"))
+    }
+
+    fn changed(&mut self) -> Result<()> {
+        self.revision += 1;
+        let (Some(protection), Some((key, data))) = (&self.protection, &self.unlocked) else {
+            return Err(anyhow!("Saved passwords are locked"));
+        };
+        let sealed = key.encrypt(&serde_json::to_vec(data)?)?;
+        let file = VaultFile { protection: protection.clone(), data: crypto::to_hex(&sealed) };
+        std::fs::write(&self.path, serde_json::to_string_pretty(&file)?).map_err(|e| anyhow!("Failed to save passwords: {}", e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the OS keychain can hold the vault key, so no master password is needed
+pub fn keychain_available() -> bool {
+    keychain::available()
+}
+
+// The OS keychain, through its command-line tool: `security` on macOS, `secret-tool` from libsecret elsewhere
+mod keychain {
+    use super::*;
+
+    #[cfg(target_os = "macos")]
+    const TOOL: &str = "security";
+    #[cfg(not(target_os = "macos"))]
+    const TOOL: &str = "secret-tool";
+
+    pub fn available() -> bool {
+        !cfg!(windows) && Command::new(TOOL).arg("--help").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok()
+    }
+
+    // The secret only ever goes through stdin, as arguments are visible to other users in the process list
+    pub fn store(secret: &str) -> Result<()> {
+        let mut command = Command::new(TOOL);
+        let input = if cfg!(target_os = "macos") {
+            // `security -i` reads its command from stdin; the secret is hex, so it needs no quoting
+            command.arg("-i");
+            format!("add-generic-password -U -s \"{}\" -a \"{}\" -w {}\n", KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT, secret)
+        } else {
+            command.args(["store", "--label", "NeonSearch saved passwords", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT]);
+            secret.to_string()
+        };
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()
+            .map_err(|e| anyhow!("No OS keychain available: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            use std::io::Write;
+            stdin.write_all(input.as_bytes())?;
+        }
+        // `security -i` exits cleanly whatever became of its command, so the key is read back to be sure
+        match child.wait()?.success() && load().is_ok_and(|stored| stored == secret) {
+            true => Ok(()),
+            false => Err(anyhow!("The OS keychain refused to store the key")),
+        }
+    }
+
+    pub fn load() -> Result<String> {
+        let output = if cfg!(target_os = "macos") {
+            Command::new(TOOL).args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-a", KEYCHAIN_ACCOUNT, "-w"]).output()
+        } else {
+            Command::new(TOOL).args(["lookup", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT]).output()
+        }.map_err(|e| anyhow!("No OS keychain available: {}", e))?;
+        let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() || secret.is_empty() {
+            return Err(anyhow!("The vault key is missing from the OS keychain"));
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_master_password_vault() {
+        let path = std::env::temp_dir().join(format!("neon-passwords-{}.json", std::process::id()));
+        let mut manager = PasswordManager::load(&path);
+        manager.set_up(Some("hunter2 but longer")).unwrap();
+        manager.save("https://example.com", "ada", "s3cret").unwrap();
+        assert!(!manager.should_offer_save("https://example.com", "ada", "s3cret"));
+        assert!(manager.should_offer_save("https://example.com", "ada", "changed"));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("s3cret"));
+
+        let mut reopened = PasswordManager::load(&path);
+        assert!(reopened.is_set_up() && !reopened.is_unlocked());
+        assert!(reopened.unlock("wrong").is_err());
+        reopened.unlock("hunter2 but longer").unwrap();
+        assert_eq!(reopened.credentials_for("https://example.com")[0].username, "ada");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_password_warnings() {
+        let login = |origin: &str, password: &str| Credential {
+            origin: origin.to_string(),
+            username: "ada".to_string(),
+            password: password.to_string(),
+            created_at: Utc::now(),
+            last_used: None,
+        };
+        let all = [login("https://a.example", "Password1"), login("https://b.example", "Password1"), login("https://c.example", "kettle-orbit-lantern")];
+        assert_eq!(password_warnings(&all[0], &all), [PasswordWarning::TooShort, PasswordWarning::Common, PasswordWarning::Reused(1)]);
+        assert!(password_warnings(&all[2], &all).is_empty());
+    }
+}
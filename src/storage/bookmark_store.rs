@@ -0,0 +1,72 @@
+// Bookmarks saved in the profile, such as those brought over from another browser
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredBookmark {
+    pub title: String,
+    pub url: String,
+    /// Folders from the top down, empty for bookmarks at the root
+    #[serde(default)]
+    pub folder: Vec<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+pub struct BookmarkStore {
+    path: PathBuf,
+    bookmarks: Vec<StoredBookmark>,
+    // Bumped on every change, so neon://bookmarks knows to show them again
+    revision: u64,
+}
+
+static SHARED_BOOKMARKS: OnceLock<Arc<Mutex<BookmarkStore>>> = OnceLock::new();
+
+impl BookmarkStore {
+    pub fn load(path: &Path) -> Self {
+        let bookmarks = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path: path.to_path_buf(), bookmarks, revision: 0 }
+    }
+
+    /// Process-wide store shared by neon://bookmarks and the importer
+    pub fn shared() -> Arc<Mutex<BookmarkStore>> {
+        SHARED_BOOKMARKS
+            .get_or_init(|| Arc::new(Mutex::new(BookmarkStore::load(&super::data_dir().join("bookmarks.json")))))
+            .clone()
+    }
+
+    pub fn list(&self) -> &[StoredBookmark] {
+        &self.bookmarks
+    }
+
+    /// Add the bookmarks that aren't already saved in the same folder. Returns how many were new
+    pub fn add_all(&mut self, bookmarks: impl IntoIterator<Item = StoredBookmark>) -> Result<usize> {
+        let before = self.bookmarks.len();
+        for bookmark in bookmarks {
+            let saved = self.bookmarks.iter().any(|b| b.url == bookmark.url && b.folder == bookmark.folder);
+            if !saved {
+                self.bookmarks.push(bookmark);
+            }
+        }
+        let added = self.bookmarks.len() - before;
+        if added > 0 {
+            self.revision += 1;
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.bookmarks)?;
+        std::fs::write(&self.path, content).map_err(|e| anyhow!("Failed to save bookmarks: {}", e))
+    }
+}
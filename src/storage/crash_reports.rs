@@ -0,0 +1,181 @@
+// Crash reports: what the browser was doing when it panicked, kept on disk for neon://crash on the next launch
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// URLs of the open tabs as of the last frame, for the report a panic writes
+static OPEN_TABS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // Set while a panic would only take down one tab, so the hook leaves the report to `isolate`
+    static ISOLATED: Cell<bool> = const { Cell::new(false) };
+    // Where the last isolated panic happened, which the panic payload doesn't say
+    static ISOLATED_LOCATION: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// Everything known about one crash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub time: DateTime<Utc>,
+    pub version: String,
+    pub os: String,
+    pub message: String,
+    /// Source file and line that panicked
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    /// URLs of the tabs that were open, which the next launch offers to restore
+    pub tabs: Vec<String>,
+    /// Set once the user has seen the report on neon://crash
+    #[serde(default)]
+    pub reviewed: bool,
+}
+
+impl CrashReport {
+    /// Where crash reports are kept
+    pub fn dir() -> PathBuf {
+        crate::storage::data_dir().join("crashes")
+    }
+
+    fn from_panic(info: &panic::PanicHookInfo) -> Self {
+        Self {
+            time: Utc::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            message: panic_message(info.payload()),
+            location: info.location().map(location_text),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            tabs: OPEN_TABS.lock().map(|tabs| tabs.clone()).unwrap_or_default(),
+            reviewed: false,
+        }
+    }
+
+    /// Write the report into `dir`, named after the time of the crash
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{}.json", self.time.format("%Y%m%d-%H%M%S%.3f")));
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to save crash report: {}", e))?;
+        Ok(path)
+    }
+
+    /// Every report in `dir`, newest first, with the file each was read from
+    pub fn load_all(dir: &Path) -> Vec<(PathBuf, CrashReport)> {
+        let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+        let mut reports: Vec<(PathBuf, CrashReport)> = entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let report = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+                Some((path, report))
+            })
+            .collect();
+        reports.sort_by_key(|(_, report)| std::cmp::Reverse(report.time));
+        reports
+    }
+
+    /// The newest report the user hasn't looked at yet, i.e. the browser crashed since they last did
+    pub fn pending(dir: &Path) -> Option<(PathBuf, CrashReport)> {
+        Self::load_all(dir).into_iter().next().filter(|(_, report)| !report.reviewed)
+    }
+
+    /// Plain text for the report viewer and bug reports
+    pub fn to_text(&self) -> String {
+        format!(
+            "NeonSearch {} on {}\nCrashed at {}\nThread: {}\nPanic: {}\nLocation: {}\n\nOpen tabs:\n{}\n\nBacktrace:\n{}",
+            self.version,
+            self.os,
+            self.time.to_rfc3339(),
+            self.thread.as_deref().unwrap_or("unnamed"),
+            self.message,
+            self.location.as_deref().unwrap_or("unknown"),
+            self.tabs.iter().map(|url| format!("  {}", url)).collect::<Vec<_>>().join("\n"),
+            self.backtrace,
+        )
+    }
+}
+
+// The message a panic was raised with
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+fn location_text(location: &panic::Location) -> String {
+    format!("{}:{}:{}", location.file(), location.line(), location.column())
+}
+
+/// Record the open tabs, which a crash report lists and the next launch can restore
+pub fn record_open_tabs(urls: Vec<String>) {
+    if let Ok(mut tabs) = OPEN_TABS.lock() {
+        *tabs = urls;
+    }
+}
+
+/// Write a crash report for every panic that would bring the browser down, then let the default hook print it
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if ISOLATED.get() {
+            ISOLATED_LOCATION.set(info.location().map(location_text));
+            return;
+        }
+        match CrashReport::from_panic(info).save(&CrashReport::dir()) {
+            Ok(path) => log::error!("Crash report written to {}", path.display()),
+            Err(e) => log::error!("{}", e),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Run `f`, turning a panic inside it into an error with the panic message instead of a crash
+pub fn isolate<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    let was_isolated = ISOLATED.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    ISOLATED.set(was_isolated);
+    result.map_err(|payload| match ISOLATED_LOCATION.take() {
+        Some(location) => format!("{} at {}", panic_message(payload.as_ref()), location),
+        None => panic_message(payload.as_ref()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_round_trip_and_pending() {
+        let dir = std::env::temp_dir().join(format!("neon-crashes-{}", std::process::id()));
+        let report = CrashReport {
+            time: Utc::now(),
+            version: "0.0.0".to_string(),
+            os: "test".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/engine/layout.rs:1:1".to_string()),
+            thread: Some("main".to_string()),
+            backtrace: String::new(),
+            tabs: vec!["https://example.com/".to_string()],
+            reviewed: false,
+        };
+        let path = report.save(&dir).unwrap();
+        assert_eq!(CrashReport::pending(&dir).map(|(_, pending)| pending), Some(report.clone()));
+
+        let reviewed = CrashReport { reviewed: true, ..report };
+        std::fs::write(&path, serde_json::to_string(&reviewed).unwrap()).unwrap();
+        assert_eq!(CrashReport::pending(&dir), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_isolate_catches_panics() {
+        assert_eq!(isolate(|| 1 + 1), Ok(2));
+        assert!(isolate(|| -> u32 { panic!("bad page") }).is_err_and(|message| message.starts_with("bad page")));
+    }
+}
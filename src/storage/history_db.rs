@@ -0,0 +1,374 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// A single page visit
+#[derive(Debug, Clone)]
+pub struct VisitRecord {
+    pub url: String,
+    pub title: String,
+    pub visited_at: DateTime<Utc>,
+}
+
+/// Visits to one URL, aggregated for the history list
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: String,
+    pub last_visit: DateTime<Utc>,
+    pub visit_count: u32,
+}
+
+/// Order of the entries returned by `query_entries`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryOrder {
+    LastVisit,
+    Title,
+    Url,
+    VisitCount,
+}
+
+/// Entries whose URL or latest title contains `search`, last visited within the bounds
+#[derive(Debug, Clone)]
+pub struct HistoryQuery {
+    pub search: String,
+    pub visited_after: Option<DateTime<Utc>>,
+    pub visited_before: Option<DateTime<Utc>>,
+    pub order: HistoryOrder,
+    pub ascending: bool,
+    pub limit: usize,
+}
+
+pub struct HistoryDatabase {
+    conn: Arc<Mutex<Connection>>,
+    // Bumped on every change, so views can tell when what they read is stale
+    revision: AtomicU64,
+}
+
+static SHARED_HISTORY: OnceLock<Option<Arc<HistoryDatabase>>> = OnceLock::new();
+
+impl HistoryDatabase {
+    /// Create a new history database at the specified path
+    pub fn new(db_path: &Path) -> Result<Self> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .context("Failed to create database directory")?;
+        }
+
+        let conn = Connection::open(db_path)
+            .context("Failed to open history database")?;
+
+        let db = Self {
+            conn: Arc::new(Mutex::new(conn)),
+            revision: AtomicU64::new(0),
+        };
+
+        db.initialize_schema()?;
+        Ok(db)
+    }
+
+    /// Process-wide history store shared by the browser and neon://history
+    pub fn shared() -> Option<Arc<HistoryDatabase>> {
+        SHARED_HISTORY
+            .get_or_init(|| {
+                let db_path = crate::storage::data_dir().join("history.db");
+                match HistoryDatabase::new(&db_path) {
+                    Ok(db) => Some(Arc::new(db)),
+                    Err(e) => {
+                        log::error!("Failed to initialize history database: {}", e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+
+    fn initialize_schema(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS visits (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                visited_at TEXT NOT NULL
+            )",
+            [],
+        ).context("Failed to create visits table")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_visits_url ON visits(url)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_visits_visited_at ON visits(visited_at)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    /// Changes whenever a visit is recorded, imported or removed
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    fn changed(&self) {
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a visit to a page
+    pub fn record_visit(&self, url: &str, title: &str, visited_at: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO visits (url, title, visited_at) VALUES (?1, ?2, ?3)",
+            params![url, title, visited_at.to_rfc3339()],
+        ).context("Failed to record visit")?;
+        self.changed();
+
+        Ok(())
+    }
+
+    /// Record visits from another browser in one transaction, skipping any already recorded. Returns how many were new
+    pub fn import_visits(&self, visits: &[VisitRecord]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO visits (url, title, visited_at)
+                 SELECT ?1, ?2, ?3 WHERE NOT EXISTS (SELECT 1 FROM visits WHERE url = ?1 AND visited_at = ?3)"
+            )?;
+            for visit in visits {
+                imported += stmt.execute(params![visit.url, visit.title, visit.visited_at.to_rfc3339()])?;
+            }
+        }
+        tx.commit().context("Failed to import history")?;
+        self.changed();
+
+        Ok(imported)
+    }
+
+    /// Whether `url` was ever visited
+    pub fn is_visited(&self, url: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        let visited = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM visits WHERE url = ?1)",
+            params![url],
+            |row| row.get(0),
+        )?;
+
+        Ok(visited)
+    }
+
+    /// All visits, oldest first
+    pub fn get_visits(&self) -> Result<Vec<VisitRecord>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT url, title, visited_at FROM visits ORDER BY visited_at ASC"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+
+        let mut visits = Vec::new();
+        for (url, title, visited_at) in rows.flatten() {
+            if let Ok(time) = DateTime::parse_from_rfc3339(&visited_at) {
+                visits.push(VisitRecord { url, title, visited_at: time.with_timezone(&Utc) });
+            }
+        }
+
+        Ok(visits)
+    }
+
+    /// One entry per URL with its most recent title and visit count
+    pub fn get_entries(&self) -> Result<Vec<HistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT url,
+                    (SELECT title FROM visits v2 WHERE v2.url = v.url ORDER BY visited_at DESC LIMIT 1),
+                    MAX(visited_at), COUNT(*)
+             FROM visits v GROUP BY url ORDER BY MAX(visited_at) DESC"
+        )?;
+
+        let rows = stmt.query_map([], Self::entry_row)?;
+        Ok(Self::collect_entries(rows))
+    }
+
+    /// Entries matching `query`, grouped, filtered, ordered and limited by the database
+    pub fn query_entries(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let order = match query.order {
+            HistoryOrder::LastVisit => "last_visit",
+            HistoryOrder::Title => "title",
+            HistoryOrder::Url => "url",
+            HistoryOrder::VisitCount => "visit_count",
+        };
+        let direction = if query.ascending { "ASC" } else { "DESC" };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT url,
+                    (SELECT title FROM visits v2 WHERE v2.url = v.url ORDER BY visited_at DESC LIMIT 1) AS title,
+                    MAX(visited_at) AS last_visit, COUNT(*) AS visit_count
+             FROM visits v GROUP BY url
+             HAVING (?1 = '' OR url LIKE ?1 ESCAPE '\\' OR title LIKE ?1 ESCAPE '\\')
+                AND (?2 IS NULL OR last_visit >= ?2) AND (?3 IS NULL OR last_visit < ?3)
+             ORDER BY {} {}, last_visit DESC LIMIT ?4",
+            order, direction
+        ))?;
+
+        let search = if query.search.is_empty() {
+            String::new()
+        } else {
+            let escaped = query.search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            format!("%{}%", escaped)
+        };
+        let rows = stmt.query_map(
+            params![
+                search,
+                query.visited_after.map(|time| time.to_rfc3339()),
+                query.visited_before.map(|time| time.to_rfc3339()),
+                query.limit as i64,
+            ],
+            Self::entry_row,
+        )?;
+        Ok(Self::collect_entries(rows))
+    }
+
+    /// Whether any visit is recorded
+    pub fn has_visits(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        Ok(conn.query_row("SELECT EXISTS(SELECT 1 FROM visits)", [], |row| row.get(0))?)
+    }
+
+    fn entry_row(row: &rusqlite::Row) -> rusqlite::Result<(String, String, String, u32)> {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    }
+
+    fn collect_entries(rows: impl Iterator<Item = rusqlite::Result<(String, String, String, u32)>>) -> Vec<HistoryEntry> {
+        let mut entries = Vec::new();
+        for (url, title, last_visit, visit_count) in rows.flatten() {
+            if let Ok(time) = DateTime::parse_from_rfc3339(&last_visit) {
+                entries.push(HistoryEntry { url, title, last_visit: time.with_timezone(&Utc), visit_count });
+            }
+        }
+        entries
+    }
+
+    /// Title from the most recent visit to exactly this URL
+    pub fn latest_title(&self, url: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT title FROM visits WHERE url = ?1 ORDER BY visited_at DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query_map(params![url], |row| row.get::<_, String>(0))?;
+
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Remove every visit to a URL
+    pub fn delete_url(&self, url: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute("DELETE FROM visits WHERE url = ?1", params![url])
+            .context("Failed to delete history entry")?;
+        self.changed();
+
+        Ok(())
+    }
+
+    /// Remove every visit
+    pub fn clear(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute("DELETE FROM visits", [])
+            .context("Failed to clear history")?;
+        self.changed();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn create_test_db() -> Result<HistoryDatabase> {
+        let test_path = std::env::temp_dir().join(format!("test_history_{}.db", Uuid::new_v4()));
+        HistoryDatabase::new(&test_path)
+    }
+
+    #[test]
+    fn test_entries_aggregate_visits() -> Result<()> {
+        let db = create_test_db()?;
+        let now = Utc::now();
+
+        db.record_visit("https://example.com", "Old title", now - chrono::Duration::hours(2))?;
+        db.record_visit("https://example.com", "Example", now)?;
+        db.record_visit("https://rust-lang.org", "Rust", now - chrono::Duration::hours(1))?;
+
+        let entries = db.get_entries()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, "https://example.com");
+        assert_eq!(entries[0].title, "Example");
+        assert_eq!(entries[0].visit_count, 2);
+
+        assert_eq!(db.latest_title("https://rust-lang.org")?.as_deref(), Some("Rust"));
+        assert_eq!(db.latest_title("https://unknown.org")?, None);
+        assert!(db.is_visited("https://rust-lang.org")?);
+        assert!(!db.is_visited("https://unknown.org")?);
+
+        db.delete_url("https://example.com")?;
+        assert_eq!(db.get_visits()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_entries_filters_in_sql() -> Result<()> {
+        let db = create_test_db()?;
+        let now = Utc::now();
+        let revision = db.revision();
+
+        db.record_visit("https://example.com/a", "Alpha", now - chrono::Duration::days(3))?;
+        db.record_visit("https://example.com/b", "100% Beta", now)?;
+        db.record_visit("https://example.com/b", "100% Beta", now - chrono::Duration::hours(1))?;
+        db.record_visit("https://rust-lang.org", "Rust", now - chrono::Duration::hours(2))?;
+        assert!(db.revision() > revision);
+
+        let mut query = HistoryQuery {
+            search: "EXAMPLE".to_string(),
+            visited_after: None,
+            visited_before: None,
+            order: HistoryOrder::VisitCount,
+            ascending: false,
+            limit: 10,
+        };
+        let urls = |entries: Vec<HistoryEntry>| entries.into_iter().map(|entry| entry.url).collect::<Vec<_>>();
+        assert_eq!(urls(db.query_entries(&query)?), ["https://example.com/b", "https://example.com/a"]);
+
+        // Wildcards in the search are matched literally
+        query.search = "0%".to_string();
+        assert_eq!(urls(db.query_entries(&query)?), ["https://example.com/b"]);
+
+        query.search.clear();
+        query.visited_after = Some(now - chrono::Duration::days(1));
+        query.order = HistoryOrder::Title;
+        query.ascending = true;
+        assert_eq!(urls(db.query_entries(&query)?), ["https://example.com/b", "https://rust-lang.org"]);
+        query.limit = 1;
+        assert_eq!(db.query_entries(&query)?.len(), 1);
+        Ok(())
+    }
+}
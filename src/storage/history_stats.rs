@@ -0,0 +1,123 @@
+// Browsing statistics derived locally from recorded visits
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use crate::storage::history_db::VisitRecord;
+
+// Visits further apart than this start a new browsing session
+const SESSION_GAP: i64 = 30 * 60;
+// Assumed time spent on the last page of a session
+const DEFAULT_DWELL: i64 = 60;
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryStats {
+    pub total_visits: usize,
+    pub visits_per_day: BTreeMap<NaiveDate, u32>,
+    pub visits_per_hour: [u32; 24],
+    pub visits_per_weekday: [u32; 7], // Monday first
+    pub top_domains: Vec<(String, u32)>,
+    pub unique_domains: usize,
+    pub estimated_time: Duration,
+    pub sessions: usize,
+}
+
+impl HistoryStats {
+    /// Compute statistics with days and hours bucketed in the given time zone
+    pub fn compute<Tz: TimeZone>(visits: &[VisitRecord], tz: &Tz) -> Self {
+        let mut stats = HistoryStats { total_visits: visits.len(), ..Default::default() };
+        let mut domains: HashMap<String, u32> = HashMap::new();
+
+        let mut times: Vec<DateTime<Tz>> = Vec::with_capacity(visits.len());
+
+        for visit in visits {
+            let time = visit.visited_at.with_timezone(tz);
+            *stats.visits_per_day.entry(time.date_naive()).or_insert(0) += 1;
+            stats.visits_per_hour[time.hour() as usize] += 1;
+            stats.visits_per_weekday[time.weekday().num_days_from_monday() as usize] += 1;
+            if let Some(domain) = domain_of(&visit.url) {
+                *domains.entry(domain).or_insert(0) += 1;
+            }
+            times.push(time);
+        }
+        times.sort();
+
+        // Time between consecutive visits counts as browsing unless the gap ends a session
+        let mut seconds = 0;
+        for pair in times.windows(2) {
+            let gap = (pair[1].clone() - pair[0].clone()).num_seconds();
+            if gap > SESSION_GAP {
+                seconds += DEFAULT_DWELL;
+                stats.sessions += 1;
+            } else {
+                seconds += gap;
+            }
+        }
+        if !times.is_empty() {
+            seconds += DEFAULT_DWELL;
+            stats.sessions += 1;
+        }
+        stats.estimated_time = Duration::from_secs(seconds.max(0) as u64);
+
+        stats.unique_domains = domains.len();
+        let mut top: Vec<(String, u32)> = domains.into_iter().collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(10);
+        stats.top_domains = top;
+
+        stats
+    }
+
+    pub fn busiest_hour(&self) -> Option<usize> {
+        let (hour, count) = self.visits_per_hour.iter().enumerate().max_by_key(|(_, c)| **c)?;
+        (*count > 0).then_some(hour)
+    }
+
+    pub fn max_daily_visits(&self) -> u32 {
+        self.visits_per_day.values().copied().max().unwrap_or(0)
+    }
+}
+
+fn domain_of(url: &str) -> Option<String> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn visit(url: &str, time: &str) -> VisitRecord {
+        VisitRecord {
+            url: url.to_string(),
+            title: String::new(),
+            visited_at: DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let visits = vec![
+            visit("https://www.github.com/a", "2026-03-02T09:00:00Z"),
+            visit("https://github.com/b", "2026-03-02T09:10:00Z"),
+            visit("https://docs.rs", "2026-03-03T21:00:00Z"),
+        ];
+        let stats = HistoryStats::compute(&visits, &Utc);
+
+        assert_eq!(stats.total_visits, 3);
+        assert_eq!(stats.visits_per_day.len(), 2);
+        assert_eq!(stats.top_domains[0], ("github.com".to_string(), 2));
+        assert_eq!(stats.busiest_hour(), Some(9));
+        assert_eq!(stats.visits_per_weekday[0], 2); // 2026-03-02 is a Monday
+        assert_eq!(stats.sessions, 2);
+        // 10 minutes between the first two visits plus one dwell per session
+        assert_eq!(stats.estimated_time, Duration::from_secs(600 + 2 * 60));
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let stats = HistoryStats::compute(&[], &Utc);
+        assert_eq!(stats.busiest_hour(), None);
+        assert_eq!(stats.estimated_time, Duration::ZERO);
+    }
+}
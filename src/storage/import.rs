@@ -0,0 +1,387 @@
+// Reads history, bookmarks and cookies out of Chrome and Firefox profiles on this machine
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::networking::cookie_manager::Cookie;
+use super::{StoredBookmark, VisitRecord};
+
+// Chrome counts microseconds from 1601-01-01, this many before the Unix epoch
+const CHROME_EPOCH_OFFSET_US: i64 = 11_644_473_600_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Firefox,
+}
+
+impl Browser {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Browser::Chrome => "Google Chrome",
+            Browser::Chromium => "Chromium",
+            Browser::Firefox => "Firefox",
+        }
+    }
+
+    fn is_chromium_based(&self) -> bool {
+        matches!(self, Browser::Chrome | Browser::Chromium)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserProfile {
+    pub browser: Browser,
+    /// Profile directory name, such as `Default` or `abcd1234.default-release`
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl BrowserProfile {
+    pub fn label(&self) -> String {
+        format!("{} — {}", self.browser.name(), self.name)
+    }
+}
+
+/// What was read from a profile, ready to hand to NeonSearch's stores
+#[derive(Debug, Default)]
+pub struct ImportData {
+    pub history: Vec<VisitRecord>,
+    pub bookmarks: Vec<StoredBookmark>,
+    /// Cookies with the host they belong to
+    pub cookies: Vec<(String, Cookie)>,
+    /// Cookies that couldn't be read, because the browser encrypts them with a key only it holds
+    pub encrypted_cookies: usize,
+}
+
+/// Profiles of the browsers installed in their usual places
+pub fn find_profiles() -> Vec<BrowserProfile> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let config = dirs::config_dir().unwrap_or_default();
+    let local = dirs::data_local_dir().unwrap_or_default();
+
+    let chrome_roots = [
+        (Browser::Chrome, config.join("google-chrome")),
+        (Browser::Chrome, config.join("Google/Chrome")),
+        (Browser::Chrome, local.join("Google/Chrome/User Data")),
+        (Browser::Chromium, config.join("chromium")),
+        (Browser::Chromium, config.join("Chromium")),
+        (Browser::Chromium, local.join("Chromium/User Data")),
+    ];
+    let firefox_roots = [
+        home.join(".mozilla/firefox"),
+        config.join("Firefox/Profiles"),
+        config.join("Mozilla/Firefox/Profiles"),
+    ];
+
+    let mut profiles = Vec::new();
+    for (browser, root) in chrome_roots {
+        profiles.extend(profiles_in(&root, browser, "History"));
+    }
+    for root in firefox_roots {
+        profiles.extend(profiles_in(&root, Browser::Firefox, "places.sqlite"));
+    }
+    profiles.dedup_by(|a, b| a.path == b.path);
+    profiles
+}
+
+// Subdirectories of `root` that hold `marker`, the file every profile of the browser has
+fn profiles_in(root: &Path, browser: Browser, marker: &str) -> Vec<BrowserProfile> {
+    let mut profiles: Vec<BrowserProfile> = std::fs::read_dir(root)
+        .map(|entries| entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.join(marker).is_file())
+            .map(|path| BrowserProfile {
+                browser,
+                name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+                path,
+            })
+            .collect())
+        .unwrap_or_default();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// Read a profile's history and bookmarks, and its cookies when `cookies` is set
+pub fn read_profile(profile: &BrowserProfile, cookies: bool) -> Result<ImportData> {
+    let mut data = ImportData::default();
+    if profile.browser.is_chromium_based() {
+        data.history = chrome_history(&profile.path.join("History"))?;
+        let bookmarks = profile.path.join("Bookmarks");
+        if bookmarks.is_file() {
+            data.bookmarks = chrome_bookmarks(&bookmarks)?;
+        }
+        if cookies {
+            // Newer versions keep the cookie database under Network/
+            let path = [profile.path.join("Network/Cookies"), profile.path.join("Cookies")].into_iter().find(|path| path.is_file());
+            if let Some(path) = path {
+                (data.cookies, data.encrypted_cookies) = chrome_cookies(&path)?;
+            }
+        }
+    } else {
+        let places = profile.path.join("places.sqlite");
+        data.history = firefox_history(&places)?;
+        data.bookmarks = firefox_bookmarks(&places)?;
+        let path = profile.path.join("cookies.sqlite");
+        if cookies && path.is_file() {
+            data.cookies = firefox_cookies(&path)?;
+        }
+    }
+    Ok(data)
+}
+
+// The browser may be running and holding a lock on its databases, so they are read from a copy
+fn open_copy(path: &Path) -> Result<(Connection, PathBuf)> {
+    let copy = std::env::temp_dir().join(format!(
+        "neon-import-{}-{}",
+        std::process::id(),
+        path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+    ));
+    std::fs::copy(path, &copy).with_context(|| format!("Failed to read {}", path.display()))?;
+    let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("{} isn't a database this importer can read", path.display()))?;
+    Ok((conn, copy))
+}
+
+fn query<T>(path: &Path, sql: &str, row: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>) -> Result<Vec<T>> {
+    let (conn, copy) = open_copy(path)?;
+    let rows = conn.prepare(sql)
+        .and_then(|mut stmt| stmt.query_map([], row)?.collect::<rusqlite::Result<Vec<T>>>())
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e));
+    drop(conn);
+    let _ = std::fs::remove_file(copy);
+    rows
+}
+
+fn from_unix_us(us: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_micros(us).filter(|_| us > 0)
+}
+
+fn from_chrome_us(us: i64) -> Option<DateTime<Utc>> {
+    from_unix_us(us - CHROME_EPOCH_OFFSET_US).filter(|_| us > 0)
+}
+
+fn importable(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn chrome_history(path: &Path) -> Result<Vec<VisitRecord>> {
+    let rows = query(path, "SELECT u.url, u.title, v.visit_time FROM visits v JOIN urls u ON u.id = v.url", |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?))
+    })?;
+    Ok(rows.into_iter()
+        .filter(|(url, _, _)| importable(url))
+        .filter_map(|(url, title, time)| Some(VisitRecord { url, title: title.unwrap_or_default(), visited_at: from_chrome_us(time)? }))
+        .collect())
+}
+
+fn chrome_bookmarks(path: &Path) -> Result<Vec<StoredBookmark>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_str(&content).context("The Bookmarks file isn't valid JSON")?;
+    let mut bookmarks = Vec::new();
+    if let Some(roots) = json.get("roots").and_then(|roots| roots.as_object()) {
+        for root in roots.values() {
+            collect_chrome_bookmarks(root, &mut Vec::new(), &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+fn collect_chrome_bookmarks(node: &serde_json::Value, folder: &mut Vec<String>, out: &mut Vec<StoredBookmark>) {
+    let name = node.get("name").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+    match node.get("type").and_then(|kind| kind.as_str()) {
+        Some("url") => {
+            let url = node.get("url").and_then(|url| url.as_str()).unwrap_or_default();
+            if importable(url) {
+                let added_at = node.get("date_added")
+                    .and_then(|date| date.as_str())
+                    .and_then(|date| date.parse().ok())
+                    .and_then(from_chrome_us)
+                    .unwrap_or_else(Utc::now);
+                out.push(StoredBookmark { title: name, url: url.to_string(), folder: folder.clone(), added_at });
+            }
+        }
+        Some("folder") => {
+            folder.push(name);
+            for child in node.get("children").and_then(|children| children.as_array()).into_iter().flatten() {
+                collect_chrome_bookmarks(child, folder, out);
+            }
+            folder.pop();
+        }
+        _ => {}
+    }
+}
+
+fn chrome_cookies(path: &Path) -> Result<(Vec<(String, Cookie)>, usize)> {
+    let rows = query(path, "SELECT host_key, name, value, path, expires_utc, is_secure, is_httponly, samesite FROM cookies", |row| {
+        let expires: i64 = row.get(4)?;
+        let same_site: i64 = row.get(7)?;
+        Ok((row.get::<_, String>(0)?, Cookie {
+            name: row.get(1)?,
+            value: row.get(2)?,
+            domain: None,
+            path: row.get(3)?,
+            expires: from_chrome_us(expires),
+            max_age: None,
+            secure: row.get(5)?,
+            http_only: row.get(6)?,
+            same_site: same_site_name(same_site),
+        }))
+    })?;
+    // Chrome encrypts cookie values and leaves `value` empty
+    let (readable, encrypted): (Vec<_>, Vec<_>) = rows.into_iter().partition(|(_, cookie)| !cookie.value.is_empty());
+    Ok((readable.into_iter().map(|(host, cookie)| jar_entry(&host, cookie)).collect(), encrypted.len()))
+}
+
+fn firefox_history(path: &Path) -> Result<Vec<VisitRecord>> {
+    let rows = query(path, "SELECT p.url, p.title, v.visit_date FROM moz_historyvisits v JOIN moz_places p ON p.id = v.place_id", |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?))
+    })?;
+    Ok(rows.into_iter()
+        .filter(|(url, _, _)| importable(url))
+        .filter_map(|(url, title, time)| Some(VisitRecord { url, title: title.unwrap_or_default(), visited_at: from_unix_us(time)? }))
+        .collect())
+}
+
+fn firefox_bookmarks(path: &Path) -> Result<Vec<StoredBookmark>> {
+    // type 1 is a bookmark and type 2 a folder; the places root (id 1) holds the menu, toolbar and other folders
+    let rows = query(path, "SELECT b.id, b.type, b.parent, b.title, b.dateAdded, p.url FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk", |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    })?;
+    let folders: HashMap<i64, (i64, String)> = rows.iter()
+        .filter(|(_, kind, ..)| *kind == 2)
+        .map(|(id, _, parent, title, ..)| (*id, (*parent, firefox_folder_name(title.as_deref().unwrap_or_default()))))
+        .collect();
+    let folder_path = |mut id: i64| {
+        let mut path = Vec::new();
+        while let Some((parent, name)) = folders.get(&id).filter(|_| id != 1) {
+            path.insert(0, name.clone());
+            id = *parent;
+        }
+        path
+    };
+    Ok(rows.iter()
+        .filter(|(_, kind, ..)| *kind == 1)
+        .filter_map(|(_, _, parent, title, added, url)| {
+            let url = url.as_ref().filter(|url| importable(url))?;
+            Some(StoredBookmark {
+                title: title.clone().unwrap_or_else(|| url.clone()),
+                url: url.clone(),
+                folder: folder_path(*parent),
+                added_at: added.and_then(from_unix_us).unwrap_or_else(Utc::now),
+            })
+        })
+        .collect())
+}
+
+// Firefox names its built-in folders by their internal ids
+fn firefox_folder_name(title: &str) -> String {
+    match title {
+        "menu" => "Bookmarks Menu",
+        "toolbar" => "Bookmarks Toolbar",
+        "unfiled" => "Other Bookmarks",
+        "mobile" => "Mobile Bookmarks",
+        title => title,
+    }.to_string()
+}
+
+fn firefox_cookies(path: &Path) -> Result<Vec<(String, Cookie)>> {
+    let rows = query(path, "SELECT host, name, value, path, expiry, isSecure, isHttpOnly, sameSite FROM moz_cookies", |row| {
+        let expiry: i64 = row.get(4)?;
+        let same_site: i64 = row.get(7)?;
+        Ok((row.get::<_, String>(0)?, Cookie {
+            name: row.get(1)?,
+            value: row.get(2)?,
+            domain: None,
+            path: row.get(3)?,
+            // Seconds in older versions, milliseconds in newer ones
+            expires: if expiry > 100_000_000_000 { DateTime::from_timestamp_millis(expiry) } else { DateTime::from_timestamp(expiry, 0) },
+            max_age: None,
+            secure: row.get(5)?,
+            http_only: row.get(6)?,
+            same_site: same_site_name(same_site),
+        }))
+    })?;
+    Ok(rows.into_iter().map(|(host, cookie)| jar_entry(&host, cookie)).collect())
+}
+
+fn same_site_name(value: i64) -> Option<String> {
+    match value {
+        0 => Some("None".to_string()),
+        1 => Some("Lax".to_string()),
+        2 => Some("Strict".to_string()),
+        _ => None,
+    }
+}
+
+// A leading dot marks a cookie shared with subdomains, which the jar keeps as a Domain attribute
+fn jar_entry(host: &str, mut cookie: Cookie) -> (String, Cookie) {
+    let bare = host.trim_start_matches('.').to_string();
+    if host.starts_with('.') {
+        cookie.domain = Some(bare.clone());
+    }
+    (bare, cookie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_firefox_profile() {
+        let dir = std::env::temp_dir().join(format!("neon-import-firefox-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let places = Connection::open(dir.join("places.sqlite")).unwrap();
+        places.execute_batch("
+            CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+            CREATE TABLE moz_historyvisits (id INTEGER PRIMARY KEY, place_id INTEGER, visit_date INTEGER);
+            CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER, title TEXT, dateAdded INTEGER);
+            INSERT INTO moz_places VALUES (1, 'https://www.rust-lang.org/', 'Rust'), (2, 'place:sort=8', NULL);
+            INSERT INTO moz_historyvisits VALUES (1, 1, 1700000000000000), (2, 2, 1700000000000000);
+            INSERT INTO moz_bookmarks VALUES (1, 2, NULL, 0, '', NULL), (2, 2, NULL, 1, 'toolbar', NULL),
+                (3, 2, NULL, 2, 'Code', NULL), (4, 1, 1, 3, 'Rust lang', 1700000000000000);
+        ").unwrap();
+        drop(places);
+        let cookies = Connection::open(dir.join("cookies.sqlite")).unwrap();
+        cookies.execute_batch("
+            CREATE TABLE moz_cookies (host TEXT, name TEXT, value TEXT, path TEXT, expiry INTEGER, isSecure INTEGER, isHttpOnly INTEGER, sameSite INTEGER);
+            INSERT INTO moz_cookies VALUES ('.example.com', 'id', 'abc', '/', 4102444800, 1, 0, 1);
+        ").unwrap();
+        drop(cookies);
+
+        let profile = BrowserProfile { browser: Browser::Firefox, name: "test".to_string(), path: dir.clone() };
+        let data = read_profile(&profile, true).unwrap();
+        assert_eq!(data.history.len(), 1);
+        assert_eq!(data.history[0].visited_at.timestamp(), 1_700_000_000);
+        assert_eq!(data.bookmarks[0].title, "Rust lang");
+        assert_eq!(data.bookmarks[0].folder, ["Bookmarks Toolbar", "Code"]);
+        let (host, cookie) = &data.cookies[0];
+        assert_eq!((host.as_str(), cookie.domain.as_deref(), cookie.same_site.as_deref()), ("example.com", Some("example.com"), Some("Lax")));
+        assert!(read_profile(&profile, false).unwrap().cookies.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_chrome_bookmarks_and_timestamps() {
+        let dir = std::env::temp_dir().join(format!("neon-import-chrome-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Bookmarks"), r#"{"roots": {"bookmark_bar": {"type": "folder", "name": "Bookmarks bar", "children": [
+            {"type": "url", "name": "Crates", "url": "https://crates.io/", "date_added": "13345000000000000"},
+            {"type": "url", "name": "Settings", "url": "chrome://settings/"}
+        ]}}}"#).unwrap();
+        let bookmarks = chrome_bookmarks(&dir.join("Bookmarks")).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].folder, ["Bookmarks bar"]);
+        assert_eq!(bookmarks[0].added_at.timestamp(), 13_345_000_000 - 11_644_473_600);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
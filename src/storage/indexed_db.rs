@@ -0,0 +1,747 @@
+// Origin-scoped structured storage for page scripts: object stores, indexes and cursors over SQLite
+use anyhow::{anyhow, bail, Context, Result};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Bytes of keys and values one origin may store across all of its databases
+pub const DEFAULT_ORIGIN_QUOTA: u64 = 10 * 1024 * 1024;
+
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// A record or index key. Numbers sort before strings, as in IndexedDB.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdbKey {
+    Number(f64),
+    String(String),
+}
+
+impl IdbKey {
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => n.as_f64().filter(|n| !n.is_nan()).map(IdbKey::Number),
+            Value::String(s) => Some(IdbKey::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            // Whole numbers come back as integers, the way JSON.stringify would print them
+            IdbKey::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => Value::from(*n as i64),
+            IdbKey::Number(n) => serde_json::Number::from_f64(*n).map(Value::Number).unwrap_or(Value::Null),
+            IdbKey::String(s) => Value::String(s.clone()),
+        }
+    }
+
+    // Text whose byte order matches key order, so SQLite can range-scan and sort on it directly
+    fn encode(&self) -> String {
+        match self {
+            IdbKey::Number(n) => {
+                let bits = n.to_bits();
+                let ordered = if bits >> 63 == 1 { !bits } else { bits | 1 << 63 };
+                format!("n{:016x}", ordered)
+            }
+            IdbKey::String(s) => format!("s{}", s),
+        }
+    }
+
+    fn decode(encoded: &str) -> Option<Self> {
+        match encoded.split_at_checked(1)? {
+            ("n", hex) => {
+                let ordered = u64::from_str_radix(hex, 16).ok()?;
+                let bits = if ordered >> 63 == 1 { ordered & !(1 << 63) } else { !ordered };
+                Some(IdbKey::Number(f64::from_bits(bits)))
+            }
+            ("s", s) => Some(IdbKey::String(s.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Bounds for a cursor or query; either side may be missing or exclusive
+#[derive(Debug, Clone, Default)]
+pub struct KeyRange {
+    pub lower: Option<IdbKey>,
+    pub upper: Option<IdbKey>,
+    pub lower_open: bool,
+    pub upper_open: bool,
+}
+
+impl KeyRange {
+    pub fn only(key: IdbKey) -> Self {
+        Self { lower: Some(key.clone()), upper: Some(key), ..Self::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorDirection {
+    #[default]
+    Next,
+    Prev,
+}
+
+/// How an object store finds the key of a record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreOptions {
+    /// Dotted path of the key inside each value; out-of-line keys when None
+    pub key_path: Option<String>,
+    pub auto_increment: bool,
+}
+
+/// A database, object store and optional index, all belonging to one origin
+#[derive(Debug, Clone)]
+pub struct StoreScope {
+    pub origin: String,
+    pub database: String,
+    pub store: String,
+}
+
+/// Storage used by one origin, for quotas and the Clear Browsing Data dialog
+#[derive(Debug, Clone, PartialEq)]
+pub struct OriginUsage {
+    pub origin: String,
+    pub bytes: u64,
+}
+
+pub struct IndexedDatabase {
+    conn: Arc<Mutex<Connection>>,
+    quota: u64,
+}
+
+static SHARED_INDEXED_DB: OnceLock<Option<Arc<IndexedDatabase>>> = OnceLock::new();
+
+impl IndexedDatabase {
+    /// Open or create the store at `db_path`, allowing each origin `quota` bytes
+    pub fn new(db_path: &Path, quota: u64) -> Result<Self> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)
+                .context("Failed to create database directory")?;
+        }
+
+        let conn = Connection::open(db_path)
+            .context("Failed to open IndexedDB database")?;
+
+        let db = Self {
+            conn: Arc::new(Mutex::new(conn)),
+            quota,
+        };
+
+        db.initialize_schema()?;
+        Ok(db)
+    }
+
+    /// Process-wide store shared by every tab
+    pub fn shared() -> Option<Arc<IndexedDatabase>> {
+        SHARED_INDEXED_DB
+            .get_or_init(|| {
+                let db_path = crate::storage::data_dir().join("indexeddb.db");
+                match IndexedDatabase::new(&db_path, DEFAULT_ORIGIN_QUOTA) {
+                    Ok(db) => Some(Arc::new(db)),
+                    Err(e) => {
+                        log::error!("Failed to initialize IndexedDB database: {}", e);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+
+    fn initialize_schema(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS object_stores (
+                origin TEXT NOT NULL,
+                db_name TEXT NOT NULL,
+                name TEXT NOT NULL,
+                key_path TEXT,
+                auto_increment INTEGER NOT NULL,
+                next_key INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (origin, db_name, name)
+            );
+            CREATE TABLE IF NOT EXISTS store_indexes (
+                origin TEXT NOT NULL,
+                db_name TEXT NOT NULL,
+                store TEXT NOT NULL,
+                name TEXT NOT NULL,
+                key_path TEXT NOT NULL,
+                is_unique INTEGER NOT NULL,
+                PRIMARY KEY (origin, db_name, store, name)
+            );
+            CREATE TABLE IF NOT EXISTS records (
+                origin TEXT NOT NULL,
+                db_name TEXT NOT NULL,
+                store TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                PRIMARY KEY (origin, db_name, store, key)
+            );
+            CREATE TABLE IF NOT EXISTS index_entries (
+                origin TEXT NOT NULL,
+                db_name TEXT NOT NULL,
+                store TEXT NOT NULL,
+                idx TEXT NOT NULL,
+                key TEXT NOT NULL,
+                primary_key TEXT NOT NULL,
+                PRIMARY KEY (origin, db_name, store, idx, key, primary_key)
+            );
+            CREATE INDEX IF NOT EXISTS idx_records_origin ON records(origin);",
+        ).context("Failed to create IndexedDB tables")?;
+
+        Ok(())
+    }
+
+    /// Create an object store, creating its database on first use
+    pub fn create_object_store(&self, scope: &StoreScope, options: &StoreOptions) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO object_stores (origin, db_name, name, key_path, auto_increment)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![scope.origin, scope.database, scope.store, options.key_path, options.auto_increment],
+        )?;
+        if inserted == 0 {
+            bail!("ConstraintError: object store '{}' already exists", scope.store);
+        }
+        Ok(())
+    }
+
+    /// Remove an object store with all of its records and indexes
+    pub fn delete_object_store(&self, scope: &StoreScope) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        store_options(&tx, scope)?;
+        for table in ["records", "index_entries", "store_indexes"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE origin = ?1 AND db_name = ?2 AND store = ?3", table),
+                params![scope.origin, scope.database, scope.store],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM object_stores WHERE origin = ?1 AND db_name = ?2 AND name = ?3",
+            params![scope.origin, scope.database, scope.store],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Names of the origin's databases
+    pub fn database_names(&self, origin: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT db_name FROM object_stores WHERE origin = ?1 ORDER BY db_name"
+        )?;
+        let names = stmt.query_map(params![origin], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(names)
+    }
+
+    /// Names of the object stores in one database
+    pub fn object_store_names(&self, origin: &str, database: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name FROM object_stores WHERE origin = ?1 AND db_name = ?2 ORDER BY name"
+        )?;
+        let names = stmt.query_map(params![origin, database], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(names)
+    }
+
+    /// Delete a whole database
+    pub fn delete_database(&self, origin: &str, database: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for table in ["records", "index_entries", "store_indexes", "object_stores"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE origin = ?1 AND db_name = ?2", table),
+                params![origin, database],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Add an index over `key_path` of every value, indexing records already in the store
+    pub fn create_index(&self, scope: &StoreScope, name: &str, key_path: &str, unique: bool) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        store_options(&tx, scope)?;
+
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO store_indexes (origin, db_name, store, name, key_path, is_unique)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![scope.origin, scope.database, scope.store, name, key_path, unique],
+        )?;
+        if inserted == 0 {
+            bail!("ConstraintError: index '{}' already exists", name);
+        }
+
+        let records: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT key, value FROM records WHERE origin = ?1 AND db_name = ?2 AND store = ?3"
+            )?;
+            let rows = stmt.query_map(params![scope.origin, scope.database, scope.store], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let index = IndexDefinition { name: name.to_string(), key_path: key_path.to_string(), unique };
+        for (primary, value) in records {
+            let value: Value = serde_json::from_str(&value)?;
+            index_record(&tx, scope, &index, &primary, &value)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Insert or replace a record, returning its key. `key` is only allowed for stores without a key path.
+    pub fn put(&self, scope: &StoreScope, key: Option<IdbKey>, mut value: Value) -> Result<IdbKey> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let (options, next_key) = store_options(&tx, scope)?;
+
+        let key = match (&options.key_path, key) {
+            (Some(_), Some(_)) => bail!("DataError: the object store uses in-line keys, so no key may be given"),
+            (Some(path), None) => match lookup_path(&value, path) {
+                Some(found) => IdbKey::from_json(found)
+                    .ok_or_else(|| anyhow!("DataError: the value at key path '{}' is not a valid key", path))?,
+                None if options.auto_increment => {
+                    let key = IdbKey::Number(next_key as f64);
+                    inject_path(&mut value, path, key.to_json())?;
+                    key
+                }
+                None => bail!("DataError: the value has no key at key path '{}'", path),
+            },
+            (None, Some(key)) => key,
+            (None, None) if options.auto_increment => IdbKey::Number(next_key as f64),
+            (None, None) => bail!("DataError: the object store needs an explicit key"),
+        };
+
+        // Explicit numeric keys move the generator past them, like IndexedDB's key generator
+        if let IdbKey::Number(n) = key {
+            if options.auto_increment && n >= next_key as f64 {
+                tx.execute(
+                    "UPDATE object_stores SET next_key = ?4 WHERE origin = ?1 AND db_name = ?2 AND name = ?3",
+                    params![scope.origin, scope.database, scope.store, (n.floor() as i64).saturating_add(1)],
+                )?;
+            }
+        }
+
+        let encoded = key.encode();
+        let serialized = serde_json::to_string(&value)?;
+        let size = (encoded.len() + serialized.len()) as u64;
+
+        let replaced: u64 = tx.query_row(
+            "SELECT COALESCE(SUM(size), 0) FROM records WHERE origin = ?1 AND db_name = ?2 AND store = ?3 AND key = ?4",
+            params![scope.origin, scope.database, scope.store, encoded],
+            |row| row.get(0),
+        )?;
+        let used = origin_usage(&tx, &scope.origin)?;
+        if used - replaced + size > self.quota {
+            bail!("QuotaExceededError: {} would use {} of its {} bytes", scope.origin, used - replaced + size, self.quota);
+        }
+
+        tx.execute(
+            "DELETE FROM index_entries WHERE origin = ?1 AND db_name = ?2 AND store = ?3 AND primary_key = ?4",
+            params![scope.origin, scope.database, scope.store, encoded],
+        )?;
+        for index in store_indexes(&tx, scope)? {
+            index_record(&tx, scope, &index, &encoded, &value)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO records (origin, db_name, store, key, value, size) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![scope.origin, scope.database, scope.store, encoded, serialized, size],
+        )?;
+        tx.commit()?;
+        Ok(key)
+    }
+
+    /// The record stored under `key`
+    pub fn get(&self, scope: &StoreScope, key: &IdbKey) -> Result<Option<Value>> {
+        let conn = self.conn.lock().unwrap();
+        store_options(&conn, scope)?;
+        let value: Option<String> = conn.query_row(
+            "SELECT value FROM records WHERE origin = ?1 AND db_name = ?2 AND store = ?3 AND key = ?4",
+            params![scope.origin, scope.database, scope.store, key.encode()],
+            |row| row.get(0),
+        ).optional()?;
+        value.map(|v| serde_json::from_str(&v).map_err(Into::into)).transpose()
+    }
+
+    /// Remove every record in `range`
+    pub fn delete(&self, scope: &StoreScope, range: &KeyRange) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        store_options(&tx, scope)?;
+
+        let (conditions, mut values) = range_conditions("key", range);
+        let mut bound = vec![scope.origin.clone(), scope.database.clone(), scope.store.clone()];
+        bound.append(&mut values);
+        let filter = format!("origin = ? AND db_name = ? AND store = ?{}", conditions);
+
+        tx.execute(
+            &format!(
+                "DELETE FROM index_entries WHERE origin = ? AND db_name = ? AND store = ? AND primary_key IN
+                 (SELECT key FROM records WHERE {})",
+                filter
+            ),
+            params_from_iter(scope_params(scope).into_iter().chain(bound.iter().cloned())),
+        )?;
+        let deleted = tx.execute(&format!("DELETE FROM records WHERE {}", filter), params_from_iter(bound.iter()))?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Remove every record in the store, keeping the store and its indexes
+    pub fn clear(&self, scope: &StoreScope) -> Result<()> {
+        self.delete(scope, &KeyRange::default()).map(|_| ())
+    }
+
+    /// Records in `range`, counted over the store or one of its indexes
+    pub fn count(&self, scope: &StoreScope, index: Option<&str>, range: &KeyRange) -> Result<usize> {
+        let mut cursor = self.open_cursor(scope, index, range.clone(), CursorDirection::Next)?;
+        let mut count = 0;
+        while cursor.advance()?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Walk the store, or one of its indexes, in key order
+    pub fn open_cursor(
+        &self,
+        scope: &StoreScope,
+        index: Option<&str>,
+        range: KeyRange,
+        direction: CursorDirection,
+    ) -> Result<Cursor<'_>> {
+        {
+            let conn = self.conn.lock().unwrap();
+            store_options(&conn, scope)?;
+            if let Some(index) = index {
+                if !store_indexes(&conn, scope)?.iter().any(|i| i.name == index) {
+                    bail!("NotFoundError: no index named '{}'", index);
+                }
+            }
+        }
+        Ok(Cursor {
+            db: self,
+            scope: scope.clone(),
+            index: index.map(str::to_string),
+            range,
+            direction,
+            position: None,
+        })
+    }
+
+    /// Bytes stored by one origin
+    pub fn usage(&self, origin: &str) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        origin_usage(&conn, origin)
+    }
+
+    pub fn quota(&self) -> u64 {
+        self.quota
+    }
+
+    /// Every origin with stored data, largest first
+    pub fn usage_by_origin(&self) -> Result<Vec<OriginUsage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT origin, SUM(size) FROM records GROUP BY origin
+             UNION SELECT origin, 0 FROM object_stores WHERE origin NOT IN (SELECT origin FROM records)
+             ORDER BY 2 DESC, 1"
+        )?;
+        let rows = stmt.query_map([], |row| Ok(OriginUsage { origin: row.get(0)?, bytes: row.get(1)? }))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Remove all databases of one origin
+    pub fn clear_origin(&self, origin: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for table in ["records", "index_entries", "store_indexes", "object_stores"] {
+            tx.execute(&format!("DELETE FROM {} WHERE origin = ?1", table), params![origin])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove every origin's data
+    pub fn clear_all(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(
+            "DELETE FROM records; DELETE FROM index_entries; DELETE FROM store_indexes; DELETE FROM object_stores;"
+        ).context("Failed to clear IndexedDB data")?;
+        Ok(())
+    }
+}
+
+/// Position in a store or index; each step is a fresh query, so no lock is held between steps
+pub struct Cursor<'a> {
+    db: &'a IndexedDatabase,
+    scope: StoreScope,
+    index: Option<String>,
+    range: KeyRange,
+    direction: CursorDirection,
+    // Encoded (key, primary key) of the record last returned
+    position: Option<(String, String)>,
+}
+
+/// One step of a cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorRecord {
+    /// The index key for index cursors, otherwise the primary key
+    pub key: IdbKey,
+    pub primary_key: IdbKey,
+    pub value: Value,
+}
+
+impl Cursor<'_> {
+    /// Move to the next record, or None once the range is exhausted
+    pub fn advance(&mut self) -> Result<Option<CursorRecord>> {
+        let conn = self.db.conn.lock().unwrap();
+
+        let mut bound = scope_params(&self.scope);
+        let source = match &self.index {
+            Some(index) => {
+                bound.push(index.clone());
+                "SELECT ie.key AS k, ie.primary_key AS p, r.value AS value FROM index_entries ie
+                 JOIN records r ON r.origin = ie.origin AND r.db_name = ie.db_name AND r.store = ie.store AND r.key = ie.primary_key
+                 WHERE ie.origin = ? AND ie.db_name = ? AND ie.store = ? AND ie.idx = ?"
+            }
+            None => "SELECT key AS k, key AS p, value FROM records WHERE origin = ? AND db_name = ? AND store = ?",
+        };
+
+        let (mut conditions, mut values) = range_conditions("k", &self.range);
+        bound.append(&mut values);
+        let (compare, order) = match self.direction {
+            CursorDirection::Next => (">", "ASC"),
+            CursorDirection::Prev => ("<", "DESC"),
+        };
+        if let Some((key, primary)) = &self.position {
+            conditions.push_str(&format!(" AND (k {0} ? OR (k = ? AND p {0} ?))", compare));
+            bound.extend([key.clone(), key.clone(), primary.clone()]);
+        }
+
+        let sql = format!("SELECT k, p, value FROM ({}) WHERE 1{} ORDER BY k {2}, p {2} LIMIT 1", source, conditions, order);
+        let row: Option<(String, String, String)> = conn
+            .query_row(&sql, params_from_iter(bound.iter()), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .optional()?;
+
+        let Some((key, primary, value)) = row else {
+            return Ok(None);
+        };
+        let record = CursorRecord {
+            key: IdbKey::decode(&key).ok_or_else(|| anyhow!("corrupt key {}", key))?,
+            primary_key: IdbKey::decode(&primary).ok_or_else(|| anyhow!("corrupt key {}", primary))?,
+            value: serde_json::from_str(&value)?,
+        };
+        self.position = Some((key, primary));
+        Ok(Some(record))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IndexDefinition {
+    name: String,
+    key_path: String,
+    unique: bool,
+}
+
+fn scope_params(scope: &StoreScope) -> Vec<String> {
+    vec![scope.origin.clone(), scope.database.clone(), scope.store.clone()]
+}
+
+// `AND`-prefixed SQL conditions on `column` for the range, with their parameters
+fn range_conditions(column: &str, range: &KeyRange) -> (String, Vec<String>) {
+    let mut sql = String::new();
+    let mut values = Vec::new();
+    if let Some(lower) = &range.lower {
+        sql.push_str(&format!(" AND {} {} ?", column, if range.lower_open { ">" } else { ">=" }));
+        values.push(lower.encode());
+    }
+    if let Some(upper) = &range.upper {
+        sql.push_str(&format!(" AND {} {} ?", column, if range.upper_open { "<" } else { "<=" }));
+        values.push(upper.encode());
+    }
+    (sql, values)
+}
+
+// The store's key options and next generated key, or NotFoundError
+fn store_options(conn: &Connection, scope: &StoreScope) -> Result<(StoreOptions, i64)> {
+    conn.query_row(
+        "SELECT key_path, auto_increment, next_key FROM object_stores WHERE origin = ?1 AND db_name = ?2 AND name = ?3",
+        params![scope.origin, scope.database, scope.store],
+        |row| Ok((StoreOptions { key_path: row.get(0)?, auto_increment: row.get(1)? }, row.get(2)?)),
+    )
+    .optional()?
+    .ok_or_else(|| anyhow!("NotFoundError: no object store '{}' in database '{}'", scope.store, scope.database))
+}
+
+fn store_indexes(conn: &Connection, scope: &StoreScope) -> Result<Vec<IndexDefinition>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, key_path, is_unique FROM store_indexes WHERE origin = ?1 AND db_name = ?2 AND store = ?3"
+    )?;
+    let rows = stmt.query_map(params![scope.origin, scope.database, scope.store], |row| {
+        Ok(IndexDefinition { name: row.get(0)?, key_path: row.get(1)?, unique: row.get(2)? })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn origin_usage(conn: &Connection, origin: &str) -> Result<u64> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(SUM(size), 0) FROM records WHERE origin = ?1",
+        params![origin],
+        |row| row.get(0),
+    )?)
+}
+
+// Values without a valid key at the index's path are simply left out of it
+fn index_record(tx: &Transaction, scope: &StoreScope, index: &IndexDefinition, primary: &str, value: &Value) -> Result<()> {
+    let Some(key) = lookup_path(value, &index.key_path).and_then(IdbKey::from_json) else {
+        return Ok(());
+    };
+    let key = key.encode();
+    if index.unique {
+        let taken: Option<String> = tx.query_row(
+            "SELECT primary_key FROM index_entries
+             WHERE origin = ?1 AND db_name = ?2 AND store = ?3 AND idx = ?4 AND key = ?5 AND primary_key != ?6",
+            params![scope.origin, scope.database, scope.store, index.name, key, primary],
+            |row| row.get(0),
+        ).optional()?;
+        if taken.is_some() {
+            bail!("ConstraintError: unique index '{}' already has that key", index.name);
+        }
+    }
+    tx.execute(
+        "INSERT OR IGNORE INTO index_entries (origin, db_name, store, idx, key, primary_key) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![scope.origin, scope.database, scope.store, index.name, key, primary],
+    )?;
+    Ok(())
+}
+
+fn lookup_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, part| value.get(part))
+}
+
+// Store a generated key at `path`, creating intermediate objects as IndexedDB does
+fn inject_path(value: &mut Value, path: &str, key: Value) -> Result<()> {
+    let mut current = value;
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let object = current.as_object_mut()
+            .ok_or_else(|| anyhow!("DataError: cannot store a generated key inside a non-object value"))?;
+        if parts.peek().is_none() {
+            object.insert(part.to_string(), key);
+            return Ok(());
+        }
+        current = object.entry(part).or_insert_with(|| Value::Object(Default::default()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn create_test_db(quota: u64) -> Result<IndexedDatabase> {
+        let test_path = std::env::temp_dir().join(format!("test_indexeddb_{}.db", Uuid::new_v4()));
+        IndexedDatabase::new(&test_path, quota)
+    }
+
+    fn scope(origin: &str, store: &str) -> StoreScope {
+        StoreScope { origin: origin.to_string(), database: "app".to_string(), store: store.to_string() }
+    }
+
+    fn keys(cursor: &mut Cursor) -> Result<Vec<IdbKey>> {
+        let mut keys = Vec::new();
+        while let Some(record) = cursor.advance()? {
+            keys.push(record.primary_key);
+        }
+        Ok(keys)
+    }
+
+    #[test]
+    fn test_key_encoding_preserves_order() {
+        let sorted = [
+            IdbKey::Number(f64::NEG_INFINITY),
+            IdbKey::Number(-2.5),
+            IdbKey::Number(-0.0),
+            IdbKey::Number(1.0),
+            IdbKey::Number(10.0),
+            IdbKey::String(String::new()),
+            IdbKey::String("a".into()),
+            IdbKey::String("b".into()),
+        ];
+        for pair in sorted.windows(2) {
+            assert!(pair[0].encode() < pair[1].encode(), "{:?} < {:?}", pair[0], pair[1]);
+        }
+        for key in sorted {
+            assert_eq!(IdbKey::decode(&key.encode()), Some(key));
+        }
+    }
+
+    #[test]
+    fn test_stores_indexes_and_cursors() -> Result<()> {
+        let db = create_test_db(DEFAULT_ORIGIN_QUOTA)?;
+        let notes = scope("https://notes.example", "notes");
+        db.create_object_store(&notes, &StoreOptions { key_path: Some("meta.id".into()), auto_increment: true })?;
+        db.create_index(&notes, "by_tag", "tag", false)?;
+
+        assert_eq!(db.put(&notes, None, json!({"tag": "work", "title": "a"}))?, IdbKey::Number(1.0));
+        assert_eq!(db.put(&notes, None, json!({"meta": {"id": 10}, "tag": "home"}))?, IdbKey::Number(10.0));
+        assert_eq!(db.put(&notes, None, json!({"tag": "work", "title": "c"}))?, IdbKey::Number(11.0));
+        assert_eq!(db.get(&notes, &IdbKey::Number(1.0))?.unwrap(), json!({"meta": {"id": 1}, "tag": "work", "title": "a"}));
+        assert!(db.put(&notes, Some(IdbKey::Number(3.0)), json!({})).is_err());
+
+        let work = KeyRange::only(IdbKey::String("work".into()));
+        let mut cursor = db.open_cursor(&notes, Some("by_tag"), work.clone(), CursorDirection::Prev)?;
+        assert_eq!(keys(&mut cursor)?, vec![IdbKey::Number(11.0), IdbKey::Number(1.0)]);
+        assert_eq!(db.count(&notes, Some("by_tag"), &KeyRange::default())?, 3);
+
+        // Replacing a record moves it between index keys
+        db.put(&notes, None, json!({"meta": {"id": 1}, "tag": "home"}))?;
+        assert_eq!(db.count(&notes, Some("by_tag"), &work)?, 1);
+
+        let range = KeyRange { lower: Some(IdbKey::Number(1.0)), lower_open: true, ..KeyRange::default() };
+        assert_eq!(db.delete(&notes, &range)?, 2);
+        let mut cursor = db.open_cursor(&notes, None, KeyRange::default(), CursorDirection::Next)?;
+        assert_eq!(keys(&mut cursor)?, vec![IdbKey::Number(1.0)]);
+        assert_eq!(db.count(&notes, Some("by_tag"), &KeyRange::default())?, 1);
+
+        // Other origins see nothing of this one
+        let other = scope("https://other.example", "notes");
+        assert!(db.get(&other, &IdbKey::Number(1.0)).is_err());
+        assert_eq!(db.database_names("https://other.example")?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_index_and_quota() -> Result<()> {
+        let db = create_test_db(200)?;
+        let users = scope("https://a.example", "users");
+        db.create_object_store(&users, &StoreOptions::default())?;
+        db.create_index(&users, "by_email", "email", true)?;
+
+        db.put(&users, Some(IdbKey::String("ann".into())), json!({"email": "ann@example.com"}))?;
+        let duplicate = db.put(&users, Some(IdbKey::String("bob".into())), json!({"email": "ann@example.com"}));
+        assert!(duplicate.unwrap_err().to_string().starts_with("ConstraintError"));
+        // Rewriting the same record keeps its own unique key
+        db.put(&users, Some(IdbKey::String("ann".into())), json!({"email": "ann@example.com", "n": 1}))?;
+
+        let big = json!({"blob": "x".repeat(150)});
+        let err = db.put(&users, Some(IdbKey::String("big".into())), big).unwrap_err();
+        assert!(err.to_string().starts_with("QuotaExceededError"));
+
+        let usage = db.usage("https://a.example")?;
+        assert!(usage > 0 && usage <= 200);
+        assert_eq!(db.usage_by_origin()?, vec![OriginUsage { origin: "https://a.example".into(), bytes: usage }]);
+
+        db.clear_origin("https://a.example")?;
+        assert_eq!(db.usage("https://a.example")?, 0);
+        assert!(db.usage_by_origin()?.is_empty());
+        Ok(())
+    }
+}
@@ -1,3 +1,63 @@
+pub mod bookmark_store;
+pub mod crash_reports;
 pub mod downloads_db;
+pub mod history_db;
+pub mod history_stats;
+pub mod import;
+pub mod indexed_db;
+pub mod preferences;
+pub mod session;
+pub mod user_styles;
 
+pub use bookmark_store::{BookmarkStore, StoredBookmark};
 pub use downloads_db::{DownloadsDatabase, DownloadRecord, DownloadState};
+pub use history_db::{HistoryDatabase, HistoryEntry, HistoryOrder, HistoryQuery, VisitRecord};
+pub use history_stats::HistoryStats;
+pub use indexed_db::IndexedDatabase;
+pub use preferences::{ImageLoadingMode, Preferences, PrivacySettings, StartupPage};
+pub use session::{ClosedItem, GroupColor, Session, SessionGroup, SessionTab};
+pub use user_styles::UserStyles;
+
+use std::path::PathBuf;
+
+/// Per-user data directory for NeonSearch (databases, extensions, settings)
+pub fn data_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("NeonSearch");
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+/// What the Clear Browsing Data dialog removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearBrowsingData {
+    pub history: bool,
+    /// Structured storage written by page scripts
+    pub site_data: bool,
+}
+
+impl Default for ClearBrowsingData {
+    fn default() -> Self {
+        Self { history: true, site_data: true }
+    }
+}
+
+impl ClearBrowsingData {
+    /// Remove the selected data, carrying on past failures and reporting the first
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        if self.history {
+            if let Some(history) = HistoryDatabase::shared() {
+                result = result.and(history.clear());
+            }
+        }
+        if self.site_data {
+            if let Some(db) = IndexedDatabase::shared() {
+                result = result.and(db.clear_all());
+            }
+        }
+        result
+    }
+}
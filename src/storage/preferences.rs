@@ -0,0 +1,757 @@
+// Browser-wide preferences persisted in preferences.json
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::networking::redirect::ReferrerPolicy;
+use crate::networking::user_agent::UserAgentPreset;
+use crate::sync::target::SyncTarget;
+use crate::translate::TranslationProvider;
+
+/// How `<img>` elements are scheduled for loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ImageLoadingMode {
+    /// Follow each image's `loading` attribute
+    #[default]
+    Auto,
+    /// Load every image with the page
+    Eager,
+    /// Defer every image until it scrolls near the viewport
+    Lazy,
+}
+
+impl ImageLoadingMode {
+    pub const ALL: [ImageLoadingMode; 3] = [ImageLoadingMode::Auto, ImageLoadingMode::Eager, ImageLoadingMode::Lazy];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageLoadingMode::Auto => "Respect loading attribute",
+            ImageLoadingMode::Eager => "Always load immediately",
+            ImageLoadingMode::Lazy => "Always load when visible",
+        }
+    }
+
+    /// Whether an image with the given `loading` attribute waits until it is near the viewport
+    pub fn defers(&self, loading_attr: Option<&str>) -> bool {
+        match self {
+            ImageLoadingMode::Auto => loading_attr.is_some_and(|value| value.trim().eq_ignore_ascii_case("lazy")),
+            ImageLoadingMode::Eager => false,
+            ImageLoadingMode::Lazy => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReaderFont {
+    #[default]
+    Serif,
+    SansSerif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReaderTheme {
+    Light,
+    #[default]
+    Sepia,
+    Dark,
+}
+
+impl ReaderTheme {
+    pub const ALL: [ReaderTheme; 3] = [ReaderTheme::Light, ReaderTheme::Sepia, ReaderTheme::Dark];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReaderTheme::Light => "Light",
+            ReaderTheme::Sepia => "Sepia",
+            ReaderTheme::Dark => "Dark",
+        }
+    }
+}
+
+/// What the first window shows when NeonSearch starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StartupPage {
+    #[default]
+    HomePage,
+    NewTab,
+    /// Reopen the tabs and tab groups open when the browser last closed
+    LastSession,
+}
+
+/// Typography and theme of reader mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReaderSettings {
+    pub font: ReaderFont,
+    /// Body text size in points
+    pub font_size: f32,
+    /// Line height as a multiple of the font size
+    pub line_height: f32,
+    pub theme: ReaderTheme,
+    /// Hosts whose pages always open in reader mode
+    pub always_sites: BTreeSet<String>,
+}
+
+impl Default for ReaderSettings {
+    fn default() -> Self {
+        Self {
+            font: ReaderFont::default(),
+            font_size: 18.0,
+            line_height: 1.6,
+            theme: ReaderTheme::default(),
+            always_sites: BTreeSet::new(),
+        }
+    }
+}
+
+// The site a per-site setting is keyed by: the URL's host without a leading www.
+fn site_host(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(|host| host.trim_start_matches("www.").to_string())
+}
+
+impl ReaderSettings {
+    /// Whether pages at `url` should open in reader mode straight away
+    pub fn always_for(&self, url: &str) -> bool {
+        site_host(url).is_some_and(|host| self.always_sites.contains(&host))
+    }
+
+    pub fn set_always_for(&mut self, url: &str, always: bool) {
+        if let Some(host) = site_host(url) {
+            if always {
+                self.always_sites.insert(host);
+            } else {
+                self.always_sites.remove(&host);
+            }
+        }
+    }
+}
+
+/// How long cookies outlive the pages that set them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CookieSettings {
+    /// Keep session cookies across restarts instead of dropping them on exit
+    pub keep_session_cookies: bool,
+    /// Hosts whose cookies are cleared once their last tab closes
+    pub clear_on_close_sites: BTreeSet<String>,
+    /// Hosts that may neither set nor read cookies
+    pub blocked_sites: BTreeSet<String>,
+}
+
+impl CookieSettings {
+    /// The host whose cookies to clear when the last tab showing `url` closes, if the user asked for that
+    pub fn clear_on_close_host(&self, url: &str) -> Option<String> {
+        site_host(url).filter(|host| self.clear_on_close_sites.contains(host))
+    }
+
+    pub fn set_clear_on_close(&mut self, url: &str, clear: bool) {
+        if let Some(host) = site_host(url) {
+            if clear {
+                self.clear_on_close_sites.insert(host);
+            } else {
+                self.clear_on_close_sites.remove(&host);
+            }
+        }
+    }
+
+    /// Whether requests to `url` go without cookies and have the ones they set dropped
+    pub fn blocks(&self, url: &str) -> bool {
+        site_host(url).is_some_and(|host| self.blocked_sites.contains(&host))
+    }
+
+    pub fn set_blocked(&mut self, url: &str, blocked: bool) {
+        if let Some(host) = site_host(url) {
+            if blocked {
+                self.blocked_sites.insert(host);
+            } else {
+                self.blocked_sites.remove(&host);
+            }
+        }
+    }
+}
+
+/// Sites whose pages are shown without images
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSettings {
+    pub blocked_sites: BTreeSet<String>,
+}
+
+impl ImageSettings {
+    /// Whether pages at `url` load their images
+    pub fn allowed(&self, url: &str) -> bool {
+        !site_host(url).is_some_and(|host| self.blocked_sites.contains(&host))
+    }
+
+    pub fn set_allowed(&mut self, url: &str, allowed: bool) {
+        if let Some(host) = site_host(url) {
+            if allowed {
+                self.blocked_sites.remove(&host);
+            } else {
+                self.blocked_sites.insert(host);
+            }
+        }
+    }
+}
+
+/// Which browser requests present themselves as, everywhere and on particular sites
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserAgentSettings {
+    pub preset: UserAgentPreset,
+    /// Hosts that get another profile than the default, e.g. to reach a mobile site
+    pub site_overrides: BTreeMap<String, UserAgentPreset>,
+}
+
+impl UserAgentSettings {
+    /// The profile requests to `url` are sent with
+    pub fn preset_for(&self, url: &str) -> UserAgentPreset {
+        site_host(url).and_then(|host| self.site_overrides.get(&host).copied()).unwrap_or(self.preset)
+    }
+
+    /// Send requests to `host` with `preset`, or with the default profile again for None
+    pub fn set_override(&mut self, host: &str, preset: Option<UserAgentPreset>) {
+        let host = host.trim().trim_start_matches("www.").to_ascii_lowercase();
+        match preset {
+            Some(preset) if !host.is_empty() => {
+                self.site_overrides.insert(host, preset);
+            }
+            _ => {
+                self.site_overrides.remove(&host);
+            }
+        }
+    }
+}
+
+/// Which sites may show notifications, and when none are shown at all
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Each origin's answer to the permission prompt: true if allowed, false if blocked
+    pub permissions: BTreeMap<String, bool>,
+    /// Hold back every notification
+    pub do_not_disturb: bool,
+    /// Daily quiet hours as minutes after local midnight, start to end; may wrap past midnight
+    pub quiet_hours: Option<(u16, u16)>,
+}
+
+impl NotificationSettings {
+    /// What the user answered for the origin of `url`; None if they haven't been asked
+    pub fn permission(&self, url: &str) -> Option<bool> {
+        crate::networking::site_metadata::origin_of(url).and_then(|origin| self.permissions.get(&origin).copied())
+    }
+
+    /// Allow or block notifications from the origin of `url`; None forgets the answer, so the site may ask again
+    pub fn set_permission(&mut self, url: &str, allowed: Option<bool>) {
+        let Some(origin) = crate::networking::site_metadata::origin_of(url) else { return };
+        match allowed {
+            Some(allowed) => {
+                self.permissions.insert(origin, allowed);
+            }
+            None => {
+                self.permissions.remove(&origin);
+            }
+        }
+    }
+
+    /// Whether notifications are held back at `minute` after local midnight
+    pub fn is_quiet(&self, minute: u16) -> bool {
+        self.do_not_disturb || self.quiet_hours.is_some_and(|(start, end)| match start <= end {
+            true => (start..end).contains(&minute),
+            false => minute >= start || minute < end,
+        })
+    }
+}
+
+/// Whether pages may run scripts, everywhere and on particular sites
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JavaScriptSettings {
+    pub enabled: bool,
+    /// Origins that differ from `enabled`: true if they may run scripts, false if they may not
+    pub site_overrides: BTreeMap<String, bool>,
+    /// Seconds a page's scripts may run in one go before the browser offers to stop them
+    pub slow_script_secs: u64,
+}
+
+impl Default for JavaScriptSettings {
+    fn default() -> Self {
+        Self { enabled: true, site_overrides: BTreeMap::new(), slow_script_secs: crate::js::watchdog::DEFAULT_SLOW_SCRIPT_SECS }
+    }
+}
+
+impl JavaScriptSettings {
+    /// Whether pages at `url` run scripts
+    pub fn allows(&self, url: &str) -> bool {
+        crate::networking::site_metadata::origin_of(url)
+            .and_then(|origin| self.site_overrides.get(&origin).copied())
+            .unwrap_or(self.enabled)
+    }
+
+    /// Allow or block scripts on the origin of `url`; an answer matching the default isn't kept as an override
+    pub fn set_allowed(&mut self, url: &str, allowed: bool) {
+        let Some(origin) = crate::networking::site_metadata::origin_of(url) else { return };
+        if allowed == self.enabled {
+            self.site_overrides.remove(&origin);
+        } else {
+            self.site_overrides.insert(origin, allowed);
+        }
+    }
+}
+
+/// When the browser moves between a light and a dark theme by itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeSwitch {
+    /// Always show the chosen theme
+    #[default]
+    Off,
+    /// Follow the operating system's light or dark mode
+    System,
+    /// Light from `light_from` until `light_until`, in minutes after local midnight, and dark otherwise
+    Schedule { light_from: u16, light_until: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoThemeSettings {
+    pub switch: ThemeSwitch,
+    pub light_theme: String,
+    pub dark_theme: String,
+}
+
+impl Default for AutoThemeSettings {
+    fn default() -> Self {
+        Self {
+            switch: ThemeSwitch::Off,
+            light_theme: crate::ui::themes::LIGHT_THEME.to_string(),
+            dark_theme: crate::ui::themes::DEFAULT_THEME.to_string(),
+        }
+    }
+}
+
+impl AutoThemeSettings {
+    /// The theme switching picks when the system is dark (if known) at `minute` after local midnight;
+    /// None when switching is off or the system's mode is unknown
+    pub fn pick(&self, system_dark: Option<bool>, minute: u16) -> Option<&str> {
+        let dark = match self.switch {
+            ThemeSwitch::Off => return None,
+            ThemeSwitch::System => system_dark?,
+            ThemeSwitch::Schedule { light_from, light_until } => !match light_from <= light_until {
+                true => (light_from..light_until).contains(&minute),
+                false => minute >= light_from || minute < light_until,
+            },
+        };
+        Some(if dark { &self.dark_theme } else { &self.light_theme })
+    }
+}
+
+/// Blocking third-party trackers and partitioning third-party cookies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackingSettings {
+    pub enabled: bool,
+    /// Hosts the user turned protection off for, e.g. because blocking broke them
+    pub exceptions: BTreeSet<String>,
+}
+
+impl Default for TrackingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            exceptions: BTreeSet::new(),
+        }
+    }
+}
+
+impl TrackingSettings {
+    /// Whether pages at `url` load with tracking protection
+    pub fn protects(&self, url: &str) -> bool {
+        self.enabled && !site_host(url).is_some_and(|host| self.exceptions.contains(&host))
+    }
+
+    pub fn set_exception(&mut self, url: &str, excepted: bool) {
+        if let Some(host) = site_host(url) {
+            if excepted {
+                self.exceptions.insert(host);
+            } else {
+                self.exceptions.remove(&host);
+            }
+        }
+    }
+}
+
+/// Loading the pages the user is likely to open next before they do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefetchSettings {
+    /// Prefetch the top address bar suggestion and pages' `<link rel="prefetch">` targets while the network is idle
+    pub enabled: bool,
+    /// Parse prefetched pages as well, so they show sooner when opened
+    pub preparse: bool,
+    /// Most pages prefetched for one page or one address bar suggestion list
+    pub max_pages: usize,
+}
+
+impl Default for PrefetchSettings {
+    fn default() -> Self {
+        Self { enabled: true, preparse: false, max_pages: 3 }
+    }
+}
+
+/// Navigating with mouse gestures over the page; the back and forward mouse buttons always work
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseSettings {
+    /// Hold the right button and click the left to go back; hold the left and click the right to go forward
+    pub rocker_gestures: bool,
+    /// Drag left or right with the right button held to go back or forward
+    pub drag_gestures: bool,
+}
+
+/// Signals sent to every site, and how much scripts may learn about the machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacySettings {
+    /// Send `DNT: 1`
+    pub do_not_track: bool,
+    /// Send `Sec-GPC: 1` and set `navigator.globalPrivacyControl`
+    pub global_privacy_control: bool,
+    /// Report the same screen, time zone, language and hardware as every other browser doing so
+    pub reduce_fingerprinting: bool,
+}
+
+impl PrivacySettings {
+    /// Headers sent with every request for the signals that are on
+    pub fn headers(&self) -> Vec<(&'static str, &'static str)> {
+        let mut headers = Vec::new();
+        if self.do_not_track {
+            headers.push(("DNT", "1"));
+        }
+        if self.global_privacy_control {
+            headers.push(("Sec-GPC", "1"));
+        }
+        headers
+    }
+}
+
+/// Which service translates pages, and into what language
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationSettings {
+    pub provider: TranslationProvider,
+    /// Language code pages are translated into, or None for the language the browser is shown in
+    pub target_language: Option<String>,
+}
+
+impl TranslationSettings {
+    pub fn target_language(&self) -> String {
+        self.target_language.clone()
+            .filter(|language| !language.trim().is_empty())
+            .unwrap_or_else(|| crate::ui::i18n::active().language.split('-').next().unwrap_or("en").to_ascii_lowercase())
+    }
+}
+
+/// Where this device syncs to and what it shares
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub target: SyncTarget,
+    /// Names this device's file on the sync target; made up the first time sync is set up
+    pub device_id: String,
+    pub device_name: String,
+    pub interval_minutes: u32,
+    pub bookmarks: bool,
+    pub settings: bool,
+    pub tabs: bool,
+    pub history: bool,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            target: SyncTarget::None,
+            device_id: String::new(),
+            device_name: String::new(),
+            interval_minutes: 15,
+            bookmarks: true,
+            settings: true,
+            tabs: true,
+            history: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub startup: StartupPage,
+    pub image_loading: ImageLoadingMode,
+    pub images: ImageSettings,
+    /// Expose the `indexedDB` storage API to page scripts
+    pub experimental_indexed_db: bool,
+    /// Pages larger than this many megabytes ask before loading in full; None loads everything
+    pub page_size_limit_mb: Option<u32>,
+    /// Speed test server neon://diagnostics measures against
+    pub diagnostics_endpoint: String,
+    /// Remove utm_* and other tracking parameters from addresses copied out of the address bar
+    pub strip_tracking_on_copy: bool,
+    /// Referer policy for pages that don't set their own
+    pub referrer_policy: ReferrerPolicy,
+    /// Let file:// addresses open files and folders from this computer
+    pub allow_file_urls: bool,
+    /// Web page templates (with `%s` for the link) that mailto:, irc: and other links open; unmapped schemes go to the system
+    pub protocol_handlers: BTreeMap<String, String>,
+    /// List tabs in a sidebar on the left instead of the strip above the page
+    pub vertical_tabs: bool,
+    /// Whether the first run already offered to import another browser's data
+    pub import_offered: bool,
+    /// Name of the theme the browser is drawn with, unless `auto_theme` picks another
+    pub theme: String,
+    pub auto_theme: AutoThemeSettings,
+    /// Language code of the UI, or None to follow the system's
+    pub language: Option<String>,
+    pub reader: ReaderSettings,
+    pub cookies: CookieSettings,
+    pub notifications: NotificationSettings,
+    pub javascript: JavaScriptSettings,
+    pub user_agent: UserAgentSettings,
+    pub tracking: TrackingSettings,
+    pub privacy: PrivacySettings,
+    pub prefetch: PrefetchSettings,
+    pub sync: SyncSettings,
+    pub translation: TranslationSettings,
+    /// Module log levels set on neon://logs, as RUST_LOG writes them; None keeps the defaults
+    pub log_levels: Option<String>,
+    /// Key chords the user rebound, by shortcut action id; an empty chord removes the shortcut
+    pub shortcuts: BTreeMap<String, String>,
+    pub mouse: MouseSettings,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            startup: StartupPage::default(),
+            image_loading: ImageLoadingMode::default(),
+            images: ImageSettings::default(),
+            experimental_indexed_db: false,
+            page_size_limit_mb: Some((crate::networking::DEFAULT_PAGE_SIZE_LIMIT / 1024 / 1024) as u32),
+            diagnostics_endpoint: crate::networking::diagnostics::DEFAULT_ENDPOINT.to_string(),
+            strip_tracking_on_copy: true,
+            referrer_policy: ReferrerPolicy::default(),
+            allow_file_urls: false,
+            protocol_handlers: BTreeMap::new(),
+            vertical_tabs: false,
+            import_offered: false,
+            theme: crate::ui::themes::DEFAULT_THEME.to_string(),
+            auto_theme: AutoThemeSettings::default(),
+            language: None,
+            reader: ReaderSettings::default(),
+            cookies: CookieSettings::default(),
+            notifications: NotificationSettings::default(),
+            javascript: JavaScriptSettings::default(),
+            user_agent: UserAgentSettings::default(),
+            tracking: TrackingSettings::default(),
+            privacy: PrivacySettings::default(),
+            prefetch: PrefetchSettings::default(),
+            sync: SyncSettings::default(),
+            translation: TranslationSettings::default(),
+            log_levels: None,
+            shortcuts: BTreeMap::new(),
+            mouse: MouseSettings::default(),
+        }
+    }
+}
+
+// Readers share one snapshot; an update swaps in a new one rather than changing what they hold
+static SHARED_PREFERENCES: OnceLock<Mutex<Arc<Preferences>>> = OnceLock::new();
+
+impl Preferences {
+    fn path() -> PathBuf {
+        crate::storage::data_dir().join("preferences.json")
+    }
+
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .map_err(|e| anyhow!("Failed to save preferences: {}", e))
+    }
+
+    /// Name of the theme to show now, given the system's mode and the minute after local midnight
+    pub fn active_theme(&self, system_dark: Option<bool>, minute: u16) -> &str {
+        self.auto_theme.pick(system_dark, minute).unwrap_or(&self.theme)
+    }
+
+    /// The page size limit in bytes
+    pub fn page_size_limit(&self) -> Option<usize> {
+        self.page_size_limit_mb.map(|mb| mb as usize * 1024 * 1024)
+    }
+
+    fn shared() -> &'static Mutex<Arc<Preferences>> {
+        SHARED_PREFERENCES.get_or_init(|| Mutex::new(Arc::new(Preferences::load(&Self::path()))))
+    }
+
+    /// Snapshot of the current preferences, cheap to take as often as needed
+    pub fn current() -> Arc<Preferences> {
+        Self::shared().lock().map(|prefs| Arc::clone(&prefs)).unwrap_or_default()
+    }
+
+    /// Change preferences and write them to disk
+    pub fn update(apply: impl FnOnce(&mut Preferences)) {
+        if let Ok(mut prefs) = Self::shared().lock() {
+            let prefs = Arc::make_mut(&mut prefs);
+            apply(prefs);
+            if let Err(e) = prefs.save(&Self::path()) {
+                log::warn!("{}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_loading_mode() {
+        assert!(ImageLoadingMode::Auto.defers(Some("lazy")));
+        assert!(ImageLoadingMode::Auto.defers(Some(" LAZY ")));
+        assert!(!ImageLoadingMode::Auto.defers(Some("eager")));
+        assert!(!ImageLoadingMode::Auto.defers(None));
+        assert!(!ImageLoadingMode::Eager.defers(Some("lazy")));
+        assert!(ImageLoadingMode::Lazy.defers(None));
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let prefs: Preferences = serde_json::from_str("{}").unwrap();
+        assert_eq!(prefs.image_loading, ImageLoadingMode::Auto);
+        assert!(!prefs.experimental_indexed_db);
+        assert_eq!(prefs.page_size_limit(), Some(crate::networking::DEFAULT_PAGE_SIZE_LIMIT));
+        assert_eq!(prefs.diagnostics_endpoint, crate::networking::diagnostics::DEFAULT_ENDPOINT);
+        assert!(prefs.strip_tracking_on_copy);
+        assert_eq!(prefs.referrer_policy, ReferrerPolicy::StrictOriginWhenCrossOrigin);
+        assert_eq!(prefs.reader, ReaderSettings::default());
+        assert_eq!(prefs.cookies, CookieSettings::default());
+        assert_eq!(prefs.notifications, NotificationSettings::default());
+        assert_eq!(prefs.user_agent, UserAgentSettings::default());
+        assert!(prefs.tracking.enabled);
+        assert!(prefs.privacy.headers().is_empty());
+
+        // An explicit null turns the limit off
+        let prefs: Preferences = serde_json::from_str(r#"{"page_size_limit_mb": null}"#).unwrap();
+        assert_eq!(prefs.page_size_limit(), None);
+    }
+
+    #[test]
+    fn test_reader_always_sites() {
+        let mut reader = ReaderSettings::default();
+        reader.set_always_for("https://www.example.com/post/1", true);
+        assert!(reader.always_for("https://example.com/other"));
+        assert!(!reader.always_for("https://news.example.com/"));
+        reader.set_always_for("https://example.com/", false);
+        assert!(!reader.always_for("https://www.example.com/post/1"));
+    }
+
+    #[test]
+    fn test_clear_cookies_on_close_sites() {
+        let mut cookies = CookieSettings::default();
+        cookies.set_clear_on_close("https://www.example.com/account", true);
+        assert_eq!(cookies.clear_on_close_host("http://example.com/").as_deref(), Some("example.com"));
+        assert_eq!(cookies.clear_on_close_host("about:home"), None);
+    }
+
+    #[test]
+    fn test_site_cookie_and_image_blocking() {
+        let mut cookies = CookieSettings::default();
+        cookies.set_blocked("https://www.example.com/", true);
+        assert!(cookies.blocks("http://example.com/login"));
+        assert!(!cookies.blocks("https://other.com/"));
+
+        let mut images = ImageSettings::default();
+        assert!(images.allowed("https://example.com/"));
+        images.set_allowed("https://example.com/gallery", false);
+        assert!(!images.allowed("https://www.example.com/"));
+        images.set_allowed("https://example.com/", true);
+        assert!(images.allowed("https://example.com/"));
+    }
+
+    #[test]
+    fn test_tracking_protection_exceptions() {
+        let mut tracking = TrackingSettings::default();
+        tracking.set_exception("https://www.example.com/video", true);
+        assert!(!tracking.protects("https://example.com/"));
+        assert!(tracking.protects("https://other.org/"));
+        tracking.enabled = false;
+        assert!(!tracking.protects("https://other.org/"));
+    }
+
+    #[test]
+    fn test_user_agent_site_overrides() {
+        let mut user_agent = UserAgentSettings { preset: UserAgentPreset::FirefoxLinux, ..Default::default() };
+        user_agent.set_override("www.Example.com", Some(UserAgentPreset::Mobile));
+        assert_eq!(user_agent.preset_for("https://example.com/page"), UserAgentPreset::Mobile);
+        assert_eq!(user_agent.preset_for("https://www.example.com/"), UserAgentPreset::Mobile);
+        assert_eq!(user_agent.preset_for("https://other.org/"), UserAgentPreset::FirefoxLinux);
+        user_agent.set_override("example.com", None);
+        assert_eq!(user_agent.preset_for("https://example.com/"), UserAgentPreset::FirefoxLinux);
+    }
+
+    #[test]
+    fn test_notification_permissions_and_quiet_hours() {
+        let mut notifications = NotificationSettings::default();
+        notifications.set_permission("https://chat.example.com/room/1", Some(true));
+        notifications.set_permission("http://ads.example.net/", Some(false));
+        assert_eq!(notifications.permission("https://chat.example.com/other"), Some(true));
+        assert_eq!(notifications.permission("http://chat.example.com/"), None);
+        assert_eq!(notifications.permission("http://ads.example.net/x"), Some(false));
+        notifications.set_permission("https://chat.example.com/", None);
+        assert_eq!(notifications.permission("https://chat.example.com/"), None);
+
+        // 22:00 to 07:00 wraps past midnight
+        notifications.quiet_hours = Some((22 * 60, 7 * 60));
+        assert!(notifications.is_quiet(23 * 60));
+        assert!(notifications.is_quiet(6 * 60));
+        assert!(!notifications.is_quiet(12 * 60));
+        notifications.do_not_disturb = true;
+        assert!(notifications.is_quiet(12 * 60));
+    }
+
+    #[test]
+    fn test_javascript_site_overrides() {
+        let mut javascript = JavaScriptSettings::default();
+        javascript.set_allowed("https://news.example.com/article", false);
+        assert!(!javascript.allows("https://news.example.com/"));
+        assert!(javascript.allows("http://news.example.com/"));
+        javascript.set_allowed("https://news.example.com/", true);
+        assert!(javascript.site_overrides.is_empty());
+
+        // With scripts off by default, a site has to be let in
+        javascript.enabled = false;
+        javascript.set_allowed("https://app.example.com/", true);
+        assert!(javascript.allows("https://app.example.com/inbox"));
+        assert!(!javascript.allows("https://other.org/"));
+    }
+
+    #[test]
+    fn test_automatic_theme_switching() {
+        let mut prefs = Preferences { theme: "Midnight".to_string(), ..Default::default() };
+        assert_eq!(prefs.active_theme(Some(false), 12 * 60), "Midnight");
+
+        prefs.auto_theme.switch = ThemeSwitch::System;
+        assert_eq!(prefs.active_theme(Some(false), 0), crate::ui::themes::LIGHT_THEME);
+        assert_eq!(prefs.active_theme(Some(true), 0), crate::ui::themes::DEFAULT_THEME);
+        assert_eq!(prefs.active_theme(None, 0), "Midnight");
+
+        prefs.auto_theme.switch = ThemeSwitch::Schedule { light_from: 7 * 60, light_until: 19 * 60 };
+        assert_eq!(prefs.active_theme(Some(true), 12 * 60), crate::ui::themes::LIGHT_THEME);
+        assert_eq!(prefs.active_theme(Some(false), 20 * 60), crate::ui::themes::DEFAULT_THEME);
+    }
+}
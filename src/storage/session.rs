@@ -0,0 +1,168 @@
+// The open tabs and their groups, saved on exit so the next launch can continue where this one left off, and the
+// tabs and windows closed recently so they can be reopened
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Closed tabs and windows kept for reopening; older ones are forgotten
+const MAX_CLOSED: usize = 25;
+
+/// Colours a tab group can be marked with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GroupColor {
+    #[default]
+    Cyan,
+    Purple,
+    Pink,
+    Green,
+    Yellow,
+    Orange,
+    Grey,
+}
+
+impl GroupColor {
+    pub const ALL: [GroupColor; 7] = [
+        GroupColor::Cyan,
+        GroupColor::Purple,
+        GroupColor::Pink,
+        GroupColor::Green,
+        GroupColor::Yellow,
+        GroupColor::Orange,
+        GroupColor::Grey,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupColor::Cyan => "Cyan",
+            GroupColor::Purple => "Purple",
+            GroupColor::Pink => "Pink",
+            GroupColor::Green => "Green",
+            GroupColor::Yellow => "Yellow",
+            GroupColor::Orange => "Orange",
+            GroupColor::Grey => "Grey",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub url: String,
+    pub title: String,
+    /// Index into the session's groups
+    pub group: Option<usize>,
+    /// Addresses of the tab's back and forward entries; empty when only `url` is known
+    #[serde(default)]
+    pub history: Vec<String>,
+    /// Index of `url` in the history
+    #[serde(default)]
+    pub history_index: usize,
+}
+
+impl SessionTab {
+    pub fn new(url: String, title: String) -> Self {
+        Self { url, title, group: None, history: Vec::new(), history_index: 0 }
+    }
+}
+
+/// Something closed that "Reopen closed tab" can bring back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClosedItem {
+    /// A tab, with the position it had in the tab strip; it comes back ungrouped
+    Tab { tab: SessionTab, index: usize },
+    /// A window's tabs and groups, when the next launch doesn't continue them
+    Window { tabs: Vec<SessionTab>, groups: Vec<SessionGroup> },
+}
+
+impl ClosedItem {
+    /// How the recently closed menu names it
+    pub fn label(&self) -> String {
+        match self {
+            ClosedItem::Tab { tab, .. } if tab.title.is_empty() => tab.url.clone(),
+            ClosedItem::Tab { tab, .. } => tab.title.clone(),
+            ClosedItem::Window { tabs, .. } if tabs.len() == 1 => "Window with 1 tab".to_string(),
+            ClosedItem::Window { tabs, .. } => format!("Window with {} tabs", tabs.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionGroup {
+    pub name: String,
+    pub color: GroupColor,
+    pub collapsed: bool,
+}
+
+/// Tabs in strip order, the groups they belong to, and which one was in front
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub tabs: Vec<SessionTab>,
+    pub groups: Vec<SessionGroup>,
+    /// Index into the tabs
+    pub active: Option<usize>,
+    /// Recently closed tabs and windows, the most recent last
+    pub closed: Vec<ClosedItem>,
+}
+
+impl Session {
+    pub fn path() -> PathBuf {
+        crate::storage::data_dir().join("session.json")
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to save session: {}", e))
+    }
+}
+
+/// Remember `item` as the most recently closed, forgetting the oldest past the limit
+pub fn push_closed(closed: &mut Vec<ClosedItem>, item: ClosedItem) {
+    closed.push(item);
+    if closed.len() > MAX_CLOSED {
+        closed.drain(..closed.len() - MAX_CLOSED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let path = std::env::temp_dir().join(format!("neon-session-{}.json", std::process::id()));
+        let mut closed = Vec::new();
+        for n in 0..MAX_CLOSED + 2 {
+            let tab = SessionTab::new(format!("https://example.com/{}", n), String::new());
+            push_closed(&mut closed, ClosedItem::Tab { tab, index: 0 });
+        }
+        assert_eq!(closed.len(), MAX_CLOSED);
+        assert_eq!(closed[0].label(), "https://example.com/2");
+
+        let session = Session {
+            tabs: vec![
+                SessionTab {
+                    group: Some(0),
+                    history: vec!["https://example.com/".to_string(), "https://example.com/next".to_string()],
+                    history_index: 0,
+                    ..SessionTab::new("https://example.com/".to_string(), "Example".to_string())
+                },
+                SessionTab::new("about:home".to_string(), "NeonSearch Home".to_string()),
+            ],
+            groups: vec![SessionGroup { name: "Research".to_string(), color: GroupColor::Green, collapsed: true }],
+            active: Some(1),
+            closed,
+        };
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path), Some(session));
+        let _ = std::fs::remove_file(&path);
+
+        // Sessions saved before tabs kept their history still load
+        let old = r#"{"tabs":[{"url":"https://example.com/","title":"Example","group":null}],"groups":[],"active":0}"#;
+        let old: Session = serde_json::from_str(old).unwrap();
+        assert!(old.tabs[0].history.is_empty() && old.closed.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+// User stylesheets: CSS the user wrote for every page or for one site, layered over the page's own styles
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+const GLOBAL_FILE: &str = "global.css";
+
+pub struct UserStyles {
+    dir: PathBuf,
+    global: String,
+    // Site (a host without `www.`) to its stylesheet
+    sites: BTreeMap<String, String>,
+    // Bumped on every change, so pages know to pick the styles up again
+    revision: u64,
+    // Site the page context menu asked to edit styles for, until the settings page takes it
+    edit_request: Option<String>,
+}
+
+static SHARED_STYLES: OnceLock<Arc<Mutex<UserStyles>>> = OnceLock::new();
+
+impl UserStyles {
+    pub fn new(data_dir: &Path) -> Self {
+        let dir = data_dir.join("user_styles");
+        let _ = std::fs::create_dir_all(&dir);
+        let mut styles = Self { dir, global: String::new(), sites: BTreeMap::new(), revision: 0, edit_request: None };
+        styles.reload();
+        styles
+    }
+
+    /// Process-wide stylesheets shared by the settings page and every tab
+    pub fn shared() -> Arc<Mutex<UserStyles>> {
+        SHARED_STYLES
+            .get_or_init(|| Arc::new(Mutex::new(UserStyles::new(&super::data_dir()))))
+            .clone()
+    }
+
+    pub fn reload(&mut self) {
+        self.global.clear();
+        self.sites.clear();
+        let entries = std::fs::read_dir(&self.dir).map(|entries| entries.flatten().collect::<Vec<_>>()).unwrap_or_default();
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(site) = name.strip_suffix(".css") else { continue };
+            let Ok(css) = std::fs::read_to_string(entry.path()) else { continue };
+            if name == GLOBAL_FILE {
+                self.global = css;
+            } else {
+                self.sites.insert(site.to_string(), css);
+            }
+        }
+        self.revision += 1;
+    }
+
+    /// The stylesheet for `site`, or the global one for None
+    pub fn get(&self, site: Option<&str>) -> &str {
+        match site {
+            Some(site) => self.sites.get(site).map(String::as_str).unwrap_or_default(),
+            None => &self.global,
+        }
+    }
+
+    /// Save the stylesheet for `site`, or the global one for None; saving an empty one removes it
+    pub fn set(&mut self, site: Option<&str>, css: &str) -> Result<()> {
+        let file = match site {
+            Some(site) => {
+                let valid = !site.is_empty() && site.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+                if !valid {
+                    return Err(anyhow!("'{}' isn't a site name like example.com", site));
+                }
+                format!("{}.css", site)
+            }
+            None => GLOBAL_FILE.to_string(),
+        };
+        let path = self.dir.join(&file);
+        if css.trim().is_empty() {
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| anyhow!("Failed to remove {}: {}", file, e))?;
+            }
+        } else {
+            std::fs::write(&path, css).map_err(|e| anyhow!("Failed to save {}: {}", file, e))?;
+        }
+
+        match site {
+            Some(site) if css.trim().is_empty() => {
+                self.sites.remove(site);
+            }
+            Some(site) => {
+                self.sites.insert(site.to_string(), css.to_string());
+            }
+            None => self.global = css.to_string(),
+        }
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Sites that have a stylesheet, in order
+    pub fn sites(&self) -> impl Iterator<Item = &str> {
+        self.sites.keys().map(String::as_str)
+    }
+
+    /// The global stylesheet followed by those of the site and its parent domains, most specific last
+    pub fn css_for_url(&self, url: &str) -> String {
+        let mut css = self.global.clone();
+        let Some(site) = site_for_url(url) else {
+            return css;
+        };
+        let parents: Vec<&str> = site.match_indices('.').map(|(i, _)| &site[i + 1..]).collect();
+        for domain in parents.into_iter().rev().chain([site.as_str()]) {
+            if let Some(sheet) = self.sites.get(domain) {
+                css.push('\n');
+                css.push_str(sheet);
+            }
+        }
+        css
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Ask the settings page to open the editor for `site`
+    pub fn request_edit(&mut self, site: String) {
+        self.edit_request = Some(site);
+    }
+
+    pub fn take_edit_request(&mut self) -> Option<String> {
+        self.edit_request.take()
+    }
+}
+
+/// Site a page's styles are kept under: its host without a leading `www.`; None for pages without a host
+pub fn site_for_url(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_for_site_and_parent_domains() {
+        let data_dir = std::env::temp_dir().join(format!("neon-user-styles-{}", std::process::id()));
+        let mut styles = UserStyles::new(&data_dir);
+        styles.set(None, "body { margin: 0 }").unwrap();
+        styles.set(Some("example.com"), "p { color: red }").unwrap();
+        styles.set(Some("docs.example.com"), "p { color: blue }").unwrap();
+        assert!(styles.set(Some("../evil"), "p {}").is_err());
+
+        assert_eq!(styles.css_for_url("https://docs.example.com/a"), "body { margin: 0 }\np { color: red }\np { color: blue }");
+        assert_eq!(UserStyles::new(&data_dir).css_for_url("https://www.example.com/"), "body { margin: 0 }\np { color: red }");
+        assert_eq!(styles.css_for_url("neon://settings"), "body { margin: 0 }");
+
+        styles.set(Some("example.com"), "").unwrap();
+        assert_eq!(UserStyles::new(&data_dir).sites().collect::<Vec<_>>(), ["docs.example.com"]);
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+}
@@ -0,0 +1,110 @@
+// End-to-end encryption for sync: a key derived from the user's passphrase seals everything before it leaves the device
+use anyhow::{Result, anyhow};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+use std::path::Path;
+
+// Marks an encrypted sync file and the format it's in
+const MAGIC: &[u8] = b"NSYNC1";
+const PBKDF2_ITERATIONS: u32 = 200_000;
+pub const SALT_LEN: usize = 16;
+
+#[derive(Clone)]
+pub struct SyncKey {
+    bytes: [u8; 32],
+}
+
+impl SyncKey {
+    /// Stretch `passphrase` into a key; every device given the same passphrase and salt gets the same key
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut bytes = [0u8; 32];
+        ring::pbkdf2::derive(
+            ring::pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations"),
+            salt,
+            passphrase.as_bytes(),
+            &mut bytes,
+        );
+        Self { bytes }
+    }
+
+    /// The key saved on this device, so syncing doesn't ask for the passphrase every time
+    pub fn load(path: &Path) -> Option<Self> {
+        let bytes = from_hex(std::fs::read_to_string(path).ok()?.trim())?;
+        Some(Self { bytes: bytes.try_into().ok()? })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, to_hex(&self.bytes)).map_err(|e| anyhow!("Failed to save the sync key: {}", e))?;
+        // Only this user may read it
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+        }
+        Ok(())
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow!("No randomness available"))?;
+        let mut sealed = plaintext.to_vec();
+        self.aead()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(MAGIC), &mut sealed)
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        Ok([MAGIC, &nonce, &sealed].concat())
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let rest = data.strip_prefix(MAGIC).ok_or_else(|| anyhow!("Not a NeonSearch sync file"))?;
+        if rest.len() < NONCE_LEN {
+            return Err(anyhow!("The sync file is truncated"));
+        }
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("The sync file is truncated"))?;
+        let mut sealed = sealed.to_vec();
+        let plaintext = self.aead()?
+            .open_in_place(nonce, Aad::from(MAGIC), &mut sealed)
+            .map_err(|_| anyhow!("Wrong passphrase, or the sync file was tampered with"))?;
+        Ok(plaintext.to_vec())
+    }
+
+    fn aead(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&AES_256_GCM, &self.bytes).map_err(|_| anyhow!("Invalid sync key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+pub fn random_salt() -> Result<[u8; SALT_LEN]> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new().fill(&mut salt).map_err(|_| anyhow!("No randomness available"))?;
+    Ok(salt)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let salt = random_salt().unwrap();
+        let key = SyncKey::derive("correct horse battery staple", &salt);
+        let sealed = key.encrypt(b"bookmarks").unwrap();
+        assert!(!sealed.windows(9).any(|w| w == b"bookmarks"));
+        assert_eq!(SyncKey::derive("correct horse battery staple", &salt).decrypt(&sealed).unwrap(), b"bookmarks");
+        assert!(SyncKey::derive("wrong", &salt).decrypt(&sealed).is_err());
+        assert_eq!(from_hex(&to_hex(&salt)).unwrap(), salt);
+    }
+}
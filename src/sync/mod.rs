@@ -0,0 +1,337 @@
+// Sync: each device uploads an encrypted snapshot of its bookmarks, settings, open tabs and recent history to a
+// target the user picks, and merges in the snapshots of the others. Only the passphrase opens them.
+pub mod crypto;
+pub mod snapshot;
+pub mod target;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::storage::preferences::SyncSettings;
+use crate::storage::{BookmarkStore, HistoryDatabase, Preferences};
+use crypto::SyncKey;
+use snapshot::{DeviceSnapshot, SyncedTab, SyncedVisit};
+
+pub use snapshot::plan_merge;
+pub use target::SyncTarget;
+
+// Salt and a passphrase check, shared by every device syncing to the target
+const META_FILE: &str = "neonsync.json";
+const DEVICES_DIR: &str = "devices";
+// Plaintext sealed into the meta file, to tell a wrong passphrase apart from a damaged device file
+const PASSPHRASE_CHECK: &[u8] = b"neonsync";
+// History older than this, or past this many visits, stays on the device
+const HISTORY_DAYS: i64 = 30;
+const HISTORY_LIMIT: usize = 5000;
+// Wait before trying again after a failed sync
+const RETRY_AFTER: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncMeta {
+    version: u32,
+    salt: String,
+    check: String,
+}
+
+// Kept on this device to notice when its settings change between syncs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    settings_hash: String,
+    settings_changed_at: Option<DateTime<Utc>>,
+}
+
+impl SyncState {
+    fn path() -> PathBuf {
+        crate::storage::data_dir().join("sync_state.json")
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        std::fs::write(Self::path(), serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to save the sync state: {}", e))
+    }
+}
+
+/// Another device syncing to the same target, as of its last upload
+#[derive(Debug, Clone)]
+pub struct RemoteDevice {
+    pub name: String,
+    pub uploaded_at: DateTime<Utc>,
+    pub tabs: Vec<SyncedTab>,
+}
+
+/// The outcome of one sync
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub devices: Vec<RemoteDevice>,
+    pub bookmarks_added: usize,
+    pub visits_added: usize,
+    /// The device whose newer settings were taken
+    pub settings_from: Option<String>,
+    /// Device files that couldn't be read; the rest still synced
+    pub errors: Vec<String>,
+}
+
+pub struct SyncEngine {
+    running: Option<Receiver<Result<SyncReport>>>,
+    last_attempt: Option<Instant>,
+    last_success: Option<DateTime<Local>>,
+    last_error: Option<String>,
+    report: Option<SyncReport>,
+    // A sync asked for from neon://sync, started on the next tick
+    requested: bool,
+    // Passphrase entered while setting up, used by the next sync to derive and save the key
+    passphrase: Option<String>,
+}
+
+static SHARED_SYNC: OnceLock<Arc<Mutex<SyncEngine>>> = OnceLock::new();
+
+impl SyncEngine {
+    fn new() -> Self {
+        Self { running: None, last_attempt: None, last_success: None, last_error: None, report: None, requested: false, passphrase: None }
+    }
+
+    /// Process-wide engine shared by the browser window and neon://sync
+    pub fn shared() -> Arc<Mutex<SyncEngine>> {
+        SHARED_SYNC.get_or_init(|| Arc::new(Mutex::new(SyncEngine::new()))).clone()
+    }
+
+    fn key_path() -> PathBuf {
+        crate::storage::data_dir().join("sync.key")
+    }
+
+    /// Whether a target is chosen and this device holds the key for it
+    pub fn is_set_up() -> bool {
+        Preferences::current().sync.target != SyncTarget::None && Self::key_path().exists()
+    }
+
+    /// Sync with the target in the preferences, deriving the key from `passphrase`
+    pub fn connect(&mut self, passphrase: String) {
+        self.passphrase = Some(passphrase);
+        self.last_error = None;
+        self.requested = true;
+    }
+
+    pub fn request_sync(&mut self) {
+        self.requested = true;
+    }
+
+    /// Stop syncing and forget the key; what was synced stays on every device
+    pub fn disconnect(&mut self) {
+        let _ = std::fs::remove_file(Self::key_path());
+        let _ = std::fs::remove_file(SyncState::path());
+        Preferences::update(|prefs| prefs.sync.target = SyncTarget::None);
+        self.report = None;
+        self.last_error = None;
+        self.last_success = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn last_success(&self) -> Option<DateTime<Local>> {
+        self.last_success
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn report(&self) -> Option<&SyncReport> {
+        self.report.as_ref()
+    }
+
+    /// Pick up a finished sync and start the next one when it's due or was asked for. `tabs` lists the open tabs
+    pub fn tick(&mut self, tabs: impl FnOnce() -> Vec<SyncedTab>) {
+        if let Some(receiver) = &self.running {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    self.running = None;
+                    match result {
+                        Ok(report) => {
+                            self.last_success = Some(Local::now());
+                            self.last_error = None;
+                            self.report = Some(report);
+                        }
+                        Err(e) => self.last_error = Some(e.to_string()),
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.running = None;
+                    self.last_error = Some("Sync stopped unexpectedly".to_string());
+                }
+            }
+        }
+
+        let settings = Preferences::current().sync.clone();
+        if settings.target == SyncTarget::None {
+            self.requested = false;
+            return;
+        }
+        let interval = if self.last_error.is_some() {
+            RETRY_AFTER
+        } else {
+            Duration::from_secs(settings.interval_minutes.max(1) as u64 * 60)
+        };
+        let due = self.last_attempt.is_none_or(|last| last.elapsed() >= interval)
+            && (self.passphrase.is_some() || Self::key_path().exists());
+        if !self.requested && !due {
+            return;
+        }
+        self.requested = false;
+        self.last_attempt = Some(Instant::now());
+
+        let passphrase = self.passphrase.take();
+        let tabs = if settings.tabs { tabs() } else { Vec::new() };
+        let (sender, receiver) = mpsc::channel();
+        // Network requests block on the shared executor, so this can't run on one of its threads
+        std::thread::spawn(move || {
+            let _ = sender.send(run_sync(&settings, passphrase.as_deref(), tabs));
+        });
+        self.running = Some(receiver);
+    }
+}
+
+/// Preferences as synced: everything but the device's own sync setup
+fn settings_json(prefs: &Preferences) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(prefs)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("sync");
+    }
+    Ok(value)
+}
+
+fn hash(value: &serde_json::Value) -> String {
+    crypto::to_hex(&Sha256::digest(value.to_string().as_bytes()))
+}
+
+// The key for the target: derived from a newly entered passphrase, or the one saved here before
+fn unlock(remote: &dyn target::Remote, passphrase: Option<&str>) -> Result<SyncKey> {
+    let Some(passphrase) = passphrase else {
+        return SyncKey::load(&SyncEngine::key_path()).ok_or_else(|| anyhow!("Enter the sync passphrase to set up sync again"));
+    };
+    let key = match remote.get(META_FILE)? {
+        Some(data) => {
+            let meta: SyncMeta = serde_json::from_slice(&data).context("The sync target's neonsync.json is damaged")?;
+            let salt = crypto::from_hex(&meta.salt).ok_or_else(|| anyhow!("The sync target's neonsync.json is damaged"))?;
+            let key = SyncKey::derive(passphrase, &salt);
+            let check = crypto::from_hex(&meta.check).unwrap_or_default();
+            if key.decrypt(&check).ok().as_deref() != Some(PASSPHRASE_CHECK) {
+                return Err(anyhow!("That isn't the passphrase this sync target was set up with"));
+            }
+            key
+        }
+        // The first device to sync sets the target up
+        None => {
+            let salt = crypto::random_salt()?;
+            let key = SyncKey::derive(passphrase, &salt);
+            let meta = SyncMeta { version: 1, salt: crypto::to_hex(&salt), check: crypto::to_hex(&key.encrypt(PASSPHRASE_CHECK)?) };
+            remote.put(META_FILE, &serde_json::to_vec_pretty(&meta)?)?;
+            key
+        }
+    };
+    key.save(&SyncEngine::key_path())?;
+    Ok(key)
+}
+
+fn run_sync(settings: &SyncSettings, passphrase: Option<&str>, tabs: Vec<SyncedTab>) -> Result<SyncReport> {
+    let remote = settings.target.open()?;
+    let key = unlock(remote.as_ref(), passphrase)?;
+    let own_file = format!("{}.bin", settings.device_id);
+
+    // This device's side
+    let mut state = SyncState::load();
+    let prefs_json = settings_json(&Preferences::current())?;
+    let prefs_hash = hash(&prefs_json);
+    if state.settings_hash != prefs_hash {
+        // A device syncing for the first time takes the others' settings rather than imposing its defaults
+        state.settings_changed_at = (!state.settings_hash.is_empty()).then(Utc::now);
+        state.settings_hash = prefs_hash;
+    }
+    let history = match (settings.history, HistoryDatabase::shared()) {
+        (true, Some(db)) => {
+            let since = Utc::now() - ChronoDuration::days(HISTORY_DAYS);
+            let visits = db.get_visits()?;
+            let recent: Vec<SyncedVisit> = visits.iter().filter(|v| v.visited_at >= since).map(SyncedVisit::from).collect();
+            recent[recent.len().saturating_sub(HISTORY_LIMIT)..].to_vec()
+        }
+        _ => Vec::new(),
+    };
+    let bookmarks = || BookmarkStore::shared().lock().map(|store| store.list().to_vec()).unwrap_or_default();
+    let mut local = DeviceSnapshot {
+        device_id: settings.device_id.clone(),
+        device_name: settings.device_name.clone(),
+        uploaded_at: Utc::now(),
+        bookmarks: if settings.bookmarks { bookmarks() } else { Vec::new() },
+        settings: settings.settings.then_some(prefs_json),
+        settings_changed_at: state.settings_changed_at,
+        tabs,
+        history,
+    };
+
+    // Everyone else's
+    let mut report = SyncReport::default();
+    let mut remotes = Vec::new();
+    for file in remote.list(DEVICES_DIR)? {
+        if file == own_file || !file.ends_with(".bin") {
+            continue;
+        }
+        let read = remote.get(&format!("{}/{}", DEVICES_DIR, file))
+            .and_then(|data| data.ok_or_else(|| anyhow!("it disappeared")))
+            .and_then(|data| key.decrypt(&data))
+            .and_then(|plain| Ok(serde_json::from_slice::<DeviceSnapshot>(&plain)?));
+        match read {
+            Ok(snapshot) => remotes.push(snapshot),
+            Err(e) => report.errors.push(format!("{}: {}", file, e)),
+        }
+    }
+
+    let plan = plan_merge(&local, &remotes);
+    if settings.bookmarks && !plan.bookmarks.is_empty() {
+        let store = BookmarkStore::shared();
+        let mut store = store.lock().map_err(|_| anyhow!("Bookmarks are unavailable"))?;
+        report.bookmarks_added = store.add_all(plan.bookmarks)?;
+        local.bookmarks = store.list().to_vec();
+    }
+    if settings.history && !plan.visits.is_empty() {
+        if let Some(db) = HistoryDatabase::shared() {
+            report.visits_added = db.import_visits(&plan.visits)?;
+        }
+    }
+    if let (true, Some(newer)) = (settings.settings, plan.settings) {
+        let mut incoming: Preferences = serde_json::from_value(newer.value.clone()).context("Another device's settings couldn't be read")?;
+        Preferences::update(|prefs| {
+            incoming.sync = prefs.sync.clone();
+            *prefs = incoming;
+        });
+        crate::ui::i18n::init(Preferences::current().language.as_deref());
+        // Taken as they were, so this device doesn't hand them back as its own newer change
+        state.settings_hash = hash(&newer.value);
+        state.settings_changed_at = newer.changed_at;
+        local.settings = Some(newer.value);
+        local.settings_changed_at = newer.changed_at;
+        report.settings_from = Some(newer.device);
+    }
+
+    remote.put(&format!("{}/{}", DEVICES_DIR, own_file), &key.encrypt(&serde_json::to_vec(&local)?)?)?;
+    state.save()?;
+
+    report.devices = remotes.into_iter()
+        .map(|r| RemoteDevice { name: r.device_name, uploaded_at: r.uploaded_at, tabs: r.tabs })
+        .collect();
+    report.devices.sort_by_key(|device| std::cmp::Reverse(device.uploaded_at));
+    Ok(report)
+}
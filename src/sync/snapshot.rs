@@ -0,0 +1,138 @@
+// What each device uploads, and how the uploads of other devices are merged into this one
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::storage::{StoredBookmark, VisitRecord};
+
+/// An open tab, as other devices see it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedTab {
+    pub url: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedVisit {
+    pub url: String,
+    pub title: String,
+    pub visited_at: DateTime<Utc>,
+}
+
+impl From<&VisitRecord> for SyncedVisit {
+    fn from(visit: &VisitRecord) -> Self {
+        Self { url: visit.url.clone(), title: visit.title.clone(), visited_at: visit.visited_at }
+    }
+}
+
+impl From<&SyncedVisit> for VisitRecord {
+    fn from(visit: &SyncedVisit) -> Self {
+        Self { url: visit.url.clone(), title: visit.title.clone(), visited_at: visit.visited_at }
+    }
+}
+
+/// Everything one device shares; each device writes only its own, so uploads never overwrite each other
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSnapshot {
+    pub device_id: String,
+    pub device_name: String,
+    pub uploaded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub bookmarks: Vec<StoredBookmark>,
+    /// Preferences as JSON, without the device's own sync setup; None when settings aren't synced
+    #[serde(default)]
+    pub settings: Option<serde_json::Value>,
+    /// When the settings were last changed on the device, which decides between conflicting changes
+    #[serde(default)]
+    pub settings_changed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tabs: Vec<SyncedTab>,
+    #[serde(default)]
+    pub history: Vec<SyncedVisit>,
+}
+
+/// Settings another device changed after they last changed here
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewerSettings {
+    pub value: serde_json::Value,
+    pub device: String,
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+/// What merging other devices' snapshots into this one changes here
+#[derive(Debug, Default)]
+pub struct MergePlan {
+    /// Bookmarks only other devices have; both sides' additions are kept
+    pub bookmarks: Vec<StoredBookmark>,
+    pub visits: Vec<VisitRecord>,
+    pub settings: Option<NewerSettings>,
+}
+
+/// Merge `remotes` into `local`: bookmarks and history are unions, and the settings changed most recently win
+pub fn plan_merge(local: &DeviceSnapshot, remotes: &[DeviceSnapshot]) -> MergePlan {
+    let mut plan = MergePlan::default();
+    let mut seen: HashSet<(&str, DateTime<Utc>)> = local.history.iter().map(|v| (v.url.as_str(), v.visited_at)).collect();
+    for remote in remotes.iter().filter(|remote| remote.device_id != local.device_id) {
+        for bookmark in &remote.bookmarks {
+            let known = |b: &StoredBookmark| b.url == bookmark.url && b.folder == bookmark.folder;
+            if !local.bookmarks.iter().any(known) && !plan.bookmarks.iter().any(known) {
+                plan.bookmarks.push(bookmark.clone());
+            }
+        }
+        for visit in &remote.history {
+            if seen.insert((visit.url.as_str(), visit.visited_at)) {
+                plan.visits.push(visit.into());
+            }
+        }
+    }
+
+    let newest = remotes.iter()
+        .filter(|remote| remote.device_id != local.device_id && remote.settings.is_some())
+        .max_by_key(|remote| remote.settings_changed_at);
+    if let Some(remote) = newest {
+        let newer = remote.settings_changed_at > local.settings_changed_at;
+        if newer && remote.settings != local.settings {
+            plan.settings = remote.settings.clone().map(|value| NewerSettings {
+                value,
+                device: remote.device_name.clone(),
+                changed_at: remote.settings_changed_at,
+            });
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn snapshot(id: &str, bookmarks: &[&str], settings: serde_json::Value, changed: i64) -> DeviceSnapshot {
+        let at = |secs| DateTime::from_timestamp(secs, 0).unwrap();
+        DeviceSnapshot {
+            device_id: id.to_string(),
+            device_name: format!("{} laptop", id),
+            uploaded_at: at(changed),
+            bookmarks: bookmarks.iter().map(|url| StoredBookmark { title: url.to_string(), url: url.to_string(), folder: Vec::new(), added_at: at(0) }).collect(),
+            settings: Some(settings),
+            settings_changed_at: Some(at(changed)),
+            tabs: Vec::new(),
+            history: vec![SyncedVisit { url: format!("https://{}.example/", id), title: String::new(), visited_at: at(100) }],
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_both_sides_and_newest_settings() {
+        let local = snapshot("a", &["https://one/", "https://two/"], json!({"theme": "Neon Dark"}), 50);
+        let b = snapshot("b", &["https://two/", "https://three/"], json!({"theme": "Neon Light"}), 40);
+        let c = snapshot("c", &["https://three/"], json!({"theme": "Paper"}), 60);
+
+        let plan = plan_merge(&local, &[b.clone(), c, local.clone()]);
+        assert_eq!(plan.bookmarks.iter().map(|b| b.url.as_str()).collect::<Vec<_>>(), ["https://three/"]);
+        assert_eq!(plan.visits.len(), 2);
+        let settings = plan.settings.unwrap();
+        assert_eq!((settings.value, settings.device.as_str()), (json!({"theme": "Paper"}), "c laptop"));
+
+        // Settings changed here after the other device's change stay as they are
+        assert_eq!(plan_merge(&local, &[b]).settings, None);
+    }
+}
@@ -0,0 +1,253 @@
+// Page translation: the page's visible text goes to a translation service in batches, and each text node is
+// swapped for its translation in place, so the elements around it and their styles stay as they were. Showing the
+// original swaps the text back.
+pub mod provider;
+
+use anyhow::{Result, anyhow};
+use crate::engine::dom::DOMNode;
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::storage::preferences::TranslationSettings;
+
+pub use provider::{TranslationProvider, Translator};
+
+// Characters sent in one request, well under what the services accept
+const MAX_BATCH_CHARS: usize = 4000;
+// Elements whose text isn't shown, or is code that shouldn't be translated
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "noscript", "template", "textarea", "code", "pre", "kbd", "samp"];
+
+/// A text node to translate, found by the child indexes leading to it from the document root
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub path: Vec<usize>,
+    /// The node's text without the whitespace around it
+    pub text: String,
+}
+
+/// The document's text nodes worth translating, in document order. Subtrees marked `translate="no"` or with the
+/// `notranslate` class are left alone
+pub fn text_blocks(dom: &DOMNode) -> Vec<TextBlock> {
+    let mut blocks = Vec::new();
+    collect_blocks(dom, &mut Vec::new(), &mut blocks);
+    blocks
+}
+
+fn collect_blocks(node: &DOMNode, path: &mut Vec<usize>, blocks: &mut Vec<TextBlock>) {
+    match node {
+        DOMNode::Element { tag_name, attributes, children } => {
+            let excluded = SKIPPED_ELEMENTS.contains(&tag_name.to_ascii_lowercase().as_str())
+                || attributes.get("translate").is_some_and(|value| value.eq_ignore_ascii_case("no"))
+                || attributes.get("class").is_some_and(|class| class.split_whitespace().any(|name| name == "notranslate"));
+            if excluded {
+                return;
+            }
+            for (index, child) in children.iter().enumerate() {
+                path.push(index);
+                collect_blocks(child, path, blocks);
+                path.pop();
+            }
+        }
+        DOMNode::Text(text) => {
+            let text = text.trim();
+            if text.chars().any(char::is_alphabetic) {
+                blocks.push(TextBlock { path: path.clone(), text: text.to_string() });
+            }
+        }
+        DOMNode::Comment(_) => {}
+    }
+}
+
+/// The language the page says it's written in, from `<html lang>`, as a primary subtag like "de"
+pub fn page_language(dom: &DOMNode) -> Option<String> {
+    // The parser's root stands in for the document, with the page's own <html> element inside it
+    let DOMNode::Element { children, .. } = dom else { return None };
+    let html = children.iter().find(|child| child.tag_name().is_some_and(|tag| tag.eq_ignore_ascii_case("html")));
+    let lang = html.and_then(|html| html.get_attribute("lang")).or_else(|| dom.get_attribute("lang"))?;
+    let lang = lang.split(['-', '_']).next()?.trim().to_ascii_lowercase();
+    (!lang.is_empty()).then_some(lang)
+}
+
+/// A copy of `dom` with each block's text swapped from `from` to `to`, keeping the whitespace around it. Nodes
+/// whose text changed since it was read are left as they are
+pub fn replace_text(dom: &DOMNode, blocks: &[TextBlock], from: &[String], to: &[String]) -> DOMNode {
+    let mut updated = dom.clone();
+    for ((block, from), to) in blocks.iter().zip(from).zip(to) {
+        let Some(DOMNode::Text(text)) = node_at_mut(&mut updated, &block.path) else { continue };
+        if text.trim() != from {
+            continue;
+        }
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        *text = format!("{}{}{}", leading, to, trailing);
+    }
+    updated
+}
+
+fn node_at_mut<'a>(node: &'a mut DOMNode, path: &[usize]) -> Option<&'a mut DOMNode> {
+    path.iter().try_fold(node, |node, &index| match node {
+        DOMNode::Element { children, .. } => children.get_mut(index),
+        _ => None,
+    })
+}
+
+// Consecutive runs of texts that together stay under the batch size; a longer text goes alone
+fn batches(texts: &[String]) -> Vec<&[String]> {
+    let mut batches = Vec::new();
+    let (mut start, mut chars) = (0, 0);
+    for (index, text) in texts.iter().enumerate() {
+        if index > start && chars + text.len() > MAX_BATCH_CHARS {
+            batches.push(&texts[start..index]);
+            (start, chars) = (index, 0);
+        }
+        chars += text.len();
+    }
+    if start < texts.len() {
+        batches.push(&texts[start..]);
+    }
+    batches
+}
+
+/// Translate `texts` a batch at a time
+pub async fn translate_all(translator: &dyn Translator, texts: &[String], source: Option<&str>, target: &str) -> Result<Vec<String>> {
+    let mut translated = Vec::with_capacity(texts.len());
+    for batch in batches(texts) {
+        let answer = translator.translate(batch, source, target).await?;
+        if answer.len() != batch.len() {
+            return Err(anyhow!("The translation service sent {} translations for {} texts", answer.len(), batch.len()));
+        }
+        translated.extend(answer);
+    }
+    Ok(translated)
+}
+
+enum TranslationState {
+    Pending(TaskHandle<Result<Vec<String>>>),
+    Shown(Vec<String>),
+    Failed(String),
+}
+
+/// One page's translation, from asking the service until the original is shown again
+pub struct PageTranslation {
+    /// Language code translated into
+    pub target: String,
+    /// The language the page says it's in, if it does
+    pub source: Option<String>,
+    blocks: Vec<TextBlock>,
+    state: TranslationState,
+}
+
+impl PageTranslation {
+    /// Send the document's text to the configured service
+    pub fn start(dom: &DOMNode, settings: &TranslationSettings) -> Self {
+        let blocks = text_blocks(dom);
+        let source = page_language(dom);
+        let target = settings.target_language();
+        let state = if blocks.is_empty() {
+            TranslationState::Failed("There's no text on this page to translate".to_string())
+        } else {
+            match settings.provider.open() {
+                Ok(translator) => {
+                    let texts: Vec<String> = blocks.iter().map(|block| block.text.clone()).collect();
+                    let (source, target) = (source.clone(), target.clone());
+                    log::info!("Translating {} text blocks into {}", texts.len(), target);
+                    TranslationState::Pending(Executor::shared().spawn(TaskKind::Network, async move {
+                        translate_all(translator.as_ref(), &texts, source.as_deref(), &target).await
+                    }))
+                }
+                Err(e) => TranslationState::Failed(e.to_string()),
+            }
+        };
+        Self { target, source, blocks, state }
+    }
+
+    /// Once the translations have arrived, `dom` with them in place; None while waiting and after
+    pub fn poll(&mut self, dom: &DOMNode) -> Option<DOMNode> {
+        let TranslationState::Pending(task) = &mut self.state else { return None };
+        match task.try_take()? {
+            Ok(Ok(translated)) => {
+                let originals: Vec<String> = self.blocks.iter().map(|block| block.text.clone()).collect();
+                let updated = replace_text(dom, &self.blocks, &originals, &translated);
+                self.state = TranslationState::Shown(translated);
+                Some(updated)
+            }
+            Ok(Err(e)) => {
+                log::warn!("Translation failed: {:#}", e);
+                self.state = TranslationState::Failed(format!("{:#}", e));
+                None
+            }
+            Err(e) => {
+                self.state = TranslationState::Failed(format!("The translation stopped: {}", e));
+                None
+            }
+        }
+    }
+
+    /// `dom` with the original text back; unchanged unless the translation is shown
+    pub fn revert(&self, dom: &DOMNode) -> DOMNode {
+        match &self.state {
+            TranslationState::Shown(translated) => {
+                let originals: Vec<String> = self.blocks.iter().map(|block| block.text.clone()).collect();
+                replace_text(dom, &self.blocks, translated, &originals)
+            }
+            _ => dom.clone(),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state, TranslationState::Pending(_))
+    }
+
+    pub fn is_shown(&self) -> bool {
+        matches!(self.state, TranslationState::Shown(_))
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match &self.state {
+            TranslationState::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for PageTranslation {
+    fn drop(&mut self) {
+        if let TranslationState::Pending(task) = &self.state {
+            task.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html_parser;
+
+    #[test]
+    fn test_translate_and_revert_text_nodes() {
+        let dom = html_parser::parse(
+            "<html lang=\"de-AT\"><body><h1>Hallo Welt</h1><p> Guten <b>Tag</b> </p>\
+             <pre>let x = 1;</pre><p translate=\"no\">NeonSearch</p><script>var a;</script><p>42</p></body></html>"
+        );
+        assert_eq!(page_language(&dom).as_deref(), Some("de"));
+        let blocks = text_blocks(&dom);
+        let texts: Vec<&str> = blocks.iter().map(|block| block.text.as_str()).collect();
+        assert_eq!(texts, ["Hallo Welt", "Guten", "Tag"]);
+
+        let originals: Vec<String> = blocks.iter().map(|block| block.text.clone()).collect();
+        let translated = vec!["Hello world".to_string(), "Good".to_string(), "day".to_string()];
+        let shown = replace_text(&dom, &blocks, &originals, &translated);
+        let html = shown.to_html();
+        assert!(html.contains("<h1>Hello world</h1>") && html.contains("<p> Good <b>day</b></p>"), "{}", html);
+        assert!(html.contains("<pre>let x = 1;</pre>"));
+        assert_eq!(replace_text(&shown, &blocks, &translated, &originals).to_html(), dom.to_html());
+    }
+
+    #[test]
+    fn test_batches_stay_under_the_limit() {
+        let texts: Vec<String> = (0..10).map(|_| "x".repeat(MAX_BATCH_CHARS / 4)).collect();
+        let sizes: Vec<usize> = batches(&texts).iter().map(|batch| batch.len()).collect();
+        assert_eq!(sizes, [4, 4, 2]);
+        let long = vec!["x".repeat(MAX_BATCH_CHARS * 2), "y".to_string()];
+        assert_eq!(batches(&long).len(), 2);
+        assert!(batches(&[]).is_empty());
+    }
+}
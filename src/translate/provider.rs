@@ -0,0 +1,150 @@
+// Services that translate a page's text: a LibreTranslate server, self-hosted by default, or DeepL's API
+use anyhow::{Context, Result, anyhow};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Where a LibreTranslate server usually listens when run on this computer
+pub const DEFAULT_LIBRETRANSLATE_URL: &str = "http://localhost:5000";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TranslationProvider {
+    /// A LibreTranslate server; public ones want an API key, self-hosted ones usually don't
+    LibreTranslate { url: String, api_key: String },
+    /// DeepL's API; keys ending in ":fx" are for its free plan, which has a host of its own
+    DeepL { api_key: String },
+}
+
+impl Default for TranslationProvider {
+    fn default() -> Self {
+        TranslationProvider::LibreTranslate { url: DEFAULT_LIBRETRANSLATE_URL.to_string(), api_key: String::new() }
+    }
+}
+
+impl TranslationProvider {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TranslationProvider::LibreTranslate { .. } => "LibreTranslate",
+            TranslationProvider::DeepL { .. } => "DeepL",
+        }
+    }
+
+    pub fn open(&self) -> Result<Box<dyn Translator>> {
+        Ok(match self {
+            TranslationProvider::LibreTranslate { url, api_key } => Box::new(LibreTranslate {
+                endpoint: format!("{}/translate", url.trim_end_matches('/')),
+                api_key: api_key.clone(),
+                client: client()?,
+            }),
+            TranslationProvider::DeepL { api_key } if api_key.is_empty() => {
+                return Err(anyhow!("DeepL needs an API key, set under Settings > General"));
+            }
+            TranslationProvider::DeepL { api_key } => Box::new(DeepL {
+                endpoint: if api_key.ends_with(":fx") {
+                    "https://api-free.deepl.com/v2/translate"
+                } else {
+                    "https://api.deepl.com/v2/translate"
+                },
+                api_key: api_key.clone(),
+                client: client()?,
+            }),
+        })
+    }
+}
+
+/// Translates batches of plain text
+pub trait Translator: Send + Sync {
+    /// Translations of `texts`, in the same order. `source` is a language code, or None to have it detected
+    fn translate<'a>(&'a self, texts: &'a [String], source: Option<&'a str>, target: &'a str) -> BoxFuture<'a, Result<Vec<String>>>;
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent("NeonSearch/0.1.0 (Custom Browser by NeonDev)")
+        .build()?)
+}
+
+// Both services answer errors with a JSON object holding a message
+async fn check(response: reqwest::Response, service: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body).ok()
+        .and_then(|value| value.get("error").or_else(|| value.get("message")).and_then(|m| m.as_str().map(str::to_string)))
+        .unwrap_or(body);
+    Err(anyhow!("{} answered {}: {}", service, status.as_u16(), message.trim()))
+}
+
+struct LibreTranslate {
+    endpoint: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateAnswer {
+    #[serde(rename = "translatedText")]
+    translated_text: Vec<String>,
+}
+
+impl Translator for LibreTranslate {
+    fn translate<'a>(&'a self, texts: &'a [String], source: Option<&'a str>, target: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let mut body = serde_json::json!({
+                "q": texts,
+                "source": source.unwrap_or("auto"),
+                "target": target,
+                "format": "text",
+            });
+            if !self.api_key.is_empty() {
+                body["api_key"] = self.api_key.clone().into();
+            }
+            let response = self.client.post(&self.endpoint).json(&body).send().await
+                .with_context(|| format!("Couldn't reach the LibreTranslate server at {}", self.endpoint))?;
+            let answer: LibreTranslateAnswer = check(response, "LibreTranslate").await?.json().await
+                .context("LibreTranslate's answer wasn't a list of translations")?;
+            Ok(answer.translated_text)
+        })
+    }
+}
+
+struct DeepL {
+    endpoint: &'static str,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct DeepLAnswer {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+impl Translator for DeepL {
+    fn translate<'a>(&'a self, texts: &'a [String], source: Option<&'a str>, target: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            // DeepL wants upper-case codes, and picks the language itself when there's no source_lang
+            let mut body = serde_json::json!({ "text": texts, "target_lang": target.to_uppercase() });
+            if let Some(source) = source {
+                body["source_lang"] = source.to_uppercase().into();
+            }
+            let response = self.client.post(self.endpoint)
+                .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+                .json(&body)
+                .send().await
+                .context("Couldn't reach DeepL")?;
+            let answer: DeepLAnswer = check(response, "DeepL").await?.json().await
+                .context("DeepL's answer wasn't a list of translations")?;
+            Ok(answer.translations.into_iter().map(|translation| translation.text).collect())
+        })
+    }
+}
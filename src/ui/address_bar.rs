@@ -1,6 +1,22 @@
 use eframe::egui;
+use crate::pages::components;
+use crate::security::{CheckStatus, SecurityCategory, SecurityLevel, SecurityReport};
+use crate::security::certificate::TlsInfo;
+use crate::storage::{HistoryDatabase, Preferences};
 use crate::ui::{NeonTheme, NeonIcons};
 
+// Most history entries offered while typing
+const MAX_HISTORY_SUGGESTIONS: usize = 5;
+
+/// What the padlock and its popover show about the active page
+pub struct SiteInfo<'a> {
+    pub report: &'a SecurityReport,
+    /// The document's connection, when it came over HTTPS
+    pub tls: Option<&'a TlsInfo>,
+    /// Cookies the site and its subdomains hold
+    pub cookies: usize,
+}
+
 // Editing lifecycle states for the address bar
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum EditState {
@@ -20,6 +36,13 @@ pub struct AddressBar {
     show_suggestions: bool,
     should_focus: bool,
     state: EditState,
+    // The last web page that was the top suggestion, and whether the app has picked it up for prefetching
+    prefetch_candidate: Option<String>,
+    prefetch_taken: bool,
+    // Set when the padlock popover changed the site's JavaScript permission
+    javascript_changed: bool,
+    // Set when it blocked or allowed the site's images or cookies, which takes a reload to show
+    site_settings_changed: bool,
 }
 
 impl AddressBar {
@@ -33,99 +56,81 @@ impl AddressBar {
             show_suggestions: false,
             should_focus: false,
             state: EditState::Idle,
+            prefetch_candidate: None,
+            prefetch_taken: false,
+            javascript_changed: false,
+            site_settings_changed: false,
         }
     }
     
-    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+    /// `site` describes the active page, shown when the padlock is clicked
+    pub fn show(&mut self, ui: &mut egui::Ui, site: Option<SiteInfo<'_>>) -> Option<String> {
         let mut navigate_to = None;
         
         // Modern address bar with enhanced styling
         egui::Frame::none()
-            .fill(NeonTheme::ELEVATED_BG)
+            .fill(NeonTheme::elevated_bg())
             .rounding(egui::Rounding::same(25.0)) // Pill shape
-            .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
+            .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
             .inner_margin(egui::Margin::symmetric(16.0, 8.0))
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
                     ui.spacing_mut().item_spacing.x = 12.0;
                     
                     // Enhanced security indicator
-                    let (icon, tooltip, color) = if self.current_url.starts_with("https://") {
-                        (NeonIcons::LOCK, "Secure HTTPS connection", NeonTheme::SUCCESS_COLOR)
-                    } else if self.current_url.starts_with("http://") {
-                        (NeonIcons::WARNING, "Insecure HTTP connection", NeonTheme::WARNING_COLOR)
-                    } else if self.current_url.starts_with("about:") {
-                        (NeonIcons::HOUSE, "Browser page", NeonTheme::NEON_BLUE)
-                    } else {
-                        (NeonIcons::GLOBE, "Local or custom scheme", NeonTheme::MUTED_TEXT)
+                    let (icon, tooltip, color) = match site.as_ref().map(|site| site.report) {
+                        Some(report) if report.secure_connection => security_indicator(report),
+                        _ if self.current_url.starts_with("https://") => {
+                            (NeonIcons::LOCK, "Secure HTTPS connection", NeonTheme::success_color())
+                        }
+                        _ if self.current_url.starts_with("http://") => {
+                            (NeonIcons::WARNING, "Insecure HTTP connection", NeonTheme::warning_color())
+                        }
+                        _ if self.current_url.starts_with("about:") => (NeonIcons::HOUSE, "Browser page", NeonTheme::neon_blue()),
+                        _ => (NeonIcons::GLOBE, "Local or custom scheme", NeonTheme::muted_text()),
                     };
                     
-                    ui.label(egui::RichText::new(icon).color(color).size(16.0))
-                        .on_hover_text(tooltip);
+                    let tooltip = match &site {
+                        Some(site) => format!("{} · security score {}/100", tooltip, site.report.security_score),
+                        None => tooltip.to_string(),
+                    };
+                    let indicator = ui.add(
+                        egui::Label::new(egui::RichText::new(icon).color(color).size(16.0))
+                            .sense(egui::Sense::click())
+                    )
+                    .on_hover_text(tooltip);
+                    if let Some(site) = &site {
+                        let popup_id = ui.make_persistent_id("security_popup");
+                        if indicator.clicked() {
+                            ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+                        }
+                        egui::popup_below_widget(ui, popup_id, &indicator, egui::PopupCloseBehavior::CloseOnClickOutside, |ui| {
+                            ui.set_min_width(360.0);
+                            self.show_site_info(ui, site, &mut navigate_to);
+                        });
+                    }
             
                     // Modern URL input field
                     let available_width = ui.available_width() - 40.0; // Space for go button
                     
                     let text_edit_id = egui::Id::new("address_bar_input");
+                    let editing = self.should_focus
+                        || self.state != EditState::Idle
+                        || ui.memory(|mem| mem.has_focus(text_edit_id));
                     
-                    // Handle focus request before creating the TextEdit
-                    if self.should_focus {
-                        // When focusing, load committed URL into buffer & select all
-                        self.edit_buffer = self.current_url.clone();
-                        self.staged_input = self.edit_buffer.clone();
-                        self.state = EditState::Editing;
-                        ui.memory_mut(|mem| mem.request_focus(text_edit_id));
-                        self.should_focus = false;
+                    if !editing {
+                        // The formatted address stands in for the text field until it's clicked
+                        let response = self.show_formatted(ui, available_width);
+                        if response.clicked() {
+                            self.focus();
+                            ui.ctx().request_repaint();
+                        }
+                        if let Some(url) = self.context_menu(&response) {
+                            navigate_to = Some(url);
+                        }
+                    } else if let Some(url) = self.show_editor(ui, available_width, text_edit_id) {
+                        navigate_to = Some(url);
                     }
-                    
-                    // Sync edit_buffer with staged_input if we just transitioned to Editing
-                    if self.state == EditState::Idle {
-                        // Ensure buffer shows committed value while idle
-                        self.edit_buffer = self.current_url.clone();
-                    }
-
-                    let text_edit = egui::TextEdit::singleline(&mut self.edit_buffer)
-                        .id(text_edit_id)
-                        .hint_text(format!("{} Search or enter URL...", NeonIcons::MAGNIFYING_GLASS))
-                        .margin(egui::vec2(8.0, 6.0))
-                        .desired_width(available_width);
-                        
-                    let response = ui.add_sized([available_width, 36.0], text_edit);
-            
-            // Always try to focus if clicked
-            if response.clicked() {
-                // Enter editing mode when clicked (if not already)
-                if self.state == EditState::Idle {
-                    self.state = EditState::Editing;
-                    self.edit_buffer = self.current_url.clone();
-                }
-                response.request_focus();
-            }
-            
-            // Handle text changes and focus
-            if response.changed() {
-                if self.state != EditState::Editing {
-                    self.state = EditState::Editing;
-                }
-                self.staged_input = self.edit_buffer.clone();
-                self.update_suggestions();
-            }
-            
-            // Submit on Enter key
-            if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                if self.state == EditState::Editing {
-                    self.state = EditState::PendingCommit;
-                }
-            }
-
-            // Commit navigation after losing focus with PendingCommit, or immediate
-            if self.state == EditState::PendingCommit {
-                // Apply staged_input, process, then leave editing mode
-                self.edit_buffer = self.staged_input.clone();
-                navigate_to = Some(self.process_input());
-                self.show_suggestions = false;
-                self.state = EditState::Idle;
-            }
             
                     // Modern Go button
                     let go_btn = egui::Button::new(
@@ -133,7 +138,7 @@ impl AddressBar {
                             .size(18.0)
                             .color(egui::Color32::WHITE)
                     )
-                    .fill(NeonTheme::NEON_CYAN)
+                    .fill(NeonTheme::neon_cyan())
                     .rounding(egui::Rounding::same(18.0))
                     .stroke(egui::Stroke::NONE);
                     
@@ -153,9 +158,14 @@ impl AddressBar {
             ui.group(|ui| {
                 let mut selected_suggestion = None;
                 for suggestion in &self.suggestions {
-                    if ui.selectable_label(false, suggestion).clicked() {
-                        selected_suggestion = Some(suggestion.clone());
-                    }
+                    ui.horizontal(|ui| {
+                        if suggestion.starts_with("http://") || suggestion.starts_with("https://") {
+                            components::site_icon(ui, suggestion, 14.0);
+                        }
+                        if ui.selectable_label(false, suggestion).clicked() {
+                            selected_suggestion = Some(suggestion.clone());
+                        }
+                    });
                 }
                 if let Some(suggestion) = selected_suggestion {
                     self.edit_buffer = suggestion.clone();
@@ -171,29 +181,159 @@ impl AddressBar {
         navigate_to
     }
     
+    // The editable address, with its full text revealed and selected on focus
+    fn show_editor(&mut self, ui: &mut egui::Ui, width: f32, text_edit_id: egui::Id) -> Option<String> {
+        let mut navigate_to = None;
+        
+        // Handle focus request before creating the TextEdit
+        let select_all = self.should_focus;
+        if self.should_focus {
+            // When focusing, load committed URL into buffer & select all
+            self.edit_buffer = self.current_url.clone();
+            self.staged_input = self.edit_buffer.clone();
+            self.state = EditState::Editing;
+            ui.memory_mut(|mem| mem.request_focus(text_edit_id));
+            self.should_focus = false;
+        }
+        
+        // Sync edit_buffer with staged_input if we just transitioned to Editing
+        if self.state == EditState::Idle {
+            // Ensure buffer shows committed value while idle
+            self.edit_buffer = self.current_url.clone();
+        }
+
+        let text_edit = egui::TextEdit::singleline(&mut self.edit_buffer)
+            .id(text_edit_id)
+            .hint_text(format!("{} Search or enter URL...", NeonIcons::MAGNIFYING_GLASS))
+            .margin(egui::vec2(8.0, 6.0))
+            .desired_width(width);
+        
+        let response = ui.add_sized([width, 36.0], text_edit);
+        let whole_text = egui::text::CCursorRange::two(
+            egui::text::CCursor::new(0),
+            egui::text::CCursor::new(self.edit_buffer.chars().count()),
+            );
+            if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), text_edit_id) {
+                if select_all {
+                    state.cursor.set_char_range(Some(whole_text));
+                    state.store(ui.ctx(), text_edit_id);
+            } else if response.has_focus()
+                && state.cursor.char_range().is_some_and(|range| range.sorted() == whole_text.sorted())
+                && ui.input(|i| i.events.iter().any(|event| matches!(event, egui::Event::Copy | egui::Event::Cut)))
+                && Preferences::current().strip_tracking_on_copy
+                {
+                    // Copying the whole address copies it without its tracking parameters
+                ui.ctx().copy_text(strip_tracking_params(&self.edit_buffer));
+            }
+        }
+        if let Some(url) = self.context_menu(&response) {
+            navigate_to = Some(url);
+        }
+        
+        // Always try to focus if clicked
+        if response.clicked() {
+            // Enter editing mode when clicked (if not already)
+            if self.state == EditState::Idle {
+                self.state = EditState::Editing;
+                self.edit_buffer = self.current_url.clone();
+            }
+            response.request_focus();
+        }
+        
+        // Handle text changes and focus
+        if response.changed() {
+            if self.state != EditState::Editing {
+                self.state = EditState::Editing;
+            }
+            self.staged_input = self.edit_buffer.clone();
+            self.update_suggestions();
+        }
+        
+        // Submit on Enter key
+        if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && self.state == EditState::Editing {
+            self.state = EditState::PendingCommit;
+        }
+        
+        // Enter takes focus away from a single-line edit, and leaving without it goes back to the formatted address
+        if response.lost_focus() && self.state == EditState::Editing {
+            if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.state = EditState::PendingCommit;
+            } else {
+                self.state = EditState::Idle;
+            }
+        }
+
+        // Commit navigation after losing focus with PendingCommit, or immediate
+        if self.state == EditState::PendingCommit {
+            // Apply staged_input, process, then leave editing mode
+            self.edit_buffer = self.staged_input.clone();
+            navigate_to = Some(self.process_input());
+            self.show_suggestions = false;
+            self.state = EditState::Idle;
+        }
+        
+        navigate_to
+    }
+    
     fn process_input(&mut self) -> String {
-        let input = self.staged_input.trim().to_string();
-        
-        // If it looks like a URL, use it directly
-        if input.starts_with("http://") || 
-           input.starts_with("https://") || 
-           input.starts_with("about:") ||
-           input.starts_with("neon://") ||
-           input.contains('.') {
-            self.current_url = input.clone();
-            input
-        } else {
-            // Otherwise, treat it as a search query
-            let search_url = format!("https://duckduckgo.com/?q={}", 
-                                   urlencoding::encode(&input));
-            self.current_url = search_url.clone();
-            search_url
+        self.current_url = resolve_input(&self.staged_input);
+        self.current_url.clone()
+    }
+    
+    // The committed address: scheme elided for https, host emphasised and the rest dimmed
+    fn show_formatted(&self, ui: &mut egui::Ui, width: f32) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(width, 36.0), egui::Sense::click());
+        let visuals = ui.style().interact(&response);
+        ui.painter().rect(rect, visuals.rounding, ui.visuals().extreme_bg_color, visuals.bg_stroke);
+        
+        let (prefix, host, rest) = display_parts(&self.current_url);
+        let font = egui::TextStyle::Body.resolve(ui.style());
+        let mut job = egui::text::LayoutJob::default();
+        for (text, color) in [(prefix, NeonTheme::muted_text()), (host, NeonTheme::primary_text()), (rest, NeonTheme::muted_text())] {
+            job.append(&text, 0.0, egui::TextFormat::simple(font.clone(), color));
         }
+        job.wrap.max_width = rect.width() - 16.0;
+        job.wrap.max_rows = 1;
+        job.wrap.break_anywhere = true;
+        let galley = ui.fonts(|fonts| fonts.layout_job(job));
+        let position = egui::pos2(rect.left() + 8.0, rect.center().y - galley.size().y / 2.0);
+        ui.painter().with_clip_rect(rect).galley(position, galley, NeonTheme::primary_text());
+        response.on_hover_cursor(egui::CursorIcon::Text).on_hover_text(&self.current_url)
+    }
+    
+    // Copy and paste actions on the address; returns where "Paste and go" leads
+    fn context_menu(&mut self, response: &egui::Response) -> Option<String> {
+        let mut navigate_to = None;
+        response.context_menu(|ui| {
+            if ui.button("Copy address").clicked() {
+                let url = if Preferences::current().strip_tracking_on_copy {
+                    strip_tracking_params(&self.current_url)
+                } else {
+                    self.current_url.clone()
+                };
+                ui.ctx().copy_text(url);
+                ui.close_menu();
+            }
+            let pasted = arboard::Clipboard::new().ok()
+                .and_then(|mut clipboard| clipboard.get_text().ok())
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|text| !text.is_empty());
+            if let Some(text) = pasted {
+                let label = if looks_like_url(&text) { "Paste and go" } else { "Paste and search" };
+                if ui.button(label).clicked() {
+                    self.staged_input = text;
+                    self.state = EditState::Idle;
+                    self.show_suggestions = false;
+                    navigate_to = Some(self.process_input());
+                    ui.close_menu();
+                }
+            }
+        });
+        navigate_to
     }
     
     fn update_suggestions(&mut self) {
-        // Smart suggestion system with history and bookmarks integration
-        // Currently shows basic URL suggestions
+        // Built-in pages, matching history entries and a search fallback
         self.suggestions.clear();
         
         if !self.staged_input.is_empty() {
@@ -218,7 +358,16 @@ impl AddressBar {
                 "neon://performance",
                 "neon://security",
                 "neon://extensions",
-                "neon://experiments"
+                "neon://experiments",
+                "neon://diagnostics",
+                "neon://notifications",
+                "neon://crash",
+                "neon://userscripts",
+                "neon://import",
+                "neon://sync",
+                "neon://passwords",
+                "neon://logs",
+                "neon://tasks"
             ];
             
             for page in &neon_pages {
@@ -227,6 +376,17 @@ impl AddressBar {
                 }
             }
             
+            // Previously visited pages whose URL or title matches
+            if let Some(history) = HistoryDatabase::shared() {
+                let matches = history.get_entries().unwrap_or_default().into_iter()
+                    .filter(|entry| entry.url.to_lowercase().contains(&input_lower)
+                        || entry.title.to_lowercase().contains(&input_lower))
+                    .take(MAX_HISTORY_SUGGESTIONS);
+                for entry in matches {
+                    self.suggestions.push(entry.url);
+                }
+            }
+            
             // Add search suggestion if not a URL-like input
             if !self.staged_input.contains("://") && !self.staged_input.starts_with("about:") {
                 self.suggestions.push(format!("Search for '{}'", self.staged_input));
@@ -234,6 +394,31 @@ impl AddressBar {
         }
         
         self.show_suggestions = !self.suggestions.is_empty();
+        
+        let top = self.suggestions.first().filter(|url| url.starts_with("http://") || url.starts_with("https://"));
+        if top.is_some() && top != self.prefetch_candidate.as_ref() {
+            self.prefetch_candidate = top.cloned();
+            self.prefetch_taken = false;
+        }
+    }
+    
+    /// Whether the site's JavaScript permission was changed from the padlock popover since the last call
+    pub fn take_javascript_change(&mut self) -> bool {
+        std::mem::take(&mut self.javascript_changed)
+    }
+    
+    /// Whether the site info popover blocked or allowed the site's images or cookies since the last call
+    pub fn take_site_settings_change(&mut self) -> bool {
+        std::mem::take(&mut self.site_settings_changed)
+    }
+    
+    /// The top suggestion, once each time it changes to a new http(s) page
+    pub fn take_prefetch_candidate(&mut self) -> Option<String> {
+        if self.prefetch_taken {
+            return None;
+        }
+        self.prefetch_taken = true;
+        self.prefetch_candidate.clone()
     }
     
     pub fn set_url(&mut self, url: String) {
@@ -249,4 +434,196 @@ impl AddressBar {
     pub fn focus(&mut self) {
         self.should_focus = true;
     }
+    
+    // The padlock popover: the connection and certificate, the site's cookies and permissions, and toggles for them
+    fn show_site_info(&mut self, ui: &mut egui::Ui, site: &SiteInfo<'_>, navigate_to: &mut Option<String>) {
+        let report = site.report;
+        ui.label(egui::RichText::new(report.site()).strong().color(NeonTheme::primary_text()));
+        if !report.secure_connection {
+            ui.label(egui::RichText::new("Your connection to this site is not encrypted, so others on the network can read and change what it sends.")
+                .color(NeonTheme::warning_color()));
+        }
+        ui.separator();
+        
+        let now = chrono::Utc::now();
+        egui::Grid::new("site_info_grid").num_columns(2).spacing([16.0, 4.0]).show(ui, |ui| {
+            let row = |ui: &mut egui::Ui, name: &str, value: egui::RichText| {
+                ui.label(egui::RichText::new(name).color(NeonTheme::secondary_text()));
+                ui.label(value);
+                ui.end_row();
+            };
+            let plain = |text: String| egui::RichText::new(text).color(NeonTheme::primary_text());
+            
+            if let Some(tls) = site.tls {
+                row(ui, "Connection", plain(format!("{}, {}", tls.protocol, tls.cipher)));
+                if let Some(certificate) = &tls.certificate {
+                    row(ui, "Issued to", plain(certificate.subject.clone()));
+                    row(ui, "Issued by", plain(certificate.issuer.clone()));
+                    let valid_until = certificate.not_after.format("%Y-%m-%d").to_string();
+                    if certificate.is_valid_at(now) {
+                        row(ui, "Valid until", plain(valid_until));
+                    } else {
+                        row(ui, "Valid until", egui::RichText::new(format!("{} (not valid now)", valid_until)).color(NeonTheme::error_color()));
+                    }
+                }
+            } else {
+                let connection = if report.secure_connection { "Encrypted" } else { "Not encrypted" };
+                row(ui, "Connection", plain(connection.to_string()));
+            }
+            
+            let cookies = match site.cookies {
+                1 => "1 cookie in use".to_string(),
+                count => format!("{} cookies in use", count),
+            };
+            row(ui, "Cookies", plain(cookies));
+            let notifications = match Preferences::current().notifications.permission(&self.current_url) {
+                Some(true) => "Allowed",
+                Some(false) => "Blocked",
+                None => "Ask",
+            };
+            row(ui, "Notifications", plain(notifications.to_string()));
+        });
+        ui.separator();
+        
+        let preferences = Preferences::current();
+        let url = self.current_url.clone();
+        let mut javascript = preferences.javascript.allows(&url);
+        if ui.checkbox(&mut javascript, "Allow JavaScript on this site").changed() {
+            Preferences::update(|prefs| prefs.javascript.set_allowed(&url, javascript));
+            self.javascript_changed = true;
+        }
+        let mut images = preferences.images.allowed(&url);
+        if ui.checkbox(&mut images, "Show images on this site").changed() {
+            Preferences::update(|prefs| prefs.images.set_allowed(&url, images));
+            self.site_settings_changed = true;
+        }
+        let mut cookies = !preferences.cookies.blocks(&url);
+        if ui.checkbox(&mut cookies, "Allow cookies on this site").changed() {
+            Preferences::update(|prefs| prefs.cookies.set_blocked(&url, !cookies));
+            self.site_settings_changed = true;
+        }
+        ui.separator();
+        
+        egui::CollapsingHeader::new(format!("Security score {}/100", report.security_score))
+            .id_salt("site_info_security")
+            .show(ui, |ui| components::security_breakdown(ui, report));
+        if ui.link("All sites on neon://security").clicked() {
+            *navigate_to = Some("neon://security".to_string());
+            ui.memory_mut(|mem| mem.close_popup());
+        }
+    }
+}
+
+// Padlock for an HTTPS page, in warning color when the page undoes some of what the connection protects
+fn security_indicator(report: &SecurityReport) -> (&'static str, &'static str, egui::Color32) {
+    let mixed_content = report.check(SecurityCategory::MixedContent).is_some_and(|check| check.status == CheckStatus::Fail);
+    if mixed_content {
+        (NeonIcons::LOCK, "Secure connection, but the page loads insecure content", NeonTheme::warning_color())
+    } else if matches!(report.level(), SecurityLevel::Poor | SecurityLevel::Fair) {
+        (NeonIcons::LOCK, "Secure connection, but the site lacks common protections", NeonTheme::warning_color())
+    } else {
+        (NeonIcons::LOCK, "Secure HTTPS connection", NeonTheme::success_color())
+    }
+}
+
+// Query parameters that only record where a click came from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "twclid", "ttclid", "yclid", "igshid",
+    "mc_cid", "mc_eid", "_hsenc", "_hsmi", "mkt_tok", "oly_anon_id", "oly_enc_id", "vero_id",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// `url` without utm_* and other click-tracking query parameters
+pub fn strip_tracking_params(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else { return url.to_string() };
+    let pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    if !pairs.iter().any(|(name, _)| is_tracking_param(name)) {
+        return url.to_string();
+    }
+    let kept: Vec<_> = pairs.into_iter().filter(|(name, _)| !is_tracking_param(name)).collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+// Whether typed or pasted text is an address rather than a search
+fn looks_like_url(input: &str) -> bool {
+    input.starts_with("http://")
+        || input.starts_with("https://")
+        || input.starts_with("about:")
+        || input.starts_with("neon://")
+        || input.starts_with("data:")
+        || input.starts_with("blob:")
+        || input.starts_with("file://")
+        || (input.contains('.') && !input.contains(' '))
+}
+
+/// Where the address bar goes for `input`: the address itself, or a search for it
+pub fn resolve_input(input: &str) -> String {
+    let input = input.trim();
+    // An absolute path is a local file
+    if input.starts_with('/') {
+        if let Ok(url) = url::Url::from_file_path(input) {
+            return url.to_string();
+        }
+    }
+    if looks_like_url(input) {
+        input.to_string()
+    } else {
+        format!("https://duckduckgo.com/?q={}", urlencoding::encode(input))
+    }
+}
+
+// An address split for display into the dimmed scheme, the host and the dimmed path, query and fragment
+fn display_parts(url: &str) -> (String, String, String) {
+    let parsed = url::Url::parse(url).ok()
+        .filter(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some());
+    let Some(parsed) = parsed else {
+        return (String::new(), url.to_string(), String::new());
+    };
+    let prefix = if parsed.scheme() == "https" { String::new() } else { "http://".to_string() };
+    let host = parsed[url::Position::BeforeHost..url::Position::AfterPort].to_string();
+    let rest = match &parsed[url::Position::BeforePath..] {
+        "/" => String::new(),
+        rest => rest.to_string(),
+    };
+    (prefix, host, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_parts() {
+        let parts = |url: &str| display_parts(url);
+        assert_eq!(parts("https://example.com/"), (String::new(), "example.com".to_string(), String::new()));
+        assert_eq!(parts("http://example.com:8080/a/b?q=1#top"),
+            ("http://".to_string(), "example.com:8080".to_string(), "/a/b?q=1#top".to_string()));
+        assert_eq!(parts("neon://settings"), (String::new(), "neon://settings".to_string(), String::new()));
+    }
+
+    #[test]
+    fn test_strip_tracking_params() {
+        assert_eq!(strip_tracking_params("https://example.com/p?id=7&utm_source=x&UTM_medium=y&fbclid=abc#s"),
+            "https://example.com/p?id=7#s");
+        assert_eq!(strip_tracking_params("https://example.com/?gclid=1"), "https://example.com/");
+        // Untouched addresses keep their original encoding
+        assert_eq!(strip_tracking_params("https://example.com/?q=a%20b"), "https://example.com/?q=a%20b");
+    }
+
+    #[test]
+    fn test_resolve_input() {
+        assert_eq!(resolve_input(" example.com "), "example.com");
+        assert_eq!(resolve_input("neon://history"), "neon://history");
+        assert_eq!(resolve_input("rust lang 1.0"), "https://duckduckgo.com/?q=rust%20lang%201.0");
+        assert_eq!(resolve_input("/tmp/my notes.txt"), "file:///tmp/my%20notes.txt");
+    }
 }
\ No newline at end of file
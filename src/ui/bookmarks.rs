@@ -65,7 +65,7 @@ impl BookmarkManager {
         ui.heading(
             egui::RichText::new(format!("{} Bookmarks", NeonIcons::BOOKMARKS))
                 .size(18.0)
-                .color(NeonTheme::NEON_CYAN)
+                .color(NeonTheme::neon_cyan())
         );
         ui.separator();
         
@@ -76,7 +76,7 @@ impl BookmarkManager {
             
             if ui.button(
                 egui::RichText::new(format!("{} Add", NeonIcons::PLUS))
-                    .color(NeonTheme::NEON_GREEN)
+                    .color(NeonTheme::neon_green())
             ).clicked() {
                 self.show_add_dialog = true;
             }
@@ -184,7 +184,7 @@ impl BookmarkManager {
             let response = ui.selectable_label(false, &bookmark.title);
             if ui.small_button(
                 egui::RichText::new(NeonIcons::TRASH)
-                    .color(NeonTheme::ERROR_COLOR)
+                    .color(NeonTheme::error_color())
                     .size(12.0)
             ).on_hover_text("Delete bookmark").clicked() {
                 // Bookmark deletion implementation pending
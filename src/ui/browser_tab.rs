@@ -1,20 +1,276 @@
 use eframe::egui;
-use crate::engine::WebPage;
-use crate::networking::HttpResponse;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use crate::engine::{ParsedDocument, WebPage};
+use crate::engine::usage::TabUsage;
+use crate::engine::forms::FormSubmission;
+use crate::engine::hit_test::HitTarget;
+use crate::js::ScriptError;
+use crate::js::event_system::EventData;
+use crate::js::locale::LocaleOverrides;
+use crate::js::location::{self, NavigationRequest};
+use crate::js::navigator::ScreenMetrics;
+use crate::networking::{ContentTooLarge, HttpResponse, OversizedBody};
+use crate::networking::http_auth::{self, AuthCache, AuthScheme, Challenge};
+use crate::networking::{connectivity, file_url};
+use crate::networking::local_url::{self, Presentation};
+use crate::networking::manual_client::RequestBody;
+use crate::networking::protocol_handlers::{self, Handoff};
+use crate::networking::redirect::{RedirectHop, ReferrerPolicy};
+use crate::networking::http_cache::CacheMode;
+use crate::networking::timing::RequestTiming;
 use crate::ui::{NeonTheme, NeonIcons};
-use crate::pages::{PageRouter, CustomPage};
+use crate::ui::load_state::{LoadState, TabLoad};
+use crate::ui::mouse_gestures::{Gesture, GestureTracker, PointerSample};
+use crate::ui::notifications::NotificationCenter;
+use crate::ui::print_preview::PrintPreview;
+use crate::ui::image_viewer::{self, ImageViewer};
+use crate::ui::pdf_viewer::{self, PdfViewer};
+use crate::ui::data_viewer::{self, DataViewer};
+use crate::ui::reader_view::ReaderView;
+use crate::engine::resource_loader::ResourceLoadHandle;
+use crate::networking::site_metadata::{origin_of, SiteMetadataService};
+use crate::pages::{components, PageRouter};
+use crate::security::SecurityReport;
+use crate::security::certificate::TlsInfo;
+use crate::security::password_manager::{self, PasswordManager, Protection};
+use crate::storage::{HistoryDatabase, HistoryEntry, Preferences, SessionTab, UserStyles};
+use crate::storage::user_styles::site_for_url;
+use crate::translate::PageTranslation;
+
+// Most visited sites shown on the new tab page
+const TOP_SITES: usize = 8;
+
+// Script problems kept per tab; older ones are dropped first
+const MAX_SCRIPT_ERRORS: usize = 200;
+
+// How long after a click or key press on the page its script may still hand a link to another application
+const USER_ACTIVATION: Duration = Duration::from_secs(5);
+
+/// Something the user asked of the page that the browser window has to carry out
+#[derive(Debug, Clone)]
+pub enum PageAction {
+    OpenInNewTab(String),
+    Inspect(HitTarget),
+    /// Fetch the current page again without the page size limit
+    LoadFullContent,
+    /// Hand the URL to the download manager instead of rendering it
+    Download(String),
+    /// Open the URL in a separate browser window
+    OpenInNewWindow(String),
+    /// Move the page's video playing from this URL to the picture-in-picture window
+    PictureInPicture(String),
+    /// Add cookies brought over from another browser to the jar, keyed by host
+    ImportCookies(Vec<(String, crate::networking::cookie_manager::Cookie)>),
+    /// Open a connection to where a hovered link leads, ahead of a click on it
+    Preconnect(String),
+    /// Reload pages whose scripts the changed JavaScript settings now allow or block
+    JavaScriptSettingsChanged,
+    /// Drop the page of the tab with this id, from neon://tasks
+    KillTab(Uuid),
+    /// Go back or forward in the tab's history, from a mouse gesture
+    GoBack,
+    GoForward,
+}
+
+/// Fill the page's login form with the login last used on `origin`
+fn autofill_login(web_page: &WebPage, origin: &str) {
+    if !web_page.has_login_form() {
+        return;
+    }
+    let passwords = PasswordManager::shared();
+    let Ok(mut passwords) = passwords.lock() else {
+        return;
+    };
+    let Some((username, password)) = passwords.credentials_for(origin).first().map(|c| (c.username.clone(), c.password.clone())) else {
+        return;
+    };
+    if web_page.autofill_login(&username, &password) {
+        passwords.mark_used(origin, &username);
+    }
+}
+
+/// Where a click on a link sends it, from the mouse button and held modifiers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkDisposition {
+    CurrentTab,
+    BackgroundTab,
+    NewWindow,
+    Download,
+}
+
+impl LinkDisposition {
+    pub fn from_click(middle: bool, modifiers: egui::Modifiers) -> Self {
+        if middle || modifiers.command {
+            LinkDisposition::BackgroundTab
+        } else if modifiers.shift {
+            LinkDisposition::NewWindow
+        } else if modifiers.alt {
+            LinkDisposition::Download
+        } else {
+            LinkDisposition::CurrentTab
+        }
+    }
+}
+
+/// A login just submitted, waiting for the user to decide whether to save it
+struct PasswordPrompt {
+    origin: String,
+    username: String,
+    password: String,
+    // Entered in the prompt when the vault needs a master password to unlock or set up
+    master_password: String,
+    error: Option<String>,
+}
+
+/// A 401 challenge waiting for the user's credentials
+struct AuthPrompt {
+    origin: String,
+    challenge: Challenge,
+    username: String,
+    password: String,
+    // Save the credentials in the password manager too
+    remember: bool,
+    // The credentials sent with the request were turned down
+    rejected: bool,
+}
+
+/// One entry of a tab's back/forward list
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationEntry {
+    pub url: String,
+    // Form data the page was requested with, sent again only once the user confirms
+    pub post: Option<RequestBody>,
+    // Page a link or form on it led here from, with that page's referrer policy
+    pub referrer: Option<(String, ReferrerPolicy)>,
+    // What the page's script passed to pushState or replaceState for this entry
+    pub state: Option<String>,
+    // Entries a page added with pushState share its document, so moving between them doesn't load anything
+    document: u64,
+}
+
+static NEXT_DOCUMENT: AtomicU64 = AtomicU64::new(1);
+
+fn new_document() -> u64 {
+    NEXT_DOCUMENT.fetch_add(1, Ordering::Relaxed)
+}
+
+impl NavigationEntry {
+    fn get(url: String) -> Self {
+        Self { url, post: None, referrer: None, state: None, document: new_document() }
+    }
+}
+
+// How long an offline page waits before each retry; the last delay repeats
+const OFFLINE_RETRY_DELAYS: [u64; 4] = [5, 10, 30, 60];
+
+/// A load that failed for lack of a connection, retried on a countdown or as soon as the OS has a route again
+struct OfflineRetry {
+    attempts: usize,
+    retry_at: Instant,
+    // Whether the OS still had a route when the load failed; if it didn't, one appearing means the connection is back
+    had_route: bool,
+}
+
+/// The page a tab showed before its current navigation, put back if the navigation is stopped
+struct ShownPage {
+    page: Option<WebPage>,
+    title: String,
+    url: String,
+    history_len: usize,
+    history_index: usize,
+}
+
+/// A request the current page made, as remote debugging lists them
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkEntry {
+    pub url: String,
+    /// "Document", or the kind of subresource
+    pub kind: String,
+    /// Only known for the document and the redirects on the way to it
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    /// Phases, bytes and connection of the fetch; redirect hops share the document's
+    pub timing: Option<RequestTiming>,
+}
 
 pub struct BrowserTab {
     pub title: String,
     pub url: String,
     pub web_page: Option<WebPage>,
-    pub loading: bool,
-    pub error: Option<String>,
-    pub history: Vec<String>,
+    // Navigation lifecycle, including the error of a failed load
+    pub load: TabLoad,
+    pub history: Vec<NavigationEntry>,
     pub history_index: usize,
-    pub redirects_followed: usize,
+    // History entry waiting for the user to confirm resending its form data
+    resubmit_prompt: Option<usize>,
+    // Link waiting for the user to confirm handing it to another application
+    handoff_prompt: Option<String>,
+    // When the user last clicked or activated something on the page
+    last_activation: Option<Instant>,
+    // Redirects followed by the networking layer for the current navigation
+    pub redirect_chain: Vec<RedirectHop>,
+    // Why the current page body is incomplete, shown above the content
+    pub truncation: Option<String>,
+    // Set when the page was not loaded because it passed the page size limit
+    pub oversized: Option<OversizedBody>,
+    // Origin whose script asked to show notifications, until the user answers
+    notification_prompt: Option<String>,
+    // The page's scripts ran too long and were interrupted; asks whether to stop them or wait
+    slow_script_prompt: bool,
+    // The page's text in another language, while it's asked for or shown
+    translation: Option<PageTranslation>,
+    password_prompt: Option<PasswordPrompt>,
+    auth_prompt: Option<AuthPrompt>,
+    // Whether the current page's login form was looked for to fill in saved credentials
+    login_autofilled: bool,
+    // How the current page scores on transport, headers, cookies, mixed content and CSP
+    pub security_report: Option<SecurityReport>,
+    // Language, locale and time zone emulated from dev tools for this tab
+    pub locale_overrides: LocaleOverrides,
+    pub print_preview: PrintPreview,
+    // The page's article in reader typography, shown instead of the page while open
+    pub reader: ReaderView,
+    // An image navigated to directly, shown in the image viewer in place of a page
+    image_viewer: Option<ImageViewer>,
+    // A PDF navigated to, shown in the PDF viewer in place of a page
+    pdf_viewer: Option<PdfViewer>,
+    // A JSON or XML response, shown as a browsable tree in place of a page
+    data_viewer: Option<DataViewer>,
+    // Muted from the tab strip; carried over to every page the tab loads
+    pub audio_muted: bool,
+    // Keeps this tab's page scroll position apart from other tabs'
+    scroll_id: egui::Id,
+    // Stylesheets, scripts and images still loading for the current page
+    pub resources: Option<ResourceLoadHandle>,
+    // Requests the current page made, in the order they finished
+    pub network_log: Vec<NetworkEntry>,
+    // Errors and warnings from the current page's scripts, oldest first
+    pub script_errors: Vec<ScriptError>,
+    // Most visited sites, refreshed whenever the new tab page loads
+    top_sites: Vec<HistoryEntry>,
     // Track current response for cleanup of temporary files
     current_response: Option<HttpResponse>,
+    // Right-clicked element and where its context menu opened
+    context_menu: Option<(egui::Pos2, HitTarget)>,
+    // Link under the pointer, so its origin is only preconnected once per hover
+    hovered_link: Option<String>,
+    // Rocker and drag gestures made over the page
+    gestures: GestureTracker,
+    page_actions: Vec<PageAction>,
+    // Why rendering the page panicked; the page is dropped and the tab offers a reload instead
+    crashed: Option<String>,
+    // Reopened from the last session and not loaded yet
+    restored: bool,
+    // What was on screen before the navigation in progress, for the Stop button to return to
+    previous: Option<ShownPage>,
+    // Set while the page couldn't load because the machine is offline
+    offline: Option<OfflineRetry>,
+    // How the current navigation and its subresources use the HTTP cache
+    pub cache_mode: CacheMode,
+    // Set by a reload for the load it starts
+    reload_mode: CacheMode,
 }
 
 impl BrowserTab {
@@ -23,26 +279,144 @@ impl BrowserTab {
             title,
             url: "about:home".to_string(),
             web_page: None,
-            loading: false,
-            error: None,
-            history: vec!["about:home".to_string()],
+            load: TabLoad::new(),
+            history: vec![NavigationEntry::get("about:home".to_string())],
             history_index: 0,
-            redirects_followed: 0,
+            resubmit_prompt: None,
+            handoff_prompt: None,
+            last_activation: None,
+            redirect_chain: Vec::new(),
+            truncation: None,
+            oversized: None,
+            notification_prompt: None,
+            slow_script_prompt: false,
+            translation: None,
+            password_prompt: None,
+            auth_prompt: None,
+            login_autofilled: false,
+            security_report: None,
+            locale_overrides: LocaleOverrides::default(),
+            print_preview: PrintPreview::new(),
+            reader: ReaderView::default(),
+            image_viewer: None,
+            pdf_viewer: None,
+            data_viewer: None,
+            audio_muted: false,
+            scroll_id: egui::Id::new(uuid::Uuid::new_v4()),
+            resources: None,
+            network_log: Vec::new(),
+            script_errors: Vec::new(),
+            top_sites: Vec::new(),
             current_response: None,
+            context_menu: None,
+            hovered_link: None,
+            gestures: GestureTracker::default(),
+            page_actions: Vec::new(),
+            crashed: None,
+            restored: false,
+            previous: None,
+            offline: None,
+            cache_mode: CacheMode::Default,
+            reload_mode: CacheMode::Default,
+        }
+    }
+    
+    /// Reopen a tab from a saved session or the closed tabs without loading it; it loads once the tab is first shown
+    pub fn restore(&mut self, saved: &SessionTab) {
+        self.url = saved.url.clone();
+        self.title = saved.title.clone();
+        if saved.history.get(saved.history_index) == Some(&saved.url) {
+            self.history = saved.history.iter().cloned().map(NavigationEntry::get).collect();
+            self.history_index = saved.history_index;
+        } else {
+            self.history = vec![NavigationEntry::get(saved.url.clone())];
+            self.history_index = 0;
         }
+        self.restored = true;
+    }
+    
+    /// The tab as the session store keeps it. Form data isn't kept, so entries that were POSTed come back as GETs
+    pub fn saved(&self) -> SessionTab {
+        SessionTab {
+            history: self.history.iter().map(|entry| entry.url.clone()).collect(),
+            history_index: self.history_index,
+            ..SessionTab::new(self.url.clone(), self.title.clone())
+        }
+    }
+    
+    /// Load a restored tab's page now that it's being shown; true when a network request is needed
+    pub fn load_if_restored(&mut self) -> bool {
+        std::mem::take(&mut self.restored) && self.load_page()
     }
     
     pub fn navigate_to(&mut self, url: String) -> bool {
+        self.navigate_from(url, None)
+    }
+    
+    // Navigate to `url`, sending `referrer` when a link or form on a page led there
+    fn navigate_from(&mut self, url: String, referrer: Option<(String, ReferrerPolicy)>) -> bool {
+        // mailto:, irc: and the like open a mapped web page, or leave this page for the system's application once
+        // the user confirms
+        let url = match protocol_handlers::handoff(&url, &Preferences::current().protocol_handlers) {
+            Some(Handoff::Navigate(target)) => target,
+            Some(Handoff::System(link)) => {
+                self.handoff_prompt = Some(link);
+                return false;
+            }
+            Some(Handoff::Refused(scheme)) => {
+                let message = format!("NeonSearch doesn't open {}: links in other applications. Map them to a web page in Settings", scheme);
+                self.web_page = Some(WebPage::create_error_page(&url, &message));
+                return false;
+            }
+            None => url,
+        };
+        self.offline = None;
+        self.remember_shown_page();
         self.url = url.clone();
-        if !url.starts_with("about:") && !self.history.is_empty() && self.history[self.history_index] == url {
+        if !url.starts_with("about:") && self.history.get(self.history_index).is_some_and(|entry| entry.url == url && entry.post.is_none()) {
             return false; // Already at this URL
         }
-        self.history.push(url);
+        self.history.push(NavigationEntry { referrer, ..NavigationEntry::get(url) });
         self.history_index = self.history.len() - 1;
         
         self.load_page()
     }
     
+    /// Follow a link on the current page, which is the Referer the new page is requested with
+    fn follow_link(&mut self, url: String) -> bool {
+        let referrer = self.page_referrer();
+        self.navigate_from(url, referrer)
+    }
+    
+    /// Follow a submitted form: GET forms are a plain navigation, POST forms become a history entry with their data
+    pub fn submit_form(&mut self, submission: FormSubmission) -> bool {
+        let referrer = self.page_referrer();
+        let Some(post) = submission.body else {
+            return self.navigate_from(submission.url, referrer);
+        };
+        self.offline = None;
+        self.remember_shown_page();
+        self.url = submission.url.clone();
+        self.history.push(NavigationEntry { post: Some(post), referrer, ..NavigationEntry::get(submission.url) });
+        self.history_index = self.history.len() - 1;
+        self.load_page()
+    }
+    
+    /// Form data the current navigation sends, if it's a POST
+    pub fn request_body(&self) -> Option<RequestBody> {
+        self.history.get(self.history_index).and_then(|entry| entry.post.clone())
+    }
+    
+    /// Page the current navigation came from, with the policy deciding how much of its address is sent
+    pub fn referrer(&self) -> Option<(String, ReferrerPolicy)> {
+        self.history.get(self.history_index).and_then(|entry| entry.referrer.clone())
+    }
+    
+    // The shown page as the referrer of navigations it starts
+    fn page_referrer(&self) -> Option<(String, ReferrerPolicy)> {
+        self.web_page.as_ref().map(|page| (self.url.clone(), page.referrer_policy()))
+    }
+    
     pub fn can_go_back(&self) -> bool {
         self.history_index > 0
     }
@@ -53,23 +427,190 @@ impl BrowserTab {
     
     pub fn go_back(&mut self) -> bool {
         if self.can_go_back() {
-            self.history_index -= 1;
-            self.url = self.history[self.history_index].clone();
-            return self.load_page();
+            return self.load_entry(self.history_index - 1);
         }
         false
     }
     
     pub fn go_forward(&mut self) -> bool {
         if self.can_go_forward() {
-            self.history_index += 1;
-            self.url = self.history[self.history_index].clone();
-            return self.load_page();
+            return self.load_entry(self.history_index + 1);
         }
         false
     }
     
+    /// Load the page again, checking cached resources with the server
     pub fn reload(&mut self) -> bool {
+        self.reload_mode = CacheMode::Revalidate;
+        self.load_entry(self.history_index)
+    }
+    
+    /// Load the page again without using the cache at all
+    pub fn hard_reload(&mut self) -> bool {
+        self.reload_mode = CacheMode::Bypass;
+        self.load_entry(self.history_index)
+    }
+    
+    // Entries of the shown document only fire popstate. Entries that were POSTed wait for the user to confirm
+    // before their form data is sent again.
+    fn load_entry(&mut self, index: usize) -> bool {
+        let Some(entry) = self.history.get(index).cloned() else {
+            return false;
+        };
+        let same_document = index != self.history_index
+            && !self.load.is_loading()
+            && self.history.get(self.history_index).is_some_and(|current| current.document == entry.document);
+        if let Some(web_page) = self.web_page.as_mut().filter(|_| same_document) {
+            self.history_index = index;
+            self.url = entry.url.clone();
+            web_page.set_session_history(self.history.len(), entry.state.clone());
+            web_page.pop_state(&entry.url, entry.state);
+            return false;
+        }
+        if entry.post.is_some() {
+            self.resubmit_prompt = Some(index);
+            return false;
+        }
+        self.remember_shown_page();
+        self.history_index = index;
+        self.url = entry.url;
+        // Loading again makes a new document, which the entries it pushed before don't belong to
+        self.history[index].document = new_document();
+        self.load_page()
+    }
+    
+    /// State the current entry was pushed with, for `history.state` once its page has loaded
+    pub fn history_state(&self) -> Option<String> {
+        self.history.get(self.history_index).and_then(|entry| entry.state.clone())
+    }
+    
+    // Carry out a navigation or history change the page's script asked for; true when a network request is needed
+    fn apply_navigation_request(&mut self, request: NavigationRequest) -> bool {
+        match request {
+            NavigationRequest::Navigate { url, replace } => {
+                if !location::navigation_allowed(&self.url, &url) {
+                    log::warn!("Blocked a script on {} from navigating to {}", self.url, url);
+                    return false;
+                }
+                // Without a click or key press just before, a script can't send the user to another application
+                let handoff = protocol_handlers::handoff(&url, &Preferences::current().protocol_handlers);
+                let activated = self.last_activation.is_some_and(|at| at.elapsed() < USER_ACTIVATION);
+                if matches!(handoff, Some(Handoff::System(_))) && !activated {
+                    log::warn!("Blocked a script from opening {} without a user gesture", url);
+                    return false;
+                }
+                let replaced = self.history_index;
+                let needs_fetch = self.follow_link(url);
+                if replace && self.history_index > replaced {
+                    self.history.remove(replaced);
+                    self.history_index -= 1;
+                }
+                needs_fetch
+            }
+            NavigationRequest::Reload => self.reload(),
+            NavigationRequest::PushState { url, state } => {
+                let current = &self.history[self.history_index];
+                let entry = NavigationEntry { url: url.clone(), post: None, referrer: current.referrer.clone(), state, document: current.document };
+                self.history.truncate(self.history_index + 1);
+                self.history.push(entry);
+                self.history_index = self.history.len() - 1;
+                self.url = url;
+                self.sync_session_history();
+                false
+            }
+            NavigationRequest::ReplaceState { url, state } => {
+                let current = &mut self.history[self.history_index];
+                current.url = url.clone();
+                current.state = state;
+                self.url = url;
+                self.sync_session_history();
+                false
+            }
+            NavigationRequest::Traverse(delta) => {
+                let target = self.history_index as i64 + delta as i64;
+                if target < 0 || target >= self.history.len() as i64 {
+                    return false;
+                }
+                self.load_entry(target as usize)
+            }
+        }
+    }
+    
+    // The page's address and `history` after its script changed the current entry
+    fn sync_session_history(&mut self) {
+        let state = self.history_state();
+        if let Some(web_page) = self.web_page.as_mut() {
+            web_page.set_document_url(&self.url);
+            web_page.set_session_history(self.history.len(), state);
+        }
+    }
+    
+    // Asks before a link leaves the browser for the system's application for its scheme
+    fn show_handoff_prompt(&mut self, ui: &mut egui::Ui) {
+        let Some(link) = self.handoff_prompt.clone() else {
+            return;
+        };
+        let scheme = protocol_handlers::external_scheme(&link).unwrap_or_default();
+        let mut open = false;
+        let mut cancel = false;
+        egui::Window::new(format!("{} Open in another application?", NeonIcons::WARNING))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ui.ctx(), |ui| {
+                ui.label(format!("This {}: link will be opened by the application your system uses for it.", scheme));
+                ui.label(egui::RichText::new(&link).color(NeonTheme::muted_text()).small());
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    open = ui.button("Open").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if cancel || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.handoff_prompt = None;
+        }
+        if !open {
+            return;
+        }
+        self.handoff_prompt = None;
+        if let Err(e) = protocol_handlers::open_with_system(&link) {
+            let message = format!("{}. Map {}: links to a web page in Settings", e, scheme);
+            self.web_page = Some(WebPage::create_error_page(&link, &message));
+        }
+    }
+    
+    // Returns true when the user chose to send the form data again
+    fn show_resubmit_prompt(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(index) = self.resubmit_prompt else {
+            return false;
+        };
+        let mut resend = false;
+        let mut cancel = false;
+        egui::Window::new(format!("{} Confirm form resubmission", NeonIcons::WARNING))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ui.ctx(), |ui| {
+                ui.label("This page was loaded by submitting a form. Loading it again sends the form data again, \
+                          which may repeat an action such as a purchase.");
+                ui.label(egui::RichText::new(&self.history[index].url).color(NeonTheme::muted_text()).small());
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    resend = ui.button("Resend").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if cancel || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.resubmit_prompt = None;
+            self.reload_mode = CacheMode::Default;
+        }
+        if !resend {
+            return false;
+        }
+        self.resubmit_prompt = None;
+        self.remember_shown_page();
+        self.history_index = index;
+        self.url = self.history[index].url.clone();
         self.load_page()
     }
     
@@ -77,22 +618,37 @@ impl BrowserTab {
         // Clean up any existing temporary files before loading new content
         self.cleanup_temp_files();
         
-        self.loading = true;
-        self.error = None;
-        self.redirects_followed = 0;
+        let was_loading = self.load.is_loading();
+        self.cancel_subresources();
+        let generation = self.load.begin(&self.url);
+        self.cache_mode = std::mem::take(&mut self.reload_mode);
+        self.redirect_chain.clear();
+        self.truncation = None;
+        self.oversized = None;
+        self.notification_prompt = None;
+        self.slow_script_prompt = false;
+        self.translation = None;
+        self.auth_prompt = None;
+        self.login_autofilled = false;
+        self.crashed = None;
+        self.reader.close();
+        self.image_viewer = None;
+        self.pdf_viewer = None;
+        self.data_viewer = None;
         
         // Handle special URLs
         match self.url.as_str() {
             "about:home" => {
                 self.title = "NeonSearch Home".to_string();
                 self.web_page = Some(WebPage::create_home_page());
-                self.loading = false;
+                self.top_sites = Self::load_top_sites();
+                self.load.finish(generation);
                 false // No network request needed
             }
             "about:blank" => {
                 self.title = "Blank Page".to_string();
                 self.web_page = Some(WebPage::create_blank_page());
-                self.loading = false;
+                self.load.finish(generation);
                 false // No network request needed
             }
             url if url.starts_with("neon://") => {
@@ -102,35 +658,141 @@ impl BrowserTab {
                     let html_content = self.generate_custom_page_html(&self.url);
                     self.title = router.get_page_title(&self.url).unwrap_or_else(|| "Custom Page".to_string());
                     self.web_page = Some(WebPage::create_simple_text_page(&html_content, &self.url));
-                    self.loading = false;
+                    self.load.finish(generation);
                     false // No network request needed
                 } else {
                     // Unknown neon:// URL
                     self.title = "Page Not Found".to_string();
                     self.web_page = Some(WebPage::create_error_page(&self.url, "Unknown neon:// page"));
-                    self.loading = false;
+                    self.load.advance(generation, LoadState::Error("Unknown neon:// page".to_string()));
                     false
                 }
             }
             _ => {
                 // Need to fetch the webpage
                 self.title = format!("Loading {}", self.url);
-                self.web_page = Some(WebPage::create_loading_page(&self.url));
+                let shown = self.web_page.replace(WebPage::create_loading_page(&self.url));
+                if let Some(previous) = self.previous.as_mut().filter(|_| !was_loading) {
+                    previous.page = shown;
+                }
                 true // Network request needed
             }
         }
     }
     
+    // Note what's on screen before a navigation changes the URL; one replacing a navigation still loading keeps
+    // the page from before both
+    fn remember_shown_page(&mut self) {
+        if self.load.is_loading() && self.previous.is_some() {
+            return;
+        }
+        self.previous = Some(ShownPage {
+            page: None,
+            title: self.title.clone(),
+            url: self.url.clone(),
+            history_len: self.history.len(),
+            history_index: self.history_index,
+        });
+    }
+    
+    /// Stop the navigation in progress and go back to the page shown before it, or stop the current page's
+    /// subresources. Returns false when nothing was loading.
+    pub fn stop(&mut self) -> bool {
+        if !self.load.is_loading() {
+            let loading_resources = self.resources.is_some();
+            self.cancel_subresources();
+            return loading_resources;
+        }
+        self.load.cancel();
+        self.resubmit_prompt = None;
+        match self.previous.take() {
+            Some(previous) => {
+                self.web_page = Some(previous.page.unwrap_or_else(WebPage::create_blank_page));
+                self.title = previous.title;
+                self.url = previous.url;
+                self.history.truncate(previous.history_len);
+                self.history_index = previous.history_index;
+            }
+            None => {
+                self.title = "Blank Page".to_string();
+                self.web_page = Some(WebPage::create_blank_page());
+            }
+        }
+        true
+    }
+    
+    /// Record whether the load that just finished failed for lack of a connection, scheduling the next retry
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline.then(|| {
+            let attempts = self.offline.as_ref().map_or(0, |retry| retry.attempts) + 1;
+            let delay = OFFLINE_RETRY_DELAYS[(attempts - 1).min(OFFLINE_RETRY_DELAYS.len() - 1)];
+            OfflineRetry {
+                attempts,
+                retry_at: Instant::now() + Duration::from_secs(delay),
+                had_route: connectivity::has_route(),
+            }
+        });
+    }
+    
+    /// Whether the offline page should load again now: its countdown ran out or the connection came back
+    pub fn offline_retry_due(&self) -> bool {
+        let Some(retry) = self.offline.as_ref().filter(|_| self.load.error().is_some()) else {
+            return false;
+        };
+        Instant::now() >= retry.retry_at || (!retry.had_route && connectivity::route_available())
+    }
+    
+    pub fn is_offline(&self) -> bool {
+        self.offline.is_some() && self.load.error().is_some()
+    }
+    
     pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
-        if self.loading {
+        if self.show_auth_prompt(ui) {
+            return true;
+        }
+        if self.show_resubmit_prompt(ui) {
+            return true;
+        }
+        self.show_handoff_prompt(ui);
+        if self.load.is_loading() {
+            let label = format!("{}...", self.load.state().label());
             ui.centered_and_justified(|ui| {
                 ui.spinner();
-                ui.label("Loading...");
+                ui.label(label);
             });
             return false;
         }
         
-        if let Some(error) = &self.error {
+        if let Some(retry) = self.offline.as_ref().filter(|_| self.load.error().is_some()) {
+            let seconds = retry.retry_at.saturating_duration_since(Instant::now()).as_secs() + 1;
+            let host = url::Url::parse(&self.url).ok().and_then(|url| url.host_str().map(str::to_string)).unwrap_or_else(|| self.url.clone());
+            let mut retry_clicked = false;
+            ui.centered_and_justified(|ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(egui::RichText::new(format!("{} You're offline", NeonIcons::WIFI_SLASH)).size(20.0).color(NeonTheme::warning_color()));
+                    ui.label(format!("NeonSearch couldn't reach {} because there's no network connection.", host));
+                    ui.separator();
+                    ui.label("- Check the network cable, Wi-Fi or mobile data");
+                    ui.label("- Restart your router if other devices can't connect either");
+                    ui.add_space(8.0);
+                    ui.label(egui::RichText::new(format!("Trying again in {} s, or as soon as the connection is back", seconds))
+                        .color(NeonTheme::muted_text()));
+                    if ui.button(
+                        egui::RichText::new(format!("{} Retry now", NeonIcons::ARROW_CLOCKWISE))
+                            .color(NeonTheme::neon_blue())
+                    ).clicked() {
+                        retry_clicked = true;
+                    }
+                });
+            });
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+            if retry_clicked {
+                return self.reload();
+            }
+            return false;
+        }
+        
+        if let Some(error) = self.load.error() {
             let mut retry_clicked = false;
             ui.centered_and_justified(|ui| {
                 ui.label(egui::RichText::new("X Error").size(20.0).color(egui::Color32::RED));
@@ -144,70 +806,935 @@ impl BrowserTab {
                 ui.label("- Try adding https:// prefix");
                 if ui.button(
                     egui::RichText::new(format!("{} Retry", NeonIcons::ARROW_CLOCKWISE))
-                        .color(NeonTheme::NEON_BLUE)
+                        .color(NeonTheme::neon_blue())
                 ).clicked() {
                     retry_clicked = true;
                 }
             });
             if retry_clicked {
-                self.error = None;
                 return self.reload(); // Return true if network request needed
             }
             return false;
         }
         
-        if let Some(web_page) = &self.web_page {
-            web_page.render(ui);
-        } else {
+        if let Some(message) = &self.crashed {
+            let mut reload_clicked = false;
+            ui.centered_and_justified(|ui| {
+                ui.label(egui::RichText::new(format!("{} This page crashed", NeonIcons::WARNING)).size(20.0).color(NeonTheme::error_color()));
+                ui.label(format!("URL: {}", self.url));
+                ui.separator();
+                ui.label(egui::RichText::new(message).color(NeonTheme::muted_text()));
+                ui.separator();
+                ui.label("Other tabs are unaffected.");
+                if ui.button(
+                    egui::RichText::new(format!("{} Reload", NeonIcons::ARROW_CLOCKWISE))
+                        .color(NeonTheme::neon_blue())
+                ).clicked() {
+                    reload_clicked = true;
+                }
+            });
+            if reload_clicked {
+                return self.reload();
+            }
+            return false;
+        }
+        
+        // Lazy images waiting offscreen keep the handle alive but aren't shown as loading
+        if let Some(progress) = self.resources.as_ref().map(|r| r.progress()).filter(|p| !p.is_finished()) {
+            let mut cancel = false;
+            ui.horizontal(|ui| {
+                ui.add(egui::ProgressBar::new(progress.fraction())
+                    .desired_width(160.0)
+                    .fill(NeonTheme::neon_cyan()));
+                ui.label(egui::RichText::new(format!("Loading resources {}/{}", progress.completed, progress.total))
+                    .size(11.0)
+                    .color(NeonTheme::muted_text()));
+                if ui.small_button(NeonIcons::X).on_hover_text("Stop loading resources").clicked() {
+                    cancel = true;
+                }
+            });
+            if cancel {
+                self.cancel_subresources();
+            }
+        }
+        
+        if let Some(reason) = &self.truncation {
+            egui::Frame::none()
+                .fill(NeonTheme::warning_color().gamma_multiply(0.15))
+                .stroke(egui::Stroke::new(1.0, NeonTheme::warning_color()))
+                .rounding(6.0)
+                .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new(format!("{} Page may be incomplete: {}", NeonIcons::WARNING, reason))
+                        .color(NeonTheme::warning_color()));
+                });
+            ui.add_space(4.0);
+        }
+        
+        if let Some(oversized) = self.oversized {
+            self.show_oversized_bar(ui, oversized);
+            ui.add_space(4.0);
+        }
+        
+        if self.translation.is_some() {
+            self.show_translation_bar(ui);
+            ui.add_space(4.0);
+        }
+        
+        if self.slow_script_prompt {
+            self.show_slow_script_prompt(ui);
+            ui.add_space(4.0);
+        }
+        
+        if let Some(origin) = self.notification_prompt.clone() {
+            self.show_notification_prompt(ui, &origin);
+            ui.add_space(4.0);
+        }
+        
+        if self.password_prompt.is_some() {
+            self.show_password_prompt(ui);
+            ui.add_space(4.0);
+        }
+        
+        if self.url == "about:home" && !self.top_sites.is_empty() {
+            if let Some(url) = self.show_top_sites(ui) {
+                return self.navigate_to(url);
+            }
+        }
+        
+        if self.reader.is_open() {
+            self.reader.show(ui, &self.url);
+            return false;
+        }
+        
+        if let Some(viewer) = self.image_viewer.as_mut() {
+            viewer.show(ui);
+            return false;
+        }
+        
+        if let Some(viewer) = self.pdf_viewer.as_mut() {
+            viewer.show(ui);
+            // The document's title is known once it has been parsed
+            self.title = viewer.title();
+            return false;
+        }
+        
+        if let Some(viewer) = self.data_viewer.as_mut() {
+            viewer.show(ui);
+            return false;
+        }
+        
+        // Only the shown tab's clock runs, so background pages don't animate
+        let mut navigation_requests = Vec::new();
+        if let Some(web_page) = self.web_page.as_mut() {
+            // The page's viewport is what's left of the tab's area for the scrolled content
+            let viewport = ui.available_size();
+            web_page.set_screen(ui.input(|i| {
+                let size = i.viewport().monitor_size.unwrap_or(i.screen_rect.size());
+                ScreenMetrics {
+                    width: size.x,
+                    height: size.y,
+                    device_pixel_ratio: i.pixels_per_point,
+                    viewport_width: viewport.x,
+                    viewport_height: viewport.y,
+                }
+            }));
+            web_page.refresh_user_styles();
+            if web_page.tick_animations(ui.input(|i| i.time)) {
+                ui.ctx().request_repaint();
+            }
+            // Workers answer on their own threads, so keep looking while any runs
+            if web_page.poll_workers() {
+                ui.ctx().request_repaint_after(Duration::from_millis(50));
+            }
+            if web_page.take_slow_script() && !self.slow_script_prompt {
+                self.slow_script_prompt = true;
+                ui.ctx().request_repaint();
+            }
+            navigation_requests = web_page.take_navigation_requests();
+            let errors = web_page.take_script_errors();
+            if !errors.is_empty() {
+                self.script_errors.extend(errors);
+                let excess = self.script_errors.len().saturating_sub(MAX_SCRIPT_ERRORS);
+                self.script_errors.drain(..excess);
+            }
+            if let Some(origin) = origin_of(&self.url) {
+                for notification in web_page.take_notifications() {
+                    NotificationCenter::shared().notify(&origin, &notification.title, &notification.body);
+                }
+                if web_page.take_notification_permission_request() && self.notification_prompt.is_none() {
+                    self.notification_prompt = Some(origin.clone());
+                    ui.ctx().request_repaint();
+                }
+                if !self.login_autofilled {
+                    self.login_autofilled = true;
+                    autofill_login(web_page, &origin);
+                }
+            }
+        }
+        for request in navigation_requests {
+            // A load replaces the page, so whatever else its script asked for is moot
+            if self.apply_navigation_request(request) {
+                return true;
+            }
+        }
+        let Some(web_page) = &self.web_page else {
             ui.centered_and_justified(|ui| {
                 ui.label("No content to display");
             });
+            return false;
+        };
+        web_page.set_audio_muted(self.audio_muted);
+        // Each navigation starts at the top of its own scroll position
+        let page_rect = egui::ScrollArea::vertical()
+            .id_salt((self.scroll_id, self.load.generation()))
+            .auto_shrink([false; 2])
+            .show(ui, |ui| web_page.render(ui))
+            .inner_rect;
+        if let Some(submission) = web_page.take_form_submission() {
+            if let (Some((username, password)), Some(origin)) = (&submission.login, origin_of(&self.url)) {
+                let offer = PasswordManager::shared().lock().is_ok_and(|p| p.should_offer_save(&origin, username, password));
+                if offer {
+                    self.password_prompt = Some(PasswordPrompt {
+                        origin,
+                        username: username.clone(),
+                        password: password.clone(),
+                        master_password: String::new(),
+                        error: None,
+                    });
+                }
+            }
+            return self.submit_form(submission);
         }
-        false
+        // Links activated with Enter or Space open like clicked ones, modifiers included
+        if let Some(url) = web_page.take_activated_link().and_then(|href| self.resolve_link(&href)) {
+            self.last_activation = Some(Instant::now());
+            let disposition = LinkDisposition::from_click(false, ui.input(|i| i.modifiers));
+            return self.open_link(url, disposition).is_some_and(|url| self.follow_link(url));
+        }
+        match self.handle_page_pointer(ui, page_rect) {
+            Some(url) => self.follow_link(url),
+            None => false,
+        }
+    }
+    
+    // A 401 asks for credentials: the prompt offers the ones saved for the site, or says the ones just sent were wrong
+    fn prompt_for_credentials(&mut self, response: &HttpResponse) {
+        let Some(origin) = origin_of(&self.url) else {
+            return;
+        };
+        let challenge = response.headers.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("www-authenticate"))
+            .and_then(|(_, value)| http_auth::parse_challenges(value).into_iter().next());
+        let Some(challenge) = challenge else {
+            return;
+        };
+        let rejected = AuthCache::shared().lock().is_ok_and(|mut auth| auth.remove(&origin));
+        let saved = PasswordManager::shared().lock().ok()
+            .and_then(|passwords| passwords.credentials_for(&origin).first().map(|c| (c.username.clone(), c.password.clone())));
+        let remember = saved.is_some();
+        let (username, password) = match saved {
+            Some((username, _)) if rejected => (username, String::new()),
+            Some(saved) => saved,
+            None => Default::default(),
+        };
+        self.auth_prompt = Some(AuthPrompt { origin, challenge, username, password, remember, rejected });
     }
     
-    pub fn handle_network_response(&mut self, result: Result<HttpResponse, String>) {
-        self.loading = false;
+    // The modal credential prompt over the page. True once the page is requested again with the credentials
+    fn show_auth_prompt(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(prompt) = self.auth_prompt.as_mut() else {
+            return false;
+        };
+        let ctx = ui.ctx().clone();
+        ctx.layer_painter(egui::LayerId::new(egui::Order::Middle, egui::Id::new("auth_prompt_backdrop")))
+            .rect_filled(ctx.screen_rect(), 0.0, egui::Color32::from_black_alpha(120));
         
+        // Without an unlocked vault, only a keychain-protected one can be created on the spot
+        let can_remember = PasswordManager::shared().lock().is_ok_and(|passwords| {
+            passwords.is_unlocked() || (!passwords.is_set_up() && password_manager::keychain_available())
+        });
+        let (mut submit, mut cancel) = (false, false);
+        egui::Window::new(format!("{} Sign in", NeonIcons::LOCK))
+            .collapsible(false)
+            .resizable(false)
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(&ctx, |ui| {
+                ui.label(egui::RichText::new(format!("{} asks for a username and password", prompt.origin)).color(NeonTheme::primary_text()));
+                if !prompt.challenge.realm.is_empty() {
+                    ui.label(egui::RichText::new(format!("“{}”", prompt.challenge.realm)).color(NeonTheme::muted_text()));
+                }
+                if prompt.challenge.scheme == AuthScheme::Basic && prompt.origin.starts_with("http://") {
+                    ui.label(egui::RichText::new(format!("{} This connection isn't secure: the password is sent readable to anyone on the network", NeonIcons::WARNING))
+                        .size(12.0)
+                        .color(NeonTheme::warning_color()));
+                }
+                if prompt.rejected {
+                    ui.label(egui::RichText::new("The username or password was not accepted").color(NeonTheme::error_color()));
+                }
+                ui.add_space(6.0);
+                egui::Grid::new("auth_prompt").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                    ui.label("Username");
+                    ui.text_edit_singleline(&mut prompt.username);
+                    ui.end_row();
+                    ui.label("Password");
+                    let response = ui.add(egui::TextEdit::singleline(&mut prompt.password).password(true));
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        submit = true;
+                    }
+                    ui.end_row();
+                });
+                prompt.remember &= can_remember;
+                ui.add_enabled(can_remember, egui::Checkbox::new(&mut prompt.remember, "Save in Passwords"))
+                    .on_disabled_hover_text("Unlock saved passwords at neon://passwords first");
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    submit |= ui.button(egui::RichText::new("Sign in").color(NeonTheme::neon_cyan())).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if cancel {
+            self.auth_prompt = None;
+        }
+        if !submit {
+            return false;
+        }
+        let Some(prompt) = self.auth_prompt.take() else {
+            return false;
+        };
+        if prompt.remember {
+            if let Ok(mut passwords) = PasswordManager::shared().lock() {
+                let set_up = if passwords.is_set_up() { Ok(()) } else { passwords.set_up(None) };
+                if let Err(e) = set_up.and_then(|_| passwords.save(&prompt.origin, &prompt.username, &prompt.password)) {
+                    log::warn!("Couldn't save the password for {}: {}", prompt.origin, e);
+                }
+            }
+        }
+        if let Ok(mut auth) = AuthCache::shared().lock() {
+            auth.insert(&prompt.origin, prompt.challenge, prompt.username, prompt.password);
+        }
+        self.reload()
+    }
+    
+    fn show_oversized_bar(&mut self, ui: &mut egui::Ui, oversized: OversizedBody) {
+        let mb = |bytes: usize| bytes as f64 / 1_048_576.0;
+        let size = match oversized.expected {
+            Some(expected) => format!("This page is {:.1} MB", mb(expected)),
+            None => format!("This page is over {:.1} MB", mb(oversized.received.max(oversized.limit))),
+        };
+        egui::Frame::none()
+            .fill(NeonTheme::info_color().gamma_multiply(0.15))
+            .stroke(egui::Stroke::new(1.0, NeonTheme::info_color()))
+            .rounding(6.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new(format!(
+                        "{} {}, larger than your {:.0} MB page size limit, so it was not loaded.",
+                        NeonIcons::INFO, size, mb(oversized.limit)
+                    )).color(NeonTheme::info_color()));
+                    if ui.button("Continue loading full content").clicked() {
+                        self.page_actions.push(PageAction::LoadFullContent);
+                    }
+                    if ui.button(format!("{} Download instead", NeonIcons::DOWNLOAD)).clicked() {
+                        self.page_actions.push(PageAction::Download(self.url.clone()));
+                    }
+                });
+            });
+    }
+    
+    /// Send the page's text to the translation service set in settings
+    pub fn translate_page(&mut self) {
+        if let Some(web_page) = &self.web_page {
+            self.translation = Some(PageTranslation::start(&web_page.dom, &Preferences::current().translation));
+        }
+    }
+    
+    /// Put the page's own text back
+    pub fn show_original(&mut self) {
+        if let (Some(translation), Some(web_page)) = (self.translation.take(), self.web_page.as_mut()) {
+            let original = translation.revert(&web_page.dom);
+            web_page.update_dom(&original);
+        }
+    }
+    
+    /// Whether the page is translated or being translated
+    pub fn is_translated(&self) -> bool {
+        self.translation.is_some()
+    }
+    
+    fn show_translation_bar(&mut self, ui: &mut egui::Ui) {
+        let Some(translation) = self.translation.as_mut() else { return };
+        if let Some(web_page) = self.web_page.as_mut() {
+            if let Some(translated) = translation.poll(&web_page.dom) {
+                web_page.update_dom(&translated);
+            }
+        }
+        let color = if translation.error().is_some() { NeonTheme::warning_color() } else { NeonTheme::info_color() };
+        let message = if let Some(error) = translation.error() {
+            format!("{} Couldn't translate this page: {}", NeonIcons::WARNING, error)
+        } else if translation.is_pending() {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+            format!("{} Translating into {}...", NeonIcons::GLOBE, translation.target)
+        } else {
+            match &translation.source {
+                Some(source) => format!("{} Translated from {} into {}", NeonIcons::GLOBE, source, translation.target),
+                None => format!("{} Translated into {}", NeonIcons::GLOBE, translation.target),
+            }
+        };
+        let (shown, pending) = (translation.is_shown(), translation.is_pending());
+        let mut close = false;
+        egui::Frame::none()
+            .fill(color.gamma_multiply(0.15))
+            .stroke(egui::Stroke::new(1.0, color))
+            .rounding(6.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new(message).color(color));
+                    let label = if shown { "Show original" } else if pending { "Cancel" } else { "Dismiss" };
+                    close = ui.button(label).clicked();
+                });
+            });
+        if close {
+            self.show_original();
+        }
+    }
+    
+    fn show_slow_script_prompt(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::none()
+            .fill(NeonTheme::warning_color().gamma_multiply(0.15))
+            .stroke(egui::Stroke::new(1.0, NeonTheme::warning_color()))
+            .rounding(6.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new(format!("{} A script is slowing down this page.", NeonIcons::WARNING))
+                        .color(NeonTheme::warning_color()));
+                    if ui.button("Stop").on_hover_text("Stop the page's scripts until it's reloaded").clicked() {
+                        if let Some(web_page) = self.web_page.as_mut() {
+                            web_page.stop_scripts();
+                        }
+                        self.slow_script_prompt = false;
+                    }
+                    if ui.button("Wait").on_hover_text("Let its scripts run for longer").clicked() {
+                        if let Some(web_page) = self.web_page.as_mut() {
+                            web_page.wait_for_scripts();
+                        }
+                        self.slow_script_prompt = false;
+                    }
+                });
+            });
+    }
+    
+    fn show_notification_prompt(&mut self, ui: &mut egui::Ui, origin: &str) {
+        egui::Frame::none()
+            .fill(NeonTheme::info_color().gamma_multiply(0.15))
+            .stroke(egui::Stroke::new(1.0, NeonTheme::info_color()))
+            .rounding(6.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new(format!("{} {} wants to show notifications.", NeonIcons::INFO, origin))
+                        .color(NeonTheme::info_color()));
+                    let answer = if ui.button("Allow").clicked() {
+                        Some(true)
+                    } else if ui.button("Block").clicked() {
+                        Some(false)
+                    } else {
+                        None
+                    };
+                    if let Some(allowed) = answer {
+                        Preferences::update(|p| p.notifications.set_permission(origin, Some(allowed)));
+                        self.notification_prompt = None;
+                    }
+                    if ui.small_button(NeonIcons::X).on_hover_text("Not now").clicked() {
+                        self.notification_prompt = None;
+                    }
+                });
+            });
+    }
+    
+    fn show_password_prompt(&mut self, ui: &mut egui::Ui) {
+        let Some(prompt) = self.password_prompt.as_mut() else {
+            return;
+        };
+        let passwords = PasswordManager::shared();
+        let Ok(mut passwords) = passwords.lock() else {
+            return;
+        };
+        // A locked or new vault needs the master password first, unless the OS keychain holds the key
+        let needs_master = match passwords.protection() {
+            None => !password_manager::keychain_available(),
+            Some(Protection::MasterPassword { .. }) => !passwords.is_unlocked(),
+            Some(Protection::Keychain) => false,
+        };
+        let mut close = false;
+        egui::Frame::none()
+            .fill(NeonTheme::info_color().gamma_multiply(0.15))
+            .stroke(egui::Stroke::new(1.0, NeonTheme::info_color()))
+            .rounding(6.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    let account = if prompt.username.is_empty() { String::new() } else { format!(" for {}", prompt.username) };
+                    ui.label(egui::RichText::new(format!("{} Save password{} on {}?", NeonIcons::SHIELD_CHECK, account, prompt.origin))
+                        .color(NeonTheme::info_color()));
+                    if needs_master {
+                        let hint = if passwords.is_set_up() { "Master password" } else { "Choose a master password" };
+                        ui.add(egui::TextEdit::singleline(&mut prompt.master_password).password(true).hint_text(hint).desired_width(160.0));
+                    }
+                    if ui.button("Save").clicked() {
+                        let result = match passwords.protection() {
+                            None if needs_master => passwords.set_up(Some(&prompt.master_password)),
+                            None => passwords.set_up(None),
+                            Some(_) if needs_master => passwords.unlock(&prompt.master_password),
+                            Some(_) => Ok(()),
+                        }.and_then(|_| passwords.save(&prompt.origin, &prompt.username, &prompt.password));
+                        match result {
+                            Ok(()) => close = true,
+                            Err(e) => prompt.error = Some(e.to_string()),
+                        }
+                    }
+                    if ui.button("Never for this site").clicked() {
+                        match passwords.never_save(&prompt.origin) {
+                            Ok(()) => close = true,
+                            Err(e) => prompt.error = Some(e.to_string()),
+                        }
+                    }
+                    if ui.small_button(NeonIcons::X).on_hover_text("Not now").clicked() {
+                        close = true;
+                    }
+                    if let Some(error) = &prompt.error {
+                        ui.label(egui::RichText::new(error).color(NeonTheme::error_color()));
+                    }
+                });
+            });
+        if close {
+            self.password_prompt = None;
+        }
+    }
+    
+    /// Actions requested from the page since the last call
+    pub fn take_page_actions(&mut self) -> Vec<PageAction> {
+        std::mem::take(&mut self.page_actions)
+    }
+    
+    /// Route pointer input over the rendered page to the element under it. Returns a link to follow.
+    fn handle_page_pointer(&mut self, ui: &mut egui::Ui, page_rect: egui::Rect) -> Option<String> {
+        let (hover, primary, secondary, middle, modifiers, escape) = ui.input(|i| (
+            i.pointer.hover_pos().filter(|pos| page_rect.contains(*pos)),
+            i.pointer.primary_clicked(),
+            i.pointer.secondary_clicked(),
+            i.pointer.button_clicked(egui::PointerButton::Middle),
+            i.modifiers,
+            i.key_pressed(egui::Key::Escape),
+        ));
+        
+        // Gestures only start over the page, and the clicks making them don't reach it
+        let mouse = Preferences::current().mouse.clone();
+        let sample = ui.input(|i| PointerSample { pos: hover, ..PointerSample::read(i) });
+        match self.gestures.update(&mouse, sample) {
+            Some(Gesture::Back) => self.page_actions.push(PageAction::GoBack),
+            Some(Gesture::Forward) => self.page_actions.push(PageAction::GoForward),
+            None => {}
+        }
+        if let (Some(start), Some(pos)) = (self.gestures.drag_start(), hover) {
+            if start.distance(pos) > 10.0 {
+                ui.painter().line_segment([start, pos], egui::Stroke::new(3.0, NeonTheme::neon_cyan().gamma_multiply(0.7)));
+            }
+        }
+        if self.gestures.swallows_clicks() {
+            return None;
+        }
+        
+        if self.context_menu.is_some() {
+            // Any click outside the menu, or Escape, dismisses it without reaching the page
+            let menu_rect = self.show_context_menu(ui);
+            let outside = !hover.is_some_and(|pos| menu_rect.contains(pos));
+            if escape || ((primary || secondary) && outside) {
+                self.context_menu = None;
+            }
+            return None;
+        }
+        
+        if hover.is_none() {
+            self.hovered_link = None;
+        }
+        let pos = hover?;
+        let web_page = self.web_page.as_ref()?;
+        let target = web_page.hit_test(pos);
+        let link = target.as_ref()
+            .and_then(|t| t.link.as_deref())
+            .and_then(|href| self.resolve_link(href));
+        if link != self.hovered_link {
+            self.hovered_link.clone_from(&link);
+            if let Some(url) = &link {
+                self.page_actions.push(PageAction::Preconnect(url.clone()));
+            }
+        }
+        
+        if secondary {
+            self.context_menu = target.map(|target| (pos, target));
+            return None;
+        }
+        
+        if let Some(url) = &link {
+            egui::Area::new(ui.id().with("hovered_link"))
+                .order(egui::Order::Tooltip)
+                .interactable(false)
+                .pivot(egui::Align2::LEFT_BOTTOM)
+                .fixed_pos(page_rect.left_bottom())
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::none()
+                        .fill(NeonTheme::darker_bg())
+                        .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+                        .rounding(4.0)
+                        .inner_margin(egui::Margin::symmetric(8.0, 3.0))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(url).size(11.0).color(NeonTheme::secondary_text()));
+                        });
+                });
+        }
+        
+        if middle {
+            if let Some(url) = link {
+                self.page_actions.push(PageAction::OpenInNewTab(url));
+            }
+            return None;
+        }
+        if !primary {
+            return None;
+        }
+        self.last_activation = Some(Instant::now());
+        if let (Some(target), Some(engine)) = (&target, &web_page.js_engine) {
+            if engine.has_event_listeners("click") {
+                let event = EventData {
+                    event_type: "click".to_string(),
+                    target: target.selector(),
+                    data: [
+                        ("clientX".to_string(), format!("{:.0}", pos.x - page_rect.left())),
+                        ("clientY".to_string(), format!("{:.0}", pos.y - page_rect.top())),
+                        ("path".to_string(), format!("{:?}", target.path)),
+                    ].into_iter().collect(),
+                };
+                if let Err(e) = engine.trigger_event(event) {
+                    log::warn!("click handler failed: {}", e);
+                }
+            }
+        }
+        self.open_link(link?, LinkDisposition::from_click(false, modifiers))
+    }
+    
+    // Carry out a link's disposition. Returns the URL when it should load in this tab; modified clicks leave
+    // the current page where it is.
+    fn open_link(&mut self, url: String, disposition: LinkDisposition) -> Option<String> {
+        match disposition {
+            LinkDisposition::CurrentTab => return Some(url),
+            LinkDisposition::BackgroundTab => self.page_actions.push(PageAction::OpenInNewTab(url)),
+            LinkDisposition::NewWindow => self.page_actions.push(PageAction::OpenInNewWindow(url)),
+            LinkDisposition::Download => self.page_actions.push(PageAction::Download(url)),
+        }
+        None
+    }
+    
+    /// Draw the open context menu and carry out its commands. Returns the area the menu covers.
+    fn show_context_menu(&mut self, ui: &mut egui::Ui) -> egui::Rect {
+        let Some((pos, target)) = self.context_menu.clone() else {
+            return egui::Rect::NOTHING;
+        };
+        let link = target.link.as_deref().and_then(|href| self.resolve_link(href));
+        let image = target.image_src().and_then(|src| self.resolve_link(src));
+        let video = self.web_page.as_ref().and_then(|page| page.video_source(&target.path));
+        
+        ui.painter().rect_stroke(target.rect, 2.0, egui::Stroke::new(1.0, NeonTheme::neon_cyan()));
+        
+        let mut close = false;
+        let menu = egui::Area::new(ui.id().with("page_context_menu"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(pos)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::menu(ui.style()).show(ui, |ui| {
+                    ui.set_min_width(180.0);
+                    if let Some(link) = &link {
+                        if ui.button("Open link in new tab").clicked() {
+                            self.page_actions.push(PageAction::OpenInNewTab(link.clone()));
+                            close = true;
+                        }
+                        if ui.button("Copy link address").clicked() {
+                            ui.ctx().copy_text(link.clone());
+                            close = true;
+                        }
+                        ui.separator();
+                    }
+                    if let Some(image) = &image {
+                        if ui.button("Copy image address").clicked() {
+                            ui.ctx().copy_text(image.clone());
+                            close = true;
+                        }
+                        ui.separator();
+                    }
+                    if let Some(video) = &video {
+                        if ui.button("Picture-in-picture").clicked() {
+                            self.page_actions.push(PageAction::PictureInPicture(video.clone()));
+                            close = true;
+                        }
+                        ui.separator();
+                    }
+                    if ui.button(format!("{} Inspect element", NeonIcons::CODE)).clicked() {
+                        self.page_actions.push(PageAction::Inspect(target.clone()));
+                        close = true;
+                    }
+                    if let Some(site) = site_for_url(&self.url) {
+                        if ui.button(format!("Edit styles for {}", site)).clicked() {
+                            if let Ok(mut styles) = UserStyles::shared().lock() {
+                                styles.request_edit(site);
+                            }
+                            self.page_actions.push(PageAction::OpenInNewTab("neon://settings".to_string()));
+                            close = true;
+                        }
+                    }
+                });
+            });
+        
+        if close {
+            self.context_menu = None;
+        }
+        menu.response.rect
+    }
+    
+    /// Absolute URL for an href or src on the current page; None for in-page and script links, and for targets
+    /// the page isn't allowed to navigate to
+    fn resolve_link(&self, href: &str) -> Option<String> {
+        if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+            return None;
+        }
+        url::Url::parse(href)
+            .or_else(|_| url::Url::parse(&self.url).and_then(|base| base.join(href)))
+            .ok()
+            .map(String::from)
+            .filter(|url| location::navigation_allowed(&self.url, url))
+    }
+    
+    fn load_top_sites() -> Vec<HistoryEntry> {
+        let Some(history) = HistoryDatabase::shared() else {
+            return Vec::new();
+        };
+        let mut entries: Vec<HistoryEntry> = history.get_entries().unwrap_or_default().into_iter()
+            .filter(|entry| entry.url.starts_with("http://") || entry.url.starts_with("https://"))
+            .collect();
+        entries.sort_by(|a, b| b.visit_count.cmp(&a.visit_count).then_with(|| b.last_visit.cmp(&a.last_visit)));
+        entries.truncate(TOP_SITES);
+        entries
+    }
+    
+    // Row of most visited sites above the home page; returns the URL of a clicked site
+    fn show_top_sites(&self, ui: &mut egui::Ui) -> Option<String> {
+        let metadata = SiteMetadataService::shared();
+        let mut clicked = None;
+        
+        ui.add_space(8.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(12.0, 12.0);
+            for entry in &self.top_sites {
+                let name = metadata.as_ref()
+                    .and_then(|m| m.lookup(&entry.url))
+                    .map(|site| site.name)
+                    .unwrap_or_else(|| entry.title.clone());
+                
+                let response = egui::Frame::none()
+                    .fill(NeonTheme::card_bg())
+                    .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(12.0, 10.0))
+                    .show(ui, |ui| {
+                        ui.set_width(96.0);
+                        ui.vertical_centered(|ui| {
+                            components::site_icon(ui, &entry.url, 24.0);
+                            ui.add(egui::Label::new(
+                                egui::RichText::new(name).size(12.0).color(NeonTheme::primary_text())
+                            ).truncate());
+                        });
+                    })
+                    .response
+                    .interact(egui::Sense::click())
+                    .on_hover_text(&entry.url);
+                if response.clicked() {
+                    clicked = Some(entry.url.clone());
+                }
+            }
+        });
+        ui.add_space(8.0);
+        
+        clicked
+    }
+    
+    /// Stop any subresource loads for the current page
+    /// Drop a page whose rendering panicked, so the tab shows the crash instead of panicking every frame
+    pub fn mark_crashed(&mut self, message: String) {
+        self.cancel_subresources();
+        self.web_page = None;
+        self.context_menu = None;
+        self.crashed = Some(message);
+    }
+    
+    /// Force the page out of memory, stopping its loads and scripts; the tab offers a reload in its place
+    pub fn kill(&mut self) {
+        self.load.cancel();
+        self.current_response = None;
+        self.mark_crashed("Ended from the task manager.".to_string());
+    }
+    
+    /// The connection the document came over, when it was HTTPS
+    pub fn tls_info(&self) -> Option<&TlsInfo> {
+        self.network_log.iter().rev()
+            .find(|entry| entry.kind == "Document")
+            .and_then(|entry| entry.timing.as_ref()?.tls.as_ref())
+    }
+    
+    /// What the tab uses, for neon://tasks
+    pub fn usage(&self, id: Uuid) -> TabUsage {
+        let mut memory = self.web_page.as_ref().map(WebPage::memory_estimate).unwrap_or_default();
+        memory.strings += self.current_response.as_ref().map_or(0, |response| response.body.len());
+        let resources = self.resources.as_ref().map_or(0, |resources| {
+            let progress = resources.progress();
+            progress.total.saturating_sub(progress.completed)
+        });
+        TabUsage {
+            id,
+            title: self.title.clone(),
+            url: self.url.clone(),
+            memory,
+            cpu: self.web_page.as_ref().map(WebPage::cpu_time).unwrap_or_default(),
+            requests: usize::from(self.load.is_loading()) + resources,
+            has_page: self.web_page.is_some(),
+        }
+    }
+    
+    pub fn cancel_subresources(&mut self) {
+        if let Some(resources) = self.resources.take() {
+            resources.cancel();
+        }
+    }
+    
+    /// Open the print preview for the current page, if there is one
+    pub fn open_print_preview(&mut self) {
+        if let Some(page) = &self.web_page {
+            self.print_preview.open_for(page, &self.title, &self.url);
+        }
+    }
+    
+    /// Show the current page in reader mode; false when it has no article to show
+    pub fn open_reader(&mut self) -> bool {
+        match &self.web_page {
+            Some(page) => self.reader.open_for(page, &self.title),
+            None => false,
+        }
+    }
+    
+    /// Enter or leave reader mode
+    pub fn toggle_reader(&mut self) {
+        if self.reader.is_open() {
+            self.reader.close();
+        } else if !self.open_reader() {
+            log::info!("No article found on {} for reader mode", self.url);
+        }
+    }
+    
+    /// Whether the page has media playing sound, for the tab's audio indicator
+    pub fn is_playing_audio(&self) -> bool {
+        self.web_page.as_ref().is_some_and(|page| page.is_playing_audio())
+    }
+    
+    pub fn toggle_audio_muted(&mut self) {
+        self.audio_muted = !self.audio_muted;
+        if let Some(page) = &self.web_page {
+            page.set_audio_muted(self.audio_muted);
+        }
+    }
+    
+    /// Adopt the final URL of a navigation after the networking layer followed redirects
+    pub fn apply_redirect_chain(&mut self, final_url: String, chain: Vec<RedirectHop>) {
+        if final_url != self.url {
+            log::info!("Redirected {} -> {}", self.url, final_url);
+            self.url = final_url.clone();
+            if let Some(entry) = self.history.get_mut(self.history_index) {
+                entry.url = final_url;
+            }
+        }
+        // After a 303 (or a POST 301/302) the page was fetched with GET, so reloading it is safe
+        if chain.last().is_some_and(|hop| hop.method == "GET") {
+            if let Some(entry) = self.history.get_mut(self.history_index) {
+                entry.post = None;
+            }
+        }
+        self.redirect_chain = chain;
+    }
+    
+    /// Show a finished navigation; `document` is the body already parsed off the UI thread, when available.
+    /// Responses for a superseded navigation are dropped.
+    pub fn handle_network_response(&mut self, generation: u64, result: Result<HttpResponse, String>, document: Option<ParsedDocument>) {
+        if !self.load.advance(generation, LoadState::Rendering) {
+            if let Ok(response) = &result {
+                let _ = response.cleanup_temp_file();
+            }
+            return;
+        }
+        // Past the point of stopping, so the page before it isn't needed any more
+        self.previous = None;
+        // The loaded page gets its own look for a login form
+        self.login_autofilled = false;
+        
+        let mut error = None;
+        self.security_report = None;
         match result {
             Ok(response) => {
                 // Store the response for potential cleanup later
                 self.current_response = Some(response.clone());
-                if response.is_redirect() {
-                    if let Some(location) = response.get_header("Location").cloned()
-                        .or_else(|| response.get_header("location").cloned()) {
-                        // Basic absolute/relative handling
-                        let new_url = if location.starts_with("http://") || location.starts_with("https://") {
-                            location
-                        } else {
-                            // Resolve relative (very naive)
-                            if let Ok(base) = reqwest::Url::parse(&self.url) {
-                                if let Ok(joined) = base.join(&location) { joined.to_string() } else { location }
-                            } else { location }
-                        };
-                        if self.redirects_followed < 5 { // cap redirects
-                            self.redirects_followed += 1;
-                            self.url = new_url.clone();
-                            // Prepare loading placeholder; actual fetch triggered externally after this returns
-                            self.web_page = Some(WebPage::create_loading_page(&new_url));
-                            self.title = format!("Redirecting {}", new_url);
-                            // Indicate to caller they should refetch by setting error? Instead log.
-                            log::info!("Following redirect to {}", new_url);
-                        } else {
-                            self.error = Some("Too many redirects".to_string());
-                            self.web_page = Some(WebPage::create_error_page(&self.url, "Too many redirects"));
-                        }
-                    } else {
-                        self.error = Some("Redirect with no Location header".to_string());
-                        self.web_page = Some(WebPage::create_error_page(&self.url, "Redirect without location"));
-                    }
+                if response.is_success() && (self.url.starts_with("https://") || self.url.starts_with("http://")) {
+                    self.security_report = Some(SecurityReport::evaluate(&self.url, &response));
+                }
+                self.truncation = response.truncation.clone();
+                if let Some(reason) = &self.truncation {
+                    log::warn!("Incomplete response for {}: {}", self.url, reason);
+                }
+                if response.status_code == 401 {
+                    self.prompt_for_credentials(&response);
+                }
+                // A data:, blob: or file:// URL that isn't HTML is shown or saved according to its type
+                let presentation = (local_url::is_local(&self.url) || file_url::is_file(&self.url))
+                    .then(|| Presentation::of(response.content_type().map_or("", String::as_str)))
+                    .filter(|presentation| *presentation != Presentation::Html);
+                let content_type = response.content_type().map_or("", String::as_str);
+                if response.is_success() && response.oversized.is_none() && image_viewer::handles(content_type) {
+                    error = self.show_image(&response);
+                } else if response.is_success() && response.oversized.is_none() && pdf_viewer::handles(content_type) {
+                    error = self.show_pdf(&response);
+                } else if response.is_success() && response.oversized.is_none() && data_viewer::handles(content_type) {
+                    error = self.show_data(&response);
+                } else if let Some(presentation) = presentation {
+                    self.show_local_content(presentation, &response);
+                } else if let Some(oversized) = response.oversized {
+                    self.show_oversized(oversized);
+                } else if response.is_redirect() {
+                    // Redirects are followed in the networking layer; one reaching the tab could not be followed
+                    let location = response.get_header("Location").cloned().unwrap_or_default();
+                    let message = format!("Redirect ({}) to '{}' could not be followed", response.status_code, location);
+                    error = Some(message.clone());
+                    self.web_page = Some(WebPage::create_error_page(&self.url, &message));
+                } else if let Some(document) = document.filter(|_| response.is_success()) {
+                    let page = WebPage::from_parsed(document);
+                    self.title = page.extracted_title.clone().unwrap_or_else(|| self.url.clone());
+                    self.web_page = Some(page);
                 } else if response.is_success() {
                     // Check raw body size first to avoid UI blocking
                     let is_large_raw = response.body.len() > 50_000; // 50KB threshold for raw content (Google.com is ~71KB)
                     
                     // For large raw content, get decompressed content but use simpler rendering
                     let (html_result, use_simple_rendering) = if is_large_raw {
-                        println!("Large raw content detected ({} bytes), using simplified rendering", response.body.len());
+                        log::info!("Large raw content detected ({} bytes), using simplified rendering", response.body.len());
                         // Get the full decompressed content but we'll render it simply
                         match response.body_as_string() {
                             Ok(full_html) => {
@@ -226,7 +1753,7 @@ impl BrowserTab {
                             Err(e) => (Err(e), true)
                         }
                     } else if response.is_large_content() {
-                        println!("Large content detected, using preview mode");
+                        log::info!("Large content detected, using preview mode");
                         (response.body_as_preview(100_000), false) // 100KB preview for large sites
                     } else {
                         (response.body_as_string(), false)
@@ -262,31 +1789,122 @@ impl BrowserTab {
                             
                             self.title = page.extracted_title.clone().unwrap_or_else(|| self.url.clone());
                             self.web_page = Some(page);
-                            self.error = None;
-                        }
-                        Err(e) => {
-                            self.error = Some(format!("Failed to parse response: {}", e));
-                            self.web_page = Some(WebPage::create_error_page(&self.url, &e.to_string()));
                         }
+                        // Compressed bodies can pass the limit only once decoded
+                        Err(e) => match e.downcast_ref::<ContentTooLarge>() {
+                            Some(&ContentTooLarge { limit }) => {
+                                self.show_oversized(OversizedBody { limit, received: limit, expected: None });
+                            }
+                            None => {
+                                error = Some(format!("Failed to parse response: {}", e));
+                                self.web_page = Some(WebPage::create_error_page(&self.url, &e.to_string()));
+                            }
+                        },
                     }
                 } else {
                     let error_msg = format!("HTTP {}", response.status_code);
-                    self.error = Some(error_msg.clone());
+                    error = Some(error_msg.clone());
                     self.web_page = Some(WebPage::create_error_page(&self.url, &error_msg));
                 }
             }
             Err(e) => {
-                self.error = Some(e.clone());
                 self.web_page = Some(WebPage::create_error_page(&self.url, &e));
+                error = Some(e);
             }
         }
+        
+        match error {
+            Some(error) => self.load.advance(generation, LoadState::Error(error)),
+            None => self.load.advance(generation, LoadState::Complete),
+        };
     }
 
+    // Stand in for a page that passed the size limit until the user picks an option from the info bar
+    fn show_local_content(&mut self, presentation: Presentation, response: &HttpResponse) {
+        self.title = self.url.chars().take(60).collect();
+        self.web_page = Some(match presentation {
+            Presentation::Text => WebPage::create_plain_text_page(&String::from_utf8_lossy(&response.body), &self.url),
+            Presentation::Image => WebPage::create_image_page(&self.url),
+            // A file already on disk has nothing to download
+            Presentation::Download if file_url::is_file(&self.url) => {
+                let mime_type = response.content_type().map_or("this", String::as_str);
+                WebPage::create_error_page(&self.url, &format!("NeonSearch can't show {} files", mime_type))
+            }
+            Presentation::Html | Presentation::Download => {
+                self.page_actions.push(PageAction::Download(self.url.clone()));
+                WebPage::create_blank_page()
+            }
+        });
+    }
+    
+    // An image navigated to directly opens in the image viewer; an error message when it can't be decoded
+    fn show_image(&mut self, response: &HttpResponse) -> Option<String> {
+        match ImageViewer::open(response, &self.url) {
+            Ok(viewer) => {
+                self.title = viewer.title();
+                self.web_page = Some(WebPage::create_blank_page());
+                self.image_viewer = Some(viewer);
+                None
+            }
+            Err(e) => {
+                log::warn!("Couldn't show the image at {}: {}", self.url, e);
+                self.web_page = Some(WebPage::create_error_page(&self.url, &e.to_string()));
+                Some(e.to_string())
+            }
+        }
+    }
+    
+    // A PDF navigated to opens in the PDF viewer, which parses it in the background
+    fn show_pdf(&mut self, response: &HttpResponse) -> Option<String> {
+        match PdfViewer::open(response, &self.url) {
+            Ok(viewer) => {
+                self.title = viewer.title();
+                self.web_page = Some(WebPage::create_blank_page());
+                self.pdf_viewer = Some(viewer);
+                None
+            }
+            Err(e) => {
+                log::warn!("Couldn't show the PDF at {}: {}", self.url, e);
+                self.web_page = Some(WebPage::create_error_page(&self.url, &e.to_string()));
+                Some(e.to_string())
+            }
+        }
+    }
+    
+    // JSON and XML open in the data viewer; one that doesn't parse is shown as raw text with the error
+    fn show_data(&mut self, response: &HttpResponse) -> Option<String> {
+        match DataViewer::open(response) {
+            Ok(viewer) => {
+                self.title = self.url.clone();
+                self.web_page = Some(WebPage::create_blank_page());
+                self.data_viewer = Some(viewer);
+                None
+            }
+            Err(e) => {
+                log::warn!("Couldn't show the data at {}: {}", self.url, e);
+                self.web_page = Some(WebPage::create_error_page(&self.url, &e.to_string()));
+                Some(e.to_string())
+            }
+        }
+    }
+    
+    fn show_oversized(&mut self, oversized: OversizedBody) {
+        log::warn!("{} passed the {}MB page size limit", self.url, oversized.limit / 1024 / 1024);
+        self.oversized = Some(oversized);
+        self.title = self.url.clone();
+        self.web_page = Some(WebPage::create_blank_page());
+    }
+    
+    /// Begin loading the current URL again; the caller fetches it without the page size limit
+    pub fn load_full_content(&mut self) -> bool {
+        self.load_page()
+    }
+    
     /// Clean up temporary files associated with the current page
     pub fn cleanup_temp_files(&mut self) {
         if let Some(response) = &self.current_response {
             if let Err(e) = response.cleanup_temp_file() {
-                eprintln!("Failed to cleanup temporary file: {}", e);
+                log::warn!("Failed to cleanup temporary file: {}", e);
             }
         }
         self.current_response = None;
@@ -633,4 +2251,118 @@ impl Drop for BrowserTab {
         // Ensure cleanup on drop
         self.cleanup_temp_files();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_disposition() {
+        let none = egui::Modifiers::NONE;
+        assert_eq!(LinkDisposition::from_click(false, none), LinkDisposition::CurrentTab);
+        assert_eq!(LinkDisposition::from_click(true, none), LinkDisposition::BackgroundTab);
+        assert_eq!(LinkDisposition::from_click(false, egui::Modifiers::COMMAND), LinkDisposition::BackgroundTab);
+        assert_eq!(LinkDisposition::from_click(false, egui::Modifiers::SHIFT), LinkDisposition::NewWindow);
+        assert_eq!(LinkDisposition::from_click(false, egui::Modifiers::ALT), LinkDisposition::Download);
+    }
+
+    #[test]
+    fn test_stop_returns_to_previous_page() {
+        let mut tab = BrowserTab::new("New Tab".to_string());
+        assert!(!tab.navigate_to("about:blank".to_string()));
+        assert!(tab.navigate_to("https://slow.example/".to_string()));
+        assert!(tab.navigate_to("https://slower.example/".to_string()));
+        assert!(tab.load.is_loading());
+
+        assert!(tab.stop());
+        assert!(!tab.load.is_loading());
+        assert_eq!((tab.url.as_str(), tab.title.as_str()), ("about:blank", "Blank Page"));
+        assert_eq!(tab.history.len(), 2);
+        assert!(tab.web_page.is_some() && !tab.stop());
+    }
+
+    #[test]
+    fn test_offline_page_waits_to_retry() {
+        let mut tab = BrowserTab::new("New Tab".to_string());
+        assert!(tab.navigate_to("https://example.com/".to_string()));
+        tab.handle_network_response(tab.load.generation(), Err("failed to lookup address".to_string()), None);
+        tab.set_offline(true);
+        assert!(tab.is_offline() && !tab.offline_retry_due());
+
+        // A retry that fails offline again waits longer; a new navigation forgets the offline state
+        assert!(tab.reload());
+        tab.handle_network_response(tab.load.generation(), Err("failed to lookup address".to_string()), None);
+        tab.set_offline(true);
+        assert_eq!(tab.offline.as_ref().map(|retry| retry.attempts), Some(2));
+        assert!(!tab.navigate_to("about:blank".to_string()));
+        assert!(!tab.is_offline());
+    }
+
+    #[test]
+    fn test_pushed_entries_stay_in_the_document() {
+        let mut tab = BrowserTab::new("New Tab".to_string());
+        assert!(tab.navigate_to("https://app.example/".to_string()));
+        tab.load.finish(tab.load.generation());
+        let mut page = WebPage::from_html("<script>window.onpopstate = (e) => { var shown = JSON.stringify(e.state) }\n\
+            history.pushState({ page: 2 }, '', 'list?page=2')</script>", None);
+        page.set_document_url(&tab.url);
+        page.run_inline_scripts();
+        let requests = page.take_navigation_requests();
+        tab.web_page = Some(page);
+        for request in requests {
+            assert!(!tab.apply_navigation_request(request));
+        }
+        assert_eq!((tab.url.as_str(), tab.history.len()), ("https://app.example/list?page=2", 3));
+
+        // Back and forward between the entries fire popstate instead of loading
+        let shown = |tab: &mut BrowserTab| {
+            let engine = tab.web_page.as_mut().and_then(|page| page.js_engine.as_mut()).unwrap();
+            (engine.execute("location.pathname").unwrap(), engine.execute("shown").unwrap())
+        };
+        assert!(!tab.go_back());
+        assert_eq!(shown(&mut tab), ("/".to_string(), "null".to_string()));
+        assert!(!tab.go_forward());
+        assert_eq!(shown(&mut tab), ("/list".to_string(), r#"{"page":2}"#.to_string()));
+        // Leaving the document loads the entry before it
+        assert!(!tab.go_back() && !tab.go_back());
+        assert_eq!((tab.url.as_str(), tab.title.as_str()), ("about:home", "NeonSearch Home"));
+    }
+
+    #[test]
+    fn test_script_navigations_stay_on_the_web() {
+        let mut page = WebPage::from_html("<script>var ready = true</script>", None);
+        page.set_document_url("https://app.example/");
+        page.run_inline_scripts();
+        let engine = page.js_engine.as_mut().unwrap();
+        for target in ["file:///etc/passwd", "data:text/html,hi", "neon://settings"] {
+            assert!(engine.execute(&format!("location.assign('{}')", target)).unwrap().starts_with("SecurityError"));
+        }
+        engine.execute("location.assign('/next')").unwrap();
+        assert_eq!(page.take_navigation_requests(), vec![NavigationRequest::Navigate { url: "https://app.example/next".to_string(), replace: false }]);
+
+        // Links are held to the same rules
+        let mut tab = BrowserTab::new("New Tab".to_string());
+        tab.url = "https://app.example/".to_string();
+        assert_eq!(tab.resolve_link("file:///etc/passwd"), None);
+        assert_eq!(tab.resolve_link("/about").as_deref(), Some("https://app.example/about"));
+    }
+
+    #[test]
+    fn test_system_handoff_needs_confirmation_and_a_gesture() {
+        let mut tab = BrowserTab::new("New Tab".to_string());
+        let script_navigation = |url: &str| NavigationRequest::Navigate { url: url.to_string(), replace: false };
+        assert!(!tab.apply_navigation_request(script_navigation("mailto:ada@example.com")));
+        assert_eq!(tab.handoff_prompt, None);
+
+        // After a click the script may ask, and the user still has to confirm
+        tab.last_activation = Some(Instant::now());
+        assert!(!tab.apply_navigation_request(script_navigation("mailto:ada@example.com")));
+        assert_eq!(tab.handoff_prompt.as_deref(), Some("mailto:ada@example.com"));
+
+        // Schemes outside the allowlist never leave the browser
+        tab.handoff_prompt = None;
+        assert!(!tab.navigate_to("ms-msdt:/id PCWDiagnostic".to_string()));
+        assert_eq!((tab.handoff_prompt.as_deref(), tab.url.as_str()), (None, "about:home"));
+    }
+}
@@ -0,0 +1,335 @@
+// JSON and XML responses as a collapsible tree, with a filter, copying a node's path or value, and the raw text as
+// received or pretty-printed
+use eframe::egui::{self, Color32, RichText};
+use crate::engine::data_tree::{DataFormat, DataNode, DataTree, NodeKind};
+use crate::networking::HttpResponse;
+use crate::ui::{NeonIcons, NeonTheme};
+
+// Documents with more nodes than this open with only the top levels expanded
+const EXPAND_ALL_LIMIT: usize = 2000;
+const INDENT: f32 = 16.0;
+
+/// Whether a response of this type is shown in the data viewer
+pub fn handles(content_type: &str) -> bool {
+    DataFormat::of(content_type).is_some()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ViewMode {
+    Tree,
+    Raw,
+}
+
+pub struct DataViewer {
+    format: DataFormat,
+    raw: String,
+    /// The parsed document, or why it couldn't be parsed
+    tree: Result<DataTree, String>,
+    mode: ViewMode,
+    pretty: bool,
+    pretty_text: Option<String>,
+    /// Per node, whether its children are shown
+    expanded: Vec<bool>,
+    query: String,
+    /// Per node, whether it matches the filter or leads to a match
+    filter: Option<Vec<bool>>,
+    selected: Option<usize>,
+    status_message: Option<String>,
+}
+
+impl DataViewer {
+    pub fn open(response: &HttpResponse) -> anyhow::Result<Self> {
+        let content_type = response.content_type().map_or("", String::as_str);
+        let format = DataFormat::of(content_type).ok_or_else(|| anyhow::anyhow!("Not JSON or XML"))?;
+        Ok(Self::new(format, response.body_as_string()?))
+    }
+
+    pub fn new(format: DataFormat, raw: String) -> Self {
+        let tree = DataTree::parse(format, raw.trim_start_matches('\u{feff}')).map_err(|e| e.to_string());
+        if let Err(e) = &tree {
+            log::info!("Showing {} as raw text: {}", format.label(), e);
+        }
+        let expanded = match &tree {
+            Ok(tree) if tree.nodes.len() <= EXPAND_ALL_LIMIT => vec![true; tree.nodes.len()],
+            Ok(tree) => tree.nodes.iter().map(|node| node.depth < 2).collect(),
+            Err(_) => Vec::new(),
+        };
+        let mode = if tree.is_ok() { ViewMode::Tree } else { ViewMode::Raw };
+        Self {
+            format,
+            raw,
+            tree,
+            mode,
+            pretty: false,
+            pretty_text: None,
+            expanded,
+            query: String::new(),
+            filter: None,
+            selected: None,
+            status_message: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.toolbar(ui);
+        if let Err(error) = &self.tree {
+            egui::Frame::none()
+                .fill(NeonTheme::error_color().gamma_multiply(0.15))
+                .stroke(egui::Stroke::new(1.0, NeonTheme::error_color()))
+                .rounding(6.0)
+                .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+                .show(ui, |ui| {
+                    ui.label(RichText::new(format!("{} Invalid {}: {}", NeonIcons::WARNING, self.format.label(), error))
+                        .color(NeonTheme::error_color()));
+                });
+        }
+        ui.separator();
+        match self.mode {
+            ViewMode::Tree => self.show_tree(ui),
+            ViewMode::Raw => self.show_raw(ui),
+        }
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let parsed = self.tree.is_ok();
+            ui.add_enabled_ui(parsed, |ui| {
+                if ui.selectable_label(self.mode == ViewMode::Tree, "Tree").clicked() {
+                    self.mode = ViewMode::Tree;
+                }
+            });
+            if ui.selectable_label(self.mode == ViewMode::Raw, "Raw").clicked() {
+                self.mode = ViewMode::Raw;
+            }
+            ui.separator();
+            match self.mode {
+                ViewMode::Tree => {
+                    let filter = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Filter").desired_width(180.0));
+                    if filter.changed() {
+                        self.filter = self.tree.as_ref().ok().and_then(|tree| tree.filter(&self.query));
+                    }
+                    if ui.button("Expand all").clicked() {
+                        self.expanded.fill(true);
+                    }
+                    if ui.button("Collapse all").clicked() {
+                        self.expanded.fill(false);
+                    }
+                }
+                ViewMode::Raw => {
+                    ui.add_enabled(parsed, egui::Checkbox::new(&mut self.pretty, "Pretty print"));
+                }
+            }
+            ui.separator();
+            if ui.button("Copy all").clicked() {
+                let text = self.raw_text().to_string();
+                ui.ctx().copy_text(text);
+                self.status_message = Some(format!("Copied the {}", self.format.label()));
+            }
+            ui.label(RichText::new(format!("{} · {}", self.format.label(), size_label(self.raw.len()))).color(NeonTheme::secondary_text()));
+            if let Some(message) = &self.status_message {
+                ui.label(RichText::new(message).size(12.0).color(NeonTheme::success_color()));
+            }
+        });
+    }
+
+    fn raw_text(&mut self) -> &str {
+        match (&self.tree, self.pretty) {
+            (Ok(tree), true) => self.pretty_text.get_or_insert_with(|| tree.pretty()),
+            _ => &self.raw,
+        }
+    }
+
+    fn show_raw(&mut self, ui: &mut egui::Ui) {
+        let text = self.raw_text().to_string();
+        egui::ScrollArea::both()
+            .id_salt("data_raw")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut text.as_str())
+                    .code_editor()
+                    .desired_width(f32::INFINITY));
+            });
+    }
+
+    fn show_tree(&mut self, ui: &mut egui::Ui) {
+        let Ok(tree) = &self.tree else { return };
+        let rows = visible_rows(tree, &self.expanded, self.filter.as_deref());
+        // A JSON object or array at the top shows its members straight away, as there's no name to give it
+        let base_depth = rows.first().map_or(0, |&row| tree.nodes[row].depth);
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + 6.0;
+        let query = self.query.trim().to_lowercase();
+        let mut toggled = None;
+        let mut selected = self.selected;
+        let mut copied = None;
+
+        let footer_height = if selected.is_some() { 32.0 } else { 0.0 };
+        let height = (ui.available_height() - footer_height).max(row_height);
+        egui::ScrollArea::vertical()
+            .id_salt("data_tree")
+            .auto_shrink([false; 2])
+            .max_height(height)
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for &index in &rows[range] {
+                    let node = &tree.nodes[index];
+                    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), row_height), egui::Sense::click());
+                    if selected == Some(index) {
+                        ui.painter().rect_filled(rect, 3.0, NeonTheme::neon_cyan().gamma_multiply(0.15));
+                    } else if response.hovered() {
+                        ui.painter().rect_filled(rect, 3.0, NeonTheme::button_hover().gamma_multiply(0.5));
+                    }
+                    let branch = !node.children.is_empty();
+                    let x = rect.left() + (node.depth - base_depth) as f32 * INDENT;
+                    let mut row = ui.new_child(egui::UiBuilder::new()
+                        .max_rect(egui::Rect::from_min_max(egui::pos2(x, rect.top()), rect.max))
+                        .layout(egui::Layout::left_to_right(egui::Align::Center)));
+                    if branch {
+                        let caret = if self.expanded[index] || self.filter.is_some() { NeonIcons::CARET_DOWN } else { NeonIcons::CARET_RIGHT };
+                        if row.add(egui::Button::new(RichText::new(caret).size(10.0)).frame(false)).clicked() {
+                            toggled = Some(index);
+                        }
+                    } else {
+                        row.add_space(14.0);
+                    }
+                    for (text, color) in row_texts(tree, index, self.expanded[index] || self.filter.is_some()) {
+                        let mut text = RichText::new(text).monospace().color(color);
+                        if !query.is_empty() && self.filter.as_ref().is_some_and(|_| matches_query(node, &query)) {
+                            text = text.background_color(NeonTheme::warning_color().gamma_multiply(0.3));
+                        }
+                        row.label(text);
+                    }
+
+                    if response.double_clicked() && branch {
+                        toggled = Some(index);
+                    } else if response.clicked() {
+                        selected = Some(index);
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("Copy path").clicked() {
+                            copied = Some((node.path.clone(), "path"));
+                            ui.close_menu();
+                        }
+                        if ui.button("Copy value").clicked() {
+                            copied = Some((tree.value_text(index), "value"));
+                            ui.close_menu();
+                        }
+                        if ui.button("Copy key").clicked() {
+                            copied = Some((node.key.clone(), "key"));
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+
+        if let Some(index) = selected.filter(|index| *index < tree.nodes.len()) {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(&tree.nodes[index].path).monospace().color(NeonTheme::secondary_text()));
+                if ui.small_button("Copy path").clicked() {
+                    copied = Some((tree.nodes[index].path.clone(), "path"));
+                }
+                if ui.small_button("Copy value").clicked() {
+                    copied = Some((tree.value_text(index), "value"));
+                }
+            });
+        }
+        if let Some((text, what)) = copied {
+            ui.ctx().copy_text(text);
+            self.status_message = Some(format!("Copied the {}", what));
+        }
+        if let Some(index) = toggled {
+            self.expanded[index] = !self.expanded[index];
+        }
+        self.selected = selected;
+    }
+}
+
+/// The nodes shown as rows, in document order: children of expanded nodes, or while filtering, the nodes on the
+/// way to a match
+fn visible_rows(tree: &DataTree, expanded: &[bool], filter: Option<&[bool]>) -> Vec<usize> {
+    let Some(root) = tree.nodes.first() else { return Vec::new() };
+    let mut stack: Vec<usize> = match root.kind {
+        NodeKind::Object | NodeKind::Array => root.children.iter().rev().copied().collect(),
+        _ => vec![0],
+    };
+    let mut rows = Vec::new();
+    while let Some(index) = stack.pop() {
+        if filter.is_some_and(|shown| !shown[index]) {
+            continue;
+        }
+        rows.push(index);
+        if expanded[index] || filter.is_some() {
+            stack.extend(tree.nodes[index].children.iter().rev());
+        }
+    }
+    rows
+}
+
+// The key and value of a row, each in its colour
+fn row_texts(tree: &DataTree, index: usize, expanded: bool) -> Vec<(String, Color32)> {
+    let node = &tree.nodes[index];
+    let count = node.children.len();
+    let key_color = NeonTheme::neon_purple();
+    let summary = NeonTheme::muted_text();
+    match node.kind {
+        NodeKind::Object | NodeKind::Array => {
+            let (open, close, unit) = if node.kind == NodeKind::Object { ("{", "}", "key") } else { ("[", "]", "item") };
+            let plural = if count == 1 { "" } else { "s" };
+            let shape = if expanded || count == 0 { format!("{}{}", open, if count == 0 { close } else { "" }) } else { format!("{}…{}", open, close) };
+            let mut texts = Vec::new();
+            if node.parent.is_some() {
+                texts.push((format!("{}:", node.key), key_color));
+            }
+            texts.push((shape, NeonTheme::primary_text()));
+            texts.push((format!("{} {}{}", count, unit, plural), summary));
+            texts
+        }
+        NodeKind::Element => {
+            let attributes = node.children.iter().filter(|&&child| tree.nodes[child].kind == NodeKind::Attribute).count();
+            let mut texts = vec![(format!("<{}>", node.key), NeonTheme::neon_cyan())];
+            if !node.value.is_empty() {
+                texts.push((node.value.clone(), NeonTheme::neon_green()));
+            } else if count > 0 && !expanded {
+                texts.push((format!("{} children", count - attributes), summary));
+            }
+            texts
+        }
+        NodeKind::Attribute => vec![(format!("{}:", node.key), key_color), (format!("\"{}\"", node.value), NeonTheme::neon_green())],
+        NodeKind::Text => vec![(node.value.clone(), NeonTheme::neon_green())],
+        NodeKind::String => vec![
+            (format!("{}:", node.key), key_color),
+            (serde_json::to_string(&node.value).unwrap_or_default(), NeonTheme::neon_green()),
+        ],
+        NodeKind::Number => vec![(format!("{}:", node.key), key_color), (node.value.clone(), NeonTheme::neon_cyan())],
+        NodeKind::Bool | NodeKind::Null => vec![(format!("{}:", node.key), key_color), (node.value.clone(), NeonTheme::neon_orange())],
+    }
+}
+
+fn matches_query(node: &DataNode, query: &str) -> bool {
+    node.key.to_lowercase().contains(query) || node.value.to_lowercase().contains(query)
+}
+
+fn size_label(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_follow_expansion_and_filter() {
+        let tree = DataTree::parse(DataFormat::Json, r#"{"a": {"b": 1, "c": 2}, "d": [3]}"#).unwrap();
+        let paths = |rows: Vec<usize>| rows.into_iter().map(|row| tree.nodes[row].path.clone()).collect::<Vec<_>>();
+        let mut expanded = vec![true; tree.nodes.len()];
+        assert_eq!(paths(visible_rows(&tree, &expanded, None)), ["$.a", "$.a.b", "$.a.c", "$.d", "$.d[0]"]);
+        expanded[1] = false;
+        assert_eq!(paths(visible_rows(&tree, &expanded, None)), ["$.a", "$.d", "$.d[0]"]);
+        // Filtering opens the way to matches in collapsed nodes
+        let filter = tree.filter("c");
+        assert_eq!(paths(visible_rows(&tree, &expanded, filter.as_deref())), ["$.a", "$.a.c"]);
+    }
+}
@@ -1,7 +1,9 @@
 // Developer Console UI for JavaScript debugging
 use eframe::egui;
-use crate::js::JSEngine;
-use crate::ui::{NeonTheme, NeonIcons};
+use crate::js::{ErrorSeverity, JSEngine, ScriptError};
+use crate::js::locale::{self, LocaleOverrides};
+use crate::networking::throttling::NetworkConditions;
+use crate::ui::{NeonTheme, NeonIcons, NetworkEntry, waterfall};
 use std::collections::VecDeque;
 
 #[derive(Debug, Clone)]
@@ -57,8 +59,20 @@ pub struct DevConsole {
     auto_scroll: bool,
     command_history: Vec<String>,
     history_index: Option<usize>,
+    // Device emulation toolbar for the active tab's locale and time zone
+    show_emulation: bool,
+    // Waterfall of the active tab's requests in place of the messages
+    show_network: bool,
+    // The active tab's script errors with where they happened
+    show_errors: bool,
 }
 
+// Locales offered by the emulation toolbar
+const EMULATED_LOCALES: &[&str] = &[
+    "en-US", "en-GB", "de-DE", "fr-FR", "es-ES", "it-IT", "pt-BR", "nl-NL", "sv-SE",
+    "pl-PL", "ru-RU", "tr-TR", "ja-JP", "zh-CN", "ko-KR", "hi-IN", "ar-EG",
+];
+
 impl Default for DevConsole {
     fn default() -> Self {
         Self::new()
@@ -75,6 +89,9 @@ impl DevConsole {
             auto_scroll: true,
             command_history: Vec::new(),
             history_index: None,
+            show_emulation: false,
+            show_network: false,
+            show_errors: false,
         };
         
         // Add welcome message
@@ -172,20 +189,7 @@ impl DevConsole {
                     }
                     
                     // Also show any console output from the engine
-                    let console_output = engine.get_console_output();
-                    for line in console_output {
-                        if line.starts_with("[LOG]") {
-                            self.add_message(ConsoleMessage::Log(line[5..].trim().to_string()));
-                        } else if line.starts_with("[ERROR]") {
-                            self.add_message(ConsoleMessage::Error(line[7..].trim().to_string()));
-                        } else if line.starts_with("[WARN]") {
-                            self.add_message(ConsoleMessage::Warn(line[6..].trim().to_string()));
-                        } else if line.starts_with("[INFO]") {
-                            self.add_message(ConsoleMessage::Info(line[6..].trim().to_string()));
-                        } else {
-                            self.add_message(ConsoleMessage::Log(line));
-                        }
-                    }
+                    self.sync_with_js_engine(engine);
                     
                     result
                 }
@@ -202,10 +206,19 @@ impl DevConsole {
         }
     }
     
-    pub fn render(&mut self, ui: &mut egui::Ui, js_engine: &mut Option<JSEngine>) {
+    /// Returns true when the emulation toolbar changed `emulation`, which the page then has to pick up
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        js_engine: &mut Option<JSEngine>,
+        emulation: Option<&mut LocaleOverrides>,
+        network_log: &[NetworkEntry],
+        script_errors: &[ScriptError],
+    ) -> bool {
         if !self.is_visible {
-            return;
+            return false;
         }
+        let mut emulation_changed = false;
         
         // Console window
         egui::Window::new(format!("{} Developer Console", NeonIcons::TERMINAL_WINDOW))
@@ -225,10 +238,38 @@ impl DevConsole {
                     ui.separator();
                     
                     ui.label(format!("Messages: {}", self.messages.len()));
+                    
+                    ui.separator();
+                    
+                    ui.toggle_value(&mut self.show_emulation, format!("{} Emulation", NeonIcons::GLOBE));
+                    
+                    ui.toggle_value(&mut self.show_network, "Network");
+                    
+                    let error_count = script_errors.iter().filter(|error| error.severity == ErrorSeverity::Error).count();
+                    let errors_label = egui::RichText::new(format!("Errors ({})", error_count));
+                    ui.toggle_value(&mut self.show_errors, if error_count > 0 { errors_label.color(severity_color(ErrorSeverity::Error)) } else { errors_label });
+                    
+                    ui.separator();
+                    
+                    Self::network_conditions_picker(ui);
                 });
                 
+                if let Some(overrides) = emulation.filter(|_| self.show_emulation) {
+                    emulation_changed = Self::emulation_toolbar(ui, overrides);
+                }
+                
                 ui.separator();
                 
+                if self.show_network {
+                    Self::network_panel(ui, network_log);
+                    ui.separator();
+                }
+                
+                if self.show_errors {
+                    Self::errors_panel(ui, script_errors);
+                    ui.separator();
+                }
+                
                 // Messages area
                 let messages_height = ui.available_height() - 60.0; // Leave space for input
                 egui::ScrollArea::vertical()
@@ -299,6 +340,111 @@ impl DevConsole {
                     }
                 });
             });
+        emulation_changed
+    }
+    
+    // The active tab's requests as a waterfall, with the ones that never got a response listed under it
+    fn network_panel(ui: &mut egui::Ui, network_log: &[NetworkEntry]) {
+        waterfall::legend(ui);
+        egui::ScrollArea::vertical()
+            .id_salt("network_waterfall")
+            .max_height(ui.available_height() * 0.5)
+            .show(ui, |ui| {
+                let timed = network_log.iter().filter_map(|entry| entry.timing.as_ref()).collect::<Vec<_>>();
+                waterfall::show(ui, &timed);
+                for entry in network_log.iter().filter(|entry| entry.timing.is_none() && entry.error.is_some()) {
+                    ui.label(
+                        egui::RichText::new(format!("{} {}: {}", NeonIcons::WARNING, entry.url, entry.error.as_deref().unwrap_or_default()))
+                            .small()
+                            .color(NeonTheme::error_color())
+                    );
+                }
+            });
+    }
+    
+    // Newest first, each with the script and line it came from
+    fn errors_panel(ui: &mut egui::Ui, script_errors: &[ScriptError]) {
+        if script_errors.is_empty() {
+            ui.label(egui::RichText::new("No script errors on this page").small().color(NeonTheme::muted_text()));
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("script_errors")
+            .max_height(ui.available_height() * 0.4)
+            .show(ui, |ui| {
+                for error in script_errors.iter().rev() {
+                    ui.horizontal(|ui| {
+                        let icon = match error.severity {
+                            ErrorSeverity::Error => NeonIcons::X_CIRCLE,
+                            ErrorSeverity::Warning => NeonIcons::WARNING,
+                        };
+                        ui.label(egui::RichText::new(format!("{} {}", icon, error.message)).size(13.0).color(severity_color(error.severity)));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.label(egui::RichText::new(error.location()).small().monospace().color(NeonTheme::muted_text()));
+                        });
+                    });
+                }
+            });
+    }
+    
+    // Throttling applies to every request the window makes from the next one on
+    fn network_conditions_picker(ui: &mut egui::Ui) {
+        let mut conditions = NetworkConditions::current();
+        ui.label("Network");
+        egui::ComboBox::from_id_salt("network_conditions")
+            .selected_text(conditions.label())
+            .show_ui(ui, |ui| {
+                for option in NetworkConditions::ALL {
+                    ui.selectable_value(&mut conditions, option, option.label());
+                }
+            })
+            .response
+            .on_hover_text("Slow down or cut off the network to test loading");
+        if conditions != NetworkConditions::current() {
+            NetworkConditions::set(conditions);
+        }
+        if conditions != NetworkConditions::NoThrottling {
+            ui.label(egui::RichText::new(format!("{} Throttled", NeonIcons::WARNING)).color(NeonTheme::warning_color()));
+        }
+    }
+    
+    // Locale, Accept-Language and time zone pickers; true if any of them changed
+    fn emulation_toolbar(ui: &mut egui::Ui, overrides: &mut LocaleOverrides) -> bool {
+        let before = overrides.clone();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Locale");
+            egui::ComboBox::from_id_salt("emulated_locale")
+                .selected_text(overrides.locale.as_deref().unwrap_or("System"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut overrides.locale, None, "System");
+                    for locale in EMULATED_LOCALES {
+                        ui.selectable_value(&mut overrides.locale, Some(locale.to_string()), *locale);
+                    }
+                });
+            
+            ui.label("Accept-Language");
+            let mut accept_language = overrides.accept_language.clone().unwrap_or_default();
+            let hint = overrides.accept_language_header().unwrap_or_else(|| "Default".to_string());
+            if ui.add(egui::TextEdit::singleline(&mut accept_language).hint_text(hint).desired_width(160.0)).changed() {
+                overrides.accept_language = Some(accept_language.trim().to_string()).filter(|v| !v.is_empty());
+            }
+            
+            ui.label("Time zone");
+            egui::ComboBox::from_id_salt("emulated_time_zone")
+                .selected_text(overrides.time_zone.as_deref().unwrap_or("System"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut overrides.time_zone, None, "System");
+                    for zone in locale::time_zone_names() {
+                        ui.selectable_value(&mut overrides.time_zone, Some(zone.to_string()), zone);
+                    }
+                });
+            
+            if ui.add_enabled(!overrides.is_empty(), egui::Button::new("Reset")).clicked() {
+                *overrides = LocaleOverrides::default();
+            }
+            ui.label(egui::RichText::new("Requests use the new language after a reload").size(11.0).color(NeonTheme::muted_text()));
+        });
+        *overrides != before
     }
     
     fn navigate_history_up(&mut self) {
@@ -330,9 +476,9 @@ impl DevConsole {
         }
     }
     
+    /// Show what the page's scripts logged since the last sync
     pub fn sync_with_js_engine(&mut self, js_engine: &JSEngine) {
-        // Sync console output from JavaScript engine
-        let console_output = js_engine.get_console_output();
+        let console_output = js_engine.take_console_output();
         for line in console_output {
             if line.starts_with("[LOG]") {
                 self.add_message(ConsoleMessage::Log(line[5..].trim().to_string()));
@@ -347,4 +493,12 @@ impl DevConsole {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Same colours as the console's error and warning messages
+fn severity_color(severity: ErrorSeverity) -> egui::Color32 {
+    match severity {
+        ErrorSeverity::Error => ConsoleMessage::Error(String::new()).color(),
+        ErrorSeverity::Warning => ConsoleMessage::Warn(String::new()).color(),
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use crate::networking::RequestBlocked;
 use crate::ui::NeonIcons;
 
 #[derive(Debug, Clone)]
@@ -43,6 +44,8 @@ pub enum ErrorType {
     InvalidUrl,
     UnsupportedProtocol,
     ResourceExhausted,
+    // An extension or tracking protection blocked the request
+    RequestBlocked,
     InternalError,
 }
 
@@ -70,6 +73,7 @@ impl fmt::Display for ErrorType {
             ErrorType::InvalidUrl => "Invalid URL",
             ErrorType::UnsupportedProtocol => "Unsupported Protocol",
             ErrorType::ResourceExhausted => "Resource Exhausted",
+            ErrorType::RequestBlocked => "Request Blocked",
             ErrorType::InternalError => "Internal Error",
         };
         write!(f, "{}", msg)
@@ -301,12 +305,22 @@ impl BrowserError {
     pub fn from_anyhow(err: &anyhow::Error, url: Option<&str>) -> Self {
         let err_str = err.to_string();
         
+        // A policy decision is known by its type, whatever its message says
+        if let Some(blocked) = err.downcast_ref::<RequestBlocked>() {
+            return Self::new(ErrorType::RequestBlocked, blocked.to_string())
+                .with_url(url.unwrap_or("unknown").to_string())
+                .with_suggestions(vec![
+                    "Check the rules of your installed extensions".to_string(),
+                    "Allow the site in the tracking protection settings".to_string(),
+                ]);
+        }
+        
         // Try to categorize the error based on the message
         if err_str.contains("TLS") || err_str.contains("SSL") || err_str.contains("certificate") {
             Self::from_tls_error(&err_str, url.unwrap_or("unknown"))
         } else if err_str.contains("timeout") || err_str.contains("dns") || err_str.contains("connect") {
             Self::from_network_error(&err_str, url.unwrap_or("unknown"))
-        } else if err_str.starts_with("REDIRECT:") {
+        } else if err_str.starts_with("REDIRECT:") || err_str.starts_with("Too many redirects") {
             Self::new(
                 ErrorType::HttpRedirectLoop,
                 "Too many redirects".to_string()
@@ -330,6 +344,16 @@ impl BrowserError {
         }
     }
 
+    /// Whether fetching again with the fallback client may get past this error. Policy decisions are final.
+    pub fn permits_fallback(&self) -> bool {
+        matches!(self.error_type, ErrorType::TlsHandshakeFailed | ErrorType::NetworkTimeout | ErrorType::InternalError)
+    }
+
+    /// Whether an exchange that failed with `err` is worth sending again over the fallback client
+    pub fn allows_fallback(err: &anyhow::Error) -> bool {
+        Self::from_anyhow(err, None).permits_fallback() || err.to_string().contains("TLS_CLOSE_NOTIFY_ISSUE")
+    }
+
     pub fn icon(&self) -> &'static str {
         NeonIcons::error_icon(&format!("{:?}", self.error_type))
     }
@@ -338,18 +362,18 @@ impl BrowserError {
         use crate::ui::theme::NeonTheme;
         match self.error_type {
             ErrorType::NetworkTimeout | ErrorType::NetworkUnreachable | ErrorType::ConnectionReset => {
-                NeonTheme::WARNING_COLOR
+                NeonTheme::warning_color()
             },
             ErrorType::CertificateInvalid | ErrorType::CertificateExpired | ErrorType::TlsHandshakeFailed => {
-                NeonTheme::ERROR_COLOR
+                NeonTheme::error_color()
             },
             ErrorType::HttpNotFound | ErrorType::HttpForbidden => {
-                NeonTheme::WARNING_COLOR
+                NeonTheme::warning_color()
             },
             ErrorType::HttpServerError => {
-                NeonTheme::ERROR_COLOR
+                NeonTheme::error_color()
             },
-            _ => NeonTheme::ERROR_COLOR,
+            _ => NeonTheme::error_color(),
         }
     }
 }
@@ -415,4 +439,27 @@ impl ErrorRecovery {
         
         fixes
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::executor::Executor;
+    use crate::networking::manual_client::{FetchContext, ManualHttpClient};
+    use crate::networking::tracking::TrackingProtection;
+
+    #[test]
+    fn test_blocked_main_frame_is_not_fetched_again() {
+        let client = ManualHttpClient::new().unwrap();
+        let context = FetchContext { tracking: TrackingProtection::new("https://news.example/"), ..FetchContext::default() };
+        let url = "https://stats.doubleclick.net/page";
+        let error = Executor::shared().block_on(client.fetch_with_context(url, &context)).unwrap_err();
+        assert!(error.downcast_ref::<RequestBlocked>().is_some());
+
+        // Even a block whose message reads like a TLS failure is final
+        let browser_error = BrowserError::from_anyhow(&error, Some(url));
+        assert_eq!(browser_error.error_type, ErrorType::RequestBlocked);
+        assert!(!browser_error.permits_fallback());
+        let named = anyhow::Error::from(RequestBlocked { reason: "Blocked by extension 'TLS Guard': https://a.example/".to_string() });
+        assert!(!BrowserError::from_anyhow(&named, None).permits_fallback());
+    }
+}
@@ -0,0 +1,276 @@
+// UI translations, gettext-style: the English text is the key, and a language pack maps it to its translation.
+// Packs are TOML files built in or placed in the profile's locales directory; they also say how the
+// language writes numbers and dates.
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+// Packs that ship with the browser; English needs none, since messages are written in it
+const BUILT_IN_PACKS: [&str; 3] = [
+    include_str!("locales/de.toml"),
+    include_str!("locales/fr.toml"),
+    include_str!("locales/es.toml"),
+];
+
+/// How a language writes numbers and dates; dates use chrono's strftime syntax
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Formats {
+    pub decimal_separator: String,
+    pub thousands_separator: String,
+    pub date: String,
+    pub date_time: String,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        Self {
+            decimal_separator: ".".to_string(),
+            thousands_separator: ",".to_string(),
+            date: "%b %-d, %Y".to_string(),
+            date_time: "%b %-d, %Y %-I:%M %p".to_string(),
+        }
+    }
+}
+
+// A message is one translation, or a singular and plural form for messages that count something
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Text(String),
+    Plural(Vec<String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LanguagePack {
+    /// BCP 47 code such as `de` or `pt-BR`
+    pub language: String,
+    /// Name of the language in itself, for the picker
+    pub name: String,
+    #[serde(default)]
+    pub formats: Formats,
+    #[serde(default)]
+    messages: HashMap<String, Message>,
+}
+
+impl LanguagePack {
+    pub fn parse(source: &str) -> Result<Self> {
+        let pack: LanguagePack = toml::from_str(source)?;
+        if pack.language.trim().is_empty() {
+            return Err(anyhow!("The language pack needs a language code"));
+        }
+        Ok(pack)
+    }
+
+    fn english() -> Self {
+        Self { language: DEFAULT_LANGUAGE.to_string(), name: "English".to_string(), formats: Formats::default(), messages: HashMap::new() }
+    }
+
+    /// `msgid` in this language, or as given when the pack doesn't translate it
+    pub fn translate(&self, msgid: &str) -> String {
+        match self.messages.get(msgid) {
+            Some(Message::Text(text)) => text.clone(),
+            Some(Message::Plural(forms)) => forms.first().cloned().unwrap_or_else(|| msgid.to_string()),
+            None => msgid.to_string(),
+        }
+    }
+
+    /// `singular` or `plural` for `n`, translated, with `{n}` replaced by the number
+    pub fn translate_plural(&self, singular: &str, plural: &str, n: u64) -> String {
+        let form = match self.messages.get(singular) {
+            Some(Message::Plural(forms)) if !forms.is_empty() => forms[if n == 1 { 0 } else { forms.len() - 1 }].clone(),
+            Some(Message::Text(text)) => text.clone(),
+            _ => if n == 1 { singular } else { plural }.to_string(),
+        };
+        form.replace("{n}", &self.format_number(n as f64, 0))
+    }
+
+    /// `value` with `decimals` digits after the separator and thousands grouped
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        let text = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push_str(&self.formats.thousands_separator);
+            }
+            grouped.push(digit);
+        }
+        let sign = if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+        match fraction {
+            "" => format!("{}{}", sign, grouped),
+            _ => format!("{}{}{}{}", sign, grouped, self.formats.decimal_separator, fraction),
+        }
+    }
+}
+
+/// Language packs found at startup, and the one the UI is shown in
+pub struct Languages {
+    packs: Vec<Arc<LanguagePack>>,
+    active: Arc<LanguagePack>,
+    load_errors: Vec<String>,
+}
+
+static LANGUAGES: OnceLock<RwLock<Languages>> = OnceLock::new();
+
+impl Languages {
+    /// The built-in packs and those in `data_dir`/locales, where a file replaces a built-in pack of its language
+    pub fn load(data_dir: &Path) -> Self {
+        let mut packs: Vec<Arc<LanguagePack>> = vec![Arc::new(LanguagePack::english())];
+        let mut load_errors = Vec::new();
+        let mut add = |pack: LanguagePack| {
+            packs.retain(|existing| existing.language != pack.language);
+            packs.push(Arc::new(pack));
+        };
+        for source in BUILT_IN_PACKS {
+            match LanguagePack::parse(source) {
+                Ok(pack) => add(pack),
+                Err(e) => load_errors.push(format!("built-in pack: {}", e)),
+            }
+        }
+
+        let dir = data_dir.join("locales");
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path: &std::path::PathBuf| path.extension().is_some_and(|ext| ext == "toml"));
+        files.sort();
+        for path in files {
+            let file = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            match std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|source| LanguagePack::parse(&source)) {
+                Ok(pack) => add(pack),
+                Err(e) => load_errors.push(format!("{}: {}", file, e)),
+            }
+        }
+        packs.sort_by_key(|pack| pack.name.to_lowercase());
+
+        let active = Arc::new(LanguagePack::english());
+        let mut languages = Self { packs, active, load_errors };
+        languages.select(DEFAULT_LANGUAGE);
+        languages
+    }
+
+    /// Show the UI in `language`, or the closest pack: `pt-BR` falls back to `pt`, and anything unknown to English
+    pub fn select(&mut self, language: &str) {
+        let primary = language.split(['-', '_']).next().unwrap_or("");
+        let pack = self.packs.iter().find(|pack| pack.language.eq_ignore_ascii_case(language))
+            .or_else(|| self.packs.iter().find(|pack| pack.language.eq_ignore_ascii_case(primary)))
+            .or_else(|| self.packs.iter().find(|pack| pack.language == DEFAULT_LANGUAGE));
+        if let Some(pack) = pack {
+            self.active = pack.clone();
+        }
+    }
+
+    pub fn packs(&self) -> &[Arc<LanguagePack>] {
+        &self.packs
+    }
+
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+}
+
+fn languages() -> &'static RwLock<Languages> {
+    LANGUAGES.get_or_init(|| RwLock::new(Languages::load(&crate::storage::data_dir())))
+}
+
+/// Load the language packs and pick the preferred language, or the system's when there's no preference
+pub fn init(language: Option<&str>) {
+    let language = language.map(str::to_string).unwrap_or_else(crate::js::locale::system_locale);
+    set_language(&language);
+}
+
+pub fn set_language(language: &str) {
+    if let Ok(mut languages) = languages().write() {
+        languages.select(language);
+    }
+}
+
+/// The pack the UI is shown in
+pub fn active() -> Arc<LanguagePack> {
+    languages().read().map(|languages| languages.active.clone()).unwrap_or_else(|_| Arc::new(LanguagePack::english()))
+}
+
+/// Every available pack, with problems reading any that failed
+pub fn available() -> (Vec<Arc<LanguagePack>>, Vec<String>) {
+    languages().read()
+        .map(|languages| (languages.packs().to_vec(), languages.load_errors().to_vec()))
+        .unwrap_or_default()
+}
+
+/// `msgid` in the UI language
+pub fn tr(msgid: &str) -> String {
+    active().translate(msgid)
+}
+
+/// `singular` or `plural` in the UI language, with `{n}` replaced by `n`
+pub fn trn(singular: &str, plural: &str, n: u64) -> String {
+    active().translate_plural(singular, plural, n)
+}
+
+pub fn format_number(value: f64, decimals: usize) -> String {
+    active().format_number(value, decimals)
+}
+
+/// A size such as `1.5 MB`, with the language's decimal separator
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", format_number(bytes as f64, 0));
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", format_number(value, 1), UNITS[unit])
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(&active().formats.date).to_string()
+}
+
+pub fn format_date_time(time: DateTime<Local>) -> String {
+    time.format(&active().formats.date_time).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_pack() {
+        let pack = LanguagePack::parse(r#"
+            language = "de"
+            name = "Deutsch"
+            [formats]
+            decimal_separator = ","
+            thousands_separator = "."
+            [messages]
+            "Settings" = "Einstellungen"
+            "{n} minute ago" = ["vor {n} Minute", "vor {n} Minuten"]
+        "#).unwrap();
+        assert_eq!(pack.translate("Settings"), "Einstellungen");
+        assert_eq!(pack.translate("Downloads"), "Downloads");
+        assert_eq!(pack.translate_plural("{n} minute ago", "{n} minutes ago", 1), "vor 1 Minute");
+        assert_eq!(pack.translate_plural("{n} minute ago", "{n} minutes ago", 1500), "vor 1.500 Minuten");
+        assert_eq!(pack.format_number(-1234567.891, 2), "-1.234.567,89");
+        assert_eq!(LanguagePack::english().format_number(999.95, 1), "1,000.0");
+    }
+
+    #[test]
+    fn test_language_selection_falls_back() {
+        let mut languages = Languages::load(&std::env::temp_dir().join("neon-no-such-profile"));
+        assert!(languages.load_errors().is_empty());
+        languages.select("de-AT");
+        assert_eq!(languages.active.language, "de");
+        languages.select("xx");
+        assert_eq!(languages.active.language, DEFAULT_LANGUAGE);
+    }
+}
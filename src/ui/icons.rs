@@ -7,6 +7,8 @@ impl NeonIcons {
     pub const ARROW_RIGHT: &'static str = "→";
     pub const ARROW_CLOCKWISE: &'static str = "↻";
     pub const HOUSE: &'static str = "⌂";
+    pub const CARET_RIGHT: &'static str = "▶";
+    pub const CARET_DOWN: &'static str = "▼";
     
     // Security/Connection icons - using Unicode symbols
     pub const LOCK: &'static str = "🔒";
@@ -41,6 +43,8 @@ impl NeonIcons {
     // Action icons - using simple symbols
     pub const PLAY: &'static str = "▶";
     pub const PAUSE: &'static str = "⏸";
+    pub const SPEAKER: &'static str = "🔊";
+    pub const SPEAKER_MUTED: &'static str = "🔇";
     pub const PLUS: &'static str = "+";
     pub const MINUS: &'static str = "-";
     pub const X: &'static str = "×";
@@ -58,6 +62,8 @@ impl NeonIcons {
     pub const STAR: &'static str = "★";
     pub const DELETE: &'static str = "🗑";
     pub const FOLDER_OPEN: &'static str = "📂";
+    pub const PRINTER: &'static str = "🖶";
+    pub const MENU: &'static str = "☰";
 }
 
 impl NeonIcons {
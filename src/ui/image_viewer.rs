@@ -0,0 +1,320 @@
+// An image opened on its own, rather than inside a page: fitted to the tab, with zoom, panning, rotation, the colour
+// of the pixel under the pointer, and saving the file as it was received
+use anyhow::{Result, anyhow};
+use eframe::egui::{self, Color32, ColorImage, RichText};
+use image::ImageFormat;
+use crate::networking::HttpResponse;
+use crate::networking::image_loader::detect_image_format;
+use crate::security::download_validator::DownloadValidator;
+use crate::ui::{NeonIcons, NeonTheme};
+
+const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.02..=32.0;
+// Zoom factor for the toolbar buttons
+const ZOOM_STEP: f32 = 1.25;
+// Points of wheel scrolling that double or halve the zoom
+const WHEEL_POINTS_PER_DOUBLING: f32 = 240.0;
+const BACKGROUND: Color32 = Color32::from_rgb(0x0e, 0x0e, 0x0e);
+
+/// Whether a response of this type is shown in the viewer; SVG is a document and goes to the page renderer
+pub fn handles(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    essence.starts_with("image/") && !essence.contains("svg")
+}
+
+pub struct ImageViewer {
+    /// The decoded image at full size, for the pixel readout
+    image: ColorImage,
+    format: ImageFormat,
+    /// The file as received, for saving
+    bytes: Vec<u8>,
+    file_name: String,
+    texture: Option<egui::TextureHandle>,
+    /// Screen points per image pixel; None while fitting the image to the tab
+    zoom: Option<f32>,
+    /// The image centre's distance from the centre of the view
+    offset: egui::Vec2,
+    /// Clockwise quarter turns
+    quarter_turns: u8,
+    status_message: Option<(String, bool)>, // (message, is_error)
+}
+
+impl ImageViewer {
+    /// Decode a navigation's response body
+    pub fn open(response: &HttpResponse, url: &str) -> Result<Self> {
+        let content_type = response.content_type().map_or("", String::as_str);
+        let mut bytes = response.get_raw_body()?;
+        if let Some(encoding) = response.get_header("Content-Encoding").or_else(|| response.get_header("content-encoding")) {
+            bytes = crate::networking::decode_content(bytes, encoding, None)?;
+        }
+        Self::decode(bytes, content_type, url)
+    }
+
+    pub fn decode(bytes: Vec<u8>, content_type: &str, url: &str) -> Result<Self> {
+        let format = detect_image_format(content_type, url, &bytes)?;
+        let decoded = image::load_from_memory_with_format(&bytes, format)
+            .map_err(|e| anyhow!("The image couldn't be decoded: {}", e))?
+            .to_rgba8();
+        let size = [decoded.width() as usize, decoded.height() as usize];
+        Ok(Self {
+            image: ColorImage::from_rgba_unmultiplied(size, decoded.as_raw()),
+            format,
+            bytes,
+            file_name: file_name(url, format),
+            texture: None,
+            zoom: None,
+            offset: egui::Vec2::ZERO,
+            quarter_turns: 0,
+            status_message: None,
+        })
+    }
+
+    /// The tab title: the file's name and its size in pixels
+    pub fn title(&self) -> String {
+        let [width, height] = self.image.size;
+        format!("{} ({} × {})", self.file_name, width, height)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let view = ui.available_rect_before_wrap();
+        let toolbar_height = 34.0;
+        let canvas = egui::Rect::from_min_max(egui::pos2(view.min.x, view.min.y + toolbar_height), view.max);
+        let fit = fit_zoom(self.rotated_size(), canvas.size());
+
+        ui.allocate_ui(egui::vec2(view.width(), toolbar_height), |ui| self.toolbar(ui, fit));
+        let response = ui.allocate_rect(canvas, egui::Sense::click_and_drag());
+        ui.painter().rect_filled(canvas, 0.0, BACKGROUND);
+
+        // Wheel and pinch zoom about the pointer, so the pixel under it stays put
+        let mut zoom = self.zoom.unwrap_or(fit);
+        if let Some(pointer) = response.hover_pos() {
+            let (scroll, pinch) = ui.input(|i| (i.raw_scroll_delta.y, i.zoom_delta()));
+            let factor = pinch * (scroll / WHEEL_POINTS_PER_DOUBLING).exp2();
+            if factor != 1.0 {
+                let zoomed = (zoom * factor).clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+                let anchor = pointer - canvas.center() - self.offset;
+                self.offset += anchor - anchor * (zoomed / zoom);
+                zoom = zoomed;
+                self.zoom = Some(zoom);
+            }
+        }
+        if response.dragged() {
+            self.offset += response.drag_delta();
+            self.zoom = Some(zoom);
+        }
+        if response.double_clicked() {
+            self.set_zoom(if self.zoom.is_none() { Some(1.0) } else { None });
+            zoom = self.zoom.unwrap_or(fit);
+        }
+
+        let [width, height] = self.rotated_size();
+        let rect = egui::Rect::from_center_size(canvas.center() + self.offset, egui::vec2(width, height) * zoom);
+        let texture = self.texture(ui.ctx()).id();
+        let painter = ui.painter_at(canvas);
+        painter.add(quad(texture, rect, self.quarter_turns));
+
+        if let Some(pointer) = response.hover_pos() {
+            let pixel = rect.contains(pointer)
+                .then(|| source_pixel((pointer - rect.min) / rect.size(), self.quarter_turns, self.image.size))
+                .flatten();
+            if let Some([x, y]) = pixel {
+                self.pixel_readout(ui, canvas, [x, y]);
+            }
+            ui.ctx().set_cursor_icon(if response.dragged() { egui::CursorIcon::Grabbing } else { egui::CursorIcon::Grab });
+        }
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui, fit: f32) {
+        ui.horizontal_centered(|ui| {
+            let [width, height] = self.image.size;
+            ui.label(RichText::new(&self.file_name).strong());
+            ui.label(RichText::new(format!("{} × {} px · {}", width, height, format_name(self.format)))
+                .color(NeonTheme::secondary_text()));
+            ui.separator();
+
+            let zoom = self.zoom.unwrap_or(fit);
+            if ui.selectable_label(self.zoom.is_none(), "Fit").on_hover_text("Fit to window").clicked() {
+                self.set_zoom(None);
+            }
+            if ui.selectable_label(self.zoom == Some(1.0), "1:1").on_hover_text("Actual size").clicked() {
+                self.set_zoom(Some(1.0));
+            }
+            if ui.small_button(NeonIcons::MINUS).on_hover_text("Zoom out").clicked() {
+                self.zoom = Some((zoom / ZOOM_STEP).max(*ZOOM_RANGE.start()));
+            }
+            ui.label(format!("{:.0}%", zoom * 100.0));
+            if ui.small_button(NeonIcons::PLUS).on_hover_text("Zoom in").clicked() {
+                self.zoom = Some((zoom * ZOOM_STEP).min(*ZOOM_RANGE.end()));
+            }
+            ui.separator();
+            if ui.small_button(NeonIcons::ARROWS_CLOCKWISE).on_hover_text("Rotate left").clicked() {
+                self.rotate(3);
+            }
+            if ui.small_button(NeonIcons::ARROW_CLOCKWISE).on_hover_text("Rotate right").clicked() {
+                self.rotate(1);
+            }
+            ui.separator();
+            if ui.button(format!("{} Save", NeonIcons::DOWNLOAD)).clicked() {
+                self.status_message = Some(match self.save() {
+                    Ok(path) => (format!("Saved to {}", path.display()), false),
+                    Err(e) => (e.to_string(), true),
+                });
+            }
+            if let Some((message, is_error)) = &self.status_message {
+                let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+                ui.label(RichText::new(message).size(12.0).color(color));
+            }
+        });
+    }
+
+    // The pointed-at pixel's position and colour, in the bottom corner of the view
+    fn pixel_readout(&self, ui: &egui::Ui, canvas: egui::Rect, [x, y]: [usize; 2]) {
+        let color = self.image.pixels[y * self.image.size[0] + x];
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let text = format!("{}, {}   #{:02x}{:02x}{:02x}   rgba({}, {}, {}, {:.2})", x, y, r, g, b, r, g, b, a as f32 / 255.0);
+        let painter = ui.painter_at(canvas);
+        let galley = painter.layout_no_wrap(text, egui::FontId::monospace(12.0), NeonTheme::primary_text());
+        let swatch = 14.0;
+        let size = egui::vec2(galley.size().x + swatch + 20.0, galley.size().y.max(swatch) + 10.0);
+        let frame = egui::Rect::from_min_size(canvas.left_bottom() + egui::vec2(8.0, -8.0 - size.y), size);
+        painter.rect_filled(frame, 4.0, Color32::from_black_alpha(200));
+        let swatch = egui::Rect::from_min_size(frame.min + egui::vec2(5.0, (size.y - swatch) / 2.0), egui::vec2(swatch, swatch));
+        painter.rect(swatch, 2.0, Color32::from_rgb(r, g, b), egui::Stroke::new(1.0, Color32::GRAY));
+        painter.galley(egui::pos2(swatch.max.x + 8.0, frame.min.y + 5.0), galley, NeonTheme::primary_text());
+    }
+
+    fn set_zoom(&mut self, zoom: Option<f32>) {
+        self.zoom = zoom;
+        self.offset = egui::Vec2::ZERO;
+    }
+
+    fn rotate(&mut self, quarter_turns: u8) {
+        self.quarter_turns = (self.quarter_turns + quarter_turns) % 4;
+        self.offset = egui::Vec2::ZERO;
+    }
+
+    // The image's size as shown, width and height swapping on a quarter turn
+    fn rotated_size(&self) -> [f32; 2] {
+        let [width, height] = self.image.size.map(|side| side as f32);
+        if self.quarter_turns % 2 == 1 { [height, width] } else { [width, height] }
+    }
+
+    fn texture(&mut self, ctx: &egui::Context) -> &egui::TextureHandle {
+        self.texture.get_or_insert_with(|| {
+            // The GPU may not take the full image; the pixel readout still uses it
+            let max_side = ctx.input(|i| i.max_texture_side);
+            let longest = self.image.size[0].max(self.image.size[1]);
+            let pixels = if longest > max_side {
+                crate::ui::tab_switcher::downscale(&self.image, (self.image.size[0] * max_side / longest).max(1))
+            } else {
+                self.image.clone()
+            };
+            ctx.load_texture(format!("image_viewer_{}", self.file_name), pixels, egui::TextureOptions::LINEAR)
+        })
+    }
+
+    fn save(&self) -> Result<std::path::PathBuf> {
+        let dir = dirs::download_dir().unwrap_or_else(std::env::temp_dir);
+        let path = DownloadValidator::generate_safe_path(&dir, &self.file_name);
+        std::fs::write(&path, &self.bytes)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Saved image to {}", path.display());
+        Ok(path)
+    }
+}
+
+/// How much to scale an image of `size` to show it whole in `available`; small images keep their size
+pub fn fit_zoom([width, height]: [f32; 2], available: egui::Vec2) -> f32 {
+    if width <= 0.0 || height <= 0.0 {
+        return 1.0;
+    }
+    (available.x / width).min(available.y / height).min(1.0).max(*ZOOM_RANGE.start())
+}
+
+/// The pixel of an image of `size` shown at `point`, a position from 0 to 1 across the shown image, after
+/// `quarter_turns` clockwise turns
+pub fn source_pixel(point: egui::Vec2, quarter_turns: u8, [width, height]: [usize; 2]) -> Option<[usize; 2]> {
+    let (u, v) = (point.x, point.y);
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None;
+    }
+    let (x, y) = match quarter_turns % 4 {
+        0 => (u, v),
+        1 => (v, 1.0 - u),
+        2 => (1.0 - u, 1.0 - v),
+        _ => (1.0 - v, u),
+    };
+    let pixel = |fraction: f32, side: usize| ((fraction * side as f32) as usize).min(side.saturating_sub(1));
+    Some([pixel(x, width), pixel(y, height)])
+}
+
+// The image's texture drawn over `rect`, turned by rotating which texture corner each corner of the rect shows
+fn quad(texture: egui::TextureId, rect: egui::Rect, quarter_turns: u8) -> egui::Mesh {
+    let corners = [rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()];
+    let uvs = [egui::pos2(0.0, 0.0), egui::pos2(1.0, 0.0), egui::pos2(1.0, 1.0), egui::pos2(0.0, 1.0)];
+    let mut mesh = egui::Mesh::with_texture(texture);
+    for (index, corner) in corners.into_iter().enumerate() {
+        let uv = uvs[(index + 4 - quarter_turns as usize % 4) % 4];
+        mesh.vertices.push(egui::epaint::Vertex { pos: corner, uv, color: Color32::WHITE });
+    }
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(0, 2, 3);
+    mesh
+}
+
+// The last path segment of the URL, or a name from the format for data: URLs and paths ending in a slash
+fn file_name(url: &str, format: ImageFormat) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let last = path.rsplit('/').next().unwrap_or("");
+    if last.contains('.') && !url.starts_with("data:") {
+        return last.to_string();
+    }
+    let extension = format.extensions_str().first().copied().unwrap_or("img");
+    format!("image.{}", extension)
+}
+
+fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "PNG",
+        ImageFormat::Jpeg => "JPEG",
+        ImageFormat::Gif => "GIF",
+        ImageFormat::WebP => "WebP",
+        ImageFormat::Bmp => "BMP",
+        ImageFormat::Ico => "ICO",
+        _ => "Image",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_and_rotated_pixel_lookup() {
+        assert_eq!(fit_zoom([2000.0, 1000.0], egui::vec2(1000.0, 1000.0)), 0.5);
+        assert_eq!(fit_zoom([200.0, 100.0], egui::vec2(1000.0, 1000.0)), 1.0);
+
+        let size = [4, 2];
+        assert_eq!(source_pixel(egui::vec2(0.0, 0.0), 0, size), Some([0, 0]));
+        assert_eq!(source_pixel(egui::vec2(0.99, 0.99), 0, size), Some([3, 1]));
+        // A clockwise turn puts the bottom-left pixel in the top-left corner
+        assert_eq!(source_pixel(egui::vec2(0.0, 0.0), 1, size), Some([0, 1]));
+        assert_eq!(source_pixel(egui::vec2(0.0, 0.0), 2, size), Some([3, 1]));
+        assert_eq!(source_pixel(egui::vec2(0.0, 0.0), 3, size), Some([3, 0]));
+        assert_eq!(source_pixel(egui::vec2(1.0, 0.5), 0, size), None);
+    }
+
+    #[test]
+    fn test_decode_names_and_sizes_the_image() {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let viewer = ImageViewer::decode(png.clone(), "image/png", "https://example.com/pics/cat.png?size=large").unwrap();
+        assert_eq!(viewer.title(), "cat.png (3 × 2)");
+        assert_eq!(viewer.image.pixels[0], Color32::RED);
+        let viewer = ImageViewer::decode(png, "image/png", "data:image/png;base64,AAAA").unwrap();
+        assert_eq!(viewer.file_name, "image.png");
+        assert!(ImageViewer::decode(b"<html>".to_vec(), "image/png", "https://example.com/").is_err());
+        assert!(handles("image/jpeg; q=1") && !handles("image/svg+xml") && !handles("text/html"));
+    }
+}
@@ -0,0 +1,211 @@
+// Per-tab navigation lifecycle: the one place the UI asks whether a tab is loading
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadState {
+    Idle,
+    Fetching,
+    Parsing,
+    Rendering,
+    Complete,
+    Error(String),
+}
+
+impl LoadState {
+    pub fn is_loading(&self) -> bool {
+        matches!(self, LoadState::Fetching | LoadState::Parsing | LoadState::Rendering)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LoadState::Idle => "Idle",
+            LoadState::Fetching => "Loading",
+            LoadState::Parsing => "Parsing",
+            LoadState::Rendering => "Rendering",
+            LoadState::Complete => "Complete",
+            LoadState::Error(_) => "Error",
+        }
+    }
+
+    // Parsing only happens for pages pre-parsed off the UI thread, so Fetching may go straight to Rendering
+    fn can_advance_to(&self, next: &LoadState) -> bool {
+        matches!(
+            (self, next),
+            (LoadState::Fetching, LoadState::Parsing)
+                | (LoadState::Fetching | LoadState::Parsing, LoadState::Rendering)
+                | (LoadState::Rendering, LoadState::Complete)
+                | (LoadState::Fetching | LoadState::Parsing | LoadState::Rendering, LoadState::Error(_))
+        )
+    }
+}
+
+/// Something that happened to a tab's navigation, for the UI and dev console to react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadEvent {
+    Started { generation: u64, url: String },
+    StateChanged { generation: u64, state: LoadState },
+    Completed { generation: u64, url: String, elapsed: Duration },
+    Failed { generation: u64, url: String, error: String },
+    Cancelled { generation: u64, url: String },
+}
+
+/// State machine for one tab's navigations. Every navigation gets a new generation, and updates
+/// carrying an older generation are ignored, so a slow response can't overwrite a newer load.
+#[derive(Debug)]
+pub struct TabLoad {
+    generation: u64,
+    state: LoadState,
+    url: String,
+    started_at: Option<Instant>,
+    events: Vec<LoadEvent>,
+}
+
+impl Default for TabLoad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TabLoad {
+    pub fn new() -> Self {
+        Self {
+            generation: 0,
+            state: LoadState::Idle,
+            url: String::new(),
+            started_at: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &LoadState {
+        &self.state
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.state.is_loading()
+    }
+
+    pub fn is_current(&self, generation: u64) -> bool {
+        generation == self.generation
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match &self.state {
+            LoadState::Error(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Start a navigation, superseding any load still in progress
+    pub fn begin(&mut self, url: &str) -> u64 {
+        if self.state.is_loading() {
+            self.events.push(LoadEvent::Cancelled { generation: self.generation, url: self.url.clone() });
+        }
+        self.generation += 1;
+        self.state = LoadState::Fetching;
+        self.url = url.to_string();
+        self.started_at = Some(Instant::now());
+        self.events.push(LoadEvent::Started { generation: self.generation, url: self.url.clone() });
+        self.generation
+    }
+
+    /// Move the navigation `generation` to `next`. Returns false, changing nothing, when the
+    /// generation is stale or the transition isn't allowed from the current state.
+    pub fn advance(&mut self, generation: u64, next: LoadState) -> bool {
+        if !self.is_current(generation) || !self.state.can_advance_to(&next) {
+            return false;
+        }
+
+        self.state = next.clone();
+        self.events.push(LoadEvent::StateChanged { generation, state: next.clone() });
+        match next {
+            LoadState::Complete => self.events.push(LoadEvent::Completed {
+                generation,
+                url: self.url.clone(),
+                elapsed: self.started_at.map(|start| start.elapsed()).unwrap_or_default(),
+            }),
+            LoadState::Error(error) => self.events.push(LoadEvent::Failed {
+                generation,
+                url: self.url.clone(),
+                error,
+            }),
+            _ => {}
+        }
+        true
+    }
+
+    /// Render and complete the current navigation in one step, for pages that need no fetch
+    pub fn finish(&mut self, generation: u64) -> bool {
+        self.advance(generation, LoadState::Rendering) && self.advance(generation, LoadState::Complete)
+    }
+
+    /// Abandon the load in progress; results that arrive for it later are ignored
+    pub fn cancel(&mut self) {
+        if !self.state.is_loading() {
+            return;
+        }
+        self.events.push(LoadEvent::Cancelled { generation: self.generation, url: self.url.clone() });
+        self.generation += 1;
+        self.state = LoadState::Idle;
+    }
+
+    /// Events emitted since the last call
+    pub fn take_events(&mut self) -> Vec<LoadEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navigation_lifecycle() {
+        let mut load = TabLoad::new();
+        let generation = load.begin("https://example.com/");
+        assert!(load.is_loading());
+
+        assert!(load.advance(generation, LoadState::Parsing));
+        assert!(load.advance(generation, LoadState::Rendering));
+        assert!(!load.advance(generation, LoadState::Parsing));
+        assert!(load.advance(generation, LoadState::Complete));
+        assert!(!load.is_loading());
+
+        let events = load.take_events();
+        assert!(matches!(events.first(), Some(LoadEvent::Started { .. })));
+        assert!(matches!(events.last(), Some(LoadEvent::Completed { url, .. }) if url == "https://example.com/"));
+        assert!(load.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_stale_generation_is_ignored() {
+        let mut load = TabLoad::new();
+        let first = load.begin("https://slow.example/");
+        let second = load.begin("https://fast.example/");
+
+        // The first response arrives after the second navigation started
+        assert!(!load.advance(first, LoadState::Rendering));
+        assert_eq!(load.state(), &LoadState::Fetching);
+
+        assert!(load.advance(second, LoadState::Error("HTTP 500".to_string())));
+        assert_eq!(load.error(), Some("HTTP 500"));
+        assert!(load.take_events().iter().any(|e| matches!(e, LoadEvent::Cancelled { generation, .. } if *generation == first)));
+    }
+
+    #[test]
+    fn test_cancel_stops_loading() {
+        let mut load = TabLoad::new();
+        let generation = load.begin("https://example.com/");
+        load.cancel();
+        assert_eq!(load.state(), &LoadState::Idle);
+        assert!(!load.finish(generation));
+
+        let generation = load.begin("about:blank");
+        assert!(load.finish(generation));
+        assert_eq!(load.state(), &LoadState::Complete);
+    }
+}
@@ -1,35 +1,123 @@
 use eframe::egui::{self, Color32, Rounding, Shadow, Stroke, Vec2};
-use tokio::runtime::Runtime;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{self, Receiver, Sender};
 use uuid::Uuid;
 use crate::networking::HttpResponse;
 use crate::networking::cookie_manager::CookieManager;
-use crate::networking::manual_client::{ManualHttpClient, FetchPhase};
+use crate::networking::http_auth::AuthCache;
+use crate::networking::{connectivity, file_url, local_url, protocol_handlers};
+use crate::networking::manual_client::{ManualHttpClient, FetchContext};
+use crate::extensions::web_request::ResourceType;
+use crate::networking::site_metadata::SiteMetadataService;
+use crate::networking::redirect::{RedirectHop, ReferrerPolicy};
+use crate::networking::timing::{self, RequestTiming};
+use crate::networking::http_cache::CacheStatus;
+use crate::js::ErrorSeverity;
+use crate::networking::tracking::{ShieldReport, TrackingProtection};
 use crate::networking::image_loader::ImageCache;
 use crate::pages::PageRouter;
+use crate::storage::{ClosedItem, HistoryDatabase, Preferences, Session, SessionGroup, SessionTab, StartupPage};
+use crate::storage::session;
+use crate::storage::crash_reports::{self, CrashReport};
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::engine::resource_loader::{self, ResourceHint, ResourceKind, ResourceLoader};
+use crate::engine::prefetch::Prefetcher;
+use crate::engine::{ParsedDocument, WebPage};
+use crate::engine::dom::DOMNode;
+use crate::engine::fonts::FontRegistry;
+use crate::engine::download_manager::DownloadManager;
+use crate::engine::usage;
+use crate::security::SecurityLog;
+use crate::sync::SyncEngine;
+use crate::sync::snapshot::SyncedTab;
+use crate::platform::{CommandLine, InstanceListener, RemoteCommand};
+use crate::platform::remote_debugging::{self, DebugListener, DebugRequest};
 
 mod browser_tab;
 mod address_bar;
 mod navigation;
 mod bookmarks;
 pub mod theme;
+pub mod themes;
+pub mod i18n;
 mod error_handler;
 mod dev_console;
+mod print_preview;
+pub mod image_viewer;
+pub mod pdf_viewer;
+pub mod data_viewer;
+mod reader_view;
+pub mod tab_switcher;
+mod tab_strip;
+mod tab_sidebar;
+mod split_view;
+mod picture_in_picture;
 pub mod icons;
+pub mod load_state;
+pub mod notifications;
+pub mod waterfall;
+pub mod shortcuts;
+pub mod mouse_gestures;
 
-pub use browser_tab::BrowserTab;
-pub use address_bar::AddressBar;
+pub use browser_tab::{BrowserTab, NetworkEntry, PageAction};
+pub use address_bar::{AddressBar, SiteInfo, resolve_input};
 pub use navigation::NavigationBar;
 pub use bookmarks::BookmarkManager;
 pub use theme::NeonTheme;
+pub use themes::ThemeManager;
 pub use error_handler::{BrowserError, ErrorType, ErrorRecovery};
 pub use dev_console::DevConsole;
 pub use icons::NeonIcons;
+pub use load_state::{LoadEvent, LoadState, TabLoad};
+use tab_switcher::TabSwitcher;
+use tab_strip::{GroupCommand, StripEdit, TabStrip};
+use tab_sidebar::{SidebarAction, TabSidebar};
+use split_view::{SplitPane, SplitView};
+use picture_in_picture::{PictureInPicture, PipContent};
+use shortcuts::{ShortcutAction, Shortcuts};
+
+// Progress of a navigation fetch, tagged with the tab and load generation it belongs to
+struct NavigationMessage {
+    tab_id: Uuid,
+    generation: u64,
+    update: NavigationUpdate,
+}
+
+enum NavigationUpdate {
+    // The response arrived and its body is being parsed on the parse pool
+    Parsing,
+    // Boxed as it's far larger than the other updates
+    Finished(Box<NavigationResult>),
+}
+
+// A finished navigation fetch as delivered to the UI thread
+struct NavigationResult {
+    response: Result<HttpResponse, String>,
+    // Parsed off the UI thread when the response is a regular-sized HTML page
+    document: Option<ParsedDocument>,
+    final_url: Option<String>,
+    redirect_chain: Vec<RedirectHop>,
+    // The fetch failed because the machine has no connection
+    offline: bool,
+    // Only known when the manual client answered
+    timing: Option<RequestTiming>,
+}
 
 pub struct NeonSearchApp {
     tabs: HashMap<Uuid, BrowserTab>,
+    // Order of the tabs and the groups they're in
+    tab_strip: TabStrip,
+    tab_sidebar: TabSidebar,
+    // Group whose name is being edited in the tab bar
+    renaming_group: Option<Uuid>,
+    // Second tab or the developer console shown beside the active tab
+    split: Option<SplitView>,
+    pip: Option<PictureInPicture>,
     active_tab: Option<Uuid>,
+    tab_switcher: TabSwitcher,
+    // Where the active tab's page was drawn last frame, for its thumbnail
+    content_rect: Option<egui::Rect>,
     address_bar: AddressBar,
     navigation_bar: NavigationBar,
     bookmark_manager: BookmarkManager,
@@ -37,26 +125,55 @@ pub struct NeonSearchApp {
     page_router: PageRouter,
     show_bookmarks: bool,
     show_settings: bool,
-    network_receiver: Receiver<(Uuid, Result<HttpResponse, String>)>,
-    network_sender: Sender<(Uuid, Result<HttpResponse, String>)>,
-    runtime: Runtime,
-    cookies: CookieManager,
-    loading_tabs: HashMap<Uuid, std::time::Instant>,
+    network_receiver: Receiver<NavigationMessage>,
+    network_sender: Sender<NavigationMessage>,
+    cookies: Arc<Mutex<CookieManager>>,
+    // In-flight navigation per tab, cancelled when the tab navigates again or closes
+    navigation_tasks: HashMap<Uuid, TaskHandle<()>>,
     manual_client: ManualHttpClient,
-    tab_phases: HashMap<Uuid, Vec<FetchPhase>>,
-    image_cache: ImageCache,
+    history: Option<Arc<HistoryDatabase>>,
+    resource_loader: ResourceLoader,
+    site_metadata: Arc<SiteMetadataService>,
+    // Links later launches handed over, when this is the running instance
+    remote_commands: Option<Receiver<RemoteCommand>>,
+    // Calls from tools attached with --remote-debugging-port
+    debug_requests: Option<Receiver<DebugRequest>>,
+    // Screenshot calls and the frame they were asked for in, answered once a later frame's capture arrives
+    pending_screenshots: Vec<(u64, DebugRequest)>,
+    // A private window keeps no history, cookies or session, and doesn't sync
+    private: bool,
+    // Recently closed tabs and windows, the most recent last; saved with the session
+    closed: Vec<ClosedItem>,
+    // When what the tabs use was last recorded for neon://tasks
+    usage_recorded: Option<std::time::Instant>,
 }
 
 impl NeonSearchApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        start_urls: Vec<String>,
+        private: bool,
+        instance: Option<InstanceListener>,
+        debugger: Option<DebugListener>,
+    ) -> Self {
         // Apply the modern Neon theme
         NeonTheme::apply_to_context(&cc.egui_ctx);
+        i18n::init(Preferences::current().language.as_deref());
         let (network_sender, network_receiver) = mpsc::channel();
-        let runtime = Runtime::new().expect("Failed to build Tokio runtime");
+        let manual_client = ManualHttpClient::new().expect("manual client init");
+        let image_cache = ImageCache::new();
+        let site_metadata = SiteMetadataService::new(manual_client.clone(), image_cache.clone()).install();
         
         let mut app = Self {
             tabs: HashMap::new(),
+            tab_strip: TabStrip::default(),
+            tab_sidebar: TabSidebar::default(),
+            renaming_group: None,
+            split: None,
+            pip: None,
             active_tab: None,
+            tab_switcher: TabSwitcher::default(),
+            content_rect: None,
             address_bar: AddressBar::new(),
             navigation_bar: NavigationBar::new(),
             bookmark_manager: BookmarkManager::new(),
@@ -66,46 +183,322 @@ impl NeonSearchApp {
             show_settings: false,
             network_receiver,
             network_sender,
-            runtime,
-            cookies: CookieManager::new(),
-            loading_tabs: HashMap::new(),
-            manual_client: ManualHttpClient::new().expect("manual client init"),
-            tab_phases: HashMap::new(),
-            image_cache: ImageCache::new(),
+            cookies: Arc::new(Mutex::new(if private { CookieManager::new() } else { CookieManager::load(&CookieManager::path()) })),
+            navigation_tasks: HashMap::new(),
+            resource_loader: ResourceLoader::new(manual_client.clone(), image_cache),
+            site_metadata,
+            manual_client,
+            history: if private { None } else { HistoryDatabase::shared() },
+            private,
+            closed: Vec::new(),
+            usage_recorded: None,
+            remote_commands: instance.map(|instance| {
+                let ctx = cc.egui_ctx.clone();
+                instance.listen(move || ctx.request_repaint())
+            }),
+            debug_requests: debugger.map(|debugger| {
+                let ctx = cc.egui_ctx.clone();
+                debugger.listen(move || ctx.request_repaint())
+            }),
+            pending_screenshots: Vec::new(),
+        };
+        
+        // Continue the last session, unless it crashed and neon://crash offers to restore it instead.
+        // A private window starts with just its own links
+        let crashed = !private && CrashReport::pending(&CrashReport::dir()).is_some();
+        let saved = if private { None } else { Session::load(&Session::path()) };
+        app.closed = saved.as_ref().map(|session| session.closed.clone()).unwrap_or_default();
+        let session = match Preferences::current().startup {
+            StartupPage::LastSession if !crashed => saved.filter(|session| !session.tabs.is_empty()),
+            _ => None,
         };
+        if let Some(session) = &session {
+            app.restore_session(session);
+        }
+        
+        // Create initial tab, unless the session brought its own; URLs on the command line get one each
+        let opened_url = !start_urls.is_empty();
+        let tab_id = if opened_url {
+            start_urls.into_iter().map(|url| app.open_url_in_new_tab(url)).next()
+        } else {
+            session.is_none().then(|| app.create_new_tab())
+        };
+        
+        // The last session crashed: offer to restore it and send the report
+        if crashed {
+            let crash_tab = match tab_id.filter(|_| !opened_url) {
+                Some(tab_id) => tab_id,
+                None => app.create_new_tab(),
+            };
+            if let Some(tab) = app.tabs.get_mut(&crash_tab) {
+                tab.navigate_to("neon://crash".to_string());
+            }
+        }
         
-        // Create initial tab
-        app.create_new_tab();
+        // On the first run, offer to bring over data from another browser that's installed
+        if !crashed && !private && !Preferences::current().import_offered {
+            Preferences::update(|prefs| prefs.import_offered = true);
+            if !crate::storage::import::find_profiles().is_empty() {
+                let import_tab = app.create_new_tab();
+                if let Some(tab) = app.tabs.get_mut(&import_tab) {
+                    tab.navigate_to("neon://import".to_string());
+                }
+            }
+        }
         
         app
     }
     
+    /// The open tabs and their groups, as the session store keeps them
+    fn session(&self) -> Session {
+        let groups: Vec<Uuid> = self.tab_strip.groups().iter().map(|group| group.id).collect();
+        let tabs: Vec<Uuid> = self.tab_strip.tabs().iter().copied().filter(|id| self.tabs.contains_key(id)).collect();
+        Session {
+            tabs: tabs.iter().map(|id| SessionTab {
+                group: self.tab_strip.group_of(*id).and_then(|group| groups.iter().position(|g| *g == group)),
+                ..self.tabs[id].saved()
+            }).collect(),
+            groups: self.tab_strip.groups().iter().map(|group| SessionGroup {
+                name: group.name.clone(),
+                color: group.color,
+                collapsed: group.collapsed,
+            }).collect(),
+            active: self.active_tab.and_then(|active| tabs.iter().position(|id| *id == active)),
+            closed: self.closed.clone(),
+        }
+    }
+    
+    /// Bring back the most recently closed tab, in its old place, or window, in new tabs of this one
+    fn reopen_closed(&mut self) {
+        if let Some(item) = self.closed.pop() {
+            self.reopen(item);
+        }
+    }
+    
+    fn reopen(&mut self, item: ClosedItem) {
+        match item {
+            ClosedItem::Tab { tab, index } => {
+                let tab_id = self.create_new_tab();
+                self.tab_strip.remove(tab_id);
+                self.tab_strip.insert(index, tab_id);
+                if let Some(reopened) = self.tabs.get_mut(&tab_id) {
+                    reopened.restore(&tab);
+                }
+            }
+            ClosedItem::Window { tabs, groups } => {
+                self.restore_session(&Session { tabs, groups, active: Some(0), closed: Vec::new() });
+            }
+        }
+    }
+    
+    // Reopen a saved session's tabs and groups. Tabs load when first shown, not all at once
+    fn restore_session(&mut self, session: &Session) {
+        let groups: Vec<Uuid> = session.groups.iter().map(|saved| {
+            let id = self.tab_strip.create_group(saved.name.clone());
+            if let Some(group) = self.tab_strip.group_mut(id) {
+                group.color = saved.color;
+                group.collapsed = saved.collapsed;
+            }
+            id
+        }).collect();
+        let mut restored = Vec::new();
+        for saved in &session.tabs {
+            let tab_id = self.create_new_tab();
+            if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                tab.restore(saved);
+            }
+            if let Some(group) = saved.group.and_then(|index| groups.get(index)) {
+                self.tab_strip.move_to_group(tab_id, Some(*group));
+            }
+            restored.push(tab_id);
+        }
+        self.active_tab = session.active.and_then(|index| restored.get(index)).or(restored.first()).copied();
+    }
+    
+    fn open_url_in_new_tab(&mut self, url: String) -> Uuid {
+        let tab_id = self.create_new_tab();
+        let needs_fetch = self.tabs.get_mut(&tab_id).is_some_and(|tab| tab.navigate_to(url.clone()));
+        if needs_fetch {
+            self.fetch_url(tab_id, url);
+        }
+        tab_id
+    }
+    
+    // Open what a later launch of NeonSearch handed over, and bring the window forward for it
+    fn process_remote_commands(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.remote_commands else { return };
+        let commands: Vec<RemoteCommand> = receiver.try_iter().collect();
+        for command in commands {
+            match command {
+                RemoteCommand::OpenUrls(urls) => {
+                    for url in urls {
+                        self.open_url_in_new_tab(url);
+                    }
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                RemoteCommand::OpenWindow { urls, private } => {
+                    if let Err(e) = CommandLine::spawn_window(&urls, private) {
+                        self.dev_console.error(format!("Could not open a new window: {}", e));
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Answer calls from remote debugging tools; a tab is named by `targetId`, or is the active one
+    fn process_debug_requests(&mut self, ctx: &egui::Context) {
+        self.answer_screenshots(ctx);
+        let Some(receiver) = &self.debug_requests else { return };
+        let requests: Vec<DebugRequest> = receiver.try_iter().collect();
+        for request in requests {
+            let Some(tab_id) = request.target().or(self.active_tab).filter(|id| self.tabs.contains_key(id)) else {
+                request.respond(Err("No such target".to_string()));
+                continue;
+            };
+            let result = match request.method.as_str() {
+                "Target.getTargets" => {
+                    let targets = self.tab_strip.tabs().iter()
+                        .filter_map(|id| self.tabs.get(id).map(|tab| (id, tab)))
+                        .map(|(id, tab)| serde_json::json!({
+                            "targetId": id.to_string(),
+                            "url": tab.url,
+                            "title": tab.title,
+                            "active": self.active_tab == Some(*id),
+                        }))
+                        .collect::<Vec<_>>();
+                    Ok(serde_json::json!({ "targets": targets }))
+                }
+                "Page.navigate" => request.string_param("url").map(|url| {
+                    let url = resolve_input(url);
+                    if self.tabs.get_mut(&tab_id).is_some_and(|tab| tab.navigate_to(url.clone())) {
+                        self.fetch_url(tab_id, url);
+                    }
+                    serde_json::json!({})
+                }),
+                // Only what's on screen can be captured, so the tab is brought forward first
+                "Page.captureScreenshot" => {
+                    self.active_tab = Some(tab_id);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+                    ctx.request_repaint();
+                    self.pending_screenshots.push((ctx.cumulative_pass_nr(), request));
+                    continue;
+                }
+                _ => debug_tab_call(self.tabs.get_mut(&tab_id).expect("checked above"), &request),
+            };
+            request.respond(result);
+        }
+    }
+    
+    // A capture asked for in an earlier frame shows the tab as it was brought forward
+    fn answer_screenshots(&mut self, ctx: &egui::Context) {
+        if self.pending_screenshots.is_empty() {
+            return;
+        }
+        let screenshot = ctx.input(|i| i.events.iter().find_map(|event| match event {
+            egui::Event::Screenshot { image, .. } => Some(image.clone()),
+            _ => None,
+        }));
+        let (Some(image), Some(rect)) = (screenshot, self.content_rect) else { return };
+        let frame = ctx.cumulative_pass_nr();
+        let (ready, waiting) = std::mem::take(&mut self.pending_screenshots).into_iter()
+            .partition(|(asked_in, _)| *asked_in < frame);
+        self.pending_screenshots = waiting;
+        if ready.is_empty() {
+            return;
+        }
+        let region = image.region(&rect, Some(ctx.pixels_per_point()));
+        let mut png = Vec::new();
+        let encoded = image::RgbaImage::from_raw(region.width() as u32, region.height() as u32, region.as_raw().to_vec())
+            .ok_or_else(|| "The capture was empty".to_string())
+            .and_then(|picture| {
+                picture.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).map_err(|e| e.to_string())
+            })
+            .map(|()| {
+                use base64::Engine;
+                serde_json::json!({ "data": base64::engine::general_purpose::STANDARD.encode(&png) })
+            });
+        for (_, request) in ready {
+            request.respond(encoded.clone());
+        }
+    }
+    
     fn create_new_tab(&mut self) -> Uuid {
         let tab_id = Uuid::new_v4();
         let tab = BrowserTab::new("New Tab".to_string());
         self.tabs.insert(tab_id, tab);
+        self.tab_strip.push(tab_id);
         self.active_tab = Some(tab_id);
         tab_id
     }
     
     fn close_tab(&mut self, tab_id: Uuid) {
+        if self.split.as_ref().is_some_and(|split| split.contains(tab_id)) {
+            self.close_split_pane(SplitPane::Tab(tab_id));
+        }
+        if self.pip.as_ref().is_some_and(|pip| pip.source_tab() == tab_id) {
+            self.close_pip();
+        }
+        self.cancel_navigation(tab_id);
+        if let Some(tab) = self.tabs.get(&tab_id).filter(|tab| !is_blank(tab)) {
+            let index = self.tab_strip.tabs().iter().position(|id| *id == tab_id).unwrap_or(0);
+            session::push_closed(&mut self.closed, ClosedItem::Tab { tab: tab.saved(), index });
+        }
+        let clear_cookies_for = self.tabs.get_mut(&tab_id).and_then(|tab| {
+            tab.load.cancel();
+            Preferences::current().cookies.clear_on_close_host(&tab.url)
+        });
         if self.tabs.len() == 1 {
             // Don't close the last tab, just navigate to home
             if let Some(tab) = self.tabs.get_mut(&tab_id) {
                 tab.navigate_to("about:home".to_string());
             }
-            return;
+        } else {
+            self.tabs.remove(&tab_id);
+            self.tab_strip.remove(tab_id);
+            
+            if self.active_tab == Some(tab_id) {
+                // Go back to the tab used before this one
+                self.active_tab = self.tab_switcher.most_recent_except(tab_id)
+                    .or_else(|| self.tab_strip.tabs().first().copied());
+            }
+            self.tab_switcher.forget(tab_id);
         }
         
-        self.tabs.remove(&tab_id);
-        
-        if self.active_tab == Some(tab_id) {
-            // Set active tab to the first remaining tab
-            self.active_tab = self.tabs.keys().next().copied();
+        // Sites set to forget their cookies do so once none of their tabs remain open
+        if let Some(host) = clear_cookies_for {
+            let cookies = Preferences::current().cookies.clone();
+            let still_open = self.tabs.values()
+                .any(|tab| cookies.clear_on_close_host(&tab.url).is_some_and(|open| open == host));
+            if !still_open {
+                if let Ok(mut jar) = self.cookies.lock() {
+                    jar.clear_cookies_for_site(&host);
+                }
+            }
         }
     }
     
-    pub fn fetch_url(&self, tab_id: Uuid, url: String) {
+    /// Fetch `url` for the tab's current navigation, which the tab has already begun
+    pub fn fetch_url(&mut self, tab_id: Uuid, url: String) {
+        let size_limit = Preferences::current().page_size_limit();
+        self.fetch_url_with_limit(tab_id, url, size_limit);
+    }
+    
+    // Bodies past `size_limit` are not read and come back marked as oversized
+    fn fetch_url_with_limit(&mut self, tab_id: Uuid, url: String, size_limit: Option<usize>) {
+        self.cancel_navigation(tab_id);
+        let Some(tab) = self.tabs.get(&tab_id) else {
+            return;
+        };
+        let generation = tab.load.generation();
+        let accept_language = tab.locale_overrides.accept_language_header();
+        let cache_mode = tab.cache_mode;
+        let request_body = tab.request_body();
+        let (referrer, referrer_policy) = tab.referrer().unzip();
+        // Credentials entered for the origin this session are sent up front instead of waiting for a 401
+        let method = request_body.as_ref().map_or("GET", |body| body.method.as_str());
+        let authorization = AuthCache::shared().lock().ok().and_then(|mut auth| auth.authorization_for(&url, method));
+        
         // Check if this is a custom page first
         if self.page_router.can_handle(&url) {
             // Handle custom pages immediately without network request
@@ -118,199 +511,1138 @@ impl NeonSearchApp {
             let response = HttpResponse::new(200, "OK".to_string(), headers, content.into_bytes());
             
             // Send immediately
-            let _ = sender.send((tab_id, Ok(response)));
+            let _ = sender.send(NavigationMessage {
+                tab_id,
+                generation,
+                update: NavigationUpdate::Finished(Box::new(NavigationResult {
+                    response: Ok(response),
+                    document: None,
+                    final_url: None,
+                    redirect_chain: Vec::new(),
+                    offline: false,
+                    timing: None,
+                })),
+            });
             return;
         }
         
         let sender = self.network_sender.clone();
-        let manual = self.manual_client.clone();
-        let cookie_header;
-        let uri = url.clone();
-        // Basic cookie header assembly (domain + path split)
-        if let Ok(parsed) = reqwest::Url::parse(&uri) {
-            let domain = parsed.host_str().unwrap_or("");
-            let path = parsed.path();
-            let is_secure = parsed.scheme() == "https";
-            cookie_header = self.cookies.get_cookie_header_for_request(domain, path, is_secure);
-        } else {
-            cookie_header = None;
-        }
+        let mut manual = self.manual_client.clone();
+        manual.set_max_body_size(size_limit);
+        // A hop that fails on a TLS quirk or timeout is sent again over reqwest, inside the same redirect loop
+        manual.set_fallback(Some(BrowserError::allows_fallback));
+        let cookies = self.cookies.clone();
+        // Redirects, per-hop cookies and Referer are all handled inside the networking layer, fallback hops included
+        let context = FetchContext {
+            cookies: Some(cookies.clone()),
+            referrer,
+            referrer_policy: referrer_policy.unwrap_or_default(),
+            authorization: authorization.clone(),
+            accept_language: accept_language.clone(),
+            body: request_body.clone(),
+            resource_type: ResourceType::MainFrame,
+            cache_mode,
+            ..FetchContext::default()
+        };
         let original_url = url.clone();
-        self.runtime.spawn(async move {
+        let task = Executor::shared().spawn(TaskKind::Network, async move {
             // Manual attempt first
-            let manual_attempt = manual.fetch(&url).await;
+            let manual_attempt = manual.fetch_with_context(&url, &context).await;
+            let mut final_url = None;
+            let mut redirect_chain = Vec::new();
+            let mut timing = None;
             let result = match manual_attempt {
-                Ok(res) => Ok(res.response),
+                Ok(res) => {
+                    final_url = Some(res.final_url);
+                    redirect_chain = res.redirect_chain;
+                    timing = Some(res.timing);
+                    Ok(res.response)
+                },
                 Err(e) => {
-                    // Enhanced error handling with detailed categorization
-                    let err_str = e.to_string();
-                    
-                    if let Some(redirect_url) = err_str.strip_prefix("REDIRECT:") {
-                        // Handle redirects by creating a proper redirect response
-                        let mut headers = std::collections::HashMap::new();
-                        headers.insert("Location".to_string(), redirect_url.to_string());
-                        Ok(HttpResponse::new(302, "Found".to_string(), headers, Vec::new()))
-                    } else {
-                        // Create detailed browser error for better user experience
-                        let browser_error = BrowserError::from_anyhow(&e, Some(&url));
-                        
-                        // Determine if we should attempt reqwest fallback
-                        let should_fallback = matches!(browser_error.error_type,
-                            ErrorType::TlsHandshakeFailed | 
-                            ErrorType::NetworkTimeout |
-                            ErrorType::InternalError
-                        ) || err_str.contains("TLS_CLOSE_NOTIFY_ISSUE");
-                        
-                        if should_fallback {
-                            println!("🔄 Attempting reqwest fallback for {} ({})", url, browser_error.error_type);
-                            
-                            let mut request = crate::networking::HttpRequest::new_get(url.clone());
-                            if let Some(c) = cookie_header.clone() { 
-                                request.headers.insert("Cookie".to_string(), c); 
-                            }
-                            
-                            match crate::networking::http_client::send_request(request).await {
-                                Ok(response) => {
-                                    println!("✅ Reqwest fallback succeeded for {}", url);
-                                    Ok(response)
-                                },
-                                Err(fallback_err) => {
-                                    // Both manual and reqwest failed
-                                    println!("❌ Both manual and reqwest failed for {}", url);
-                                    Err(format!(
-                                        "{}. Fallback error: {}", 
-                                        browser_error.message,
-                                        fallback_err
-                                    ))
-                                }
-                            }
-                        } else {
-                            // Don't fallback for DNS errors, 404s, etc.
-                            println!("⚠️  No fallback attempted for {} ({})", url, browser_error.error_type);
-                            Err(browser_error.message)
-                        }
-                    }
+                    // Hops the manual client couldn't complete were already retried over reqwest where that helps
+                    let browser_error = BrowserError::from_anyhow(&e, Some(&url));
+                    let answered_locally = local_url::is_local(&url) || file_url::is_file(&url);
+                    Err(if answered_locally { e.to_string() } else { browser_error.message })
                 }
             };
-            if let Err(e) = &result { eprintln!("[network] Failed to fetch {original_url}: {e}"); }
-            let _ = sender.send((tab_id, result));
+            if let Err(e) = &result { log::error!("Failed to fetch {original_url}: {e}"); }
+            let offline = result.is_err() && (url.starts_with("http://") || url.starts_with("https://")) && connectivity::is_offline().await;
+            
+            // A page parsed when it was prefetched is used as is when the cache answered with the same body
+            let from_cache = timing.as_ref().is_some_and(|timing| timing.cache != CacheStatus::Network);
+            let preparsed = final_url.as_deref().filter(|_| from_cache).and_then(|url| Prefetcher::shared().take_preparsed(url));
+            
+            // Decode and parse on the parse pool so the UI thread only builds the page
+            let document = match &result {
+                _ if preparsed.is_some() => preparsed,
+                Ok(response) if ParsedDocument::should_preparse(response) => {
+                    let _ = sender.send(NavigationMessage { tab_id, generation, update: NavigationUpdate::Parsing });
+                    let response = response.clone();
+                    Executor::shared()
+                        .spawn_blocking(TaskKind::Parse, move || {
+                            response.body_as_string().ok().map(|html| ParsedDocument::parse(&html))
+                        })
+                        .join()
+                        .await
+                        .ok()
+                        .flatten()
+                }
+                _ => None,
+            };
+            // Act on the page's resource hints while the UI thread builds it
+            if let Some(document) = &document {
+                apply_resource_hints(&manual, &cookies, &document.dom, final_url.as_deref().unwrap_or(&url));
+            }
+            
+            let _ = sender.send(NavigationMessage {
+                tab_id,
+                generation,
+                update: NavigationUpdate::Finished(Box::new(NavigationResult {
+                    response: result,
+                    document,
+                    final_url,
+                    redirect_chain,
+                    offline,
+                    timing,
+                })),
+            });
         });
+        self.navigation_tasks.insert(tab_id, task);
     }
     
-    fn process_network_responses(&mut self) {
-        while let Ok((tab_id, result)) = self.network_receiver.try_recv() {
-            if let Some(tab) = self.tabs.get_mut(&tab_id) {
-                if let Err(e) = &result {
-                    eprintln!("[network] response error for tab {tab_id}: {e}");
-                }
-                if let Ok(resp) = &result {
-                    // Parse cookies
-                    if let Ok(parsed_url) = reqwest::Url::parse(&tab.url) {
-                        let domain = parsed_url.host_str().unwrap_or("");
-                        for (k, v) in &resp.headers {
-                            if k.eq_ignore_ascii_case("set-cookie") {
-                                self.cookies.parse_set_cookie_header(v, domain);
-                            }
-                        }
+    /// Stop the in-flight fetch for a tab, if any
+    fn cancel_navigation(&mut self, tab_id: Uuid) {
+        if let Some(task) = self.navigation_tasks.remove(&tab_id) {
+            task.cancel();
+        }
+    }
+    
+    /// Reload tabs whose offline page is due to try again, including ones in the background
+    fn retry_offline_tabs(&mut self, ctx: &egui::Context) {
+        let due: Vec<Uuid> = self.tabs.iter().filter(|(_, tab)| tab.offline_retry_due()).map(|(id, _)| *id).collect();
+        for tab_id in due {
+            let Some(tab) = self.tabs.get_mut(&tab_id) else { continue };
+            if tab.reload() {
+                let url = tab.url.clone();
+                self.fetch_url(tab_id, url);
+            }
+        }
+        if self.tabs.values().any(BrowserTab::is_offline) {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+    
+    /// Stop a tab's navigation, leaving the page it showed before, or its page's subresources
+    fn stop_tab(&mut self, tab_id: Uuid) {
+        self.cancel_navigation(tab_id);
+        let Some(tab) = self.tabs.get_mut(&tab_id) else { return };
+        if tab.stop() && self.active_tab == Some(tab_id) {
+            self.address_bar.set_url(tab.url.clone());
+        }
+    }
+    
+    /// Apply subresources that finished loading since the last frame
+    fn process_subresources(&mut self, ctx: &egui::Context) {
+        for tab in self.tabs.values_mut() {
+            let Some(resources) = tab.resources.as_mut() else {
+                continue;
+            };
+            // Lazy images that scrolled near the viewport during the last frame
+            if let Some(page) = tab.web_page.as_ref() {
+                resources.load_deferred(page.take_visible_images());
+            }
+            
+            // Snapshot first: once every fetch has counted itself, all results are already queued
+            let mut finished = resources.progress().is_finished();
+            let ready = resources.take_ready();
+            
+            if let Some(page) = tab.web_page.as_mut() {
+                for resource in ready {
+                    if let Some(timing) = &resource.timing {
+                        timing::record_subresource(&tab.url, timing.clone());
+                    }
+                    tab.network_log.push(NetworkEntry {
+                        url: resource.request.url.clone(),
+                        kind: format!("{:?}", resource.request.kind),
+                        status: None,
+                        error: resource.result.as_ref().err().cloned(),
+                        timing: resource.timing.clone(),
+                    });
+                    if let Err(e) = page.apply_resource(resource) {
+                        self.dev_console.warn(format!("Failed to load resource {}", e));
                     }
                 }
-                let was_redirect = match &result {
-                    Ok(r) if r.is_redirect() => true,
-                    _ => false,
-                };
-                // Extract HTML content before handling response (for favicon loading)
-                let html_content = if let Ok(ref resp) = result {
-                    if resp.is_success() {
-                        resp.body_as_string().ok()
-                    } else {
-                        None
+                // Stylesheets that just arrived may declare fonts the page uses
+                let fonts = page.take_font_requests();
+                if !fonts.is_empty() {
+                    resources.request(fonts);
+                    finished = false;
+                }
+            }
+            
+            if !finished {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            } else if !resources.has_deferred() {
+                tab.resources = None;
+            }
+        }
+    }
+    
+    fn process_network_responses(&mut self) {
+        while let Ok(NavigationMessage { tab_id, generation, update }) = self.network_receiver.try_recv() {
+            let Some(tab) = self.tabs.get_mut(&tab_id) else {
+                continue;
+            };
+            // Anything from a superseded navigation is stale
+            if !tab.load.is_current(generation) {
+                continue;
+            }
+            
+            let navigation = match update {
+                NavigationUpdate::Parsing => {
+                    tab.load.advance(generation, LoadState::Parsing);
+                    continue;
+                }
+                NavigationUpdate::Finished(navigation) => navigation,
+            };
+            
+            self.navigation_tasks.remove(&tab_id);
+            let NavigationResult { response: result, document, final_url, redirect_chain, offline, timing: document_timing } = *navigation;
+            if let Err(e) = &result {
+                log::warn!("Response error for tab {tab_id}: {e}");
+            }
+            
+            // Report the redirect chain so it shows up next to the page's console output
+            for hop in &redirect_chain {
+                self.dev_console.info(format!(
+                    "↪ {} {} → {}{}",
+                    hop.status_code,
+                    hop.from,
+                    hop.to,
+                    if hop.cross_origin { " (cross-origin)" } else { "" }
+                ));
+            }
+            if let Some(final_url) = final_url {
+                tab.apply_redirect_chain(final_url, redirect_chain);
+                if self.active_tab == Some(tab_id) {
+                    self.address_bar.set_url(tab.url.clone());
+                }
+            }
+            
+            let is_success = result.as_ref().is_ok_and(|resp| resp.is_success());
+            let referrer_policy = result.as_ref().ok()
+                .and_then(|response| response.headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("referrer-policy")))
+                .and_then(|(_, value)| ReferrerPolicy::from_header(value));
+            
+            tab.script_errors.clear();
+            tab.network_log = tab.redirect_chain.iter()
+                .map(|hop| NetworkEntry { url: hop.from.clone(), kind: "Document".to_string(), status: Some(hop.status_code), error: None, timing: None })
+                .chain(std::iter::once(NetworkEntry {
+                    url: tab.url.clone(),
+                    kind: "Document".to_string(),
+                    status: result.as_ref().ok().map(|response| response.status_code),
+                    error: result.as_ref().err().cloned(),
+                    timing: document_timing.clone(),
+                }))
+                .collect();
+            if let Some(document_timing) = document_timing {
+                timing::record_navigation(document_timing);
+            }
+            tab.handle_network_response(generation, result, document);
+            tab.set_offline(offline);
+            
+            // Successful page loads are recorded in history and fetch their subresources
+            if tab.load.state() == &LoadState::Complete && !self.page_router.can_handle(&tab.url) {
+                if let Some(history) = &self.history {
+                    if let Err(e) = history.record_visit(&tab.url, &tab.title, chrono::Utc::now()) {
+                        log::warn!("Failed to record history visit: {}", e);
                     }
-                } else {
-                    None
-                };
+                }
                 
-                tab.handle_network_response(result);
+                if Preferences::current().reader.always_for(&tab.url) {
+                    tab.open_reader();
+                }
                 
-                // Preload favicon if we successfully loaded HTML content
-                if let Some(html) = html_content {
-                    let image_cache = self.image_cache.clone();
-                    let manual_client = self.manual_client.clone();
-                    let base_url = tab.url.clone();
-                    
-                    self.runtime.spawn(async move {
-                        image_cache.preload_favicon(&base_url, &html, &manual_client).await;
+                let session_history = (tab.history.len(), tab.history_state());
+                let requests = tab.web_page.as_mut()
+                    .map(|page| {
+                        page.set_scripts_enabled(Preferences::current().javascript.allows(&tab.url));
+                        page.set_script_time_budget(std::time::Duration::from_secs(Preferences::current().javascript.slow_script_secs));
+                        page.set_locale_overrides(&tab.locale_overrides);
+                        page.set_document_url(&tab.url);
+                        page.set_referrer_policy(referrer_policy);
+                        page.set_cookie_jar(self.cookies.clone());
+                        page.set_tracking_protection(Preferences::current().tracking.protects(&tab.url)
+                            .then(|| TrackingProtection::new(&tab.url))
+                            .flatten());
+                        page.set_session_history(session_history.0, session_history.1);
+                        page.load_inline_styles(&tab.url);
+                        // Before discovery, so elements a script removes aren't fetched
+                        page.run_inline_scripts();
+                        page.run_content_scripts(&crate::extensions::content_scripts::for_url(&tab.url));
+                        let mut requests = resource_loader::discover_resources(&page.dom, &tab.url, Preferences::current().image_loading);
+                        let images_allowed = Preferences::current().images.allowed(&tab.url);
+                        requests.retain(|request| match request.kind {
+                            ResourceKind::Script => page.scripts_enabled(),
+                            ResourceKind::Image => images_allowed,
+                            _ => true,
+                        });
+                        requests.extend(page.take_font_requests());
+                        requests
+                    })
+                    .unwrap_or_default();
+                if let Some(report) = tab.security_report.as_mut() {
+                    report.check_mixed_content(&requests);
+                    SecurityLog::shared().record(report);
+                }
+                if !requests.is_empty() {
+                    let context = FetchContext {
+                        cookies: Some(self.cookies.clone()),
+                        referrer: Some(tab.url.clone()),
+                        referrer_policy: tab.web_page.as_ref().map(WebPage::referrer_policy).unwrap_or_default(),
+                        tracking: tab.web_page.as_ref().and_then(|page| page.tracking_protection().cloned()),
+                        accept_language: tab.locale_overrides.accept_language_header(),
+                        cache_mode: tab.cache_mode,
+                        ..FetchContext::default()
+                    };
+                    tab.resources = Some(self.resource_loader.start(requests, context));
+                }
+            }
+            
+            // Let the site metadata service pick up the favicon and site name from the loaded page
+            if is_success {
+                if let Some(page) = &tab.web_page {
+                    self.site_metadata.record_page(&tab.url, &page.dom);
+                }
+            }
+        }
+    }
+    
+    fn handle_page_action(&mut self, action: PageAction) {
+        match action {
+            PageAction::OpenInNewTab(url) => {
+                // Links open in the background, like other browsers
+                let active = self.active_tab;
+                let tab_id = self.create_new_tab();
+                self.active_tab = active;
+                let needs_fetch = self.tabs.get_mut(&tab_id).is_some_and(|tab| tab.navigate_to(url.clone()));
+                if needs_fetch {
+                    self.fetch_url(tab_id, url);
+                }
+            }
+            PageAction::Inspect(target) => {
+                self.dev_console.set_visible(true);
+                self.dev_console.info(format!("Inspect element\n{}", target.describe()));
+            }
+            PageAction::LoadFullContent => {
+                let Some(tab_id) = self.active_tab else { return };
+                let Some(tab) = self.tabs.get_mut(&tab_id) else { return };
+                if tab.load_full_content() {
+                    let url = tab.url.clone();
+                    self.fetch_url_with_limit(tab_id, url, None);
+                }
+            }
+            PageAction::Download(url) => self.start_download(url),
+            PageAction::PictureInPicture(url) => {
+                let Some(tab_id) = self.active_tab else { return };
+                let natural = self.tabs.get(&tab_id)
+                    .and_then(|tab| tab.web_page.as_ref())
+                    .and_then(|page| page.video_size(&url));
+                self.open_pip(PictureInPicture::video(tab_id, url, natural));
+            }
+            PageAction::ImportCookies(cookies) => {
+                if let Ok(mut jar) = self.cookies.lock() {
+                    for (host, cookie) in cookies {
+                        jar.add_cookie(cookie, &host);
+                    }
+                    if let Err(e) = jar.save(&CookieManager::path()) {
+                        self.dev_console.warn(format!("Failed to save imported cookies: {}", e));
+                    }
+                }
+            }
+            PageAction::Preconnect(url) => self.manual_client.preconnect(&url),
+            PageAction::JavaScriptSettingsChanged => self.apply_javascript_settings(),
+            PageAction::KillTab(tab_id) => {
+                if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                    tab.kill();
+                }
+            }
+            PageAction::GoBack | PageAction::GoForward => {
+                if let Some(tab_id) = self.active_tab {
+                    self.go_in_history(tab_id, matches!(action, PageAction::GoForward));
+                }
+            }
+            PageAction::OpenInNewWindow(url) => {
+                // Each window is its own browser process, started on the link
+                if let Err(e) = CommandLine::spawn_window(&[url], self.private) {
+                    self.dev_console.error(format!("Could not open a new window: {}", e));
+                }
+            }
+        }
+    }
+    
+    // Walking every page's document isn't free, so it's only done while neon://tasks is open, once a second
+    fn record_tab_usage(&mut self) {
+        let showing = self.active_tab.and_then(|id| self.tabs.get(&id)).is_some_and(|tab| tab.url == "neon://tasks");
+        if !showing || self.usage_recorded.is_some_and(|recorded| recorded.elapsed() < std::time::Duration::from_secs(1)) {
+            return;
+        }
+        self.usage_recorded = Some(std::time::Instant::now());
+        usage::record_tabs(self.tab_strip.tabs().iter()
+            .filter_map(|id| Some(self.tabs.get(id)?.usage(*id)))
+            .collect());
+    }
+    
+    /// Go back, or forward, a history entry in the tab, fetching the page unless it's the same document
+    fn go_in_history(&mut self, tab_id: Uuid, forward: bool) {
+        let Some(tab) = self.tabs.get_mut(&tab_id) else { return };
+        let previous_url = tab.url.clone();
+        let needs_fetch = if forward { tab.go_forward() } else { tab.go_back() };
+        let url = tab.url.clone();
+        if Some(tab_id) == self.active_tab && url != previous_url {
+            self.address_bar.set_url(url.clone());
+        }
+        if needs_fetch {
+            self.fetch_url(tab_id, url);
+        }
+    }
+    
+    /// Save `url` to the downloads folder; it shows up on neon://downloads
+    fn start_download(&mut self, url: String) {
+        let db_path = crate::storage::data_dir().join("downloads.db");
+        let manager = match DownloadManager::new(&db_path) {
+            Ok(manager) => manager,
+            Err(e) => {
+                self.dev_console.warn(format!("Failed to start download of {}: {}", url, e));
+                return;
+            }
+        };
+        let download_dir = dirs::download_dir().unwrap_or_else(std::env::temp_dir);
+        let save_path = DownloadManager::generate_safe_download_path(&download_dir, &url);
+        self.dev_console.info(format!("Downloading {} to {}", url, save_path.display()));
+        Executor::shared().spawn(TaskKind::Download, async move {
+            if let Err(e) = manager.start_download(url.clone(), save_path).await {
+                log::warn!("Failed to download {url}: {e}");
+            }
+        });
+    }
+    
+    /// Report what happened to each tab's navigations since the last frame
+    fn apply_strip_edit(&mut self, edit: StripEdit) {
+        match edit {
+            StripEdit::Update(group) => {
+                if let Some(existing) = self.tab_strip.group_mut(group.id) {
+                    *existing = group;
+                }
+            }
+            StripEdit::Rename(id, name) => {
+                if let Some(group) = self.tab_strip.group_mut(id) {
+                    group.name = name;
+                }
+            }
+            StripEdit::FinishRename => self.renaming_group = None,
+            StripEdit::Command(id, GroupCommand::Rename) => self.renaming_group = Some(id),
+            StripEdit::Command(id, GroupCommand::Ungroup) => self.tab_strip.ungroup(id),
+            StripEdit::Command(id, GroupCommand::Close) => self.close_group(id),
+            StripEdit::MoveTab(tab, group) => self.tab_strip.move_to_group(tab, group),
+            StripEdit::NewGroupWith(tab) => {
+                let group = self.tab_strip.create_group(format!("Group {}", self.tab_strip.groups().len() + 1));
+                self.tab_strip.move_to_group(tab, Some(group));
+                self.renaming_group = Some(group);
+            }
+        }
+    }
+    
+    /// Close every tab in `group`, which takes the group with them
+    fn close_group(&mut self, group: Uuid) {
+        for tab_id in self.tab_strip.tabs_in(group) {
+            self.close_tab(tab_id);
+        }
+        self.tab_strip.ungroup(group);
+    }
+    
+    /// Draw `tab_id`'s page into `ui`, then start the fetches and carry out the actions it asked for
+    fn show_tab_content(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, tab_id: Uuid) {
+        // While the picture-in-picture window is drawing it, `pip` is taken, so this only matches elsewhere
+        if self.pip.as_ref().is_some_and(|pip| pip.content == PipContent::Tab(tab_id)) {
+            ui.centered_and_justified(|ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(egui::RichText::new("This tab is showing in picture-in-picture").size(16.0).color(NeonTheme::secondary_text()));
+                    if ui.button("Bring it back").clicked() {
+                        self.close_pip();
+                    }
+                });
+            });
+            return;
+        }
+        
+        let mut needs_fetch = false;
+        let mut current_url = String::new();
+        let mut page_actions = Vec::new();
+        
+        if let Some(tab) = self.tabs.get_mut(&tab_id) {
+            current_url = tab.url.clone();
+            
+            // Check if this is a custom page
+            if self.page_router.can_handle(&current_url) {
+                // Render custom page directly
+                egui::Frame::none()
+                    .fill(NeonTheme::card_bg())
+                    .rounding(egui::Rounding::same(12.0))
+                    .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+                    .inner_margin(egui::Margin::same(16.0))
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .auto_shrink([false; 2])
+                            .show(ui, |ui| {
+                                self.page_router.render_page(&current_url, ui, ctx);
+                            });
                     });
+                page_actions = self.page_router.take_page_actions(&current_url);
+                
+                // Update tab title if needed
+                if let Some(page_title) = self.page_router.get_page_title(&current_url) {
+                    tab.title = page_title;
                 }
+            } else {
+                // Render normal web page
+                egui::Frame::none()
+                    .fill(NeonTheme::card_bg())
+                    .rounding(egui::Rounding::same(12.0))
+                    .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+                    .inner_margin(egui::Margin::same(16.0))
+                    .show(ui, |ui| {
+                        // A panic while rendering one page takes down that tab, not the browser
+                        match crash_reports::isolate(|| tab.show(ui)) {
+                            Ok(fetch) => needs_fetch = fetch,
+                            Err(message) => {
+                                self.dev_console.error(format!("Page crashed while rendering {}: {}", current_url, message));
+                                tab.mark_crashed(message);
+                            }
+                        }
+                    });
+                page_actions = tab.take_page_actions();
                 
-                if was_redirect {
-                    // Follow redirect automatically
-                    let current_url = tab.url.clone();
-                    if tab.redirects_followed > 0 && tab.redirects_followed <= 5 {
-                        self.fetch_url(tab_id, current_url.clone());
-                        self.loading_tabs.insert(tab_id, std::time::Instant::now());
+                // Following a link, or a script's pushState, changes the tab's URL during show()
+                if tab.url != current_url {
+                    current_url = tab.url.clone();
+                    if self.active_tab == Some(tab_id) {
+                        self.address_bar.set_url(current_url.clone());
                     }
-                } else {
-                    self.loading_tabs.remove(&tab_id);
                 }
             }
         }
+        
+        if needs_fetch {
+            self.fetch_url(tab_id, current_url);
+        }
+        for action in page_actions {
+            self.handle_page_action(action);
+        }
+    }
+    
+    /// Both sides of the split view with the divider between them, which can be dragged to resize them
+    fn show_split(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let Some(mut split) = self.split.clone() else { return };
+        let rect = ui.available_rect_before_wrap();
+        let (left, divider, right) = split.layout(rect);
+        
+        let response = ui.interact(divider, ui.id().with("split_divider"), egui::Sense::drag());
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.dragged()) {
+            split.drag_to(rect, pos.x);
+        }
+        if response.hovered() || response.dragged() {
+            ctx.set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        }
+        let color = if response.dragged() { NeonTheme::neon_cyan() } else { NeonTheme::border_color() };
+        ui.painter().vline(divider.center().x, divider.y_range().shrink(8.0), Stroke::new(2.0, color));
+        self.split = Some(split.clone());
+        
+        self.show_split_pane(ui, ctx, left, SplitPane::Tab(split.left));
+        self.show_split_pane(ui, ctx, right, split.right);
+        ui.allocate_rect(rect, egui::Sense::hover());
+    }
+    
+    fn show_split_pane(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, rect: egui::Rect, pane: SplitPane) {
+        let mut ui = ui.new_child(egui::UiBuilder::new().max_rect(rect).layout(egui::Layout::top_down(egui::Align::Min)));
+        let ui = &mut ui;
+        // Clicking into a side makes its tab the active one, which the toolbar then acts on
+        let clicked = ui.input(|i| i.pointer.any_pressed() && i.pointer.interact_pos().is_some_and(|pos| rect.contains(pos)));
+        if let (SplitPane::Tab(tab_id), true) = (pane, clicked) {
+            if self.active_tab != Some(tab_id) {
+                self.active_tab = Some(tab_id);
+                if let Some(tab) = self.tabs.get(&tab_id) {
+                    self.address_bar.set_url(tab.url.clone());
+                }
+            }
+        }
+        
+        let mut close = false;
+        let mut needs_fetch = None;
+        ui.horizontal(|ui| {
+            match pane {
+                SplitPane::Tab(tab_id) => {
+                    let is_active = self.active_tab == Some(tab_id);
+                    if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                        if ui.add_enabled(tab.can_go_back(), egui::Button::new(icons::NeonIcons::ARROW_LEFT).small()).clicked() {
+                            needs_fetch = Some(tab.go_back());
+                        }
+                        if ui.add_enabled(tab.can_go_forward(), egui::Button::new(icons::NeonIcons::ARROW_RIGHT).small()).clicked() {
+                            needs_fetch = Some(tab.go_forward());
+                        }
+                        if ui.small_button(icons::NeonIcons::ARROW_CLOCKWISE).clicked() {
+                            needs_fetch = Some(tab.reload());
+                        }
+                        ui.add(egui::Label::new(
+                            egui::RichText::new(&tab.title).size(12.0).color(if is_active { NeonTheme::neon_cyan() } else { NeonTheme::secondary_text() })
+                        ).truncate());
+                    }
+                }
+                SplitPane::DevConsole => {
+                    ui.label(egui::RichText::new(format!("{} Developer console", icons::NeonIcons::TERMINAL)).size(12.0).color(NeonTheme::secondary_text()));
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                close = ui.small_button(icons::NeonIcons::X).on_hover_text("Close this side of the split view").clicked();
+            });
+        });
+        ui.add_space(4.0);
+        
+        match pane {
+            SplitPane::Tab(tab_id) => {
+                if let Some(needs_fetch) = needs_fetch {
+                    let url = self.tabs.get(&tab_id).map(|tab| tab.url.clone()).unwrap_or_default();
+                    if self.active_tab == Some(tab_id) {
+                        self.address_bar.set_url(url.clone());
+                    }
+                    if needs_fetch {
+                        self.fetch_url(tab_id, url);
+                    }
+                }
+                self.show_tab_content(ui, ctx, tab_id);
+            }
+            SplitPane::DevConsole => self.show_dev_console(ui),
+        }
+        
+        if close {
+            self.close_split_pane(pane);
+        }
+    }
+    
+    /// Leave split view by closing one side; the other side's tab stays in front
+    fn close_split_pane(&mut self, pane: SplitPane) {
+        let Some(split) = self.split.take() else { return };
+        let remaining = match (pane, split.right) {
+            (SplitPane::Tab(tab_id), SplitPane::Tab(right)) if tab_id == split.left => right,
+            _ => split.left,
+        };
+        self.active_tab = Some(remaining);
+    }
+    
+    /// Show `tab_id` beside the active tab, or the developer console when None
+    fn open_split(&mut self, tab_id: Option<Uuid>) {
+        let Some(active) = self.active_tab else { return };
+        let right = match tab_id {
+            Some(tab_id) if tab_id != active => SplitPane::Tab(tab_id),
+            Some(_) => return,
+            None => {
+                self.dev_console.set_visible(true);
+                SplitPane::DevConsole
+            }
+        };
+        self.split = Some(SplitView::new(active, right));
+    }
+    
+    /// Show `pip` in the picture-in-picture window, in place of whatever it was showing
+    fn open_pip(&mut self, pip: PictureInPicture) {
+        self.close_pip();
+        if let PipContent::Video { tab, url } = &pip.content {
+            if let Some(page) = self.tabs.get(tab).and_then(|tab| tab.web_page.as_ref()) {
+                page.set_picture_in_picture(Some(url.clone()));
+            }
+        }
+        self.pip = Some(pip);
+    }
+    
+    fn close_pip(&mut self) {
+        if let Some(PipContent::Video { tab, .. }) = self.pip.take().map(|pip| pip.content) {
+            if let Some(page) = self.tabs.get(&tab).and_then(|tab| tab.web_page.as_ref()) {
+                page.set_picture_in_picture(None);
+            }
+        }
+    }
+    
+    /// The picture-in-picture window, closed once the user closes it or its video leaves the page
+    fn show_pip(&mut self, ctx: &egui::Context) {
+        let Some(pip) = self.pip.take() else { return };
+        let title = self.tabs.get(&pip.source_tab()).map(|tab| tab.title.clone()).unwrap_or_default();
+        let open = pip.show(ctx, &title, |ui| match &pip.content {
+            PipContent::Tab(tab_id) => {
+                let ctx = ui.ctx().clone();
+                self.show_tab_content(ui, &ctx, *tab_id);
+                true
+            }
+            PipContent::Video { tab, url } => self.tabs.get(tab)
+                .and_then(|tab| tab.web_page.as_ref())
+                .is_some_and(|page| page.render_picture_in_picture(ui, url)),
+        });
+        self.pip = Some(pip);
+        if !open {
+            self.close_pip();
+        }
+    }
+    
+    /// The developer console, attached to the active tab's script engine
+    fn show_dev_console(&mut self, ui: &mut egui::Ui) {
+        // Get reference to active tab's JS engine
+        let mut no_engine: Option<crate::js::JSEngine> = None;
+        match self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+            Some(active_tab) => {
+                let engine = match active_tab.web_page.as_mut() {
+                    Some(web_page) => &mut web_page.js_engine,
+                    None => &mut no_engine,
+                };
+                if self.dev_console.render(ui, engine, Some(&mut active_tab.locale_overrides), &active_tab.network_log, &active_tab.script_errors) {
+                    if let Some(web_page) = active_tab.web_page.as_mut() {
+                        web_page.set_locale_overrides(&active_tab.locale_overrides);
+                    }
+                }
+            }
+            // No active tab, render with None
+            None => {
+                self.dev_console.render(ui, &mut no_engine, None, &[], &[]);
+            }
+        }
+    }
+    
+    // What the active page's scripts logged goes to the developer console; background tabs keep theirs until shown
+    fn sync_script_console(&mut self) {
+        let engine = self.active_tab
+            .and_then(|id| self.tabs.get(&id))
+            .and_then(|tab| tab.web_page.as_ref())
+            .and_then(|page| page.js_engine.as_ref());
+        if let Some(engine) = engine {
+            self.dev_console.sync_with_js_engine(engine);
+        }
+    }
+    
+    /// Esc stops the active tab while it loads, unless the tab switcher is open and closes on it
+    fn handle_stop_shortcut(&mut self, ctx: &egui::Context, shortcuts: &Shortcuts) {
+        let Some(tab_id) = self.active_tab else { return };
+        let loading = self.tabs.get(&tab_id).is_some_and(|tab| tab.load.is_loading());
+        if loading && !self.tab_switcher.is_open() && ctx.input_mut(|i| shortcuts.consume(i, ShortcutAction::Stop)) {
+            self.stop_tab(tab_id);
+        }
+    }
+    
+    // Stop and switching tabs are handled where they're checked, since they depend on what's showing
+    fn run_shortcut(&mut self, action: ShortcutAction) {
+        match action {
+            ShortcutAction::FocusAddressBar => self.address_bar.focus(),
+            ShortcutAction::NewTab => {
+                self.create_new_tab();
+            }
+            ShortcutAction::ReopenClosedTab => self.reopen_closed(),
+            ShortcutAction::Reload | ShortcutAction::HardReload => {
+                let Some(active_id) = self.active_tab else { return };
+                let Some(active_tab) = self.tabs.get_mut(&active_id) else { return };
+                let needs_fetch = if action == ShortcutAction::HardReload { active_tab.hard_reload() } else { active_tab.reload() };
+                if needs_fetch {
+                    let current_url = active_tab.url.clone();
+                    self.fetch_url(active_id, current_url);
+                }
+            }
+            ShortcutAction::Print => {
+                if let Some(active_tab) = self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+                    active_tab.open_print_preview();
+                }
+            }
+            ShortcutAction::ToggleReader => {
+                if let Some(active_tab) = self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+                    active_tab.toggle_reader();
+                }
+            }
+            ShortcutAction::ToggleDevConsole => self.dev_console.toggle_visibility(),
+            ShortcutAction::Stop | ShortcutAction::NextTab | ShortcutAction::PreviousTab => {}
+        }
+    }
+    
+    /// Ctrl+Tab and Ctrl+Shift+Tab through the tab switcher, Ctrl+1..9 straight to a tab in the strip
+    fn handle_tab_shortcuts(&mut self, ctx: &egui::Context, shortcuts: &Shortcuts) {
+        if let Some(active) = self.active_tab {
+            self.tab_switcher.touch(active);
+        }
+        self.tab_switcher.update_thumbnails(ctx, self.active_tab, self.content_rect);
+        
+        let (backward, forward, number) = ctx.input_mut(|i| {
+            let backward = shortcuts.consume(i, ShortcutAction::PreviousTab);
+            let forward = shortcuts.consume(i, ShortcutAction::NextTab);
+            let number = shortcuts::TAB_NUMBER_KEYS.iter().position(|key| i.consume_key(egui::Modifiers::COMMAND, *key));
+            (backward, forward, number)
+        });
+        if backward || forward {
+            self.tab_switcher.cycle(ctx, self.tab_strip.tabs(), self.active_tab, self.content_rect, backward);
+        }
+        if let Some(tab_id) = number.and_then(|index| self.tab_strip.tabs().get(index)) {
+            self.active_tab = Some(*tab_id);
+        }
+        if let Some(tab_id) = self.tab_switcher.show(ctx, self.tab_strip.tabs(), &self.tabs) {
+            self.active_tab = Some(tab_id);
+        }
+    }
+    
+    fn process_load_events(&mut self) {
+        for tab in self.tabs.values_mut() {
+            for event in tab.load.take_events() {
+                match event {
+                    LoadEvent::Completed { url, elapsed, .. } => {
+                        self.dev_console.info(format!("Loaded {} in {} ms", url, elapsed.as_millis()));
+                    }
+                    LoadEvent::Failed { url, error, .. } => {
+                        self.dev_console.warn(format!("Failed to load {}: {}", url, error));
+                    }
+                    LoadEvent::Cancelled { url, .. } => {
+                        log::info!("Navigation to {} was superseded", url);
+                    }
+                    LoadEvent::Started { .. } | LoadEvent::StateChanged { .. } => {}
+                }
+            }
+        }
+    }
+    
+    /// Toolbar shield: what tracking protection did on the active page, with a switch to turn it off there
+    // The main menu: new tabs, the recently closed tabs and windows, translating the page, and the browser's own pages
+    fn show_main_menu(&mut self, ui: &mut egui::Ui) {
+        let shortcuts = Shortcuts::current();
+        let menu_btn = egui::Button::new(
+            egui::RichText::new(icons::NeonIcons::MENU)
+                .size(13.0)
+                .color(NeonTheme::secondary_text())
+        )
+        .fill(NeonTheme::button_idle())
+        .stroke(Stroke::new(1.0, NeonTheme::border_color()))
+        .rounding(Rounding::same(12.0));
+        
+        egui::menu::menu_custom_button(ui, menu_btn, |ui| {
+            ui.set_min_width(220.0);
+            let item = |action: ShortcutAction| egui::Button::new(action.label()).shortcut_text(shortcuts.describe(action));
+            if ui.add(item(ShortcutAction::NewTab)).clicked() {
+                self.create_new_tab();
+                ui.close_menu();
+            }
+            if ui.add_enabled(!self.closed.is_empty(), item(ShortcutAction::ReopenClosedTab)).clicked() {
+                self.reopen_closed();
+                ui.close_menu();
+            }
+            ui.menu_button("Recently closed", |ui| {
+                ui.set_min_width(260.0);
+                if self.closed.is_empty() {
+                    ui.label(egui::RichText::new("Nothing closed yet").color(NeonTheme::muted_text()));
+                }
+                let mut reopen = None;
+                for (index, closed) in self.closed.iter().enumerate().rev() {
+                    let hover = match closed {
+                        ClosedItem::Tab { tab, .. } => tab.url.clone(),
+                        ClosedItem::Window { tabs, .. } => tabs.iter().map(|tab| tab.title.as_str()).collect::<Vec<_>>().join("\n"),
+                    };
+                    if ui.button(closed.label()).on_hover_text(hover).clicked() {
+                        reopen = Some(index);
+                    }
+                }
+                if let Some(index) = reopen {
+                    let item = self.closed.remove(index);
+                    self.reopen(item);
+                    ui.close_menu();
+                }
+            });
+            ui.separator();
+            let translatable = self.active_tab
+                .and_then(|id| self.tabs.get(&id))
+                .filter(|tab| tab.web_page.is_some() && (tab.url.starts_with("http://") || tab.url.starts_with("https://") || tab.url.starts_with("file://")))
+                .map(BrowserTab::is_translated);
+            let translate_label = if translatable == Some(true) { "Show original" } else { "Translate page" };
+            if ui.add_enabled(translatable.is_some(), egui::Button::new(translate_label)).clicked() {
+                if let Some(tab) = self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+                    if tab.is_translated() {
+                        tab.show_original();
+                    } else {
+                        tab.translate_page();
+                    }
+                }
+                ui.close_menu();
+            }
+            ui.separator();
+            for (label, url) in [
+                ("History", "neon://history"),
+                ("Downloads", "neon://downloads"),
+                ("Bookmarks", "neon://bookmarks"),
+                ("Task manager", "neon://tasks"),
+            ] {
+                if ui.button(label).clicked() {
+                    self.open_url_in_new_tab(url.to_string());
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+    
+    fn show_shield(&mut self, ui: &mut egui::Ui) {
+        let Some(active_id) = self.active_tab else { return };
+        let Some(tab) = self.tabs.get(&active_id) else { return };
+        let Some(host) = url::Url::parse(&tab.url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+            return;
+        };
+        let protected = Preferences::current().tracking.protects(&tab.url);
+        let report = tab.web_page.as_ref()
+            .and_then(|page| page.tracking_protection())
+            .map(TrackingProtection::report)
+            .unwrap_or_default();
+        
+        let blocked = report.trackers_blocked();
+        let text = match blocked {
+            0 => icons::NeonIcons::SHIELD_CHECK.to_string(),
+            count => format!("{} {}", icons::NeonIcons::SHIELD_CHECK, count),
+        };
+        let shield_btn = egui::Button::new(
+            egui::RichText::new(text)
+                .size(13.0)
+                .color(if protected { NeonTheme::neon_cyan() } else { NeonTheme::muted_text() })
+        )
+        .fill(NeonTheme::button_idle())
+        .stroke(Stroke::new(1.0, NeonTheme::border_color()))
+        .rounding(Rounding::same(12.0));
+        let response = ui.add(shield_btn).on_hover_text(match protected {
+            true => format!("Tracking protection: {} blocked on this page", blocked),
+            false => "Tracking protection is off for this site".to_string(),
+        });
+        
+        let popup_id = ui.make_persistent_id("shield_popup");
+        if response.clicked() {
+            ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+        }
+        let mut protect = protected;
+        egui::popup_below_widget(ui, popup_id, &response, egui::PopupCloseBehavior::CloseOnClickOutside, |ui| {
+            ui.set_min_width(280.0);
+            ui.label(egui::RichText::new(&host).strong().color(NeonTheme::primary_text()));
+            ui.checkbox(&mut protect, "Tracking protection on this site");
+            ui.separator();
+            shield_breakdown(ui, &report);
+        });
+        
+        if protect != protected {
+            let url = tab.url.clone();
+            Preferences::update(|prefs| prefs.tracking.set_exception(&url, !protect));
+            // Blocked requests only come back, and blocked ones only go away, with a fresh load
+            if self.tabs.get_mut(&active_id).is_some_and(BrowserTab::reload) {
+                self.fetch_url(active_id, url);
+            }
+        }
+    }
+    
+    /// Reload the pages whose scripts are on when the JavaScript settings now block them, or the other way round
+    fn apply_javascript_settings(&mut self) {
+        let javascript = Preferences::current().javascript.clone();
+        let stale: Vec<(Uuid, String)> = self.tabs.iter()
+            .filter(|(_, tab)| !self.page_router.can_handle(&tab.url))
+            .filter(|(_, tab)| tab.web_page.as_ref().is_some_and(|page| page.scripts_enabled() != javascript.allows(&tab.url)))
+            .map(|(id, tab)| (*id, tab.url.clone()))
+            .collect();
+        for (tab_id, url) in stale {
+            if self.tabs.get_mut(&tab_id).is_some_and(BrowserTab::reload) {
+                self.fetch_url(tab_id, url);
+            }
+        }
+    }
+}
+
+// Trackers blocked and third parties whose cookies were partitioned, per host
+// A new tab that never left the start page isn't worth reopening
+fn is_blank(tab: &BrowserTab) -> bool {
+    tab.history.iter().all(|entry| entry.url.starts_with("about:"))
+}
+
+fn shield_breakdown(ui: &mut egui::Ui, report: &ShieldReport) {
+    let sections = [
+        ("tracker requests blocked", report.trackers_blocked(), &report.blocked),
+        ("third-party cookies partitioned", report.cookies_partitioned(), &report.partitioned),
+    ];
+    for (label, total, hosts) in sections {
+        ui.label(egui::RichText::new(format!("{} {}", total, label)).color(NeonTheme::primary_text()));
+        for (host, count) in hosts {
+            ui.label(egui::RichText::new(format!("    {} ({})", host, count)).size(11.0).color(NeonTheme::secondary_text()));
+        }
+    }
+}
+
+// Act on a page's preconnect, dns-prefetch and prefetch links, except to trackers tracking protection would block
+fn apply_resource_hints(client: &ManualHttpClient, cookies: &Arc<Mutex<CookieManager>>, dom: &DOMNode, page_url: &str) {
+    let tracking = Preferences::current().tracking.protects(page_url).then(|| TrackingProtection::new(page_url)).flatten();
+    let mut prefetches = Vec::new();
+    for hint in resource_loader::resource_hints(dom, page_url) {
+        match hint {
+            ResourceHint::Preconnect(url) | ResourceHint::DnsPrefetch(url) | ResourceHint::Prefetch(url)
+                if tracking.as_ref().is_some_and(|t| t.intercept(&url)) =>
+            {
+                log::debug!("Ignoring a resource hint for tracker {}", url);
+            }
+            ResourceHint::Preconnect(url) => client.preconnect(&url),
+            ResourceHint::DnsPrefetch(url) => client.dns_prefetch(&url),
+            ResourceHint::Prefetch(url) => prefetches.push(url),
+        }
+    }
+    if !prefetches.is_empty() {
+        let context = FetchContext {
+            cookies: Some(cookies.clone()),
+            referrer: Some(page_url.to_string()),
+            tracking,
+            ..FetchContext::default()
+        };
+        Prefetcher::shared().prefetch(client, context, prefetches);
+    }
+}
+
+// Remote debugging calls that look into a single tab
+fn debug_tab_call(tab: &mut BrowserTab, request: &DebugRequest) -> Result<serde_json::Value, String> {
+    match request.method.as_str() {
+        "Runtime.evaluate" => {
+            let expression = request.string_param("expression")?;
+            let engine = tab.web_page.as_mut()
+                .and_then(|page| page.js_engine.as_mut())
+                .ok_or_else(|| "The page has no script engine".to_string())?;
+            engine.execute(expression)
+                .map(|value| serde_json::json!({ "result": { "value": value } }))
+                .map_err(|e| e.to_string())
+        }
+        "DOM.getDocument" => tab.web_page.as_ref()
+            .map(|page| serde_json::json!({ "root": remote_debugging::dom_tree(&page.dom) }))
+            .ok_or_else(|| "The tab has no page".to_string()),
+        "DOM.getOuterHTML" => tab.web_page.as_ref()
+            .map(|page| serde_json::json!({ "outerHTML": page.dom.to_html() }))
+            .ok_or_else(|| "The tab has no page".to_string()),
+        "Network.getRequests" => {
+            let requests = tab.network_log.iter()
+                .map(|entry| serde_json::json!({
+                    "url": entry.url,
+                    "type": entry.kind,
+                    "status": entry.status,
+                    "error": entry.error,
+                    "timing": entry.timing.as_ref().map(|timing| serde_json::json!({
+                        "durationMs": timing.duration().as_secs_f64() * 1000.0,
+                        "bytesSent": timing.bytes_sent,
+                        "bytesReceived": timing.bytes_received,
+                        "connectionReused": timing.connection_reused,
+                        "cache": timing.cache.label(),
+                        "phases": timing.phases.iter().map(|phase| serde_json::json!({
+                            "phase": phase.phase.label(),
+                            "startMs": phase.start.as_secs_f64() * 1000.0,
+                            "endMs": phase.end.as_secs_f64() * 1000.0,
+                        })).collect::<Vec<_>>(),
+                    })),
+                }))
+                .collect::<Vec<_>>();
+            Ok(serde_json::json!({ "requests": requests }))
+        }
+        method => Err(format!("Unknown method {}", method)),
     }
 }
 
 impl eframe::App for NeonSearchApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.private {
+            return;
+        }
+        // Tabs the next launch won't continue can still be reopened from it, all together
+        let mut session = self.session();
+        if Preferences::current().startup != StartupPage::LastSession && session.tabs.iter().any(|tab| !tab.url.starts_with("about:")) {
+            let Session { tabs, groups, .. } = session.clone();
+            session::push_closed(&mut session.closed, ClosedItem::Window { tabs, groups });
+        }
+        if let Err(e) = session.save(&Session::path()) {
+            log::warn!("Could not save the session: {}", e);
+        }
+        if let Ok(mut jar) = self.cookies.lock() {
+            if !Preferences::current().cookies.keep_session_cookies {
+                jar.clear_session_cookies();
+            }
+            if let Err(e) = jar.save(&CookieManager::path()) {
+                log::warn!("Could not save cookies: {}", e);
+            }
+        }
+    }
+    
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Process any incoming network responses
         self.process_network_responses();
+        self.process_load_events();
+        self.process_subresources(ctx);
+        self.sync_script_console();
+        self.process_remote_commands(ctx);
+        self.process_debug_requests(ctx);
+        self.retry_offline_tabs(ctx);
+        if !self.private {
+            crash_reports::record_open_tabs(self.tab_strip.tabs().iter()
+                .filter_map(|id| self.tabs.get(id))
+                .map(|tab| tab.url.clone())
+                .filter(|url| url != "neon://crash")
+                .collect());
+        }
+        self.record_tab_usage();
+        if FontRegistry::shared().apply(ctx) {
+            ctx.request_repaint();
+        }
+        if let Ok(mut themes) = ThemeManager::shared().lock() {
+            themes.sync(ctx, &Preferences::current());
+        }
+        if let Some(mut sync) = SyncEngine::shared().lock().ok().filter(|_| !self.private) {
+            sync.tick(|| self.tab_strip.tabs().iter()
+                .filter_map(|id| self.tabs.get(id))
+                .filter(|tab| tab.url.starts_with("http://") || tab.url.starts_with("https://"))
+                .map(|tab| SyncedTab { url: tab.url.clone(), title: tab.title.clone() })
+                .collect());
+            if sync.is_running() {
+                ctx.request_repaint_after(std::time::Duration::from_millis(500));
+            }
+        }
+        
+        // The mouse's back and forward buttons work anywhere in the window
+        let (back, forward) = ctx.input(|i| (
+            i.pointer.button_pressed(egui::PointerButton::Extra1),
+            i.pointer.button_pressed(egui::PointerButton::Extra2),
+        ));
+        if let Some(tab_id) = self.active_tab.filter(|_| back || forward) {
+            self.go_in_history(tab_id, forward);
+        }
+        
+        // Shortcuts wait while the settings page records a new chord, so it gets the key press instead
+        let shortcuts = if shortcuts::is_recording(ctx) { Shortcuts::none() } else { Shortcuts::current() };
+        self.handle_tab_shortcuts(ctx, &shortcuts);
+        self.handle_stop_shortcut(ctx, &shortcuts);
+        if let Some(active_id) = self.active_tab {
+            if self.tabs.get_mut(&active_id).is_some_and(|tab| tab.load_if_restored()) {
+                let url = self.tabs[&active_id].url.clone();
+                self.fetch_url(active_id, url);
+            }
+        }
         
         // Handle keyboard shortcuts (but not when address bar has focus to avoid input interference)
         let address_bar_has_focus = ctx.memory(|mem| {
             mem.has_focus(egui::Id::new("address_bar_input"))
         });
         
-        if !address_bar_has_focus {
-            ctx.input(|i| {
-                // Cmd+L (or Ctrl+L) to focus address bar
-                if (i.modifiers.mac_cmd || i.modifiers.ctrl) && i.key_pressed(egui::Key::L) {
-                    self.address_bar.focus();
-                }
-                
-                // Cmd+T (or Ctrl+T) to create new tab
-                if (i.modifiers.mac_cmd || i.modifiers.ctrl) && i.key_pressed(egui::Key::T) {
-                    self.create_new_tab();
-                }
-                
-                // Cmd+R (or Ctrl+R) to reload
-                if (i.modifiers.mac_cmd || i.modifiers.ctrl) && i.key_pressed(egui::Key::R) {
-                    if let Some(active_id) = self.active_tab {
-                        if let Some(active_tab) = self.tabs.get_mut(&active_id) {
-                            let needs_fetch = active_tab.reload();
-                            let current_url = active_tab.url.clone();
-                            
-                            if needs_fetch {
-                                self.fetch_url(active_id, current_url);
-                            }
-                        }
-                    }
-                }
-                
-                // F12 to toggle developer console
-                if i.key_pressed(egui::Key::F12) {
-                    self.dev_console.toggle_visibility();
-                }
-            });
-        } else {
-            // Still handle Cmd+L even when focused to allow re-focusing
-            ctx.input(|i| {
-                if (i.modifiers.mac_cmd || i.modifiers.ctrl) && i.key_pressed(egui::Key::L) {
-                    self.address_bar.focus();
-                }
-            });
+        // Focusing the address bar still works from the address bar, to select its text again
+        let pressed: Vec<ShortcutAction> = ctx.input_mut(|i| ShortcutAction::ALL.into_iter()
+            .filter(|action| !address_bar_has_focus || *action == ShortcutAction::FocusAddressBar)
+            .filter(|action| !matches!(action, ShortcutAction::Stop | ShortcutAction::NextTab | ShortcutAction::PreviousTab))
+            .filter(|action| shortcuts.consume(i, *action))
+            .collect());
+        for action in pressed {
+            self.run_shortcut(action);
         }
         
         // Modern top panel with sophisticated design
         egui::TopBottomPanel::top("top_panel")
             .exact_height(80.0)
-            .frame(egui::Frame::none().fill(NeonTheme::DARK_BG))
+            .frame(egui::Frame::none().fill(NeonTheme::dark_bg()))
             .show(ctx, |ui| {
                 let rect = ui.available_rect_before_wrap();
                 
@@ -321,7 +1653,7 @@ impl eframe::App for NeonSearchApp {
                 ui.painter().hline(
                     rect.left()..=rect.right(),
                     rect.bottom(),
-                    egui::Stroke::new(2.0, NeonTheme::NEON_CYAN.gamma_multiply(0.6))
+                    egui::Stroke::new(2.0, NeonTheme::neon_cyan().gamma_multiply(0.6))
                 );
                 
                 ui.allocate_ui_with_layout(
@@ -345,11 +1677,11 @@ impl eframe::App for NeonSearchApp {
                                     let lightning_response = ui.label(
                                         egui::RichText::new("⚡")
                                             .size(32.0)
-                                            .color(NeonTheme::NEON_CYAN)
+                                            .color(NeonTheme::neon_cyan())
                                     );
                                     
                                     // Add glow around the lightning
-                                    NeonTheme::add_glow_effect(ui, lightning_response.rect, NeonTheme::NEON_CYAN, 0.8);
+                                    NeonTheme::add_glow_effect(ui, lightning_response.rect, NeonTheme::neon_cyan(), 0.8);
                                     
                                     ui.add_space(8.0);
                                     
@@ -357,7 +1689,7 @@ impl eframe::App for NeonSearchApp {
                                     ui.label(
                                         egui::RichText::new("NeonSearch")
                                             .size(28.0)
-                                            .color(NeonTheme::PRIMARY_TEXT)
+                                            .color(NeonTheme::primary_text())
                                             .strong()
                                     );
                                 });
@@ -375,16 +1707,24 @@ impl eframe::App for NeonSearchApp {
                                 let nav_action = self.navigation_bar.show(ui, self.active_tab.and_then(|id| self.tabs.get(&id)));
                                 
                                 // Handle navigation actions (simplified)
-                                if let Some(active_id) = self.active_tab {
+                                if let (crate::ui::navigation::NavigationAction::Stop, Some(active_id)) = (&nav_action, self.active_tab) {
+                                    self.stop_tab(active_id);
+                                } else if let Some(active_id) = self.active_tab {
                                     if let Some(active_tab) = self.tabs.get_mut(&active_id) {
+                                        let previous_url = active_tab.url.clone();
                                         let needs_fetch = match nav_action {
                                             crate::ui::navigation::NavigationAction::Back => active_tab.go_back(),
                                             crate::ui::navigation::NavigationAction::Forward => active_tab.go_forward(),
                                             crate::ui::navigation::NavigationAction::Reload => active_tab.reload(),
+                                            crate::ui::navigation::NavigationAction::HardReload => active_tab.hard_reload(),
                                             crate::ui::navigation::NavigationAction::Home => active_tab.navigate_to("about:home".to_string()),
-                                            crate::ui::navigation::NavigationAction::None => false,
+                                            crate::ui::navigation::NavigationAction::Stop | crate::ui::navigation::NavigationAction::None => false,
                                         };
                                         
+                                        // Back and forward within a document change the address without a fetch
+                                        if active_tab.url != previous_url {
+                                            self.address_bar.set_url(active_tab.url.clone());
+                                        }
                                         if needs_fetch {
                                             let current_url = active_tab.url.clone();
                                             self.fetch_url(active_id, current_url);
@@ -397,20 +1737,35 @@ impl eframe::App for NeonSearchApp {
                         ui.add_space(16.0);
                         
                         // Address bar with enhanced styling
-                        let remaining_width = ui.available_width().max(400.0) - 180.0;
+                        let remaining_width = ui.available_width().max(400.0) - 320.0;
                         ui.allocate_ui_with_layout(
                             egui::Vec2::new(remaining_width, ui.available_height()),
                             egui::Layout::left_to_right(egui::Align::Center),
                             |ui| {
                                 // Use existing address bar
-                                if let Some(navigate_url) = self.address_bar.show(ui) {
+                                let cookies = self.cookies.clone();
+                                let site = self.active_tab
+                                    .and_then(|id| self.tabs.get(&id))
+                                    .and_then(|tab| {
+                                        let report = tab.security_report.as_ref()?;
+                                        let cookies = cookies.lock().map_or(0, |jar| jar.cookie_count_for_site(&report.site()));
+                                        Some(SiteInfo { report, tls: tab.tls_info(), cookies })
+                                    });
+                                if let Some(candidate) = self.address_bar.take_prefetch_candidate() {
+                                    let context = FetchContext { cookies: Some(self.cookies.clone()), ..FetchContext::default() };
+                                    Prefetcher::shared().prefetch(&self.manual_client, context, [candidate]);
+                                }
+                                if let Some(navigate_url) = self.address_bar.show(ui, site) {
                                     if let Some(active_id) = self.active_tab {
                                         if let Some(active_tab) = self.tabs.get_mut(&active_id) {
                                             // Normalize URL - add https:// if no protocol is specified
                                             let normalized_url = if !navigate_url.starts_with("http://") && 
                                                                     !navigate_url.starts_with("https://") && 
                                                                     !navigate_url.starts_with("about:") &&
-                                                                    !navigate_url.starts_with("neon://") {
+                                                                    !navigate_url.starts_with("neon://") &&
+                                                                    !local_url::is_local(&navigate_url) &&
+                                                                    !file_url::is_file(&navigate_url) &&
+                                                                    protocol_handlers::external_scheme(&navigate_url).is_none() {
                                                 format!("https://{}", navigate_url)
                                             } else {
                                                 navigate_url
@@ -422,11 +1777,16 @@ impl eframe::App for NeonSearchApp {
                                             
                                             if needs_fetch {
                                                 self.fetch_url(active_id, current_url);
-                                                self.loading_tabs.insert(active_id, std::time::Instant::now());
                                             }
                                         }
                                     }
                                 }
+                                if self.address_bar.take_javascript_change() {
+                                    self.apply_javascript_settings();
+                                }
+                                if self.address_bar.take_site_settings_change() {
+                                    self.run_shortcut(ShortcutAction::Reload);
+                                }
                             },
                         );
                         
@@ -436,28 +1796,84 @@ impl eframe::App for NeonSearchApp {
                         ui.horizontal(|ui| {
                             ui.spacing_mut().item_spacing.x = 12.0;
                             
+                            self.show_shield(ui);
+                            
+                            // Reader mode button, lit while the active tab is in reader mode
+                            let reading = self.active_tab
+                                .and_then(|id| self.tabs.get(&id))
+                                .is_some_and(|tab| tab.reader.is_open());
+                            let reader_btn = egui::Button::new(
+                                egui::RichText::new("📖 Reader")
+                                    .size(13.0)
+                                    .color(if reading { NeonTheme::primary_text() } else { NeonTheme::secondary_text() })
+                            )
+                            .fill(if reading { NeonTheme::button_primary_color() } else { NeonTheme::button_idle() })
+                            .stroke(Stroke::new(1.0, if reading { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
+                            .rounding(Rounding::same(12.0));
+                            
+                            if ui.add(reader_btn).on_hover_text("Reader mode (F9)").clicked() {
+                                if let Some(active_tab) = self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+                                    active_tab.toggle_reader();
+                                }
+                            }
+                            
+                            // Developer console, badged with the page's script errors so they don't go unnoticed
+                            let console_open = self.dev_console.is_visible();
+                            let script_errors = self.active_tab
+                                .and_then(|id| self.tabs.get(&id))
+                                .map_or(0, |tab| tab.script_errors.iter().filter(|error| error.severity == ErrorSeverity::Error).count());
+                            let console_btn = egui::Button::new(
+                                egui::RichText::new(icons::NeonIcons::TERMINAL)
+                                    .size(13.0)
+                                    .color(if console_open { NeonTheme::primary_text() } else { NeonTheme::secondary_text() })
+                            )
+                            .fill(if console_open { NeonTheme::button_primary_color() } else { NeonTheme::button_idle() })
+                            .stroke(Stroke::new(1.0, if console_open { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
+                            .rounding(Rounding::same(12.0));
+                            let console_response = ui.add(console_btn).on_hover_text(match script_errors {
+                                0 => "Developer console (F12)".to_string(),
+                                1 => "Developer console (F12): 1 script error on this page".to_string(),
+                                n => format!("Developer console (F12): {} script errors on this page", n),
+                            });
+                            if script_errors > 0 {
+                                let center = console_response.rect.right_top() + egui::vec2(-3.0, 3.0);
+                                ui.painter().circle_filled(center, 7.0, NeonTheme::error_color());
+                                ui.painter().text(
+                                    center,
+                                    egui::Align2::CENTER_CENTER,
+                                    if script_errors > 99 { "99+".to_string() } else { script_errors.to_string() },
+                                    egui::FontId::proportional(9.0),
+                                    Color32::WHITE,
+                                );
+                            }
+                            if console_response.clicked() {
+                                self.dev_console.toggle_visibility();
+                            }
+                            
                             // Bookmarks button with modern styling
                             let bookmarks_btn = egui::Button::new(
                                 egui::RichText::new(format!("{} Bookmarks", icons::NeonIcons::BOOKMARKS))
                                     .size(13.0)
-                                    .color(if self.show_bookmarks { NeonTheme::PRIMARY_TEXT } else { NeonTheme::SECONDARY_TEXT })
+                                    .color(if self.show_bookmarks { NeonTheme::primary_text() } else { NeonTheme::secondary_text() })
                             )
-                            .fill(if self.show_bookmarks { NeonTheme::BUTTON_PRIMARY } else { NeonTheme::BUTTON_IDLE })
-                            .stroke(Stroke::new(1.0, if self.show_bookmarks { NeonTheme::NEON_CYAN } else { NeonTheme::BORDER_COLOR }))
+                            .fill(if self.show_bookmarks { NeonTheme::button_primary_color() } else { NeonTheme::button_idle() })
+                            .stroke(Stroke::new(1.0, if self.show_bookmarks { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
                             .rounding(Rounding::same(12.0));
                             
                             if ui.add(bookmarks_btn).on_hover_text("Manage bookmarks").clicked() {
                                 self.show_bookmarks = !self.show_bookmarks;
                             }
                             
+                            self.show_main_menu(ui);
+                            
                             // Settings button
                             let settings_btn = egui::Button::new(
                                 egui::RichText::new(format!("{} Settings", icons::NeonIcons::GEAR))
                                     .size(13.0)
-                                    .color(if self.show_settings { NeonTheme::PRIMARY_TEXT } else { NeonTheme::SECONDARY_TEXT })
+                                    .color(if self.show_settings { NeonTheme::primary_text() } else { NeonTheme::secondary_text() })
                             )
-                            .fill(if self.show_settings { NeonTheme::BUTTON_PRIMARY } else { NeonTheme::BUTTON_IDLE })
-                            .stroke(Stroke::new(1.0, if self.show_settings { NeonTheme::NEON_CYAN } else { NeonTheme::BORDER_COLOR }))
+                            .fill(if self.show_settings { NeonTheme::button_primary_color() } else { NeonTheme::button_idle() })
+                            .stroke(Stroke::new(1.0, if self.show_settings { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
                             .rounding(Rounding::same(12.0));
                             
                             if ui.add(settings_btn).on_hover_text("Browser settings").clicked() {
@@ -468,9 +1884,9 @@ impl eframe::App for NeonSearchApp {
                             let new_tab_btn = egui::Button::new(
                                 egui::RichText::new(format!("{} New", icons::NeonIcons::PLUS))
                                     .size(13.0)
-                                    .color(NeonTheme::PRIMARY_TEXT)
+                                    .color(NeonTheme::primary_text())
                             )
-                            .fill(NeonTheme::SUCCESS_COLOR)
+                            .fill(NeonTheme::success_color())
                             .stroke(Stroke::new(0.0, Color32::TRANSPARENT))
                             .rounding(Rounding::same(12.0));
                             
@@ -487,11 +1903,33 @@ impl eframe::App for NeonSearchApp {
         // Handle navigation and address bar logic (simplified for now)
         // TODO: Re-implement navigation action handling
         
-        // Modern tab bar with enhanced styling
+        // Modern tab bar with enhanced styling, or the tab sidebar in its place
+        let vertical_tabs = Preferences::current().vertical_tabs;
+        egui::SidePanel::left("tab_sidebar")
+            .resizable(true)
+            .default_width(240.0)
+            .width_range(160.0..=420.0)
+            .frame(egui::Frame::none().fill(NeonTheme::panel_bg()).inner_margin(egui::Margin::symmetric(8.0, 0.0)))
+            .show_animated(ctx, vertical_tabs, |ui| {
+                for action in self.tab_sidebar.show(ui, &mut self.tab_strip, &self.tabs, self.active_tab) {
+                    match action {
+                        SidebarAction::Activate(tab_id) => self.active_tab = Some(tab_id),
+                        SidebarAction::Close(tab_id) => self.close_tab(tab_id),
+                        SidebarAction::CloseGroup(group) => self.close_group(group),
+                        SidebarAction::OpenInSplit(tab_id) => self.open_split(Some(tab_id)),
+                        SidebarAction::PictureInPicture(tab_id) => self.open_pip(PictureInPicture::tab(tab_id)),
+                        SidebarAction::ToggleMute(tab_id) => {
+                            if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                                tab.toggle_audio_muted();
+                            }
+                        }
+                    }
+                }
+            });
         egui::TopBottomPanel::top("tab_panel")
             .exact_height(50.0)
-            .frame(egui::Frame::none().fill(NeonTheme::PANEL_BG))
-            .show(ctx, |ui| {
+            .frame(egui::Frame::none().fill(NeonTheme::panel_bg()))
+            .show_animated(ctx, !vertical_tabs, |ui| {
                 ui.horizontal(|ui| {
                     ui.add_space(8.0);
                     
@@ -501,41 +1939,101 @@ impl eframe::App for NeonSearchApp {
                             ui.horizontal(|ui| {
                                 ui.spacing_mut().item_spacing.x = 4.0;
                                 let mut tabs_to_close = Vec::new();
+                                let mut tabs_to_mute = Vec::new();
+                                // Group changes, applied after the strip has been drawn
+                                let mut strip_edits = Vec::new();
+                                let mut previous_group = None;
+                                // Tab to show beside the active one, or None for the developer console
+                                let mut open_split = None;
+                                let mut pip_tab = None;
                                 
-                                for (&tab_id, tab) in &self.tabs {
+                                for (tab_id, tab) in self.tab_strip.tabs().iter().filter_map(|id| Some((*id, self.tabs.get(id)?))) {
                                     let is_active = self.active_tab == Some(tab_id);
-                                    let is_loading = self.loading_tabs.contains_key(&tab_id);
+                                    let is_loading = tab.load.is_loading();
+                                    
+                                    // A group starts with a label in its colour; collapsed groups show nothing else
+                                    let group = self.tab_strip.group_of(tab_id).and_then(|id| self.tab_strip.group(id));
+                                    if let Some(group) = group.filter(|group| previous_group != Some(group.id)) {
+                                        ui.add_space(4.0);
+                                        if self.renaming_group == Some(group.id) {
+                                            let mut name = group.name.clone();
+                                            let response = ui.add(egui::TextEdit::singleline(&mut name).desired_width(100.0));
+                                            if !response.has_focus() {
+                                                response.request_focus();
+                                            }
+                                            if response.changed() {
+                                                strip_edits.push(StripEdit::Rename(group.id, name));
+                                            }
+                                            if response.lost_focus() {
+                                                strip_edits.push(StripEdit::FinishRename);
+                                            }
+                                        } else {
+                                            let label = match group.collapsed {
+                                                true => format!("{} {}", group.name, self.tab_strip.tabs_in(group.id).len()),
+                                                false => group.name.clone(),
+                                            };
+                                            let chip = egui::Button::new(egui::RichText::new(label).size(12.0).strong().color(NeonTheme::dark_bg()))
+                                                .fill(tab_strip::group_color(group.color))
+                                                .rounding(egui::Rounding::same(10.0));
+                                            let mut edited = group.clone();
+                                            let response = ui.add(chip).on_hover_text("Click to collapse or expand; right-click for more");
+                                            if response.clicked() {
+                                                edited.collapsed = !edited.collapsed;
+                                            }
+                                            response.context_menu(|ui| {
+                                                if let Some(command) = tab_strip::group_menu(ui, &mut edited) {
+                                                    strip_edits.push(StripEdit::Command(group.id, command));
+                                                }
+                                            });
+                                            if edited != *group {
+                                                strip_edits.push(StripEdit::Update(edited));
+                                            }
+                                        }
+                                    }
+                                    previous_group = group.map(|group| group.id);
+                                    if group.is_some_and(|group| group.collapsed) {
+                                        continue;
+                                    }
                                     
                                     // Create modern tab styling
                                     let tab_bg = if is_active {
-                                        NeonTheme::NEON_PURPLE
+                                        NeonTheme::neon_purple()
                                     } else {
-                                        NeonTheme::ELEVATED_BG
+                                        NeonTheme::elevated_bg()
                                     };
                                     
                                     let tab_text_color = if is_active {
                                         egui::Color32::WHITE
                                     } else {
-                                        NeonTheme::PRIMARY_TEXT
+                                        NeonTheme::primary_text()
                                     };
                                     
-                                    egui::Frame::none()
+                                    let tab_frame = egui::Frame::none()
                                         .fill(tab_bg)
                                         .rounding(egui::Rounding::same(8.0))
-                                        .stroke(egui::Stroke::new(1.0, if is_active { NeonTheme::NEON_CYAN } else { NeonTheme::BORDER_COLOR }))
+                                        .stroke(egui::Stroke::new(1.0, if is_active { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
                                         .inner_margin(egui::Margin::symmetric(12.0, 8.0))
                                         .show(ui, |ui| {
                                             ui.horizontal(|ui| {
                                                 ui.spacing_mut().item_spacing.x = 8.0;
                                                 
-                                                // Favicon placeholder
-                                                let favicon_text = if is_loading {
-                                                    let phase_anim = (ctx.input(|i| i.time) * 6.0) as i32 % 4;
-                                                    match phase_anim { 0 => "⠋", 1 => "⠙", 2 => "⠹", _ => "⠸" }
+                                                // Favicon, or a spinner while loading
+                                                let favicon = if is_loading {
+                                                    None
                                                 } else {
-                                                    icons::NeonIcons::GLOBE_SIMPLE
+                                                    self.site_metadata.favicon_texture(ctx, &tab.url)
                                                 };
-                                                ui.label(egui::RichText::new(favicon_text).color(tab_text_color));
+                                                if let Some(texture) = favicon {
+                                                    ui.add(egui::Image::new(&texture).fit_to_exact_size(egui::vec2(16.0, 16.0)));
+                                                } else {
+                                                    let favicon_text = if is_loading {
+                                                        let phase_anim = (ctx.input(|i| i.time) * 6.0) as i32 % 4;
+                                                        match phase_anim { 0 => "⠋", 1 => "⠙", 2 => "⠹", _ => "⠸" }
+                                                    } else {
+                                                        icons::NeonIcons::GLOBE_SIMPLE
+                                                    };
+                                                    ui.label(egui::RichText::new(favicon_text).color(tab_text_color));
+                                                }
                                                 
                                                 // Tab title with loading indicator
                                                 let mut title = tab.title.clone();
@@ -544,19 +2042,7 @@ impl eframe::App for NeonSearchApp {
                                                 }
                                                 
                                                 if is_loading {
-                                                    let phase_label = self.tab_phases.get(&tab_id)
-                                                        .and_then(|v| v.last())
-                                                        .map(|p| match p {
-                                                            FetchPhase::Resolving => "Resolving DNS",
-                                                            FetchPhase::Connecting => "Connecting",
-                                                            FetchPhase::TlsHandshake => "Securing connection",
-                                                            FetchPhase::SendingRequest => "Sending request",
-                                                            FetchPhase::ReadingHeaders => "Reading headers",
-                                                            FetchPhase::ReadingBody => "Loading content",
-                                                            FetchPhase::Redirecting => "Following redirect",
-                                                            FetchPhase::Completed => "Complete"
-                                                        }).unwrap_or("Loading");
-                                                    title = format!("{} • {}", title, phase_label);
+                                                    title = format!("{} • {}", title, tab.load.state().label());
                                                 }
                                                 
                                                 let tab_response = ui.selectable_label(
@@ -569,12 +2055,69 @@ impl eframe::App for NeonSearchApp {
                                                 if tab_response.clicked() {
                                                     self.active_tab = Some(tab_id);
                                                 }
+                                                if tab_response.middle_clicked() {
+                                                    tabs_to_close.push(tab_id);
+                                                }
+                                                
+                                                tab_response.context_menu(|ui| {
+                                                    if !is_active && ui.button("Open in split view").clicked() {
+                                                        open_split = Some(Some(tab_id));
+                                                        ui.close_menu();
+                                                    }
+                                                    if is_active && ui.button("Show developer console beside page").clicked() {
+                                                        open_split = Some(None);
+                                                        ui.close_menu();
+                                                    }
+                                                    if ui.button("Picture-in-picture").clicked() {
+                                                        pip_tab = Some(tab_id);
+                                                        ui.close_menu();
+                                                    }
+                                                    ui.menu_button("Add to group", |ui| {
+                                                        for group in self.tab_strip.groups() {
+                                                            if ui.button(&group.name).clicked() {
+                                                                strip_edits.push(StripEdit::MoveTab(tab_id, Some(group.id)));
+                                                                ui.close_menu();
+                                                            }
+                                                        }
+                                                        if ui.button(format!("{} New group", icons::NeonIcons::PLUS)).clicked() {
+                                                            strip_edits.push(StripEdit::NewGroupWith(tab_id));
+                                                            ui.close_menu();
+                                                        }
+                                                    });
+                                                    if self.tab_strip.group_of(tab_id).is_some() && ui.button("Remove from group").clicked() {
+                                                        strip_edits.push(StripEdit::MoveTab(tab_id, None));
+                                                        ui.close_menu();
+                                                    }
+                                                    if tab.url.starts_with("http") {
+                                                        let mut clear = Preferences::current().cookies.clear_on_close_host(&tab.url).is_some();
+                                                        if ui.checkbox(&mut clear, "Clear cookies when this site's last tab closes").changed() {
+                                                            let url = tab.url.clone();
+                                                            Preferences::update(|prefs| prefs.cookies.set_clear_on_close(&url, clear));
+                                                            ui.close_menu();
+                                                        }
+                                                    }
+                                                });
+                                                
+                                                // Audio indicator, which mutes the whole tab when clicked
+                                                if tab.is_playing_audio() || tab.audio_muted {
+                                                    let (icon, hint) = if tab.audio_muted {
+                                                        (icons::NeonIcons::SPEAKER_MUTED, "Unmute tab")
+                                                    } else {
+                                                        (icons::NeonIcons::SPEAKER, "Mute tab")
+                                                    };
+                                                    let mute_btn = egui::Button::new(egui::RichText::new(icon).size(12.0).color(tab_text_color))
+                                                        .fill(egui::Color32::TRANSPARENT)
+                                                        .stroke(egui::Stroke::NONE);
+                                                    if ui.add(mute_btn).on_hover_text(hint).clicked() {
+                                                        tabs_to_mute.push(tab_id);
+                                                    }
+                                                }
                                                 
                                                 // Close button with hover effect
                                                 let close_btn = egui::Button::new(
                                                     egui::RichText::new(icons::NeonIcons::X)
                                                         .size(12.0)
-                                                        .color(if is_active { egui::Color32::WHITE } else { NeonTheme::MUTED_TEXT })
+                                                        .color(if is_active { egui::Color32::WHITE } else { NeonTheme::muted_text() })
                                                 )
                                                 .fill(egui::Color32::TRANSPARENT)
                                                 .stroke(egui::Stroke::NONE);
@@ -587,10 +2130,30 @@ impl eframe::App for NeonSearchApp {
                                                 }
                                             });
                                         });
+                                    if let Some(group) = group {
+                                        let rect = tab_frame.response.rect;
+                                        ui.painter().hline(rect.x_range().shrink(6.0), rect.bottom() + 2.0, egui::Stroke::new(3.0, tab_strip::group_color(group.color)));
+                                    }
                                     
                                     ui.add_space(2.0);
                                 }
                                 
+                                for tab_id in tabs_to_mute {
+                                    if let Some(tab) = self.tabs.get_mut(&tab_id) {
+                                        tab.toggle_audio_muted();
+                                    }
+                                }
+                                
+                                for edit in strip_edits {
+                                    self.apply_strip_edit(edit);
+                                }
+                                if let Some(tab_id) = open_split {
+                                    self.open_split(tab_id);
+                                }
+                                if let Some(tab_id) = pip_tab {
+                                    self.open_pip(PictureInPicture::tab(tab_id));
+                                }
+                                
                                 // Close tabs after iteration to avoid borrow checker issues
                                 for tab_id in tabs_to_close {
                                     self.close_tab(tab_id);
@@ -604,53 +2167,18 @@ impl eframe::App for NeonSearchApp {
         egui::CentralPanel::default()
             .frame(
                 egui::Frame::none()
-                    .fill(NeonTheme::DARK_BG)
+                    .fill(NeonTheme::dark_bg())
                     .inner_margin(egui::Margin::symmetric(16.0, 12.0))
             )
             .show(ctx, |ui| {
+                self.content_rect = self.active_tab.map(|_| ui.available_rect_before_wrap());
                 if let Some(active_id) = self.active_tab {
-                    let mut needs_fetch = false;
-                    let mut current_url = String::new();
-                    
-                    if let Some(active_tab) = self.tabs.get_mut(&active_id) {
-                        current_url = active_tab.url.clone();
-                        
-                        // Check if this is a custom page
-                        if self.page_router.can_handle(&current_url) {
-                            // Render custom page directly
-                            egui::Frame::none()
-                                .fill(NeonTheme::CARD_BG)
-                                .rounding(egui::Rounding::same(12.0))
-                                .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
-                                .inner_margin(egui::Margin::same(16.0))
-                                .show(ui, |ui| {
-                                    egui::ScrollArea::vertical()
-                                        .auto_shrink([false; 2])
-                                        .show(ui, |ui| {
-                                            self.page_router.render_page(&current_url, ui, ctx);
-                                        });
-                                });
-                            
-                            // Update tab title if needed
-                            if let Some(page_title) = self.page_router.get_page_title(&current_url) {
-                                active_tab.title = page_title;
-                            }
-                        } else {
-                            // Render normal web page
-                            egui::Frame::none()
-                                .fill(NeonTheme::CARD_BG)
-                                .rounding(egui::Rounding::same(12.0))
-                                .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
-                                .inner_margin(egui::Margin::same(16.0))
-                                .show(ui, |ui| {
-                                    needs_fetch = active_tab.show(ui);
-                                });
+                    match self.split.as_mut() {
+                        Some(split) => {
+                            split.follow_active(active_id);
+                            self.show_split(ui, ctx);
                         }
-                    }
-                    
-                    if needs_fetch {
-                        self.fetch_url(active_id, current_url);
-                        self.loading_tabs.insert(active_id, std::time::Instant::now());
+                        None => self.show_tab_content(ui, ctx, active_id),
                     }
                 } else {
                     // No tab selected - show welcome screen
@@ -659,53 +2187,40 @@ impl eframe::App for NeonSearchApp {
                             ui.label(
                                 egui::RichText::new("⚡ Welcome to NeonSearch")
                                     .size(32.0)
-                                    .color(NeonTheme::NEON_CYAN)
+                                    .color(NeonTheme::neon_cyan())
                                     .strong()
                             );
                             ui.add_space(16.0);
                             ui.label(
                                 egui::RichText::new("Press Cmd+T to open a new tab")
                                     .size(16.0)
-                                    .color(NeonTheme::SECONDARY_TEXT)
+                                    .color(NeonTheme::secondary_text())
                             );
                         });
                     });
                 }
             });
         
-        // Developer Console (F12 to toggle)
-        if self.dev_console.is_visible() {
+        self.show_pip(ctx);
+        
+        // Print preview belongs to the active tab
+        if let Some(active_tab) = self.active_tab.and_then(|id| self.tabs.get_mut(&id)) {
+            active_tab.print_preview.show(ctx);
+        }
+        
+        // Developer Console (F12 to toggle), unless it's already showing in split view
+        let console_in_split = self.split.as_ref().is_some_and(|split| split.right == SplitPane::DevConsole);
+        if self.dev_console.is_visible() && !console_in_split {
             egui::TopBottomPanel::bottom("dev_console_panel")
                 .resizable(true)
                 .default_height(300.0)
                 .frame(
                     egui::Frame::none()
-                        .fill(NeonTheme::DARKER_BG)
-                        .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
+                        .fill(NeonTheme::darker_bg())
+                        .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
                         .inner_margin(egui::Margin::same(8.0))
                 )
-                .show(ctx, |ui| {
-                    // Get reference to active tab's JS engine
-                    if let Some(active_id) = self.active_tab {
-                        if let Some(active_tab) = self.tabs.get_mut(&active_id) {
-                            if let Some(ref mut web_page) = active_tab.web_page {
-                                self.dev_console.render(ui, &mut web_page.js_engine);
-                            } else {
-                                // No webpage loaded, render with None
-                                let mut no_engine: Option<crate::js::JSEngine> = None;
-                                self.dev_console.render(ui, &mut no_engine);
-                            }
-                        } else {
-                            // No active tab, render with None
-                            let mut no_engine: Option<crate::js::JSEngine> = None;
-                            self.dev_console.render(ui, &mut no_engine);
-                        }
-                    } else {
-                        // No active tab, render with None
-                        let mut no_engine: Option<crate::js::JSEngine> = None;
-                        self.dev_console.render(ui, &mut no_engine);
-                    }
-                });
+                .show(ctx, |ui| self.show_dev_console(ui));
         }
         
         // Side panels with enhanced styling
@@ -715,8 +2230,8 @@ impl eframe::App for NeonSearchApp {
                 .default_width(280.0)
                 .frame(
                     egui::Frame::none()
-                        .fill(NeonTheme::DARKER_BG)
-                        .stroke(egui::Stroke::new(1.0, NeonTheme::BORDER_COLOR))
+                        .fill(NeonTheme::darker_bg())
+                        .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
                         .inner_margin(egui::Margin::same(12.0))
                 )
                 .show(ctx, |ui| {
@@ -725,13 +2240,13 @@ impl eframe::App for NeonSearchApp {
                         ui.label(
                             egui::RichText::new(format!("{} Bookmarks", icons::NeonIcons::BOOKMARKS))
                                 .size(18.0)
-                                .color(NeonTheme::NEON_CYAN)
+                                .color(NeonTheme::neon_cyan())
                                 .strong()
                         );
                         
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             let close_btn = egui::Button::new(
-                                egui::RichText::new(icons::NeonIcons::X).color(NeonTheme::MUTED_TEXT)
+                                egui::RichText::new(icons::NeonIcons::X).color(NeonTheme::muted_text())
                             )
                             .fill(egui::Color32::TRANSPARENT)
                             .stroke(egui::Stroke::NONE);
@@ -773,7 +2288,7 @@ impl eframe::App for NeonSearchApp {
                     
                     ui.label(
                         egui::RichText::new(format!("{} Settings panel coming soon!", NeonIcons::WRENCH))
-                            .color(NeonTheme::WARNING_COLOR)
+                            .color(NeonTheme::warning_color())
                     );
                     ui.label("This will include:");
                     ui.label("• Theme customization");
@@ -0,0 +1,148 @@
+// Going back and forward with the mouse alone: rocker gestures, which hold one button and click the other, and
+// drag gestures, which move the pointer sideways with the right button held
+use eframe::egui::{self, Pos2};
+use crate::storage::preferences::MouseSettings;
+
+// How far sideways a drag has to go to count, and how much more sideways than up or down
+const MIN_DRAG: f32 = 80.0;
+const DRAG_SLOPE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Back,
+    Forward,
+}
+
+/// The pointer over the page in one frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PointerSample {
+    pub pos: Option<Pos2>,
+    pub primary_down: bool,
+    pub secondary_down: bool,
+    pub primary_pressed: bool,
+    pub secondary_pressed: bool,
+}
+
+impl PointerSample {
+    pub fn read(input: &egui::InputState) -> Self {
+        Self {
+            pos: input.pointer.hover_pos(),
+            primary_down: input.pointer.primary_down(),
+            secondary_down: input.pointer.secondary_down(),
+            primary_pressed: input.pointer.button_pressed(egui::PointerButton::Primary),
+            secondary_pressed: input.pointer.button_pressed(egui::PointerButton::Secondary),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GestureTracker {
+    // Where the right button went down, while a drag gesture may be under way
+    drag_start: Option<Pos2>,
+    // Set once a gesture ran, until both buttons are up, so the clicks that made it don't reach the page
+    swallowing: bool,
+    swallow_clicks: bool,
+}
+
+impl GestureTracker {
+    /// Follow the pointer for a frame; returns the gesture it completed, if any
+    pub fn update(&mut self, settings: &MouseSettings, sample: PointerSample) -> Option<Gesture> {
+        // Buttons come up in the frame their clicks are reported in, so those are still swallowed
+        self.swallow_clicks = self.swallowing;
+        if !sample.primary_down && !sample.secondary_down {
+            self.swallowing = false;
+        }
+
+        let mut gesture = None;
+        if settings.rocker_gestures {
+            if sample.secondary_down && sample.primary_pressed && !sample.secondary_pressed {
+                gesture = Some(Gesture::Back);
+            } else if sample.primary_down && sample.secondary_pressed && !sample.primary_pressed {
+                gesture = Some(Gesture::Forward);
+            }
+        }
+
+        if !settings.drag_gestures || gesture.is_some() || sample.primary_down {
+            self.drag_start = None;
+        } else if sample.secondary_pressed {
+            self.drag_start = sample.pos;
+        } else if !sample.secondary_down {
+            let start = self.drag_start.take();
+            if let (Some(start), Some(end)) = (start, sample.pos) {
+                let delta = end - start;
+                if delta.x.abs() >= MIN_DRAG && delta.x.abs() >= delta.y.abs() * DRAG_SLOPE {
+                    gesture = Some(if delta.x < 0.0 { Gesture::Back } else { Gesture::Forward });
+                }
+            }
+        }
+
+        if gesture.is_some() {
+            self.swallowing = sample.primary_down || sample.secondary_down;
+            self.swallow_clicks = true;
+        }
+        gesture
+    }
+
+    /// Whether this frame's clicks were part of a gesture and shouldn't act on the page
+    pub fn swallows_clicks(&self) -> bool {
+        self.swallow_clicks
+    }
+
+    /// Where a drag gesture under way started
+    pub fn drag_start(&self) -> Option<Pos2> {
+        self.drag_start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(x: f32, primary: (bool, bool), secondary: (bool, bool)) -> PointerSample {
+        PointerSample {
+            pos: Some(Pos2::new(x, 100.0)),
+            primary_down: primary.0,
+            primary_pressed: primary.1,
+            secondary_down: secondary.0,
+            secondary_pressed: secondary.1,
+        }
+    }
+
+    #[test]
+    fn test_rocker_and_drag_gestures() {
+        let both = MouseSettings { rocker_gestures: true, drag_gestures: true };
+        let mut tracker = GestureTracker::default();
+        const UP: (bool, bool) = (false, false);
+        const HELD: (bool, bool) = (true, false);
+        const PRESSED: (bool, bool) = (true, true);
+
+        // Hold right, click left: back, and neither click reaches the page
+        assert_eq!(tracker.update(&both, sample(300.0, UP, PRESSED)), None);
+        assert_eq!(tracker.update(&both, sample(300.0, PRESSED, HELD)), Some(Gesture::Back));
+        assert_eq!(tracker.update(&both, sample(300.0, UP, HELD)), None);
+        assert!(tracker.swallows_clicks());
+        assert_eq!(tracker.update(&both, sample(300.0, UP, UP)), None);
+        assert!(tracker.swallows_clicks());
+        assert_eq!(tracker.update(&both, sample(300.0, UP, UP)), None);
+        assert!(!tracker.swallows_clicks());
+
+        // Hold left, click right: forward
+        tracker.update(&both, sample(300.0, PRESSED, UP));
+        assert_eq!(tracker.update(&both, sample(300.0, HELD, PRESSED)), Some(Gesture::Forward));
+        tracker.update(&both, sample(300.0, UP, UP));
+
+        // Dragging left with the right button held: back, once the button comes up
+        tracker.update(&both, sample(300.0, UP, PRESSED));
+        assert_eq!(tracker.update(&both, sample(250.0, UP, HELD)), None);
+        assert_eq!(tracker.drag_start(), Some(Pos2::new(300.0, 100.0)));
+        assert_eq!(tracker.update(&both, sample(200.0, UP, UP)), Some(Gesture::Back));
+
+        // Too short a drag, or gestures turned off, does nothing
+        tracker.update(&both, sample(300.0, UP, PRESSED));
+        assert_eq!(tracker.update(&both, sample(340.0, UP, UP)), None);
+        let off = MouseSettings::default();
+        tracker.update(&off, sample(300.0, UP, PRESSED));
+        assert_eq!(tracker.update(&off, sample(300.0, PRESSED, HELD)), None);
+        assert_eq!(tracker.update(&off, sample(500.0, UP, UP)), None);
+    }
+}
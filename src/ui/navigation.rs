@@ -19,7 +19,7 @@ impl NavigationBar {
                 egui::Button::new(
                     egui::RichText::new(NeonIcons::ARROW_LEFT)
                         .size(16.0)
-                        .color(if can_go_back { NeonTheme::PRIMARY_TEXT } else { NeonTheme::MUTED_TEXT })
+                        .color(if can_go_back { NeonTheme::primary_text() } else { NeonTheme::muted_text() })
                 )
             );
             if back_button.clicked() {
@@ -34,7 +34,7 @@ impl NavigationBar {
                 egui::Button::new(
                     egui::RichText::new(NeonIcons::ARROW_RIGHT)
                         .size(16.0)
-                        .color(if can_go_forward { NeonTheme::PRIMARY_TEXT } else { NeonTheme::MUTED_TEXT })
+                        .color(if can_go_forward { NeonTheme::primary_text() } else { NeonTheme::muted_text() })
                 )
             );
             if forward_button.clicked() {
@@ -42,22 +42,35 @@ impl NavigationBar {
             }
             forward_button.on_hover_text("Go forward");
             
-            // Refresh button
-            let refresh_button = ui.button(
-                egui::RichText::new(NeonIcons::ARROW_CLOCKWISE)
-                    .size(16.0)
-                    .color(NeonTheme::PRIMARY_TEXT)
-            );
-            if refresh_button.clicked() {
-                action = NavigationAction::Reload;
+            // Refresh button, which stops the page instead while it loads
+            let loading = current_tab.is_some_and(|tab| tab.load.is_loading());
+            if loading {
+                let stop_button = ui.button(
+                    egui::RichText::new(NeonIcons::X)
+                        .size(16.0)
+                        .color(NeonTheme::primary_text())
+                );
+                if stop_button.clicked() {
+                    action = NavigationAction::Stop;
+                }
+                stop_button.on_hover_text("Stop loading (Esc)");
+            } else {
+                let refresh_button = ui.button(
+                    egui::RichText::new(NeonIcons::ARROW_CLOCKWISE)
+                        .size(16.0)
+                        .color(NeonTheme::primary_text())
+                );
+                if refresh_button.clicked() {
+                    action = if ui.input(|i| i.modifiers.shift) { NavigationAction::HardReload } else { NavigationAction::Reload };
+                }
+                refresh_button.on_hover_text("Reload (Shift+click or Ctrl+Shift+R to bypass the cache)");
             }
-            refresh_button.on_hover_text("Reload");
             
             // Home button
             let home_button = ui.button(
                 egui::RichText::new(NeonIcons::HOUSE)
                     .size(16.0)
-                    .color(NeonTheme::NEON_BLUE)
+                    .color(NeonTheme::neon_blue())
             );
             if home_button.clicked() {
                 action = NavigationAction::Home;
@@ -74,5 +87,7 @@ pub enum NavigationAction {
     Back,
     Forward,
     Reload,
+    HardReload,
+    Stop,
     Home,
 }
\ No newline at end of file
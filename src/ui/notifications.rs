@@ -0,0 +1,94 @@
+// Web notifications: handing them to the desktop's notification service, unless the user has silenced them
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use chrono::{DateTime, Local, Timelike};
+use crate::storage::Preferences;
+
+// How many notifications neon://notifications remembers
+const LOG_LIMIT: usize = 50;
+
+static SHARED_CENTER: OnceLock<NotificationCenter> = OnceLock::new();
+
+/// A notification a site asked to show
+#[derive(Debug, Clone)]
+pub struct NotificationRecord {
+    pub time: DateTime<Local>,
+    pub origin: String,
+    pub title: String,
+    pub body: String,
+    /// False if do-not-disturb or quiet hours held it back
+    pub shown: bool,
+}
+
+/// Recently requested notifications, newest first, for `neon://notifications`
+#[derive(Default)]
+pub struct NotificationCenter {
+    log: Mutex<VecDeque<NotificationRecord>>,
+}
+
+impl NotificationCenter {
+    pub fn shared() -> &'static NotificationCenter {
+        SHARED_CENTER.get_or_init(NotificationCenter::default)
+    }
+
+    /// Show a notification from `origin` on the desktop, unless notifications are silenced right now.
+    /// Returns whether it was shown
+    pub fn notify(&self, origin: &str, title: &str, body: &str) -> bool {
+        let time = Local::now();
+        let minute = (time.hour() * 60 + time.minute()) as u16;
+        let quiet = Preferences::current().notifications.is_quiet(minute);
+        let shown = !quiet && show_native(&format!("{} — {}", title, origin), body);
+        if let Ok(mut log) = self.log.lock() {
+            log.push_front(NotificationRecord {
+                time,
+                origin: origin.to_string(),
+                title: title.to_string(),
+                body: body.to_string(),
+                shown,
+            });
+            log.truncate(LOG_LIMIT);
+        }
+        shown
+    }
+
+    pub fn recent(&self) -> Vec<NotificationRecord> {
+        self.log.lock().map(|log| log.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut log) = self.log.lock() {
+            log.clear();
+        }
+    }
+}
+
+// Hand the notification to the platform's notifier; false if there isn't one
+fn show_native(title: &str, body: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!("display notification {} with title {}", apple_string(body), apple_string(title));
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(windows) {
+        let script = format!(
+            "[reflection.assembly]::loadwithpartialname('System.Windows.Forms') | Out-Null; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; $n.Icon = [System.Drawing.SystemIcons]::Information; \
+             $n.Visible = $true; $n.ShowBalloonTip(5000, '{}', '{}', 'Info'); Start-Sleep 6; $n.Dispose()",
+            title.replace('\'', "''"), body.replace('\'', "''")
+        );
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=NeonSearch", title, body]);
+        command
+    };
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().is_ok()
+}
+
+// An AppleScript string literal
+fn apple_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
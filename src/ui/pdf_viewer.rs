@@ -0,0 +1,462 @@
+// A PDF opened in a tab: its pages one after another with thumbnails beside them, zoom, search through the text,
+// and saving the file as it was received. The file is parsed off the UI thread
+use anyhow::{Result, anyhow};
+use eframe::egui::{self, Color32, Pos2, RichText};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::engine::executor::{Executor, TaskHandle, TaskKind};
+use crate::engine::pdf::{self, PdfDocument, PdfItem, PdfMatch, PdfPage};
+use crate::networking::HttpResponse;
+use crate::security::download_validator::DownloadValidator;
+use crate::ui::{NeonIcons, NeonTheme};
+
+const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.25..=5.0;
+const ZOOM_STEP: f32 = 1.2;
+const PAGE_GAP: f32 = 12.0;
+const THUMBNAIL_WIDTH: f32 = 110.0;
+// Text smaller than this on screen is drawn as a grey bar, as nobody could read it
+const MIN_TEXT_SIZE: f32 = 3.5;
+const BACKGROUND: Color32 = Color32::from_rgb(0x3a, 0x3a, 0x3f);
+
+/// Whether a response of this type is shown in the PDF viewer
+pub fn handles(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    essence == "application/pdf" || essence == "application/x-pdf"
+}
+
+enum PdfState {
+    Loading(TaskHandle<Result<PdfDocument>>),
+    Ready(PdfDocument),
+    Failed(String),
+}
+
+pub struct PdfViewer {
+    /// The file as received, for saving
+    bytes: Arc<Vec<u8>>,
+    file_name: String,
+    state: PdfState,
+    /// Scale from points to screen points; None while fitting the page width to the tab
+    zoom: Option<f32>,
+    /// The zoom that fitted the page width when last drawn
+    fit: f32,
+    show_thumbnails: bool,
+    textures: HashMap<usize, egui::TextureHandle>,
+    query: String,
+    matches: Vec<PdfMatch>,
+    current_match: Option<usize>,
+    /// The page at the top of the view
+    current_page: usize,
+    /// A page, and where on it in points, to bring into view next frame
+    scroll_to: Option<(usize, Option<egui::Rect>)>,
+    status_message: Option<(String, bool)>, // (message, is_error)
+}
+
+impl PdfViewer {
+    /// Start parsing a navigation's response body
+    pub fn open(response: &HttpResponse, url: &str) -> Result<Self> {
+        let mut bytes = response.get_raw_body()?;
+        if let Some(encoding) = response.get_header("Content-Encoding").or_else(|| response.get_header("content-encoding")) {
+            bytes = crate::networking::decode_content(bytes, encoding, None)?;
+        }
+        if !pdf::is_pdf(&bytes) {
+            return Err(anyhow!("The server said this is a PDF, but it isn't one"));
+        }
+        let bytes = Arc::new(bytes);
+        let data = bytes.clone();
+        let task = Executor::shared().spawn_blocking(TaskKind::Parse, move || PdfDocument::parse(&data));
+        Ok(Self {
+            bytes,
+            file_name: file_name(url),
+            state: PdfState::Loading(task),
+            zoom: None,
+            fit: 1.0,
+            show_thumbnails: true,
+            textures: HashMap::new(),
+            query: String::new(),
+            matches: Vec::new(),
+            current_match: None,
+            current_page: 0,
+            scroll_to: None,
+            status_message: None,
+        })
+    }
+
+    /// The document's own title once it's known, or the file's name
+    pub fn title(&self) -> String {
+        match &self.state {
+            PdfState::Ready(PdfDocument { title: Some(title), .. }) => title.clone(),
+            _ => self.file_name.clone(),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll(ui.ctx());
+        self.toolbar(ui);
+        ui.separator();
+        let document = match &self.state {
+            PdfState::Ready(document) => document,
+            PdfState::Loading(_) => {
+                ui.centered_and_justified(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Opening {}…", self.file_name));
+                    });
+                });
+                return;
+            }
+            PdfState::Failed(error) => {
+                ui.centered_and_justified(|ui| {
+                    ui.label(RichText::new(format!("{} This PDF can't be shown: {}", NeonIcons::WARNING, error))
+                        .color(NeonTheme::error_color()));
+                });
+                return;
+            }
+        };
+
+        let area = ui.available_rect_before_wrap();
+        ui.painter().rect_filled(area, 0.0, BACKGROUND);
+        let ctx = ui.ctx().clone();
+        let textures = &mut self.textures;
+        let mut scroll_to = self.scroll_to.take();
+        ui.horizontal_top(|ui| {
+            if self.show_thumbnails {
+                let clicked = thumbnails(ui, document, self.current_page, textures);
+                if let Some(page) = clicked {
+                    scroll_to = Some((page, None));
+                }
+                ui.separator();
+            }
+            self.fit = fit_width(document, ui.available_width());
+            let zoom = self.zoom.unwrap_or(self.fit);
+            let current_match = self.current_match.and_then(|index| self.matches.get(index)).copied();
+            let output = egui::ScrollArea::both()
+                .id_salt("pdf_pages")
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        let mut top_page = None;
+                        for (index, page) in document.pages.iter().enumerate() {
+                            ui.add_space(PAGE_GAP);
+                            let (rect, _) = ui.allocate_exact_size(page.size * zoom, egui::Sense::hover());
+                            if let Some((target, area)) = scroll_to.filter(|(target, _)| *target == index) {
+                                let area = area.map_or(rect, |area| {
+                                    egui::Rect::from_min_max(rect.min + area.min.to_vec2() * zoom, rect.min + area.max.to_vec2() * zoom)
+                                });
+                                ui.scroll_to_rect(area, Some(if area == rect { egui::Align::TOP } else { egui::Align::Center }));
+                                top_page = Some(target);
+                            }
+                            if top_page.is_none() && rect.bottom() > ui.clip_rect().top() + ui.clip_rect().height() / 3.0 {
+                                top_page = Some(index);
+                            }
+                            if ui.is_rect_visible(rect) {
+                                let matches: Vec<(PdfMatch, bool)> = self.matches.iter()
+                                    .filter(|found| found.page == index)
+                                    .map(|found| (*found, Some(*found) == current_match))
+                                    .collect();
+                                paint_page(&ui.painter_at(rect), &ctx, page, rect, zoom, textures, &matches, false);
+                            }
+                        }
+                        ui.add_space(PAGE_GAP);
+                        top_page
+                    }).inner
+                });
+            if let Some(page) = output.inner {
+                self.current_page = page;
+            }
+            // Ctrl and the wheel, or a pinch, zoom the pages
+            if ui.rect_contains_pointer(output.inner_rect) {
+                let pinch = ui.input(|i| i.zoom_delta());
+                if pinch != 1.0 {
+                    self.zoom = Some((zoom * pinch).clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end()));
+                }
+            }
+        });
+    }
+
+    fn poll(&mut self, ctx: &egui::Context) {
+        let PdfState::Loading(task) = &mut self.state else { return };
+        match task.try_take() {
+            None => ctx.request_repaint_after(Duration::from_millis(50)),
+            Some(Ok(Ok(document))) => {
+                log::info!("Opened {} with {} pages", self.file_name, document.pages.len());
+                self.state = PdfState::Ready(document);
+                self.search();
+            }
+            Some(Ok(Err(e))) => {
+                log::warn!("Couldn't open {}: {}", self.file_name, e);
+                self.state = PdfState::Failed(e.to_string());
+            }
+            Some(Err(e)) => self.state = PdfState::Failed(e.to_string()),
+        }
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.selectable_label(self.show_thumbnails, NeonIcons::TERMINAL_WINDOW).on_hover_text("Page thumbnails").clicked() {
+                self.show_thumbnails = !self.show_thumbnails;
+            }
+            ui.label(RichText::new(&self.file_name).strong());
+            let PdfState::Ready(document) = &self.state else { return };
+            let page_count = document.pages.len();
+            ui.label(RichText::new(format!("Page {} of {}", self.current_page + 1, page_count)).color(NeonTheme::secondary_text()));
+            ui.separator();
+
+            let zoom = self.zoom.unwrap_or(self.fit);
+            if ui.small_button(NeonIcons::MINUS).on_hover_text("Zoom out").clicked() {
+                self.zoom = Some((zoom / ZOOM_STEP).max(*ZOOM_RANGE.start()));
+            }
+            ui.label(format!("{:.0}%", zoom * 100.0));
+            if ui.small_button(NeonIcons::PLUS).on_hover_text("Zoom in").clicked() {
+                self.zoom = Some((zoom * ZOOM_STEP).min(*ZOOM_RANGE.end()));
+            }
+            if ui.selectable_label(self.zoom.is_none(), "Fit width").clicked() {
+                self.zoom = None;
+            }
+            ui.separator();
+
+            let search = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Find in PDF").desired_width(160.0));
+            if search.changed() {
+                self.search();
+            }
+            if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let backwards = ui.input(|i| i.modifiers.shift);
+                self.step_match(backwards);
+                search.request_focus();
+            }
+            if !self.query.is_empty() {
+                let position = self.current_match.map_or(0, |index| index + 1);
+                ui.label(RichText::new(format!("{} of {}", position, self.matches.len())).color(NeonTheme::secondary_text()));
+                if ui.small_button("▲").on_hover_text("Previous match").clicked() {
+                    self.step_match(true);
+                }
+                if ui.small_button("▼").on_hover_text("Next match").clicked() {
+                    self.step_match(false);
+                }
+            }
+            ui.separator();
+            if ui.button(format!("{} Download", NeonIcons::DOWNLOAD)).clicked() {
+                self.status_message = Some(match self.save() {
+                    Ok(path) => (format!("Saved to {}", path.display()), false),
+                    Err(e) => (e.to_string(), true),
+                });
+            }
+            if let Some((message, is_error)) = &self.status_message {
+                let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+                ui.label(RichText::new(message).size(12.0).color(color));
+            }
+        });
+    }
+
+    fn search(&mut self) {
+        let PdfState::Ready(document) = &self.state else { return };
+        self.matches = document.find(&self.query);
+        // Start from the page in view rather than going back to the first page
+        self.current_match = self.matches.iter().position(|found| found.page >= self.current_page)
+            .or((!self.matches.is_empty()).then_some(0));
+        self.scroll_to_match();
+    }
+
+    fn step_match(&mut self, backwards: bool) {
+        let count = self.matches.len();
+        if count == 0 {
+            return;
+        }
+        self.current_match = Some(match (self.current_match, backwards) {
+            (Some(index), false) => (index + 1) % count,
+            (Some(index), true) => (index + count - 1) % count,
+            (None, _) => 0,
+        });
+        self.scroll_to_match();
+    }
+
+    fn scroll_to_match(&mut self) {
+        let (PdfState::Ready(document), Some(found)) = (&self.state, self.current_match.and_then(|index| self.matches.get(index))) else {
+            return;
+        };
+        self.scroll_to = Some((found.page, match_rect(&document.pages[found.page], found)));
+    }
+
+    fn save(&self) -> Result<std::path::PathBuf> {
+        let dir = dirs::download_dir().unwrap_or_else(std::env::temp_dir);
+        let path = DownloadValidator::generate_safe_path(&dir, &self.file_name);
+        std::fs::write(&path, self.bytes.as_slice())
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Saved PDF to {}", path.display());
+        Ok(path)
+    }
+}
+
+impl Drop for PdfViewer {
+    fn drop(&mut self) {
+        if let PdfState::Loading(task) = &self.state {
+            task.cancel();
+        }
+    }
+}
+
+// The scale that fits the widest page across `width`
+fn fit_width(document: &PdfDocument, width: f32) -> f32 {
+    let widest = document.pages.iter().map(|page| page.size.x).fold(1.0, f32::max);
+    ((width - 2.0 * PAGE_GAP - 16.0) / widest).clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end())
+}
+
+// The page thumbnails; the one clicked, if any
+fn thumbnails(ui: &mut egui::Ui, document: &PdfDocument, current: usize, textures: &mut HashMap<usize, egui::TextureHandle>) -> Option<usize> {
+    let ctx = ui.ctx().clone();
+    let mut clicked = None;
+    egui::ScrollArea::vertical()
+        .id_salt("pdf_thumbnails")
+        .auto_shrink([true, false])
+        .show(ui, |ui| {
+            ui.set_width(THUMBNAIL_WIDTH + 16.0);
+            ui.vertical_centered(|ui| {
+                for (index, page) in document.pages.iter().enumerate() {
+                    ui.add_space(8.0);
+                    let zoom = THUMBNAIL_WIDTH / page.size.x.max(1.0);
+                    let (rect, response) = ui.allocate_exact_size(page.size * zoom, egui::Sense::click());
+                    if ui.is_rect_visible(rect) {
+                        paint_page(&ui.painter_at(rect.expand(2.0)), &ctx, page, rect, zoom, textures, &[], true);
+                        if index == current {
+                            ui.painter().rect_stroke(rect.expand(2.0), 2.0, egui::Stroke::new(2.0, NeonTheme::neon_cyan()));
+                        }
+                    }
+                    if response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                        clicked = Some(index);
+                    }
+                    ui.label(RichText::new((index + 1).to_string()).size(11.0).color(Color32::LIGHT_GRAY));
+                }
+            });
+        });
+    clicked
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_page(
+    painter: &egui::Painter,
+    ctx: &egui::Context,
+    page: &PdfPage,
+    rect: egui::Rect,
+    zoom: f32,
+    textures: &mut HashMap<usize, egui::TextureHandle>,
+    matches: &[(PdfMatch, bool)],
+    thumbnail: bool,
+) {
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    let to_screen = |point: Pos2| rect.min + point.to_vec2() * zoom;
+    for item in &page.items {
+        match item {
+            PdfItem::Path { subpaths, fill, stroke } => {
+                for subpath in subpaths {
+                    let points: Vec<Pos2> = subpath.iter().map(|&point| to_screen(point)).collect();
+                    if let Some(fill) = fill {
+                        if let Some(area) = axis_aligned_rect(&points) {
+                            painter.rect_filled(area, 0.0, *fill);
+                        } else if points.len() > 2 {
+                            // Shapes are filled as convex polygons, which most are
+                            painter.add(egui::Shape::convex_polygon(points.clone(), *fill, egui::Stroke::NONE));
+                        }
+                    }
+                    if let Some((width, color)) = stroke {
+                        painter.add(egui::Shape::line(points, egui::Stroke::new((width * zoom).max(0.5), *color)));
+                    }
+                }
+            }
+            PdfItem::Image { rect: area, id, image } => {
+                let texture = textures.entry(*id).or_insert_with(|| {
+                    let max_side = ctx.input(|i| i.max_texture_side);
+                    let longest = image.size[0].max(image.size[1]);
+                    let pixels = if longest > max_side {
+                        crate::ui::tab_switcher::downscale(image, (image.size[0] * max_side / longest).max(1))
+                    } else {
+                        image.as_ref().clone()
+                    };
+                    ctx.load_texture(format!("pdf_image_{}", id), pixels, egui::TextureOptions::LINEAR)
+                });
+                let area = egui::Rect::from_min_max(to_screen(area.min), to_screen(area.max));
+                let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                painter.image(texture.id(), area, uv, Color32::WHITE);
+            }
+            PdfItem::Text { origin, size, width, angle, text, color, monospace } => {
+                let origin = to_screen(*origin);
+                let (size, width) = (size * zoom, width * zoom);
+                if thumbnail || size < MIN_TEXT_SIZE {
+                    let bar = egui::Rect::from_min_size(origin - egui::vec2(0.0, size * 0.6), egui::vec2(width.max(1.0), size * 0.5));
+                    painter.rect_filled(bar, 0.0, color.gamma_multiply(0.35));
+                    continue;
+                }
+                let font = |size: f32| if *monospace { egui::FontId::monospace(size) } else { egui::FontId::proportional(size) };
+                let mut galley = painter.layout_no_wrap(text.clone(), font(size), *color);
+                // Our fonts aren't the PDF's; narrow the text back into the room its glyphs had
+                if width > 0.0 && galley.size().x > width * 1.02 {
+                    galley = painter.layout_no_wrap(text.clone(), font(size * width / galley.size().x), *color);
+                }
+                let ascent = egui::emath::Rot2::from_angle(*angle) * egui::vec2(0.0, -galley.size().y * 0.8);
+                painter.add(egui::epaint::TextShape::new(origin + ascent, galley, *color).with_angle(*angle));
+            }
+        }
+    }
+    for (found, current) in matches {
+        let Some(area) = match_rect(page, found) else { continue };
+        let area = egui::Rect::from_min_max(to_screen(area.min), to_screen(area.max));
+        let highlight = if *current { Color32::from_rgba_unmultiplied(255, 150, 0, 140) } else { Color32::from_rgba_unmultiplied(255, 230, 0, 110) };
+        painter.rect_filled(area, 2.0, highlight);
+    }
+}
+
+fn axis_aligned_rect(points: &[Pos2]) -> Option<egui::Rect> {
+    let corners = match points {
+        [a, b, c, d] | [a, b, c, d, _] => [*a, *b, *c, *d],
+        _ => return None,
+    };
+    let aligned = (0..4).all(|index| {
+        let (from, to) = (corners[index], corners[(index + 1) % 4]);
+        (from.x - to.x).abs() < 0.01 || (from.y - to.y).abs() < 0.01
+    });
+    aligned.then(|| egui::Rect::from_points(&corners))
+}
+
+/// Where a match sits on its page, in points, judged by its share of the text run's width
+pub fn match_rect(page: &PdfPage, found: &PdfMatch) -> Option<egui::Rect> {
+    let PdfItem::Text { origin, size, width, text, .. } = page.items.get(found.item)? else { return None };
+    let length = text.chars().count().max(1) as f32;
+    let (start, end) = (found.start as f32 / length, found.end as f32 / length);
+    Some(egui::Rect::from_min_max(
+        egui::pos2(origin.x + width * start, origin.y - size * 0.85),
+        egui::pos2(origin.x + width * end, origin.y + size * 0.2),
+    ))
+}
+
+// The last path segment of the URL, or a generic name
+fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let last = path.rsplit('/').next().unwrap_or("");
+    if last.contains('.') && !url.starts_with("data:") { last.to_string() } else { "document.pdf".to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_rect_and_file_name() {
+        let page = PdfPage {
+            size: egui::vec2(200.0, 300.0),
+            items: vec![PdfItem::Text {
+                origin: egui::pos2(10.0, 50.0),
+                size: 10.0,
+                width: 100.0,
+                angle: 0.0,
+                text: "0123456789".to_string(),
+                color: Color32::BLACK,
+                monospace: false,
+            }],
+        };
+        let area = match_rect(&page, &PdfMatch { page: 0, item: 0, start: 2, end: 5 }).unwrap();
+        assert_eq!((area.min.x, area.max.x), (30.0, 60.0));
+        assert!(area.min.y < 50.0 && area.max.y > 50.0);
+        assert_eq!(file_name("https://example.com/papers/paper.pdf?download=1"), "paper.pdf");
+        assert_eq!(file_name("https://example.com/view/"), "document.pdf");
+        assert!(handles("application/pdf; charset=binary") && !handles("text/html"));
+    }
+}
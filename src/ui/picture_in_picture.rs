@@ -0,0 +1,86 @@
+// Picture-in-picture: a small always-on-top window playing a page's video, or showing a whole tab, while other tabs are in front
+use eframe::egui;
+use uuid::Uuid;
+use crate::ui::NeonTheme;
+
+// Largest size the window opens at; the user can resize it from there
+const MAX_SIZE: egui::Vec2 = egui::vec2(480.0, 320.0);
+const MIN_SIZE: egui::Vec2 = egui::vec2(200.0, 120.0);
+
+/// What the picture-in-picture window shows
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipContent {
+    Tab(Uuid),
+    /// The video playing from `url` on the tab's page
+    Video { tab: Uuid, url: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct PictureInPicture {
+    pub content: PipContent,
+    size: egui::Vec2,
+}
+
+impl PictureInPicture {
+    pub fn tab(tab: Uuid) -> Self {
+        Self { content: PipContent::Tab(tab), size: MAX_SIZE }
+    }
+
+    /// A video of `natural` size, with room under it for the controls
+    pub fn video(tab: Uuid, url: String, natural: Option<[usize; 2]>) -> Self {
+        let picture = natural.map_or(egui::vec2(16.0, 9.0), |[width, height]| egui::vec2(width as f32, height as f32));
+        let size = fit_within(picture, MAX_SIZE - egui::vec2(0.0, 32.0)) + egui::vec2(0.0, 32.0);
+        Self { content: PipContent::Video { tab, url }, size }
+    }
+
+    /// The tab the window's contents come from
+    pub fn source_tab(&self) -> Uuid {
+        match &self.content {
+            PipContent::Tab(tab) | PipContent::Video { tab, .. } => *tab,
+        }
+    }
+
+    /// Draw the window around `add_contents`. False once the user closed it or the contents are gone
+    pub fn show(&self, ctx: &egui::Context, title: &str, mut add_contents: impl FnMut(&mut egui::Ui) -> bool) -> bool {
+        let builder = egui::ViewportBuilder::default()
+            .with_title(format!("{} - Picture-in-picture", title))
+            .with_inner_size(self.size)
+            .with_min_inner_size(MIN_SIZE)
+            .with_always_on_top();
+        ctx.show_viewport_immediate(egui::ViewportId::from_hash_of("picture_in_picture"), builder, |ctx, class| {
+            let frame = egui::Frame::none().fill(NeonTheme::dark_bg()).inner_margin(egui::Margin::same(4.0));
+            if class == egui::ViewportClass::Embedded {
+                // Backends without native windows get an egui window instead
+                let mut open = true;
+                let shown = egui::Window::new(title)
+                    .id(egui::Id::new("picture_in_picture"))
+                    .open(&mut open)
+                    .default_size(self.size)
+                    .frame(egui::Frame::window(&ctx.style()).fill(NeonTheme::dark_bg()))
+                    .show(ctx, |ui| add_contents(ui));
+                return open && shown.and_then(|shown| shown.inner).unwrap_or(true);
+            }
+            let shown = egui::CentralPanel::default().frame(frame).show(ctx, |ui| add_contents(ui)).inner;
+            shown && !ctx.input(|i| i.viewport().close_requested())
+        })
+    }
+}
+
+/// `size` scaled down to fit inside `bounds`, keeping its aspect ratio
+fn fit_within(size: egui::Vec2, bounds: egui::Vec2) -> egui::Vec2 {
+    size * (bounds.x / size.x).min(bounds.y / size.y).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_window_keeps_aspect_ratio() {
+        assert_eq!(fit_within(egui::vec2(1920.0, 1080.0), egui::vec2(480.0, 288.0)), egui::vec2(480.0, 270.0));
+        assert_eq!(fit_within(egui::vec2(320.0, 240.0), egui::vec2(480.0, 288.0)), egui::vec2(320.0, 240.0));
+
+        let pip = PictureInPicture::video(Uuid::new_v4(), "https://example.com/clip.mp4".to_string(), Some([1080, 1920]));
+        assert_eq!(pip.size, egui::vec2(162.0, 320.0));
+    }
+}
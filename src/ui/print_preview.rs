@@ -0,0 +1,242 @@
+use eframe::egui;
+use crate::engine::WebPage;
+use crate::engine::paged_layout::{
+    self, BlockKind, Orientation, PaperSize, PrintBlock, PrintLine, PrintPage, PrintSettings, MM_TO_PX,
+};
+use crate::security::download_validator::DownloadValidator;
+use crate::ui::{NeonIcons, NeonTheme};
+
+// Width of each page thumbnail in the preview pane
+const PREVIEW_PAGE_WIDTH: f32 = 420.0;
+
+/// Print preview for a single tab; keeps its own settings between openings
+pub struct PrintPreview {
+    pub open: bool,
+    settings: PrintSettings,
+    blocks: Vec<PrintBlock>,
+    title: String,
+    url: String,
+    // Layout for the settings it was computed with; rebuilt whenever they change
+    layout: Option<(PrintSettings, Vec<PrintLine>, Vec<PrintPage>)>,
+    status_message: Option<(String, bool)>, // (message, is_error)
+}
+
+impl PrintPreview {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            settings: PrintSettings::default(),
+            blocks: Vec::new(),
+            title: String::new(),
+            url: String::new(),
+            layout: None,
+            status_message: None,
+        }
+    }
+
+    /// Snapshot the page content and show the preview
+    pub fn open_for(&mut self, page: &WebPage, title: &str, url: &str) {
+        self.blocks = paged_layout::collect_blocks(&page.dom);
+        self.title = title.to_string();
+        self.url = url.to_string();
+        self.layout = None;
+        self.status_message = None;
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        let needs_layout = self.layout.as_ref().is_none_or(|(settings, _, _)| *settings != self.settings);
+        if needs_layout {
+            let lines = measure_lines(ctx, &self.blocks, &self.settings);
+            let (_, _, _, content_height) = self.settings.content_rect_px();
+            let pages = paged_layout::paginate(&lines, content_height);
+            self.layout = Some((self.settings.clone(), lines, pages));
+        }
+
+        let mut open = self.open;
+        egui::Window::new(format!("{} Print Preview", NeonIcons::PRINTER))
+            .open(&mut open)
+            .default_size([760.0, 620.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        ui.set_width(220.0);
+                        self.render_controls(ui);
+                    });
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false; 2])
+                        .show(ui, |ui| self.render_pages(ui));
+                });
+            });
+        self.open = open;
+    }
+
+    fn render_controls(&mut self, ui: &mut egui::Ui) {
+        let page_count = self.layout.as_ref().map_or(0, |(_, _, pages)| pages.len());
+        ui.label(egui::RichText::new(format!("{} page{}", page_count, if page_count == 1 { "" } else { "s" }))
+            .size(16.0)
+            .strong()
+            .color(NeonTheme::primary_text()));
+        ui.add_space(8.0);
+
+        ui.label(egui::RichText::new("Paper size").color(NeonTheme::secondary_text()));
+        egui::ComboBox::from_id_salt("print_paper_size")
+            .selected_text(self.settings.paper_size.label())
+            .show_ui(ui, |ui| {
+                for size in PaperSize::ALL {
+                    ui.selectable_value(&mut self.settings.paper_size, size, size.label());
+                }
+            });
+        ui.add_space(6.0);
+
+        ui.label(egui::RichText::new("Orientation").color(NeonTheme::secondary_text()));
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.settings.orientation, Orientation::Portrait, "Portrait");
+            ui.radio_value(&mut self.settings.orientation, Orientation::Landscape, "Landscape");
+        });
+        ui.add_space(6.0);
+
+        ui.label(egui::RichText::new("Margins").color(NeonTheme::secondary_text()));
+        ui.add(egui::Slider::new(&mut self.settings.margin_mm, 0.0..=40.0).suffix(" mm"));
+        ui.add_space(6.0);
+
+        ui.label(egui::RichText::new("Scale").color(NeonTheme::secondary_text()));
+        let mut percent = (self.settings.scale * 100.0).round();
+        if ui.add(egui::Slider::new(&mut percent, 25.0..=200.0).suffix("%")).changed() {
+            self.settings.scale = percent / 100.0;
+        }
+        ui.add_space(6.0);
+
+        ui.checkbox(&mut self.settings.headers_footers, "Headers and footers");
+        ui.add_space(12.0);
+
+        let save_btn = egui::Button::new(
+            egui::RichText::new(format!("{} Save as PDF", NeonIcons::DOWNLOAD)).color(NeonTheme::primary_text())
+        )
+        .fill(NeonTheme::button_primary_color())
+        .rounding(egui::Rounding::same(8.0));
+        if ui.add(save_btn).clicked() {
+            self.status_message = Some(match self.save_pdf() {
+                Ok(path) => (format!("Saved to {}", path.display()), false),
+                Err(e) => (e.to_string(), true),
+            });
+        }
+
+        if let Some((message, is_error)) = &self.status_message {
+            let color = if *is_error { NeonTheme::error_color() } else { NeonTheme::success_color() };
+            ui.add_space(6.0);
+            ui.label(egui::RichText::new(message).size(12.0).color(color));
+        }
+    }
+
+    fn render_pages(&self, ui: &mut egui::Ui) {
+        let Some((settings, lines, pages)) = &self.layout else {
+            return;
+        };
+        let (page_w, page_h) = settings.page_size_px();
+        let (content_x, content_y, content_w, _) = settings.content_rect_px();
+        let margin = settings.margin_mm * MM_TO_PX;
+        let zoom = PREVIEW_PAGE_WIDTH / page_w;
+
+        for (index, page) in pages.iter().enumerate() {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(page_w * zoom, page_h * zoom), egui::Sense::hover());
+            let painter = ui.painter_at(rect.expand(4.0));
+            painter.rect_filled(rect.translate(egui::vec2(3.0, 3.0)), 2.0, egui::Color32::from_black_alpha(90));
+            painter.rect_filled(rect, 2.0, egui::Color32::WHITE);
+            let to_screen = |x: f32, y: f32| rect.min + egui::vec2(x * zoom, y * zoom);
+
+            for placed in &page.lines {
+                let line = &lines[placed.line];
+                let top = content_y + placed.y;
+                if line.kind == BlockKind::Rule {
+                    let y = to_screen(0.0, top + line.height / 2.0).y;
+                    painter.hline(
+                        to_screen(content_x, 0.0).x..=to_screen(content_x + content_w, 0.0).x,
+                        y,
+                        egui::Stroke::new(1.0, egui::Color32::GRAY),
+                    );
+                    continue;
+                }
+                painter.text(
+                    to_screen(content_x, top),
+                    egui::Align2::LEFT_TOP,
+                    &line.text,
+                    font_for(line.kind, settings.scale * zoom),
+                    egui::Color32::from_gray(20),
+                );
+            }
+
+            if settings.headers_footers {
+                let [(header_left, header_right), (footer_left, footer_right)] =
+                    paged_layout::header_footer(&self.title, &self.url, index, pages.len());
+                let font = egui::FontId::proportional(12.0 * zoom);
+                let gray = egui::Color32::from_gray(110);
+                let header_y = margin / 2.0;
+                let footer_y = page_h - margin / 2.0;
+                painter.text(to_screen(content_x, header_y), egui::Align2::LEFT_CENTER, header_left, font.clone(), gray);
+                painter.text(to_screen(page_w - margin, header_y), egui::Align2::RIGHT_CENTER, header_right, font.clone(), gray);
+                painter.text(to_screen(content_x, footer_y), egui::Align2::LEFT_CENTER, footer_left, font.clone(), gray);
+                painter.text(to_screen(page_w - margin, footer_y), egui::Align2::RIGHT_CENTER, footer_right, font, gray);
+            }
+
+            ui.add_space(12.0);
+        }
+    }
+
+    fn save_pdf(&self) -> anyhow::Result<std::path::PathBuf> {
+        let (settings, lines, pages) = self.layout.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Nothing to export yet"))?;
+        let pdf = paged_layout::write_pdf(settings, lines, pages, &self.title, &self.url);
+
+        let dir = dirs::download_dir().unwrap_or_else(std::env::temp_dir);
+        let name = if self.title.trim().is_empty() { "page" } else { self.title.trim() };
+        let path = DownloadValidator::generate_safe_path(&dir, &format!("{}.pdf", name));
+        std::fs::write(&path, pdf)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Exported {} page(s) to {}", pages.len(), path.display());
+        Ok(path)
+    }
+}
+
+fn font_for(kind: BlockKind, scale: f32) -> egui::FontId {
+    let size = kind.font_size() * scale;
+    if kind.is_monospace() {
+        egui::FontId::monospace(size)
+    } else {
+        egui::FontId::proportional(size)
+    }
+}
+
+// Wrap every block at the printable width and split it into individually placed lines
+fn measure_lines(ctx: &egui::Context, blocks: &[PrintBlock], settings: &PrintSettings) -> Vec<PrintLine> {
+    let (_, _, content_width, _) = settings.content_rect_px();
+    let mut lines = Vec::new();
+
+    ctx.fonts(|fonts| {
+        for block in blocks {
+            let gap_before = block.kind.space_before() * settings.scale;
+            if block.kind == BlockKind::Rule {
+                lines.push(PrintLine { kind: block.kind, text: String::new(), height: 8.0 * settings.scale, gap_before });
+                continue;
+            }
+
+            let galley = fonts.layout(block.text.clone(), font_for(block.kind, settings.scale), egui::Color32::BLACK, content_width);
+            for (i, row) in galley.rows.iter().enumerate() {
+                lines.push(PrintLine {
+                    kind: block.kind,
+                    text: row.glyphs.iter().map(|g| g.chr).collect(),
+                    height: row.rect.height(),
+                    gap_before: if i == 0 { gap_before } else { 0.0 },
+                });
+            }
+        }
+    });
+
+    lines
+}
@@ -0,0 +1,160 @@
+use eframe::egui::{self, Color32, RichText};
+use crate::engine::WebPage;
+use crate::engine::fonts::{FontDescriptor, FontRegistry};
+use crate::engine::paged_layout::BlockKind;
+use crate::engine::reader::ReaderArticle;
+use crate::storage::Preferences;
+use crate::storage::preferences::{ReaderFont, ReaderSettings, ReaderTheme};
+use crate::ui::{NeonIcons, NeonTheme};
+
+// Widest the text column gets, so lines stay comfortable to read
+const MAX_COLUMN_WIDTH: f32 = 720.0;
+const FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 12.0..=32.0;
+
+/// Reader mode for a single tab: the page's article in the user's chosen typography
+#[derive(Default)]
+pub struct ReaderView {
+    article: Option<ReaderArticle>,
+}
+
+impl ReaderView {
+    pub fn is_open(&self) -> bool {
+        self.article.is_some()
+    }
+
+    /// Switch to the reader view of `page`; false when the page has no article to show
+    pub fn open_for(&mut self, page: &WebPage, title: &str) -> bool {
+        self.article = ReaderArticle::extract(&page.dom, title);
+        self.article.is_some()
+    }
+
+    pub fn close(&mut self) {
+        self.article = None;
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, url: &str) {
+        let Some(article) = &self.article else { return };
+        let mut settings = Preferences::current().reader.clone();
+        let (background, text, muted) = theme_colors(settings.theme);
+        let mut close = false;
+
+        egui::Frame::none()
+            .fill(background)
+            .inner_margin(egui::Margin::symmetric(24.0, 20.0))
+            .show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+                let column = ui.available_width().min(MAX_COLUMN_WIDTH);
+                let margin = (ui.available_width() - column) / 2.0;
+                ui.horizontal(|ui| {
+                    ui.add_space(margin);
+                    ui.vertical(|ui| {
+                        ui.set_width(column);
+                        let before = settings.clone();
+                        close = toolbar(ui, &mut settings, article, url, muted);
+                        if settings != before {
+                            let changed = settings.clone();
+                            Preferences::update(|prefs| prefs.reader = changed);
+                        }
+                        ui.add_space(16.0);
+                        render_article(ui, article, &settings, text, muted);
+                    });
+                });
+            });
+
+        if close {
+            self.close();
+        }
+    }
+}
+
+// Background, text and secondary text colors of a reader theme
+fn theme_colors(theme: ReaderTheme) -> (Color32, Color32, Color32) {
+    match theme {
+        ReaderTheme::Light => (Color32::from_rgb(0xfb, 0xfb, 0xf8), Color32::from_rgb(0x1f, 0x23, 0x28), Color32::from_rgb(0x6a, 0x73, 0x7d)),
+        ReaderTheme::Sepia => (Color32::from_rgb(0xf4, 0xec, 0xd8), Color32::from_rgb(0x5b, 0x46, 0x36), Color32::from_rgb(0x8a, 0x74, 0x60)),
+        ReaderTheme::Dark => (NeonTheme::dark_bg(), NeonTheme::secondary_text(), NeonTheme::muted_text()),
+    }
+}
+
+// Returns whether the reader was closed
+fn toolbar(ui: &mut egui::Ui, settings: &mut ReaderSettings, article: &ReaderArticle, url: &str, muted: Color32) -> bool {
+    let mut close = false;
+    ui.horizontal_wrapped(|ui| {
+        ui.label(RichText::new(format!("{} {} min read · {} words", NeonIcons::CLOCK, article.reading_minutes(), article.word_count))
+            .color(muted));
+        ui.separator();
+        ui.selectable_value(&mut settings.font, ReaderFont::Serif, "Serif");
+        ui.selectable_value(&mut settings.font, ReaderFont::SansSerif, "Sans");
+        ui.separator();
+        if ui.small_button("A−").on_hover_text("Smaller text").clicked() {
+            settings.font_size = (settings.font_size - 1.0).max(*FONT_SIZE_RANGE.start());
+        }
+        if ui.small_button("A+").on_hover_text("Larger text").clicked() {
+            settings.font_size = (settings.font_size + 1.0).min(*FONT_SIZE_RANGE.end());
+        }
+        ui.add(egui::Slider::new(&mut settings.line_height, 1.2..=2.2).step_by(0.1).text("Line height"));
+        ui.separator();
+        for theme in ReaderTheme::ALL {
+            ui.selectable_value(&mut settings.theme, theme, theme.label());
+        }
+        ui.separator();
+        let mut always = settings.always_for(url);
+        if ui.checkbox(&mut always, "Always for this site").changed() {
+            settings.set_always_for(url, always);
+        }
+        if ui.small_button(NeonIcons::X).on_hover_text("Leave reader mode (F9)").clicked() {
+            close = true;
+        }
+    });
+    close
+}
+
+fn render_article(ui: &mut egui::Ui, article: &ReaderArticle, settings: &ReaderSettings, text: Color32, muted: Color32) {
+    let family = match settings.font {
+        ReaderFont::Serif => FontRegistry::shared().resolve(
+            &FontDescriptor { families: vec!["serif".to_string()], ..FontDescriptor::default() },
+            &[],
+        ),
+        ReaderFont::SansSerif => egui::FontFamily::Proportional,
+    };
+    let paragraph = |size: f32, color: Color32, family: egui::FontFamily, text: &str| {
+        let mut job = egui::text::LayoutJob::default();
+        job.append(text, 0.0, egui::TextFormat {
+            font_id: egui::FontId::new(size, family),
+            color,
+            line_height: Some(size * settings.line_height),
+            ..Default::default()
+        });
+        job
+    };
+
+    ui.label(paragraph(settings.font_size * 1.8, text, family.clone(), &article.title));
+    ui.add_space(settings.font_size);
+    for block in &article.blocks {
+        match block.kind {
+            BlockKind::Heading(level) => {
+                let scale = match level {
+                    1 => 1.6,
+                    2 => 1.4,
+                    3 => 1.2,
+                    _ => 1.1,
+                };
+                ui.add_space(settings.font_size * 0.6);
+                ui.label(paragraph(settings.font_size * scale, text, family.clone(), &block.text));
+            }
+            BlockKind::Paragraph | BlockKind::ListItem => {
+                ui.label(paragraph(settings.font_size, text, family.clone(), &block.text));
+            }
+            BlockKind::Preformatted => {
+                ui.label(paragraph(settings.font_size * 0.85, text, egui::FontFamily::Monospace, &block.text));
+            }
+            BlockKind::Rule => {
+                ui.add(egui::Separator::default().spacing(settings.font_size));
+                continue;
+            }
+        }
+        ui.add_space(settings.font_size * 0.6);
+    }
+    ui.add_space(settings.font_size);
+    ui.label(RichText::new("End of article").color(muted).italics());
+}
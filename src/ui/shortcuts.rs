@@ -0,0 +1,307 @@
+// Keyboard shortcuts for the browser's own commands: each action has a key chord, defaulting to what's usual on
+// the platform, that the user can rebind under Settings > Shortcuts
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use std::collections::BTreeMap;
+use crate::storage::Preferences;
+
+/// Ctrl+1 to Ctrl+9 switch to the tab at that place in the strip, and can't be rebound
+pub const TAB_NUMBER_KEYS: [Key; 9] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+
+// Where the settings page notes the pass it's waiting for a new chord in
+const RECORDING_ID: &str = "shortcuts_recording";
+
+/// A command the browser runs from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShortcutAction {
+    FocusAddressBar,
+    NewTab,
+    /// Bring back the most recently closed tab or window
+    ReopenClosedTab,
+    Reload,
+    /// Reload without the HTTP cache
+    HardReload,
+    /// Stop the page while it loads
+    Stop,
+    Print,
+    ToggleReader,
+    ToggleDevConsole,
+    NextTab,
+    PreviousTab,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 11] = [
+        ShortcutAction::FocusAddressBar,
+        ShortcutAction::NewTab,
+        ShortcutAction::ReopenClosedTab,
+        ShortcutAction::Reload,
+        ShortcutAction::HardReload,
+        ShortcutAction::Stop,
+        ShortcutAction::Print,
+        ShortcutAction::ToggleReader,
+        ShortcutAction::ToggleDevConsole,
+        ShortcutAction::NextTab,
+        ShortcutAction::PreviousTab,
+    ];
+
+    /// Name the preferences store the action's chord under
+    pub fn id(&self) -> &'static str {
+        match self {
+            ShortcutAction::FocusAddressBar => "focus_address_bar",
+            ShortcutAction::NewTab => "new_tab",
+            ShortcutAction::ReopenClosedTab => "reopen_closed_tab",
+            ShortcutAction::Reload => "reload",
+            ShortcutAction::HardReload => "hard_reload",
+            ShortcutAction::Stop => "stop",
+            ShortcutAction::Print => "print",
+            ShortcutAction::ToggleReader => "toggle_reader",
+            ShortcutAction::ToggleDevConsole => "toggle_dev_console",
+            ShortcutAction::NextTab => "next_tab",
+            ShortcutAction::PreviousTab => "previous_tab",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShortcutAction::FocusAddressBar => "Focus address bar",
+            ShortcutAction::NewTab => "New tab",
+            ShortcutAction::ReopenClosedTab => "Reopen closed tab",
+            ShortcutAction::Reload => "Reload",
+            ShortcutAction::HardReload => "Reload, bypassing the cache",
+            ShortcutAction::Stop => "Stop loading",
+            ShortcutAction::Print => "Print",
+            ShortcutAction::ToggleReader => "Reader view",
+            ShortcutAction::ToggleDevConsole => "Developer console",
+            ShortcutAction::NextTab => "Next tab",
+            ShortcutAction::PreviousTab => "Previous tab",
+        }
+    }
+
+    /// The chord the action has until the user rebinds it
+    pub fn default_chord(&self, platform: Platform) -> Option<KeyboardShortcut> {
+        let command = |key| Some(KeyboardShortcut::new(Modifiers::COMMAND, key));
+        // Control on every platform, as Cmd+Tab belongs to macOS; elsewhere it's the command key
+        let control = if platform.is_mac() { Modifiers::CTRL } else { Modifiers::COMMAND };
+        match (self, platform) {
+            (ShortcutAction::FocusAddressBar, _) => command(Key::L),
+            (ShortcutAction::NewTab, _) => command(Key::T),
+            (ShortcutAction::ReopenClosedTab, _) => Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::T)),
+            (ShortcutAction::Reload, _) => command(Key::R),
+            (ShortcutAction::HardReload, _) => Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::R)),
+            (ShortcutAction::Stop, _) => Some(KeyboardShortcut::new(Modifiers::NONE, Key::Escape)),
+            (ShortcutAction::Print, _) => command(Key::P),
+            (ShortcutAction::ToggleReader, Platform::Mac) => Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::ALT, Key::R)),
+            (ShortcutAction::ToggleReader, _) => Some(KeyboardShortcut::new(Modifiers::NONE, Key::F9)),
+            (ShortcutAction::ToggleDevConsole, Platform::Mac) => Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::ALT, Key::I)),
+            (ShortcutAction::ToggleDevConsole, Platform::Windows) => Some(KeyboardShortcut::new(Modifiers::NONE, Key::F12)),
+            (ShortcutAction::ToggleDevConsole, Platform::Linux) => Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::I)),
+            (ShortcutAction::NextTab, _) => Some(KeyboardShortcut::new(control, Key::Tab)),
+            (ShortcutAction::PreviousTab, _) => Some(KeyboardShortcut::new(control | Modifiers::SHIFT, Key::Tab)),
+        }
+    }
+}
+
+/// Which platform's conventions the default chords and their names follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Mac,
+    Windows,
+    Linux,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::Mac
+        } else if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Linux
+        }
+    }
+
+    fn is_mac(self) -> bool {
+        self == Platform::Mac
+    }
+}
+
+/// The chord as the platform writes it, like "Ctrl+Shift+R" or "Shift+Cmd+R"
+pub fn format_chord(chord: &KeyboardShortcut, platform: Platform) -> String {
+    chord.format(&egui::ModifierNames::NAMES, platform.is_mac())
+}
+
+/// Read a chord written as `format_chord` writes it. "Ctrl" is the command key outside macOS, where Control is
+/// the key used for shortcuts
+pub fn parse_chord(text: &str, platform: Platform) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop().filter(|key| !key.is_empty())?)?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers = modifiers | match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" if platform.is_mac() => Modifiers::CTRL,
+            "ctrl" | "control" | "cmd" | "command" => Modifiers::COMMAND,
+            "alt" | "option" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+/// The chord a key pressed with `modifiers` makes, in the form the default chords are written in
+pub fn chord_from_press(key: Key, modifiers: Modifiers, platform: Platform) -> KeyboardShortcut {
+    let modifiers = Modifiers {
+        alt: modifiers.alt,
+        shift: modifiers.shift,
+        ctrl: platform.is_mac() && modifiers.ctrl,
+        command: modifiers.command,
+        mac_cmd: false,
+    };
+    KeyboardShortcut::new(modifiers, key)
+}
+
+/// Whether the chord is one of the tab number shortcuts
+pub fn is_reserved(chord: &KeyboardShortcut) -> bool {
+    chord.modifiers == Modifiers::COMMAND && TAB_NUMBER_KEYS.contains(&chord.logical_key)
+}
+
+/// Every action's chord, with the user's changes over the platform's defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcuts {
+    platform: Platform,
+    bindings: Vec<(ShortcutAction, Option<KeyboardShortcut>)>,
+}
+
+impl Shortcuts {
+    /// `overrides` maps action ids to chords; an empty chord leaves the action without one
+    pub fn new(platform: Platform, overrides: &BTreeMap<String, String>) -> Self {
+        let bindings = ShortcutAction::ALL.iter()
+            .map(|action| {
+                let chord = match overrides.get(action.id()) {
+                    Some(text) if text.is_empty() => None,
+                    Some(text) => parse_chord(text, platform).or_else(|| action.default_chord(platform)),
+                    None => action.default_chord(platform),
+                };
+                (*action, chord)
+            })
+            .collect();
+        Self { platform, bindings }
+    }
+
+    /// The shortcuts as the preferences set them
+    pub fn current() -> Self {
+        Self::new(Platform::current(), &Preferences::current().shortcuts)
+    }
+
+    /// No action has a chord
+    pub fn none() -> Self {
+        Self {
+            platform: Platform::current(),
+            bindings: ShortcutAction::ALL.iter().map(|action| (*action, None)).collect(),
+        }
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn chord(&self, action: ShortcutAction) -> Option<KeyboardShortcut> {
+        self.bindings.iter().find(|(bound, _)| *bound == action).and_then(|(_, chord)| *chord)
+    }
+
+    /// The action `chord` is bound to, if any
+    pub fn action_for(&self, chord: &KeyboardShortcut) -> Option<ShortcutAction> {
+        self.bindings.iter().find(|(_, bound)| bound.as_ref() == Some(chord)).map(|(action, _)| *action)
+    }
+
+    /// Pairs of actions bound to the same chord, of which only the first runs
+    pub fn conflicts(&self) -> Vec<(ShortcutAction, ShortcutAction)> {
+        let mut conflicts = Vec::new();
+        for (index, (action, chord)) in self.bindings.iter().enumerate() {
+            let Some(chord) = chord else { continue };
+            for (other, other_chord) in &self.bindings[index + 1..] {
+                if other_chord.as_ref() == Some(chord) {
+                    conflicts.push((*action, *other));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// The action's chord as the platform writes it, or "None"
+    pub fn describe(&self, action: ShortcutAction) -> String {
+        self.chord(action).map_or_else(|| "None".to_string(), |chord| format_chord(&chord, self.platform))
+    }
+
+    /// Whether the action's chord was pressed this frame, taking the key press so nothing else acts on it. Only
+    /// the exact chord counts: Ctrl+Shift+R doesn't also press Ctrl+R
+    pub fn consume(&self, input: &mut egui::InputState, action: ShortcutAction) -> bool {
+        let Some(chord) = self.chord(action) else { return false };
+        let mut pressed = false;
+        input.events.retain(|event| {
+            let matches = matches!(
+                event,
+                egui::Event::Key { key, pressed: true, modifiers, .. } if *key == chord.logical_key && modifiers.matches_exact(chord.modifiers)
+            );
+            pressed |= matches;
+            !matches
+        });
+        pressed
+    }
+}
+
+/// Keep shortcuts from running during this pass and the next, while the settings page waits for a new chord
+pub fn hold_for_recording(ctx: &egui::Context) {
+    let pass = ctx.cumulative_pass_nr();
+    ctx.data_mut(|data| data.insert_temp(egui::Id::new(RECORDING_ID), pass));
+}
+
+/// Whether the settings page is waiting for a new chord
+pub fn is_recording(ctx: &egui::Context) -> bool {
+    let pass = ctx.cumulative_pass_nr();
+    ctx.data(|data| data.get_temp::<u64>(egui::Id::new(RECORDING_ID))).is_some_and(|held| held + 1 >= pass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_differ_by_platform_without_conflicts() {
+        for platform in [Platform::Mac, Platform::Windows, Platform::Linux] {
+            let shortcuts = Shortcuts::new(platform, &BTreeMap::new());
+            assert!(shortcuts.conflicts().is_empty(), "{:?}", platform);
+            for action in ShortcutAction::ALL {
+                let chord = shortcuts.chord(action).unwrap();
+                assert!(!is_reserved(&chord));
+                assert_eq!(parse_chord(&format_chord(&chord, platform), platform), Some(chord), "{:?}", action);
+            }
+        }
+        let describe = |platform| Shortcuts::new(platform, &BTreeMap::new()).describe(ShortcutAction::ToggleDevConsole);
+        assert_eq!(describe(Platform::Mac), "Option+Cmd+I");
+        assert_eq!(describe(Platform::Windows), "F12");
+        assert_eq!(describe(Platform::Linux), "Ctrl+Shift+I");
+        assert_eq!(parse_chord("Ctrl+Tab", Platform::Mac), Some(KeyboardShortcut::new(Modifiers::CTRL, Key::Tab)));
+        assert_eq!(parse_chord("Ctrl+", Platform::Linux), None);
+        assert_eq!(parse_chord("Hyper+K", Platform::Linux), None);
+    }
+
+    #[test]
+    fn test_overrides_rebind_and_conflict() {
+        let overrides = BTreeMap::from([
+            ("new_tab".to_string(), "Ctrl+R".to_string()),
+            ("print".to_string(), String::new()),
+            ("stop".to_string(), "not a chord".to_string()),
+        ]);
+        let shortcuts = Shortcuts::new(Platform::Linux, &overrides);
+        let ctrl_r = KeyboardShortcut::new(Modifiers::COMMAND, Key::R);
+        assert_eq!(shortcuts.chord(ShortcutAction::NewTab), Some(ctrl_r));
+        assert_eq!(shortcuts.chord(ShortcutAction::Print), None);
+        assert_eq!(shortcuts.describe(ShortcutAction::Stop), "Escape");
+        assert_eq!(shortcuts.action_for(&ctrl_r), Some(ShortcutAction::NewTab));
+        assert_eq!(shortcuts.conflicts(), [(ShortcutAction::NewTab, ShortcutAction::Reload)]);
+        // Ctrl as pressed on Linux sets command too
+        assert_eq!(chord_from_press(Key::R, Modifiers::CTRL | Modifiers::COMMAND, Platform::Linux), ctrl_r);
+        assert_eq!(chord_from_press(Key::R, Modifiers::MAC_CMD | Modifiers::COMMAND, Platform::Mac), ctrl_r);
+    }
+}
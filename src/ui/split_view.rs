@@ -0,0 +1,93 @@
+// Split view: the content area shared by two tabs, or a tab and the developer console, side by side
+use eframe::egui;
+use uuid::Uuid;
+
+// Narrowest share of the width either side can be dragged down to
+const MIN_RATIO: f32 = 0.2;
+const DIVIDER_WIDTH: f32 = 8.0;
+
+/// What the right-hand side of the split shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPane {
+    Tab(Uuid),
+    DevConsole,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitView {
+    pub left: Uuid,
+    pub right: SplitPane,
+    /// Share of the width the left side takes
+    pub ratio: f32,
+    // Whether the right-hand tab was the last one in use, so a tab picked from the strip replaces it
+    right_focused: bool,
+}
+
+impl SplitView {
+    pub fn new(left: Uuid, right: SplitPane) -> Self {
+        Self { left, right, ratio: 0.5, right_focused: false }
+    }
+
+    pub fn contains(&self, tab: Uuid) -> bool {
+        self.left == tab || self.right == SplitPane::Tab(tab)
+    }
+
+    /// Keep the active tab on screen: it takes over whichever side was in use when another tab was picked
+    pub fn follow_active(&mut self, active: Uuid) {
+        if self.left == active {
+            self.right_focused = false;
+        } else if self.right == SplitPane::Tab(active) {
+            self.right_focused = true;
+        } else if self.right_focused && matches!(self.right, SplitPane::Tab(_)) {
+            self.right = SplitPane::Tab(active);
+        } else {
+            self.left = active;
+        }
+    }
+
+    /// The left side, the divider between the sides and the right side of `rect`
+    pub fn layout(&self, rect: egui::Rect) -> (egui::Rect, egui::Rect, egui::Rect) {
+        let ratio = self.ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+        let split_x = rect.left() + (rect.width() - DIVIDER_WIDTH) * ratio;
+        let left = egui::Rect::from_min_max(rect.min, egui::pos2(split_x, rect.bottom()));
+        let divider = egui::Rect::from_min_max(egui::pos2(split_x, rect.top()), egui::pos2(split_x + DIVIDER_WIDTH, rect.bottom()));
+        let right = egui::Rect::from_min_max(egui::pos2(split_x + DIVIDER_WIDTH, rect.top()), rect.max);
+        (left, divider, right)
+    }
+
+    /// Move the divider to `x` within `rect`
+    pub fn drag_to(&mut self, rect: egui::Rect, x: f32) {
+        self.ratio = ((x - rect.left()) / (rect.width() - DIVIDER_WIDTH).max(1.0)).clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_follows_active_tab() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut split = SplitView::new(a, SplitPane::Tab(b));
+        split.follow_active(c);
+        assert_eq!((split.left, split.right), (c, SplitPane::Tab(b)));
+        split.follow_active(b);
+        split.follow_active(a);
+        assert_eq!((split.left, split.right), (c, SplitPane::Tab(a)));
+
+        let mut console = SplitView::new(a, SplitPane::DevConsole);
+        console.follow_active(b);
+        assert_eq!((console.left, console.right), (b, SplitPane::DevConsole));
+    }
+
+    #[test]
+    fn test_layout_and_drag() {
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1008.0, 600.0));
+        let mut split = SplitView::new(Uuid::new_v4(), SplitPane::DevConsole);
+        let (left, divider, right) = split.layout(rect);
+        assert_eq!((left.width(), divider.width(), right.width()), (500.0, DIVIDER_WIDTH, 500.0));
+
+        split.drag_to(rect, 10.0);
+        assert_eq!(split.ratio, MIN_RATIO);
+    }
+}
@@ -0,0 +1,199 @@
+// Vertical tab strip: tabs listed down the left side in their groups, with a filter for finding one among many
+use eframe::egui::{self, RichText};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::pages::components;
+use crate::ui::tab_strip::{self, GroupCommand, TabSection, TabStrip};
+use crate::ui::{BrowserTab, NeonIcons, NeonTheme};
+
+/// What the user did in the sidebar, for the app to carry out
+pub enum SidebarAction {
+    Activate(Uuid),
+    Close(Uuid),
+    /// Close every tab in the group
+    CloseGroup(Uuid),
+    ToggleMute(Uuid),
+    /// Show the tab beside the active one
+    OpenInSplit(Uuid),
+    /// Show the tab in the picture-in-picture window
+    PictureInPicture(Uuid),
+}
+
+#[derive(Default)]
+pub struct TabSidebar {
+    query: String,
+    // Group whose name is being edited
+    renaming: Option<Uuid>,
+    actions: Vec<SidebarAction>,
+    // Tabs moved to another group (None to ungroup them), applied once the strip is no longer being drawn
+    moves: Vec<(Uuid, Option<Uuid>)>,
+}
+
+impl TabSidebar {
+    pub fn show(&mut self, ui: &mut egui::Ui, strip: &mut TabStrip, tabs: &HashMap<Uuid, BrowserTab>, active: Option<Uuid>) -> Vec<SidebarAction> {
+        ui.add_space(8.0);
+        ui.add(egui::TextEdit::singleline(&mut self.query)
+            .hint_text(format!("{} Search tabs", NeonIcons::MAGNIFYING_GLASS))
+            .desired_width(f32::INFINITY));
+        ui.add_space(6.0);
+
+        let query = self.query.trim().to_lowercase();
+        let matches = |tab: &BrowserTab| query.is_empty()
+            || tab.title.to_lowercase().contains(&query)
+            || tab.url.to_lowercase().contains(&query);
+
+        egui::ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+            for section in strip.sections() {
+                let shown: Vec<Uuid> = section.tabs.iter().copied()
+                    .filter(|id| tabs.get(id).is_some_and(matches))
+                    .collect();
+                if !query.is_empty() && shown.is_empty() {
+                    continue;
+                }
+                let frame = egui::Frame::none().inner_margin(egui::Margin::symmetric(0.0, 2.0)).rounding(6.0);
+                let (_, dropped) = ui.dnd_drop_zone::<Uuid, _>(frame, |ui| {
+                    ui.set_width(ui.available_width());
+                    let collapsed = self.group_header(ui, strip, &section) && query.is_empty();
+                    if collapsed {
+                        return;
+                    }
+                    for id in &shown {
+                        if let Some(tab) = tabs.get(id) {
+                            self.tab_row(ui, *id, tab, active == Some(*id), strip);
+                        }
+                    }
+                    if section.tabs.is_empty() {
+                        ui.label(RichText::new("Drag tabs here").size(11.0).italics().color(NeonTheme::muted_text()));
+                    }
+                });
+                if let Some(tab) = dropped {
+                    self.moves.push((*tab, section.group));
+                }
+            }
+
+            ui.add_space(8.0);
+            if ui.button(format!("{} New group", NeonIcons::PLUS)).clicked() {
+                let name = format!("Group {}", strip.groups().len() + 1);
+                self.renaming = Some(strip.create_group(name));
+            }
+        });
+
+        for (tab, group) in std::mem::take(&mut self.moves) {
+            if strip.group_of(tab) != group {
+                strip.move_to_group(tab, group);
+            }
+        }
+        std::mem::take(&mut self.actions)
+    }
+
+    // Returns whether the group's tabs are hidden
+    fn group_header(&mut self, ui: &mut egui::Ui, strip: &mut TabStrip, section: &TabSection) -> bool {
+        let Some(group) = section.group.and_then(|id| strip.group_mut(id)) else {
+            return false;
+        };
+        let mut command = None;
+        ui.horizontal(|ui| {
+            let (dot, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+            ui.painter().circle_filled(dot.center(), 5.0, tab_strip::group_color(group.color));
+            let arrow = if group.collapsed { NeonIcons::CARET_RIGHT } else { NeonIcons::CARET_DOWN };
+            if ui.small_button(arrow).clicked() {
+                group.collapsed = !group.collapsed;
+            }
+            if self.renaming == Some(group.id) {
+                let response = ui.text_edit_singleline(&mut group.name);
+                if !response.has_focus() && !response.gained_focus() {
+                    response.request_focus();
+                }
+                if response.lost_focus() {
+                    self.renaming = None;
+                }
+            } else {
+                let header = ui.add(egui::Label::new(
+                    RichText::new(format!("{} ({})", group.name, section.tabs.len())).strong().color(tab_strip::group_color(group.color))
+                ).sense(egui::Sense::click()));
+                if header.clicked() {
+                    group.collapsed = !group.collapsed;
+                }
+                if header.double_clicked() {
+                    self.renaming = Some(group.id);
+                }
+                header.context_menu(|ui| command = tab_strip::group_menu(ui, group));
+            }
+        });
+        let (id, collapsed) = (group.id, group.collapsed);
+        match command {
+            Some(GroupCommand::Rename) => self.renaming = Some(id),
+            Some(GroupCommand::Ungroup) => strip.ungroup(id),
+            Some(GroupCommand::Close) => self.actions.push(SidebarAction::CloseGroup(id)),
+            None => {}
+        }
+        collapsed
+    }
+
+    fn tab_row(&mut self, ui: &mut egui::Ui, id: Uuid, tab: &BrowserTab, is_active: bool, strip: &TabStrip) {
+        let indent = if strip.group_of(id).is_some() { 12.0 } else { 0.0 };
+        ui.dnd_drag_source(egui::Id::new(("sidebar_tab", id)), id, |ui| {
+            egui::Frame::none()
+                .fill(if is_active { NeonTheme::neon_purple() } else { NeonTheme::elevated_bg() })
+                .stroke(egui::Stroke::new(1.0, if is_active { NeonTheme::neon_cyan() } else { NeonTheme::border_color() }))
+                .rounding(6.0)
+                .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+                .outer_margin(egui::Margin { left: indent, ..Default::default() })
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if tab.load.is_loading() {
+                            ui.spinner();
+                        } else {
+                            components::site_icon(ui, &tab.url, 16.0);
+                        }
+                        // Title takes what the buttons leave, so they line up on the right
+                        let audio = tab.is_playing_audio() || tab.audio_muted;
+                        let buttons_width = if audio { 52.0 } else { 26.0 };
+                        let title_size = egui::vec2((ui.available_width() - buttons_width).max(0.0), 18.0);
+                        ui.allocate_ui_with_layout(title_size, egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                            let title = ui.add(egui::Label::new(
+                                RichText::new(&tab.title).size(13.0).color(if is_active { egui::Color32::WHITE } else { NeonTheme::primary_text() })
+                            ).truncate().sense(egui::Sense::click()));
+                            if title.clicked() {
+                                self.actions.push(SidebarAction::Activate(id));
+                            }
+                            if title.middle_clicked() {
+                                self.actions.push(SidebarAction::Close(id));
+                            }
+                            title.on_hover_text(&tab.url).context_menu(|ui| {
+                                if !is_active && ui.button("Open in split view").clicked() {
+                                    self.actions.push(SidebarAction::OpenInSplit(id));
+                                    ui.close_menu();
+                                }
+                                if ui.button("Picture-in-picture").clicked() {
+                                    self.actions.push(SidebarAction::PictureInPicture(id));
+                                    ui.close_menu();
+                                }
+                                ui.menu_button("Move to group", |ui| {
+                                    for group in strip.groups() {
+                                        if ui.button(&group.name).clicked() {
+                                            self.moves.push((id, Some(group.id)));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                    if strip.group_of(id).is_some() && ui.button("Remove from group").clicked() {
+                                        self.moves.push((id, None));
+                                        ui.close_menu();
+                                    }
+                                });
+                            });
+                        });
+                        if audio {
+                            let icon = if tab.audio_muted { NeonIcons::SPEAKER_MUTED } else { NeonIcons::SPEAKER };
+                            if ui.small_button(icon).clicked() {
+                                self.actions.push(SidebarAction::ToggleMute(id));
+                            }
+                        }
+                        if ui.small_button(NeonIcons::X).on_hover_text("Close tab").clicked() {
+                            self.actions.push(SidebarAction::Close(id));
+                        }
+                    });
+                });
+        });
+    }
+}
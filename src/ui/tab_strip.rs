@@ -0,0 +1,248 @@
+// Order of the open tabs and the groups they're collected into, shared by the tab bar and the tab sidebar
+use eframe::egui::{self, Color32};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::storage::GroupColor;
+use crate::ui::NeonTheme;
+
+/// A named set of tabs, kept next to each other in the strip
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub color: GroupColor,
+    pub collapsed: bool,
+}
+
+/// One run of the strip: the tabs of a group, or ungrouped tabs between groups
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabSection {
+    pub group: Option<Uuid>,
+    pub tabs: Vec<Uuid>,
+}
+
+#[derive(Debug, Default)]
+pub struct TabStrip {
+    order: Vec<Uuid>,
+    groups: Vec<TabGroup>,
+    // Group of each grouped tab
+    membership: HashMap<Uuid, Uuid>,
+}
+
+impl TabStrip {
+    /// Every tab, in strip order
+    pub fn tabs(&self) -> &[Uuid] {
+        &self.order
+    }
+
+    pub fn push(&mut self, tab: Uuid) {
+        self.order.push(tab);
+    }
+
+    /// Put ungrouped `tab` at `index` in the strip, or just after the group that place is inside of
+    pub fn insert(&mut self, index: usize, tab: Uuid) {
+        let mut index = index.min(self.order.len());
+        while index > 0 && index < self.order.len() && self.group_of(self.order[index - 1]).is_some()
+            && self.group_of(self.order[index - 1]) == self.group_of(self.order[index])
+        {
+            index += 1;
+        }
+        self.order.insert(index, tab);
+    }
+
+    /// Take `tab` out of the strip; a group goes with its last tab
+    pub fn remove(&mut self, tab: Uuid) {
+        self.order.retain(|id| *id != tab);
+        if let Some(group) = self.membership.remove(&tab) {
+            if !self.membership.values().any(|id| *id == group) {
+                self.groups.retain(|g| g.id != group);
+            }
+        }
+    }
+
+    pub fn groups(&self) -> &[TabGroup] {
+        &self.groups
+    }
+
+    pub fn group(&self, id: Uuid) -> Option<&TabGroup> {
+        self.groups.iter().find(|group| group.id == id)
+    }
+
+    pub fn group_mut(&mut self, id: Uuid) -> Option<&mut TabGroup> {
+        self.groups.iter_mut().find(|group| group.id == id)
+    }
+
+    pub fn group_of(&self, tab: Uuid) -> Option<Uuid> {
+        self.membership.get(&tab).copied()
+    }
+
+    /// Start an empty group, which tabs are then moved into. Each new group takes the next colour
+    pub fn create_group(&mut self, name: String) -> Uuid {
+        let id = Uuid::new_v4();
+        let color = GroupColor::ALL[self.groups.len() % GroupColor::ALL.len()];
+        self.groups.push(TabGroup { id, name, color, collapsed: false });
+        id
+    }
+
+    /// Tabs in `group`, in strip order
+    pub fn tabs_in(&self, group: Uuid) -> Vec<Uuid> {
+        self.order.iter().copied().filter(|tab| self.group_of(*tab) == Some(group)).collect()
+    }
+
+    /// Remove a group, leaving its tabs ungrouped where they are
+    pub fn ungroup(&mut self, group: Uuid) {
+        self.membership.retain(|_, id| *id != group);
+        self.groups.retain(|g| g.id != group);
+    }
+
+    /// Move `tab` into `group`, after its other tabs, or out of any group to the end of the strip
+    pub fn move_to_group(&mut self, tab: Uuid, group: Option<Uuid>) {
+        if !self.order.contains(&tab) || group.is_some_and(|id| self.group(id).is_none()) {
+            return;
+        }
+        self.order.retain(|id| *id != tab);
+        match group {
+            Some(group) => {
+                let position = self.order.iter()
+                    .rposition(|id| self.membership.get(id) == Some(&group))
+                    .map_or(self.order.len(), |last| last + 1);
+                self.order.insert(position, tab);
+                self.membership.insert(tab, group);
+            }
+            None => {
+                self.order.push(tab);
+                self.membership.remove(&tab);
+            }
+        }
+    }
+
+    /// The strip split into runs of grouped and ungrouped tabs, followed by any groups still empty
+    pub fn sections(&self) -> Vec<TabSection> {
+        let mut sections: Vec<TabSection> = Vec::new();
+        for tab in &self.order {
+            let group = self.group_of(*tab);
+            match sections.last_mut() {
+                Some(section) if section.group == group => section.tabs.push(*tab),
+                _ => sections.push(TabSection { group, tabs: vec![*tab] }),
+            }
+        }
+        for group in &self.groups {
+            if !sections.iter().any(|section| section.group == Some(group.id)) {
+                sections.push(TabSection { group: Some(group.id), tabs: Vec::new() });
+            }
+        }
+        sections
+    }
+}
+
+/// What a group's context menu asked for beyond the changes it makes to the group itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupCommand {
+    Rename,
+    Ungroup,
+    Close,
+}
+
+/// A change to the strip asked for while it was being drawn, made once drawing is done
+#[derive(Debug, Clone, PartialEq)]
+pub enum StripEdit {
+    /// New name, colour or collapsed state for a group
+    Update(TabGroup),
+    Rename(Uuid, String),
+    FinishRename,
+    Command(Uuid, GroupCommand),
+    MoveTab(Uuid, Option<Uuid>),
+    /// Start a group holding just this tab
+    NewGroupWith(Uuid),
+}
+
+pub fn group_color(color: GroupColor) -> Color32 {
+    match color {
+        GroupColor::Cyan => NeonTheme::neon_cyan(),
+        GroupColor::Purple => NeonTheme::neon_purple(),
+        GroupColor::Pink => Color32::from_rgb(255, 92, 170),
+        GroupColor::Green => NeonTheme::success_color(),
+        GroupColor::Yellow => NeonTheme::warning_color(),
+        GroupColor::Orange => Color32::from_rgb(255, 149, 0),
+        GroupColor::Grey => NeonTheme::muted_text(),
+    }
+}
+
+/// Context menu of a group's label, shared by the tab bar and the sidebar; picking a colour applies it directly
+pub fn group_menu(ui: &mut egui::Ui, group: &mut TabGroup) -> Option<GroupCommand> {
+    let mut command = None;
+    if ui.button("Rename group").clicked() {
+        command = Some(GroupCommand::Rename);
+    }
+    ui.horizontal(|ui| {
+        for color in GroupColor::ALL {
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::click());
+            ui.painter().circle_filled(rect.center(), 7.0, group_color(color));
+            if group.color == color {
+                ui.painter().circle_stroke(rect.center(), 8.0, egui::Stroke::new(1.5, Color32::WHITE));
+            }
+            if response.on_hover_text(color.label()).clicked() {
+                group.color = color;
+            }
+        }
+    });
+    let collapse = if group.collapsed { "Expand group" } else { "Collapse group" };
+    if ui.button(collapse).clicked() {
+        group.collapsed = !group.collapsed;
+        ui.close_menu();
+    }
+    ui.separator();
+    if ui.button("Ungroup").clicked() {
+        command = Some(GroupCommand::Ungroup);
+    }
+    if ui.button("Close group").clicked() {
+        command = Some(GroupCommand::Close);
+    }
+    if command.is_some() {
+        ui.close_menu();
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_stay_together() {
+        let tabs: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let mut strip = TabStrip::default();
+        for tab in &tabs {
+            strip.push(*tab);
+        }
+        let docs = strip.create_group("Docs".to_string());
+        strip.move_to_group(tabs[3], Some(docs));
+        strip.move_to_group(tabs[0], Some(docs));
+        assert_eq!(strip.tabs(), [tabs[1], tabs[2], tabs[3], tabs[0]]);
+        assert_eq!(strip.sections(), [
+            TabSection { group: None, tabs: vec![tabs[1], tabs[2]] },
+            TabSection { group: Some(docs), tabs: vec![tabs[3], tabs[0]] },
+        ]);
+
+        strip.move_to_group(tabs[3], None);
+        assert_eq!(strip.group_of(tabs[3]), None);
+        assert_eq!(strip.tabs().last(), Some(&tabs[3]));
+
+        let empty = strip.create_group("Empty".to_string());
+        assert_eq!(strip.sections().last(), Some(&TabSection { group: Some(empty), tabs: Vec::new() }));
+
+        strip.remove(tabs[0]);
+        assert!(strip.group(docs).is_none());
+
+        // A reopened tab goes back to its place, or after the group that place is now inside of
+        let reading = strip.create_group("Reading".to_string());
+        strip.move_to_group(tabs[1], Some(reading));
+        strip.move_to_group(tabs[2], Some(reading));
+        let reopened = Uuid::new_v4();
+        assert_eq!(strip.tabs(), [tabs[3], tabs[1], tabs[2]]);
+        strip.insert(2, reopened);
+        assert_eq!(strip.tabs(), [tabs[3], tabs[1], tabs[2], reopened]);
+        strip.insert(0, tabs[0]);
+        assert_eq!(strip.tabs()[0], tabs[0]);
+    }
+}
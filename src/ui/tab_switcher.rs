@@ -0,0 +1,261 @@
+// Ctrl+Tab switching: tabs in most-recently-used order, shown with thumbnails of their pages
+use eframe::egui::{self, Color32, ColorImage, RichText};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::engine::focus;
+use crate::ui::{BrowserTab, NeonIcons, NeonTheme};
+
+// Width in pixels thumbnails are stored at, and the size they're shown at
+const THUMBNAIL_WIDTH: usize = 320;
+const CARD_SIZE: egui::Vec2 = egui::vec2(200.0, 125.0);
+// How often the active tab's thumbnail is retaken while it's shown
+const THUMBNAIL_REFRESH_SECS: f64 = 3.0;
+
+struct Thumbnail {
+    texture: egui::TextureHandle,
+    taken_at: f64,
+}
+
+/// Tab switcher overlay plus the bookkeeping it needs: which tabs were used most recently and what they looked like
+#[derive(Default)]
+pub struct TabSwitcher {
+    // Most recently active first
+    recent: Vec<Uuid>,
+    // Highlighted tab while the overlay is open
+    selected: Option<Uuid>,
+    // The overlay waits a frame after opening so the active tab's thumbnail doesn't include it
+    opening: bool,
+    thumbnails: HashMap<Uuid, Thumbnail>,
+    // Tab and screen area of the screenshot in flight
+    capture: Option<(Uuid, egui::Rect)>,
+}
+
+impl TabSwitcher {
+    /// Record `tab` as the one in use now
+    pub fn touch(&mut self, tab: Uuid) {
+        if self.recent.first() != Some(&tab) {
+            self.recent.retain(|id| *id != tab);
+            self.recent.insert(0, tab);
+        }
+    }
+
+    pub fn forget(&mut self, tab: Uuid) {
+        self.recent.retain(|id| *id != tab);
+        self.thumbnails.remove(&tab);
+        if self.selected == Some(tab) {
+            self.selected = None;
+        }
+    }
+
+    /// The most recently used tab other than `except`, which is where focus goes when a tab closes
+    pub fn most_recent_except(&self, except: Uuid) -> Option<Uuid> {
+        self.recent.iter().copied().find(|id| *id != except)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    /// Open the overlay, or move its highlight to the next or previous tab. `content` is where the active
+    /// tab's page is on screen, for a fresh thumbnail of it
+    pub fn cycle(&mut self, ctx: &egui::Context, tabs: &[Uuid], active: Option<Uuid>, content: Option<egui::Rect>, backward: bool) {
+        let order = mru_order(&self.recent, tabs);
+        if order.len() < 2 {
+            return;
+        }
+        if self.selected.is_none() {
+            if let (Some(active), Some(rect)) = (active, content) {
+                self.request_capture(ctx, active, rect);
+            }
+            self.opening = true;
+            self.selected = active;
+        }
+        self.selected = focus::step(&order, self.selected, backward);
+        ctx.request_repaint();
+    }
+
+    fn request_capture(&mut self, ctx: &egui::Context, tab: Uuid, rect: egui::Rect) {
+        if self.capture.is_none() {
+            self.capture = Some((tab, rect));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+        }
+    }
+
+    /// Collect finished screenshots, and retake the active tab's thumbnail once it's gone stale
+    pub fn update_thumbnails(&mut self, ctx: &egui::Context, active: Option<Uuid>, content: Option<egui::Rect>) {
+        let screenshot = ctx.input(|i| i.events.iter().find_map(|event| match event {
+            egui::Event::Screenshot { image, .. } => Some(image.clone()),
+            _ => None,
+        }));
+        if let (Some(image), Some((tab, rect))) = (screenshot, self.capture.take()) {
+            let region = image.region(&rect, Some(ctx.pixels_per_point()));
+            if region.width() > 0 && region.height() > 0 {
+                let thumbnail = downscale(&region, THUMBNAIL_WIDTH);
+                let texture = ctx.load_texture(format!("tab-thumbnail-{}", tab), thumbnail, egui::TextureOptions::LINEAR);
+                self.thumbnails.insert(tab, Thumbnail { texture, taken_at: ctx.input(|i| i.time) });
+            }
+        }
+
+        let (Some(active), Some(rect)) = (active, content) else { return };
+        let now = ctx.input(|i| i.time);
+        let stale = self.thumbnails.get(&active).is_none_or(|thumbnail| now - thumbnail.taken_at > THUMBNAIL_REFRESH_SECS);
+        if stale && !self.is_open() {
+            self.request_capture(ctx, active, rect);
+        }
+    }
+
+    /// Draw the overlay while it's open. Returns the tab to switch to once Ctrl is released or a tab is clicked
+    pub fn show(&mut self, ctx: &egui::Context, tab_order: &[Uuid], tabs: &HashMap<Uuid, BrowserTab>) -> Option<Uuid> {
+        let selected = self.selected?;
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.selected = None;
+            return None;
+        }
+        if !ctx.input(|i| i.modifiers.ctrl) {
+            self.selected = None;
+            return Some(selected);
+        }
+        if std::mem::take(&mut self.opening) {
+            ctx.request_repaint();
+            return None;
+        }
+
+        let screen = ctx.screen_rect();
+        ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tab_switcher_dim")))
+            .rect_filled(screen, 0.0, Color32::from_black_alpha(140));
+
+        let mut clicked = None;
+        let order = mru_order(&self.recent, tab_order);
+        let columns = ((screen.width() * 0.8 / (CARD_SIZE.x + 12.0)) as usize).clamp(1, 6);
+        egui::Area::new(egui::Id::new("tab_switcher"))
+            .order(egui::Order::Tooltip)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Frame::none()
+                    .fill(NeonTheme::card_bg())
+                    .stroke(egui::Stroke::new(1.0, NeonTheme::border_color()))
+                    .rounding(12.0)
+                    .inner_margin(egui::Margin::same(16.0))
+                    .show(ui, |ui| {
+                        egui::Grid::new("tab_switcher_grid").spacing(egui::vec2(12.0, 12.0)).show(ui, |ui| {
+                            for (index, id) in order.iter().enumerate() {
+                                let Some(tab) = tabs.get(id) else { continue };
+                                if self.card(ui, tab, *id == selected, self.thumbnails.get(id)).clicked() {
+                                    clicked = Some(*id);
+                                }
+                                if (index + 1) % columns == 0 {
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                    });
+            });
+        if clicked.is_some() {
+            self.selected = None;
+        }
+        clicked
+    }
+
+    fn card(&self, ui: &mut egui::Ui, tab: &BrowserTab, selected: bool, thumbnail: Option<&Thumbnail>) -> egui::Response {
+        let stroke = if selected {
+            egui::Stroke::new(2.0, NeonTheme::neon_cyan())
+        } else {
+            egui::Stroke::new(1.0, NeonTheme::border_color())
+        };
+        let response = egui::Frame::none()
+            .fill(NeonTheme::dark_bg())
+            .stroke(stroke)
+            .rounding(8.0)
+            .inner_margin(egui::Margin::same(6.0))
+            .show(ui, |ui| {
+                ui.set_width(CARD_SIZE.x);
+                match thumbnail {
+                    Some(thumbnail) => {
+                        ui.add(egui::Image::new(&thumbnail.texture).fit_to_exact_size(CARD_SIZE).maintain_aspect_ratio(true));
+                    }
+                    None => {
+                        ui.allocate_ui(CARD_SIZE, |ui| {
+                            ui.centered_and_justified(|ui| {
+                                ui.label(RichText::new(NeonIcons::GLOBE).size(32.0).color(NeonTheme::muted_text()));
+                            });
+                        });
+                    }
+                }
+                let mut title = tab.title.clone();
+                if title.chars().count() > 28 {
+                    title = format!("{}...", title.chars().take(25).collect::<String>());
+                }
+                ui.label(RichText::new(title).size(12.0).color(if selected { NeonTheme::primary_text() } else { NeonTheme::secondary_text() }));
+            })
+            .response;
+        response.interact(egui::Sense::click())
+    }
+}
+
+/// Tabs in most-recently-used order; tabs never made active follow in tab strip order
+pub fn mru_order(recent: &[Uuid], tabs: &[Uuid]) -> Vec<Uuid> {
+    let mut order: Vec<Uuid> = recent.iter().copied().filter(|id| tabs.contains(id)).collect();
+    order.extend(tabs.iter().copied().filter(|id| !recent.contains(id)));
+    order
+}
+
+/// Shrink `image` to `width` pixels wide, averaging the pixels each one covers
+pub fn downscale(image: &ColorImage, width: usize) -> ColorImage {
+    let [source_width, source_height] = image.size;
+    if source_width <= width {
+        return image.clone();
+    }
+    let height = (source_height * width / source_width).max(1);
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (top, bottom) = (y * source_height / height, ((y + 1) * source_height / height).max(y * source_height / height + 1));
+        for x in 0..width {
+            let (left, right) = (x * source_width / width, ((x + 1) * source_width / width).max(x * source_width / width + 1));
+            let mut sum = [0u32; 4];
+            for sy in top..bottom {
+                for sx in left..right {
+                    let pixel = image.pixels[sy * source_width + sx];
+                    for (channel, value) in sum.iter_mut().zip(pixel.to_array()) {
+                        *channel += value as u32;
+                    }
+                }
+            }
+            let count = ((bottom - top) * (right - left)) as u32;
+            let [r, g, b, a] = sum.map(|channel| (channel / count) as u8);
+            pixels.push(Color32::from_rgba_premultiplied(r, g, b, a));
+        }
+    }
+    ColorImage { size: [width, height], pixels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mru_order_and_cycling() {
+        let tabs: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let mut switcher = TabSwitcher::default();
+        switcher.touch(tabs[0]);
+        switcher.touch(tabs[2]);
+        switcher.touch(tabs[1]);
+        assert_eq!(mru_order(&switcher.recent, &tabs), [tabs[1], tabs[2], tabs[0], tabs[3]]);
+        assert_eq!(switcher.most_recent_except(tabs[1]), Some(tabs[2]));
+
+        switcher.forget(tabs[2]);
+        let open: Vec<Uuid> = tabs.iter().copied().filter(|id| *id != tabs[2]).collect();
+        assert_eq!(mru_order(&switcher.recent, &open), [tabs[1], tabs[0], tabs[3]]);
+    }
+
+    #[test]
+    fn test_downscale_averages() {
+        let image = ColorImage {
+            size: [4, 2],
+            pixels: vec![Color32::WHITE, Color32::BLACK, Color32::WHITE, Color32::WHITE, Color32::BLACK, Color32::WHITE, Color32::WHITE, Color32::WHITE],
+        };
+        let small = downscale(&image, 2);
+        assert_eq!(small.size, [2, 1]);
+        assert_eq!(small.pixels[0], Color32::from_rgba_premultiplied(127, 127, 127, 255));
+        assert_eq!(small.pixels[1], Color32::WHITE);
+    }
+}
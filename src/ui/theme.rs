@@ -1,57 +1,239 @@
 use eframe::egui::{self, Color32, Rounding, Shadow, Stroke, Style, Visuals, Vec2};
+use std::sync::RwLock;
 
 pub struct NeonTheme;
 
+/// Colors and roundings of a theme. The names are roles from the original neon palette: `neon_cyan` is the
+/// main accent and `dark_bg` the window background, whether or not the theme is dark.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub dark: bool,
+    pub neon_cyan: Color32,
+    pub neon_purple: Color32,
+    pub neon_pink: Color32,
+    pub neon_green: Color32,
+    pub neon_blue: Color32,
+    pub neon_orange: Color32,
+    pub neon_gold: Color32,
+    pub dark_bg: Color32,
+    pub darker_bg: Color32,
+    pub card_bg: Color32,
+    pub elevated_bg: Color32,
+    pub panel_bg: Color32,
+    pub surface_bg: Color32,
+    pub primary_text: Color32,
+    pub secondary_text: Color32,
+    pub muted_text: Color32,
+    pub hint_text: Color32,
+    pub accent_text: Color32,
+    pub border_color: Color32,
+    pub focus_border: Color32,
+    pub hover_border: Color32,
+    pub active_border: Color32,
+    pub error_color: Color32,
+    pub success_color: Color32,
+    pub warning_color: Color32,
+    pub info_color: Color32,
+    pub button_idle: Color32,
+    pub button_hover: Color32,
+    pub button_active: Color32,
+    pub button_primary: Color32,
+    pub button_primary_hover: Color32,
+    /// Corner radius of widgets
+    pub rounding: f32,
+    /// Corner radius of windows and cards
+    pub window_rounding: f32,
+}
+
+impl Palette {
+    pub const NEON_DARK: Palette = Palette {
+        dark: true,
+        // Modern Neon color palette - more sophisticated and vibrant
+        neon_cyan: Color32::from_rgb(0, 255, 255),       // Electric cyan
+        neon_purple: Color32::from_rgb(138, 43, 226),    // Electric purple
+        neon_pink: Color32::from_rgb(255, 20, 147),      // Hot pink
+        neon_green: Color32::from_rgb(57, 255, 20),      // Electric lime
+        neon_blue: Color32::from_rgb(30, 144, 255),      // Dodger blue
+        neon_orange: Color32::from_rgb(255, 140, 0),     // Dark orange
+        neon_gold: Color32::from_rgb(255, 215, 0),       // Gold accent
+        // Modern dark theme with deeper contrasts and warmer tones
+        dark_bg: Color32::from_rgb(13, 17, 23),          // GitHub dark
+        darker_bg: Color32::from_rgb(8, 12, 16),         // Deeper dark
+        card_bg: Color32::from_rgb(21, 32, 43),          // Card background
+        elevated_bg: Color32::from_rgb(33, 41, 54),      // Elevated surfaces
+        panel_bg: Color32::from_rgb(22, 27, 34),         // Panel background
+        surface_bg: Color32::from_rgb(30, 35, 42),       // Surface background
+        // Enhanced text colors with better hierarchy
+        primary_text: Color32::from_rgb(248, 250, 252),  // Almost white
+        secondary_text: Color32::from_rgb(201, 209, 217), // Cool gray
+        muted_text: Color32::from_rgb(139, 148, 158),    // Muted gray
+        hint_text: Color32::from_rgb(87, 96, 106),       // Hint text
+        accent_text: Color32::from_rgb(88, 166, 255),    // Accent blue
+        // Enhanced border and outline colors with more sophistication
+        border_color: Color32::from_rgb(48, 54, 61),     // Subtle border
+        focus_border: Color32::from_rgb(0, 122, 255),    // Focus indicator
+        hover_border: Color32::from_rgb(88, 166, 255),   // Hover state
+        active_border: Color32::from_rgb(0, 255, 255),   // Active state
+        // Status colors with modern design system approach
+        error_color: Color32::from_rgb(255, 69, 58),     // iOS red
+        success_color: Color32::from_rgb(52, 199, 89),   // iOS green
+        warning_color: Color32::from_rgb(255, 204, 0),   // iOS yellow
+        info_color: Color32::from_rgb(90, 200, 250),     // Info blue
+        // Interactive state colors with smooth transitions
+        button_idle: Color32::from_rgb(48, 54, 61),      // Button at rest
+        button_hover: Color32::from_rgb(65, 72, 80),     // Button hovered
+        button_active: Color32::from_rgb(82, 90, 99),    // Button pressed
+        button_primary: Color32::from_rgb(0, 122, 255),  // Primary button
+        button_primary_hover: Color32::from_rgb(10, 132, 255), // Primary hover
+        rounding: 12.0,
+        window_rounding: 16.0,
+    };
+
+    pub const NEON_LIGHT: Palette = Palette {
+        dark: false,
+        neon_cyan: Color32::from_rgb(0, 128, 148),
+        neon_purple: Color32::from_rgb(111, 66, 193),
+        neon_pink: Color32::from_rgb(214, 51, 132),
+        neon_green: Color32::from_rgb(26, 140, 60),
+        neon_blue: Color32::from_rgb(13, 110, 253),
+        neon_orange: Color32::from_rgb(217, 108, 0),
+        neon_gold: Color32::from_rgb(176, 132, 0),
+        dark_bg: Color32::from_rgb(246, 248, 250),
+        darker_bg: Color32::from_rgb(234, 238, 242),
+        card_bg: Color32::from_rgb(255, 255, 255),
+        elevated_bg: Color32::from_rgb(240, 243, 246),
+        panel_bg: Color32::from_rgb(250, 251, 252),
+        surface_bg: Color32::from_rgb(238, 241, 244),
+        primary_text: Color32::from_rgb(31, 35, 40),
+        secondary_text: Color32::from_rgb(66, 74, 83),
+        muted_text: Color32::from_rgb(101, 109, 118),
+        hint_text: Color32::from_rgb(140, 149, 159),
+        accent_text: Color32::from_rgb(9, 105, 218),
+        border_color: Color32::from_rgb(208, 215, 222),
+        focus_border: Color32::from_rgb(9, 105, 218),
+        hover_border: Color32::from_rgb(84, 174, 255),
+        active_border: Color32::from_rgb(0, 128, 148),
+        error_color: Color32::from_rgb(207, 34, 46),
+        success_color: Color32::from_rgb(26, 127, 55),
+        warning_color: Color32::from_rgb(154, 103, 0),
+        info_color: Color32::from_rgb(2, 136, 209),
+        button_idle: Color32::from_rgb(234, 238, 242),
+        button_hover: Color32::from_rgb(220, 225, 230),
+        button_active: Color32::from_rgb(208, 215, 222),
+        button_primary: Color32::from_rgb(9, 105, 218),
+        button_primary_hover: Color32::from_rgb(3, 102, 214),
+        rounding: 12.0,
+        window_rounding: 16.0,
+    };
+
+    /// Names of the palette's colors, as theme files spell them
+    pub const COLOR_NAMES: [&'static str; 31] = [
+        "neon_cyan", "neon_purple", "neon_pink", "neon_green", "neon_blue", "neon_orange", "neon_gold",
+        "dark_bg", "darker_bg", "card_bg", "elevated_bg", "panel_bg", "surface_bg",
+        "primary_text", "secondary_text", "muted_text", "hint_text", "accent_text",
+        "border_color", "focus_border", "hover_border", "active_border",
+        "error_color", "success_color", "warning_color", "info_color",
+        "button_idle", "button_hover", "button_active", "button_primary", "button_primary_hover",
+    ];
+
+    /// The built-in palette a theme starts from
+    pub fn base(dark: bool) -> Palette {
+        if dark { Palette::NEON_DARK } else { Palette::NEON_LIGHT }
+    }
+
+    /// The color called `name` in theme files
+    pub fn color_mut(&mut self, name: &str) -> Option<&mut Color32> {
+        Some(match name {
+            "neon_cyan" => &mut self.neon_cyan,
+            "neon_purple" => &mut self.neon_purple,
+            "neon_pink" => &mut self.neon_pink,
+            "neon_green" => &mut self.neon_green,
+            "neon_blue" => &mut self.neon_blue,
+            "neon_orange" => &mut self.neon_orange,
+            "neon_gold" => &mut self.neon_gold,
+            "dark_bg" => &mut self.dark_bg,
+            "darker_bg" => &mut self.darker_bg,
+            "card_bg" => &mut self.card_bg,
+            "elevated_bg" => &mut self.elevated_bg,
+            "panel_bg" => &mut self.panel_bg,
+            "surface_bg" => &mut self.surface_bg,
+            "primary_text" => &mut self.primary_text,
+            "secondary_text" => &mut self.secondary_text,
+            "muted_text" => &mut self.muted_text,
+            "hint_text" => &mut self.hint_text,
+            "accent_text" => &mut self.accent_text,
+            "border_color" => &mut self.border_color,
+            "focus_border" => &mut self.focus_border,
+            "hover_border" => &mut self.hover_border,
+            "active_border" => &mut self.active_border,
+            "error_color" => &mut self.error_color,
+            "success_color" => &mut self.success_color,
+            "warning_color" => &mut self.warning_color,
+            "info_color" => &mut self.info_color,
+            "button_idle" => &mut self.button_idle,
+            "button_hover" => &mut self.button_hover,
+            "button_active" => &mut self.button_active,
+            "button_primary" => &mut self.button_primary,
+            "button_primary_hover" => &mut self.button_primary_hover,
+            _ => return None,
+        })
+    }
+}
+
+// The palette every NeonTheme color is read from, switched by the theme manager
+static ACTIVE_PALETTE: RwLock<Palette> = RwLock::new(Palette::NEON_DARK);
+
 impl NeonTheme {
-    // Modern Neon color palette - more sophisticated and vibrant
-    pub const NEON_CYAN: Color32 = Color32::from_rgb(0, 255, 255);       // Electric cyan
-    pub const NEON_PURPLE: Color32 = Color32::from_rgb(138, 43, 226);     // Electric purple  
-    pub const NEON_PINK: Color32 = Color32::from_rgb(255, 20, 147);       // Hot pink
-    pub const NEON_GREEN: Color32 = Color32::from_rgb(57, 255, 20);       // Electric lime
-    pub const NEON_BLUE: Color32 = Color32::from_rgb(30, 144, 255);       // Dodger blue
-    pub const NEON_ORANGE: Color32 = Color32::from_rgb(255, 140, 0);      // Dark orange
-    pub const NEON_GOLD: Color32 = Color32::from_rgb(255, 215, 0);        // Gold accent
-    
-    // Modern dark theme with deeper contrasts and warmer tones
-    pub const DARK_BG: Color32 = Color32::from_rgb(13, 17, 23);           // GitHub dark
-    pub const DARKER_BG: Color32 = Color32::from_rgb(8, 12, 16);          // Deeper dark
-    pub const CARD_BG: Color32 = Color32::from_rgb(21, 32, 43);           // Card background
-    pub const ELEVATED_BG: Color32 = Color32::from_rgb(33, 41, 54);       // Elevated surfaces
-    pub const PANEL_BG: Color32 = Color32::from_rgb(22, 27, 34);          // Panel background
-    pub const SURFACE_BG: Color32 = Color32::from_rgb(30, 35, 42);        // Surface background
-    
-    // Enhanced text colors with better hierarchy
-    pub const PRIMARY_TEXT: Color32 = Color32::from_rgb(248, 250, 252);   // Almost white
-    pub const SECONDARY_TEXT: Color32 = Color32::from_rgb(201, 209, 217); // Cool gray
-    pub const MUTED_TEXT: Color32 = Color32::from_rgb(139, 148, 158);     // Muted gray
-    pub const HINT_TEXT: Color32 = Color32::from_rgb(87, 96, 106);        // Hint text
-    pub const ACCENT_TEXT: Color32 = Color32::from_rgb(88, 166, 255);     // Accent blue
-    
-    // Enhanced border and outline colors with more sophistication
-    pub const BORDER_COLOR: Color32 = Color32::from_rgb(48, 54, 61);      // Subtle border
-    pub const FOCUS_BORDER: Color32 = Color32::from_rgb(0, 122, 255);     // Focus indicator
-    pub const HOVER_BORDER: Color32 = Color32::from_rgb(88, 166, 255);    // Hover state
-    pub const ACTIVE_BORDER: Color32 = Color32::from_rgb(0, 255, 255);    // Active state
-    
-    // Status colors with modern design system approach
-    pub const ERROR_COLOR: Color32 = Color32::from_rgb(255, 69, 58);      // iOS red
-    pub const SUCCESS_COLOR: Color32 = Color32::from_rgb(52, 199, 89);    // iOS green
-    pub const WARNING_COLOR: Color32 = Color32::from_rgb(255, 204, 0);    // iOS yellow
-    pub const INFO_COLOR: Color32 = Color32::from_rgb(90, 200, 250);      // Info blue
-    
-    // Interactive state colors with smooth transitions
-    pub const BUTTON_IDLE: Color32 = Color32::from_rgb(48, 54, 61);       // Button at rest
-    pub const BUTTON_HOVER: Color32 = Color32::from_rgb(65, 72, 80);      // Button hovered
-    pub const BUTTON_ACTIVE: Color32 = Color32::from_rgb(82, 90, 99);     // Button pressed
-    pub const BUTTON_PRIMARY: Color32 = Color32::from_rgb(0, 122, 255);   // Primary button
-    pub const BUTTON_PRIMARY_HOVER: Color32 = Color32::from_rgb(10, 132, 255); // Primary hover
+    /// The active theme's palette, for pages that draw with more than the named colors
+    pub fn palette() -> Palette {
+        *ACTIVE_PALETTE.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make `palette` the active one and restyle the UI with it
+    pub fn set_palette(ctx: &egui::Context, palette: Palette) {
+        *ACTIVE_PALETTE.write().unwrap_or_else(|e| e.into_inner()) = palette;
+        Self::apply_to_context(ctx);
+        ctx.request_repaint();
+    }
+
+    pub fn neon_cyan() -> Color32 { Self::palette().neon_cyan }
+    pub fn neon_purple() -> Color32 { Self::palette().neon_purple }
+    pub fn neon_pink() -> Color32 { Self::palette().neon_pink }
+    pub fn neon_green() -> Color32 { Self::palette().neon_green }
+    pub fn neon_blue() -> Color32 { Self::palette().neon_blue }
+    pub fn neon_orange() -> Color32 { Self::palette().neon_orange }
+    pub fn neon_gold() -> Color32 { Self::palette().neon_gold }
+    pub fn dark_bg() -> Color32 { Self::palette().dark_bg }
+    pub fn darker_bg() -> Color32 { Self::palette().darker_bg }
+    pub fn card_bg() -> Color32 { Self::palette().card_bg }
+    pub fn elevated_bg() -> Color32 { Self::palette().elevated_bg }
+    pub fn panel_bg() -> Color32 { Self::palette().panel_bg }
+    pub fn surface_bg() -> Color32 { Self::palette().surface_bg }
+    pub fn primary_text() -> Color32 { Self::palette().primary_text }
+    pub fn secondary_text() -> Color32 { Self::palette().secondary_text }
+    pub fn muted_text() -> Color32 { Self::palette().muted_text }
+    pub fn hint_text() -> Color32 { Self::palette().hint_text }
+    pub fn accent_text() -> Color32 { Self::palette().accent_text }
+    pub fn border_color() -> Color32 { Self::palette().border_color }
+    pub fn focus_border() -> Color32 { Self::palette().focus_border }
+    pub fn hover_border() -> Color32 { Self::palette().hover_border }
+    pub fn active_border() -> Color32 { Self::palette().active_border }
+    pub fn info_color() -> Color32 { Self::palette().info_color }
+    pub fn button_idle() -> Color32 { Self::palette().button_idle }
+    pub fn button_hover() -> Color32 { Self::palette().button_hover }
+    pub fn button_active() -> Color32 { Self::palette().button_active }
+    pub fn button_primary_color() -> Color32 { Self::palette().button_primary }
+    pub fn button_primary_hover() -> Color32 { Self::palette().button_primary_hover }
 
     pub fn apply_to_context(ctx: &egui::Context) {
+        // egui keeps a style per mode; pin the mode so the one set here is the one shown
+        ctx.set_theme(if Self::palette().dark { egui::Theme::Dark } else { egui::Theme::Light });
         ctx.set_style(Self::create_style());
         ctx.set_visuals(Self::create_visuals());
     }
 
     pub fn create_style() -> Style {
+        let palette = Self::palette();
         let mut style = Style::default();
         
         // Modern spacing and layout with better proportions
@@ -67,7 +249,7 @@ impl NeonTheme {
         style.spacing.text_edit_width = 280.0;
         
         // Modern window styling with sophisticated shadows
-        style.visuals.window_rounding = Rounding::same(20.0);
+        style.visuals.window_rounding = Rounding::same(palette.window_rounding + 4.0);
         style.visuals.window_shadow = Shadow {
             offset: Vec2::new(0.0, 12.0),
             blur: 40.0,
@@ -84,69 +266,71 @@ impl NeonTheme {
         // Enhanced widget styling with modern design principles
         
         // Non-interactive widgets (labels, separators, etc.)
-        style.visuals.widgets.noninteractive.bg_fill = Self::SURFACE_BG;
-        style.visuals.widgets.noninteractive.weak_bg_fill = Self::DARK_BG;
-        style.visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Self::BORDER_COLOR);
-        style.visuals.widgets.noninteractive.rounding = Rounding::same(12.0);
-        style.visuals.widgets.noninteractive.fg_stroke = Stroke::new(1.0, Self::PRIMARY_TEXT);
+        style.visuals.widgets.noninteractive.bg_fill = Self::surface_bg();
+        style.visuals.widgets.noninteractive.weak_bg_fill = Self::dark_bg();
+        style.visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Self::border_color());
+        style.visuals.widgets.noninteractive.rounding = Rounding::same(palette.rounding);
+        style.visuals.widgets.noninteractive.fg_stroke = Stroke::new(1.0, Self::primary_text());
         
         // Inactive widgets (buttons, inputs at rest)
-        style.visuals.widgets.inactive.bg_fill = Self::BUTTON_IDLE;
-        style.visuals.widgets.inactive.weak_bg_fill = Self::CARD_BG;
-        style.visuals.widgets.inactive.bg_stroke = Stroke::new(1.0, Self::BORDER_COLOR);
-        style.visuals.widgets.inactive.rounding = Rounding::same(12.0);
-        style.visuals.widgets.inactive.fg_stroke = Stroke::new(1.0, Self::SECONDARY_TEXT);
+        style.visuals.widgets.inactive.bg_fill = Self::button_idle();
+        style.visuals.widgets.inactive.weak_bg_fill = Self::card_bg();
+        style.visuals.widgets.inactive.bg_stroke = Stroke::new(1.0, Self::border_color());
+        style.visuals.widgets.inactive.rounding = Rounding::same(palette.rounding);
+        style.visuals.widgets.inactive.fg_stroke = Stroke::new(1.0, Self::secondary_text());
         
         // Hovered widgets
-        style.visuals.widgets.hovered.bg_fill = Self::BUTTON_HOVER;
-        style.visuals.widgets.hovered.weak_bg_fill = Self::ELEVATED_BG;
-        style.visuals.widgets.hovered.bg_stroke = Stroke::new(2.0, Self::HOVER_BORDER);
-        style.visuals.widgets.hovered.rounding = Rounding::same(12.0);
-        style.visuals.widgets.hovered.fg_stroke = Stroke::new(1.0, Self::PRIMARY_TEXT);
+        style.visuals.widgets.hovered.bg_fill = Self::button_hover();
+        style.visuals.widgets.hovered.weak_bg_fill = Self::elevated_bg();
+        style.visuals.widgets.hovered.bg_stroke = Stroke::new(2.0, Self::hover_border());
+        style.visuals.widgets.hovered.rounding = Rounding::same(palette.rounding);
+        style.visuals.widgets.hovered.fg_stroke = Stroke::new(1.0, Self::primary_text());
         
         // Active widgets (pressed/clicked)
-        style.visuals.widgets.active.bg_fill = Self::BUTTON_ACTIVE;
-        style.visuals.widgets.active.weak_bg_fill = Self::ELEVATED_BG;
-        style.visuals.widgets.active.bg_stroke = Stroke::new(2.0, Self::ACTIVE_BORDER);
-        style.visuals.widgets.active.rounding = Rounding::same(12.0);
-        style.visuals.widgets.active.fg_stroke = Stroke::new(1.0, Self::NEON_CYAN);
+        style.visuals.widgets.active.bg_fill = Self::button_active();
+        style.visuals.widgets.active.weak_bg_fill = Self::elevated_bg();
+        style.visuals.widgets.active.bg_stroke = Stroke::new(2.0, Self::active_border());
+        style.visuals.widgets.active.rounding = Rounding::same(palette.rounding);
+        style.visuals.widgets.active.fg_stroke = Stroke::new(1.0, Self::neon_cyan());
         
         // Open widgets (dropdowns, menus, etc.)
-        style.visuals.widgets.open.bg_fill = Self::ELEVATED_BG;
-        style.visuals.widgets.open.weak_bg_fill = Self::CARD_BG;
-        style.visuals.widgets.open.bg_stroke = Stroke::new(2.0, Self::FOCUS_BORDER);
-        style.visuals.widgets.open.rounding = Rounding::same(12.0);
-        style.visuals.widgets.open.fg_stroke = Stroke::new(1.0, Self::FOCUS_BORDER);
+        style.visuals.widgets.open.bg_fill = Self::elevated_bg();
+        style.visuals.widgets.open.weak_bg_fill = Self::card_bg();
+        style.visuals.widgets.open.bg_stroke = Stroke::new(2.0, Self::focus_border());
+        style.visuals.widgets.open.rounding = Rounding::same(palette.rounding);
+        style.visuals.widgets.open.fg_stroke = Stroke::new(1.0, Self::focus_border());
         
         style
     }
 
     pub fn create_visuals() -> Visuals {
-        let mut visuals = Visuals::dark();
+        let palette = Self::palette();
+        let mut visuals = if palette.dark { Visuals::dark() } else { Visuals::light() };
         
         // Main background and panel colors with sophisticated hierarchy
-        visuals.panel_fill = Self::DARK_BG;
-        visuals.window_fill = Self::CARD_BG;
-        visuals.extreme_bg_color = Self::DARKER_BG;
-        visuals.faint_bg_color = Self::SURFACE_BG;
+        visuals.panel_fill = Self::dark_bg();
+        visuals.window_fill = Self::card_bg();
+        visuals.extreme_bg_color = Self::darker_bg();
+        visuals.faint_bg_color = Self::surface_bg();
         
         // Text colors with enhanced contrast
-        visuals.override_text_color = Some(Self::PRIMARY_TEXT);
+        visuals.override_text_color = Some(Self::primary_text());
         
         // Selection and highlighting with modern blue
-        visuals.selection.bg_fill = Color32::from_rgba_unmultiplied(0, 122, 255, 50);
-        visuals.selection.stroke = Stroke::new(1.5, Self::FOCUS_BORDER);
+        let focus = palette.focus_border;
+        visuals.selection.bg_fill = Color32::from_rgba_unmultiplied(focus.r(), focus.g(), focus.b(), 50);
+        visuals.selection.stroke = Stroke::new(1.5, Self::focus_border());
         
         // Hyperlink colors using accent blue
-        visuals.hyperlink_color = Self::ACCENT_TEXT;
+        visuals.hyperlink_color = Self::accent_text();
         
         // Warning and error colors
-        visuals.warn_fg_color = Self::WARNING_COLOR;
-        visuals.error_fg_color = Self::ERROR_COLOR;
+        visuals.warn_fg_color = Self::warning_color();
+        visuals.error_fg_color = Self::error_color();
         
         // Window and shadow effects with modern aesthetics
-        visuals.window_rounding = Rounding::same(16.0);
-        visuals.menu_rounding = Rounding::same(12.0);
+        visuals.window_rounding = Rounding::same(palette.window_rounding);
+        visuals.menu_rounding = Rounding::same(palette.rounding);
         
         visuals.window_shadow = Shadow {
             offset: Vec2::new(0.0, 8.0),
@@ -163,11 +347,11 @@ impl NeonTheme {
         };
         
         // Widget colors using our modern color scheme
-        visuals.widgets.active.bg_fill = Self::BUTTON_PRIMARY;
-        visuals.widgets.hovered.bg_fill = Self::BUTTON_HOVER;
+        visuals.widgets.active.bg_fill = Self::button_primary_color();
+        visuals.widgets.hovered.bg_fill = Self::button_hover();
         
         // Separator colors
-        visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Self::BORDER_COLOR);
+        visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Self::border_color());
         
         visuals
     }
@@ -175,30 +359,30 @@ impl NeonTheme {
     // Helper methods for modern widget styling
     pub fn button_primary() -> egui::Button<'static> {
         egui::Button::new("")
-            .fill(Self::BUTTON_PRIMARY)
+            .fill(Self::button_primary_color())
             .stroke(Stroke::new(0.0, Color32::TRANSPARENT))
-            .rounding(Rounding::same(12.0))
+            .rounding(Rounding::same(Self::palette().rounding))
     }
 
     pub fn button_secondary() -> egui::Button<'static> {
         egui::Button::new("")
-            .fill(Self::ELEVATED_BG)
-            .stroke(Stroke::new(1.5, Self::BORDER_COLOR))
-            .rounding(Rounding::same(12.0))
+            .fill(Self::elevated_bg())
+            .stroke(Stroke::new(1.5, Self::border_color()))
+            .rounding(Rounding::same(Self::palette().rounding))
     }
 
     pub fn button_danger() -> egui::Button<'static> {
         egui::Button::new("")
-            .fill(Self::ERROR_COLOR)
+            .fill(Self::error_color())
             .stroke(Stroke::new(0.0, Color32::TRANSPARENT))
-            .rounding(Rounding::same(12.0))
+            .rounding(Rounding::same(Self::palette().rounding))
     }
 
     pub fn button_success() -> egui::Button<'static> {
         egui::Button::new("")
-            .fill(Self::SUCCESS_COLOR)
+            .fill(Self::success_color())
             .stroke(Stroke::new(0.0, Color32::TRANSPARENT))
-            .rounding(Rounding::same(12.0))
+            .rounding(Rounding::same(Self::palette().rounding))
     }
 
     // Modern gradient backgrounds
@@ -206,7 +390,7 @@ impl NeonTheme {
         let painter = ui.painter();
         
         // Create a subtle gradient effect using layered rectangles
-        painter.rect_filled(rect, Rounding::same(16.0), Self::DARK_BG);
+        painter.rect_filled(rect, Rounding::same(Self::palette().window_rounding), Self::dark_bg());
         
         // Add a subtle overlay for depth
         let overlay_rect = egui::Rect::from_min_size(
@@ -215,7 +399,7 @@ impl NeonTheme {
         );
         painter.rect_filled(
             overlay_rect,
-            Rounding::same(16.0),
+            Rounding::same(Self::palette().window_rounding),
             Color32::from_rgba_unmultiplied(0, 122, 255, 8)
         );
     }
@@ -231,7 +415,7 @@ impl NeonTheme {
             let glow_rect = rect.expand(blur_size * i as f32);
             painter.rect_filled(
                 glow_rect,
-                Rounding::same(16.0 + blur_size),
+                Rounding::same(Self::palette().window_rounding + blur_size),
                 Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), layer_alpha)
             );
         }
@@ -242,9 +426,9 @@ impl NeonTheme {
         let available_rect = ui.available_rect_before_wrap();
         
         egui::Frame::none()
-            .fill(Self::CARD_BG)
-            .stroke(Stroke::new(1.0, Self::BORDER_COLOR))
-            .rounding(Rounding::same(16.0))
+            .fill(Self::card_bg())
+            .stroke(Stroke::new(1.0, Self::border_color()))
+            .rounding(Rounding::same(Self::palette().window_rounding))
             .shadow(Shadow {
                 offset: Vec2::new(0.0, 4.0),
                 blur: 12.0,
@@ -259,35 +443,35 @@ impl NeonTheme {
     pub fn style_text_edit(response: &egui::Response, ui: &mut egui::Ui) {
         if response.hovered() {
             // Add subtle glow on hover
-            Self::add_glow_effect(ui, response.rect, Self::FOCUS_BORDER, 0.3);
+            Self::add_glow_effect(ui, response.rect, Self::focus_border(), 0.3);
         }
         if response.has_focus() {
             // Add stronger glow on focus
-            Self::add_glow_effect(ui, response.rect, Self::NEON_CYAN, 0.5);
+            Self::add_glow_effect(ui, response.rect, Self::neon_cyan(), 0.5);
         }
     }
 
     pub fn gradient_background() -> egui::Color32 {
-        Self::DARK_BG
+        Self::dark_bg()
     }
 
     pub fn neon_text_color() -> Color32 {
-        Self::NEON_CYAN
+        Self::neon_cyan()
     }
 
     pub fn success_color() -> Color32 {
-        Self::SUCCESS_COLOR
+        Self::palette().success_color
     }
 
     pub fn error_color() -> Color32 {
-        Self::ERROR_COLOR
+        Self::palette().error_color
     }
 
     pub fn warning_color() -> Color32 {
-        Self::WARNING_COLOR
+        Self::palette().warning_color
     }
 
     pub fn accent_color() -> Color32 {
-        Self::ACCENT_TEXT
+        Self::accent_text()
     }
 }
@@ -0,0 +1,269 @@
+// Themes: the built-in palettes plus TOML theme files in the profile, which are picked up again when edited
+use anyhow::{Result, anyhow};
+use chrono::{Local, Timelike};
+use eframe::egui::{self, Color32};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use crate::storage::Preferences;
+use crate::storage::preferences::ThemeSwitch;
+use crate::ui::theme::{NeonTheme, Palette};
+
+pub const DEFAULT_THEME: &str = "Neon Dark";
+pub const LIGHT_THEME: &str = "Neon Light";
+
+// How often the themes directory is checked for edits
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// How often a scheduled theme switch is checked for while nothing else repaints
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+    /// The file a user theme was read from; None for the built-in ones
+    pub path: Option<PathBuf>,
+}
+
+// A theme file: colors it leaves out come from the built-in palette of its mode
+#[derive(Debug, Deserialize)]
+struct ThemeFile {
+    name: String,
+    #[serde(default = "default_dark")]
+    dark: bool,
+    rounding: Option<f32>,
+    window_rounding: Option<f32>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+}
+
+fn default_dark() -> bool {
+    true
+}
+
+impl Theme {
+    pub fn parse(source: &str) -> Result<Self> {
+        let file: ThemeFile = toml::from_str(source)?;
+        if file.name.trim().is_empty() {
+            return Err(anyhow!("The theme needs a name"));
+        }
+        let mut palette = Palette::base(file.dark);
+        palette.rounding = file.rounding.unwrap_or(palette.rounding).max(0.0);
+        palette.window_rounding = file.window_rounding.unwrap_or(palette.window_rounding).max(0.0);
+        for (name, value) in &file.colors {
+            let color = palette.color_mut(name).ok_or_else(|| anyhow!("Unknown color '{}'", name))?;
+            *color = parse_hex(value).ok_or_else(|| anyhow!("'{}' isn't a color like #1e90ff for {}", value, name))?;
+        }
+        Ok(Self { name: file.name.trim().to_string(), palette, path: None })
+    }
+
+    /// The theme as a file listing every color, to edit from
+    pub fn to_toml(&self) -> String {
+        let mut palette = self.palette;
+        let mut out = format!(
+            "# NeonSearch theme. Colors are #rrggbb or #rrggbbaa; edits apply as soon as the file is saved.\n\
+             name = \"{}\"\ndark = {}\nrounding = {:?}\nwindow_rounding = {:?}\n\n[colors]\n",
+            self.name.replace('"', "'"), palette.dark, palette.rounding, palette.window_rounding,
+        );
+        for name in Palette::COLOR_NAMES {
+            if let Some(color) = palette.color_mut(name) {
+                out.push_str(&format!("{} = \"{}\"\n", name, to_hex(*color)));
+            }
+        }
+        out
+    }
+}
+
+fn parse_hex(value: &str) -> Option<Color32> {
+    let hex = value.trim().strip_prefix('#')?;
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match hex.len() {
+        6 => Some(Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?)),
+        8 => Some(Color32::from_rgba_unmultiplied(channel(0)?, channel(2)?, channel(4)?, channel(6)?)),
+        _ => None,
+    }
+}
+
+fn to_hex(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    match a {
+        255 => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+    }
+}
+
+pub struct ThemeManager {
+    dir: PathBuf,
+    themes: Vec<Theme>,
+    load_errors: Vec<String>,
+    // Modification times the themes were read at, to notice edits
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+    last_poll: Option<Instant>,
+    // The palette last handed to the UI
+    applied: Option<Palette>,
+}
+
+static SHARED_THEMES: OnceLock<Arc<Mutex<ThemeManager>>> = OnceLock::new();
+
+impl ThemeManager {
+    pub fn new(data_dir: &Path) -> Self {
+        let dir = data_dir.join("themes");
+        let _ = std::fs::create_dir_all(&dir);
+        let mut manager = Self { dir, themes: Vec::new(), load_errors: Vec::new(), stamps: Vec::new(), last_poll: None, applied: None };
+        manager.reload();
+        manager
+    }
+
+    /// Process-wide themes shared by the window and the settings page
+    pub fn shared() -> Arc<Mutex<ThemeManager>> {
+        SHARED_THEMES
+            .get_or_init(|| Arc::new(Mutex::new(ThemeManager::new(&crate::storage::data_dir()))))
+            .clone()
+    }
+
+    /// Read the built-in themes and every theme file again
+    pub fn reload(&mut self) {
+        self.themes = vec![
+            Theme { name: DEFAULT_THEME.to_string(), palette: Palette::NEON_DARK, path: None },
+            Theme { name: LIGHT_THEME.to_string(), palette: Palette::NEON_LIGHT, path: None },
+        ];
+        self.load_errors.clear();
+        self.stamps = self.theme_files();
+        for (path, _) in self.stamps.clone() {
+            let file = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let loaded = std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|source| Theme::parse(&source));
+            match loaded {
+                Ok(theme) if self.get(&theme.name).is_some() => {
+                    self.load_errors.push(format!("{}: another theme is already called '{}'", file, theme.name));
+                }
+                Ok(theme) => self.themes.push(Theme { path: Some(path), ..theme }),
+                Err(e) => self.load_errors.push(format!("{}: {}", file, e)),
+            }
+        }
+    }
+
+    pub fn list(&self) -> &[Theme] {
+        &self.themes
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    pub fn load_errors(&self) -> &[String] {
+        &self.load_errors
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write a new theme file named `name` with `palette`'s colors. Returns its path
+    pub fn create(&mut self, name: &str, palette: Palette) -> Result<PathBuf> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("The theme needs a name"));
+        }
+        if self.get(name).is_some() {
+            return Err(anyhow!("There's already a theme called '{}'", name));
+        }
+        let slug: String = name.to_lowercase().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+        let path = (1..)
+            .map(|n| self.dir.join(if n == 1 { format!("{}.toml", slug) } else { format!("{}-{}.toml", slug, n) }))
+            .find(|path| !path.exists())
+            .unwrap_or_default();
+        let theme = Theme { name: name.to_string(), palette, path: None };
+        std::fs::write(&path, theme.to_toml()).map_err(|e| anyhow!("Failed to save the theme: {}", e))?;
+        self.reload();
+        Ok(path)
+    }
+
+    /// Re-read the theme files if any was added, changed or removed. Checks at most once a second
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+            return false;
+        }
+        self.last_poll = Some(Instant::now());
+        if self.theme_files() == self.stamps {
+            return false;
+        }
+        self.reload();
+        true
+    }
+
+    /// Pick up edited theme files and show the theme `prefs` call for now, given the system's light or dark
+    /// mode and the time of day; the default shows when there's no theme by that name
+    pub fn sync(&mut self, ctx: &egui::Context, prefs: &Preferences) {
+        self.poll();
+        let system_dark = ctx.system_theme().map(|theme| theme == egui::Theme::Dark);
+        let time = Local::now();
+        let minute = (time.hour() * 60 + time.minute()) as u16;
+        if matches!(prefs.auto_theme.switch, ThemeSwitch::Schedule { .. }) {
+            ctx.request_repaint_after(SCHEDULE_INTERVAL);
+        }
+        let name = prefs.active_theme(system_dark, minute);
+        let palette = self.get(name).map_or(Palette::NEON_DARK, |theme| theme.palette);
+        if self.applied != Some(palette) {
+            NeonTheme::set_palette(ctx, palette);
+            self.applied = Some(palette);
+        }
+    }
+
+    fn theme_files(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut files: Vec<(PathBuf, Option<SystemTime>)> = std::fs::read_dir(&self.dir)
+            .map(|entries| entries.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .map(|path| {
+                    let modified = std::fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                    (path, modified)
+                })
+                .collect())
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme_file() {
+        let theme = Theme::parse(r##"
+            name = "Paper"
+            dark = false
+            rounding = 4
+            [colors]
+            neon_cyan = "#ff6600"
+            card_bg = "#ffffff80"
+        "##).unwrap();
+        assert_eq!(theme.name, "Paper");
+        assert_eq!(theme.palette.neon_cyan, Color32::from_rgb(255, 102, 0));
+        assert_eq!(theme.palette.card_bg, Color32::from_rgba_unmultiplied(255, 255, 255, 128));
+        assert_eq!(theme.palette.primary_text, Palette::NEON_LIGHT.primary_text);
+        assert_eq!(theme.palette.rounding, 4.0);
+        assert_eq!(Theme::parse(&theme.to_toml()).unwrap().palette, theme.palette);
+
+        assert!(Theme::parse("name = \"Bad\"\n[colors]\nneon_cyan = \"cyan\"").is_err());
+        assert!(Theme::parse("name = \"Bad\"\n[colors]\nno_such_color = \"#000000\"").is_err());
+    }
+
+    #[test]
+    fn test_created_themes_reload_when_edited() {
+        let data_dir = std::env::temp_dir().join(format!("neon-themes-{}", std::process::id()));
+        let mut manager = ThemeManager::new(&data_dir);
+        let path = manager.create("Night Owl", Palette::NEON_DARK).unwrap();
+        assert!(manager.create("Night Owl", Palette::NEON_DARK).is_err());
+        assert_eq!(manager.get("Night Owl").unwrap().palette, Palette::NEON_DARK);
+
+        std::fs::write(&path, "name = \"Night Owl\"\n[colors]\nneon_cyan = \"#7fdbca\"").unwrap();
+        manager.stamps.clear();
+        assert!(manager.poll());
+        assert_eq!(manager.get("Night Owl").unwrap().palette.neon_cyan, Color32::from_rgb(0x7f, 0xdb, 0xca));
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+}
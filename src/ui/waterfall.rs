@@ -0,0 +1,111 @@
+// Requests drawn on a shared time axis, one bar per request split into its fetch phases
+use eframe::egui::{self, Color32, RichText, Sense};
+use crate::networking::http_cache::CacheStatus;
+use crate::networking::manual_client::FetchPhase;
+use crate::networking::timing::RequestTiming;
+use crate::ui::theme::NeonTheme;
+
+const LABEL_WIDTH: f32 = 260.0;
+const ROW_HEIGHT: f32 = 16.0;
+
+// Phases worth a colour in the legend; redirects and completion take no real time
+const SHOWN_PHASES: [FetchPhase; 6] = [
+    FetchPhase::Resolving,
+    FetchPhase::Connecting,
+    FetchPhase::TlsHandshake,
+    FetchPhase::SendingRequest,
+    FetchPhase::ReadingHeaders,
+    FetchPhase::ReadingBody,
+];
+
+fn phase_color(phase: FetchPhase) -> Color32 {
+    match phase {
+        FetchPhase::Resolving => NeonTheme::neon_green(),
+        FetchPhase::Connecting => NeonTheme::neon_orange(),
+        FetchPhase::TlsHandshake => NeonTheme::neon_purple(),
+        FetchPhase::SendingRequest => NeonTheme::neon_gold(),
+        FetchPhase::ReadingHeaders => NeonTheme::neon_blue(),
+        FetchPhase::ReadingBody => NeonTheme::neon_cyan(),
+        FetchPhase::Redirecting | FetchPhase::Completed => NeonTheme::muted_text(),
+    }
+}
+
+/// Colour key for the phases
+pub fn legend(ui: &mut egui::Ui) {
+    ui.horizontal_wrapped(|ui| {
+        for phase in SHOWN_PHASES {
+            ui.label(RichText::new("■").color(phase_color(phase)));
+            ui.label(RichText::new(phase.label()).small().color(NeonTheme::secondary_text()));
+            ui.add_space(8.0);
+        }
+    });
+}
+
+/// One row per request, offset by when it started relative to the first
+pub fn show(ui: &mut egui::Ui, requests: &[&RequestTiming]) {
+    let Some(origin) = requests.iter().map(|timing| timing.started).min() else {
+        ui.label(RichText::new("No timed requests").color(NeonTheme::muted_text()));
+        return;
+    };
+    let span = requests.iter()
+        .map(|timing| (timing.offset_from(origin) + timing.duration()).as_secs_f32())
+        .fold(0.001, f32::max);
+
+    for timing in requests {
+        ui.horizontal(|ui| {
+            let label = ui.add_sized(
+                [LABEL_WIDTH, ROW_HEIGHT],
+                egui::Label::new(RichText::new(&timing.url).small().color(NeonTheme::primary_text())).truncate(),
+            );
+            label.on_hover_text(&timing.url);
+
+            let width = (ui.available_width() - 80.0).max(40.0);
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width, ROW_HEIGHT), Sense::hover());
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 2.0, NeonTheme::surface_bg());
+            let offset = timing.offset_from(origin).as_secs_f32();
+            for phase in &timing.phases {
+                let start = rect.left() + (offset + phase.start.as_secs_f32()) / span * width;
+                let end = rect.left() + (offset + phase.end.as_secs_f32()) / span * width;
+                let bar = egui::Rect::from_min_max(
+                    egui::pos2(start, rect.top() + 3.0),
+                    egui::pos2(end.max(start + 1.0), rect.bottom() - 3.0),
+                );
+                painter.rect_filled(bar, 0.0, phase_color(phase.phase));
+            }
+            response.on_hover_ui(|ui| breakdown(ui, timing));
+
+            ui.label(RichText::new(format!("{} ms", timing.duration().as_millis())).small().color(NeonTheme::secondary_text()));
+            if timing.cache != CacheStatus::Network {
+                ui.label(RichText::new(timing.cache.label()).small().color(NeonTheme::neon_green()))
+                    .on_hover_text("Served from the HTTP cache");
+            }
+        });
+    }
+}
+
+fn breakdown(ui: &mut egui::Ui, timing: &RequestTiming) {
+    egui::Grid::new("waterfall_breakdown").num_columns(2).show(ui, |ui| {
+        for phase in timing.phases.iter().filter(|phase| phase.phase != FetchPhase::Completed) {
+            ui.label(RichText::new(phase.phase.label()).color(phase_color(phase.phase)));
+            ui.label(format!("{:.1} ms", (phase.end - phase.start).as_secs_f64() * 1000.0));
+            ui.end_row();
+        }
+        ui.label("Sent");
+        ui.label(format!("{} B", timing.bytes_sent));
+        ui.end_row();
+        ui.label("Received");
+        ui.label(format!("{:.1} KB", timing.bytes_received as f64 / 1024.0));
+        ui.end_row();
+        ui.label("Source");
+        ui.label(match timing.cache {
+            CacheStatus::Network => "Network",
+            CacheStatus::Fresh => "Cache, not checked with the server",
+            CacheStatus::Revalidated => "Cache, confirmed by 304 Not Modified",
+        });
+        ui.end_row();
+        ui.label("Connection");
+        ui.label(if timing.connection_reused { "Reused" } else { "New" });
+        ui.end_row();
+    });
+}